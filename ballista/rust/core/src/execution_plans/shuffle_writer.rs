@@ -44,7 +44,7 @@ use datafusion::arrow::ipc::reader::FileReader;
 use datafusion::arrow::ipc::writer::FileWriter;
 use datafusion::arrow::record_batch::RecordBatch;
 use datafusion::error::{DataFusionError, Result};
-use datafusion::physical_plan::hash_utils::create_hashes;
+use datafusion::physical_plan::hash_utils::{create_hashes, partition_random_state};
 use datafusion::physical_plan::metrics::{
     self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
 };
@@ -203,7 +203,7 @@ impl ShuffleWriterExec {
                 }
 
                 let hashes_buf = &mut vec![];
-                let random_state = ahash::RandomState::with_seeds(0, 0, 0, 0);
+                let random_state = partition_random_state();
 
                 while let Some(result) = stream.next().await {
                     let input_batch = result?;
@@ -848,6 +848,11 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
                     ))),
                 })
             }
+            LogicalPlan::Join(Join {
+                null_aware: true, ..
+            }) => Err(proto_error(
+                "Error converting null-aware anti join. Not yet supported in Ballista",
+            )),
             LogicalPlan::Join(Join {
                 left,
                 right,
@@ -1015,9 +1020,21 @@ impl TryInto<protobuf::LogicalPlanNode> for &LogicalPlan {
             LogicalPlan::CreateMemoryTable(_) => Err(proto_error(
                 "Error converting CreateMemoryTable. Not yet supported in Ballista",
             )),
+            LogicalPlan::InsertInto(_) => Err(proto_error(
+                "Error converting InsertInto. Not yet supported in Ballista",
+            )),
             LogicalPlan::DropTable(_) => Err(proto_error(
                 "Error converting DropTable. Not yet supported in Ballista",
             )),
+            LogicalPlan::SetVariable(_) => Err(proto_error(
+                "Error converting SetVariable. Not yet supported in Ballista",
+            )),
+            LogicalPlan::Unnest(_) => Err(proto_error(
+                "Error converting Unnest. Not yet supported in Ballista",
+            )),
+            LogicalPlan::RecursiveQuery(_) => Err(proto_error(
+                "Error converting RecursiveQuery. Not yet supported in Ballista",
+            )),
         }
     }
 }
@@ -1091,6 +1108,11 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                             protobuf::BuiltInWindowFunction::from(fun).into(),
                         )
                     }
+                    WindowFunction::AggregateUDF(_) | WindowFunction::WindowUDF(_) => {
+                        return Err(proto_error(
+                            "Error converting user-defined window function. Not yet supported in Ballista",
+                        ))
+                    }
                 };
                 let arg_expr: Option<Box<protobuf::LogicalExprNode>> = if !args.is_empty()
                 {
@@ -1128,12 +1150,16 @@ impl TryInto<protobuf::LogicalExprNode> for &Expr {
                     AggregateFunction::ApproxDistinct => {
                         protobuf::AggregateFunction::ApproxDistinct
                     }
+                    AggregateFunction::ApproxMedian => {
+                        protobuf::AggregateFunction::ApproxMedian
+                    }
                     AggregateFunction::ArrayAgg => protobuf::AggregateFunction::ArrayAgg,
                     AggregateFunction::Min => protobuf::AggregateFunction::Min,
                     AggregateFunction::Max => protobuf::AggregateFunction::Max,
                     AggregateFunction::Sum => protobuf::AggregateFunction::Sum,
                     AggregateFunction::Avg => protobuf::AggregateFunction::Avg,
                     AggregateFunction::Count => protobuf::AggregateFunction::Count,
+                    AggregateFunction::Stddev => protobuf::AggregateFunction::Stddev,
                 };
 
                 let arg = &args[0];
@@ -1364,6 +1390,8 @@ impl From<&AggregateFunction> for protobuf::AggregateFunction {
             AggregateFunction::Count => Self::Count,
             AggregateFunction::ApproxDistinct => Self::ApproxDistinct,
             AggregateFunction::ArrayAgg => Self::ArrayAgg,
+            AggregateFunction::ApproxMedian => Self::ApproxMedian,
+            AggregateFunction::Stddev => Self::Stddev,
         }
     }
 }
@@ -119,6 +119,8 @@ impl From<protobuf::AggregateFunction> for AggregateFunction {
                 AggregateFunction::ApproxDistinct
             }
             protobuf::AggregateFunction::ArrayAgg => AggregateFunction::ArrayAgg,
+            protobuf::AggregateFunction::ApproxMedian => AggregateFunction::ApproxMedian,
+            protobuf::AggregateFunction::Stddev => AggregateFunction::Stddev,
         }
     }
 }
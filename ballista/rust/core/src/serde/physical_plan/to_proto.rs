@@ -131,6 +131,11 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 ))),
             })
         } else if let Some(exec) = plan.downcast_ref::<HashJoinExec>() {
+            if exec.null_aware() {
+                return Err(BallistaError::NotImplemented(
+                    "Null-aware anti joins are not supported in Ballista".to_owned(),
+                ));
+            }
             let left: protobuf::PhysicalPlanNode = exec.left().to_owned().try_into()?;
             let right: protobuf::PhysicalPlanNode = exec.right().to_owned().try_into()?;
             let on: Vec<protobuf::JoinOn> = exec
@@ -208,6 +213,9 @@ impl TryInto<protobuf::PhysicalPlanNode> for Arc<dyn ExecutionPlan> {
                 AggregateMode::FinalPartitioned => {
                     protobuf::AggregateMode::FinalPartitioned
                 }
+                AggregateMode::SinglePartitioned => {
+                    protobuf::AggregateMode::SinglePartitioned
+                }
             };
             let input_schema = exec.input_schema();
             let input: protobuf::PhysicalPlanNode = exec.input().to_owned().try_into()?;
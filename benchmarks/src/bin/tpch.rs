@@ -220,7 +220,7 @@ async fn benchmark_datafusion(opt: DataFusionBenchmarkOpt) -> Result<Vec<RecordB
             let start = Instant::now();
 
             let memtable =
-                MemTable::load(table_provider, opt.batch_size, Some(opt.partitions))
+                MemTable::load(table_provider, Some(opt.partitions), ctx.task_ctx())
                     .await?;
             println!(
                 "Loaded table '{}' into memory in {} ms",
@@ -0,0 +1,149 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+/// In this example we will declare a user-defined window function (UDWF) that computes the
+/// exponential moving average of a column over the rows of each partition of a window.
+/// The exponential moving average is described here: https://en.wikipedia.org/wiki/Moving_average#Exponential_moving_average
+use datafusion::arrow::{
+    array::{Array, ArrayRef, Float64Array, Int64Array, StringArray},
+    datatypes::{DataType, Field, Schema},
+    record_batch::RecordBatch,
+    util::pretty::print_batches,
+};
+
+use datafusion::datasource::MemTable;
+use datafusion::physical_plan::functions::Volatility;
+use datafusion::physical_plan::udwf::PartitionEvaluator;
+use datafusion::prelude::*;
+use datafusion::{error::Result, logical_plan::create_udwf};
+use std::sync::Arc;
+
+// create local execution context with an in-memory table of sensor readings
+fn create_context() -> Result<ExecutionContext> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("sensor", DataType::Utf8, false),
+        Field::new("time", DataType::Int64, false),
+        Field::new("reading", DataType::Float64, true),
+    ]));
+
+    // the rows of a partition of the window can be spread over several batches
+    let batch1 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b", "a", "b"])),
+            Arc::new(Int64Array::from(vec![1, 1, 2, 2])),
+            Arc::new(Float64Array::from(vec![
+                Some(10.0),
+                Some(1.0),
+                Some(20.0),
+                None,
+            ])),
+        ],
+    )?;
+    let batch2 = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(StringArray::from(vec!["a", "b"])),
+            Arc::new(Int64Array::from(vec![3, 3])),
+            Arc::new(Float64Array::from(vec![Some(40.0), Some(3.0)])),
+        ],
+    )?;
+
+    let mut ctx = ExecutionContext::new();
+    let provider = MemTable::try_new(schema, vec![vec![batch1], vec![batch2]])?;
+    ctx.register_table("readings", Arc::new(provider))?;
+    Ok(ctx)
+}
+
+/// A UDWF receives all the rows of a partition at once, sorted by the ORDER BY of the
+/// window, so the state of the moving average only lives while a partition is evaluated.
+struct ExponentialMovingAverage {}
+
+impl PartitionEvaluator for ExponentialMovingAverage {
+    // `values` has one array per argument of the function, holding the values of the
+    // arguments for the rows of the partition. DataFusion expects an array with one value
+    // per row, of the return type of the function.
+    fn evaluate_partition(
+        &mut self,
+        values: &[ArrayRef],
+        num_rows: usize,
+    ) -> Result<ArrayRef> {
+        let readings = values[0].as_any().downcast_ref::<Float64Array>().unwrap();
+        // alpha is a constant, passed as the second argument of every row
+        let alphas = values[1].as_any().downcast_ref::<Float64Array>().unwrap();
+
+        let mut average: Option<f64> = None;
+        let averages = (0..num_rows)
+            .map(|row| {
+                // nulls leave the average as it is
+                if readings.is_valid(row) {
+                    let reading = readings.value(row);
+                    let alpha = alphas.value(row);
+                    average = Some(match average {
+                        Some(average) => alpha * reading + (1.0 - alpha) * average,
+                        None => reading,
+                    });
+                }
+                average
+            })
+            .collect::<Float64Array>();
+        Ok(Arc::new(averages))
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut ctx = create_context()?;
+
+    // here is where we define the UDWF. We also declare its signature:
+    let exponential_moving_average = create_udwf(
+        // the name; used to represent it in plan descriptions and in the registry, to use in SQL.
+        "exponential_moving_average",
+        // the input types; DataFusion coerces the arguments to these types.
+        vec![DataType::Float64, DataType::Float64],
+        // the return type; DataFusion expects this to match the type returned by the evaluator.
+        Arc::new(DataType::Float64),
+        Volatility::Immutable,
+        // This is the evaluator factory; DataFusion uses it to create an evaluator per partition.
+        Arc::new(|| Ok(Box::new(ExponentialMovingAverage {}))),
+    );
+    ctx.register_udwf(exponential_moving_average);
+
+    // the function is called with an OVER clause, like the built-in window functions
+    let df = ctx
+        .sql(
+            "SELECT sensor, time, reading, \
+             exponential_moving_average(reading, 0.5) \
+             OVER (PARTITION BY sensor ORDER BY time) AS ema \
+             FROM readings ORDER BY sensor, time",
+        )
+        .await?;
+    let results = df.collect().await?;
+    print_batches(&results)?;
+
+    // verify the averages of sensor `a`, whose readings are 10, 20 and 40
+    let ema = results[0]
+        .column(3)
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .unwrap();
+    assert!((ema.value(0) - 10.0).abs() < f64::EPSILON);
+    assert!((ema.value(1) - 15.0).abs() < f64::EPSILON);
+    assert!((ema.value(2) - 27.5).abs() < f64::EPSILON);
+
+    Ok(())
+}
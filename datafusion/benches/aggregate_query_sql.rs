@@ -24,7 +24,7 @@ mod data_utils;
 use crate::criterion::Criterion;
 use data_utils::create_table_provider;
 use datafusion::error::Result;
-use datafusion::execution::context::ExecutionContext;
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
 use std::sync::{Arc, Mutex};
 use tokio::runtime::Runtime;
 
@@ -38,8 +38,9 @@ fn create_context(
     partitions_len: usize,
     array_len: usize,
     batch_size: usize,
+    config: ExecutionConfig,
 ) -> Result<Arc<Mutex<ExecutionContext>>> {
-    let mut ctx = ExecutionContext::new();
+    let mut ctx = ExecutionContext::with_config(config);
     let provider = create_table_provider(partitions_len, array_len, batch_size)?;
     ctx.register_table("t", provider)?;
     Ok(Arc::new(Mutex::new(ctx)))
@@ -49,7 +50,20 @@ fn criterion_benchmark(c: &mut Criterion) {
     let partitions_len = 8;
     let array_len = 32768 * 2; // 2^16
     let batch_size = 2048; // 2^11
-    let ctx = create_context(partitions_len, array_len, batch_size).unwrap();
+    let ctx = create_context(
+        partitions_len,
+        array_len,
+        batch_size,
+        ExecutionConfig::new(),
+    )
+    .unwrap();
+    let accumulators_ctx = create_context(
+        partitions_len,
+        array_len,
+        batch_size,
+        ExecutionConfig::new().with_row_aggregation(false),
+    )
+    .unwrap();
 
     c.bench_function("aggregate_query_no_group_by 15 12", |b| {
         b.iter(|| {
@@ -132,6 +146,29 @@ fn criterion_benchmark(c: &mut Criterion) {
             )
         })
     });
+
+    c.bench_function("aggregate_query_group_by_u64_fixed_width", |b| {
+        b.iter(|| {
+            query(
+                ctx.clone(),
+                "SELECT u64_narrow, SUM(f64), COUNT(u64_wide), MIN(f64), MAX(f32) \
+                 FROM t GROUP BY u64_narrow",
+            )
+        })
+    });
+
+    c.bench_function(
+        "aggregate_query_group_by_u64_fixed_width_accumulators",
+        |b| {
+            b.iter(|| {
+                query(
+                    accumulators_ctx.clone(),
+                    "SELECT u64_narrow, SUM(f64), COUNT(u64_wide), MIN(f64), MAX(f32) \
+                     FROM t GROUP BY u64_narrow",
+                )
+            })
+        },
+    );
 }
 
 criterion_group!(benches, criterion_benchmark);
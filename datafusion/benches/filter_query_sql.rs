@@ -16,8 +16,8 @@
 // under the License.
 
 use arrow::{
-    array::{Float32Array, Float64Array},
-    datatypes::{DataType, Field, Schema},
+    array::{DictionaryArray, Float32Array, Float64Array, StringArray},
+    datatypes::{DataType, Field, Int32Type, Schema},
     record_batch::RecordBatch,
 };
 use criterion::{criterion_group, criterion_main, Criterion};
@@ -40,8 +40,20 @@ fn create_context(array_len: usize, batch_size: usize) -> Result<ExecutionContex
     let schema = Arc::new(Schema::new(vec![
         Field::new("f32", DataType::Float32, false),
         Field::new("f64", DataType::Float64, false),
+        Field::new(
+            "dict",
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8)),
+            false,
+        ),
+        Field::new("utf8", DataType::Utf8, false),
     ]));
 
+    // low cardinality strings
+    let names = ["apache", "arrow", "datafusion", "ballista"];
+    let strings = (0..batch_size)
+        .map(|i| names[i % names.len()])
+        .collect::<Vec<_>>();
+
     // define data.
     let batches = (0..array_len / batch_size)
         .map(|i| {
@@ -50,6 +62,13 @@ fn create_context(array_len: usize, batch_size: usize) -> Result<ExecutionContex
                 vec![
                     Arc::new(Float32Array::from(vec![i as f32; batch_size])),
                     Arc::new(Float64Array::from(vec![i as f64; batch_size])),
+                    Arc::new(
+                        strings
+                            .iter()
+                            .copied()
+                            .collect::<DictionaryArray<Int32Type>>(),
+                    ),
+                    Arc::new(StringArray::from(strings.clone())),
                 ],
             )
             .unwrap()
@@ -84,6 +103,22 @@ fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
+    c.bench_function("filter_scalar dictionary", |b| {
+        let mut ctx = create_context(array_len, batch_size).unwrap();
+        b.iter(|| block_on(query(&mut ctx, "select f32 from t where dict = 'arrow'")))
+    });
+
+    c.bench_function("filter_scalar utf8", |b| {
+        let mut ctx = create_context(array_len, batch_size).unwrap();
+        b.iter(|| block_on(query(&mut ctx, "select f32 from t where utf8 = 'arrow'")))
+    });
+
+    // the rows of a single batch, returned as a slice of the input
+    c.bench_function("filter_scalar point lookup", |b| {
+        let mut ctx = create_context(array_len, batch_size).unwrap();
+        b.iter(|| block_on(query(&mut ctx, "select f32, f64 from t where f32 = 100")))
+    });
+
     c.bench_function("filter_scalar in list", |b| {
         let mut ctx = create_context(array_len, batch_size).unwrap();
         b.iter(|| {
@@ -93,6 +128,27 @@ fn criterion_benchmark(c: &mut Criterion) {
             ))
         })
     });
+
+    // the values of long lists are hashed
+    c.bench_function("filter_scalar in list 10k", |b| {
+        let mut ctx = create_context(array_len, batch_size).unwrap();
+        let list = (0..10_000)
+            .map(|v| (v * 2).to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!("select f32, f64 from t where f64 in ({})", list);
+        b.iter(|| block_on(query(&mut ctx, &sql)))
+    });
+
+    c.bench_function("filter_scalar not in list 10k utf8", |b| {
+        let mut ctx = create_context(array_len, batch_size).unwrap();
+        let list = (0..10_000)
+            .map(|v| format!("'name{}'", v))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!("select f32 from t where utf8 not in ({})", list);
+        b.iter(|| block_on(query(&mut ctx, &sql)))
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -29,6 +29,7 @@ use arrow::{
 };
 use tokio::runtime::Runtime;
 
+use datafusion::execution::context::ExecutionContext;
 use datafusion::physical_plan::{
     collect,
     expressions::{col, PhysicalSortExpr},
@@ -57,8 +58,10 @@ fn sort_preserving_merge_operator(batches: Vec<RecordBatch>, sort: &[&str]) {
     .unwrap();
     let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec), 8192));
 
+    let task_ctx = ExecutionContext::new().task_ctx();
+
     let rt = Runtime::new().unwrap();
-    rt.block_on(collect(merge)).unwrap();
+    rt.block_on(collect(merge, task_ctx)).unwrap();
 }
 
 // Produces `n` record batches of row size `m`. Each record batch will have
@@ -30,7 +30,7 @@ extern crate datafusion;
 use arrow::datatypes::{DataType, Field, Schema};
 
 use datafusion::datasource::MemTable;
-use datafusion::execution::context::ExecutionContext;
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
 
 use tokio::runtime::Runtime;
 
@@ -79,7 +79,11 @@ fn create_context() -> Arc<Mutex<ExecutionContext>> {
     let partitions = 16;
 
     rt.block_on(async {
-        let mem_table = MemTable::load(Arc::new(csv), 16 * 1024, Some(partitions))
+        let task_ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_batch_size(16 * 1024),
+        )
+        .task_ctx();
+        let mem_table = MemTable::load(Arc::new(csv), Some(partitions), task_ctx)
             .await
             .unwrap();
 
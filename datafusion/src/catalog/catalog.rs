@@ -19,6 +19,7 @@
 //! representing collections of named schemas.
 
 use crate::catalog::schema::SchemaProvider;
+use crate::error::{DataFusionError, Result};
 use std::any::Any;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -95,6 +96,28 @@ pub trait CatalogProvider: Sync + Send {
 
     /// Retrieves a specific schema from the catalog by name, provided it exists.
     fn schema(&self, name: &str) -> Option<Arc<dyn SchemaProvider>>;
+
+    /// If supported by the implementation, adds a new schema to this catalog.
+    /// If a schema of the same name existed before, it is replaced in the catalog and returned.
+    #[allow(unused_variables)]
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        Err(DataFusionError::Execution(
+            "catalog provider does not support registering schemas".to_owned(),
+        ))
+    }
+
+    /// If supported by the implementation, removes an existing schema from this catalog and returns it.
+    /// If no schema of that name exists, returns Ok(None).
+    #[allow(unused_variables)]
+    fn deregister_schema(&self, name: &str) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        Err(DataFusionError::Execution(
+            "catalog provider does not support deregistering schemas".to_owned(),
+        ))
+    }
 }
 
 /// Simple in-memory implementation of a catalog.
@@ -109,17 +132,6 @@ impl MemoryCatalogProvider {
             schemas: RwLock::new(HashMap::new()),
         }
     }
-
-    /// Adds a new schema to this catalog.
-    /// If a schema of the same name existed before, it is replaced in the catalog and returned.
-    pub fn register_schema(
-        &self,
-        name: impl Into<String>,
-        schema: Arc<dyn SchemaProvider>,
-    ) -> Option<Arc<dyn SchemaProvider>> {
-        let mut schemas = self.schemas.write().unwrap();
-        schemas.insert(name.into(), schema)
-    }
 }
 
 impl CatalogProvider for MemoryCatalogProvider {
@@ -136,4 +148,18 @@ impl CatalogProvider for MemoryCatalogProvider {
         let schemas = self.schemas.read().unwrap();
         schemas.get(name).cloned()
     }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        let mut schemas = self.schemas.write().unwrap();
+        Ok(schemas.insert(name.to_owned(), schema))
+    }
+
+    fn deregister_schema(&self, name: &str) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        let mut schemas = self.schemas.write().unwrap();
+        Ok(schemas.remove(name))
+    }
 }
@@ -31,6 +31,7 @@ use arrow::{
 };
 
 use crate::datasource::{MemTable, TableProvider, TableType};
+use crate::error::{DataFusionError, Result};
 
 use super::{
     catalog::{CatalogList, CatalogProvider},
@@ -84,6 +85,30 @@ impl CatalogProvider for CatalogWithInformationSchema {
             self.inner.schema(name)
         }
     }
+
+    fn register_schema(
+        &self,
+        name: &str,
+        schema: Arc<dyn SchemaProvider>,
+    ) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        if name.eq_ignore_ascii_case(INFORMATION_SCHEMA) {
+            return Err(DataFusionError::Execution(format!(
+                "The schema {} is reserved",
+                INFORMATION_SCHEMA
+            )));
+        }
+        self.inner.register_schema(name, schema)
+    }
+
+    fn deregister_schema(&self, name: &str) -> Result<Option<Arc<dyn SchemaProvider>>> {
+        if name.eq_ignore_ascii_case(INFORMATION_SCHEMA) {
+            return Err(DataFusionError::Execution(format!(
+                "The schema {} is reserved",
+                INFORMATION_SCHEMA
+            )));
+        }
+        self.inner.deregister_schema(name)
+    }
 }
 
 /// Implements the `information_schema` virtual schema and tables
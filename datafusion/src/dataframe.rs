@@ -17,8 +17,10 @@
 
 //! DataFrame API for building and executing query plans.
 
+use crate::arrow::datatypes::Schema;
 use crate::arrow::record_batch::RecordBatch;
 use crate::error::Result;
+use crate::execution::options::{AlignOptions, CsvWriteOptions};
 use crate::logical_plan::{
     DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, Partitioning,
 };
@@ -278,6 +280,31 @@ pub trait DataFrame: Send + Sync {
     /// ```
     async fn show_limit(&self, n: usize) -> Result<()>;
 
+    /// Summarize the columns of this DataFrame, like `describe` in pandas. The
+    /// `describe` column of the result names the statistic of each row, and is
+    /// followed by a column for each column of this DataFrame, in their order.
+    ///
+    /// The numeric columns are summarized by their count, null_count, mean, std
+    /// (the sample standard deviation), min, max and approximate median, as
+    /// doubles. The string columns are summarized by their count, null_count,
+    /// distinct_count, min and max, and the other columns by their count and
+    /// null_count, as strings. The nested columns are skipped, and listed under
+    /// the `describe.skipped` key of the metadata of the result's schema. All the
+    /// statistics are computed by a single aggregation of this DataFrame.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// df.describe().await?.show().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn describe(&self) -> Result<Arc<dyn DataFrame>>;
+
     /// Executes this DataFrame and returns a stream over a single partition
     ///
     /// ```
@@ -324,6 +351,27 @@ pub trait DataFrame: Send + Sync {
     /// ```
     async fn execute_stream_partitioned(&self) -> Result<Vec<SendableRecordBatchStream>>;
 
+    /// Executes this DataFrame and writes each output partition to its own CSV
+    /// file inside the directory `path`. Returns the paths of the files written.
+    ///
+    /// ```no_run
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let options = CsvWriteOptions::new().delimiter(b'|').null_value("NULL");
+    /// let files = df.write_csv("/tmp/example_out", &options).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    async fn write_csv(
+        &self,
+        path: &str,
+        options: &CsvWriteOptions,
+    ) -> Result<Vec<String>>;
+
     /// Returns the schema describing the output of this DataFrame in terms of columns returned,
     /// where each column has a name, data type, and nullability attribute.
 
@@ -405,4 +453,56 @@ pub trait DataFrame: Send + Sync {
     /// # }
     /// ```
     fn except(&self, dataframe: Arc<dyn DataFrame>) -> Result<Arc<dyn DataFrame>>;
+
+    /// Project the columns of this DataFrame to the columns of `target`, in
+    /// their order, e.g. to union DataFrames read from sources whose schemas
+    /// drifted apart.
+    ///
+    /// The columns are matched by name, after the renames of `options`. The
+    /// columns of other types are coerced to the types of `target`, the nullable
+    /// columns missing from this DataFrame are filled with nulls and the other
+    /// columns are dropped, unless `options` disables it. This function errors
+    /// with a report of every column that can't be aligned.
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # use datafusion::arrow::datatypes::{DataType, Field, Schema};
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let target = Schema::new(vec![
+    ///     Field::new("id", DataType::Int64, false),
+    ///     Field::new("b", DataType::Float64, false),
+    ///     Field::new("comment", DataType::Utf8, true),
+    /// ]);
+    /// let df = df.align_schema(&target, AlignOptions::new().rename("a", "id"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn align_schema(
+        &self,
+        target: &Schema,
+        options: AlignOptions,
+    ) -> Result<Arc<dyn DataFrame>>;
+
+    /// Qualify all the columns of this DataFrame by `alias`, like a table
+    /// alias in SQL, e.g. to join a DataFrame with itself
+    ///
+    /// ```
+    /// # use datafusion::prelude::*;
+    /// # use datafusion::error::Result;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<()> {
+    /// let mut ctx = ExecutionContext::new();
+    /// let df = ctx.read_csv("tests/example.csv", CsvReadOptions::new()).await?;
+    /// let join = df
+    ///     .alias("l")?
+    ///     .join(df.alias("r")?, JoinType::Inner, &["l.a"], &["r.b"])?
+    ///     .select(vec![col("l.a"), col("r.a")])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn alias(&self, alias: &str) -> Result<Arc<dyn DataFrame>>;
 }
@@ -0,0 +1,90 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! The table through which the recursive term of a recursive query reads the
+//! rows of the previous iteration
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use async_trait::async_trait;
+
+use crate::datasource::TableProvider;
+use crate::error::Result;
+use crate::logical_plan::Expr;
+use crate::physical_plan::recursive_query::WorkTableExec;
+use crate::physical_plan::ExecutionPlan;
+
+/// The rows produced by the previous iteration of a recursive query, read by
+/// its recursive term under the name of the common table expression.
+///
+/// The table has no rows of its own: its scan is bound to the rows of an
+/// iteration by the
+/// [`RecursiveQueryExec`](crate::physical_plan::recursive_query::RecursiveQueryExec)
+/// evaluating the query.
+pub struct CteWorkTable {
+    /// The name of the common table expression
+    name: String,
+    /// The schema of the rows of the iterations
+    schema: SchemaRef,
+}
+
+impl CteWorkTable {
+    /// Create the work table of the recursive query `name`, whose rows have
+    /// the schema `schema`
+    pub fn new(name: &str, schema: SchemaRef) -> Self {
+        Self {
+            name: name.to_owned(),
+            schema,
+        }
+    }
+
+    /// The name of the common table expression
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[async_trait]
+impl TableProvider for CteWorkTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(WorkTableExec::new(
+            &self.name,
+            self.schema.clone(),
+            projection.clone(),
+        )))
+    }
+}
@@ -25,7 +25,7 @@ use async_trait::async_trait;
 use crate::arrow::datatypes::SchemaRef;
 use crate::error::Result;
 use crate::logical_plan::Expr;
-use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::{ExecutionPlan, Statistics};
 
 /// Indicates whether and how a filter expression can be handled by a
 /// TableProvider for table scans.
@@ -55,6 +55,47 @@ pub enum TableType {
     Temporary,
 }
 
+/// The arguments of a table scan, see [`TableProvider::scan_with_args`].
+#[derive(Debug, Clone)]
+pub struct ScanArgs {
+    /// The indices of the columns to read, all the columns if `None`
+    pub projection: Option<Vec<usize>>,
+    /// The target number of rows of the record batches
+    pub batch_size: usize,
+    /// The filters pushed down to the scan, along with the answer of
+    /// [`TableProvider::supports_filter_pushdown`] for each of them. The
+    /// `Inexact` filters are also evaluated on the output of the scan, the
+    /// `Exact` ones are not and must be fully applied by the provider.
+    pub filters: Vec<(Expr, TableProviderFilterPushDown)>,
+    /// The number of rows needed by the query. If set, the provider should
+    /// return *at least* this number of rows (after applying the `Exact`
+    /// filters) if available.
+    pub limit: Option<usize>,
+}
+
+impl ScanArgs {
+    /// All the filters pushed down to the scan
+    pub fn filter_exprs(&self) -> Vec<Expr> {
+        self.filters.iter().map(|(expr, _)| expr.clone()).collect()
+    }
+
+    /// The filters that the provider must fully apply
+    pub fn exact_filters(&self) -> impl Iterator<Item = &Expr> {
+        self.filters
+            .iter()
+            .filter(|(_, support)| support == &TableProviderFilterPushDown::Exact)
+            .map(|(expr, _)| expr)
+    }
+
+    /// The filters that the provider can use to reduce the data it returns
+    pub fn inexact_filters(&self) -> impl Iterator<Item = &Expr> {
+        self.filters
+            .iter()
+            .filter(|(_, support)| support == &TableProviderFilterPushDown::Inexact)
+            .map(|(expr, _)| expr)
+    }
+}
+
 /// Source table
 #[async_trait]
 pub trait TableProvider: Sync + Send {
@@ -89,6 +130,35 @@ pub trait TableProvider: Sync + Send {
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>>;
 
+    /// Create an ExecutionPlan that will scan the table, with the filters
+    /// classified by [`TableProvider::supports_filter_pushdown`]. This is
+    /// the method called by the physical planner, the default
+    /// implementation forwards to [`TableProvider::scan`].
+    async fn scan_with_args(&self, args: ScanArgs) -> Result<Arc<dyn ExecutionPlan>> {
+        self.scan(
+            &args.projection,
+            args.batch_size,
+            &args.filter_exprs(),
+            args.limit,
+        )
+        .await
+    }
+
+    /// Statistics of the whole table, used by the optimizer to estimate the
+    /// size of the relations it plans. Unknown by default.
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+
+    /// Version of the contents of the table, incremented whenever they
+    /// change, e.g. when batches are appended to a
+    /// [`MemTable`](crate::datasource::MemTable). `None` by default, for the
+    /// tables that don't track their changes, whose queries are never served
+    /// from the [`ResultCache`](crate::execution::result_cache::ResultCache).
+    fn version(&self) -> Option<u64> {
+        None
+    }
+
     /// Tests whether the table provider can make use of a filter expression
     /// to optimise data retrieval.
     fn supports_filter_pushdown(
@@ -74,4 +74,9 @@ impl TableProvider for EmptyTable {
         );
         Ok(Arc::new(EmptyExec::new(false, Arc::new(projected_schema))))
     }
+
+    fn version(&self) -> Option<u64> {
+        // the table never changes
+        Some(0)
+    }
 }
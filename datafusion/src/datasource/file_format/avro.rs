@@ -77,6 +77,7 @@ mod tests {
             local_object_reader, local_object_reader_stream, local_unpartitioned_file,
             LocalFileSystem,
         },
+        execution::context::ExecutionContext,
         physical_plan::collect,
     };
 
@@ -89,9 +90,10 @@ mod tests {
 
     #[tokio::test]
     async fn read_small_batches() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.avro", &projection, 2, None).await?;
-        let stream = exec.execute(0).await?;
+        let stream = exec.execute(0, task_ctx).await?;
 
         let tt_batches = stream
             .map(|batch| {
@@ -109,9 +111,10 @@ mod tests {
 
     #[tokio::test]
     async fn read_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, Some(1)).await?;
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(11, batches[0].num_columns());
         assert_eq!(1, batches[0].num_rows());
@@ -121,6 +124,7 @@ mod tests {
 
     #[tokio::test]
     async fn read_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
@@ -147,7 +151,7 @@ mod tests {
             x
         );
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
 
         let expected =  vec![
@@ -171,10 +175,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_bool_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![1]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -199,10 +204,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_i32_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![0]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -224,10 +230,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_i96_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![10]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -249,10 +256,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_f32_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![6]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -277,10 +285,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_f64_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![7]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -305,10 +314,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_binary_alltypes_plain_avro() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![9]);
         let exec = get_exec("alltypes_plain.avro", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(batches.len(), 1);
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -29,7 +29,9 @@ use super::FileFormat;
 use crate::datasource::object_store::{ObjectReader, ObjectReaderStream};
 use crate::error::Result;
 use crate::logical_plan::Expr;
-use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
+use crate::physical_plan::file_format::{
+    CsvExec, PhysicalPlanConfig, ScanErrorPolicy, SkipLines,
+};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::Statistics;
 
@@ -39,6 +41,8 @@ pub struct CsvFormat {
     has_header: bool,
     delimiter: u8,
     schema_infer_max_rec: Option<usize>,
+    skip_rows: usize,
+    scan_error_policy: ScanErrorPolicy,
 }
 
 impl Default for CsvFormat {
@@ -47,6 +51,8 @@ impl Default for CsvFormat {
             schema_infer_max_rec: None,
             has_header: true,
             delimiter: b',',
+            skip_rows: 0,
+            scan_error_policy: ScanErrorPolicy::Fail,
         }
     }
 }
@@ -82,6 +88,32 @@ impl CsvFormat {
     pub fn delimiter(&self) -> u8 {
         self.delimiter
     }
+
+    /// Set the number of lines to skip at the start of each file, before
+    /// the header if any.
+    /// - default to 0
+    pub fn with_skip_rows(mut self, skip_rows: usize) -> Self {
+        self.skip_rows = skip_rows;
+        self
+    }
+
+    /// The number of lines skipped at the start of each file.
+    pub fn skip_rows(&self) -> usize {
+        self.skip_rows
+    }
+
+    /// Set what the scan does when a file cannot be read, see
+    /// [`ScanErrorPolicy`].
+    /// - default to [`ScanErrorPolicy::Fail`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read.
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
 }
 
 #[async_trait]
@@ -96,7 +128,7 @@ impl FileFormat for CsvFormat {
         let mut records_to_read = self.schema_infer_max_rec.unwrap_or(std::usize::MAX);
 
         while let Some(obj_reader) = readers.next().await {
-            let mut reader = obj_reader?.sync_reader()?;
+            let mut reader = SkipLines::new(obj_reader?.sync_reader()?, self.skip_rows);
             let (schema, records_read) = arrow::csv::reader::infer_reader_schema(
                 &mut reader,
                 self.delimiter,
@@ -126,7 +158,9 @@ impl FileFormat for CsvFormat {
         conf: PhysicalPlanConfig,
         _filters: &[Expr],
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let exec = CsvExec::new(conf, self.has_header, self.delimiter);
+        let exec = CsvExec::new(conf, self.has_header, self.delimiter)
+            .with_skip_rows(self.skip_rows)
+            .with_scan_error_policy(self.scan_error_policy);
         Ok(Arc::new(exec))
     }
 }
@@ -144,15 +178,18 @@ mod tests {
                 local_unpartitioned_file, LocalFileSystem,
             },
         },
+        execution::context::ExecutionContext,
+        execution::options::CsvReadOptions,
         physical_plan::collect,
     };
 
     #[tokio::test]
     async fn read_small_batches() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         // skip column 9 that overflows the automaticly discovered column type of i64 (u64 would work)
         let projection = Some(vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 10, 11, 12]);
         let exec = get_exec("aggregate_test_100.csv", &projection, 2, None).await?;
-        let stream = exec.execute(0).await?;
+        let stream = exec.execute(0, task_ctx).await?;
 
         let tt_batches: i32 = stream
             .map(|batch| {
@@ -174,9 +211,10 @@ mod tests {
 
     #[tokio::test]
     async fn read_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![0, 1, 2, 3]);
         let exec = get_exec("aggregate_test_100.csv", &projection, 1024, Some(1)).await?;
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(4, batches[0].num_columns());
         assert_eq!(1, batches[0].num_rows());
@@ -219,10 +257,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_char_column() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![0]);
         let exec = get_exec("aggregate_test_100.csv", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await.expect("Collect batches");
+        let batches = collect(exec, task_ctx).await.expect("Collect batches");
 
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
@@ -243,6 +282,35 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn read_with_skip_rows() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().join("preamble.csv");
+        std::fs::write(
+            &path,
+            "exported by some tool\non 2021-12-01, at noon\na,b\n1,x\n2,y\n",
+        )?;
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "t",
+            path.to_str().unwrap(),
+            CsvReadOptions::new().skip_rows(2),
+        )
+        .await?;
+        let batches = ctx.sql("SELECT a, b FROM t").await?.collect().await?;
+        #[rustfmt::skip]
+        crate::assert_batches_eq!(&[
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 | x |",
+            "| 2 | y |",
+            "+---+---+",
+        ], &batches);
+        Ok(())
+    }
+
     async fn get_exec(
         file_name: &str,
         projection: &Option<Vec<usize>>,
@@ -0,0 +1,100 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Arrow IPC (Feather v2) format abstractions
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::{Schema, SchemaRef};
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use super::FileFormat;
+use crate::datasource::object_store::{ObjectReader, ObjectReaderStream};
+use crate::error::Result;
+use crate::logical_plan::Expr;
+use crate::physical_plan::file_format::{open_ipc_file, IpcExec, PhysicalPlanConfig};
+use crate::physical_plan::ExecutionPlan;
+use crate::physical_plan::Statistics;
+
+/// The default file extension of Arrow IPC files
+pub const DEFAULT_IPC_EXTENSION: &str = ".arrow";
+/// The default size of the partitions the large Arrow IPC files are split in
+pub const DEFAULT_IPC_PARTITION_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Arrow IPC `FileFormat` implementation.
+#[derive(Debug)]
+pub struct IpcFormat {
+    partition_size: u64,
+}
+
+impl Default for IpcFormat {
+    fn default() -> Self {
+        Self {
+            partition_size: DEFAULT_IPC_PARTITION_SIZE,
+        }
+    }
+}
+
+impl IpcFormat {
+    /// Scan the files of more than `partition_size` bytes in several
+    /// partitions of their record batch blocks
+    /// - defaults to [`DEFAULT_IPC_PARTITION_SIZE`]
+    pub fn with_partition_size(mut self, partition_size: u64) -> Self {
+        self.partition_size = partition_size;
+        self
+    }
+
+    /// The size of the partitions the large files are split in
+    pub fn partition_size(&self) -> u64 {
+        self.partition_size
+    }
+}
+
+#[async_trait]
+impl FileFormat for IpcFormat {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    async fn infer_schema(&self, mut readers: ObjectReaderStream) -> Result<SchemaRef> {
+        let mut schemas = vec![];
+        while let Some(obj_reader) = readers.next().await {
+            // the schema is stored in the file footer, no inference is needed
+            let reader = open_ipc_file(obj_reader?, Default::default())?;
+            schemas.push(reader.schema().as_ref().clone());
+        }
+        let merged_schema = Schema::try_merge(schemas)?;
+        Ok(Arc::new(merged_schema))
+    }
+
+    async fn infer_stats(&self, _reader: Arc<dyn ObjectReader>) -> Result<Statistics> {
+        Ok(Statistics::default())
+    }
+
+    async fn create_physical_plan(
+        &self,
+        conf: PhysicalPlanConfig,
+        _filters: &[Expr],
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let exec = IpcExec::new(conf)
+            .with_block_partitions(self.partition_size)
+            .await?;
+        Ok(Arc::new(exec))
+    }
+}
@@ -33,7 +33,7 @@ use super::PhysicalPlanConfig;
 use crate::datasource::object_store::{ObjectReader, ObjectReaderStream};
 use crate::error::Result;
 use crate::logical_plan::Expr;
-use crate::physical_plan::file_format::NdJsonExec;
+use crate::physical_plan::file_format::{NdJsonExec, ScanErrorPolicy};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::Statistics;
 
@@ -41,6 +41,7 @@ use crate::physical_plan::Statistics;
 #[derive(Debug, Default)]
 pub struct JsonFormat {
     schema_infer_max_rec: Option<usize>,
+    scan_error_policy: ScanErrorPolicy,
 }
 
 impl JsonFormat {
@@ -50,6 +51,19 @@ impl JsonFormat {
         self.schema_infer_max_rec = max_rec;
         self
     }
+
+    /// Set what the scan does when a file cannot be read, see
+    /// [`ScanErrorPolicy`].
+    /// - default to [`ScanErrorPolicy::Fail`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read.
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
 }
 
 #[async_trait]
@@ -88,7 +102,7 @@ impl FileFormat for JsonFormat {
         conf: PhysicalPlanConfig,
         _filters: &[Expr],
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let exec = NdJsonExec::new(conf);
+        let exec = NdJsonExec::new(conf).with_scan_error_policy(self.scan_error_policy);
         Ok(Arc::new(exec))
     }
 }
@@ -106,14 +120,16 @@ mod tests {
                 local_unpartitioned_file, LocalFileSystem,
             },
         },
+        execution::context::ExecutionContext,
         physical_plan::collect,
     };
 
     #[tokio::test]
     async fn read_small_batches() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec(&projection, 2, None).await?;
-        let stream = exec.execute(0).await?;
+        let stream = exec.execute(0, task_ctx).await?;
 
         let tt_batches: i32 = stream
             .map(|batch| {
@@ -135,9 +151,10 @@ mod tests {
 
     #[tokio::test]
     async fn read_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec(&projection, 1024, Some(1)).await?;
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(4, batches[0].num_columns());
         assert_eq!(1, batches[0].num_rows());
@@ -163,10 +180,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_int_column() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![0]);
         let exec = get_exec(&projection, 1024, None).await?;
 
-        let batches = collect(exec).await.expect("Collect batches");
+        let batches = collect(exec, task_ctx).await.expect("Collect batches");
 
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
@@ -19,6 +19,7 @@
 
 pub mod avro;
 pub mod csv;
+pub mod ipc;
 pub mod json;
 pub mod parquet;
 
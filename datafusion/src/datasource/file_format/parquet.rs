@@ -27,6 +27,7 @@ use async_trait::async_trait;
 use futures::stream::StreamExt;
 use parquet::arrow::ArrowReader;
 use parquet::arrow::ParquetFileArrowReader;
+use parquet::arrow::ARROW_SCHEMA_META_KEY;
 use parquet::errors::ParquetError;
 use parquet::errors::Result as ParquetResult;
 use parquet::file::reader::ChunkReader;
@@ -44,7 +45,7 @@ use crate::error::Result;
 use crate::logical_plan::combine_filters;
 use crate::logical_plan::Expr;
 use crate::physical_plan::expressions::{MaxAccumulator, MinAccumulator};
-use crate::physical_plan::file_format::ParquetExec;
+use crate::physical_plan::file_format::{ParquetExec, ScanErrorPolicy};
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::{Accumulator, Statistics};
 use crate::scalar::ScalarValue;
@@ -56,12 +57,14 @@ pub const DEFAULT_PARQUET_EXTENSION: &str = ".parquet";
 #[derive(Debug)]
 pub struct ParquetFormat {
     enable_pruning: bool,
+    scan_error_policy: ScanErrorPolicy,
 }
 
 impl Default for ParquetFormat {
     fn default() -> Self {
         Self {
             enable_pruning: true,
+            scan_error_policy: ScanErrorPolicy::Fail,
         }
     }
 }
@@ -77,6 +80,19 @@ impl ParquetFormat {
     pub fn enable_pruning(&self) -> bool {
         self.enable_pruning
     }
+
+    /// Set what the scan does when a file cannot be read, see
+    /// [`ScanErrorPolicy`].
+    /// - default to [`ScanErrorPolicy::Fail`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read.
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
 }
 
 #[async_trait]
@@ -116,7 +132,10 @@ impl FileFormat for ParquetFormat {
             None
         };
 
-        Ok(Arc::new(ParquetExec::new(conf, predicate)))
+        Ok(Arc::new(
+            ParquetExec::new(conf, predicate)
+                .with_scan_error_policy(self.scan_error_policy),
+        ))
     }
 }
 
@@ -127,6 +146,35 @@ fn summarize_min_max(
     i: usize,
     stat: &ParquetStatistics,
 ) {
+    if let DataType::Decimal(precision, scale) = fields[i].data_type() {
+        // decimals of up to 18 digits may be stored as integers, whose
+        // statistics are compared as the decimals are. The statistics of the
+        // decimals stored as bytes are not, as they may be compared unsigned.
+        let min_max = match stat {
+            ParquetStatistics::Int32(s) if s.has_min_max_set() => {
+                Some((*s.min() as i128, *s.max() as i128))
+            }
+            ParquetStatistics::Int64(s) if s.has_min_max_set() => {
+                Some((*s.min() as i128, *s.max() as i128))
+            }
+            _ => None,
+        };
+        if let Some((min, max)) = min_max {
+            let min = ScalarValue::Decimal128(Some(min), *precision, *scale);
+            let max = ScalarValue::Decimal128(Some(max), *precision, *scale);
+            if let Some(max_value) = &mut max_values[i] {
+                if max_value.update(&[max]).is_err() {
+                    max_values[i] = None;
+                }
+            }
+            if let Some(min_value) = &mut min_values[i] {
+                if min_value.update(&[min]).is_err() {
+                    min_values[i] = None;
+                }
+            }
+        }
+        return;
+    }
     match stat {
         ParquetStatistics::Boolean(s) => {
             if let DataType::Boolean = fields[i].data_type() {
@@ -242,14 +290,33 @@ fn summarize_min_max(
     }
 }
 
-/// Read and parse the schema of the Parquet file at location `path`
+/// Read and parse the schema of the Parquet file at location `path`.
+///
+/// The metadata of the schema and of its fields is the one of the Arrow
+/// schema embedded by the writer, if any, completed with the key/value
+/// metadata of the file.
 fn fetch_schema(object_reader: Arc<dyn ObjectReader>) -> Result<Schema> {
     let obj_reader = ChunkObjectReader(object_reader);
     let file_reader = Arc::new(SerializedFileReader::new(obj_reader)?);
     let mut arrow_reader = ParquetFileArrowReader::new(file_reader);
     let schema = arrow_reader.get_schema()?;
 
-    Ok(schema)
+    let mut metadata = schema.metadata().clone();
+    let file_metadata = arrow_reader.get_metadata();
+    if let Some(key_value_metadata) = file_metadata.file_metadata().key_value_metadata() {
+        for kv in key_value_metadata {
+            if kv.key == ARROW_SCHEMA_META_KEY {
+                continue;
+            }
+            if let Some(value) = &kv.value {
+                metadata
+                    .entry(kv.key.clone())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+
+    Ok(Schema::new_with_metadata(schema.fields().clone(), metadata))
 }
 
 /// Read and parse the statistics of the Parquet file at location `path`
@@ -337,6 +404,7 @@ mod tests {
             local_object_reader, local_object_reader_stream, local_unpartitioned_file,
             LocalFileSystem,
         },
+        execution::context::ExecutionContext,
         physical_plan::collect,
     };
 
@@ -349,9 +417,10 @@ mod tests {
 
     #[tokio::test]
     async fn read_small_batches() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.parquet", &projection, 2, None).await?;
-        let stream = exec.execute(0).await?;
+        let stream = exec.execute(0, task_ctx).await?;
 
         let tt_batches = stream
             .map(|batch| {
@@ -373,6 +442,7 @@ mod tests {
 
     #[tokio::test]
     async fn read_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, Some(1)).await?;
 
@@ -380,7 +450,7 @@ mod tests {
         assert_eq!(exec.statistics().num_rows, Some(8));
         assert_eq!(exec.statistics().total_byte_size, Some(671));
         assert!(exec.statistics().is_exact);
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(11, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -390,6 +460,7 @@ mod tests {
 
     #[tokio::test]
     async fn read_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = None;
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
@@ -415,7 +486,7 @@ mod tests {
             y
         );
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
 
         assert_eq!(1, batches.len());
         assert_eq!(11, batches[0].num_columns());
@@ -426,10 +497,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_bool_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![1]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -454,10 +526,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_i32_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![0]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -479,10 +552,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_i96_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![10]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -504,10 +578,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_f32_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![6]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -532,10 +607,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_f64_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![7]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
@@ -560,10 +636,11 @@ mod tests {
 
     #[tokio::test]
     async fn read_binary_alltypes_plain_parquet() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let projection = Some(vec![9]);
         let exec = get_exec("alltypes_plain.parquet", &projection, 1024, None).await?;
 
-        let batches = collect(exec).await?;
+        let batches = collect(exec, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(8, batches[0].num_rows());
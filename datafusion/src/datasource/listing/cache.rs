@@ -0,0 +1,148 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cache of the files listed from an object store
+
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use futures::{stream, TryStreamExt};
+
+use crate::datasource::object_store::{FileMeta, FileMetaStream};
+use crate::error::Result;
+
+/// A listing of the files of a prefix, with the time it was made
+struct CachedListing {
+    listed_at: Instant,
+    files: Vec<FileMeta>,
+}
+
+/// Caches the files listed under a prefix so that planning successive queries
+/// over the same table does not list the object store again.
+///
+/// Entries expire after the optional `ttl`, or when the cache is cleared.
+pub struct ListingCache {
+    ttl: Option<Duration>,
+    entries: RwLock<HashMap<String, CachedListing>>,
+}
+
+impl ListingCache {
+    /// Create an empty cache whose entries expire after `ttl`, or never
+    /// expire if `ttl` is `None`
+    pub fn new(ttl: Option<Duration>) -> Self {
+        Self {
+            ttl,
+            entries: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Get the files cached for `prefix`, if any and not expired
+    pub fn get(&self, prefix: &str) -> Option<FileMetaStream> {
+        let entries = self.entries.read().unwrap();
+        let listing = entries.get(prefix)?;
+        if let Some(ttl) = self.ttl {
+            if listing.listed_at.elapsed() > ttl {
+                return None;
+            }
+        }
+        Some(Box::pin(stream::iter(
+            listing.files.clone().into_iter().map(Ok),
+        )))
+    }
+
+    /// Collect `files` and cache them for `prefix`. Returns a stream of the
+    /// collected files.
+    pub async fn insert(
+        &self,
+        prefix: &str,
+        files: FileMetaStream,
+    ) -> Result<FileMetaStream> {
+        let files: Vec<FileMeta> = files.try_collect().await?;
+        self.entries.write().unwrap().insert(
+            prefix.to_owned(),
+            CachedListing {
+                listed_at: Instant::now(),
+                files: files.clone(),
+            },
+        );
+        Ok(Box::pin(stream::iter(files.into_iter().map(Ok))))
+    }
+
+    /// Remove all the cached listings
+    pub fn clear(&self) {
+        self.entries.write().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::object_store::SizedFile;
+    use futures::StreamExt;
+
+    fn file_stream(paths: &[&str]) -> FileMetaStream {
+        let files: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                Ok(FileMeta {
+                    sized_file: SizedFile {
+                        path: path.to_string(),
+                        size: 10,
+                    },
+                    last_modified: None,
+                })
+            })
+            .collect();
+        Box::pin(stream::iter(files))
+    }
+
+    async fn paths(files: FileMetaStream) -> Vec<String> {
+        files.map(|f| f.unwrap().path().to_owned()).collect().await
+    }
+
+    #[tokio::test]
+    async fn cache_until_cleared() -> Result<()> {
+        let cache = ListingCache::new(None);
+        assert!(cache.get("table/").is_none());
+
+        let listed = cache
+            .insert("table/", file_stream(&["table/a", "table/b"]))
+            .await?;
+        assert_eq!(paths(listed).await, vec!["table/a", "table/b"]);
+        assert_eq!(
+            paths(cache.get("table/").unwrap()).await,
+            vec!["table/a", "table/b"]
+        );
+        assert!(cache.get("other/").is_none());
+
+        cache.clear();
+        assert!(cache.get("table/").is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn entries_expire_after_ttl() -> Result<()> {
+        let cache = ListingCache::new(Some(Duration::from_millis(20)));
+        cache.insert("table/", file_stream(&["table/a"])).await?;
+        assert!(cache.get("table/").is_some());
+
+        std::thread::sleep(Duration::from_millis(40));
+        assert!(cache.get("table/").is_none());
+        Ok(())
+    }
+}
@@ -21,9 +21,10 @@ use std::sync::Arc;
 
 use arrow::{
     array::{
-        Array, ArrayBuilder, ArrayRef, Date64Array, Date64Builder, StringArray,
-        StringBuilder, UInt64Array, UInt64Builder,
+        new_empty_array, Array, ArrayBuilder, ArrayRef, Date64Array, Date64Builder,
+        StringArray, StringBuilder, UInt64Array, UInt64Builder,
     },
+    compute::cast,
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
 };
@@ -32,18 +33,20 @@ use futures::{
     stream::{self},
     StreamExt, TryStreamExt,
 };
+use glob::{MatchOptions, Pattern};
 use log::debug;
 
 use crate::{
-    error::Result,
+    error::{DataFusionError, Result},
     execution::context::ExecutionContext,
     logical_plan::{self, Expr, ExpressionVisitor, Recursion},
+    optimizer::utils::split_conjunction,
     physical_plan::functions::Volatility,
     scalar::ScalarValue,
 };
 
 use crate::datasource::{
-    object_store::{FileMeta, ObjectStore, SizedFile},
+    object_store::{FileMeta, FileMetaStream, ObjectStore, SizedFile},
     MemTable, PartitionedFile, PartitionedFileStream,
 };
 
@@ -92,6 +95,7 @@ impl ExpressionVisitor for ApplicabilityVisitor<'_> {
             | Expr::Between { .. }
             | Expr::InList { .. }
             | Expr::GetIndexedField { .. }
+            | Expr::Struct { .. }
             | Expr::Case { .. } => Recursion::Continue(self),
 
             Expr::ScalarFunction { fun, .. } => self.visit_volatility(fun.volatility()),
@@ -147,85 +151,137 @@ pub fn split_files(
         .collect()
 }
 
-/// Discover the partitions on the given path and prune out files
-/// that belong to irrelevant partitions using `filters` expressions.
-/// `filters` might contain expressions that can be resolved only at the
-/// file level (e.g. Parquet row group pruning).
+/// Split `table_path` into the longest directory prefix without glob
+/// characters and, if the path contains any, the glob pattern that listed
+/// files must match. A pattern ending with `/` matches all the files below
+/// the directories it matches.
+pub fn split_glob_path(table_path: &str) -> Result<(&str, Option<Pattern>)> {
+    let glob_start = match table_path.find(|c| matches!(c, '*' | '?' | '[')) {
+        Some(glob_start) => glob_start,
+        None => return Ok((table_path, None)),
+    };
+    let prefix = match table_path[..glob_start].rfind('/') {
+        Some(i) => &table_path[..=i],
+        None => {
+            return Err(DataFusionError::Plan(format!(
+                "Glob pattern {} must start with a directory",
+                table_path
+            )))
+        }
+    };
+
+    let pattern = if table_path.ends_with('/') {
+        format!("{}**", table_path)
+    } else {
+        table_path.to_owned()
+    };
+    let pattern = Pattern::new(&pattern).map_err(|e| {
+        DataFusionError::Plan(format!("Invalid glob pattern {}: {}", table_path, e))
+    })?;
+    Ok((prefix, Some(pattern)))
+}
+
+/// List the files with the given suffix on `table_path`, which can be a
+/// glob pattern (see [`split_glob_path`]).
+pub async fn list_files_matching(
+    store: &dyn ObjectStore,
+    table_path: &str,
+    file_extension: &str,
+) -> Result<FileMetaStream> {
+    let (prefix, pattern) = split_glob_path(table_path)?;
+    let files = store.list_file_with_suffix(prefix, file_extension).await?;
+    match pattern {
+        None => Ok(files),
+        Some(pattern) => {
+            let options = MatchOptions {
+                case_sensitive: true,
+                require_literal_separator: true,
+                require_literal_leading_dot: false,
+            };
+            Ok(Box::pin(files.filter(move |f| {
+                let matches = match f {
+                    Ok(f) => pattern.matches_with(f.path(), options),
+                    Err(_) => true,
+                };
+                async move { matches }
+            })))
+        }
+    }
+}
+
+/// Prune out the `files` listed on `table_path` that belong to irrelevant
+/// partitions using `filters` expressions, and parse the partition values of
+/// the others. `filters` might contain expressions that can be resolved only
+/// at the file level (e.g. Parquet row group pruning).
 ///
 /// TODO for tables with many files (10k+), it will usually more efficient
 /// to first list the folders relative to the first partition dimension,
 /// prune those, then list only the contain of the remaining folders.
-pub async fn pruned_partition_list(
-    store: &dyn ObjectStore,
+pub async fn prune_partitions(
+    files: FileMetaStream,
     table_path: &str,
     filters: &[Expr],
-    file_extension: &str,
-    table_partition_cols: &[String],
+    table_partition_cols: &[(String, DataType)],
 ) -> Result<PartitionedFileStream> {
     // if no partition col => simply list all the files
     if table_partition_cols.is_empty() {
-        return Ok(Box::pin(
-            store
-                .list_file_with_suffix(table_path, file_extension)
-                .await?
-                .map(|f| {
-                    Ok(PartitionedFile {
-                        partition_values: vec![],
-                        file_meta: f?,
-                    })
-                }),
-        ));
+        return Ok(Box::pin(files.map(|f| {
+            Ok(PartitionedFile {
+                partition_values: vec![],
+                file_meta: f?,
+            })
+        })));
     }
 
-    let applicable_filters: Vec<_> = filters
+    // conjuncts that only use partition columns can prune, even if the rest of
+    // the filter has to be evaluated on the rows of the files
+    let mut conjuncts = vec![];
+    for filter in filters {
+        split_conjunction(filter, &mut conjuncts);
+    }
+    let partition_col_names: Vec<_> = table_partition_cols
         .iter()
-        .filter(|f| expr_applicable_for_cols(table_partition_cols, f))
+        .map(|(name, _)| name.clone())
+        .collect();
+    let applicable_filters: Vec<_> = conjuncts
+        .into_iter()
+        .filter(|f| expr_applicable_for_cols(&partition_col_names, f))
         .collect();
-    let stream_path = table_path.to_owned();
+    // partition values are parsed from the path relative to the glob prefix
+    let (prefix, _) = split_glob_path(table_path)?;
+    let stream_path = prefix.to_owned();
     if applicable_filters.is_empty() {
         // Parse the partition values while listing all the files
         // Note: We might avoid parsing the partition values if they are not used in any projection,
         // but the cost of parsing will likely be far dominated by the time to fetch the listing from
         // the object store.
         let table_partition_cols_stream = table_partition_cols.to_vec();
-        Ok(Box::pin(
-            store
-                .list_file_with_suffix(table_path, file_extension)
-                .await?
-                .filter_map(move |f| {
-                    let stream_path = stream_path.clone();
-                    let table_partition_cols_stream = table_partition_cols_stream.clone();
-                    async move {
-                        let file_meta = match f {
-                            Ok(fm) => fm,
-                            Err(err) => return Some(Err(err)),
-                        };
-                        let parsed_path = parse_partitions_for_path(
-                            &stream_path,
-                            file_meta.path(),
-                            &table_partition_cols_stream,
-                        )
-                        .map(|p| {
-                            p.iter()
-                                .map(|&pn| ScalarValue::Utf8(Some(pn.to_owned())))
-                                .collect()
-                        });
-
-                        parsed_path.map(|partition_values| {
-                            Ok(PartitionedFile {
-                                partition_values,
-                                file_meta,
-                            })
-                        })
-                    }
-                }),
-        ))
+        Ok(Box::pin(files.filter_map(move |f| {
+            let stream_path = stream_path.clone();
+            let table_partition_cols_stream = table_partition_cols_stream.clone();
+            async move {
+                let file_meta = match f {
+                    Ok(fm) => fm,
+                    Err(err) => return Some(Err(err)),
+                };
+                let partition_values = parse_partition_values(
+                    &stream_path,
+                    file_meta.path(),
+                    &table_partition_cols_stream,
+                );
+
+                partition_values.map(|partition_values| {
+                    Ok(PartitionedFile {
+                        partition_values,
+                        file_meta,
+                    })
+                })
+            }
+        })))
     } else {
         // parse the partition values and serde them as a RecordBatch to filter them
         // TODO avoid collecting but have a streaming memory table instead
-        let batches: Vec<RecordBatch> = store
-            .list_file_with_suffix(table_path, file_extension)
-            .await?
+        let batches: Vec<RecordBatch> = files
             // TODO we set an arbitrary high batch size here, it does not matter as we list
             // all the files anyway. This number will need to be adjusted according to the object
             // store if we switch to a streaming-stlye pruning of the files. For instance S3 lists
@@ -236,6 +292,9 @@ pub async fn pruned_partition_list(
             .try_collect()
             .await?;
 
+        if batches.is_empty() {
+            return Ok(Box::pin(stream::empty()));
+        }
         let mem_table = MemTable::try_new(batches[0].schema(), vec![batches])?;
 
         // Filter the partitions using a local datafusion context
@@ -262,20 +321,20 @@ pub async fn pruned_partition_list(
 ///
 /// Note: For the last modified date, this looses precisions higher than millisecond.
 fn paths_to_batch(
-    table_partition_cols: &[String],
+    table_partition_cols: &[(String, DataType)],
     table_path: &str,
     metas: &[FileMeta],
 ) -> Result<RecordBatch> {
     let mut key_builder = StringBuilder::new(metas.len());
     let mut length_builder = UInt64Builder::new(metas.len());
     let mut modified_builder = Date64Builder::new(metas.len());
-    let mut partition_builders = table_partition_cols
+    let mut partition_values = table_partition_cols
         .iter()
-        .map(|_| StringBuilder::new(metas.len()))
+        .map(|_| Vec::with_capacity(metas.len()))
         .collect::<Vec<_>>();
     for file_meta in metas {
-        if let Some(partition_values) =
-            parse_partitions_for_path(table_path, file_meta.path(), table_partition_cols)
+        if let Some(file_partition_values) =
+            parse_partition_values(table_path, file_meta.path(), table_partition_cols)
         {
             key_builder.append_value(file_meta.path())?;
             length_builder.append_value(file_meta.size())?;
@@ -283,11 +342,9 @@ fn paths_to_batch(
                 Some(lm) => modified_builder.append_value(lm.timestamp_millis())?,
                 None => modified_builder.append_null()?,
             }
-            for (i, part_val) in partition_values.iter().enumerate() {
-                partition_builders[i].append_value(part_val)?;
+            for (i, part_val) in file_partition_values.into_iter().enumerate() {
+                partition_values[i].push(part_val);
             }
-        } else {
-            debug!("No partitioning for path {}", file_meta.path());
         }
     }
 
@@ -297,8 +354,13 @@ fn paths_to_batch(
         ArrayBuilder::finish(&mut length_builder),
         ArrayBuilder::finish(&mut modified_builder),
     ];
-    for mut partition_builder in partition_builders {
-        col_arrays.push(ArrayBuilder::finish(&mut partition_builder));
+    for (values, (_, data_type)) in partition_values.into_iter().zip(table_partition_cols)
+    {
+        col_arrays.push(if values.is_empty() {
+            new_empty_array(data_type)
+        } else {
+            ScalarValue::iter_to_array(values)?
+        });
     }
 
     // put the schema together
@@ -307,8 +369,8 @@ fn paths_to_batch(
         Field::new(FILE_SIZE_COLUMN_NAME, DataType::UInt64, false),
         Field::new(FILE_MODIFIED_COLUMN_NAME, DataType::Date64, false),
     ];
-    for pn in table_partition_cols {
-        fields.push(Field::new(pn, DataType::Utf8, false));
+    for (pn, data_type) in table_partition_cols {
+        fields.push(Field::new(pn, data_type.clone(), false));
     }
 
     let batch = RecordBatch::try_new(Arc::new(Schema::new(fields)), col_arrays)?;
@@ -357,6 +419,56 @@ fn batches_to_paths(batches: &[RecordBatch]) -> Vec<PartitionedFile> {
         .collect()
 }
 
+/// Extract the partition values for the given `file_path` (in the given `table_path`)
+/// and parse them to the types of the partitions defined by `table_partition_cols`.
+/// Returns `None` if the path does not follow the partitioning.
+fn parse_partition_values(
+    table_path: &str,
+    file_path: &str,
+    table_partition_cols: &[(String, DataType)],
+) -> Option<Vec<ScalarValue>> {
+    let partition_col_names: Vec<_> = table_partition_cols
+        .iter()
+        .map(|(name, _)| name.clone())
+        .collect();
+    let values =
+        match parse_partitions_for_path(table_path, file_path, &partition_col_names) {
+            Some(values) => values,
+            None => {
+                debug!("No partitioning for path {}", file_path);
+                return None;
+            }
+        };
+    values
+        .into_iter()
+        .zip(table_partition_cols)
+        .map(|(value, (name, data_type))| {
+            let parsed = parse_partition_value(value, data_type);
+            if parsed.is_none() {
+                debug!(
+                    "Partition value {} of column {} in path {} is not a valid {}",
+                    value, name, file_path, data_type
+                );
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// Parse a partition value to a scalar of type `data_type`, `None` if it is
+/// not a valid value of that type
+fn parse_partition_value(value: &str, data_type: &DataType) -> Option<ScalarValue> {
+    let value = ScalarValue::Utf8(Some(value.to_owned()));
+    if data_type == &DataType::Utf8 {
+        return Some(value);
+    }
+    let array = cast(&value.to_array(), data_type).ok()?;
+    if array.is_null(0) {
+        return None;
+    }
+    ScalarValue::try_from_array(&array, 0).ok()
+}
+
 /// Extract the partition values for the given `file_path` (in the given `table_path`)
 /// associated to the partitions defined by `table_partition_cols`
 fn parse_partitions_for_path<'a>(
@@ -391,6 +503,22 @@ mod tests {
 
     use super::*;
 
+    /// List the files on `table_path` and prune their partitions
+    async fn pruned_partition_list(
+        store: &dyn ObjectStore,
+        table_path: &str,
+        filters: &[Expr],
+        file_extension: &str,
+        table_partition_cols: &[String],
+    ) -> Result<PartitionedFileStream> {
+        let table_partition_cols: Vec<_> = table_partition_cols
+            .iter()
+            .map(|name| (name.clone(), DataType::Utf8))
+            .collect();
+        let files = list_files_matching(store, table_path, file_extension).await?;
+        prune_partitions(files, table_path, filters, &table_partition_cols).await
+    }
+
     #[test]
     fn test_split_files() {
         let new_partitioned_file = |path: &str| PartitionedFile::new(path.to_owned(), 10);
@@ -546,6 +674,95 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_pruned_partition_list_typed() -> Result<()> {
+        let store = TestObjectStore::new_arc(&[
+            ("tablepath/year=2019/file.parquet", 100),
+            ("tablepath/year=2020/file.parquet", 100),
+            ("tablepath/year=2021/file.parquet", 100),
+            ("tablepath/year=2022/file.parquet", 100),
+            ("tablepath/year=unknown/file.parquet", 100),
+        ]);
+        let partition_cols = vec![("year".to_owned(), DataType::Int32)];
+        let years = |filters: Vec<Expr>| {
+            let store = Arc::clone(&store);
+            let partition_cols = partition_cols.clone();
+            async move {
+                let files =
+                    list_files_matching(store.as_ref(), "tablepath/", ".parquet").await?;
+                prune_partitions(files, "tablepath/", &filters, &partition_cols)
+                    .await?
+                    .map_ok(|f| f.partition_values)
+                    .try_collect::<Vec<_>>()
+                    .await
+            }
+        };
+        let year = |year: i32| vec![ScalarValue::Int32(Some(year))];
+
+        // the values that are not integers are ignored
+        assert_eq!(
+            years(vec![]).await?,
+            vec![year(2019), year(2020), year(2021), year(2022)]
+        );
+
+        // the values are compared as integers
+        let range = col("year").gt_eq(lit(2020)).and(col("year").lt(lit(2022)));
+        assert_eq!(years(vec![range]).await?, vec![year(2020), year(2021)]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pruned_partition_list_glob() {
+        let store = TestObjectStore::new_arc(&[
+            ("tablepath/part1=p1v1/a/file.parquet", 100),
+            ("tablepath/part1=p1v1/b/file.parquet", 100),
+            ("tablepath/part1=p1v2/a/file.parquet", 100),
+            ("tablepath/part1=p1v2/a/nested/file.parquet", 100),
+        ]);
+        let filter = Expr::eq(col("part1"), lit("p1v2"));
+        let pruned = pruned_partition_list(
+            store.as_ref(),
+            "tablepath/part1=*/a/*",
+            &[filter],
+            ".parquet",
+            &[String::from("part1")],
+        )
+        .await
+        .expect("partition pruning failed")
+        .collect::<Vec<_>>()
+        .await;
+
+        assert_eq!(pruned.len(), 1);
+        let f1 = pruned[0].as_ref().expect("first item not an error");
+        assert_eq!(
+            &f1.file_meta.sized_file.path,
+            "tablepath/part1=p1v2/a/file.parquet"
+        );
+        assert_eq!(
+            &f1.partition_values,
+            &[ScalarValue::Utf8(Some(String::from("p1v2"))),]
+        );
+    }
+
+    #[test]
+    fn test_split_glob_path() {
+        let (prefix, pattern) = split_glob_path("bucket/path/").unwrap();
+        assert_eq!("bucket/path/", prefix);
+        assert!(pattern.is_none());
+
+        let (prefix, pattern) = split_glob_path("bucket/path/dt=2021-*/").unwrap();
+        assert_eq!("bucket/path/", prefix);
+        assert_eq!("bucket/path/dt=2021-*/**", pattern.unwrap().as_str());
+
+        let (prefix, pattern) = split_glob_path("bucket/p?th/*.csv").unwrap();
+        assert_eq!("bucket/", prefix);
+        assert_eq!("bucket/p?th/*.csv", pattern.unwrap().as_str());
+
+        assert!(split_glob_path("*.csv").is_err());
+        assert!(split_glob_path("bucket/[a/b").is_err());
+    }
+
     #[test]
     fn test_parse_partitions_for_path() {
         assert_eq!(
@@ -18,7 +18,8 @@
 //! A table that uses the `ObjectStore` listing capability
 //! to get the list of files to process.
 
+mod cache;
 mod helpers;
 mod table;
 
-pub use table::{ListingOptions, ListingTable};
+pub use table::{ListingOptions, ListingTable, SchemaRefresh};
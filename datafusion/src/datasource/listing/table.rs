@@ -17,28 +17,40 @@
 
 //! The table implementation.
 
-use std::{any::Any, sync::Arc};
+use std::{
+    any::Any,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 
-use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use async_trait::async_trait;
 use futures::StreamExt;
 
 use crate::{
-    error::Result,
+    error::{DataFusionError, Result},
     logical_plan::Expr,
     physical_plan::{
         empty::EmptyExec,
-        file_format::{PhysicalPlanConfig, DEFAULT_PARTITION_COLUMN_DATATYPE},
+        file_format::{partition_column_type, PhysicalPlanConfig},
+        metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet, Time},
         ExecutionPlan, Statistics,
     },
 };
 
 use crate::datasource::{
-    datasource::TableProviderFilterPushDown, file_format::FileFormat,
-    get_statistics_with_limit, object_store::ObjectStore, PartitionedFile, TableProvider,
+    datasource::TableProviderFilterPushDown,
+    file_format::FileFormat,
+    get_statistics_with_limit,
+    object_store::{FileMetaStream, ObjectStore},
+    PartitionedFile, TableProvider,
 };
 
-use super::helpers::{expr_applicable_for_cols, pruned_partition_list, split_files};
+use super::cache::ListingCache;
+use super::helpers::{
+    expr_applicable_for_cols, list_files_matching, prune_partitions, split_files,
+};
 
 /// Options for creating a `ListingTable`
 pub struct ListingOptions {
@@ -47,14 +59,16 @@ pub struct ListingOptions {
     pub file_extension: String,
     /// The file format
     pub format: Arc<dyn FileFormat>,
-    /// The expected partition column names in the folder structure.
-    /// For example `Vec["a", "b"]` means that the two first levels of
-    /// partitioning expected should be named "a" and "b":
+    /// The expected partition column names in the folder structure, with the
+    /// types their values are parsed to.
+    /// For example `Vec[("a", Utf8), ("b", Int32)]` means that the two first
+    /// levels of partitioning expected should be named "a" and "b":
     /// - If there is a third level of partitioning it will be ignored.
-    /// - Files that don't follow this partitioning will be ignored.
-    /// Note that only `DEFAULT_PARTITION_COLUMN_DATATYPE` is currently
-    /// supported for the column type.
-    pub table_partition_cols: Vec<String>,
+    /// - Files that don't follow this partitioning, or whose partition values
+    ///   can't be parsed to the column types, will be ignored.
+    /// The partition columns are dictionary encoded, see
+    /// [`partition_column_type`].
+    pub table_partition_cols: Vec<(String, DataType)>,
     /// Set true to try to guess statistics from the files.
     /// This can add a lot of overhead as it will usually require files
     /// to be opened and at least partially parsed.
@@ -92,25 +106,123 @@ impl ListingOptions {
         object_store: Arc<dyn ObjectStore>,
         path: &'a str,
     ) -> Result<SchemaRef> {
-        let file_stream = object_store
-            .list_file_with_suffix(path, &self.file_extension)
-            .await?
-            .map(move |file_meta| object_store.file_reader(file_meta?.sized_file));
+        let file_stream =
+            list_files_matching(object_store.as_ref(), path, &self.file_extension)
+                .await?
+                .map(move |file_meta| object_store.file_reader(file_meta?.sized_file));
         let file_schema = self.format.infer_schema(Box::pin(file_stream)).await?;
         Ok(file_schema)
     }
 }
 
+/// How [`ListingTable::refresh`] updates the schema of the table.
+///
+/// The columns that are still in the files keep their position in the table,
+/// whatever their order in the files, and the new columns are appended to it.
+/// A refresh fails if the type of a column changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaRefresh {
+    /// Keep the schema the table was created with
+    Keep,
+    /// Replace the schema of the files by the schema inferred from them, so
+    /// that the columns that are no longer in the files are dropped
+    Infer,
+    /// Merge the schema inferred from the files into the current schema of
+    /// the files, so that the columns of new files are added to the table
+    Merge,
+}
+
+/// Update the `current` schema of the files with the schema `inferred` from
+/// them, as described in [`SchemaRefresh`]. The columns missing from the
+/// `inferred` schema are only kept if `keep_missing` is set.
+fn refresh_file_schema(
+    current: &Schema,
+    inferred: &Schema,
+    keep_missing: bool,
+) -> Result<Schema> {
+    let mut fields = Vec::with_capacity(inferred.fields().len());
+    for field in current.fields() {
+        match inferred.field_with_name(field.name()) {
+            Ok(new_field) if new_field.data_type() != field.data_type() => {
+                return Err(DataFusionError::Plan(format!(
+                    "Cannot refresh the schema of the table: the type of column '{}' \
+                     changed from {:?} to {:?}",
+                    field.name(),
+                    field.data_type(),
+                    new_field.data_type()
+                )));
+            }
+            Ok(_) => fields.push(field.clone()),
+            Err(_) if keep_missing => fields.push(field.clone()),
+            Err(_) => {}
+        }
+    }
+    for new_field in inferred.fields() {
+        if current.field_with_name(new_field.name()).is_err() {
+            fields.push(new_field.clone());
+        }
+    }
+    Ok(Schema::new_with_metadata(
+        fields,
+        current.metadata().clone(),
+    ))
+}
+
+/// The schemas of a `ListingTable`, swapped together when it is refreshed
+struct TableSchemas {
+    /// File fields only
+    file_schema: SchemaRef,
+    /// File fields + partition columns
+    table_schema: SchemaRef,
+}
+
+impl TableSchemas {
+    fn new(file_schema: SchemaRef, table_partition_cols: &[(String, DataType)]) -> Self {
+        // Add the partition columns to the file schema
+        let mut table_fields = file_schema.fields().clone();
+        for (name, value_type) in table_partition_cols {
+            table_fields.push(Field::new(
+                name,
+                partition_column_type(value_type.clone()),
+                false,
+            ));
+        }
+        Self {
+            file_schema,
+            table_schema: Arc::new(Schema::new(table_fields)),
+        }
+    }
+}
+
 /// An implementation of `TableProvider` that uses the object store
 /// or file system listing capability to get the list of files.
+///
+/// By default the files are listed again at each scan, so that queries see
+/// the files added since the table was created. With a
+/// [listing cache](Self::with_listing_cache) the files are only listed again
+/// when the cache expires or the table is [refreshed](Self::refresh).
 pub struct ListingTable {
     object_store: Arc<dyn ObjectStore>,
     table_path: String,
-    /// File fields only
-    file_schema: SchemaRef,
-    /// File fields + partition columns
-    table_schema: SchemaRef,
+    schemas: RwLock<TableSchemas>,
+    /// How the schema is updated when the table is refreshed
+    schema_refresh: SchemaRefresh,
     options: ListingOptions,
+    /// Files listed by previous scans, if caching is enabled
+    listing_cache: Option<ListingCache>,
+    /// Planning metrics of the scans of the table
+    metrics: ExecutionPlanMetricsSet,
+    /// Time spent listing the files and collecting their statistics
+    listing_time: Time,
+    /// Number of times the object store was listed
+    list_calls: Count,
+    /// Number of listings served from the cache
+    listing_cache_hits: Count,
+    /// Number of refreshes since the table was created
+    version: AtomicU64,
+    /// Statistics of all the files of the table, see
+    /// [`collect_statistics`](Self::collect_statistics)
+    statistics: RwLock<Statistics>,
 }
 
 impl ListingTable {
@@ -124,23 +236,119 @@ impl ListingTable {
         file_schema: SchemaRef,
         options: ListingOptions,
     ) -> Self {
-        // Add the partition columns to the file schema
-        let mut table_fields = file_schema.fields().clone();
-        for part in &options.table_partition_cols {
-            table_fields.push(Field::new(
-                part,
-                DEFAULT_PARTITION_COLUMN_DATATYPE.clone(),
-                false,
-            ));
-        }
+        let schemas = TableSchemas::new(file_schema, &options.table_partition_cols);
+
+        let metrics = ExecutionPlanMetricsSet::new();
+        let listing_time = MetricBuilder::new(&metrics).global_timer("listing_time");
+        let list_calls = MetricBuilder::new(&metrics).global_counter("list_calls");
+        let listing_cache_hits =
+            MetricBuilder::new(&metrics).global_counter("listing_cache_hits");
 
         Self {
             object_store,
             table_path,
-            file_schema,
-            table_schema: Arc::new(Schema::new(table_fields)),
+            schemas: RwLock::new(schemas),
+            schema_refresh: SchemaRefresh::Keep,
             options,
+            listing_cache: None,
+            metrics,
+            listing_time,
+            list_calls,
+            listing_cache_hits,
+            version: AtomicU64::new(0),
+            statistics: RwLock::new(Statistics::default()),
+        }
+    }
+
+    /// Cache the files listed from the object store so that the following
+    /// scans don't list it again. The cached files are listed again after
+    /// `ttl`, if any, or after the table is refreshed.
+    pub fn with_listing_cache(mut self, ttl: Option<Duration>) -> Self {
+        self.listing_cache = Some(ListingCache::new(ttl));
+        self
+    }
+
+    /// Set how the schema is updated when the table is refreshed. Defaults to
+    /// [`SchemaRefresh::Keep`].
+    pub fn with_schema_refresh(mut self, schema_refresh: SchemaRefresh) -> Self {
+        self.schema_refresh = schema_refresh;
+        self
+    }
+
+    /// List the files of the table again and update its schema according to
+    /// its [`SchemaRefresh`] mode. The cached listing, if any, and the schema
+    /// are swapped once the new ones are complete, so the scans planned
+    /// before keep the files and schema they were planned with.
+    ///
+    /// Every refresh increments the [version](TableProvider::version) of the
+    /// table, as the files may have changed, and collects its
+    /// [statistics](Self::collect_statistics) again.
+    pub async fn refresh(&self) -> Result<()> {
+        let file_schema = match self.schema_refresh {
+            SchemaRefresh::Keep => None,
+            SchemaRefresh::Infer | SchemaRefresh::Merge => {
+                let inferred = self.infer_file_schema().await?;
+                Some(Arc::new(refresh_file_schema(
+                    &self.file_schema(),
+                    &inferred,
+                    self.schema_refresh == SchemaRefresh::Merge,
+                )?))
+            }
+        };
+
+        if let Some(cache) = &self.listing_cache {
+            let files = self.list_object_store().await?;
+            cache.insert(&self.table_path, files).await?;
+        }
+        if let Some(file_schema) = file_schema {
+            *self.schemas.write().unwrap() =
+                TableSchemas::new(file_schema, &self.options.table_partition_cols);
         }
+        self.collect_statistics().await?;
+        self.version.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// Collect the statistics of all the files of the table, which are then
+    /// returned by [`TableProvider::statistics`] for the optimizer to estimate
+    /// the size of the table. They are only collected if
+    /// [`ListingOptions::collect_stat`] is set, and are also updated by the
+    /// scans that read all the files.
+    pub async fn collect_statistics(&self) -> Result<Statistics> {
+        if !self.options.collect_stat {
+            return Ok(Statistics::default());
+        }
+        let (_, statistics) = self.list_files_for_scan(self.schema(), &[], None).await?;
+        self.set_statistics(&statistics);
+        Ok(self.statistics())
+    }
+
+    /// Keep the statistics of all the files of the table. They stay unknown
+    /// if the format does not provide any, as the number of rows is then 0.
+    fn set_statistics(&self, statistics: &Statistics) {
+        *self.statistics.write().unwrap() = match statistics.column_statistics {
+            Some(_) => statistics.clone(),
+            None => Statistics::default(),
+        };
+    }
+
+    async fn infer_file_schema(&self) -> Result<SchemaRef> {
+        self.options
+            .infer_schema(Arc::clone(&self.object_store), &self.table_path)
+            .await
+    }
+
+    /// Get the schema of the files, without the partition columns
+    pub fn file_schema(&self) -> SchemaRef {
+        Arc::clone(&self.schemas.read().unwrap().file_schema)
+    }
+
+    /// Planning metrics of the scans of the table: the time spent listing
+    /// files (`listing_time`), the number of times the object store was
+    /// listed (`list_calls`) and the number of listings served from the
+    /// cache (`listing_cache_hits`)
+    pub fn metrics(&self) -> MetricsSet {
+        self.metrics.clone_inner()
     }
 
     /// Get object store ref
@@ -168,7 +376,7 @@ impl TableProvider for ListingTable {
     }
 
     fn schema(&self) -> SchemaRef {
-        Arc::clone(&self.table_schema)
+        Arc::clone(&self.schemas.read().unwrap().table_schema)
     }
 
     async fn scan(
@@ -178,12 +386,23 @@ impl TableProvider for ListingTable {
         filters: &[Expr],
         limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        let (partitioned_file_lists, statistics) =
-            self.list_files_for_scan(filters, limit).await?;
+        // the scan keeps the schemas it started with if the table is refreshed
+        let (file_schema, schema) = {
+            let schemas = self.schemas.read().unwrap();
+            (
+                Arc::clone(&schemas.file_schema),
+                Arc::clone(&schemas.table_schema),
+            )
+        };
+        let (partitioned_file_lists, statistics) = self
+            .list_files_for_scan(Arc::clone(&schema), filters, limit)
+            .await?;
+        if self.options.collect_stat && filters.is_empty() && limit.is_none() {
+            self.set_statistics(&statistics);
+        }
 
         // if no files need to be read, return an `EmptyExec`
         if partitioned_file_lists.is_empty() {
-            let schema = self.schema();
             let projected_schema = match &projection {
                 None => schema,
                 Some(p) => Arc::new(Schema::new(
@@ -199,7 +418,7 @@ impl TableProvider for ListingTable {
             .create_physical_plan(
                 PhysicalPlanConfig {
                     object_store: Arc::clone(&self.object_store),
-                    file_schema: Arc::clone(&self.file_schema),
+                    file_schema,
                     file_groups: partitioned_file_lists,
                     statistics,
                     projection: projection.clone(),
@@ -216,7 +435,13 @@ impl TableProvider for ListingTable {
         &self,
         filter: &Expr,
     ) -> Result<TableProviderFilterPushDown> {
-        if expr_applicable_for_cols(&self.options.table_partition_cols, filter) {
+        let partition_col_names: Vec<_> = self
+            .options
+            .table_partition_cols
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect();
+        if expr_applicable_for_cols(&partition_col_names, filter) {
             // if filter can be handled by partiton pruning, it is exact
             Ok(TableProviderFilterPushDown::Exact)
         } else {
@@ -225,6 +450,14 @@ impl TableProvider for ListingTable {
             Ok(TableProviderFilterPushDown::Inexact)
         }
     }
+
+    fn statistics(&self) -> Statistics {
+        self.statistics.read().unwrap().clone()
+    }
+
+    fn version(&self) -> Option<u64> {
+        Some(self.version.load(Ordering::SeqCst))
+    }
 }
 
 impl ListingTable {
@@ -233,15 +466,17 @@ impl ListingTable {
     /// be distributed to different threads / executors.
     async fn list_files_for_scan<'a>(
         &'a self,
+        table_schema: SchemaRef,
         filters: &'a [Expr],
         limit: Option<usize>,
     ) -> Result<(Vec<Vec<PartitionedFile>>, Statistics)> {
+        let _timer = self.listing_time.timer();
+
         // list files (with partitions)
-        let file_list = pruned_partition_list(
-            self.object_store.as_ref(),
+        let file_list = prune_partitions(
+            self.list_files().await?,
             &self.table_path,
             filters,
-            &self.options.file_extension,
             &self.options.table_partition_cols,
         )
         .await?;
@@ -264,13 +499,41 @@ impl ListingTable {
         });
 
         let (files, statistics) =
-            get_statistics_with_limit(files, self.schema(), limit).await?;
+            get_statistics_with_limit(files, table_schema, limit).await?;
 
         Ok((
             split_files(files, self.options.target_partitions),
             statistics,
         ))
     }
+
+    /// List the files of the table, from the cache if possible
+    async fn list_files(&self) -> Result<FileMetaStream> {
+        let cache = match &self.listing_cache {
+            Some(cache) => cache,
+            None => return self.list_object_store().await,
+        };
+        match cache.get(&self.table_path) {
+            Some(files) => {
+                self.listing_cache_hits.add(1);
+                Ok(files)
+            }
+            None => {
+                let files = self.list_object_store().await?;
+                cache.insert(&self.table_path, files).await
+            }
+        }
+    }
+
+    async fn list_object_store(&self) -> Result<FileMetaStream> {
+        self.list_calls.add(1);
+        list_files_matching(
+            self.object_store.as_ref(),
+            &self.table_path,
+            &self.options.file_extension,
+        )
+        .await
+    }
 }
 
 #[cfg(test)]
@@ -279,9 +542,10 @@ mod tests {
 
     use crate::{
         datasource::{
-            file_format::{avro::AvroFormat, parquet::ParquetFormat},
+            file_format::{avro::AvroFormat, csv::CsvFormat, parquet::ParquetFormat},
             object_store::local::LocalFileSystem,
         },
+        execution::context::ExecutionContext,
         logical_plan::{col, lit},
         test::{columns, object_store::TestObjectStore},
     };
@@ -331,7 +595,7 @@ mod tests {
         let opt = ListingOptions {
             file_extension: ".avro".to_owned(),
             format: Arc::new(AvroFormat {}),
-            table_partition_cols: vec![String::from("p1")],
+            table_partition_cols: vec![("p1".to_owned(), DataType::Utf8)],
             target_partitions: 4,
             collect_stat: true,
         };
@@ -362,6 +626,258 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn prune_partitions_with_filters() -> Result<()> {
+        let files: Vec<_> = (1..=5)
+            .map(|day| (format!("table/dt=2021-01-0{}/file.avro", day), 10))
+            .collect();
+        let store = TestObjectStore::new_arc(
+            &files
+                .iter()
+                .map(|(f, s)| (f.as_str(), *s))
+                .collect::<Vec<_>>(),
+        );
+
+        let opt = ListingOptions {
+            file_extension: ".avro".to_owned(),
+            format: Arc::new(AvroFormat {}),
+            table_partition_cols: vec![("dt".to_owned(), DataType::Utf8)],
+            target_partitions: 1,
+            collect_stat: false,
+        };
+        let file_schema = Schema::new(vec![Field::new("a", DataType::Boolean, false)]);
+        let table =
+            ListingTable::new(store, "table/".to_owned(), Arc::new(file_schema), opt);
+
+        let range = col("dt")
+            .gt_eq(lit("2021-01-02"))
+            .and(col("dt").lt(lit("2021-01-04")));
+        assert_listed_days(&table, range, &[2, 3]).await?;
+
+        let in_list =
+            col("dt").in_list(vec![lit("2021-01-01"), lit("2021-01-05")], false);
+        assert_listed_days(&table, in_list, &[1, 5]).await?;
+
+        let between = Expr::Between {
+            expr: Box::new(col("dt")),
+            negated: false,
+            low: Box::new(lit("2021-01-03")),
+            high: Box::new(lit("2021-01-05")),
+        };
+        assert_listed_days(&table, between, &[3, 4, 5]).await?;
+
+        // only the partition column conjunct prunes, `a` is filtered on the rows
+        let mixed = col("dt").eq(lit("2021-01-04")).and(col("a").eq(lit(true)));
+        assert_eq!(
+            table.supports_filter_pushdown(&mixed)?,
+            TableProviderFilterPushDown::Inexact
+        );
+        assert_listed_days(&table, mixed, &[4]).await?;
+
+        // a disjunction with a non partition column can't prune
+        let disjunction = col("dt").eq(lit("2021-01-04")).or(col("a").eq(lit(true)));
+        assert_listed_days(&table, disjunction, &[1, 2, 3, 4, 5]).await?;
+
+        Ok(())
+    }
+
+    /// Check that scanning `table` with `filter` only lists the files of the `days`
+    async fn assert_listed_days(
+        table: &ListingTable,
+        filter: Expr,
+        days: &[usize],
+    ) -> Result<()> {
+        let (file_list, _) = table
+            .list_files_for_scan(table.schema(), &[filter], None)
+            .await?;
+        let mut paths: Vec<_> = file_list
+            .iter()
+            .flatten()
+            .map(|f| f.file_meta.path().to_owned())
+            .collect();
+        paths.sort();
+        let expected: Vec<_> = days
+            .iter()
+            .map(|day| format!("table/dt=2021-01-0{}/file.avro", day))
+            .collect();
+        assert_eq!(paths, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn listing_cache_metrics() -> Result<()> {
+        let store = Arc::new(TestObjectStore::new(&[
+            ("table/file1.avro", 10),
+            ("table/file2.avro", 10),
+        ]));
+        let opt = ListingOptions {
+            file_extension: ".avro".to_owned(),
+            format: Arc::new(AvroFormat {}),
+            table_partition_cols: vec![],
+            target_partitions: 1,
+            collect_stat: false,
+        };
+        let file_schema = Schema::new(vec![Field::new("a", DataType::Boolean, false)]);
+        let table = ListingTable::new(
+            store.clone(),
+            "table/".to_owned(),
+            Arc::new(file_schema),
+            opt,
+        )
+        .with_listing_cache(None);
+
+        for _ in 0..3 {
+            let (file_list, _) =
+                table.list_files_for_scan(table.schema(), &[], None).await?;
+            assert_eq!(file_list.iter().flatten().count(), 2);
+        }
+        assert_eq!(store.list_calls(), 1);
+
+        let metric = |name: &str| {
+            table
+                .metrics()
+                .sum(|m| m.value().name() == name)
+                .map(|v| v.as_usize())
+        };
+        assert_eq!(metric("list_calls"), Some(1));
+        assert_eq!(metric("listing_cache_hits"), Some(2));
+        assert!(metric("listing_time").is_some());
+
+        // the refresh lists the files, the scan uses them
+        table.refresh().await?;
+        assert_eq!(store.list_calls(), 2);
+        table.list_files_for_scan(table.schema(), &[], None).await?;
+        assert_eq!(store.list_calls(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refresh_sees_new_files() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().to_str().unwrap().to_owned();
+        std::fs::write(tmp_dir.path().join("file1.csv"), "a\n1\n2\n")?;
+
+        let options = || ListingOptions {
+            file_extension: ".csv".to_owned(),
+            format: Arc::new(CsvFormat::default()),
+            table_partition_cols: vec![],
+            target_partitions: 1,
+            collect_stat: false,
+        };
+        let store: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem {});
+        let schema = options().infer_schema(Arc::clone(&store), &path).await?;
+        let cached = ListingTable::new(
+            Arc::clone(&store),
+            path.clone(),
+            schema.clone(),
+            options(),
+        )
+        .with_listing_cache(None);
+        let lazy = ListingTable::new(Arc::clone(&store), path.clone(), schema, options());
+
+        assert_eq!(count_rows(&cached).await?, 2);
+        assert_eq!(count_rows(&lazy).await?, 2);
+
+        std::fs::write(tmp_dir.path().join("file2.csv"), "a\n3\n")?;
+        assert_eq!(count_rows(&cached).await?, 2);
+        assert_eq!(count_rows(&lazy).await?, 3);
+
+        cached.refresh().await?;
+        assert_eq!(count_rows(&cached).await?, 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refresh_merges_schema() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().to_str().unwrap().to_owned();
+        std::fs::write(tmp_dir.path().join("file1.csv"), "a\n1\n")?;
+
+        let opt = ListingOptions {
+            file_extension: ".csv".to_owned(),
+            format: Arc::new(CsvFormat::default()),
+            table_partition_cols: vec![],
+            target_partitions: 1,
+            collect_stat: false,
+        };
+        let store: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem {});
+        let schema = opt.infer_schema(Arc::clone(&store), &path).await?;
+        let table = ListingTable::new(store, path, schema, opt)
+            .with_schema_refresh(SchemaRefresh::Merge);
+        // a scan planned before the refresh keeps the files and schema it had
+        let planned = table.scan(&None, 1024, &[], None).await?;
+
+        std::fs::write(tmp_dir.path().join("file2.csv"), "a,b\n2,x\n")?;
+        table.refresh().await?;
+
+        assert_eq!(planned.schema().fields().len(), 1);
+        let fields: Vec<_> = table
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(fields, vec!["a", "b"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_column_order() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = tmp_dir.path().to_str().unwrap().to_owned();
+        std::fs::write(tmp_dir.path().join("file1.csv"), "a,b\n1,x\n")?;
+
+        let opt = ListingOptions {
+            file_extension: ".csv".to_owned(),
+            format: Arc::new(CsvFormat::default()),
+            table_partition_cols: vec![],
+            target_partitions: 1,
+            collect_stat: false,
+        };
+        let store: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem {});
+        let schema = opt.infer_schema(Arc::clone(&store), &path).await?;
+        let table = ListingTable::new(store, path, schema, opt)
+            .with_schema_refresh(SchemaRefresh::Infer);
+        let field_names = |table: &ListingTable| -> Vec<String> {
+            table
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().clone())
+                .collect()
+        };
+
+        // the new file lists the columns in a different order
+        std::fs::write(tmp_dir.path().join("file2.csv"), "c,b,a\n2.5,y,3\n")?;
+        table.refresh().await?;
+        assert_eq!(field_names(&table), vec!["a", "b", "c"]);
+        assert_eq!(table.version(), Some(1));
+
+        // the type of a column can't change
+        std::fs::remove_file(tmp_dir.path().join("file1.csv"))?;
+        std::fs::write(tmp_dir.path().join("file2.csv"), "c,b,a\n2.5,y,z\n")?;
+        let err = table.refresh().await.unwrap_err();
+        assert!(
+            err.to_string().contains("the type of column 'a' changed"),
+            "{}",
+            err
+        );
+        assert_eq!(field_names(&table), vec!["a", "b", "c"]);
+        assert_eq!(table.version(), Some(1));
+
+        Ok(())
+    }
+
+    async fn count_rows(table: &ListingTable) -> Result<usize> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let exec = table.scan(&None, 1024, &[], None).await?;
+        let batches = crate::physical_plan::collect(exec, task_ctx).await?;
+        Ok(batches.iter().map(|b| b.num_rows()).sum())
+    }
+
     #[tokio::test]
     async fn test_assert_list_files_for_scan_grouping() -> Result<()> {
         // more expected partitions than files
@@ -472,7 +988,7 @@ mod tests {
         let table =
             ListingTable::new(mock_store, table_prefix.to_owned(), Arc::new(schema), opt);
 
-        let (file_list, _) = table.list_files_for_scan(&[], None).await?;
+        let (file_list, _) = table.list_files_for_scan(table.schema(), &[], None).await?;
 
         assert_eq!(file_list.len(), output_partitioning);
 
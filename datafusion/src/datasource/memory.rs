@@ -21,60 +21,125 @@
 
 use futures::StreamExt;
 use std::any::Any;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use arrow::datatypes::SchemaRef;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
 
-use crate::datasource::TableProvider;
+use crate::datasource::{check_ordering, project_ordering, TableProvider};
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
 use crate::logical_plan::Expr;
 use crate::physical_plan::common;
+use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::memory::MemoryExec;
-use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::{repartition::RepartitionExec, Partitioning};
+use crate::physical_plan::{ExecutionPlan, Statistics};
 
-/// In-memory table
+/// In-memory table.
+///
+/// Batches can be appended with [`MemTable::insert_batches`], or with an
+/// `INSERT INTO` SQL statement, while the table is being queried: every scan
+/// reads the batches present when it was planned.
 pub struct MemTable {
     schema: SchemaRef,
+    state: RwLock<MemTableState>,
+}
+
+/// The contents of a [`MemTable`], replaced as a whole on every append
+struct MemTableState {
     batches: Vec<Vec<RecordBatch>>,
+    ordering: Option<Vec<PhysicalSortExpr>>,
+    statistics: Statistics,
+    /// Number of appends since the table was created
+    version: u64,
+}
+
+impl MemTableState {
+    fn new(
+        batches: Vec<Vec<RecordBatch>>,
+        ordering: Option<Vec<PhysicalSortExpr>>,
+        schema: &SchemaRef,
+    ) -> Self {
+        let statistics = common::compute_record_batch_statistics(&batches, schema, None);
+        Self {
+            batches,
+            ordering,
+            statistics,
+            version: 0,
+        }
+    }
 }
 
 impl MemTable {
     /// Create a new in-memory table from the provided schema and record batches
     pub fn try_new(schema: SchemaRef, partitions: Vec<Vec<RecordBatch>>) -> Result<Self> {
-        if partitions
-            .iter()
-            .flatten()
-            .all(|batches| schema.contains(&batches.schema()))
-        {
-            Ok(Self {
-                schema,
-                batches: partitions,
-            })
-        } else {
-            Err(DataFusionError::Plan(
-                "Mismatch between schema and batches".to_string(),
-            ))
+        check_schema(&schema, partitions.iter().flatten())?;
+        let state = MemTableState::new(partitions, None, &schema);
+        Ok(Self {
+            schema,
+            state: RwLock::new(state),
+        })
+    }
+
+    /// Declare the sort order of the batches within each partition. The
+    /// expressions must be columns of the table schema.
+    pub fn with_ordering(self, ordering: Vec<PhysicalSortExpr>) -> Result<Self> {
+        check_ordering(&self.schema, &ordering)?;
+        self.state.write().unwrap().ordering = Some(ordering);
+        Ok(self)
+    }
+
+    /// The declared sort order of the batches within each partition
+    pub fn ordering(&self) -> Option<Vec<PhysicalSortExpr>> {
+        self.state.read().unwrap().ordering.clone()
+    }
+
+    /// Append `batches` to the table, spreading them over its partitions, and
+    /// increment the [version](TableProvider::version) of the table.
+    ///
+    /// The scans already planned do not see the appended batches. Since the
+    /// appended rows are not known to follow the declared sort order of the
+    /// table, the ordering is dropped.
+    pub fn insert_batches(&self, batches: Vec<RecordBatch>) -> Result<()> {
+        check_schema(&self.schema, batches.iter())?;
+        if batches.is_empty() {
+            return Ok(());
         }
+
+        let mut state = self.state.write().unwrap();
+        let mut partitions = state.batches.clone();
+        if partitions.is_empty() {
+            partitions.push(vec![]);
+        }
+        let partition_count = partitions.len();
+        for (i, batch) in batches.into_iter().enumerate() {
+            partitions[i % partition_count].push(batch);
+        }
+        let version = state.version + 1;
+        *state = MemTableState::new(partitions, None, &self.schema);
+        state.version = version;
+        Ok(())
     }
 
-    /// Create a mem table by reading from another data source
+    /// Create a mem table by reading from another data source, in batches of
+    /// the batch size of `context`
     pub async fn load(
         t: Arc<dyn TableProvider>,
-        batch_size: usize,
         output_partitions: Option<usize>,
+        context: Arc<TaskContext>,
     ) -> Result<Self> {
         let schema = t.schema();
-        let exec = t.scan(&None, batch_size, &[], None).await?;
+        let exec = t.scan(&None, context.batch_size(), &[], None).await?;
         let partition_count = exec.output_partitioning().partition_count();
 
         let tasks = (0..partition_count)
             .map(|part_i| {
                 let exec = exec.clone();
+                let context = context.clone();
                 tokio::spawn(async move {
-                    let stream = exec.execute(part_i).await?;
+                    let stream = exec.execute(part_i, context).await?;
                     common::collect(stream).await
                 })
             })
@@ -101,7 +166,7 @@ impl MemTable {
             let mut output_partitions = vec![];
             for i in 0..exec.output_partitioning().partition_count() {
                 // execute this *output* partition and collect all batches
-                let mut stream = exec.execute(i).await?;
+                let mut stream = exec.execute(i, context.clone()).await?;
                 let mut batches = vec![];
                 while let Some(result) = stream.next().await {
                     batches.push(result?);
@@ -116,7 +181,24 @@ impl MemTable {
 
     /// Get batches from MemTable.
     pub fn batches(&mut self) -> Vec<Vec<RecordBatch>> {
-        std::mem::replace(&mut self.batches, Vec::new())
+        let state = self.state.get_mut().unwrap();
+        let batches = std::mem::take(&mut state.batches);
+        *state = MemTableState::new(vec![], None, &self.schema);
+        batches
+    }
+}
+
+/// Check that all the `batches` can be read as rows of `schema`
+fn check_schema<'a>(
+    schema: &SchemaRef,
+    mut batches: impl Iterator<Item = &'a RecordBatch>,
+) -> Result<()> {
+    if batches.all(|batch| schema.contains(&batch.schema())) {
+        Ok(())
+    } else {
+        Err(DataFusionError::Plan(
+            "Mismatch between schema and batches".to_string(),
+        ))
     }
 }
 
@@ -141,24 +223,61 @@ impl TableProvider for MemTable {
         _filters: &[Expr],
         _limit: Option<usize>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Ok(Arc::new(MemoryExec::try_new(
-            &self.batches.clone(),
-            self.schema(),
-            projection.clone(),
-        )?))
+        // planning the scan copies the list of batches, so that the batches
+        // appended afterwards are not read
+        let state = self.state.read().unwrap();
+        let exec =
+            MemoryExec::try_new(&state.batches, self.schema(), projection.clone())?;
+        Ok(Arc::new(
+            match project_ordering(state.ordering.as_deref(), projection) {
+                Some(ordering) => exec.with_ordering(ordering),
+                None => exec,
+            },
+        ))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.state.read().unwrap().statistics.clone()
+    }
+
+    fn version(&self) -> Option<u64> {
+        Some(self.state.read().unwrap().version)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::expressions::Column;
+    use crate::physical_plan::{collect, displayable};
     use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
     use arrow::datatypes::{DataType, Field, Schema};
     use futures::StreamExt;
     use std::collections::HashMap;
 
+    fn two_column_batch(a: Vec<i32>, b: Vec<i32>) -> Result<RecordBatch> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        Ok(RecordBatch::try_new(
+            schema,
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )?)
+    }
+
+    fn sort_by(name: &str, index: usize) -> PhysicalSortExpr {
+        PhysicalSortExpr {
+            expr: Arc::new(Column::new(name, index)),
+            options: SortOptions::default(),
+        }
+    }
+
     #[tokio::test]
     async fn test_with_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = Arc::new(Schema::new(vec![
             Field::new("a", DataType::Int32, false),
             Field::new("b", DataType::Int32, false),
@@ -180,7 +299,15 @@ mod tests {
 
         // scan with projection
         let exec = provider.scan(&Some(vec![2, 1]), 1024, &[], None).await?;
-        let mut it = exec.execute(0).await?;
+        assert_eq!(
+            vec!["c", "b"],
+            exec.schema()
+                .fields()
+                .iter()
+                .map(|f| f.name().as_str())
+                .collect::<Vec<_>>()
+        );
+        let mut it = exec.execute(0, task_ctx).await?;
         let batch2 = it.next().await.unwrap()?;
         assert_eq!(2, batch2.schema().fields().len());
         assert_eq!("c", batch2.schema().field(0).name());
@@ -192,6 +319,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_without_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = Arc::new(Schema::new(vec![
             Field::new("a", DataType::Int32, false),
             Field::new("b", DataType::Int32, false),
@@ -210,7 +338,7 @@ mod tests {
         let provider = MemTable::try_new(schema, vec![vec![batch]])?;
 
         let exec = provider.scan(&None, 1024, &[], None).await?;
-        let mut it = exec.execute(0).await?;
+        let mut it = exec.execute(0, task_ctx).await?;
         let batch1 = it.next().await.unwrap()?;
         assert_eq!(3, batch1.schema().fields().len());
         assert_eq!(3, batch1.num_columns());
@@ -317,6 +445,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_merged_schema() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let mut metadata = HashMap::new();
         metadata.insert("foo".to_string(), "bar".to_string());
 
@@ -361,11 +490,82 @@ mod tests {
             MemTable::try_new(Arc::new(merged_schema), vec![vec![batch1, batch2]])?;
 
         let exec = provider.scan(&None, 1024, &[], None).await?;
-        let mut it = exec.execute(0).await?;
+        let mut it = exec.execute(0, task_ctx).await?;
         let batch1 = it.next().await.unwrap()?;
         assert_eq!(3, batch1.schema().fields().len());
         assert_eq!(3, batch1.num_columns());
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn scans_do_not_see_inserted_batches() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let batch = two_column_batch(vec![1, 2, 3], vec![4, 5, 6])?;
+        let provider = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        assert_eq!(Some(3), provider.statistics().num_rows);
+
+        let planned = provider.scan(&None, 1024, &[], None).await?;
+        provider.insert_batches(vec![
+            two_column_batch(vec![7], vec![8])?,
+            two_column_batch(vec![9, 10], vec![11, 12])?,
+        ])?;
+        assert_eq!(Some(6), provider.statistics().num_rows);
+
+        let rows = |batches: Vec<RecordBatch>| -> usize {
+            batches.iter().map(|b| b.num_rows()).sum()
+        };
+        assert_eq!(3, rows(collect(planned, task_ctx.clone()).await?));
+        let exec = provider.scan(&None, 1024, &[], None).await?;
+        assert_eq!(6, rows(collect(exec, task_ctx).await?));
+
+        let other_schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            other_schema,
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )?;
+        assert!(provider.insert_batches(vec![batch]).is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn declared_ordering_removes_sort() -> Result<()> {
+        let batch = two_column_batch(vec![1, 2, 3], vec![6, 5, 4])?;
+        let provider = MemTable::try_new(batch.schema(), vec![vec![batch]])?
+            .with_ordering(vec![sort_by("a", 0)])?;
+
+        let exec = provider.scan(&Some(vec![1, 0]), 1024, &[], None).await?;
+        let ordering = exec.output_ordering().unwrap();
+        assert_eq!("a@1 ASC", ordering[0].to_string());
+        let exec = provider.scan(&Some(vec![1]), 1024, &[], None).await?;
+        assert!(exec.output_ordering().is_none());
+
+        let provider = Arc::new(provider);
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        ctx.register_table("t", provider.clone())?;
+        let plan = ctx.create_logical_plan("SELECT a, b FROM t ORDER BY a")?;
+        let plan = ctx.optimize(&plan)?;
+        let physical_plan = ctx.create_physical_plan(&plan).await?;
+        let actual = format!("{}", displayable(physical_plan.as_ref()).indent());
+        assert_eq!(
+            "MemoryExec: partitions=1, partition_sizes=[1], ordering=[a@0 ASC]",
+            actual.trim()
+        );
+
+        // the inserted rows may not follow the declared order
+        provider.insert_batches(vec![two_column_batch(vec![0], vec![7])?])?;
+        assert!(provider.ordering().is_none());
+        let physical_plan = ctx.create_physical_plan(&plan).await?;
+        let actual = format!("{}", displayable(physical_plan.as_ref()).indent());
+        assert_eq!(
+            "SortExec: [a@0 ASC]\n  MemoryExec: partitions=1, partition_sizes=[2]",
+            actual.trim()
+        );
+
+        Ok(())
+    }
 }
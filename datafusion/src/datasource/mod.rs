@@ -17,25 +17,31 @@
 
 //! DataFusion data sources
 
+pub mod cte_worktable;
 pub mod datasource;
 pub mod empty;
 pub mod file_format;
 pub mod listing;
 pub mod memory;
 pub mod object_store;
+pub mod streaming;
+pub mod table_function;
 
 use futures::Stream;
 
-pub use self::datasource::{TableProvider, TableType};
+pub use self::datasource::{ScanArgs, TableProvider, TableType};
 pub use self::memory::MemTable;
 use self::object_store::{FileMeta, SizedFile};
 use crate::arrow::datatypes::{Schema, SchemaRef};
-use crate::error::Result;
-use crate::physical_plan::expressions::{MaxAccumulator, MinAccumulator};
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::expressions::{
+    Column, MaxAccumulator, MinAccumulator, PhysicalSortExpr,
+};
 use crate::physical_plan::{Accumulator, ColumnStatistics, Statistics};
 use crate::scalar::ScalarValue;
 use futures::StreamExt;
 use std::pin::Pin;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 /// Get all files as well as the file level summary statistics (no statistic for partition columns).
@@ -158,6 +164,61 @@ impl std::fmt::Display for PartitionedFile {
     }
 }
 
+/// Check that the declared sort order of a table only sorts by columns of
+/// its `schema`
+pub(crate) fn check_ordering(
+    schema: &Schema,
+    ordering: &[PhysicalSortExpr],
+) -> Result<()> {
+    for sort_expr in ordering {
+        let valid = sort_expr
+            .expr
+            .as_any()
+            .downcast_ref::<Column>()
+            .map(|c| schema.index_of(c.name()).ok() == Some(c.index()))
+            .unwrap_or(false);
+        if !valid {
+            return Err(DataFusionError::Plan(format!(
+                "Table ordering expression {} is not a column of the table",
+                sort_expr
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Remap the declared sort order of a table to the schema of a scan with
+/// `projection`. The ordering is truncated at the first column that is not
+/// part of the projection.
+pub(crate) fn project_ordering(
+    ordering: Option<&[PhysicalSortExpr]>,
+    projection: &Option<Vec<usize>>,
+) -> Option<Vec<PhysicalSortExpr>> {
+    let ordering = ordering?;
+    let projection = match projection {
+        Some(projection) => projection,
+        None => return Some(ordering.to_vec()),
+    };
+
+    let projected: Vec<PhysicalSortExpr> = ordering
+        .iter()
+        .map_while(|sort_expr| {
+            let column = sort_expr.expr.as_any().downcast_ref::<Column>()?;
+            let index = projection.iter().position(|i| *i == column.index())?;
+            Some(PhysicalSortExpr {
+                expr: Arc::new(Column::new(column.name(), index)),
+                options: sort_expr.options,
+            })
+        })
+        .collect();
+
+    if projected.is_empty() {
+        None
+    } else {
+        Some(projected)
+    }
+}
+
 fn create_max_min_accs(
     schema: &Schema,
 ) -> (Vec<Option<MaxAccumulator>>, Vec<Option<MinAccumulator>>) {
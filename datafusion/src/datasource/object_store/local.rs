@@ -18,14 +18,16 @@
 //! Object store that represents the Local File System.
 
 use std::fs::{self, File, Metadata};
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use futures::{stream, AsyncRead, StreamExt};
+use futures::{AsyncRead, StreamExt};
 
 use crate::datasource::object_store::{
-    FileMeta, FileMetaStream, ListEntryStream, ObjectReader, ObjectStore,
+    paginate, FileMeta, FileMetaPage, FileMetaStream, ListEntryStream, ObjectReader,
+    ObjectStore,
 };
 use crate::datasource::PartitionedFile;
 use crate::error::DataFusionError;
@@ -43,7 +45,18 @@ pub struct LocalFileSystem;
 #[typetag::serde(name = "local_file_system")]
 impl ObjectStore for LocalFileSystem {
     async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
-        list_all(prefix.to_owned()).await
+        let prefix = prefix.to_owned();
+        Ok(paginate(move |continuation| {
+            list_page(prefix.clone(), continuation)
+        }))
+    }
+
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        list_page(prefix.to_owned(), continuation).await
     }
 
     async fn list_dir(
@@ -57,6 +70,13 @@ impl ObjectStore for LocalFileSystem {
     fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
         Ok(Arc::new(LocalFileReader::new(file)?))
     }
+
+    fn file_writer(&self, path: &str) -> Result<Box<dyn Write + Send>> {
+        if let Some(parent) = Path::new(path).parent() {
+            fs::create_dir_all(parent)?;
+        }
+        Ok(Box::new(BufWriter::new(File::create(path)?)))
+    }
 }
 
 struct LocalFileReader {
@@ -101,62 +121,72 @@ impl ObjectReader for LocalFileReader {
     }
 }
 
-async fn list_all(prefix: String) -> Result<FileMetaStream> {
-    fn get_meta(path: String, metadata: Metadata) -> FileMeta {
-        FileMeta {
-            sized_file: SizedFile {
-                path,
-                size: metadata.len(),
-            },
-            last_modified: metadata.modified().map(chrono::DateTime::from).ok(),
-        }
+fn get_meta(path: String, metadata: Metadata) -> FileMeta {
+    FileMeta {
+        sized_file: SizedFile {
+            path,
+            size: metadata.len(),
+        },
+        last_modified: metadata.modified().map(chrono::DateTime::from).ok(),
     }
+}
+
+async fn find_files_in_dir(
+    path: String,
+    to_visit: &mut Vec<String>,
+) -> Result<Vec<FileMeta>> {
+    let mut dir = tokio::fs::read_dir(path).await?;
+    let mut files = Vec::new();
 
-    async fn find_files_in_dir(
-        path: String,
-        to_visit: &mut Vec<String>,
-    ) -> Result<Vec<FileMeta>> {
-        let mut dir = tokio::fs::read_dir(path).await?;
-        let mut files = Vec::new();
-
-        while let Some(child) = dir.next_entry().await? {
-            if let Some(child_path) = child.path().to_str() {
-                let metadata = child.metadata().await?;
-                if metadata.is_dir() {
-                    to_visit.push(child_path.to_string());
-                } else {
-                    files.push(get_meta(child_path.to_owned(), metadata))
-                }
+    while let Some(child) = dir.next_entry().await? {
+        if let Some(child_path) = child.path().to_str() {
+            let metadata = child.metadata().await?;
+            if metadata.is_dir() {
+                to_visit.push(child_path.to_string());
             } else {
-                return Err(DataFusionError::Plan("Invalid path".to_string()));
+                files.push(get_meta(child_path.to_owned(), metadata))
             }
+        } else {
+            return Err(DataFusionError::Plan("Invalid path".to_string()));
         }
-        Ok(files)
     }
+    Ok(files)
+}
 
-    let prefix_meta = tokio::fs::metadata(&prefix).await?;
-    let prefix = prefix.to_owned();
-    if prefix_meta.is_file() {
-        Ok(Box::pin(stream::once(async move {
-            Ok(get_meta(prefix, prefix_meta))
-        })))
-    } else {
-        let result = stream::unfold(vec![prefix], move |mut to_visit| async move {
-            match to_visit.pop() {
-                None => None,
-                Some(path) => {
-                    let file_stream = match find_files_in_dir(path, &mut to_visit).await {
-                        Ok(files) => stream::iter(files).map(Ok).left_stream(),
-                        Err(e) => stream::once(async { Err(e) }).right_stream(),
-                    };
-
-                    Some((file_stream, to_visit))
-                }
+/// List the files of a directory under `prefix`, a page per directory. The
+/// continuation token holds the directories left to visit, `prefix` is
+/// visited first.
+async fn list_page(prefix: String, continuation: Option<String>) -> Result<FileMetaPage> {
+    let mut to_visit: Vec<String> = match continuation {
+        Some(token) => serde_json::from_str(&token).map_err(|e| {
+            DataFusionError::Plan(format!("Invalid continuation token {}: {}", token, e))
+        })?,
+        None => {
+            let prefix_meta = tokio::fs::metadata(&prefix).await?;
+            if prefix_meta.is_file() {
+                return Ok(FileMetaPage {
+                    files: vec![get_meta(prefix, prefix_meta)],
+                    continuation: None,
+                });
             }
-        })
-        .flatten();
-        Ok(Box::pin(result))
-    }
+            vec![prefix]
+        }
+    };
+    let files = match to_visit.pop() {
+        Some(path) => find_files_in_dir(path, &mut to_visit).await?,
+        None => vec![],
+    };
+    let continuation = if to_visit.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&to_visit).map_err(|e| {
+            DataFusionError::Internal(format!("Cannot write continuation token: {}", e))
+        })?)
+    };
+    Ok(FileMetaPage {
+        files,
+        continuation,
+    })
 }
 
 /// Create a stream of `ObjectReader` by converting each file in the `files` vector
@@ -214,7 +244,9 @@ mod tests {
         File::create(&c_path)?;
 
         let mut all_files = HashSet::new();
-        let mut files = list_all(tmp.path().to_str().unwrap().to_string()).await?;
+        let mut files = LocalFileSystem
+            .list_file(tmp.path().to_str().unwrap())
+            .await?;
         while let Some(file) = files.next().await {
             let file = file?;
             assert_eq!(file.size(), 0);
@@ -226,6 +258,41 @@ mod tests {
         assert!(all_files.contains(b_path.to_str().unwrap()));
         assert!(all_files.contains(c_path.to_str().unwrap()));
 
+        // a page per directory
+        let prefix = tmp.path().to_str().unwrap();
+        let page = LocalFileSystem.list_file_page(prefix, None).await?;
+        let paths = page.files.iter().map(|f| f.path()).collect::<Vec<_>>();
+        assert_eq!(paths, vec![a_path.to_str().unwrap()]);
+        let mut pages = 1;
+        let mut continuation = page.continuation;
+        while continuation.is_some() {
+            let page = LocalFileSystem.list_file_page(prefix, continuation).await?;
+            assert_eq!(page.files.len(), 1);
+            continuation = page.continuation;
+            pages += 1;
+        }
+        assert_eq!(pages, 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_then_read() -> Result<()> {
+        let tmp = tempdir()?;
+        let path = tmp.path().join("x").join("a.txt");
+        let path = path.to_str().unwrap();
+
+        let mut writer = LocalFileSystem.file_writer(path)?;
+        writer.write_all(b"hello")?;
+        writer.flush()?;
+        drop(writer);
+
+        let reader = local_object_reader(path.to_owned());
+        assert_eq!(reader.length(), 5);
+        let mut content = String::new();
+        reader.sync_reader()?.read_to_string(&mut content)?;
+        assert_eq!(content, "hello");
+
         Ok(())
     }
 }
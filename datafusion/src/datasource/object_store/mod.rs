@@ -18,16 +18,19 @@
 //! Object Store abstracts access to an underlying file/object storage.
 
 pub mod local;
+#[cfg(feature = "s3")]
+pub mod s3;
 
 use std::collections::HashMap;
 use std::fmt::{self, Debug};
-use std::io::Read;
+use std::future::Future;
+use std::io::{Read, Write};
 use std::pin::Pin;
 use std::sync::{Arc, RwLock};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use futures::{AsyncRead, Stream, StreamExt};
+use futures::{stream, AsyncRead, Stream, StreamExt, TryStreamExt};
 
 use local::LocalFileSystem;
 
@@ -118,6 +121,46 @@ impl std::fmt::Display for FileMeta {
 pub type FileMetaStream =
     Pin<Box<dyn Stream<Item = Result<FileMeta>> + Send + Sync + 'static>>;
 
+/// A page of the files listed from object store
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileMetaPage {
+    /// The files of the page
+    pub files: Vec<FileMeta>,
+    /// The token listing the next page, `None` after the last page
+    pub continuation: Option<String>,
+}
+
+/// Stream the files of the pages listed by `list_page`, which is called with
+/// the continuation token of the previous page, `None` for the first page.
+/// A page is only listed once the files of the previous one have been
+/// consumed.
+pub fn paginate<F, Fut>(list_page: F) -> FileMetaStream
+where
+    F: Fn(Option<String>) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<FileMetaPage>> + Send + Sync + 'static,
+{
+    // `None` once the last page has been listed
+    let start = Some(None);
+    let pages = stream::try_unfold(start, move |token: Option<Option<String>>| {
+        let page = token.map(&list_page);
+        async move {
+            match page {
+                Some(page) => {
+                    let page = page.await?;
+                    let next = page.continuation.map(Some);
+                    Ok(Some((page.files, next)))
+                }
+                None => Ok(None),
+            }
+        }
+    });
+    Box::pin(
+        pages
+            .map_ok(|files| stream::iter(files.into_iter().map(Ok)))
+            .try_flatten(),
+    )
+}
+
 /// Stream of list entries obtained from object store
 pub type ListEntryStream =
     Pin<Box<dyn Stream<Item = Result<ListEntry>> + Send + Sync + 'static>>;
@@ -131,9 +174,18 @@ pub type ObjectReaderStream =
 #[async_trait]
 #[typetag::serde(tag = "object_store")]
 pub trait ObjectStore: Sync + Send + Debug {
-    /// Returns all the files in path `prefix`
+    /// Returns all the files in path `prefix`, streaming the pages of
+    /// [`list_file_page`](Self::list_file_page) with [`paginate`]
     async fn list_file(&self, prefix: &str) -> Result<FileMetaStream>;
 
+    /// Returns the page of the files in path `prefix` listed by the
+    /// continuation token of the previous page, the first page if `None`
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<FileMetaPage>;
+
     /// Calls `list_file` with a suffix filter
     async fn list_file_with_suffix(
         &self,
@@ -161,9 +213,28 @@ pub trait ObjectStore: Sync + Send + Debug {
 
     /// Get object reader for one file
     fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>>;
+
+    /// Get a writer creating or replacing the file at `path`. The file is
+    /// complete once the writer has been flushed.
+    fn file_writer(&self, path: &str) -> Result<Box<dyn Write + Send>> {
+        Err(DataFusionError::NotImplemented(format!(
+            "Writing {} is not supported by {:?}",
+            path, self
+        )))
+    }
+
+    /// The scheme whose registered store replaces this store once a plan that
+    /// reads from it is deserialized, see [`ObjectStoreRegistry`]. Stores whose
+    /// state is not serialized (e.g. in-memory stores) should return it, stores
+    /// that can be used as deserialized return `None`.
+    fn registry_scheme(&self) -> Option<&str> {
+        None
+    }
 }
 
 static LOCAL_SCHEME: &str = "file";
+#[cfg(feature = "s3")]
+static S3_SCHEME: &str = "s3";
 
 /// A Registry holds all the object stores at runtime with a scheme for each store.
 /// This allows the user to extend DataFusion with different storage systems such as S3 or HDFS
@@ -192,9 +263,15 @@ impl fmt::Debug for ObjectStoreRegistry {
 impl ObjectStoreRegistry {
     /// Create the registry that object stores can registered into.
     /// ['LocalFileSystem'] store is registered in by default to support read local files natively.
+    /// With the `s3` feature, the `S3FileSystem` store is registered for `s3://`.
     pub fn new() -> Self {
         let mut map: HashMap<String, Arc<dyn ObjectStore>> = HashMap::new();
         map.insert(LOCAL_SCHEME.to_string(), Arc::new(LocalFileSystem));
+        #[cfg(feature = "s3")]
+        map.insert(
+            S3_SCHEME.to_string(),
+            Arc::new(s3::S3FileSystem::new(Default::default())),
+        );
 
         Self {
             object_stores: RwLock::new(map),
@@ -218,8 +295,24 @@ impl ObjectStoreRegistry {
         stores.get(scheme).cloned()
     }
 
+    /// Get the store registered for scheme, or an error listing the
+    /// registered schemes if there is none
+    pub fn get_by_scheme(&self, scheme: &str) -> Result<Arc<dyn ObjectStore>> {
+        let stores = self.object_stores.read().unwrap();
+        stores.get(&*scheme.to_lowercase()).cloned().ok_or_else(|| {
+            let mut schemes = stores.keys().cloned().collect::<Vec<_>>();
+            schemes.sort();
+            DataFusionError::Plan(format!(
+                "No object store registered for scheme '{}', registered schemes are: {}",
+                scheme,
+                schemes.join(", ")
+            ))
+        })
+    }
+
     /// Get a suitable store for the URI based on it's scheme. For example:
-    /// - URI with scheme `file://` or no schema will return the default LocalFS store
+    /// - URI with scheme `file://` or no schema will return the `file` store,
+    ///   which is LocalFS unless it was replaced
     /// - URI with scheme `s3://` will return the S3 store if it's registered
     /// Returns a tuple with the store and the path of the file in that store
     /// (URI=scheme://path).
@@ -227,20 +320,50 @@ impl ObjectStoreRegistry {
         &self,
         uri: &'a str,
     ) -> Result<(Arc<dyn ObjectStore>, &'a str)> {
-        if let Some((scheme, path)) = uri.split_once("://") {
-            let stores = self.object_stores.read().unwrap();
-            let store = stores
-                .get(&*scheme.to_lowercase())
-                .map(Clone::clone)
-                .ok_or_else(|| {
-                    DataFusionError::Internal(format!(
-                        "No suitable object store found for {}",
-                        scheme
-                    ))
-                })?;
-            Ok((store, path))
-        } else {
-            Ok((Arc::new(LocalFileSystem), uri))
+        match uri.split_once("://") {
+            Some((scheme, path)) => Ok((self.get_by_scheme(scheme)?, path)),
+            None => Ok((self.get_by_scheme(LOCAL_SCHEME)?, uri)),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::object_store::TestObjectStore;
+
+    #[tokio::test]
+    async fn list_pages_lazily() -> Result<()> {
+        let store = TestObjectStore::new(&[
+            ("data/a.csv", 1),
+            ("data/b.csv", 1),
+            ("data/c.csv", 1),
+            ("other/d.csv", 1),
+        ])
+        .with_page_size(2);
+
+        let page = store.list_file_page("data/", None).await?;
+        let paths = page.files.iter().map(|f| f.path()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["data/a.csv", "data/b.csv"]);
+        let page = store.list_file_page("data/", page.continuation).await?;
+        let paths = page.files.iter().map(|f| f.path()).collect::<Vec<_>>();
+        assert_eq!(paths, vec!["data/c.csv"]);
+        assert_eq!(page.continuation, None);
+        assert_eq!(store.page_calls(), 2);
+
+        // the second page is not listed if the first one is enough
+        let mut files = store.list_file("data/").await?;
+        assert_eq!(files.next().await.unwrap()?.path(), "data/a.csv");
+        assert_eq!(files.next().await.unwrap()?.path(), "data/b.csv");
+        assert_eq!(store.page_calls(), 3);
+
+        let paths = files
+            .map(|f| f.map(|f| f.path().to_owned()))
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(paths, vec!["data/c.csv"]);
+        assert_eq!(store.page_calls(), 4);
+
+        Ok(())
+    }
+}
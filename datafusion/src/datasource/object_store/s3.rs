@@ -0,0 +1,882 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Object store that reads from Amazon S3 or an S3 compatible service
+//! (e.g. MinIO or localstack).
+//!
+//! Paths are relative to the `s3://` scheme and start with the bucket, i.e.
+//! `s3://bucket/some/key` is the path `bucket/some/key` in the store.
+
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::{stream, AsyncRead, TryStreamExt};
+use rusoto_core::{HttpClient, Region, RusotoError};
+use rusoto_credential::DefaultCredentialsProvider;
+use rusoto_s3::{
+    GetObjectError, GetObjectRequest, ListObjectsV2Request, S3Client as RusotoS3Client,
+    S3,
+};
+use tokio::runtime::Runtime;
+
+use crate::datasource::object_store::{
+    paginate, FileMeta, FileMetaPage, FileMetaStream, ListEntry, ListEntryStream,
+    ObjectReader, ObjectStore, SizedFile,
+};
+use crate::error::{DataFusionError, Result};
+
+use serde::{Deserialize, Serialize};
+
+/// Configuration of an [`S3FileSystem`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Options {
+    /// Region of the buckets, e.g. `us-east-1`
+    pub region: String,
+    /// Endpoint of an S3 compatible service, e.g. `http://localhost:9000`.
+    /// If `None`, the AWS endpoint of the region is used.
+    pub endpoint: Option<String>,
+    /// Maximum number of times a throttled or failed request is retried
+    pub max_retries: usize,
+    /// Delay before the first retry, doubled at each following retry
+    pub retry_base_delay: Duration,
+    /// Maximum delay between two retries
+    pub retry_max_delay: Duration,
+    /// Number of threads of the runtime that performs the requests
+    pub io_threads: usize,
+}
+
+impl Default for S3Options {
+    /// The region is read from the `AWS_REGION` or `AWS_DEFAULT_REGION`
+    /// environment variables, and defaults to `us-east-1`
+    fn default() -> Self {
+        let region = std::env::var("AWS_REGION")
+            .or_else(|_| std::env::var("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|_| "us-east-1".to_owned());
+        Self {
+            region,
+            endpoint: None,
+            max_retries: 5,
+            retry_base_delay: Duration::from_millis(50),
+            retry_max_delay: Duration::from_secs(5),
+            io_threads: 2,
+        }
+    }
+}
+
+/// An S3 object, as listed by [`S3Api::list_objects`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct S3Object {
+    /// Key of the object in its bucket
+    pub key: String,
+    /// Size of the object in bytes
+    pub size: u64,
+    /// Last modification time of the object
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// One page of the result of [`S3Api::list_objects`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListObjectsPage {
+    /// The objects of this page
+    pub objects: Vec<S3Object>,
+    /// The prefixes up to the delimiter, if a delimiter was provided
+    pub common_prefixes: Vec<String>,
+    /// Token to get the next page, `None` if this is the last page
+    pub next_continuation_token: Option<String>,
+}
+
+/// The S3 requests used by [`S3FileSystem`]. The default implementation uses
+/// the AWS client, other implementations can be plugged in with
+/// [`S3FileSystem::new_with_client`], e.g. to test without a server.
+#[async_trait]
+pub trait S3Api: Send + Sync {
+    /// List a page of the objects of `bucket` whose key starts with `prefix`
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage>;
+
+    /// Get `length` bytes of the object `key` of `bucket`, starting at `start`
+    async fn get_object_range(
+        &self,
+        bucket: &str,
+        key: &str,
+        start: u64,
+        length: usize,
+    ) -> Result<Vec<u8>>;
+}
+
+/// Object store for Amazon S3 and S3 compatible services.
+///
+/// Credentials are resolved with the standard AWS provider chain: environment
+/// variables, profile files, ECS container and EC2 instance metadata. The
+/// requests are sent from a dedicated runtime that keeps a pool of
+/// connections, and throttled or failed requests are retried with an
+/// exponential backoff.
+///
+/// Only the options are serialized. The client is created on first use, so
+/// a store plugged with [`S3FileSystem::new_with_client`] is deserialized
+/// with the default client.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct S3FileSystem {
+    options: S3Options,
+    #[serde(skip)]
+    client: ClientCell,
+}
+
+impl S3FileSystem {
+    /// Create a store that will use the AWS client configured by `options`
+    pub fn new(options: S3Options) -> Self {
+        Self {
+            options,
+            client: ClientCell::default(),
+        }
+    }
+
+    /// Create a store that sends its requests with `client`
+    pub fn new_with_client(options: S3Options, client: Arc<dyn S3Api>) -> Self {
+        Self {
+            options,
+            client: ClientCell(Mutex::new(Some(client))),
+        }
+    }
+
+    /// Get the options of the store
+    pub fn options(&self) -> &S3Options {
+        &self.options
+    }
+
+    fn client(&self) -> Result<Arc<dyn S3Api>> {
+        let mut client = self.client.0.lock().unwrap();
+        if client.is_none() {
+            *client = Some(Arc::new(RusotoS3Api::try_new(&self.options)?));
+        }
+        Ok(Arc::clone(client.as_ref().unwrap()))
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "s3_file_system")]
+impl ObjectStore for S3FileSystem {
+    async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        let client = self.client()?;
+        let prefix = prefix.to_owned();
+        Ok(paginate(move |continuation| {
+            list_file_page(Arc::clone(&client), prefix.clone(), continuation)
+        }))
+    }
+
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        list_file_page(self.client()?, prefix.to_owned(), continuation).await
+    }
+
+    async fn list_dir(
+        &self,
+        prefix: &str,
+        delimiter: Option<String>,
+    ) -> Result<ListEntryStream> {
+        let (bucket, prefix) = split_bucket(prefix);
+        let pages = list_pages(
+            self.client()?,
+            bucket.to_owned(),
+            prefix.to_owned(),
+            delimiter,
+        );
+
+        let bucket = bucket.to_owned();
+        Ok(Box::pin(
+            pages
+                .map_ok(move |page| {
+                    let files = page.objects.into_iter().map({
+                        let bucket = bucket.clone();
+                        move |object| {
+                            ListEntry::FileMeta(FileMeta {
+                                sized_file: SizedFile {
+                                    path: format!("{}/{}", bucket, object.key),
+                                    size: object.size,
+                                },
+                                last_modified: object.last_modified,
+                            })
+                        }
+                    });
+                    let prefixes = page.common_prefixes.into_iter().map({
+                        let bucket = bucket.clone();
+                        move |prefix| ListEntry::Prefix(format!("{}/{}", bucket, prefix))
+                    });
+                    stream::iter(prefixes.chain(files).map(Ok))
+                })
+                .try_flatten(),
+        ))
+    }
+
+    fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
+        let (bucket, key) = split_bucket(&file.path);
+        if key.is_empty() {
+            return Err(DataFusionError::Plan(format!(
+                "S3 path {} does not have a key",
+                file.path
+            )));
+        }
+        Ok(Arc::new(S3FileReader {
+            client: self.client()?,
+            bucket: bucket.to_owned(),
+            key: key.to_owned(),
+            size: file.size,
+        }))
+    }
+}
+
+/// Split a path of the store into its bucket and key
+fn split_bucket(path: &str) -> (&str, &str) {
+    match path.split_once('/') {
+        Some((bucket, key)) => (bucket, key),
+        None => (path, ""),
+    }
+}
+
+/// List the page of the files in path `prefix` following the page whose
+/// continuation token is `continuation`
+async fn list_file_page(
+    client: Arc<dyn S3Api>,
+    prefix: String,
+    continuation: Option<String>,
+) -> Result<FileMetaPage> {
+    let (bucket, key_prefix) = split_bucket(&prefix);
+    let request = {
+        let (bucket, key_prefix) = (bucket.to_owned(), key_prefix.to_owned());
+        // the request is spawned as the futures of `S3Api` are not `Sync`
+        tokio::spawn(async move {
+            client
+                .list_objects(&bucket, &key_prefix, None, continuation)
+                .await
+        })
+    };
+    let page = request
+        .await
+        .map_err(|e| DataFusionError::Execution(e.to_string()))??;
+    Ok(FileMetaPage {
+        files: page
+            .objects
+            .into_iter()
+            .map(|object| FileMeta {
+                sized_file: SizedFile {
+                    path: format!("{}/{}", bucket, object.key),
+                    size: object.size,
+                },
+                last_modified: object.last_modified,
+            })
+            .collect(),
+        continuation: page.next_continuation_token,
+    })
+}
+
+/// Stream the pages of the listing of `prefix`. A page is only requested once
+/// the previous one has been consumed.
+fn list_pages(
+    client: Arc<dyn S3Api>,
+    bucket: String,
+    prefix: String,
+    delimiter: Option<String>,
+) -> impl futures::Stream<Item = Result<ListObjectsPage>> + Send + Sync + 'static {
+    // `None` once the last page has been listed
+    let start = Some(None);
+    stream::try_unfold(start, move |token: Option<Option<String>>| {
+        let client = Arc::clone(&client);
+        let bucket = bucket.clone();
+        let prefix = prefix.clone();
+        let delimiter = delimiter.clone();
+        async move {
+            let token = match token {
+                Some(token) => token,
+                None => return Ok::<_, DataFusionError>(None),
+            };
+            // the request is spawned as the futures of `S3Api` are not `Sync`
+            let page = tokio::spawn(async move {
+                client
+                    .list_objects(&bucket, &prefix, delimiter, token)
+                    .await
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(e.to_string()))??;
+            let next = page.next_continuation_token.clone().map(Some);
+            Ok(Some((page, next)))
+        }
+    })
+}
+
+struct S3FileReader {
+    client: Arc<dyn S3Api>,
+    bucket: String,
+    key: String,
+    size: u64,
+}
+
+impl S3FileReader {
+    async fn get_range(&self, start: u64, length: usize) -> Result<Vec<u8>> {
+        if length == 0 {
+            return Ok(vec![]);
+        }
+        self.client
+            .get_object_range(&self.bucket, &self.key, start, length)
+            .await
+    }
+}
+
+#[async_trait]
+impl ObjectReader for S3FileReader {
+    async fn chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn AsyncRead>> {
+        let bytes = self.get_range(start, length).await?;
+        Ok(Box::new(futures::io::Cursor::new(bytes)))
+    }
+
+    fn sync_chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn Read + Send + Sync>> {
+        // the request runs on the runtime of the client, so blocking this
+        // thread does not prevent it from completing
+        let bytes = futures::executor::block_on(self.get_range(start, length))?;
+        Ok(Box::new(io::Cursor::new(bytes)))
+    }
+
+    fn length(&self) -> u64 {
+        self.size
+    }
+}
+
+/// Holds the client of an [`S3FileSystem`] once created
+#[derive(Default)]
+struct ClientCell(Mutex<Option<Arc<dyn S3Api>>>);
+
+impl fmt::Debug for ClientCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let created = self.0.lock().unwrap().is_some();
+        f.debug_struct("ClientCell")
+            .field("created", &created)
+            .finish()
+    }
+}
+
+/// Runtime dedicated to the S3 requests, shut down without waiting for the
+/// pending requests when dropped so that it can be dropped from async code
+struct IoRuntime(Option<Runtime>);
+
+impl IoRuntime {
+    fn spawn<F>(&self, future: F) -> tokio::task::JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.0.as_ref().unwrap().spawn(future)
+    }
+}
+
+impl Drop for IoRuntime {
+    fn drop(&mut self) {
+        if let Some(runtime) = self.0.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+/// [`S3Api`] implementation with the AWS client
+struct RusotoS3Api {
+    client: RusotoS3Client,
+    runtime: IoRuntime,
+    retry: RetryPolicy,
+}
+
+impl RusotoS3Api {
+    fn try_new(options: &S3Options) -> Result<Self> {
+        let region = match &options.endpoint {
+            Some(endpoint) => Region::Custom {
+                name: options.region.clone(),
+                endpoint: endpoint.clone(),
+            },
+            None => options.region.parse().map_err(|e| {
+                DataFusionError::Plan(format!(
+                    "Invalid S3 region {}: {}",
+                    options.region, e
+                ))
+            })?,
+        };
+        let http_client = HttpClient::new().map_err(|e| {
+            DataFusionError::Execution(format!("Failed to create S3 HTTP client: {}", e))
+        })?;
+        let credentials = DefaultCredentialsProvider::new().map_err(|e| {
+            DataFusionError::Execution(format!(
+                "Failed to create S3 credentials provider: {}",
+                e
+            ))
+        })?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(options.io_threads.max(1))
+            .thread_name("datafusion-s3")
+            .enable_all()
+            .build()?;
+
+        Ok(Self {
+            client: RusotoS3Client::new_with(http_client, credentials, region),
+            runtime: IoRuntime(Some(runtime)),
+            retry: RetryPolicy::from(options),
+        })
+    }
+}
+
+#[async_trait]
+impl S3Api for RusotoS3Api {
+    async fn list_objects(
+        &self,
+        bucket: &str,
+        prefix: &str,
+        delimiter: Option<String>,
+        continuation_token: Option<String>,
+    ) -> Result<ListObjectsPage> {
+        let client = self.client.clone();
+        let retry = self.retry.clone();
+        let request = ListObjectsV2Request {
+            bucket: bucket.to_owned(),
+            prefix: Some(prefix.to_owned()),
+            delimiter,
+            continuation_token,
+            ..Default::default()
+        };
+
+        let output = self
+            .runtime
+            .spawn(async move {
+                retry
+                    .run(is_retryable, || client.list_objects_v2(request.clone()))
+                    .await
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(e.to_string()))?
+            .map_err(|e| s3_error("list", bucket, prefix, e))?;
+
+        let objects = output
+            .contents
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|object| {
+                Some(S3Object {
+                    key: object.key?,
+                    size: object.size.unwrap_or(0) as u64,
+                    last_modified: object
+                        .last_modified
+                        .and_then(|date| DateTime::parse_from_rfc3339(&date).ok())
+                        .map(|date| date.with_timezone(&Utc)),
+                })
+            })
+            .collect();
+        let common_prefixes = output
+            .common_prefixes
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|prefix| prefix.prefix)
+            .collect();
+
+        Ok(ListObjectsPage {
+            objects,
+            common_prefixes,
+            next_continuation_token: output.next_continuation_token,
+        })
+    }
+
+    async fn get_object_range(
+        &self,
+        bucket: &str,
+        key: &str,
+        start: u64,
+        length: usize,
+    ) -> Result<Vec<u8>> {
+        let client = self.client.clone();
+        let retry = self.retry.clone();
+        let request = GetObjectRequest {
+            bucket: bucket.to_owned(),
+            key: key.to_owned(),
+            // the end of the range is inclusive
+            range: Some(format!("bytes={}-{}", start, start + length as u64 - 1)),
+            ..Default::default()
+        };
+
+        self.runtime
+            .spawn(async move {
+                retry
+                    .run(is_retryable, || get_object(&client, request.clone()))
+                    .await
+            })
+            .await
+            .map_err(|e| DataFusionError::Execution(e.to_string()))?
+            .map_err(|e| s3_error("get", bucket, key, e))
+    }
+}
+
+async fn get_object(
+    client: &RusotoS3Client,
+    request: GetObjectRequest,
+) -> std::result::Result<Vec<u8>, RusotoError<GetObjectError>> {
+    let output = client.get_object(request).await?;
+    match output.body {
+        Some(body) => body
+            .map_ok(|bytes| bytes.to_vec())
+            .try_concat()
+            .await
+            .map_err(|e| RusotoError::ParseError(e.to_string())),
+        None => Ok(vec![]),
+    }
+}
+
+/// Whether a failed request should be retried: throttling (429 and 503
+/// `SlowDown`), server errors and connection failures are
+fn is_retryable<E>(error: &RusotoError<E>) -> bool {
+    match error {
+        RusotoError::HttpDispatch(_) => true,
+        RusotoError::Unknown(response) => {
+            response.status.as_u16() == 429 || response.status.is_server_error()
+        }
+        _ => false,
+    }
+}
+
+fn s3_error<E: std::error::Error>(
+    operation: &str,
+    bucket: &str,
+    key: &str,
+    error: RusotoError<E>,
+) -> DataFusionError {
+    let kind = match &error {
+        RusotoError::Unknown(response) if response.status.as_u16() == 404 => {
+            io::ErrorKind::NotFound
+        }
+        _ => io::ErrorKind::Other,
+    };
+    DataFusionError::IoError(io::Error::new(
+        kind,
+        format!(
+            "S3 {} of s3://{}/{} failed: {}",
+            operation, bucket, key, error
+        ),
+    ))
+}
+
+/// Retries of the failed requests with an exponential backoff
+#[derive(Debug, Clone)]
+struct RetryPolicy {
+    max_retries: usize,
+    base_delay: Duration,
+    max_delay: Duration,
+}
+
+impl From<&S3Options> for RetryPolicy {
+    fn from(options: &S3Options) -> Self {
+        Self {
+            max_retries: options.max_retries,
+            base_delay: options.retry_base_delay,
+            max_delay: options.retry_max_delay,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the retry following `attempt` failed attempts
+    fn delay(&self, attempt: usize) -> Duration {
+        let factor = 1u32 << attempt.min(16);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Run `request` until it succeeds, fails with an error that is not
+    /// retryable or the retries are exhausted
+    async fn run<T, E, F, Fut>(
+        &self,
+        retryable: impl Fn(&E) -> bool,
+        mut request: F,
+    ) -> std::result::Result<T, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match request().await {
+                Err(e) if attempt < self.max_retries && retryable(&e) => {
+                    tokio::time::sleep(self.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::object_store::ObjectStoreRegistry;
+    use futures::StreamExt;
+    use std::collections::BTreeMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// In memory S3 that returns `page_size` objects per page
+    struct MockS3 {
+        objects: BTreeMap<String, Vec<u8>>,
+        page_size: usize,
+        list_calls: AtomicUsize,
+    }
+
+    impl MockS3 {
+        fn new(objects: &[(&str, &str)], page_size: usize) -> Self {
+            Self {
+                objects: objects
+                    .iter()
+                    .map(|(key, data)| (key.to_string(), data.as_bytes().to_vec()))
+                    .collect(),
+                page_size,
+                list_calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl S3Api for MockS3 {
+        async fn list_objects(
+            &self,
+            bucket: &str,
+            prefix: &str,
+            delimiter: Option<String>,
+            continuation_token: Option<String>,
+        ) -> Result<ListObjectsPage> {
+            assert_eq!(bucket, "bucket");
+            assert!(delimiter.is_none());
+            self.list_calls.fetch_add(1, Ordering::SeqCst);
+            let skip = continuation_token.map_or(0, |token| token.parse().unwrap());
+            let keys: Vec<_> = self
+                .objects
+                .iter()
+                .filter(|(key, _)| key.starts_with(prefix))
+                .collect();
+            let objects = keys
+                .iter()
+                .skip(skip)
+                .take(self.page_size)
+                .map(|(key, data)| S3Object {
+                    key: key.to_string(),
+                    size: data.len() as u64,
+                    last_modified: None,
+                })
+                .collect();
+            let next = skip + self.page_size;
+            Ok(ListObjectsPage {
+                objects,
+                common_prefixes: vec![],
+                next_continuation_token: (next < keys.len()).then(|| next.to_string()),
+            })
+        }
+
+        async fn get_object_range(
+            &self,
+            bucket: &str,
+            key: &str,
+            start: u64,
+            length: usize,
+        ) -> Result<Vec<u8>> {
+            assert_eq!(bucket, "bucket");
+            let data = self.objects.get(key).ok_or_else(|| {
+                DataFusionError::IoError(io::Error::new(io::ErrorKind::NotFound, key))
+            })?;
+            let start = start as usize;
+            Ok(data[start..start + length].to_vec())
+        }
+    }
+
+    fn mock_store(page_size: usize) -> (Arc<MockS3>, S3FileSystem) {
+        let client = Arc::new(MockS3::new(
+            &[
+                ("data/a.csv", "a,b\n1,2\n"),
+                ("data/b.csv", "a,b\n3,4\n"),
+                ("data/c.csv", "a,b\n5,6\n"),
+                ("other/d.csv", "a,b\n7,8\n"),
+            ],
+            page_size,
+        ));
+        let store = S3FileSystem::new_with_client(S3Options::default(), client.clone());
+        (client, store)
+    }
+
+    #[tokio::test]
+    async fn list_pages_lazily() -> Result<()> {
+        let (client, store) = mock_store(2);
+
+        let files: Vec<_> = store
+            .list_file("bucket/data/")
+            .await?
+            .map(|f| f.unwrap().path().to_owned())
+            .collect()
+            .await;
+        assert_eq!(
+            files,
+            vec![
+                "bucket/data/a.csv",
+                "bucket/data/b.csv",
+                "bucket/data/c.csv"
+            ]
+        );
+        assert_eq!(client.list_calls.load(Ordering::SeqCst), 2);
+
+        // the second page is not requested if the first is enough
+        let mut files = store.list_file("bucket/data/").await?;
+        files.next().await.unwrap()?;
+        assert_eq!(client.list_calls.load(Ordering::SeqCst), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn read_ranges() -> Result<()> {
+        let (_, store) = mock_store(10);
+        let file = store
+            .list_file("bucket/other/")
+            .await?
+            .next()
+            .await
+            .unwrap()?;
+        assert_eq!(file.size(), 8);
+
+        let reader = store.file_reader(file.sized_file)?;
+        let mut data = String::new();
+        reader.sync_chunk_reader(4, 4)?.read_to_string(&mut data)?;
+        assert_eq!(data, "7,8\n");
+
+        let mut data = String::new();
+        reader.sync_reader()?.read_to_string(&mut data)?;
+        assert_eq!(data, "a,b\n7,8\n");
+
+        let mut data = vec![];
+        futures::AsyncReadExt::read_to_end(
+            &mut Box::into_pin(reader.chunk_reader(0, 3).await?),
+            &mut data,
+        )
+        .await?;
+        assert_eq!(data, b"a,b");
+
+        assert!(store
+            .file_reader(SizedFile {
+                path: "bucket".to_owned(),
+                size: 0
+            })
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn serde_keeps_options() {
+        let options = S3Options {
+            region: "eu-west-1".to_owned(),
+            endpoint: Some("http://localhost:9000".to_owned()),
+            ..Default::default()
+        };
+        let store: Arc<dyn ObjectStore> = Arc::new(S3FileSystem::new(options.clone()));
+        let json = serde_json::to_string(&store).unwrap();
+        let store: Arc<dyn ObjectStore> = serde_json::from_str(&json).unwrap();
+        let store = format!("{:?}", store);
+        assert!(store.contains("eu-west-1"), "{}", store);
+        assert!(store.contains("http://localhost:9000"), "{}", store);
+    }
+
+    #[test]
+    fn registered_for_s3_scheme() -> Result<()> {
+        let registry = ObjectStoreRegistry::new();
+        let (store, path) = registry.get_by_uri("s3://bucket/data/a.csv")?;
+        assert_eq!(path, "bucket/data/a.csv");
+        assert!(format!("{:?}", store).starts_with("S3FileSystem"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn retry_with_backoff() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(1));
+        assert_eq!(policy.delay(1), Duration::from_millis(2));
+        assert_eq!(policy.delay(40), Duration::from_millis(2));
+
+        // throttled twice, then succeeds
+        let calls = AtomicUsize::new(0);
+        let result: std::result::Result<usize, &str> = policy
+            .run(
+                |e| *e == "throttled",
+                || async {
+                    match calls.fetch_add(1, Ordering::SeqCst) {
+                        0 | 1 => Err("throttled"),
+                        n => Ok(n),
+                    }
+                },
+            )
+            .await;
+        assert_eq!(result, Ok(2));
+
+        // not retryable
+        let calls = AtomicUsize::new(0);
+        let result: std::result::Result<(), &str> = policy
+            .run(
+                |e| *e == "throttled",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err("denied")
+                },
+            )
+            .await;
+        assert_eq!(result, Err("denied"));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // retries exhausted
+        let calls = AtomicUsize::new(0);
+        let result: std::result::Result<(), &str> = policy
+            .run(
+                |e| *e == "throttled",
+                || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Err("throttled")
+                },
+            )
+            .await;
+        assert_eq!(result, Err("throttled"));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
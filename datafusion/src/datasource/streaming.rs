@@ -0,0 +1,246 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Streaming data source for presenting record batches that arrive over async
+//! channels as a table that can be queried by DataFusion.
+
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use tokio::sync::mpsc::Receiver;
+
+use crate::datasource::{check_ordering, project_ordering, TableProvider};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Expr;
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::streaming::{PartitionReceivers, StreamingTableExec};
+use crate::physical_plan::ExecutionPlan;
+
+/// Table whose partitions are fed by `tokio::sync::mpsc` channels.
+///
+/// Batches are yielded as they arrive and each partition ends once all the
+/// senders of its channel are dropped. Since the received batches are
+/// consumed, every partition can only be scanned once.
+pub struct StreamingTable {
+    schema: SchemaRef,
+    receivers: PartitionReceivers,
+    ordering: Option<Vec<PhysicalSortExpr>>,
+}
+
+impl StreamingTable {
+    /// Create a new streaming table from the provided schema and one receiver
+    /// per partition
+    pub fn try_new(
+        schema: SchemaRef,
+        receivers: Vec<Receiver<RecordBatch>>,
+    ) -> Result<Self> {
+        if receivers.is_empty() {
+            return Err(DataFusionError::Plan(
+                "StreamingTable requires at least one partition".to_string(),
+            ));
+        }
+        Ok(Self {
+            schema,
+            receivers: Arc::new(Mutex::new(receivers.into_iter().map(Some).collect())),
+            ordering: None,
+        })
+    }
+
+    /// Declare the sort order of the batches within each partition. The
+    /// expressions must be columns of the table schema.
+    pub fn with_ordering(mut self, ordering: Vec<PhysicalSortExpr>) -> Result<Self> {
+        check_ordering(&self.schema, &ordering)?;
+        self.ordering = Some(ordering);
+        Ok(self)
+    }
+
+    /// The declared sort order of the batches within each partition
+    pub fn ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.ordering.as_deref()
+    }
+}
+
+#[async_trait]
+impl TableProvider for StreamingTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        _batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(StreamingTableExec::try_new(
+            Arc::clone(&self.receivers),
+            self.schema(),
+            projection.clone(),
+            project_ordering(self.ordering(), projection),
+        )?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::expressions::Column;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use futures::StreamExt;
+    use tokio::sync::mpsc;
+
+    fn test_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]))
+    }
+
+    fn test_batch(schema: &SchemaRef, value: i32) -> RecordBatch {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![value])),
+                Arc::new(Int32Array::from(vec![value * 10])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn limit_consumes_part_of_stream() -> Result<()> {
+        let schema = test_schema();
+        let (tx, rx) = mpsc::channel(1);
+
+        let producer_schema = schema.clone();
+        let producer = tokio::spawn(async move {
+            let mut sent = 0;
+            for i in 0..10 {
+                if tx.send(test_batch(&producer_schema, i)).await.is_err() {
+                    break;
+                }
+                sent += 1;
+            }
+            sent
+        });
+
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        ctx.register_table("t", Arc::new(StreamingTable::try_new(schema, vec![rx])?))?;
+        let results = ctx.sql("SELECT a FROM t LIMIT 3").await?.collect().await?;
+
+        let rows: usize = results.iter().map(|b| b.num_rows()).sum();
+        assert_eq!(3, rows);
+
+        // the receiver is dropped once the query is done, so the producer stops
+        let sent = producer.await.unwrap();
+        assert!(sent < 10, "producer sent all {} batches", sent);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stream_ends_when_senders_dropped() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let schema = test_schema();
+        let (tx, rx) = mpsc::channel(4);
+        let table = StreamingTable::try_new(schema.clone(), vec![rx])?;
+
+        tokio::spawn(async move {
+            for i in 0..3 {
+                tx.send(test_batch(&schema, i)).await.unwrap();
+            }
+        });
+
+        let exec = table.scan(&Some(vec![1]), 1024, &[], None).await?;
+        let mut stream = exec.execute(0, task_ctx.clone()).await?;
+        let mut values = vec![];
+        while let Some(batch) = stream.next().await {
+            let batch = batch?;
+            assert_eq!(1, batch.num_columns());
+            assert_eq!("b", batch.schema().field(0).name());
+            let array = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int32Array>()
+                .unwrap();
+            values.extend(array.values().iter().copied());
+        }
+        assert_eq!(vec![0, 10, 20], values);
+
+        // the partition has been consumed
+        assert!(exec.execute(0, task_ctx).await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ordering_follows_projection() -> Result<()> {
+        let schema = test_schema();
+        let (_tx, rx) = mpsc::channel(1);
+        let options = SortOptions::default();
+        let table =
+            StreamingTable::try_new(schema.clone(), vec![rx])?.with_ordering(vec![
+                PhysicalSortExpr {
+                    expr: Arc::new(Column::new("a", 0)),
+                    options,
+                },
+                PhysicalSortExpr {
+                    expr: Arc::new(Column::new("b", 1)),
+                    options,
+                },
+            ])?;
+
+        let exec = table.scan(&None, 1024, &[], None).await?;
+        assert_eq!(2, exec.output_ordering().unwrap().len());
+
+        // projecting only `b` loses the leading sort key
+        let exec = table.scan(&Some(vec![1]), 1024, &[], None).await?;
+        assert!(exec.output_ordering().is_none());
+
+        let exec = table.scan(&Some(vec![1, 0]), 1024, &[], None).await?;
+        let ordering = exec.output_ordering().unwrap();
+        assert_eq!(2, ordering.len());
+        let column = ordering[0].expr.as_any().downcast_ref::<Column>().unwrap();
+        assert_eq!(("a", 1), (column.name(), column.index()));
+
+        assert!(StreamingTable::try_new(schema, vec![mpsc::channel(1).1])?
+            .with_ordering(vec![PhysicalSortExpr {
+                expr: Arc::new(Column::new("c", 2)),
+                options,
+            }])
+            .is_err());
+
+        Ok(())
+    }
+}
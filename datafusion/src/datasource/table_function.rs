@@ -0,0 +1,297 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Table functions, called in the FROM clause of a query to produce a table,
+//! e.g. `SELECT * FROM generate_series(1, 10)`.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arrow::array::Int64Array;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+
+use crate::datasource::{TableProvider, TableType};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Expr;
+use crate::physical_plan::memory::MemoryExec;
+use crate::physical_plan::{ColumnStatistics, ExecutionPlan, Statistics};
+use crate::scalar::ScalarValue;
+
+/// A function returning a table, registered with
+/// [`ExecutionContext::register_udtf`](crate::execution::context::ExecutionContext::register_udtf).
+pub trait TableFunction: Send + Sync {
+    /// Returns the table of a call of the function with the constant arguments `args`,
+    /// evaluated when the query is planned.
+    fn call(&self, args: &[ScalarValue]) -> Result<Arc<dyn TableProvider>>;
+}
+
+/// The table functions of every context
+pub(crate) fn built_in_table_functions() -> HashMap<String, Arc<dyn TableFunction>> {
+    let mut functions = HashMap::new();
+    functions.insert(
+        GENERATE_SERIES.to_owned(),
+        Arc::new(GenerateSeries::default()) as Arc<dyn TableFunction>,
+    );
+    functions
+}
+
+/// `generate_series(start, stop[, step])`: the integers from `start` to `stop`
+/// (inclusive) every `step`, 1 by default, in an Int64 column `generate_series`.
+#[derive(Debug, Default)]
+pub struct GenerateSeries {}
+
+/// The name of [`GenerateSeries`], which is also the name of its column
+pub const GENERATE_SERIES: &str = "generate_series";
+
+impl TableFunction for GenerateSeries {
+    fn call(&self, args: &[ScalarValue]) -> Result<Arc<dyn TableProvider>> {
+        let args = args
+            .iter()
+            .map(|arg| match arg {
+                ScalarValue::Int8(Some(v)) => Ok(*v as i64),
+                ScalarValue::Int16(Some(v)) => Ok(*v as i64),
+                ScalarValue::Int32(Some(v)) => Ok(*v as i64),
+                ScalarValue::Int64(Some(v)) => Ok(*v),
+                ScalarValue::UInt8(Some(v)) => Ok(*v as i64),
+                ScalarValue::UInt16(Some(v)) => Ok(*v as i64),
+                ScalarValue::UInt32(Some(v)) => Ok(*v as i64),
+                ScalarValue::UInt64(Some(v)) => i64::try_from(*v).map_err(|_| {
+                    DataFusionError::Plan(format!(
+                        "{} argument {} is out of range",
+                        GENERATE_SERIES, v
+                    ))
+                }),
+                other => Err(DataFusionError::Plan(format!(
+                    "{} arguments must be non-null integers, got {:?}",
+                    GENERATE_SERIES, other
+                ))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let (start, stop, step) = match args.as_slice() {
+            [start, stop] => (*start, *stop, 1),
+            [start, stop, step] => (*start, *stop, *step),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "{} takes 2 or 3 arguments, got {}",
+                    GENERATE_SERIES,
+                    args.len()
+                )))
+            }
+        };
+        if step == 0 {
+            return Err(DataFusionError::Plan(format!(
+                "{} step cannot be 0",
+                GENERATE_SERIES
+            )));
+        }
+        Ok(Arc::new(GenerateSeriesTable::new(start, stop, step)))
+    }
+}
+
+/// The table of a call of [`GenerateSeries`], whose values are only generated
+/// when it is scanned, up to the limit of the scan.
+#[derive(Debug)]
+pub struct GenerateSeriesTable {
+    schema: SchemaRef,
+    start: i64,
+    step: i64,
+    num_rows: usize,
+}
+
+/// Rows of the partitions of a [`GenerateSeriesTable`], but the last one
+const PARTITION_ROWS: usize = 128 * 1024;
+
+impl GenerateSeriesTable {
+    /// The integers from `start` to `stop` (inclusive) every `step`
+    pub fn new(start: i64, stop: i64, step: i64) -> Self {
+        let (start_i128, stop_i128, step_i128) =
+            (start as i128, stop as i128, step as i128);
+        let num_rows = if (step > 0 && start <= stop) || (step < 0 && start >= stop) {
+            (stop_i128 - start_i128) / step_i128 + 1
+        } else {
+            0
+        };
+        Self {
+            schema: Arc::new(Schema::new(vec![Field::new(
+                GENERATE_SERIES,
+                DataType::Int64,
+                false,
+            )])),
+            start,
+            step,
+            num_rows: usize::try_from(num_rows).unwrap_or(usize::MAX),
+        }
+    }
+
+    /// The value of the row `i`, which can't overflow for the rows of the table
+    fn value(&self, i: usize) -> i64 {
+        self.start.wrapping_add((i as i64).wrapping_mul(self.step))
+    }
+}
+
+#[async_trait]
+impl TableProvider for GenerateSeriesTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn table_type(&self) -> TableType {
+        TableType::Temporary
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        _filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let num_rows = limit.map_or(self.num_rows, |limit| limit.min(self.num_rows));
+        let batch_size = batch_size.max(1);
+        let mut partitions = (0..num_rows)
+            .step_by(PARTITION_ROWS)
+            .map(|partition_start| {
+                let partition_end = (partition_start + PARTITION_ROWS).min(num_rows);
+                (partition_start..partition_end)
+                    .step_by(batch_size)
+                    .map(|batch_start| {
+                        let batch_end = (batch_start + batch_size).min(partition_end);
+                        let values = (batch_start..batch_end).map(|i| self.value(i));
+                        RecordBatch::try_new(
+                            self.schema.clone(),
+                            vec![Arc::new(Int64Array::from_iter_values(values))],
+                        )
+                        .map_err(DataFusionError::ArrowError)
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if partitions.is_empty() {
+            partitions.push(vec![]);
+        }
+        Ok(Arc::new(MemoryExec::try_new(
+            &partitions,
+            self.schema.clone(),
+            projection.clone(),
+        )?))
+    }
+
+    fn statistics(&self) -> Statistics {
+        let (min, max) = match self.num_rows {
+            0 => (None, None),
+            num_rows => {
+                let last = self.value(num_rows - 1);
+                (
+                    Some(ScalarValue::Int64(Some(self.start.min(last)))),
+                    Some(ScalarValue::Int64(Some(self.start.max(last)))),
+                )
+            }
+        };
+        Statistics {
+            num_rows: Some(self.num_rows),
+            total_byte_size: Some(self.num_rows * std::mem::size_of::<i64>()),
+            column_statistics: Some(vec![ColumnStatistics {
+                null_count: Some(0),
+                max_value: max,
+                min_value: min,
+                distinct_count: Some(self.num_rows),
+            }]),
+            is_exact: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::collect;
+
+    async fn generate(args: Vec<ScalarValue>, limit: Option<usize>) -> Result<Vec<i64>> {
+        let table = GenerateSeries::default().call(&args)?;
+        let plan = table.scan(&None, 1000, &[], limit).await?;
+        let batches = collect(plan, ExecutionContext::new().task_ctx()).await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                let array = batch
+                    .column(0)
+                    .as_any()
+                    .downcast_ref::<Int64Array>()
+                    .unwrap();
+                array.values().to_vec()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn generate_series() -> Result<()> {
+        let int = |v: i64| ScalarValue::Int64(Some(v));
+        assert_eq!(vec![1, 2, 3], generate(vec![int(1), int(3)], None).await?);
+        assert_eq!(
+            vec![1, 4, 7],
+            generate(vec![int(1), int(8), int(3)], None).await?
+        );
+        assert_eq!(
+            vec![5, 3, 1],
+            generate(vec![int(5), int(0), int(-2)], None).await?
+        );
+        assert!(generate(vec![int(3), int(1)], None).await?.is_empty());
+        assert_eq!(
+            vec![10, 20],
+            generate(vec![int(10), int(100), int(10)], Some(2)).await?
+        );
+
+        let err = generate(vec![int(1), int(3), int(0)], None)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            "Error during planning: generate_series step cannot be 0",
+            err.to_string()
+        );
+        let err = generate(vec![int(1)], None).await.unwrap_err();
+        assert_eq!(
+            "Error during planning: generate_series takes 2 or 3 arguments, got 1",
+            err.to_string()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn generate_series_partitions() -> Result<()> {
+        let table = GenerateSeriesTable::new(1, 1_000_000, 1);
+        assert_eq!(Some(1_000_000), table.statistics().num_rows);
+
+        let plan = table.scan(&None, 8192, &[], None).await?;
+        assert_eq!(8, plan.output_partitioning().partition_count());
+
+        let plan = table.scan(&None, 8192, &[], Some(10)).await?;
+        assert_eq!(1, plan.output_partitioning().partition_count());
+        Ok(())
+    }
+}
@@ -61,6 +61,17 @@ pub enum DataFusionError {
     /// Error returned during execution of the query.
     /// Examples include files not found, errors in parsing certain types.
     Execution(String),
+    /// Error returned when an operator cannot reserve the memory it needs
+    /// from the memory manager, and cannot spill to disk to free some.
+    ResourcesExhausted(String),
+    /// Error returned by the operators of a query cancelled with its
+    /// [`CancellationToken`](crate::execution::cancellation::CancellationToken).
+    Cancelled,
+    /// Error annotated with where it happened, such as the position in the
+    /// SQL text of the identifier it is about, or the operator and partition
+    /// which failed during execution. See [`DataFusionError::find_root`]
+    /// to match on the kind of the annotated error.
+    Context(String, Box<DataFusionError>),
 }
 
 impl DataFusionError {
@@ -68,6 +79,27 @@ impl DataFusionError {
     pub fn into_arrow_external_error(self) -> ArrowError {
         ArrowError::from_external_error(Box::new(self))
     }
+
+    /// Annotate this error with `context`
+    pub fn context(self, context: impl Into<String>) -> Self {
+        DataFusionError::Context(context.into(), Box::new(self))
+    }
+
+    /// The innermost error annotated by the [`DataFusionError::Context`]s
+    /// of this error, looking through the [`DataFusionError`]s wrapped as
+    /// [`ArrowError::ExternalError`]s
+    pub fn find_root(&self) -> &DataFusionError {
+        match self {
+            DataFusionError::Context(_, source) => source.find_root(),
+            DataFusionError::ArrowError(ArrowError::ExternalError(source)) => {
+                match source.downcast_ref::<DataFusionError>() {
+                    Some(source) => source.find_root(),
+                    None => self,
+                }
+            }
+            _ => self,
+        }
+    }
 }
 
 impl From<io::Error> for DataFusionError {
@@ -78,7 +110,18 @@ impl From<io::Error> for DataFusionError {
 
 impl From<ArrowError> for DataFusionError {
     fn from(e: ArrowError) -> Self {
-        DataFusionError::ArrowError(e)
+        match e {
+            // unwrap the errors of DataFusion returned by the operators
+            ArrowError::ExternalError(source) => {
+                match source.downcast::<DataFusionError>() {
+                    Ok(e) => *e,
+                    Err(source) => {
+                        DataFusionError::ArrowError(ArrowError::ExternalError(source))
+                    }
+                }
+            }
+            e => DataFusionError::ArrowError(e),
+        }
     }
 }
 
@@ -129,8 +172,56 @@ impl Display for DataFusionError {
             DataFusionError::Execution(ref desc) => {
                 write!(f, "Execution error: {}", desc)
             }
+            DataFusionError::ResourcesExhausted(ref desc) => {
+                write!(f, "Resources exhausted: {}", desc)
+            }
+            DataFusionError::Cancelled => write!(f, "Query cancelled"),
+            DataFusionError::Context(ref context, ref source) => {
+                // the first line of the context prefixes the source, its other
+                // lines, such as a snippet of the SQL text, follow it
+                match context.split_once('\n') {
+                    Some((first, rest)) => write!(f, "{}: {}\n{}", first, source, rest),
+                    None => write!(f, "{}: {}", context, source),
+                }
+            }
+        }
+    }
+}
+
+impl error::Error for DataFusionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            DataFusionError::ArrowError(e) => Some(e),
+            DataFusionError::Context(_, source) => Some(source.as_ref()),
+            _ => None,
         }
     }
 }
 
-impl error::Error for DataFusionError {}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_root_through_contexts() {
+        let e = DataFusionError::Plan("No field named 'a'".to_owned())
+            .context("line 1, column 8");
+        assert_eq!(
+            e.to_string(),
+            "line 1, column 8: Error during planning: No field named 'a'"
+        );
+        assert!(matches!(e.find_root(), DataFusionError::Plan(_)));
+
+        // as returned by the streams of the operators
+        let e = DataFusionError::from(
+            DataFusionError::Execution("divide by zero".to_owned())
+                .context("FilterExec: a@0 > 1 in partition 3")
+                .into_arrow_external_error(),
+        );
+        assert_eq!(
+            e.to_string(),
+            "FilterExec: a@0 > 1 in partition 3: Execution error: divide by zero"
+        );
+        assert!(matches!(e.find_root(), DataFusionError::Execution(_)));
+    }
+}
@@ -26,12 +26,13 @@ use crate::{
         file_format::{
             avro::AvroFormat,
             csv::CsvFormat,
+            ipc::IpcFormat,
             parquet::{ParquetFormat, DEFAULT_PARQUET_EXTENSION},
             FileFormat,
         },
         MemTable,
     },
-    logical_plan::{PlanType, ToStringifiedPlan},
+    logical_plan::PlanType,
     optimizer::eliminate_limit::EliminateLimit,
     physical_optimizer::{
         aggregate_statistics::AggregateStatistics,
@@ -40,18 +41,21 @@ use crate::{
 };
 use log::debug;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::string::String;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{
-    collections::{HashMap, HashSet},
-    sync::Mutex,
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Mutex, MutexGuard},
 };
 
 use futures::{StreamExt, TryStreamExt};
 use tokio::task::{self, JoinHandle};
 
-use arrow::{csv, datatypes::SchemaRef};
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow::record_batch::RecordBatch;
 
 use crate::catalog::{
     catalog::{CatalogProvider, MemoryCatalogProvider},
@@ -59,30 +63,49 @@ use crate::catalog::{
     ResolvedTableReference, TableReference,
 };
 use crate::datasource::object_store::{ObjectStore, ObjectStoreRegistry};
+use crate::datasource::table_function::{built_in_table_functions, TableFunction};
 use crate::datasource::TableProvider;
 use crate::error::{DataFusionError, Result};
 use crate::execution::dataframe_impl::DataFrameImpl;
 use crate::logical_plan::{
-    CreateExternalTable, CreateMemoryTable, DropTable, FunctionRegistry, LogicalPlan,
-    LogicalPlanBuilder, UNNAMED_TABLE,
+    CreateExternalTable, CreateMemoryTable, DropTable, FunctionRegistry, InsertInto,
+    LogicalPlan, LogicalPlanBuilder, SetVariable, UNNAMED_TABLE,
 };
 use crate::optimizer::common_subexpr_eliminate::CommonSubexprEliminate;
 use crate::optimizer::filter_push_down::FilterPushDown;
+use crate::optimizer::infer_predicates::InferPredicates;
+use crate::optimizer::join_reorder::JoinReorder;
 use crate::optimizer::limit_push_down::LimitPushDown;
-use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::optimizer::{apply_rule, OptimizerRule};
 use crate::optimizer::projection_push_down::ProjectionPushDown;
 use crate::optimizer::simplify_expressions::SimplifyExpressions;
+use crate::physical_optimizer::aggregate_push_down::AggregatePushDown;
 use crate::physical_optimizer::coalesce_batches::CoalesceBatches;
+use crate::physical_optimizer::dynamic_filter_push_down::DynamicFilterPushDown;
+use crate::physical_optimizer::eliminate_redundant::EliminateRedundant;
 use crate::physical_optimizer::merge_exec::AddCoalescePartitionsExec;
+use crate::physical_optimizer::merge_selection::MergeSelection;
 use crate::physical_optimizer::repartition::Repartition;
 
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::disk_manager::DiskManager;
+use crate::execution::memory_manager::MemoryManager;
+use crate::execution::progress::ProgressHandler;
+use crate::execution::result_cache::ResultCache;
+use crate::execution::runtime_env::{bind_runtime, RuntimeEnv};
+use crate::execution::task_context::TaskContext;
 use crate::logical_plan::plan::Explain;
 use crate::optimizer::single_distinct_to_groupby::SingleDistinctToGroupBy;
+use crate::physical_plan::expressions::ArithmeticOverflow;
+use crate::physical_plan::file_format::{plan_to_csv, plan_to_ipc};
 use crate::physical_plan::planner::DefaultPhysicalPlanner;
-use crate::physical_plan::udf::ScalarUDF;
+use crate::physical_plan::udf::{ScalarUDF, UdfErrorMode};
+use crate::physical_plan::udwf::WindowUDF;
 use crate::physical_plan::ExecutionPlan;
 use crate::physical_plan::PhysicalPlanner;
 use crate::sql::{
+    location,
     parser::{DFParser, FileType},
     planner::{ContextProvider, SqlToRel},
 };
@@ -93,7 +116,7 @@ use chrono::{DateTime, Utc};
 use parquet::arrow::ArrowWriter;
 use parquet::file::properties::WriterProperties;
 
-use super::options::{AvroReadOptions, CsvReadOptions};
+use super::options::{AvroReadOptions, CsvReadOptions, CsvWriteOptions, IpcReadOptions};
 
 /// ExecutionContext is the main interface for executing queries with DataFusion. The context
 /// provides the following functionality:
@@ -154,10 +177,12 @@ impl ExecutionContext {
         if config.create_default_catalog_and_schema {
             let default_catalog = MemoryCatalogProvider::new();
 
-            default_catalog.register_schema(
-                config.default_schema.clone(),
-                Arc::new(MemorySchemaProvider::new()),
-            );
+            default_catalog
+                .register_schema(
+                    &config.default_schema,
+                    Arc::new(MemorySchemaProvider::new()),
+                )
+                .expect("memory catalog provider can register schema");
 
             let default_catalog: Arc<dyn CatalogProvider> = if config.information_schema {
                 Arc::new(CatalogWithInformationSchema::new(
@@ -172,15 +197,35 @@ impl ExecutionContext {
                 .register_catalog(config.default_catalog.clone(), default_catalog);
         }
 
+        let memory_manager = config
+            .memory_pool_size
+            .map(|pool_size| Arc::new(MemoryManager::new(pool_size)));
+        let result_cache = config
+            .result_cache_size
+            .map(|capacity| Arc::new(ResultCache::new(capacity)));
+        let disk_manager = Arc::new(DiskManager::new(
+            config.spill_dirs.clone(),
+            config.max_spill_bytes,
+        ));
+        let cpu_executor = config.cpu_threads.map(CpuExecutor::new).unwrap_or_default();
+
         Self {
             state: Arc::new(Mutex::new(ExecutionContextState {
                 catalog_list,
                 scalar_functions: HashMap::new(),
                 var_provider: HashMap::new(),
                 aggregate_functions: HashMap::new(),
+                window_functions: HashMap::new(),
+                table_functions: built_in_table_functions(),
                 config,
                 execution_props: ExecutionProps::new(),
                 object_store_registry: Arc::new(ObjectStoreRegistry::new()),
+                memory_manager,
+                result_cache,
+                disk_manager,
+                cancellation: CancellationToken::new(),
+                cpu_executor,
+                progress_handler: None,
             })),
         }
     }
@@ -198,35 +243,23 @@ impl ExecutionContext {
                 ref location,
                 ref file_type,
                 ref has_header,
+                ref table_partition_cols,
+                ref options,
             }) => {
-                let file_format = match file_type {
-                    FileType::CSV => {
-                        Ok(Arc::new(CsvFormat::default().with_has_header(*has_header))
-                            as Arc<dyn FileFormat>)
-                    }
-                    FileType::Parquet => {
-                        Ok(Arc::new(ParquetFormat::default()) as Arc<dyn FileFormat>)
-                    }
-                    FileType::Avro => {
-                        Ok(Arc::new(AvroFormat::default()) as Arc<dyn FileFormat>)
-                    }
-                    _ => Err(DataFusionError::NotImplemented(format!(
-                        "Unsupported file type {:?}.",
-                        file_type
-                    ))),
-                }?;
+                let (file_format, file_extension) =
+                    external_table_format(file_type, *has_header, options)?;
 
                 let options = ListingOptions {
                     format: file_format,
                     collect_stat: false,
-                    file_extension: String::new(),
+                    file_extension,
                     target_partitions: self
                         .state
                         .lock()
                         .unwrap()
                         .config
                         .target_partitions,
-                    table_partition_cols: vec![],
+                    table_partition_cols: table_partition_cols.clone(),
                 };
 
                 // TODO make schema in CreateExternalTable optional instead of empty
@@ -257,6 +290,48 @@ impl ExecutionContext {
                 Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
             }
 
+            LogicalPlan::InsertInto(InsertInto { name, input }) => {
+                let provider = self
+                    .state
+                    .lock()
+                    .unwrap()
+                    .get_table_provider(name.as_str().into())
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "Table with name '{}' not found",
+                            name
+                        ))
+                    })?;
+                let table = match provider.as_any().downcast_ref::<MemTable>() {
+                    Some(table) => table,
+                    None => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "INSERT INTO is only supported for in memory tables, not {}",
+                            name
+                        )))
+                    }
+                };
+
+                let plan = self.optimize(&input)?;
+                let physical = Arc::new(DataFrameImpl::new(self.state.clone(), &plan));
+
+                // the query is projected to the columns of the table, rewrap the
+                // batches in the schema of the table
+                let schema = table.schema();
+                let batches = physical
+                    .collect()
+                    .await?
+                    .into_iter()
+                    .map(|batch| {
+                        RecordBatch::try_new(schema.clone(), batch.columns().to_vec())
+                    })
+                    .collect::<arrow::error::Result<Vec<_>>>()?;
+                table.insert_batches(batches)?;
+
+                let plan = LogicalPlanBuilder::empty(false).build()?;
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+
             LogicalPlan::DropTable(DropTable { name, if_exist, .. }) => {
                 let returned = self.deregister_table(name.as_str())?;
                 if !if_exist && returned.is_none() {
@@ -270,6 +345,14 @@ impl ExecutionContext {
                 }
             }
 
+            LogicalPlan::SetVariable(SetVariable {
+                variable, value, ..
+            }) => {
+                self.state.lock().unwrap().config.set(&variable, &value)?;
+                let plan = LogicalPlanBuilder::empty(false).build()?;
+                Ok(Arc::new(DataFrameImpl::new(self.state.clone(), &plan)))
+            }
+
             plan => Ok(Arc::new(DataFrameImpl::new(
                 self.state.clone(),
                 &self.optimize(&plan)?,
@@ -292,7 +375,9 @@ impl ExecutionContext {
         // create a query planner
         let state = self.state.lock().unwrap().clone();
         let query_planner = SqlToRel::new(&state);
-        query_planner.statement_to_plan(&statements[0])
+        query_planner
+            .statement_to_plan(&statements[0])
+            .map_err(|e| location::with_location(sql, e))
     }
 
     /// Registers a variable provider within this context.
@@ -308,19 +393,18 @@ impl ExecutionContext {
             .insert(variable_type, provider);
     }
 
-    /// Registers a scalar UDF within this context.
-    ///
-    /// Note in SQL queries, function names are looked up using
-    /// lowercase unless the query uses quotes. For example,
+    /// Registers a scalar UDF within this context, replacing any function
+    /// registered under the same name, regardless of case.
     ///
-    /// `SELECT MY_FUNC(x)...` will look for a function named `"my_func"`
-    /// `SELECT "my_FUNC"(x)` will look for a function named `"my_FUNC"`
+    /// Function names are resolved case-insensitively. For example,
+    /// `SELECT MY_FUNC(x)...` and `SELECT "my_FUNC"(x)` both call a
+    /// function registered as `"My_Func"`.
     pub fn register_udf(&mut self, f: ScalarUDF) {
-        self.state
-            .lock()
-            .unwrap()
+        let mut state = self.state.lock().unwrap();
+        state
             .scalar_functions
-            .insert(f.name.clone(), Arc::new(f));
+            .retain(|name, _| !name.eq_ignore_ascii_case(&f.name));
+        state.scalar_functions.insert(f.name.clone(), Arc::new(f));
     }
 
     /// Registers an aggregate UDF within this context.
@@ -338,6 +422,39 @@ impl ExecutionContext {
             .insert(f.name.clone(), Arc::new(f));
     }
 
+    /// Registers a user-defined window function within this context, called
+    /// with an OVER clause in SQL queries.
+    ///
+    /// Like aggregates, window function names are looked up using lowercase
+    /// unless the query uses quotes.
+    pub fn register_udwf(&mut self, f: WindowUDF) {
+        self.state
+            .lock()
+            .unwrap()
+            .window_functions
+            .insert(f.name.clone(), Arc::new(f));
+    }
+
+    /// Registers a table function within this context, called in the FROM
+    /// clause of SQL queries, e.g. `SELECT * FROM my_udtf(1, 'a')`. It
+    /// replaces any table function registered under the same name,
+    /// regardless of case, including the built-in `generate_series`.
+    pub fn register_udtf(&mut self, name: &str, f: Arc<dyn TableFunction>) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .table_functions
+            .retain(|function_name, _| !function_name.eq_ignore_ascii_case(name));
+        state.table_functions.insert(name.to_owned(), f);
+    }
+
+    /// Returns a snapshot of the user-defined functions registered in this
+    /// context, to resolve the functions of the plans deserialized with
+    /// [`from_json_with_registry`](crate::physical_plan::from_json_with_registry).
+    pub fn function_registry(&self) -> Arc<dyn FunctionRegistry> {
+        let registry = self.state.lock().unwrap().clone();
+        Arc::new(registry)
+    }
+
     /// Creates a DataFrame for reading an Avro data source.
 
     pub async fn read_avro(
@@ -362,6 +479,29 @@ impl ExecutionContext {
         )))
     }
 
+    /// Creates a DataFrame for reading an Arrow IPC data source.
+    pub async fn read_ipc(
+        &mut self,
+        uri: impl Into<String>,
+        options: IpcReadOptions<'_>,
+    ) -> Result<Arc<dyn DataFrame>> {
+        let uri: String = uri.into();
+        let (object_store, path) = self.object_store(&uri)?;
+        let target_partitions = self.state.lock().unwrap().config.target_partitions;
+        Ok(Arc::new(DataFrameImpl::new(
+            self.state.clone(),
+            &LogicalPlanBuilder::scan_ipc(
+                object_store,
+                path,
+                options,
+                None,
+                target_partitions,
+            )
+            .await?
+            .build()?,
+        )))
+    }
+
     /// Creates an empty DataFrame.
     pub fn read_empty(&self) -> Result<Arc<dyn DataFrame>> {
         Ok(Arc::new(DataFrameImpl::new(
@@ -441,8 +581,27 @@ impl ExecutionContext {
             }
             Some(s) => s,
         };
-        let table =
+        if let Some((col, _)) = options
+            .table_partition_cols
+            .iter()
+            .find(|(col, _)| resolved_schema.field_with_name(col).is_ok())
+        {
+            return Err(DataFusionError::Plan(format!(
+                "Partition column {} collides with a column of the files of table {}",
+                col, name
+            )));
+        }
+        let mut table =
             ListingTable::new(object_store, path.to_owned(), resolved_schema, options);
+        let (listing_cache, listing_cache_ttl) = {
+            let config = &self.state.lock().unwrap().config;
+            (config.listing_cache, config.listing_cache_ttl)
+        };
+        if listing_cache {
+            table = table.with_listing_cache(listing_cache_ttl);
+        }
+        // the optimizer estimates the size of the table from its statistics
+        table.collect_statistics().await?;
         self.register_table(name, Arc::new(table))?;
         Ok(())
     }
@@ -507,6 +666,22 @@ impl ExecutionContext {
         Ok(())
     }
 
+    /// Registers an Arrow IPC data source so that it can be referenced from SQL
+    /// statements executed against this context.
+    pub async fn register_ipc(
+        &mut self,
+        name: &str,
+        uri: &str,
+        options: IpcReadOptions<'_>,
+    ) -> Result<()> {
+        let listing_options = options
+            .to_listing_options(self.state.lock().unwrap().config.target_partitions);
+
+        self.register_listing_table(name, uri, listing_options, options.schema)
+            .await?;
+        Ok(())
+    }
+
     /// Registers a named catalog using a custom `CatalogProvider` so that
     /// it can be referenced from SQL statements executed against this
     /// context.
@@ -568,6 +743,95 @@ impl ExecutionContext {
             .get_by_uri(uri)
     }
 
+    /// Returns the runtime environment of this context: its object stores,
+    /// a snapshot of its user-defined functions, its batch size and memory
+    /// limit, its memory and disk managers, the cancellation token of the
+    /// queries it plans and its CPU executor
+    pub fn runtime_env(&self) -> RuntimeEnv {
+        let state = self.state.lock().unwrap();
+        RuntimeEnv {
+            object_store_registry: Arc::clone(&state.object_store_registry),
+            function_registry: Arc::new(state.clone()),
+            batch_size: state.config.batch_size,
+            memory_limit: state.config.memory_limit,
+            memory_manager: state.memory_manager.clone(),
+            disk_manager: state.disk_manager.clone(),
+            cancellation: state.cancellation.clone(),
+            cpu_executor: state.cpu_executor.clone(),
+            progress_handler: state.progress_handler.clone(),
+        }
+    }
+
+    /// Returns the context executing the plans of this context: its current
+    /// settings and its runtime environment, see [`Self::runtime_env`]
+    pub fn task_ctx(&self) -> Arc<TaskContext> {
+        let session_config = self.state.lock().unwrap().config.clone();
+        let runtime = Arc::new(self.runtime_env());
+        Arc::new(TaskContext::new(session_config, runtime))
+    }
+
+    /// Returns the memory manager shared by the queries of this context, if
+    /// a memory pool size is configured. Its usage reports the memory
+    /// currently reserved by the executing operators.
+    pub fn memory_manager(&self) -> Option<Arc<MemoryManager>> {
+        self.state.lock().unwrap().memory_manager.clone()
+    }
+
+    /// Returns the cache of the query results of this context, if a result
+    /// cache size is configured
+    pub fn result_cache(&self) -> Option<Arc<ResultCache>> {
+        self.state.lock().unwrap().result_cache.clone()
+    }
+
+    /// Returns the disk manager allocating the spill files of the queries of
+    /// this context
+    pub fn disk_manager(&self) -> Arc<DiskManager> {
+        self.state.lock().unwrap().disk_manager.clone()
+    }
+
+    /// Returns the executor running the CPU-bound work of the queries of this
+    /// context
+    pub fn cpu_executor(&self) -> CpuExecutor {
+        self.state.lock().unwrap().cpu_executor.clone()
+    }
+
+    /// Returns the state of this context, locked until the guard is dropped.
+    /// The changes of its configuration, see
+    /// [`ExecutionContextState::config_mut`], take effect for the queries
+    /// planned afterwards.
+    pub fn state_mut(&self) -> MutexGuard<'_, ExecutionContextState> {
+        self.state.lock().unwrap()
+    }
+
+    /// Reports the progress of the operators of the queries of this context
+    /// to `handler`, see [`ProgressEvent`](crate::execution::progress::ProgressEvent)
+    /// and [`ExecutionConfig::with_progress_interval`]
+    pub fn with_progress_handler(self, handler: ProgressHandler) -> Self {
+        self.state.lock().unwrap().progress_handler = Some(handler);
+        self
+    }
+
+    /// Cancels the queries planned by this context so far, their operators
+    /// fail with [`DataFusionError::Cancelled`]. The queries planned
+    /// afterwards are not cancelled.
+    ///
+    /// A single query can be cancelled by binding its plan to a runtime
+    /// environment with its own [`CancellationToken`].
+    pub fn cancel_all(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.cancellation.cancel();
+        state.cancellation = CancellationToken::new();
+    }
+
+    /// Binds a deserialized plan to the runtime environment of this context,
+    /// see [`bind_runtime`]
+    pub fn bind_runtime(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        bind_runtime(plan, &self.runtime_env())
+    }
+
     /// Registers a table using a custom `TableProvider` so that
     /// it can be referenced from SQL statements executed against this
     /// context.
@@ -587,6 +851,49 @@ impl ExecutionContext {
             .register_table(table_ref.table().to_owned(), provider)
     }
 
+    /// Registers the `partitions` of record batches of `schema` as an
+    /// in-memory table, with the key/value `metadata` attached to its schema
+    /// and the key/value metadata of `field_metadata` attached to the fields
+    /// it names. The metadata is added to the existing metadata of `schema`.
+    ///
+    /// Returns the `TableProvider` previously registered for this
+    /// reference, if any
+    pub fn register_memtable_with_metadata<'a>(
+        &'a mut self,
+        table_ref: impl Into<TableReference<'a>>,
+        schema: SchemaRef,
+        partitions: Vec<Vec<RecordBatch>>,
+        metadata: HashMap<String, String>,
+        field_metadata: HashMap<String, BTreeMap<String, String>>,
+    ) -> Result<Option<Arc<dyn TableProvider>>> {
+        let mut fields = schema.fields().clone();
+        for (name, field_metadata) in field_metadata {
+            let field = &mut fields[schema.index_of(&name)?];
+            let mut merged = field.metadata().clone().unwrap_or_default();
+            merged.extend(field_metadata);
+            field.set_metadata(Some(merged));
+        }
+        let mut schema_metadata = schema.metadata().clone();
+        schema_metadata.extend(metadata);
+        let schema = Arc::new(Schema::new_with_metadata(fields, schema_metadata));
+
+        // the batches carry the schema with its metadata, so that it is kept
+        // by the operators passing them through
+        let partitions = partitions
+            .into_iter()
+            .map(|batches| {
+                batches
+                    .into_iter()
+                    .map(|batch| {
+                        RecordBatch::try_new(schema.clone(), batch.columns().to_vec())
+                    })
+                    .collect::<arrow::error::Result<Vec<_>>>()
+            })
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        let table = MemTable::try_new(schema, partitions)?;
+        self.register_table(table_ref, Arc::new(table))
+    }
+
     /// Deregisters the given table.
     ///
     /// Returns the registered provider, if any
@@ -602,6 +909,28 @@ impl ExecutionContext {
             .deregister_table(table_ref.table())
     }
 
+    /// Lists the files of the given listing table again and updates its schema,
+    /// see [`ListingTable::refresh`].
+    ///
+    /// Returns an error if no listing table has been registered with the provided reference.
+    pub async fn refresh_table<'a>(
+        &self,
+        table_ref: impl Into<TableReference<'a>>,
+    ) -> Result<()> {
+        let table_ref = table_ref.into();
+        let schema = self.state.lock().unwrap().schema_for_ref(table_ref)?;
+        let provider = schema.table(table_ref.table()).ok_or_else(|| {
+            DataFusionError::Plan(format!("No table named '{}'", table_ref.table()))
+        })?;
+        match provider.as_any().downcast_ref::<ListingTable>() {
+            Some(table) => table.refresh().await,
+            None => Err(DataFusionError::Plan(format!(
+                "Table '{}' is not a listing table",
+                table_ref.table()
+            ))),
+        }
+    }
+
     /// Retrieves a DataFrame representing a table previously registered by calling the
     /// register_table function.
     ///
@@ -660,7 +989,7 @@ impl ExecutionContext {
                 self.optimize_internal(e.plan.as_ref(), |optimized_plan, optimizer| {
                     let optimizer_name = optimizer.name().to_string();
                     let plan_type = PlanType::OptimizedLogicalPlan { optimizer_name };
-                    stringified_plans.push(optimized_plan.to_stringified(plan_type));
+                    stringified_plans.push(e.format.stringify(optimized_plan, plan_type));
                 })?;
 
             Ok(LogicalPlan::Explain(Explain {
@@ -668,6 +997,7 @@ impl ExecutionContext {
                 plan: Arc::new(plan),
                 stringified_plans,
                 schema: e.schema.clone(),
+                format: e.format,
             }))
         } else {
             self.optimize_internal(plan, |_, _| {})
@@ -695,48 +1025,39 @@ impl ExecutionContext {
             (state.clone(), Arc::clone(&state.config.query_planner))
         };
 
-        planner.create_physical_plan(logical_plan, &state).await
+        let plan = planner.create_physical_plan(logical_plan, &state).await?;
+        // the buffering operators account their memory on the manager of the
+        // context, and are cancelled by `cancel_all`
+        self.bind_runtime(plan)
     }
 
-    /// Executes a query and writes the results to a partitioned CSV file.
+    /// Executes a query and writes the results to a partitioned CSV file using
+    /// the default [`CsvWriteOptions`]. Returns the paths of the files written.
     pub async fn write_csv(
         &self,
         plan: Arc<dyn ExecutionPlan>,
         path: impl AsRef<str>,
-    ) -> Result<()> {
-        let path = path.as_ref();
-        // create directory to contain the CSV files (one per partition)
-        let fs_path = Path::new(path);
-        match fs::create_dir(fs_path) {
-            Ok(()) => {
-                let mut tasks = vec![];
-                for i in 0..plan.output_partitioning().partition_count() {
-                    let plan = plan.clone();
-                    let filename = format!("part-{}.csv", i);
-                    let path = fs_path.join(&filename);
-                    let file = fs::File::create(path)?;
-                    let mut writer = csv::Writer::new(file);
-                    let stream = plan.execute(i).await?;
-                    let handle: JoinHandle<Result<()>> = task::spawn(async move {
-                        stream
-                            .map(|batch| writer.write(&batch?))
-                            .try_collect()
-                            .await
-                            .map_err(DataFusionError::from)
-                    });
-                    tasks.push(handle);
-                }
-                futures::future::join_all(tasks).await;
-                Ok(())
-            }
-            Err(e) => Err(DataFusionError::Execution(format!(
-                "Could not create directory {}: {:?}",
-                path, e
-            ))),
-        }
+    ) -> Result<Vec<String>> {
+        self.write_csv_with_options(plan, path, &CsvWriteOptions::new())
+            .await
+    }
+
+    /// Executes a query and writes the results to a partitioned CSV file, one
+    /// file per output partition. Returns the paths of the files written.
+    pub async fn write_csv_with_options(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        path: impl AsRef<str>,
+        options: &CsvWriteOptions,
+    ) -> Result<Vec<String>> {
+        plan_to_csv(plan, path, options, self.task_ctx()).await
     }
 
     /// Executes a query and writes the results to a partitioned Parquet file.
+    ///
+    /// The metadata of the schema of `plan` and of its fields is written
+    /// with the Arrow schema embedded in the key/value metadata of the files,
+    /// and is read back when registering them.
     pub async fn write_parquet(
         &self,
         plan: Arc<dyn ExecutionPlan>,
@@ -748,6 +1069,7 @@ impl ExecutionContext {
         let fs_path = Path::new(path);
         match fs::create_dir(fs_path) {
             Ok(()) => {
+                let context = self.task_ctx();
                 let mut tasks = vec![];
                 for i in 0..plan.output_partitioning().partition_count() {
                     let plan = plan.clone();
@@ -759,7 +1081,7 @@ impl ExecutionContext {
                         plan.schema(),
                         writer_properties.clone(),
                     )?;
-                    let stream = plan.execute(i).await?;
+                    let stream = plan.execute(i, context.clone()).await?;
                     let handle: JoinHandle<Result<()>> = task::spawn(async move {
                         stream
                             .map(|batch| writer.write(&batch?))
@@ -780,6 +1102,17 @@ impl ExecutionContext {
         }
     }
 
+    /// Executes a query and writes the results to a partitioned Arrow IPC file.
+    /// Returns the paths of the files written.
+    pub async fn write_ipc(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        path: impl AsRef<str>,
+        write_options: Option<IpcWriteOptions>,
+    ) -> Result<Vec<String>> {
+        plan_to_ipc(plan, path, write_options, self.task_ctx()).await
+    }
+
     /// Optimizes the logical plan by applying optimizer rules, and
     /// invoking observer function after each call
     fn optimize_internal<F>(
@@ -792,14 +1125,19 @@ impl ExecutionContext {
     {
         let state = &mut self.state.lock().unwrap();
         let execution_props = &mut state.execution_props.clone();
-        let optimizers = &state.config.optimizers;
+        let config = &state.config;
 
         let execution_props = execution_props.start_execution();
 
         let mut new_plan = plan.clone();
         debug!("Logical plan:\n {:?}", plan);
-        for optimizer in optimizers {
-            new_plan = optimizer.optimize(&new_plan, execution_props)?;
+        for optimizer in &config.optimizers {
+            if !config.is_rule_enabled(optimizer.name()) {
+                continue;
+            }
+            new_plan = apply_rule(optimizer.name(), || {
+                optimizer.optimize(&new_plan, execution_props)
+            })?;
             observer(&new_plan, optimizer.as_ref());
         }
         debug!("Optimized logical plan:\n {:?}", new_plan);
@@ -825,6 +1163,85 @@ impl FunctionRegistry for ExecutionContext {
     fn udaf(&self, name: &str) -> Result<Arc<AggregateUDF>> {
         self.state.lock().unwrap().udaf(name)
     }
+
+    fn udwf(&self, name: &str) -> Result<Arc<WindowUDF>> {
+        self.state.lock().unwrap().udwf(name)
+    }
+}
+
+/// Create the file format of a `CREATE EXTERNAL TABLE` statement with the
+/// format specific `OPTIONS` applied. Returns the format and the extension
+/// of the files to read.
+fn external_table_format(
+    file_type: &FileType,
+    has_header: bool,
+    options: &HashMap<String, String>,
+) -> Result<(Arc<dyn FileFormat>, String)> {
+    fn parse_option<T: std::str::FromStr>(key: &str, value: &str) -> Result<T> {
+        value.parse().map_err(|_| {
+            DataFusionError::Plan(format!("Invalid value '{}' for option {}", value, key))
+        })
+    }
+
+    let unsupported = |key: &str| {
+        Err(DataFusionError::Plan(format!(
+            "Unsupported option {} for {:?} files",
+            key, file_type
+        )))
+    };
+
+    // the file extension applies to all the formats
+    let mut options = options.clone();
+    let file_extension = options.remove("file_extension").unwrap_or_default();
+
+    let file_format: Arc<dyn FileFormat> = match file_type {
+        FileType::CSV => {
+            let mut format = CsvFormat::default().with_has_header(has_header);
+            for (key, value) in &options {
+                format = match key.as_str() {
+                    "has_header" => format.with_has_header(parse_option(key, value)?),
+                    "delimiter" => match value.as_bytes() {
+                        [delimiter] => format.with_delimiter(*delimiter),
+                        _ => {
+                            return Err(DataFusionError::Plan(format!(
+                                "Invalid value '{}' for option {}, expected a single character",
+                                value, key
+                            )))
+                        }
+                    },
+                    "schema_infer_max_rec" => {
+                        format.with_schema_infer_max_rec(Some(parse_option(key, value)?))
+                    }
+                    _ => return unsupported(key),
+                };
+            }
+            Arc::new(format)
+        }
+        FileType::Parquet => {
+            let mut format = ParquetFormat::default();
+            for (key, value) in &options {
+                format = match key.as_str() {
+                    "enable_pruning" => {
+                        format.with_enable_pruning(parse_option(key, value)?)
+                    }
+                    _ => return unsupported(key),
+                };
+            }
+            Arc::new(format)
+        }
+        FileType::Avro | FileType::Arrow if !options.is_empty() => {
+            return unsupported(options.keys().next().unwrap());
+        }
+        FileType::Avro => Arc::new(AvroFormat::default()),
+        FileType::Arrow => Arc::new(IpcFormat::default()),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Unsupported file type {:?}.",
+                file_type
+            )))
+        }
+    };
+    Ok((file_format, file_extension))
 }
 
 /// A planner used to add extensions to DataFusion logical and physical plans.
@@ -865,6 +1282,8 @@ pub struct ExecutionConfig {
     optimizers: Vec<Arc<dyn OptimizerRule + Send + Sync>>,
     /// Responsible for optimizing a physical execution plan
     pub physical_optimizers: Vec<Arc<dyn PhysicalOptimizerRule + Send + Sync>>,
+    /// Names of the logical and physical optimizer rules that are skipped
+    disabled_rules: HashSet<String>,
     /// Responsible for planning `LogicalPlan`s, and `ExecutionPlan`
     query_planner: Arc<dyn QueryPlanner + Send + Sync>,
     /// Default catalog name for table resolution
@@ -887,6 +1306,82 @@ pub struct ExecutionConfig {
     pub repartition_windows: bool,
     /// Should Datafusion parquet reader using the predicate to prune data
     parquet_pruning: bool,
+    /// Should DataFusion run partial aggregates below the inner joins when the
+    /// groups and the aggregated values come from one side of the join
+    pub aggregate_push_down: bool,
+    /// Maximum estimated number of rows of the left input of an inner join for
+    /// it to be collected and broadcast to all the partitions of the right
+    /// input, instead of repartitioning both inputs. Never if `None`
+    pub collect_left_join_rows: Option<usize>,
+    /// Should listing tables cache the files listed from the object store,
+    /// until `ExecutionContext::refresh_table` is called
+    pub listing_cache: bool,
+    /// How long the cached listings stay valid, `None` to keep them until
+    /// the table is refreshed
+    pub listing_cache_ttl: Option<Duration>,
+    /// Maximum number of bytes an operator may buffer, unlimited if `None`
+    pub memory_limit: Option<usize>,
+    /// Number of bytes the buffering operators of all the queries of a
+    /// context may reserve in total, unaccounted if `None`
+    pub memory_pool_size: Option<usize>,
+    /// Number of bytes of the query results a context caches, no caching if
+    /// `None`
+    pub result_cache_size: Option<usize>,
+    /// Directories the spilling operators write their temporary files to,
+    /// round-robin. The temporary directory of the system if empty
+    pub spill_dirs: Vec<PathBuf>,
+    /// Maximum number of bytes of the spill files of the queries of a
+    /// context, unlimited if `None`
+    pub max_spill_bytes: Option<usize>,
+    /// Maximum number of bytes of the batches combined to reach the target
+    /// number of rows of `CoalesceBatchesExec`, unlimited if `None`
+    pub coalesce_batch_bytes: Option<usize>,
+    /// Number of threads running the CPU-bound work of the operators of the
+    /// queries of a context. If `None`, the work runs on a pool with a thread
+    /// per core shared by the contexts
+    pub cpu_threads: Option<usize>,
+    /// Number of batches buffered for each output partition of
+    /// `RepartitionExec` being read before its input tasks wait for the
+    /// partition to be read further. The partitions that are not read yet
+    /// buffer their batches without bound.
+    pub repartition_channel_capacity: usize,
+    /// Whether `FilterExec` combines the rows it selects into batches of at
+    /// least half the batch size, rather than returning a batch per input
+    /// batch
+    pub coalesce_filter_output: bool,
+    /// Observed selectivity of a `FilterExec` under which it returns the rows
+    /// it selects as a slice of the input batch, without copying them, when
+    /// they are contiguous. 0 disables the slicing
+    pub filter_slice_selectivity: f64,
+    /// Whether `HashJoinExec` splits the rows joined with a probe-side batch
+    /// into batches of at most the batch size
+    pub split_join_output: bool,
+    /// Whether `HashAggregateExec` keeps the states of its groups in rows of
+    /// bytes when its group keys and aggregates are all of fixed width,
+    /// rather than in an accumulator per aggregate and group
+    pub row_aggregation: bool,
+    /// Whether `EXPLAIN` shows the statistics of the operators of the
+    /// physical plans, as `EXPLAIN VERBOSE` does
+    pub explain_statistics: bool,
+    /// Number of output batches of a partition of an operator between two
+    /// events sent to the progress handler of the context
+    pub progress_interval: usize,
+    /// Whether the SQL planner parses the numbers with a decimal point, such
+    /// as `1.23`, as decimals rather than as `Float64` values, and the integers
+    /// out of the range of `Int64` as decimals rather than failing
+    pub parse_float_as_decimal: bool,
+    /// How the integer arithmetic, the sums of integers and the casts to
+    /// integers handle the results out of the range of their type
+    pub arithmetic_overflow: ArithmeticOverflow,
+    /// How the errors of the UDFs on some rows of a batch are handled, unless
+    /// a UDF has its own error mode
+    pub udf_error_mode: UdfErrorMode,
+    /// Maximum number of calls of async UDFs a partition of a projection
+    /// awaits at once, spread over the batches it projects concurrently
+    pub async_udf_concurrency: usize,
+    /// Maximum number of iterations of the recursive term of a recursive
+    /// query, beyond which the query fails
+    pub recursive_query_max_iterations: usize,
 }
 
 impl Default for ExecutionConfig {
@@ -901,17 +1396,24 @@ impl Default for ExecutionConfig {
                 Arc::new(CommonSubexprEliminate::new()),
                 Arc::new(EliminateLimit::new()),
                 Arc::new(ProjectionPushDown::new()),
+                Arc::new(InferPredicates::new()),
                 Arc::new(FilterPushDown::new()),
+                Arc::new(JoinReorder::new()),
                 Arc::new(LimitPushDown::new()),
                 Arc::new(SingleDistinctToGroupBy::new()),
             ],
             physical_optimizers: vec![
                 Arc::new(AggregateStatistics::new()),
+                Arc::new(EliminateRedundant::new()),
+                Arc::new(AggregatePushDown::new()),
                 Arc::new(HashBuildProbeOrder::new()),
                 Arc::new(CoalesceBatches::new()),
                 Arc::new(Repartition::new()),
                 Arc::new(AddCoalescePartitionsExec::new()),
+                Arc::new(MergeSelection::new()),
+                Arc::new(DynamicFilterPushDown::new()),
             ],
+            disabled_rules: HashSet::new(),
             query_planner: Arc::new(DefaultQueryPlanner {}),
             default_catalog: "datafusion".to_owned(),
             default_schema: "public".to_owned(),
@@ -921,6 +1423,29 @@ impl Default for ExecutionConfig {
             repartition_aggregations: true,
             repartition_windows: true,
             parquet_pruning: true,
+            aggregate_push_down: true,
+            collect_left_join_rows: None,
+            listing_cache: false,
+            listing_cache_ttl: None,
+            memory_limit: None,
+            memory_pool_size: None,
+            result_cache_size: None,
+            spill_dirs: vec![],
+            max_spill_bytes: None,
+            coalesce_batch_bytes: None,
+            cpu_threads: None,
+            repartition_channel_capacity: 8,
+            coalesce_filter_output: true,
+            filter_slice_selectivity: 0.01,
+            split_join_output: true,
+            row_aggregation: true,
+            explain_statistics: false,
+            progress_interval: 16,
+            parse_float_as_decimal: false,
+            arithmetic_overflow: ArithmeticOverflow::default(),
+            udf_error_mode: UdfErrorMode::default(),
+            async_udf_concurrency: 16,
+            recursive_query_max_iterations: 100,
         }
     }
 }
@@ -974,7 +1499,25 @@ impl ExecutionConfig {
         self
     }
 
-    /// Adds a new [`OptimizerRule`]
+    /// The logical optimizer rules, in the order they are applied
+    pub fn optimizer_rules(&self) -> &[Arc<dyn OptimizerRule + Send + Sync>] {
+        &self.optimizers
+    }
+
+    /// Disables the logical and physical optimizer rules with the given names,
+    /// see [`OptimizerRule::name`] and [`PhysicalOptimizerRule::name`]
+    pub fn with_disabled_rules(mut self, names: &[&str]) -> Self {
+        self.disabled_rules
+            .extend(names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Returns true if the optimizer rule named `name` is applied
+    pub fn is_rule_enabled(&self, name: &str) -> bool {
+        !self.disabled_rules.contains(name)
+    }
+
+    /// Adds a new [`OptimizerRule`], applied after the rules already added
     pub fn add_optimizer_rule(
         mut self,
         optimizer_rule: Arc<dyn OptimizerRule + Send + Sync>,
@@ -983,7 +1526,7 @@ impl ExecutionConfig {
         self
     }
 
-    /// Adds a new [`PhysicalOptimizerRule`]
+    /// Adds a new [`PhysicalOptimizerRule`], applied after the rules already added
     pub fn add_physical_optimizer_rule(
         mut self,
         optimizer_rule: Arc<dyn PhysicalOptimizerRule + Send + Sync>,
@@ -1038,72 +1581,410 @@ impl ExecutionConfig {
         self.parquet_pruning = enabled;
         self
     }
-}
 
-/// Holds per-execution properties and data (such as starting timestamps, etc).
-/// An instance of this struct is created each time a [`LogicalPlan`] is prepared for
-/// execution (optimized). If the same plan is optimized multiple times, a new
-/// `ExecutionProps` is created each time.
-#[derive(Clone)]
-pub struct ExecutionProps {
-    pub(crate) query_execution_start_time: DateTime<Utc>,
-}
+    /// Enables or disables the pushing down of partial aggregates below joins
+    pub fn with_aggregate_push_down(mut self, enabled: bool) -> Self {
+        self.aggregate_push_down = enabled;
+        self
+    }
 
-/// Execution context for registering data sources and executing queries
-#[derive(Clone)]
-pub struct ExecutionContextState {
-    /// Collection of catalogs containing schemas and ultimately TableProviders
-    pub catalog_list: Arc<dyn CatalogList>,
-    /// Scalar functions that are registered with the context
-    pub scalar_functions: HashMap<String, Arc<ScalarUDF>>,
-    /// Variable provider that are registered with the context
-    pub var_provider: HashMap<VarType, Arc<dyn VarProvider + Send + Sync>>,
-    /// Aggregate functions registered in the context
-    pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
-    /// Context configuration
-    pub config: ExecutionConfig,
-    /// Execution properties
-    pub execution_props: ExecutionProps,
-    /// Object Store that are registered with the context
-    pub object_store_registry: Arc<ObjectStoreRegistry>,
-}
+    /// Broadcast the left input of the inner joins estimated to have at most
+    /// `rows` rows
+    pub fn with_collect_left_join_rows(mut self, rows: usize) -> Self {
+        self.collect_left_join_rows = Some(rows);
+        self
+    }
 
-impl ExecutionProps {
-    /// Creates a new execution props
-    pub fn new() -> Self {
-        ExecutionProps {
-            query_execution_start_time: chrono::Utc::now(),
-        }
+    /// Enables or disables the caching of the files listed by listing tables
+    pub fn with_listing_cache(mut self, enabled: bool) -> Self {
+        self.listing_cache = enabled;
+        self
     }
 
-    /// Marks the execution of query started timestamp
-    pub fn start_execution(&mut self) -> &Self {
-        self.query_execution_start_time = chrono::Utc::now();
-        &*self
+    /// Customize how long the cached listings stay valid
+    pub fn with_listing_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.listing_cache_ttl = Some(ttl);
+        self
     }
-}
 
-impl ExecutionContextState {
-    /// Returns new ExecutionContextState
-    pub fn new() -> Self {
-        ExecutionContextState {
-            catalog_list: Arc::new(MemoryCatalogList::new()),
-            scalar_functions: HashMap::new(),
-            var_provider: HashMap::new(),
-            aggregate_functions: HashMap::new(),
-            config: ExecutionConfig::new(),
-            execution_props: ExecutionProps::new(),
-            object_store_registry: Arc::new(ObjectStoreRegistry::new()),
-        }
+    /// Limit the number of bytes an operator may buffer
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes);
+        self
     }
 
-    fn resolve_table_ref<'a>(
-        &'a self,
-        table_ref: impl Into<TableReference<'a>>,
-    ) -> ResolvedTableReference<'a> {
-        table_ref
-            .into()
-            .resolve(&self.config.default_catalog, &self.config.default_schema)
+    /// Account the memory buffered by the sorts, joins and aggregations of
+    /// the context on a pool of `bytes` bytes. A sort spills to disk when
+    /// the pool is full, the other operators fail.
+    pub fn with_memory_pool_size(mut self, bytes: usize) -> Self {
+        self.memory_pool_size = Some(bytes);
+        self
+    }
+
+    /// Cache up to `bytes` bytes of the results collected by the DataFrames
+    /// of the context. Only the results of queries over tables with a
+    /// [version](crate::datasource::TableProvider::version) and without
+    /// volatile functions are cached, until the tables change.
+    pub fn with_result_cache_size(mut self, bytes: usize) -> Self {
+        self.result_cache_size = Some(bytes);
+        self
+    }
+
+    /// Customize the directories the spill files are written to
+    pub fn with_spill_dirs(mut self, dirs: Vec<PathBuf>) -> Self {
+        self.spill_dirs = dirs;
+        self
+    }
+
+    /// Limit the number of bytes of the spill files
+    pub fn with_max_spill_bytes(mut self, bytes: usize) -> Self {
+        self.max_spill_bytes = Some(bytes);
+        self
+    }
+
+    /// Stop combining small batches once they reach `bytes` bytes, even if
+    /// they have fewer rows than the target batch size
+    pub fn with_coalesce_batch_bytes(mut self, bytes: usize) -> Self {
+        self.coalesce_batch_bytes = Some(bytes);
+        self
+    }
+
+    /// Run the CPU-bound work of the operators on a pool of `n` threads owned
+    /// by the context
+    pub fn with_cpu_threads(mut self, n: usize) -> Self {
+        self.cpu_threads = Some(n);
+        self
+    }
+
+    /// Buffer up to `capacity` batches for each output partition of
+    /// `RepartitionExec`, at least one
+    pub fn with_repartition_channel_capacity(mut self, capacity: usize) -> Self {
+        self.repartition_channel_capacity = capacity.max(1);
+        self
+    }
+
+    /// Enables or disables the combining of the rows selected by `FilterExec`
+    /// into batches of the batch size, instead of wrapping the filters in a
+    /// `CoalesceBatchesExec`
+    pub fn with_coalesce_filter_output(mut self, enabled: bool) -> Self {
+        self.coalesce_filter_output = enabled;
+        self
+    }
+
+    /// Customize the selectivity of a `FilterExec` under which it returns
+    /// the rows it selects as a slice of the input batch when they are
+    /// contiguous, between 0 to disable the slicing and 1 to always slice
+    pub fn with_filter_slice_selectivity(mut self, selectivity: f64) -> Self {
+        self.filter_slice_selectivity = selectivity.max(0.0).min(1.0);
+        self
+    }
+
+    /// Enables or disables the splitting of the output of `HashJoinExec` into
+    /// batches of at most the batch size
+    pub fn with_split_join_output(mut self, enabled: bool) -> Self {
+        self.split_join_output = enabled;
+        self
+    }
+
+    /// Enables or disables the row-based states of the groups of
+    /// `HashAggregateExec` for the fixed-width group keys and aggregates
+    pub fn with_row_aggregation(mut self, enabled: bool) -> Self {
+        self.row_aggregation = enabled;
+        self
+    }
+
+    /// Enables or disables the statistics of the operators in the physical
+    /// plans shown by `EXPLAIN`, which `EXPLAIN VERBOSE` always shows
+    pub fn with_explain_statistics(mut self, enabled: bool) -> Self {
+        self.explain_statistics = enabled;
+        self
+    }
+
+    /// Enables or disables the parsing of the numbers with a decimal point in
+    /// SQL statements as decimals rather than as `Float64` values, and of the
+    /// integers out of the range of `Int64` as decimals
+    pub fn with_parse_float_as_decimal(mut self, enabled: bool) -> Self {
+        self.parse_float_as_decimal = enabled;
+        self
+    }
+
+    /// Sets how the integer arithmetic handles the results out of the range
+    /// of their type: failing, as ANSI SQL does, wrapping around, the
+    /// default, or saturating
+    pub fn with_arithmetic_overflow(mut self, overflow: ArithmeticOverflow) -> Self {
+        self.arithmetic_overflow = overflow;
+        self
+    }
+
+    /// Handle the errors of the UDFs on some rows of a batch in `mode`, unless
+    /// a UDF has its own error mode
+    pub fn with_udf_error_mode(mut self, mode: UdfErrorMode) -> Self {
+        self.udf_error_mode = mode;
+        self
+    }
+
+    /// Await up to `n` calls of async UDFs at once in each partition of a
+    /// projection, at least one
+    pub fn with_async_udf_concurrency(mut self, n: usize) -> Self {
+        self.async_udf_concurrency = n.max(1);
+        self
+    }
+
+    /// Fail the recursive queries whose recursive term doesn't reach a
+    /// fixpoint within `n` iterations, at least one
+    pub fn with_recursive_query_max_iterations(mut self, n: usize) -> Self {
+        self.recursive_query_max_iterations = n.max(1);
+        self
+    }
+
+    /// Report the progress of the partitions of the operators every `n`
+    /// output batches, at least one, if the context has a progress handler
+    pub fn with_progress_interval(mut self, n: usize) -> Self {
+        self.progress_interval = n.max(1);
+        self
+    }
+
+    /// Returns the value of the setting named `key`, one of
+    /// [`CONFIG_OPTIONS`]. Names are case insensitive.
+    pub fn get(&self, key: &str) -> Result<String> {
+        let value = match key.to_lowercase().as_str() {
+            "datafusion.execution.arithmetic_overflow" => {
+                self.arithmetic_overflow.to_string()
+            }
+            "datafusion.execution.batch_size" => self.batch_size.to_string(),
+            "datafusion.execution.parquet_pruning" => self.parquet_pruning.to_string(),
+            "datafusion.execution.recursive_query_max_iterations" => {
+                self.recursive_query_max_iterations.to_string()
+            }
+            "datafusion.execution.target_partitions" => {
+                self.target_partitions.to_string()
+            }
+            "datafusion.execution.udf_error_mode" => self.udf_error_mode.to_string(),
+            "datafusion.optimizer.aggregate_push_down" => {
+                self.aggregate_push_down.to_string()
+            }
+            "datafusion.optimizer.repartition_aggregations" => {
+                self.repartition_aggregations.to_string()
+            }
+            "datafusion.optimizer.repartition_joins" => {
+                self.repartition_joins.to_string()
+            }
+            "datafusion.optimizer.repartition_windows" => {
+                self.repartition_windows.to_string()
+            }
+            "datafusion.sql_parser.parse_float_as_decimal" => {
+                self.parse_float_as_decimal.to_string()
+            }
+            _ => return Err(unknown_config_option(key)),
+        };
+        Ok(value)
+    }
+
+    /// Sets the setting named `key`, one of [`CONFIG_OPTIONS`], to `value`
+    /// parsed as a value of the type of the setting. Names are case
+    /// insensitive.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        match key.to_lowercase().as_str() {
+            "datafusion.execution.arithmetic_overflow" => {
+                self.arithmetic_overflow = parse_arithmetic_overflow(key, value)?
+            }
+            "datafusion.execution.batch_size" => {
+                self.batch_size = parse_positive(key, value)?
+            }
+            "datafusion.execution.parquet_pruning" => {
+                self.parquet_pruning = parse_bool(key, value)?
+            }
+            "datafusion.execution.recursive_query_max_iterations" => {
+                self.recursive_query_max_iterations = parse_positive(key, value)?
+            }
+            "datafusion.execution.target_partitions" => {
+                self.target_partitions = parse_positive(key, value)?
+            }
+            "datafusion.execution.udf_error_mode" => {
+                self.udf_error_mode = parse_udf_error_mode(key, value)?
+            }
+            "datafusion.optimizer.aggregate_push_down" => {
+                self.aggregate_push_down = parse_bool(key, value)?
+            }
+            "datafusion.optimizer.repartition_aggregations" => {
+                self.repartition_aggregations = parse_bool(key, value)?
+            }
+            "datafusion.optimizer.repartition_joins" => {
+                self.repartition_joins = parse_bool(key, value)?
+            }
+            "datafusion.optimizer.repartition_windows" => {
+                self.repartition_windows = parse_bool(key, value)?
+            }
+            "datafusion.sql_parser.parse_float_as_decimal" => {
+                self.parse_float_as_decimal = parse_bool(key, value)?
+            }
+            _ => return Err(unknown_config_option(key)),
+        }
+        Ok(())
+    }
+
+    /// Name and value of the settings of [`CONFIG_OPTIONS`], sorted by name
+    pub fn options(&self) -> Vec<(String, String)> {
+        CONFIG_OPTIONS
+            .iter()
+            .map(|key| (key.to_string(), self.get(key).unwrap()))
+            .collect()
+    }
+}
+
+/// Names of the settings of an [`ExecutionConfig`] that can be read and
+/// changed by name, with [`ExecutionConfig::get`] and [`ExecutionConfig::set`]
+/// or the `SHOW` and `SET` SQL statements, sorted
+pub const CONFIG_OPTIONS: &[&str] = &[
+    "datafusion.execution.arithmetic_overflow",
+    "datafusion.execution.batch_size",
+    "datafusion.execution.parquet_pruning",
+    "datafusion.execution.recursive_query_max_iterations",
+    "datafusion.execution.target_partitions",
+    "datafusion.execution.udf_error_mode",
+    "datafusion.optimizer.aggregate_push_down",
+    "datafusion.optimizer.repartition_aggregations",
+    "datafusion.optimizer.repartition_joins",
+    "datafusion.optimizer.repartition_windows",
+    "datafusion.sql_parser.parse_float_as_decimal",
+];
+
+fn unknown_config_option(key: &str) -> DataFusionError {
+    DataFusionError::Plan(format!("Unknown configuration option '{}'", key))
+}
+
+fn parse_bool(key: &str, value: &str) -> Result<bool> {
+    value.to_lowercase().parse().map_err(|_| {
+        DataFusionError::Plan(format!(
+            "Configuration option '{}' expects a boolean, got '{}'",
+            key, value
+        ))
+    })
+}
+
+fn parse_arithmetic_overflow(key: &str, value: &str) -> Result<ArithmeticOverflow> {
+    value.parse().map_err(|_| {
+        DataFusionError::Plan(format!(
+            "Configuration option '{}' expects one of error, wrap or saturate, got '{}'",
+            key, value
+        ))
+    })
+}
+
+fn parse_udf_error_mode(key: &str, value: &str) -> Result<UdfErrorMode> {
+    value.parse().map_err(|_| {
+        DataFusionError::Plan(format!(
+            "Configuration option '{}' expects one of strict or permissive, got '{}'",
+            key, value
+        ))
+    })
+}
+
+fn parse_positive(key: &str, value: &str) -> Result<usize> {
+    match value.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(DataFusionError::Plan(format!(
+            "Configuration option '{}' expects a positive integer, got '{}'",
+            key, value
+        ))),
+    }
+}
+
+/// Holds per-execution properties and data (such as starting timestamps, etc).
+/// An instance of this struct is created each time a [`LogicalPlan`] is prepared for
+/// execution (optimized). If the same plan is optimized multiple times, a new
+/// `ExecutionProps` is created each time.
+#[derive(Clone)]
+pub struct ExecutionProps {
+    pub(crate) query_execution_start_time: DateTime<Utc>,
+}
+
+/// Execution context for registering data sources and executing queries
+#[derive(Clone)]
+pub struct ExecutionContextState {
+    /// Collection of catalogs containing schemas and ultimately TableProviders
+    pub catalog_list: Arc<dyn CatalogList>,
+    /// Scalar functions that are registered with the context
+    pub scalar_functions: HashMap<String, Arc<ScalarUDF>>,
+    /// Variable provider that are registered with the context
+    pub var_provider: HashMap<VarType, Arc<dyn VarProvider + Send + Sync>>,
+    /// Aggregate functions registered in the context
+    pub aggregate_functions: HashMap<String, Arc<AggregateUDF>>,
+    /// Window functions registered in the context
+    pub window_functions: HashMap<String, Arc<WindowUDF>>,
+    /// Table functions registered in the context, including the built-in ones
+    pub table_functions: HashMap<String, Arc<dyn TableFunction>>,
+    /// Context configuration
+    pub config: ExecutionConfig,
+    /// Execution properties
+    pub execution_props: ExecutionProps,
+    /// Object Store that are registered with the context
+    pub object_store_registry: Arc<ObjectStoreRegistry>,
+    /// Memory manager shared by the queries of the context
+    pub memory_manager: Option<Arc<MemoryManager>>,
+    /// Cache of the query results of the context
+    pub result_cache: Option<Arc<ResultCache>>,
+    /// Disk manager allocating the spill files of the queries of the context
+    pub disk_manager: Arc<DiskManager>,
+    /// Cancellation token of the queries planned by the context, replaced
+    /// when they are cancelled
+    pub cancellation: CancellationToken,
+    /// Executor running the CPU-bound work of the queries of the context
+    pub cpu_executor: CpuExecutor,
+    /// Handler receiving the progress of the queries of the context
+    pub progress_handler: Option<ProgressHandler>,
+}
+
+impl ExecutionProps {
+    /// Creates a new execution props
+    pub fn new() -> Self {
+        ExecutionProps {
+            query_execution_start_time: chrono::Utc::now(),
+        }
+    }
+
+    /// Marks the execution of query started timestamp
+    pub fn start_execution(&mut self) -> &Self {
+        self.query_execution_start_time = chrono::Utc::now();
+        &*self
+    }
+}
+
+impl ExecutionContextState {
+    /// Returns new ExecutionContextState
+    pub fn new() -> Self {
+        ExecutionContextState {
+            catalog_list: Arc::new(MemoryCatalogList::new()),
+            scalar_functions: HashMap::new(),
+            var_provider: HashMap::new(),
+            aggregate_functions: HashMap::new(),
+            window_functions: HashMap::new(),
+            table_functions: built_in_table_functions(),
+            config: ExecutionConfig::new(),
+            execution_props: ExecutionProps::new(),
+            object_store_registry: Arc::new(ObjectStoreRegistry::new()),
+            memory_manager: None,
+            result_cache: None,
+            disk_manager: Arc::new(DiskManager::default()),
+            cancellation: CancellationToken::new(),
+            cpu_executor: CpuExecutor::default(),
+            progress_handler: None,
+        }
+    }
+
+    /// Configuration of the context, to change its settings for the queries
+    /// planned afterwards
+    pub fn config_mut(&mut self) -> &mut ExecutionConfig {
+        &mut self.config
+    }
+
+    fn resolve_table_ref<'a>(
+        &'a self,
+        table_ref: impl Into<TableReference<'a>>,
+    ) -> ResolvedTableReference<'a> {
+        table_ref
+            .into()
+            .resolve(&self.config.default_catalog, &self.config.default_schema)
     }
 
     fn schema_for_ref<'a>(
@@ -1128,6 +2009,16 @@ impl ExecutionContextState {
                 ))
             })
     }
+
+    /// The scalar UDF named `name`, ignoring case
+    fn scalar_function(&self, name: &str) -> Option<Arc<ScalarUDF>> {
+        self.scalar_functions.get(name).cloned().or_else(|| {
+            self.scalar_functions
+                .iter()
+                .find(|(function_name, _)| function_name.eq_ignore_ascii_case(name))
+                .map(|(_, function)| function.clone())
+        })
+    }
 }
 
 impl ContextProvider for ExecutionContextState {
@@ -1138,12 +2029,29 @@ impl ContextProvider for ExecutionContextState {
     }
 
     fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>> {
-        self.scalar_functions.get(name).cloned()
+        self.scalar_function(name)
     }
 
     fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>> {
         self.aggregate_functions.get(name).cloned()
     }
+
+    fn get_window_meta(&self, name: &str) -> Option<Arc<WindowUDF>> {
+        self.window_functions.get(name).cloned()
+    }
+
+    fn get_table_function(&self, name: &str) -> Option<Arc<dyn TableFunction>> {
+        self.table_functions.get(name).cloned().or_else(|| {
+            self.table_functions
+                .iter()
+                .find(|(function_name, _)| function_name.eq_ignore_ascii_case(name))
+                .map(|(_, function)| function.clone())
+        })
+    }
+
+    fn get_config_options(&self) -> Vec<(String, String)> {
+        self.config.options()
+    }
 }
 
 impl FunctionRegistry for ExecutionContextState {
@@ -1152,9 +2060,7 @@ impl FunctionRegistry for ExecutionContextState {
     }
 
     fn udf(&self, name: &str) -> Result<Arc<ScalarUDF>> {
-        let result = self.scalar_functions.get(name);
-
-        result.cloned().ok_or_else(|| {
+        self.scalar_function(name).ok_or_else(|| {
             DataFusionError::Plan(format!(
                 "There is no UDF named \"{}\" in the registry",
                 name
@@ -1172,6 +2078,15 @@ impl FunctionRegistry for ExecutionContextState {
             ))
         })
     }
+
+    fn udwf(&self, name: &str) -> Result<Arc<WindowUDF>> {
+        self.window_functions.get(name).cloned().ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "There is no UDWF named \"{}\" in the registry",
+                name
+            ))
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1179,14 +2094,27 @@ mod tests {
     use super::*;
     use crate::logical_plan::plan::Projection;
     use crate::logical_plan::TableScan;
-    use crate::logical_plan::{binary_expr, lit, Operator};
-    use crate::physical_plan::functions::{make_scalar_function, Volatility};
-    use crate::physical_plan::{collect, collect_partitioned};
+    use crate::logical_plan::{binary_expr, lit, ExprRewriter, Operator};
+    use crate::physical_plan::displayable;
+    use crate::physical_plan::functions::{
+        make_scalar_function, Signature, TypeSignature, Volatility,
+    };
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::udf::UdfRowError;
+    use crate::physical_plan::udwf::PartitionEvaluator;
+    use crate::physical_plan::{
+        collect, collect_partitioned, Accumulator, ColumnarValue,
+    };
+    use crate::scalar::ScalarValue;
     use crate::test;
     use crate::variable::VarType;
     use crate::{
-        assert_batches_eq, assert_batches_sorted_eq,
-        logical_plan::{col, create_udf, sum, Expr},
+        assert_batches_eq, assert_batches_sorted_eq, assert_contains,
+        assert_not_contains,
+        logical_plan::{
+            col, create_async_udf, create_udaf_with_state_fields, create_udf,
+            create_udf_with_signature, create_udwf, sum, Expr,
+        },
     };
     use crate::{
         datasource::{empty::EmptyTable, MemTable, TableType},
@@ -1196,8 +2124,8 @@ mod tests {
     use arrow::array::{
         Array, ArrayRef, BinaryArray, DictionaryArray, Float32Array, Float64Array,
         Int16Array, Int32Array, Int64Array, Int8Array, LargeBinaryArray,
-        LargeStringArray, StringArray, TimestampNanosecondArray, UInt16Array,
-        UInt32Array, UInt64Array, UInt8Array,
+        LargeStringArray, PrimitiveArray, StringArray, TimestampNanosecondArray,
+        TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
     };
     use arrow::compute::add;
     use arrow::datatypes::*;
@@ -1360,7 +2288,8 @@ mod tests {
 
         let physical_plan = ctx.create_physical_plan(&logical_plan).await?;
 
-        let results = collect_partitioned(physical_plan).await?;
+        let task_ctx = ctx.task_ctx();
+        let results = collect_partitioned(physical_plan, task_ctx).await?;
 
         // note that the order of partitions is not deterministic
         let mut num_rows = 0;
@@ -1439,7 +2368,8 @@ mod tests {
         assert_eq!(1, physical_plan.schema().fields().len());
         assert_eq!("c2", physical_plan.schema().field(0).name().as_str());
 
-        let batches = collect(physical_plan).await?;
+        let task_ctx = ctx.task_ctx();
+        let batches = collect(physical_plan, task_ctx).await?;
         assert_eq!(40, batches.iter().map(|x| x.num_rows()).sum::<usize>());
 
         Ok(())
@@ -1515,7 +2445,8 @@ mod tests {
         assert_eq!(1, physical_plan.schema().fields().len());
         assert_eq!("b", physical_plan.schema().field(0).name().as_str());
 
-        let batches = collect(physical_plan).await?;
+        let task_ctx = ctx.task_ctx();
+        let batches = collect(physical_plan, task_ctx).await?;
         assert_eq!(1, batches.len());
         assert_eq!(1, batches[0].num_columns());
         assert_eq!(4, batches[0].num_rows());
@@ -2968,23 +3899,46 @@ mod tests {
             myfunc,
         ));
 
-        // doesn't work as it was registered with non lowercase
-        let err = plan_and_collect(&mut ctx, "SELECT MY_FUNC(i) FROM t")
-            .await
-            .unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            "Error during planning: Invalid function \'my_func\'"
-        );
+        let expected = vec![
+            "+--------------+",
+            "| MY_FUNC(t.i) |",
+            "+--------------+",
+            "| 1            |",
+            "+--------------+",
+        ];
 
-        // Can call it if you put quotes
+        // functions are resolved regardless of the case of their name
+        let result = plan_and_collect(&mut ctx, "SELECT MY_FUNC(i) FROM t").await?;
+        assert_batches_eq!(expected, &result);
         let result = plan_and_collect(&mut ctx, "SELECT \"MY_FUNC\"(i) FROM t").await?;
+        assert_batches_eq!(expected, &result);
+        let result = plan_and_collect(&mut ctx, "SELECT \"My_Func\"(i) FROM t").await?;
+        assert_batches_eq!(expected, &result);
 
+        // registering a function of the same name replaces it
+        let plus_one = |args: &[ArrayRef]| {
+            let array = args[0].as_any().downcast_ref::<Int32Array>().unwrap();
+            Ok(Arc::new(
+                array
+                    .iter()
+                    .map(|v| v.map(|v| v + 1))
+                    .collect::<Int32Array>(),
+            ) as ArrayRef)
+        };
+        ctx.register_udf(create_udf(
+            "my_func",
+            vec![DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Immutable,
+            make_scalar_function(plus_one),
+        ));
+        assert_eq!(ctx.udfs(), HashSet::from(["my_func".to_string()]));
+        let result = plan_and_collect(&mut ctx, "SELECT MY_FUNC(i) FROM t").await?;
         let expected = vec![
             "+--------------+",
-            "| MY_FUNC(t.i) |",
+            "| my_func(t.i) |",
             "+--------------+",
-            "| 1            |",
+            "| 2            |",
             "+--------------+",
         ];
         assert_batches_eq!(expected, &result);
@@ -3112,37 +4066,257 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn write_parquet_results() -> Result<()> {
-        // create partitioned input file and context
+    async fn write_csv_with_options_round_trip() -> Result<()> {
         let tmp_dir = TempDir::new()?;
-        let mut ctx = create_ctx(&tmp_dir, 4).await?;
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(StringArray::from(vec![Some("x|y"), Some("z"), None])),
+            ],
+        )?;
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch]],
+            schema.clone(),
+            None,
+        )?);
 
-        // execute a simple query and write the results to CSV
+        let ctx = ExecutionContext::new();
         let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
-        write_parquet(&mut ctx, "SELECT c1, c2 FROM test", &out_dir, None).await?;
-
-        // create a new context and verify that the results were saved to a partitioned csv file
-        let mut ctx = ExecutionContext::new();
-
-        // register each partition as well as the top level dir
-        ctx.register_parquet("part0", &format!("{}/part-0.parquet", out_dir))
-            .await?;
-        ctx.register_parquet("part1", &format!("{}/part-1.parquet", out_dir))
-            .await?;
-        ctx.register_parquet("part2", &format!("{}/part-2.parquet", out_dir))
-            .await?;
-        ctx.register_parquet("part3", &format!("{}/part-3.parquet", out_dir))
+        let options = CsvWriteOptions::new().delimiter(b'|');
+        let files = ctx
+            .write_csv_with_options(plan.clone(), &out_dir, &options)
             .await?;
-        ctx.register_parquet("allparts", &out_dir).await?;
+        assert_eq!(
+            files,
+            vec![
+                format!("{}/part-0.csv", out_dir),
+                format!("{}/part-1.csv", out_dir)
+            ]
+        );
 
-        let part0 = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM part0").await?;
-        let allparts = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM allparts").await?;
+        // the target directory is only replaced if asked to
+        assert!(ctx
+            .write_csv_with_options(plan.clone(), &out_dir, &options)
+            .await
+            .is_err());
+        ctx.write_csv_with_options(plan, &out_dir, &options.overwrite(true))
+            .await?;
 
-        let allparts_count: usize = allparts.iter().map(|batch| batch.num_rows()).sum();
+        let mut ctx = ExecutionContext::new();
+        ctx.register_csv(
+            "t",
+            &out_dir,
+            CsvReadOptions::new().delimiter(b'|').schema(&schema),
+        )
+        .await?;
 
-        assert_eq!(part0[0].schema(), allparts[0].schema());
+        let results =
+            plan_and_collect(&mut ctx, "SELECT COUNT(*), COUNT(a) FROM t").await?;
+        let expected = vec![
+            "+-----------------+------------+",
+            "| COUNT(UInt8(1)) | COUNT(t.a) |",
+            "+-----------------+------------+",
+            "| 6               | 4          |",
+            "+-----------------+------------+",
+        ];
+        assert_batches_eq!(expected, &results);
 
-        assert_eq!(allparts_count, 40);
+        let results =
+            plan_and_collect(&mut ctx, "SELECT a, b FROM t WHERE a IS NOT NULL").await?;
+        let expected = vec![
+            "+---+-----+",
+            "| a | b   |",
+            "+---+-----+",
+            "| 1 | x|y |",
+            "| 1 | x|y |",
+            "| 3 |     |",
+            "| 3 |     |",
+            "+---+-----+",
+        ];
+        assert_batches_sorted_eq!(expected, &results);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_csv_null_value_and_formats() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("ts", DataType::Timestamp(TimeUnit::Second, None), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None])),
+                Arc::new(TimestampSecondArray::from_opt_vec(
+                    vec![None, Some(86_400)],
+                    None,
+                )),
+            ],
+        )?;
+        let plan = Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None)?);
+
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let options = CsvWriteOptions::new()
+            .delimiter(b';')
+            .null_value("NULL")
+            .timestamp_format("%Y/%m/%d");
+        let files = ExecutionContext::new()
+            .write_csv_with_options(plan, &out_dir, &options)
+            .await?;
+
+        let content = fs::read_to_string(&files[0])?;
+        assert_eq!(content, "a;ts\n1;NULL\nNULL;1970/01/02\n");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_ipc_results() -> Result<()> {
+        // create partitioned input file and context
+        let tmp_dir = TempDir::new()?;
+        let mut ctx = create_ctx(&tmp_dir, 4).await?;
+
+        // execute a simple query and write the results to IPC files
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let logical_plan = ctx.create_logical_plan("SELECT c1, c2 FROM test")?;
+        let logical_plan = ctx.optimize(&logical_plan)?;
+        let physical_plan = ctx.create_physical_plan(&logical_plan).await?;
+        let files = ctx.write_ipc(physical_plan, &out_dir, None).await?;
+        assert_eq!(files.len(), 4);
+
+        // create a new context and verify that the results can be read back,
+        // both through the API and through SQL
+        let mut ctx = ExecutionContext::new();
+        ctx.register_ipc("allparts", &out_dir, IpcReadOptions::default())
+            .await?;
+        ctx.sql(&format!(
+            "CREATE EXTERNAL TABLE part0 STORED AS ARROW LOCATION '{}'",
+            files[0]
+        ))
+        .await?;
+
+        let part0 = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM part0").await?;
+        let allparts = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM allparts").await?;
+
+        let allparts_count: usize = allparts.iter().map(|batch| batch.num_rows()).sum();
+
+        assert_eq!(part0[0].schema(), allparts[0].schema());
+
+        assert_eq!(allparts_count, 40);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_parquet_results() -> Result<()> {
+        // create partitioned input file and context
+        let tmp_dir = TempDir::new()?;
+        let mut ctx = create_ctx(&tmp_dir, 4).await?;
+
+        // execute a simple query and write the results to CSV
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        write_parquet(&mut ctx, "SELECT c1, c2 FROM test", &out_dir, None).await?;
+
+        // create a new context and verify that the results were saved to a partitioned csv file
+        let mut ctx = ExecutionContext::new();
+
+        // register each partition as well as the top level dir
+        ctx.register_parquet("part0", &format!("{}/part-0.parquet", out_dir))
+            .await?;
+        ctx.register_parquet("part1", &format!("{}/part-1.parquet", out_dir))
+            .await?;
+        ctx.register_parquet("part2", &format!("{}/part-2.parquet", out_dir))
+            .await?;
+        ctx.register_parquet("part3", &format!("{}/part-3.parquet", out_dir))
+            .await?;
+        ctx.register_parquet("allparts", &out_dir).await?;
+
+        let part0 = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM part0").await?;
+        let allparts = plan_and_collect(&mut ctx, "SELECT c1, c2 FROM allparts").await?;
+
+        let allparts_count: usize = allparts.iter().map(|batch| batch.num_rows()).sum();
+
+        assert_eq!(part0[0].schema(), allparts[0].schema());
+
+        assert_eq!(allparts_count, 40);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn write_parquet_keeps_metadata() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])),
+            ],
+        )?;
+        let metadata: HashMap<String, String> =
+            vec![("origin".to_owned(), "sensor".to_owned())]
+                .into_iter()
+                .collect();
+        let unit: BTreeMap<String, String> = vec![("unit".to_owned(), "m".to_owned())]
+            .into_iter()
+            .collect();
+        let field_metadata = vec![("a".to_owned(), unit.clone())].into_iter().collect();
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_memtable_with_metadata(
+            "t",
+            schema,
+            vec![vec![batch]],
+            metadata.clone(),
+            field_metadata,
+        )?;
+
+        let df = ctx.sql("SELECT a AS d, b FROM t WHERE a > 1").await?;
+        let df_schema = df.schema();
+        assert_eq!(&metadata, df_schema.metadata());
+        let d = df_schema.field_with_unqualified_name("d")?.field();
+        assert_eq!(&Some(unit.clone()), d.metadata());
+        let b = df_schema.field_with_unqualified_name("b")?.field();
+        assert_eq!(&None, b.metadata());
+
+        let plan = ctx.optimize(&df.to_logical_plan())?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        assert_eq!(&metadata, plan.schema().metadata());
+        assert_eq!(&Some(unit.clone()), plan.schema().field(0).metadata());
+        test::assert_plan_roundtrip(plan.clone());
+
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        ctx.write_parquet(plan, &out_dir, None).await?;
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_parquet("out", &out_dir).await?;
+        let df = ctx.sql("SELECT d, b FROM out").await?;
+        let df_schema = df.schema();
+        assert_eq!(&metadata, df_schema.metadata());
+        let d = df_schema.field_with_unqualified_name("d")?.field();
+        assert_eq!(&Some(unit), d.metadata());
+
+        let results = df.collect().await?;
+        let expected = vec![
+            "+---+---+",
+            "| d | b |",
+            "+---+---+",
+            "| 2 | y |",
+            "| 3 | z |",
+            "+---+---+",
+        ];
+        assert_batches_sorted_eq!(expected, &results);
 
         Ok(())
     }
@@ -3289,7 +4463,8 @@ mod tests {
 
         let plan = ctx.optimize(&plan)?;
         let plan = ctx.create_physical_plan(&plan).await?;
-        let result = collect(plan).await?;
+        let task_ctx = ctx.task_ctx();
+        let result = collect(plan, task_ctx).await?;
 
         let expected = vec![
             "+-----+-----+-----------------+",
@@ -3332,711 +4507,2417 @@ mod tests {
         Ok(())
     }
 
-    #[tokio::test]
-    async fn simple_avg() -> Result<()> {
-        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
-
-        let batch1 = RecordBatch::try_new(
-            Arc::new(schema.clone()),
-            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
-        )?;
-        let batch2 = RecordBatch::try_new(
-            Arc::new(schema.clone()),
-            vec![Arc::new(Int32Array::from(vec![4, 5]))],
-        )?;
-
-        let mut ctx = ExecutionContext::new();
-
-        let provider =
-            MemTable::try_new(Arc::new(schema), vec![vec![batch1], vec![batch2]])?;
-        ctx.register_table("t", Arc::new(provider))?;
-
-        let result = plan_and_collect(&mut ctx, "SELECT AVG(a) FROM t").await?;
-
-        let batch = &result[0];
-        assert_eq!(1, batch.num_columns());
-        assert_eq!(1, batch.num_rows());
-
-        let values = batch
-            .column(0)
-            .as_any()
-            .downcast_ref::<Float64Array>()
-            .expect("failed to cast version");
-        assert_eq!(values.len(), 1);
-        // avg(1,2,3,4,5) = 3.0
-        assert_eq!(values.value(0), 3.0_f64);
-        Ok(())
+    /// `clamp(x, lo, hi)` over any primitive type
+    fn clamp_values<T: ArrowPrimitiveType>(arrays: &[ArrayRef]) -> PrimitiveArray<T>
+    where
+        T::Native: PartialOrd,
+    {
+        let array = |i: usize| {
+            arrays[i]
+                .as_any()
+                .downcast_ref::<PrimitiveArray<T>>()
+                .expect("cast failed")
+        };
+        array(0)
+            .iter()
+            .zip(array(1).iter())
+            .zip(array(2).iter())
+            .map(|((x, lo), hi)| match (x, lo, hi) {
+                (Some(x), Some(lo), Some(_)) if x < lo => Some(lo),
+                (Some(x), Some(_), Some(hi)) if x > hi => Some(hi),
+                (Some(x), Some(_), Some(_)) => Some(x),
+                _ => None,
+            })
+            .collect()
     }
 
-    /// tests the creation, registration and usage of a UDAF
     #[tokio::test]
-    async fn simple_udaf() -> Result<()> {
-        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
-
-        let batch1 = RecordBatch::try_new(
-            Arc::new(schema.clone()),
-            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
-        )?;
-        let batch2 = RecordBatch::try_new(
-            Arc::new(schema.clone()),
-            vec![Arc::new(Int32Array::from(vec![4, 5]))],
+    async fn scalar_udf_with_signature() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("i", DataType::Int32, false),
+            Field::new("f", DataType::Float64, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 5, 10])),
+                Arc::new(Float64Array::from(vec![0.5, 2.5, 7.5])),
+            ],
         )?;
 
         let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
 
-        let provider =
-            MemTable::try_new(Arc::new(schema), vec![vec![batch1], vec![batch2]])?;
-        ctx.register_table("t", Arc::new(provider))?;
-
-        // define a udaf, using a DataFusion's accumulator
-        let my_avg = create_udaf(
-            "my_avg",
-            DataType::Float64,
-            Arc::new(DataType::Float64),
-            Volatility::Immutable,
-            Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
-            Arc::new(vec![DataType::UInt64, DataType::Float64]),
-        );
-
-        ctx.register_udaf(my_avg);
-
-        let result = plan_and_collect(&mut ctx, "SELECT MY_AVG(a) FROM t").await?;
+        let clamp = |arg_types: &[DataType], args: &[ColumnarValue]| {
+            let len = args
+                .iter()
+                .find_map(|arg| match arg {
+                    ColumnarValue::Array(array) => Some(array.len()),
+                    ColumnarValue::Scalar(_) => None,
+                })
+                .unwrap_or(1);
+            let arrays = args
+                .iter()
+                .map(|arg| arg.clone().into_array(len))
+                .collect::<Vec<_>>();
+            let result: ArrayRef = match &arg_types[0] {
+                DataType::Int64 => Arc::new(clamp_values::<Int64Type>(&arrays)),
+                DataType::Float64 => Arc::new(clamp_values::<Float64Type>(&arrays)),
+                other => {
+                    return Err(DataFusionError::Internal(format!(
+                        "clamp does not support {:?}",
+                        other
+                    )))
+                }
+            };
+            Ok(ColumnarValue::Array(result))
+        };
+        ctx.register_udf(create_udf_with_signature(
+            "clamp",
+            Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Int64; 3]),
+                    TypeSignature::Exact(vec![DataType::Float64; 3]),
+                ],
+                Volatility::Immutable,
+            ),
+            Arc::new(|arg_types| Ok(Arc::new(arg_types[0].clone()))),
+            Arc::new(clamp),
+        ));
 
+        let result = plan_and_collect(
+            &mut ctx,
+            "SELECT clamp(i, 2, 8) AS ci, CLAMP(i, 1.5, 8) AS cf, clamp(f, 1, 5) AS ff \
+             FROM t",
+        )
+        .await?;
         let expected = vec![
-            "+-------------+",
-            "| my_avg(t.a) |",
-            "+-------------+",
-            "| 3           |",
-            "+-------------+",
+            "+----+-----+-----+",
+            "| ci | cf  | ff  |",
+            "+----+-----+-----+",
+            "| 2  | 1.5 | 1   |",
+            "| 5  | 5   | 2.5 |",
+            "| 8  | 8   | 5   |",
+            "+----+-----+-----+",
+        ];
+        assert_batches_eq!(expected, &result);
+        assert_eq!(result[0].schema().field(0).data_type(), &DataType::Int64);
+        assert_eq!(result[0].schema().field(1).data_type(), &DataType::Float64);
+
+        let clamp = ctx.udf("clamp")?;
+        let result = ctx
+            .table("t")?
+            .select(vec![clamp.call(vec![col("i"), lit(2), lit(8)]).alias("ci")])?
+            .collect()
+            .await?;
+        let expected = vec![
+            "+----+", "| ci |", "+----+", "| 2  |", "| 5  |", "| 8  |", "+----+",
         ];
         assert_batches_eq!(expected, &result);
 
+        let err = plan_and_collect(&mut ctx, "SELECT clamp(i, 'a', 8) FROM t")
+            .await
+            .unwrap_err();
+        assert_contains!(err.to_string(), "Coercion from");
+
         Ok(())
     }
 
-    #[tokio::test]
-    async fn custom_query_planner() -> Result<()> {
-        let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_query_planner(Arc::new(MyQueryPlanner {})),
-        );
-
-        let df = ctx.sql("SELECT 1").await?;
-        df.collect().await.expect_err("query not supported");
-        Ok(())
+    /// Square root of a Float64 column, which errors, or panics if `panics`, on the
+    /// negative values
+    fn checked_sqrt(name: &str, panics: bool) -> ScalarUDF {
+        create_udf(
+            name,
+            vec![DataType::Float64],
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            make_scalar_function(move |args: &[ArrayRef]| {
+                let input = args[0]
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .expect("cast failed");
+                let array = input
+                    .iter()
+                    .map(|v| match v {
+                        Some(v) if v < 0.0 && panics => panic!("negative input {}", v),
+                        Some(v) if v < 0.0 => Err(DataFusionError::Execution(format!(
+                            "negative input {}",
+                            v
+                        ))),
+                        v => Ok(v.map(f64::sqrt)),
+                    })
+                    .collect::<Result<Float64Array>>()?;
+                Ok(Arc::new(array) as ArrayRef)
+            }),
+        )
     }
 
     #[tokio::test]
-    async fn information_schema_tables_not_exist_by_default() {
+    async fn scalar_udf_row_errors() -> Result<()> {
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("x", DataType::Float64, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Float64Array::from(vec![
+                Some(4.0),
+                Some(-1.0),
+                None,
+                Some(9.0),
+                Some(-4.0),
+            ]))],
+        )?;
         let mut ctx = ExecutionContext::new();
-
-        let err = plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+        let sqrt = checked_sqrt("checked_sqrt", false);
+        ctx.register_udf(sqrt.clone());
+        ctx.register_udf(checked_sqrt("panicking_sqrt", true));
+        let lenient_sqrt =
+            checked_sqrt("lenient_sqrt", true).with_error_mode(UdfErrorMode::Permissive);
+        ctx.register_udf(lenient_sqrt.clone());
+
+        // in strict mode, the error names the UDF and the arguments of the first row
+        // it failed on
+        let err = plan_and_collect(&mut ctx, "SELECT checked_sqrt(x) FROM t")
             .await
             .unwrap_err();
-        assert_eq!(
+        assert_contains!(
             err.to_string(),
-            "Error during planning: Table or CTE with name 'information_schema.tables' not found"
+            "UDF checked_sqrt failed on row 1 of a batch with arguments (Float64(-1)): \
+             negative input -1"
         );
-    }
-
-    #[tokio::test]
-    async fn information_schema_tables_no_tables() {
-        let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
+        let err = plan_and_collect(&mut ctx, "SELECT panicking_sqrt(x) FROM t")
+            .await
+            .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "UDF panicking_sqrt failed on row 1 of a batch with arguments (Float64(-1)): \
+             panicked: negative input -1"
         );
+        assert_eq!(0, sqrt.row_errors.count());
 
-        let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
-                .await
-                .unwrap();
-
+        // in permissive mode, the rows the UDF failed on are nulls, and are recorded
         let expected = vec![
-            "+---------------+--------------------+------------+------------+",
-            "| table_catalog | table_schema       | table_name | table_type |",
-            "+---------------+--------------------+------------+------------+",
-            "| datafusion    | information_schema | columns    | VIEW       |",
-            "| datafusion    | information_schema | tables     | VIEW       |",
-            "+---------------+--------------------+------------+------------+",
+            "+----+---+",
+            "| x  | s |",
+            "+----+---+",
+            "| 4  | 2 |",
+            "| -1 |   |",
+            "|    |   |",
+            "| 9  | 3 |",
+            "| -4 |   |",
+            "+----+---+",
         ];
-        assert_batches_sorted_eq!(expected, &result);
-    }
-
-    #[tokio::test]
-    async fn information_schema_tables_tables_default_catalog() {
+        let result =
+            plan_and_collect(&mut ctx, "SELECT x, lenient_sqrt(x) AS s FROM t").await?;
+        assert_batches_eq!(expected, &result);
+        assert_eq!(2, lenient_sqrt.row_errors.count());
+        assert_eq!(
+            "panicked: negative input -4",
+            lenient_sqrt.row_errors.samples()[1].message
+        );
+
+        plan_and_collect(
+            &mut ctx,
+            "SET datafusion.execution.udf_error_mode = 'permissive'",
+        )
+        .await?;
+        let result =
+            plan_and_collect(&mut ctx, "SELECT x, checked_sqrt(x) AS s FROM t").await?;
+        assert_batches_eq!(expected, &result);
+        assert_eq!(2, sqrt.row_errors.count());
+        assert_eq!(
+            vec![
+                UdfRowError {
+                    args: vec![ScalarValue::Float64(Some(-1.0))],
+                    message: "negative input -1".to_string(),
+                },
+                UdfRowError {
+                    args: vec![ScalarValue::Float64(Some(-4.0))],
+                    message: "negative input -4".to_string(),
+                },
+            ],
+            sqrt.row_errors.samples()
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn async_udf_pipelines_batches() -> Result<()> {
+        use crate::physical_plan::LambdaExecPlan;
+        use futures::FutureExt;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+        let batches = (0..8)
+            .map(|i| {
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from(vec![2 * i, 2 * i + 1]))],
+                )
+            })
+            .collect::<arrow::error::Result<Vec<_>>>()?;
+        let sql =
+            "SELECT i, delayed_plus_one(i) AS j, delayed_plus_one(i) * 10 AS k FROM t";
+        let check = |result: &[RecordBatch]| {
+            let mut rows = 0;
+            for batch in result {
+                let column = |i: usize| {
+                    batch
+                        .column(i)
+                        .as_any()
+                        .downcast_ref::<Int32Array>()
+                        .unwrap()
+                        .values()
+                        .to_vec()
+                };
+                for ((i, j), k) in column(0).into_iter().zip(column(1)).zip(column(2)) {
+                    assert_eq!(rows, i);
+                    assert_eq!(i + 1, j);
+                    assert_eq!(10 * j, k);
+                    rows += 1;
+                }
+            }
+            assert_eq!(16, rows);
+        };
+
+        for concurrency in [8, 1] {
+            let mut ctx = ExecutionContext::with_config(
+                ExecutionConfig::new()
+                    .with_target_partitions(1)
+                    .with_async_udf_concurrency(concurrency),
+            );
+            ctx.register_table(
+                "t",
+                Arc::new(MemTable::try_new(schema.clone(), vec![batches.clone()])?),
+            )?;
+
+            // adds one after a delay, recording the number of calls awaited at once
+            let in_flight = Arc::new(AtomicUsize::new(0));
+            let max_in_flight = Arc::new(AtomicUsize::new(0));
+            let (current, max) = (in_flight.clone(), max_in_flight.clone());
+            ctx.register_udf(create_async_udf(
+                "delayed_plus_one",
+                vec![DataType::Int32],
+                Arc::new(DataType::Int32),
+                Volatility::Stable,
+                Arc::new(move |args: Vec<ArrayRef>| {
+                    let (current, max) = (current.clone(), max.clone());
+                    async move {
+                        let n = current.fetch_add(1, Ordering::SeqCst) + 1;
+                        max.fetch_max(n, Ordering::SeqCst);
+                        tokio::task::spawn_blocking(|| {
+                            thread::sleep(Duration::from_millis(20))
+                        })
+                        .await
+                        .unwrap();
+                        current.fetch_sub(1, Ordering::SeqCst);
+                        let input =
+                            args[0].as_any().downcast_ref::<Int32Array>().unwrap();
+                        let array: Int32Array =
+                            input.iter().map(|v| v.map(|v| v + 1)).collect();
+                        Ok(Arc::new(array) as ArrayRef)
+                    }
+                    .boxed()
+                }),
+            ));
+
+            let result = plan_and_collect(&mut ctx, sql).await?;
+            check(&result);
+            if concurrency == 1 {
+                assert_eq!(1, max_in_flight.load(Ordering::SeqCst));
+            } else {
+                // the calls on several batches were awaited at once
+                assert!(max_in_flight.load(Ordering::SeqCst) > 1);
+            }
+            assert_eq!(0, in_flight.load(Ordering::SeqCst));
+        }
+
+        // the async UDFs are serialized by name, and resolved from the registry
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        ctx.register_table(
+            "t",
+            Arc::new(MemTable::try_new(schema.clone(), vec![batches.clone()])?),
+        )?;
+        ctx.register_udf(create_async_udf(
+            "delayed_plus_one",
+            vec![DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Stable,
+            Arc::new(|args: Vec<ArrayRef>| {
+                let input = args[0].as_any().downcast_ref::<Int32Array>().unwrap();
+                let array: Int32Array = input.iter().map(|v| v.map(|v| v + 1)).collect();
+                futures::future::ready(Ok(Arc::new(array) as ArrayRef)).boxed()
+            }),
+        ));
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let json = crate::physical_plan::serde::to_json(&plan)?;
+        let mut plan = crate::physical_plan::from_json_with_registry(
+            &json,
+            &ctx.function_registry(),
+        )?;
+        Arc::get_mut(&mut plan)
+            .unwrap()
+            .feed_batches(vec![batches])?;
+        check(&collect(plan, ctx.task_ctx()).await?);
+
+        // the other operators can't await them
+        let err =
+            plan_and_collect(&mut ctx, "SELECT i FROM t WHERE delayed_plus_one(i) > 3")
+                .await
+                .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "Async UDF delayed_plus_one can only be called in the expressions of a projection"
+        );
+        Ok(())
+    }
+
+    /// Average of the first argument weighted by the second one, which slides along
+    /// window frames when `retractable`
+    #[derive(Debug)]
+    struct WeightedAvg {
+        weighted_sum: f64,
+        weight: f64,
+        retractable: bool,
+    }
+
+    impl WeightedAvg {
+        fn add_batch(&mut self, values: &[ArrayRef], sign: f64) {
+            let array = |i: usize| {
+                values[i]
+                    .as_any()
+                    .downcast_ref::<Float64Array>()
+                    .expect("cast failed")
+            };
+            for (x, w) in array(0).iter().zip(array(1).iter()) {
+                if let (Some(x), Some(w)) = (x, w) {
+                    self.weighted_sum += sign * x * w;
+                    self.weight += sign * w;
+                }
+            }
+        }
+    }
+
+    impl Accumulator for WeightedAvg {
+        fn state(&self) -> Result<Vec<ScalarValue>> {
+            Ok(vec![
+                ScalarValue::Float64(Some(self.weighted_sum)),
+                ScalarValue::Float64(Some(self.weight)),
+            ])
+        }
+
+        fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+            if let (ScalarValue::Float64(Some(x)), ScalarValue::Float64(Some(w))) =
+                (&values[0], &values[1])
+            {
+                self.weighted_sum += x * w;
+                self.weight += w;
+            }
+            Ok(())
+        }
+
+        fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+            self.add_batch(values, 1.0);
+            Ok(())
+        }
+
+        fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+            if let (
+                ScalarValue::Float64(Some(weighted_sum)),
+                ScalarValue::Float64(Some(weight)),
+            ) = (&states[0], &states[1])
+            {
+                self.weighted_sum += weighted_sum;
+                self.weight += weight;
+            }
+            Ok(())
+        }
+
+        fn evaluate(&self) -> Result<ScalarValue> {
+            Ok(ScalarValue::Float64(if self.weight == 0.0 {
+                None
+            } else {
+                Some(self.weighted_sum / self.weight)
+            }))
+        }
+
+        fn retract_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+            self.add_batch(values, -1.0);
+            Ok(())
+        }
+
+        fn supports_retract_batch(&self) -> bool {
+            self.retractable
+        }
+    }
+
+    fn weighted_avg(name: &str, retractable: bool) -> AggregateUDF {
+        create_udaf_with_state_fields(
+            name,
+            Signature::exact(
+                vec![DataType::Float64, DataType::Float64],
+                Volatility::Immutable,
+            ),
+            Arc::new(DataType::Float64),
+            Arc::new(move || {
+                Ok(Box::new(WeightedAvg {
+                    weighted_sum: 0.0,
+                    weight: 0.0,
+                    retractable,
+                }))
+            }),
+            vec![
+                Field::new("weighted_sum", DataType::Float64, false),
+                Field::new("weight", DataType::Float64, false),
+            ],
+        )
+    }
+
+    /// Whether a node of the plan has a field named `name`
+    fn has_field(plan: &Arc<dyn ExecutionPlan>, name: &str) -> bool {
+        plan.schema().field_with_name(name).is_ok()
+            || plan.children().iter().any(|child| has_field(child, name))
+    }
+
+    #[tokio::test]
+    async fn udaf_over_sliding_window_frames() -> Result<()> {
+        // rows `id` in partitions `id % 3`, all sums of `x * w` and of `w` are exact
+        let ids = (0..20).collect::<Vec<i64>>();
+        let x = |id: i64| (id * 7 % 11) as f64;
+        let w = |id: i64| (id % 4 + 1) as f64;
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("p", DataType::Int64, false),
+            Field::new("x", DataType::Float64, false),
+            Field::new("w", DataType::Float64, false),
+        ]));
+        let partitions = ids
+            .chunks(10)
+            .map(|ids| {
+                Ok(vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(ids.to_vec())),
+                        Arc::new(Int64Array::from_iter_values(
+                            ids.iter().map(|id| id % 3),
+                        )),
+                        Arc::new(Float64Array::from_iter_values(
+                            ids.iter().map(|id| x(*id)),
+                        )),
+                        Arc::new(Float64Array::from_iter_values(
+                            ids.iter().map(|id| w(*id)),
+                        )),
+                    ],
+                )?])
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, partitions)?))?;
+        ctx.register_udaf(weighted_avg("wavg", true));
+        ctx.register_udaf(weighted_avg("wavg_recompute", false));
+
+        // weighted average of the rows from `start` to `end` (inclusive, unbounded when
+        // None) relative to every row within its partition
+        let brute_force = |start: Option<i64>, end: Option<i64>| {
+            ids.iter()
+                .map(|id| {
+                    let rows =
+                        ids.iter().filter(|r| *r % 3 == id % 3).collect::<Vec<_>>();
+                    let row = rows.iter().position(|r| *r == id).unwrap() as i64;
+                    let first = start.map_or(0, |start| row + start).max(0);
+                    let last = end
+                        .map_or(rows.len() as i64 - 1, |end| row + end)
+                        .min(rows.len() as i64 - 1);
+                    let (weighted_sum, weight) = (first..=last)
+                        .map(|i| *rows[i as usize])
+                        .fold((0.0, 0.0), |(s, t), r| (s + x(r) * w(r), t + w(r)));
+                    (weight != 0.0).then(|| weighted_sum / weight)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let frames = vec![
+            ("2 PRECEDING AND 1 FOLLOWING", Some(-2), Some(1)),
+            ("1 FOLLOWING AND 3 FOLLOWING", Some(1), Some(3)),
+            ("3 PRECEDING AND 1 PRECEDING", Some(-3), Some(-1)),
+            ("UNBOUNDED PRECEDING AND CURRENT ROW", None, Some(0)),
+            ("CURRENT ROW AND UNBOUNDED FOLLOWING", Some(0), None),
+        ];
+        for (frame, start, end) in frames {
+            let sql = format!(
+                "SELECT \
+                 wavg(x, w) OVER (PARTITION BY p ORDER BY id ROWS BETWEEN {0}), \
+                 wavg_recompute(x, w) OVER (PARTITION BY p ORDER BY id ROWS BETWEEN {0}) \
+                 FROM t ORDER BY id",
+                frame
+            );
+            let result = plan_and_collect(&mut ctx, &sql).await?;
+            let column = |i: usize| {
+                result
+                    .iter()
+                    .flat_map(|batch| {
+                        let array = batch
+                            .column(i)
+                            .as_any()
+                            .downcast_ref::<Float64Array>()
+                            .expect("cast failed");
+                        array.iter().collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let expected = brute_force(start, end);
+            assert_eq!(expected, column(0), "sliding over ROWS BETWEEN {}", frame);
+            assert_eq!(expected, column(1), "recomputing ROWS BETWEEN {}", frame);
+        }
+
+        // the partial states of both partitions of `t` are merged by `p`
+        let sql = "SELECT p, wavg(x, w) FROM t GROUP BY p ORDER BY p";
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        assert!(has_field(&plan, "wavg(t.x,t.w)[weighted_sum]"));
+        assert!(has_field(&plan, "wavg(t.x,t.w)[weight]"));
+
+        let result = collect(plan, ctx.task_ctx()).await?;
+        let actual = result[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .expect("cast failed")
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(brute_force(None, None)[..3], actual);
+
+        Ok(())
+    }
+
+    /// Means of the rows of a partition, up to every row or over its frame
+    struct RunningMean {}
+
+    impl PartitionEvaluator for RunningMean {
+        fn evaluate_partition(
+            &mut self,
+            values: &[ArrayRef],
+            num_rows: usize,
+        ) -> Result<ArrayRef> {
+            let x = values[0].as_any().downcast_ref::<Float64Array>().unwrap();
+            let mut sum = 0.0;
+            let means = (0..num_rows)
+                .map(|row| {
+                    sum += x.value(row);
+                    sum / (row + 1) as f64
+                })
+                .collect::<Float64Array>();
+            Ok(Arc::new(means))
+        }
+
+        fn evaluate_frame(
+            &mut self,
+            values: &[ArrayRef],
+            frame: std::ops::Range<usize>,
+        ) -> Result<ScalarValue> {
+            let x = values[0].as_any().downcast_ref::<Float64Array>().unwrap();
+            let len = frame.end - frame.start;
+            let sum = frame.map(|row| x.value(row)).sum::<f64>();
+            Ok(ScalarValue::Float64((len != 0).then(|| sum / len as f64)))
+        }
+    }
+
+    #[tokio::test]
+    async fn udwf_over_partitions_and_frames() -> Result<()> {
+        // rows `id` in partitions `id % 3`, spread over both partitions of `t`
+        let ids = (0..20).collect::<Vec<i64>>();
+        let x = |id: i64| (id * 7 % 11) as f64;
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("p", DataType::Int64, false),
+            Field::new("x", DataType::Float64, false),
+        ]));
+        let partitions = ids
+            .chunks(10)
+            .map(|ids| {
+                Ok(vec![RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int64Array::from(ids.to_vec())),
+                        Arc::new(Int64Array::from_iter_values(
+                            ids.iter().map(|id| id % 3),
+                        )),
+                        Arc::new(Float64Array::from_iter_values(
+                            ids.iter().map(|id| x(*id)),
+                        )),
+                    ],
+                )?])
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", Arc::new(MemTable::try_new(schema, partitions)?))?;
+        ctx.register_udwf(create_udwf(
+            "running_mean",
+            vec![DataType::Float64],
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            Arc::new(|| Ok(Box::new(RunningMean {}))),
+        ));
+
+        // mean of the rows from `start` to `end` relative to every row within its
+        // partition
+        let brute_force = |start: i64, end: i64| {
+            ids.iter()
+                .map(|id| {
+                    let rows =
+                        ids.iter().filter(|r| *r % 3 == id % 3).collect::<Vec<_>>();
+                    let row = rows.iter().position(|r| *r == id).unwrap() as i64;
+                    let first = (row + start).max(0);
+                    let last = (row + end).min(rows.len() as i64 - 1);
+                    let sum = (first..=last).map(|i| x(*rows[i as usize])).sum::<f64>();
+                    Some(sum / (last - first + 1) as f64)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let sql = "SELECT \
+                   running_mean(x) OVER (PARTITION BY p ORDER BY id), \
+                   running_mean(x) OVER (PARTITION BY p ORDER BY id \
+                   ROWS BETWEEN 1 PRECEDING AND 1 FOLLOWING) \
+                   FROM t ORDER BY id";
+        let result = plan_and_collect(&mut ctx, sql).await?;
+        let column = |i: usize| {
+            result
+                .iter()
+                .flat_map(|batch| {
+                    let array = batch
+                        .column(i)
+                        .as_any()
+                        .downcast_ref::<Float64Array>()
+                        .expect("cast failed");
+                    array.iter().collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(brute_force(-(ids.len() as i64), 0), column(0));
+        assert_eq!(brute_force(-1, 1), column(1));
+
+        // the UDWF is resolved by name when the plan is deserialized
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let json = crate::physical_plan::serde::to_json(&plan)?;
+        let err = crate::physical_plan::from_json_with_registry(
+            &json,
+            &ExecutionContext::new().function_registry(),
+        )
+        .unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "There is no UDWF named \"running_mean\" in the registry"
+        );
+        let deserialized = crate::physical_plan::from_json_with_registry(
+            &json,
+            &ctx.function_registry(),
+        )?;
+        assert_eq!(plan.schema(), deserialized.schema());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn simple_avg() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+
+        let batch1 = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Int32Array::from(vec![4, 5]))],
+        )?;
+
+        let mut ctx = ExecutionContext::new();
+
+        let provider =
+            MemTable::try_new(Arc::new(schema), vec![vec![batch1], vec![batch2]])?;
+        ctx.register_table("t", Arc::new(provider))?;
+
+        let result = plan_and_collect(&mut ctx, "SELECT AVG(a) FROM t").await?;
+
+        let batch = &result[0];
+        assert_eq!(1, batch.num_columns());
+        assert_eq!(1, batch.num_rows());
+
+        let values = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .expect("failed to cast version");
+        assert_eq!(values.len(), 1);
+        // avg(1,2,3,4,5) = 3.0
+        assert_eq!(values.value(0), 3.0_f64);
+        Ok(())
+    }
+
+    /// tests the creation, registration and usage of a UDAF
+    #[tokio::test]
+    async fn simple_udaf() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+
+        let batch1 = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(Int32Array::from(vec![4, 5]))],
+        )?;
+
+        let mut ctx = ExecutionContext::new();
+
+        let provider =
+            MemTable::try_new(Arc::new(schema), vec![vec![batch1], vec![batch2]])?;
+        ctx.register_table("t", Arc::new(provider))?;
+
+        // define a udaf, using a DataFusion's accumulator
+        let my_avg = create_udaf(
+            "my_avg",
+            DataType::Float64,
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
+            Arc::new(vec![DataType::UInt64, DataType::Float64]),
+        );
+
+        ctx.register_udaf(my_avg);
+
+        let result = plan_and_collect(&mut ctx, "SELECT MY_AVG(a) FROM t").await?;
+
+        let expected = vec![
+            "+-------------+",
+            "| my_avg(t.a) |",
+            "+-------------+",
+            "| 3           |",
+            "+-------------+",
+        ];
+        assert_batches_eq!(expected, &result);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn custom_query_planner() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_query_planner(Arc::new(MyQueryPlanner {})),
+        );
+
+        let df = ctx.sql("SELECT 1").await?;
+        df.collect().await.expect_err("query not supported");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn information_schema_tables_not_exist_by_default() {
+        let mut ctx = ExecutionContext::new();
+
+        let err = plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Table or CTE with name 'information_schema.tables' not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn information_schema_tables_no_tables() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+                .await
+                .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------------+------------+------------+",
+            "| table_catalog | table_schema       | table_name | table_type |",
+            "+---------------+--------------------+------------+------------+",
+            "| datafusion    | information_schema | columns    | VIEW       |",
+            "| datafusion    | information_schema | tables     | VIEW       |",
+            "+---------------+--------------------+------------+------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_tables_tables_default_catalog() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        // Now, register an empty table
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+                .await
+                .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------------+------------+------------+",
+            "| table_catalog | table_schema       | table_name | table_type |",
+            "+---------------+--------------------+------------+------------+",
+            "| datafusion    | information_schema | tables     | VIEW       |",
+            "| datafusion    | information_schema | columns    | VIEW       |",
+            "| datafusion    | public             | t          | BASE TABLE |",
+            "+---------------+--------------------+------------+------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        // Newly added tables should appear
+        ctx.register_table("t2", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+                .await
+                .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------------+------------+------------+",
+            "| table_catalog | table_schema       | table_name | table_type |",
+            "+---------------+--------------------+------------+------------+",
+            "| datafusion    | information_schema | columns    | VIEW       |",
+            "| datafusion    | information_schema | tables     | VIEW       |",
+            "| datafusion    | public             | t          | BASE TABLE |",
+            "| datafusion    | public             | t2         | BASE TABLE |",
+            "+---------------+--------------------+------------+------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_tables_tables_with_multiple_catalogs() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+        let catalog = MemoryCatalogProvider::new();
+        let schema = MemorySchemaProvider::new();
+        schema
+            .register_table("t1".to_owned(), test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+        schema
+            .register_table("t2".to_owned(), test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+        catalog
+            .register_schema("my_schema", Arc::new(schema))
+            .unwrap();
+        ctx.register_catalog("my_catalog", Arc::new(catalog));
+
+        let catalog = MemoryCatalogProvider::new();
+        let schema = MemorySchemaProvider::new();
+        schema
+            .register_table("t3".to_owned(), test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+        catalog
+            .register_schema("my_other_schema", Arc::new(schema))
+            .unwrap();
+        ctx.register_catalog("my_other_catalog", Arc::new(catalog));
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+                .await
+                .unwrap();
+
+        let expected = vec![
+            "+------------------+--------------------+------------+------------+",
+            "| table_catalog    | table_schema       | table_name | table_type |",
+            "+------------------+--------------------+------------+------------+",
+            "| datafusion       | information_schema | columns    | VIEW       |",
+            "| datafusion       | information_schema | tables     | VIEW       |",
+            "| my_catalog       | information_schema | columns    | VIEW       |",
+            "| my_catalog       | information_schema | tables     | VIEW       |",
+            "| my_catalog       | my_schema          | t1         | BASE TABLE |",
+            "| my_catalog       | my_schema          | t2         | BASE TABLE |",
+            "| my_other_catalog | information_schema | columns    | VIEW       |",
+            "| my_other_catalog | information_schema | tables     | VIEW       |",
+            "| my_other_catalog | my_other_schema    | t3         | BASE TABLE |",
+            "+------------------+--------------------+------------+------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_tables_table_types() {
+        struct TestTable(TableType);
+
+        #[async_trait]
+        impl TableProvider for TestTable {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+
+            fn table_type(&self) -> TableType {
+                self.0
+            }
+
+            fn schema(&self) -> SchemaRef {
+                unimplemented!()
+            }
+
+            async fn scan(
+                &self,
+                _: &Option<Vec<usize>>,
+                _: usize,
+                _: &[Expr],
+                _: Option<usize>,
+            ) -> Result<Arc<dyn ExecutionPlan>> {
+                unimplemented!()
+            }
+        }
+
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        ctx.register_table("physical", Arc::new(TestTable(TableType::Base)))
+            .unwrap();
+        ctx.register_table("query", Arc::new(TestTable(TableType::View)))
+            .unwrap();
+        ctx.register_table("temp", Arc::new(TestTable(TableType::Temporary)))
+            .unwrap();
+
+        let result =
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+                .await
+                .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------------+------------+-----------------+",
+            "| table_catalog | table_schema       | table_name | table_type      |",
+            "+---------------+--------------------+------------+-----------------+",
+            "| datafusion    | information_schema | tables     | VIEW            |",
+            "| datafusion    | information_schema | columns    | VIEW            |",
+            "| datafusion    | public             | physical   | BASE TABLE      |",
+            "| datafusion    | public             | query      | VIEW            |",
+            "| datafusion    | public             | temp       | LOCAL TEMPORARY |",
+            "+---------------+--------------------+------------+-----------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_tables_no_information_schema() {
+        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        // use show tables alias
+        let err = plan_and_collect(&mut ctx, "SHOW TABLES").await.unwrap_err();
+
+        assert_eq!(err.to_string(), "Error during planning: SHOW TABLES is not supported unless information_schema is enabled");
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_tables() {
         let mut ctx = ExecutionContext::with_config(
             ExecutionConfig::new().with_information_schema(true),
         );
 
-        // Now, register an empty table
         ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
             .unwrap();
 
-        let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
-                .await
-                .unwrap();
+        // use show tables alias
+        let result = plan_and_collect(&mut ctx, "SHOW TABLES").await.unwrap();
 
         let expected = vec![
             "+---------------+--------------------+------------+------------+",
             "| table_catalog | table_schema       | table_name | table_type |",
             "+---------------+--------------------+------------+------------+",
-            "| datafusion    | information_schema | tables     | VIEW       |",
             "| datafusion    | information_schema | columns    | VIEW       |",
+            "| datafusion    | information_schema | tables     | VIEW       |",
             "| datafusion    | public             | t          | BASE TABLE |",
             "+---------------+--------------------+------------+------------+",
         ];
         assert_batches_sorted_eq!(expected, &result);
 
-        // Newly added tables should appear
-        ctx.register_table("t2", test::table_with_sequence(1, 1).unwrap())
+        let result = plan_and_collect(&mut ctx, "SHOW tables").await.unwrap();
+
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_columns_no_information_schema() {
+        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
             .unwrap();
 
-        let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+        let err = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t")
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "Error during planning: SHOW COLUMNS is not supported unless information_schema is enabled");
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_columns_like_where() {
+        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let expected =
+            "Error during planning: SHOW COLUMNS with WHERE or LIKE is not supported";
+
+        let err = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t LIKE 'f'")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+
+        let err =
+            plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t WHERE column_name = 'bar'")
                 .await
-                .unwrap();
+                .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_columns() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let result = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t")
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "| table_catalog | table_schema | table_name | column_name | data_type | is_nullable |",
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "| datafusion    | public       | t          | i           | Int32     | YES         |",
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let result = plan_and_collect(&mut ctx, "SHOW columns from t")
+            .await
+            .unwrap();
+        assert_batches_sorted_eq!(expected, &result);
+
+        // This isn't ideal but it is consistent behavior for `SELECT * from T`
+        let err = plan_and_collect(&mut ctx, "SHOW columns from T")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unknown relation for SHOW COLUMNS: T"
+        );
+    }
+
+    // test errors with WHERE and LIKE
+    #[tokio::test]
+    async fn information_schema_show_columns_full_extended() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let result = plan_and_collect(&mut ctx, "SHOW FULL COLUMNS FROM t")
+            .await
+            .unwrap();
+        let expected = vec![
+            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+            "| table_catalog | table_schema | table_name | column_name | ordinal_position | column_default | is_nullable | data_type | character_maximum_length | character_octet_length | numeric_precision | numeric_precision_radix | numeric_scale | datetime_precision | interval_type |",
+            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+            "| datafusion    | public       | t          | i           | 0                |                | YES         | Int32     |                          |                        | 32                | 2                       |               |                    |               |",
+            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let result = plan_and_collect(&mut ctx, "SHOW EXTENDED COLUMNS FROM t")
+            .await
+            .unwrap();
+        assert_batches_sorted_eq!(expected, &result);
+    }
+
+    #[tokio::test]
+    async fn information_schema_show_table_table_names() {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_information_schema(true),
+        );
+
+        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
+            .unwrap();
+
+        let result = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM public.t")
+            .await
+            .unwrap();
+
+        let expected = vec![
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "| table_catalog | table_schema | table_name | column_name | data_type | is_nullable |",
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "| datafusion    | public       | t          | i           | Int32     | YES         |",
+            "+---------------+--------------+------------+-------------+-----------+-------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
+
+        let result = plan_and_collect(&mut ctx, "SHOW columns from datafusion.public.t")
+            .await
+            .unwrap();
+        assert_batches_sorted_eq!(expected, &result);
+
+        let err = plan_and_collect(&mut ctx, "SHOW columns from t2")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unknown relation for SHOW COLUMNS: t2"
+        );
+
+        let err = plan_and_collect(&mut ctx, "SHOW columns from datafusion.public.t2")
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Error during planning: Unknown relation for SHOW COLUMNS: datafusion.public.t2");
+    }
+
+    #[tokio::test]
+    async fn show_unsupported() {
+        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+
+        let err = plan_and_collect(&mut ctx, "SHOW SOMETHING_UNKNOWN")
+            .await
+            .unwrap_err();
+
+        assert_eq!(err.to_string(), "This feature is not implemented: SHOW SOMETHING_UNKNOWN not implemented. Supported syntax: SHOW <TABLES>, SHOW ALL or SHOW <setting>");
+    }
+
+    #[tokio::test]
+    async fn set_variable_applies_to_subsequent_queries() -> Result<()> {
+        let mut ctx = create_ctx(&TempDir::new()?, 1).await?;
+
+        plan_and_collect(&mut ctx, "SET datafusion.execution.batch_size = 1000").await?;
+        plan_and_collect(
+            &mut ctx,
+            "SET datafusion.optimizer.repartition_joins TO false",
+        )
+        .await?;
+        assert_eq!(ctx.state.lock().unwrap().config.batch_size, 1000);
+        assert!(!ctx.state.lock().unwrap().config.repartition_joins);
+
+        // plan the filter in a `CoalesceBatchesExec` targeting half the batch size
+        ctx.state.lock().unwrap().config.coalesce_filter_output = false;
+        let plan = ctx.create_logical_plan("SELECT c1 FROM test WHERE c2 > 5")?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let plan = displayable(plan.as_ref()).indent().to_string();
+        assert!(
+            plan.contains("CoalesceBatchesExec: target_batch_size=500"),
+            "{}",
+            plan
+        );
+
+        let results =
+            plan_and_collect(&mut ctx, "SHOW datafusion.execution.BATCH_SIZE").await?;
+        let expected = vec![
+            "+---------------------------------+---------+",
+            "| name                            | setting |",
+            "+---------------------------------+---------+",
+            "| datafusion.execution.batch_size | 1000    |",
+            "+---------------------------------+---------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn show_all_settings() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_batch_size(4096)
+                .with_target_partitions(4),
+        );
+        ctx.state_mut()
+            .config_mut()
+            .set("datafusion.optimizer.aggregate_push_down", "FALSE")?;
+
+        let results = plan_and_collect(&mut ctx, "SHOW ALL").await?;
+        let expected = vec![
+            "+-----------------------------------------------------+---------+",
+            "| name                                                | setting |",
+            "+-----------------------------------------------------+---------+",
+            "| datafusion.execution.arithmetic_overflow            | wrap    |",
+            "| datafusion.execution.batch_size                     | 4096    |",
+            "| datafusion.execution.parquet_pruning                | true    |",
+            "| datafusion.execution.recursive_query_max_iterations | 100     |",
+            "| datafusion.execution.target_partitions              | 4       |",
+            "| datafusion.execution.udf_error_mode                 | strict  |",
+            "| datafusion.optimizer.aggregate_push_down            | false   |",
+            "| datafusion.optimizer.repartition_aggregations       | true    |",
+            "| datafusion.optimizer.repartition_joins              | true    |",
+            "| datafusion.optimizer.repartition_windows            | true    |",
+            "| datafusion.sql_parser.parse_float_as_decimal        | false   |",
+            "+-----------------------------------------------------+---------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn set_variable_invalid() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+
+        let err = plan_and_collect(&mut ctx, "SET datafusion.unknown = 1")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Unknown configuration option 'datafusion.unknown'"
+        );
+
+        let err = plan_and_collect(&mut ctx, "SET datafusion.execution.batch_size = 0")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Configuration option 'datafusion.execution.batch_size' \
+             expects a positive integer, got '0'"
+        );
+
+        let err = ctx
+            .state_mut()
+            .config_mut()
+            .set("datafusion.execution.parquet_pruning", "maybe")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Configuration option 'datafusion.execution.parquet_pruning' \
+             expects a boolean, got 'maybe'"
+        );
+
+        // the settings are unchanged
+        let config = &ctx.state.lock().unwrap().config;
+        assert_eq!(config.batch_size, ExecutionConfig::new().batch_size);
+        assert_eq!(config.get("datafusion.execution.parquet_pruning")?, "true");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn information_schema_columns_not_exist_by_default() {
+        let mut ctx = ExecutionContext::new();
+
+        let err = plan_and_collect(&mut ctx, "SELECT * from information_schema.columns")
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Table or CTE with name 'information_schema.columns' not found"
+        );
+    }
+
+    fn table_with_many_types() -> Arc<dyn TableProvider> {
+        let schema = Schema::new(vec![
+            Field::new("int32_col", DataType::Int32, false),
+            Field::new("float64_col", DataType::Float64, true),
+            Field::new("utf8_col", DataType::Utf8, true),
+            Field::new("large_utf8_col", DataType::LargeUtf8, false),
+            Field::new("binary_col", DataType::Binary, false),
+            Field::new("large_binary_col", DataType::LargeBinary, false),
+            Field::new(
+                "timestamp_nanos",
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                false,
+            ),
+        ]);
 
-        let expected = vec![
-            "+---------------+--------------------+------------+------------+",
-            "| table_catalog | table_schema       | table_name | table_type |",
-            "+---------------+--------------------+------------+------------+",
-            "| datafusion    | information_schema | columns    | VIEW       |",
-            "| datafusion    | information_schema | tables     | VIEW       |",
-            "| datafusion    | public             | t          | BASE TABLE |",
-            "| datafusion    | public             | t2         | BASE TABLE |",
-            "+---------------+--------------------+------------+------------+",
-        ];
-        assert_batches_sorted_eq!(expected, &result);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int32Array::from(vec![1])),
+                Arc::new(Float64Array::from(vec![1.0])),
+                Arc::new(StringArray::from(vec![Some("foo")])),
+                Arc::new(LargeStringArray::from(vec![Some("bar")])),
+                Arc::new(BinaryArray::from(vec![b"foo" as &[u8]])),
+                Arc::new(LargeBinaryArray::from(vec![b"foo" as &[u8]])),
+                Arc::new(TimestampNanosecondArray::from_opt_vec(
+                    vec![Some(123)],
+                    None,
+                )),
+            ],
+        )
+        .unwrap();
+        let provider = MemTable::try_new(Arc::new(schema), vec![vec![batch]]).unwrap();
+        Arc::new(provider)
     }
 
     #[tokio::test]
-    async fn information_schema_tables_tables_with_multiple_catalogs() {
+    async fn information_schema_columns() {
         let mut ctx = ExecutionContext::with_config(
             ExecutionConfig::new().with_information_schema(true),
         );
         let catalog = MemoryCatalogProvider::new();
         let schema = MemorySchemaProvider::new();
+
         schema
             .register_table("t1".to_owned(), test::table_with_sequence(1, 1).unwrap())
             .unwrap();
-        schema
-            .register_table("t2".to_owned(), test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
-        catalog.register_schema("my_schema", Arc::new(schema));
-        ctx.register_catalog("my_catalog", Arc::new(catalog));
 
-        let catalog = MemoryCatalogProvider::new();
-        let schema = MemorySchemaProvider::new();
         schema
-            .register_table("t3".to_owned(), test::table_with_sequence(1, 1).unwrap())
+            .register_table("t2".to_owned(), table_with_many_types())
             .unwrap();
-        catalog.register_schema("my_other_schema", Arc::new(schema));
-        ctx.register_catalog("my_other_catalog", Arc::new(catalog));
+        catalog
+            .register_schema("my_schema", Arc::new(schema))
+            .unwrap();
+        ctx.register_catalog("my_catalog", Arc::new(catalog));
 
         let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
+            plan_and_collect(&mut ctx, "SELECT * from information_schema.columns")
                 .await
                 .unwrap();
 
         let expected = vec![
-            "+------------------+--------------------+------------+------------+",
-            "| table_catalog    | table_schema       | table_name | table_type |",
-            "+------------------+--------------------+------------+------------+",
-            "| datafusion       | information_schema | columns    | VIEW       |",
-            "| datafusion       | information_schema | tables     | VIEW       |",
-            "| my_catalog       | information_schema | columns    | VIEW       |",
-            "| my_catalog       | information_schema | tables     | VIEW       |",
-            "| my_catalog       | my_schema          | t1         | BASE TABLE |",
-            "| my_catalog       | my_schema          | t2         | BASE TABLE |",
-            "| my_other_catalog | information_schema | columns    | VIEW       |",
-            "| my_other_catalog | information_schema | tables     | VIEW       |",
-            "| my_other_catalog | my_other_schema    | t3         | BASE TABLE |",
-            "+------------------+--------------------+------------+------------+",
+            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+            "| table_catalog | table_schema | table_name | column_name      | ordinal_position | column_default | is_nullable | data_type                   | character_maximum_length | character_octet_length | numeric_precision | numeric_precision_radix | numeric_scale | datetime_precision | interval_type |",
+            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+            "| my_catalog    | my_schema    | t1         | i                | 0                |                | YES         | Int32                       |                          |                        | 32                | 2                       |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | binary_col       | 4                |                | NO          | Binary                      |                          | 2147483647             |                   |                         |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | float64_col      | 1                |                | YES         | Float64                     |                          |                        | 24                | 2                       |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | int32_col        | 0                |                | NO          | Int32                       |                          |                        | 32                | 2                       |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | large_binary_col | 5                |                | NO          | LargeBinary                 |                          | 9223372036854775807    |                   |                         |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | large_utf8_col   | 3                |                | NO          | LargeUtf8                   |                          | 9223372036854775807    |                   |                         |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | timestamp_nanos  | 6                |                | NO          | Timestamp(Nanosecond, None) |                          |                        |                   |                         |               |                    |               |",
+            "| my_catalog    | my_schema    | t2         | utf8_col         | 2                |                | YES         | Utf8                        |                          | 2147483647             |                   |                         |               |                    |               |",
+            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
         ];
         assert_batches_sorted_eq!(expected, &result);
     }
 
     #[tokio::test]
-    async fn information_schema_tables_table_types() {
-        struct TestTable(TableType);
-
-        #[async_trait]
-        impl TableProvider for TestTable {
-            fn as_any(&self) -> &dyn std::any::Any {
-                self
-            }
-
-            fn table_type(&self) -> TableType {
-                self.0
-            }
-
-            fn schema(&self) -> SchemaRef {
-                unimplemented!()
-            }
-
-            async fn scan(
-                &self,
-                _: &Option<Vec<usize>>,
-                _: usize,
-                _: &[Expr],
-                _: Option<usize>,
-            ) -> Result<Arc<dyn ExecutionPlan>> {
-                unimplemented!()
-            }
-        }
-
+    async fn disabled_default_catalog_and_schema() -> Result<()> {
         let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
+            ExecutionConfig::new().create_default_catalog_and_schema(false),
         );
 
-        ctx.register_table("physical", Arc::new(TestTable(TableType::Base)))
-            .unwrap();
-        ctx.register_table("query", Arc::new(TestTable(TableType::View)))
-            .unwrap();
-        ctx.register_table("temp", Arc::new(TestTable(TableType::Temporary)))
-            .unwrap();
+        assert!(matches!(
+            ctx.register_table("test", test::table_with_sequence(1, 1)?),
+            Err(DataFusionError::Plan(_))
+        ));
 
-        let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.tables")
-                .await
-                .unwrap();
+        assert!(matches!(
+            ctx.sql("select * from datafusion.public.test").await,
+            Err(DataFusionError::Plan(_))
+        ));
 
-        let expected = vec![
-            "+---------------+--------------------+------------+-----------------+",
-            "| table_catalog | table_schema       | table_name | table_type      |",
-            "+---------------+--------------------+------------+-----------------+",
-            "| datafusion    | information_schema | tables     | VIEW            |",
-            "| datafusion    | information_schema | columns    | VIEW            |",
-            "| datafusion    | public             | physical   | BASE TABLE      |",
-            "| datafusion    | public             | query      | VIEW            |",
-            "| datafusion    | public             | temp       | LOCAL TEMPORARY |",
-            "+---------------+--------------------+------------+-----------------+",
-        ];
-        assert_batches_sorted_eq!(expected, &result);
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_show_tables_no_information_schema() {
-        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+    async fn custom_catalog_and_schema() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .create_default_catalog_and_schema(false)
+                .with_default_catalog_and_schema("my_catalog", "my_schema"),
+        );
 
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+        let catalog = MemoryCatalogProvider::new();
+        let schema = MemorySchemaProvider::new();
+        schema.register_table("test".to_owned(), test::table_with_sequence(1, 1)?)?;
+        catalog.register_schema("my_schema", Arc::new(schema))?;
+        ctx.register_catalog("my_catalog", Arc::new(catalog));
 
-        // use show tables alias
-        let err = plan_and_collect(&mut ctx, "SHOW TABLES").await.unwrap_err();
+        for table_ref in &["my_catalog.my_schema.test", "my_schema.test", "test"] {
+            let result = plan_and_collect(
+                &mut ctx,
+                &format!("SELECT COUNT(*) AS count FROM {}", table_ref),
+            )
+            .await?;
 
-        assert_eq!(err.to_string(), "Error during planning: SHOW TABLES is not supported unless information_schema is enabled");
+            let expected = vec![
+                "+-------+",
+                "| count |",
+                "+-------+",
+                "| 1     |",
+                "+-------+",
+            ];
+            assert_batches_eq!(expected, &result);
+        }
+
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_show_tables() {
-        let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
-        );
+    async fn cross_catalog_access() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
 
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+        let catalog_a = MemoryCatalogProvider::new();
+        let schema_a = MemorySchemaProvider::new();
+        schema_a
+            .register_table("table_a".to_owned(), test::table_with_sequence(1, 1)?)?;
+        catalog_a.register_schema("schema_a", Arc::new(schema_a))?;
+        ctx.register_catalog("catalog_a", Arc::new(catalog_a));
 
-        // use show tables alias
-        let result = plan_and_collect(&mut ctx, "SHOW TABLES").await.unwrap();
+        let catalog_b = MemoryCatalogProvider::new();
+        let schema_b = MemorySchemaProvider::new();
+        schema_b
+            .register_table("table_b".to_owned(), test::table_with_sequence(1, 2)?)?;
+        catalog_b.register_schema("schema_b", Arc::new(schema_b))?;
+        ctx.register_catalog("catalog_b", Arc::new(catalog_b));
+
+        let result = plan_and_collect(
+            &mut ctx,
+            "SELECT cat, SUM(i) AS total FROM (
+                    SELECT i, 'a' AS cat FROM catalog_a.schema_a.table_a
+                    UNION ALL
+                    SELECT i, 'b' AS cat FROM catalog_b.schema_b.table_b
+                ) AS all
+                GROUP BY cat
+                ORDER BY cat
+                ",
+        )
+        .await?;
 
         let expected = vec![
-            "+---------------+--------------------+------------+------------+",
-            "| table_catalog | table_schema       | table_name | table_type |",
-            "+---------------+--------------------+------------+------------+",
-            "| datafusion    | information_schema | columns    | VIEW       |",
-            "| datafusion    | information_schema | tables     | VIEW       |",
-            "| datafusion    | public             | t          | BASE TABLE |",
-            "+---------------+--------------------+------------+------------+",
+            "+-----+-------+",
+            "| cat | total |",
+            "+-----+-------+",
+            "| a   | 1     |",
+            "| b   | 3     |",
+            "+-----+-------+",
         ];
-        assert_batches_sorted_eq!(expected, &result);
-
-        let result = plan_and_collect(&mut ctx, "SHOW tables").await.unwrap();
+        assert_batches_eq!(expected, &result);
 
-        assert_batches_sorted_eq!(expected, &result);
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_show_columns_no_information_schema() {
-        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+    async fn create_external_table_with_timestamps() {
+        let mut ctx = ExecutionContext::new();
 
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+        let data = "Jorge,2018-12-13T12:12:10.011Z\n\
+                    Andrew,2018-11-13T17:11:10.011Z";
+
+        let tmp_dir = TempDir::new().unwrap();
+        let file_path = tmp_dir.path().join("timestamps.csv");
+
+        // scope to ensure the file is closed and written
+        {
+            File::create(&file_path)
+                .expect("creating temp file")
+                .write_all(data.as_bytes())
+                .expect("writing data");
+        }
+
+        let sql = format!(
+            "CREATE EXTERNAL TABLE csv_with_timestamps (
+                  name VARCHAR,
+                  ts TIMESTAMP
+              )
+              STORED AS CSV
+              LOCATION '{}'
+              ",
+            file_path.to_str().expect("path is utf8")
+        );
 
-        let err = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t")
+        plan_and_collect(&mut ctx, &sql)
             .await
-            .unwrap_err();
+            .expect("Executing CREATE EXTERNAL TABLE");
 
-        assert_eq!(err.to_string(), "Error during planning: SHOW COLUMNS is not supported unless information_schema is enabled");
+        let sql = "SELECT * from csv_with_timestamps";
+        let result = plan_and_collect(&mut ctx, sql).await.unwrap();
+        let expected = vec![
+            "+--------+-------------------------+",
+            "| name   | ts                      |",
+            "+--------+-------------------------+",
+            "| Andrew | 2018-11-13 17:11:10.011 |",
+            "| Jorge  | 2018-12-13 12:12:10.011 |",
+            "+--------+-------------------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
     }
 
     #[tokio::test]
-    async fn information_schema_show_columns_like_where() {
-        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
-
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+    async fn create_external_table_typed_partitions() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        for year in [2019, 2020, 2021, 2022] {
+            let partition_dir = tmp_dir.path().join(format!("year={}", year));
+            fs::create_dir(&partition_dir)?;
+            File::create(partition_dir.join("data.csv"))?
+                .write_all(format!("a\n{}\n", year % 100).as_bytes())?;
+        }
 
-        let expected =
-            "Error during planning: SHOW COLUMNS with WHERE or LIKE is not supported";
+        let mut ctx = ExecutionContext::new();
+        let sql = format!(
+            "CREATE EXTERNAL TABLE t STORED AS CSV WITH HEADER ROW \
+             PARTITIONED BY (year INT) LOCATION '{}'",
+            tmp_dir.path().to_str().expect("path is utf8")
+        );
+        plan_and_collect(&mut ctx, &sql).await?;
 
-        let err = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t LIKE 'f'")
-            .await
-            .unwrap_err();
-        assert_eq!(err.to_string(), expected);
+        let result = plan_and_collect(
+            &mut ctx,
+            "SELECT a, year FROM t WHERE year >= 2020 AND year < 2022",
+        )
+        .await?;
+        let expected = vec![
+            "+----+------+",
+            "| a  | year |",
+            "+----+------+",
+            "| 20 | 2020 |",
+            "| 21 | 2021 |",
+            "+----+------+",
+        ];
+        assert_batches_sorted_eq!(expected, &result);
 
-        let err =
-            plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t WHERE column_name = 'bar'")
-                .await
-                .unwrap_err();
-        assert_eq!(err.to_string(), expected);
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_show_columns() {
-        let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
-        );
-
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+    async fn create_external_table_partitioned_with_options() -> Result<()> {
+        let tmp_dir = TempDir::new()?;
+        for (dt, data) in [
+            ("2021-01-01", "a|b\n1|x\n2|y\n"),
+            ("2021-01-02", "a|b\n3|z\n"),
+        ] {
+            let partition_dir = tmp_dir.path().join(format!("dt={}", dt));
+            fs::create_dir(&partition_dir)?;
+            File::create(partition_dir.join("data.csv"))?.write_all(data.as_bytes())?;
+            // not matched by the glob pattern of the location
+            File::create(partition_dir.join("notes.txt"))?.write_all(b"not a csv")?;
+        }
 
-        let result = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM t")
-            .await
-            .unwrap();
+        let mut ctx = ExecutionContext::new();
+        let sql = format!(
+            "CREATE EXTERNAL TABLE t STORED AS CSV \
+             PARTITIONED BY (dt STRING) \
+             OPTIONS ('has_header' 'true', 'delimiter' '|') \
+             LOCATION '{}/dt=*/*.csv'",
+            tmp_dir.path().to_str().expect("path is utf8")
+        );
+        plan_and_collect(&mut ctx, &sql).await?;
 
+        let result = plan_and_collect(&mut ctx, "SELECT a, b, dt FROM t").await?;
         let expected = vec![
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
-            "| table_catalog | table_schema | table_name | column_name | data_type | is_nullable |",
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
-            "| datafusion    | public       | t          | i           | Int32     | YES         |",
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "+---+---+------------+",
+            "| a | b | dt         |",
+            "+---+---+------------+",
+            "| 1 | x | 2021-01-01 |",
+            "| 2 | y | 2021-01-01 |",
+            "| 3 | z | 2021-01-02 |",
+            "+---+---+------------+",
         ];
         assert_batches_sorted_eq!(expected, &result);
 
-        let result = plan_and_collect(&mut ctx, "SHOW columns from t")
-            .await
-            .unwrap();
-        assert_batches_sorted_eq!(expected, &result);
+        let result =
+            plan_and_collect(&mut ctx, "SELECT a, b FROM t WHERE dt = '2021-01-02'")
+                .await?;
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 3 | z |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &result);
 
-        // This isn't ideal but it is consistent behavior for `SELECT * from T`
-        let err = plan_and_collect(&mut ctx, "SHOW columns from T")
-            .await
-            .unwrap_err();
+        // the partition column must not be a column of the files
+        let sql = format!(
+            "CREATE EXTERNAL TABLE t2 STORED AS CSV PARTITIONED BY (a) \
+             OPTIONS ('has_header' 'true', 'delimiter' '|') LOCATION '{}/dt=*/*.csv'",
+            tmp_dir.path().to_str().expect("path is utf8")
+        );
+        let err = plan_and_collect(&mut ctx, &sql).await.unwrap_err();
         assert_eq!(
-            err.to_string(),
-            "Error during planning: Unknown relation for SHOW COLUMNS: T"
+            "Error during planning: Partition column a collides with a column of the files of table t2",
+            err.to_string()
+        );
+
+        let sql = "CREATE EXTERNAL TABLE t3 STORED AS AVRO \
+                   OPTIONS ('delimiter' '|') LOCATION 'foo.avro'";
+        let err = plan_and_collect(&mut ctx, sql).await.unwrap_err();
+        assert_eq!(
+            "Error during planning: Unsupported option delimiter for Avro files",
+            err.to_string()
         );
+
+        Ok(())
     }
 
-    // test errors with WHERE and LIKE
     #[tokio::test]
-    async fn information_schema_show_columns_full_extended() {
+    async fn listing_cache_until_refresh() -> Result<()> {
+        let store = Arc::new(test::object_store::TestObjectStore::new(&[
+            ("table/p=1/file.arrow", 10),
+            ("table/p=2/file.arrow", 10),
+        ]));
         let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
+            ExecutionConfig::new().with_listing_cache(true),
         );
+        ctx.register_object_store("mock", store.clone());
+
+        let options = ListingOptions {
+            file_extension: ".arrow".to_owned(),
+            format: Arc::new(IpcFormat::default()),
+            table_partition_cols: vec![("p".to_owned(), DataType::Utf8)],
+            collect_stat: false,
+            target_partitions: 1,
+        };
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        ctx.register_listing_table("t", "mock://table/", options, Some(schema))
+            .await?;
 
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
+        // planning a query lists the files of the table
+        async fn plan_query(ctx: &ExecutionContext) -> Result<()> {
+            let plan = ctx.create_logical_plan("SELECT a FROM t WHERE p = '1'")?;
+            let plan = ctx.optimize(&plan)?;
+            ctx.create_physical_plan(&plan).await?;
+            Ok(())
+        }
 
-        let result = plan_and_collect(&mut ctx, "SHOW FULL COLUMNS FROM t")
-            .await
-            .unwrap();
-        let expected = vec![
-            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
-            "| table_catalog | table_schema | table_name | column_name | ordinal_position | column_default | is_nullable | data_type | character_maximum_length | character_octet_length | numeric_precision | numeric_precision_radix | numeric_scale | datetime_precision | interval_type |",
-            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
-            "| datafusion    | public       | t          | i           | 0                |                | YES         | Int32     |                          |                        | 32                | 2                       |               |                    |               |",
-            "+---------------+--------------+------------+-------------+------------------+----------------+-------------+-----------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
-        ];
-        assert_batches_sorted_eq!(expected, &result);
+        plan_query(&ctx).await?;
+        plan_query(&ctx).await?;
+        assert_eq!(store.list_calls(), 1);
 
-        let result = plan_and_collect(&mut ctx, "SHOW EXTENDED COLUMNS FROM t")
-            .await
-            .unwrap();
-        assert_batches_sorted_eq!(expected, &result);
+        ctx.refresh_table("t").await?;
+        plan_query(&ctx).await?;
+        assert_eq!(store.list_calls(), 2);
+
+        let err = ctx.refresh_table("missing").await.unwrap_err();
+        assert_eq!(
+            "Error during planning: No table named 'missing'",
+            err.to_string()
+        );
+
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_show_table_table_names() {
+    async fn custom_object_store_scheme() -> Result<()> {
+        let store = Arc::new(test::object_store::InMemoryObjectStore::new(&[
+            ("table/part-0.csv", "a,b\n1,x\n2,y\n"),
+            ("table/part-1.csv", "a,b\n3,z\n"),
+            ("other/part-0.csv", "a,b\n4,w\n"),
+        ]));
         let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
+            ExecutionConfig::new().with_target_partitions(1),
         );
+        ctx.register_object_store("mem", store);
 
-        ctx.register_table("t", test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
-
-        let result = plan_and_collect(&mut ctx, "SHOW COLUMNS FROM public.t")
-            .await
-            .unwrap();
+        ctx.sql(
+            "CREATE EXTERNAL TABLE t (a INT, b VARCHAR) \
+             STORED AS CSV WITH HEADER ROW LOCATION 'mem://table/'",
+        )
+        .await?;
 
+        let plan = ctx.create_logical_plan("SELECT a, b FROM t WHERE a > 1")?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
         let expected = vec![
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
-            "| table_catalog | table_schema | table_name | column_name | data_type | is_nullable |",
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
-            "| datafusion    | public       | t          | i           | Int32     | YES         |",
-            "+---------------+--------------+------------+-------------+-----------+-------------+",
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 2 | y |",
+            "| 3 | z |",
+            "+---+---+",
         ];
-        assert_batches_sorted_eq!(expected, &result);
-
-        let result = plan_and_collect(&mut ctx, "SHOW columns from datafusion.public.t")
-            .await
-            .unwrap();
-        assert_batches_sorted_eq!(expected, &result);
-
-        let err = plan_and_collect(&mut ctx, "SHOW columns from t2")
-            .await
-            .unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            "Error during planning: Unknown relation for SHOW COLUMNS: t2"
+        let task_ctx = ctx.task_ctx();
+        assert_batches_sorted_eq!(
+            expected,
+            &collect(plan.clone(), task_ctx.clone()).await?
         );
 
-        let err = plan_and_collect(&mut ctx, "SHOW columns from datafusion.public.t2")
+        // the files of the store are not serialized with the plan
+        let json = serde_json::to_string(&plan).unwrap();
+        let plan: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+        assert!(collect(plan.clone(), task_ctx.clone()).await.is_err());
+        let plan = ctx.bind_runtime(plan)?;
+        assert_batches_sorted_eq!(expected, &collect(plan, task_ctx).await?);
+
+        let err = ctx
+            .sql(
+                "CREATE EXTERNAL TABLE u (a INT) STORED AS CSV \
+                 LOCATION 'hdfs://table/'",
+            )
             .await
             .unwrap_err();
-        assert_eq!(err.to_string(), "Error during planning: Unknown relation for SHOW COLUMNS: datafusion.public.t2");
+        assert!(err.to_string().starts_with(
+            "Error during planning: No object store registered for scheme 'hdfs', \
+             registered schemes are: file, mem"
+        ));
+
+        Ok(())
     }
 
     #[tokio::test]
-    async fn show_unsupported() {
-        let mut ctx = ExecutionContext::with_config(ExecutionConfig::new());
+    async fn bind_deserialized_plan_to_runtime() -> Result<()> {
+        use crate::physical_plan::file_format::CsvExec;
 
-        let err = plan_and_collect(&mut ctx, "SHOW SOMETHING_UNKNOWN")
-            .await
-            .unwrap_err();
+        let files = [
+            ("table/part-0.csv", "a,b\n1,x\n2,y\n"),
+            ("table/part-1.csv", "a,b\n3,z\n"),
+        ];
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        ctx.register_object_store(
+            "mem",
+            Arc::new(test::object_store::InMemoryObjectStore::new(&files)),
+        );
+        ctx.sql(
+            "CREATE EXTERNAL TABLE t (a INT, b VARCHAR) \
+             STORED AS CSV WITH HEADER ROW LOCATION 'mem://table/'",
+        )
+        .await?;
+        let plan = ctx.create_logical_plan("SELECT a, b FROM t WHERE a > 1")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let json = crate::physical_plan::serde::to_json(&plan)?;
 
-        assert_eq!(err.to_string(), "This feature is not implemented: SHOW SOMETHING_UNKNOWN not implemented. Supported syntax: SHOW <TABLES>");
-    }
+        // the executing process has its own store and batch size
+        let lambda = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_batch_size(1)
+                .with_memory_limit(1),
+        );
+        let runtime = lambda.runtime_env();
+        assert!(runtime.plan_from_json(&json).is_err());
+        lambda.register_object_store(
+            "mem",
+            Arc::new(test::object_store::InMemoryObjectStore::new(&files)),
+        );
+        let plan = lambda.runtime_env().plan_from_json(&json)?;
 
-    #[tokio::test]
-    async fn information_schema_columns_not_exist_by_default() {
-        let mut ctx = ExecutionContext::new();
+        fn find_scan(plan: &Arc<dyn ExecutionPlan>) -> Option<Arc<dyn ExecutionPlan>> {
+            if plan.as_any().is::<CsvExec>() {
+                Some(plan.clone())
+            } else {
+                plan.children().iter().find_map(find_scan)
+            }
+        }
+        let scan = find_scan(&plan).unwrap();
+        let scan = scan.as_any().downcast_ref::<CsvExec>().unwrap();
+        assert_eq!(scan.base_config().batch_size, 1);
 
-        let err = plan_and_collect(&mut ctx, "SELECT * from information_schema.columns")
-            .await
-            .unwrap_err();
-        assert_eq!(
-            err.to_string(),
-            "Error during planning: Table or CTE with name 'information_schema.columns' not found"
+        let batches = collect(plan, lambda.task_ctx()).await?;
+        assert!(batches.iter().all(|batch| batch.num_rows() <= 1));
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 2 | y |",
+            "| 3 | z |",
+            "+---+---+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // the memory limit is bound to the buffering operators
+        let plan = ctx.create_logical_plan("SELECT a FROM t ORDER BY a")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let plan = lambda.bind_runtime(plan)?;
+        let err = collect(plan, lambda.task_ctx()).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("more than the memory limit of 1 bytes"),
+            "{}",
+            err
         );
-    }
-
-    fn table_with_many_types() -> Arc<dyn TableProvider> {
-        let schema = Schema::new(vec![
-            Field::new("int32_col", DataType::Int32, false),
-            Field::new("float64_col", DataType::Float64, true),
-            Field::new("utf8_col", DataType::Utf8, true),
-            Field::new("large_utf8_col", DataType::LargeUtf8, false),
-            Field::new("binary_col", DataType::Binary, false),
-            Field::new("large_binary_col", DataType::LargeBinary, false),
-            Field::new(
-                "timestamp_nanos",
-                DataType::Timestamp(TimeUnit::Nanosecond, None),
-                false,
-            ),
-        ]);
 
-        let batch = RecordBatch::try_new(
-            Arc::new(schema.clone()),
-            vec![
-                Arc::new(Int32Array::from(vec![1])),
-                Arc::new(Float64Array::from(vec![1.0])),
-                Arc::new(StringArray::from(vec![Some("foo")])),
-                Arc::new(LargeStringArray::from(vec![Some("bar")])),
-                Arc::new(BinaryArray::from(vec![b"foo" as &[u8]])),
-                Arc::new(LargeBinaryArray::from(vec![b"foo" as &[u8]])),
-                Arc::new(TimestampNanosecondArray::from_opt_vec(
-                    vec![Some(123)],
-                    None,
-                )),
-            ],
-        )
-        .unwrap();
-        let provider = MemTable::try_new(Arc::new(schema), vec![vec![batch]]).unwrap();
-        Arc::new(provider)
+        Ok(())
     }
 
     #[tokio::test]
-    async fn information_schema_columns() {
+    async fn memory_pool_accounts_buffering_operators() -> Result<()> {
         let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().with_information_schema(true),
+            ExecutionConfig::new()
+                .with_target_partitions(1)
+                .with_memory_pool_size(1),
         );
-        let catalog = MemoryCatalogProvider::new();
-        let schema = MemorySchemaProvider::new();
-
-        schema
-            .register_table("t1".to_owned(), test::table_with_sequence(1, 1).unwrap())
-            .unwrap();
-
-        schema
-            .register_table("t2".to_owned(), table_with_many_types())
-            .unwrap();
-        catalog.register_schema("my_schema", Arc::new(schema));
-        ctx.register_catalog("my_catalog", Arc::new(catalog));
-
-        let result =
-            plan_and_collect(&mut ctx, "SELECT * from information_schema.columns")
-                .await
-                .unwrap();
-
-        let expected = vec![
-            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
-            "| table_catalog | table_schema | table_name | column_name      | ordinal_position | column_default | is_nullable | data_type                   | character_maximum_length | character_octet_length | numeric_precision | numeric_precision_radix | numeric_scale | datetime_precision | interval_type |",
-            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
-            "| my_catalog    | my_schema    | t1         | i                | 0                |                | YES         | Int32                       |                          |                        | 32                | 2                       |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | binary_col       | 4                |                | NO          | Binary                      |                          | 2147483647             |                   |                         |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | float64_col      | 1                |                | YES         | Float64                     |                          |                        | 24                | 2                       |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | int32_col        | 0                |                | NO          | Int32                       |                          |                        | 32                | 2                       |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | large_binary_col | 5                |                | NO          | LargeBinary                 |                          | 9223372036854775807    |                   |                         |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | large_utf8_col   | 3                |                | NO          | LargeUtf8                   |                          | 9223372036854775807    |                   |                         |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | timestamp_nanos  | 6                |                | NO          | Timestamp(Nanosecond, None) |                          |                        |                   |                         |               |                    |               |",
-            "| my_catalog    | my_schema    | t2         | utf8_col         | 2                |                | YES         | Utf8                        |                          | 2147483647             |                   |                         |               |                    |               |",
-            "+---------------+--------------+------------+------------------+------------------+----------------+-------------+-----------------------------+--------------------------+------------------------+-------------------+-------------------------+---------------+--------------------+---------------+",
+        ctx.register_table("t", test::table_with_sequence(1, 100)?)?;
+
+        // a single batch does not fit in the pool, the sort cannot spill
+        for sql in &[
+            "SELECT i FROM t ORDER BY i DESC",
+            "SELECT i, COUNT(*) FROM t GROUP BY i",
+            "SELECT t1.i FROM t t1 JOIN t t2 ON t1.i = t2.i",
+        ] {
+            let err = plan_and_collect(&mut ctx, sql).await.unwrap_err();
+            assert!(
+                err.to_string().contains("Resources exhausted"),
+                "{}: {}",
+                sql,
+                err
+            );
+        }
+        assert_eq!(ctx.memory_manager().unwrap().used(), 0);
+
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_target_partitions(1)
+                .with_memory_pool_size(1 << 20),
+        );
+        ctx.register_table("t", test::table_with_sequence(1, 3)?)?;
+        let results =
+            plan_and_collect(&mut ctx, "SELECT i FROM t ORDER BY i DESC").await?;
+        let expected = vec![
+            "+---+", //
+            "| i |", //
+            "+---+", //
+            "| 3 |", //
+            "| 2 |", //
+            "| 1 |", //
+            "+---+", //
         ];
-        assert_batches_sorted_eq!(expected, &result);
+        assert_batches_eq!(expected, &results);
+        let manager = ctx.memory_manager().unwrap();
+        assert_eq!(manager.pool_size(), 1 << 20);
+        assert_eq!(manager.used(), 0);
+        assert!(manager.usage().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn spill_dirs_configure_disk_manager() {
+        let dirs = vec![PathBuf::from("/spill/a"), PathBuf::from("/spill/b")];
+        let ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_spill_dirs(dirs.clone())
+                .with_max_spill_bytes(1 << 30),
+        );
+        assert_eq!(ctx.disk_manager().spill_dirs(), dirs.as_slice());
+        assert_eq!(ctx.runtime_env().disk_manager.spill_dirs(), dirs.as_slice());
+
+        let ctx = ExecutionContext::new();
+        assert_eq!(ctx.disk_manager().spill_dirs(), &[std::env::temp_dir()]);
     }
 
     #[tokio::test]
-    async fn disabled_default_catalog_and_schema() -> Result<()> {
+    async fn cancel_all_interrupts_running_queries() -> Result<()> {
         let mut ctx = ExecutionContext::with_config(
-            ExecutionConfig::new().create_default_catalog_and_schema(false),
+            ExecutionConfig::new().with_target_partitions(2),
         );
+        ctx.register_table("t", test::table_with_sequence(1, 100_000)?)?;
 
-        assert!(matches!(
-            ctx.register_table("test", test::table_with_sequence(1, 1)?),
-            Err(DataFusionError::Plan(_))
-        ));
+        // 10^10 joined rows, the query cannot complete before it is cancelled
+        let plan =
+            ctx.create_logical_plan("SELECT SUM(t1.i + t2.i) FROM t t1 CROSS JOIN t t2")?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
 
-        assert!(matches!(
-            ctx.sql("select * from datafusion.public.test").await,
-            Err(DataFusionError::Plan(_))
-        ));
+        let canceller = ctx.clone();
+        let cancelled_at = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            canceller.cancel_all();
+            std::time::Instant::now()
+        });
+        let task_ctx = ctx.task_ctx();
+        let err = collect(plan, task_ctx).await.unwrap_err();
+        let cancelled_at = cancelled_at.join().unwrap();
+        assert!(err.to_string().contains("Query cancelled"), "{}", err);
+        assert!(cancelled_at.elapsed() < Duration::from_secs(5));
+
+        // the queries planned afterwards are not cancelled
+        let results = plan_and_collect(&mut ctx, "SELECT SUM(i) FROM t").await?;
+        let expected = vec![
+            "+------------+",
+            "| SUM(t.i)   |",
+            "+------------+",
+            "| 5000050000 |",
+            "+------------+",
+        ];
+        assert_batches_eq!(expected, &results);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn custom_catalog_and_schema() -> Result<()> {
+    async fn cpu_bound_work_does_not_block_other_queries() -> Result<()> {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
         let mut ctx = ExecutionContext::with_config(
             ExecutionConfig::new()
-                .create_default_catalog_and_schema(false)
-                .with_default_catalog_and_schema("my_catalog", "my_schema"),
+                .with_target_partitions(1)
+                .with_cpu_threads(2),
         );
+        assert_eq!(ctx.cpu_executor().num_threads(), 2);
+        ctx.register_table("t", test::table_with_sequence(1, 10)?)?;
+        let sorting = Arc::new(AtomicBool::new(false));
+        let sort_started = sorting.clone();
+        ctx.register_udf(create_udf(
+            "slow",
+            vec![DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Immutable,
+            make_scalar_function(move |args: &[ArrayRef]| {
+                sort_started.store(true, Ordering::SeqCst);
+                thread::sleep(Duration::from_millis(500));
+                Ok(Arc::clone(&args[0]))
+            }),
+        ));
 
-        let catalog = MemoryCatalogProvider::new();
-        let schema = MemorySchemaProvider::new();
-        schema.register_table("test".to_owned(), test::table_with_sequence(1, 1)?)?;
-        catalog.register_schema("my_schema", Arc::new(schema));
-        ctx.register_catalog("my_catalog", Arc::new(catalog));
-
-        for table_ref in &["my_catalog.my_schema.test", "my_schema.test", "test"] {
-            let result = plan_and_collect(
-                &mut ctx,
-                &format!("SELECT COUNT(*) AS count FROM {}", table_ref),
-            )
-            .await?;
-
-            let expected = vec![
-                "+-------+",
-                "| count |",
-                "+-------+",
-                "| 1     |",
-                "+-------+",
-            ];
-            assert_batches_eq!(expected, &result);
+        // the sort evaluates the slow function on the CPU executor, the
+        // single thread of the test runtime keeps running the other query
+        let slow = ctx.sql("SELECT i FROM t ORDER BY slow(i) DESC").await?;
+        let slow = tokio::spawn(async move {
+            let results = slow.collect().await;
+            (results, std::time::Instant::now())
+        });
+        while !sorting.load(Ordering::SeqCst) {
+            tokio::task::yield_now().await;
         }
+        let fast = plan_and_collect(&mut ctx, "SELECT i FROM t WHERE i = 3").await?;
+        let fast_done = std::time::Instant::now();
+        let (slow, slow_done) = slow.await.unwrap();
+
+        assert!(fast_done < slow_done);
+        assert_eq!(fast[0].num_rows(), 1);
+        let slow = slow?;
+        assert_eq!(slow.iter().map(|batch| batch.num_rows()).sum::<usize>(), 10);
+        let first = slow[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .unwrap();
+        assert_eq!(first.value(0), 10);
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn cross_catalog_access() -> Result<()> {
-        let mut ctx = ExecutionContext::new();
+    async fn serialize_user_defined_functions() -> Result<()> {
+        use crate::physical_plan::LambdaExecPlan;
 
-        let catalog_a = MemoryCatalogProvider::new();
-        let schema_a = MemorySchemaProvider::new();
-        schema_a
-            .register_table("table_a".to_owned(), test::table_with_sequence(1, 1)?)?;
-        catalog_a.register_schema("schema_a", Arc::new(schema_a));
-        ctx.register_catalog("catalog_a", Arc::new(catalog_a));
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", test::table_with_sequence(1, 3)?)?;
+        ctx.register_udf(create_udf(
+            "my_add",
+            vec![DataType::Int32, DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Immutable,
+            make_scalar_function(|args: &[ArrayRef]| {
+                let l = args[0].as_any().downcast_ref::<Int32Array>().unwrap();
+                let r = args[1].as_any().downcast_ref::<Int32Array>().unwrap();
+                Ok(Arc::new(add(l, r)?) as ArrayRef)
+            }),
+        ));
+        ctx.register_udaf(create_udaf(
+            "my_avg",
+            DataType::Float64,
+            Arc::new(DataType::Float64),
+            Volatility::Immutable,
+            Arc::new(|| Ok(Box::new(AvgAccumulator::try_new(&DataType::Float64)?))),
+            Arc::new(vec![DataType::UInt64, DataType::Float64]),
+        ));
 
-        let catalog_b = MemoryCatalogProvider::new();
-        let schema_b = MemorySchemaProvider::new();
-        schema_b
-            .register_table("table_b".to_owned(), test::table_with_sequence(1, 2)?)?;
-        catalog_b.register_schema("schema_b", Arc::new(schema_b));
-        ctx.register_catalog("catalog_b", Arc::new(catalog_b));
+        let plan = ctx.create_logical_plan("SELECT my_add(i, i) AS d FROM t")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let json = crate::physical_plan::serde::to_json(&plan)?;
 
-        let result = plan_and_collect(
-            &mut ctx,
-            "SELECT cat, SUM(i) AS total FROM (
-                    SELECT i, 'a' AS cat FROM catalog_a.schema_a.table_a
-                    UNION ALL
-                    SELECT i, 'b' AS cat FROM catalog_b.schema_b.table_b
-                ) AS all
-                GROUP BY cat
-                ORDER BY cat
-                ",
+        let err = crate::physical_plan::from_json_with_registry(
+            &json,
+            &ExecutionContext::new().function_registry(),
         )
-        .await?;
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("There is no UDF named \"my_add\" in the registry"),
+            "{}",
+            err
+        );
+        let err = serde_json::from_str::<Arc<dyn ExecutionPlan>>(&json).unwrap_err();
+        assert!(err.to_string().contains(
+            "Cannot resolve the user-defined function \"my_add\" without a function registry"
+        ));
 
+        // the scanned batches are not serialized, they are fed to the plan
+        let mut plan = crate::physical_plan::from_json_with_registry(
+            &json,
+            &ctx.function_registry(),
+        )?;
+        Arc::get_mut(&mut plan)
+            .unwrap()
+            .feed_batches(vec![vec![test::make_partition(4)]])?;
         let expected = vec![
-            "+-----+-------+",
-            "| cat | total |",
-            "+-----+-------+",
-            "| a   | 1     |",
-            "| b   | 3     |",
-            "+-----+-------+",
+            "+---+", "| d |", "+---+", "| 0 |", "| 2 |", "| 4 |", "| 6 |", "+---+",
         ];
-        assert_batches_eq!(expected, &result);
+        let task_ctx = ctx.task_ctx();
+        assert_batches_sorted_eq!(expected, &collect(plan, task_ctx).await?);
+
+        let plan = ctx.create_logical_plan("SELECT my_avg(i) AS a FROM t")?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let bytes = crate::physical_plan::serde::to_bytes(&plan)?;
+        assert!(crate::physical_plan::serde::from_bytes(&bytes).is_err());
+        let plan = crate::physical_plan::serde::from_bytes_with_registry(
+            &bytes,
+            &ctx.function_registry(),
+        )?;
+        assert_eq!(plan.schema().field(0).name(), "a");
 
         Ok(())
     }
 
     #[tokio::test]
-    async fn create_external_table_with_timestamps() {
-        let mut ctx = ExecutionContext::new();
+    async fn merge_aggregation_states_across_contexts() -> Result<()> {
+        use crate::physical_plan::aggregates::{
+            STATE_LAYOUT_VERSION, STATE_LAYOUT_VERSION_KEY,
+        };
+        use crate::physical_plan::expressions::Column;
+        use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+        use crate::physical_plan::LambdaExecPlan;
+        use arrow::ipc::{reader::StreamReader, writer::StreamWriter};
+        use arrow::util::pretty::pretty_format_batches;
+
+        fn find_partial(plan: &Arc<dyn ExecutionPlan>) -> Option<Arc<dyn ExecutionPlan>> {
+            match plan.as_any().downcast_ref::<HashAggregateExec>() {
+                Some(agg) if *agg.mode() == AggregateMode::Partial => Some(plan.clone()),
+                _ => plan.children().iter().find_map(find_partial),
+            }
+        }
 
-        let data = "Jorge,2018-12-13T12:12:10.011Z\n\
-                    Andrew,2018-11-13T17:11:10.011Z";
+        fn ship_through_ipc(batches: &[RecordBatch]) -> Result<Vec<RecordBatch>> {
+            let mut bytes = vec![];
+            {
+                let mut writer = StreamWriter::try_new(&mut bytes, &batches[0].schema())?;
+                for batch in batches {
+                    writer.write(batch)?;
+                }
+                writer.finish()?;
+            }
+            let reader = StreamReader::try_new(std::io::Cursor::new(bytes))?;
+            Ok(reader.collect::<arrow::error::Result<Vec<_>>>()?)
+        }
 
-        let tmp_dir = TempDir::new().unwrap();
-        let file_path = tmp_dir.path().join("timestamps.csv");
+        // the rows of the output, regardless of the column names and row order
+        fn sorted_rows(batches: &[RecordBatch]) -> Result<Vec<String>> {
+            let mut rows = pretty_format_batches(batches)?
+                .lines()
+                .skip(3)
+                .filter(|line| !line.starts_with('+'))
+                .map(|line| line.to_owned())
+                .collect::<Vec<_>>();
+            rows.sort();
+            Ok(rows)
+        }
 
-        // scope to ensure the file is closed and written
-        {
-            File::create(&file_path)
-                .expect("creating temp file")
-                .write_all(data.as_bytes())
-                .expect("writing data");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("c", DataType::Utf8, false),
+            Field::new("v", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "a", "b", "b"])),
+                Arc::new(Int32Array::from(vec![1, 2, 3, 2, 5])),
+            ],
+        )?;
+
+        // a single partition keeps the order of ARRAY_AGG deterministic
+        let mut producer = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        producer.register_table(
+            "t",
+            Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+        )?;
+        let consumer = ExecutionContext::new();
+
+        let aggregates = "COUNT(v), SUM(v), MIN(v), MAX(v), AVG(v), \
+            APPROX_DISTINCT(v), APPROX_MEDIAN(v), ARRAY_AGG(v), COUNT(DISTINCT v), \
+            STDDEV(v)";
+        for sql in &[
+            format!("SELECT c, {} FROM t GROUP BY c", aggregates),
+            format!("SELECT {} FROM t", aggregates),
+        ] {
+            let single_stage = plan_and_collect(&mut producer, sql).await?;
+
+            // run the partial aggregation in the producing context
+            let plan = producer.create_logical_plan(sql)?;
+            let plan = producer
+                .create_physical_plan(&producer.optimize(&plan)?)
+                .await?;
+            let partial = find_partial(&plan).unwrap();
+            let states = collect(partial.clone(), producer.task_ctx()).await?;
+            let states = ship_through_ipc(&states)?;
+            assert_eq!(
+                states[0].schema().metadata().get(STATE_LAYOUT_VERSION_KEY),
+                Some(&STATE_LAYOUT_VERSION.to_string())
+            );
+
+            // and the final aggregation in the consuming one
+            let partial = partial
+                .as_any()
+                .downcast_ref::<HashAggregateExec>()
+                .unwrap();
+            let final_group = partial
+                .group_expr()
+                .iter()
+                .enumerate()
+                .map(|(i, (_, name))| {
+                    (
+                        Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>,
+                        name.clone(),
+                    )
+                })
+                .collect();
+            let final_agg: Arc<dyn ExecutionPlan> = Arc::new(HashAggregateExec::try_new(
+                AggregateMode::Final,
+                final_group,
+                partial.aggr_expr().to_vec(),
+                Arc::new(MemoryExec::try_new(&[], partial.schema(), None)?),
+                partial.input_schema(),
+            )?);
+            let json = crate::physical_plan::serde::to_json(&final_agg)?;
+            let mut final_agg = crate::physical_plan::from_json_with_registry(
+                &json,
+                &consumer.function_registry(),
+            )?;
+            Arc::get_mut(&mut final_agg)
+                .unwrap()
+                .feed_batches(vec![states])?;
+            let two_stage = collect(final_agg, consumer.task_ctx()).await?;
+
+            assert_eq!(sorted_rows(&single_stage)?, sorted_rows(&two_stage)?);
+            assert!(!two_stage.is_empty());
         }
 
-        let sql = format!(
-            "CREATE EXTERNAL TABLE csv_with_timestamps (
-                  name VARCHAR,
-                  ts TIMESTAMP
-              )
-              STORED AS CSV
-              LOCATION '{}'
-              ",
-            file_path.to_str().expect("path is utf8")
+        // states with an unknown layout are rejected
+        let plan = producer.create_logical_plan("SELECT COUNT(v) FROM t")?;
+        let plan = producer
+            .create_physical_plan(&producer.optimize(&plan)?)
+            .await?;
+        let partial = find_partial(&plan).unwrap();
+        let partial = partial
+            .as_any()
+            .downcast_ref::<HashAggregateExec>()
+            .unwrap();
+        let metadata = [(STATE_LAYOUT_VERSION_KEY.to_owned(), "999".to_owned())]
+            .iter()
+            .cloned()
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(
+            partial.schema().fields().clone(),
+            metadata,
+        ));
+        let err = HashAggregateExec::try_new(
+            AggregateMode::Final,
+            vec![],
+            partial.aggr_expr().to_vec(),
+            Arc::new(MemoryExec::try_new(&[], schema, None)?),
+            partial.input_schema(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Unsupported aggregation state layout version 999, expected 2"
         );
 
-        plan_and_collect(&mut ctx, &sql)
-            .await
-            .expect("Executing CREATE EXTERNAL TABLE");
+        Ok(())
+    }
 
-        let sql = "SELECT * from csv_with_timestamps";
-        let result = plan_and_collect(&mut ctx, sql).await.unwrap();
+    #[test]
+    fn default_optimizer_rules_by_name() {
+        let config = ExecutionConfig::new();
+        let names = config
+            .optimizer_rules()
+            .iter()
+            .map(|rule| rule.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "simplify_expressions",
+                "common_sub_expression_eliminate",
+                "eliminate_limit",
+                "projection_push_down",
+                "filter_push_down",
+                "join_reorder",
+                "limit_push_down",
+                "single_distinct_to_group_by",
+            ]
+        );
+        let names = config
+            .physical_optimizers
+            .iter()
+            .map(|rule| rule.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "aggregate_statistics",
+                "eliminate_redundant",
+                "aggregate_push_down",
+                "hash_build_probe_order",
+                "coalesce_batches",
+                "repartition",
+                "add_merge_exec",
+                "merge_selection",
+                "dynamic_filter_push_down",
+            ]
+        );
+    }
+
+    /// Replaces the calls to the `marker` UDF by their argument plus 100
+    struct MarkerRule {}
+
+    impl OptimizerRule for MarkerRule {
+        fn optimize(
+            &self,
+            plan: &LogicalPlan,
+            execution_props: &ExecutionProps,
+        ) -> Result<LogicalPlan> {
+            struct MarkerRewriter {}
+
+            impl ExprRewriter for MarkerRewriter {
+                fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+                    Ok(match expr {
+                        Expr::ScalarUDF { fun, args } if fun.name == "marker" => {
+                            args[0].clone() + lit(100)
+                        }
+                        expr => expr,
+                    })
+                }
+            }
+
+            let inputs = plan
+                .inputs()
+                .into_iter()
+                .map(|input| self.optimize(input, execution_props))
+                .collect::<Result<Vec<_>>>()?;
+            let expr = plan
+                .expressions()
+                .into_iter()
+                .map(|expr| expr.rewrite(&mut MarkerRewriter {}))
+                .collect::<Result<Vec<_>>>()?;
+            crate::optimizer::utils::from_plan(plan, &expr, &inputs)
+        }
+
+        fn name(&self) -> &str {
+            "marker"
+        }
+    }
+
+    struct PanickingRule {}
+
+    impl PhysicalOptimizerRule for PanickingRule {
+        fn optimize(
+            &self,
+            _plan: Arc<dyn ExecutionPlan>,
+            _config: &ExecutionConfig,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            panic!("unsupported plan")
+        }
+
+        fn name(&self) -> &str {
+            "panicking"
+        }
+    }
+
+    fn marker_context(config: ExecutionConfig) -> ExecutionContext {
+        let mut ctx = ExecutionContext::with_config(config);
+        ctx.register_table("t", test::table_with_sequence(1, 3).unwrap())
+            .unwrap();
+        let identity = make_scalar_function(|args: &[ArrayRef]| Ok(Arc::clone(&args[0])));
+        ctx.register_udf(create_udf(
+            "marker",
+            vec![DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Immutable,
+            identity,
+        ));
+        ctx
+    }
+
+    #[tokio::test]
+    async fn custom_optimizer_rule() -> Result<()> {
+        let sql = "SELECT marker(i) AS m FROM t";
+
+        let mut ctx = marker_context(ExecutionConfig::new());
         let expected = vec![
-            "+--------+-------------------------+",
-            "| name   | ts                      |",
-            "+--------+-------------------------+",
-            "| Andrew | 2018-11-13 17:11:10.011 |",
-            "| Jorge  | 2018-12-13 12:12:10.011 |",
-            "+--------+-------------------------+",
+            "+---+", "| m |", "+---+", "| 1 |", "| 2 |", "| 3 |", "+---+",
         ];
-        assert_batches_sorted_eq!(expected, &result);
+        assert_batches_eq!(expected, &plan_and_collect(&mut ctx, sql).await?);
+
+        // the custom rule runs after the default rules
+        let config = ExecutionConfig::new().add_optimizer_rule(Arc::new(MarkerRule {}));
+        let names = config.optimizer_rules().iter().map(|rule| rule.name());
+        assert_eq!(names.last(), Some("marker"));
+
+        let mut ctx = marker_context(config);
+        let expected = vec![
+            "+-----+", "| m   |", "+-----+", "| 101 |", "| 102 |", "| 103 |", "+-----+",
+        ];
+        assert_batches_eq!(expected, &plan_and_collect(&mut ctx, sql).await?);
+
+        // a failing rule is reported by name
+        let config = ExecutionConfig::new()
+            .add_physical_optimizer_rule(Arc::new(PanickingRule {}));
+        let mut ctx = marker_context(config);
+        let err = plan_and_collect(&mut ctx, sql).await.unwrap_err();
+        assert_contains!(
+            err.to_string(),
+            "Optimizer rule 'panicking' failed: unsupported plan"
+        );
+
+        // unless it is disabled
+        let config = ExecutionConfig::new()
+            .add_physical_optimizer_rule(Arc::new(PanickingRule {}))
+            .with_disabled_rules(&["panicking"]);
+        let mut ctx = marker_context(config);
+        plan_and_collect(&mut ctx, sql).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disabled_optimizer_rules() -> Result<()> {
+        let sql = "SELECT i FROM t WHERE i > 1 LIMIT 0";
+
+        let ctx = marker_context(ExecutionConfig::new());
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        assert_eq!(format!("{:?}", plan), "EmptyRelation");
+
+        let ctx = marker_context(
+            ExecutionConfig::new().with_disabled_rules(&["eliminate_limit"]),
+        );
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        assert_contains!(format!("{:?}", plan), "Limit: 0");
+
+        let sql = "SELECT i FROM t WHERE i > 1";
+        let ctx =
+            marker_context(ExecutionConfig::new().with_coalesce_filter_output(false));
+        let plan = ctx
+            .create_physical_plan(&ctx.create_logical_plan(sql)?)
+            .await?;
+        assert_contains!(
+            format!("{}", displayable(plan.as_ref()).indent()),
+            "CoalesceBatchesExec"
+        );
+
+        let ctx = marker_context(
+            ExecutionConfig::new()
+                .with_coalesce_filter_output(false)
+                .with_disabled_rules(&["coalesce_batches"]),
+        );
+        let plan = ctx
+            .create_physical_plan(&ctx.create_logical_plan(sql)?)
+            .await?;
+        assert_not_contains!(
+            format!("{}", displayable(plan.as_ref()).indent()),
+            "CoalesceBatchesExec"
+        );
+        Ok(())
     }
 
     #[tokio::test]
@@ -4194,7 +7075,9 @@ mod tests {
         let logical_plan = ctx.create_logical_plan(sql)?;
         let logical_plan = ctx.optimize(&logical_plan)?;
         let physical_plan = ctx.create_physical_plan(&logical_plan).await?;
-        ctx.write_csv(physical_plan, out_dir.to_string()).await
+        ctx.write_csv(physical_plan, out_dir.to_string())
+            .await
+            .map(|_| ())
     }
 
     /// Execute SQL and write results to partitioned parquet files
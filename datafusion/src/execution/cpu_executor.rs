@@ -0,0 +1,324 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Thread pool running the CPU-bound work of the operators.
+//!
+//! Decoding files, sorting and building hash tables keep their thread busy
+//! until they are done. Run on the threads of the tokio runtime, they stall
+//! the other tasks of the runtime, including the other queries of the
+//! context. The operators hand this work to the [`CpuExecutor`] they are
+//! bound to instead, and receive its results through channels.
+//!
+//! A file scan decodes its batches in a single task, which blocks while the
+//! channel to its output stream is full. So that a consumer waiting on
+//! several scans cannot starve the pool, another thread is started in its
+//! place while a task is blocked.
+
+use std::fmt;
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+use lazy_static::lazy_static;
+use tokio::runtime::Handle;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc as batch_mpsc, oneshot};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::stream::RecordBatchReceiverStream;
+use crate::physical_plan::SendableRecordBatchStream;
+
+type Task = Box<dyn FnOnce() + Send>;
+
+lazy_static! {
+    /// Executor shared by the contexts and operators not configured with
+    /// their own, with a thread per core
+    static ref DEFAULT_EXECUTOR: CpuExecutor = CpuExecutor::new(num_cpus::get());
+}
+
+/// Pool of threads running CPU-bound tasks, started on the first task. The
+/// threads exit once all the clones of the executor are dropped.
+#[derive(Clone)]
+pub struct CpuExecutor {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    /// Number of threads running tasks, not counting the blocked ones
+    num_threads: usize,
+    /// Queue of the tasks, shared by the threads
+    receiver: Arc<Mutex<mpsc::Receiver<Task>>>,
+    state: Mutex<PoolState>,
+}
+
+struct PoolState {
+    sender: mpsc::Sender<Task>,
+    /// Number of threads started
+    threads: usize,
+    /// Number of threads blocked in [`BatchSender::send`]
+    blocked: usize,
+}
+
+impl Default for CpuExecutor {
+    fn default() -> Self {
+        DEFAULT_EXECUTOR.clone()
+    }
+}
+
+impl fmt::Debug for CpuExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CpuExecutor")
+            .field("num_threads", &self.inner.num_threads)
+            .finish()
+    }
+}
+
+impl CpuExecutor {
+    /// Create an executor running up to `num_threads` tasks at once, at least
+    /// one
+    pub fn new(num_threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            inner: Arc::new(Inner {
+                num_threads: num_threads.max(1),
+                receiver: Arc::new(Mutex::new(receiver)),
+                state: Mutex::new(PoolState {
+                    sender,
+                    threads: 0,
+                    blocked: 0,
+                }),
+            }),
+        }
+    }
+
+    /// Number of tasks run at once
+    pub fn num_threads(&self) -> usize {
+        self.inner.num_threads
+    }
+
+    /// Number of threads started, including the ones started in place of
+    /// blocked tasks
+    pub fn started_threads(&self) -> usize {
+        self.inner.state.lock().unwrap().threads
+    }
+
+    /// Run `f` on the pool, returning a future of its result. The future
+    /// fails with [`DataFusionError::Execution`] if `f` panics. `f` runs even
+    /// if the future is dropped.
+    pub fn spawn<F, T>(&self, f: F) -> impl Future<Output = Result<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        self.submit(Box::new(move || {
+            // failing here is OK, the receiver is gone and does not care about the result
+            tx.send(f()).ok();
+        }));
+        async move {
+            rx.await.map_err(|_| {
+                DataFusionError::Execution("task of the CPU executor panicked".to_owned())
+            })
+        }
+    }
+
+    /// Run `produce` on the pool, returning the stream of the batches it
+    /// sends. A panic of `produce` ends the stream with an error.
+    pub fn spawn_batches<F>(
+        &self,
+        schema: &SchemaRef,
+        produce: F,
+    ) -> SendableRecordBatchStream
+    where
+        F: FnOnce(&BatchSender) + Send + 'static,
+    {
+        let (tx, rx) = batch_mpsc::channel(2);
+        let sender = BatchSender {
+            tx: tx.clone(),
+            executor: self.clone(),
+        };
+        let task = self.spawn(move || produce(&sender));
+        let join_handle = tokio::spawn(async move {
+            if let Err(e) = task.await {
+                tx.send(Err(ArrowError::ExternalError(Box::new(e))))
+                    .await
+                    .ok();
+            }
+        });
+        RecordBatchReceiverStream::create(schema, rx, join_handle)
+    }
+
+    /// Queue `task`, starting the threads if they are not started yet. The
+    /// task runs in the context of the tokio runtime of the caller, if any,
+    /// so that the object stores can use it.
+    fn submit(&self, task: Task) {
+        let runtime = Handle::try_current().ok();
+        let task: Task = Box::new(move || {
+            let _guard = runtime.as_ref().map(Handle::enter);
+            task()
+        });
+        let mut state = self.inner.state.lock().unwrap();
+        self.inner.start_threads(&mut state);
+        // the threads hold the receiver until the executor is dropped
+        state.sender.send(task).ok();
+    }
+
+    /// Run `f`, which blocks the thread of the pool calling it, while another
+    /// thread runs the queued tasks
+    fn block<T>(&self, f: impl FnOnce() -> T) -> T {
+        {
+            let mut state = self.inner.state.lock().unwrap();
+            state.blocked += 1;
+            self.inner.start_threads(&mut state);
+        }
+        let result = f();
+        self.inner.state.lock().unwrap().blocked -= 1;
+        result
+    }
+}
+
+impl Inner {
+    /// Start threads until `num_threads` of them are not blocked
+    fn start_threads(&self, state: &mut PoolState) {
+        while state.threads - state.blocked < self.num_threads {
+            let receiver = self.receiver.clone();
+            thread::Builder::new()
+                .name(format!("datafusion-cpu-{}", state.threads))
+                .spawn(move || loop {
+                    let task = match receiver.lock().unwrap().recv() {
+                        Ok(task) => task,
+                        // the executor is dropped
+                        Err(_) => break,
+                    };
+                    // a panic is reported by the dropped result channel
+                    panic::catch_unwind(AssertUnwindSafe(task)).ok();
+                })
+                .expect("failed to start a thread of the CPU executor");
+            state.threads += 1;
+        }
+    }
+}
+
+/// Sender of the batches of a task started by [`CpuExecutor::spawn_batches`]
+pub struct BatchSender {
+    tx: batch_mpsc::Sender<ArrowResult<RecordBatch>>,
+    executor: CpuExecutor,
+}
+
+impl BatchSender {
+    /// Send `batch` to the output stream, waiting while the stream is full.
+    /// Returns false if the stream is dropped, in which case the task can
+    /// stop producing batches.
+    pub fn send(&self, batch: ArrowResult<RecordBatch>) -> bool {
+        match self.tx.try_send(batch) {
+            Ok(()) => true,
+            Err(TrySendError::Full(batch)) => {
+                self.executor.block(|| self.tx.blocking_send(batch).is_ok())
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::make_partition;
+    use futures::StreamExt;
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn spawn_runs_on_pool() -> Result<()> {
+        let executor = CpuExecutor::new(2);
+        assert_eq!(executor.started_threads(), 0);
+
+        let name = executor
+            .spawn(|| thread::current().name().map(str::to_owned))
+            .await?;
+        assert!(name.unwrap().starts_with("datafusion-cpu-"));
+        assert_eq!(executor.started_threads(), 2);
+
+        let err = executor.spawn(|| panic!("boom")).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: task of the CPU executor panicked"
+        );
+        // the thread survives the panic
+        assert_eq!(executor.spawn(|| 1 + 1).await?, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tasks_run_concurrently() -> Result<()> {
+        let executor = CpuExecutor::new(2);
+        let (tx, rx) = channel();
+        // the first task only completes once the second one ran
+        let first = executor.spawn(move || rx.recv_timeout(Duration::from_secs(5)));
+        let second = executor.spawn(move || tx.send(()));
+        second.await?.unwrap();
+        first.await?.unwrap();
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn blocked_producers_do_not_starve_pool() -> Result<()> {
+        let executor = CpuExecutor::new(1);
+        let batch = make_partition(3);
+        let schema = batch.schema();
+        let streams = (0..3)
+            .map(|_| {
+                let batch = batch.clone();
+                executor.spawn_batches(&schema, move |sender| {
+                    for _ in 0..4 {
+                        if !sender.send(Ok(batch.clone())) {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // all the streams must produce their first batch before any produces
+        // its last one, which blocks the producers on full streams
+        let mut streams = streams;
+        for stream in streams.iter_mut() {
+            assert_eq!(stream.next().await.unwrap()?.num_rows(), 3);
+        }
+        for stream in streams {
+            assert_eq!(stream.count().await, 3);
+        }
+        assert!(executor.started_threads() > 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn producer_panic_fails_stream() {
+        let executor = CpuExecutor::new(1);
+        let schema = make_partition(1).schema();
+        let mut stream = executor.spawn_batches(&schema, |_| panic!("boom"));
+        let err = stream.next().await.unwrap().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("task of the CPU executor panicked"));
+        assert!(stream.next().await.is_none());
+    }
+}
@@ -17,26 +17,38 @@
 
 //! Implementation of DataFrame API.
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+use crate::arrow::array::{ArrayRef, StringArray};
+use crate::arrow::compute;
+use crate::arrow::datatypes::{DataType, Field, Schema};
 use crate::arrow::record_batch::RecordBatch;
-use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::execution::context::{ExecutionContext, ExecutionContextState};
+use crate::execution::options::{AlignOptions, CsvWriteOptions};
+use crate::execution::result_cache::ResultKey;
+use crate::execution::task_context::TaskContext;
 use crate::logical_plan::{
-    col, DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, LogicalPlanBuilder,
+    approx_median, avg, cast, col, count, count_distinct, lit, max, min, stddev, DFField,
+    DFSchema, Expr, FunctionRegistry, JoinType, LogicalPlan, LogicalPlanBuilder,
     Partitioning,
 };
+use crate::physical_plan::type_coercion::can_coerce_from;
+use crate::scalar::ScalarValue;
 use crate::{
     dataframe::*,
     physical_plan::{collect, collect_partitioned},
 };
 
 use crate::arrow::util::pretty;
+use crate::physical_plan::file_format::plan_to_csv;
 use crate::physical_plan::{
     execute_stream, execute_stream_partitioned, ExecutionPlan, SendableRecordBatchStream,
 };
 use crate::sql::utils::find_window_exprs;
 use async_trait::async_trait;
+use log::warn;
 
 /// Implementation of DataFrame API
 pub struct DataFrameImpl {
@@ -60,6 +72,11 @@ impl DataFrameImpl {
         let plan = ctx.optimize(&self.plan)?;
         ctx.create_physical_plan(&plan).await
     }
+
+    /// Create the context executing the physical plans of this DataFrame
+    fn task_ctx(&self) -> Arc<TaskContext> {
+        ExecutionContext::from(self.ctx_state.clone()).task_ctx()
+    }
 }
 
 #[async_trait]
@@ -161,8 +178,25 @@ impl DataFrame for DataFrameImpl {
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, collecting all resulting batches into memory
     async fn collect(&self) -> Result<Vec<RecordBatch>> {
+        let result_cache = self.ctx_state.lock().unwrap().result_cache.clone();
+        let cached = match &result_cache {
+            Some(result_cache) => {
+                ResultKey::try_new(&self.plan)?.map(|key| (result_cache.clone(), key))
+            }
+            None => None,
+        };
+        if let Some((result_cache, key)) = &cached {
+            if let Some(batches) = result_cache.get(key) {
+                return Ok(batches);
+            }
+        }
+
         let plan = self.create_physical_plan().await?;
-        Ok(collect(plan).await?)
+        let batches = collect(plan, self.task_ctx()).await?;
+        if let Some((result_cache, key)) = cached {
+            result_cache.insert(key, batches.clone());
+        }
+        Ok(batches)
     }
 
     /// Print results.
@@ -177,11 +211,116 @@ impl DataFrame for DataFrameImpl {
         Ok(pretty::print_batches(&results)?)
     }
 
+    async fn describe(&self) -> Result<Arc<dyn DataFrame>> {
+        let schema = self.plan.schema();
+        let mut columns = vec![];
+        let mut skipped = vec![];
+        for field in schema.fields() {
+            // the columns sharing a name, e.g. after a join, keep their qualifier
+            let ambiguous = schema
+                .fields()
+                .iter()
+                .filter(|other| other.name() == field.name())
+                .count()
+                > 1;
+            let name = if ambiguous {
+                field.qualified_name()
+            } else {
+                field.name().clone()
+            };
+            match Summary::of(field.data_type()) {
+                Some(summary) => columns.push((name, summary, field.qualified_column())),
+                None => skipped.push(name),
+            }
+        }
+        if !skipped.is_empty() {
+            warn!("describe skips the nested columns {}", skipped.join(", "));
+        }
+
+        // a single aggregation computes the statistics of all the columns
+        let alias = |statistic: &str, i: usize| format!("{}_{}", statistic, i);
+        let mut aggr_expr = vec![count(lit(1u8)).alias("rows")];
+        for (i, (_, summary, column)) in columns.iter().enumerate() {
+            let column = Expr::Column(column.clone());
+            aggr_expr.push(count(column.clone()).alias(&alias("count", i)));
+            if *summary == Summary::Counts {
+                continue;
+            }
+            aggr_expr.push(min(column.clone()).alias(&alias("min", i)));
+            aggr_expr.push(max(column.clone()).alias(&alias("max", i)));
+            if *summary == Summary::Text {
+                aggr_expr.push(
+                    count_distinct(column.clone()).alias(&alias("distinct_count", i)),
+                );
+                continue;
+            }
+            aggr_expr.push(avg(column.clone()).alias(&alias("mean", i)));
+            aggr_expr.push(approx_median(column.clone()).alias(&alias("median", i)));
+            aggr_expr.push(stddev(column).alias(&alias("std", i)));
+        }
+        let batches = self.aggregate(vec![], aggr_expr)?.collect().await?;
+        let batch = batches
+            .iter()
+            .find(|batch| batch.num_rows() == 1)
+            .ok_or_else(|| {
+                DataFusionError::Internal(
+                    "The aggregation of describe produced no row".to_owned(),
+                )
+            })?;
+        let scalar = |name: &str| -> Result<ScalarValue> {
+            let index = batch.schema().index_of(name)?;
+            ScalarValue::try_from_array(batch.column(index), 0)
+        };
+        let rows = count_value(&scalar("rows")?);
+
+        let mut fields = vec![Field::new("describe", DataType::Utf8, false)];
+        let mut arrays: Vec<ArrayRef> =
+            vec![Arc::new(StringArray::from(DESCRIBE_STATISTICS.to_vec()))];
+        for (i, (name, summary, _)) in columns.iter().enumerate() {
+            let data_type = summary.data_type();
+            let count = scalar(&alias("count", i))?;
+            let values = DESCRIBE_STATISTICS
+                .iter()
+                .map(|statistic| {
+                    let value = match (*statistic, summary) {
+                        ("count", _) => count.clone(),
+                        ("null_count", _) => {
+                            ScalarValue::UInt64(Some(rows - count_value(&count)))
+                        }
+                        ("min" | "max", Summary::Numeric | Summary::Text)
+                        | ("mean" | "median" | "std", Summary::Numeric)
+                        | ("distinct_count", Summary::Text) => {
+                            scalar(&alias(statistic, i))?
+                        }
+                        _ => ScalarValue::try_from(&data_type)?,
+                    };
+                    Ok(compute::cast(&value.to_array(), &data_type)?)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let values = values
+                .iter()
+                .map(|value| value.as_ref())
+                .collect::<Vec<_>>();
+            arrays.push(compute::concat(&values)?);
+            fields.push(Field::new(name, data_type, true));
+        }
+
+        let mut metadata = HashMap::new();
+        if !skipped.is_empty() {
+            metadata.insert("describe.skipped".to_owned(), skipped.join(", "));
+        }
+        let schema = Arc::new(Schema::new_with_metadata(fields, metadata));
+        let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+        let plan =
+            LogicalPlanBuilder::scan_memory(vec![vec![batch]], schema, None)?.build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, returning a stream over a single partition
     async fn execute_stream(&self) -> Result<SendableRecordBatchStream> {
         let plan = self.create_physical_plan().await?;
-        execute_stream(plan).await
+        execute_stream(plan, self.task_ctx()).await
     }
 
     /// Convert the logical plan represented by this DataFrame into a physical plan and
@@ -189,14 +328,25 @@ impl DataFrame for DataFrameImpl {
     /// partitioning
     async fn collect_partitioned(&self) -> Result<Vec<Vec<RecordBatch>>> {
         let plan = self.create_physical_plan().await?;
-        Ok(collect_partitioned(plan).await?)
+        Ok(collect_partitioned(plan, self.task_ctx()).await?)
     }
 
     /// Convert the logical plan represented by this DataFrame into a physical plan and
     /// execute it, returning a stream for each partition
     async fn execute_stream_partitioned(&self) -> Result<Vec<SendableRecordBatchStream>> {
         let plan = self.create_physical_plan().await?;
-        Ok(execute_stream_partitioned(plan).await?)
+        Ok(execute_stream_partitioned(plan, self.task_ctx()).await?)
+    }
+
+    /// Convert the logical plan represented by this DataFrame into a physical plan and
+    /// write the output partitions to CSV files
+    async fn write_csv(
+        &self,
+        path: &str,
+        options: &CsvWriteOptions,
+    ) -> Result<Vec<String>> {
+        let plan = self.create_physical_plan().await?;
+        plan_to_csv(plan, path, options, self.task_ctx()).await
     }
 
     /// Returns the schema from the logical plan
@@ -249,6 +399,182 @@ impl DataFrame for DataFrameImpl {
             &LogicalPlanBuilder::except(left_plan, right_plan, true)?,
         )))
     }
+
+    fn align_schema(
+        &self,
+        target: &Schema,
+        options: AlignOptions,
+    ) -> Result<Arc<dyn DataFrame>> {
+        let expr = align_exprs(self.plan.schema(), target, &options)?;
+        let plan = LogicalPlanBuilder::from(self.to_logical_plan())
+            .project(expr)?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+
+    fn alias(&self, alias: &str) -> Result<Arc<dyn DataFrame>> {
+        let expr = self
+            .plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()));
+        let plan = LogicalPlanBuilder::from(self.to_logical_plan())
+            .project_with_alias(expr, Some(alias.to_owned()))?
+            .build()?;
+        Ok(Arc::new(DataFrameImpl::new(self.ctx_state.clone(), &plan)))
+    }
+}
+
+/// The statistics of [`DataFrame::describe`], in the order of its rows
+const DESCRIBE_STATISTICS: [&str; 8] = [
+    "count",
+    "null_count",
+    "distinct_count",
+    "mean",
+    "std",
+    "min",
+    "max",
+    "median",
+];
+
+/// How [`DataFrame::describe`] summarizes a column
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Summary {
+    /// All the statistics but distinct_count, as doubles
+    Numeric,
+    /// All the statistics but mean, std and median, as strings
+    Text,
+    /// count and null_count, as strings
+    Counts,
+}
+
+impl Summary {
+    /// The summary of a column of type `data_type`, `None` if it is nested
+    fn of(data_type: &DataType) -> Option<Self> {
+        match data_type {
+            DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64 => Some(Summary::Numeric),
+            DataType::Utf8 | DataType::LargeUtf8 => Some(Summary::Text),
+            DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Struct(_)
+            | DataType::Union(_)
+            | DataType::Map(_, _) => None,
+            _ => Some(Summary::Counts),
+        }
+    }
+
+    /// The type of the statistics of the column
+    fn data_type(&self) -> DataType {
+        match self {
+            Summary::Numeric => DataType::Float64,
+            Summary::Text | Summary::Counts => DataType::Utf8,
+        }
+    }
+}
+
+/// The value of a COUNT, zero if it is null
+fn count_value(count: &ScalarValue) -> u64 {
+    match count {
+        ScalarValue::UInt64(Some(count)) => *count,
+        _ => 0,
+    }
+}
+
+/// The projection aligning the columns of `schema` to `target`, see
+/// [`DataFrame::align_schema`]
+fn align_exprs(
+    schema: &DFSchema,
+    target: &Schema,
+    options: &AlignOptions,
+) -> Result<Vec<Expr>> {
+    let renamed = |field: &DFField| -> String {
+        options
+            .renames
+            .get(field.name())
+            .unwrap_or_else(|| field.name())
+            .clone()
+    };
+    let matches = |field: &DFField, target_name: &str| {
+        if options.case_insensitive {
+            renamed(field).eq_ignore_ascii_case(target_name)
+        } else {
+            renamed(field) == target_name
+        }
+    };
+
+    let mut expr = vec![];
+    let mut problems = vec![];
+    for target_field in target.fields() {
+        let name = target_field.name();
+        let data_type = target_field.data_type();
+        let sources = schema
+            .fields()
+            .iter()
+            .filter(|field| matches(field, name))
+            .collect::<Vec<_>>();
+        match sources.as_slice() {
+            [] if options.fill_missing && target_field.is_nullable() => {
+                expr.push(Expr::Literal(ScalarValue::try_from(data_type)?).alias(name))
+            }
+            [] => problems.push(format!("{} is missing", name)),
+            [source] if source.data_type() == data_type => {
+                let column = Expr::Column(source.qualified_column());
+                expr.push(if source.name() == name {
+                    column
+                } else {
+                    column.alias(name)
+                })
+            }
+            [source]
+                if options.coerce && can_coerce_from(data_type, source.data_type()) =>
+            {
+                let column = Expr::Column(source.qualified_column());
+                expr.push(cast(column, data_type.clone()).alias(name))
+            }
+            [source] => problems.push(format!(
+                "{} has the type {:?}, which can't be coerced to {:?}",
+                source.name(),
+                source.data_type(),
+                data_type
+            )),
+            sources => problems.push(format!(
+                "{} matches several columns: {}",
+                name,
+                sources
+                    .iter()
+                    .map(|field| field.qualified_name())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+        }
+    }
+    if !options.drop_extra {
+        for field in schema.fields() {
+            if !target.fields().iter().any(|t| matches(field, t.name())) {
+                problems.push(format!("{} is not in the target schema", field.name()));
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(expr)
+    } else {
+        Err(DataFusionError::Plan(format!(
+            "Cannot align the DataFrame to the target schema:\n  {}",
+            problems.join("\n  ")
+        )))
+    }
 }
 
 #[cfg(test)]
@@ -256,13 +582,19 @@ mod tests {
     use std::vec;
 
     use super::*;
+    use crate::datasource::MemTable;
     use crate::execution::options::CsvReadOptions;
     use crate::physical_plan::functions::ScalarFunctionImplementation;
     use crate::physical_plan::functions::Volatility;
     use crate::physical_plan::{window_functions, ColumnarValue};
+    use crate::test::columns;
     use crate::{assert_batches_sorted_eq, execution::context::ExecutionContext};
     use crate::{logical_plan::*, test_util};
-    use arrow::datatypes::DataType;
+    use arrow::array::{
+        Array, ArrayRef, BooleanArray, Float32Array, Float64Array, Int32Array,
+        Int64Array, ListArray, StringArray,
+    };
+    use arrow::datatypes::{DataType, Field, Int32Type};
 
     #[tokio::test]
     async fn select_columns() -> Result<()> {
@@ -321,6 +653,29 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn select_struct() -> Result<()> {
+        // build plan using Table API
+        let t = test_table().await?;
+        let t2 = t.select(vec![
+            struct_(vec![col("c1"), col("c2")]).alias("pair"),
+            named_struct(vec![("x", col("c3")), ("y", lit(1_i64))]),
+        ])?;
+        let plan = t2.to_logical_plan();
+
+        // build query using SQL
+        let sql_plan = create_plan(
+            "SELECT struct(c1, c2) AS pair, named_struct('x', c3, 'y', 1) \
+             FROM aggregate_test_100",
+        )
+        .await?;
+
+        // the two plans should be identical
+        assert_same_plan(&plan, &sql_plan);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn aggregate() -> Result<()> {
         // build plan using DataFrame API
@@ -484,6 +839,332 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn align_schema_of_drifted_frames() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        register_columns(
+            &mut ctx,
+            "t1",
+            vec![
+                ("id", Arc::new(Int64Array::from(vec![1, 2])) as ArrayRef),
+                (
+                    "name",
+                    Arc::new(StringArray::from(vec!["a", "b"])) as ArrayRef,
+                ),
+                (
+                    "score",
+                    Arc::new(Float64Array::from(vec![1.5, 2.5])) as ArrayRef,
+                ),
+            ],
+        )?;
+        register_columns(
+            &mut ctx,
+            "t2",
+            vec![
+                ("Score", Arc::new(Float32Array::from(vec![3.5])) as ArrayRef),
+                ("ID", Arc::new(Int32Array::from(vec![3])) as ArrayRef),
+                ("Name", Arc::new(StringArray::from(vec!["c"])) as ArrayRef),
+            ],
+        )?;
+        register_columns(
+            &mut ctx,
+            "t3",
+            vec![
+                ("user_id", Arc::new(Int64Array::from(vec![4])) as ArrayRef),
+                ("name", Arc::new(StringArray::from(vec!["d"])) as ArrayRef),
+                (
+                    "deleted",
+                    Arc::new(BooleanArray::from(vec![false])) as ArrayRef,
+                ),
+            ],
+        )?;
+        let target = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("name", DataType::Utf8, false),
+            Field::new("score", DataType::Float64, true),
+        ]);
+
+        let t1 = ctx
+            .table("t1")?
+            .align_schema(&target, AlignOptions::new())?;
+        let t2 = ctx
+            .table("t2")?
+            .align_schema(&target, AlignOptions::new().case_insensitive(true))?;
+        let t3 = ctx
+            .table("t3")?
+            .align_schema(&target, AlignOptions::new().rename("user_id", "id"))?;
+        let results = t1.union(t2)?.union(t3)?.collect().await?;
+        assert_batches_sorted_eq!(
+            vec![
+                "+----+------+-------+",
+                "| id | name | score |",
+                "+----+------+-------+",
+                "| 1  | a    | 1.5   |",
+                "| 2  | b    | 2.5   |",
+                "| 3  | c    | 3.5   |",
+                "| 4  | d    |       |",
+                "+----+------+-------+",
+            ],
+            &results
+        );
+
+        let err = ctx
+            .table("t3")?
+            .align_schema(
+                &target,
+                AlignOptions::new().fill_missing(false).drop_extra(false),
+            )
+            .unwrap_err();
+        assert_eq!(
+            "Error during planning: Cannot align the DataFrame to the target schema:\n  \
+             id is missing\n  \
+             score is missing\n  \
+             user_id is not in the target schema\n  \
+             deleted is not in the target schema",
+            err.to_string()
+        );
+        let target = Schema::new(vec![Field::new("name", DataType::Int64, false)]);
+        let err = ctx
+            .table("t1")?
+            .align_schema(&target, AlignOptions::new())
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("name has the type Utf8, which can't be coerced to Int64"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_mixed_types() -> Result<()> {
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "id",
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+            ),
+            (
+                "score",
+                Arc::new(Float64Array::from(vec![
+                    Some(1.0),
+                    None,
+                    Some(3.0),
+                    Some(8.0),
+                ])) as ArrayRef,
+            ),
+            (
+                "name",
+                Arc::new(StringArray::from(vec![
+                    Some("b"),
+                    Some("a"),
+                    None,
+                    Some("b"),
+                ])) as ArrayRef,
+            ),
+            (
+                "flag",
+                Arc::new(BooleanArray::from(vec![
+                    Some(true),
+                    Some(false),
+                    None,
+                    None,
+                ])) as ArrayRef,
+            ),
+            (
+                "empty",
+                Arc::new(Int64Array::from(vec![None, None, None, None])) as ArrayRef,
+            ),
+            (
+                "tags",
+                Arc::new(ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+                    Some(vec![Some(1)]),
+                    None,
+                    Some(vec![]),
+                    Some(vec![Some(2), Some(3)]),
+                ])) as ArrayRef,
+            ),
+        ])?;
+        let mut ctx = ExecutionContext::new();
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        ctx.register_table("t", Arc::new(table))?;
+
+        let description = ctx.table("t")?.describe().await?;
+        assert_eq!(
+            description.schema().metadata().get("describe.skipped"),
+            Some(&"tags".to_owned())
+        );
+        let batches = description.collect().await?;
+        assert_eq!(
+            columns(&batches[0].schema()),
+            vec!["describe", "id", "score", "name", "flag", "empty"]
+        );
+        assert_eq!(
+            strings(&batches[0], "describe"),
+            [
+                "count",
+                "null_count",
+                "distinct_count",
+                "mean",
+                "std",
+                "min",
+                "max",
+                "median"
+            ]
+            .iter()
+            .map(|s| Some(s.to_string()))
+            .collect::<Vec<_>>()
+        );
+
+        // sample std of 1, 2, 3, 4: sqrt(5 / 3), and of 1, 3, 8: sqrt(26 / 2)
+        let expected = vec![
+            Some(4.0),
+            Some(0.0),
+            None,
+            Some(2.5),
+            Some((5.0_f64 / 3.0).sqrt()),
+            Some(1.0),
+            Some(4.0),
+            Some(2.5),
+        ];
+        assert_floats_eq(&floats(&batches[0], "id"), &expected);
+        let expected = vec![
+            Some(3.0),
+            Some(1.0),
+            None,
+            Some(4.0),
+            Some(13.0_f64.sqrt()),
+            Some(1.0),
+            Some(8.0),
+            Some(3.0),
+        ];
+        assert_floats_eq(&floats(&batches[0], "score"), &expected);
+        let expected = vec![Some(0.0), Some(4.0), None, None, None, None, None, None];
+        assert_floats_eq(&floats(&batches[0], "empty"), &expected);
+
+        let expected = vec![
+            Some("3"),
+            Some("1"),
+            Some("2"),
+            None,
+            None,
+            Some("a"),
+            Some("b"),
+            None,
+        ];
+        assert_eq!(strings(&batches[0], "name"), owned(&expected));
+        let expected = vec![Some("2"), Some("2"), None, None, None, None, None, None];
+        assert_eq!(strings(&batches[0], "flag"), owned(&expected));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn describe_empty_table() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Float64, true),
+            Field::new("b", DataType::Utf8, true),
+        ]));
+        let mut ctx = ExecutionContext::new();
+        let table = MemTable::try_new(
+            schema.clone(),
+            vec![vec![RecordBatch::new_empty(schema)]],
+        )?;
+        ctx.register_table("t", Arc::new(table))?;
+
+        let batches = ctx.table("t")?.describe().await?.collect().await?;
+        assert!(batches[0].schema().metadata().is_empty());
+        let expected = vec![Some(0.0), Some(0.0), None, None, None, None, None, None];
+        assert_floats_eq(&floats(&batches[0], "a"), &expected);
+        let expected = vec![
+            Some("0"),
+            Some("0"),
+            Some("0"),
+            None,
+            None,
+            None,
+            None,
+            None,
+        ];
+        assert_eq!(strings(&batches[0], "b"), owned(&expected));
+        Ok(())
+    }
+
+    fn floats(batch: &RecordBatch, name: &str) -> Vec<Option<f64>> {
+        let index = batch.schema().index_of(name).unwrap();
+        let array = batch.column(index);
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        array.iter().collect()
+    }
+
+    fn strings(batch: &RecordBatch, name: &str) -> Vec<Option<String>> {
+        let index = batch.schema().index_of(name).unwrap();
+        let array = batch.column(index);
+        let array = array.as_any().downcast_ref::<StringArray>().unwrap();
+        array.iter().map(|s| s.map(|s| s.to_owned())).collect()
+    }
+
+    fn owned(values: &[Option<&str>]) -> Vec<Option<String>> {
+        values.iter().map(|s| s.map(|s| s.to_owned())).collect()
+    }
+
+    fn assert_floats_eq(actual: &[Option<f64>], expected: &[Option<f64>]) {
+        assert_eq!(actual.len(), expected.len(), "{:?}", actual);
+        for (a, e) in actual.iter().zip(expected) {
+            match (a, e) {
+                (Some(a), Some(e)) => assert!((a - e).abs() < 1e-9, "{:?}", actual),
+                _ => assert_eq!(a, e, "{:?}", actual),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn alias_self_join() -> Result<()> {
+        let mut ctx = ExecutionContext::new();
+        register_columns(
+            &mut ctx,
+            "employees",
+            vec![
+                ("id", Arc::new(Int64Array::from(vec![1, 2, 3])) as ArrayRef),
+                (
+                    "name",
+                    Arc::new(StringArray::from(vec!["ann", "bob", "cid"])) as ArrayRef,
+                ),
+                (
+                    "manager",
+                    Arc::new(Int64Array::from(vec![None, Some(1), Some(2)])) as ArrayRef,
+                ),
+            ],
+        )?;
+        let employees = ctx.table("employees")?;
+
+        // both sides are qualified by `employees` without the aliases
+        assert!(employees
+            .join(employees.clone(), JoinType::Inner, &["manager"], &["id"])
+            .is_err());
+
+        let results = employees
+            .alias("e")?
+            .join(
+                employees.alias("m")?,
+                JoinType::Inner,
+                &["e.manager"],
+                &["m.id"],
+            )?
+            .select(vec![col("e.name"), col("m.name").alias("manager")])?
+            .collect()
+            .await?;
+        assert_batches_sorted_eq!(
+            vec![
+                "+------+---------+",
+                "| name | manager |",
+                "+------+---------+",
+                "| bob  | ann     |",
+                "| cid  | bob     |",
+                "+------+---------+",
+            ],
+            &results
+        );
+        Ok(())
+    }
+
     /// Compare the formatted string representation of two plans for equality
     fn assert_same_plan(plan1: &LogicalPlan, plan2: &LogicalPlan) {
         assert_eq!(format!("{:?}", plan1), format!("{:?}", plan2));
@@ -506,6 +1187,18 @@ mod tests {
         test_table_with_name("aggregate_test_100").await
     }
 
+    /// Register a table with a single batch of the columns `columns`
+    fn register_columns(
+        ctx: &mut ExecutionContext,
+        table_name: &str,
+        columns: Vec<(&str, ArrayRef)>,
+    ) -> Result<()> {
+        let batch = RecordBatch::try_from_iter(columns)?;
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        ctx.register_table(table_name, Arc::new(table))?;
+        Ok(())
+    }
+
     async fn register_aggregate_csv(
         ctx: &mut ExecutionContext,
         table_name: &str,
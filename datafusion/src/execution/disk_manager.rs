@@ -0,0 +1,239 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Allocation of the temporary files operators spill their buffers to.
+//!
+//! The files are created round-robin across the spill directories of the
+//! context, so that the spills of a machine with several disks are spread
+//! over all of them. A [`SpillFile`] is deleted when dropped, which happens
+//! when the operator is done with it or when its query is cancelled.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tempfile::NamedTempFile;
+
+use crate::error::{DataFusionError, Result};
+
+/// Allocator of the spill files of the queries of a context
+#[derive(Debug)]
+pub struct DiskManager {
+    /// Directories the spill files are created in
+    spill_dirs: Vec<PathBuf>,
+    /// Index in `spill_dirs` of the directory of the next spill file
+    next_dir: AtomicUsize,
+    /// Maximum number of bytes of the live spill files, unlimited if `None`
+    max_spill_bytes: Option<usize>,
+    /// Number of bytes of the live spill files
+    spilled_bytes: Arc<AtomicUsize>,
+}
+
+impl Default for DiskManager {
+    fn default() -> Self {
+        Self::new(vec![], None)
+    }
+}
+
+impl DiskManager {
+    /// Create a disk manager spilling to `spill_dirs`, or to the temporary
+    /// directory of the system if empty, and failing the writes that would
+    /// take the spill files over `max_spill_bytes` bytes in total
+    pub fn new(spill_dirs: Vec<PathBuf>, max_spill_bytes: Option<usize>) -> Self {
+        let spill_dirs = if spill_dirs.is_empty() {
+            vec![std::env::temp_dir()]
+        } else {
+            spill_dirs
+        };
+        Self {
+            spill_dirs,
+            next_dir: AtomicUsize::new(0),
+            max_spill_bytes,
+            spilled_bytes: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Directories the spill files are created in
+    pub fn spill_dirs(&self) -> &[PathBuf] {
+        &self.spill_dirs
+    }
+
+    /// Number of bytes currently written to the spill files that are not
+    /// deleted yet
+    pub fn spilled_bytes(&self) -> usize {
+        self.spilled_bytes.load(Ordering::SeqCst)
+    }
+
+    /// Create an empty spill file whose name starts with `name`, in the next
+    /// spill directory. The directory is created if it does not exist.
+    pub fn create_spill_file(&self, name: &str) -> Result<SpillFile> {
+        let index = self.next_dir.fetch_add(1, Ordering::Relaxed);
+        let dir = &self.spill_dirs[index % self.spill_dirs.len()];
+        fs::create_dir_all(dir)?;
+        let file = tempfile::Builder::new()
+            .prefix(&format!("{}-", name))
+            .suffix(".arrow")
+            .tempfile_in(dir)?;
+        Ok(SpillFile {
+            file,
+            size: 0,
+            max_spill_bytes: self.max_spill_bytes,
+            spilled_bytes: self.spilled_bytes.clone(),
+        })
+    }
+}
+
+/// Temporary file allocated by a [`DiskManager`], deleted when dropped
+#[derive(Debug)]
+pub struct SpillFile {
+    file: NamedTempFile,
+    /// Number of bytes written to the file
+    size: usize,
+    max_spill_bytes: Option<usize>,
+    spilled_bytes: Arc<AtomicUsize>,
+}
+
+impl SpillFile {
+    /// Path of the file
+    pub fn path(&self) -> &Path {
+        self.file.path()
+    }
+
+    /// Number of bytes written to the file
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Open the file again to read it from the start
+    pub fn reopen(&self) -> io::Result<File> {
+        self.file.reopen()
+    }
+}
+
+impl Write for SpillFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let spilled = self.spilled_bytes.fetch_add(buf.len(), Ordering::SeqCst);
+        if let Some(max) = self.max_spill_bytes {
+            if spilled + buf.len() > max {
+                self.spilled_bytes.fetch_sub(buf.len(), Ordering::SeqCst);
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    DataFusionError::ResourcesExhausted(format!(
+                        "cannot spill {} more bytes to {}, {} of the {} bytes of the \
+                         spill budget are in use",
+                        buf.len(),
+                        self.path().display(),
+                        spilled,
+                        max
+                    )),
+                ));
+            }
+        }
+        let written = match self.file.write(buf) {
+            Ok(written) => written,
+            Err(e) => {
+                self.spilled_bytes.fetch_sub(buf.len(), Ordering::SeqCst);
+                return Err(e);
+            }
+        };
+        self.spilled_bytes
+            .fetch_sub(buf.len() - written, Ordering::SeqCst);
+        self.size += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        self.spilled_bytes.fetch_sub(self.size, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn spill_files_round_robin() -> Result<()> {
+        let dirs = [TempDir::new()?, TempDir::new()?];
+        // the spill directories are created on demand
+        let spill_dirs = dirs
+            .iter()
+            .map(|dir| dir.path().join("spill"))
+            .collect::<Vec<_>>();
+        let manager = DiskManager::new(spill_dirs.clone(), None);
+
+        let files = (0..4)
+            .map(|_| manager.create_spill_file("sort"))
+            .collect::<Result<Vec<_>>>()?;
+        for (i, file) in files.iter().enumerate() {
+            assert_eq!(file.path().parent().unwrap(), spill_dirs[i % 2]);
+            let name = file.path().file_name().unwrap().to_str().unwrap();
+            assert!(name.starts_with("sort-") && name.ends_with(".arrow"));
+        }
+
+        let paths = files
+            .iter()
+            .map(|file| file.path().to_owned())
+            .collect::<Vec<_>>();
+        drop(files);
+        assert!(paths.iter().all(|path| !path.exists()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn spilled_bytes() -> Result<()> {
+        let dir = TempDir::new()?;
+        let manager = DiskManager::new(vec![dir.path().to_owned()], Some(10));
+
+        let mut first = manager.create_spill_file("first")?;
+        first.write_all(b"0123")?;
+        let mut second = manager.create_spill_file("second")?;
+        second.write_all(b"456789")?;
+        assert_eq!(manager.spilled_bytes(), 10);
+        assert_eq!(first.size(), 4);
+
+        let err = first.write_all(b"a").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Resources exhausted: cannot spill 1"));
+        assert_eq!(manager.spilled_bytes(), 10);
+
+        let mut content = String::new();
+        second.reopen()?.read_to_string(&mut content)?;
+        assert_eq!(content, "456789");
+
+        drop(second);
+        assert_eq!(manager.spilled_bytes(), 4);
+        first.write_all(b"a")?;
+        assert_eq!(manager.spilled_bytes(), 5);
+        drop(first);
+        assert_eq!(manager.spilled_bytes(), 0);
+        assert_eq!(fs::read_dir(dir.path())?.count(), 0);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Accounting of the memory buffered by the operators of the queries of a
+//! context, against a budget shared by all of them.
+//!
+//! An operator buffering its input registers a [`MemoryConsumer`] per
+//! partition it executes, and reserves the bytes of the batches it buffers
+//! with [`MemoryConsumer::try_grow`] before buffering them. When the
+//! reservation fails, an operator able to spill writes its buffer to disk and
+//! releases its reservation, the other operators fail with
+//! [`DataFusionError::ResourcesExhausted`].
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::metrics::Gauge;
+
+/// Memory budget shared by the consumers registered to it
+#[derive(Debug)]
+pub struct MemoryManager {
+    /// Number of bytes the consumers may reserve in total
+    pool_size: usize,
+    /// Identifier of the next registered consumer
+    next_id: AtomicUsize,
+    /// Reservations of the registered consumers
+    state: Mutex<MemoryState>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryState {
+    /// Number of bytes reserved by all consumers
+    used: usize,
+    /// Name and number of bytes reserved of each consumer, by identifier
+    consumers: HashMap<usize, (String, usize)>,
+}
+
+impl MemoryManager {
+    /// Create a memory manager whose consumers may reserve `pool_size` bytes
+    /// in total
+    pub fn new(pool_size: usize) -> Self {
+        Self {
+            pool_size,
+            next_id: AtomicUsize::new(0),
+            state: Mutex::new(MemoryState::default()),
+        }
+    }
+
+    /// Number of bytes the consumers may reserve in total
+    pub fn pool_size(&self) -> usize {
+        self.pool_size
+    }
+
+    /// Number of bytes currently reserved by all consumers
+    pub fn used(&self) -> usize {
+        self.state.lock().unwrap().used
+    }
+
+    /// Name and number of bytes currently reserved of each registered
+    /// consumer, sorted by name
+    pub fn usage(&self) -> Vec<(String, usize)> {
+        let state = self.state.lock().unwrap();
+        let mut usage = state.consumers.values().cloned().collect::<Vec<_>>();
+        usage.sort();
+        usage
+    }
+
+    /// Register a consumer named `name`, reserving no memory. The largest
+    /// reservation of the consumer is recorded by `mem_used`.
+    pub fn register_consumer(
+        self: &Arc<Self>,
+        name: impl Into<String>,
+        mem_used: Gauge,
+    ) -> MemoryConsumer {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let name = name.into();
+        self.state
+            .lock()
+            .unwrap()
+            .consumers
+            .insert(id, (name.clone(), 0));
+        MemoryConsumer {
+            id,
+            name,
+            manager: self.clone(),
+            used: 0,
+            mem_used,
+        }
+    }
+}
+
+/// Reservation of an operator partition on a [`MemoryManager`], released
+/// when dropped
+#[derive(Debug)]
+pub struct MemoryConsumer {
+    id: usize,
+    name: String,
+    manager: Arc<MemoryManager>,
+    /// Number of bytes currently reserved
+    used: usize,
+    /// Metric recording the largest reservation of the consumer
+    mem_used: Gauge,
+}
+
+impl MemoryConsumer {
+    /// Name of the consumer
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Number of bytes currently reserved by the consumer
+    pub fn mem_used(&self) -> usize {
+        self.used
+    }
+
+    /// Reserve `bytes` more bytes, or fail with
+    /// [`DataFusionError::ResourcesExhausted`] if the pool cannot hold them
+    pub fn try_grow(&mut self, bytes: usize) -> Result<()> {
+        let mut state = self.manager.state.lock().unwrap();
+        if state.used + bytes > self.manager.pool_size {
+            return Err(DataFusionError::ResourcesExhausted(format!(
+                "{} cannot reserve {} bytes, {} of the {} bytes of the memory pool are in use",
+                self.name, bytes, state.used, self.manager.pool_size
+            )));
+        }
+        state.used += bytes;
+        if let Some((_, used)) = state.consumers.get_mut(&self.id) {
+            *used += bytes;
+        }
+        self.used += bytes;
+        self.mem_used.set_max(self.used);
+        Ok(())
+    }
+
+    /// Release `bytes` of the bytes reserved by the consumer
+    pub fn shrink(&mut self, bytes: usize) {
+        let bytes = bytes.min(self.used);
+        let mut state = self.manager.state.lock().unwrap();
+        state.used -= bytes;
+        if let Some((_, used)) = state.consumers.get_mut(&self.id) {
+            *used -= bytes;
+        }
+        self.used -= bytes;
+    }
+
+    /// Release all the bytes reserved by the consumer, returning their number
+    pub fn free(&mut self) -> usize {
+        let bytes = self.used;
+        self.shrink(bytes);
+        bytes
+    }
+}
+
+impl Drop for MemoryConsumer {
+    fn drop(&mut self) {
+        self.free();
+        self.manager
+            .state
+            .lock()
+            .unwrap()
+            .consumers
+            .remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reservations_share_pool() {
+        let manager = Arc::new(MemoryManager::new(100));
+        let mut first = manager.register_consumer("first", Gauge::new());
+        let mut second = manager.register_consumer("second", Gauge::new());
+
+        first.try_grow(60).unwrap();
+        second.try_grow(30).unwrap();
+        assert_eq!(manager.used(), 90);
+        assert_eq!(
+            manager.usage(),
+            vec![("first".to_string(), 60), ("second".to_string(), 30)]
+        );
+
+        let err = second.try_grow(20).unwrap_err();
+        assert!(matches!(err, DataFusionError::ResourcesExhausted(_)));
+        assert_eq!(
+            err.to_string(),
+            "Resources exhausted: second cannot reserve 20 bytes, \
+             90 of the 100 bytes of the memory pool are in use"
+        );
+        assert_eq!(second.mem_used(), 30);
+
+        first.shrink(40);
+        second.try_grow(20).unwrap();
+        assert_eq!(manager.used(), 70);
+        assert_eq!(first.free(), 20);
+        assert_eq!(manager.used(), 50);
+    }
+
+    #[test]
+    fn drop_releases_and_records_peak() {
+        let manager = Arc::new(MemoryManager::new(100));
+        let mem_used = Gauge::new();
+        let mut consumer = manager.register_consumer("sort", mem_used.clone());
+        consumer.try_grow(80).unwrap();
+        consumer.shrink(50);
+        consumer.try_grow(10).unwrap();
+        drop(consumer);
+
+        assert_eq!(manager.used(), 0);
+        assert!(manager.usage().is_empty());
+        assert_eq!(mem_used.value(), 80);
+    }
+}
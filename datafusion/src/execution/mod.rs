@@ -17,6 +17,14 @@
 
 //! DataFusion query execution
 
+pub mod cancellation;
 pub mod context;
+pub mod cpu_executor;
 pub mod dataframe_impl;
+pub mod disk_manager;
+pub mod memory_manager;
 pub mod options;
+pub mod progress;
+pub mod result_cache;
+pub mod runtime_env;
+pub mod task_context;
@@ -17,14 +17,21 @@
 
 //! User facing options for the file formats readers
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use arrow::datatypes::{Schema, SchemaRef};
 
 use crate::datasource::{
-    file_format::{avro::AvroFormat, csv::CsvFormat},
+    file_format::{
+        avro::AvroFormat,
+        csv::CsvFormat,
+        ipc::{IpcFormat, DEFAULT_IPC_EXTENSION},
+        json::JsonFormat,
+    },
     listing::ListingOptions,
 };
+pub use crate::physical_plan::file_format::ScanErrorPolicy;
 
 /// CSV file read option
 #[derive(Copy, Clone)]
@@ -44,6 +51,12 @@ pub struct CsvReadOptions<'a> {
     /// File extension; only files with this extension are selected for data input.
     /// Defaults to ".csv".
     pub file_extension: &'a str,
+    /// Number of lines to skip at the start of each file, before the header if
+    /// any. Defaults to 0.
+    pub skip_rows: usize,
+    /// What the scan does when a file cannot be read. Defaults to
+    /// [`ScanErrorPolicy::Fail`].
+    pub scan_error_policy: ScanErrorPolicy,
 }
 
 impl<'a> CsvReadOptions<'a> {
@@ -55,6 +68,8 @@ impl<'a> CsvReadOptions<'a> {
             schema_infer_max_records: 1000,
             delimiter: b',',
             file_extension: ".csv",
+            skip_rows: 0,
+            scan_error_policy: ScanErrorPolicy::Fail,
         }
     }
 
@@ -96,12 +111,26 @@ impl<'a> CsvReadOptions<'a> {
         self
     }
 
+    /// Configure the number of lines to skip at the start of each file
+    pub fn skip_rows(mut self, skip_rows: usize) -> Self {
+        self.skip_rows = skip_rows;
+        self
+    }
+
+    /// Configure what the scan does when a file cannot be read
+    pub fn scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
     /// Helper to convert these user facing options to `ListingTable` options
     pub fn to_listing_options(&self, target_partitions: usize) -> ListingOptions {
         let file_format = CsvFormat::default()
             .with_has_header(self.has_header)
             .with_delimiter(self.delimiter)
-            .with_schema_infer_max_rec(Some(self.schema_infer_max_records));
+            .with_schema_infer_max_rec(Some(self.schema_infer_max_records))
+            .with_skip_rows(self.skip_rows)
+            .with_scan_error_policy(self.scan_error_policy);
 
         ListingOptions {
             format: Arc::new(file_format),
@@ -113,6 +142,168 @@ impl<'a> CsvReadOptions<'a> {
     }
 }
 
+/// Quoting style used when writing CSV fields
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsvQuoteStyle {
+    /// Only quote fields that contain a delimiter, a quote or a line terminator
+    Necessary,
+    /// Quote every field
+    Always,
+    /// Quote every field that is not a number
+    NonNumeric,
+    /// Never quote fields, even if this produces invalid CSV
+    Never,
+}
+
+/// CSV file write option
+#[derive(Debug, Clone)]
+pub struct CsvWriteOptions {
+    /// Should a header row with the column names be written first?
+    pub has_header: bool,
+    /// Column delimiter. Defaults to `b','`.
+    pub delimiter: u8,
+    /// When to quote fields. Defaults to [`CsvQuoteStyle::Necessary`].
+    pub quote_style: CsvQuoteStyle,
+    /// Token written in place of null values. Defaults to the empty string.
+    pub null_value: String,
+    /// Optional chrono format string applied to `Date32` and `Date64` columns.
+    pub date_format: Option<String>,
+    /// Optional chrono format string applied to `Timestamp` columns.
+    pub timestamp_format: Option<String>,
+    /// Replace the target directory if it already exists. Defaults to `false`.
+    pub overwrite: bool,
+}
+
+impl Default for CsvWriteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvWriteOptions {
+    /// Create a CSV write option with default presets
+    pub fn new() -> Self {
+        Self {
+            has_header: true,
+            delimiter: b',',
+            quote_style: CsvQuoteStyle::Necessary,
+            null_value: String::new(),
+            date_format: None,
+            timestamp_format: None,
+            overwrite: false,
+        }
+    }
+
+    /// Configure has_header setting
+    pub fn has_header(mut self, has_header: bool) -> Self {
+        self.has_header = has_header;
+        self
+    }
+
+    /// Specify delimiter to use for CSV write
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Specify when fields are quoted
+    pub fn quote_style(mut self, quote_style: CsvQuoteStyle) -> Self {
+        self.quote_style = quote_style;
+        self
+    }
+
+    /// Specify the token written for null values
+    pub fn null_value(mut self, null_value: impl Into<String>) -> Self {
+        self.null_value = null_value.into();
+        self
+    }
+
+    /// Specify the format used for date columns
+    pub fn date_format(mut self, format: impl Into<String>) -> Self {
+        self.date_format = Some(format.into());
+        self
+    }
+
+    /// Specify the format used for timestamp columns
+    pub fn timestamp_format(mut self, format: impl Into<String>) -> Self {
+        self.timestamp_format = Some(format.into());
+        self
+    }
+
+    /// Configure whether an existing target directory may be replaced
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+}
+
+/// Options of [`DataFrame::align_schema`](crate::dataframe::DataFrame::align_schema)
+#[derive(Debug, Clone)]
+pub struct AlignOptions {
+    /// Source column names renamed to target column names before matching them
+    pub renames: HashMap<String, String>,
+    /// Match column names regardless of their ASCII case. Defaults to `false`.
+    pub case_insensitive: bool,
+    /// Fill the nullable target columns missing from the source with nulls.
+    /// Defaults to `true`.
+    pub fill_missing: bool,
+    /// Cast the source columns to the types of their target columns when their
+    /// values can be coerced losslessly. Defaults to `true`.
+    pub coerce: bool,
+    /// Drop the source columns that are not in the target schema. Defaults to
+    /// `true`.
+    pub drop_extra: bool,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlignOptions {
+    /// Create an align option with default presets
+    pub fn new() -> Self {
+        Self {
+            renames: HashMap::new(),
+            case_insensitive: false,
+            fill_missing: true,
+            coerce: true,
+            drop_extra: true,
+        }
+    }
+
+    /// Match the source column `from` to the target column `to`
+    pub fn rename(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.renames.insert(from.into(), to.into());
+        self
+    }
+
+    /// Configure whether column names are matched regardless of their case
+    pub fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Configure whether missing columns are filled with nulls
+    pub fn fill_missing(mut self, fill_missing: bool) -> Self {
+        self.fill_missing = fill_missing;
+        self
+    }
+
+    /// Configure whether columns are coerced to the types of the target schema
+    pub fn coerce(mut self, coerce: bool) -> Self {
+        self.coerce = coerce;
+        self
+    }
+
+    /// Configure whether the columns out of the target schema are dropped
+    pub fn drop_extra(mut self, drop_extra: bool) -> Self {
+        self.drop_extra = drop_extra;
+        self
+    }
+}
+
 /// Avro read options
 #[derive(Clone)]
 pub struct AvroReadOptions<'a> {
@@ -148,6 +339,39 @@ impl<'a> AvroReadOptions<'a> {
     }
 }
 
+/// Arrow IPC read options
+#[derive(Clone)]
+pub struct IpcReadOptions<'a> {
+    /// The data source schema. If None, it is read from the file footers.
+    pub schema: Option<SchemaRef>,
+
+    /// File extension; only files with this extension are selected for data input.
+    /// Defaults to ".arrow".
+    pub file_extension: &'a str,
+}
+
+impl<'a> Default for IpcReadOptions<'a> {
+    fn default() -> Self {
+        Self {
+            schema: None,
+            file_extension: DEFAULT_IPC_EXTENSION,
+        }
+    }
+}
+
+impl<'a> IpcReadOptions<'a> {
+    /// Helper to convert these user facing options to `ListingTable` options
+    pub fn to_listing_options(&self, target_partitions: usize) -> ListingOptions {
+        ListingOptions {
+            format: Arc::new(IpcFormat::default()),
+            collect_stat: false,
+            file_extension: self.file_extension.to_owned(),
+            target_partitions,
+            table_partition_cols: vec![],
+        }
+    }
+}
+
 /// Line-delimited JSON read options
 #[derive(Clone)]
 pub struct NdJsonReadOptions<'a> {
@@ -160,6 +384,10 @@ pub struct NdJsonReadOptions<'a> {
     /// File extension; only files with this extension are selected for data input.
     /// Defaults to ".json".
     pub file_extension: &'a str,
+
+    /// What the scan does when a file cannot be read. Defaults to
+    /// [`ScanErrorPolicy::Fail`].
+    pub scan_error_policy: ScanErrorPolicy,
 }
 
 impl<'a> Default for NdJsonReadOptions<'a> {
@@ -168,6 +396,30 @@ impl<'a> Default for NdJsonReadOptions<'a> {
             schema: None,
             schema_infer_max_records: 1000,
             file_extension: ".json",
+            scan_error_policy: ScanErrorPolicy::Fail,
+        }
+    }
+}
+
+impl<'a> NdJsonReadOptions<'a> {
+    /// Configure what the scan does when a file cannot be read
+    pub fn scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// Helper to convert these user facing options to `ListingTable` options
+    pub fn to_listing_options(&self, target_partitions: usize) -> ListingOptions {
+        let file_format = JsonFormat::default()
+            .with_schema_infer_max_rec(Some(self.schema_infer_max_records))
+            .with_scan_error_policy(self.scan_error_policy);
+
+        ListingOptions {
+            format: Arc::new(file_format),
+            collect_stat: false,
+            file_extension: self.file_extension.to_owned(),
+            target_partitions,
+            table_partition_cols: vec![],
         }
     }
 }
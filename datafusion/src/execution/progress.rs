@@ -0,0 +1,182 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Progress reporting of the execution of the queries.
+//!
+//! A context configured with a [`ProgressHandler`], see
+//! [`ExecutionContext::with_progress_handler`](crate::execution::context::ExecutionContext::with_progress_handler),
+//! receives a [`ProgressEvent`] from each partition of the operators every
+//! [`ExecutionConfig::progress_interval`](crate::execution::context::ExecutionConfig::progress_interval)
+//! output batches, and once the partition is done. The operators report their
+//! output through their
+//! [`BaselineMetrics`](crate::physical_plan::metrics::BaselineMetrics), which
+//! do nothing more than recording their metrics without a handler.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use arrow::record_batch::RecordBatch;
+
+use crate::physical_plan::common::batch_memory_size;
+
+/// Callback receiving the progress events of the queries of a context. It is
+/// called by the threads running the operators, and should return quickly.
+pub type ProgressHandler = Arc<dyn Fn(ProgressEvent) + Send + Sync>;
+
+/// Progress of a partition of an operator
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProgressEvent {
+    /// Name of the operator, such as `CsvExec`
+    pub operator: &'static str,
+    /// Partition of the operator
+    pub partition: usize,
+    /// Number of rows produced by the partition so far
+    pub rows_out: usize,
+    /// Number of bytes of the batches produced by the partition so far
+    pub bytes_out: usize,
+    /// Files the operator scans, discovered when it was planned, if it is a
+    /// file scan. They are the same for all the partitions of the scan.
+    pub scan_total: Option<ScanTotal>,
+}
+
+/// Files scanned by all the partitions of a file scan, to estimate the
+/// fraction of the scan that is complete
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanTotal {
+    /// Number of files
+    pub files: usize,
+    /// Total size of the files, in bytes
+    pub bytes: u64,
+}
+
+/// Reports the progress of a partition of an operator to a [`ProgressHandler`]
+pub struct ProgressReporter {
+    handler: ProgressHandler,
+    operator: &'static str,
+    partition: usize,
+    /// Number of output batches between two events, at least one
+    interval: usize,
+    scan_total: Option<ScanTotal>,
+    state: Mutex<ProgressState>,
+}
+
+#[derive(Default)]
+struct ProgressState {
+    rows_out: usize,
+    bytes_out: usize,
+    /// Number of batches produced since the last event
+    pending_batches: usize,
+}
+
+impl fmt::Debug for ProgressReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressReporter")
+            .field("operator", &self.operator)
+            .field("partition", &self.partition)
+            .field("interval", &self.interval)
+            .field("scan_total", &self.scan_total)
+            .finish()
+    }
+}
+
+impl ProgressReporter {
+    /// Create a reporter of partition `partition` of `operator`, sending an
+    /// event to `handler` every `interval` batches
+    pub fn new(
+        handler: ProgressHandler,
+        operator: &'static str,
+        partition: usize,
+        interval: usize,
+    ) -> Self {
+        Self {
+            handler,
+            operator,
+            partition,
+            interval: interval.max(1),
+            scan_total: None,
+            state: Mutex::new(ProgressState::default()),
+        }
+    }
+
+    /// Report `scan_total` along with the progress of the partition
+    pub fn with_scan_total(mut self, scan_total: ScanTotal) -> Self {
+        self.scan_total = Some(scan_total);
+        self
+    }
+
+    /// Record that `batch` was produced, sending an event if it completes an
+    /// interval
+    pub fn record_batch(&self, batch: &RecordBatch) {
+        let mut state = self.state.lock().unwrap();
+        state.rows_out += batch.num_rows();
+        state.bytes_out += batch_memory_size(batch);
+        state.pending_batches += 1;
+        if state.pending_batches >= self.interval {
+            self.report(&mut state);
+        }
+    }
+
+    /// Record that the partition is done, sending an event for the batches
+    /// produced since the last one
+    pub fn done(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.pending_batches > 0 {
+            self.report(&mut state);
+        }
+    }
+
+    fn report(&self, state: &mut ProgressState) {
+        state.pending_batches = 0;
+        (self.handler)(ProgressEvent {
+            operator: self.operator,
+            partition: self.partition,
+            rows_out: state.rows_out,
+            bytes_out: state.bytes_out,
+            scan_total: self.scan_total,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test::make_partition;
+
+    #[test]
+    fn reports_every_interval() {
+        let events = Arc::new(Mutex::new(vec![]));
+        let handler: ProgressHandler = {
+            let events = events.clone();
+            Arc::new(move |event| events.lock().unwrap().push(event))
+        };
+        let reporter = ProgressReporter::new(handler, "TestExec", 1, 2);
+        let batch = make_partition(3);
+        for _ in 0..5 {
+            reporter.record_batch(&batch);
+        }
+        reporter.done();
+        reporter.done();
+
+        let events = events.lock().unwrap();
+        let rows = events.iter().map(|e| e.rows_out).collect::<Vec<_>>();
+        assert_eq!(rows, vec![6, 12, 15]);
+        assert!(events
+            .iter()
+            .all(|e| e.operator == "TestExec" && e.partition == 1));
+        assert_eq!(events[2].bytes_out, 5 * batch_memory_size(&batch));
+    }
+}
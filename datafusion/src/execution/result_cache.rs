@@ -0,0 +1,256 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Cache of the results of the queries of a context, see [`ResultCache`]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::{collect_table_scans, fingerprint, LogicalPlan, PlanVisitor};
+use crate::optimizer::utils::is_immutable;
+use crate::physical_plan::common::batch_memory_size;
+
+/// The key of the result of a query: the fingerprint of its logical plan and
+/// the versions of the tables it reads
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResultKey {
+    fingerprint: u64,
+    versions: Vec<(String, u64)>,
+}
+
+impl ResultKey {
+    /// The key of the result of `plan`, or `None` if the result can't be
+    /// cached: if the plan calls functions that are not immutable, such as
+    /// `now()` or `random()`, reads variables, reads tables without
+    /// [version](crate::datasource::TableProvider::version), or is not a
+    /// query
+    pub fn try_new(plan: &LogicalPlan) -> Result<Option<Self>> {
+        let mut visitor = CacheableVisitor {};
+        if !plan.accept(&mut visitor)? {
+            return Ok(None);
+        }
+        let versions = collect_table_scans(plan)
+            .into_iter()
+            .map(|scan| Some((scan.table_name, scan.source.version()?)))
+            .collect::<Option<Vec<_>>>();
+        Ok(versions.map(|versions| Self {
+            fingerprint: fingerprint(plan),
+            versions,
+        }))
+    }
+}
+
+/// Stops at the first node whose result can't be cached
+struct CacheableVisitor {}
+
+impl PlanVisitor for CacheableVisitor {
+    type Error = DataFusionError;
+
+    fn pre_visit(&mut self, plan: &LogicalPlan) -> Result<bool> {
+        match plan {
+            LogicalPlan::CreateExternalTable(_)
+            | LogicalPlan::CreateMemoryTable(_)
+            | LogicalPlan::InsertInto(_)
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::SetVariable(_)
+            | LogicalPlan::Explain(_)
+            | LogicalPlan::Analyze(_)
+            | LogicalPlan::Extension(_) => Ok(false),
+            _ => {
+                for expr in plan.expressions() {
+                    if !is_immutable(&expr)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// A cached result, with the versions of the tables it was computed from
+struct CachedResult {
+    versions: Vec<(String, u64)>,
+    batches: Vec<RecordBatch>,
+    size: usize,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct ResultCacheState {
+    entries: HashMap<u64, CachedResult>,
+    /// Number of bytes of the cached batches
+    size: usize,
+    /// Incremented on every access, to order the entries by last use
+    clock: u64,
+}
+
+impl ResultCacheState {
+    fn remove(&mut self, fingerprint: u64) {
+        if let Some(entry) = self.entries.remove(&fingerprint) {
+            self.size -= entry.size;
+        }
+    }
+}
+
+/// Caches the collected results of the queries of a context, keyed by
+/// [`ResultKey`], so that queries issued again, e.g. by dashboards, are not
+/// executed again.
+///
+/// A result is invalidated once the version of one of the tables it was
+/// computed from changes. The least recently used results are evicted to
+/// keep the batches within the capacity of the cache.
+pub struct ResultCache {
+    capacity: usize,
+    state: Mutex<ResultCacheState>,
+}
+
+impl ResultCache {
+    /// Create an empty cache of at most `capacity` bytes of batches
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            state: Mutex::new(ResultCacheState::default()),
+        }
+    }
+
+    /// Maximum number of bytes of the cached batches
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Number of bytes of the cached batches
+    pub fn size(&self) -> usize {
+        self.state.lock().unwrap().size
+    }
+
+    /// Number of cached results
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    /// Whether no result is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the result cached for `key`, if its tables did not change since
+    pub fn get(&self, key: &ResultKey) -> Option<Vec<RecordBatch>> {
+        let mut state = self.state.lock().unwrap();
+        state.clock += 1;
+        let clock = state.clock;
+        let entry = state.entries.get_mut(&key.fingerprint)?;
+        if entry.versions != key.versions {
+            state.remove(key.fingerprint);
+            return None;
+        }
+        entry.last_used = clock;
+        Some(entry.batches.clone())
+    }
+
+    /// Cache `batches` as the result for `key`, unless they exceed the
+    /// capacity of the cache
+    pub fn insert(&self, key: ResultKey, batches: Vec<RecordBatch>) {
+        let size = batches.iter().map(batch_memory_size).sum::<usize>();
+        let mut state = self.state.lock().unwrap();
+        state.remove(key.fingerprint);
+        if size > self.capacity {
+            return;
+        }
+        while state.size + size > self.capacity {
+            let least_recently_used = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(fingerprint, _)| *fingerprint);
+            match least_recently_used {
+                Some(fingerprint) => state.remove(fingerprint),
+                None => break,
+            }
+        }
+
+        state.clock += 1;
+        let last_used = state.clock;
+        state.size += size;
+        state.entries.insert(
+            key.fingerprint,
+            CachedResult {
+                versions: key.versions,
+                batches,
+                size,
+                last_used,
+            },
+        );
+    }
+
+    /// Remove all the cached results
+    pub fn clear(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.entries.clear();
+        state.size = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test;
+
+    fn key(fingerprint: u64, version: u64) -> ResultKey {
+        ResultKey {
+            fingerprint,
+            versions: vec![("t".to_owned(), version)],
+        }
+    }
+
+    #[test]
+    fn invalidate_changed_tables() {
+        let cache = ResultCache::new(1 << 20);
+        let batches = vec![test::make_partition(4)];
+        cache.insert(key(1, 0), batches.clone());
+        assert_eq!(Some(batches), cache.get(&key(1, 0)));
+        assert_eq!(None, cache.get(&key(2, 0)));
+
+        assert_eq!(None, cache.get(&key(1, 1)));
+        assert!(cache.is_empty());
+        assert_eq!(0, cache.size());
+    }
+
+    #[test]
+    fn evict_least_recently_used() {
+        let batch = test::make_partition(1000);
+        let size = batch_memory_size(&batch);
+        let cache = ResultCache::new(2 * size);
+        cache.insert(key(1, 0), vec![batch.clone()]);
+        cache.insert(key(2, 0), vec![batch.clone()]);
+        assert!(cache.get(&key(1, 0)).is_some());
+
+        cache.insert(key(3, 0), vec![batch.clone()]);
+        assert!(cache.get(&key(1, 0)).is_some());
+        assert!(cache.get(&key(2, 0)).is_none());
+        assert!(cache.get(&key(3, 0)).is_some());
+        assert_eq!(2 * size, cache.size());
+
+        // too large to be cached
+        cache.insert(key(4, 0), vec![batch.clone(), batch.clone(), batch]);
+        assert!(cache.get(&key(4, 0)).is_none());
+        assert_eq!(2, cache.len());
+    }
+}
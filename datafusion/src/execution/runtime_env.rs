@@ -0,0 +1,123 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Runtime environment of the process executing a physical plan.
+//!
+//! A deserialized plan references nothing from the context that planned it:
+//! the resources that cannot be serialized (object stores, user-defined
+//! functions, memory and disk managers, cancellation token, CPU executor,
+//! progress handler) and the settings of the executing process (batch size,
+//! memory limit) are bound to it by [`bind_runtime`].
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::datasource::object_store::ObjectStoreRegistry;
+use crate::error::Result;
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::disk_manager::DiskManager;
+use crate::execution::memory_manager::MemoryManager;
+use crate::execution::progress::ProgressHandler;
+use crate::logical_plan::FunctionRegistry;
+use crate::physical_plan::{serde, ExecutionPlan};
+
+/// The resources and settings of the process executing a physical plan
+#[derive(Clone)]
+pub struct RuntimeEnv {
+    /// Object stores replacing the stores of the file scans, see
+    /// [`ObjectStore::registry_scheme`](crate::datasource::object_store::ObjectStore::registry_scheme)
+    pub object_store_registry: Arc<ObjectStoreRegistry>,
+    /// Registry resolving the user-defined functions of deserialized plans
+    pub function_registry: Arc<dyn FunctionRegistry>,
+    /// Number of rows of the batches read from data sources
+    pub batch_size: usize,
+    /// Maximum number of bytes an operator may buffer, unlimited if `None`
+    pub memory_limit: Option<usize>,
+    /// Memory manager the buffering operators reserve their memory from,
+    /// unaccounted if `None`
+    pub memory_manager: Option<Arc<MemoryManager>>,
+    /// Disk manager the spilling operators allocate their temporary files from
+    pub disk_manager: Arc<DiskManager>,
+    /// Token cancelling the long-running operators of the plan
+    pub cancellation: CancellationToken,
+    /// Executor running the CPU-bound work of the operators of the plan
+    pub cpu_executor: CpuExecutor,
+    /// Handler receiving the progress of the operators of the plan, not
+    /// reported if `None`
+    pub progress_handler: Option<ProgressHandler>,
+}
+
+impl fmt::Debug for RuntimeEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut udfs = self
+            .function_registry
+            .udfs()
+            .into_iter()
+            .collect::<Vec<_>>();
+        udfs.sort();
+        f.debug_struct("RuntimeEnv")
+            .field("object_store_registry", &self.object_store_registry)
+            .field("udfs", &udfs)
+            .field("batch_size", &self.batch_size)
+            .field("memory_limit", &self.memory_limit)
+            .field("memory_manager", &self.memory_manager)
+            .field("disk_manager", &self.disk_manager)
+            .field("cancellation", &self.cancellation)
+            .field("cpu_executor", &self.cpu_executor)
+            .field("progress_handler", &self.progress_handler.is_some())
+            .finish()
+    }
+}
+
+impl RuntimeEnv {
+    /// Deserialize a plan produced by [`serde::to_bytes`] or
+    /// [`serde::to_compressed_bytes`] and bind it to this environment
+    pub fn plan_from_bytes(&self, bytes: &[u8]) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = serde::from_bytes_with_registry(bytes, &self.function_registry)?;
+        bind_runtime(plan, self)
+    }
+
+    /// Deserialize a plan produced by [`serde::to_json`] and bind it to this
+    /// environment
+    pub fn plan_from_json(&self, json: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = serde::from_json_with_registry(json, &self.function_registry)?;
+        bind_runtime(plan, self)
+    }
+}
+
+/// Bind `plan` to the runtime environment `runtime`, by rebinding every
+/// operator of the tree with [`ExecutionPlan::with_runtime`].
+///
+/// The user-defined functions are resolved when the plan is deserialized,
+/// see [`RuntimeEnv::plan_from_bytes`] and [`RuntimeEnv::plan_from_json`].
+pub fn bind_runtime(
+    plan: Arc<dyn ExecutionPlan>,
+    runtime: &RuntimeEnv,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let children = children
+            .into_iter()
+            .map(|child| bind_runtime(child, runtime))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(children)?
+    };
+    Ok(plan.with_runtime(runtime)?.unwrap_or(plan))
+}
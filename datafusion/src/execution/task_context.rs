@@ -0,0 +1,101 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Context of the execution of the partitions of a plan.
+//!
+//! [`ExecutionPlan::execute`](crate::physical_plan::ExecutionPlan::execute)
+//! receives a [`TaskContext`] along with the partition to execute. The
+//! operators read the settings of the session running the query and the
+//! runtime environment of the executing process from it when they run,
+//! rather than from their own fields, which were set by the process that
+//! planned the query and may have been serialized since.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::execution::context::ExecutionConfig;
+use crate::execution::progress::ProgressReporter;
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::logical_plan::FunctionRegistry;
+
+/// Settings and runtime environment of the execution of a plan
+#[derive(Clone)]
+pub struct TaskContext {
+    /// Settings of the session running the query
+    session_config: ExecutionConfig,
+    /// Runtime environment of the executing process
+    runtime: Arc<RuntimeEnv>,
+}
+
+impl fmt::Debug for TaskContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskContext")
+            .field("batch_size", &self.session_config.batch_size)
+            .field("runtime", &self.runtime)
+            .finish()
+    }
+}
+
+impl TaskContext {
+    /// Create a context executing plans with the settings `session_config`
+    /// in the environment `runtime`
+    pub fn new(session_config: ExecutionConfig, runtime: Arc<RuntimeEnv>) -> Self {
+        Self {
+            session_config,
+            runtime,
+        }
+    }
+
+    /// Settings of the session running the query
+    pub fn session_config(&self) -> &ExecutionConfig {
+        &self.session_config
+    }
+
+    /// Runtime environment of the executing process: its object stores,
+    /// memory and disk managers...
+    pub fn runtime_env(&self) -> Arc<RuntimeEnv> {
+        self.runtime.clone()
+    }
+
+    /// Number of rows of the batches read from data sources and combined by
+    /// the operators
+    pub fn batch_size(&self) -> usize {
+        self.session_config.batch_size
+    }
+
+    /// Registry resolving the user-defined functions of the plan
+    pub fn function_registry(&self) -> Arc<dyn FunctionRegistry> {
+        self.runtime.function_registry.clone()
+    }
+
+    /// Reporter of the progress of partition `partition` of the operator
+    /// named `operator`, if the runtime environment has a progress handler
+    pub fn progress_reporter(
+        &self,
+        operator: &'static str,
+        partition: usize,
+    ) -> Option<ProgressReporter> {
+        self.runtime.progress_handler.as_ref().map(|handler| {
+            ProgressReporter::new(
+                handler.clone(),
+                operator,
+                partition,
+                self.session_config.progress_interval,
+            )
+        })
+    }
+}
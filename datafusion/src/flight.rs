@@ -0,0 +1,459 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! An [Arrow Flight](https://arrow.apache.org/docs/format/Flight.html) service
+//! serving the results of queries, see [`FlightQueryService`].
+//!
+//! A query is either SQL or a serialized physical plan, see [`FlightQuery`].
+//! Clients can:
+//! * fetch the result of a query with `do_get`, given the ticket of the query
+//! * get the schema of the result of a query and a ticket per output partition
+//!   with `get_flight_info`, to fetch the partitions in parallel. The tickets
+//!   execute the partitions of the same physical plan, kept by the service
+//!   until each of them is fetched once.
+//! * exchange a query for its ticket with `handshake`, by sending the query as
+//!   the payload of a handshake request
+//!
+//! ```no_run
+//! # use datafusion::error::Result;
+//! # use datafusion::flight::FlightQueryService;
+//! # use datafusion::prelude::*;
+//! # #[tokio::main]
+//! # async fn main() -> Result<()> {
+//! let mut ctx = ExecutionContext::new();
+//! ctx.register_csv("example", "tests/example.csv", CsvReadOptions::new())
+//!     .await?;
+//! let listener = tokio::net::TcpListener::bind("127.0.0.1:50051").await?;
+//! FlightQueryService::new(ctx).serve(listener).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::utils::flight_data_from_arrow_batch;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint,
+    FlightInfo, HandshakeRequest, HandshakeResponse, IpcMessage, Location, PutResult,
+    SchemaAsIpc, SchemaResult, Ticket,
+};
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::ExecutionContext;
+use crate::physical_plan::serde::{from_json_with_registry, to_json};
+use crate::physical_plan::{execute_stream, ExecutionPlan};
+
+/// A query served by a [`FlightQueryService`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FlightQuery {
+    /// A SQL query, planned by the context of the service
+    Sql(String),
+    /// A physical plan serialized by [`to_json`], whose user-defined
+    /// functions are resolved by the context of the service
+    PhysicalPlan(String),
+}
+
+impl FlightQuery {
+    /// The query of the physical plan `plan`
+    pub fn physical_plan(plan: &Arc<dyn ExecutionPlan>) -> Result<Self> {
+        Ok(Self::PhysicalPlan(to_json(plan)?))
+    }
+
+    /// Encode this query, as the command of a flight descriptor or the
+    /// payload of a handshake request
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        encode(self)
+    }
+
+    /// The flight descriptor of this query, to get its flight info
+    pub fn to_descriptor(&self) -> Result<FlightDescriptor> {
+        Ok(FlightDescriptor {
+            r#type: DescriptorType::Cmd as i32,
+            cmd: self.to_bytes()?,
+            path: vec![],
+        })
+    }
+
+    /// The ticket of the result of this query, over all its partitions
+    pub fn to_ticket(&self) -> Result<Ticket> {
+        FlightTicket::Query(self.clone()).to_ticket()
+    }
+}
+
+/// The ticket of the result of a query, or of one of the output partitions of
+/// a plan kept by the service
+#[derive(Debug, Serialize, Deserialize)]
+enum FlightTicket {
+    Query(FlightQuery),
+    Partition { plan: u64, partition: usize },
+}
+
+impl FlightTicket {
+    fn to_ticket(&self) -> Result<Ticket> {
+        Ok(Ticket {
+            ticket: encode(self)?,
+        })
+    }
+}
+
+fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec(value).map_err(|e| {
+        DataFusionError::Internal(format!("Cannot encode a flight message: {}", e))
+    })
+}
+
+fn decode<T: DeserializeOwned>(bytes: &[u8]) -> std::result::Result<T, Status> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| Status::invalid_argument(format!("Invalid flight message: {}", e)))
+}
+
+/// The query of a flight descriptor, see [`FlightQuery::to_descriptor`]
+fn query_of(descriptor: &FlightDescriptor) -> std::result::Result<FlightQuery, Status> {
+    if descriptor.r#type != DescriptorType::Cmd as i32 {
+        return Err(Status::invalid_argument(
+            "The flight descriptor must be the command of a query",
+        ));
+    }
+    decode(&descriptor.cmd)
+}
+
+/// Map the errors of DataFusion to the status codes of gRPC
+fn to_status(e: DataFusionError) -> Status {
+    match e.find_root() {
+        DataFusionError::SQL(_)
+        | DataFusionError::Plan(_)
+        | DataFusionError::NotImplemented(_) => Status::invalid_argument(e.to_string()),
+        DataFusionError::ResourcesExhausted(_) => {
+            Status::resource_exhausted(e.to_string())
+        }
+        DataFusionError::Cancelled => Status::cancelled(e.to_string()),
+        _ => Status::internal(e.to_string()),
+    }
+}
+
+/// The physical plans of the flight infos, whose output partitions are
+/// executed by the tickets of their endpoints
+#[derive(Default)]
+struct SharedPlans {
+    next_id: u64,
+    plans: HashMap<u64, SharedPlan>,
+}
+
+struct SharedPlan {
+    plan: Arc<dyn ExecutionPlan>,
+    /// Whether each output partition is executed
+    executed: Vec<bool>,
+}
+
+/// A Flight service executing the [`FlightQuery`]s of its clients with an
+/// [`ExecutionContext`]
+#[derive(Clone)]
+pub struct FlightQueryService {
+    ctx: ExecutionContext,
+    location: Option<String>,
+    plans: Arc<Mutex<SharedPlans>>,
+}
+
+impl FlightQueryService {
+    /// Create a service executing the queries with `ctx`
+    pub fn new(ctx: ExecutionContext) -> Self {
+        Self {
+            ctx,
+            location: None,
+            plans: Arc::new(Mutex::new(SharedPlans::default())),
+        }
+    }
+
+    /// Advertise `location`, e.g. `grpc://host:port`, in the endpoints of the
+    /// flight infos. By default the endpoints have no location, meaning that
+    /// the partitions are fetched from this service.
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    /// The gRPC service of this service, to add to a tonic server
+    pub fn into_server(self) -> FlightServiceServer<Self> {
+        FlightServiceServer::new(self)
+    }
+
+    /// Serve the connections accepted by `listener` until the server fails
+    pub async fn serve(self, listener: TcpListener) -> Result<()> {
+        Server::builder()
+            .add_service(self.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .map_err(|e| {
+                DataFusionError::Execution(format!("Flight service failed: {}", e))
+            })
+    }
+
+    async fn plan(&self, query: &FlightQuery) -> Result<Arc<dyn ExecutionPlan>> {
+        match query {
+            FlightQuery::Sql(sql) => {
+                let plan = self.ctx.create_logical_plan(sql)?;
+                let plan = self.ctx.optimize(&plan)?;
+                self.ctx.create_physical_plan(&plan).await
+            }
+            FlightQuery::PhysicalPlan(json) => {
+                from_json_with_registry(json, &self.ctx.function_registry())
+            }
+        }
+    }
+
+    /// Keep `plan` for the tickets of its output partitions, and return its id
+    fn share_plan(&self, plan: Arc<dyn ExecutionPlan>) -> u64 {
+        let mut plans = self.plans.lock().unwrap();
+        let id = plans.next_id;
+        plans.next_id += 1;
+        let executed = vec![false; plan.output_partitioning().partition_count()];
+        plans.plans.insert(id, SharedPlan { plan, executed });
+        id
+    }
+
+    /// The plan `id` whose output partition `partition` is not executed yet,
+    /// forgotten once all its partitions are executed
+    fn plan_of_partition(
+        &self,
+        id: u64,
+        partition: usize,
+    ) -> std::result::Result<Arc<dyn ExecutionPlan>, Status> {
+        let mut plans = self.plans.lock().unwrap();
+        let shared = plans.plans.get_mut(&id).ok_or_else(|| {
+            Status::not_found(format!(
+                "The plan {} is unknown or all its partitions are fetched",
+                id
+            ))
+        })?;
+        match shared.executed.get_mut(partition) {
+            Some(executed) if !*executed => *executed = true,
+            Some(_) => {
+                return Err(Status::failed_precondition(format!(
+                    "The partition {} of the plan {} is already fetched",
+                    partition, id
+                )))
+            }
+            None => {
+                return Err(Status::invalid_argument(format!(
+                    "Invalid partition {}, the plan has {} partitions",
+                    partition,
+                    shared.executed.len()
+                )))
+            }
+        }
+        let plan = shared.plan.clone();
+        if shared.executed.iter().all(|executed| *executed) {
+            plans.plans.remove(&id);
+        }
+        Ok(plan)
+    }
+}
+
+type FlightStream<T> =
+    Pin<Box<dyn Stream<Item = std::result::Result<T, Status>> + Send + Sync>>;
+
+#[tonic::async_trait]
+impl FlightService for FlightQueryService {
+    type HandshakeStream = FlightStream<HandshakeResponse>;
+    type ListFlightsStream = FlightStream<FlightInfo>;
+    type DoGetStream = FlightStream<FlightData>;
+    type DoPutStream = FlightStream<PutResult>;
+    type DoActionStream = FlightStream<arrow_flight::Result>;
+    type ListActionsStream = FlightStream<ActionType>;
+    type DoExchangeStream = FlightStream<FlightData>;
+
+    /// Answer every query of the client with its ticket, after checking that
+    /// it can be planned
+    async fn handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> std::result::Result<Response<Self::HandshakeStream>, Status> {
+        let mut requests = request.into_inner();
+        let mut responses = vec![];
+        while let Some(request) = requests.message().await? {
+            let query: FlightQuery = decode(&request.payload)?;
+            self.plan(&query).await.map_err(to_status)?;
+            let ticket = query.to_ticket().map_err(to_status)?;
+            responses.push(Ok(HandshakeResponse {
+                protocol_version: request.protocol_version,
+                payload: ticket.ticket,
+            }));
+        }
+        Ok(Response::new(Box::pin(futures::stream::iter(responses))))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> std::result::Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    /// Return the schema of the result of the query of the descriptor, and an
+    /// endpoint per output partition of its physical plan. The plan is kept
+    /// until all its partitions are fetched, so that they are executed once,
+    /// by the same plan.
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<FlightInfo>, Status> {
+        let descriptor = request.into_inner();
+        let query = query_of(&descriptor)?;
+        let plan = self.plan(&query).await.map_err(to_status)?;
+        let schema = plan.schema();
+        let total_records = plan.statistics().num_rows.map_or(-1, |rows| rows as i64);
+        let partitions = plan.output_partitioning().partition_count();
+        let id = self.share_plan(plan);
+
+        let location = self
+            .location
+            .iter()
+            .map(|uri| Location { uri: uri.clone() })
+            .collect::<Vec<_>>();
+        let endpoint = (0..partitions)
+            .map(|partition| {
+                let ticket = FlightTicket::Partition {
+                    plan: id,
+                    partition,
+                };
+                Ok(FlightEndpoint {
+                    ticket: Some(ticket.to_ticket()?),
+                    location: location.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()
+            .map_err(to_status)?;
+
+        let options = IpcWriteOptions::default();
+        let IpcMessage(schema) = SchemaAsIpc::new(&schema, &options)
+            .try_into()
+            .map_err(|e| to_status(DataFusionError::ArrowError(e)))?;
+        Ok(Response::new(FlightInfo {
+            schema,
+            flight_descriptor: Some(descriptor),
+            endpoint,
+            total_records,
+            total_bytes: -1,
+        }))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> std::result::Result<Response<SchemaResult>, Status> {
+        let query = query_of(&request.into_inner())?;
+        let plan = self.plan(&query).await.map_err(to_status)?;
+        let options = IpcWriteOptions::default();
+        Ok(Response::new(
+            SchemaAsIpc::new(&plan.schema(), &options).into(),
+        ))
+    }
+
+    /// Stream the schema of the result of the ticket, then its batches
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> std::result::Result<Response<Self::DoGetStream>, Status> {
+        let ticket: FlightTicket = decode(&request.into_inner().ticket)?;
+        let task_ctx = self.ctx.task_ctx();
+        let (schema, stream) = match ticket {
+            FlightTicket::Query(query) => {
+                let plan = self.plan(&query).await.map_err(to_status)?;
+                (plan.schema(), execute_stream(plan, task_ctx).await)
+            }
+            FlightTicket::Partition { plan, partition } => {
+                let plan = self.plan_of_partition(plan, partition)?;
+                (plan.schema(), plan.execute(partition, task_ctx).await)
+            }
+        };
+        let mut stream = stream.map_err(to_status)?;
+
+        // the batches are sent from a task, as the streams of the batches are
+        // not Sync
+        let (sender, receiver) = mpsc::channel(2);
+        tokio::spawn(async move {
+            let options = IpcWriteOptions::default();
+            let schema = SchemaAsIpc::new(&schema, &options).into();
+            if sender.send(Ok(schema)).await.is_err() {
+                return;
+            }
+            while let Some(batch) = stream.next().await {
+                let messages = match batch {
+                    Ok(batch) => {
+                        let (dictionaries, batch) =
+                            flight_data_from_arrow_batch(&batch, &options);
+                        dictionaries
+                            .into_iter()
+                            .chain(std::iter::once(batch))
+                            .map(Ok)
+                            .collect()
+                    }
+                    Err(e) => vec![Err(Status::internal(e.to_string()))],
+                };
+                for message in messages {
+                    // the client is gone
+                    if sender.send(message).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> std::result::Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> std::result::Result<Response<Self::ListActionsStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> std::result::Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("Not yet implemented"))
+    }
+}
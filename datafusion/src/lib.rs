@@ -180,6 +180,7 @@
 //! * extend the planner to use user-defined logical and physical nodes ([`QueryPlanner`](execution::context::QueryPlanner))
 //! * declare and use user-defined scalar functions ([`ScalarUDF`](physical_plan::udf::ScalarUDF))
 //! * declare and use user-defined aggregate functions ([`AggregateUDF`](physical_plan::udaf::AggregateUDF))
+//! * declare and use user-defined window functions ([`WindowUDF`](physical_plan::udwf::WindowUDF))
 //!
 //! you can find examples of each of them in examples section.
 //!
@@ -218,6 +219,8 @@ pub mod dataframe;
 pub mod datasource;
 pub mod error;
 pub mod execution;
+#[cfg(feature = "flight")]
+pub mod flight;
 pub mod logical_plan;
 pub mod optimizer;
 pub mod physical_optimizer;
@@ -26,10 +26,11 @@ use crate::datasource::{
 };
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::plan::{
-    Aggregate, Analyze, EmptyRelation, Explain, Filter, Join, Projection, Sort,
-    TableScan, ToStringifiedPlan, Union, Window,
+    Aggregate, Analyze, EmptyRelation, Explain, ExplainFormat, Filter, Join, Projection,
+    RecursiveQuery, Sort, TableScan, ToStringifiedPlan, Union, Unnest, Window,
 };
 use crate::optimizer::utils;
+use crate::physical_plan::array_expressions::list_element_type;
 use crate::prelude::*;
 use crate::scalar::ScalarValue;
 use arrow::{
@@ -344,6 +345,53 @@ impl LogicalPlanBuilder {
         Self::scan(table_name, Arc::new(provider), projection)
     }
 
+    /// Scan an Arrow IPC data source
+    pub async fn scan_ipc(
+        object_store: Arc<dyn ObjectStore>,
+        path: impl Into<String>,
+        options: IpcReadOptions<'_>,
+        projection: Option<Vec<usize>>,
+        target_partitions: usize,
+    ) -> Result<Self> {
+        let path = path.into();
+        Self::scan_ipc_with_name(
+            object_store,
+            path.clone(),
+            options,
+            projection,
+            path,
+            target_partitions,
+        )
+        .await
+    }
+
+    /// Scan an Arrow IPC data source and register it with a given table name
+    pub async fn scan_ipc_with_name(
+        object_store: Arc<dyn ObjectStore>,
+        path: impl Into<String>,
+        options: IpcReadOptions<'_>,
+        projection: Option<Vec<usize>>,
+        table_name: impl Into<String>,
+        target_partitions: usize,
+    ) -> Result<Self> {
+        let listing_options = options.to_listing_options(target_partitions);
+
+        let path: String = path.into();
+
+        let resolved_schema = match options.schema {
+            Some(s) => s,
+            None => {
+                listing_options
+                    .infer_schema(Arc::clone(&object_store), &path)
+                    .await?
+            }
+        };
+        let provider =
+            ListingTable::new(object_store, path, resolved_schema, listing_options);
+
+        Self::scan(table_name, Arc::new(provider), projection)
+    }
+
     /// Scan an empty data source, mainly used in tests
     pub fn scan_empty(
         name: Option<&str>,
@@ -384,12 +432,13 @@ impl LogicalPlanBuilder {
         let projected_schema = projection
             .as_ref()
             .map(|p| {
-                DFSchema::new(
+                DFSchema::new_with_metadata(
                     p.iter()
                         .map(|i| {
                             DFField::from_qualified(&table_name, schema.field(*i).clone())
                         })
                         .collect(),
+                    schema.metadata().clone(),
                 )
             })
             .unwrap_or_else(|| {
@@ -419,7 +468,23 @@ impl LogicalPlanBuilder {
         // if there's an empty over, it'll be at the top level
         groups.sort_by(|(key_a, _), (key_b, _)| key_a.len().cmp(&key_b.len()));
         groups.reverse();
-        for (_, exprs) in groups {
+        // then, from the bottom up, nest right above each group the longest
+        // group sorted by a prefix of the sort keys of the group last sorted,
+        // which shares its sort
+        let mut nested = Vec::with_capacity(groups.len());
+        let mut sorted_by: Vec<Expr> = vec![];
+        while !groups.is_empty() {
+            let next = groups
+                .iter()
+                .position(|(key, _)| !key.is_empty() && sorted_by.starts_with(key))
+                .unwrap_or(0);
+            let (key, exprs) = groups.remove(next);
+            if !sorted_by.starts_with(&key) {
+                sorted_by = key;
+            }
+            nested.push(exprs);
+        }
+        for exprs in nested {
             let window_exprs = exprs.into_iter().cloned().collect::<Vec<_>>();
             // the partition and sort itself is done at physical level, see physical_planner's
             // fn create_initial_plan
@@ -492,7 +557,10 @@ impl LogicalPlanBuilder {
 
                 expr.extend(missing_exprs);
 
-                let new_schema = DFSchema::new(exprlist_to_fields(&expr, input_schema)?)?;
+                let new_schema = DFSchema::new_with_metadata(
+                    exprlist_to_fields(&expr, input_schema)?,
+                    input_schema.metadata().clone(),
+                )?;
 
                 Ok(LogicalPlan::Projection(Projection {
                     expr,
@@ -559,7 +627,10 @@ impl LogicalPlanBuilder {
             .iter()
             .map(|f| Expr::Column(f.qualified_column()))
             .collect();
-        let new_schema = DFSchema::new(exprlist_to_fields(&new_expr, schema)?)?;
+        let new_schema = DFSchema::new_with_metadata(
+            exprlist_to_fields(&new_expr, schema)?,
+            schema.metadata().clone(),
+        )?;
 
         Ok(Self::from(LogicalPlan::Projection(Projection {
             expr: new_expr,
@@ -601,6 +672,35 @@ impl LogicalPlanBuilder {
         join_type: JoinType,
         join_keys: (Vec<impl Into<Column>>, Vec<impl Into<Column>>),
         null_equals_null: bool,
+    ) -> Result<Self> {
+        self.join_on(right, join_type, join_keys, null_equals_null, false)
+    }
+
+    /// Apply a null-aware anti join on a single key, returning the rows for
+    /// which `left_key NOT IN (SELECT right_key FROM right)` is true: none if
+    /// `right` has a null key, and the rows with a null key only if `right`
+    /// is empty
+    pub fn null_aware_anti_join(
+        &self,
+        right: &LogicalPlan,
+        join_keys: (impl Into<Column>, impl Into<Column>),
+    ) -> Result<Self> {
+        self.join_on(
+            right,
+            JoinType::Anti,
+            (vec![join_keys.0], vec![join_keys.1]),
+            false,
+            true,
+        )
+    }
+
+    fn join_on(
+        &self,
+        right: &LogicalPlan,
+        join_type: JoinType,
+        join_keys: (Vec<impl Into<Column>>, Vec<impl Into<Column>>),
+        null_equals_null: bool,
+        null_aware: bool,
     ) -> Result<Self> {
         if join_keys.0.len() != join_keys.1.len() {
             return Err(DataFusionError::Plan(
@@ -690,6 +790,7 @@ impl LogicalPlanBuilder {
             join_constraint: JoinConstraint::On,
             schema: DFSchemaRef::new(join_schema),
             null_equals_null,
+            null_aware,
         })))
     }
 
@@ -722,6 +823,7 @@ impl LogicalPlanBuilder {
             join_constraint: JoinConstraint::Using,
             schema: DFSchemaRef::new(join_schema),
             null_equals_null: false,
+            null_aware: false,
         })))
     }
 
@@ -743,6 +845,88 @@ impl LogicalPlanBuilder {
         })))
     }
 
+    /// Unnest the lists of `column` into a row per element of each list,
+    /// repeating the values of the other columns. The rows whose list is
+    /// empty or null produce no rows.
+    pub fn unnest(&self, column: impl Into<Column>) -> Result<Self> {
+        let column = column.into().normalize(&self.plan)?;
+        let schema = self.plan.schema();
+        let index = schema.index_of_column(&column)?;
+        let fields = schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                if i != index {
+                    return Ok(field.clone());
+                }
+                match list_element_type(field.data_type()) {
+                    Some(element_type) => Ok(DFField::new(
+                        field.qualifier().map(|qualifier| qualifier.as_str()),
+                        field.name(),
+                        element_type.clone(),
+                        true,
+                    )),
+                    None => Err(DataFusionError::Plan(format!(
+                        "Can not unnest {} of type {:?}, which is not a list",
+                        column,
+                        field.data_type()
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::from(LogicalPlan::Unnest(Unnest {
+            input: Arc::new(self.plan.clone()),
+            column,
+            schema: DFSchemaRef::new(DFSchema::new_with_metadata(
+                fields,
+                schema.metadata().clone(),
+            )?),
+        })))
+    }
+
+    /// Apply a recursive query named `name` whose static term is this plan.
+    /// `recursive_term` reads the rows of the previous iteration by scanning
+    /// the [`CteWorkTable`](crate::datasource::cte_worktable::CteWorkTable)
+    /// `name`, and must produce columns of the types of the static term. If
+    /// `is_distinct`, the rows already produced are dropped from the rows of
+    /// an iteration.
+    pub fn recursive_query(
+        &self,
+        name: &str,
+        recursive_term: LogicalPlan,
+        is_distinct: bool,
+    ) -> Result<Self> {
+        let static_fields = self.plan.schema().fields();
+        let recursive_fields = recursive_term.schema().fields();
+        let same_types = static_fields.len() == recursive_fields.len()
+            && static_fields
+                .iter()
+                .zip(recursive_fields)
+                .all(|(s, r)| s.data_type() == r.data_type());
+        if !same_types {
+            return Err(DataFusionError::Plan(format!(
+                "The static and recursive terms of recursive query {} must have \
+                columns of the same types, got {:?} and {:?}",
+                name,
+                static_fields
+                    .iter()
+                    .map(|f| f.data_type())
+                    .collect::<Vec<_>>(),
+                recursive_fields
+                    .iter()
+                    .map(|f| f.data_type())
+                    .collect::<Vec<_>>(),
+            )));
+        }
+        Ok(Self::from(LogicalPlan::RecursiveQuery(RecursiveQuery {
+            name: name.to_owned(),
+            static_term: Arc::new(self.plan.clone()),
+            recursive_term: Arc::new(recursive_term),
+            is_distinct,
+        })))
+    }
+
     /// Apply a window functions to extend the schema
     pub fn window(
         &self,
@@ -757,7 +941,10 @@ impl LogicalPlanBuilder {
         Ok(Self::from(LogicalPlan::Window(Window {
             input: Arc::new(self.plan.clone()),
             window_expr,
-            schema: Arc::new(DFSchema::new(window_fields)?),
+            schema: Arc::new(DFSchema::new_with_metadata(
+                window_fields,
+                self.plan.schema().metadata().clone(),
+            )?),
         })))
     }
 
@@ -773,8 +960,10 @@ impl LogicalPlanBuilder {
         let aggr_expr = normalize_cols(aggr_expr, &self.plan)?;
         let all_expr = group_expr.iter().chain(aggr_expr.iter());
         validate_unique_names("Aggregations", all_expr.clone(), self.plan.schema())?;
-        let aggr_schema =
-            DFSchema::new(exprlist_to_fields(all_expr, self.plan.schema())?)?;
+        let aggr_schema = DFSchema::new_with_metadata(
+            exprlist_to_fields(all_expr, self.plan.schema())?,
+            self.plan.schema().metadata().clone(),
+        )?;
         Ok(Self::from(LogicalPlan::Aggregate(Aggregate {
             input: Arc::new(self.plan.clone()),
             group_expr,
@@ -808,6 +997,7 @@ impl LogicalPlanBuilder {
                 plan: Arc::new(self.plan.clone()),
                 stringified_plans,
                 schema,
+                format: ExplainFormat::Indent,
             })))
         }
     }
@@ -878,7 +1068,8 @@ impl LogicalPlanBuilder {
 }
 
 /// Creates a schema for a join operation.
-/// The fields from the left side are first
+/// The fields from the left side are first, and the metadata of the left side
+/// takes precedence over the metadata of the right side
 pub fn build_join_schema(
     left: &DFSchema,
     right: &DFSchema,
@@ -897,7 +1088,9 @@ pub fn build_join_schema(
         }
     };
 
-    DFSchema::new(fields)
+    let mut metadata = right.metadata().clone();
+    metadata.extend(left.metadata().clone());
+    DFSchema::new_with_metadata(fields, metadata)
 }
 
 /// Errors if one or more expressions have equal names.
@@ -989,7 +1182,10 @@ pub fn project_with_alias(
         }
     }
     validate_unique_names("Projections", projected_expr.iter(), input_schema)?;
-    let input_schema = DFSchema::new(exprlist_to_fields(&projected_expr, input_schema)?)?;
+    let input_schema = DFSchema::new_with_metadata(
+        exprlist_to_fields(&projected_expr, input_schema)?,
+        plan.schema().metadata().clone(),
+    )?;
     let schema = match alias {
         Some(ref alias) => input_schema.replace_qualifier(alias.as_str()),
         None => input_schema,
@@ -1235,6 +1431,36 @@ mod tests {
         }
     }
 
+    #[test]
+    fn plan_builder_unnest() -> Result<()> {
+        let item = Box::new(Field::new("item", DataType::Utf8, true));
+        let schema = Schema::new(vec![
+            Field::new("id", DataType::Int32, false),
+            Field::new("tags", DataType::List(item), true),
+        ]);
+        let plan = LogicalPlanBuilder::scan_empty(Some("t"), &schema, None)?
+            .unnest("tags")?
+            .build()?;
+
+        let expected = "Unnest: #t.tags        \n  TableScan: t projection=None";
+        assert_eq!(expected, format!("{:?}", plan));
+
+        // the lists are replaced by their elements
+        let field = plan.schema().field_with_qualified_name("t", "tags")?;
+        assert_eq!(field.data_type(), &DataType::Utf8);
+        assert!(field.is_nullable());
+
+        // only lists can be unnested
+        let err = LogicalPlanBuilder::scan_empty(Some("t"), &schema, None)?
+            .unnest("id")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Can not unnest #t.id of type Int32, which is not a list"
+        );
+        Ok(())
+    }
+
     fn employee_schema() -> Schema {
         Schema::new(vec![
             Field::new("id", DataType::Int32, false),
@@ -1257,6 +1483,11 @@ mod tests {
         assert!(stringified_plan.should_display(true));
         assert!(stringified_plan.should_display(false)); // display in non verbose mode too
 
+        let stringified_plan =
+            StringifiedPlan::new(PlanType::FinalLogicalPlanWithSchema, "...the plan...");
+        assert!(stringified_plan.should_display(true));
+        assert!(!stringified_plan.should_display(false)); // not in non verbose mode
+
         let stringified_plan =
             StringifiedPlan::new(PlanType::InitialPhysicalPlan, "...the plan...");
         assert!(stringified_plan.should_display(true));
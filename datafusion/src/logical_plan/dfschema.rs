@@ -18,7 +18,7 @@
 //! DFSchema is an extended schema struct that DataFusion uses to provide support for
 //! fields with optional relation names.
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::sync::Arc;
 
@@ -36,16 +36,29 @@ pub type DFSchemaRef = Arc<DFSchema>;
 pub struct DFSchema {
     /// Fields
     fields: Vec<DFField>,
+    /// Additional metadata in form of key value pairs
+    metadata: HashMap<String, String>,
 }
 
 impl DFSchema {
     /// Creates an empty `DFSchema`
     pub fn empty() -> Self {
-        Self { fields: vec![] }
+        Self {
+            fields: vec![],
+            metadata: HashMap::new(),
+        }
     }
 
     /// Create a new `DFSchema`
     pub fn new(fields: Vec<DFField>) -> Result<Self> {
+        Self::new_with_metadata(fields, HashMap::new())
+    }
+
+    /// Create a new `DFSchema` with the key/value `metadata` of the schema
+    pub fn new_with_metadata(
+        fields: Vec<DFField>,
+        metadata: HashMap<String, String>,
+    ) -> Result<Self> {
         let mut qualified_names = HashSet::new();
         let mut unqualified_names = HashSet::new();
 
@@ -86,25 +99,29 @@ impl DFSchema {
                 )));
             }
         }
-        Ok(Self { fields })
+        Ok(Self { fields, metadata })
     }
 
     /// Create a `DFSchema` from an Arrow schema
     pub fn try_from_qualified_schema(qualifier: &str, schema: &Schema) -> Result<Self> {
-        Self::new(
+        Self::new_with_metadata(
             schema
                 .fields()
                 .iter()
                 .map(|f| DFField::from_qualified(qualifier, f.clone()))
                 .collect(),
+            schema.metadata().clone(),
         )
     }
 
-    /// Combine two schemas
+    /// Combine two schemas, the metadata of `self` taking precedence over
+    /// the metadata of `schema`
     pub fn join(&self, schema: &DFSchema) -> Result<Self> {
         let mut fields = self.fields.clone();
         fields.extend_from_slice(schema.fields().as_slice());
-        Self::new(fields)
+        let mut metadata = schema.metadata.clone();
+        metadata.extend(self.metadata.clone());
+        Self::new_with_metadata(fields, metadata)
     }
 
     /// Merge a schema into self
@@ -120,6 +137,11 @@ impl DFSchema {
                 self.fields.push(field.clone());
             }
         }
+        for (key, value) in other_schema.metadata() {
+            self.metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
     }
 
     /// Get a list of fields
@@ -127,6 +149,11 @@ impl DFSchema {
         &self.fields
     }
 
+    /// Get the key/value metadata of the schema
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
     /// Returns an immutable reference of a specific `Field` instance selected using an
     /// offset within the internal `fields` vector
     pub fn field(&self, i: usize) -> &DFField {
@@ -247,6 +274,44 @@ impl DFSchema {
         }
     }
 
+    /// Resolve the dotted `path` of an identifier such as `t.pair.x` to the
+    /// column it starts with and the names of the nested struct fields to
+    /// access in that column.
+    ///
+    /// A leading `relation.column` takes precedence over an unqualified
+    /// column followed by field names, so `t.x` is the column `x` of the
+    /// relation `t` whenever there is one, and the field `x` of the column
+    /// `t` otherwise.
+    pub fn resolve_column_path<'a>(
+        &self,
+        path: &'a [String],
+    ) -> Result<(Column, &'a [String])> {
+        if path.len() >= 2
+            && self
+                .index_of_column_by_name(Some(&path[0]), &path[1])
+                .is_ok()
+        {
+            let column = Column {
+                relation: Some(path[0].clone()),
+                name: path[1].clone(),
+            };
+            return Ok((column, &path[2..]));
+        }
+        match path.first() {
+            Some(name) => {
+                self.field_with_unqualified_name(name)?;
+                let column = Column {
+                    relation: None,
+                    name: name.clone(),
+                };
+                Ok((column, &path[1..]))
+            }
+            None => Err(DataFusionError::Plan(
+                "Cannot resolve an empty column path".to_owned(),
+            )),
+        }
+    }
+
     /// Check to see if unqualified field names matches field names in Arrow schema
     pub fn matches_arrow_schema(&self, arrow_schema: &Schema) -> bool {
         self.fields
@@ -263,6 +328,7 @@ impl DFSchema {
                 .into_iter()
                 .map(|f| f.strip_qualifier())
                 .collect(),
+            metadata: self.metadata,
         }
     }
 
@@ -272,15 +338,9 @@ impl DFSchema {
             fields: self
                 .fields
                 .into_iter()
-                .map(|f| {
-                    DFField::new(
-                        Some(qualifier),
-                        f.name(),
-                        f.data_type().to_owned(),
-                        f.is_nullable(),
-                    )
-                })
+                .map(|f| DFField::from_qualified(qualifier, f.field))
                 .collect(),
+            metadata: self.metadata,
         }
     }
 
@@ -300,21 +360,9 @@ impl DFSchema {
 impl Into<Schema> for DFSchema {
     /// Convert a schema into a DFSchema
     fn into(self) -> Schema {
-        Schema::new(
-            self.fields
-                .into_iter()
-                .map(|f| {
-                    if f.qualifier().is_some() {
-                        Field::new(
-                            f.name().as_str(),
-                            f.data_type().to_owned(),
-                            f.is_nullable(),
-                        )
-                    } else {
-                        f.field
-                    }
-                })
-                .collect(),
+        Schema::new_with_metadata(
+            self.fields.into_iter().map(|f| f.field).collect(),
+            self.metadata,
         )
     }
 }
@@ -322,7 +370,10 @@ impl Into<Schema> for DFSchema {
 impl Into<Schema> for &DFSchema {
     /// Convert a schema into a DFSchema
     fn into(self) -> Schema {
-        Schema::new(self.fields.iter().map(|f| f.field.clone()).collect())
+        Schema::new_with_metadata(
+            self.fields.iter().map(|f| f.field.clone()).collect(),
+            self.metadata.clone(),
+        )
     }
 }
 
@@ -330,12 +381,13 @@ impl Into<Schema> for &DFSchema {
 impl TryFrom<Schema> for DFSchema {
     type Error = DataFusionError;
     fn try_from(schema: Schema) -> std::result::Result<Self, Self::Error> {
-        Self::new(
+        Self::new_with_metadata(
             schema
                 .fields()
                 .iter()
                 .map(|f| DFField::from(f.clone()))
                 .collect(),
+            schema.metadata().clone(),
         )
     }
 }
@@ -501,6 +553,7 @@ impl DFField {
 mod tests {
     use super::*;
     use arrow::datatypes::DataType;
+    use std::collections::BTreeMap;
 
     #[test]
     fn from_unqualified_field() {
@@ -618,6 +671,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn resolve_column_path() -> Result<()> {
+        let nested = DataType::Struct(vec![Field::new("c0", DataType::Int64, true)]);
+        let t2 = Schema::new(vec![
+            Field::new("t1", nested.clone(), true),
+            Field::new("s", nested, true),
+        ]);
+        let left = DFSchema::try_from_qualified_schema("t1", &test_schema_1())?;
+        let right = DFSchema::try_from_qualified_schema("t2", &t2)?;
+        let schema = left.join(&right)?;
+        let path = |p: &str| p.split('.').map(|s| s.to_owned()).collect::<Vec<_>>();
+
+        // a qualified column is preferred over the field of a struct column
+        let p = path("t1.c0");
+        let (column, fields) = schema.resolve_column_path(&p)?;
+        assert_eq!(Column::from_qualified_name("t1.c0"), column);
+        assert!(fields.is_empty());
+
+        let p = path("t2.s.c0");
+        let (column, fields) = schema.resolve_column_path(&p)?;
+        assert_eq!(Column::from_qualified_name("t2.s"), column);
+        assert_eq!(&["c0".to_owned()], fields);
+
+        let p = path("s.c0");
+        let (column, fields) = schema.resolve_column_path(&p)?;
+        assert_eq!(Column::from_name("s"), column);
+        assert_eq!(&["c0".to_owned()], fields);
+
+        // there is no column `t1.x`, so this is the field `x` of the column `t1`
+        let p = path("t1.x");
+        let (column, fields) = schema.resolve_column_path(&p)?;
+        assert_eq!(Column::from_name("t1"), column);
+        assert_eq!(&["x".to_owned()], fields);
+
+        assert!(schema.resolve_column_path(&path("x.c0")).is_err());
+        Ok(())
+    }
+
     #[test]
     fn helpful_error_messages() -> Result<()> {
         let schema = DFSchema::try_from_qualified_schema("t1", &test_schema_1())?;
@@ -671,6 +762,48 @@ mod tests {
         assert_eq!(df_schema_ref, arrow_schema_ref.to_dfschema_ref().unwrap());
     }
 
+    #[test]
+    fn metadata_round_trip() -> Result<()> {
+        let mut field = Field::new("c0", DataType::Boolean, true);
+        let field_metadata: BTreeMap<String, String> =
+            [("unit".to_owned(), "m".to_owned())].into_iter().collect();
+        field.set_metadata(Some(field_metadata.clone()));
+        let metadata: HashMap<String, String> = [("origin".to_owned(), "t1".to_owned())]
+            .into_iter()
+            .collect();
+        let schema = Schema::new_with_metadata(vec![field], metadata.clone());
+
+        let df_schema = DFSchema::try_from_qualified_schema("t1", &schema)?;
+        assert_eq!(&metadata, df_schema.metadata());
+        let df_schema = df_schema.replace_qualifier("t2");
+        assert_eq!(&metadata, df_schema.metadata());
+        let arrow_schema: Schema = df_schema.clone().into();
+        assert_eq!(schema, arrow_schema);
+        let arrow_schema: Schema = (&df_schema).into();
+        assert_eq!(schema, arrow_schema);
+
+        // the metadata of the left side takes precedence
+        let other = DFSchema::new_with_metadata(
+            vec![DFField::new(Some("t3"), "c1", DataType::Int64, true)],
+            [
+                ("origin".to_owned(), "t3".to_owned()),
+                ("format".to_owned(), "csv".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
+        )?;
+        let join = df_schema.join(&other)?;
+        assert_eq!(join.metadata()["origin"], "t1");
+        assert_eq!(join.metadata()["format"], "csv");
+        assert_eq!(
+            join.field_with_qualified_name("t2", "c0")?
+                .field()
+                .metadata(),
+            &Some(field_metadata)
+        );
+        Ok(())
+    }
+
     fn test_schema_1() -> Schema {
         Schema::new(vec![
             Field::new("c0", DataType::Boolean, true),
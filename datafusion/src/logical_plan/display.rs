@@ -16,10 +16,14 @@
 // under the License.
 //! This module provides logic for displaying LogicalPlans in various styles
 
-use super::{LogicalPlan, PlanVisitor};
-use arrow::datatypes::Schema;
+use super::{DFSchema, LogicalPlan, PlanVisitor};
+use arrow::datatypes::{Field, Schema};
 use std::fmt;
 
+/// The number of fields [`display_df_schema`] displays before abbreviating
+/// the schema with the count of the others
+const MAX_DISPLAYED_FIELDS: usize = 10;
+
 /// Formats plans with a single line per node. For example:
 ///
 /// Projection: #id
@@ -29,6 +33,11 @@ pub struct IndentVisitor<'a, 'b> {
     f: &'a mut fmt::Formatter<'b>,
     /// If true, includes summarized schema information
     with_schema: bool,
+    /// If true, the summarized schema information includes the qualifiers of
+    /// the fields
+    qualified_schema: bool,
+    /// The number of spaces per indent
+    indent_width: usize,
     /// The current indent
     indent: usize,
 }
@@ -40,9 +49,26 @@ impl<'a, 'b> IndentVisitor<'a, 'b> {
         Self {
             f,
             with_schema,
+            qualified_schema: false,
+            indent_width: 2,
             indent: 0,
         }
     }
+
+    /// Include the schema information on each line with the qualifiers of the
+    /// fields, see [`display_df_schema`]
+    pub fn with_qualified_schema(mut self) -> Self {
+        self.with_schema = true;
+        self.qualified_schema = true;
+        self
+    }
+
+    /// Indent each node by `indent_width` spaces more than its parent, 2 by
+    /// default
+    pub fn with_indent_width(mut self, indent_width: usize) -> Self {
+        self.indent_width = indent_width;
+        self
+    }
 }
 
 impl<'a, 'b> PlanVisitor for IndentVisitor<'a, 'b> {
@@ -52,9 +78,16 @@ impl<'a, 'b> PlanVisitor for IndentVisitor<'a, 'b> {
         if self.indent > 0 {
             writeln!(self.f)?;
         }
-        write!(self.f, "{:indent$}", "", indent = self.indent * 2)?;
+        write!(
+            self.f,
+            "{:indent$}",
+            "",
+            indent = self.indent * self.indent_width
+        )?;
         write!(self.f, "{}", plan.display())?;
-        if self.with_schema {
+        if self.qualified_schema {
+            write!(self.f, " {}", display_df_schema(plan.schema()))?;
+        } else if self.with_schema {
             write!(
                 self.f,
                 " {}",
@@ -103,14 +136,52 @@ pub fn display_schema(schema: &Schema) -> impl fmt::Display + '_ {
                 if idx > 0 {
                     write!(f, ", ")?;
                 }
-                let nullable_str = if field.is_nullable() { ";N" } else { "" };
-                write!(
-                    f,
-                    "{}:{:?}{}",
-                    field.name(),
-                    field.data_type(),
-                    nullable_str
-                )?;
+                write_field(f, field.name(), field)?;
+            }
+            write!(f, "]")
+        }
+    }
+    Wrapper(schema)
+}
+
+/// Print the schema of a plan in a compact representation like
+/// [`display_schema`], with the qualifiers of the fields. Only the first
+/// fields of a long schema are printed, followed by the count of the others.
+///
+/// For example: `[employee.id:Int32, employee.first_name:Utf8;N]`
+///
+/// ```
+/// use arrow::datatypes::{Field, Schema, DataType};
+/// # use datafusion::logical_plan::{display_df_schema, DFSchema};
+/// let schema = Schema::new(
+///     (0..12)
+///         .map(|i| Field::new(&format!("c{}", i), DataType::Int32, false))
+///         .collect(),
+/// );
+/// let schema = DFSchema::try_from_qualified_schema("t", &schema).unwrap();
+///
+///  assert_eq!(
+///      "[t.c0:Int32, t.c1:Int32, t.c2:Int32, t.c3:Int32, t.c4:Int32, \
+///       t.c5:Int32, t.c6:Int32, t.c7:Int32, t.c8:Int32, t.c9:Int32, \
+///       ...2 more]",
+///      format!("{}", display_df_schema(&schema))
+///  );
+/// ```
+pub fn display_df_schema(schema: &DFSchema) -> impl fmt::Display + '_ {
+    struct Wrapper<'a>(&'a DFSchema);
+
+    impl<'a> fmt::Display for Wrapper<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "[")?;
+            let fields = self.0.fields();
+            for (idx, field) in fields.iter().take(MAX_DISPLAYED_FIELDS).enumerate() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+                write_field(f, &field.qualified_name(), field.field())?;
+            }
+            if fields.len() > MAX_DISPLAYED_FIELDS {
+                write!(f, ", ...{} more", fields.len() - MAX_DISPLAYED_FIELDS)?;
             }
             write!(f, "]")
         }
@@ -118,6 +189,12 @@ pub fn display_schema(schema: &Schema) -> impl fmt::Display + '_ {
     Wrapper(schema)
 }
 
+/// Write `field` named `name`, followed by its type and `;N` if nullable
+fn write_field(f: &mut fmt::Formatter, name: &str, field: &Field) -> fmt::Result {
+    let nullable_str = if field.is_nullable() { ";N" } else { "" };
+    write!(f, "{}:{:?}{}", name, field.data_type(), nullable_str)
+}
+
 /// Logic related to creating DOT language graphs.
 #[derive(Default)]
 struct GraphvizBuilder {
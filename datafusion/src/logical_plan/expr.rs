@@ -24,20 +24,28 @@ use crate::field_util::get_indexed_field;
 use crate::logical_plan::{window_frames, DFField, DFSchema, LogicalPlan};
 use crate::physical_plan::functions::Volatility;
 use crate::physical_plan::{
-    aggregates, expressions::binary_operator_data_type, functions, udf::ScalarUDF,
-    window_functions,
+    aggregates,
+    expressions::{binary_operator_data_type, can_cast_types, case_return_type},
+    functions,
+    type_coercion::data_types,
+    udf::ScalarUDF,
+    udwf::{PartitionEvaluatorFactory, WindowUDF},
+    window_functions, ColumnarValue,
 };
 use crate::{physical_plan::udaf::AggregateUDF, scalar::ScalarValue};
 use aggregates::{AccumulatorFunctionImplementation, StateTypeFunction};
-use arrow::{compute::can_cast_types, datatypes::DataType};
-use functions::{ReturnTypeFunction, ScalarFunctionImplementation, Signature};
+use arrow::datatypes::{DataType, Field};
+use functions::{
+    AsyncScalarFunctionImplementation, ReturnTypeFunction, ScalarFunctionImplementation,
+    Signature,
+};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 use std::convert::Infallible;
 use std::fmt;
 use std::ops::Not;
 use std::str::FromStr;
 use std::sync::Arc;
-use serde::{Deserialize, Serialize};
 
 /// A named reference to a qualified field in a schema.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
@@ -254,6 +262,13 @@ pub enum Expr {
         /// The name of the field to take
         key: ScalarValue,
     },
+    /// Builds a [`StructArray`] with one named field per argument
+    Struct {
+        /// The names of the fields
+        names: Vec<String>,
+        /// The expressions computing the values of the fields
+        args: Vec<Expr>,
+    },
     /// Whether an expression is between a given range.
     Between {
         /// The value to compare
@@ -385,22 +400,47 @@ impl Expr {
             Expr::Column(c) => Ok(schema.field_from_column(c)?.data_type().clone()),
             Expr::ScalarVariable(_) => Ok(DataType::Utf8),
             Expr::Literal(l) => Ok(l.get_datatype()),
-            Expr::Case { when_then_expr, .. } => when_then_expr[0].1.get_type(schema),
+            Expr::Case {
+                when_then_expr,
+                else_expr,
+                ..
+            } => {
+                // the results are coerced to a common type, to which NULL coerces
+                let result_types = when_then_expr
+                    .iter()
+                    .map(|(_, then_expr)| then_expr.as_ref())
+                    .chain(else_expr.as_deref())
+                    .map(|e| match e {
+                        Expr::Literal(value) if value.is_null() => Ok(None),
+                        e => e.get_type(schema).map(Some),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                case_return_type(&result_types)
+            }
             Expr::Cast { data_type, .. } => Ok(data_type.clone()),
             Expr::TryCast { data_type, .. } => Ok(data_type.clone()),
             Expr::ScalarUDF { fun, args } => {
-                let data_types = args
+                let arg_types = args
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
-                Ok((fun.return_type)(&data_types)?.as_ref().clone())
+                // the arguments are coerced to the signature when planned
+                let coerced_types = data_types(&arg_types, &fun.signature)?;
+                Ok((fun.return_type)(&coerced_types)?.as_ref().clone())
             }
             Expr::ScalarFunction { fun, args } => {
                 let data_types = args
                     .iter()
                     .map(|e| e.get_type(schema))
                     .collect::<Result<Vec<_>>>()?;
-                functions::return_type(fun, &data_types)
+                let literals = args
+                    .iter()
+                    .map(|e| match e {
+                        Expr::Literal(value) => Some(value.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+                functions::return_type_with_literals(fun, &data_types, &literals)
             }
             Expr::WindowFunction { fun, args, .. } => {
                 let data_types = args
@@ -447,6 +487,20 @@ impl Expr {
 
                 get_indexed_field(&data_type, key).map(|x| x.data_type().clone())
             }
+            Expr::Struct { names, args } => {
+                let fields = names
+                    .iter()
+                    .zip(args.iter())
+                    .map(|(name, arg)| {
+                        Ok(Field::new(
+                            name,
+                            arg.get_type(schema)?,
+                            arg.nullable(schema)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(DataType::Struct(fields))
+            }
         }
     }
 
@@ -467,7 +521,8 @@ impl Expr {
                 else_expr,
                 ..
             } => {
-                // this expression is nullable if any of the input expressions are nullable
+                // this expression is nullable if any of the input expressions are nullable,
+                // or if there is no ELSE, for the rows not matching any WHEN
                 let then_nullable = when_then_expr
                     .iter()
                     .map(|(_, t)| t.nullable(input_schema))
@@ -477,7 +532,7 @@ impl Expr {
                 } else if let Some(e) = else_expr {
                     e.nullable(input_schema)
                 } else {
-                    Ok(false)
+                    Ok(true)
                 }
             }
             Expr::Cast { expr, .. } => expr.nullable(input_schema),
@@ -491,6 +546,10 @@ impl Expr {
             Expr::Negative(expr) => expr.nullable(input_schema),
             Expr::IsNull(_) => Ok(false),
             Expr::IsNotNull(_) => Ok(false),
+            Expr::BinaryExpr {
+                op: Operator::IsDistinctFrom | Operator::IsNotDistinctFrom,
+                ..
+            } => Ok(false),
             Expr::BinaryExpr {
                 ref left,
                 ref right,
@@ -504,8 +563,11 @@ impl Expr {
             )),
             Expr::GetIndexedField { ref expr, key } => {
                 let data_type = expr.get_type(input_schema)?;
-                get_indexed_field(&data_type, key).map(|x| x.is_nullable())
+                let field = get_indexed_field(&data_type, key)?;
+                // the field of a null struct is null as well
+                Ok(field.is_nullable() || expr.nullable(input_schema)?)
             }
+            Expr::Struct { .. } => Ok(false),
         }
     }
 
@@ -517,21 +579,35 @@ impl Expr {
     }
 
     /// Returns a [arrow::datatypes::Field] compatible with this expression.
+    ///
+    /// A column, aliased or not, keeps the metadata of the field of
+    /// `input_schema` it refers to.
     pub fn to_field(&self, input_schema: &DFSchema) -> Result<DFField> {
-        match self {
-            Expr::Column(c) => Ok(DFField::new(
-                c.relation.as_deref(),
-                &c.name,
-                self.get_type(input_schema)?,
-                self.nullable(input_schema)?,
-            )),
-            _ => Ok(DFField::new(
-                None,
-                &self.name(input_schema)?,
-                self.get_type(input_schema)?,
-                self.nullable(input_schema)?,
-            )),
+        let (qualifier, name) = match self {
+            Expr::Column(c) => (c.relation.as_deref(), c.name.clone()),
+            _ => (None, self.name(input_schema)?),
+        };
+        let mut field = Field::new(
+            &name,
+            self.get_type(input_schema)?,
+            self.nullable(input_schema)?,
+        );
+        let column = match self {
+            Expr::Column(c) => Some(c),
+            Expr::Alias(expr, _) => match expr.as_ref() {
+                Expr::Column(c) => Some(c),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(c) = column {
+            let input_field = input_schema.field_from_column(c)?;
+            field.set_metadata(input_field.field().metadata().clone());
         }
+        Ok(match qualifier {
+            Some(qualifier) => DFField::from_qualified(qualifier, field),
+            None => DFField::from(field),
+        })
     }
 
     /// Wraps this expression in a cast to a target [arrow::datatypes::DataType].
@@ -622,6 +698,31 @@ impl Expr {
         binary_expr(self, Operator::NotLike, other)
     }
 
+    /// Return `self ILIKE other`
+    pub fn ilike(self, other: Expr) -> Expr {
+        binary_expr(self, Operator::ILike, other)
+    }
+
+    /// Return `self NOT ILIKE other`
+    pub fn not_ilike(self, other: Expr) -> Expr {
+        binary_expr(self, Operator::NotILike, other)
+    }
+
+    /// Return `self SIMILAR TO other`, as the match of `self` with the
+    /// regular expression the pattern `other` is converted to
+    pub fn similar_to(self, other: Expr) -> Expr {
+        binary_expr(self, Operator::RegexMatch, similar_to_escape(vec![other]))
+    }
+
+    /// Return `self NOT SIMILAR TO other`
+    pub fn not_similar_to(self, other: Expr) -> Expr {
+        binary_expr(
+            self,
+            Operator::RegexNotMatch,
+            similar_to_escape(vec![other]),
+        )
+    }
+
     /// Return `self AS name` alias expression
     pub fn alias(self, name: &str) -> Expr {
         Expr::Alias(Box::new(self), name.to_owned())
@@ -649,6 +750,19 @@ impl Expr {
         Expr::IsNotNull(Box::new(self))
     }
 
+    /// Return the field `name` of the struct `self`.
+    ///
+    /// ```
+    /// # use datafusion::logical_plan::{col, struct_};
+    /// let x = struct_(vec![col("a"), col("b")]).field("a");
+    /// ```
+    pub fn field(self, name: &str) -> Expr {
+        Expr::GetIndexedField {
+            expr: Box::new(self),
+            key: ScalarValue::Utf8(Some(name.to_owned())),
+        }
+    }
+
     /// Create a sort expression from an existing expression.
     ///
     /// ```
@@ -785,6 +899,9 @@ impl Expr {
             }
             Expr::Wildcard => Ok(visitor),
             Expr::GetIndexedField { ref expr, .. } => expr.accept(visitor),
+            Expr::Struct { args, .. } => args
+                .iter()
+                .try_fold(visitor, |visitor, arg| arg.accept(visitor)),
         }?;
 
         visitor.post_visit(self)
@@ -949,6 +1066,10 @@ impl Expr {
                 expr: rewrite_boxed(expr, rewriter)?,
                 key,
             },
+            Expr::Struct { names, args } => Expr::Struct {
+                names,
+                args: rewrite_vec(args, rewriter)?,
+            },
         };
 
         // now rewrite this expression itself
@@ -1115,25 +1236,17 @@ impl CaseBuilder {
             then_expr.push(e.as_ref().to_owned());
         }
 
-        let then_types: Vec<DataType> = then_expr
+        // the types of the other expressions can't be verified until planned
+        let literal_types = then_expr
             .iter()
-            .map(|e| match e {
-                Expr::Literal(_) => e.get_type(&DFSchema::empty()),
-                _ => Ok(DataType::Null),
+            .filter_map(|e| match e {
+                Expr::Literal(value) if !value.is_null() => {
+                    Some(Some(value.get_datatype()))
+                }
+                _ => None,
             })
-            .collect::<Result<Vec<_>>>()?;
-
-        if then_types.contains(&DataType::Null) {
-            // cannot verify types until execution type
-        } else {
-            let unique_types: HashSet<&DataType> = then_types.iter().collect();
-            if unique_types.len() != 1 {
-                return Err(DataFusionError::Plan(format!(
-                    "CASE expression 'then' values had multiple data types: {:?}",
-                    unique_types
-                )));
-            }
-        }
+            .collect::<Vec<_>>();
+        case_return_type(&literal_types)?;
 
         Ok(Expr::Case {
             expr: self.expr.clone(),
@@ -1422,6 +1535,24 @@ pub fn in_list(expr: Expr, list: Vec<Expr>, negated: bool) -> Expr {
     }
 }
 
+/// Create an expression casting `expr` to `data_type`, failing on the values
+/// that can't be casted
+pub fn cast(expr: Expr, data_type: DataType) -> Expr {
+    Expr::Cast {
+        expr: Box::new(expr),
+        data_type,
+    }
+}
+
+/// Create an expression casting `expr` to `data_type`, the values that can't
+/// be casted being nulls
+pub fn try_cast(expr: Expr, data_type: DataType) -> Expr {
+    Expr::TryCast {
+        expr: Box::new(expr),
+        data_type,
+    }
+}
+
 /// Trait for converting a type to a [`Literal`] literal expression.
 pub trait Literal {
     /// convert the value to a Literal expression
@@ -1561,6 +1692,24 @@ pub fn approx_distinct(expr: Expr) -> Expr {
     }
 }
 
+/// Returns the approximate median of the non-null input values, as a double.
+pub fn approx_median(expr: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::ApproxMedian,
+        distinct: false,
+        args: vec![expr],
+    }
+}
+
+/// Returns the sample standard deviation of the non-null input values, as a double.
+pub fn stddev(expr: Expr) -> Expr {
+    Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::Stddev,
+        distinct: false,
+        args: vec![expr],
+    }
+}
+
 // TODO(kszucs): this seems buggy, unary_scalar_expr! is used for many
 // varying arity functions
 /// Create an convenience function representing a unary scalar function
@@ -1649,6 +1798,7 @@ scalar_expr!(SHA224, sha224, string);
 scalar_expr!(SHA256, sha256, string);
 scalar_expr!(SHA384, sha384, string);
 scalar_expr!(SHA512, sha512, string);
+nary_scalar_expr!(SimilarToEscape, similar_to_escape);
 scalar_expr!(SplitPart, split_part, expr, delimiter, index);
 scalar_expr!(StartsWith, starts_with, string, characters);
 scalar_expr!(Strpos, strpos, string, substring);
@@ -1661,8 +1811,13 @@ scalar_expr!(Upper, upper, string);
 // date functions
 scalar_expr!(DatePart, date_part, part, date);
 scalar_expr!(DateTrunc, date_trunc, part, date);
+scalar_expr!(AtTimeZone, at_time_zone, timestamp, time_zone);
+scalar_expr!(ToTimezone, to_timezone, timestamp, time_zone);
+nary_scalar_expr!(FromUnixtime, from_unixtime);
+scalar_expr!(JustifyDays, justify_days, interval);
+scalar_expr!(JustifyHours, justify_hours, interval);
 
-/// returns an array of fixed size with each argument on it.
+/// returns a list with each argument on it.
 pub fn array(args: Vec<Expr>) -> Expr {
     Expr::ScalarFunction {
         fun: functions::BuiltinScalarFunction::Array,
@@ -1670,6 +1825,35 @@ pub fn array(args: Vec<Expr>) -> Expr {
     }
 }
 
+// array functions
+scalar_expr!(ArrayContains, array_contains, array, value);
+scalar_expr!(ArrayLength, array_length, array);
+scalar_expr!(ArrayPosition, array_position, array, value);
+
+/// returns a struct with one field per argument, named after the argument
+/// when it is a column or an alias and `c<i>` otherwise.
+pub fn struct_(args: Vec<Expr>) -> Expr {
+    let (names, args) = args
+        .into_iter()
+        .enumerate()
+        .map(|(i, arg)| match arg {
+            Expr::Column(ref c) => (c.name.clone(), arg),
+            Expr::Alias(arg, name) => (name, *arg),
+            _ => (format!("c{}", i), arg),
+        })
+        .unzip();
+    Expr::Struct { names, args }
+}
+
+/// returns a struct with the given fields.
+pub fn named_struct(fields: Vec<(&str, Expr)>) -> Expr {
+    let (names, args) = fields
+        .into_iter()
+        .map(|(name, arg)| (name.to_owned(), arg))
+        .unzip();
+    Expr::Struct { names, args }
+}
+
 /// Creates a new UDF with a specific signature and specific return type.
 /// This is a helper function to create a new UDF.
 /// The function `create_udf` returns a subset of all possible `ScalarFunction`:
@@ -1691,8 +1875,50 @@ pub fn create_udf(
     )
 }
 
+/// Creates a new UDF accepting the arguments of any of the variants of
+/// `signature`. The arguments are coerced to the variant of the signature
+/// requiring the fewest casts, and `fun` is called with the types of the
+/// coerced arguments, so that a single implementation can handle every
+/// variant.
+pub fn create_udf_with_signature(
+    name: &str,
+    signature: Signature,
+    return_type: ReturnTypeFunction,
+    fun: Arc<
+        dyn Fn(&[DataType], &[ColumnarValue]) -> Result<ColumnarValue> + Send + Sync,
+    >,
+) -> ScalarUDF {
+    let fun: ScalarFunctionImplementation = Arc::new(move |args: &[ColumnarValue]| {
+        let arg_types = args.iter().map(|arg| arg.data_type()).collect::<Vec<_>>();
+        fun(&arg_types, args)
+    });
+    ScalarUDF::new(name, &signature, &return_type, &fun)
+}
+
+/// Creates a new async UDF with a fixed signature and return type, e.g. looking
+/// the values of its arguments up in an external service. `fun` is called with
+/// the arrays of the arguments of each batch, and its calls are only awaited by
+/// the projections, see [`ScalarUDF::new_async`].
+pub fn create_async_udf(
+    name: &str,
+    input_types: Vec<DataType>,
+    return_type: Arc<DataType>,
+    volatility: Volatility,
+    fun: AsyncScalarFunctionImplementation,
+) -> ScalarUDF {
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(return_type.clone()));
+    ScalarUDF::new_async(
+        name,
+        &Signature::exact(input_types, volatility),
+        &return_type,
+        &fun,
+    )
+}
+
 /// Creates a new UDAF with a specific signature, state type and return type.
 /// The signature and state type must match the `Accumulator's implementation`.
+/// The UDAF can be evaluated over sliding window frames without recomputing every
+/// frame when its accumulators implement `Accumulator::retract_batch`.
 #[allow(clippy::rc_buffer)]
 pub fn create_udaf(
     name: &str,
@@ -1713,6 +1939,42 @@ pub fn create_udaf(
     )
 }
 
+/// Creates a new UDAF with a signature over any number of arguments, whose
+/// accumulators exchange their partial states as columns of `state_fields`.
+/// The signature and state fields must match the `Accumulator's implementation`.
+pub fn create_udaf_with_state_fields(
+    name: &str,
+    signature: Signature,
+    return_type: Arc<DataType>,
+    accumulator: AccumulatorFunctionImplementation,
+    state_fields: Vec<Field>,
+) -> AggregateUDF {
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(return_type.clone()));
+    let state_type: StateTypeFunction = Arc::new(|_| Ok(Arc::new(vec![])));
+    AggregateUDF::new(name, &signature, &return_type, &accumulator, &state_type)
+        .with_state_fields(state_fields)
+}
+
+/// Creates a new UDWF with a specific signature and return type.
+/// The evaluators created by `partition_evaluator_factory` receive the
+/// arguments of the rows of one partition of the window at a time, sorted by
+/// the ORDER BY of the window.
+pub fn create_udwf(
+    name: &str,
+    input_types: Vec<DataType>,
+    return_type: Arc<DataType>,
+    volatility: Volatility,
+    partition_evaluator_factory: PartitionEvaluatorFactory,
+) -> WindowUDF {
+    let return_type: ReturnTypeFunction = Arc::new(move |_| Ok(return_type.clone()));
+    WindowUDF::new(
+        name,
+        &Signature::exact(input_types, volatility),
+        &return_type,
+        &partition_evaluator_factory,
+    )
+}
+
 fn fmt_function(
     f: &mut fmt::Formatter,
     fun: &str,
@@ -1854,6 +2116,16 @@ impl fmt::Debug for Expr {
             Expr::GetIndexedField { ref expr, key } => {
                 write!(f, "({:?})[{}]", expr, key)
             }
+            Expr::Struct { names, args } => {
+                write!(f, "struct(")?;
+                for (i, (name, arg)) in names.iter().zip(args.iter()).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {:?}", name, arg)?;
+                }
+                write!(f, ")")
+            }
         }
     }
 }
@@ -1938,6 +2210,16 @@ fn create_name(e: &Expr, input_schema: &DFSchema) -> Result<String> {
             let expr = create_name(expr, input_schema)?;
             Ok(format!("{}[{}]", expr, key))
         }
+        Expr::Struct { names, args } => {
+            let fields = names
+                .iter()
+                .zip(args.iter())
+                .map(|(name, arg)| {
+                    Ok(format!("{}: {}", name, create_name(arg, input_schema)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("struct({})", fields.join(", ")))
+        }
         Expr::ScalarFunction { fun, args, .. } => {
             create_function_name(&fun.to_string(), false, args, input_schema)
         }
@@ -2047,6 +2329,23 @@ mod tests {
         assert!(maybe_expr.is_err());
     }
 
+    #[test]
+    fn case_when_coercible_literal_then_types() -> Result<()> {
+        let expr = when(col("state").eq(lit("CO")), lit(303))
+            .when(col("state").eq(lit("NY")), lit(ScalarValue::Utf8(None)))
+            .otherwise(lit(2.5))?;
+        let schema =
+            DFSchema::new(vec![DFField::new(None, "state", DataType::Utf8, false)])?;
+        assert_eq!(expr.get_type(&schema)?, DataType::Float64);
+        assert!(expr.nullable(&schema)?);
+
+        // without ELSE, the rows not matching any WHEN are null
+        let expr = when(col("state").eq(lit("CO")), lit(303)).end()?;
+        assert_eq!(expr.get_type(&schema)?, DataType::Int32);
+        assert!(expr.nullable(&schema)?);
+        Ok(())
+    }
+
     #[test]
     fn test_lit_timestamp_nano() {
         let expr = col("time").eq(lit_timestamp_nano(10)); // 10 is an implicit i32
@@ -2345,6 +2644,8 @@ mod tests {
         test_scalar_expr!(SHA256, sha256, string);
         test_scalar_expr!(SHA384, sha384, string);
         test_scalar_expr!(SHA512, sha512, string);
+        test_nary_scalar_expr!(SimilarToEscape, similar_to_escape, pattern);
+        test_nary_scalar_expr!(SimilarToEscape, similar_to_escape, pattern, escape);
         test_scalar_expr!(SplitPart, split_part, expr, delimiter, index);
         test_scalar_expr!(StartsWith, starts_with, string, characters);
         test_scalar_expr!(Strpos, strpos, string, substring);
@@ -2356,6 +2657,16 @@ mod tests {
 
         test_scalar_expr!(DatePart, date_part, part, date);
         test_scalar_expr!(DateTrunc, date_trunc, part, date);
+        test_scalar_expr!(AtTimeZone, at_time_zone, timestamp, time_zone);
+        test_scalar_expr!(ToTimezone, to_timezone, timestamp, time_zone);
+        test_nary_scalar_expr!(FromUnixtime, from_unixtime, seconds);
+        test_nary_scalar_expr!(FromUnixtime, from_unixtime, seconds, time_zone);
+        test_scalar_expr!(JustifyDays, justify_days, interval);
+        test_scalar_expr!(JustifyHours, justify_hours, interval);
+
+        test_scalar_expr!(ArrayContains, array_contains, array, value);
+        test_scalar_expr!(ArrayLength, array_length, array);
+        test_scalar_expr!(ArrayPosition, array_position, array, value);
     }
 
     #[test]
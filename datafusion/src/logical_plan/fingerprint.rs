@@ -0,0 +1,98 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Deterministic fingerprints of logical plans
+
+use super::{LogicalPlan, PlanVisitor};
+use crate::physical_plan::fingerprint::FingerprintHasher;
+
+/// Returns a 64-bit digest identifying `plan`, stable across processes.
+///
+/// Every node contributes its one line description (which includes its
+/// expressions and their literals) and its output fields.
+pub fn fingerprint(plan: &LogicalPlan) -> u64 {
+    let mut visitor = FingerprintVisitor {
+        hasher: FingerprintHasher::default(),
+    };
+    // the visitor never stops the recursion
+    let _ = plan.accept(&mut visitor);
+    visitor.hasher.finish()
+}
+
+struct FingerprintVisitor {
+    hasher: FingerprintHasher,
+}
+
+impl PlanVisitor for FingerprintVisitor {
+    type Error = ();
+
+    fn pre_visit(&mut self, plan: &LogicalPlan) -> Result<bool, ()> {
+        self.hasher.write(b"(");
+        self.hasher.write(plan.display().to_string().as_bytes());
+        for field in plan.schema().fields() {
+            self.hasher.write(field.qualified_name().as_bytes());
+            self.hasher
+                .write(format!("{:?}", field.data_type()).as_bytes());
+            self.hasher.write(&[field.is_nullable() as u8]);
+        }
+        Ok(true)
+    }
+
+    fn post_visit(&mut self, _plan: &LogicalPlan) -> Result<bool, ()> {
+        self.hasher.write(b")");
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::test_table_scan;
+
+    fn filter(value: i64) -> Result<LogicalPlan> {
+        LogicalPlanBuilder::from(test_table_scan()?)
+            .filter(col("a").gt(lit(value)))?
+            .project(vec![col("b")])?
+            .build()
+    }
+
+    #[test]
+    fn equal_plans_have_equal_fingerprints() -> Result<()> {
+        assert_eq!(fingerprint(&filter(1)?), fingerprint(&filter(1)?));
+        Ok(())
+    }
+
+    #[test]
+    fn literals_change_fingerprint() -> Result<()> {
+        assert_ne!(fingerprint(&filter(1)?), fingerprint(&filter(2)?));
+        Ok(())
+    }
+
+    #[test]
+    fn shape_changes_fingerprint() -> Result<()> {
+        let scan = test_table_scan()?;
+        let nested = LogicalPlanBuilder::from(scan.clone())
+            .limit(1)?
+            .limit(2)?
+            .build()?;
+        let swapped = LogicalPlanBuilder::from(scan).limit(2)?.limit(1)?.build()?;
+        assert_ne!(fingerprint(&nested), fingerprint(&swapped));
+        Ok(())
+    }
+}
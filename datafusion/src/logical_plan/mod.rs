@@ -26,34 +26,49 @@ mod dfschema;
 mod display;
 mod expr;
 mod extension;
+mod fingerprint;
 mod operators;
 pub mod plan;
 mod registry;
+#[cfg(feature = "substrait")]
+mod substrait;
+mod summary;
 pub mod window_frames;
 pub use builder::{
     build_join_schema, union_with_alias, LogicalPlanBuilder, UNNAMED_TABLE,
 };
 pub use dfschema::{DFField, DFSchema, DFSchemaRef, ToDFSchema};
-pub use display::display_schema;
+pub use display::{display_df_schema, display_schema};
 pub use expr::{
-    abs, acos, and, approx_distinct, array, ascii, asin, atan, avg, binary_expr,
-    bit_length, btrim, case, ceil, character_length, chr, col, columnize_expr,
-    combine_filters, concat, concat_ws, cos, count, count_distinct, create_udaf,
-    create_udf, date_part, date_trunc, digest, exp, exprlist_to_fields, floor, in_list,
-    initcap, left, length, lit, lit_timestamp_nano, ln, log10, log2, lower, lpad, ltrim,
-    max, md5, min, normalize_col, normalize_cols, now, octet_length, or, random,
-    regexp_match, regexp_replace, repeat, replace, replace_col, reverse, right, round,
-    rpad, rtrim, sha224, sha256, sha384, sha512, signum, sin, split_part, sqrt,
-    starts_with, strpos, substr, sum, tan, to_hex, translate, trim, trunc, unalias,
-    unnormalize_col, unnormalize_cols, upper, when, Column, Expr, ExprRewriter,
-    ExpressionVisitor, Literal, Recursion, RewriteRecursion,
+    abs, acos, and, approx_distinct, approx_median, array, array_contains, array_length,
+    array_position, ascii, asin, at_time_zone, atan, avg, binary_expr, bit_length, btrim,
+    case, cast, ceil, character_length, chr, col, columnize_expr, combine_filters,
+    concat, concat_ws, cos, count, count_distinct, create_async_udf, create_udaf,
+    create_udaf_with_state_fields, create_udf, create_udf_with_signature, create_udwf,
+    date_part, date_trunc, digest, exp, exprlist_to_fields, floor, from_unixtime,
+    in_list, initcap, justify_days, justify_hours, left, length, lit, lit_timestamp_nano,
+    ln, log10, log2, lower, lpad, ltrim, max, md5, min, named_struct, normalize_col,
+    normalize_cols, now, octet_length, or, random, regexp_match, regexp_replace, repeat,
+    replace, replace_col, reverse, right, round, rpad, rtrim, sha224, sha256, sha384,
+    sha512, signum, similar_to_escape, sin, split_part, sqrt, starts_with, stddev,
+    strpos, struct_, substr, sum, tan, to_hex, to_timezone, translate, trim, trunc,
+    try_cast, unalias, unnormalize_col, unnormalize_cols, upper, when, Column, Expr,
+    ExprRewriter, ExpressionVisitor, Literal, Recursion, RewriteRecursion,
 };
 pub use extension::UserDefinedLogicalNode;
+pub use fingerprint::fingerprint;
 pub use operators::Operator;
 pub use plan::{
     CreateExternalTable, CreateMemoryTable, CrossJoin, DropTable, EmptyRelation,
-    JoinConstraint, JoinType, Limit, LogicalPlan, Partitioning, PlanType, PlanVisitor,
-    Repartition, TableScan, Union, Values,
+    ExplainFormat, InsertInto, JoinConstraint, JoinType, Limit, LogicalPlan,
+    Partitioning, PlanType, PlanVisitor, RecursiveQuery, Repartition, SetVariable,
+    TableScan, Union, Unnest, Values,
 };
 pub(crate) use plan::{StringifiedPlan, ToStringifiedPlan};
 pub use registry::FunctionRegistry;
+#[cfg(feature = "substrait")]
+pub use substrait::{
+    from_substrait, to_substrait, DEFAULT_TYPE_VARIATION, LARGE_CONTAINER_TYPE_VARIATION,
+    UNSIGNED_INTEGER_TYPE_VARIATION,
+};
+pub use summary::{collect_filters, collect_table_scans, PlanSummary};
@@ -25,12 +25,12 @@ use crate::error::DataFusionError;
 use crate::logical_plan::dfschema::DFSchemaRef;
 use crate::sql::parser::FileType;
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::{HashMap, HashSet},
     fmt::{self, Display},
     sync::Arc,
 };
-use serde::{Deserialize, Serialize};
 
 /// Join type
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq)]
@@ -136,6 +136,22 @@ pub struct Repartition {
     pub partitioning_scheme: Partitioning,
 }
 
+/// Unnests a column of lists into a row per element of each list, repeating
+/// the values of the other columns of the row.
+///
+/// Like an inner join of each row with the elements of its list, the rows
+/// whose list is empty or null produce no rows.
+#[derive(Clone)]
+pub struct Unnest {
+    /// The incoming logical plan
+    pub input: Arc<LogicalPlan>,
+    /// The column of lists to unnest
+    pub column: Column,
+    /// The output schema, where the column of lists is replaced by a nullable
+    /// column of their elements
+    pub schema: DFSchemaRef,
+}
+
 /// Union multiple inputs
 #[derive(Clone)]
 pub struct Union {
@@ -147,6 +163,24 @@ pub struct Union {
     pub alias: Option<String>,
 }
 
+/// Evaluates a recursive common table expression: the recursive term is
+/// evaluated over the rows produced by the previous iteration, starting with
+/// the rows of the static term, until an iteration produces no rows. The
+/// result is the union of the rows of all the iterations.
+#[derive(Clone)]
+pub struct RecursiveQuery {
+    /// The name of the common table expression, by which the recursive term
+    /// reads the rows of the previous iteration
+    pub name: String,
+    /// The term evaluated once, producing the rows of the first iteration
+    pub static_term: Arc<LogicalPlan>,
+    /// The term evaluated over the rows of the previous iteration
+    pub recursive_term: Arc<LogicalPlan>,
+    /// Whether the rows already produced are dropped from the rows of an
+    /// iteration (`UNION`), or kept (`UNION ALL`)
+    pub is_distinct: bool,
+}
+
 /// Creates an in memory table.
 #[derive(Clone)]
 pub struct CreateMemoryTable {
@@ -156,6 +190,15 @@ pub struct CreateMemoryTable {
     pub input: Arc<LogicalPlan>,
 }
 
+/// Appends the rows of a query to an in memory table.
+#[derive(Clone)]
+pub struct InsertInto {
+    /// The table name
+    pub name: String,
+    /// The logical plan of the rows, with the columns of the table
+    pub input: Arc<LogicalPlan>,
+}
+
 /// Creates an external table.
 #[derive(Clone)]
 pub struct CreateExternalTable {
@@ -169,6 +212,11 @@ pub struct CreateExternalTable {
     pub file_type: FileType,
     /// Whether the CSV file contains a header
     pub has_header: bool,
+    /// Partition columns and the types of their values, appended to the
+    /// table schema
+    pub table_partition_cols: Vec<(String, DataType)>,
+    /// Format specific options
+    pub options: HashMap<String, String>,
 }
 
 /// Drops a table.
@@ -182,6 +230,17 @@ pub struct DropTable {
     pub schema: DFSchemaRef,
 }
 
+/// Sets a setting of the session for its subsequent queries.
+#[derive(Clone)]
+pub struct SetVariable {
+    /// The name of the setting
+    pub variable: String,
+    /// The value of the setting, parsed when it is set
+    pub value: String,
+    /// Dummy schema
+    pub schema: DFSchemaRef,
+}
+
 /// Produces a relation with string representations of
 /// various parts of the plan
 #[derive(Clone)]
@@ -194,6 +253,30 @@ pub struct Explain {
     pub stringified_plans: Vec<StringifiedPlan>,
     /// The output schema of the explain (2 columns of text)
     pub schema: DFSchemaRef,
+    /// How the plans are formatted
+    pub format: ExplainFormat,
+}
+
+/// How `EXPLAIN` formats the plans, chosen by `EXPLAIN (FORMAT format)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExplainFormat {
+    /// A line per node, indented under its parent
+    Indent,
+    /// A graph in the `DOT` language, which can be rendered by
+    /// [`graphviz`](https://graphviz.org/)
+    Graphviz,
+}
+
+impl ExplainFormat {
+    /// Create a stringified plan of `plan` in this format
+    pub fn stringify(self, plan: &LogicalPlan, plan_type: PlanType) -> StringifiedPlan {
+        match self {
+            ExplainFormat::Indent => plan.to_stringified(plan_type),
+            ExplainFormat::Graphviz => {
+                StringifiedPlan::new(plan_type, plan.display_graphviz().to_string())
+            }
+        }
+    }
 }
 
 /// Runs the actual plan, and then prints the physical plan with
@@ -284,6 +367,11 @@ pub struct Join {
     pub schema: DFSchemaRef,
     /// If null_equals_null is true, null == null else null != null
     pub null_equals_null: bool,
+    /// If null_aware is true, the anti join follows the semantics of
+    /// `NOT IN`: no row is returned if the right input has a null key, and
+    /// the rows of the left input with a null key are only returned if the
+    /// right input is empty
+    pub null_aware: bool,
 }
 /// A LogicalPlan represents the different types of relational
 /// operators (such as Projection, Filter, etc) and can be created by
@@ -320,8 +408,12 @@ pub enum LogicalPlan {
     CrossJoin(CrossJoin),
     /// Repartition the plan based on a partitioning scheme.
     Repartition(Repartition),
+    /// Unnests a column of lists into a row per element of each list.
+    Unnest(Unnest),
     /// Union multiple inputs
     Union(Union),
+    /// Evaluates a recursive common table expression until a fixpoint
+    RecursiveQuery(RecursiveQuery),
     /// Produces rows from a table provider by reference or from the context
     TableScan(TableScan),
     /// Produces no rows: An empty relation with an empty schema
@@ -332,8 +424,12 @@ pub enum LogicalPlan {
     CreateExternalTable(CreateExternalTable),
     /// Creates an in memory table.
     CreateMemoryTable(CreateMemoryTable),
+    /// Appends rows to an in memory table.
+    InsertInto(InsertInto),
     /// Drops a table.
     DropTable(DropTable),
+    /// Sets a setting of the session.
+    SetVariable(SetVariable),
     /// Values expression. See
     /// [Postgres VALUES](https://www.postgresql.org/docs/current/queries-values.html)
     /// documentation for more details.
@@ -365,6 +461,7 @@ impl LogicalPlan {
             LogicalPlan::Join(Join { schema, .. }) => schema,
             LogicalPlan::CrossJoin(CrossJoin { schema, .. }) => schema,
             LogicalPlan::Repartition(Repartition { input, .. }) => input.schema(),
+            LogicalPlan::Unnest(Unnest { schema, .. }) => schema,
             LogicalPlan::Limit(Limit { input, .. }) => input.schema(),
             LogicalPlan::CreateExternalTable(CreateExternalTable { schema, .. }) => {
                 schema
@@ -373,10 +470,13 @@ impl LogicalPlan {
             LogicalPlan::Analyze(analyze) => &analyze.schema,
             LogicalPlan::Extension(extension) => extension.node.schema(),
             LogicalPlan::Union(Union { schema, .. }) => schema,
-            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. }) => {
-                input.schema()
+            LogicalPlan::RecursiveQuery(RecursiveQuery { static_term, .. }) => {
+                static_term.schema()
             }
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. }) => input.schema(),
             LogicalPlan::DropTable(DropTable { schema, .. }) => schema,
+            LogicalPlan::SetVariable(SetVariable { schema, .. }) => schema,
         }
     }
 
@@ -389,6 +489,7 @@ impl LogicalPlan {
             LogicalPlan::Values(Values { schema, .. }) => vec![schema],
             LogicalPlan::Window(Window { input, schema, .. })
             | LogicalPlan::Projection(Projection { input, schema, .. })
+            | LogicalPlan::Unnest(Unnest { input, schema, .. })
             | LogicalPlan::Aggregate(Aggregate { input, schema, .. }) => {
                 let mut schemas = input.all_schemas();
                 schemas.insert(0, schema);
@@ -413,6 +514,15 @@ impl LogicalPlan {
             LogicalPlan::Union(Union { schema, .. }) => {
                 vec![schema]
             }
+            LogicalPlan::RecursiveQuery(RecursiveQuery {
+                static_term,
+                recursive_term,
+                ..
+            }) => {
+                let mut schemas = static_term.all_schemas();
+                schemas.extend(recursive_term.all_schemas());
+                schemas
+            }
             LogicalPlan::Extension(extension) => vec![extension.node.schema()],
             LogicalPlan::Explain(Explain { schema, .. })
             | LogicalPlan::Analyze(Analyze { schema, .. })
@@ -424,8 +534,9 @@ impl LogicalPlan {
             | LogicalPlan::Repartition(Repartition { input, .. })
             | LogicalPlan::Sort(Sort { input, .. })
             | LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. })
             | LogicalPlan::Filter(Filter { input, .. }) => input.all_schemas(),
-            LogicalPlan::DropTable(_) => vec![],
+            LogicalPlan::DropTable(_) | LogicalPlan::SetVariable(_) => vec![],
         }
     }
 
@@ -454,6 +565,9 @@ impl LogicalPlan {
                 Partitioning::Hash(expr, _) => expr.clone(),
                 _ => vec![],
             },
+            LogicalPlan::Unnest(Unnest { column, .. }) => {
+                vec![Expr::Column(column.clone())]
+            }
             LogicalPlan::Window(Window { window_expr, .. }) => window_expr.clone(),
             LogicalPlan::Aggregate(Aggregate {
                 group_expr,
@@ -472,11 +586,14 @@ impl LogicalPlan {
             | LogicalPlan::Limit(_)
             | LogicalPlan::CreateExternalTable(_)
             | LogicalPlan::CreateMemoryTable(_)
+            | LogicalPlan::InsertInto(_)
             | LogicalPlan::DropTable(_)
+            | LogicalPlan::SetVariable(_)
             | LogicalPlan::CrossJoin(_)
             | LogicalPlan::Analyze { .. }
             | LogicalPlan::Explain { .. }
-            | LogicalPlan::Union(_) => {
+            | LogicalPlan::Union(_)
+            | LogicalPlan::RecursiveQuery(_) => {
                 vec![]
             }
         }
@@ -489,6 +606,7 @@ impl LogicalPlan {
             LogicalPlan::Projection(Projection { input, .. }) => vec![input],
             LogicalPlan::Filter(Filter { input, .. }) => vec![input],
             LogicalPlan::Repartition(Repartition { input, .. }) => vec![input],
+            LogicalPlan::Unnest(Unnest { input, .. }) => vec![input],
             LogicalPlan::Window(Window { input, .. }) => vec![input],
             LogicalPlan::Aggregate(Aggregate { input, .. }) => vec![input],
             LogicalPlan::Sort(Sort { input, .. }) => vec![input],
@@ -497,17 +615,22 @@ impl LogicalPlan {
             LogicalPlan::Limit(Limit { input, .. }) => vec![input],
             LogicalPlan::Extension(extension) => extension.node.inputs(),
             LogicalPlan::Union(Union { inputs, .. }) => inputs.iter().collect(),
+            LogicalPlan::RecursiveQuery(RecursiveQuery {
+                static_term,
+                recursive_term,
+                ..
+            }) => vec![static_term, recursive_term],
             LogicalPlan::Explain(explain) => vec![&explain.plan],
             LogicalPlan::Analyze(analyze) => vec![&analyze.input],
-            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. }) => {
-                vec![input]
-            }
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. }) => vec![input],
             // plans without inputs
             LogicalPlan::TableScan { .. }
             | LogicalPlan::EmptyRelation { .. }
             | LogicalPlan::Values { .. }
             | LogicalPlan::CreateExternalTable(_)
-            | LogicalPlan::DropTable(_) => vec![],
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::SetVariable(_) => vec![],
         }
     }
 
@@ -614,7 +737,7 @@ impl LogicalPlan {
     /// cut short the recursion
     pub fn accept<V>(&self, visitor: &mut V) -> std::result::Result<bool, V::Error>
     where
-        V: PlanVisitor,
+        V: PlanVisitor + ?Sized,
     {
         if !visitor.pre_visit(self)? {
             return Ok(false);
@@ -626,6 +749,7 @@ impl LogicalPlan {
             LogicalPlan::Repartition(Repartition { input, .. }) => {
                 input.accept(visitor)?
             }
+            LogicalPlan::Unnest(Unnest { input, .. }) => input.accept(visitor)?,
             LogicalPlan::Window(Window { input, .. }) => input.accept(visitor)?,
             LogicalPlan::Aggregate(Aggregate { input, .. }) => input.accept(visitor)?,
             LogicalPlan::Sort(Sort { input, .. }) => input.accept(visitor)?,
@@ -641,8 +765,14 @@ impl LogicalPlan {
                 }
                 true
             }
+            LogicalPlan::RecursiveQuery(RecursiveQuery {
+                static_term,
+                recursive_term,
+                ..
+            }) => static_term.accept(visitor)? && recursive_term.accept(visitor)?,
             LogicalPlan::Limit(Limit { input, .. }) => input.accept(visitor)?,
-            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. }) => {
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { input, .. })
+            | LogicalPlan::InsertInto(InsertInto { input, .. }) => {
                 input.accept(visitor)?
             }
             LogicalPlan::Extension(extension) => {
@@ -660,7 +790,8 @@ impl LogicalPlan {
             | LogicalPlan::EmptyRelation(_)
             | LogicalPlan::Values(_)
             | LogicalPlan::CreateExternalTable(_)
-            | LogicalPlan::DropTable(_) => true,
+            | LogicalPlan::DropTable(_)
+            | LogicalPlan::SetVariable(_) => true,
         };
         if !recurse {
             return Ok(false);
@@ -758,6 +889,48 @@ impl LogicalPlan {
         Wrapper(self)
     }
 
+    /// Return a `format`able structure that produces a single line
+    /// per node that includes the output schema with the qualifiers of
+    /// its fields, abbreviated if long, see
+    /// [`display_df_schema`](super::display_df_schema). For example:
+    ///
+    /// ```text
+    /// Projection: #employee.id [employee.id:Int32]\
+    ///    Filter: #employee.state = Utf8(\"CO\") [employee.id:Int32, employee.state:Utf8]\
+    ///      TableScan: employee projection=Some([0, 3]) [employee.id:Int32, employee.state:Utf8]";
+    /// ```
+    ///
+    /// ```
+    /// use arrow::datatypes::{Field, Schema, DataType};
+    /// use datafusion::logical_plan::{lit, col, LogicalPlanBuilder};
+    /// let schema = Schema::new(vec![
+    ///     Field::new("id", DataType::Int32, false),
+    /// ]);
+    /// let plan = LogicalPlanBuilder::scan_empty(Some("foo_csv"), &schema, None).unwrap()
+    ///     .filter(col("id").eq(lit(5))).unwrap()
+    ///     .build().unwrap();
+    ///
+    /// // Format using display_indent_qualified_schema
+    /// let display_string = format!("{}", plan.display_indent_qualified_schema());
+    ///
+    /// assert_eq!("Filter: #foo_csv.id = Int32(5) [foo_csv.id:Int32]\
+    ///             \n  TableScan: foo_csv projection=None [foo_csv.id:Int32]",
+    ///             display_string);
+    /// ```
+    pub fn display_indent_qualified_schema(&self) -> impl fmt::Display + '_ {
+        // Boilerplate structure to wrap LogicalPlan with something
+        // that that can be formatted
+        struct Wrapper<'a>(&'a LogicalPlan);
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                let mut visitor = IndentVisitor::new(f, true).with_qualified_schema();
+                self.0.accept(&mut visitor).unwrap();
+                Ok(())
+            }
+        }
+        Wrapper(self)
+    }
+
     /// Return a `format`able structure that produces lines meant for
     /// graphical display using the `DOT` language. This format can be
     /// visualized using software from
@@ -935,18 +1108,23 @@ impl LogicalPlan {
                     LogicalPlan::Join(Join {
                         on: ref keys,
                         join_constraint,
+                        null_aware,
                         ..
                     }) => {
                         let join_expr: Vec<String> =
                             keys.iter().map(|(l, r)| format!("{} = {}", l, r)).collect();
                         match join_constraint {
                             JoinConstraint::On => {
-                                write!(f, "Join: {}", join_expr.join(", "))
+                                write!(f, "Join: {}", join_expr.join(", "))?
                             }
                             JoinConstraint::Using => {
-                                write!(f, "Join: Using {}", join_expr.join(", "))
+                                write!(f, "Join: Using {}", join_expr.join(", "))?
                             }
                         }
+                        if *null_aware {
+                            write!(f, ", null_aware=true")?;
+                        }
+                        Ok(())
                     }
                     LogicalPlan::CrossJoin(_) => {
                         write!(f, "CrossJoin:")
@@ -971,6 +1149,9 @@ impl LogicalPlan {
                             )
                         }
                     },
+                    LogicalPlan::Unnest(Unnest { ref column, .. }) => {
+                        write!(f, "Unnest: {}", column)
+                    }
                     LogicalPlan::Limit(Limit { ref n, .. }) => write!(f, "Limit: {}", n),
                     LogicalPlan::CreateExternalTable(CreateExternalTable {
                         ref name,
@@ -983,12 +1164,31 @@ impl LogicalPlan {
                     }) => {
                         write!(f, "CreateMemoryTable: {:?}", name)
                     }
+                    LogicalPlan::InsertInto(InsertInto { name, .. }) => {
+                        write!(f, "InsertInto: {:?}", name)
+                    }
                     LogicalPlan::DropTable(DropTable { name, if_exist, .. }) => {
                         write!(f, "DropTable: {:?} if not exist:={}", name, if_exist)
                     }
+                    LogicalPlan::SetVariable(SetVariable {
+                        variable, value, ..
+                    }) => {
+                        write!(f, "SetVariable: {} = {:?}", variable, value)
+                    }
                     LogicalPlan::Explain { .. } => write!(f, "Explain"),
                     LogicalPlan::Analyze { .. } => write!(f, "Analyze"),
                     LogicalPlan::Union(_) => write!(f, "Union"),
+                    LogicalPlan::RecursiveQuery(RecursiveQuery {
+                        name,
+                        is_distinct,
+                        ..
+                    }) => {
+                        write!(
+                            f,
+                            "RecursiveQuery: name={}, is_distinct={}",
+                            name, is_distinct
+                        )
+                    }
                     LogicalPlan::Extension(e) => e.node.fmt_for_explain(f),
                 }
             }
@@ -1015,6 +1215,8 @@ pub enum PlanType {
     },
     /// The final, fully optimized LogicalPlan that was converted to a physical plan
     FinalLogicalPlan,
+    /// The final LogicalPlan with the qualified output schema of each node
+    FinalLogicalPlanWithSchema,
     /// The initial physical plan, prepared for execution
     InitialPhysicalPlan,
     /// The ExecutionPlan which results from applying an optimizer pass
@@ -1034,6 +1236,9 @@ impl fmt::Display for PlanType {
                 write!(f, "logical_plan after {}", optimizer_name)
             }
             PlanType::FinalLogicalPlan => write!(f, "logical_plan"),
+            PlanType::FinalLogicalPlanWithSchema => {
+                write!(f, "logical_plan_with_schema")
+            }
             PlanType::InitialPhysicalPlan => write!(f, "initial_physical_plan"),
             PlanType::OptimizedPhysicalPlan { optimizer_name } => {
                 write!(f, "physical_plan after {}", optimizer_name)
@@ -1087,8 +1292,9 @@ impl ToStringifiedPlan for LogicalPlan {
 
 #[cfg(test)]
 mod tests {
-    use super::super::{col, lit, LogicalPlanBuilder};
+    use super::super::{col, count, lit, LogicalPlanBuilder};
     use super::*;
+    use crate::error::Result;
 
     fn employee_schema() -> Schema {
         Schema::new(vec![
@@ -1137,6 +1343,37 @@ mod tests {
         assert_eq!(expected, format!("{}", plan.display_indent_schema()));
     }
 
+    #[test]
+    fn test_display_indent_qualified_schema() -> Result<()> {
+        let states = Schema::new(vec![
+            Field::new("state", DataType::Utf8, false),
+            Field::new("region", DataType::Utf8, true),
+        ]);
+        let states =
+            LogicalPlanBuilder::scan_empty(Some("states"), &states, None)?.build()?;
+        let plan = LogicalPlanBuilder::scan_empty(
+            Some("employee_csv"),
+            &employee_schema(),
+            Some(vec![0, 3]),
+        )?
+        .project_with_alias(vec![col("id"), col("state")], Some("e".to_owned()))?
+        .join(&states, JoinType::Inner, (vec!["state"], vec!["state"]))?
+        .aggregate(vec![col("region")], vec![count(col("id"))])?
+        .build()?;
+
+        let expected = "Aggregate: groupBy=[[#states.region]], aggr=[[COUNT(#e.id)]] [states.region:Utf8;N, COUNT(e.id):Int64;N]\
+                        \n  Join: #e.state = #states.state [e.id:Int32, e.state:Utf8, states.state:Utf8, states.region:Utf8;N]\
+                        \n    Projection: #employee_csv.id, #employee_csv.state, alias=e [e.id:Int32, e.state:Utf8]\
+                        \n      TableScan: employee_csv projection=Some([0, 3]) [employee_csv.id:Int32, employee_csv.state:Utf8]\
+                        \n    TableScan: states projection=None [states.state:Utf8, states.region:Utf8;N]";
+
+        assert_eq!(
+            expected,
+            format!("{}", plan.display_indent_qualified_schema())
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_display_graphviz() {
         let plan = display_plan();
@@ -0,0 +1,1377 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Conversion of logical plans from and to [Substrait](https://substrait.io)
+//! plans, the engine-neutral format shared with other engines and with
+//! coordinators not written in Rust.
+//!
+//! The plans may hold the following nodes:
+//! * scans of the tables registered in the context, with their projection and
+//!   filters, as `ReadRel`s of named tables
+//! * projections, filters, aggregations, sorts and limits
+//! * equi-joins of any type and cross joins
+//!
+//! Their expressions may be column references, literals, binary operators,
+//! `NOT`, `IS [NOT] NULL`, negations, `BETWEEN` (converted to comparisons),
+//! `CASE`, casts and the calls of built-in scalar and aggregate functions.
+//! Functions are declared by name, without extension URIs. Unsigned integers
+//! are signed integers of the [`UNSIGNED_INTEGER_TYPE_VARIATION`] variation.
+//!
+//! The conversion fails with a [`DataFusionError::NotImplemented`] naming the
+//! construct for anything else, in particular:
+//! * windows, unions, unnests, repartitions, `VALUES`, empty relations,
+//!   extension nodes, DDL and `EXPLAIN`
+//! * `IN` lists, struct fields, variables, user-defined functions and
+//!   aggregates with a `FILTER`
+//! * types other than booleans, integers, floats, strings, binaries and dates
+//! * grouping sets, `OFFSET`s and non-equi join conditions
+//!
+//! Aliases are not part of Substrait expressions: only the names of the
+//! columns of the result of the plan are kept.
+
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::datatypes::DataType;
+use prost::Message;
+use substrait_proto::protobuf::{
+    aggregate_function::AggregationInvocation,
+    aggregate_rel::{Grouping, Measure},
+    expression::{
+        cast::FailureBehavior,
+        field_reference::ReferenceType,
+        if_then::IfClause,
+        literal::LiteralType,
+        mask_expression::{StructItem, StructSelect},
+        reference_segment, Cast, FieldReference, IfThen, Literal, MaskExpression,
+        ReferenceSegment, RexType, ScalarFunction,
+    },
+    extensions::{
+        simple_extension_declaration::{ExtensionFunction, MappingType},
+        SimpleExtensionDeclaration,
+    },
+    function_argument::ArgType,
+    join_rel, plan_rel,
+    r#type::{self, Kind, Nullability},
+    read_rel::{NamedTable, ReadType},
+    rel::RelType,
+    rel_common::{Emit, EmitKind},
+    sort_field::{SortDirection, SortKind},
+    AggregateFunction, AggregateRel, AggregationPhase, CrossRel, Expression, FetchRel,
+    FilterRel, FunctionArgument, JoinRel, NamedStruct, Plan, PlanRel, ProjectRel,
+    ReadRel, Rel, RelCommon, RelRoot, SortField, SortRel, Type,
+};
+
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::ExecutionContext;
+use crate::logical_plan::plan::{Aggregate, Filter, Join, Projection, Sort};
+use crate::logical_plan::{
+    binary_expr, combine_filters, CrossJoin, DFSchema, Expr, JoinType, Limit,
+    LogicalPlan, LogicalPlanBuilder, Operator, TableScan,
+};
+use crate::optimizer::utils::split_conjunction;
+use crate::physical_plan::aggregates;
+use crate::physical_plan::functions::BuiltinScalarFunction;
+use crate::scalar::ScalarValue;
+
+/// Type variation of the types of DataFusion that are the default Substrait
+/// types
+pub const DEFAULT_TYPE_VARIATION: u32 = 0;
+/// Type variation of the integer types holding the unsigned integer types of
+/// DataFusion, e.g. `UInt8` is an `i8` of this variation
+pub const UNSIGNED_INTEGER_TYPE_VARIATION: u32 = 1;
+/// Type variation of the string and binary types holding `LargeUtf8` and
+/// `LargeBinary`
+pub const LARGE_CONTAINER_TYPE_VARIATION: u32 = 2;
+
+/// The names of the Substrait functions of the binary operators
+const OPERATORS: &[(Operator, &str)] = &[
+    (Operator::Eq, "equal"),
+    (Operator::NotEq, "not_equal"),
+    (Operator::Lt, "lt"),
+    (Operator::LtEq, "lte"),
+    (Operator::Gt, "gt"),
+    (Operator::GtEq, "gte"),
+    (Operator::Plus, "add"),
+    (Operator::Minus, "subtract"),
+    (Operator::Multiply, "multiply"),
+    (Operator::Divide, "divide"),
+    (Operator::Modulo, "modulus"),
+    (Operator::And, "and"),
+    (Operator::Or, "or"),
+    (Operator::Like, "like"),
+    (Operator::NotLike, "not_like"),
+    (Operator::ILike, "ilike"),
+    (Operator::NotILike, "not_ilike"),
+    (Operator::IsDistinctFrom, "is_distinct_from"),
+    (Operator::IsNotDistinctFrom, "is_not_distinct_from"),
+    (Operator::RegexMatch, "regex_match"),
+    (Operator::RegexIMatch, "regex_imatch"),
+    (Operator::RegexNotMatch, "regex_not_match"),
+    (Operator::RegexNotIMatch, "regex_not_imatch"),
+];
+
+/// Serialize `plan` as a Substrait plan, whose root has the names of the
+/// columns of `plan`
+pub fn to_substrait(plan: &LogicalPlan) -> Result<Vec<u8>> {
+    let mut functions = FunctionExtensions::default();
+    let rel = to_substrait_rel(plan, &mut functions)?;
+    let names = plan
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect();
+    let plan = Plan {
+        extensions: functions.declarations,
+        relations: vec![PlanRel {
+            rel_type: Some(plan_rel::RelType::Root(RelRoot {
+                input: Some(*rel),
+                names,
+            })),
+        }],
+        ..Default::default()
+    };
+    Ok(plan.encode_to_vec())
+}
+
+/// Deserialize the logical plan of a Substrait plan, whose named tables are
+/// the tables registered in `ctx`
+pub fn from_substrait(bytes: &[u8], ctx: &ExecutionContext) -> Result<LogicalPlan> {
+    let plan = Plan::decode(bytes)
+        .map_err(|e| DataFusionError::Plan(format!("Invalid Substrait plan: {}", e)))?;
+    let functions = plan
+        .extensions
+        .iter()
+        .filter_map(|declaration| match &declaration.mapping_type {
+            Some(MappingType::ExtensionFunction(function)) => {
+                Some((function.function_anchor, function.name.clone()))
+            }
+            _ => None,
+        })
+        .collect::<HashMap<_, _>>();
+
+    let (rel, names) = match plan.relations.as_slice() {
+        [relation] => match &relation.rel_type {
+            Some(plan_rel::RelType::Root(root)) => {
+                (root.input.as_ref(), root.names.clone())
+            }
+            Some(plan_rel::RelType::Rel(rel)) => (Some(rel), vec![]),
+            None => (None, vec![]),
+        },
+        _ => {
+            return Err(DataFusionError::Plan(
+                "Substrait plans must have exactly one relation".to_owned(),
+            ))
+        }
+    };
+    let rel = rel.ok_or_else(|| {
+        DataFusionError::Plan("The root of the Substrait plan has no input".to_owned())
+    })?;
+    let plan = from_substrait_rel(rel, ctx, &functions)?;
+
+    let fields = plan.schema().fields();
+    if names.is_empty() || fields.iter().map(|field| field.name()).eq(names.iter()) {
+        return Ok(plan);
+    }
+    if names.len() != fields.len() {
+        return Err(DataFusionError::Plan(format!(
+            "The root of the Substrait plan has {} names for {} columns",
+            names.len(),
+            fields.len()
+        )));
+    }
+    let exprs = fields
+        .iter()
+        .zip(names.iter())
+        .map(|(field, name)| Expr::Column(field.qualified_column()).alias(name))
+        .collect::<Vec<_>>();
+    LogicalPlanBuilder::from(plan).project(exprs)?.build()
+}
+
+/// The functions declared by a Substrait plan, anchored by the order of
+/// their first use
+#[derive(Default)]
+struct FunctionExtensions {
+    anchors: HashMap<String, u32>,
+    declarations: Vec<SimpleExtensionDeclaration>,
+}
+
+impl FunctionExtensions {
+    /// The anchor of the function `name`, declared on its first use
+    fn anchor(&mut self, name: &str) -> u32 {
+        if let Some(anchor) = self.anchors.get(name) {
+            return *anchor;
+        }
+        let anchor = self.declarations.len() as u32;
+        self.declarations.push(SimpleExtensionDeclaration {
+            mapping_type: Some(MappingType::ExtensionFunction(ExtensionFunction {
+                extension_uri_reference: 0,
+                function_anchor: anchor,
+                name: name.to_owned(),
+            })),
+        });
+        self.anchors.insert(name.to_owned(), anchor);
+        anchor
+    }
+}
+
+fn rel(rel_type: RelType) -> Box<Rel> {
+    Box::new(Rel {
+        rel_type: Some(rel_type),
+    })
+}
+
+fn to_substrait_rel(
+    plan: &LogicalPlan,
+    functions: &mut FunctionExtensions,
+) -> Result<Box<Rel>> {
+    match plan {
+        LogicalPlan::TableScan(TableScan {
+            table_name,
+            source,
+            projection,
+            filters,
+            ..
+        }) => {
+            let schema = source.schema();
+            let base_schema = NamedStruct {
+                names: schema.fields().iter().map(|f| f.name().clone()).collect(),
+                r#struct: Some(r#type::Struct {
+                    types: schema
+                        .fields()
+                        .iter()
+                        .map(|f| to_substrait_type(f.data_type(), f.is_nullable()))
+                        .collect::<Result<_>>()?,
+                    nullability: Nullability::Required as i32,
+                    ..Default::default()
+                }),
+            };
+            // the filters of a read refer to the columns of the whole table
+            let filter = match combine_filters(filters) {
+                Some(filter) => {
+                    let table_schema =
+                        DFSchema::try_from_qualified_schema(table_name, &schema)?;
+                    Some(Box::new(to_substrait_rex(
+                        &filter,
+                        &table_schema,
+                        functions,
+                    )?))
+                }
+                None => None,
+            };
+            let projection = projection.as_ref().map(|projection| MaskExpression {
+                select: Some(StructSelect {
+                    struct_items: projection
+                        .iter()
+                        .map(|i| StructItem {
+                            field: *i as i32,
+                            child: None,
+                        })
+                        .collect(),
+                }),
+                maintain_singular_struct: false,
+            });
+            Ok(rel(RelType::Read(Box::new(ReadRel {
+                base_schema: Some(base_schema),
+                filter,
+                projection,
+                read_type: Some(ReadType::NamedTable(NamedTable {
+                    names: vec![table_name.clone()],
+                    ..Default::default()
+                })),
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Projection(Projection { expr, input, .. }) => {
+            let expressions = expr
+                .iter()
+                .map(|e| to_substrait_rex(e, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            // a project appends its expressions to the columns of its input,
+            // only emit the expressions
+            let input_len = input.schema().fields().len();
+            let output_mapping = (input_len..input_len + expr.len())
+                .map(|i| i as i32)
+                .collect();
+            Ok(rel(RelType::Project(Box::new(ProjectRel {
+                common: Some(RelCommon {
+                    emit_kind: Some(EmitKind::Emit(Emit { output_mapping })),
+                    ..Default::default()
+                }),
+                input: Some(to_substrait_rel(input, functions)?),
+                expressions,
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Filter(Filter { predicate, input }) => {
+            let condition = to_substrait_rex(predicate, input.schema(), functions)?;
+            Ok(rel(RelType::Filter(Box::new(FilterRel {
+                input: Some(to_substrait_rel(input, functions)?),
+                condition: Some(Box::new(condition)),
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Aggregate(Aggregate {
+            input,
+            group_expr,
+            aggr_expr,
+            ..
+        }) => {
+            let grouping_expressions = group_expr
+                .iter()
+                .map(|e| to_substrait_rex(e, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            let groupings = if grouping_expressions.is_empty() {
+                vec![]
+            } else {
+                vec![Grouping {
+                    grouping_expressions,
+                }]
+            };
+            let measures = aggr_expr
+                .iter()
+                .map(|e| to_substrait_measure(e, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(rel(RelType::Aggregate(Box::new(AggregateRel {
+                input: Some(to_substrait_rel(input, functions)?),
+                groupings,
+                measures,
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Sort(Sort { expr, input }) => {
+            let sorts = expr
+                .iter()
+                .map(|e| to_substrait_sort_field(e, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(rel(RelType::Sort(Box::new(SortRel {
+                input: Some(to_substrait_rel(input, functions)?),
+                sorts,
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Limit(Limit { n, input }) => {
+            Ok(rel(RelType::Fetch(Box::new(FetchRel {
+                input: Some(to_substrait_rel(input, functions)?),
+                offset: 0,
+                count: *n as i64,
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::Join(Join {
+            null_aware: true, ..
+        }) => Err(DataFusionError::NotImplemented(
+            "Null-aware anti joins are not supported by the Substrait conversion"
+                .to_owned(),
+        )),
+        LogicalPlan::Join(Join {
+            left,
+            right,
+            on,
+            join_type,
+            null_equals_null,
+            ..
+        }) => {
+            // the join condition refers to the columns of the left input
+            // followed by the columns of the right input
+            let left_len = left.schema().fields().len();
+            let key_function = if *null_equals_null {
+                "is_not_distinct_from"
+            } else {
+                "equal"
+            };
+            let mut keys = vec![];
+            for (l, r) in on {
+                let l = left.schema().index_of_column(l)?;
+                let r = left_len + right.schema().index_of_column(r)?;
+                keys.push(scalar_function(
+                    functions.anchor(key_function),
+                    vec![field_reference(l), field_reference(r)],
+                ));
+            }
+            let expression = keys
+                .into_iter()
+                .reduce(|l, r| scalar_function(functions.anchor("and"), vec![l, r]));
+            let join_type = match join_type {
+                JoinType::Inner => join_rel::JoinType::Inner,
+                JoinType::Left => join_rel::JoinType::Left,
+                JoinType::Right => join_rel::JoinType::Right,
+                JoinType::Full => join_rel::JoinType::Outer,
+                JoinType::Semi => join_rel::JoinType::Semi,
+                JoinType::Anti => join_rel::JoinType::Anti,
+            };
+            Ok(rel(RelType::Join(Box::new(JoinRel {
+                left: Some(to_substrait_rel(left, functions)?),
+                right: Some(to_substrait_rel(right, functions)?),
+                expression: expression.map(Box::new),
+                r#type: join_type as i32,
+                ..Default::default()
+            }))))
+        }
+        LogicalPlan::CrossJoin(CrossJoin { left, right, .. }) => {
+            Ok(rel(RelType::Cross(Box::new(CrossRel {
+                left: Some(to_substrait_rel(left, functions)?),
+                right: Some(to_substrait_rel(right, functions)?),
+                ..Default::default()
+            }))))
+        }
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "{} is not supported by the Substrait conversion",
+            plan.to_summary().operator
+        ))),
+    }
+}
+
+fn field_reference(index: usize) -> Expression {
+    Expression {
+        rex_type: Some(RexType::Selection(Box::new(FieldReference {
+            reference_type: Some(ReferenceType::DirectReference(ReferenceSegment {
+                reference_type: Some(reference_segment::ReferenceType::StructField(
+                    Box::new(reference_segment::StructField {
+                        field: index as i32,
+                        child: None,
+                    }),
+                )),
+            })),
+            root_type: None,
+        }))),
+    }
+}
+
+fn function_arguments(args: Vec<Expression>) -> Vec<FunctionArgument> {
+    args.into_iter()
+        .map(|arg| FunctionArgument {
+            arg_type: Some(ArgType::Value(arg)),
+        })
+        .collect()
+}
+
+fn scalar_function(function_reference: u32, args: Vec<Expression>) -> Expression {
+    Expression {
+        rex_type: Some(RexType::ScalarFunction(ScalarFunction {
+            function_reference,
+            arguments: function_arguments(args),
+            ..Default::default()
+        })),
+    }
+}
+
+/// The name of a built-in function, as parsed by its `FromStr`: its
+/// lowercase name, e.g. `btrim`, or its snake case name, e.g. `date_trunc`
+fn function_name<F>(fun: &F) -> Result<String>
+where
+    F: FromStr + PartialEq + std::fmt::Debug,
+{
+    let debug = format!("{:?}", fun);
+    let mut snake_case = String::new();
+    for (i, c) in debug.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            snake_case.push('_');
+        }
+        snake_case.extend(c.to_lowercase());
+    }
+    [debug.to_lowercase(), snake_case]
+        .into_iter()
+        .find(|name| F::from_str(name).map_or(false, |parsed| &parsed == fun))
+        .ok_or_else(|| {
+            DataFusionError::NotImplemented(format!(
+                "Function {} is not supported by the Substrait conversion",
+                debug
+            ))
+        })
+}
+
+fn to_substrait_call(
+    name: &str,
+    args: &[&Expr],
+    schema: &DFSchema,
+    functions: &mut FunctionExtensions,
+) -> Result<Expression> {
+    let args = args
+        .iter()
+        .map(|arg| to_substrait_rex(arg, schema, functions))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(scalar_function(functions.anchor(name), args))
+}
+
+fn to_substrait_cast(
+    expr: &Expr,
+    data_type: &DataType,
+    failure_behavior: FailureBehavior,
+    schema: &DFSchema,
+    functions: &mut FunctionExtensions,
+) -> Result<Expression> {
+    Ok(Expression {
+        rex_type: Some(RexType::Cast(Box::new(Cast {
+            r#type: Some(to_substrait_type(data_type, true)?),
+            input: Some(Box::new(to_substrait_rex(expr, schema, functions)?)),
+            failure_behavior: failure_behavior as i32,
+        }))),
+    })
+}
+
+fn to_substrait_rex(
+    expr: &Expr,
+    schema: &DFSchema,
+    functions: &mut FunctionExtensions,
+) -> Result<Expression> {
+    match expr {
+        // only the names of the columns of the root are kept
+        Expr::Alias(expr, _) => to_substrait_rex(expr, schema, functions),
+        Expr::Column(col) => Ok(field_reference(schema.index_of_column(col)?)),
+        Expr::Literal(value) => Ok(Expression {
+            rex_type: Some(RexType::Literal(to_substrait_literal(value)?)),
+        }),
+        Expr::BinaryExpr { left, op, right } => {
+            let name = OPERATORS
+                .iter()
+                .find(|(operator, _)| operator == op)
+                .map(|(_, name)| *name)
+                .ok_or_else(|| {
+                    DataFusionError::NotImplemented(format!(
+                        "Operator {} is not supported by the Substrait conversion",
+                        op
+                    ))
+                })?;
+            to_substrait_call(name, &[left.as_ref(), right.as_ref()], schema, functions)
+        }
+        Expr::Not(expr) => to_substrait_call("not", &[expr.as_ref()], schema, functions),
+        Expr::IsNull(expr) => {
+            to_substrait_call("is_null", &[expr.as_ref()], schema, functions)
+        }
+        Expr::IsNotNull(expr) => {
+            to_substrait_call("is_not_null", &[expr.as_ref()], schema, functions)
+        }
+        Expr::Negative(expr) => {
+            to_substrait_call("negate", &[expr.as_ref()], schema, functions)
+        }
+        Expr::Between {
+            expr,
+            negated,
+            low,
+            high,
+        } => {
+            let expr = expr.as_ref().clone();
+            let low = low.as_ref().clone();
+            let high = high.as_ref().clone();
+            let between = if *negated {
+                expr.clone().lt(low).or(expr.gt(high))
+            } else {
+                expr.clone().gt_eq(low).and(expr.lt_eq(high))
+            };
+            to_substrait_rex(&between, schema, functions)
+        }
+        Expr::Case {
+            expr,
+            when_then_expr,
+            else_expr,
+        } => {
+            let mut ifs = vec![];
+            for (when, then) in when_then_expr {
+                let when = match expr {
+                    Some(expr) => expr.as_ref().clone().eq(when.as_ref().clone()),
+                    None => when.as_ref().clone(),
+                };
+                ifs.push(IfClause {
+                    r#if: Some(to_substrait_rex(&when, schema, functions)?),
+                    then: Some(to_substrait_rex(then, schema, functions)?),
+                });
+            }
+            let r#else = match else_expr {
+                Some(else_expr) => {
+                    Some(Box::new(to_substrait_rex(else_expr, schema, functions)?))
+                }
+                None => None,
+            };
+            Ok(Expression {
+                rex_type: Some(RexType::IfThen(Box::new(IfThen { ifs, r#else }))),
+            })
+        }
+        Expr::Cast { expr, data_type } => to_substrait_cast(
+            expr,
+            data_type,
+            FailureBehavior::ThrowException,
+            schema,
+            functions,
+        ),
+        Expr::TryCast { expr, data_type } => to_substrait_cast(
+            expr,
+            data_type,
+            FailureBehavior::ReturnNull,
+            schema,
+            functions,
+        ),
+        Expr::ScalarFunction { fun, args } => {
+            let args = args.iter().collect::<Vec<_>>();
+            to_substrait_call(&function_name(fun)?, &args, schema, functions)
+        }
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "Expression {:?} is not supported by the Substrait conversion",
+            expr
+        ))),
+    }
+}
+
+fn to_substrait_measure(
+    expr: &Expr,
+    schema: &DFSchema,
+    functions: &mut FunctionExtensions,
+) -> Result<Measure> {
+    match expr {
+        Expr::Alias(expr, _) => to_substrait_measure(expr, schema, functions),
+        Expr::AggregateFunction {
+            fun,
+            args,
+            distinct,
+        } => {
+            let args = args
+                .iter()
+                .map(|arg| to_substrait_rex(arg, schema, functions))
+                .collect::<Result<Vec<_>>>()?;
+            let invocation = if *distinct {
+                AggregationInvocation::Distinct
+            } else {
+                AggregationInvocation::All
+            };
+            Ok(Measure {
+                measure: Some(AggregateFunction {
+                    function_reference: functions.anchor(&function_name(fun)?),
+                    arguments: function_arguments(args),
+                    phase: AggregationPhase::InitialToResult as i32,
+                    invocation: invocation as i32,
+                    ..Default::default()
+                }),
+                filter: None,
+            })
+        }
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "Aggregate expression {:?} is not supported by the Substrait conversion",
+            expr
+        ))),
+    }
+}
+
+fn to_substrait_sort_field(
+    expr: &Expr,
+    schema: &DFSchema,
+    functions: &mut FunctionExtensions,
+) -> Result<SortField> {
+    match expr {
+        Expr::Sort {
+            expr,
+            asc,
+            nulls_first,
+        } => {
+            let direction = match (asc, nulls_first) {
+                (true, true) => SortDirection::AscNullsFirst,
+                (true, false) => SortDirection::AscNullsLast,
+                (false, true) => SortDirection::DescNullsFirst,
+                (false, false) => SortDirection::DescNullsLast,
+            };
+            Ok(SortField {
+                expr: Some(to_substrait_rex(expr, schema, functions)?),
+                sort_kind: Some(SortKind::Direction(direction as i32)),
+            })
+        }
+        _ => Err(DataFusionError::Plan(format!(
+            "Expected a sort expression, got {:?}",
+            expr
+        ))),
+    }
+}
+
+fn to_substrait_type(data_type: &DataType, nullable: bool) -> Result<Type> {
+    let nullability = if nullable {
+        Nullability::Nullable
+    } else {
+        Nullability::Required
+    } as i32;
+    macro_rules! kind {
+        ($KIND:ident, $TYPE:ident, $VARIATION:expr) => {
+            Kind::$KIND(r#type::$TYPE {
+                type_variation_reference: $VARIATION,
+                nullability,
+            })
+        };
+    }
+    let kind = match data_type {
+        DataType::Boolean => kind!(Bool, Boolean, DEFAULT_TYPE_VARIATION),
+        DataType::Int8 => kind!(I8, I8, DEFAULT_TYPE_VARIATION),
+        DataType::Int16 => kind!(I16, I16, DEFAULT_TYPE_VARIATION),
+        DataType::Int32 => kind!(I32, I32, DEFAULT_TYPE_VARIATION),
+        DataType::Int64 => kind!(I64, I64, DEFAULT_TYPE_VARIATION),
+        DataType::UInt8 => kind!(I8, I8, UNSIGNED_INTEGER_TYPE_VARIATION),
+        DataType::UInt16 => kind!(I16, I16, UNSIGNED_INTEGER_TYPE_VARIATION),
+        DataType::UInt32 => kind!(I32, I32, UNSIGNED_INTEGER_TYPE_VARIATION),
+        DataType::UInt64 => kind!(I64, I64, UNSIGNED_INTEGER_TYPE_VARIATION),
+        DataType::Float32 => kind!(Fp32, Fp32, DEFAULT_TYPE_VARIATION),
+        DataType::Float64 => kind!(Fp64, Fp64, DEFAULT_TYPE_VARIATION),
+        DataType::Utf8 => kind!(String, String, DEFAULT_TYPE_VARIATION),
+        DataType::LargeUtf8 => kind!(String, String, LARGE_CONTAINER_TYPE_VARIATION),
+        DataType::Binary => kind!(Binary, Binary, DEFAULT_TYPE_VARIATION),
+        DataType::LargeBinary => kind!(Binary, Binary, LARGE_CONTAINER_TYPE_VARIATION),
+        DataType::Date32 => kind!(Date, Date, DEFAULT_TYPE_VARIATION),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Type {:?} is not supported by the Substrait conversion",
+                data_type
+            )))
+        }
+    };
+    Ok(Type { kind: Some(kind) })
+}
+
+fn from_substrait_type(substrait_type: &Type) -> Result<DataType> {
+    let unsupported = || {
+        DataFusionError::NotImplemented(format!(
+            "Substrait type {:?} is not supported",
+            substrait_type
+        ))
+    };
+    let data_type = match &substrait_type.kind {
+        Some(Kind::Bool(_)) => DataType::Boolean,
+        Some(Kind::I8(t)) => match t.type_variation_reference {
+            UNSIGNED_INTEGER_TYPE_VARIATION => DataType::UInt8,
+            _ => DataType::Int8,
+        },
+        Some(Kind::I16(t)) => match t.type_variation_reference {
+            UNSIGNED_INTEGER_TYPE_VARIATION => DataType::UInt16,
+            _ => DataType::Int16,
+        },
+        Some(Kind::I32(t)) => match t.type_variation_reference {
+            UNSIGNED_INTEGER_TYPE_VARIATION => DataType::UInt32,
+            _ => DataType::Int32,
+        },
+        Some(Kind::I64(t)) => match t.type_variation_reference {
+            UNSIGNED_INTEGER_TYPE_VARIATION => DataType::UInt64,
+            _ => DataType::Int64,
+        },
+        Some(Kind::Fp32(_)) => DataType::Float32,
+        Some(Kind::Fp64(_)) => DataType::Float64,
+        Some(Kind::String(t)) => match t.type_variation_reference {
+            LARGE_CONTAINER_TYPE_VARIATION => DataType::LargeUtf8,
+            _ => DataType::Utf8,
+        },
+        Some(Kind::Binary(t)) => match t.type_variation_reference {
+            LARGE_CONTAINER_TYPE_VARIATION => DataType::LargeBinary,
+            _ => DataType::Binary,
+        },
+        Some(Kind::Date(_)) => DataType::Date32,
+        _ => return Err(unsupported()),
+    };
+    Ok(data_type)
+}
+
+fn to_substrait_literal(value: &ScalarValue) -> Result<Literal> {
+    if value.is_null() {
+        return Ok(Literal {
+            nullable: true,
+            literal_type: Some(LiteralType::Null(to_substrait_type(
+                &value.get_datatype(),
+                true,
+            )?)),
+            ..Default::default()
+        });
+    }
+    let (literal_type, type_variation_reference) = match value {
+        ScalarValue::Boolean(Some(v)) => {
+            (LiteralType::Boolean(*v), DEFAULT_TYPE_VARIATION)
+        }
+        ScalarValue::Int8(Some(v)) => {
+            (LiteralType::I8(*v as i32), DEFAULT_TYPE_VARIATION)
+        }
+        ScalarValue::Int16(Some(v)) => {
+            (LiteralType::I16(*v as i32), DEFAULT_TYPE_VARIATION)
+        }
+        ScalarValue::Int32(Some(v)) => (LiteralType::I32(*v), DEFAULT_TYPE_VARIATION),
+        ScalarValue::Int64(Some(v)) => (LiteralType::I64(*v), DEFAULT_TYPE_VARIATION),
+        ScalarValue::UInt8(Some(v)) => {
+            (LiteralType::I8(*v as i32), UNSIGNED_INTEGER_TYPE_VARIATION)
+        }
+        ScalarValue::UInt16(Some(v)) => {
+            (LiteralType::I16(*v as i32), UNSIGNED_INTEGER_TYPE_VARIATION)
+        }
+        ScalarValue::UInt32(Some(v)) => {
+            (LiteralType::I32(*v as i32), UNSIGNED_INTEGER_TYPE_VARIATION)
+        }
+        ScalarValue::UInt64(Some(v)) => {
+            (LiteralType::I64(*v as i64), UNSIGNED_INTEGER_TYPE_VARIATION)
+        }
+        ScalarValue::Float32(Some(v)) => (LiteralType::Fp32(*v), DEFAULT_TYPE_VARIATION),
+        ScalarValue::Float64(Some(v)) => (LiteralType::Fp64(*v), DEFAULT_TYPE_VARIATION),
+        ScalarValue::Utf8(Some(v)) => {
+            (LiteralType::String(v.clone()), DEFAULT_TYPE_VARIATION)
+        }
+        ScalarValue::LargeUtf8(Some(v)) => (
+            LiteralType::String(v.clone()),
+            LARGE_CONTAINER_TYPE_VARIATION,
+        ),
+        ScalarValue::Binary(Some(v)) => {
+            (LiteralType::Binary(v.clone()), DEFAULT_TYPE_VARIATION)
+        }
+        ScalarValue::LargeBinary(Some(v)) => (
+            LiteralType::Binary(v.clone()),
+            LARGE_CONTAINER_TYPE_VARIATION,
+        ),
+        ScalarValue::Date32(Some(v)) => (LiteralType::Date(*v), DEFAULT_TYPE_VARIATION),
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Literal {:?} is not supported by the Substrait conversion",
+                value
+            )))
+        }
+    };
+    Ok(Literal {
+        nullable: false,
+        type_variation_reference,
+        literal_type: Some(literal_type),
+    })
+}
+
+fn from_substrait_literal(literal: &Literal) -> Result<ScalarValue> {
+    let unsigned = literal.type_variation_reference == UNSIGNED_INTEGER_TYPE_VARIATION;
+    let large = literal.type_variation_reference == LARGE_CONTAINER_TYPE_VARIATION;
+    let value = match &literal.literal_type {
+        Some(LiteralType::Boolean(v)) => ScalarValue::Boolean(Some(*v)),
+        Some(LiteralType::I8(v)) if unsigned => ScalarValue::UInt8(Some(*v as u8)),
+        Some(LiteralType::I8(v)) => ScalarValue::Int8(Some(*v as i8)),
+        Some(LiteralType::I16(v)) if unsigned => ScalarValue::UInt16(Some(*v as u16)),
+        Some(LiteralType::I16(v)) => ScalarValue::Int16(Some(*v as i16)),
+        Some(LiteralType::I32(v)) if unsigned => ScalarValue::UInt32(Some(*v as u32)),
+        Some(LiteralType::I32(v)) => ScalarValue::Int32(Some(*v)),
+        Some(LiteralType::I64(v)) if unsigned => ScalarValue::UInt64(Some(*v as u64)),
+        Some(LiteralType::I64(v)) => ScalarValue::Int64(Some(*v)),
+        Some(LiteralType::Fp32(v)) => ScalarValue::Float32(Some(*v)),
+        Some(LiteralType::Fp64(v)) => ScalarValue::Float64(Some(*v)),
+        Some(LiteralType::String(v)) if large => ScalarValue::LargeUtf8(Some(v.clone())),
+        Some(LiteralType::String(v)) => ScalarValue::Utf8(Some(v.clone())),
+        Some(LiteralType::Binary(v)) if large => {
+            ScalarValue::LargeBinary(Some(v.clone()))
+        }
+        Some(LiteralType::Binary(v)) => ScalarValue::Binary(Some(v.clone())),
+        Some(LiteralType::Date(v)) => ScalarValue::Date32(Some(*v)),
+        Some(LiteralType::Null(null_type)) => {
+            ScalarValue::try_from(&from_substrait_type(null_type)?)?
+        }
+        _ => {
+            return Err(DataFusionError::NotImplemented(format!(
+                "Substrait literal {:?} is not supported",
+                literal
+            )))
+        }
+    };
+    Ok(value)
+}
+
+/// The registered table `name`
+fn table_provider(ctx: &ExecutionContext, name: &str) -> Result<Arc<dyn TableProvider>> {
+    match ctx.table(name)?.to_logical_plan() {
+        LogicalPlan::TableScan(scan) => Ok(scan.source),
+        _ => Err(DataFusionError::Plan(format!("No table named '{}'", name))),
+    }
+}
+
+fn from_substrait_input(
+    input: &Option<Box<Rel>>,
+    ctx: &ExecutionContext,
+    functions: &HashMap<u32, String>,
+) -> Result<LogicalPlan> {
+    match input {
+        Some(input) => from_substrait_rel(input, ctx, functions),
+        None => Err(DataFusionError::Plan(
+            "Substrait relation without input".to_owned(),
+        )),
+    }
+}
+
+fn from_substrait_rel(
+    rel: &Rel,
+    ctx: &ExecutionContext,
+    functions: &HashMap<u32, String>,
+) -> Result<LogicalPlan> {
+    match &rel.rel_type {
+        Some(RelType::Read(read)) => {
+            let table_name = match &read.read_type {
+                Some(ReadType::NamedTable(table)) => table.names.join("."),
+                _ => {
+                    return Err(DataFusionError::NotImplemented(
+                        "Only the reads of named tables are supported".to_owned(),
+                    ))
+                }
+            };
+            let provider = table_provider(ctx, &table_name)?;
+            let projection = read
+                .projection
+                .as_ref()
+                .and_then(|mask| mask.select.as_ref())
+                .map(|select| {
+                    select
+                        .struct_items
+                        .iter()
+                        .map(|item| item.field as usize)
+                        .collect()
+                });
+            let filters = match &read.filter {
+                Some(filter) => {
+                    let table_schema = DFSchema::try_from_qualified_schema(
+                        &table_name,
+                        &provider.schema(),
+                    )?;
+                    let filter = from_substrait_rex(filter, &table_schema, functions)?;
+                    let mut filters = vec![];
+                    split_conjunction(&filter, &mut filters);
+                    filters.into_iter().cloned().collect()
+                }
+                None => vec![],
+            };
+            LogicalPlanBuilder::scan_with_filters(
+                table_name, provider, projection, filters,
+            )?
+            .build()
+        }
+        Some(RelType::Project(project)) => {
+            let input = from_substrait_input(&project.input, ctx, functions)?;
+            let schema = input.schema();
+            // the columns of the input followed by the expressions
+            let mut exprs = schema
+                .fields()
+                .iter()
+                .map(|field| Expr::Column(field.qualified_column()))
+                .collect::<Vec<_>>();
+            for expr in &project.expressions {
+                exprs.push(from_substrait_rex(expr, schema, functions)?);
+            }
+            let exprs = match project.common.as_ref().and_then(|c| c.emit_kind.as_ref()) {
+                Some(EmitKind::Emit(emit)) => emit
+                    .output_mapping
+                    .iter()
+                    .map(|i| {
+                        exprs.get(*i as usize).cloned().ok_or_else(|| {
+                            DataFusionError::Plan(format!(
+                                "Invalid output mapping {} of a Substrait project",
+                                i
+                            ))
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+                _ => exprs,
+            };
+            // aliases are lost, tell the expressions of the same name apart
+            let mut names = HashSet::new();
+            let exprs = exprs
+                .into_iter()
+                .enumerate()
+                .map(|(i, expr)| {
+                    let name = expr.name(schema)?;
+                    Ok(if names.insert(name.clone()) {
+                        expr
+                    } else {
+                        expr.alias(&format!("{}_{}", name, i))
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            LogicalPlanBuilder::from(input).project(exprs)?.build()
+        }
+        Some(RelType::Filter(filter)) => {
+            let input = from_substrait_input(&filter.input, ctx, functions)?;
+            let condition = filter.condition.as_ref().ok_or_else(|| {
+                DataFusionError::Plan("Substrait filter without condition".to_owned())
+            })?;
+            let predicate = from_substrait_rex(condition, input.schema(), functions)?;
+            LogicalPlanBuilder::from(input).filter(predicate)?.build()
+        }
+        Some(RelType::Fetch(fetch)) => {
+            if fetch.offset != 0 {
+                return Err(DataFusionError::NotImplemented(
+                    "OFFSET is not supported by the Substrait conversion".to_owned(),
+                ));
+            }
+            let input = from_substrait_input(&fetch.input, ctx, functions)?;
+            LogicalPlanBuilder::from(input)
+                .limit(fetch.count as usize)?
+                .build()
+        }
+        Some(RelType::Sort(sort)) => {
+            let input = from_substrait_input(&sort.input, ctx, functions)?;
+            let exprs = sort
+                .sorts
+                .iter()
+                .map(|field| from_substrait_sort_field(field, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            LogicalPlanBuilder::from(input).sort(exprs)?.build()
+        }
+        Some(RelType::Aggregate(aggregate)) => {
+            let input = from_substrait_input(&aggregate.input, ctx, functions)?;
+            let group_expr = match aggregate.groupings.as_slice() {
+                [] => vec![],
+                [grouping] => grouping
+                    .grouping_expressions
+                    .iter()
+                    .map(|e| from_substrait_rex(e, input.schema(), functions))
+                    .collect::<Result<Vec<_>>>()?,
+                _ => {
+                    return Err(DataFusionError::NotImplemented(
+                        "Grouping sets are not supported by the Substrait conversion"
+                            .to_owned(),
+                    ))
+                }
+            };
+            let aggr_expr = aggregate
+                .measures
+                .iter()
+                .map(|measure| from_substrait_measure(measure, input.schema(), functions))
+                .collect::<Result<Vec<_>>>()?;
+            LogicalPlanBuilder::from(input)
+                .aggregate(group_expr, aggr_expr)?
+                .build()
+        }
+        Some(RelType::Join(join)) => {
+            let left = from_substrait_input(&join.left, ctx, functions)?;
+            let right = from_substrait_input(&join.right, ctx, functions)?;
+            if join.post_join_filter.is_some() {
+                return Err(DataFusionError::NotImplemented(
+                    "Post join filters are not supported by the Substrait conversion"
+                        .to_owned(),
+                ));
+            }
+            let join_type = match join_rel::JoinType::from_i32(join.r#type) {
+                Some(join_rel::JoinType::Inner) => JoinType::Inner,
+                Some(join_rel::JoinType::Left) => JoinType::Left,
+                Some(join_rel::JoinType::Right) => JoinType::Right,
+                Some(join_rel::JoinType::Outer) => JoinType::Full,
+                Some(join_rel::JoinType::Semi) => JoinType::Semi,
+                Some(join_rel::JoinType::Anti) => JoinType::Anti,
+                _ => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "Join type {} is not supported by the Substrait conversion",
+                        join.r#type
+                    )))
+                }
+            };
+            let keys = match &join.expression {
+                Some(expression) => join_keys(expression, functions)?,
+                None => vec![],
+            };
+            let null_equals_null = keys.iter().any(|(_, _, null_equal)| *null_equal);
+            if keys
+                .iter()
+                .any(|(_, _, null_equal)| *null_equal != null_equals_null)
+            {
+                return Err(DataFusionError::NotImplemented(
+                    "Joins mixing equal and is_not_distinct_from keys are not \
+                     supported by the Substrait conversion"
+                        .to_owned(),
+                ));
+            }
+            let left_len = left.schema().fields().len();
+            let mut left_keys = vec![];
+            let mut right_keys = vec![];
+            for (a, b, _) in keys {
+                let (l, r) = match (a < left_len, b < left_len) {
+                    (true, false) => (a, b - left_len),
+                    (false, true) => (b, a - left_len),
+                    _ => {
+                        return Err(DataFusionError::NotImplemented(
+                            "Join keys must compare a column of each input".to_owned(),
+                        ))
+                    }
+                };
+                left_keys.push(left.schema().field(l).qualified_column());
+                right_keys.push(right.schema().field(r).qualified_column());
+            }
+            LogicalPlanBuilder::from(left)
+                .join_detailed(
+                    &right,
+                    join_type,
+                    (left_keys, right_keys),
+                    null_equals_null,
+                )?
+                .build()
+        }
+        Some(RelType::Cross(cross)) => {
+            let left = from_substrait_input(&cross.left, ctx, functions)?;
+            let right = from_substrait_input(&cross.right, ctx, functions)?;
+            LogicalPlanBuilder::from(left).cross_join(&right)?.build()
+        }
+        Some(rel_type) => {
+            let debug = format!("{:?}", rel_type);
+            Err(DataFusionError::NotImplemented(format!(
+                "Substrait relation {} is not supported",
+                debug.split('(').next().unwrap_or_default()
+            )))
+        }
+        None => Err(DataFusionError::Plan(
+            "Substrait relation without type".to_owned(),
+        )),
+    }
+}
+
+/// The fields compared by the conjunction of equalities of a join
+/// condition, with whether nulls are equal
+fn join_keys(
+    expression: &Expression,
+    functions: &HashMap<u32, String>,
+) -> Result<Vec<(usize, usize, bool)>> {
+    let not_equi_join = || {
+        DataFusionError::NotImplemented(
+            "Only equi-joins are supported by the Substrait conversion".to_owned(),
+        )
+    };
+    let function = match &expression.rex_type {
+        Some(RexType::ScalarFunction(function)) => function,
+        _ => return Err(not_equi_join()),
+    };
+    let args = function
+        .arguments
+        .iter()
+        .map(|arg| match &arg.arg_type {
+            Some(ArgType::Value(arg)) => Ok(arg),
+            _ => Err(not_equi_join()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    match (
+        function_by_anchor(functions, function.function_reference)?,
+        args.as_slice(),
+    ) {
+        ("and", [l, r]) => {
+            let mut keys = join_keys(l, functions)?;
+            keys.extend(join_keys(r, functions)?);
+            Ok(keys)
+        }
+        (name @ ("equal" | "is_not_distinct_from"), [l, r]) => {
+            match (&l.rex_type, &r.rex_type) {
+                (Some(RexType::Selection(l)), Some(RexType::Selection(r))) => Ok(vec![(
+                    field_index(l)?,
+                    field_index(r)?,
+                    name == "is_not_distinct_from",
+                )]),
+                _ => Err(not_equi_join()),
+            }
+        }
+        _ => Err(not_equi_join()),
+    }
+}
+
+fn function_by_anchor(functions: &HashMap<u32, String>, anchor: u32) -> Result<&str> {
+    functions
+        .get(&anchor)
+        .map(|name| name.as_str())
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!("Undeclared Substrait function {}", anchor))
+        })
+}
+
+fn field_index(field_reference: &FieldReference) -> Result<usize> {
+    match &field_reference.reference_type {
+        Some(ReferenceType::DirectReference(ReferenceSegment {
+            reference_type: Some(reference_segment::ReferenceType::StructField(field)),
+        })) if field.child.is_none() => Ok(field.field as usize),
+        _ => Err(DataFusionError::NotImplemented(
+            "Only the direct references to fields are supported by the Substrait \
+             conversion"
+                .to_owned(),
+        )),
+    }
+}
+
+fn from_substrait_args(
+    arguments: &[FunctionArgument],
+    schema: &DFSchema,
+    functions: &HashMap<u32, String>,
+) -> Result<Vec<Expr>> {
+    arguments
+        .iter()
+        .map(|arg| match &arg.arg_type {
+            Some(ArgType::Value(arg)) => from_substrait_rex(arg, schema, functions),
+            _ => Err(DataFusionError::NotImplemented(
+                "Only value arguments are supported by the Substrait conversion"
+                    .to_owned(),
+            )),
+        })
+        .collect()
+}
+
+fn from_substrait_rex(
+    expression: &Expression,
+    schema: &DFSchema,
+    functions: &HashMap<u32, String>,
+) -> Result<Expr> {
+    match &expression.rex_type {
+        Some(RexType::Literal(literal)) => {
+            Ok(Expr::Literal(from_substrait_literal(literal)?))
+        }
+        Some(RexType::Selection(field_reference)) => {
+            let index = field_index(field_reference)?;
+            if index >= schema.fields().len() {
+                return Err(DataFusionError::Plan(format!(
+                    "Invalid reference to the field {} of {} fields",
+                    index,
+                    schema.fields().len()
+                )));
+            }
+            Ok(Expr::Column(schema.field(index).qualified_column()))
+        }
+        Some(RexType::ScalarFunction(function)) => {
+            let name = function_by_anchor(functions, function.function_reference)?;
+            let mut args = from_substrait_args(&function.arguments, schema, functions)?;
+            if let Some((op, _)) = OPERATORS.iter().find(|(_, n)| *n == name) {
+                check_arity(name, &args, 2)?;
+                let right = args.pop().unwrap();
+                let left = args.pop().unwrap();
+                return Ok(binary_expr(left, *op, right));
+            }
+            let unary: Option<fn(Box<Expr>) -> Expr> = match name {
+                "not" => Some(Expr::Not),
+                "is_null" => Some(Expr::IsNull),
+                "is_not_null" => Some(Expr::IsNotNull),
+                "negate" => Some(Expr::Negative),
+                _ => None,
+            };
+            if let Some(unary) = unary {
+                check_arity(name, &args, 1)?;
+                return Ok(unary(Box::new(args.pop().unwrap())));
+            }
+            Ok(Expr::ScalarFunction {
+                fun: BuiltinScalarFunction::from_str(name)?,
+                args,
+            })
+        }
+        Some(RexType::IfThen(if_then)) => {
+            let when_then_expr = if_then
+                .ifs
+                .iter()
+                .map(|clause| match (&clause.r#if, &clause.then) {
+                    (Some(when), Some(then)) => Ok((
+                        Box::new(from_substrait_rex(when, schema, functions)?),
+                        Box::new(from_substrait_rex(then, schema, functions)?),
+                    )),
+                    _ => Err(DataFusionError::Plan(
+                        "Substrait if clause without condition or result".to_owned(),
+                    )),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let else_expr = match &if_then.r#else {
+                Some(else_expr) => {
+                    Some(Box::new(from_substrait_rex(else_expr, schema, functions)?))
+                }
+                None => None,
+            };
+            Ok(Expr::Case {
+                expr: None,
+                when_then_expr,
+                else_expr,
+            })
+        }
+        Some(RexType::Cast(cast)) => {
+            let (input, cast_type) = match (&cast.input, &cast.r#type) {
+                (Some(input), Some(cast_type)) => (input, cast_type),
+                _ => {
+                    return Err(DataFusionError::Plan(
+                        "Substrait cast without input or type".to_owned(),
+                    ))
+                }
+            };
+            let expr = Box::new(from_substrait_rex(input, schema, functions)?);
+            let data_type = from_substrait_type(cast_type)?;
+            if cast.failure_behavior == FailureBehavior::ReturnNull as i32 {
+                Ok(Expr::TryCast { expr, data_type })
+            } else {
+                Ok(Expr::Cast { expr, data_type })
+            }
+        }
+        _ => Err(DataFusionError::NotImplemented(format!(
+            "Substrait expression {:?} is not supported",
+            expression
+        ))),
+    }
+}
+
+fn check_arity(name: &str, args: &[Expr], arity: usize) -> Result<()> {
+    if args.len() == arity {
+        Ok(())
+    } else {
+        Err(DataFusionError::Plan(format!(
+            "Substrait function {} expects {} arguments, got {}",
+            name,
+            arity,
+            args.len()
+        )))
+    }
+}
+
+fn from_substrait_measure(
+    measure: &Measure,
+    schema: &DFSchema,
+    functions: &HashMap<u32, String>,
+) -> Result<Expr> {
+    if measure.filter.is_some() {
+        return Err(DataFusionError::NotImplemented(
+            "Filtered aggregates are not supported by the Substrait conversion"
+                .to_owned(),
+        ));
+    }
+    let function = measure.measure.as_ref().ok_or_else(|| {
+        DataFusionError::Plan("Substrait measure without function".to_owned())
+    })?;
+    let name = function_by_anchor(functions, function.function_reference)?;
+    Ok(Expr::AggregateFunction {
+        fun: aggregates::AggregateFunction::from_str(name)?,
+        args: from_substrait_args(&function.arguments, schema, functions)?,
+        distinct: function.invocation == AggregationInvocation::Distinct as i32,
+    })
+}
+
+fn from_substrait_sort_field(
+    field: &SortField,
+    schema: &DFSchema,
+    functions: &HashMap<u32, String>,
+) -> Result<Expr> {
+    let expr = field.expr.as_ref().ok_or_else(|| {
+        DataFusionError::Plan("Substrait sort field without expression".to_owned())
+    })?;
+    let (asc, nulls_first) = match &field.sort_kind {
+        Some(SortKind::Direction(direction)) => {
+            match SortDirection::from_i32(*direction) {
+                Some(SortDirection::AscNullsFirst) => (true, true),
+                Some(SortDirection::AscNullsLast) => (true, false),
+                Some(SortDirection::DescNullsFirst) => (false, true),
+                Some(SortDirection::DescNullsLast) => (false, false),
+                _ => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "Sort direction {} is not supported by the Substrait conversion",
+                        direction
+                    )))
+                }
+            }
+        }
+        _ => {
+            return Err(DataFusionError::NotImplemented(
+                "Sorts by comparison functions are not supported by the Substrait \
+                 conversion"
+                    .to_owned(),
+            ))
+        }
+    };
+    Ok(Expr::Sort {
+        expr: Box::new(from_substrait_rex(expr, schema, functions)?),
+        asc,
+        nulls_first,
+    })
+}
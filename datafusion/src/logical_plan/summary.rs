@@ -0,0 +1,374 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Structured summaries of plans, and ready-made visitors collecting their
+//! table scans and filters, for tools that inspect plans without parsing
+//! their display strings
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::plan::{
+    Aggregate, Analyze, CreateExternalTable, CreateMemoryTable, DropTable, EmptyRelation,
+    Explain, Filter, InsertInto, Join, Limit, Partitioning, Projection, RecursiveQuery,
+    Repartition, SetVariable, Sort, TableScan, Union, Unnest, Values, Window,
+};
+use super::{Expr, LogicalPlan, PlanVisitor};
+use crate::error::{DataFusionError, Result};
+
+/// The operator of a node of a logical or physical plan, with its parameters
+/// and the summaries of its inputs.
+///
+/// Summaries only hold strings, so that tools written in other languages can
+/// read their JSON, see [`PlanSummary::to_json`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanSummary {
+    /// The name of the operator, e.g. `Filter` or `HashJoinExec`
+    pub operator: String,
+    /// The parameters of the operator, by name
+    pub parameters: BTreeMap<String, String>,
+    /// The summaries of the inputs of the operator
+    pub children: Vec<PlanSummary>,
+}
+
+impl PlanSummary {
+    /// A summary of a node without parameters
+    pub fn new(operator: impl Into<String>, children: Vec<PlanSummary>) -> Self {
+        Self {
+            operator: operator.into(),
+            parameters: BTreeMap::new(),
+            children,
+        }
+    }
+
+    /// Add the parameter `name`
+    pub fn with_parameter(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.parameters.insert(name.to_owned(), value.into());
+        self
+    }
+
+    /// Serialize this summary to JSON
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(self).map_err(|e| {
+            DataFusionError::Execution(format!("Could not serialize summary: {}", e))
+        })
+    }
+
+    /// Deserialize a summary serialized with [`PlanSummary::to_json`]
+    pub fn from_json(json: &str) -> Result<Self> {
+        serde_json::from_str(json).map_err(|e| {
+            DataFusionError::Execution(format!("Could not deserialize summary: {}", e))
+        })
+    }
+}
+
+fn exprs_to_string(exprs: &[Expr]) -> String {
+    exprs
+        .iter()
+        .map(|expr| format!("{:?}", expr))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl LogicalPlan {
+    /// Returns the structured summary of this plan. Expressions are formatted
+    /// as in [`LogicalPlan::display`].
+    pub fn to_summary(&self) -> PlanSummary {
+        let children = self
+            .inputs()
+            .into_iter()
+            .map(|input| input.to_summary())
+            .collect();
+        let summary = |operator: &str| PlanSummary::new(operator, children);
+        match self {
+            LogicalPlan::Projection(Projection { expr, alias, .. }) => {
+                let summary =
+                    summary("Projection").with_parameter("expr", exprs_to_string(expr));
+                match alias {
+                    Some(alias) => summary.with_parameter("alias", alias),
+                    None => summary,
+                }
+            }
+            LogicalPlan::Filter(Filter { predicate, .. }) => {
+                summary("Filter").with_parameter("predicate", format!("{:?}", predicate))
+            }
+            LogicalPlan::Window(Window { window_expr, .. }) => summary("WindowAggr")
+                .with_parameter("window_expr", exprs_to_string(window_expr)),
+            LogicalPlan::Aggregate(Aggregate {
+                group_expr,
+                aggr_expr,
+                ..
+            }) => summary("Aggregate")
+                .with_parameter("group_expr", exprs_to_string(group_expr))
+                .with_parameter("aggr_expr", exprs_to_string(aggr_expr)),
+            LogicalPlan::Sort(Sort { expr, .. }) => {
+                summary("Sort").with_parameter("expr", exprs_to_string(expr))
+            }
+            LogicalPlan::Join(Join {
+                on,
+                join_type,
+                join_constraint,
+                null_equals_null,
+                null_aware,
+                ..
+            }) => {
+                let on = on
+                    .iter()
+                    .map(|(l, r)| format!("{} = {}", l, r))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                summary("Join")
+                    .with_parameter("on", on)
+                    .with_parameter("join_type", format!("{:?}", join_type))
+                    .with_parameter("join_constraint", format!("{:?}", join_constraint))
+                    .with_parameter("null_equals_null", null_equals_null.to_string())
+                    .with_parameter("null_aware", null_aware.to_string())
+            }
+            LogicalPlan::CrossJoin(_) => summary("CrossJoin"),
+            LogicalPlan::Repartition(Repartition {
+                partitioning_scheme,
+                ..
+            }) => match partitioning_scheme {
+                Partitioning::RoundRobinBatch(n) => summary("Repartition")
+                    .with_parameter("scheme", "RoundRobinBatch")
+                    .with_parameter("partition_count", n.to_string()),
+                Partitioning::Hash(expr, n) => summary("Repartition")
+                    .with_parameter("scheme", "Hash")
+                    .with_parameter("expr", exprs_to_string(expr))
+                    .with_parameter("partition_count", n.to_string()),
+            },
+            LogicalPlan::Unnest(Unnest { column, .. }) => {
+                summary("Unnest").with_parameter("column", column.to_string())
+            }
+            LogicalPlan::Union(Union { alias, .. }) => match alias {
+                Some(alias) => summary("Union").with_parameter("alias", alias),
+                None => summary("Union"),
+            },
+            LogicalPlan::RecursiveQuery(RecursiveQuery {
+                name, is_distinct, ..
+            }) => summary("RecursiveQuery")
+                .with_parameter("name", name)
+                .with_parameter("is_distinct", is_distinct.to_string()),
+            LogicalPlan::TableScan(TableScan {
+                table_name,
+                projection,
+                projected_schema,
+                filters,
+                limit,
+                ..
+            }) => {
+                let mut summary =
+                    summary("TableScan").with_parameter("table_name", table_name);
+                if projection.is_some() {
+                    let columns = projected_schema
+                        .fields()
+                        .iter()
+                        .map(|field| field.name().as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    summary = summary.with_parameter("projection", columns);
+                }
+                if !filters.is_empty() {
+                    summary = summary.with_parameter("filters", exprs_to_string(filters));
+                }
+                match limit {
+                    Some(limit) => summary.with_parameter("limit", limit.to_string()),
+                    None => summary,
+                }
+            }
+            LogicalPlan::EmptyRelation(EmptyRelation {
+                produce_one_row, ..
+            }) => summary("EmptyRelation")
+                .with_parameter("produce_one_row", produce_one_row.to_string()),
+            LogicalPlan::Limit(Limit { n, .. }) => {
+                summary("Limit").with_parameter("n", n.to_string())
+            }
+            LogicalPlan::CreateExternalTable(CreateExternalTable {
+                name,
+                location,
+                file_type,
+                has_header,
+                ..
+            }) => summary("CreateExternalTable")
+                .with_parameter("name", name)
+                .with_parameter("location", location)
+                .with_parameter("file_type", format!("{:?}", file_type))
+                .with_parameter("has_header", has_header.to_string()),
+            LogicalPlan::CreateMemoryTable(CreateMemoryTable { name, .. }) => {
+                summary("CreateMemoryTable").with_parameter("name", name)
+            }
+            LogicalPlan::InsertInto(InsertInto { name, .. }) => {
+                summary("InsertInto").with_parameter("name", name)
+            }
+            LogicalPlan::DropTable(DropTable { name, if_exist, .. }) => {
+                summary("DropTable")
+                    .with_parameter("name", name)
+                    .with_parameter("if_exist", if_exist.to_string())
+            }
+            LogicalPlan::SetVariable(SetVariable {
+                variable, value, ..
+            }) => summary("SetVariable")
+                .with_parameter("variable", variable)
+                .with_parameter("value", value),
+            LogicalPlan::Values(Values { values, .. }) => {
+                summary("Values").with_parameter("rows", values.len().to_string())
+            }
+            LogicalPlan::Explain(Explain { verbose, .. }) => {
+                summary("Explain").with_parameter("verbose", verbose.to_string())
+            }
+            LogicalPlan::Analyze(Analyze { verbose, .. }) => {
+                summary("Analyze").with_parameter("verbose", verbose.to_string())
+            }
+            LogicalPlan::Extension(_) => {
+                summary("Extension").with_parameter("node", self.display().to_string())
+            }
+        }
+    }
+}
+
+/// Returns the table scans of `plan`, in the order of a depth first walk
+pub fn collect_table_scans(plan: &LogicalPlan) -> Vec<TableScan> {
+    struct TableScanCollector(Vec<TableScan>);
+
+    impl PlanVisitor for TableScanCollector {
+        type Error = ();
+
+        fn pre_visit(&mut self, plan: &LogicalPlan) -> std::result::Result<bool, ()> {
+            if let LogicalPlan::TableScan(scan) = plan {
+                self.0.push(scan.clone());
+            }
+            Ok(true)
+        }
+    }
+
+    let mut visitor = TableScanCollector(vec![]);
+    // the visitor never stops the recursion
+    let _ = plan.accept(&mut visitor);
+    visitor.0
+}
+
+/// Returns the predicates of the filters of `plan`, including the filters
+/// pushed down into its table scans, in the order of a depth first walk
+pub fn collect_filters(plan: &LogicalPlan) -> Vec<Expr> {
+    struct FilterCollector(Vec<Expr>);
+
+    impl PlanVisitor for FilterCollector {
+        type Error = ();
+
+        fn pre_visit(&mut self, plan: &LogicalPlan) -> std::result::Result<bool, ()> {
+            match plan {
+                LogicalPlan::Filter(Filter { predicate, .. }) => {
+                    self.0.push(predicate.clone())
+                }
+                LogicalPlan::TableScan(TableScan { filters, .. }) => {
+                    self.0.extend(filters.iter().cloned())
+                }
+                _ => {}
+            }
+            Ok(true)
+        }
+    }
+
+    let mut visitor = FilterCollector(vec![]);
+    // the visitor never stops the recursion
+    let _ = plan.accept(&mut visitor);
+    visitor.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, JoinType, LogicalPlanBuilder};
+    use crate::test::test_table_scan_with_name;
+
+    /// `a JOIN b JOIN c JOIN d`, filtered above the joins and on `b`
+    fn three_joins() -> Result<LogicalPlan> {
+        let b = LogicalPlanBuilder::from(test_table_scan_with_name("b")?)
+            .filter(col("b.c").gt(lit(1u32)))?
+            .build()?;
+        LogicalPlanBuilder::from(test_table_scan_with_name("a")?)
+            .join(&b, JoinType::Inner, (vec!["a.a"], vec!["b.a"]))?
+            .join(
+                &test_table_scan_with_name("c")?,
+                JoinType::Left,
+                (vec!["b.b"], vec!["c.b"]),
+            )?
+            .join(
+                &test_table_scan_with_name("d")?,
+                JoinType::Inner,
+                (vec!["c.c"], vec!["d.c"]),
+            )?
+            .filter(col("a.b").eq(col("d.b")))?
+            .project(vec![col("a.a"), col("d.c")])?
+            .build()
+    }
+
+    #[test]
+    fn collect_scans_and_filters() -> Result<()> {
+        let plan = three_joins()?;
+        let scans = collect_table_scans(&plan)
+            .into_iter()
+            .map(|scan| scan.table_name)
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["a", "b", "c", "d"], scans);
+
+        let filters = collect_filters(&plan)
+            .iter()
+            .map(|expr| format!("{:?}", expr))
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["#a.b = #d.b", "#b.c > UInt32(1)"], filters);
+        Ok(())
+    }
+
+    #[test]
+    fn summary() -> Result<()> {
+        let summary = three_joins()?.to_summary();
+        assert_eq!("Projection", summary.operator);
+        assert_eq!("#a.a, #d.c", summary.parameters["expr"]);
+
+        let filter = &summary.children[0];
+        assert_eq!("Filter", filter.operator);
+        let join = &filter.children[0];
+        assert_eq!("Join", join.operator);
+        assert_eq!("#c.c = #d.c", join.parameters["on"]);
+        assert_eq!("Inner", join.parameters["join_type"]);
+        assert_eq!(2, join.children.len());
+        assert_eq!("Left", join.children[0].parameters["join_type"]);
+        let scan = &join.children[1];
+        assert_eq!(
+            PlanSummary::new("TableScan", vec![]).with_parameter("table_name", "d"),
+            *scan
+        );
+
+        let json = summary.to_json()?;
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!("Projection", value["operator"]);
+        assert_eq!("Filter", value["children"][0]["operator"]);
+        assert_eq!(
+            "#a.b = #d.b",
+            value["children"][0]["parameters"]["predicate"]
+        );
+        assert_eq!(
+            "d",
+            value["children"][0]["children"][0]["children"][1]["parameters"]
+                ["table_name"]
+        );
+        assert_eq!(summary, PlanSummary::from_json(&json)?);
+        Ok(())
+    }
+}
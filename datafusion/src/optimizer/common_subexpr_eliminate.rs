@@ -23,11 +23,12 @@ use crate::logical_plan::plan::{Filter, Projection, Window};
 use crate::logical_plan::{
     col,
     plan::{Aggregate, Sort},
-    DFField, DFSchema, Expr, ExprRewriter, ExpressionVisitor, LogicalPlan, Recursion,
-    RewriteRecursion,
+    DFField, DFSchema, DFSchemaRef, Expr, ExprRewriter, ExpressionVisitor, LogicalPlan,
+    Recursion, RewriteRecursion,
 };
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
+use crate::sql::utils::{find_aggregate_exprs, find_window_exprs};
 use arrow::datatypes::DataType;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -53,6 +54,12 @@ type Identifier = String;
 ///
 /// Currently only common sub-expressions within one logical plan will
 /// be eliminated.
+///
+/// The repeated sub-expressions are evaluated once by a projection inserted
+/// below the plan, and replaced by references to its columns. Expressions
+/// that are cheap (columns, literals), volatile or that contain aggregate or
+/// window functions are never extracted. The output schema of the plan is
+/// unchanged.
 pub struct CommonSubexprEliminate {}
 
 impl OptimizerRule for CommonSubexprEliminate {
@@ -125,10 +132,11 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
                 execution_props,
             )?;
 
-            Ok(LogicalPlan::Filter(Filter {
+            let filter = LogicalPlan::Filter(Filter {
                 predicate: new_expr.pop().unwrap().pop().unwrap(),
                 input: Arc::new(new_input),
-            }))
+            });
+            Ok(restore_schema(filter, input.schema()))
         }
         LogicalPlan::Window(Window {
             input,
@@ -146,11 +154,26 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
                 execution_props,
             )?;
 
-            Ok(LogicalPlan::Window(Window {
+            if new_input.schema() == input.schema() {
+                return Ok(LogicalPlan::Window(Window {
+                    input: Arc::new(new_input),
+                    window_expr: new_expr.pop().unwrap(),
+                    schema: schema.clone(),
+                }));
+            }
+
+            // the window fields are followed by the fields of the (extended) input
+            let mut fields = schema.fields()[..window_expr.len()].to_vec();
+            fields.extend_from_slice(new_input.schema().fields());
+            let window = LogicalPlan::Window(Window {
                 input: Arc::new(new_input),
                 window_expr: new_expr.pop().unwrap(),
-                schema: schema.clone(),
-            }))
+                schema: Arc::new(DFSchema::new_with_metadata(
+                    fields,
+                    schema.metadata().clone(),
+                )?),
+            });
+            Ok(restore_schema(window, schema))
         }
         LogicalPlan::Aggregate(Aggregate {
             group_expr,
@@ -192,15 +215,18 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
                 execution_props,
             )?;
 
-            Ok(LogicalPlan::Sort(Sort {
+            let sort = LogicalPlan::Sort(Sort {
                 expr: new_expr.pop().unwrap(),
                 input: Arc::new(new_input),
-            }))
+            });
+            Ok(restore_schema(sort, input.schema()))
         }
         LogicalPlan::Join { .. }
         | LogicalPlan::CrossJoin(_)
         | LogicalPlan::Repartition(_)
+        | LogicalPlan::Unnest(_)
         | LogicalPlan::Union(_)
+        | LogicalPlan::RecursiveQuery(_)
         | LogicalPlan::TableScan { .. }
         | LogicalPlan::Values(_)
         | LogicalPlan::EmptyRelation(_)
@@ -209,7 +235,9 @@ fn optimize(plan: &LogicalPlan, execution_props: &ExecutionProps) -> Result<Logi
         | LogicalPlan::Explain { .. }
         | LogicalPlan::Analyze { .. }
         | LogicalPlan::CreateMemoryTable(_)
+        | LogicalPlan::InsertInto(_)
         | LogicalPlan::DropTable(_)
+        | LogicalPlan::SetVariable(_)
         | LogicalPlan::Extension { .. } => {
             // apply the optimization to all inputs of the plan
             let expr = plan.expressions();
@@ -254,9 +282,13 @@ fn build_project_plan(
     let mut fields = vec![];
 
     for id in affected_id {
-        let (expr, _, data_type) = expr_set.get(&id).unwrap();
-        // todo: check `nullable`
-        fields.push(DFField::new(None, &id, data_type.clone(), true));
+        let (expr, _, _) = expr_set.get(&id).unwrap();
+        fields.push(DFField::new(
+            None,
+            &id,
+            expr.get_type(input.schema())?,
+            expr.nullable(input.schema())?,
+        ));
         project_exprs.push(expr.clone().alias(&id));
     }
 
@@ -276,6 +308,34 @@ fn build_project_plan(
     }))
 }
 
+/// Project the output of `plan` back to `schema`, dropping the columns of
+/// the projection inserted below it by [`build_project_plan`].
+fn restore_schema(plan: LogicalPlan, schema: &DFSchemaRef) -> LogicalPlan {
+    if plan.schema() == schema {
+        return plan;
+    }
+    LogicalPlan::Projection(Projection {
+        expr: schema
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect(),
+        input: Arc::new(plan),
+        schema: schema.clone(),
+        alias: None,
+    })
+}
+
+/// Returns true if `expr` may be evaluated once by the projection inserted
+/// below the plan: it must be deterministic and must not contain aggregate or
+/// window functions, which can only be evaluated by their own plans.
+fn is_materializable(expr: &Expr) -> Result<bool> {
+    let exprs = std::slice::from_ref(expr);
+    Ok(find_aggregate_exprs(exprs).is_empty()
+        && find_window_exprs(exprs).is_empty()
+        && !utils::is_volatile(expr)?)
+}
+
 #[inline]
 fn rewrite_expr(
     exprs_list: &[&[Expr]],
@@ -451,6 +511,10 @@ impl ExprIdentifierVisitor<'_> {
                 desc.push_str("GetIndexedField-");
                 desc.push_str(&key.to_string());
             }
+            Expr::Struct { names, .. } => {
+                desc.push_str("Struct-");
+                desc.push_str(&names.join(","));
+            }
         }
 
         desc
@@ -499,7 +563,8 @@ impl ExpressionVisitor for ExprIdentifierVisitor<'_> {
                 | Expr::Alias(..)
                 | Expr::Sort { .. }
                 | Expr::Wildcard
-        ) {
+        ) || !is_materializable(expr)?
+        {
             self.id_array[idx].0 = self.series_number;
             let desc = Self::desc_expr(expr);
             self.visit_stack.push(VisitRecord::ExprItem(desc));
@@ -632,11 +697,16 @@ fn replace_common_expr(
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::assert_batches_eq;
+    use crate::execution::context::ExecutionContext;
     use crate::logical_plan::{
-        avg, binary_expr, col, lit, sum, LogicalPlanBuilder, Operator,
+        avg, binary_expr, col, create_udf, lit, random, sum, LogicalPlanBuilder, Operator,
     };
+    use crate::physical_plan::functions::{make_scalar_function, Volatility};
     use crate::test::*;
+    use arrow::array::ArrayRef;
     use std::iter;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
         let optimizer = CommonSubexprEliminate {};
@@ -663,12 +733,14 @@ mod test {
         expr_to_identifier(&expr, &mut HashMap::new(), &mut id_array, DataType::Int64)?;
 
         let expected = vec![
-            (9, "BinaryExpr-*Literal2BinaryExpr--AggregateFunction-AVGfalseColumn-cAggregateFunction-SUMfalseBinaryExpr-+Literal1Column-a"),
-            (7, "BinaryExpr--AggregateFunction-AVGfalseColumn-cAggregateFunction-SUMfalseBinaryExpr-+Literal1Column-a"),
-            (4, "AggregateFunction-SUMfalseBinaryExpr-+Literal1Column-a"), (3, "BinaryExpr-+Literal1Column-a"),
+            // expressions containing aggregate functions are not identified
+            (9, ""),
+            (7, ""),
+            (4, ""),
+            (3, "BinaryExpr-+Literal1Column-a"),
             (1, ""),
             (2, ""),
-            (6, "AggregateFunction-AVGfalseColumn-c"),
+            (6, ""),
             (5, ""),
             (8, ""),
         ]
@@ -737,9 +809,9 @@ mod test {
             )?
             .build()?;
 
-        let expected = "Aggregate: groupBy=[[]], aggr=[[Int32(1) + #AggregateFunction-AVGfalseColumn-test.a AS AVG(test.a), Int32(1) - #AggregateFunction-AVGfalseColumn-test.a AS AVG(test.a)]]\
-        \n  Projection: AVG(#test.a) AS AggregateFunction-AVGfalseColumn-test.a, #test.a, #test.b, #test.c\
-        \n    TableScan: test projection=None";
+        // aggregate functions cannot be evaluated by the projection below
+        let expected = "Aggregate: groupBy=[[]], aggr=[[Int32(1) + AVG(#test.a), Int32(1) - AVG(#test.a)]]\
+        \n  TableScan: test projection=None";
 
         assert_optimized_plan_eq(&plan, expected);
 
@@ -802,4 +874,92 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn volatile_subexpr() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(vec![
+                binary_expr(random(), Operator::Plus, lit(1.0)).alias("first"),
+                binary_expr(random(), Operator::Plus, lit(1.0)).alias("second"),
+            ])?
+            .build()?;
+
+        let expected =
+            "Projection: random() + Float64(1) AS first, random() + Float64(1) AS second\
+        \n  TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn filter_keeps_schema() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let a_plus_b = || binary_expr(col("a"), Operator::Plus, col("b"));
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(a_plus_b().gt(lit(1)).and(a_plus_b().lt(lit(10))))?
+            .build()?;
+
+        let expected = "Projection: #test.a, #test.b, #test.c\
+        \n  Filter: #BinaryExpr-+Column-test.bColumn-test.a AS test.a + test.b > Int32(1) AND #BinaryExpr-+Column-test.bColumn-test.a AS test.a + test.b < Int32(10)\
+        \n    Projection: #test.a + #test.b AS BinaryExpr-+Column-test.bColumn-test.a, #test.a, #test.b, #test.c\
+        \n      TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+        assert_eq!(
+            plan.schema(),
+            CommonSubexprEliminate::new()
+                .optimize(&plan, &ExecutionProps::new())?
+                .schema()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn subexpr_evaluated_once() -> Result<()> {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let fun = make_scalar_function(move |args: &[ArrayRef]| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Arc::clone(&args[0]))
+        });
+
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", table_with_sequence(1, 3)?)?;
+        ctx.register_udf(create_udf(
+            "f",
+            vec![DataType::Int32],
+            Arc::new(DataType::Int32),
+            Volatility::Immutable,
+            fun,
+        ));
+
+        let sql = "SELECT f(i) + 1 AS a, f(i) * 2 AS b, \
+                   CASE WHEN f(i) > 1 THEN 'big' ELSE 'small' END AS c FROM t";
+        let results = ctx.sql(sql).await?.collect().await?;
+
+        // the table has a single batch: `f` is called once instead of three times
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        let expected = vec![
+            "+---+---+-------+",
+            "| a | b | c     |",
+            "+---+---+-------+",
+            "| 2 | 2 | small |",
+            "| 3 | 4 | big   |",
+            "| 4 | 6 | big   |",
+            "+---+---+-------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // the optimized plan has the column names of the original plan
+        let plan = ctx.create_logical_plan(sql)?;
+        assert_eq!(plan.schema(), ctx.optimize(&plan)?.schema());
+
+        Ok(())
+    }
 }
@@ -15,15 +15,15 @@
 //! Filter Push Down optimizer rule ensures that filters are applied as early as possible in the plan
 
 use crate::datasource::datasource::TableProviderFilterPushDown;
+use crate::error::Result;
 use crate::execution::context::ExecutionProps;
-use crate::logical_plan::plan::{Aggregate, Filter, Join, Projection};
+use crate::logical_plan::plan::{Aggregate, Extension, Filter, Join, Projection};
 use crate::logical_plan::{
-    and, replace_col, Column, CrossJoin, Limit, LogicalPlan, TableScan,
+    and, replace_col, Column, CrossJoin, JoinType, Limit, LogicalPlan, TableScan, Union,
 };
 use crate::logical_plan::{DFSchema, Expr};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
-use crate::{error::Result, logical_plan::Operator};
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
@@ -54,6 +54,13 @@ use std::{
 /// and when it reaches a node that does not commute with it, it adds the filter to that place.
 /// When it passes through a projection, it re-writes the filter's expression taking into accoun that projection.
 /// When multiple filters would have been written, it `AND` their expressions into a single expression.
+///
+/// The predicates of a conjunction are pushed independently: below a join, each one goes to the
+/// input whose columns it depends on if the join preserves the rows of that input (both inputs of
+/// an inner join, the outer one of a left or right join); below a union, it is duplicated into
+/// every input. Predicates calling volatile functions, or UDFs that don't support filter pushdown
+/// (see [`ScalarUDF::supports_filter_pushdown`](crate::physical_plan::udf::ScalarUDF)), are
+/// never moved.
 pub struct FilterPushDown {}
 
 #[derive(Debug, Clone, Default)]
@@ -83,14 +90,15 @@ fn get_predicates<'a>(
 }
 
 // returns 3 (potentially overlaping) sets of predicates:
-// * pushable to left: its columns are all on the left
-// * pushable to right: its columns is all on the right
-// * keep: the set of columns is not in only either left or right
+// * pushable to left: its columns are all on the left, which is preserved by the join
+// * pushable to right: its columns is all on the right, which is preserved by the join
+// * keep: the predicate cannot be pushed to either side
 // Note that a predicate can be both pushed to the left and to the right.
 fn get_join_predicates<'a>(
     state: &'a State,
     left: &DFSchema,
     right: &DFSchema,
+    (left_preserved, right_preserved): (bool, bool),
 ) -> (
     Vec<&'a HashSet<Column>>,
     Vec<&'a HashSet<Column>>,
@@ -138,21 +146,24 @@ fn get_join_predicates<'a>(
 
     let pushable_to_left = filters
         .iter()
-        .filter(|(_, (columns, left, _))| left.len() == columns.len())
+        .filter(|(_, (columns, left, _))| left_preserved && left.len() == columns.len())
         .map(|((_, b), _)| *b)
         .collect();
     let pushable_to_right = filters
         .iter()
-        .filter(|(_, (columns, _, right))| right.len() == columns.len())
+        .filter(|(_, (columns, _, right))| {
+            right_preserved && right.len() == columns.len()
+        })
         .map(|((_, b), _)| *b)
         .collect();
     let keep = filters
         .iter()
         .filter(|(_, (columns, left, right))| {
-            // predicates whose columns are not in only one side of the join need to remain
-            let all_in_left = left.len() == columns.len();
-            let all_in_right = right.len() == columns.len();
-            !all_in_left && !all_in_right
+            // predicates whose columns are not in only one preserved side of the join
+            // need to remain
+            let push_left = left_preserved && left.len() == columns.len();
+            let push_right = right_preserved && right.len() == columns.len();
+            !push_left && !push_right
         })
         .map(|((a, b), _)| (a, b))
         .unzip();
@@ -234,21 +245,18 @@ fn issue_filters(
     push_down(&state, &plan)
 }
 
-/// converts "A AND B AND C" => [A, B, C]
-fn split_members<'a>(predicate: &'a Expr, predicates: &mut Vec<&'a Expr>) {
-    match predicate {
-        Expr::BinaryExpr {
-            right,
-            op: Operator::And,
-            left,
-        } => {
-            split_members(left, predicates);
-            split_members(right, predicates);
-        }
-        Expr::Alias(expr, _) => {
-            split_members(expr, predicates);
-        }
-        other => predicates.push(other),
+/// Returns whether the rows of the (left, right) inputs of a join of type
+/// `join_type` are preserved by the join: a filter on the columns of a
+/// preserved input commutes with the join, while the rows of the other input
+/// may be null-extended or dropped by the join.
+fn preserved_join_sides(join_type: &JoinType) -> (bool, bool) {
+    match join_type {
+        JoinType::Inner => (true, true),
+        JoinType::Left => (true, false),
+        JoinType::Right => (false, true),
+        JoinType::Full => (false, false),
+        // the output of semi and anti joins only has the columns of the left input
+        JoinType::Semi | JoinType::Anti => (true, false),
     }
 }
 
@@ -257,9 +265,10 @@ fn optimize_join(
     plan: &LogicalPlan,
     left: &LogicalPlan,
     right: &LogicalPlan,
+    preserved: (bool, bool),
 ) -> Result<LogicalPlan> {
     let (pushable_to_left, pushable_to_right, keep) =
-        get_join_predicates(&state, left.schema(), right.schema());
+        get_join_predicates(&state, left.schema(), right.schema(), preserved);
 
     let mut left_state = state.clone();
     left_state.filters = keep_filters(&left_state.filters, &pushable_to_left);
@@ -293,9 +302,12 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
         LogicalPlan::Analyze { .. } => push_down(&state, plan),
         LogicalPlan::Filter(Filter { input, predicate }) => {
             let mut predicates = vec![];
-            split_members(predicate, &mut predicates);
+            utils::split_conjunction(predicate, &mut predicates);
 
-            // Predicates without referencing columns (WHERE FALSE, WHERE 1=1, etc.)
+            // Predicates without referencing columns (WHERE FALSE, WHERE 1=1, etc.),
+            // and predicates calling volatile functions (WHERE random() < 0.5), whose
+            // value would change if they were evaluated elsewhere or several times, or
+            // UDFs that don't support filter pushdown
             let mut no_col_predicates = vec![];
 
             predicates
@@ -303,7 +315,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .try_for_each::<_, Result<()>>(|predicate| {
                     let mut columns: HashSet<Column> = HashSet::new();
                     utils::expr_to_columns(predicate, &mut columns)?;
-                    if columns.is_empty() {
+                    if columns.is_empty() || utils::prevents_filter_pushdown(predicate)? {
                         no_col_predicates.push(predicate)
                     } else {
                         // collect the predicate
@@ -314,7 +326,9 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
 
             // Predicates without columns will not be pushed down.
             // As those contain only literals, they could be optimized using constant folding
-            // and removal of WHERE TRUE / WHERE FALSE
+            // and removal of WHERE TRUE / WHERE FALSE.
+            // Volatile predicates and the ones calling UDFs without filter pushdown
+            // support are not pushed down either.
             if !no_col_predicates.is_empty() {
                 Ok(add_filter(optimize(input, state)?, &no_col_predicates))
             } else {
@@ -346,34 +360,57 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
 
             // re-write all filters based on this projection
             // E.g. in `Filter: #b\n  Projection: #a > 1 as b`, we can swap them, but the filter must be "#a > 1"
-            for (predicate, columns) in state.filters.iter_mut() {
-                *predicate = rewrite(predicate, &projection)?;
+            // A filter on a volatile projected expression stays above the projection, since
+            // the re-written filter would evaluate the expression again, as well as a
+            // filter on a projected UDF that doesn't support filter pushdown.
+            let mut volatile_predicates = vec![];
+            let mut filters = vec![];
+            for (predicate, mut columns) in state.filters {
+                let rewritten = rewrite(&predicate, &projection)?;
+                if utils::prevents_filter_pushdown(&rewritten)? {
+                    volatile_predicates.push(predicate);
+                    continue;
+                }
 
                 columns.clear();
-                utils::expr_to_columns(predicate, columns)?;
+                utils::expr_to_columns(&rewritten, &mut columns)?;
+                filters.push((rewritten, columns));
             }
+            state.filters = filters;
 
             // optimize inner
             let new_input = optimize(input, state)?;
 
-            utils::from_plan(plan, expr, &[new_input])
+            let plan = utils::from_plan(plan, expr, &[new_input])?;
+            if volatile_predicates.is_empty() {
+                Ok(plan)
+            } else {
+                Ok(add_filter(
+                    plan,
+                    &volatile_predicates.iter().collect::<Vec<_>>(),
+                ))
+            }
         }
-        LogicalPlan::Aggregate(Aggregate {
-            aggr_expr, input, ..
-        }) => {
-            // An aggregate's aggreagate columns are _not_ filter-commutable => collect these:
-            // * columns whose aggregation expression depends on
-            // * the aggregation columns themselves
-
-            // construct set of columns that `aggr_expr` depends on
-            let mut used_columns = HashSet::new();
-            utils::exprlist_to_columns(aggr_expr, &mut used_columns)?;
-
-            let agg_columns = aggr_expr
+        LogicalPlan::Aggregate(Aggregate { group_expr, .. }) => {
+            // An aggregate is filter-commutable for the predicates on its group keys only:
+            // such a predicate keeps or removes all the rows of a group. The aggregate
+            // columns and the computed group keys are _not_ filter-commutable => collect
+            // all the output columns but the group keys that are input columns
+            let key_columns = group_expr
                 .iter()
-                .map(|x| Ok(Column::from_name(x.name(input.schema())?)))
-                .collect::<Result<HashSet<_>>>()?;
-            used_columns.extend(agg_columns);
+                .filter_map(|expr| match expr {
+                    Expr::Column(column) => Some(column.clone()),
+                    _ => None,
+                })
+                .collect::<HashSet<_>>();
+            let used_columns = plan
+                .schema()
+                .fields()
+                .iter()
+                .filter(|f| !key_columns.contains(&f.qualified_column()))
+                .map(|f| [f.qualified_column(), f.unqualified_column()])
+                .flatten()
+                .collect::<HashSet<_>>();
 
             issue_filters(state, used_columns, plan)
         }
@@ -381,9 +418,38 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             // sort is filter-commutable
             push_down(&state, plan)
         }
-        LogicalPlan::Union(_) => {
-            // union all is filter-commutable
-            push_down(&state, plan)
+        LogicalPlan::Union(Union { inputs, schema, .. }) => {
+            // union all is filter-commutable: the predicates are duplicated into every
+            // input, re-written on the columns of that input
+            let new_inputs = inputs
+                .iter()
+                .map(|input| {
+                    let projection = schema
+                        .fields()
+                        .iter()
+                        .zip(input.schema().fields())
+                        .map(|(field, input_field)| {
+                            let column = Expr::Column(input_field.qualified_column());
+                            [
+                                (field.qualified_name(), column.clone()),
+                                (field.name().clone(), column),
+                            ]
+                        })
+                        .flatten()
+                        .collect::<HashMap<_, _>>();
+
+                    let mut input_state = state.clone();
+                    for (predicate, columns) in input_state.filters.iter_mut() {
+                        *predicate = rewrite(predicate, &projection)?;
+
+                        columns.clear();
+                        utils::expr_to_columns(predicate, columns)?;
+                    }
+                    optimize(input, input_state)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            utils::from_plan(plan, &plan.expressions(), &new_inputs)
         }
         LogicalPlan::Limit(Limit { input, .. }) => {
             // limit is _not_ filter-commutable => collect all columns from its input
@@ -396,11 +462,16 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             issue_filters(state, used_columns, plan)
         }
         LogicalPlan::CrossJoin(CrossJoin { left, right, .. }) => {
-            optimize_join(state, plan, left, right)
+            optimize_join(state, plan, left, right, (true, true))
         }
         LogicalPlan::Join(Join {
-            left, right, on, ..
+            left,
+            right,
+            on,
+            join_type,
+            ..
         }) => {
+            let preserved = preserved_join_sides(join_type);
             // duplicate filters for joined columns so filters can be pushed down to both sides.
             // Take the following query as an example:
             //
@@ -413,6 +484,12 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
             //
             // Join clauses with `Using` constraints also take advantage of this logic to make sure
             // predicates reference the shared join columns are pushed to both sides.
+            //
+            // This only holds for inner joins: the duplicated predicate would not be implied
+            // by the original one above the null-extended rows of an outer join.
+            if *join_type != JoinType::Inner {
+                return optimize_join(state, plan, left, right, preserved);
+            }
             let join_side_filters = state
                 .filters
                 .iter()
@@ -456,7 +533,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 .collect::<Result<Vec<_>>>()?;
             state.filters.extend(join_side_filters);
 
-            optimize_join(state, plan, left, right)
+            optimize_join(state, plan, left, right, preserved)
         }
         LogicalPlan::TableScan(TableScan {
             source,
@@ -485,7 +562,7 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                     // Don't add expression again if it's already present in
                     // pushed down filters.
                     if new_filters.contains(filter_expr) {
-                        break;
+                        continue;
                     }
                     new_filters.push(filter_expr.clone());
                 }
@@ -504,6 +581,28 @@ fn optimize(plan: &LogicalPlan, mut state: State) -> Result<LogicalPlan> {
                 }),
             )
         }
+        LogicalPlan::Extension(Extension { node }) => {
+            // filters can only be pushed below a user-defined node on the columns
+            // of its single input that the node doesn't prevent them from
+            let prevented_columns = node.prevent_predicate_push_down_columns();
+            let input_schema = match node.inputs().as_slice() {
+                [input] => Some(input.schema().clone()),
+                _ => None,
+            };
+            let used_columns = plan
+                .schema()
+                .fields()
+                .iter()
+                .map(|f| f.qualified_column())
+                .filter(|column| {
+                    prevented_columns.contains(&column.name)
+                        || !input_schema.as_ref().map_or(false, |schema| {
+                            schema.field_from_column(column).is_ok()
+                        })
+                })
+                .collect::<HashSet<_>>();
+            issue_filters(state, used_columns, plan)
+        }
         _ => {
             // all other plans are _not_ filter-commutable
             let used_columns = plan
@@ -556,11 +655,14 @@ fn rewrite(expr: &Expr, projection: &HashMap<String, Expr>) -> Result<Expr> {
 mod tests {
     use super::*;
     use crate::datasource::TableProvider;
-    use crate::logical_plan::{lit, sum, DFSchema, Expr, LogicalPlanBuilder, Operator};
-    use crate::physical_plan::ExecutionPlan;
+    use crate::logical_plan::{
+        create_udf, lit, random, sum, DFSchema, Expr, LogicalPlanBuilder, Operator,
+    };
+    use crate::physical_plan::functions::Volatility;
+    use crate::physical_plan::{ColumnarValue, ExecutionPlan};
     use crate::test::*;
     use crate::{logical_plan::col, prelude::JoinType};
-    use arrow::datatypes::SchemaRef;
+    use arrow::datatypes::{DataType, SchemaRef};
     use async_trait::async_trait;
 
     fn optimize_plan(plan: &LogicalPlan) -> LogicalPlan {
@@ -872,12 +974,12 @@ mod tests {
             .union(LogicalPlanBuilder::from(table_scan).build()?)?
             .filter(col("a").eq(lit(1i64)))?
             .build()?;
-        // filter appears below Union
+        // filter appears below Union, on the columns of each input
         let expected = "\
             Union\
-            \n  Filter: #a = Int64(1)\
+            \n  Filter: #test.a = Int64(1)\
             \n    TableScan: test projection=None\
-            \n  Filter: #a = Int64(1)\
+            \n  Filter: #test.a = Int64(1)\
             \n    TableScan: test projection=None";
         assert_optimized_plan_eq(&plan, expected);
         Ok(())
@@ -973,6 +1075,41 @@ mod tests {
         Ok(())
     }
 
+    /// filters are kept above a user defined node, unless it allows them to
+    /// be pushed down to its input
+    #[test]
+    fn filters_over_user_defined_node() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = crate::test::user_defined::new(table_scan.clone());
+        let plan = LogicalPlanBuilder::from(plan)
+            .filter(col("a").lt_eq(lit(1i64)))?
+            .build()?;
+
+        let expected = "\
+            Filter: #test.a <= Int64(1)\
+            \n  TestUserDefined\
+            \n    TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+
+        let prevented_columns = HashSet::from(["b".to_owned()]);
+        let plan = crate::test::user_defined::new_with_prevented_columns(
+            table_scan,
+            prevented_columns,
+        );
+        let plan = LogicalPlanBuilder::from(plan)
+            .filter(col("a").lt_eq(lit(1i64)))?
+            .filter(col("b").eq(lit(2i64)))?
+            .build()?;
+
+        let expected = "\
+            Filter: #test.b = Int64(2)\
+            \n  TestUserDefined\
+            \n    Filter: #test.a <= Int64(1)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     /// post-on-join predicates on a column common to both sides is pushed to both sides
     #[test]
     fn filter_on_join_on_common_independent() -> Result<()> {
@@ -1138,6 +1275,216 @@ mod tests {
         Ok(())
     }
 
+    /// the predicates of a conjunction above a join are pushed to the side they depend on
+    #[test]
+    fn filter_join_split_conjunction() -> Result<()> {
+        let left = test_table_scan()?;
+        let right = test_table_scan_with_name("test2")?;
+        let plan = LogicalPlanBuilder::from(left)
+            .join(
+                &right,
+                JoinType::Inner,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .filter(
+                col("test.b")
+                    .gt(lit(5i64))
+                    .and(col("test2.c").eq(lit(1i64)))
+                    .and(col("test.c").eq(col("test2.b"))),
+            )?
+            .build()?;
+
+        // the predicate depending on both sides remains above the join
+        let expected = "\
+        Filter: #test.c = #test2.b\
+        \n  Join: #test.a = #test2.a\
+        \n    Filter: #test.b > Int64(5)\
+        \n      TableScan: test projection=None\
+        \n    Filter: #test2.c = Int64(1)\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    fn outer_join_filter(join_type: JoinType) -> Result<LogicalPlan> {
+        let left = test_table_scan()?;
+        let right = test_table_scan_with_name("test2")?;
+        LogicalPlanBuilder::from(left)
+            .join(
+                &right,
+                join_type,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .filter(
+                col("test.a")
+                    .lt_eq(lit(1i64))
+                    .and(col("test.b").gt(lit(5i64)))
+                    .and(col("test2.c").eq(lit(1i64))),
+            )?
+            .build()
+    }
+
+    /// predicates are only pushed to the preserved side of an outer join, and predicates on
+    /// the join keys are not duplicated to the other side
+    #[test]
+    fn filter_left_join() -> Result<()> {
+        let plan = outer_join_filter(JoinType::Left)?;
+        let expected = "\
+        Filter: #test2.c = Int64(1)\
+        \n  Join: #test.a = #test2.a\
+        \n    Filter: #test.a <= Int64(1) AND #test.b > Int64(5)\
+        \n      TableScan: test projection=None\
+        \n    TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_right_join() -> Result<()> {
+        let plan = outer_join_filter(JoinType::Right)?;
+        let expected = "\
+        Filter: #test.a <= Int64(1) AND #test.b > Int64(5)\
+        \n  Join: #test.a = #test2.a\
+        \n    TableScan: test projection=None\
+        \n    Filter: #test2.c = Int64(1)\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn filter_full_join() -> Result<()> {
+        let plan = outer_join_filter(JoinType::Full)?;
+        // expected is equal: no push-down
+        let expected = &format!("{:?}", plan);
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// the predicates on the group keys of a conjunction are pushed below the aggregate
+    #[test]
+    fn filter_agg_split_conjunction() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .aggregate(vec![col("a"), col("b")], vec![sum(col("b")).alias("total")])?
+            .filter(
+                col("a")
+                    .gt(lit(10i64))
+                    .and(col("total").gt(lit(100i64)))
+                    .and(col("b").lt(lit(5i64))),
+            )?
+            .build()?;
+        // `b` is both a group key and aggregated
+        let expected = "\
+            Filter: #total > Int64(100)\
+            \n  Aggregate: groupBy=[[#test.a, #test.b]], aggr=[[SUM(#test.b) AS total]]\
+            \n    Filter: #test.a > Int64(10) AND #test.b < Int64(5)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// the predicates pushed below a union are re-written on the columns of each input
+    #[test]
+    fn union_all_different_columns() -> Result<()> {
+        let left = LogicalPlanBuilder::from(test_table_scan()?)
+            .project(vec![col("a")])?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(test_table_scan_with_name("test2")?)
+            .project(vec![col("b").alias("a")])?
+            .union(left)?
+            .filter(col("a").eq(lit(1i64)))?
+            .build()?;
+        let expected = "\
+            Union\
+            \n  Projection: #test2.b AS a\
+            \n    Filter: #test2.b = Int64(1)\
+            \n      TableScan: test2 projection=None\
+            \n  Projection: #test.a\
+            \n    Filter: #test.a = Int64(1)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// volatile predicates are neither pushed nor duplicated
+    #[test]
+    fn filter_volatile() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(table_scan.clone())
+            .union(LogicalPlanBuilder::from(table_scan).build()?)?
+            .filter(random().lt(lit(0.5)).and(col("a").eq(lit(1i64))))?
+            .build()?;
+        let expected = "\
+            Filter: random() < Float64(0.5)\
+            \n  Union\
+            \n    Filter: #test.a = Int64(1)\
+            \n      TableScan: test projection=None\
+            \n    Filter: #test.a = Int64(1)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// a predicate on a volatile projected expression is not pushed below the projection,
+    /// where it would evaluate the expression again
+    #[test]
+    fn filter_volatile_projection() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(vec![random().alias("r"), col("a")])?
+            .filter(col("r").lt(lit(0.5)).and(col("a").eq(lit(1i64))))?
+            .build()?;
+        let expected = "\
+            Filter: #r < Float64(0.5)\
+            \n  Projection: random() AS r, #test.a\
+            \n    Filter: #test.a = Int64(1)\
+            \n      TableScan: test projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    /// predicates calling non-volatile UDFs are pushed below a join unless the UDF
+    /// disables filter pushdown
+    #[test]
+    fn filter_udf_join() -> Result<()> {
+        let udf = |name: &str, volatility: Volatility| {
+            create_udf(
+                name,
+                vec![DataType::UInt32],
+                Arc::new(DataType::UInt32),
+                volatility,
+                Arc::new(|args: &[ColumnarValue]| Ok(args[0].clone())),
+            )
+        };
+        let pure = udf("pure", Volatility::Immutable);
+        let stable = udf("stable", Volatility::Stable);
+        let expensive =
+            udf("expensive", Volatility::Immutable).with_filter_pushdown(false);
+        let volatile = udf("volatile", Volatility::Volatile);
+
+        let left = test_table_scan()?;
+        let right = test_table_scan_with_name("test2")?;
+        let plan = LogicalPlanBuilder::from(left)
+            .join(
+                &right,
+                JoinType::Inner,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .filter(
+                pure.call(vec![col("test.b")])
+                    .gt(lit(5u32))
+                    .and(stable.call(vec![col("test2.c")]).eq(lit(1u32)))
+                    .and(expensive.call(vec![col("test.c")]).eq(lit(2u32)))
+                    .and(volatile.call(vec![col("test2.b")]).eq(lit(3u32))),
+            )?
+            .build()?;
+
+        let expected = "        Filter: expensive(#test.c) = UInt32(2) AND volatile(#test2.b) = UInt32(3)        \n  Join: #test.a = #test2.a        \n    Filter: pure(#test.b) > UInt32(5)        \n      TableScan: test projection=None        \n    Filter: stable(#test2.c) = UInt32(1)        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     struct PushDownProvider {
         pub filter_support: TableProviderFilterPushDown,
     }
@@ -0,0 +1,319 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule inferring the equalities of columns with literals implied
+//! by the keys of inner joins.
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::plan::{Filter, Join, TableScan};
+use crate::logical_plan::{Column, Expr, JoinType, LogicalPlan, Operator};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+use crate::scalar::ScalarValue;
+use std::sync::Arc;
+
+/// Infers the predicates implied by the equi-join conditions of a tree of
+/// inner joins below a filter.
+///
+/// The join keys of the inner joins form equivalence classes of columns: in
+/// the output of `a JOIN b ON a.k = b.k JOIN c ON b.k = c.k`, the columns
+/// `a.k`, `b.k` and `c.k` are equal. An equality of a column with a literal,
+/// either in the filter or in the filters and scans of the joined relations,
+/// then holds for every column of its class, so
+///
+/// ```text
+/// Filter: #a.k = Int64(5)
+///   Join: #b.k = #c.k
+///     Join: #a.k = #b.k
+/// ```
+///
+/// is rewritten to filter on `#a.k = Int64(5) AND #b.k = Int64(5) AND #c.k = Int64(5)`,
+/// which [`FilterPushDown`](crate::optimizer::filter_push_down::FilterPushDown)
+/// then pushes to the three relations. The predicates already known to hold
+/// are not inferred again, and the equalities are only propagated between
+/// columns of the same type.
+///
+/// The keys of outer joins form no equivalence, as the rows of their
+/// null-producing sides don't satisfy the join condition: only their
+/// preserved inputs are traversed.
+pub struct InferPredicates {}
+
+impl InferPredicates {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for InferPredicates {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        match plan {
+            LogicalPlan::Filter(Filter { predicate, input }) => {
+                let input = self.optimize(input, execution_props)?;
+
+                let mut classes = vec![];
+                let mut equalities = vec![];
+                collect_equalities(predicate, &mut equalities);
+                collect_joined(&input, &mut classes, &mut equalities);
+
+                let predicate = infer_equalities(&input, &classes, &equalities)
+                    .into_iter()
+                    .fold(predicate.clone(), |predicate, (column, value)| {
+                        predicate.and(Expr::Column(column).eq(Expr::Literal(value)))
+                    });
+                Ok(LogicalPlan::Filter(Filter {
+                    predicate,
+                    input: Arc::new(input),
+                }))
+            }
+            _ => utils::optimize_children(self, plan, execution_props),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "infer_predicates"
+    }
+}
+
+/// Collects the equivalence classes of the join keys of the tree of inner
+/// joins `plan`, and the equalities of columns with literals of the filters
+/// and scans of the joined relations
+fn collect_joined(
+    plan: &LogicalPlan,
+    classes: &mut Vec<Vec<Column>>,
+    equalities: &mut Vec<(Column, ScalarValue)>,
+) {
+    match plan {
+        LogicalPlan::Join(Join {
+            left,
+            right,
+            on,
+            join_type: JoinType::Inner,
+            ..
+        }) => {
+            for (l, r) in on {
+                merge_classes(classes, l, r);
+            }
+            collect_joined(left, classes, equalities);
+            collect_joined(right, classes, equalities);
+        }
+        // the keys of the other joins form no equivalence in their output,
+        // but the rows of their preserved inputs are kept
+        LogicalPlan::Join(Join {
+            left,
+            join_type: JoinType::Left | JoinType::Semi | JoinType::Anti,
+            ..
+        }) => collect_joined(left, classes, equalities),
+        LogicalPlan::Join(Join {
+            right,
+            join_type: JoinType::Right,
+            ..
+        }) => collect_joined(right, classes, equalities),
+        LogicalPlan::Filter(Filter { predicate, input }) => {
+            collect_equalities(predicate, equalities);
+            collect_joined(input, classes, equalities);
+        }
+        LogicalPlan::TableScan(TableScan { filters, .. }) => {
+            for filter in filters {
+                collect_equalities(filter, equalities);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Records that the columns `l` and `r` are equal, merging their classes
+fn merge_classes(classes: &mut Vec<Vec<Column>>, l: &Column, r: &Column) {
+    let find = |classes: &Vec<Vec<Column>>, column: &Column| {
+        classes.iter().position(|class| class.contains(column))
+    };
+    match (find(classes, l), find(classes, r)) {
+        (Some(i), Some(j)) if i == j => {}
+        (Some(i), Some(j)) => {
+            let class = classes.remove(j);
+            let i = if j < i { i - 1 } else { i };
+            classes[i].extend(class);
+        }
+        (Some(i), None) => classes[i].push(r.clone()),
+        (None, Some(j)) => classes[j].push(l.clone()),
+        (None, None) => classes.push(vec![l.clone(), r.clone()]),
+    }
+}
+
+/// Collects the equalities of a column with a non-null literal of the
+/// conjunction `predicate`
+fn collect_equalities(predicate: &Expr, equalities: &mut Vec<(Column, ScalarValue)>) {
+    let mut predicates = vec![];
+    utils::split_conjunction(predicate, &mut predicates);
+    for predicate in predicates {
+        if let Expr::BinaryExpr {
+            left,
+            op: Operator::Eq,
+            right,
+        } = predicate
+        {
+            match (left.as_ref(), right.as_ref()) {
+                (Expr::Column(column), Expr::Literal(value))
+                | (Expr::Literal(value), Expr::Column(column))
+                    if !value.is_null() =>
+                {
+                    equalities.push((column.clone(), value.clone()))
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Returns the equalities of columns with literals implied by `equalities`
+/// and the equivalence `classes` of the columns of `plan` which aren't
+/// already known
+fn infer_equalities(
+    plan: &LogicalPlan,
+    classes: &[Vec<Column>],
+    equalities: &[(Column, ScalarValue)],
+) -> Vec<(Column, ScalarValue)> {
+    let schema = plan.schema();
+    let data_type = |column: &Column| {
+        schema
+            .field_from_column(column)
+            .ok()
+            .map(|field| field.data_type().clone())
+    };
+
+    let mut inferred: Vec<(Column, ScalarValue)> = vec![];
+    for (column, value) in equalities {
+        let class = match classes.iter().find(|class| class.contains(column)) {
+            Some(class) => class,
+            None => continue,
+        };
+        let column_type = data_type(column);
+        for member in class {
+            let equality = (member.clone(), value.clone());
+            if column_type.is_some()
+                && data_type(member) == column_type
+                && !equalities.contains(&equality)
+                && !inferred.contains(&equality)
+            {
+                inferred.push(equality);
+            }
+        }
+    }
+    inferred
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::{col, lit, LogicalPlanBuilder};
+    use crate::test::*;
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let rule = InferPredicates::new();
+        let optimized_plan = rule
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+    }
+
+    /// `test1 JOIN test2 ON test1.a = test2.a JOIN test3 ON test2.a = test3.a`
+    fn three_way_join(join_type: JoinType) -> Result<LogicalPlanBuilder> {
+        let test2 = test_table_scan_with_name("test2")?;
+        let test3 = test_table_scan_with_name("test3")?;
+        LogicalPlanBuilder::from(test_table_scan_with_name("test1")?)
+            .join(
+                &test2,
+                JoinType::Inner,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .join(
+                &test3,
+                join_type,
+                (vec![Column::from("test2.a")], vec![Column::from_name("a")]),
+            )
+    }
+
+    #[test]
+    fn infer_through_joins() -> Result<()> {
+        let plan = three_way_join(JoinType::Inner)?
+            .filter(
+                col("test1.a")
+                    .eq(lit(5u32))
+                    .and(col("test1.b").eq(lit(1u32))),
+            )?
+            .build()?;
+
+        let expected = "\
+        Filter: #test1.a = UInt32(5) AND #test1.b = UInt32(1) AND #test2.a = UInt32(5) AND #test3.a = UInt32(5)\
+        \n  Join: #test2.a = #test3.a\
+        \n    Join: #test1.a = #test2.a\
+        \n      TableScan: test1 projection=None\
+        \n      TableScan: test2 projection=None\
+        \n    TableScan: test3 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn skip_known_predicates() -> Result<()> {
+        let test2 = LogicalPlanBuilder::from(test_table_scan_with_name("test2")?)
+            .filter(lit(5u32).eq(col("a")))?
+            .build()?;
+        let plan = LogicalPlanBuilder::from(test_table_scan_with_name("test1")?)
+            .join(
+                &test2,
+                JoinType::Inner,
+                (vec![Column::from_name("a")], vec![Column::from_name("a")]),
+            )?
+            .filter(col("test1.a").eq(lit(5u32)))?
+            .build()?;
+
+        // both sides are already filtered
+        let expected = "\
+        Filter: #test1.a = UInt32(5)\
+        \n  Join: #test1.a = #test2.a\
+        \n    TableScan: test1 projection=None\
+        \n    Filter: UInt32(5) = #test2.a\
+        \n      TableScan: test2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_inference_across_outer_joins() -> Result<()> {
+        let plan = three_way_join(JoinType::Left)?
+            .filter(col("test1.a").eq(lit(5u32)))?
+            .build()?;
+
+        // test3 is the null-producing side of the left join
+        let expected = "\
+        Filter: #test1.a = UInt32(5) AND #test2.a = UInt32(5)\
+        \n  Join: #test2.a = #test3.a\
+        \n    Join: #test1.a = #test2.a\
+        \n      TableScan: test1 projection=None\
+        \n      TableScan: test2 projection=None\
+        \n    TableScan: test3 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+}
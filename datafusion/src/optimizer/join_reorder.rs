@@ -0,0 +1,740 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Optimizer rule reordering the relations of multi-way inner joins using
+//! the statistics of the tables.
+
+use crate::error::Result;
+use crate::execution::context::ExecutionProps;
+use crate::logical_plan::plan::{Filter, Join, Limit, Projection, Sort, TableScan};
+use crate::logical_plan::{
+    Column, Expr, JoinConstraint, JoinType, LogicalPlan, LogicalPlanBuilder,
+};
+use crate::optimizer::optimizer::OptimizerRule;
+use crate::optimizer::utils;
+
+/// Maximum number of relations whose orders are enumerated
+const MAX_RELATIONS: usize = 6;
+
+/// Number of rows assumed for a relation without statistics. It is large
+/// enough for such relations to be joined last.
+const UNKNOWN_ROWS: f64 = 1e12;
+
+/// Number of bytes assumed for a field of a relation without statistics
+const UNKNOWN_FIELD_BYTES: f64 = 8.0;
+
+/// Reorders the relations of a connected tree of inner equi-joins.
+///
+/// The left-deep orders of up to 6 relations are enumerated, and the order
+/// minimizing the estimated size in bytes of the build sides (the left
+/// inputs, which are the intermediate results of a left-deep tree) is
+/// chosen. The sizes are estimated from the statistics of the scanned
+/// tables, see [`TableProvider::statistics`](crate::datasource::TableProvider::statistics);
+/// the relations without statistics are assumed to be very large. Orders
+/// requiring a cross join are never chosen.
+///
+/// The plan is only rewritten if a strictly cheaper order is found, and a
+/// projection restores the order of the output columns. The inputs of a
+/// join of two relations are ordered by the physical optimizer, see
+/// [`HashBuildProbeOrder`](crate::physical_optimizer::hash_build_probe_order::HashBuildProbeOrder).
+pub struct JoinReorder {}
+
+impl JoinReorder {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl OptimizerRule for JoinReorder {
+    fn optimize(
+        &self,
+        plan: &LogicalPlan,
+        execution_props: &ExecutionProps,
+    ) -> Result<LogicalPlan> {
+        if !is_reorderable(plan) {
+            return utils::optimize_children(self, plan, execution_props);
+        }
+
+        let mut relations = vec![];
+        let mut on = vec![];
+        flatten_join(plan, &mut relations, &mut on);
+        let relations = relations
+            .into_iter()
+            .map(|relation| Ok(Relation::new(self.optimize(relation, execution_props)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        let edges = join_edges(&relations, &on);
+        let graph = match edges {
+            Some(edges)
+                if (3..=MAX_RELATIONS).contains(&relations.len())
+                    && relations.iter().any(|relation| relation.has_statistics) =>
+            {
+                JoinGraph { relations, edges }
+            }
+            _ => return rebuild(plan, &mut relations.into_iter()),
+        };
+
+        // the original order is kept unless another one is strictly cheaper
+        let original = (0..graph.relations.len()).collect::<Vec<_>>();
+        let mut best_order = original.clone();
+        let mut best_cost = graph.cost(&original);
+        permutations(&original, &mut |order| {
+            if let Some(cost) = graph.cost(order) {
+                if best_cost.map(|best| cost < best).unwrap_or(true) {
+                    best_order = order.to_vec();
+                    best_cost = Some(cost);
+                }
+            }
+        });
+
+        if best_order == original {
+            return rebuild(plan, &mut graph.relations.into_iter());
+        }
+        let columns = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .collect::<Vec<_>>();
+        LogicalPlanBuilder::from(graph.build(&best_order)?)
+            .project(columns)?
+            .build()
+    }
+
+    fn name(&self) -> &str {
+        "join_reorder"
+    }
+}
+
+/// Returns true if `plan` is a join that can be reordered with its inputs
+fn is_reorderable(plan: &LogicalPlan) -> bool {
+    matches!(
+        plan,
+        LogicalPlan::Join(Join {
+            join_type: JoinType::Inner,
+            join_constraint: JoinConstraint::On,
+            null_equals_null: false,
+            ..
+        })
+    )
+}
+
+/// Collect the relations and the join keys of the tree of reorderable joins
+/// rooted at `plan`, from left to right
+fn flatten_join<'a>(
+    plan: &'a LogicalPlan,
+    relations: &mut Vec<&'a LogicalPlan>,
+    on: &mut Vec<(Column, Column)>,
+) {
+    match plan {
+        LogicalPlan::Join(join) if is_reorderable(plan) => {
+            flatten_join(&join.left, relations, on);
+            flatten_join(&join.right, relations, on);
+            on.extend(join.on.iter().cloned());
+        }
+        _ => relations.push(plan),
+    }
+}
+
+/// Rebuild the tree of joins `plan` over `relations`, listed in the order
+/// of [`flatten_join`]
+fn rebuild(
+    plan: &LogicalPlan,
+    relations: &mut impl Iterator<Item = Relation>,
+) -> Result<LogicalPlan> {
+    if !is_reorderable(plan) {
+        return Ok(relations.next().unwrap().plan);
+    }
+    let inputs = plan
+        .inputs()
+        .into_iter()
+        .map(|input| rebuild(input, relations))
+        .collect::<Result<Vec<_>>>()?;
+    utils::from_plan(plan, &plan.expressions(), &inputs)
+}
+
+/// Call `f` with every permutation of `items`
+fn permutations(items: &[usize], f: &mut impl FnMut(&[usize])) {
+    fn permute(items: &mut [usize], k: usize, f: &mut impl FnMut(&[usize])) {
+        if k == items.len() {
+            return f(items);
+        }
+        for i in k..items.len() {
+            items.swap(k, i);
+            permute(items, k + 1, f);
+            items.swap(k, i);
+        }
+    }
+    permute(&mut items.to_vec(), 0, f)
+}
+
+/// A relation joined by a tree of joins, with its estimated size
+struct Relation {
+    plan: LogicalPlan,
+    has_statistics: bool,
+    rows: f64,
+    row_bytes: f64,
+}
+
+impl Relation {
+    fn new(plan: LogicalPlan) -> Self {
+        let estimate = estimate(&plan);
+        let (rows, row_bytes) = estimate.unwrap_or((
+            UNKNOWN_ROWS,
+            UNKNOWN_FIELD_BYTES * plan.schema().fields().len() as f64,
+        ));
+        Self {
+            plan,
+            has_statistics: estimate.is_some(),
+            rows,
+            row_bytes,
+        }
+    }
+
+    /// Number of distinct values of `column`, if known from the statistics
+    /// of the scanned table
+    fn distinct_count(&self, column: &Column) -> Option<f64> {
+        let mut plan = &self.plan;
+        loop {
+            match plan {
+                LogicalPlan::TableScan(TableScan { source, .. }) => {
+                    let i = source.schema().index_of(&column.name).ok()?;
+                    let statistics = source.statistics().column_statistics?;
+                    let distinct_count = statistics.get(i)?.distinct_count?;
+                    return Some((distinct_count as f64).min(self.rows));
+                }
+                LogicalPlan::Filter(Filter { input, .. })
+                | LogicalPlan::Sort(Sort { input, .. })
+                | LogicalPlan::Limit(Limit { input, .. }) => plan = input.as_ref(),
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Estimated number of rows and size in bytes of a row of `plan`
+fn estimate(plan: &LogicalPlan) -> Option<(f64, f64)> {
+    match plan {
+        LogicalPlan::TableScan(TableScan {
+            source,
+            projection,
+            limit,
+            ..
+        }) => {
+            let statistics = source.statistics();
+            let num_rows = statistics.num_rows?;
+            let num_fields = source.schema().fields().len().max(1) as f64;
+            let mut row_bytes = match statistics.total_byte_size {
+                Some(bytes) if num_rows > 0 => bytes as f64 / num_rows as f64,
+                _ => UNKNOWN_FIELD_BYTES * num_fields,
+            };
+            if let Some(projection) = projection {
+                row_bytes *= projection.len() as f64 / num_fields;
+            }
+            let rows = match limit {
+                Some(limit) => num_rows.min(*limit),
+                None => num_rows,
+            };
+            Some((rows as f64, row_bytes))
+        }
+        // the number of rows of the input is an upper bound of the number
+        // of rows of a filter
+        LogicalPlan::Filter(Filter { input, .. })
+        | LogicalPlan::Sort(Sort { input, .. }) => estimate(input),
+        LogicalPlan::Projection(Projection { input, expr, .. }) => {
+            let (rows, row_bytes) = estimate(input)?;
+            let input_fields = input.schema().fields().len().max(1) as f64;
+            Some((rows, row_bytes * expr.len() as f64 / input_fields))
+        }
+        LogicalPlan::Limit(Limit { n, input }) => {
+            let (rows, row_bytes) = estimate(input)?;
+            Some((rows.min(*n as f64), row_bytes))
+        }
+        _ => None,
+    }
+}
+
+/// The join keys `on` as `(left relation, left column, right relation, right
+/// column)`, or `None` if a column cannot be resolved to a single relation
+fn join_edges(
+    relations: &[Relation],
+    on: &[(Column, Column)],
+) -> Option<Vec<(usize, Column, usize, Column)>> {
+    let find = |column: &Column| {
+        let mut found = relations.iter().enumerate().filter(|(_, relation)| {
+            relation.plan.schema().field_from_column(column).is_ok()
+        });
+        match (found.next(), found.next()) {
+            (Some((i, _)), None) => Some(i),
+            _ => None,
+        }
+    };
+    on.iter()
+        .map(|(l, r)| Some((find(l)?, l.clone(), find(r)?, r.clone())))
+        .collect()
+}
+
+/// The relations of a tree of joins and the join keys connecting them
+struct JoinGraph {
+    relations: Vec<Relation>,
+    /// `(left relation, left column, right relation, right column)`
+    edges: Vec<(usize, Column, usize, Column)>,
+}
+
+impl JoinGraph {
+    /// The join keys between the relations `joined` and the relation `next`,
+    /// as `(joined relation, joined column, next column)`
+    fn keys(&self, joined: &[usize], next: usize) -> Vec<(usize, &Column, &Column)> {
+        self.edges
+            .iter()
+            .filter_map(|(l, l_col, r, r_col)| {
+                if *r == next && joined.contains(l) {
+                    Some((*l, l_col, r_col))
+                } else if *l == next && joined.contains(r) {
+                    Some((*r, r_col, l_col))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Estimated cost of the left-deep tree joining the relations in
+    /// `order`: the total size in bytes of the left inputs of its joins.
+    /// Returns `None` if the order requires a cross join.
+    fn cost(&self, order: &[usize]) -> Option<f64> {
+        let first = &self.relations[order[0]];
+        let mut rows = first.rows;
+        let mut row_bytes = first.row_bytes;
+        let mut cost = 0.0;
+        for (i, next) in order.iter().enumerate().skip(1) {
+            // the relations joined so far are the left input of this join
+            cost += rows * row_bytes;
+
+            let keys = self.keys(&order[..i], *next);
+            if keys.is_empty() {
+                return None;
+            }
+            // the most selective key determines the size of the join
+            let relation = &self.relations[*next];
+            let distinct_count = keys
+                .iter()
+                .map(|(joined, joined_col, next_col)| {
+                    let joined = self.relations[*joined]
+                        .distinct_count(joined_col)
+                        .map(|count| count.min(rows));
+                    match (joined, relation.distinct_count(next_col)) {
+                        (Some(joined), Some(next)) => joined.max(next),
+                        (Some(count), None) | (None, Some(count)) => count,
+                        // assume that a foreign key is joined to a primary key
+                        (None, None) => rows.min(relation.rows),
+                    }
+                })
+                .fold(1.0, f64::max);
+            rows = rows * relation.rows / distinct_count;
+            row_bytes += relation.row_bytes;
+        }
+        Some(cost)
+    }
+
+    /// Build the left-deep tree joining the relations in `order`
+    fn build(&self, order: &[usize]) -> Result<LogicalPlan> {
+        let mut builder = LogicalPlanBuilder::from(self.relations[order[0]].plan.clone());
+        for (i, next) in order.iter().enumerate().skip(1) {
+            let (left, right): (Vec<Column>, Vec<Column>) = self
+                .keys(&order[..i], *next)
+                .into_iter()
+                .map(|(_, l, r)| (l.clone(), r.clone()))
+                .unzip();
+            builder = builder.join(
+                &self.relations[*next].plan,
+                JoinType::Inner,
+                (left, right),
+            )?;
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_eq;
+    use crate::datasource::file_format::parquet::ParquetFormat;
+    use crate::datasource::listing::ListingOptions;
+    use crate::datasource::TableProvider;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{ExecutionPlan, Statistics};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+    use async_trait::async_trait;
+    use std::any::Any;
+    use std::sync::Arc;
+
+    /// A table of Int32 columns with synthetic statistics
+    struct StatisticsTable {
+        schema: SchemaRef,
+        batches: Vec<RecordBatch>,
+        statistics: Statistics,
+    }
+
+    #[async_trait]
+    impl TableProvider for StatisticsTable {
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn as_mut_any(&mut self) -> &mut dyn Any {
+            self
+        }
+
+        fn schema(&self) -> SchemaRef {
+            self.schema.clone()
+        }
+
+        async fn scan(
+            &self,
+            projection: &Option<Vec<usize>>,
+            _batch_size: usize,
+            _filters: &[Expr],
+            _limit: Option<usize>,
+        ) -> Result<Arc<dyn ExecutionPlan>> {
+            Ok(Arc::new(MemoryExec::try_new(
+                &[self.batches.clone()],
+                self.schema(),
+                projection.clone(),
+            )?))
+        }
+
+        fn statistics(&self) -> Statistics {
+            self.statistics.clone()
+        }
+    }
+
+    /// A table with the `columns` and `num_rows` rows according to its
+    /// statistics, or without statistics if `num_rows` is `None`
+    fn table(
+        columns: Vec<(&str, Vec<i32>)>,
+        num_rows: Option<usize>,
+    ) -> Result<Arc<dyn TableProvider>> {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|(name, _)| Field::new(name, DataType::Int32, false))
+                .collect(),
+        ));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            columns
+                .into_iter()
+                .map(|(_, values)| Arc::new(Int32Array::from(values)) as _)
+                .collect(),
+        )?;
+        let statistics = Statistics {
+            num_rows,
+            total_byte_size: num_rows.map(|rows| rows * 4 * schema.fields().len()),
+            ..Statistics::default()
+        };
+        Ok(Arc::new(StatisticsTable {
+            schema,
+            batches: vec![batch],
+            statistics,
+        }))
+    }
+
+    fn scan(
+        name: &str,
+        columns: &[&str],
+        num_rows: Option<usize>,
+    ) -> Result<LogicalPlan> {
+        let columns = columns.iter().map(|name| (*name, vec![])).collect();
+        LogicalPlanBuilder::scan(name, table(columns, num_rows)?, None)?.build()
+    }
+
+    fn fact(num_rows: Option<usize>) -> Result<LogicalPlan> {
+        scan("fact", &["f_id", "f_dim1", "f_dim2"], num_rows)
+    }
+
+    fn dim1(num_rows: Option<usize>) -> Result<LogicalPlan> {
+        scan("dim1", &["d1_id", "d1_value"], num_rows)
+    }
+
+    fn dim2(num_rows: Option<usize>) -> Result<LogicalPlan> {
+        scan("dim2", &["d2_id", "d2_value"], num_rows)
+    }
+
+    fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
+        let rule = JoinReorder::new();
+        let optimized_plan = rule
+            .optimize(plan, &ExecutionProps::new())
+            .expect("failed to optimize plan");
+        let formatted_plan = format!("{:?}", optimized_plan);
+        assert_eq!(formatted_plan, expected);
+        assert_eq!(plan.schema(), optimized_plan.schema());
+    }
+
+    #[test]
+    fn reorder_three_way_join() -> Result<()> {
+        // the fact table is the build side of the first join
+        let plan = LogicalPlanBuilder::from(fact(Some(1_000_000))?)
+            .join(
+                &dim2(Some(10_000))?,
+                JoinType::Inner,
+                (vec!["f_dim2"], vec!["d2_id"]),
+            )?
+            .join(
+                &dim1(Some(100))?,
+                JoinType::Inner,
+                (vec!["f_dim1"], vec!["d1_id"]),
+            )?
+            .build()?;
+
+        let expected = "\
+        Projection: #fact.f_id, #fact.f_dim1, #fact.f_dim2, #dim2.d2_id, #dim2.d2_value, #dim1.d1_id, #dim1.d1_value\
+        \n  Join: #fact.f_dim2 = #dim2.d2_id\
+        \n    Join: #dim1.d1_id = #fact.f_dim1\
+        \n      TableScan: dim1 projection=None\
+        \n      TableScan: fact projection=None\
+        \n    TableScan: dim2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn keep_cheapest_order() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(dim1(Some(100))?)
+            .join(
+                &fact(Some(1_000_000))?,
+                JoinType::Inner,
+                (vec!["d1_id"], vec!["f_dim1"]),
+            )?
+            .join(
+                &dim2(Some(10_000))?,
+                JoinType::Inner,
+                (vec!["f_dim2"], vec!["d2_id"]),
+            )?
+            .build()?;
+
+        let expected = "\
+        Join: #fact.f_dim2 = #dim2.d2_id\
+        \n  Join: #dim1.d1_id = #fact.f_dim1\
+        \n    TableScan: dim1 projection=None\
+        \n    TableScan: fact projection=None\
+        \n  TableScan: dim2 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_statistics_joined_last() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(dim1(None)?)
+            .join(
+                &fact(Some(1_000_000))?,
+                JoinType::Inner,
+                (vec!["d1_id"], vec!["f_dim1"]),
+            )?
+            .join(
+                &dim2(Some(10_000))?,
+                JoinType::Inner,
+                (vec!["f_dim2"], vec!["d2_id"]),
+            )?
+            .build()?;
+
+        let expected = "\
+        Projection: #dim1.d1_id, #dim1.d1_value, #fact.f_id, #fact.f_dim1, #fact.f_dim2, #dim2.d2_id, #dim2.d2_value\
+        \n  Join: #fact.f_dim1 = #dim1.d1_id\
+        \n    Join: #dim2.d2_id = #fact.f_dim2\
+        \n      TableScan: dim2 projection=None\
+        \n      TableScan: fact projection=None\
+        \n    TableScan: dim1 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn no_statistics_keeps_order() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(fact(None)?)
+            .join(
+                &dim2(None)?,
+                JoinType::Inner,
+                (vec!["f_dim2"], vec!["d2_id"]),
+            )?
+            .join(
+                &dim1(None)?,
+                JoinType::Inner,
+                (vec!["f_dim1"], vec!["d1_id"]),
+            )?
+            .build()?;
+
+        let expected = "\
+        Join: #fact.f_dim1 = #dim1.d1_id\
+        \n  Join: #fact.f_dim2 = #dim2.d2_id\
+        \n    TableScan: fact projection=None\
+        \n    TableScan: dim2 projection=None\
+        \n  TableScan: dim1 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn outer_join_not_reordered() -> Result<()> {
+        let plan = LogicalPlanBuilder::from(fact(Some(1_000_000))?)
+            .join(
+                &dim2(Some(10_000))?,
+                JoinType::Left,
+                (vec!["f_dim2"], vec!["d2_id"]),
+            )?
+            .join(
+                &dim1(Some(100))?,
+                JoinType::Inner,
+                (vec!["f_dim1"], vec!["d1_id"]),
+            )?
+            .build()?;
+
+        // the left join is a single relation of the inner join, and the
+        // joins of two relations are not reordered
+        let expected = "\
+        Join: #fact.f_dim1 = #dim1.d1_id\
+        \n  Join: #fact.f_dim2 = #dim2.d2_id\
+        \n    TableScan: fact projection=None\
+        \n    TableScan: dim2 projection=None\
+        \n  TableScan: dim1 projection=None";
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reordering_preserves_results() -> Result<()> {
+        let sql = "SELECT * FROM fact \
+                   JOIN dim2 ON f_dim2 = d2_id \
+                   JOIN dim1 ON f_dim1 = d1_id \
+                   ORDER BY f_id";
+        let register = |ctx: &mut ExecutionContext| -> Result<()> {
+            let fact = vec![
+                ("f_id", vec![1, 2, 3, 4]),
+                ("f_dim1", vec![1, 2, 1, 2]),
+                ("f_dim2", vec![10, 20, 30, 10]),
+            ];
+            ctx.register_table("fact", table(fact, Some(1_000_000))?)?;
+            let dim1 = vec![("d1_id", vec![1, 2]), ("d1_value", vec![100, 200])];
+            ctx.register_table("dim1", table(dim1, Some(100))?)?;
+            let dim2 = vec![
+                ("d2_id", vec![10, 20, 30]),
+                ("d2_value", vec![1000, 2000, 3000]),
+            ];
+            ctx.register_table("dim2", table(dim2, Some(10_000))?)?;
+            Ok(())
+        };
+
+        let mut ctx = ExecutionContext::new();
+        register(&mut ctx)?;
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        assert!(format!("{:?}", plan).contains("Join: #dim1.d1_id = #fact.f_dim1"));
+        let results = ctx.sql(sql).await?.collect().await?;
+
+        let expected = vec![
+            "+------+--------+--------+-------+----------+-------+----------+",
+            "| f_id | f_dim1 | f_dim2 | d2_id | d2_value | d1_id | d1_value |",
+            "+------+--------+--------+-------+----------+-------+----------+",
+            "| 1    | 1      | 10     | 10    | 1000     | 1     | 100      |",
+            "| 2    | 2      | 20     | 20    | 2000     | 2     | 200      |",
+            "| 3    | 1      | 30     | 30    | 3000     | 1     | 100      |",
+            "| 4    | 2      | 10     | 10    | 1000     | 2     | 200      |",
+            "+------+--------+--------+-------+----------+-------+----------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        // the same results without optimization
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_optimizer_rules(vec![]),
+        );
+        register(&mut ctx)?;
+        let results = ctx.sql(sql).await?.collect().await?;
+        assert_batches_eq!(expected, &results);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reorder_parquet_tables() -> Result<()> {
+        let tmp_dir = tempfile::TempDir::new()?;
+        let path = |name: &str| format!("{}/{}", tmp_dir.path().to_str().unwrap(), name);
+        let tables: [(&str, &[&str], i32); 3] = [
+            (
+                "fact",
+                &["f_id", "f_dim1", "f_dim2", "f_a", "f_b", "f_c"],
+                10_000,
+            ),
+            ("dim1", &["d1_id", "d1_value"], 10),
+            ("dim2", &["d2_id", "d2_value"], 100),
+        ];
+
+        // write the tables to Parquet files, the fact table being the largest
+        let ctx = ExecutionContext::new();
+        for (name, columns, num_rows) in tables {
+            let columns = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    // the keys of the fact table reference the dimensions
+                    let modulo = [num_rows, 10, 100][i.min(2)];
+                    (*column, (0..num_rows).map(|v| v % modulo).collect())
+                })
+                .collect();
+            let plan =
+                LogicalPlanBuilder::scan(name, table(columns, None)?, None)?.build()?;
+            let plan = ctx.create_physical_plan(&plan).await?;
+            ctx.write_parquet(plan, path(name), None).await?;
+        }
+
+        let sql = "SELECT * FROM fact \
+                   JOIN dim2 ON f_dim2 = d2_id \
+                   JOIN dim1 ON f_dim1 = d1_id";
+        // the scanned tables, from the first one joined
+        let scanned_tables = |ctx: &ExecutionContext| -> Result<Vec<String>> {
+            let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+            Ok(format!("{:?}", plan)
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("TableScan: "))
+                .map(|scan| scan.split(' ').next().unwrap().to_owned())
+                .collect())
+        };
+
+        // without statistics the tables are joined in the order of the query
+        let mut ctx = ExecutionContext::new();
+        for (name, _, _) in tables {
+            let mut options = ListingOptions::new(Arc::new(ParquetFormat::default()));
+            options.collect_stat = false;
+            ctx.register_listing_table(name, &path(name), options, None)
+                .await?;
+        }
+        assert_eq!(scanned_tables(&ctx)?, vec!["fact", "dim2", "dim1"]);
+
+        // the statistics of the Parquet files are collected when the tables
+        // are registered, so the fact table is no longer joined first
+        let mut ctx = ExecutionContext::new();
+        for (name, _, _) in tables {
+            ctx.register_parquet(name, &path(name)).await?;
+        }
+        let scanned = scanned_tables(&ctx)?;
+        assert_eq!(scanned.len(), 3);
+        assert_ne!(scanned[0], "fact");
+        Ok(())
+    }
+}
@@ -21,6 +21,8 @@
 pub mod common_subexpr_eliminate;
 pub mod eliminate_limit;
 pub mod filter_push_down;
+pub mod infer_predicates;
+pub mod join_reorder;
 pub mod limit_push_down;
 pub mod optimizer;
 pub mod projection_push_down;
@@ -17,7 +17,9 @@
 
 //! Query optimizer traits
 
-use crate::error::Result;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::error::{DataFusionError, Result};
 use crate::execution::context::ExecutionProps;
 use crate::logical_plan::LogicalPlan;
 
@@ -35,3 +37,64 @@ pub trait OptimizerRule {
     /// A human readable name for this optimizer rule
     fn name(&self) -> &str;
 }
+
+/// Runs `optimize`, an application of the optimizer rule named `rule`, and
+/// names the rule in the error it returns or the panic it raises, so that a
+/// misbehaving rule can be identified and disabled.
+pub fn apply_rule<T>(rule: &str, optimize: impl FnOnce() -> Result<T>) -> Result<T> {
+    let context =
+        |message: String| format!("Optimizer rule '{}' failed: {}", rule, message);
+    match panic::catch_unwind(AssertUnwindSafe(optimize)) {
+        Ok(Ok(plan)) => Ok(plan),
+        Ok(Err(e)) => Err(match e {
+            DataFusionError::NotImplemented(message) => {
+                DataFusionError::NotImplemented(context(message))
+            }
+            DataFusionError::Internal(message) => {
+                DataFusionError::Internal(context(message))
+            }
+            DataFusionError::Plan(message) => DataFusionError::Plan(context(message)),
+            DataFusionError::Execution(message) => {
+                DataFusionError::Execution(context(message))
+            }
+            e => DataFusionError::Plan(context(e.to_string())),
+        }),
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "panic".to_string()
+            };
+            Err(DataFusionError::Internal(context(message)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_rule_names_failing_rule() {
+        let err = apply_rule::<()>("my_rule", || {
+            Err(DataFusionError::Plan("bad plan".to_string()))
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Optimizer rule 'my_rule' failed: bad plan"
+        );
+
+        let err = apply_rule::<()>("my_rule", || panic!("oops")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Internal error: Optimizer rule 'my_rule' failed: oops. This was likely \
+             caused by a bug in DataFusion's code and we would welcome that you file \
+             an bug report in our issue tracker"
+        );
+
+        assert_eq!(apply_rule("my_rule", || Ok(1)).unwrap(), 1);
+    }
+}
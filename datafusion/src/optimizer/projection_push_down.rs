@@ -25,12 +25,12 @@ use crate::logical_plan::plan::{
 };
 use crate::logical_plan::{
     build_join_schema, Column, DFField, DFSchema, DFSchemaRef, LogicalPlan,
-    LogicalPlanBuilder, ToDFSchema, Union,
+    LogicalPlanBuilder, Union,
 };
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
 use crate::sql::utils::find_sort_exprs;
-use arrow::datatypes::{Field, Schema};
+use arrow::datatypes::{DataType, Field, Schema};
 use arrow::error::Result as ArrowResult;
 use std::{
     collections::{BTreeSet, HashSet},
@@ -94,8 +94,9 @@ fn get_projected_schema(
         if has_projection && !schema.fields().is_empty() {
             // Ensure that we are reading at least one column from the table in case the query
             // does not reference any columns directly such as "SELECT COUNT(1) FROM table",
-            // except when the table is empty (no column)
-            projection.insert(0);
+            // except when the table is empty (no column). The narrowest column is the
+            // cheapest to read.
+            projection.insert(cheapest_column(schema));
         } else {
             // for table scan without projection, we default to return all columns
             projection = schema
@@ -120,7 +121,50 @@ fn get_projected_schema(
     };
 
     let projection = projection.into_iter().collect::<Vec<_>>();
-    Ok((projection, projected_fields.to_dfschema_ref()?))
+    let projected_schema =
+        DFSchema::new_with_metadata(projected_fields, schema.metadata().clone())?;
+    Ok((projection, Arc::new(projected_schema)))
+}
+
+/// Index of the column of `schema` whose values are the cheapest to read, the
+/// first one if several are as cheap
+fn cheapest_column(schema: &Schema) -> usize {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, field)| value_bits(field.data_type()))
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+/// Number of bits of a value of `data_type`, `usize::MAX` for the variable
+/// sized and nested values
+fn value_bits(data_type: &DataType) -> usize {
+    match data_type {
+        DataType::Null => 0,
+        DataType::Boolean => 1,
+        DataType::Int8 | DataType::UInt8 => 8,
+        DataType::Int16 | DataType::UInt16 | DataType::Float16 => 16,
+        DataType::Int32
+        | DataType::UInt32
+        | DataType::Float32
+        | DataType::Date32
+        | DataType::Time32(_) => 32,
+        DataType::Int64
+        | DataType::UInt64
+        | DataType::Float64
+        | DataType::Date64
+        | DataType::Time64(_)
+        | DataType::Timestamp(_, _)
+        | DataType::Duration(_)
+        | DataType::Interval(_) => 64,
+        DataType::Decimal(_, _) => 128,
+        DataType::FixedSizeBinary(size) => *size as usize * 8,
+        // the values are read through the keys
+        DataType::Dictionary(key_type, _) => value_bits(key_type),
+        _ => usize::MAX,
+    }
 }
 
 /// Recursively transverses the logical plan removing expressions and that are not needed.
@@ -187,7 +231,10 @@ fn optimize_plan(
                 Ok(LogicalPlan::Projection(Projection {
                     expr: new_expr,
                     input: Arc::new(new_input),
-                    schema: DFSchemaRef::new(DFSchema::new(new_fields)?),
+                    schema: DFSchemaRef::new(DFSchema::new_with_metadata(
+                        new_fields,
+                        schema.metadata().clone(),
+                    )?),
                     alias: alias.clone(),
                 }))
             }
@@ -199,6 +246,7 @@ fn optimize_plan(
             join_type,
             join_constraint,
             null_equals_null,
+            null_aware,
             ..
         }) => {
             for (l, r) in on {
@@ -236,6 +284,7 @@ fn optimize_plan(
                 on: on.clone(),
                 schema: DFSchemaRef::new(schema),
                 null_equals_null: *null_equals_null,
+                null_aware: *null_aware,
             }))
         }
         LogicalPlan::Window(Window {
@@ -306,13 +355,14 @@ fn optimize_plan(
                 }
             })?;
 
-            let new_schema = DFSchema::new(
+            let new_schema = DFSchema::new_with_metadata(
                 schema
                     .fields()
                     .iter()
                     .filter(|x| new_required_columns.contains(&x.qualified_column()))
                     .cloned()
                     .collect(),
+                schema.metadata().clone(),
             )?;
 
             Ok(LogicalPlan::Aggregate(Aggregate {
@@ -412,13 +462,14 @@ fn optimize_plan(
                     )
                 })
                 .collect::<Result<Vec<_>>>()?;
-            let new_schema = DFSchema::new(
+            let new_schema = DFSchema::new_with_metadata(
                 schema
                     .fields()
                     .iter()
                     .filter(|f| union_required_fields.contains(f.field()))
                     .cloned()
                     .collect(),
+                schema.metadata().clone(),
             )?;
             Ok(LogicalPlan::Union(Union {
                 inputs: new_inputs,
@@ -431,14 +482,16 @@ fn optimize_plan(
         LogicalPlan::Limit(_)
         | LogicalPlan::Filter { .. }
         | LogicalPlan::Repartition(_)
+        | LogicalPlan::Unnest(_)
         | LogicalPlan::EmptyRelation(_)
         | LogicalPlan::Values(_)
         | LogicalPlan::Sort { .. }
         | LogicalPlan::CreateExternalTable(_)
         | LogicalPlan::CreateMemoryTable(_)
+        | LogicalPlan::InsertInto(_)
         | LogicalPlan::DropTable(_)
-        | LogicalPlan::CrossJoin(_)
-        | LogicalPlan::Extension { .. } => {
+        | LogicalPlan::SetVariable(_)
+        | LogicalPlan::CrossJoin(_) => {
             let expr = plan.expressions();
             // collect all required columns by this plan
             utils::exprlist_to_columns(&expr, &mut new_required_columns)?;
@@ -460,6 +513,34 @@ fn optimize_plan(
 
             utils::from_plan(plan, &expr, &new_inputs)
         }
+        // the columns a user-defined node uses are not known beyond its
+        // expressions, and its schema may not follow the schemas of its inputs:
+        // all the columns of its inputs are kept. The terms of a recursive
+        // query keep all their columns, as the rows of an iteration are read by
+        // the next one
+        LogicalPlan::Extension { .. } | LogicalPlan::RecursiveQuery(_) => {
+            let new_inputs = plan
+                .inputs()
+                .iter()
+                .map(|input_plan| {
+                    let required_columns = input_plan
+                        .schema()
+                        .fields()
+                        .iter()
+                        .map(|f| f.qualified_column())
+                        .collect::<HashSet<_>>();
+                    optimize_plan(
+                        optimizer,
+                        input_plan,
+                        &required_columns,
+                        has_projection,
+                        execution_props,
+                    )
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            utils::from_plan(plan, &plan.expressions(), &new_inputs)
+        }
     }
 }
 
@@ -468,10 +549,9 @@ mod tests {
 
     use super::*;
     use crate::logical_plan::{
-        col, exprlist_to_fields, lit, max, min, Expr, JoinType, LogicalPlanBuilder,
+        col, count, exprlist_to_fields, lit, max, min, Expr, JoinType, LogicalPlanBuilder,
     };
     use crate::test::*;
-    use arrow::datatypes::DataType;
 
     #[test]
     fn aggregate_no_group_by() -> Result<()> {
@@ -904,6 +984,70 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn count_reads_cheapest_column() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Utf8, false),
+            Field::new("b", DataType::Int64, false),
+            Field::new("c", DataType::Boolean, false),
+            Field::new("d", DataType::Int8, false),
+        ]);
+        let table_scan =
+            LogicalPlanBuilder::scan_empty(Some("test"), &schema, None)?.build()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .aggregate(Vec::<Expr>::new(), vec![count(lit(1))])?
+            .build()?;
+
+        let expected = "Aggregate: groupBy=[[]], aggr=[[COUNT(Int32(1))]]\
+        \n  TableScan: test projection=Some([2])";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn expression_under_alias() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project_with_alias(
+                vec![col("a"), col("b"), col("c")],
+                Some("sub".to_string()),
+            )?
+            .project(vec![(col("sub.a") + col("sub.c")).alias("sum")])?
+            .build()?;
+
+        let expected = "Projection: #sub.a + #sub.c AS sum\
+        \n  Projection: #test.a, #test.c, alias=sub\
+        \n    TableScan: test projection=Some([0, 2])";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn user_defined_node_keeps_input_columns() -> Result<()> {
+        let table_scan = test_table_scan()?;
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(vec![col("a"), col("b"), col("c")])?
+            .build()?;
+        let plan = crate::test::user_defined::new(plan);
+        let plan = LogicalPlanBuilder::from(plan)
+            .project(vec![col("a")])?
+            .build()?;
+
+        let expected = "Projection: #test.a\
+        \n  TestUserDefined\
+        \n    Projection: #test.a, #test.b, #test.c\
+        \n      TableScan: test projection=Some([0, 1, 2])";
+
+        assert_optimized_plan_eq(&plan, expected);
+
+        Ok(())
+    }
+
     fn assert_optimized_plan_eq(plan: &LogicalPlan, expected: &str) {
         let optimized_plan = optimize(plan).expect("failed to optimize plan");
         let formatted_plan = format!("{:?}", optimized_plan);
@@ -17,12 +17,15 @@
 
 //! Simplify expressions optimizer rule
 
+use std::convert::TryFrom;
+
 use arrow::array::new_null_array;
 use arrow::datatypes::{DataType, Field, Schema};
 use arrow::record_batch::RecordBatch;
 
 use crate::error::DataFusionError;
 use crate::execution::context::{ExecutionContextState, ExecutionProps};
+use crate::logical_plan::plan::EmptyRelation;
 use crate::logical_plan::{lit, DFSchemaRef, Expr};
 use crate::logical_plan::{DFSchema, ExprRewriter, LogicalPlan, RewriteRecursion};
 use crate::optimizer::optimizer::OptimizerRule;
@@ -43,6 +46,10 @@ use crate::{error::Result, logical_plan::Operator};
 /// is optimized to
 /// `Filter: b > 2`
 ///
+/// A filter whose predicate simplifies to `true` is removed, and a filter
+/// whose predicate simplifies to `false` or `null` is replaced by an empty
+/// relation.
+///
 pub struct SimplifyExpressions {}
 
 fn expr_contains(expr: &Expr, needle: &Expr) -> bool {
@@ -301,18 +308,22 @@ impl OptimizerRule for SimplifyExpressions {
                 // Constant folding should not change expression name.
                 let name = &e.name(plan.schema());
 
-                // TODO combine simplify into Simplifier
-                let e = simplify(&e);
-
-                // TODO iterate until no changes are made
-                // during rewrite (evaluating constants can
-                // enable new simplifications and
-                // simplifications can enable new constant
+                // Iterate until no changes are made during rewrite
+                // (evaluating constants can enable new simplifications
+                // and simplifications can enable new constant
                 // evaluation)
-                let new_e = e
-                    // fold constants and then simplify
-                    .rewrite(&mut const_evaluator)?
-                    .rewrite(&mut simplifier)?;
+                let mut new_e = e;
+                loop {
+                    // TODO combine simplify into Simplifier
+                    let simplified = simplify(&new_e)
+                        // fold constants and then simplify
+                        .rewrite(&mut const_evaluator)?
+                        .rewrite(&mut simplifier)?;
+                    if simplified == new_e {
+                        break;
+                    }
+                    new_e = simplified;
+                }
 
                 let new_name = &new_e.name(plan.schema());
 
@@ -329,10 +340,39 @@ impl OptimizerRule for SimplifyExpressions {
             })
             .collect::<Result<Vec<_>>>()?;
 
+        if let LogicalPlan::Filter(_) = plan {
+            let mut predicate = &expr[0];
+            while let Expr::Alias(inner, _) = predicate {
+                predicate = inner;
+            }
+            match predicate {
+                // WHERE TRUE keeps every row
+                Expr::Literal(ScalarValue::Boolean(Some(true))) => {
+                    return Ok(new_inputs[0].clone());
+                }
+                // WHERE FALSE and WHERE NULL keep none
+                Expr::Literal(ScalarValue::Boolean(Some(false))) => {
+                    return Ok(empty_relation(plan));
+                }
+                Expr::Literal(value) if value.is_null() => {
+                    return Ok(empty_relation(plan));
+                }
+                _ => {}
+            }
+        }
+
         utils::from_plan(plan, &expr, &new_inputs)
     }
 }
 
+/// A relation without rows with the schema of `plan`
+fn empty_relation(plan: &LogicalPlan) -> LogicalPlan {
+    LogicalPlan::EmptyRelation(EmptyRelation {
+        produce_one_row: false,
+        schema: plan.schema().clone(),
+    })
+}
+
 impl SimplifyExpressions {
     #[allow(missing_docs)]
     pub fn new() -> Self {
@@ -476,7 +516,10 @@ impl ConstEvaluator {
             Expr::ScalarVariable(_) => false,
             Expr::Column(_) => false,
             Expr::ScalarFunction { fun, .. } => Self::volatility_ok(fun.volatility()),
-            Expr::ScalarUDF { fun, .. } => Self::volatility_ok(fun.signature.volatility),
+            // the async UDFs can only be awaited by the projections
+            Expr::ScalarUDF { fun, .. } => {
+                !fun.is_async() && Self::volatility_ok(fun.signature.volatility)
+            }
             Expr::WindowFunction { .. } => false,
             Expr::Sort { .. } => false,
             Expr::Wildcard => false,
@@ -493,6 +536,7 @@ impl ConstEvaluator {
             Expr::TryCast { .. } => true,
             Expr::InList { .. } => true,
             Expr::GetIndexedField { .. } => true,
+            Expr::Struct { .. } => true,
         }
     }
 
@@ -534,6 +578,8 @@ impl ConstEvaluator {
 /// * `false = true` and `true = false` to `false`
 /// * `!!expr` to `expr`
 /// * `expr = null` and `expr != null` to `null`
+/// * `CASE WHEN true THEN a ELSE b END` to `a`, and the branches of a `CASE`
+///   whose condition is `false` or `null` are removed
 pub(crate) struct Simplifier<'a> {
     /// input schemas
     schemas: Vec<&'a DFSchemaRef>,
@@ -554,6 +600,64 @@ impl<'a> Simplifier<'a> {
         false
     }
 
+    fn get_type(&self, expr: &Expr) -> Option<DataType> {
+        self.schemas
+            .iter()
+            .find_map(|schema| expr.get_type(schema).ok())
+    }
+
+    /// Remove the branches of `CASE WHEN ... END` that are never taken: the
+    /// ones whose condition is `false` or `null`, and the ones following a
+    /// condition that is `true`. The expression is left unchanged if the
+    /// rewrite would change its type.
+    fn simplify_case(
+        &self,
+        when_then_expr: Vec<(Box<Expr>, Box<Expr>)>,
+        else_expr: Option<Box<Expr>>,
+    ) -> Expr {
+        // the type of a CASE is the type of its first branch
+        let data_type = self.get_type(&when_then_expr[0].1);
+
+        let mut branches = vec![];
+        let mut otherwise = else_expr.clone();
+        for (when, then) in &when_then_expr {
+            match when.as_ref() {
+                Expr::Literal(ScalarValue::Boolean(Some(true))) => {
+                    otherwise = Some(then.clone());
+                    break;
+                }
+                Expr::Literal(ScalarValue::Boolean(Some(false)))
+                | Expr::Literal(ScalarValue::Boolean(None)) => {}
+                _ => branches.push((when.clone(), then.clone())),
+            }
+        }
+
+        let simplified = match (branches.is_empty(), otherwise, &data_type) {
+            (false, otherwise, _) => Some(Expr::Case {
+                expr: None,
+                when_then_expr: branches,
+                else_expr: otherwise,
+            }),
+            (true, Some(otherwise), _) => Some(*otherwise),
+            // no branch can be taken
+            (true, None, Some(data_type)) => {
+                ScalarValue::try_from(data_type).ok().map(Expr::Literal)
+            }
+            (true, None, None) => None,
+        };
+
+        match simplified {
+            Some(expr) if data_type.is_some() && self.get_type(&expr) == data_type => {
+                expr
+            }
+            _ => Expr::Case {
+                expr: None,
+                when_then_expr,
+                else_expr,
+            },
+        }
+    }
+
     fn boolean_folding_for_or(
         const_bool: &Option<bool>,
         bool_expr: Box<Expr>,
@@ -760,6 +864,11 @@ impl<'a> ExprRewriter for Simplifier<'a> {
                     Expr::Not(inner)
                 }
             }
+            Expr::Case {
+                expr: None,
+                when_then_expr,
+                else_expr,
+            } => self.simplify_case(when_then_expr, else_expr),
             expr => {
                 // no additional rewrites possible
                 expr
@@ -1354,11 +1463,90 @@ mod tests {
         );
     }
 
+    fn case_when(when_then_expr: Vec<(Expr, Expr)>, else_expr: Option<Expr>) -> Expr {
+        Expr::Case {
+            expr: None,
+            when_then_expr: when_then_expr
+                .into_iter()
+                .map(|(when, then)| (Box::new(when), Box::new(then)))
+                .collect(),
+            else_expr: else_expr.map(Box::new),
+        }
+    }
+
+    #[test]
+    fn simplify_expr_case_constant_condition() {
+        // CASE WHEN true THEN c1 ELSE 'b' END --> c1
+        assert_eq!(
+            do_simplify(case_when(vec![(lit(true), col("c1"))], Some(lit("b")))),
+            col("c1"),
+        );
+
+        // branches whose condition is false or null are never taken
+        assert_eq!(
+            do_simplify(case_when(
+                vec![
+                    (lit(false), lit("a")),
+                    (col("c2"), col("c1")),
+                    (lit_null(), lit("b")),
+                ],
+                Some(lit("c")),
+            )),
+            case_when(vec![(col("c2"), col("c1"))], Some(lit("c"))),
+        );
+
+        // the branches following a true condition are never taken
+        assert_eq!(
+            do_simplify(case_when(
+                vec![
+                    (col("c2"), col("c1")),
+                    (lit(true), lit("a")),
+                    (col("c2"), lit("b")),
+                ],
+                Some(lit("c")),
+            )),
+            case_when(vec![(col("c2"), col("c1"))], Some(lit("a"))),
+        );
+
+        // no branch can be taken and there is no ELSE
+        assert_eq!(
+            do_simplify(case_when(
+                vec![(lit(false), lit("a")), (lit_null(), lit("b"))],
+                None,
+            )),
+            lit(ScalarValue::Utf8(None)),
+        );
+
+        // removing the first branch would change the type of the CASE
+        let expr = case_when(vec![(lit(false), lit(1))], Some(col("c1")));
+        assert_eq!(do_simplify(expr.clone()), expr);
+    }
+
     /// Boolean null
     fn lit_null() -> Expr {
         Expr::Literal(ScalarValue::Boolean(None))
     }
 
+    #[test]
+    fn simplify_expr_three_valued_logic() {
+        // c2 AND false is false, even if c2 is null
+        assert_eq!(do_simplify(col("c2").and(lit(false))), lit(false));
+
+        // c2 OR true is true, even if c2 is null
+        assert_eq!(do_simplify(lit(true).or(col("c2"))), lit(true));
+
+        // c2 OR null is null (not c2) if c2 is false
+        let expr = col("c2").or(lit_null());
+        assert_eq!(do_simplify(expr.clone()), expr);
+
+        // c2 AND null is null (not c2) if c2 is true
+        let expr = lit_null().and(col("c2"));
+        assert_eq!(do_simplify(expr.clone()), expr);
+
+        // NOT NOT c2 is c2, also if c2 is null
+        assert_eq!(do_simplify(col("c2").not().not()), col("c2"));
+    }
+
     #[test]
     fn simplify_expr_bool_or() {
         // col || true is always true
@@ -1663,6 +1851,83 @@ mod tests {
         assert_optimized_plan_eq(&plan, expected);
     }
 
+    #[test]
+    fn test_simplify_optimized_plan_fold_constants() {
+        let table_scan = test_table_scan();
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(lit(1).eq(lit(1)).and(col("d").gt(lit(2) + lit(3))))
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let expected = "\
+        Filter: #test.d > Int32(5) AS Int32(1) = Int32(1) AND test.d > Int32(2) + Int32(3)\
+        \n  TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+    }
+
+    #[test]
+    fn test_simplify_optimized_plan_remove_true_filter() {
+        let table_scan = test_table_scan();
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .filter(lit(1).eq(lit(1)).or(col("a")))
+            .unwrap()
+            .project(vec![col("a")])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let expected = "\
+        Projection: #test.a\
+        \n  TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+    }
+
+    #[test]
+    fn test_simplify_optimized_plan_false_filter() {
+        for predicate in vec![col("a").and(lit(1).gt(lit(2))), lit_null().and(lit(true))]
+        {
+            let table_scan = test_table_scan();
+            let plan = LogicalPlanBuilder::from(table_scan)
+                .filter(predicate)
+                .unwrap()
+                .project(vec![col("a")])
+                .unwrap()
+                .build()
+                .unwrap();
+
+            let expected = "\
+            Projection: #test.a\
+            \n  EmptyRelation";
+
+            assert_optimized_plan_eq(&plan, expected);
+        }
+    }
+
+    #[test]
+    fn test_simplify_optimized_plan_case() {
+        let table_scan = test_table_scan();
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .project(vec![
+                case_when(vec![(lit(true), col("d"))], Some(lit(2))),
+                // folding `a OR true` collapses the CASE, which can then be
+                // folded with the addition
+                lit(1) + case_when(vec![(col("a").or(lit(true)), lit(2))], Some(lit(3))),
+            ])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let expected = "\
+        Projection: #test.d AS CASE WHEN Boolean(true) THEN test.d ELSE Int32(2) END, \
+        Int32(3) AS Int32(1) + CASE WHEN test.a OR Boolean(true) THEN Int32(2) ELSE Int32(3) END\
+        \n  TableScan: test projection=None";
+
+        assert_optimized_plan_eq(&plan, expected);
+    }
+
     // expect optimizing will result in an error, returning the error string
     fn get_optimized_plan_err(plan: &LogicalPlan, date_time: &DateTime<Utc>) -> String {
         let rule = SimplifyExpressions::new();
@@ -1830,8 +2095,8 @@ mod tests {
 
         // Note that constant folder runs and folds the entire
         // expression down to a single constant (true)
-        let expected = "Filter: Boolean(true) AS CAST(now() AS Int64) < CAST(totimestamp(Utf8(\"2020-09-08T12:05:00+00:00\")) AS Int64) + Int32(50000)\
-                        \n  TableScan: test projection=None";
+        // expression down to a single constant (true), and the filter is removed
+        let expected = "TableScan: test projection=None";
         let actual = get_optimized_plan_formatted(&plan, &time);
 
         assert_eq!(expected, actual);
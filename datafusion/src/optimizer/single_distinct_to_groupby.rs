@@ -23,6 +23,7 @@ use crate::logical_plan::plan::{Aggregate, Projection};
 use crate::logical_plan::{columnize_expr, DFSchema, Expr, LogicalPlan};
 use crate::optimizer::optimizer::OptimizerRule;
 use crate::optimizer::utils;
+use crate::physical_plan::aggregates::AggregateFunction;
 use hashbrown::HashSet;
 use std::sync::Arc;
 
@@ -59,55 +60,92 @@ fn optimize(plan: &LogicalPlan) -> Result<LogicalPlan> {
         }) => {
             if is_single_distinct_agg(plan) {
                 let mut group_fields_set = HashSet::new();
+                let mut inner_aggr_fields_set = HashSet::new();
                 let mut all_group_args = group_expr.clone();
-                // remove distinct and collection args
-                let new_aggr_expr = aggr_expr
-                    .iter()
-                    .map(|agg_expr| match agg_expr {
-                        Expr::AggregateFunction { fun, args, .. } => {
+                let mut inner_aggr_expr = Vec::new();
+                // remove distinct and collection args, and compute the
+                // other aggregates of each group of the inner aggregate
+                for agg_expr in aggr_expr {
+                    if let Expr::AggregateFunction { args, distinct, .. } = agg_expr {
+                        if *distinct {
                             // is_single_distinct_agg ensure args.len=1
-                            if group_fields_set
-                                .insert(args[0].name(input.schema()).unwrap())
-                            {
+                            if group_fields_set.insert(args[0].name(input.schema())?) {
                                 all_group_args.push(args[0].clone());
                             }
-                            Expr::AggregateFunction {
-                                fun: fun.clone(),
-                                args: args.clone(),
-                                distinct: false,
-                            }
+                        } else if inner_aggr_fields_set
+                            .insert(agg_expr.name(input.schema())?)
+                        {
+                            inner_aggr_expr.push(agg_expr.clone());
                         }
-                        _ => agg_expr.clone(),
-                    })
-                    .collect::<Vec<_>>();
+                    }
+                }
 
                 let all_field = all_group_args
                     .iter()
-                    .map(|expr| expr.to_field(input.schema()).unwrap())
-                    .collect::<Vec<_>>();
-
-                let grouped_schema = DFSchema::new(all_field).unwrap();
+                    .chain(inner_aggr_expr.iter())
+                    .map(|expr| expr.to_field(input.schema()))
+                    .collect::<Result<Vec<_>>>()?;
+
+                let grouped_schema = DFSchema::new_with_metadata(
+                    all_field,
+                    input.schema().metadata().clone(),
+                )?;
                 let grouped_agg = LogicalPlan::Aggregate(Aggregate {
                     input: input.clone(),
                     group_expr: all_group_args,
-                    aggr_expr: Vec::new(),
+                    aggr_expr: inner_aggr_expr,
                     schema: Arc::new(grouped_schema.clone()),
                 });
-                let grouped_agg = optimize_children(&grouped_agg);
-                let final_agg_schema = Arc::new(
-                    DFSchema::new(
-                        group_expr
-                            .iter()
-                            .chain(new_aggr_expr.iter())
-                            .map(|expr| expr.to_field(&grouped_schema).unwrap())
-                            .collect::<Vec<_>>(),
-                    )
-                    .unwrap(),
-                );
+                let grouped_agg = optimize_children(&grouped_agg)?;
+
+                // the outer aggregate reads the columns of the inner one
+                let outer_group_expr = group_expr
+                    .iter()
+                    .map(|expr| columnize_expr(expr.clone(), &grouped_schema))
+                    .collect::<Vec<_>>();
+                let new_aggr_expr = aggr_expr
+                    .iter()
+                    .map(|agg_expr| match agg_expr {
+                        Expr::AggregateFunction {
+                            fun,
+                            args,
+                            distinct: true,
+                        } => Expr::AggregateFunction {
+                            fun: fun.clone(),
+                            args: vec![columnize_expr(args[0].clone(), &grouped_schema)],
+                            distinct: false,
+                        },
+                        // MIN, MAX and SUM of the partial results of each group
+                        Expr::AggregateFunction { fun, .. } => Expr::AggregateFunction {
+                            fun: fun.clone(),
+                            args: vec![columnize_expr(agg_expr.clone(), &grouped_schema)],
+                            distinct: false,
+                        },
+                        _ => agg_expr.clone(),
+                    })
+                    .collect::<Vec<_>>();
+
+                let final_agg_fields = outer_group_expr
+                    .iter()
+                    .chain(new_aggr_expr.iter())
+                    .map(|expr| expr.to_field(&grouped_schema))
+                    .collect::<Result<Vec<_>>>()?;
+                // e.g. the sum of partial decimal sums is wider than the sum
+                if final_agg_fields
+                    .iter()
+                    .zip(schema.fields())
+                    .any(|(new, old)| new.data_type() != old.data_type())
+                {
+                    return optimize_children(plan);
+                }
+                let final_agg_schema = Arc::new(DFSchema::new_with_metadata(
+                    final_agg_fields,
+                    schema.metadata().clone(),
+                )?);
 
                 let final_agg = LogicalPlan::Aggregate(Aggregate {
-                    input: Arc::new(grouped_agg.unwrap()),
-                    group_expr: group_expr.clone(),
+                    input: Arc::new(grouped_agg),
+                    group_expr: outer_group_expr,
                     aggr_expr: new_aggr_expr,
                     schema: final_agg_schema.clone(),
                 });
@@ -149,27 +187,37 @@ fn optimize_children(plan: &LogicalPlan) -> Result<LogicalPlan> {
     utils::from_plan(plan, &expr, &new_inputs)
 }
 
+/// Returns true if the aggregates of `plan` include distinct aggregates, all of
+/// the same argument, and the other aggregates can be computed from their
+/// results on the groups of that argument
 fn is_single_distinct_agg(plan: &LogicalPlan) -> bool {
     match plan {
         LogicalPlan::Aggregate(Aggregate {
             input, aggr_expr, ..
         }) => {
             let mut fields_set = HashSet::new();
-            aggr_expr
-                .iter()
-                .filter(|expr| {
-                    let mut is_distinct = false;
-                    if let Expr::AggregateFunction { distinct, args, .. } = expr {
-                        is_distinct = *distinct;
-                        args.iter().for_each(|expr| {
-                            fields_set.insert(expr.name(input.schema()).unwrap());
-                        })
-                    }
-                    is_distinct
-                })
-                .count()
-                == aggr_expr.len()
-                && fields_set.len() == 1
+            let mut distinct_count = 0;
+            let all_supported = aggr_expr.iter().all(|expr| match expr {
+                Expr::AggregateFunction {
+                    distinct: true,
+                    args,
+                    ..
+                } => {
+                    distinct_count += 1;
+                    args.iter().for_each(|expr| {
+                        fields_set.insert(expr.name(input.schema()).unwrap());
+                    });
+                    true
+                }
+                Expr::AggregateFunction { fun, .. } => matches!(
+                    fun,
+                    AggregateFunction::Min
+                        | AggregateFunction::Max
+                        | AggregateFunction::Sum
+                ),
+                _ => false,
+            });
+            all_supported && distinct_count > 0 && fields_set.len() == 1
         }
         _ => false,
     }
@@ -184,14 +232,14 @@ impl OptimizerRule for SingleDistinctToGroupBy {
         optimize(plan)
     }
     fn name(&self) -> &str {
-        "SingleDistinctAggregationToGroupBy"
+        "single_distinct_to_group_by"
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::logical_plan::{col, count, count_distinct, max, LogicalPlanBuilder};
+    use crate::logical_plan::{col, count, count_distinct, max, sum, LogicalPlanBuilder};
     use crate::physical_plan::aggregates;
     use crate::test::*;
 
@@ -302,6 +350,27 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn distinct_and_partial_aggregates() -> Result<()> {
+        let table_scan = test_table_scan()?;
+
+        let plan = LogicalPlanBuilder::from(table_scan)
+            .aggregate(
+                vec![col("a")],
+                vec![count_distinct(col("b")), max(col("c")), sum(col("c"))],
+            )?
+            .build()?;
+
+        // MAX and SUM are computed on each group of the inner aggregate
+        let expected = "Projection: #test.a AS a, #COUNT(test.b) AS COUNT(DISTINCT test.b), #MAX(MAX(test.c)) AS MAX(test.c), #SUM(SUM(test.c)) AS SUM(test.c) [a:UInt32, COUNT(DISTINCT test.b):UInt64;N, MAX(test.c):UInt32;N, SUM(test.c):UInt64;N]\
+                            \n  Aggregate: groupBy=[[#test.a]], aggr=[[COUNT(#test.b), MAX(#MAX(test.c)), SUM(#SUM(test.c))]] [a:UInt32, COUNT(test.b):UInt64;N, MAX(MAX(test.c)):UInt32;N, SUM(SUM(test.c)):UInt64;N]\
+                            \n    Aggregate: groupBy=[[#test.a, #test.b]], aggr=[[MAX(#test.c), SUM(#test.c)]] [a:UInt32, b:UInt32, MAX(test.c):UInt32;N, SUM(test.c):UInt64;N]\
+                            \n      TableScan: test projection=None [a:UInt32, b:UInt32, c:UInt32]";
+
+        assert_optimized_plan_eq(&plan, expected);
+        Ok(())
+    }
+
     #[test]
     fn distinct_and_common() -> Result<()> {
         let table_scan = test_table_scan()?;
@@ -23,9 +23,12 @@ use crate::logical_plan::plan::{
     Aggregate, Analyze, Extension, Filter, Join, Projection, Sort, Window,
 };
 use crate::logical_plan::{
-    build_join_schema, Column, CreateMemoryTable, DFSchemaRef, Expr, Limit, LogicalPlan,
-    LogicalPlanBuilder, Operator, Partitioning, Recursion, Repartition, Union, Values,
+    build_join_schema, Column, CreateMemoryTable, DFSchemaRef, Expr, InsertInto, Limit,
+    LogicalPlan, LogicalPlanBuilder, Operator, Partitioning, Recursion, RecursiveQuery,
+    Repartition, Union, Unnest, Values,
 };
+use crate::physical_plan::functions::Volatility;
+use crate::physical_plan::window_functions::WindowFunction;
 use crate::prelude::lit;
 use crate::scalar::ScalarValue;
 use crate::{
@@ -83,6 +86,7 @@ impl ExpressionVisitor for ColumnNameVisitor<'_> {
             Expr::InList { .. } => {}
             Expr::Wildcard => {}
             Expr::GetIndexedField { .. } => {}
+            Expr::Struct { .. } => {}
         }
         Ok(Recursion::Continue(self))
     }
@@ -95,6 +99,110 @@ pub fn expr_to_columns(expr: &Expr, accum: &mut HashSet<Column>) -> Result<()> {
     Ok(())
 }
 
+struct VolatileFunctionVisitor {
+    found: bool,
+}
+
+impl ExpressionVisitor for VolatileFunctionVisitor {
+    fn pre_visit(mut self, expr: &Expr) -> Result<Recursion<Self>> {
+        let volatility = match expr {
+            Expr::ScalarFunction { fun, .. } => fun.volatility(),
+            Expr::ScalarUDF { fun, .. } => fun.signature.volatility,
+            _ => return Ok(Recursion::Continue(self)),
+        };
+        if volatility == Volatility::Volatile {
+            self.found = true;
+            return Ok(Recursion::Stop(self));
+        }
+        Ok(Recursion::Continue(self))
+    }
+}
+
+/// Recursively walk an expression tree, returning whether it calls a volatile
+/// function (e.g. `random()`), whose value may change at every evaluation
+pub fn is_volatile(expr: &Expr) -> Result<bool> {
+    Ok(expr.accept(VolatileFunctionVisitor { found: false })?.found)
+}
+
+struct MutableExprVisitor {
+    found: bool,
+}
+
+impl ExpressionVisitor for MutableExprVisitor {
+    fn pre_visit(mut self, expr: &Expr) -> Result<Recursion<Self>> {
+        let volatility = match expr {
+            Expr::ScalarVariable(_) => Volatility::Stable,
+            Expr::ScalarFunction { fun, .. } => fun.volatility(),
+            Expr::ScalarUDF { fun, .. } => fun.signature.volatility,
+            Expr::AggregateUDF { fun, .. } => fun.signature.volatility,
+            Expr::WindowFunction {
+                fun: WindowFunction::AggregateUDF(fun),
+                ..
+            } => fun.signature.volatility,
+            Expr::WindowFunction {
+                fun: WindowFunction::WindowUDF(fun),
+                ..
+            } => fun.signature.volatility,
+            _ => return Ok(Recursion::Continue(self)),
+        };
+        if volatility != Volatility::Immutable {
+            self.found = true;
+            return Ok(Recursion::Stop(self));
+        }
+        Ok(Recursion::Continue(self))
+    }
+}
+
+/// Recursively walk an expression tree, returning whether it only calls
+/// immutable functions and reads no variable, so that its value only depends
+/// on its inputs. Unlike [`is_volatile`], `now()` is not immutable.
+pub fn is_immutable(expr: &Expr) -> Result<bool> {
+    Ok(!expr.accept(MutableExprVisitor { found: false })?.found)
+}
+
+struct PinnedFunctionVisitor {
+    found: bool,
+}
+
+impl ExpressionVisitor for PinnedFunctionVisitor {
+    fn pre_visit(mut self, expr: &Expr) -> Result<Recursion<Self>> {
+        self.found = match expr {
+            Expr::ScalarFunction { fun, .. } => fun.volatility() == Volatility::Volatile,
+            Expr::ScalarUDF { fun, .. } => !fun.filter_pushdown_allowed(),
+            _ => false,
+        };
+        if self.found {
+            return Ok(Recursion::Stop(self));
+        }
+        Ok(Recursion::Continue(self))
+    }
+}
+
+/// Recursively walk an expression tree, returning whether it calls a volatile
+/// function or a UDF that doesn't support filter pushdown, which prevent a
+/// filter on the expression from being moved
+pub fn prevents_filter_pushdown(expr: &Expr) -> Result<bool> {
+    Ok(expr.accept(PinnedFunctionVisitor { found: false })?.found)
+}
+
+/// converts "A AND B AND C" => [A, B, C]
+pub fn split_conjunction<'a>(predicate: &'a Expr, predicates: &mut Vec<&'a Expr>) {
+    match predicate {
+        Expr::BinaryExpr {
+            right,
+            op: Operator::And,
+            left,
+        } => {
+            split_conjunction(left, predicates);
+            split_conjunction(right, predicates);
+        }
+        Expr::Alias(expr, _) => {
+            split_conjunction(expr, predicates);
+        }
+        other => predicates.push(other),
+    }
+}
+
 /// Convenience rule for writing optimizers: recursively invoke
 /// optimize on plan's children and then return a node of the same
 /// type. Useful for optimizer rules which want to leave the type
@@ -174,6 +282,16 @@ pub fn from_plan(
                 input: Arc::new(inputs[0].clone()),
             })),
         },
+        // the schema is computed again as the type of the column may have changed
+        LogicalPlan::Unnest(Unnest { .. }) => match &expr[0] {
+            Expr::Column(column) => LogicalPlanBuilder::from(inputs[0].clone())
+                .unnest(column.clone())?
+                .build(),
+            other => Err(DataFusionError::Internal(format!(
+                "Unnest expects a column, found {:?}",
+                other
+            ))),
+        },
         LogicalPlan::Window(Window {
             window_expr,
             schema,
@@ -200,6 +318,7 @@ pub fn from_plan(
             join_constraint,
             on,
             null_equals_null,
+            null_aware,
             ..
         }) => {
             let schema =
@@ -212,6 +331,7 @@ pub fn from_plan(
                 on: on.clone(),
                 schema: DFSchemaRef::new(schema),
                 null_equals_null: *null_equals_null,
+                null_aware: *null_aware,
             }))
         }
         LogicalPlan::CrossJoin(_) => {
@@ -229,6 +349,12 @@ pub fn from_plan(
                 name: name.clone(),
             }))
         }
+        LogicalPlan::InsertInto(InsertInto { name, .. }) => {
+            Ok(LogicalPlan::InsertInto(InsertInto {
+                input: Arc::new(inputs[0].clone()),
+                name: name.clone(),
+            }))
+        }
         LogicalPlan::Extension(e) => Ok(LogicalPlan::Extension(Extension {
             node: e.node.from_template(expr, inputs),
         })),
@@ -239,6 +365,14 @@ pub fn from_plan(
                 alias: alias.clone(),
             }))
         }
+        LogicalPlan::RecursiveQuery(RecursiveQuery {
+            name, is_distinct, ..
+        }) => Ok(LogicalPlan::RecursiveQuery(RecursiveQuery {
+            name: name.clone(),
+            static_term: Arc::new(inputs[0].clone()),
+            recursive_term: Arc::new(inputs[1].clone()),
+            is_distinct: *is_distinct,
+        })),
         LogicalPlan::Analyze(a) => {
             assert!(expr.is_empty());
             assert_eq!(inputs.len(), 1);
@@ -265,7 +399,8 @@ pub fn from_plan(
         LogicalPlan::EmptyRelation(_)
         | LogicalPlan::TableScan { .. }
         | LogicalPlan::CreateExternalTable(_)
-        | LogicalPlan::DropTable(_) => {
+        | LogicalPlan::DropTable(_)
+        | LogicalPlan::SetVariable(_) => {
             // All of these plan types have no inputs / exprs so should not be called
             assert!(expr.is_empty(), "{:?} should have no exprs", plan);
             assert!(inputs.is_empty(), "{:?}  should have no inputs", plan);
@@ -349,6 +484,7 @@ pub fn expr_sub_expressions(expr: &Expr) -> Result<Vec<Expr>> {
             "Wildcard expressions are not valid in a logical query plan".to_owned(),
         )),
         Expr::GetIndexedField { expr, .. } => Ok(vec![expr.as_ref().to_owned()]),
+        Expr::Struct { args, .. } => Ok(args.clone()),
     }
 }
 
@@ -512,6 +648,10 @@ pub fn rewrite_expression(expr: &Expr, expressions: &[Expr]) -> Result<Expr> {
             expr: Box::new(expressions[0].clone()),
             key: key.clone(),
         }),
+        Expr::Struct { names, .. } => Ok(Expr::Struct {
+            names: names.clone(),
+            args: expressions.to_vec(),
+        }),
     }
 }
 
@@ -0,0 +1,440 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! AggregatePushDown optimizer rule running the partial aggregate of an
+//! aggregation over an inner join below the join, when the groups and the
+//! aggregated values all come from one side of the join
+
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use super::utils::optimize_children;
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::logical_plan::JoinType;
+use crate::physical_plan::expressions::{
+    Avg, BinaryExpr, CastExpr, Column, Count, Literal, Max, Min, Sum, TryCastExpr,
+};
+use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+use crate::physical_plan::hash_join::HashJoinExec;
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr};
+
+/// Optimizer rule pushing partial aggregates below inner joins.
+///
+/// A group of the pre-aggregated side meets every matching row of the other
+/// side, so its partial state is merged by the final aggregate exactly as
+/// many times as the join would have repeated each of its rows. This is
+/// correct for the aggregates whose states are merged with a sum, a minimum
+/// or a maximum, and for `AVG`, which divides two such sums.
+pub struct AggregatePushDown {}
+
+impl AggregatePushDown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for AggregatePushDown {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if !config.aggregate_push_down {
+            return Ok(plan);
+        }
+        let plan = optimize_children(self, plan, config)?;
+        match plan.as_any().downcast_ref::<HashAggregateExec>() {
+            Some(aggregate) if *aggregate.mode() == AggregateMode::Partial => {
+                Ok(push_below_join(aggregate)?.unwrap_or(plan))
+            }
+            _ => Ok(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "aggregate_push_down"
+    }
+}
+
+/// Returns the partial `aggregate` of a join rewritten to run below the join,
+/// or `None` if it is not eligible
+fn push_below_join(
+    aggregate: &HashAggregateExec,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let join = match aggregate.input().as_any().downcast_ref::<HashJoinExec>() {
+        Some(join) if *join.join_type() == JoinType::Inner => join,
+        _ => return Ok(None),
+    };
+    match push_to_side(aggregate, join, true)? {
+        Some(plan) => Ok(Some(plan)),
+        None => push_to_side(aggregate, join, false),
+    }
+}
+
+/// Pushes the partial `aggregate` to the left input of `join` if `left`, or
+/// to its right input otherwise.
+///
+/// The groups and the aggregated values must only reference that input, and
+/// its join keys must be group keys: the rows merged by a partial group then
+/// all join with the same rows of the other input.
+fn push_to_side(
+    aggregate: &HashAggregateExec,
+    join: &HashJoinExec,
+    left: bool,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let left_len = join.left().schema().fields().len();
+    let (input, offset) = if left {
+        (join.left(), 0)
+    } else {
+        (join.right(), left_len)
+    };
+    let input_schema = input.schema();
+    let len = input_schema.fields().len();
+
+    let group_expr = aggregate
+        .group_expr()
+        .iter()
+        .map(|(expr, name)| Some((rebind(expr, offset, len)?, name.clone())))
+        .collect::<Option<Vec<_>>>();
+    let aggr_expr = aggregate
+        .aggr_expr()
+        .iter()
+        .map(|expr| rebind_aggregate(expr, offset, len))
+        .collect::<Result<Option<Vec<_>>>>()?;
+    let (group_expr, aggr_expr) = match (group_expr, aggr_expr) {
+        (Some(group_expr), Some(aggr_expr)) => (group_expr, aggr_expr),
+        _ => return Ok(None),
+    };
+
+    let pushed = Arc::new(HashAggregateExec::try_new(
+        AggregateMode::Partial,
+        group_expr,
+        aggr_expr,
+        input.clone(),
+        input_schema,
+    )?);
+    let pushed_schema = pushed.schema();
+
+    // the join keys of the pre-aggregated side become the matching group columns
+    let mut on = Vec::with_capacity(join.on().len());
+    for (left_key, right_key) in join.on() {
+        let key = if left { left_key } else { right_key };
+        let index = pushed
+            .group_expr()
+            .iter()
+            .position(|(expr, _)| expr.as_any().downcast_ref::<Column>() == Some(key));
+        let group_key = match index {
+            Some(index) => Column::new(pushed_schema.field(index).name(), index),
+            None => return Ok(None),
+        };
+        on.push(if left {
+            (group_key, right_key.clone())
+        } else {
+            (left_key.clone(), group_key)
+        });
+    }
+
+    let (new_left, new_right, pushed_offset): (Arc<dyn ExecutionPlan>, _, _) = if left {
+        (pushed, join.right().clone(), 0)
+    } else {
+        (
+            join.left().clone(),
+            pushed as Arc<dyn ExecutionPlan>,
+            left_len,
+        )
+    };
+    let new_join = Arc::new(HashJoinExec::try_new(
+        new_left,
+        new_right,
+        on,
+        join.join_type(),
+        *join.partition_mode(),
+        join.null_equals_null(),
+    )?);
+
+    // the final aggregate expects the output of the partial aggregate
+    let expr = pushed_schema
+        .fields()
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let column: Arc<dyn PhysicalExpr> =
+                Arc::new(Column::new(field.name(), pushed_offset + i));
+            (column, field.name().clone())
+        })
+        .collect();
+    Ok(Some(Arc::new(ProjectionExec::try_new(expr, new_join)?)))
+}
+
+/// Rebinds `expr`, which is evaluated against the output of a join, to the
+/// input whose columns start at `offset` and has `len` columns. Returns `None`
+/// if `expr` references columns of the other input or contains expressions
+/// that cannot be rebound.
+fn rebind(
+    expr: &Arc<dyn PhysicalExpr>,
+    offset: usize,
+    len: usize,
+) -> Option<Arc<dyn PhysicalExpr>> {
+    let expr_any = expr.as_any();
+    if let Some(column) = expr_any.downcast_ref::<Column>() {
+        let index = column.index().checked_sub(offset).filter(|i| *i < len)?;
+        Some(Arc::new(Column::new(column.name(), index)))
+    } else if expr_any.is::<Literal>() {
+        Some(expr.clone())
+    } else if let Some(cast) = expr_any.downcast_ref::<CastExpr>() {
+        Some(Arc::new(CastExpr::new_with_overflow(
+            rebind(cast.expr(), offset, len)?,
+            cast.cast_type().clone(),
+            cast.cast_options().clone(),
+            cast.overflow(),
+        )))
+    } else if let Some(cast) = expr_any.downcast_ref::<TryCastExpr>() {
+        Some(Arc::new(TryCastExpr::new(
+            rebind(cast.expr(), offset, len)?,
+            cast.cast_type().clone(),
+        )))
+    } else if let Some(binary) = expr_any.downcast_ref::<BinaryExpr>() {
+        Some(Arc::new(BinaryExpr::new_with_overflow(
+            rebind(binary.left(), offset, len)?,
+            *binary.op(),
+            rebind(binary.right(), offset, len)?,
+            binary.overflow(),
+        )))
+    } else {
+        None
+    }
+}
+
+/// Rebinds the argument of `expr` like [`rebind`]. Returns `None` if the
+/// aggregate cannot be pushed below a join.
+fn rebind_aggregate(
+    expr: &Arc<dyn AggregateExpr>,
+    offset: usize,
+    len: usize,
+) -> Result<Option<Arc<dyn AggregateExpr>>> {
+    let arguments = expr.expressions();
+    let argument = match arguments.as_slice() {
+        [argument] => rebind(argument, offset, len),
+        _ => None,
+    };
+    let argument = match argument {
+        Some(argument) => argument,
+        None => return Ok(None),
+    };
+    let name = expr.name();
+    let data_type = expr.field()?.data_type().clone();
+
+    let expr_any = expr.as_any();
+    let sum = expr_any.downcast_ref::<Sum>();
+    let rebound: Arc<dyn AggregateExpr> = if let Some(sum) = sum {
+        Arc::new(Sum::new_with_overflow(
+            argument,
+            name,
+            data_type,
+            sum.overflow(),
+        ))
+    } else if expr_any.is::<Count>() {
+        Arc::new(Count::new(argument, name, data_type))
+    } else if expr_any.is::<Min>() {
+        Arc::new(Min::new(argument, name, data_type))
+    } else if expr_any.is::<Max>() {
+        Arc::new(Max::new(argument, name, data_type))
+    } else if expr_any.is::<Avg>() {
+        Arc::new(Avg::new(argument, name, data_type))
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(rebound))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, Int64Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+
+    use crate::assert_batches_sorted_eq;
+    use crate::datasource::MemTable;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::collect;
+
+    /// `fact` has several rows per key, `dim` repeats the key 2 and misses
+    /// the key 4
+    fn context(config: ExecutionConfig) -> Result<ExecutionContext> {
+        let mut ctx = ExecutionContext::with_config(config);
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int32, false),
+            Field::new("v", DataType::Int64, true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 1, 3, 2, 4, 1])),
+                Arc::new(Int64Array::from(vec![
+                    Some(10),
+                    Some(20),
+                    Some(30),
+                    None,
+                    Some(50),
+                    Some(60),
+                    Some(50),
+                ])),
+            ],
+        )?;
+        let table = MemTable::try_new(
+            schema,
+            vec![vec![batch.slice(0, 4)], vec![batch.slice(4, 3)]],
+        )?;
+        ctx.register_table("fact", Arc::new(table))?;
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int32, false),
+            Field::new("name", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 2, 3])),
+                Arc::new(StringArray::from(vec!["a", "b", "c", "a"])),
+            ],
+        )?;
+        let table = MemTable::try_new(schema, vec![vec![batch]])?;
+        ctx.register_table("dim", Arc::new(table))?;
+        Ok(ctx)
+    }
+
+    async fn plan(
+        ctx: &mut ExecutionContext,
+        sql: &str,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        ctx.create_physical_plan(&plan).await
+    }
+
+    /// Returns true if a join of `plan` has a partial aggregate as input
+    fn aggregate_pushed(plan: &Arc<dyn ExecutionPlan>) -> bool {
+        let pushed = match plan.as_any().downcast_ref::<HashJoinExec>() {
+            Some(join) => [join.left(), join.right()].iter().any(|input| {
+                input
+                    .as_any()
+                    .downcast_ref::<HashAggregateExec>()
+                    .map(|aggregate| *aggregate.mode() == AggregateMode::Partial)
+                    .unwrap_or(false)
+            }),
+            None => false,
+        };
+        pushed || plan.children().iter().any(aggregate_pushed)
+    }
+
+    /// Checks whether the aggregate of `sql` is pushed below the join, with
+    /// each partitioning of the join, and that the results are unchanged
+    async fn assert_push_down(
+        sql: &str,
+        pushed: bool,
+        expected: Vec<&str>,
+    ) -> Result<()> {
+        for target_partitions in [1, 4] {
+            let config = ExecutionConfig::new().with_target_partitions(target_partitions);
+            let mut ctx = context(config.clone())?;
+            let optimized = plan(&mut ctx, sql).await?;
+            assert_eq!(aggregate_pushed(&optimized), pushed, "{}", sql);
+            assert_batches_sorted_eq!(
+                expected,
+                &collect(optimized, ctx.task_ctx()).await?
+            );
+
+            let mut ctx = context(config.with_aggregate_push_down(false))?;
+            let unoptimized = plan(&mut ctx, sql).await?;
+            assert!(!aggregate_pushed(&unoptimized));
+            assert_batches_sorted_eq!(
+                expected,
+                &collect(unoptimized, ctx.task_ctx()).await?
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn push_down_to_left() -> Result<()> {
+        let sql = "SELECT fact.k, SUM(v), COUNT(v), MIN(v), MAX(v), AVG(v) \
+                   FROM fact JOIN dim ON fact.k = dim.k GROUP BY fact.k";
+        let expected = vec![
+            "+---+-------------+---------------+-------------+-------------+-------------+",
+            "| k | SUM(fact.v) | COUNT(fact.v) | MIN(fact.v) | MAX(fact.v) | AVG(fact.v) |",
+            "+---+-------------+---------------+-------------+-------------+-------------+",
+            "| 1 | 90          | 3             | 10          | 50          | 30          |",
+            "| 2 | 140         | 4             | 20          | 50          | 35          |",
+            "| 3 |             | 0             |             |             |             |",
+            "+---+-------------+---------------+-------------+-------------+-------------+",
+        ];
+        assert_push_down(sql, true, expected).await
+    }
+
+    #[tokio::test]
+    async fn push_down_to_right() -> Result<()> {
+        let sql = "SELECT dim.k, COUNT(*), MAX(name) \
+                   FROM fact JOIN dim ON fact.k = dim.k GROUP BY dim.k";
+        let expected = vec![
+            "+---+-----------------+---------------+",
+            "| k | COUNT(UInt8(1)) | MAX(dim.name) |",
+            "+---+-----------------+---------------+",
+            "| 1 | 3               | a             |",
+            "| 2 | 4               | c             |",
+            "| 3 | 1               | a             |",
+            "+---+-----------------+---------------+",
+        ];
+        assert_push_down(sql, true, expected).await
+    }
+
+    #[tokio::test]
+    async fn groups_from_both_sides_not_pushed() -> Result<()> {
+        let sql =
+            "SELECT name, SUM(v) FROM fact JOIN dim ON fact.k = dim.k GROUP BY name";
+        let expected = vec![
+            "+------+-------------+",
+            "| name | SUM(fact.v) |",
+            "+------+-------------+",
+            "| a    | 90          |",
+            "| b    | 70          |",
+            "| c    | 70          |",
+            "+------+-------------+",
+        ];
+        assert_push_down(sql, false, expected).await
+    }
+
+    #[tokio::test]
+    async fn join_key_not_grouped_not_pushed() -> Result<()> {
+        let sql = "SELECT v, COUNT(*) FROM fact JOIN dim ON fact.k = dim.k GROUP BY v";
+        let expected = vec![
+            "+----+-----------------+",
+            "| v  | COUNT(UInt8(1)) |",
+            "+----+-----------------+",
+            "|    | 1               |",
+            "| 10 | 1               |",
+            "| 20 | 2               |",
+            "| 30 | 1               |",
+            "| 50 | 3               |",
+            "+----+-----------------+",
+        ];
+        assert_push_down(sql, false, expected).await
+    }
+}
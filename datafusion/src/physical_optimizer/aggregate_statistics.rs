@@ -258,6 +258,7 @@ mod tests {
     use arrow::record_batch::RecordBatch;
 
     use crate::error::Result;
+    use crate::execution::context::ExecutionContext;
     use crate::logical_plan::Operator;
     use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
     use crate::physical_plan::common;
@@ -293,6 +294,7 @@ mod tests {
         plan: HashAggregateExec,
         nulls: bool,
     ) -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let conf = ExecutionConfig::new();
         let optimized = AggregateStatistics::new().optimize(Arc::new(plan), &conf)?;
 
@@ -303,7 +305,7 @@ mod tests {
 
         // A ProjectionExec is a sign that the count optimization was applied
         assert!(optimized.as_any().is::<ProjectionExec>());
-        let result = common::collect(optimized.execute(0).await?).await?;
+        let result = common::collect(optimized.execute(0, task_ctx).await?).await?;
         assert_eq!(result[0].schema(), Arc::new(Schema::new(vec![col])));
         assert_eq!(
             result[0]
@@ -56,7 +56,10 @@ impl PhysicalOptimizerRule for CoalesceBatches {
         // or having an API so that operators can declare when their inputs or outputs
         // need to be wrapped in a coalesce batches operator.
         // See https://issues.apache.org/jira/browse/ARROW-11068
-        let wrap_in_coalesce = plan_any.downcast_ref::<FilterExec>().is_some()
+        // the filters combine the rows they select themselves if
+        // `coalesce_filter_output` is enabled
+        let wrap_in_coalesce = (plan_any.downcast_ref::<FilterExec>().is_some()
+            && !config.coalesce_filter_output)
             || plan_any.downcast_ref::<HashJoinExec>().is_some()
             || plan_any.downcast_ref::<RepartitionExec>().is_some();
 
@@ -75,7 +78,10 @@ impl PhysicalOptimizerRule for CoalesceBatches {
                 // implemented. For now, we choose half the configured batch size to avoid copies
                 // when a small number of rows are removed from a batch
                 let target_batch_size = config.batch_size / 2;
-                Arc::new(CoalesceBatchesExec::new(plan.clone(), target_batch_size))
+                Arc::new(
+                    CoalesceBatchesExec::new(plan.clone(), target_batch_size)
+                        .with_target_batch_bytes(config.coalesce_batch_bytes),
+                )
             } else {
                 plan.clone()
             })
@@ -0,0 +1,245 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Connects hash joins to the Parquet scans of their probe sides, which
+//! prune their row groups with the ranges of the keys of the build sides
+
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::logical_plan::JoinType;
+use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use crate::physical_plan::dynamic_filter::DynamicFilterSource;
+use crate::physical_plan::file_format::ParquetExec;
+use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use crate::physical_plan::repartition::RepartitionExec;
+use crate::physical_plan::ExecutionPlan;
+
+use super::optimizer::PhysicalOptimizerRule;
+use super::utils::optimize_children;
+
+/// DynamicFilterPushDown shares a [`DynamicFilterSource`] between a hash
+/// join in [`PartitionMode::CollectLeft`] and the Parquet scan of its probe
+/// side: once the join has collected its build side, the scan skips the row
+/// groups whose keys are out of the ranges of the keys of the build side.
+///
+/// The scan must be reached from the join through operators keeping the
+/// columns of their input, and the keys must be columns of the files of the
+/// scan, of the same types on both sides. Only the joins not outputting the
+/// unmatched rows of their probe side are connected.
+pub struct DynamicFilterPushDown {}
+
+impl DynamicFilterPushDown {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// Whether the unmatched rows of the probe side of a join of type
+/// `join_type` are dropped
+fn drops_unmatched_probe_rows(join_type: JoinType) -> bool {
+    match join_type {
+        JoinType::Inner | JoinType::Left | JoinType::Semi | JoinType::Anti => true,
+        JoinType::Right | JoinType::Full => false,
+    }
+}
+
+/// Connect the Parquet scan found under `plan` to `source`, returning the
+/// rewritten plan, if there is such a scan
+fn push_down(
+    plan: &Arc<dyn ExecutionPlan>,
+    source: &DynamicFilterSource,
+) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+    let any = plan.as_any();
+    if let Some(scan) = any.downcast_ref::<ParquetExec>() {
+        let file_schema = &scan.base_config().file_schema;
+        let in_files = source
+            .columns()
+            .iter()
+            .all(|name| file_schema.field_with_name(name).is_ok());
+        if !in_files || scan.dynamic_filter().is_some() {
+            return Ok(None);
+        }
+        let scan = scan.clone().with_dynamic_filter(source.clone());
+        return Ok(Some(Arc::new(scan)));
+    }
+    // these operators keep the columns of their input
+    if any.is::<FilterExec>()
+        || any.is::<CoalesceBatchesExec>()
+        || any.is::<RepartitionExec>()
+    {
+        let input = &plan.children()[0];
+        return match push_down(input, source)? {
+            Some(input) => Ok(Some(plan.with_new_children(vec![input])?)),
+            None => Ok(None),
+        };
+    }
+    Ok(None)
+}
+
+impl PhysicalOptimizerRule for DynamicFilterPushDown {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        execution_config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = optimize_children(self, plan, execution_config)?;
+        let hash_join = match plan.as_any().downcast_ref::<HashJoinExec>() {
+            // the filter would drop the probe rows with a null key, which
+            // decide the output of a null-aware join
+            Some(hash_join)
+                if *hash_join.partition_mode() == PartitionMode::CollectLeft
+                    && drops_unmatched_probe_rows(*hash_join.join_type())
+                    && !hash_join.null_aware()
+                    && hash_join.dynamic_filter().is_none() =>
+            {
+                hash_join
+            }
+            _ => return Ok(plan),
+        };
+        let (left_schema, right_schema) =
+            (hash_join.left().schema(), hash_join.right().schema());
+        let same_types = hash_join.on().iter().all(|(l, r)| {
+            left_schema.field(l.index()).data_type()
+                == right_schema.field(r.index()).data_type()
+        });
+        if !same_types {
+            return Ok(plan);
+        }
+
+        let columns = hash_join
+            .on()
+            .iter()
+            .map(|(_, r)| r.name().to_owned())
+            .collect();
+        let source = DynamicFilterSource::new(columns);
+        match push_down(hash_join.right(), &source)? {
+            Some(right) => {
+                let new_join = HashJoinExec::try_new(
+                    Arc::clone(hash_join.left()),
+                    right,
+                    hash_join.on().to_vec(),
+                    hash_join.join_type(),
+                    *hash_join.partition_mode(),
+                    hash_join.null_equals_null(),
+                )?
+                .with_dynamic_filter(source);
+                Ok(Arc::new(new_join))
+            }
+            None => Ok(plan),
+        }
+    }
+
+    fn name(&self) -> &str {
+        "dynamic_filter_push_down"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::file_format::{parquet::ParquetFormat, FileFormat};
+    use crate::datasource::object_store::local::{
+        local_object_reader_stream, local_unpartitioned_file, LocalFileSystem,
+    };
+    use crate::physical_plan::expressions::Column;
+    use crate::physical_plan::file_format::PhysicalPlanConfig;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::Statistics;
+    use crate::test::build_table_i32;
+
+    async fn parquet_scan() -> Result<Arc<dyn ExecutionPlan>> {
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        Ok(Arc::new(ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_groups: vec![vec![local_unpartitioned_file(filename.clone())]],
+                file_schema: ParquetFormat::default()
+                    .infer_schema(local_object_reader_stream(vec![filename]))
+                    .await?,
+                statistics: Statistics::default(),
+                projection: Some(vec![0, 1]),
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            None,
+        )))
+    }
+
+    async fn optimized_join(join_type: JoinType) -> Result<Arc<dyn ExecutionPlan>> {
+        let batch =
+            build_table_i32(("id", &vec![1, 2]), ("b", &vec![3, 4]), ("c", &vec![5, 6]));
+        let left = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let right = parquet_scan().await?;
+        let on = vec![(
+            Column::new_with_schema("id", &left.schema())?,
+            Column::new_with_schema("id", &right.schema())?,
+        )];
+        let join = HashJoinExec::try_new(
+            left,
+            Arc::new(CoalesceBatchesExec::new(right, 4096)),
+            on,
+            &join_type,
+            PartitionMode::CollectLeft,
+            &false,
+        )?;
+        DynamicFilterPushDown::new().optimize(Arc::new(join), &ExecutionConfig::new())
+    }
+
+    /// The sources of the join `plan` and of the scan of its probe side
+    fn sources(
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> (Option<DynamicFilterSource>, Option<DynamicFilterSource>) {
+        let join = plan.as_any().downcast_ref::<HashJoinExec>().unwrap();
+        let scan = join.right().children()[0].clone();
+        let scan = scan.as_any().downcast_ref::<ParquetExec>().unwrap();
+        (
+            join.dynamic_filter().cloned(),
+            scan.dynamic_filter().cloned(),
+        )
+    }
+
+    #[tokio::test]
+    async fn connect_join_to_probe_scan() -> Result<()> {
+        let plan = optimized_join(JoinType::Inner).await?;
+        let (join_source, scan_source) = sources(&plan);
+        let (join_source, scan_source) = (join_source.unwrap(), scan_source.unwrap());
+        assert_eq!(join_source.columns(), &["id".to_owned()]);
+
+        // the join and the scan share the filter
+        let keys = vec![Arc::new(arrow::array::Int32Array::from(vec![1, 2])) as _];
+        join_source.publish(&keys);
+        assert!(scan_source.filter().is_some());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn keep_unmatched_probe_rows() -> Result<()> {
+        let plan = optimized_join(JoinType::Right).await?;
+        assert!(matches!(sources(&plan), (None, None)));
+        Ok(())
+    }
+}
@@ -0,0 +1,403 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! EliminateRedundant optimizer rule removing operators that do not change
+//! the output of their input: identity projections, limits above a smaller
+//! limit, sorts above a finer sort and filters whose predicate is `true`
+
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use super::utils::{optimize_children, ordering_satisfies};
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::physical_plan::expressions::{Column, Literal, PhysicalSortExpr};
+use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::{ExecutionPlan, PhysicalExpr};
+use crate::scalar::ScalarValue;
+
+/// Optimizer rule removing the operators that are no-ops
+pub struct EliminateRedundant {}
+
+impl EliminateRedundant {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl PhysicalOptimizerRule for EliminateRedundant {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // remove the redundant operators bottom-up, so that a chain of no-op
+        // operators collapses in one pass
+        let plan = optimize_children(self, plan, config)?;
+        Ok(without_redundant_root(&plan).unwrap_or(plan))
+    }
+
+    fn name(&self) -> &str {
+        "eliminate_redundant"
+    }
+}
+
+/// Returns the input of `plan` if `plan` produces the same output as its
+/// input, or `None` if it must be kept
+fn without_redundant_root(
+    plan: &Arc<dyn ExecutionPlan>,
+) -> Option<Arc<dyn ExecutionPlan>> {
+    let plan_any = plan.as_any();
+    let redundant_input =
+        if let Some(projection) = plan_any.downcast_ref::<ProjectionExec>() {
+            Some(projection.input()).filter(|_| is_identity_projection(projection))
+        } else if let Some(limit) = plan_any.downcast_ref::<GlobalLimitExec>() {
+            Some(limit.input()).filter(|input| limited_to(input, limit.limit()))
+        } else if let Some(limit) = plan_any.downcast_ref::<LocalLimitExec>() {
+            Some(limit.input()).filter(|input| limited_to(input, limit.limit()))
+        } else if let Some(sort) = plan_any.downcast_ref::<SortExec>() {
+            Some(sort.input()).filter(|input| is_sorted_by(input, sort.expr(), plan))
+        } else if let Some(filter) = plan_any.downcast_ref::<FilterExec>() {
+            Some(filter.input()).filter(|_| is_true_literal(filter.predicate()))
+        } else {
+            None
+        };
+    redundant_input.cloned()
+}
+
+/// A projection is an identity if it selects every column of its input, in
+/// order, under the same names. An alias changes the output schema, so the
+/// projection is kept even if it only renames columns.
+fn is_identity_projection(projection: &ProjectionExec) -> bool {
+    let input_schema = projection.input().schema();
+    projection.expr().len() == input_schema.fields().len()
+        && projection
+            .expr()
+            .iter()
+            .enumerate()
+            .all(
+                |(i, (expr, alias))| match expr.as_any().downcast_ref::<Column>() {
+                    Some(column) => {
+                        column.index() == i
+                            && column.name() == alias
+                            && input_schema.field(i).name() == alias
+                    }
+                    None => false,
+                },
+            )
+        && projection.schema() == input_schema
+}
+
+/// Returns true if `plan` is a limit producing at most `limit` rows in each
+/// partition
+fn limited_to(plan: &Arc<dyn ExecutionPlan>, limit: usize) -> bool {
+    let plan_any = plan.as_any();
+    if let Some(input_limit) = plan_any.downcast_ref::<GlobalLimitExec>() {
+        input_limit.limit() <= limit
+    } else if let Some(input_limit) = plan_any.downcast_ref::<LocalLimitExec>() {
+        input_limit.limit() <= limit
+    } else {
+        false
+    }
+}
+
+/// Returns true if `input` is a sort whose ordering starts with `expr` and
+/// whose output is partitioned like the output of `sort`.
+///
+/// Only column sort keys are compared: two evaluations of the same
+/// expression, such as `random()`, may not produce the same values.
+fn is_sorted_by(
+    input: &Arc<dyn ExecutionPlan>,
+    expr: &[PhysicalSortExpr],
+    sort: &Arc<dyn ExecutionPlan>,
+) -> bool {
+    let input_sort = match input.as_any().downcast_ref::<SortExec>() {
+        Some(input_sort) => input_sort,
+        None => return false,
+    };
+    input.output_partitioning().partition_count()
+        == sort.output_partitioning().partition_count()
+        && ordering_satisfies(input_sort.expr(), expr)
+}
+
+fn is_true_literal(expr: &Arc<dyn PhysicalExpr>) -> bool {
+    match expr.as_any().downcast_ref::<Literal>() {
+        Some(literal) => literal.value() == &ScalarValue::Boolean(Some(true)),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+
+    use crate::assert_batches_eq;
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::Operator;
+    use crate::physical_plan::expressions::{binary, col, lit};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, displayable};
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]))
+    }
+
+    fn memory_exec() -> Result<Arc<dyn ExecutionPlan>> {
+        let batch = RecordBatch::try_new(
+            schema(),
+            vec![
+                Arc::new(Int32Array::from(vec![2, 1, 2, 1])),
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            ],
+        )?;
+        Ok(Arc::new(MemoryExec::try_new(
+            &[vec![batch]],
+            schema(),
+            None,
+        )?))
+    }
+
+    fn projection(
+        exprs: Vec<(&str, &str)>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = input.schema();
+        let exprs = exprs
+            .into_iter()
+            .map(|(name, alias)| Ok((col(name, &schema)?, alias.to_string())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(exprs, input)?))
+    }
+
+    fn sort(
+        columns: Vec<&str>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = input.schema();
+        let expr = columns
+            .into_iter()
+            .map(|name| {
+                Ok(PhysicalSortExpr {
+                    expr: col(name, &schema)?,
+                    options: SortOptions::default(),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(SortExec::try_new(expr, input)?))
+    }
+
+    fn filter(
+        predicate: Arc<dyn PhysicalExpr>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Ok(Arc::new(FilterExec::try_new(predicate, input)?))
+    }
+
+    fn optimize(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        EliminateRedundant::new().optimize(plan, &ExecutionConfig::new())
+    }
+
+    fn assert_optimized(plan: Arc<dyn ExecutionPlan>, expected: &str) -> Result<()> {
+        let optimized = optimize(plan)?;
+        let actual = format!("{}", displayable(optimized.as_ref()).indent());
+        assert_eq!(expected, actual.trim());
+        Ok(())
+    }
+
+    #[test]
+    fn identity_projection_removed() -> Result<()> {
+        let plan = projection(vec![("a", "a"), ("b", "b")], memory_exec()?)?;
+        assert_optimized(plan, "MemoryExec: partitions=1, partition_sizes=[1]")
+    }
+
+    #[test]
+    fn alias_projection_kept() -> Result<()> {
+        let plan = projection(vec![("a", "x"), ("b", "b")], memory_exec()?)?;
+        assert_optimized(
+            plan,
+            "ProjectionExec: expr=[a@0 as x, b@1 as b]\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn reordering_projection_kept() -> Result<()> {
+        let plan = projection(vec![("b", "b"), ("a", "a")], memory_exec()?)?;
+        assert_optimized(
+            plan,
+            "ProjectionExec: expr=[b@1 as b, a@0 as a]\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )?;
+
+        let plan = projection(vec![("a", "a")], memory_exec()?)?;
+        assert_optimized(
+            plan,
+            "ProjectionExec: expr=[a@0 as a]\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn larger_outer_limit_removed() -> Result<()> {
+        let inner = Arc::new(GlobalLimitExec::new(memory_exec()?, 2));
+        let plan = Arc::new(GlobalLimitExec::new(inner, 10));
+        assert_optimized(
+            plan,
+            "GlobalLimitExec: limit=2\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )?;
+
+        // the planner inserts a local limit below each global limit
+        let inner = Arc::new(LocalLimitExec::new(memory_exec()?, 2));
+        let inner = Arc::new(GlobalLimitExec::new(inner, 2));
+        let outer = Arc::new(LocalLimitExec::new(inner, 10));
+        let plan = Arc::new(GlobalLimitExec::new(outer, 10));
+        assert_optimized(
+            plan,
+            "GlobalLimitExec: limit=2\
+            \n  LocalLimitExec: limit=2\
+            \n    MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn smaller_outer_limit_kept() -> Result<()> {
+        let inner = Arc::new(GlobalLimitExec::new(memory_exec()?, 10));
+        let plan = Arc::new(GlobalLimitExec::new(inner, 2));
+        assert_optimized(
+            plan,
+            "GlobalLimitExec: limit=2\
+            \n  GlobalLimitExec: limit=10\
+            \n    MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn prefix_sort_removed() -> Result<()> {
+        let plan = sort(vec!["a"], sort(vec!["a", "b"], memory_exec()?)?)?;
+        assert_optimized(
+            plan,
+            "SortExec: [a@0 ASC NULLS LAST,b@1 ASC NULLS LAST]\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn unsatisfied_sort_kept() -> Result<()> {
+        let plan = sort(vec!["a", "b"], sort(vec!["a"], memory_exec()?)?)?;
+        assert_optimized(
+            plan,
+            "SortExec: [a@0 ASC NULLS LAST,b@1 ASC NULLS LAST], sorted_prefix=1\
+            \n  SortExec: [a@0 ASC NULLS LAST]\
+            \n    MemoryExec: partitions=1, partition_sizes=[1]",
+        )?;
+
+        let plan = sort(vec!["b"], sort(vec!["a", "b"], memory_exec()?)?)?;
+        assert_optimized(
+            plan,
+            "SortExec: [b@1 ASC NULLS LAST]\
+            \n  SortExec: [a@0 ASC NULLS LAST,b@1 ASC NULLS LAST]\
+            \n    MemoryExec: partitions=1, partition_sizes=[1]",
+        )?;
+
+        // the direction of the sort keys must match
+        let input = sort(vec!["a", "b"], memory_exec()?)?;
+        let plan = Arc::new(SortExec::try_new(
+            vec![PhysicalSortExpr {
+                expr: col("a", &schema())?,
+                options: SortOptions {
+                    descending: true,
+                    nulls_first: true,
+                },
+            }],
+            input,
+        )?);
+        assert_optimized(
+            plan,
+            "SortExec: [a@0 DESC]\
+            \n  SortExec: [a@0 ASC NULLS LAST,b@1 ASC NULLS LAST]\
+            \n    MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn true_filter_removed() -> Result<()> {
+        let plan = filter(lit(ScalarValue::Boolean(Some(true))), memory_exec()?)?;
+        assert_optimized(plan, "MemoryExec: partitions=1, partition_sizes=[1]")?;
+
+        let predicate = binary(
+            col("a", &schema())?,
+            Operator::Gt,
+            lit(ScalarValue::Int32(Some(1))),
+            &schema(),
+        )?;
+        let plan = filter(predicate, memory_exec()?)?;
+        assert_optimized(
+            plan,
+            "FilterExec: a@0 > 1\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[test]
+    fn nested_redundant_operators_removed() -> Result<()> {
+        let plan = projection(vec![("a", "a"), ("b", "b")], memory_exec()?)?;
+        let plan = filter(lit(ScalarValue::Boolean(Some(true))), plan)?;
+        let plan = sort(vec!["a", "b"], plan)?;
+        let plan = projection(vec![("a", "a"), ("b", "b")], plan)?;
+        let plan = sort(vec!["a"], plan)?;
+        assert_optimized(
+            plan,
+            "SortExec: [a@0 ASC NULLS LAST,b@1 ASC NULLS LAST]\
+            \n  MemoryExec: partitions=1, partition_sizes=[1]",
+        )
+    }
+
+    #[tokio::test]
+    async fn results_unchanged() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let plan = projection(vec![("a", "a"), ("b", "b")], memory_exec()?)?;
+        let plan = sort(vec!["a", "b"], plan)?;
+        let plan = sort(vec!["a"], plan)?;
+        let plan = Arc::new(GlobalLimitExec::new(plan, 3));
+        let plan = Arc::new(GlobalLimitExec::new(plan, 10));
+
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 | 2 |",
+            "| 1 | 4 |",
+            "| 2 | 1 |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &collect(plan.clone(), task_ctx.clone()).await?);
+        assert_batches_eq!(expected, &collect(optimize(plan)?, task_ctx).await?);
+        Ok(())
+    }
+}
@@ -0,0 +1,429 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! MergeSelection optimizer rule choosing, for every merge of partitions,
+//! between a `CoalescePartitionsExec` and a `SortPreservingMergeExec`
+//! depending on whether the order of the merged rows matters downstream
+
+use std::sync::Arc;
+
+use super::optimizer::PhysicalOptimizerRule;
+use super::utils::ordering_satisfies;
+use crate::error::Result;
+use crate::execution::context::ExecutionConfig;
+use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::filter::FilterExec;
+use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
+use crate::physical_plan::ExecutionPlan;
+
+/// Optimizer rule merging sorted partitions with a `SortPreservingMergeExec`
+/// where a sort order is required above the merge, and merging them with a
+/// cheaper `CoalescePartitionsExec` where it is not.
+///
+/// A `SortExec` above a merge of partitions that are already sorted by its
+/// sort keys is removed.
+pub struct MergeSelection {}
+
+impl MergeSelection {
+    #[allow(missing_docs)]
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+/// What the operators above a plan expect of the order of its output
+#[derive(Clone, Copy)]
+struct OrderRequirement<'a> {
+    /// The sort order an operator above relies on or could take advantage of
+    ordering: Option<&'a [PhysicalSortExpr]>,
+    /// Whether the order of the rows must be kept, because it is visible in
+    /// the output of the query or an operator above relies on it
+    needed: bool,
+}
+
+impl PhysicalOptimizerRule for MergeSelection {
+    fn optimize(
+        &self,
+        plan: Arc<dyn ExecutionPlan>,
+        config: &ExecutionConfig,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        // the order of the output of the query is visible to the user
+        let requirement = OrderRequirement {
+            ordering: plan.output_ordering(),
+            needed: true,
+        };
+        select_merges(&plan, requirement, config.batch_size)
+    }
+
+    fn name(&self) -> &str {
+        "merge_selection"
+    }
+}
+
+fn select_merges(
+    plan: &Arc<dyn ExecutionPlan>,
+    requirement: OrderRequirement,
+    batch_size: usize,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let plan_any = plan.as_any();
+    if let Some(coalesce) = plan_any.downcast_ref::<CoalescePartitionsExec>() {
+        let input = coalesce.input();
+        if let (Some(required), Some(provided)) =
+            (requirement.ordering, input.output_ordering())
+        {
+            if ordering_satisfies(provided, required) {
+                let ordering = provided.to_vec();
+                let input = select_merges(input, ordered(&ordering), batch_size)?;
+                return Ok(Arc::new(SortPreservingMergeExec::new(
+                    ordering, input, batch_size,
+                )));
+            }
+        }
+        let input = select_merges(input, unordered(), batch_size)?;
+        return plan.with_new_children(vec![input]);
+    }
+
+    if let Some(merge) = plan_any.downcast_ref::<SortPreservingMergeExec>() {
+        let satisfied = requirement
+            .ordering
+            .map(|required| ordering_satisfies(merge.expr(), required))
+            .unwrap_or(false);
+        if !satisfied && !requirement.needed {
+            let input = select_merges(merge.input(), unordered(), batch_size)?;
+            return Ok(Arc::new(CoalescePartitionsExec::new(input)));
+        }
+        let input = select_merges(merge.input(), ordered(merge.expr()), batch_size)?;
+        return plan.with_new_children(vec![input]);
+    }
+
+    if let Some(sort) = plan_any.downcast_ref::<SortExec>() {
+        // the sort discards the order of its input, which is only useful if
+        // it makes the sort itself unnecessary
+        let requirement = OrderRequirement {
+            ordering: Some(sort.expr()),
+            needed: false,
+        };
+        let input = select_merges(sort.input(), requirement, batch_size)?;
+        let sorted = input
+            .output_ordering()
+            .map(|provided| ordering_satisfies(provided, sort.expr()))
+            .unwrap_or(false);
+        let partitioning_kept = sort.preserve_partitioning()
+            || input.output_partitioning().partition_count() == 1;
+        if sorted && partitioning_kept {
+            return Ok(input);
+        }
+        return plan.with_new_children(vec![input]);
+    }
+
+    let requirement = match plan.required_input_ordering() {
+        Some(ordering) => ordered(ordering),
+        None if maintains_input_order(plan) => requirement,
+        // the projection keeps the order of the rows, but its sort keys
+        // would refer to the columns of its output rather than its input
+        None if plan_any.is::<ProjectionExec>() => OrderRequirement {
+            ordering: None,
+            needed: requirement.needed,
+        },
+        None => unordered(),
+    };
+    let children = plan
+        .children()
+        .iter()
+        .map(|child| select_merges(child, requirement, batch_size))
+        .collect::<Result<Vec<_>>>()?;
+    if children.is_empty() {
+        Ok(Arc::clone(plan))
+    } else {
+        plan.with_new_children(children)
+    }
+}
+
+/// Requirement of the input of an operator relying on `ordering`
+fn ordered(ordering: &[PhysicalSortExpr]) -> OrderRequirement {
+    OrderRequirement {
+        ordering: Some(ordering),
+        needed: true,
+    }
+}
+
+/// Requirement of the input of an operator ignoring the order of its input
+fn unordered<'a>() -> OrderRequirement<'a> {
+    OrderRequirement {
+        ordering: None,
+        needed: false,
+    }
+}
+
+/// Returns true if `plan` outputs the rows of its input in the same order,
+/// with the same columns
+fn maintains_input_order(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let plan_any = plan.as_any();
+    plan_any.is::<FilterExec>()
+        || plan_any.is::<CoalesceBatchesExec>()
+        || plan_any.is::<LocalLimitExec>()
+        || plan_any.is::<GlobalLimitExec>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+    use arrow::record_batch::RecordBatch;
+    use tokio::sync::mpsc;
+
+    use crate::assert_batches_eq;
+    use crate::datasource::streaming::StreamingTable;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::expressions::{col, Column};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, displayable};
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]))
+    }
+
+    fn batch(a: Vec<i32>, b: Vec<i32>) -> Result<RecordBatch> {
+        Ok(RecordBatch::try_new(
+            schema(),
+            vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+        )?)
+    }
+
+    fn sort_exprs(
+        columns: Vec<&str>,
+        schema: &SchemaRef,
+    ) -> Result<Vec<PhysicalSortExpr>> {
+        columns
+            .into_iter()
+            .map(|name| {
+                Ok(PhysicalSortExpr {
+                    expr: col(name, schema)?,
+                    options: SortOptions::default(),
+                })
+            })
+            .collect()
+    }
+
+    /// Two partitions of a memory table, each sorted by `a`, sorted within
+    /// each partition by a `SortExec` preserving the partitioning
+    fn sorted_partitions() -> Result<Arc<dyn ExecutionPlan>> {
+        let partitions = vec![
+            vec![batch(vec![1, 3, 5], vec![6, 4, 2])?],
+            vec![batch(vec![2, 4, 6], vec![5, 3, 1])?],
+        ];
+        let memory = Arc::new(MemoryExec::try_new(&partitions, schema(), None)?);
+        Ok(Arc::new(SortExec::new_with_partitioning(
+            sort_exprs(vec!["a"], &schema())?,
+            memory,
+            true,
+        )))
+    }
+
+    fn sort(
+        columns: Vec<&str>,
+        input: Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let expr = sort_exprs(columns, &input.schema())?;
+        Ok(Arc::new(SortExec::try_new(expr, input)?))
+    }
+
+    fn optimize(plan: Arc<dyn ExecutionPlan>) -> Result<Arc<dyn ExecutionPlan>> {
+        MergeSelection::new().optimize(plan, &ExecutionConfig::new())
+    }
+
+    fn assert_optimized(plan: &Arc<dyn ExecutionPlan>, expected: &str) -> Result<()> {
+        let optimized = optimize(plan.clone())?;
+        let actual = format!("{}", displayable(optimized.as_ref()).indent());
+        assert_eq!(expected, actual.trim());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sorted_partitions_merged_in_order() -> Result<()> {
+        let coalesce = Arc::new(CoalescePartitionsExec::new(sorted_partitions()?));
+        let plan = sort(vec!["a"], coalesce)?;
+        assert_optimized(
+            &plan,
+            "SortPreservingMergeExec: [a@0 ASC]\
+             \n  SortExec: [a@0 ASC]\
+             \n    MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )?;
+
+        let ctx = ExecutionContext::new();
+        let optimized = optimize(plan)?;
+        let results = collect(optimized, ctx.task_ctx()).await?;
+        let expected = vec![
+            "+---+---+",
+            "| a | b |",
+            "+---+---+",
+            "| 1 | 6 |",
+            "| 2 | 5 |",
+            "| 3 | 4 |",
+            "| 4 | 3 |",
+            "| 5 | 2 |",
+            "| 6 | 1 |",
+            "+---+---+",
+        ];
+        assert_batches_eq!(expected, &results);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_kept_when_order_not_satisfied() -> Result<()> {
+        let coalesce = Arc::new(CoalescePartitionsExec::new(sorted_partitions()?));
+        let plan = sort(vec!["b"], coalesce)?;
+        assert_optimized(
+            &plan,
+            "SortExec: [b@1 ASC]\
+             \n  CoalescePartitionsExec\
+             \n    SortExec: [a@0 ASC]\
+             \n      MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )
+    }
+
+    #[test]
+    fn order_required_through_limit() -> Result<()> {
+        let coalesce = Arc::new(CoalescePartitionsExec::new(sorted_partitions()?));
+        let limit = Arc::new(GlobalLimitExec::new(coalesce, 3));
+        let plan = sort(vec!["a", "b"], limit)?;
+        assert_optimized(
+            &plan,
+            "SortExec: [a@0 ASC,b@1 ASC]\
+             \n  GlobalLimitExec: limit=3\
+             \n    CoalescePartitionsExec\
+             \n      SortExec: [a@0 ASC]\
+             \n        MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )?;
+
+        let coalesce = Arc::new(CoalescePartitionsExec::new(sorted_partitions()?));
+        let limit = Arc::new(GlobalLimitExec::new(coalesce, 3));
+        let plan = sort(vec!["a"], limit)?;
+        assert_optimized(
+            &plan,
+            "GlobalLimitExec: limit=3\
+             \n  SortPreservingMergeExec: [a@0 ASC]\
+             \n    SortExec: [a@0 ASC]\
+             \n      MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )
+    }
+
+    #[test]
+    fn unnecessary_merge_downgraded() -> Result<()> {
+        let input = sorted_partitions()?;
+        let expr = sort_exprs(vec!["a"], &input.schema())?;
+        let merge = Arc::new(SortPreservingMergeExec::new(expr, input, 1024));
+
+        // the order of the output of the query is kept
+        assert_optimized(
+            &(merge.clone() as Arc<dyn ExecutionPlan>),
+            "SortPreservingMergeExec: [a@0 ASC]\
+             \n  SortExec: [a@0 ASC]\
+             \n    MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )?;
+
+        // a sort by other keys discards the order of the merge
+        let plan = sort(vec!["b"], merge)?;
+        assert_optimized(
+            &plan,
+            "SortExec: [b@1 ASC]\
+             \n  CoalescePartitionsExec\
+             \n    SortExec: [a@0 ASC]\
+             \n      MemoryExec: partitions=2, partition_sizes=[1, 1]",
+        )
+    }
+
+    fn sorted_streaming_table(
+        senders: &mut Vec<mpsc::Sender<RecordBatch>>,
+    ) -> Result<Arc<StreamingTable>> {
+        let receivers = (0..2)
+            .map(|_| {
+                let (tx, rx) = mpsc::channel(2);
+                senders.push(tx);
+                rx
+            })
+            .collect();
+        let table = StreamingTable::try_new(schema(), receivers)?.with_ordering(vec![
+            PhysicalSortExpr {
+                expr: Arc::new(Column::new("a", 0)),
+                options: SortOptions::default(),
+            },
+        ])?;
+        Ok(Arc::new(table))
+    }
+
+    async fn explain(ctx: &ExecutionContext, sql: &str) -> Result<String> {
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        Ok(format!("{}", displayable(plan.as_ref()).indent()))
+    }
+
+    #[tokio::test]
+    async fn explain_order_by_sorted_partitions() -> Result<()> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(2),
+        );
+        let mut senders = vec![];
+        ctx.register_table("t", sorted_streaming_table(&mut senders)?)?;
+
+        let actual = explain(&ctx, "SELECT a, b FROM t ORDER BY b").await?;
+        let expected = "SortExec: [b@1 ASC]\
+             \n  CoalescePartitionsExec\
+             \n    StreamingTableExec: partitions=2, ordering=[a@0 ASC]";
+        assert_eq!(expected, actual.trim());
+
+        let actual = explain(&ctx, "SELECT a, b FROM t ORDER BY a").await?;
+        let expected = "SortPreservingMergeExec: [a@0 ASC]\
+             \n  StreamingTableExec: partitions=2, ordering=[a@0 ASC]";
+        assert_eq!(expected, actual.trim());
+
+        let df = ctx.sql("SELECT a, b FROM t ORDER BY a").await?;
+        senders[0]
+            .send(batch(vec![1, 4], vec![10, 40])?)
+            .await
+            .unwrap();
+        senders[1]
+            .send(batch(vec![2, 3], vec![20, 30])?)
+            .await
+            .unwrap();
+        senders.clear();
+        let results = df.collect().await?;
+        let expected = vec![
+            "+---+----+",
+            "| a | b  |",
+            "+---+----+",
+            "| 1 | 10 |",
+            "| 2 | 20 |",
+            "| 3 | 30 |",
+            "| 4 | 40 |",
+            "+---+----+",
+        ];
+        assert_batches_eq!(expected, &results);
+        Ok(())
+    }
+}
@@ -18,10 +18,14 @@
 //! This module contains a query optimizer that operates against a physical plan and applies
 //! rules to a physical plan, such as "Repartition".
 
+pub mod aggregate_push_down;
 pub mod aggregate_statistics;
 pub mod coalesce_batches;
+pub mod dynamic_filter_push_down;
+pub mod eliminate_redundant;
 pub mod hash_build_probe_order;
 pub mod merge_exec;
+pub mod merge_selection;
 pub mod optimizer;
 pub mod pruning;
 pub mod repartition;
@@ -24,10 +24,45 @@
 //! parquet files if it can be determined from the predicate that
 //! nothing in the row group can match.
 //!
-//! This code is currently specific to Parquet, but soon (TM), via
-//! https://github.com/apache/arrow-datafusion/issues/363 it will
-//! be genericized.
+//! The containers can be anything a [`TableProvider`] knows the
+//! statistics of, such as the files or the blocks of a custom columnar
+//! store: the provider implements [`PruningStatistics`] over them, and
+//! evaluates a [`PruningPredicate`] built from the filters passed to
+//! its `scan` to find the containers to read.
+//!
+//! ```
+//! use std::sync::Arc;
+//! use arrow::array::{ArrayRef, Int32Array};
+//! use arrow::datatypes::{DataType, Field, Schema};
+//! use datafusion::logical_plan::{col, lit, Column};
+//! use datafusion::physical_optimizer::pruning::{PruningPredicate, PruningStatistics};
+//!
+//! /// The minimum and the maximum of the column `a` of two blocks
+//! struct BlockStatistics;
+//!
+//! impl PruningStatistics for BlockStatistics {
+//!     fn min_values(&self, _column: &Column) -> Option<ArrayRef> {
+//!         Some(Arc::new(Int32Array::from(vec![0, 10])))
+//!     }
+//!
+//!     fn max_values(&self, _column: &Column) -> Option<ArrayRef> {
+//!         Some(Arc::new(Int32Array::from(vec![9, 19])))
+//!     }
+//!
+//!     fn num_containers(&self) -> usize {
+//!         2
+//!     }
+//! }
+//!
+//! let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+//! let predicate = PruningPredicate::try_new(&col("a").gt(lit(12)), schema).unwrap();
+//! // only the second block may have rows where a > 12
+//! assert_eq!(predicate.prune(&BlockStatistics).unwrap(), vec![false, true]);
+//! ```
+//!
+//! [`TableProvider`]: crate::datasource::TableProvider
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
 use std::{collections::HashSet, sync::Arc};
 
@@ -43,6 +78,7 @@ use crate::{
     logical_plan::{Column, DFSchema, Expr, Operator},
     optimizer::utils,
     physical_plan::{planner::DefaultPhysicalPlanner, ColumnarValue, PhysicalExpr},
+    scalar::ScalarValue,
 };
 
 use serde::{Deserialize, Serialize};
@@ -65,6 +101,9 @@ use serde::{Deserialize, Serialize};
 /// max_values("a") -> Some([20, Null, 30])
 /// min_values("X") -> None
 /// ```
+///
+/// The statistics it doesn't know, `None` or null, never prune a
+/// container.
 pub trait PruningStatistics {
     /// return the minimum values for the named column, if known.
     /// Note: the returned array must contain `num_containers()` rows
@@ -77,6 +116,13 @@ pub trait PruningStatistics {
     /// return the number of containers (e.g. row groups) being
     /// pruned with these statistics
     fn num_containers(&self) -> usize;
+
+    /// return the number of null values of the named column in each
+    /// container, as a `UInt64` array, if known.
+    /// Note: the returned array must contain `num_containers()` rows.
+    fn null_counts(&self, _column: &Column) -> Option<ArrayRef> {
+        None
+    }
 }
 
 /// Evaluates filter expressions on statistics in order to
@@ -274,6 +320,19 @@ impl RequiredStatColumns {
         self.stat_column_expr(column, column_expr, field, StatisticsType::Min, "min")
     }
 
+    /// rewrite col --> col_null_count, the number of nulls of col
+    fn null_count_column_expr(&mut self, column: &Column) -> Result<Expr> {
+        let field = Field::new(column.flat_name().as_str(), DataType::UInt64, true);
+        let column_expr = Expr::Column(column.clone());
+        self.stat_column_expr(
+            column,
+            &column_expr,
+            &field,
+            StatisticsType::NullCount,
+            "null_count",
+        )
+    }
+
     /// rewrite col --> col_max
     fn max_column_expr(
         &mut self,
@@ -331,6 +390,7 @@ fn build_statistics_record_batch<S: PruningStatistics>(
         let array = match statistics_type {
             StatisticsType::Min => statistics.min_values(column),
             StatisticsType::Max => statistics.max_values(column),
+            StatisticsType::NullCount => statistics.null_counts(column),
         };
         let array = array.unwrap_or_else(|| new_null_array(data_type, num_containers));
 
@@ -390,11 +450,15 @@ impl<'a> PruningExpressionBuilder<'a> {
                 }
             };
 
-        let (column_expr, correct_operator, scalar_expr) =
-            match rewrite_expr_to_prunable(column_expr, correct_operator, scalar_expr) {
-                Ok(ret) => ret,
-                Err(e) => return Err(e),
-            };
+        let (column_expr, correct_operator, scalar_expr) = match rewrite_expr_to_prunable(
+            column_expr,
+            correct_operator,
+            scalar_expr,
+            schema,
+        ) {
+            Ok(ret) => ret,
+            Err(e) => return Err(e),
+        };
         let column = columns.iter().next().unwrap().clone();
         let field = match schema.column_with_name(&column.flat_name()) {
             Some((_, f)) => f,
@@ -441,13 +505,15 @@ impl<'a> PruningExpressionBuilder<'a> {
 /// 1. `col > 10`
 /// 2. `-col > 10` should be rewritten to `col < -10`
 /// 3. `!col = true` would be rewritten to `col = !true`
-/// 4. `abs(a - 10) > 0` not supported
+/// 4. `CAST(col AS Int64) > 10` is kept as is, if `col` is numeric
+/// 5. `abs(a - 10) > 0` not supported
 ///
 /// More rewrite rules are still in progress.
 fn rewrite_expr_to_prunable(
     column_expr: &Expr,
     op: Operator,
     scalar_expr: &Expr,
+    schema: &Schema,
 ) -> Result<(Expr, Operator, Expr)> {
     if !is_compare_op(op) {
         return Err(DataFusionError::Plan(
@@ -493,6 +559,30 @@ fn rewrite_expr_to_prunable(
             };
         }
 
+        // `cast(col) > lit()`: the casts between numeric types keep the
+        // order of the values, the min and the max of `col` being cast to the
+        // bounds of `cast(col)`, or to nulls if they are out of range
+        Expr::Cast { expr, data_type } | Expr::TryCast { expr, data_type } => {
+            match expr.as_ref() {
+                Expr::Column(c) => {
+                    let field = schema.field_with_name(&c.name)?;
+                    if is_numeric(field.data_type()) && is_numeric(data_type) {
+                        Ok((column_expr.clone(), op, scalar_expr.clone()))
+                    } else {
+                        Err(DataFusionError::Plan(format!(
+                            "cast from {:?} to {:?} is not supported",
+                            field.data_type(),
+                            data_type
+                        )))
+                    }
+                }
+                _ => Err(DataFusionError::Plan(format!(
+                    "cast of complex expression {:?} is not supported",
+                    column_expr
+                ))),
+            }
+        }
+
         _ => {
             return Err(DataFusionError::Plan(format!(
                 "column expression {:?} is not supported",
@@ -503,6 +593,23 @@ fn rewrite_expr_to_prunable(
     // Ok((column_expr.clone(), op, scalar_expr.clone()))
 }
 
+fn is_numeric(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Float32
+            | DataType::Float64
+            | DataType::Decimal(_, _)
+    )
+}
+
 fn is_compare_op(op: Operator) -> bool {
     matches!(
         op,
@@ -609,15 +716,38 @@ fn build_predicate_expression(
                 .unwrap_or(unhandled);
             return Ok(expr);
         }
-        // match !col (don't do so recursively)
+        // match !col
         Expr::Not(input) => {
             if let Expr::Column(col) = input.as_ref() {
                 let expr = build_single_column_expr(col, schema, required_columns, true)
                     .unwrap_or(unhandled);
                 return Ok(expr);
-            } else {
-                return Ok(unhandled);
             }
+            // the negation of the unsupported expressions, always true, is
+            // not always false: the negation is pushed down to the
+            // comparisons before the expressions are rewritten
+            return match negate(input, schema) {
+                Some(expr) => build_predicate_expression(&expr, schema, required_columns),
+                None => Ok(unhandled),
+            };
+        }
+        // `col IS NULL` => null_count > 0
+        Expr::IsNull(input) => {
+            let expr = match input.as_ref() {
+                Expr::Column(col) => required_columns
+                    .null_count_column_expr(col)
+                    .map(|null_count| null_count.gt(logical_plan::lit(0u64)))
+                    .unwrap_or(unhandled),
+                _ => unhandled,
+            };
+            return Ok(expr);
+        }
+        // `col IN (1, 2)` => `col = 1 OR col = 2`
+        Expr::InList { .. } => {
+            return match in_list_comparisons(expr) {
+                Some(expr) => build_predicate_expression(&expr, schema, required_columns),
+                None => Ok(unhandled),
+            };
         }
         _ => {
             return Ok(unhandled);
@@ -630,6 +760,11 @@ fn build_predicate_expression(
         return Ok(logical_plan::binary_expr(left_expr, op, right_expr));
     }
 
+    // `col LIKE 'prefix%'` => the range of the values starting with the prefix
+    if let Some(range) = like_prefix_range(expr) {
+        return build_predicate_expression(&range, schema, required_columns);
+    }
+
     let expr_builder =
         PruningExpressionBuilder::try_new(left, right, op, schema, required_columns);
     let mut expr_builder = match expr_builder {
@@ -645,6 +780,224 @@ fn build_predicate_expression(
     Ok(statistics_expr)
 }
 
+/// The negation of `expr` pushed down to its comparisons, if it can be, such
+/// as `a <= 1 OR b != 2` for `NOT (a > 1 AND b = 2)`. The operands of the
+/// `AND`s and `OR`s that can't be negated are kept under a `NOT`.
+///
+/// The negations are exact in the three-valued logic of SQL: a row passes
+/// the negated expression if and only if it doesn't pass `expr` and its
+/// values are not null. The comparisons of floats are not negated, since
+/// NaN fails both `a > 1` and `a <= 1`.
+fn negate(expr: &Expr, schema: &Schema) -> Option<Expr> {
+    use crate::logical_plan;
+
+    let negate_or_not = |expr: &Expr| {
+        negate(expr, schema).unwrap_or_else(|| Expr::Not(Box::new(expr.clone())))
+    };
+    match expr {
+        Expr::Not(input) => Some(input.as_ref().clone()),
+        Expr::IsNull(input) => Some(Expr::IsNotNull(input.clone())),
+        Expr::IsNotNull(input) => Some(Expr::IsNull(input.clone())),
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => Some(Expr::InList {
+            expr: expr.clone(),
+            list: list.clone(),
+            negated: !negated,
+        }),
+        Expr::BinaryExpr { left, op, right } => {
+            let op = match op {
+                Operator::And => {
+                    return Some(negate_or_not(left).or(negate_or_not(right)))
+                }
+                Operator::Or => {
+                    return Some(negate_or_not(left).and(negate_or_not(right)))
+                }
+                Operator::Eq => Operator::NotEq,
+                Operator::NotEq => Operator::Eq,
+                Operator::Lt => Operator::GtEq,
+                Operator::LtEq => Operator::Gt,
+                Operator::Gt => Operator::LtEq,
+                Operator::GtEq => Operator::Lt,
+                _ => return None,
+            };
+            let mut columns = HashSet::new();
+            utils::expr_to_columns(left, &mut columns).ok()?;
+            utils::expr_to_columns(right, &mut columns).ok()?;
+            let has_floats = columns.iter().any(|column| {
+                schema
+                    .field_with_name(&column.name)
+                    .map(|field| {
+                        matches!(field.data_type(), DataType::Float32 | DataType::Float64)
+                    })
+                    .unwrap_or(true)
+            });
+            if has_floats {
+                return None;
+            }
+            Some(logical_plan::binary_expr(
+                left.as_ref().clone(),
+                op,
+                right.as_ref().clone(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Maximum number of values of an `IN` list compared one by one with the
+/// statistics
+const MAX_IN_LIST_COMPARISONS: usize = 20;
+
+/// The comparisons with literals equivalent, for pruning, to `expr`, an `IN`
+/// or `NOT IN` list of literals: `col = 1 OR col = 2` for `col IN (1, 2)`
+/// and `col != 1 AND col != 2` for `col NOT IN (1, 2)`.
+///
+/// The nulls of an `IN` list are ignored, since the list only contains the
+/// values equal to one of its other literals. Beyond 20 values, an `IN` list
+/// is replaced by the range of its values, such as `col >= 1 AND col <= 9`,
+/// and a `NOT IN` list has no comparisons.
+fn in_list_comparisons(expr: &Expr) -> Option<Expr> {
+    let (expr, list, negated) = match expr {
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => (expr.as_ref(), list, *negated),
+        _ => return None,
+    };
+    let mut values = Vec::with_capacity(list.len());
+    for item in list {
+        match item {
+            Expr::Literal(value) if value.is_null() && negated => return None,
+            Expr::Literal(value) if value.is_null() => {}
+            Expr::Literal(value) => values.push(value),
+            _ => return None,
+        }
+    }
+
+    let comparison = |value: &ScalarValue| {
+        let value = Expr::Literal(value.clone());
+        if negated {
+            expr.clone().not_eq(value)
+        } else {
+            expr.clone().eq(value)
+        }
+    };
+    if values.len() <= MAX_IN_LIST_COMPARISONS {
+        let (first, rest) = values.split_first()?;
+        return Some(rest.iter().fold(comparison(*first), |comparisons, value| {
+            if negated {
+                comparisons.and(comparison(*value))
+            } else {
+                comparisons.or(comparison(*value))
+            }
+        }));
+    }
+    if negated {
+        return None;
+    }
+    let (mut min, mut max) = (values[0], values[0]);
+    for &value in &values[1..] {
+        if value.partial_cmp(min)? == Ordering::Less {
+            min = value;
+        }
+        if value.partial_cmp(max)? == Ordering::Greater {
+            max = value;
+        }
+    }
+    Some(
+        expr.clone()
+            .gt_eq(Expr::Literal(min.clone()))
+            .and(expr.clone().lt_eq(Expr::Literal(max.clone()))),
+    )
+}
+
+/// The range of the values of a column matching `expr`, a `LIKE` or `ILIKE`
+/// of the column with a pattern starting with a literal prefix, such as
+/// `url >= 'https://a.' AND url < 'https://a/'` for `url LIKE 'https://a.%'`.
+///
+/// The values matching the pattern are in the range, so the range can be
+/// used to skip the values out of it, such as the row groups whose
+/// statistics don't overlap it, but the range doesn't replace the pattern.
+///
+/// The prefix stops at the first wildcard or escape character, and
+/// patterns without a prefix have no range. Since the case variants of some
+/// letters include characters that aren't ASCII, such as the Kelvin sign
+/// (U+212A) for `k`, the prefix of an `ILIKE` pattern also stops at the first
+/// character that isn't ASCII or that is an `i`, a `k` or an `s`.
+pub fn like_prefix_range(expr: &Expr) -> Option<Expr> {
+    let (column, op, pattern) = match expr {
+        Expr::BinaryExpr { left, op, right } => match (left.as_ref(), right.as_ref()) {
+            (Expr::Column(_), Expr::Literal(pattern)) => (left.as_ref(), *op, pattern),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    let case_insensitive = match op {
+        Operator::Like => false,
+        Operator::ILike => true,
+        _ => return None,
+    };
+    let (pattern, large) = match pattern {
+        ScalarValue::Utf8(Some(pattern)) => (pattern, false),
+        ScalarValue::LargeUtf8(Some(pattern)) => (pattern, true),
+        _ => return None,
+    };
+
+    let prefix = pattern
+        .chars()
+        .take_while(|c| !matches!(c, '%' | '_' | '\\'))
+        .take_while(|c| {
+            !case_insensitive
+                || (c.is_ascii() && !matches!(c.to_ascii_lowercase(), 'i' | 'k' | 's'))
+        })
+        .collect::<String>();
+    if prefix.is_empty() {
+        return None;
+    }
+    // in the byte order of UTF-8 strings, the case variants of an ASCII
+    // prefix are between its upper case and its lower case
+    let (lower, upper) = if case_insensitive {
+        (prefix.to_ascii_uppercase(), prefix.to_ascii_lowercase())
+    } else {
+        (prefix.clone(), prefix)
+    };
+
+    let literal = |value: String| {
+        Expr::Literal(if large {
+            ScalarValue::LargeUtf8(Some(value))
+        } else {
+            ScalarValue::Utf8(Some(value))
+        })
+    };
+    let range = column.clone().gt_eq(literal(lower));
+    Some(match prefix_successor(&upper) {
+        Some(successor) => range.and(column.clone().lt(literal(successor))),
+        None => range,
+    })
+}
+
+/// A string greater than all the strings starting with `prefix`, in the
+/// byte order of UTF-8 strings, if there is one
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut chars = prefix.chars().collect::<Vec<_>>();
+    while let Some(c) = chars.pop() {
+        // skip the surrogates, which aren't characters
+        let next = match c {
+            '\u{D7FF}' => Some('\u{E000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            chars.push(next);
+            return Some(chars.into_iter().collect());
+        }
+    }
+    None
+}
+
 fn build_statistics_expr(expr_builder: &mut PruningExpressionBuilder) -> Result<Expr> {
     let statistics_expr =
         match expr_builder.op() {
@@ -704,6 +1057,7 @@ fn build_statistics_expr(expr_builder: &mut PruningExpressionBuilder) -> Result<
 enum StatisticsType {
     Min,
     Max,
+    NullCount,
 }
 
 #[cfg(test)]
@@ -714,7 +1068,7 @@ mod tests {
     use crate::logical_plan::{col, lit};
     use crate::{assert_batches_eq, physical_optimizer::pruning::StatisticsType};
     use arrow::{
-        array::{BinaryArray, Int32Array, Int64Array, StringArray},
+        array::{BinaryArray, Int32Array, Int64Array, StringArray, UInt64Array},
         datatypes::{DataType, TimeUnit},
     };
 
@@ -723,6 +1077,7 @@ mod tests {
     struct ContainerStats {
         min: ArrayRef,
         max: ArrayRef,
+        null_counts: Option<ArrayRef>,
     }
 
     impl ContainerStats {
@@ -733,6 +1088,7 @@ mod tests {
             Self {
                 min: Arc::new(min.into_iter().collect::<Int32Array>()),
                 max: Arc::new(max.into_iter().collect::<Int32Array>()),
+                null_counts: None,
             }
         }
 
@@ -743,6 +1099,7 @@ mod tests {
             Self {
                 min: Arc::new(min.into_iter().collect::<StringArray>()),
                 max: Arc::new(max.into_iter().collect::<StringArray>()),
+                null_counts: None,
             }
         }
 
@@ -753,9 +1110,19 @@ mod tests {
             Self {
                 min: Arc::new(min.into_iter().collect::<BooleanArray>()),
                 max: Arc::new(max.into_iter().collect::<BooleanArray>()),
+                null_counts: None,
             }
         }
 
+        fn with_null_counts(
+            mut self,
+            null_counts: impl IntoIterator<Item = Option<u64>>,
+        ) -> Self {
+            let null_counts = null_counts.into_iter().collect::<UInt64Array>();
+            self.null_counts = Some(Arc::new(null_counts));
+            self
+        }
+
         fn min(&self) -> Option<ArrayRef> {
             Some(self.min.clone())
         }
@@ -814,6 +1181,12 @@ mod tests {
                 .map(|container_stats| container_stats.len())
                 .unwrap_or(0)
         }
+
+        fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+            self.stats
+                .get(column)
+                .and_then(|container_stats| container_stats.null_counts.clone())
+        }
     }
 
     /// Returns the specified min/max container values
@@ -1503,4 +1876,227 @@ mod tests {
         let result = p.prune(&statistics).unwrap();
         assert_eq!(result, expected_ret);
     }
+
+    #[test]
+    fn prune_not_pushed_down() {
+        let (schema, statistics) = int32_setup();
+
+        // NOT (i > 0) ==> i <= 0
+        let expr = col("i").gt(lit(0)).not();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, false, true, true, false]);
+
+        // NOT (NOT (i > 0)) ==> i > 0
+        let expr = col("i").gt(lit(0)).not().not();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, true, false, true, true]);
+
+        // NOT (i > 0 AND i < 100) ==> i <= 0 OR i >= 100
+        // i [1, NULL] ==> unknown (must keep)
+        let expr = col("i").gt(lit(0)).and(col("i").lt(lit(100))).not();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, false, true, true, true]);
+
+        // NOT (i > 0 OR i % 2 = 0) ==> i <= 0 AND i % 2 != 0 ==> i <= 0 AND true
+        let unsupported = col("i").modulus(lit(2)).eq(lit(0));
+        let expr = col("i").gt(lit(0)).or(unsupported.clone()).not();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, false, true, true, false]);
+
+        // NOT (i > 0 AND i % 2 = 0) ==> i <= 0 OR true: the unsupported
+        // expression, rewritten to true, must not be negated to false
+        let expr = col("i").gt(lit(0)).and(unsupported).not();
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true; 5]);
+    }
+
+    #[test]
+    fn row_group_predicate_not_float() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("f", DataType::Float64, true)]);
+
+        // NaN fails both f > 0 and f <= 0
+        let expr = col("f").gt(lit(0.0)).not();
+        let predicate_expr =
+            build_predicate_expression(&expr, &schema, &mut RequiredStatColumns::new())?;
+        assert_eq!(format!("{:?}", predicate_expr), "Boolean(true)");
+
+        Ok(())
+    }
+
+    #[test]
+    fn prune_cast() {
+        let (schema, statistics) = int32_setup();
+
+        // CAST(i AS Int64) > 0 ==> CAST(i_max AS Int64) > 0
+        let expected_ret = vec![true, true, false, true, true];
+        let expr = Expr::Cast {
+            expr: Box::new(col("i")),
+            data_type: DataType::Int64,
+        }
+        .gt(lit(0i64));
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, expected_ret);
+
+        let expr = Expr::TryCast {
+            expr: Box::new(col("i")),
+            data_type: DataType::Float64,
+        }
+        .gt(lit(0.0));
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, expected_ret);
+
+        // the strings are not ordered as the numbers
+        let expr = Expr::Cast {
+            expr: Box::new(col("i")),
+            data_type: DataType::Utf8,
+        }
+        .gt(lit("0"));
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true; 5]);
+    }
+
+    #[test]
+    fn like_prefix_ranges() {
+        let url = || col("url");
+
+        let expr = url().like(lit("https://a.%"));
+        let expected = url()
+            .gt_eq(lit("https://a."))
+            .and(url().lt(lit("https://a/")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+
+        // the prefix stops at the first wildcard or escape
+        let expr = url().like(lit("ab_c%"));
+        let expected = url().gt_eq(lit("ab")).and(url().lt(lit("ac")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+        let expr = url().like(lit("ab\\%c%"));
+        let expected = url().gt_eq(lit("ab")).and(url().lt(lit("ac")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+
+        // the case variants of the prefix are between its upper and lower case
+        let expr = url().ilike(lit("Ftp:/%"));
+        let expected = url().gt_eq(lit("FTP:/")).and(url().lt(lit("ftp:0")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+        let expr = url().ilike(lit("http%"));
+        let expected = url().gt_eq(lit("HTTP")).and(url().lt(lit("httq")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+
+        // the maximal characters have no successor
+        let expr = url().like(lit("a\u{10FFFF}%"));
+        let expected = url().gt_eq(lit("a\u{10FFFF}")).and(url().lt(lit("b")));
+        assert_eq!(like_prefix_range(&expr), Some(expected));
+        let expr = url().like(lit("\u{10FFFF}%"));
+        assert_eq!(
+            like_prefix_range(&expr),
+            Some(url().gt_eq(lit("\u{10FFFF}")))
+        );
+
+        // no prefix
+        assert_eq!(like_prefix_range(&url().like(lit("%a"))), None);
+        assert_eq!(like_prefix_range(&url().like(lit("_a%"))), None);
+        assert_eq!(like_prefix_range(&url().like(lit("\\%a"))), None);
+        assert_eq!(like_prefix_range(&url().ilike(lit("sftp:%"))), None);
+        assert_eq!(like_prefix_range(&url().not_like(lit("a%"))), None);
+        assert_eq!(like_prefix_range(&lit("a").like(url())), None);
+    }
+
+    #[test]
+    fn prune_like() {
+        let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+        let statistics = TestStatistics::new().with(
+            "s",
+            ContainerStats::new_utf8(
+                vec![Some("a"), Some("ab"), Some("abd"), None, Some("b")], // min
+                vec![Some("aa"), Some("abc"), Some("b"), None, Some("c")], // max
+            ),
+        );
+
+        // s LIKE 'abc%' ==> s_max >= 'abc' AND s_min < 'abd'
+        let expr = col("s").like(lit("abc%"));
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![false, true, false, true, false]);
+
+        // no prefix
+        let expr = col("s").like(lit("%abc"));
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true; 5]);
+    }
+
+    #[test]
+    fn prune_in_list() {
+        let (schema, statistics) = int32_setup();
+
+        // i IN (0, 20, NULL) ==> i = 0 OR i = 20
+        let expr =
+            col("i").in_list(vec![lit(0), lit(20), lit(ScalarValue::Int32(None))], false);
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, false, false, true, true]);
+
+        // NOT (i NOT IN (0, 20)) ==> i = 0 OR i = 20
+        let expr = Expr::Not(Box::new(col("i").in_list(vec![lit(0), lit(20)], true)));
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true, false, false, true, true]);
+
+        // i IN (100, ..., 130) ==> i >= 100 AND i <= 130
+        let expr = col("i").in_list((100..=130).map(lit).collect(), false);
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![false, false, false, true, true]);
+
+        // a NOT IN list with a null is never true, but isn't used
+        let expr = col("i").in_list(vec![lit(0), lit(ScalarValue::Int32(None))], true);
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true; 5]);
+    }
+
+    #[test]
+    fn prune_null_counts() {
+        let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, true)]));
+        let statistics = TestStatistics::new().with(
+            "i",
+            ContainerStats::new_i32(
+                vec![Some(-5), Some(1), None, None, Some(1)], // min
+                vec![Some(5), Some(11), None, None, Some(2)], // max
+            )
+            .with_null_counts(vec![Some(0), Some(3), None, Some(1), Some(0)]),
+        );
+
+        // i IS NULL ==> i_null_count > 0
+        let expected_ret = vec![false, true, true, true, false];
+        let expr = col("i").is_null();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, expected_ret);
+
+        // NOT (i IS NOT NULL) ==> i IS NULL
+        let expr = col("i").is_not_null().not();
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, expected_ret);
+
+        // i IS NULL OR i > 10
+        let expr = col("i").is_null().or(col("i").gt(lit(10)));
+        let p = PruningPredicate::try_new(&expr, schema.clone()).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![false, true, true, true, false]);
+
+        // the number of rows of the containers isn't known
+        let expr = col("i").is_not_null();
+        let p = PruningPredicate::try_new(&expr, schema).unwrap();
+        let result = p.prune(&statistics).unwrap();
+        assert_eq!(result, vec![true; 5]);
+    }
 }
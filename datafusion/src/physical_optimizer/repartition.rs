@@ -37,7 +37,7 @@ impl Repartition {
 
 fn optimize_partitions(
     target_partitions: usize,
-    requires_single_partition: bool,
+    keep_partitioning: bool,
     plan: Arc<dyn ExecutionPlan>,
 ) -> Result<Arc<dyn ExecutionPlan>> {
     // Recurse into children bottom-up (added nodes should be as deep as possible)
@@ -50,12 +50,14 @@ fn optimize_partitions(
             .children()
             .iter()
             .map(|child| {
+                // repartitioning would shuffle the rows of the sorted
+                // partitions an operator relies on
                 optimize_partitions(
                     target_partitions,
                     matches!(
                         plan.required_child_distribution(),
                         Distribution::SinglePartition
-                    ),
+                    ) || plan.required_input_ordering().is_some(),
                     child.clone(),
                 )
             })
@@ -66,6 +68,7 @@ fn optimize_partitions(
     let perform_repartition = match new_plan.output_partitioning() {
         // Apply when underlying node has less than `self.target_partitions` amount of concurrency
         RoundRobinBatch(x) => x < target_partitions,
+        RoundRobinRows(x) => x < target_partitions,
         UnknownPartitioning(x) => x < target_partitions,
         // we don't want to introduce partitioning after hash partitioning
         // as the plan will likely depend on this
@@ -77,7 +80,7 @@ fn optimize_partitions(
     // But also not very useful to inlude
     let is_empty_exec = plan.as_any().downcast_ref::<EmptyExec>().is_some();
 
-    if perform_repartition && !requires_single_partition && !is_empty_exec {
+    if perform_repartition && !keep_partitioning && !is_empty_exec {
         Ok(Arc::new(RepartitionExec::try_new(
             new_plan,
             RoundRobinBatch(target_partitions),
@@ -107,12 +110,15 @@ impl PhysicalOptimizerRule for Repartition {
 }
 #[cfg(test)]
 mod tests {
-    use arrow::datatypes::Schema;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
 
     use super::*;
     use crate::datasource::PartitionedFile;
+    use crate::physical_plan::expressions::{col, PhysicalSortExpr};
     use crate::physical_plan::file_format::{ParquetExec, PhysicalPlanConfig};
     use crate::physical_plan::projection::ProjectionExec;
+    use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
     use crate::physical_plan::Statistics;
     use crate::test::object_store::TestObjectStore;
 
@@ -198,4 +204,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn keep_sorted_input() -> Result<()> {
+        let file_schema =
+            Arc::new(Schema::new(vec![Field::new("c", DataType::Int32, true)]));
+        let sort_expr = vec![PhysicalSortExpr {
+            expr: col("c", &file_schema)?,
+            options: SortOptions::default(),
+        }];
+        let merge = SortPreservingMergeExec::new(
+            sort_expr,
+            Arc::new(ParquetExec::new(
+                PhysicalPlanConfig {
+                    object_store: TestObjectStore::new_arc(&[("x", 100)]),
+                    file_schema,
+                    file_groups: vec![vec![PartitionedFile::new("x".to_string(), 100)]],
+                    statistics: Statistics::default(),
+                    projection: None,
+                    batch_size: 2048,
+                    limit: None,
+                    table_partition_cols: vec![],
+                },
+                None,
+            )),
+            2048,
+        );
+
+        let optimizer = Repartition {};
+
+        let optimized = optimizer.optimize(
+            Arc::new(merge),
+            &ExecutionConfig::new().with_target_partitions(10),
+        )?;
+
+        // the merge relies on the order of the rows of its input
+        assert!(optimized.children()[0]
+            .as_any()
+            .downcast_ref::<RepartitionExec>()
+            .is_none());
+
+        Ok(())
+    }
 }
@@ -21,6 +21,7 @@ use super::optimizer::PhysicalOptimizerRule;
 use crate::execution::context::ExecutionConfig;
 
 use crate::error::Result;
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
 use crate::physical_plan::ExecutionPlan;
 use std::sync::Arc;
 
@@ -45,3 +46,33 @@ pub fn optimize_children(
         plan.with_new_children(children)
     }
 }
+
+/// Returns true if `left` and `right` sort by the same column in the same
+/// direction.
+///
+/// Only column sort keys are compared: two evaluations of the same
+/// expression, such as `random()`, may not produce the same values.
+pub fn same_sort_key(left: &PhysicalSortExpr, right: &PhysicalSortExpr) -> bool {
+    match (
+        left.expr.as_any().downcast_ref::<Column>(),
+        right.expr.as_any().downcast_ref::<Column>(),
+    ) {
+        (Some(left_column), Some(right_column)) => {
+            left_column == right_column && left.options == right.options
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if data sorted by `provided` is also sorted by `required`,
+/// that is if `required` is a prefix of `provided`
+pub fn ordering_satisfies(
+    provided: &[PhysicalSortExpr],
+    required: &[PhysicalSortExpr],
+) -> bool {
+    required.len() <= provided.len()
+        && required
+            .iter()
+            .zip(provided)
+            .all(|(required, provided)| same_sort_key(required, provided))
+}
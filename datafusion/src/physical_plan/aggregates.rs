@@ -35,7 +35,7 @@ use crate::physical_plan::coercion_rule::aggregate_rule::{coerce_exprs, coerce_t
 use crate::physical_plan::distinct_expressions;
 use crate::physical_plan::expressions;
 use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
-use expressions::{avg_return_type, sum_return_type};
+use expressions::{avg_return_type, sum_return_type, ArithmeticOverflow};
 use std::{fmt, str::FromStr, sync::Arc};
 
 /// the implementation of an aggregate function
@@ -47,6 +47,50 @@ pub type AccumulatorFunctionImplementation =
 pub type StateTypeFunction =
     Arc<dyn Fn(&DataType) -> Result<Arc<Vec<DataType>>> + Send + Sync>;
 
+/// Version of the layout of the intermediate states that a `Partial`
+/// aggregation emits and a `Final` aggregation merges.
+///
+/// The states of every built-in accumulator are plain Arrow columns, named
+/// `<expr name>[<state name>]`, so that they can be shipped between processes
+/// (e.g. through Arrow IPC) and merged by `Accumulator::merge_batch`:
+///
+/// | aggregate                | state columns                                        |
+/// |--------------------------|------------------------------------------------------|
+/// | `COUNT(x)`               | `[count]: UInt64`                                    |
+/// | `SUM(x)`                 | `[sum]: <sum type>`                                  |
+/// | `MIN(x)` / `MAX(x)`      | `[min]` / `[max]`: the type of `x`                   |
+/// | `AVG(x)`                 | `[count]: UInt64`, `[sum]: Float64`                  |
+/// | `APPROX_DISTINCT(x)`     | `[hll_registers]: Binary`, 16384 registers           |
+/// | `APPROX_MEDIAN(x)`       | `[means]`, `[weights]`: List<Float64> of a t-digest  |
+/// | `ARRAY_AGG(x)`           | `[array_agg]: List<type of x>`                       |
+/// | `COUNT(DISTINCT x, ...)` | one `[count distinct]: List<type of x>` per argument |
+/// | `STDDEV(x)`              | `[count]: UInt64`, `[mean]`, `[m2]`: Float64         |
+///
+/// HyperLogLog registers are only mergeable between processes that hash values
+/// with the same fixed seed, which is therefore part of this layout.
+///
+/// This version must be bumped whenever any of these layouts changes.
+pub const STATE_LAYOUT_VERSION: u32 = 2;
+
+/// Key of the schema metadata entry in which a `Partial` aggregation records
+/// the [`STATE_LAYOUT_VERSION`] of its output.
+pub const STATE_LAYOUT_VERSION_KEY: &str = "datafusion.aggregate.state_layout_version";
+
+/// Checks that the aggregation states described by `schema` use a layout
+/// that this build knows how to merge. Schemas without a version are
+/// accepted, as they were produced by a build that predates versioning.
+pub fn check_state_layout(schema: &Schema) -> Result<()> {
+    match schema.metadata().get(STATE_LAYOUT_VERSION_KEY) {
+        Some(version) if *version != STATE_LAYOUT_VERSION.to_string() => {
+            Err(DataFusionError::Execution(format!(
+                "Unsupported aggregation state layout version {}, expected {}",
+                version, STATE_LAYOUT_VERSION
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Enum of all built-in aggregate functions
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
 pub enum AggregateFunction {
@@ -62,8 +106,12 @@ pub enum AggregateFunction {
     Avg,
     /// Approximate aggregate function
     ApproxDistinct,
+    /// Approximate median
+    ApproxMedian,
     /// array_agg
     ArrayAgg,
+    /// Sample standard deviation
+    Stddev,
 }
 
 impl fmt::Display for AggregateFunction {
@@ -83,7 +131,9 @@ impl FromStr for AggregateFunction {
             "avg" => AggregateFunction::Avg,
             "sum" => AggregateFunction::Sum,
             "approx_distinct" => AggregateFunction::ApproxDistinct,
+            "approx_median" => AggregateFunction::ApproxMedian,
             "array_agg" => AggregateFunction::ArrayAgg,
+            "stddev" => AggregateFunction::Stddev,
             _ => {
                 return Err(DataFusionError::Plan(format!(
                     "There is no built-in function named {}",
@@ -117,6 +167,9 @@ pub fn return_type(
         }
         AggregateFunction::Sum => sum_return_type(&coerced_data_types[0]),
         AggregateFunction::Avg => avg_return_type(&coerced_data_types[0]),
+        AggregateFunction::ApproxMedian | AggregateFunction::Stddev => {
+            Ok(DataType::Float64)
+        }
         AggregateFunction::ArrayAgg => Ok(DataType::List(Box::new(Field::new(
             "item",
             coerced_data_types[0].clone(),
@@ -133,6 +186,27 @@ pub fn create_aggregate_expr(
     input_phy_exprs: &[Arc<dyn PhysicalExpr>],
     input_schema: &Schema,
     name: impl Into<String>,
+) -> Result<Arc<dyn AggregateExpr>> {
+    create_aggregate_expr_with_overflow(
+        fun,
+        distinct,
+        input_phy_exprs,
+        input_schema,
+        name,
+        ArithmeticOverflow::default(),
+    )
+}
+
+/// Create a physical aggregation expression, whose sums of integers handle
+/// the results out of range as `overflow` says.
+/// This function errors when `input_phy_exprs`' can't be coerced to a valid argument type of the aggregation function.
+pub fn create_aggregate_expr_with_overflow(
+    fun: &AggregateFunction,
+    distinct: bool,
+    input_phy_exprs: &[Arc<dyn PhysicalExpr>],
+    input_schema: &Schema,
+    name: impl Into<String>,
+    overflow: ArithmeticOverflow,
 ) -> Result<Arc<dyn AggregateExpr>> {
     let name = name.into();
     // get the coerced phy exprs if some expr need to be wrapped with the try cast.
@@ -170,10 +244,11 @@ pub fn create_aggregate_expr(
                 return_type,
             ))
         }
-        (AggregateFunction::Sum, false) => Arc::new(expressions::Sum::new(
+        (AggregateFunction::Sum, false) => Arc::new(expressions::Sum::new_with_overflow(
             coerced_phy_exprs[0].clone(),
             name,
             return_type,
+            overflow,
         )),
         (AggregateFunction::Sum, true) => {
             return Err(DataFusionError::NotImplemented(
@@ -187,11 +262,21 @@ pub fn create_aggregate_expr(
                 coerced_exprs_types[0].clone(),
             ))
         }
+        (AggregateFunction::ApproxMedian, _) => Arc::new(expressions::ApproxMedian::new(
+            coerced_phy_exprs[0].clone(),
+            name,
+            return_type,
+        )),
         (AggregateFunction::ArrayAgg, _) => Arc::new(expressions::ArrayAgg::new(
             coerced_phy_exprs[0].clone(),
             name,
             coerced_exprs_types[0].clone(),
         )),
+        (AggregateFunction::Stddev, _) => Arc::new(expressions::Stddev::new(
+            coerced_phy_exprs[0].clone(),
+            name,
+            return_type,
+        )),
         (AggregateFunction::Min, _) => Arc::new(expressions::Min::new(
             coerced_phy_exprs[0].clone(),
             name,
@@ -217,6 +302,8 @@ pub fn create_aggregate_expr(
 
 static STRINGS: &[DataType] = &[DataType::Utf8, DataType::LargeUtf8];
 
+static BINARIES: &[DataType] = &[DataType::Binary, DataType::LargeBinary];
+
 static NUMERICS: &[DataType] = &[
     DataType::Int8,
     DataType::Int16,
@@ -249,6 +336,7 @@ pub fn signature(fun: &AggregateFunction) -> Signature {
         AggregateFunction::Min | AggregateFunction::Max => {
             let valid = STRINGS
                 .iter()
+                .chain(BINARIES.iter())
                 .chain(NUMERICS.iter())
                 .chain(TIMESTAMPS.iter())
                 .chain(DATES.iter())
@@ -256,7 +344,10 @@ pub fn signature(fun: &AggregateFunction) -> Signature {
                 .collect::<Vec<_>>();
             Signature::uniform(1, valid, Volatility::Immutable)
         }
-        AggregateFunction::Avg | AggregateFunction::Sum => {
+        AggregateFunction::Avg
+        | AggregateFunction::Sum
+        | AggregateFunction::ApproxMedian
+        | AggregateFunction::Stddev => {
             Signature::uniform(1, NUMERICS.to_vec(), Volatility::Immutable)
         }
     }
@@ -544,4 +635,31 @@ mod tests {
         let observed = return_type(&AggregateFunction::Avg, &[DataType::Utf8]);
         assert!(observed.is_err());
     }
+
+    #[test]
+    fn test_approx_median_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::ApproxMedian, &[DataType::Int32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed =
+            return_type(&AggregateFunction::ApproxMedian, &[DataType::Float32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(&AggregateFunction::ApproxMedian, &[DataType::Utf8]);
+        assert!(observed.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stddev_return_type() -> Result<()> {
+        let observed = return_type(&AggregateFunction::Stddev, &[DataType::Int64])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(&AggregateFunction::Stddev, &[DataType::Float32])?;
+        assert_eq!(DataType::Float64, observed);
+
+        let observed = return_type(&AggregateFunction::Stddev, &[DataType::Utf8]);
+        assert!(observed.is_err());
+        Ok(())
+    }
 }
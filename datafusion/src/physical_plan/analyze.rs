@@ -22,14 +22,16 @@ use std::{any::Any, time::Instant};
 
 use crate::{
     error::{DataFusionError, Result},
+    execution::task_context::TaskContext,
     physical_plan::{
-        display::DisplayableExecutionPlan, DisplayFormatType, ExecutionPlan,
-        Partitioning, Statistics, LambdaExecPlan,
+        display::DisplayableExecutionPlan, fmt_with_statistics, trace::instrument_stream,
+        DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning, Statistics,
     },
 };
 use arrow::{array::StringBuilder, datatypes::SchemaRef, record_batch::RecordBatch};
 use futures::StreamExt;
 
+use super::memory::feed_input;
 use super::{stream::RecordBatchReceiverStream, Distribution, SendableRecordBatchStream};
 use async_trait::async_trait;
 
@@ -60,8 +62,9 @@ impl AnalyzeExec {
 
 #[async_trait]
 impl LambdaExecPlan for AnalyzeExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!("AnalyzeExec::feed_batches");
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -113,7 +116,21 @@ impl ExecutionPlan for AnalyzeExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "AnalyzeExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
                 "AnalyzeExec invalid partition. Expected 0, got {}",
@@ -133,7 +150,7 @@ impl ExecutionPlan for AnalyzeExec {
         let (tx, rx) = tokio::sync::mpsc::channel(input_partitions);
 
         let captured_input = self.input.clone();
-        let mut input_stream = captured_input.execute(0).await?;
+        let mut input_stream = captured_input.execute(0, context).await?;
         let captured_schema = self.schema.clone();
         let verbose = self.verbose;
 
@@ -208,10 +225,10 @@ impl ExecutionPlan for AnalyzeExec {
             tx.send(maybe_batch).await.ok();
         });
 
-        Ok(RecordBatchReceiverStream::create(
-            &self.schema,
-            rx,
-            join_handle,
+        Ok(instrument_stream(
+            self,
+            partition,
+            RecordBatchReceiverStream::create(&self.schema, rx, join_handle),
         ))
     }
 
@@ -224,6 +241,7 @@ impl ExecutionPlan for AnalyzeExec {
             DisplayFormatType::Default => {
                 write!(f, "AnalyzeExec verbose={}", self.verbose)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -239,6 +257,7 @@ mod tests {
     use futures::FutureExt;
 
     use crate::{
+        execution::context::ExecutionContext,
         physical_plan::collect,
         test::{
             assert_is_pending,
@@ -250,6 +269,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -257,7 +277,7 @@ mod tests {
         let refs = blocking_exec.refs();
         let analyze_exec = Arc::new(AnalyzeExec::new(true, blocking_exec, schema));
 
-        let fut = collect(analyze_exec);
+        let fut = collect(analyze_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
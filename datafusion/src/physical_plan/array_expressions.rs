@@ -18,49 +18,18 @@
 //! Array expressions
 
 use crate::error::{DataFusionError, Result};
+use crate::scalar::ScalarValue;
 use arrow::array::*;
-use arrow::datatypes::DataType;
+use arrow::buffer::Buffer;
+use arrow::compute::kernels::cast::cast;
+use arrow::datatypes::{DataType, Field};
+use std::ops::Range;
 use std::sync::Arc;
 
+use super::functions::make_scalar_function;
 use super::ColumnarValue;
 
-macro_rules! downcast_vec {
-    ($ARGS:expr, $ARRAY_TYPE:ident) => {{
-        $ARGS
-            .iter()
-            .map(|e| match e.as_any().downcast_ref::<$ARRAY_TYPE>() {
-                Some(array) => Ok(array),
-                _ => Err(DataFusionError::Internal("failed to downcast".to_string())),
-            })
-    }};
-}
-
-macro_rules! array {
-    ($ARGS:expr, $ARRAY_TYPE:ident, $BUILDER_TYPE:ident) => {{
-        // downcast all arguments to their common format
-        let args =
-            downcast_vec!($ARGS, $ARRAY_TYPE).collect::<Result<Vec<&$ARRAY_TYPE>>>()?;
-
-        let mut builder = FixedSizeListBuilder::<$BUILDER_TYPE>::new(
-            <$BUILDER_TYPE>::new(args[0].len()),
-            args.len() as i32,
-        );
-        // for each entry in the array
-        for index in 0..args[0].len() {
-            for arg in &args {
-                if arg.is_null(index) {
-                    builder.values().append_null()?;
-                } else {
-                    builder.values().append_value(arg.value(index))?;
-                }
-            }
-            builder.append(true)?;
-        }
-        Ok(Arc::new(builder.finish()))
-    }};
-}
-
-fn array_array(args: &[&dyn Array]) -> Result<ArrayRef> {
+fn array_array(args: &[ArrayRef]) -> Result<ArrayRef> {
     // do not accept 0 arguments.
     if args.is_empty() {
         return Err(DataFusionError::Internal(
@@ -68,43 +37,147 @@ fn array_array(args: &[&dyn Array]) -> Result<ArrayRef> {
         ));
     }
 
-    match args[0].data_type() {
-        DataType::Utf8 => array!(args, StringArray, StringBuilder),
-        DataType::LargeUtf8 => array!(args, LargeStringArray, LargeStringBuilder),
-        DataType::Boolean => array!(args, BooleanArray, BooleanBuilder),
-        DataType::Float32 => array!(args, Float32Array, Float32Builder),
-        DataType::Float64 => array!(args, Float64Array, Float64Builder),
-        DataType::Int8 => array!(args, Int8Array, Int8Builder),
-        DataType::Int16 => array!(args, Int16Array, Int16Builder),
-        DataType::Int32 => array!(args, Int32Array, Int32Builder),
-        DataType::Int64 => array!(args, Int64Array, Int64Builder),
-        DataType::UInt8 => array!(args, UInt8Array, UInt8Builder),
-        DataType::UInt16 => array!(args, UInt16Array, UInt16Builder),
-        DataType::UInt32 => array!(args, UInt32Array, UInt32Builder),
-        DataType::UInt64 => array!(args, UInt64Array, UInt64Builder),
-        data_type => Err(DataFusionError::NotImplemented(format!(
+    let data_type = args[0].data_type();
+    if !SUPPORTED_ARRAY_TYPES.contains(data_type) {
+        return Err(DataFusionError::NotImplemented(format!(
             "Array is not implemented for type '{:?}'.",
             data_type
-        ))),
+        )));
     }
+
+    // interleave the arguments, row by row, into the values of the lists
+    let len = args[0].len();
+    let data = args.iter().map(|arg| arg.data()).collect::<Vec<_>>();
+    let mut values = MutableArrayData::new(data, true, len * args.len());
+    for index in 0..len {
+        for arg in 0..args.len() {
+            values.extend(arg, index, index + 1);
+        }
+    }
+    let offsets = (0..=len)
+        .map(|index| (index * args.len()) as i32)
+        .collect::<Vec<_>>();
+
+    let field = Field::new("item", data_type.clone(), true);
+    let data = ArrayData::builder(DataType::List(Box::new(field)))
+        .len(len)
+        .add_buffer(Buffer::from_slice_ref(&offsets))
+        .add_child_data(values.freeze())
+        .build()?;
+    Ok(Arc::new(ListArray::from(data)))
 }
 
 /// put values in an array.
 pub fn array(values: &[ColumnarValue]) -> Result<ColumnarValue> {
-    let arrays: Vec<&dyn Array> = values
-        .iter()
-        .map(|value| {
-            if let ColumnarValue::Array(value) = value {
-                Ok(value.as_ref())
-            } else {
-                Err(DataFusionError::NotImplemented(
-                    "Array is not implemented for scalar values.".to_string(),
-                ))
+    make_scalar_function(array_array)(values)
+}
+
+/// The type of the elements of the lists of type `data_type`, `None` if it is
+/// not a list type
+pub fn list_element_type(data_type: &DataType) -> Option<&DataType> {
+    match data_type {
+        DataType::List(field)
+        | DataType::LargeList(field)
+        | DataType::FixedSizeList(field, _) => Some(field.data_type()),
+        _ => None,
+    }
+}
+
+/// The values of the lists of `array` and, for each of its rows, the range of
+/// these values its list spans, `None` when the list is null
+pub(crate) fn list_ranges(
+    array: &dyn Array,
+) -> Result<(ArrayRef, Vec<Option<Range<usize>>>)> {
+    macro_rules! ranges {
+        ($LIST:expr, $RANGE:expr) => {{
+            let list = $LIST;
+            let ranges = (0..list.len())
+                .map(|index| list.is_valid(index).then(|| $RANGE(list, index)))
+                .collect();
+            Ok((list.values(), ranges))
+        }};
+    }
+
+    match array.data_type() {
+        DataType::List(_) => ranges!(
+            array.as_any().downcast_ref::<ListArray>().unwrap(),
+            |list: &ListArray, index| {
+                let offsets = list.value_offsets();
+                offsets[index] as usize..offsets[index + 1] as usize
+            }
+        ),
+        DataType::LargeList(_) => ranges!(
+            array.as_any().downcast_ref::<LargeListArray>().unwrap(),
+            |list: &LargeListArray, index| {
+                let offsets = list.value_offsets();
+                offsets[index] as usize..offsets[index + 1] as usize
+            }
+        ),
+        DataType::FixedSizeList(_, _) => ranges!(
+            array.as_any().downcast_ref::<FixedSizeListArray>().unwrap(),
+            |list: &FixedSizeListArray, index| {
+                let start = list.value_offset(index) as usize;
+                start..start + list.value_length() as usize
+            }
+        ),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a list, found {:?}",
+            other
+        ))),
+    }
+}
+
+/// The number of elements of each list, null for null lists.
+pub fn array_length(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let (_, ranges) = list_ranges(args[0].as_ref())?;
+    let result = ranges
+        .into_iter()
+        .map(|range| range.map(|range| range.len() as i64))
+        .collect::<Int64Array>();
+    Ok(Arc::new(result))
+}
+
+/// For each row, the 0-based index within its list of the first element equal
+/// to the value, `None` if there is none, or when the list or the value is null
+fn find_in_lists(args: &[ArrayRef]) -> Result<Vec<Option<Option<usize>>>> {
+    let (values, ranges) = list_ranges(args[0].as_ref())?;
+    // compare the values as the type of the elements of the lists
+    let needles = cast(&args[1], values.data_type())?;
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(index, range)| match range {
+            Some(range) if needles.is_valid(index) => {
+                let needle = ScalarValue::try_from_array(&needles, index)?;
+                for (position, value_index) in range.enumerate() {
+                    if ScalarValue::try_from_array(&values, value_index)? == needle {
+                        return Ok(Some(Some(position)));
+                    }
+                }
+                Ok(Some(None))
             }
+            _ => Ok(None),
         })
-        .collect::<Result<_>>()?;
+        .collect()
+}
+
+/// Whether each list contains the value, null when the list or the value is null.
+pub fn array_contains(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let result = find_in_lists(args)?
+        .into_iter()
+        .map(|found| found.map(|position| position.is_some()))
+        .collect::<BooleanArray>();
+    Ok(Arc::new(result))
+}
 
-    Ok(ColumnarValue::Array(array_array(&arrays)?))
+/// The 1-based position within each list of the first element equal to the
+/// value, null when there is none, or when the list or the value is null.
+pub fn array_position(args: &[ArrayRef]) -> Result<ArrayRef> {
+    let result = find_in_lists(args)?
+        .into_iter()
+        .map(|found| found.flatten().map(|position| position as i64 + 1))
+        .collect::<Int64Array>();
+    Ok(Arc::new(result))
 }
 
 /// Currently supported types by the array function.
@@ -24,15 +24,23 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::memory::MemoryExec;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::common::batch_memory_size;
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::memory::{feed_input, MemoryExec};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream,
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream,
 };
 use crate::physical_plan::LambdaExecPlan;
 
+use arrow::array::{Array, ArrayData, ArrayRef, DictionaryArray};
 use arrow::compute::kernels::concat::concat;
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{
+    ArrowDictionaryKeyType, DataType, Int16Type, Int32Type, Int64Type, Int8Type,
+    SchemaRef, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
@@ -40,7 +48,7 @@ use futures::stream::{Stream, StreamExt};
 use log::debug;
 
 use serde::{Deserialize, Serialize};
-use super::metrics::{BaselineMetrics, MetricsSet};
+use super::metrics::{self, BaselineMetrics, MetricBuilder, MetricsSet};
 use super::{metrics::ExecutionPlanMetricsSet, Statistics};
 
 /// CoalesceBatchesExec combines small batches into larger batches for more efficient use of
@@ -49,8 +57,11 @@ use super::{metrics::ExecutionPlanMetricsSet, Statistics};
 pub struct CoalesceBatchesExec {
     /// The input plan
     input: Arc<dyn ExecutionPlan>,
-    /// Minimum number of rows for coalesces batches
+    /// Minimum number of rows for coalesces batches, lowered to the batch
+    /// size of the task executing the plan
     target_batch_size: usize,
+    /// Maximum number of bytes of coalesced batches, unlimited if `None`
+    target_batch_bytes: Option<usize>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -61,10 +72,19 @@ impl CoalesceBatchesExec {
         Self {
             input,
             target_batch_size,
+            target_batch_bytes: None,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
 
+    /// Stop buffering batches once they reach `target_batch_bytes` bytes,
+    /// before they reach the target number of rows. The buffered batches are
+    /// returned before a batch that would take them over that many bytes.
+    pub fn with_target_batch_bytes(mut self, target_batch_bytes: Option<usize>) -> Self {
+        self.target_batch_bytes = target_batch_bytes;
+        self
+    }
+
     /// Get new orphan of execution plan
     pub fn new_orphan(&self) -> Arc<CoalesceBatchesExec> {
         let mut projection = None;
@@ -75,6 +95,7 @@ impl CoalesceBatchesExec {
         Arc::new(CoalesceBatchesExec {
             input: Arc::new(memory_exec),
             target_batch_size: self.target_batch_size,
+            target_batch_bytes: self.target_batch_bytes,
             metrics: ExecutionPlanMetricsSet::new(), 
         })
     }
@@ -88,17 +109,18 @@ impl CoalesceBatchesExec {
     pub fn target_batch_size(&self) -> usize {
         self.target_batch_size
     }
+
+    /// Maximum number of bytes of coalesced batches, unlimited if `None`
+    pub fn target_batch_bytes(&self) -> Option<usize> {
+        self.target_batch_bytes
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for CoalesceBatchesExec {
-    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) {
-        self.input = Arc::new(MemoryExec {
-            partitions,
-            projected_schema: self.schema(),
-            schema: self.schema(),
-            projection: None,
-        });
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -130,31 +152,61 @@ impl ExecutionPlan for CoalesceBatchesExec {
         self.input.output_partitioning()
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(CoalesceBatchesExec::new(
-                children[0].clone(),
-                self.target_batch_size,
-            ))),
+            1 => Ok(Arc::new(
+                CoalesceBatchesExec::new(children[0].clone(), self.target_batch_size)
+                    .with_target_batch_bytes(self.target_batch_bytes),
+            )),
             _ => Err(DataFusionError::Internal(
                 "CoalesceBatchesExec wrong number of children".to_string(),
             )),
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(CoalesceBatchesStream {
-            input: self.input.execute(partition).await?,
-            schema: self.input.schema(),
-            target_batch_size: self.target_batch_size,
-            buffer: Vec::new(),
-            buffered_rows: 0,
-            is_closed: false,
-            baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
-        }))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "CoalesceBatchesExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("CoalesceBatchesExec", partition));
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(CoalesceBatchesStream {
+                target_batch_size: self.target_batch_size.min(context.batch_size()),
+                input: self.input.execute(partition, context).await?,
+                schema: self.input.schema(),
+                target_batch_bytes: self.target_batch_bytes,
+                buffer: Vec::new(),
+                buffered_rows: 0,
+                buffered_bytes: 0,
+                is_closed: false,
+                baseline_metrics,
+                coalesced_batches: MetricBuilder::new(&self.metrics)
+                    .counter("coalesced_batches", partition),
+                bytes_out: MetricBuilder::new(&self.metrics)
+                    .counter("bytes_out", partition),
+            }),
+        ))
     }
 
     fn fmt_as(
@@ -168,8 +220,13 @@ impl ExecutionPlan for CoalesceBatchesExec {
                     f,
                     "CoalesceBatchesExec: target_batch_size={}",
                     self.target_batch_size
-                )
+                )?;
+                if let Some(bytes) = self.target_batch_bytes {
+                    write!(f, ", target_batch_bytes={}", bytes)?;
+                }
+                Ok(())
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -189,14 +246,22 @@ struct CoalesceBatchesStream {
     schema: SchemaRef,
     /// Minimum number of rows for coalesces batches
     target_batch_size: usize,
+    /// Maximum number of bytes of coalesced batches, unlimited if `None`
+    target_batch_bytes: Option<usize>,
     /// Buffered batches
     buffer: Vec<RecordBatch>,
     /// Buffered row count
     buffered_rows: usize,
+    /// Buffered byte count
+    buffered_bytes: usize,
     /// Whether the stream has finished returning all of its data or not
     is_closed: bool,
     /// Execution metrics
     baseline_metrics: BaselineMetrics,
+    /// Number of batches produced by concatenating buffered batches
+    coalesced_batches: metrics::Count,
+    /// Number of bytes of the produced batches
+    bytes_out: metrics::Count,
 }
 
 impl Stream for CoalesceBatchesStream {
@@ -230,34 +295,37 @@ impl CoalesceBatchesStream {
             return Poll::Ready(None);
         }
         loop {
+            // return the buffered batches once they reach one of the targets
+            if self.is_full() {
+                return Poll::Ready(Some(self.flush()));
+            }
             let input_batch = self.input.poll_next_unpin(cx);
             match input_batch {
                 Poll::Ready(x) => match x {
-                    Some(Ok(ref batch)) => {
-                        if batch.num_rows() >= self.target_batch_size
-                            && self.buffer.is_empty()
-                        {
-                            return Poll::Ready(Some(Ok(batch.clone())));
-                        } else if batch.num_rows() == 0 {
+                    Some(Ok(batch)) => {
+                        if batch.num_rows() == 0 {
                             // discard empty batches
-                        } else {
-                            // add to the buffered batches
-                            self.buffer.push(batch.clone());
-                            self.buffered_rows += batch.num_rows();
-                            // check to see if we have enough batches yet
-                            if self.buffered_rows >= self.target_batch_size {
-                                // combine the batches and return
-                                let batch = concat_batches(
-                                    &self.schema,
-                                    &self.buffer,
-                                    self.buffered_rows,
-                                )?;
-                                // reset buffer state
-                                self.buffer.clear();
-                                self.buffered_rows = 0;
-                                // return batch
-                                return Poll::Ready(Some(Ok(batch)));
+                            continue;
+                        }
+                        let rows = batch.num_rows();
+                        let bytes = batch_memory_size(&batch);
+                        // return the buffered batches first if the batch would take
+                        // them over the target number of bytes
+                        let output = match self.target_batch_bytes {
+                            Some(target_bytes)
+                                if !self.buffer.is_empty()
+                                    && self.buffered_bytes + bytes > target_bytes =>
+                            {
+                                Some(self.flush())
                             }
+                            _ => None,
+                        };
+                        // add to the buffered batches
+                        self.buffer.push(batch);
+                        self.buffered_rows += rows;
+                        self.buffered_bytes += bytes;
+                        if let Some(output) = output {
+                            return Poll::Ready(Some(output));
                         }
                     }
                     None => {
@@ -267,17 +335,7 @@ impl CoalesceBatchesStream {
                         if self.buffer.is_empty() {
                             return Poll::Ready(None);
                         } else {
-                            // combine the batches and return
-                            let batch = concat_batches(
-                                &self.schema,
-                                &self.buffer,
-                                self.buffered_rows,
-                            )?;
-                            // reset buffer state
-                            self.buffer.clear();
-                            self.buffered_rows = 0;
-                            // return batch
-                            return Poll::Ready(Some(Ok(batch)));
+                            return Poll::Ready(Some(self.flush()));
                         }
                     }
                     other => return Poll::Ready(other),
@@ -286,6 +344,32 @@ impl CoalesceBatchesStream {
             }
         }
     }
+
+    /// Whether the buffered batches reach the target number of rows or bytes
+    fn is_full(&self) -> bool {
+        let full_bytes = match self.target_batch_bytes {
+            Some(bytes) => self.buffered_bytes >= bytes,
+            None => false,
+        };
+        self.buffered_rows >= self.target_batch_size || full_bytes
+    }
+
+    /// Combine the buffered batches and reset the buffer. A single buffered
+    /// batch is returned as is, without copying it.
+    fn flush(&mut self) -> ArrowResult<RecordBatch> {
+        let batch = if self.buffer.len() == 1 {
+            self.buffer.pop().unwrap()
+        } else {
+            self.coalesced_batches.add(1);
+            concat_batches(&self.schema, &self.buffer, self.buffered_rows)?
+        };
+        // reset buffer state
+        self.buffer.clear();
+        self.buffered_rows = 0;
+        self.buffered_bytes = 0;
+        self.bytes_out.add(batch_memory_size(&batch));
+        Ok(batch)
+    }
 }
 
 impl RecordBatchStream for CoalesceBatchesStream {
@@ -305,12 +389,14 @@ pub fn concat_batches(
     }
     let mut arrays = Vec::with_capacity(schema.fields().len());
     for i in 0..schema.fields().len() {
-        let array = concat(
-            &batches
-                .iter()
-                .map(|batch| batch.column(i).as_ref())
-                .collect::<Vec<_>>(),
-        )?;
+        let columns = batches
+            .iter()
+            .map(|batch| batch.column(i).as_ref())
+            .collect::<Vec<_>>();
+        let array = match concat_dictionaries(&columns)? {
+            Some(array) => array,
+            None => concat(&columns)?,
+        };
         arrays.push(array);
     }
     debug!(
@@ -321,11 +407,66 @@ pub fn concat_batches(
     RecordBatch::try_new(schema.clone(), arrays)
 }
 
+/// Concatenates dictionary arrays sharing the same dictionary by concatenating their
+/// keys, so that the result is still dictionary encoded. `None` if the arrays are not
+/// dictionary arrays or if their dictionaries differ.
+fn concat_dictionaries(arrays: &[&dyn Array]) -> ArrowResult<Option<ArrayRef>> {
+    match arrays[0].data_type() {
+        DataType::Dictionary(key_type, _) => match key_type.as_ref() {
+            DataType::Int8 => concat_dictionary_keys::<Int8Type>(arrays),
+            DataType::Int16 => concat_dictionary_keys::<Int16Type>(arrays),
+            DataType::Int32 => concat_dictionary_keys::<Int32Type>(arrays),
+            DataType::Int64 => concat_dictionary_keys::<Int64Type>(arrays),
+            DataType::UInt8 => concat_dictionary_keys::<UInt8Type>(arrays),
+            DataType::UInt16 => concat_dictionary_keys::<UInt16Type>(arrays),
+            DataType::UInt32 => concat_dictionary_keys::<UInt32Type>(arrays),
+            DataType::UInt64 => concat_dictionary_keys::<UInt64Type>(arrays),
+            _ => Ok(None),
+        },
+        _ => Ok(None),
+    }
+}
+
+fn concat_dictionary_keys<K: ArrowDictionaryKeyType>(
+    arrays: &[&dyn Array],
+) -> ArrowResult<Option<ArrayRef>> {
+    let dictionaries = arrays
+        .iter()
+        .map(|array| array.as_any().downcast_ref::<DictionaryArray<K>>().unwrap())
+        .collect::<Vec<_>>();
+    let values = dictionaries[0].values();
+    let same_values = dictionaries.iter().all(|dictionary| {
+        Arc::ptr_eq(dictionary.values(), values)
+            || dictionary.values().data() == values.data()
+    });
+    if !same_values {
+        return Ok(None);
+    }
+
+    let keys = dictionaries
+        .iter()
+        .map(|dictionary| dictionary.keys() as &dyn Array)
+        .collect::<Vec<_>>();
+    let keys = concat(&keys)?;
+    let keys = keys.data();
+    let mut builder = ArrayData::builder(arrays[0].data_type().clone())
+        .len(keys.len())
+        .add_buffer(keys.buffers()[0].clone())
+        .add_child_data(values.data().clone());
+    if let Some(nulls) = keys.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    Ok(Some(Arc::new(DictionaryArray::<K>::from(builder.build()?))))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::physical_plan::{memory::MemoryExec, repartition::RepartitionExec};
-    use arrow::array::UInt32Array;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::{
+        collect, memory::MemoryExec, repartition::RepartitionExec,
+    };
+    use arrow::array::{BinaryArray, StringArray, UInt32Array};
     use arrow::datatypes::{DataType, Field, Schema};
 
     #[tokio::test(flavor = "multi_thread")]
@@ -349,6 +490,104 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn coalesce_up_to_target_bytes() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        // 10 batches of 4 rows of 1000 bytes
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("c0", DataType::Binary, false)]));
+        let value = vec![0u8; 1000];
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(BinaryArray::from(vec![value.as_slice(); 4]))],
+        )?;
+        let batch_bytes = batch_memory_size(&batch);
+        let target_bytes = 3 * batch_bytes + batch_bytes / 2;
+
+        let exec = MemoryExec::try_new(&[vec![batch; 10]], schema.clone(), None)?;
+        let exec = Arc::new(
+            CoalesceBatchesExec::new(Arc::new(exec), 1000)
+                .with_target_batch_bytes(Some(target_bytes)),
+        );
+        let batches = collect(exec.clone(), task_ctx).await?;
+
+        // the batches stop growing before going over the target number of bytes
+        let rows = batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+        assert_eq!(rows, vec![12, 12, 12, 4]);
+
+        let metrics = exec.metrics().unwrap();
+        let counter = |name: &str| {
+            metrics
+                .sum(|metric| metric.value().name() == name)
+                .map(|value| value.as_usize())
+        };
+        // the last batch is returned as is
+        assert_eq!(counter("coalesced_batches"), Some(3));
+        assert_eq!(
+            counter("bytes_out"),
+            Some(batches.iter().map(batch_memory_size).sum())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn large_batches_are_not_copied() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let schema = test_schema();
+        let batch = create_batch(&schema);
+        let exec = MemoryExec::try_new(&[vec![batch.clone()]], schema.clone(), None)?;
+        let exec = Arc::new(CoalesceBatchesExec::new(Arc::new(exec), 4));
+
+        let batches = collect(exec, task_ctx).await?;
+        assert_eq!(batches.len(), 1);
+        assert!(Arc::ptr_eq(batches[0].column(0), batch.column(0)));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn concat_preserves_dictionary_encoding() -> Result<()> {
+        let dictionary_type =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "c0",
+            dictionary_type.clone(),
+            true,
+        )]));
+        let array: DictionaryArray<Int32Type> =
+            vec![Some("a"), None, Some("b"), Some("a")]
+                .into_iter()
+                .collect();
+        let batch = RecordBatch::try_new(schema.clone(), vec![Arc::new(array)])?;
+
+        let batches = vec![batch.clone(), batch.clone(), batch];
+        let batch = concat_batches(&schema, &batches, 12)?;
+
+        assert_eq!(batch.column(0).data_type(), &dictionary_type);
+        let array = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int32Type>>()
+            .unwrap();
+        // the dictionary is shared, not concatenated
+        assert_eq!(array.values().len(), 2);
+        assert_eq!(array.null_count(), 3);
+        let values = array
+            .values()
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let decoded = array
+            .keys()
+            .iter()
+            .map(|key| key.map(|key| values.value(key as usize)))
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, [Some("a"), None, Some("b"), Some("a")].repeat(3));
+
+        Ok(())
+    }
+
     fn test_schema() -> Arc<Schema> {
         Arc::new(Schema::new(vec![Field::new("c0", DataType::UInt32, false)]))
     }
@@ -375,6 +614,7 @@ mod tests {
         input_partitions: Vec<Vec<RecordBatch>>,
         target_batch_size: usize,
     ) -> Result<Vec<Vec<RecordBatch>>> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         // create physical plan
         let exec = MemoryExec::try_new(&input_partitions, schema.clone(), None)?;
         let exec =
@@ -387,7 +627,7 @@ mod tests {
         let mut output_partitions = Vec::with_capacity(output_partition_count);
         for i in 0..output_partition_count {
             // execute this *output* partition and collect all batches
-            let mut stream = exec.execute(i).await?;
+            let mut stream = exec.execute(i, task_ctx.clone()).await?;
             let mut batches = vec![];
             while let Some(result) = stream.next().await {
                 batches.push(result?);
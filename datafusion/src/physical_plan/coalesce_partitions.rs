@@ -34,7 +34,12 @@ use super::common::AbortOnDropMany;
 use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
 use super::{RecordBatchStream, Statistics};
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::{DisplayFormatType, ExecutionPlan, Partitioning};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
+use crate::physical_plan::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, Partitioning,
+};
+use crate::physical_plan::memory::feed_input;
 use crate::physical_plan::LambdaExecPlan;
 
 use super::SendableRecordBatchStream;
@@ -105,7 +110,21 @@ impl ExecutionPlan for CoalescePartitionsExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "CoalescePartitionsExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // CoalescePartitionsExec produces a single partition
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
@@ -121,10 +140,13 @@ impl ExecutionPlan for CoalescePartitionsExec {
             )),
             1 => {
                 // bypass any threading / metrics if there is a single partition
-                self.input.execute(0).await
+                self.input.execute(0, context).await
             }
             _ => {
-                let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+                let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+                    .with_progress(
+                        context.progress_reporter("CoalescePartitionsExec", partition),
+                    );
                 // record the (very) minimal work done so that
                 // elapsed_compute is not reported as 0
                 let elapsed_compute = baseline_metrics.elapsed_compute().clone();
@@ -144,15 +166,20 @@ impl ExecutionPlan for CoalescePartitionsExec {
                         self.input.clone(),
                         sender.clone(),
                         part_i,
+                        context.clone(),
                     ));
                 }
 
-                Ok(Box::pin(MergeStream {
-                    input: receiver,
-                    schema: self.schema(),
-                    baseline_metrics,
-                    drop_helper: AbortOnDropMany(join_handles),
-                }))
+                Ok(instrument_stream(
+                    self,
+                    partition,
+                    Box::pin(MergeStream {
+                        input: receiver,
+                        schema: self.schema(),
+                        baseline_metrics,
+                        drop_helper: AbortOnDropMany(join_handles),
+                    }),
+                ))
             }
         }
     }
@@ -166,6 +193,7 @@ impl ExecutionPlan for CoalescePartitionsExec {
             DisplayFormatType::Default => {
                 write!(f, "CoalescePartitionsExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -180,8 +208,9 @@ impl ExecutionPlan for CoalescePartitionsExec {
 
 #[async_trait]
 impl LambdaExecPlan for CoalescePartitionsExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -222,6 +251,7 @@ mod tests {
 
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
     use crate::physical_plan::{collect, common};
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
@@ -230,6 +260,7 @@ mod tests {
 
     #[tokio::test]
     async fn merge() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let num_partitions = 4;
@@ -259,7 +290,7 @@ mod tests {
         assert_eq!(merge.output_partitioning().partition_count(), 1);
 
         // the result should contain 4 batches (one per input partition)
-        let iter = merge.execute(0).await?;
+        let iter = merge.execute(0, task_ctx).await?;
         let batches = common::collect(iter).await?;
         assert_eq!(batches.len(), num_partitions);
 
@@ -272,6 +303,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -280,7 +312,7 @@ mod tests {
         let coaelesce_partitions_exec =
             Arc::new(CoalescePartitionsExec::new(blocking_exec));
 
-        let fut = collect(coaelesce_partitions_exec);
+        let fut = collect(coaelesce_partitions_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
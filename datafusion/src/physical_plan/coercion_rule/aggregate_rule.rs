@@ -86,6 +86,18 @@ pub(crate) fn coerce_types(
             }
             Ok(input_types.to_vec())
         }
+        AggregateFunction::ApproxMedian | AggregateFunction::Stddev => {
+            // the values are cast to double precision
+            if matches!(input_types[0], DataType::Decimal(_, _))
+                || !is_avg_support_arg_type(&input_types[0])
+            {
+                return Err(DataFusionError::Plan(format!(
+                    "The function {:?} does not support inputs of type {:?}.",
+                    agg_fun, input_types[0]
+                )));
+            }
+            Ok(input_types.to_vec())
+        }
     }
 }
 
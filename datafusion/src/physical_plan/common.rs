@@ -19,6 +19,9 @@
 
 use super::{RecordBatchStream, SendableRecordBatchStream};
 use crate::error::{DataFusionError, Result};
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::memory_manager::MemoryConsumer;
+use crate::execution::task_context::TaskContext;
 use crate::physical_plan::{ColumnStatistics, ExecutionPlan, Statistics};
 use arrow::compute::concat;
 use arrow::datatypes::{Schema, SchemaRef};
@@ -82,6 +85,35 @@ pub async fn collect(stream: SendableRecordBatchStream) -> Result<Vec<RecordBatc
         .map_err(DataFusionError::from)
 }
 
+/// Create a vector of record batches from a stream, reserving the memory of
+/// each batch from `consumer`, if any, before buffering it. Fails with
+/// [`DataFusionError::Cancelled`] once `cancellation` is cancelled.
+pub(crate) async fn collect_reserved(
+    mut stream: SendableRecordBatchStream,
+    mut consumer: Option<&mut MemoryConsumer>,
+    cancellation: &CancellationToken,
+) -> Result<Vec<RecordBatch>> {
+    let mut batches = vec![];
+    while let Some(batch) = stream.next().await {
+        let batch = batch?;
+        cancellation.check()?;
+        if let Some(consumer) = consumer.as_mut() {
+            consumer.try_grow(batch_memory_size(&batch))?;
+        }
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+/// Number of bytes of memory taken by the columns of `batch`
+pub(crate) fn batch_memory_size(batch: &RecordBatch) -> usize {
+    batch
+        .columns()
+        .iter()
+        .map(|column| column.get_array_memory_size())
+        .sum()
+}
+
 /// Combine a slice of record batches into one, or returns None if the slice itself
 /// is empty; all the record batches inside the slice must be of the same schema.
 pub(crate) fn combine_batches(
@@ -163,9 +195,10 @@ pub(crate) fn spawn_execution(
     input: Arc<dyn ExecutionPlan>,
     mut output: mpsc::Sender<ArrowResult<RecordBatch>>,
     partition: usize,
+    context: Arc<TaskContext>,
 ) -> JoinHandle<()> {
     tokio::spawn(async move {
-        let mut stream = match input.execute(partition).await {
+        let mut stream = match input.execute(partition, context).await {
             Err(e) => {
                 // If send fails, plan being torn
                 // down, no place to send the error
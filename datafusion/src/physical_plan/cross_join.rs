@@ -25,23 +25,32 @@ use arrow::datatypes::{Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
-use futures::{Stream, TryStreamExt};
+use futures::Stream;
 
 use super::{
-    coalesce_partitions::CoalescePartitionsExec, join_utils::check_join_is_valid,
+    coalesce_partitions::CoalescePartitionsExec,
+    common::collect_reserved,
+    join_utils::{check_join_is_valid, right_output_partitioning},
+    metrics::{ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
     ColumnStatistics, Statistics,
 };
 use crate::{
     error::{DataFusionError, Result},
+    execution::cancellation::CancellationToken,
+    execution::memory_manager::{MemoryConsumer, MemoryManager},
+    execution::runtime_env::RuntimeEnv,
+    execution::task_context::TaskContext,
     scalar::ScalarValue,
 };
 use async_trait::async_trait;
 use std::time::Instant;
 
 use super::{
-    coalesce_batches::concat_batches, memory::MemoryStream, DisplayFormatType,
-    ExecutionPlan, Partitioning, RecordBatchStream, SendableRecordBatchStream,
+    coalesce_batches::concat_batches, fmt_with_statistics, memory::MemoryStream,
+    trace::instrument_stream, DisplayFormatType, ExecutionPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream,
 };
+use super::memory::feed_input;
 use super::LambdaExecPlan;
 
 use serde::{Deserialize, Serialize};
@@ -60,9 +69,17 @@ pub struct CrossJoinExec {
     right: Arc<dyn ExecutionPlan>,
     /// The schema once the join is applied
     schema: SchemaRef,
-    /// Build-side data
+    /// Build-side data, with its reservation from the memory manager
     #[serde(skip)]
-    build_side: Arc<Mutex<Option<JoinLeftData>>>,
+    build_side: Arc<Mutex<Option<(JoinLeftData, Option<MemoryConsumer>)>>>,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+    /// Memory manager the build side is reserved from, bound by the runtime
+    #[serde(skip)]
+    memory_manager: Option<Arc<MemoryManager>>,
+    /// Token cancelling the build side and the output, bound by the runtime
+    #[serde(skip)]
+    cancellation: CancellationToken,
 }
 
 impl CrossJoinExec {
@@ -86,13 +103,19 @@ impl CrossJoinExec {
         // left then right
         let all_columns = left_fields.chain(right_fields).cloned().collect();
 
-        let schema = Arc::new(Schema::new(all_columns));
+        let mut metadata = right_schema.metadata().clone();
+        metadata.extend(left_schema.metadata().clone());
+
+        let schema = Arc::new(Schema::new_with_metadata(all_columns, metadata));
 
         Ok(CrossJoinExec {
             left,
             right,
             schema,
             build_side: Arc::new(Mutex::new(None)),
+            metrics: ExecutionPlanMetricsSet::new(),
+            memory_manager: None,
+            cancellation: CancellationToken::new(),
         })
     }
 
@@ -131,10 +154,13 @@ impl ExecutionPlan for CrossJoinExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            2 => Ok(Arc::new(CrossJoinExec::try_new(
-                children[0].clone(),
-                children[1].clone(),
-            )?)),
+            2 => {
+                let mut join =
+                    CrossJoinExec::try_new(children[0].clone(), children[1].clone())?;
+                join.memory_manager = self.memory_manager.clone();
+                join.cancellation = self.cancellation.clone();
+                Ok(Arc::new(join))
+            }
             _ => Err(DataFusionError::Internal(
                 "CrossJoinExec wrong number of children".to_string(),
             )),
@@ -142,34 +168,57 @@ impl ExecutionPlan for CrossJoinExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        right_output_partitioning(
+            self.right.output_partitioning(),
+            self.left.schema().fields().len(),
+        )
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "CrossJoinExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // we only want to compute the build side once
         let left_data = {
             let mut build_side = self.build_side.lock().await;
 
             match build_side.as_ref() {
-                Some(stream) => stream.clone(),
+                Some((stream, _)) => stream.clone(),
                 None => {
                     let start = Instant::now();
 
                     // merge all left parts into a single stream
                     let merge = CoalescePartitionsExec::new(self.left.clone());
-                    let stream = merge.execute(0).await?;
-
-                    // Load all batches and count the rows
-                    let (batches, num_rows) = stream
-                        .try_fold((Vec::new(), 0usize), |mut acc, batch| async {
-                            acc.1 += batch.num_rows();
-                            acc.0.push(batch);
-                            Ok(acc)
-                        })
-                        .await?;
+                    let stream = merge.execute(0, context.clone()).await?;
+
+                    // Load all batches and count the rows. The build side
+                    // cannot spill, the join fails if it does not fit in the
+                    // memory pool
+                    let mut consumer = self.memory_manager.as_ref().map(|manager| {
+                        manager.register_consumer(
+                            "CrossJoinExec",
+                            MetricBuilder::new(&self.metrics).global_mem_used(),
+                        )
+                    });
+                    let batches =
+                        collect_reserved(stream, consumer.as_mut(), &self.cancellation)
+                            .await?;
+                    let num_rows: usize =
+                        batches.iter().map(|batch| batch.num_rows()).sum();
                     let merged_batch =
                         concat_batches(&self.left.schema(), &batches, num_rows)?;
-                    *build_side = Some(merged_batch.clone());
+                    *build_side = Some((merged_batch.clone(), consumer));
 
                     debug!(
                         "Built build-side of cross join containing {} rows in {} ms",
@@ -182,28 +231,34 @@ impl ExecutionPlan for CrossJoinExec {
             }
         };
 
-        let stream = self.right.execute(partition).await?;
+        let stream = self.right.execute(partition, context).await?;
 
         if left_data.num_rows() == 0 {
-            return Ok(Box::pin(MemoryStream::try_new(
-                vec![],
-                self.schema.clone(),
-                None,
-            )?));
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(vec![], self.schema.clone(), None)?),
+            ));
         }
 
-        Ok(Box::pin(CrossJoinStream {
-            schema: self.schema.clone(),
-            left_data,
-            right: stream,
-            right_batch: Arc::new(std::sync::Mutex::new(None)),
-            left_index: 0,
-            num_input_batches: 0,
-            num_input_rows: 0,
-            num_output_batches: 0,
-            num_output_rows: 0,
-            join_time: 0,
-        }))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(CrossJoinStream {
+                schema: self.schema.clone(),
+                left_data,
+                right: stream,
+                right_batch: Arc::new(std::sync::Mutex::new(None)),
+                left_index: 0,
+                num_input_batches: 0,
+                num_input_rows: 0,
+                num_output_batches: 0,
+                num_output_rows: 0,
+                join_time: 0,
+                cancellation: self.cancellation.clone(),
+                cancelled: false,
+            }),
+        ))
     }
 
     fn fmt_as(
@@ -215,9 +270,14 @@ impl ExecutionPlan for CrossJoinExec {
             DisplayFormatType::Default => {
                 write!(f, "CrossJoinExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
     fn statistics(&self) -> Statistics {
         stats_cartesian_product(
             self.left.statistics(),
@@ -226,6 +286,16 @@ impl ExecutionPlan for CrossJoinExec {
             self.right.schema().fields().len(),
         )
     }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut join = CrossJoinExec::try_new(self.left.clone(), self.right.clone())?;
+        join.memory_manager = runtime.memory_manager.clone();
+        join.cancellation = runtime.cancellation.clone();
+        Ok(Some(Arc::new(join)))
+    }
 }
 
 /// [left/right]_col_count are required in case the column statistics are None
@@ -294,8 +364,30 @@ fn stats_cartesian_product(
 
 #[async_trait]
 impl LambdaExecPlan for CrossJoinExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.feed_child_batches(0, partitions)
+    }
+
+    fn feed_child_batches(
+        &mut self,
+        child: usize,
+        partitions: Vec<Vec<RecordBatch>>,
+    ) -> Result<()> {
+        match child {
+            0 => {
+                feed_input(&mut self.left, partitions);
+                // the build side is collected again from the fed batches
+                self.build_side = Arc::new(Mutex::new(None));
+            }
+            1 => feed_input(&mut self.right, partitions),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "CrossJoinExec has no input {} to feed",
+                    child
+                )))
+            }
+        }
+        Ok(())
     }
 }
 
@@ -321,6 +413,10 @@ struct CrossJoinStream {
     num_output_rows: usize,
     /// total time for joining probe-side batches to the build-side batches
     join_time: usize,
+    /// Token cancelling the output, checked before each output batch
+    cancellation: CancellationToken,
+    /// Whether the stream ended on a cancellation
+    cancelled: bool,
 }
 
 impl RecordBatchStream for CrossJoinStream {
@@ -363,6 +459,15 @@ impl Stream for CrossJoinStream {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
+        if self.cancelled {
+            return Poll::Ready(None);
+        }
+        // an output batch is built for every row of the left side, the
+        // stream may be pulled for a long time by a cancelled query
+        if let Err(e) = self.cancellation.check() {
+            self.cancelled = true;
+            return Poll::Ready(Some(Err(e.into_arrow_external_error())));
+        }
         if self.left_index > 0 && self.left_index < self.left_data.num_rows() {
             let start = Instant::now();
             let right_batch = {
@@ -18,6 +18,9 @@
 //! DateTime expressions
 use std::sync::Arc;
 
+use super::expressions::{
+    day_time, format_day_time, to_days_millis, MILLISECONDS_IN_DAY,
+};
 use super::ColumnarValue;
 use crate::{
     error::{DataFusionError, Result},
@@ -33,15 +36,17 @@ use arrow::{
 };
 use arrow::{
     array::{
-        Date32Array, Date64Array, TimestampMicrosecondArray, TimestampMillisecondArray,
-        TimestampNanosecondArray, TimestampSecondArray,
+        Date32Array, Date64Array, Int64Array, IntervalDayTimeArray,
+        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
+        TimestampSecondArray,
     },
     compute::kernels::temporal,
-    datatypes::TimeUnit,
+    datatypes::{IntervalUnit, TimeUnit},
     temporal_conversions::timestamp_ns_to_datetime,
 };
 use chrono::prelude::*;
-use chrono::Duration;
+use chrono::{Duration, LocalResult};
+use chrono_tz::Tz;
 use std::borrow::Borrow;
 
 /// given a function `op` that maps a `&str` to a Result of an arrow native type,
@@ -248,14 +253,16 @@ pub fn date_trunc(args: &[ColumnarValue]) -> Result<ColumnarValue> {
             ))
         }
         ColumnarValue::Array(array) => {
+            let tz_opt = match array.data_type() {
+                DataType::Timestamp(_, tz_opt) => tz_opt.clone(),
+                _ => None,
+            };
             let array = array
                 .as_any()
                 .downcast_ref::<TimestampNanosecondArray>()
                 .unwrap();
-            let array = array
-                .iter()
-                .map(f)
-                .collect::<Result<TimestampNanosecondArray>>()?;
+            let values = array.iter().map(f).collect::<Result<Vec<_>>>()?;
+            let array = TimestampNanosecondArray::from_opt_vec(values, tz_opt);
 
             ColumnarValue::Array(Arc::new(array))
         }
@@ -359,6 +366,270 @@ pub fn date_part(args: &[ColumnarValue]) -> Result<ColumnarValue> {
     })
 }
 
+/// Parse the name of a time zone of the IANA database, such as
+/// `America/New_York`
+pub fn parse_time_zone(time_zone: &str) -> Result<Tz> {
+    time_zone
+        .parse::<Tz>()
+        .map_err(|_| DataFusionError::Plan(format!("Invalid time zone '{}'", time_zone)))
+}
+
+/// Number of timestamps of unit `unit` in a second
+fn units_per_second(unit: &TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    }
+}
+
+/// UTC date and time of the timestamp `value` of unit `unit`
+fn to_naive_datetime(value: i64, unit: &TimeUnit) -> Result<NaiveDateTime> {
+    let per_second = units_per_second(unit);
+    let nanos = value.rem_euclid(per_second) * (1_000_000_000 / per_second);
+    NaiveDateTime::from_timestamp_opt(value.div_euclid(per_second), nanos as u32)
+        .ok_or_else(|| {
+            DataFusionError::Execution(format!("Timestamp {} is out of range", value))
+        })
+}
+
+/// Offset from UTC, in seconds, of `time_zone` at the UTC date and time `utc`
+fn utc_offset(time_zone: &Tz, utc: &NaiveDateTime) -> i64 {
+    time_zone
+        .offset_from_utc_datetime(utc)
+        .fix()
+        .local_minus_utc() as i64
+}
+
+/// Offset from UTC, in seconds, of `time_zone` at its wall clock time `local`.
+/// As in PostgreSQL, a time repeated when the clocks are turned back takes the
+/// offset after the transition, and a time skipped when they are turned
+/// forward takes the offset before it.
+fn local_offset(time_zone: &Tz, local: &NaiveDateTime) -> i64 {
+    let offset = match time_zone.from_local_datetime(local) {
+        LocalResult::Single(datetime) => datetime.offset().fix(),
+        LocalResult::Ambiguous(first, second) => first.max(second).offset().fix(),
+        LocalResult::None => time_zone
+            .offset_from_utc_datetime(&(*local - Duration::days(1)))
+            .fix(),
+    };
+    offset.local_minus_utc() as i64
+}
+
+macro_rules! map_timestamp_array {
+    ($ARRAY:expr, $ARRAY_TYPE:ident, $TIME_ZONE:expr, $OP:expr) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        let values = array
+            .iter()
+            .map(|v| v.map(&$OP).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        Arc::new($ARRAY_TYPE::from_opt_vec(values, $TIME_ZONE)) as ArrayRef
+    }};
+}
+
+/// Apply `op` to the timestamps of `arg`, returning timestamps of the same
+/// unit in the time zone `time_zone`
+fn map_timestamps<F>(
+    arg: &ColumnarValue,
+    time_zone: Option<String>,
+    op: F,
+) -> Result<ColumnarValue>
+where
+    F: Fn(i64) -> Result<i64>,
+{
+    let array = match arg {
+        ColumnarValue::Array(array) => array.clone(),
+        ColumnarValue::Scalar(scalar) => scalar.to_array(),
+    };
+    let result = match array.data_type() {
+        DataType::Timestamp(TimeUnit::Second, _) => {
+            map_timestamp_array!(array, TimestampSecondArray, time_zone, op)
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, _) => {
+            map_timestamp_array!(array, TimestampMillisecondArray, time_zone, op)
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, _) => {
+            map_timestamp_array!(array, TimestampMicrosecondArray, time_zone, op)
+        }
+        DataType::Timestamp(TimeUnit::Nanosecond, _) => {
+            map_timestamp_array!(array, TimestampNanosecondArray, time_zone, op)
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Expected a timestamp, got {:?}",
+                other
+            )))
+        }
+    };
+    Ok(match arg {
+        ColumnarValue::Array(_) => ColumnarValue::Array(result),
+        ColumnarValue::Scalar(_) => {
+            ColumnarValue::Scalar(ScalarValue::try_from_array(&result, 0)?)
+        }
+    })
+}
+
+/// The time zone argument `arg` of the function `name`
+fn time_zone_arg<'a>(arg: &'a ColumnarValue, name: &str) -> Result<&'a str> {
+    match arg {
+        ColumnarValue::Scalar(ScalarValue::Utf8(Some(time_zone))) => {
+            parse_time_zone(time_zone)?;
+            Ok(time_zone)
+        }
+        _ => Err(DataFusionError::Execution(format!(
+            "Time zone of `{}` must be non-null scalar Utf8",
+            name
+        ))),
+    }
+}
+
+/// `timestamp AT TIME ZONE time_zone` SQL operator, planned as the function
+/// `at_time_zone`: the wall clock time in `time_zone` of a timestamp with a
+/// time zone, or the timestamp with the time zone `time_zone` of a wall clock
+/// time of `time_zone`
+pub fn at_time_zone(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let name = time_zone_arg(&args[1], "at_time_zone")?;
+    let time_zone = parse_time_zone(name)?;
+    let (unit, tz_opt) = match args[0].data_type() {
+        DataType::Timestamp(unit, tz_opt) => (unit, tz_opt),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported data type {:?} for function at_time_zone",
+                other
+            )))
+        }
+    };
+    let per_second = units_per_second(&unit);
+    match tz_opt {
+        Some(_) => map_timestamps(&args[0], None, |value| {
+            let offset = utc_offset(&time_zone, &to_naive_datetime(value, &unit)?);
+            Ok(value + offset * per_second)
+        }),
+        None => map_timestamps(&args[0], Some(name.to_owned()), |value| {
+            let offset = local_offset(&time_zone, &to_naive_datetime(value, &unit)?);
+            Ok(value - offset * per_second)
+        }),
+    }
+}
+
+/// to_timezone SQL function: the same instant as the timestamp, in
+/// `time_zone`. Timestamps without a time zone are in UTC.
+pub fn to_timezone(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let time_zone = time_zone_arg(&args[1], "to_timezone")?;
+    map_timestamps(&args[0], Some(time_zone.to_owned()), Ok)
+}
+
+/// from_unixtime SQL function: the timestamp in seconds of a number of seconds
+/// since the Unix epoch, in the time zone of the second argument if any, UTC
+/// otherwise
+pub fn from_unixtime(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    let time_zone = match args.get(1) {
+        Some(arg) => time_zone_arg(arg, "from_unixtime")?,
+        None => "UTC",
+    };
+    match &args[0] {
+        ColumnarValue::Array(array) => {
+            let seconds =
+                array.as_any().downcast_ref::<Int64Array>().ok_or_else(|| {
+                    DataFusionError::Internal(format!(
+                        "Unsupported data type {:?} for function from_unixtime",
+                        array.data_type()
+                    ))
+                })?;
+            Ok(ColumnarValue::Array(Arc::new(
+                TimestampSecondArray::from_opt_vec(
+                    seconds.iter().collect(),
+                    Some(time_zone.to_owned()),
+                ),
+            )))
+        }
+        ColumnarValue::Scalar(ScalarValue::Int64(seconds)) => Ok(ColumnarValue::Scalar(
+            ScalarValue::TimestampSecond(*seconds, Some(time_zone.to_owned())),
+        )),
+        other => Err(DataFusionError::Internal(format!(
+            "Unsupported data type {:?} for function from_unixtime",
+            other.data_type()
+        ))),
+    }
+}
+
+/// Apply `op` to the intervals of days and milliseconds of `arg`, the
+/// intervals of months are returned as they are
+fn map_day_times<F>(arg: &ColumnarValue, op: F) -> Result<ColumnarValue>
+where
+    F: Fn(i64) -> Result<i64>,
+{
+    match arg {
+        ColumnarValue::Array(array) => match array.data_type() {
+            DataType::Interval(IntervalUnit::DayTime) => {
+                let array = array
+                    .as_any()
+                    .downcast_ref::<IntervalDayTimeArray>()
+                    .unwrap();
+                let values = array
+                    .iter()
+                    .map(|v| v.map(&op).transpose())
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(ColumnarValue::Array(Arc::new(IntervalDayTimeArray::from(
+                    values,
+                ))))
+            }
+            DataType::Interval(IntervalUnit::YearMonth) => Ok(arg.clone()),
+            other => Err(DataFusionError::Internal(format!(
+                "Expected an interval, got {:?}",
+                other
+            ))),
+        },
+        ColumnarValue::Scalar(ScalarValue::IntervalDayTime(value)) => {
+            Ok(ColumnarValue::Scalar(ScalarValue::IntervalDayTime(
+                value.map(op).transpose()?,
+            )))
+        }
+        ColumnarValue::Scalar(ScalarValue::IntervalYearMonth(_)) => Ok(arg.clone()),
+        other => Err(DataFusionError::Internal(format!(
+            "Expected an interval, got {:?}",
+            other.data_type()
+        ))),
+    }
+}
+
+/// justify_hours SQL function: the interval with its periods of 24 hours as
+/// days, and its days and time of the same sign, e.g. `1 day 06:00:00` for
+/// `30:00:00`
+pub fn justify_hours(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    map_day_times(&args[0], |value| {
+        let (days, millis) = to_days_millis(value);
+        let mut days = days as i64 + millis as i64 / MILLISECONDS_IN_DAY;
+        let mut millis = millis as i64 % MILLISECONDS_IN_DAY;
+        if days > 0 && millis < 0 {
+            days -= 1;
+            millis += MILLISECONDS_IN_DAY;
+        } else if days < 0 && millis > 0 {
+            days += 1;
+            millis -= MILLISECONDS_IN_DAY;
+        }
+        day_time(days, millis)
+    })
+}
+
+/// justify_days SQL function: the interval with its periods of 30 days as
+/// months. The intervals of arrow hold either months or days, so the
+/// intervals of days of 30 days or more are errors, the others are returned
+/// as they are.
+pub fn justify_days(args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    map_day_times(&args[0], |value| {
+        let (days, _) = to_days_millis(value);
+        if days <= -30 || days >= 30 {
+            return Err(DataFusionError::Execution(format!(
+                "justify_days can't convert the interval {} to months and days",
+                format_day_time(value)
+            )));
+        }
+        Ok(value)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -476,4 +747,189 @@ mod tests {
         }
         Ok(())
     }
+
+    fn seconds(date: (i32, u32, u32), time: (u32, u32, u32)) -> Option<i64> {
+        let date = NaiveDate::from_ymd(date.0, date.1, date.2);
+        Some(date.and_hms(time.0, time.1, time.2).timestamp())
+    }
+
+    fn at_time_zone_seconds(
+        values: Vec<Option<i64>>,
+        tz_opt: Option<&str>,
+        time_zone: &str,
+    ) -> Result<(Vec<Option<i64>>, DataType)> {
+        let array = TimestampSecondArray::from_opt_vec(values, tz_opt.map(String::from));
+        let args = [
+            ColumnarValue::Array(Arc::new(array)),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some(time_zone.to_owned()))),
+        ];
+        let result = match at_time_zone(&args)? {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(_) => unreachable!(),
+        };
+        let values = result
+            .as_any()
+            .downcast_ref::<TimestampSecondArray>()
+            .unwrap()
+            .iter()
+            .collect();
+        Ok((values, result.data_type().clone()))
+    }
+
+    #[test]
+    fn at_time_zone_dst_transitions() -> Result<()> {
+        let new_york = "America/New_York";
+        // the clocks of New York go forward from 2:00 EST to 3:00 EDT on
+        // 2021-03-14, and back from 2:00 EDT to 1:00 EST on 2021-11-07
+        let wall_clock = vec![
+            seconds((2021, 3, 14), (1, 59, 59)),
+            // skipped: the offset before the transition
+            seconds((2021, 3, 14), (2, 30, 0)),
+            seconds((2021, 3, 14), (3, 0, 0)),
+            // repeated: the offset after the transition
+            seconds((2021, 11, 7), (1, 30, 0)),
+            seconds((2021, 11, 7), (2, 0, 0)),
+            None,
+        ];
+        let (instants, data_type) =
+            at_time_zone_seconds(wall_clock.clone(), None, new_york)?;
+        assert_eq!(
+            data_type,
+            DataType::Timestamp(TimeUnit::Second, Some(new_york.to_owned()))
+        );
+        let expected = vec![
+            seconds((2021, 3, 14), (6, 59, 59)),
+            seconds((2021, 3, 14), (7, 30, 0)),
+            seconds((2021, 3, 14), (7, 0, 0)),
+            seconds((2021, 11, 7), (6, 30, 0)),
+            seconds((2021, 11, 7), (7, 0, 0)),
+            None,
+        ];
+        assert_eq!(instants, expected);
+
+        // and back to the wall clock, but for the skipped time
+        let (values, data_type) = at_time_zone_seconds(instants, Some("UTC"), new_york)?;
+        assert_eq!(data_type, DataType::Timestamp(TimeUnit::Second, None));
+        let expected = vec![
+            seconds((2021, 3, 14), (1, 59, 59)),
+            seconds((2021, 3, 14), (3, 30, 0)),
+            seconds((2021, 3, 14), (3, 0, 0)),
+            seconds((2021, 11, 7), (1, 30, 0)),
+            seconds((2021, 11, 7), (2, 0, 0)),
+            None,
+        ];
+        assert_eq!(values, expected);
+
+        // the two instants of a repeated time have the same wall clock time
+        let instants = vec![
+            seconds((2021, 11, 7), (5, 30, 0)),
+            seconds((2021, 11, 7), (6, 30, 0)),
+        ];
+        let (values, _) = at_time_zone_seconds(instants, Some("+00:00"), new_york)?;
+        let expected = vec![
+            seconds((2021, 11, 7), (1, 30, 0)),
+            seconds((2021, 11, 7), (1, 30, 0)),
+        ];
+        assert_eq!(values, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn at_time_zone_scalar_nanoseconds() -> Result<()> {
+        let instant = seconds((2021, 7, 1), (12, 0, 0)).unwrap() * 1_000_000_000 + 1;
+        let args = [
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(
+                Some(instant),
+                Some("UTC".to_owned()),
+            )),
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("Asia/Kolkata".to_owned()))),
+        ];
+        match at_time_zone(&args)? {
+            ColumnarValue::Scalar(ScalarValue::TimestampNanosecond(value, None)) => {
+                let offset = (5 * 3600 + 30 * 60) * 1_000_000_000;
+                assert_eq!(value, Some(instant + offset));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn to_timezone_and_from_unixtime() -> Result<()> {
+        let time_zone =
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("Europe/Paris".to_owned())));
+        let timestamp =
+            ColumnarValue::Scalar(ScalarValue::TimestampMillisecond(Some(1_000), None));
+        match to_timezone(&[timestamp, time_zone.clone()])? {
+            ColumnarValue::Scalar(scalar) => assert_eq!(
+                scalar,
+                ScalarValue::TimestampMillisecond(
+                    Some(1_000),
+                    Some("Europe/Paris".to_owned())
+                )
+            ),
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let seconds: ArrayRef = Arc::new(Int64Array::from(vec![Some(1), None]));
+        let result = from_unixtime(&[ColumnarValue::Array(seconds), time_zone])?;
+        let expected = TimestampSecondArray::from_opt_vec(
+            vec![Some(1), None],
+            Some("Europe/Paris".to_owned()),
+        );
+        match result {
+            ColumnarValue::Array(array) => {
+                assert_eq!(array.data_type(), expected.data_type());
+                assert_eq!(array.as_ref(), &expected as &dyn Array);
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        let invalid =
+            ColumnarValue::Scalar(ScalarValue::Utf8(Some("Mars/Olympus".to_owned())));
+        let seconds = ColumnarValue::Scalar(ScalarValue::Int64(Some(1)));
+        let err = from_unixtime(&[seconds, invalid]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Invalid time zone 'Mars/Olympus'"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn justify_intervals() -> Result<()> {
+        let interval = |days, millis| day_time(days, millis).unwrap();
+        let intervals: ArrayRef = Arc::new(IntervalDayTimeArray::from(vec![
+            Some(interval(0, 108_000_000)),
+            Some(interval(2, -3_600_000)),
+            Some(interval(-1, 90_000_000)),
+            None,
+        ]));
+        let expected = IntervalDayTimeArray::from(vec![
+            Some(interval(1, 21_600_000)),
+            Some(interval(1, 82_800_000)),
+            Some(interval(0, 3_600_000)),
+            None,
+        ]);
+        match justify_hours(&[ColumnarValue::Array(intervals.clone())])? {
+            ColumnarValue::Array(array) => {
+                assert_eq!(array.as_ref(), &expected as &dyn Array)
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+
+        match justify_days(&[ColumnarValue::Array(intervals)])? {
+            ColumnarValue::Array(array) => assert_eq!(array.len(), 4),
+            other => panic!("Unexpected result {:?}", other),
+        }
+        let month =
+            ColumnarValue::Scalar(ScalarValue::IntervalDayTime(Some(interval(30, 0))));
+        let err = justify_days(&[month]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: justify_days can't convert the interval 30 days to \
+             months and days"
+        );
+        Ok(())
+    }
 }
@@ -20,16 +20,48 @@
 //! format
 
 use std::fmt;
+use std::time::Duration;
 
 use crate::logical_plan::{StringifiedPlan, ToStringifiedPlan};
 
-use super::{accept, ExecutionPlan, ExecutionPlanVisitor};
+use super::lambda_input::LambdaInputExec;
+use super::stage::{stage_input_id, QueryStage};
+use super::{accept, ExecutionPlan, ExecutionPlanVisitor, Statistics};
 
 /// Options for controlling how each [`ExecutionPlan`] should format itself
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DisplayFormatType {
     /// Default, compact format. Example: `FilterExec: c12 < 10.0`
     Default,
+    /// Default format followed by the statistics of the operator, shown by
+    /// `EXPLAIN VERBOSE`. Example:
+    /// `FilterExec: c12 < 10.0, statistics=[rows=unknown, bytes=unknown, exact=false]`
+    Verbose,
+}
+
+/// Format `plan` in the [`DisplayFormatType::Default`] type followed by its
+/// statistics, the [`DisplayFormatType::Verbose`] type of most operators
+pub fn fmt_with_statistics(
+    plan: &dyn ExecutionPlan,
+    f: &mut fmt::Formatter,
+) -> fmt::Result {
+    plan.fmt_as(DisplayFormatType::Default, f)?;
+    write!(f, ", statistics=[{}]", plan.statistics())
+}
+
+impl fmt::Display for Statistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn fmt_value(value: Option<usize>) -> String {
+            value.map_or_else(|| "unknown".to_owned(), |v| v.to_string())
+        }
+        write!(
+            f,
+            "rows={}, bytes={}, exact={}",
+            fmt_value(self.num_rows),
+            fmt_value(self.total_byte_size),
+            self.is_exact
+        )
+    }
 }
 
 /// Wraps an `ExecutionPlan` with various ways to display this plan
@@ -37,6 +69,8 @@ pub struct DisplayableExecutionPlan<'a> {
     inner: &'a dyn ExecutionPlan,
     /// How to show metrics
     show_metrics: ShowMetrics,
+    /// How to format each node
+    format: DisplayFormatType,
 }
 
 impl<'a> DisplayableExecutionPlan<'a> {
@@ -46,6 +80,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::None,
+            format: DisplayFormatType::Default,
         }
     }
 
@@ -56,6 +91,7 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::Aggregated,
+            format: DisplayFormatType::Default,
         }
     }
 
@@ -66,29 +102,36 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Self {
             inner,
             show_metrics: ShowMetrics::Full,
+            format: DisplayFormatType::Default,
         }
     }
 
+    /// Format each node in the type `format` rather than in the
+    /// [`DisplayFormatType::Default`] type
+    pub fn with_format(mut self, format: DisplayFormatType) -> Self {
+        self.format = format;
+        self
+    }
+
     /// Return a `format`able structure that produces a single line
     /// per node.
     ///
     /// ```text
     /// ProjectionExec: expr=[a]
-    ///   CoalesceBatchesExec: target_batch_size=4096
-    ///     FilterExec: a < 5
-    ///       RepartitionExec: partitioning=RoundRobinBatch(16)
-    ///         CsvExec: source=...",
+    ///   FilterExec: a < 5
+    ///     RepartitionExec: partitioning=RoundRobinBatch(16)
+    ///       CsvExec: source=...",
     /// ```
     pub fn indent(&self) -> impl fmt::Display + 'a {
         struct Wrapper<'a> {
             plan: &'a dyn ExecutionPlan,
             show_metrics: ShowMetrics,
+            format: DisplayFormatType,
         }
         impl<'a> fmt::Display for Wrapper<'a> {
             fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                let t = DisplayFormatType::Default;
                 let mut visitor = IndentVisitor {
-                    t,
+                    t: self.format,
                     f,
                     indent: 0,
                     show_metrics: self.show_metrics,
@@ -99,8 +142,111 @@ impl<'a> DisplayableExecutionPlan<'a> {
         Wrapper {
             plan: self.inner,
             show_metrics: self.show_metrics,
+            format: self.format,
+        }
+    }
+
+    /// Return a `format`able structure that produces the single line of the
+    /// root node, without its inputs.
+    ///
+    /// ```text
+    /// FilterExec: a < 5
+    /// ```
+    pub fn one_line(&self) -> impl fmt::Display + 'a {
+        struct Wrapper<'a> {
+            plan: &'a dyn ExecutionPlan,
+            format: DisplayFormatType,
+        }
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.plan.fmt_as(self.format, f)
+            }
+        }
+        Wrapper {
+            plan: self.inner,
+            format: self.format,
+        }
+    }
+
+    /// Return a `format`able structure that produces a graph in the `DOT`
+    /// language, which can be rendered by [graphviz](https://graphviz.org/).
+    /// See [`graphviz`] for its content.
+    pub fn graphviz(&self) -> impl fmt::Display + 'a {
+        struct Wrapper<'a> {
+            plan: &'a dyn ExecutionPlan,
+            format: DisplayFormatType,
+        }
+        impl<'a> fmt::Display for Wrapper<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                writeln!(f, "digraph {{")?;
+                let mut visitor = GraphvizVisitor::new(f, self.format);
+                accept(self.plan, &mut visitor)?;
+                writeln!(f, "}}")
+            }
+        }
+        Wrapper {
+            plan: self.inner,
+            format: self.format,
+        }
+    }
+}
+
+/// Format `plan` as a graph in the `DOT` language, which can be rendered by
+/// [graphviz](https://graphviz.org/), for example with
+/// `dot -Tpdf < /tmp/plan.dot > /tmp/plan.pdf`.
+///
+/// The graph has a node per operator, labeled with its
+/// [`DisplayFormatType::Default`] format followed by its number of output
+/// rows and compute time once executed, and an edge from each operator to
+/// each of its inputs, labeled with the output partitioning of the input.
+pub fn graphviz(plan: &dyn ExecutionPlan) -> String {
+    DisplayableExecutionPlan::new(plan).graphviz().to_string()
+}
+
+/// Format `stages`, as split by
+/// [`split_into_stages`](super::stage::split_into_stages), as a graph in the
+/// `DOT` language like [`graphviz`] does, with the nodes of each stage in
+/// their own cluster. The [`LambdaInputExec`] standing for the output of a
+/// stage has an edge to the root of that stage.
+pub fn graphviz_stages(stages: &[QueryStage]) -> String {
+    struct Wrapper<'a>(&'a [QueryStage]);
+    impl<'a> fmt::Display for Wrapper<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            writeln!(f, "digraph {{")?;
+            let mut visitor = GraphvizVisitor::new(f, DisplayFormatType::Default);
+            let mut stage_roots = vec![];
+            for stage in self.0 {
+                writeln!(visitor.f, "  subgraph cluster_stage_{}", stage.stage_id)?;
+                writeln!(visitor.f, "  {{")?;
+                writeln!(
+                    visitor.f,
+                    "    graph[label={}]",
+                    quoted(&[format!("Stage {}", stage.stage_id)])
+                )?;
+                stage_roots.push((stage, visitor.id_gen + 1));
+                accept(stage.plan.as_ref(), &mut visitor)?;
+                writeln!(visitor.f, "  }}")?;
+            }
+            for (stage, root_id) in stage_roots {
+                let input_id = stage_input_id(stage.stage_id);
+                for (node_id, _) in visitor
+                    .stage_inputs
+                    .iter()
+                    .filter(|(_, id)| *id == input_id)
+                {
+                    writeln!(
+                        visitor.f,
+                        "  {} -> {} [arrowhead=none, arrowtail=normal, dir=back, label={}]",
+                        node_id,
+                        root_id,
+                        quoted(&[format!("{:?}", stage.output_partitioning)])
+                    )?;
+                }
+            }
+            writeln!(visitor.f, "}}")
         }
     }
+    Wrapper(stages).to_string()
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -168,6 +314,93 @@ impl<'a, 'b> ExecutionPlanVisitor for IndentVisitor<'a, 'b> {
     }
 }
 
+/// Formats plans as graphs in the `DOT` language, with a node per operator
+/// and an edge from each operator to each of its inputs.
+struct GraphvizVisitor<'a, 'b> {
+    /// Write to this formatter
+    f: &'a mut fmt::Formatter<'b>,
+    /// How to format each node
+    t: DisplayFormatType,
+    /// Identifier of the last node written
+    id_gen: usize,
+    /// Identifiers of the ancestors of the visited node
+    parent_ids: Vec<usize>,
+    /// Identifiers of the [`LambdaInputExec`] nodes written, with the
+    /// identifiers of the inputs they stand for
+    stage_inputs: Vec<(usize, String)>,
+}
+
+impl<'a, 'b> GraphvizVisitor<'a, 'b> {
+    fn new(f: &'a mut fmt::Formatter<'b>, t: DisplayFormatType) -> Self {
+        Self {
+            f,
+            t,
+            id_gen: 0,
+            parent_ids: vec![],
+            stage_inputs: vec![],
+        }
+    }
+}
+
+impl<'a, 'b> ExecutionPlanVisitor for GraphvizVisitor<'a, 'b> {
+    type Error = fmt::Error;
+    fn pre_visit(
+        &mut self,
+        plan: &dyn ExecutionPlan,
+    ) -> std::result::Result<bool, Self::Error> {
+        self.id_gen += 1;
+        let id = self.id_gen;
+
+        struct Node<'a>(&'a dyn ExecutionPlan, DisplayFormatType);
+        impl<'a> fmt::Display for Node<'a> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt_as(self.1, f)
+            }
+        }
+        let mut label = vec![Node(plan, self.t).to_string()];
+        if let Some(metrics) = plan.metrics() {
+            if let Some(output_rows) = metrics.output_rows() {
+                label.push(format!("output_rows={}", output_rows));
+            }
+            if let Some(elapsed_compute) = metrics.elapsed_compute() {
+                let elapsed_compute = Duration::from_nanos(elapsed_compute as u64);
+                label.push(format!("elapsed_compute={:?}", elapsed_compute));
+            }
+        }
+        writeln!(self.f, "    {}[shape=box label={}]", id, quoted(&label))?;
+
+        if let Some(parent_id) = self.parent_ids.last() {
+            writeln!(
+                self.f,
+                "    {} -> {} [arrowhead=none, arrowtail=normal, dir=back, label={}]",
+                parent_id,
+                id,
+                quoted(&[format!("{:?}", plan.output_partitioning())])
+            )?;
+        }
+        if let Some(input) = plan.as_any().downcast_ref::<LambdaInputExec>() {
+            self.stage_inputs.push((id, input.id().to_owned()));
+        }
+
+        self.parent_ids.push(id);
+        Ok(true)
+    }
+
+    fn post_visit(&mut self, _plan: &dyn ExecutionPlan) -> Result<bool, Self::Error> {
+        self.parent_ids.pop();
+        Ok(true)
+    }
+}
+
+/// Quote `lines` as a `DOT` string, one line per element
+fn quoted(lines: &[String]) -> String {
+    let lines = lines
+        .iter()
+        .map(|line| line.replace('\\', "\\\\").replace('"', "\\\""))
+        .collect::<Vec<_>>();
+    format!("\"{}\"", lines.join("\\n"))
+}
+
 impl<'a> ToStringifiedPlan for DisplayableExecutionPlan<'a> {
     fn to_stringified(
         &self,
@@ -176,3 +409,168 @@ impl<'a> ToStringifiedPlan for DisplayableExecutionPlan<'a> {
         StringifiedPlan::new(plan_type, self.indent().to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    use super::*;
+    use crate::error::Result;
+    use crate::logical_plan::{JoinType, Operator};
+    use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+    use crate::physical_plan::expressions::{binary, col, lit, Column};
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+    use crate::physical_plan::limit::GlobalLimitExec;
+    use crate::physical_plan::repartition::RepartitionExec;
+    use crate::physical_plan::stage::split_into_stages;
+    use crate::physical_plan::{displayable, ColumnStatistics, Partitioning};
+    use crate::scalar::ScalarValue;
+    use crate::test::exec::StatisticsExec;
+
+    /// `num_rows` rows of two Int32 columns `a` and `b`, where `a` is
+    /// between 0 and 100
+    fn statistics_exec(a: &str, b: &str, num_rows: usize) -> Arc<dyn ExecutionPlan> {
+        let schema = Schema::new(vec![
+            Field::new(a, DataType::Int32, false),
+            Field::new(b, DataType::Int32, false),
+        ]);
+        let statistics = Statistics {
+            num_rows: Some(num_rows),
+            total_byte_size: Some(num_rows * 8),
+            column_statistics: Some(vec![
+                ColumnStatistics {
+                    null_count: Some(0),
+                    min_value: Some(ScalarValue::Int32(Some(0))),
+                    max_value: Some(ScalarValue::Int32(Some(100))),
+                    distinct_count: None,
+                },
+                ColumnStatistics::default(),
+            ]),
+            is_exact: true,
+        };
+        Arc::new(StatisticsExec::new(statistics, schema))
+    }
+
+    /// `LIMIT 10` of the join of 1000 rows filtered by `a < 25` with 100 rows
+    fn filter_join_limit() -> Result<Arc<dyn ExecutionPlan>> {
+        let left = statistics_exec("a", "b", 1000);
+        let left_schema = left.schema();
+        let predicate = binary(
+            col("a", &left_schema)?,
+            Operator::Lt,
+            lit(ScalarValue::Int32(Some(25))),
+            &left_schema,
+        )?;
+        let filter = Arc::new(FilterExec::try_new(predicate, left)?);
+        let join = HashJoinExec::try_new(
+            filter,
+            statistics_exec("c", "d", 100),
+            vec![(Column::new("a", 0), Column::new("c", 0))],
+            &JoinType::Inner,
+            PartitionMode::CollectLeft,
+            &false,
+        )?;
+        Ok(Arc::new(GlobalLimitExec::new(Arc::new(join), 10)))
+    }
+
+    #[test]
+    fn verbose_format_shows_statistics() -> Result<()> {
+        let plan = filter_join_limit()?;
+
+        let formatted = displayable(plan.as_ref())
+            .with_format(DisplayFormatType::Verbose)
+            .indent()
+            .to_string();
+        let expected = vec![
+            "GlobalLimitExec: limit=10, statistics=[rows=unknown, bytes=unknown, exact=false]",
+            "  HashJoinExec: mode=CollectLeft, join_type=Inner, on=[(Column { name: \"a\", index: 0 }, Column { name: \"c\", index: 0 })], statistics=[rows=unknown, bytes=unknown, exact=false]",
+            "    FilterExec: a@0 < 25, statistics=[rows=250, bytes=2000, exact=false]",
+            "      StatisticsExec: col_count=2, row_count=Some(1000), statistics=[rows=1000, bytes=8000, exact=true]",
+            "    StatisticsExec: col_count=2, row_count=Some(100), statistics=[rows=100, bytes=800, exact=true]",
+        ];
+        let actual: Vec<&str> = formatted.trim().lines().collect();
+        assert_eq!(
+            expected, actual,
+            "\n\nexpected:\n\n{:#?}\nactual:\n\n{:#?}\n\n",
+            expected, actual
+        );
+
+        // the default format does not show them
+        let formatted = displayable(plan.as_ref()).indent().to_string();
+        assert!(!formatted.contains("statistics="), "{}", formatted);
+        Ok(())
+    }
+
+    /// Check that `dot` is a single graph with balanced braces, and return
+    /// the labels of its nodes and the number of its edges
+    fn parse_dot(dot: &str) -> (Vec<String>, usize) {
+        let lines: Vec<&str> = dot.trim().lines().collect();
+        assert_eq!(lines.first(), Some(&"digraph {"), "{}", dot);
+        assert_eq!(lines.last(), Some(&"}"), "{}", dot);
+        assert_eq!(
+            dot.matches('{').count(),
+            dot.matches('}').count(),
+            "{}",
+            dot
+        );
+
+        let nodes = lines
+            .iter()
+            .filter_map(|line| line.split_once("[shape=box label=\""))
+            .map(|(_, label)| label.trim_end_matches("\"]").to_owned())
+            .collect();
+        let edges = lines.iter().filter(|line| line.contains(" -> ")).count();
+        (nodes, edges)
+    }
+
+    #[test]
+    fn graphviz_join() -> Result<()> {
+        let plan = filter_join_limit()?;
+
+        let dot = graphviz(plan.as_ref());
+        let (nodes, edges) = parse_dot(&dot);
+        let expected = vec![
+            "GlobalLimitExec: limit=10",
+            "HashJoinExec: mode=CollectLeft, join_type=Inner, on=[(Column { name: \\\"a\\\", index: 0 }, Column { name: \\\"c\\\", index: 0 })]",
+            "FilterExec: a@0 < 25",
+            "StatisticsExec: col_count=2, row_count=Some(1000)",
+            "StatisticsExec: col_count=2, row_count=Some(100)",
+        ];
+        assert_eq!(expected, nodes, "{}", dot);
+        assert_eq!(edges, 4, "{}", dot);
+        assert!(
+            dot.contains("    2 -> 5 [arrowhead=none, arrowtail=normal, dir=back, label=\"UnknownPartitioning(2)\"]"),
+            "{}",
+            dot
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn graphviz_clusters_stages() -> Result<()> {
+        let input = statistics_exec("a", "b", 100);
+        let repartition =
+            RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(3))?;
+        let plan = Arc::new(CoalescePartitionsExec::new(Arc::new(repartition)));
+        let stages = split_into_stages(plan)?;
+
+        let dot = graphviz_stages(&stages);
+        let (nodes, edges) = parse_dot(&dot);
+        assert_eq!(nodes.len(), 4, "{}", dot);
+        assert_eq!(nodes[0], "RepartitionExec: partitioning=RoundRobinBatch(3)");
+        assert_eq!(nodes[2], "CoalescePartitionsExec");
+        // an edge in each stage, and one between the stages
+        assert_eq!(edges, 3, "{}", dot);
+        assert!(dot.contains("  subgraph cluster_stage_0"), "{}", dot);
+        assert!(dot.contains("  subgraph cluster_stage_1"), "{}", dot);
+        assert!(
+            dot.contains("  4 -> 1 [arrowhead=none, arrowtail=normal, dir=back, label=\"RoundRobinBatch(3)\"]"),
+            "{}",
+            dot
+        );
+        Ok(())
+    }
+}
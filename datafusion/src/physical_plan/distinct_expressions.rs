@@ -22,6 +22,7 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::sync::Arc;
 
+use arrow::array::{Array, ArrayRef, ListArray};
 use arrow::datatypes::{DataType, Field};
 
 use ahash::RandomState;
@@ -169,6 +170,41 @@ impl Accumulator for DistinctCountAccumulator {
         })
     }
 
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        }
+
+        let lists = states
+            .iter()
+            .map(|state| {
+                state.as_any().downcast_ref::<ListArray>().ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "Invalid count distinct state: expected a List, got {:?}",
+                        state.data_type()
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        (0..lists[0].len()).try_for_each(|row_index| {
+            if lists.iter().any(|list| list.is_null(row_index)) {
+                return Ok(());
+            }
+            let col_values = lists
+                .iter()
+                .map(|list| list.value(row_index))
+                .collect::<Vec<_>>();
+            (0..col_values[0].len()).try_for_each(|value_index| {
+                let row_values = col_values
+                    .iter()
+                    .map(|values| ScalarValue::try_from_array(values, value_index))
+                    .collect::<Result<Vec<_>>>()?;
+                self.update(&row_values)
+            })
+        })
+    }
+
     fn state(&self) -> Result<Vec<ScalarValue>> {
         let mut cols_out = self
             .state_data_types
@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Filters of the probe side of a hash join computed at runtime from the
+//! keys of its build side, see [`DynamicFilterSource`]
+
+use std::sync::{Arc, RwLock};
+
+use arrow::array::ArrayRef;
+
+use crate::logical_plan::{lit, Column, Expr};
+use crate::physical_plan::expressions::{MaxAccumulator, MinAccumulator};
+use crate::physical_plan::Accumulator;
+use crate::scalar::ScalarValue;
+
+/// A channel from a [`HashJoinExec`](super::hash_join::HashJoinExec) to a
+/// scan of its probe side, established at planning time by the
+/// [`DynamicFilterPushDown`](crate::physical_optimizer::dynamic_filter_push_down::DynamicFilterPushDown)
+/// rule.
+///
+/// Once it has built its hash table, the join publishes the range of each of
+/// its keys on the build side, and the scan reads the resulting filter when
+/// opening a file to skip the row groups whose keys are out of these ranges:
+/// their rows can't match. A scan opening a file before the filter is
+/// published reads the whole file. Clones share the published filter.
+#[derive(Debug, Clone)]
+pub struct DynamicFilterSource {
+    /// The columns of the scan compared with the keys, in the order of the
+    /// keys
+    columns: Vec<String>,
+    /// The filter published by the join
+    filter: Arc<RwLock<Option<Expr>>>,
+}
+
+impl DynamicFilterSource {
+    /// Create a source of filters on the columns `columns` of a scan, in the
+    /// order of the keys of the join
+    pub fn new(columns: Vec<String>) -> Self {
+        Self {
+            columns,
+            filter: Default::default(),
+        }
+    }
+
+    /// The columns of the scan compared with the keys of the join
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Publish the filter bounding each column of the scan by the minimum
+    /// and the maximum of the values of `keys`, the keys of the build side.
+    /// The keys without values, or of a type without ordering, are not
+    /// filtered on.
+    pub fn publish(&self, keys: &[ArrayRef]) {
+        let filter = self
+            .columns
+            .iter()
+            .zip(keys)
+            .filter_map(|(column, key)| {
+                let (min, max) = bounds(key)?;
+                let column = Expr::Column(Column::from_name(column.as_str()));
+                Some(column.clone().gt_eq(lit(min)).and(column.lt_eq(lit(max))))
+            })
+            .reduce(Expr::and);
+        *self.filter.write().unwrap() = filter;
+    }
+
+    /// The filter published by the join, if any yet
+    pub fn filter(&self) -> Option<Expr> {
+        self.filter.read().unwrap().clone()
+    }
+}
+
+/// The minimum and the maximum of the values of `array`, if any
+fn bounds(array: &ArrayRef) -> Option<(ScalarValue, ScalarValue)> {
+    let mut min = MinAccumulator::try_new(array.data_type()).ok()?;
+    let mut max = MaxAccumulator::try_new(array.data_type()).ok()?;
+    min.update_batch(&[array.clone()]).ok()?;
+    max.update_batch(&[array.clone()]).ok()?;
+    let (min, max) = (min.evaluate().ok()?, max.evaluate().ok()?);
+    if min.is_null() || max.is_null() {
+        None
+    } else {
+        Some((min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logical_plan::col;
+    use arrow::array::{Int32Array, StringArray};
+
+    #[test]
+    fn publish_key_ranges() {
+        let source = DynamicFilterSource::new(vec!["a".to_owned(), "b".to_owned()]);
+        assert_eq!(source.filter(), None);
+
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(7), None, Some(3)]));
+        let b: ArrayRef = Arc::new(StringArray::from(vec![None, None, None]));
+        source.clone().publish(&[a, b]);
+        assert_eq!(
+            source.filter(),
+            Some(col("a").gt_eq(lit(3)).and(col("a").lt_eq(lit(7))))
+        );
+    }
+}
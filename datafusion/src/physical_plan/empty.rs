@@ -21,11 +21,14 @@ use std::any::Any;
 use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    memory::MemoryStream, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    fmt_with_statistics, memory::MemoryStream, DisplayFormatType, Distribution,
+    ExecutionPlan, Partitioning,
 };
 use crate::physical_plan::LambdaExecPlan;
-use arrow::array::NullArray;
+use arrow::array::{new_null_array, NullArray};
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 
@@ -59,7 +62,17 @@ impl EmptyExec {
     }
 
     fn data(&self) -> Result<Vec<RecordBatch>> {
-        let batch = if self.produce_one_row {
+        let batch = if self.produce_one_row && !self.schema.fields().is_empty() {
+            // a row of nulls of the schema
+            let columns = self
+                .schema
+                .fields()
+                .iter()
+                .map(|field| new_null_array(field.data_type(), 1))
+                .collect();
+            vec![RecordBatch::try_new(self.schema.clone(), columns)?]
+        } else if self.produce_one_row {
+            // a record batch without columns can't hold a row
             vec![RecordBatch::try_new(
                 Arc::new(Schema::new(vec![Field::new(
                     "placeholder",
@@ -117,7 +130,21 @@ impl ExecutionPlan for EmptyExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "EmptyExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // GlobalLimitExec has a single output partition
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
@@ -126,11 +153,15 @@ impl ExecutionPlan for EmptyExec {
             )));
         }
 
-        Ok(Box::pin(MemoryStream::try_new(
-            self.data()?,
-            self.schema.clone(),
-            None,
-        )?))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(MemoryStream::try_new(
+                self.data()?,
+                self.schema.clone(),
+                None,
+            )?),
+        ))
     }
 
     fn fmt_as(
@@ -142,6 +173,7 @@ impl ExecutionPlan for EmptyExec {
             DisplayFormatType::Default => {
                 write!(f, "EmptyExec: produce_one_row={}", self.produce_one_row)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -155,25 +187,29 @@ impl ExecutionPlan for EmptyExec {
 
 #[async_trait]
 impl LambdaExecPlan for EmptyExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "EmptyExec has no input to feed".to_owned(),
+        ))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::context::ExecutionContext;
     use crate::{physical_plan::common, test_util};
 
     #[tokio::test]
     async fn empty() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let empty = EmptyExec::new(false, schema.clone());
         assert_eq!(empty.schema(), schema);
 
         // we should have no results
-        let iter = empty.execute(0).await?;
+        let iter = empty.execute(0, task_ctx).await?;
         let batches = common::collect(iter).await?;
         assert!(batches.is_empty());
 
@@ -198,25 +234,34 @@ mod tests {
 
     #[tokio::test]
     async fn invalid_execute() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
         let empty = EmptyExec::new(false, schema);
 
         // ask for the wrong partition
-        assert!(empty.execute(1).await.is_err());
-        assert!(empty.execute(20).await.is_err());
+        assert!(empty.execute(1, task_ctx.clone()).await.is_err());
+        assert!(empty.execute(20, task_ctx).await.is_err());
         Ok(())
     }
 
     #[tokio::test]
     async fn produce_one_row() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
         let empty = EmptyExec::new(true, schema);
 
-        let iter = empty.execute(0).await?;
+        let iter = empty.execute(0, task_ctx.clone()).await?;
         let batches = common::collect(iter).await?;
 
         // should have one item
         assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 1);
+        assert_eq!(batches[0].schema(), empty.schema());
+
+        // with a placeholder column without a schema
+        let empty = EmptyExec::new(true, Arc::new(Schema::empty()));
+        let batches = common::collect(empty.execute(0, task_ctx).await?).await?;
+        assert_eq!(batches[0].num_rows(), 1);
 
         Ok(())
     }
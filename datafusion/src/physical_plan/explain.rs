@@ -24,10 +24,12 @@ use crate::{
     error::{DataFusionError, Result},
     logical_plan::StringifiedPlan,
     physical_plan::{
-        common::SizedRecordBatchStream, DisplayFormatType, ExecutionPlan, Partitioning,
-        Statistics,
+        common::SizedRecordBatchStream, fmt_with_statistics, DisplayFormatType,
+        ExecutionPlan, Partitioning, Statistics,
     },
 };
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::LambdaExecPlan;
 use arrow::{array::StringBuilder, datatypes::SchemaRef, record_batch::RecordBatch};
 
@@ -36,36 +38,50 @@ use async_trait::async_trait;
 
 use serde::{Deserialize, Serialize};
 
-/// Explain execution plan operator. This operator contains the string
+/// Explain execution plan operator. This operator renders the string
 /// values of the various plans it has when it is created, and passes
 /// them to its output.
+///
+/// Only the rendered text is kept, so that the operator can be serialized
+/// and executed wherever the plan is shipped.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExplainExec {
     /// The schema that this exec plan node outputs
     schema: SchemaRef,
-    /// The strings to be printed
-    stringified_plans: Vec<StringifiedPlan>,
-    /// control which plans to print
-    verbose: bool,
+    /// The type and the text of each plan to print
+    plans: Vec<(String, String)>,
 }
 
 impl ExplainExec {
-    /// Create a new ExplainExec
+    /// Create a new ExplainExec printing the `stringified_plans` to display,
+    /// which depend on `verbose`
     pub fn new(
         schema: SchemaRef,
         stringified_plans: Vec<StringifiedPlan>,
         verbose: bool,
     ) -> Self {
-        ExplainExec {
-            schema,
-            stringified_plans,
-            verbose,
+        let plans_to_print = stringified_plans
+            .iter()
+            .filter(|s| s.should_display(verbose));
+
+        // Identify plans that are not changed
+        let mut prev: Option<&StringifiedPlan> = None;
+        let mut plans = vec![];
+        for p in plans_to_print {
+            let text = match prev {
+                Some(prev) if !should_show(prev, p) => "SAME TEXT AS ABOVE".to_string(),
+                Some(_) | None => p.plan.to_string(),
+            };
+            plans.push((p.plan_type.to_string(), text));
+            prev = Some(p);
         }
+
+        ExplainExec { schema, plans }
     }
 
-    /// The strings to be printed
-    pub fn stringified_plans(&self) -> &[StringifiedPlan] {
-        &self.stringified_plans
+    /// The type and the text of each plan to print
+    pub fn plans(&self) -> &[(String, String)] {
+        &self.plans
     }
 }
 
@@ -109,7 +125,21 @@ impl ExecutionPlan for ExplainExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ExplainExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
                 "ExplainExec invalid partition {}",
@@ -117,28 +147,11 @@ impl ExecutionPlan for ExplainExec {
             )));
         }
 
-        let mut type_builder = StringBuilder::new(self.stringified_plans.len());
-        let mut plan_builder = StringBuilder::new(self.stringified_plans.len());
-
-        let plans_to_print = self
-            .stringified_plans
-            .iter()
-            .filter(|s| s.should_display(self.verbose));
-
-        // Identify plans that are not changed
-        let mut prev: Option<&StringifiedPlan> = None;
-
-        for p in plans_to_print {
-            type_builder.append_value(p.plan_type.to_string())?;
-            match prev {
-                Some(prev) if !should_show(prev, p) => {
-                    plan_builder.append_value("SAME TEXT AS ABOVE")?;
-                }
-                Some(_) | None => {
-                    plan_builder.append_value(&*p.plan)?;
-                }
-            }
-            prev = Some(p);
+        let mut type_builder = StringBuilder::new(self.plans.len());
+        let mut plan_builder = StringBuilder::new(self.plans.len());
+        for (plan_type, plan) in &self.plans {
+            type_builder.append_value(plan_type)?;
+            plan_builder.append_value(plan)?;
         }
 
         let record_batch = RecordBatch::try_new(
@@ -149,10 +162,14 @@ impl ExecutionPlan for ExplainExec {
             ],
         )?;
 
-        Ok(Box::pin(SizedRecordBatchStream::new(
-            self.schema.clone(),
-            vec![Arc::new(record_batch)],
-        )))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(SizedRecordBatchStream::new(
+                self.schema.clone(),
+                vec![Arc::new(record_batch)],
+            )),
+        ))
     }
 
     fn fmt_as(
@@ -164,6 +181,7 @@ impl ExecutionPlan for ExplainExec {
             DisplayFormatType::Default => {
                 write!(f, "ExplainExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -186,7 +204,9 @@ fn should_show(previous_plan: &StringifiedPlan, this_plan: &StringifiedPlan) ->
 
 #[async_trait]
 impl LambdaExecPlan for ExplainExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "ExplainExec has no input to feed".to_owned(),
+        ))
     }
 }
@@ -195,9 +195,10 @@ impl<T: Hash> TryFrom<&[u8]> for HyperLogLog<T> {
     type Error = DataFusionError;
     fn try_from(v: &[u8]) -> Result<HyperLogLog<T>> {
         let arr: [u8; 16384] = v.try_into().map_err(|_| {
-            DataFusionError::Internal(
-                "Impossibly got invalid binary array from states".into(),
-            )
+            DataFusionError::Execution(format!(
+                "Invalid approx_distinct state: expected 16384 HyperLogLog registers, got {} bytes",
+                v.len()
+            ))
         })?;
         Ok(HyperLogLog::<T>::new_with_registers(arr))
     }
@@ -237,14 +238,23 @@ macro_rules! default_accumulator_impl {
         }
 
         fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
-            assert_eq!(1, states.len(), "expect only 1 element in the states");
-            let binary_array = states[0].as_any().downcast_ref::<BinaryArray>().unwrap();
-            for v in binary_array.iter() {
-                let v = v.ok_or_else(|| {
-                    DataFusionError::Internal(
-                        "Impossibly got empty binary array from states".into(),
-                    )
+            if states.len() != 1 {
+                return Err(DataFusionError::Execution(format!(
+                    "Invalid approx_distinct state: expected 1 column, got {}",
+                    states.len()
+                )));
+            }
+            let binary_array = states[0]
+                .as_any()
+                .downcast_ref::<BinaryArray>()
+                .ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "Invalid approx_distinct state: expected Binary registers, got {:?}",
+                        states[0].data_type()
+                    ))
                 })?;
+            // a null state comes from a partial aggregation that saw no rows
+            for v in binary_array.iter().flatten() {
                 let other = v.try_into()?;
                 self.hll.merge(&other);
             }
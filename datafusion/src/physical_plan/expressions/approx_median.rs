@@ -0,0 +1,413 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use super::format_state_name;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{Array, ArrayRef, Float64Array, ListArray};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use std::sync::Arc;
+
+/// Maximum number of centroids of the sketch of an APPROX_MEDIAN, which
+/// bounds its size whatever the number of values
+pub const MAX_CENTROIDS: usize = 100;
+
+/// APPROX_MEDIAN aggregate expression
+///
+/// The values are summarized by a t-digest of at most [`MAX_CENTROIDS`]
+/// centroids, so the median is exact for small inputs and approximate for
+/// the larger ones. The NaN values are ignored.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ApproxMedian {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    data_type: DataType,
+}
+
+impl ApproxMedian {
+    /// Create a new APPROX_MEDIAN aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        // the median is computed over the values cast to FLOAT64
+        assert_eq!(data_type, DataType::Float64);
+        Self {
+            name: name.into(),
+            expr,
+            data_type,
+        }
+    }
+}
+
+#[typetag::serde(name = "approx_median")]
+impl AggregateExpr for ApproxMedian {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(ApproxMedianAccumulator::default()))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        let list =
+            || DataType::List(Box::new(Field::new("item", DataType::Float64, true)));
+        Ok(vec![
+            Field::new(&format_state_name(&self.name, "means"), list(), false),
+            Field::new(&format_state_name(&self.name, "weights"), list(), false),
+        ])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// A cluster of `weight` values whose mean is `mean`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A t-digest: the values are clustered into centroids sorted by mean, the
+/// centroids close to the extreme quantiles being smaller than the others.
+/// See "Computing Extremely Accurate Quantiles Using t-Digests" by Dunning
+/// and Ertl.
+#[derive(Debug, Default, Clone)]
+struct TDigest {
+    /// The centroids, only sorted and bounded after [`Self::compress`]
+    centroids: Vec<Centroid>,
+}
+
+impl TDigest {
+    fn add(&mut self, centroid: Centroid) {
+        if centroid.mean.is_nan() || centroid.weight <= 0.0 {
+            return;
+        }
+        self.centroids.push(centroid);
+        // the added centroids are buffered, and merged once they are as many
+        // as the merged ones
+        if self.centroids.len() > 2 * MAX_CENTROIDS {
+            self.compress();
+        }
+    }
+
+    /// Sort the centroids and merge the adjacent ones until there are at
+    /// most [`MAX_CENTROIDS`] of them, using the scale function
+    /// `k(q) = (MAX_CENTROIDS - 1) / (2 * PI) * asin(2 * q - 1)`: a centroid
+    /// spans at most one unit of `k`, so that the centroids are smaller near
+    /// the extreme quantiles, and two adjacent centroids span more than one.
+    fn compress(&mut self) {
+        self.centroids
+            .sort_by(|a, b| a.mean.partial_cmp(&b.mean).unwrap_or(Ordering::Equal));
+        if self.centroids.len() <= MAX_CENTROIDS {
+            return;
+        }
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        let scale = (MAX_CENTROIDS - 1) as f64 / (2.0 * PI);
+        let k = |weight: f64| scale * (2.0 * (weight / total).min(1.0) - 1.0).asin();
+
+        let mut merged = Vec::with_capacity(MAX_CENTROIDS);
+        let mut current = self.centroids[0];
+        // the weight of the centroids before `current`
+        let mut before = 0.0;
+        for next in &self.centroids[1..] {
+            let weight = current.weight + next.weight;
+            if k(before + weight) - k(before) <= 1.0 {
+                current.mean += (next.mean - current.mean) * next.weight / weight;
+                current.weight = weight;
+            } else {
+                before += current.weight;
+                merged.push(current);
+                current = *next;
+            }
+        }
+        merged.push(current);
+        self.centroids = merged;
+    }
+
+    /// The estimated value of quantile `q` of the centroids sorted by
+    /// [`Self::compress`], interpolated between the centers of the centroids
+    fn quantile(&self, q: f64) -> Option<f64> {
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        let rank = q * total;
+        let mut before = 0.0;
+        let mut previous: Option<(f64, f64)> = None;
+        for centroid in &self.centroids {
+            let center = before + centroid.weight / 2.0;
+            if rank <= center {
+                return Some(match previous {
+                    Some((previous_center, previous_mean)) => {
+                        previous_mean
+                            + (centroid.mean - previous_mean) * (rank - previous_center)
+                                / (center - previous_center)
+                    }
+                    None => centroid.mean,
+                });
+            }
+            before += centroid.weight;
+            previous = Some((center, centroid.mean));
+        }
+        previous.map(|(_, mean)| mean)
+    }
+}
+
+/// Accumulates the non-null values of the input as FLOAT64 into a
+/// [`TDigest`]
+#[derive(Debug, Default)]
+struct ApproxMedianAccumulator {
+    digest: TDigest,
+}
+
+impl ApproxMedianAccumulator {
+    fn extend(&mut self, array: &ArrayRef) -> Result<()> {
+        let array = cast(array, &DataType::Float64)?;
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        for value in array.iter().flatten() {
+            self.digest.add(Centroid {
+                mean: value,
+                weight: 1.0,
+            });
+        }
+        Ok(())
+    }
+
+    /// Merge the centroids of another sketch, as listed by its state
+    fn merge_centroids(&mut self, means: &ArrayRef, weights: &ArrayRef) -> Result<()> {
+        let means = as_float64(means)?;
+        let weights = as_float64(weights)?;
+        for (mean, weight) in means.iter().zip(weights.iter()) {
+            if let (Some(mean), Some(weight)) = (mean, weight) {
+                self.digest.add(Centroid { mean, weight });
+            }
+        }
+        Ok(())
+    }
+}
+
+fn as_float64(array: &ArrayRef) -> Result<&Float64Array> {
+    array
+        .as_any()
+        .downcast_ref::<Float64Array>()
+        .ok_or_else(|| invalid_state(array.data_type()))
+}
+
+fn invalid_state(data_type: &DataType) -> DataFusionError {
+    DataFusionError::Internal(format!(
+        "Invalid approx_median state: expected a List of Float64, got {:?}",
+        data_type
+    ))
+}
+
+impl Accumulator for ApproxMedianAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        let mut digest = self.digest.clone();
+        digest.compress();
+        let list = |values: Vec<ScalarValue>| {
+            ScalarValue::List(Some(Box::new(values)), Box::new(DataType::Float64))
+        };
+        let (means, weights) = digest
+            .centroids
+            .iter()
+            .map(|c| {
+                (
+                    ScalarValue::Float64(Some(c.mean)),
+                    ScalarValue::Float64(Some(c.weight)),
+                )
+            })
+            .unzip();
+        Ok(vec![list(means), list(weights)])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.update_batch(&[values[0].to_array()])
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        self.extend(&values[0])
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let states = states
+            .iter()
+            .map(|state| state.to_array())
+            .collect::<Vec<_>>();
+        self.merge_batch(&states)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let lists = |state: &ArrayRef| {
+            state
+                .as_any()
+                .downcast_ref::<ListArray>()
+                .map(|lists| lists.iter().collect::<Vec<_>>())
+                .ok_or_else(|| invalid_state(state.data_type()))
+        };
+        // each row lists the centroids of a sketch
+        for (means, weights) in lists(&states[0])?.into_iter().zip(lists(&states[1])?) {
+            if let (Some(means), Some(weights)) = (means, weights) {
+                self.merge_centroids(&means, &weights)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        let mut digest = self.digest.clone();
+        digest.compress();
+        Ok(ScalarValue::Float64(digest.quantile(0.5)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use crate::{error::Result, generic_test_op};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::*;
+    use arrow::record_batch::RecordBatch;
+
+    #[test]
+    fn approx_median_i32_odd() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![5, 1, 4, 2, 3]));
+        generic_test_op!(a, DataType::Int32, ApproxMedian, 3_f64, DataType::Float64)
+    }
+
+    #[test]
+    fn approx_median_f64_even() -> Result<()> {
+        let a: ArrayRef = Arc::new(Float64Array::from(vec![4.0, 1.0, 2.5, 10.0]));
+        generic_test_op!(
+            a,
+            DataType::Float64,
+            ApproxMedian,
+            3.25_f64,
+            DataType::Float64
+        )
+    }
+
+    #[test]
+    fn approx_median_with_nulls() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(1),
+            None,
+            Some(3),
+            Some(8),
+            None,
+        ]));
+        generic_test_op!(a, DataType::Int32, ApproxMedian, 3_f64, DataType::Float64)
+    }
+
+    #[test]
+    fn approx_median_all_nulls() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![None, None]));
+        generic_test_op!(
+            a,
+            DataType::Int32,
+            ApproxMedian,
+            ScalarValue::Float64(None),
+            DataType::Float64
+        )
+    }
+
+    #[test]
+    fn approx_median_merge() -> Result<()> {
+        let mut left = ApproxMedianAccumulator::default();
+        left.update_batch(&[Arc::new(Int32Array::from(vec![1, 7])) as ArrayRef])?;
+        let mut right = ApproxMedianAccumulator::default();
+        right.update_batch(&[Arc::new(Int32Array::from(vec![2, 9, 5])) as ArrayRef])?;
+
+        right.merge(&left.state()?)?;
+        assert_eq!(right.evaluate()?, ScalarValue::Float64(Some(5.0)));
+        Ok(())
+    }
+
+    /// A permutation of `0..=10_000`, whose median is 5_000
+    fn shuffled() -> Vec<i32> {
+        (0..=10_000).map(|i| (i * 7_919) % 10_001).collect()
+    }
+
+    #[test]
+    fn approx_median_bounded() -> Result<()> {
+        let mut accumulator = ApproxMedianAccumulator::default();
+        accumulator
+            .update_batch(&[Arc::new(Int32Array::from(shuffled())) as ArrayRef])?;
+
+        assert!(accumulator.digest.centroids.len() <= 2 * MAX_CENTROIDS);
+        match &accumulator.state()?[0] {
+            ScalarValue::List(Some(means), _) => assert!(means.len() <= MAX_CENTROIDS),
+            state => panic!("unexpected state {:?}", state),
+        }
+        match accumulator.evaluate()? {
+            ScalarValue::Float64(Some(median)) => {
+                assert!((median - 5_000.0).abs() < 100.0)
+            }
+            median => panic!("unexpected median {:?}", median),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn approx_median_merge_batch() -> Result<()> {
+        // the sketches of four partitions, one per row of the state arrays
+        let mut states = vec![vec![], vec![]];
+        for chunk in shuffled().chunks(2_501) {
+            let mut accumulator = ApproxMedianAccumulator::default();
+            accumulator.update_batch(&[
+                Arc::new(Int32Array::from(chunk.to_vec())) as ArrayRef
+            ])?;
+            for (i, state) in accumulator.state()?.into_iter().enumerate() {
+                states[i].push(state);
+            }
+        }
+        let states = states
+            .into_iter()
+            .map(ScalarValue::iter_to_array)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut accumulator = ApproxMedianAccumulator::default();
+        accumulator.merge_batch(&states)?;
+        assert!(accumulator.digest.centroids.len() <= 2 * MAX_CENTROIDS);
+        match accumulator.evaluate()? {
+            ScalarValue::Float64(Some(median)) => {
+                assert!((median - 5_000.0).abs() < 100.0)
+            }
+            median => panic!("unexpected median {:?}", median),
+        }
+        Ok(())
+    }
+}
@@ -18,9 +18,10 @@
 //! Defines physical expressions that can evaluated at runtime during query execution
 
 use super::format_state_name;
-use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
 use crate::scalar::ScalarValue;
+use arrow::array::{Array, ArrayRef, ListArray};
 use arrow::datatypes::{DataType, Field};
 use std::any::Any;
 use std::sync::Arc;
@@ -140,6 +141,28 @@ impl Accumulator for ArrayAggAccumulator {
         Ok(())
     }
 
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        if states.is_empty() {
+            return Ok(());
+        };
+
+        let lists = states[0]
+            .as_any()
+            .downcast_ref::<ListArray>()
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "Invalid array_agg state: expected a List, got {:?}",
+                    states[0].data_type()
+                ))
+            })?;
+        for list in lists.iter().flatten() {
+            for index in 0..list.len() {
+                self.array.push(ScalarValue::try_from_array(&list, index)?);
+            }
+        }
+        Ok(())
+    }
+
     fn evaluate(&self) -> Result<ScalarValue> {
         Ok(ScalarValue::List(
             Some(Box::new(self.array.clone())),
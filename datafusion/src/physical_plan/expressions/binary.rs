@@ -49,9 +49,28 @@ use crate::physical_plan::expressions::try_cast;
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use crate::scalar::ScalarValue;
 
+use super::bytes::{
+    eq_binary, eq_binary_scalar, gt_binary, gt_binary_scalar, gt_eq_binary,
+    gt_eq_binary_scalar, is_distinct_from_binary, is_not_distinct_from_binary, lt_binary,
+    lt_binary_scalar, lt_eq_binary, lt_eq_binary_scalar, neq_binary, neq_binary_scalar,
+};
 use super::coercion::{
-    eq_coercion, like_coercion, numerical_coercion, order_coercion, string_coercion,
+    decimal_arithmetic_type, decimal_precision_scale, eq_coercion,
+    interval_arithmetic_type, like_coercion, numerical_coercion, order_coercion,
+    string_coercion, temporal_coercion,
+};
+use super::decimal::{
+    decimal_arithmetic, eq_decimal, eq_decimal_scalar, gt_decimal, gt_decimal_scalar,
+    gt_eq_decimal, gt_eq_decimal_scalar, is_distinct_from_decimal,
+    is_not_distinct_from_decimal, lt_decimal, lt_decimal_scalar, lt_eq_decimal,
+    lt_eq_decimal_scalar, neq_decimal, neq_decimal_scalar,
+};
+use super::dictionary::{
+    dictionary_values, is_dictionary, map_dictionary_keys, unpack_dictionary,
 };
+use super::interval::{interval_operation, is_interval_operation};
+use super::like::{ilike_utf8, ilike_utf8_scalar, nilike_utf8, nilike_utf8_scalar};
+use super::overflow::{integer_arithmetic, is_integer, ArithmeticOverflow};
 
 use serde::{Deserialize, Serialize};
 
@@ -87,6 +106,9 @@ pub struct BinaryExpr {
     left: Arc<dyn PhysicalExpr>,
     op: Operator,
     right: Arc<dyn PhysicalExpr>,
+    /// How the integer arithmetic handles the results out of range
+    #[serde(default)]
+    overflow: ArithmeticOverflow,
 }
 
 impl BinaryExpr {
@@ -96,7 +118,23 @@ impl BinaryExpr {
         op: Operator,
         right: Arc<dyn PhysicalExpr>,
     ) -> Self {
-        Self { left, op, right }
+        Self::new_with_overflow(left, op, right, ArithmeticOverflow::default())
+    }
+
+    /// Create new binary expression whose integer arithmetic handles the
+    /// results out of range as `overflow` says
+    pub fn new_with_overflow(
+        left: Arc<dyn PhysicalExpr>,
+        op: Operator,
+        right: Arc<dyn PhysicalExpr>,
+        overflow: ArithmeticOverflow,
+    ) -> Self {
+        Self {
+            left,
+            op,
+            right,
+            overflow,
+        }
     }
 
     /// Get the left side of the binary expression
@@ -113,6 +151,11 @@ impl BinaryExpr {
     pub fn op(&self) -> &Operator {
         &self.op
     }
+
+    /// How the integer arithmetic handles the results out of range
+    pub fn overflow(&self) -> ArithmeticOverflow {
+        self.overflow
+    }
 }
 
 impl std::fmt::Display for BinaryExpr {
@@ -216,6 +259,79 @@ macro_rules! compute_op_scalar {
     }};
 }
 
+/// Invoke a compute kernel on a decimal array and a decimal scalar value of
+/// the same scale
+macro_rules! compute_decimal_op_scalar {
+    ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
+        let ll = $LEFT
+            .as_any()
+            .downcast_ref::<DecimalArray>()
+            .expect("compute_op failed to downcast array");
+        match $RIGHT {
+            ScalarValue::Decimal128(Some(value), _, _) => Ok(Arc::new(
+                paste::expr! {[<$OP _decimal_scalar>]}(&ll, value)?,
+            )),
+            ScalarValue::Decimal128(None, _, _) => {
+                Ok(Arc::new(BooleanArray::from(vec![None; ll.len()])))
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "compute_decimal_op_scalar for '{}' failed to cast literal value {}",
+                stringify!($OP),
+                other
+            ))),
+        }
+    }};
+}
+
+/// Invoke a compute kernel on a pair of decimal arrays of the same scale
+macro_rules! compute_decimal_op {
+    ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
+        let ll = $LEFT
+            .as_any()
+            .downcast_ref::<DecimalArray>()
+            .expect("compute_op failed to downcast array");
+        let rr = $RIGHT
+            .as_any()
+            .downcast_ref::<DecimalArray>()
+            .expect("compute_op failed to downcast array");
+        Ok(Arc::new(paste::expr! {[<$OP _decimal>]}(&ll, &rr)?))
+    }};
+}
+
+/// Invoke a compute kernel on an array of binary values and a binary scalar
+/// value
+macro_rules! compute_binary_op_scalar {
+    ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
+        match $RIGHT {
+            ScalarValue::Binary(Some(value))
+            | ScalarValue::LargeBinary(Some(value))
+            | ScalarValue::FixedSizeBinary(_, Some(value)) => Ok(Arc::new(
+                paste::expr! {[<$OP _binary_scalar>]}($LEFT.as_ref(), &value)?,
+            )),
+            ScalarValue::Binary(None)
+            | ScalarValue::LargeBinary(None)
+            | ScalarValue::FixedSizeBinary(_, None) => {
+                Ok(Arc::new(BooleanArray::from(vec![None; $LEFT.len()])))
+            }
+            other => Err(DataFusionError::Internal(format!(
+                "compute_binary_op_scalar for '{}' failed to cast literal value {}",
+                stringify!($OP),
+                other
+            ))),
+        }
+    }};
+}
+
+/// Invoke a compute kernel on a pair of arrays of binary values
+macro_rules! compute_binary_op {
+    ($LEFT:expr, $RIGHT:expr, $OP:ident) => {{
+        Ok(Arc::new(paste::expr! {[<$OP _binary>]}(
+            $LEFT.as_ref(),
+            $RIGHT.as_ref(),
+        )?))
+    }};
+}
+
 /// Invoke a compute kernel on array(s)
 macro_rules! compute_op {
     // invoke binary operator
@@ -350,6 +466,10 @@ macro_rules! binary_array_op_scalar {
                 compute_op_scalar!($LEFT, $RIGHT, $OP, Date64Array)
             }
             DataType::Boolean => compute_bool_op_scalar!($LEFT, $RIGHT, $OP, BooleanArray),
+            DataType::Decimal(_, _) => compute_decimal_op_scalar!($LEFT, $RIGHT, $OP),
+            DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+                compute_binary_op_scalar!($LEFT, $RIGHT, $OP)
+            }
             other => Err(DataFusionError::Internal(format!(
                 "Data type {:?} not supported for scalar operation '{}' on dyn array",
                 other, stringify!($OP)
@@ -395,6 +515,10 @@ macro_rules! binary_array_op {
                 compute_op!($LEFT, $RIGHT, $OP, Date64Array)
             }
             DataType::Boolean => compute_bool_op!($LEFT, $RIGHT, $OP, BooleanArray),
+            DataType::Decimal(_, _) => compute_decimal_op!($LEFT, $RIGHT, $OP),
+            DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_) => {
+                compute_binary_op!($LEFT, $RIGHT, $OP)
+            }
             other => Err(DataFusionError::Internal(format!(
                 "Data type {:?} not supported for binary operation '{}' on dyn arrays",
                 other, stringify!($OP)
@@ -520,7 +644,9 @@ fn common_binary_type(
         // logical equality operators have their own rules, and always return a boolean
         Operator::Eq | Operator::NotEq => eq_coercion(lhs_type, rhs_type),
         // "like" operators operate on strings and always return a boolean
-        Operator::Like | Operator::NotLike => like_coercion(lhs_type, rhs_type),
+        Operator::Like | Operator::NotLike | Operator::ILike | Operator::NotILike => {
+            like_coercion(lhs_type, rhs_type)
+        }
         // order-comparison operators have their own rules
         Operator::Lt | Operator::Gt | Operator::GtEq | Operator::LtEq => {
             order_coercion(lhs_type, rhs_type)
@@ -531,7 +657,8 @@ fn common_binary_type(
         | Operator::Minus
         | Operator::Modulo
         | Operator::Divide
-        | Operator::Multiply => numerical_coercion(lhs_type, rhs_type),
+        | Operator::Multiply => numerical_coercion(lhs_type, rhs_type)
+            .or_else(|| interval_arithmetic_type(lhs_type, op, rhs_type)),
         Operator::RegexMatch
         | Operator::RegexIMatch
         | Operator::RegexNotMatch
@@ -576,6 +703,8 @@ pub fn binary_operator_data_type(
         | Operator::Or
         | Operator::Like
         | Operator::NotLike
+        | Operator::ILike
+        | Operator::NotILike
         | Operator::Lt
         | Operator::Gt
         | Operator::GtEq
@@ -586,15 +715,55 @@ pub fn binary_operator_data_type(
         | Operator::RegexNotIMatch
         | Operator::IsDistinctFrom
         | Operator::IsNotDistinctFrom => Ok(DataType::Boolean),
-        // math operations return the same value as the common coerced type
+        // math operations return the same value as the common coerced type,
+        // except for decimals, whose precision and scale depend on the operator
         Operator::Plus
         | Operator::Minus
         | Operator::Divide
         | Operator::Multiply
-        | Operator::Modulo => Ok(common_type),
+        | Operator::Modulo => match common_type {
+            DataType::Decimal(_, _) => decimal_arithmetic_type(lhs_type, op, rhs_type)
+                .ok_or_else(|| {
+                    DataFusionError::Plan(format!(
+                        "'{:?} {} {:?}' is not a decimal operation",
+                        lhs_type, op, rhs_type
+                    ))
+                }),
+            _ => Ok(common_type),
+        },
     }
 }
 
+/// Whether `op` is a comparison evaluated on the values of a dictionary
+/// when compared with a literal
+fn is_dictionary_comparison(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+            | Operator::Like
+            | Operator::NotLike
+            | Operator::ILike
+            | Operator::NotILike
+    )
+}
+
+/// Whether `op` is an arithmetic operator
+fn is_arithmetic(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Plus
+            | Operator::Minus
+            | Operator::Divide
+            | Operator::Multiply
+            | Operator::Modulo
+    )
+}
+
 #[typetag::serde(name = "binary_expr")]
 impl PhysicalExpr for BinaryExpr {
     /// Return a reference to Any that can be used for downcasting
@@ -611,15 +780,63 @@ impl PhysicalExpr for BinaryExpr {
     }
 
     fn nullable(&self, input_schema: &Schema) -> Result<bool> {
-        Ok(self.left.nullable(input_schema)? || self.right.nullable(input_schema)?)
+        match self.op {
+            // null is not distinct from null
+            Operator::IsDistinctFrom | Operator::IsNotDistinctFrom => Ok(false),
+            _ => {
+                Ok(self.left.nullable(input_schema)?
+                    || self.right.nullable(input_schema)?)
+            }
+        }
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let left_value = self.left.evaluate(batch)?;
         let right_value = self.right.evaluate(batch)?;
+
+        // the comparisons of dictionaries with literals are evaluated on the
+        // values of the dictionaries, the other operations unpack them
+        if let Some(result) =
+            self.evaluate_dictionary_scalar(&left_value, &right_value)?
+        {
+            return Ok(ColumnarValue::Array(result));
+        }
+        let left_value = unpack_dictionary(left_value)?;
+        let right_value = unpack_dictionary(right_value)?;
         let left_data_type = left_value.data_type();
         let right_data_type = right_value.data_type();
 
+        // the arithmetic of decimals accepts any precisions and scales
+        if let (DataType::Decimal(_, _), DataType::Decimal(_, _)) =
+            (&left_data_type, &right_data_type)
+        {
+            if is_arithmetic(&self.op) {
+                let result_type = binary_operator_data_type(
+                    &left_data_type,
+                    &self.op,
+                    &right_data_type,
+                )?;
+                let left = left_value.into_array(batch.num_rows());
+                let right = right_value.into_array(batch.num_rows());
+                let result = decimal_arithmetic(
+                    left.as_any().downcast_ref::<DecimalArray>().unwrap(),
+                    &self.op,
+                    right.as_any().downcast_ref::<DecimalArray>().unwrap(),
+                    &result_type,
+                )?;
+                return Ok(ColumnarValue::Array(Arc::new(result)));
+            }
+        }
+
+        // the intervals are compared on their durations, and their arithmetic
+        // mixes them with numbers
+        if is_interval_operation(&left_data_type, &self.op, &right_data_type) {
+            let left = left_value.into_array(batch.num_rows());
+            let right = right_value.into_array(batch.num_rows());
+            return interval_operation(&left, &self.op, &right)
+                .map(|a| ColumnarValue::Array(a));
+        }
+
         if left_data_type != right_data_type {
             return Err(DataFusionError::Internal(format!(
                 "Cannot evaluate binary expression {:?} with types {:?} and {:?}",
@@ -627,6 +844,18 @@ impl PhysicalExpr for BinaryExpr {
             )));
         }
 
+        // the arrow kernels wrap the integers around, the other modes of
+        // overflow have their own kernels
+        if self.overflow != ArithmeticOverflow::Wrap && is_integer(&left_data_type) {
+            let left = left_value.clone().into_array(batch.num_rows());
+            let right = right_value.clone().into_array(batch.num_rows());
+            if let Some(result) =
+                integer_arithmetic(&left, &self.op, &right, self.overflow, self)?
+            {
+                return Ok(ColumnarValue::Array(result));
+            }
+        }
+
         // Attempt to use special kernels if one input is scalar and the other is an array
         let scalar_result = match (&left_value, &right_value) {
             (ColumnarValue::Array(array), ColumnarValue::Scalar(scalar)) => {
@@ -655,6 +884,38 @@ impl PhysicalExpr for BinaryExpr {
 }
 
 impl BinaryExpr {
+    /// Evaluate the comparison of a dictionary array with a literal once per
+    /// value of the dictionary, and map the results through its keys. `None`
+    /// if neither input is a dictionary array compared with a literal.
+    fn evaluate_dictionary_scalar(
+        &self,
+        left: &ColumnarValue,
+        right: &ColumnarValue,
+    ) -> Result<Option<ArrayRef>> {
+        if !is_dictionary_comparison(&self.op) {
+            return Ok(None);
+        }
+        let (array, results) = match (left, right) {
+            (ColumnarValue::Array(array), ColumnarValue::Scalar(scalar))
+                if is_dictionary(array.data_type()) =>
+            {
+                let values = dictionary_values(array)?;
+                (array, self.evaluate_array_scalar(&values, scalar)?)
+            }
+            (ColumnarValue::Scalar(scalar), ColumnarValue::Array(array))
+                if is_dictionary(array.data_type()) =>
+            {
+                let values = dictionary_values(array)?;
+                (array, self.evaluate_scalar_array(scalar, &values)?)
+            }
+            _ => return Ok(None),
+        };
+        match results {
+            Some(results) => Ok(Some(map_dictionary_keys(array, &results?)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Evaluate the expression of the left input is an array and
     /// right is literal - use scalar operations
     fn evaluate_array_scalar(
@@ -681,6 +942,12 @@ impl BinaryExpr {
             Operator::NotLike => {
                 binary_string_array_op_scalar!(array, scalar.clone(), nlike)
             }
+            Operator::ILike => {
+                binary_string_array_op_scalar!(array, scalar.clone(), ilike)
+            }
+            Operator::NotILike => {
+                binary_string_array_op_scalar!(array, scalar.clone(), nilike)
+            }
             Operator::Divide => {
                 binary_primitive_array_op_scalar!(array, scalar.clone(), divide)
             }
@@ -758,6 +1025,8 @@ impl BinaryExpr {
         match &self.op {
             Operator::Like => binary_string_array_op!(left, right, like),
             Operator::NotLike => binary_string_array_op!(left, right, nlike),
+            Operator::ILike => binary_string_array_op!(left, right, ilike),
+            Operator::NotILike => binary_string_array_op!(left, right, nilike),
             Operator::Lt => binary_array_op!(left, right, lt),
             Operator::LtEq => binary_array_op!(left, right, lt_eq),
             Operator::Gt => binary_array_op!(left, right, gt),
@@ -874,12 +1143,89 @@ fn binary_cast(
 
     let cast_type = common_binary_type(lhs_type, op, rhs_type)?;
 
+    // a dictionary compared with a value of the type of its values is kept
+    // encoded, to be compared on its values
+    if is_dictionary_comparison(op) {
+        match (lhs_type, rhs_type) {
+            (DataType::Dictionary(_, value_type), other)
+                if **value_type == cast_type && !is_dictionary(other) =>
+            {
+                return Ok((lhs, try_cast(rhs, input_schema, cast_type)?));
+            }
+            (other, DataType::Dictionary(_, value_type))
+                if **value_type == cast_type && !is_dictionary(other) =>
+            {
+                return Ok((try_cast(lhs, input_schema, cast_type)?, rhs));
+            }
+            _ => {}
+        }
+    }
+
+    // the arithmetic of decimals accepts any precisions and scales, only the
+    // integers are casted to decimals
+    if is_arithmetic(op) && matches!(cast_type, DataType::Decimal(_, _)) {
+        return Ok((
+            decimal_operand(lhs, lhs_type, input_schema)?,
+            decimal_operand(rhs, rhs_type, input_schema)?,
+        ));
+    }
+
+    // the intervals are not casted, only the numbers and the timestamps of
+    // their arithmetic
+    if is_arithmetic(op) && matches!(cast_type, DataType::Interval(_)) {
+        return Ok((
+            interval_operand(lhs, lhs_type, rhs_type, input_schema)?,
+            interval_operand(rhs, rhs_type, lhs_type, input_schema)?,
+        ));
+    }
+
     Ok((
         try_cast(lhs, input_schema, cast_type.clone())?,
         try_cast(rhs, input_schema, cast_type)?,
     ))
 }
 
+/// Cast `expr`, an integer or decimal operand of a decimal arithmetic
+/// operation, to a decimal type holding its values
+fn decimal_operand(
+    expr: Arc<dyn PhysicalExpr>,
+    data_type: &DataType,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    match decimal_precision_scale(data_type) {
+        Some((precision, scale)) => {
+            try_cast(expr, input_schema, DataType::Decimal(precision, scale))
+        }
+        None => Err(DataFusionError::Internal(format!(
+            "{:?} is not an operand of a decimal operation",
+            data_type
+        ))),
+    }
+}
+
+/// Cast `expr`, an operand of an interval arithmetic operation whose other
+/// operand is of type `other_type`: the numbers to `Float64`, and the
+/// timestamps to the type of the other timestamp
+fn interval_operand(
+    expr: Arc<dyn PhysicalExpr>,
+    data_type: &DataType,
+    other_type: &DataType,
+    input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    let operand_type = match data_type {
+        DataType::Interval(_) => return Ok(expr),
+        DataType::Timestamp(_, _) => temporal_coercion(data_type, other_type)
+            .ok_or_else(|| {
+                DataFusionError::Internal(format!(
+                    "{:?} is not an operand of an interval operation",
+                    data_type
+                ))
+            })?,
+        _ => DataType::Float64,
+    };
+    try_cast(expr, input_schema, operand_type)
+}
+
 /// Create a binary expression whose arguments are correctly coerced.
 /// This function errors if it is not possible to coerce the arguments
 /// to computational types supported by the operator.
@@ -888,9 +1234,23 @@ pub fn binary(
     op: Operator,
     rhs: Arc<dyn PhysicalExpr>,
     input_schema: &Schema,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    binary_with_overflow(lhs, op, rhs, input_schema, ArithmeticOverflow::default())
+}
+
+/// Create a binary expression whose arguments are correctly coerced, and
+/// whose integer arithmetic handles the results out of range as `overflow`
+/// says. This function errors if it is not possible to coerce the arguments
+/// to computational types supported by the operator.
+pub fn binary_with_overflow(
+    lhs: Arc<dyn PhysicalExpr>,
+    op: Operator,
+    rhs: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    overflow: ArithmeticOverflow,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let (l, r) = binary_cast(lhs, &op, rhs, input_schema)?;
-    Ok(Arc::new(BinaryExpr::new(l, op, r)))
+    Ok(Arc::new(BinaryExpr::new_with_overflow(l, op, r, overflow)))
 }
 
 #[cfg(test)]
@@ -1083,6 +1443,18 @@ mod tests {
             DataType::Boolean,
             vec![true, false]
         );
+        test_coercion!(
+            StringArray,
+            DataType::Utf8,
+            vec!["Hello World", "world"],
+            StringArray,
+            DataType::Utf8,
+            vec!["%HELLO%", "h_llo%"],
+            Operator::ILike,
+            BooleanArray,
+            DataType::Boolean,
+            vec![true, false]
+        );
         test_coercion!(
             StringArray,
             DataType::Utf8,
@@ -1308,6 +1680,55 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn dictionary_compared_with_literal() -> Result<()> {
+        let dict_type =
+            DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+        let schema = Schema::new(vec![Field::new("dict", dict_type.clone(), true)]);
+        let dict_array: DictionaryArray<Int32Type> =
+            vec![Some("one"), None, Some("three"), Some("one")]
+                .into_iter()
+                .collect();
+        let batch =
+            RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(dict_array)])?;
+
+        let cases = vec![
+            (
+                col("dict", &schema)?,
+                Operator::Eq,
+                lit("one".into()),
+                "true\n\nfalse\ntrue",
+            ),
+            (
+                lit("p".into()),
+                Operator::Lt,
+                col("dict", &schema)?,
+                "false\n\ntrue\nfalse",
+            ),
+            (
+                col("dict", &schema)?,
+                Operator::NotLike,
+                lit("o%".into()),
+                "false\n\ntrue\nfalse",
+            ),
+        ];
+        for (left, op, right, expected) in cases {
+            let expression = binary(left, op, right, &schema)?;
+            // the dictionary is not unpacked
+            let binary_expr = expression.as_any().downcast_ref::<BinaryExpr>().unwrap();
+            let data_types = (
+                binary_expr.left().data_type(&schema)?,
+                binary_expr.right().data_type(&schema)?,
+            );
+            assert!(data_types.0 == dict_type || data_types.1 == dict_type);
+
+            let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.data_type(), &DataType::Boolean);
+            assert_eq!(expected, array_to_string(&result)?);
+        }
+        Ok(())
+    }
+
     // Convert the array to a newline delimited string of pretty printed values
     fn array_to_string(array: &ArrayRef) -> Result<String> {
         let s = (0..array.len())
@@ -1382,6 +1803,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn multiply_overflow() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let a = Arc::new(Int32Array::from(vec![1, 2_000, 3_000]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+        let multiply = |overflow| -> Result<ArrayRef> {
+            let expr = binary_with_overflow(
+                col("a", &schema)?,
+                Operator::Multiply,
+                lit(ScalarValue::Int32(Some(1_000_000))),
+                &schema,
+                overflow,
+            )?;
+            Ok(expr.evaluate(&batch)?.into_array(batch.num_rows()))
+        };
+
+        let result = multiply(ArithmeticOverflow::Saturate)?;
+        assert_eq!(
+            result.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![1_000_000, 2_000_000_000, i32::MAX])
+        );
+
+        let err = multiply(ArithmeticOverflow::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow in a@0 * 1000000: 3000 * 1000000 is out of the range of Int32"
+        );
+        Ok(())
+    }
+
     #[test]
     fn divide_op() -> Result<()> {
         let schema = Arc::new(Schema::new(vec![
@@ -1420,6 +1871,70 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn decimal_op() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Decimal(10, 2), true),
+            Field::new("b", DataType::Int32, true),
+        ]));
+        // 1.25, -3.50 and NULL with 2, 3 and 4
+        let mut a = DecimalBuilder::new(3, 10, 2);
+        a.append_value(125)?;
+        a.append_value(-350)?;
+        a.append_null()?;
+        let b = Int32Array::from(vec![2, 3, 4]);
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(a.finish()), Arc::new(b)],
+        )?;
+
+        // the integers are casted to DECIMAL(10, 0)
+        let cases = vec![
+            (Operator::Plus, DataType::Decimal(13, 2), vec![325, -50]),
+            (
+                Operator::Multiply,
+                DataType::Decimal(21, 2),
+                vec![250, -1050],
+            ),
+            (
+                Operator::Divide,
+                DataType::Decimal(21, 13),
+                vec![6250000000000, -11666666666667],
+            ),
+        ];
+        for (op, result_type, expected) in cases {
+            let expr = binary(col("a", &schema)?, op, col("b", &schema)?, &schema)?;
+            assert_eq!(expr.data_type(&schema)?, result_type);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.data_type(), &result_type);
+            let result = result.as_any().downcast_ref::<DecimalArray>().unwrap();
+            assert_eq!(result.value(0), expected[0]);
+            assert_eq!(result.value(1), expected[1]);
+            assert!(result.is_null(2));
+        }
+
+        // the operands of comparisons are casted to a common decimal type
+        let expr = binary(
+            col("a", &schema)?,
+            Operator::Lt,
+            col("b", &schema)?,
+            &schema,
+        )?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(
+            result.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &BooleanArray::from(vec![Some(true), Some(true), None])
+        );
+        let literal = lit(ScalarValue::Decimal128(Some(-3500), 10, 3));
+        let expr = binary(col("a", &schema)?, Operator::Eq, literal, &schema)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(
+            result.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &BooleanArray::from(vec![Some(false), Some(true), None])
+        );
+        Ok(())
+    }
+
     fn apply_arithmetic<T: ArrowNumericType>(
         schema: SchemaRef,
         data: Vec<ArrayRef>,
@@ -1863,6 +2378,33 @@ mod tests {
         apply_logic_op(schema, a, b, Operator::IsNotDistinctFrom, expected).unwrap();
     }
 
+    #[test]
+    fn is_distinct_from_nulls() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ]);
+        let a = Int32Array::from(vec![Some(1), None, Some(3), None]);
+        let b = Int32Array::from(vec![Some(1), Some(2), None, None]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![Arc::new(a), Arc::new(b)],
+        )?;
+
+        let cases = vec![
+            (Operator::IsDistinctFrom, vec![false, true, true, false]),
+            (Operator::IsNotDistinctFrom, vec![true, false, false, true]),
+        ];
+        for (op, expected) in cases {
+            let expr = binary(col("a", &schema)?, op, col("b", &schema)?, &schema)?;
+            // unlike the other comparisons, null operands don't make it null
+            assert!(!expr.nullable(&schema)?);
+            let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+            assert_eq!(result.as_ref(), &BooleanArray::from(expected));
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_coersion_error() -> Result<()> {
         let expr =
@@ -0,0 +1,528 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels of the binary data types, `Binary`, `LargeBinary` and
+//! `FixedSizeBinary`, that arrow doesn't provide yet: comparisons, sorting,
+//! minimum and maximum, lengths, casts from and to strings and hexadecimal
+//! formatting.
+
+use std::cmp::Ordering;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::compute::{SortColumn, SortOptions};
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+
+use crate::error::{DataFusionError, Result};
+
+use super::interval::{build_interval_compare, is_interval};
+
+/// Whether `data_type` is one of the binary data types
+pub(crate) fn is_binary(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Binary | DataType::LargeBinary | DataType::FixedSizeBinary(_)
+    )
+}
+
+/// An array of one of the binary data types
+#[derive(Clone, Copy)]
+pub(crate) enum BinaryValues<'a> {
+    Binary(&'a BinaryArray),
+    LargeBinary(&'a LargeBinaryArray),
+    FixedSizeBinary(&'a FixedSizeBinaryArray),
+}
+
+impl<'a> BinaryValues<'a> {
+    /// Downcast `array`, which must be of one of the binary data types
+    pub(crate) fn try_new(array: &'a dyn Array) -> Result<Self> {
+        let any = array.as_any();
+        let values = match array.data_type() {
+            DataType::Binary => any.downcast_ref().map(BinaryValues::Binary),
+            DataType::LargeBinary => any.downcast_ref().map(BinaryValues::LargeBinary),
+            DataType::FixedSizeBinary(_) => {
+                any.downcast_ref().map(BinaryValues::FixedSizeBinary)
+            }
+            _ => None,
+        };
+        values.ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Expected an array of binary values, got {:?}",
+                array.data_type()
+            ))
+        })
+    }
+
+    fn array(self) -> &'a dyn Array {
+        match self {
+            BinaryValues::Binary(array) => array,
+            BinaryValues::LargeBinary(array) => array,
+            BinaryValues::FixedSizeBinary(array) => array,
+        }
+    }
+
+    /// The value at `index`, whether it is null or not
+    pub(crate) fn value(self, index: usize) -> &'a [u8] {
+        match self {
+            BinaryValues::Binary(array) => array.value(index),
+            BinaryValues::LargeBinary(array) => array.value(index),
+            BinaryValues::FixedSizeBinary(array) => array.value(index),
+        }
+    }
+
+    /// The value at `index`, `None` if it is null
+    pub(crate) fn get(self, index: usize) -> Option<&'a [u8]> {
+        self.array().is_valid(index).then(|| self.value(index))
+    }
+
+    /// The values, `None` for the nulls
+    pub(crate) fn iter(self) -> impl Iterator<Item = Option<&'a [u8]>> {
+        (0..self.array().len()).map(move |index| self.get(index))
+    }
+}
+
+/// Format `value` as two lowercase hexadecimal digits per byte
+pub(crate) fn format_hex(value: &[u8]) -> String {
+    let mut hex = String::with_capacity(value.len() * 2);
+    for byte in value {
+        write!(hex, "{:02x}", byte).unwrap();
+    }
+    hex
+}
+
+/// Parse `hex`, two hexadecimal digits per byte. `None` if it is not made of
+/// pairs of hexadecimal digits
+pub(crate) fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.is_ascii() {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn compare_binary<F: Fn(&[u8], &[u8]) -> bool>(
+    left: &dyn Array,
+    right: &dyn Array,
+    op: F,
+) -> Result<BooleanArray> {
+    if left.len() != right.len() {
+        return Err(DataFusionError::ArrowError(ArrowError::ComputeError(
+            "Cannot perform comparison operation on arrays of different length"
+                .to_string(),
+        )));
+    }
+    let left = BinaryValues::try_new(left)?;
+    let right = BinaryValues::try_new(right)?;
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(left, right)| match (left, right) {
+            (Some(left), Some(right)) => Some(op(left, right)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn compare_binary_scalar<F: Fn(&[u8], &[u8]) -> bool>(
+    left: &dyn Array,
+    right: &[u8],
+    op: F,
+) -> Result<BooleanArray> {
+    Ok(BinaryValues::try_new(left)?
+        .iter()
+        .map(|left| left.map(|left| op(left, right)))
+        .collect())
+}
+
+macro_rules! binary_comparisons {
+    ($($NAME:ident, $SCALAR_NAME:ident, $OP:tt;)*) => {
+        $(
+            pub(crate) fn $NAME(
+                left: &dyn Array,
+                right: &dyn Array,
+            ) -> Result<BooleanArray> {
+                compare_binary(left, right, |left, right| left $OP right)
+            }
+
+            pub(crate) fn $SCALAR_NAME(
+                left: &dyn Array,
+                right: &[u8],
+            ) -> Result<BooleanArray> {
+                compare_binary_scalar(left, right, |left, right| left $OP right)
+            }
+        )*
+    };
+}
+
+// The comparison kernels order the values lexicographically, as unsigned
+// bytes, and expect arrays of any of the binary data types
+binary_comparisons!(
+    eq_binary, eq_binary_scalar, ==;
+    neq_binary, neq_binary_scalar, !=;
+    lt_binary, lt_binary_scalar, <;
+    lt_eq_binary, lt_eq_binary_scalar, <=;
+    gt_binary, gt_binary_scalar, >;
+    gt_eq_binary, gt_eq_binary_scalar, >=;
+);
+
+pub(crate) fn is_distinct_from_binary(
+    left: &dyn Array,
+    right: &dyn Array,
+) -> Result<BooleanArray> {
+    let left = BinaryValues::try_new(left)?;
+    let right = BinaryValues::try_new(right)?;
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(left, right)| Some(left != right))
+        .collect())
+}
+
+pub(crate) fn is_not_distinct_from_binary(
+    left: &dyn Array,
+    right: &dyn Array,
+) -> Result<BooleanArray> {
+    let left = BinaryValues::try_new(left)?;
+    let right = BinaryValues::try_new(right)?;
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(left, right)| Some(left == right))
+        .collect())
+}
+
+/// The smallest value of `array`, `None` if all its values are null
+pub(crate) fn min_binary(array: &dyn Array) -> Result<Option<Vec<u8>>> {
+    Ok(BinaryValues::try_new(array)?
+        .iter()
+        .flatten()
+        .min()
+        .map(|value| value.to_vec()))
+}
+
+/// The largest value of `array`, `None` if all its values are null
+pub(crate) fn max_binary(array: &dyn Array) -> Result<Option<Vec<u8>>> {
+    Ok(BinaryValues::try_new(array)?
+        .iter()
+        .flatten()
+        .max()
+        .map(|value| value.to_vec()))
+}
+
+/// The number of bytes of the values of `array`, as `Int64` for
+/// `LargeBinary` and `Int32` otherwise
+pub(crate) fn octet_length_binary(array: &dyn Array) -> Result<ArrayRef> {
+    let values = BinaryValues::try_new(array)?;
+    let lengths = values.iter().map(|value| value.map(|value| value.len()));
+    Ok(match values {
+        BinaryValues::LargeBinary(_) => Arc::new(
+            lengths
+                .map(|length| length.map(|length| length as i64))
+                .collect::<Int64Array>(),
+        ),
+        _ => Arc::new(
+            lengths
+                .map(|length| length.map(|length| length as i32))
+                .collect::<Int32Array>(),
+        ),
+    })
+}
+
+/// A comparator of the values of `left` and `right`, of the same binary data
+/// type, that ignores their validity, as
+/// [`build_compare`](arrow::array::build_compare) does
+pub(crate) fn build_binary_compare(
+    left: &dyn Array,
+    right: &dyn Array,
+) -> Result<DynComparator> {
+    macro_rules! compare {
+        ($ARRAY:ident) => {{
+            let left = $ARRAY::from(left.data().clone());
+            let right = $ARRAY::from(right.data().clone());
+            Box::new(move |i, j| left.value(i).cmp(right.value(j)))
+        }};
+    }
+    Ok(match (left.data_type(), right.data_type()) {
+        (DataType::Binary, DataType::Binary) => compare!(BinaryArray),
+        (DataType::LargeBinary, DataType::LargeBinary) => compare!(LargeBinaryArray),
+        (DataType::FixedSizeBinary(_), DataType::FixedSizeBinary(_)) => {
+            compare!(FixedSizeBinaryArray)
+        }
+        (left_type, right_type) => {
+            return Err(DataFusionError::Internal(format!(
+                "Cannot compare the binary values of {:?} and {:?}",
+                left_type, right_type
+            )))
+        }
+    })
+}
+
+/// Sort the rows of `columns` lexicographically, like
+/// [`lexsort_to_indices`](arrow::compute::lexsort_to_indices), when some of
+/// them are of binary data types, which arrow can't sort yet, or intervals,
+/// which arrow doesn't sort on their durations. Only the first `limit`
+/// indices are returned, if any.
+pub(crate) fn lexsort_binary_to_indices(
+    columns: &[SortColumn],
+    limit: Option<usize>,
+) -> Result<UInt32Array> {
+    let row_count = columns.first().map_or(0, |column| column.values.len());
+    let comparators = columns
+        .iter()
+        .map(|column| {
+            let values = column.values.as_ref();
+            let comparator = if is_binary(values.data_type()) {
+                build_binary_compare(values, values)?
+            } else if is_interval(values.data_type()) {
+                build_interval_compare(values, values)?
+            } else {
+                build_compare(values, values)?
+            };
+            Ok((values, column.options.unwrap_or_default(), comparator))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut indices = (0..row_count as u32).collect::<Vec<_>>();
+    indices.sort_by(|i, j| {
+        let (i, j) = (*i as usize, *j as usize);
+        for (values, options, comparator) in &comparators {
+            let ordering = compare_rows(*values, options, comparator, i, j);
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+    if let Some(limit) = limit {
+        indices.truncate(limit);
+    }
+    Ok(UInt32Array::from(indices))
+}
+
+fn compare_rows(
+    values: &dyn Array,
+    options: &SortOptions,
+    comparator: &DynComparator,
+    i: usize,
+    j: usize,
+) -> Ordering {
+    match (values.is_valid(i), values.is_valid(j)) {
+        (true, true) => {
+            let ordering = comparator(i, j);
+            if options.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        }
+        (false, false) => Ordering::Equal,
+        (false, true) if options.nulls_first => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (true, false) if options.nulls_first => Ordering::Greater,
+        (true, false) => Ordering::Less,
+    }
+}
+
+/// Whether [`cast_binary`] supports casting from `from_type` to `to_type`
+pub(crate) fn can_cast_binary(from_type: &DataType, to_type: &DataType) -> bool {
+    use DataType::*;
+    matches!(
+        (from_type, to_type),
+        (
+            Binary | LargeBinary | FixedSizeBinary(_),
+            Binary | LargeBinary | Utf8 | LargeUtf8
+        ) | (Utf8 | LargeUtf8, Binary | LargeBinary)
+    )
+}
+
+macro_rules! binary_to_string {
+    ($VALUES:expr, $ARRAY:ident, $SAFE:expr) => {{
+        let strings = $VALUES
+            .iter()
+            .map(|value| match value.map(std::str::from_utf8) {
+                Some(Err(_)) if $SAFE => Ok(None),
+                Some(Err(e)) => Err(DataFusionError::Execution(format!(
+                    "Cannot cast binary value {} to a string: {}",
+                    format_hex(value.unwrap()),
+                    e
+                ))),
+                Some(Ok(value)) => Ok(Some(value)),
+                None => Ok(None),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Arc::new($ARRAY::from(strings))
+    }};
+}
+
+/// Cast `array` to `to_type`, see [`can_cast_binary`]. The strings are casted
+/// to their UTF-8 bytes, and the binary values to strings if they are valid
+/// UTF-8. Those that are not are nulls if `safe` and errors otherwise.
+pub(crate) fn cast_binary(
+    array: &ArrayRef,
+    to_type: &DataType,
+    safe: bool,
+) -> Result<ArrayRef> {
+    let bytes: Vec<Option<&[u8]>> = match array.data_type() {
+        DataType::Utf8 => as_string_array(array)
+            .iter()
+            .map(|value| value.map(str::as_bytes))
+            .collect(),
+        DataType::LargeUtf8 => as_largestring_array(array)
+            .iter()
+            .map(|value| value.map(str::as_bytes))
+            .collect(),
+        _ => BinaryValues::try_new(array.as_ref())?.iter().collect(),
+    };
+    Ok(match to_type {
+        DataType::Binary => Arc::new(bytes.into_iter().collect::<BinaryArray>()),
+        DataType::LargeBinary => {
+            Arc::new(bytes.into_iter().collect::<LargeBinaryArray>())
+        }
+        DataType::Utf8 => binary_to_string!(bytes, StringArray, safe),
+        DataType::LargeUtf8 => binary_to_string!(bytes, LargeStringArray, safe),
+        _ => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to {:?}",
+                array.data_type(),
+                to_type
+            )))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_size_binary(values: &[Option<&[u8]>]) -> ArrayRef {
+        Arc::new(
+            FixedSizeBinaryArray::try_from_sparse_iter(values.iter().cloned()).unwrap(),
+        )
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(format_hex(b"\xde\xad\x00\x0f"), "dead000f");
+        assert_eq!(parse_hex("DEad000f"), Some(b"\xde\xad\x00\x0f".to_vec()));
+        assert_eq!(parse_hex(""), Some(vec![]));
+        assert_eq!(parse_hex("abc"), None);
+        assert_eq!(parse_hex("zz"), None);
+    }
+
+    #[test]
+    fn comparisons() -> Result<()> {
+        let left = BinaryArray::from(vec![
+            Some(&b"\x01"[..]),
+            Some(b"\x01\x02"),
+            Some(b"\xff"),
+            None,
+        ]);
+        let right = LargeBinaryArray::from(vec![
+            Some(&b"\x01"[..]),
+            Some(b"\x02"),
+            Some(b"\x01\xff"),
+            Some(b""),
+        ]);
+        let expected = vec![Some(true), Some(false), Some(false), None];
+        assert_eq!(eq_binary(&left, &right)?, BooleanArray::from(expected));
+        let expected = vec![Some(false), Some(true), Some(false), None];
+        assert_eq!(lt_binary(&left, &right)?, BooleanArray::from(expected));
+        let expected = vec![Some(false), Some(false), Some(true), Some(true)];
+        assert_eq!(
+            is_distinct_from_binary(&left, &right)?,
+            BooleanArray::from(expected)
+        );
+        let expected = vec![Some(false), Some(false), Some(true), None];
+        assert_eq!(
+            gt_binary_scalar(&left, b"\x01\x02")?,
+            BooleanArray::from(expected)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn min_max_and_length() -> Result<()> {
+        let array = fixed_size_binary(&[Some(b"\x02\x00"), None, Some(b"\x01\xff")]);
+        assert_eq!(min_binary(array.as_ref())?, Some(b"\x01\xff".to_vec()));
+        assert_eq!(max_binary(array.as_ref())?, Some(b"\x02\x00".to_vec()));
+        let lengths = octet_length_binary(array.as_ref())?;
+        let lengths = lengths.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(lengths, &Int32Array::from(vec![Some(2), None, Some(2)]));
+
+        let array = fixed_size_binary(&[None]);
+        assert_eq!(min_binary(array.as_ref())?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn sort_binary_columns() -> Result<()> {
+        let keys: ArrayRef = Arc::new(BinaryArray::from(vec![
+            Some(&b"\x02"[..]),
+            None,
+            Some(b"\x01"),
+            Some(b"\x02"),
+        ]));
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 4]));
+        let columns = vec![
+            SortColumn {
+                values: keys,
+                options: Some(SortOptions {
+                    descending: false,
+                    nulls_first: true,
+                }),
+            },
+            SortColumn {
+                values,
+                options: Some(SortOptions {
+                    descending: true,
+                    nulls_first: false,
+                }),
+            },
+        ];
+        let indices = lexsort_binary_to_indices(&columns, None)?;
+        assert_eq!(indices, UInt32Array::from(vec![1, 2, 3, 0]));
+        let indices = lexsort_binary_to_indices(&columns, Some(2))?;
+        assert_eq!(indices, UInt32Array::from(vec![1, 2]));
+        Ok(())
+    }
+
+    #[test]
+    fn casts() -> Result<()> {
+        let array = fixed_size_binary(&[Some(b"ok"), None, Some(b"\xff\xfe")]);
+        assert!(can_cast_binary(array.data_type(), &DataType::Utf8));
+        assert!(!can_cast_binary(&DataType::Int32, &DataType::Binary));
+
+        // the invalid UTF-8 is an error unless the cast is safe
+        let result = cast_binary(&array, &DataType::Utf8, false);
+        assert!(result.unwrap_err().to_string().contains("fffe"));
+        let strings = cast_binary(&array, &DataType::LargeUtf8, true)?;
+        let strings = as_largestring_array(&strings);
+        assert_eq!(
+            strings,
+            &LargeStringArray::from(vec![Some("ok"), None, None])
+        );
+
+        let bytes = cast_binary(&strings.slice(0, 2), &DataType::Binary, false)?;
+        let bytes = bytes.as_any().downcast_ref::<BinaryArray>().unwrap();
+        assert_eq!(bytes, &BinaryArray::from(vec![Some(&b"ok"[..]), None]));
+        Ok(())
+    }
+}
@@ -17,10 +17,12 @@
 
 use std::{any::Any, sync::Arc};
 
+use super::coercion::{case_coercion, eq_coercion};
+use super::{try_cast, Literal};
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use arrow::array::{self, *};
-use arrow::compute::{eq, eq_utf8};
+use arrow::compute::{eq, eq_bool, eq_utf8, filter_record_batch};
 use arrow::datatypes::{DataType, Schema};
 use arrow::record_batch::RecordBatch;
 
@@ -105,142 +107,64 @@ impl CaseExpr {
     }
 }
 
-macro_rules! if_then_else {
-    ($BUILDER_TYPE:ty, $ARRAY_TYPE:ty, $BOOLS:expr, $TRUE:expr, $FALSE:expr) => {{
-        let true_values = $TRUE
-            .as_ref()
-            .as_any()
-            .downcast_ref::<$ARRAY_TYPE>()
-            .expect("true_values downcast failed");
-
-        let false_values = $FALSE
-            .as_ref()
-            .as_any()
-            .downcast_ref::<$ARRAY_TYPE>()
-            .expect("false_values downcast failed");
-
-        let mut builder = <$BUILDER_TYPE>::new($BOOLS.len());
-        for i in 0..$BOOLS.len() {
-            if $BOOLS.is_null(i) {
-                if false_values.is_null(i) {
-                    builder.append_null()?;
-                } else {
-                    builder.append_value(false_values.value(i))?;
-                }
-            } else if $BOOLS.value(i) {
-                if true_values.is_null(i) {
-                    builder.append_null()?;
-                } else {
-                    builder.append_value(true_values.value(i))?;
-                }
-            } else {
-                if false_values.is_null(i) {
-                    builder.append_null()?;
-                } else {
-                    builder.append_value(false_values.value(i))?;
-                }
-            }
+/// Runs of consecutive rows of `mask` with the same value, as
+/// `(value, start, end)`. Null rows are false.
+fn mask_runs(mask: &BooleanArray) -> Vec<(bool, usize, usize)> {
+    let mut runs: Vec<(bool, usize, usize)> = vec![];
+    for (row, value) in mask.iter().enumerate() {
+        let value = value == Some(true);
+        match runs.last_mut() {
+            Some((run_value, _, end)) if *run_value == value => *end = row + 1,
+            _ => runs.push((value, row, row + 1)),
         }
-        Ok(Arc::new(builder.finish()))
-    }};
+    }
+    runs
 }
 
+/// The values of `true_values` for the rows where `mask` is true, and of
+/// `false_values` for the others
 fn if_then_else(
-    bools: &BooleanArray,
-    true_values: ArrayRef,
-    false_values: ArrayRef,
-    data_type: &DataType,
+    mask: &BooleanArray,
+    true_values: &ArrayRef,
+    false_values: &ArrayRef,
 ) -> Result<ArrayRef> {
-    match data_type {
-        DataType::UInt8 => if_then_else!(
-            array::UInt8Builder,
-            array::UInt8Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::UInt16 => if_then_else!(
-            array::UInt16Builder,
-            array::UInt16Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::UInt32 => if_then_else!(
-            array::UInt32Builder,
-            array::UInt32Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::UInt64 => if_then_else!(
-            array::UInt64Builder,
-            array::UInt64Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Int8 => if_then_else!(
-            array::Int8Builder,
-            array::Int8Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Int16 => if_then_else!(
-            array::Int16Builder,
-            array::Int16Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Int32 => if_then_else!(
-            array::Int32Builder,
-            array::Int32Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Int64 => if_then_else!(
-            array::Int64Builder,
-            array::Int64Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Float32 => if_then_else!(
-            array::Float32Builder,
-            array::Float32Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Float64 => if_then_else!(
-            array::Float64Builder,
-            array::Float64Array,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Utf8 => if_then_else!(
-            array::StringBuilder,
-            array::StringArray,
-            bools,
-            true_values,
-            false_values
-        ),
-        DataType::Boolean => if_then_else!(
-            array::BooleanBuilder,
-            array::BooleanArray,
-            bools,
-            true_values,
-            false_values
-        ),
-        other => Err(DataFusionError::Execution(format!(
-            "CASE does not support '{:?}'",
-            other
-        ))),
+    let mut values = MutableArrayData::new(
+        vec![true_values.data(), false_values.data()],
+        true,
+        mask.len(),
+    );
+    for (value, start, end) in mask_runs(mask) {
+        values.extend(if value { 0 } else { 1 }, start, end);
+    }
+    Ok(make_array(values.freeze()))
+}
+
+/// Evaluate `expr` on the rows of `batch` where `selection` is true only, the
+/// other rows of the result being null
+fn evaluate_selection(
+    expr: &Arc<dyn PhysicalExpr>,
+    batch: &RecordBatch,
+    selection: &BooleanArray,
+) -> Result<ArrayRef> {
+    let runs = mask_runs(selection);
+    if let [(true, _, _)] = runs.as_slice() {
+        return Ok(expr.evaluate(batch)?.into_array(batch.num_rows()));
+    }
+
+    let selected = filter_record_batch(batch, selection)?;
+    let selected_values = expr.evaluate(&selected)?.into_array(selected.num_rows());
+    let mut values =
+        MutableArrayData::new(vec![selected_values.data()], true, selection.len());
+    let mut position = 0;
+    for (value, start, end) in runs {
+        if value {
+            values.extend(0, position, position + end - start);
+            position += end - start;
+        } else {
+            values.extend_nulls(end - start);
+        }
     }
+    Ok(make_array(values.freeze()))
 }
 
 macro_rules! array_equals {
@@ -297,9 +221,21 @@ fn array_equals(
         DataType::Float64 => {
             array_equals!(array::Float64Array, when_value, base_value, eq)
         }
+        DataType::Date32 => {
+            array_equals!(array::Date32Array, when_value, base_value, eq)
+        }
+        DataType::Date64 => {
+            array_equals!(array::Date64Array, when_value, base_value, eq)
+        }
+        DataType::Boolean => {
+            array_equals!(array::BooleanArray, when_value, base_value, eq_bool)
+        }
         DataType::Utf8 => {
             array_equals!(array::StringArray, when_value, base_value, eq_utf8)
         }
+        DataType::LargeUtf8 => {
+            array_equals!(array::LargeStringArray, when_value, base_value, eq_utf8)
+        }
         other => Err(DataFusionError::Execution(format!(
             "CASE does not support '{:?}'",
             other
@@ -308,92 +244,90 @@ fn array_equals(
 }
 
 impl CaseExpr {
-    /// This function evaluates the form of CASE that matches an expression to fixed values.
+    /// This function evaluates both forms of CASE, the one that matches an
+    /// expression to fixed values
     ///
     /// CASE expression
     ///     WHEN value THEN result
     ///     [WHEN ...]
     ///     [ELSE result]
     /// END
-    fn case_when_with_expr(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        let return_type = self.when_then_expr[0].1.data_type(&batch.schema())?;
-        let expr = self.expr.as_ref().unwrap();
-        let base_value = expr.evaluate(batch)?;
-        let base_type = expr.data_type(&batch.schema())?;
-        let base_value = base_value.into_array(batch.num_rows());
-
-        // start with the else condition, or nulls
-        let mut current_value: Option<ArrayRef> = if let Some(e) = &self.else_expr {
-            Some(e.evaluate(batch)?.into_array(batch.num_rows()))
-        } else {
-            Some(new_null_array(&return_type, batch.num_rows()))
-        };
-
-        // walk backwards through the when/then expressions
-        for i in (0..self.when_then_expr.len()).rev() {
-            let i = i as usize;
-
-            let when_value = self.when_then_expr[i].0.evaluate(batch)?;
-            let when_value = when_value.into_array(batch.num_rows());
-
-            let then_value = self.when_then_expr[i].1.evaluate(batch)?;
-            let then_value = then_value.into_array(batch.num_rows());
-
-            // build boolean array representing which rows match the "when" value
-            let when_match = array_equals(&base_type, when_value, base_value.clone())?;
-
-            current_value = Some(if_then_else(
-                &when_match,
-                then_value,
-                current_value.unwrap(),
-                &return_type,
-            )?);
-        }
-
-        Ok(ColumnarValue::Array(current_value.unwrap()))
-    }
-
-    /// This function evaluates the form of CASE where each WHEN expression is a boolean
-    /// expression.
+    ///
+    /// and the one where each WHEN expression is a boolean expression.
     ///
     /// CASE WHEN condition THEN result
     ///      [WHEN ...]
     ///      [ELSE result]
     /// END
-    fn case_when_no_expr(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        let return_type = self.when_then_expr[0].1.data_type(&batch.schema())?;
-
-        // start with the else condition, or nulls
-        let mut current_value: Option<ArrayRef> = if let Some(e) = &self.else_expr {
-            Some(e.evaluate(batch)?.into_array(batch.num_rows()))
-        } else {
-            Some(new_null_array(&return_type, batch.num_rows()))
+    ///
+    /// The expressions of each branch are only evaluated for the rows that
+    /// didn't match the previous branches, so that a THEN expression that
+    /// would fail for some rows can be guarded by a previous WHEN.
+    fn case_when(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let return_type = self.data_type(&batch.schema())?;
+        let base_value = match &self.expr {
+            Some(expr) => {
+                let base_type = expr.data_type(&batch.schema())?;
+                Some((
+                    expr.evaluate(batch)?.into_array(batch.num_rows()),
+                    base_type,
+                ))
+            }
+            None => None,
         };
 
-        // walk backwards through the when/then expressions
-        for i in (0..self.when_then_expr.len()).rev() {
-            let i = i as usize;
-
-            let when_value = self.when_then_expr[i].0.evaluate(batch)?;
-            let when_value = when_value.into_array(batch.num_rows());
-            let when_value = when_value
-                .as_ref()
-                .as_any()
-                .downcast_ref::<BooleanArray>()
-                .expect("WHEN expression did not return a BooleanArray");
-
-            let then_value = self.when_then_expr[i].1.evaluate(batch)?;
-            let then_value = then_value.into_array(batch.num_rows());
-
-            current_value = Some(if_then_else(
-                when_value,
-                then_value,
-                current_value.unwrap(),
-                &return_type,
-            )?);
+        // start with nulls, and the rows that didn't match any WHEN yet
+        let mut current_value = new_null_array(&return_type, batch.num_rows());
+        let mut remainder = BooleanArray::from(vec![true; batch.num_rows()]);
+        for (when_expr, then_expr) in &self.when_then_expr {
+            if !remainder.iter().any(|row| row == Some(true)) {
+                break;
+            }
+
+            let when_value = evaluate_selection(when_expr, batch, &remainder)?;
+            // the remaining rows matching the "when" value or condition, a null
+            // condition not matching
+            let when_match = match &base_value {
+                Some((base_value, base_type)) => {
+                    array_equals(base_type, when_value, base_value.clone())?
+                }
+                None => when_value
+                    .as_any()
+                    .downcast_ref::<BooleanArray>()
+                    .ok_or_else(|| {
+                        DataFusionError::Internal(
+                            "WHEN expression did not return a BooleanArray".to_string(),
+                        )
+                    })?
+                    .clone(),
+            };
+            let when_match = when_match
+                .iter()
+                .zip(remainder.iter())
+                .map(|(when, remaining)| {
+                    Some(when == Some(true) && remaining == Some(true))
+                })
+                .collect::<BooleanArray>();
+
+            let then_value = evaluate_selection(then_expr, batch, &when_match)?;
+            current_value = if_then_else(&when_match, &then_value, &current_value)?;
+            remainder = remainder
+                .iter()
+                .zip(when_match.iter())
+                .map(|(remaining, when)| {
+                    Some(remaining == Some(true) && when != Some(true))
+                })
+                .collect();
         }
 
-        Ok(ColumnarValue::Array(current_value.unwrap()))
+        if let Some(else_expr) = &self.else_expr {
+            if remainder.iter().any(|row| row == Some(true)) {
+                let else_value = evaluate_selection(else_expr, batch, &remainder)?;
+                current_value = if_then_else(&remainder, &else_value, &current_value)?;
+            }
+        }
+
+        Ok(ColumnarValue::Array(current_value))
     }
 }
 
@@ -409,7 +343,8 @@ impl PhysicalExpr for CaseExpr {
     }
 
     fn nullable(&self, input_schema: &Schema) -> Result<bool> {
-        // this expression is nullable if any of the input expressions are nullable
+        // this expression is nullable if any of the input expressions are nullable,
+        // or if there is no ELSE, for the rows not matching any WHEN
         let then_nullable = self
             .when_then_expr
             .iter()
@@ -420,30 +355,124 @@ impl PhysicalExpr for CaseExpr {
         } else if let Some(e) = &self.else_expr {
             e.nullable(input_schema)
         } else {
-            Ok(false)
+            Ok(true)
         }
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
-        if self.expr.is_some() {
-            // this use case evaluates "expr" and then compares the values with the "when"
-            // values
-            self.case_when_with_expr(batch)
-        } else {
-            // The "when" conditions all evaluate to boolean in this use case and can be
-            // arbitrary expressions
-            self.case_when_no_expr(batch)
-        }
+        self.case_when(batch)
+    }
+}
+
+/// The type of the results of a CASE expression, to which the types of its
+/// THEN and ELSE expressions, `result_types`, are coerced. The `None` types are
+/// the ones of NULL literals, which are coerced to any type.
+pub fn case_return_type(result_types: &[Option<DataType>]) -> Result<DataType> {
+    result_types
+        .iter()
+        .flatten()
+        .try_fold(None, |common_type: Option<DataType>, data_type| {
+            match common_type {
+                None => Ok(Some(data_type.clone())),
+                Some(common_type) => case_coercion(&common_type, data_type)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "The results of CASE of types {:?} and {:?} can't be coerced to a common type",
+                            common_type, data_type
+                        ))
+                    }),
+            }
+        })
+        // only NULL results
+        .map(|common_type| common_type.unwrap_or(DataType::Utf8))
+}
+
+/// The type to which the base expression of a CASE and its WHEN values, of
+/// types `types`, are coerced to be compared. The `None` types are the ones
+/// of NULL literals.
+fn case_operand_type(types: &[Option<DataType>]) -> Result<DataType> {
+    types
+        .iter()
+        .flatten()
+        .try_fold(None, |common_type: Option<DataType>, data_type| {
+            match common_type {
+                None => Ok(Some(data_type.clone())),
+                Some(common_type) => eq_coercion(&common_type, data_type)
+                    .map(Some)
+                    .ok_or_else(|| {
+                        DataFusionError::Plan(format!(
+                            "The CASE operand of type {:?} can't be compared with a WHEN value of type {:?}",
+                            common_type, data_type
+                        ))
+                    }),
+            }
+        })
+        .map(|common_type| common_type.unwrap_or(DataType::Utf8))
+}
+
+/// The type of `expr`, `None` for a NULL literal
+fn coercible_type(
+    expr: &Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+) -> Result<Option<DataType>> {
+    match expr.as_any().downcast_ref::<Literal>() {
+        Some(literal) if literal.value().is_null() => Ok(None),
+        _ => expr.data_type(input_schema).map(Some),
     }
 }
 
-/// Create a CASE expression
+/// Create a CASE expression whose results are coerced to a common type, as
+/// well as its base expression and its WHEN values if any.
+/// This function errors if there is no such common type.
 pub fn case(
     expr: Option<Arc<dyn PhysicalExpr>>,
     when_thens: &[(Arc<dyn PhysicalExpr>, Arc<dyn PhysicalExpr>)],
     else_expr: Option<Arc<dyn PhysicalExpr>>,
+    input_schema: &Schema,
 ) -> Result<Arc<dyn PhysicalExpr>> {
-    Ok(Arc::new(CaseExpr::try_new(expr, when_thens, else_expr)?))
+    let result_types = when_thens
+        .iter()
+        .map(|(_, then_expr)| then_expr)
+        .chain(else_expr.iter())
+        .map(|e| coercible_type(e, input_schema))
+        .collect::<Result<Vec<_>>>()?;
+    let return_type = case_return_type(&result_types)?;
+
+    let (expr, when_types) = match expr {
+        Some(expr) => {
+            let operand_types = std::iter::once(&expr)
+                .chain(when_thens.iter().map(|(when_expr, _)| when_expr))
+                .map(|e| coercible_type(e, input_schema))
+                .collect::<Result<Vec<_>>>()?;
+            let operand_type = case_operand_type(&operand_types)?;
+            (
+                Some(try_cast(expr, input_schema, operand_type.clone())?),
+                Some(operand_type),
+            )
+        }
+        None => (None, None),
+    };
+
+    let when_thens = when_thens
+        .iter()
+        .map(|(when_expr, then_expr)| {
+            let when_expr = match &when_types {
+                Some(operand_type) => {
+                    try_cast(when_expr.clone(), input_schema, operand_type.clone())?
+                }
+                None => when_expr.clone(),
+            };
+            let then_expr =
+                try_cast(then_expr.clone(), input_schema, return_type.clone())?;
+            Ok((when_expr, then_expr))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let else_expr = else_expr
+        .map(|e| try_cast(e, input_schema, return_type.clone()))
+        .transpose()?;
+
+    Ok(Arc::new(CaseExpr::try_new(expr, &when_thens, else_expr)?))
 }
 
 #[cfg(test)]
@@ -473,6 +502,7 @@ mod tests {
             Some(col("a", &schema)?),
             &[(when1, then1), (when2, then2)],
             None,
+            &schema,
         )?;
         let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
         let result = result
@@ -503,6 +533,7 @@ mod tests {
             Some(col("a", &schema)?),
             &[(when1, then1), (when2, then2)],
             Some(else_value),
+            &schema,
         )?;
         let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
         let result = result
@@ -539,7 +570,7 @@ mod tests {
         )?;
         let then2 = lit(ScalarValue::Int32(Some(456)));
 
-        let expr = case(None, &[(when1, then1), (when2, then2)], None)?;
+        let expr = case(None, &[(when1, then1), (when2, then2)], None, &schema)?;
         let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
         let result = result
             .as_any()
@@ -575,7 +606,12 @@ mod tests {
         let then2 = lit(ScalarValue::Int32(Some(456)));
         let else_value = lit(ScalarValue::Int32(Some(999)));
 
-        let expr = case(None, &[(when1, then1), (when2, then2)], Some(else_value))?;
+        let expr = case(
+            None,
+            &[(when1, then1), (when2, then2)],
+            Some(else_value),
+            &schema,
+        )?;
         let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
         let result = result
             .as_any()
@@ -590,6 +626,118 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn case_coerces_results() -> Result<()> {
+        let batch = case_test_batch()?;
+        let schema = batch.schema();
+
+        // CASE WHEN a = 'foo' THEN 123 WHEN a = 'bar' THEN NULL ELSE 2.5 END
+        let when1 = binary(
+            col("a", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Utf8(Some("foo".to_string()))),
+            &schema,
+        )?;
+        let then1 = lit(ScalarValue::Int32(Some(123)));
+        let when2 = binary(
+            col("a", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Utf8(Some("bar".to_string()))),
+            &schema,
+        )?;
+        let then2 = lit(ScalarValue::Utf8(None));
+        let else_value = lit(ScalarValue::Float64(Some(2.5)));
+
+        let expr = case(
+            None,
+            &[(when1, then1), (when2, then2)],
+            Some(else_value),
+            &schema,
+        )?;
+        assert_eq!(expr.data_type(&schema)?, DataType::Float64);
+        assert!(expr.nullable(&schema)?);
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .expect("failed to downcast to Float64Array");
+
+        let expected = &Float64Array::from(vec![Some(123.0), Some(2.5), Some(2.5), None]);
+
+        assert_eq!(expected, result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn case_return_types() {
+        let cases = vec![
+            (
+                vec![Some(DataType::Int32), Some(DataType::Float64)],
+                Some(DataType::Float64),
+            ),
+            (vec![Some(DataType::Int64), None], Some(DataType::Int64)),
+            (
+                vec![None, Some(DataType::Int8), Some(DataType::Int16)],
+                Some(DataType::Int16),
+            ),
+            (
+                vec![Some(DataType::Utf8), Some(DataType::LargeUtf8)],
+                Some(DataType::LargeUtf8),
+            ),
+            (vec![None, None], Some(DataType::Utf8)),
+            (vec![Some(DataType::Utf8), Some(DataType::Int64)], None),
+            (vec![Some(DataType::Boolean), Some(DataType::Float32)], None),
+        ];
+        for (result_types, expected) in cases {
+            let return_type = case_return_type(&result_types);
+            assert_eq!(return_type.ok(), expected, "{:?}", result_types);
+        }
+    }
+
+    #[test]
+    fn case_evaluates_branches_lazily() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![6, 7, 8])),
+                Arc::new(Int32Array::from(vec![2, 0, 4])),
+            ],
+        )?;
+
+        // CASE WHEN b = 0 THEN 0 ELSE a / b END, whose ELSE would fail for the
+        // second row
+        let when = binary(
+            col("b", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Int32(Some(0))),
+            &schema,
+        )?;
+        let then = lit(ScalarValue::Int32(Some(0)));
+        let else_value = binary(
+            col("a", &schema)?,
+            Operator::Divide,
+            col("b", &schema)?,
+            &schema,
+        )?;
+        assert!(else_value.evaluate(&batch).is_err());
+
+        let expr = case(None, &[(when, then)], Some(else_value), &schema)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result
+            .as_any()
+            .downcast_ref::<Int32Array>()
+            .expect("failed to downcast to Int32Array");
+
+        assert_eq!(&Int32Array::from(vec![3, 0, 2]), result);
+
+        Ok(())
+    }
+
     fn case_test_batch() -> Result<RecordBatch> {
         let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
         let a = StringArray::from(vec![Some("foo"), Some("baz"), None, Some("bar")]);
@@ -19,16 +19,20 @@ use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
 
+use super::bytes::{can_cast_binary, cast_binary};
+use super::decimal::{can_cast_decimal, cast_decimal};
+use super::overflow::{check_cast_overflow, ArithmeticOverflow};
+use super::string_cast::{can_cast_string, cast_string};
 use super::ColumnarValue;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::PhysicalExpr;
 use crate::scalar::ScalarValue;
+use arrow::array::ArrayRef;
 use arrow::compute;
 use arrow::compute::kernels;
 use arrow::compute::CastOptions;
 use arrow::datatypes::{DataType, Schema};
 use arrow::record_batch::RecordBatch;
-use compute::can_cast_types;
 
 use serde::{Deserialize, Serialize};
 
@@ -44,6 +48,10 @@ pub struct CastExpr {
     cast_type: DataType,
     /// Cast options
     cast_options: CastOptions,
+    /// Whether the integers out of range fail the cast rather than being
+    /// casted to nulls
+    #[serde(default)]
+    overflow: ArithmeticOverflow,
 }
 
 impl CastExpr {
@@ -52,11 +60,28 @@ impl CastExpr {
         expr: Arc<dyn PhysicalExpr>,
         cast_type: DataType,
         cast_options: CastOptions,
+    ) -> Self {
+        Self::new_with_overflow(
+            expr,
+            cast_type,
+            cast_options,
+            ArithmeticOverflow::default(),
+        )
+    }
+
+    /// Create a new CastExpr which fails on the integers out of range in the
+    /// `Error` mode of `overflow`, and casts them to nulls otherwise
+    pub fn new_with_overflow(
+        expr: Arc<dyn PhysicalExpr>,
+        cast_type: DataType,
+        cast_options: CastOptions,
+        overflow: ArithmeticOverflow,
     ) -> Self {
         Self {
             expr,
             cast_type,
             cast_options,
+            overflow,
         }
     }
 
@@ -69,6 +94,16 @@ impl CastExpr {
     pub fn cast_type(&self) -> &DataType {
         &self.cast_type
     }
+
+    /// The options of the cast
+    pub fn cast_options(&self) -> &CastOptions {
+        &self.cast_options
+    }
+
+    /// How the cast handles the integers out of range
+    pub fn overflow(&self) -> ArithmeticOverflow {
+        self.overflow
+    }
 }
 
 impl fmt::Display for CastExpr {
@@ -94,7 +129,57 @@ impl PhysicalExpr for CastExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
-        cast_column(&value, &self.cast_type, &self.cast_options)
+        let cast = cast_column(&value, &self.cast_type, &self.cast_options)?;
+        if self.overflow == ArithmeticOverflow::Error {
+            match (&value, &cast) {
+                (ColumnarValue::Array(array), ColumnarValue::Array(cast_array)) => {
+                    check_cast_overflow(array, cast_array, self.overflow, self)?
+                }
+                (ColumnarValue::Scalar(scalar), ColumnarValue::Scalar(cast_scalar)) => {
+                    check_cast_overflow(
+                        &scalar.to_array(),
+                        &cast_scalar.to_array(),
+                        self.overflow,
+                        self,
+                    )?
+                }
+                _ => {}
+            }
+        }
+        Ok(cast)
+    }
+}
+
+/// Whether values of `from_type` can be casted to `to_type`, by the arrow cast
+/// kernel or by the casts of decimals, binary values and strings it doesn't
+/// support yet
+pub fn can_cast_types(from_type: &DataType, to_type: &DataType) -> bool {
+    compute::can_cast_types(from_type, to_type)
+        || can_cast_decimal(from_type, to_type)
+        || can_cast_binary(from_type, to_type)
+        || can_cast_string(from_type, to_type)
+}
+
+/// Cast `array` to `cast_type`, with the arrow cast kernel or the casts of
+/// decimals, binary values and strings it doesn't support yet
+pub(super) fn cast_array(
+    array: &ArrayRef,
+    cast_type: &DataType,
+    cast_options: &CastOptions,
+) -> Result<ArrayRef> {
+    let from_type = array.data_type();
+    if from_type != cast_type && can_cast_decimal(from_type, cast_type) {
+        cast_decimal(array, cast_type, cast_options.safe)
+    } else if from_type != cast_type && can_cast_binary(from_type, cast_type) {
+        cast_binary(array, cast_type, cast_options.safe)
+    } else if from_type != cast_type && can_cast_string(from_type, cast_type) {
+        cast_string(array, cast_type, cast_options.safe)
+    } else {
+        Ok(kernels::cast::cast_with_options(
+            array,
+            cast_type,
+            cast_options,
+        )?)
     }
 }
 
@@ -105,13 +190,14 @@ pub fn cast_column(
     cast_options: &CastOptions,
 ) -> Result<ColumnarValue> {
     match value {
-        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(
-            kernels::cast::cast_with_options(array, cast_type, cast_options)?,
-        )),
+        ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_array(
+            array,
+            cast_type,
+            cast_options,
+        )?)),
         ColumnarValue::Scalar(scalar) => {
             let scalar_array = scalar.to_array();
-            let cast_array =
-                kernels::cast::cast_with_options(&scalar_array, cast_type, cast_options)?;
+            let cast_array = cast_array(&scalar_array, cast_type, cast_options)?;
             let cast_scalar = ScalarValue::try_from_array(&cast_array, 0)?;
             Ok(ColumnarValue::Scalar(cast_scalar))
         }
@@ -127,12 +213,33 @@ pub fn cast_with_options(
     input_schema: &Schema,
     cast_type: DataType,
     cast_options: CastOptions,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    cast_expr(
+        expr,
+        input_schema,
+        cast_type,
+        cast_options,
+        ArithmeticOverflow::default(),
+    )
+}
+
+fn cast_expr(
+    expr: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    cast_type: DataType,
+    cast_options: CastOptions,
+    overflow: ArithmeticOverflow,
 ) -> Result<Arc<dyn PhysicalExpr>> {
     let expr_type = expr.data_type(input_schema)?;
     if expr_type == cast_type {
         Ok(expr.clone())
     } else if can_cast_types(&expr_type, &cast_type) {
-        Ok(Arc::new(CastExpr::new(expr, cast_type, cast_options)))
+        Ok(Arc::new(CastExpr::new_with_overflow(
+            expr,
+            cast_type,
+            cast_options,
+            overflow,
+        )))
     } else {
         Err(DataFusionError::Internal(format!(
             "Unsupported CAST from {:?} to {:?}",
@@ -141,6 +248,24 @@ pub fn cast_with_options(
     }
 }
 
+/// Return a PhysicalExpression representing `expr` casted to
+/// `cast_type`, if any casting is needed, failing on the integers out of
+/// range in the `Error` mode of `overflow`
+pub fn cast_with_overflow(
+    expr: Arc<dyn PhysicalExpr>,
+    input_schema: &Schema,
+    cast_type: DataType,
+    overflow: ArithmeticOverflow,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    cast_expr(
+        expr,
+        input_schema,
+        cast_type,
+        DEFAULT_DATAFUSION_CAST_OPTIONS,
+        overflow,
+    )
+}
+
 /// Return a PhysicalExpression representing `expr` casted to
 /// `cast_type`, if any casting is needed.
 ///
@@ -163,7 +288,10 @@ mod tests {
     use super::*;
     use crate::error::Result;
     use crate::physical_plan::expressions::col;
-    use arrow::array::{StringArray, Time64NanosecondArray};
+    use arrow::array::{
+        BinaryArray, BooleanArray, DictionaryArray, Float64Array, LargeBinaryArray,
+        StringArray, Time64NanosecondArray, TimestampMillisecondArray,
+    };
     use arrow::{
         array::{Array, Int32Array, Int64Array, TimestampNanosecondArray, UInt32Array},
         datatypes::*,
@@ -254,6 +382,140 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_cast_binary_utf8() -> Result<()> {
+        generic_test_cast!(
+            BinaryArray,
+            DataType::Binary,
+            vec![&b"a"[..], b"\xe2\x82\xac", b"\xff"],
+            StringArray,
+            DataType::Utf8,
+            vec![Some("a"), Some("\u{20ac}"), None],
+            CastOptions { safe: true }
+        );
+        generic_test_cast!(
+            StringArray,
+            DataType::Utf8,
+            vec!["a", "\u{20ac}"],
+            LargeBinaryArray,
+            DataType::LargeBinary,
+            vec![Some(&b"a"[..]), Some(b"\xe2\x82\xac")],
+            DEFAULT_DATAFUSION_CAST_OPTIONS
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cast_utf8_temporal_boolean() -> Result<()> {
+        generic_test_cast!(
+            StringArray,
+            DataType::Utf8,
+            vec!["yes", "F", "unknown"],
+            BooleanArray,
+            DataType::Boolean,
+            vec![Some(true), Some(false), None],
+            CastOptions { safe: true }
+        );
+        generic_test_cast!(
+            StringArray,
+            DataType::Utf8,
+            vec!["2000-01-01T00:00:01.5Z", "noon"],
+            TimestampMillisecondArray,
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            vec![Some(946684801500_i64), None],
+            CastOptions { safe: true }
+        );
+        generic_test_cast!(
+            Float64Array,
+            DataType::Float64,
+            vec![1.5, f64::INFINITY],
+            StringArray,
+            DataType::Utf8,
+            vec![Some("1.5"), Some("Infinity")],
+            DEFAULT_DATAFUSION_CAST_OPTIONS
+        );
+
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, false)]);
+        let a = StringArray::from(vec!["2000-01-01", "2000-13-01"]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+        let expression = cast(col("a", &schema)?, &schema, DataType::Date32)?;
+        let err = expression.evaluate(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Cannot cast string '2000-13-01' to Date32"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cast_overflow() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let a = Int64Array::from(vec![Some(1), None, Some(i64::MAX)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+
+        // the values out of range are casted to nulls
+        let expression = cast_with_overflow(
+            col("a", &schema)?,
+            &schema,
+            DataType::Int32,
+            ArithmeticOverflow::Saturate,
+        )?;
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(
+            result.as_any().downcast_ref::<Int32Array>().unwrap(),
+            &Int32Array::from(vec![Some(1), None, None])
+        );
+
+        // or fail
+        let expression = cast_with_overflow(
+            col("a", &schema)?,
+            &schema,
+            DataType::Int32,
+            ArithmeticOverflow::Error,
+        )?;
+        let err = expression.evaluate(&batch).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow in CAST(a@0 AS Int32): 9223372036854775807 is out of the range of Int32"
+        );
+
+        // the values in range don't
+        let result = expression.evaluate(&batch.slice(0, 2))?.into_array(2);
+        assert_eq!(result.null_count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cast_dictionary() -> Result<()> {
+        let dict_type =
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+        let schema = Schema::new(vec![Field::new("a", DataType::Utf8, true)]);
+        let a = StringArray::from(vec![Some("x"), None, Some("y"), Some("x")]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![Arc::new(a)])?;
+
+        // plain values to a dictionary
+        let expression = cast(col("a", &schema)?, &schema, dict_type.clone())?;
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        assert_eq!(result.data_type(), &dict_type);
+        let dictionary = result
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int8Type>>()
+            .unwrap();
+        assert_eq!(dictionary.values().len(), 2);
+
+        // and back
+        let schema = Schema::new(vec![Field::new("a", dict_type, true)]);
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![result])?;
+        let expression = cast(col("a", &schema)?, &schema, DataType::Utf8)?;
+        let result = expression.evaluate(&batch)?.into_array(batch.num_rows());
+        let expected = StringArray::from(vec![Some("x"), None, Some("y"), Some("x")]);
+        assert_eq!(
+            result.as_any().downcast_ref::<StringArray>().unwrap(),
+            &expected
+        );
+        Ok(())
+    }
+
     #[allow(clippy::redundant_clone)]
     #[test]
     fn test_cast_i64_t64() -> Result<()> {
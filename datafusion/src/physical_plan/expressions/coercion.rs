@@ -17,7 +17,10 @@
 
 //! Coercion rules used to coerce types to match existing expressions' implementations
 
-use arrow::datatypes::DataType;
+use arrow::datatypes::{DataType, IntervalUnit};
+
+use crate::logical_plan::Operator;
+use crate::scalar::MAX_PRECISION_FOR_DECIMAL128;
 
 /// Determine if a DataType is signed numeric or not
 pub fn is_signed_numeric(dt: &DataType) -> bool {
@@ -37,9 +40,11 @@ pub fn is_signed_numeric(dt: &DataType) -> bool {
 pub fn is_numeric(dt: &DataType) -> bool {
     is_signed_numeric(dt)
         || match dt {
-            DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
-                true
-            }
+            DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+            | DataType::Decimal(_, _) => true,
             _ => false,
         }
 }
@@ -55,11 +60,11 @@ fn dictionary_value_coercion(
 /// Coercion rules for Dictionaries: the type that both lhs and rhs
 /// can be casted to for the purpose of a computation.
 ///
-/// It would likely be preferable to cast primitive values to
-/// dictionaries, and thus avoid unpacking dictionary as well as doing
-/// faster comparisons. However, the arrow compute kernels (e.g. eq)
-/// don't have DictionaryArray support yet, so fall back to unpacking
-/// the dictionaries
+/// The arrow compute kernels (e.g. eq) don't have DictionaryArray
+/// support yet, so the computations are done on the values. Note that a
+/// dictionary compared with a value of this type is not unpacked: the
+/// comparison is evaluated on the values of the dictionary instead (see
+/// `BinaryExpr`)
 pub fn dictionary_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
     match (lhs_type, rhs_type) {
         (
@@ -76,6 +81,20 @@ pub fn dictionary_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<D
     }
 }
 
+/// Coercion rules for the results of CASE: the type that the values of both
+/// lhs and rhs can be casted to, to be the results of the same expression
+pub fn case_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    if lhs_type == rhs_type {
+        return Some(lhs_type.clone());
+    }
+    numerical_coercion(lhs_type, rhs_type)
+        .or_else(|| string_coercion(lhs_type, rhs_type))
+        .or_else(|| dictionary_coercion(lhs_type, rhs_type))
+        .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| boolean_coercion(lhs_type, rhs_type))
+        .or_else(|| binary_coercion(lhs_type, rhs_type))
+}
+
 /// Coercion rules for Strings: the type that both lhs and rhs can be
 /// casted to for the purpose of a string computation
 pub fn string_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
@@ -89,6 +108,18 @@ pub fn string_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataT
     }
 }
 
+/// Coercion rules for binary values: the type that both lhs and rhs can be
+/// casted to for the purpose of a comparison
+pub fn binary_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (LargeBinary, Binary | LargeBinary | FixedSizeBinary(_))
+        | (Binary | FixedSizeBinary(_), LargeBinary) => Some(LargeBinary),
+        (Binary | FixedSizeBinary(_), Binary | FixedSizeBinary(_)) => Some(Binary),
+        _ => None,
+    }
+}
+
 /// coercion rules for like operations.
 /// This is a union of string coercion rules and dictionary coercion rules
 pub fn like_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
@@ -102,20 +133,17 @@ pub fn temporal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Dat
     use arrow::datatypes::DataType::*;
     use arrow::datatypes::TimeUnit;
     match (lhs_type, rhs_type) {
-        (Utf8, Date32) => Some(Date32),
-        (Date32, Utf8) => Some(Date32),
-        (Utf8, Date64) => Some(Date64),
-        (Date64, Utf8) => Some(Date64),
+        (Utf8 | LargeUtf8, Date32) | (Date32, Utf8 | LargeUtf8) => Some(Date32),
+        (Utf8 | LargeUtf8, Date64) | (Date64, Utf8 | LargeUtf8) => Some(Date64),
+        (Utf8 | LargeUtf8, Timestamp(unit, tz))
+        | (Timestamp(unit, tz), Utf8 | LargeUtf8) => {
+            Some(Timestamp(unit.clone(), tz.clone()))
+        }
         (Timestamp(lhs_unit, lhs_tz), Timestamp(rhs_unit, rhs_tz)) => {
             let tz = match (lhs_tz, rhs_tz) {
-                // can't cast across timezones
-                (Some(lhs_tz), Some(rhs_tz)) => {
-                    if lhs_tz != rhs_tz {
-                        return None;
-                    } else {
-                        Some(lhs_tz.clone())
-                    }
-                }
+                // the timestamps are instants whatever their time zones, which
+                // casting across time zones keeps
+                (Some(lhs_tz), Some(_)) => Some(lhs_tz.clone()),
                 (Some(lhs_tz), None) => Some(lhs_tz.clone()),
                 (None, Some(rhs_tz)) => Some(rhs_tz.clone()),
                 (None, None) => None,
@@ -146,6 +174,16 @@ pub fn temporal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Dat
     }
 }
 
+/// Coercion rules for booleans compared to strings, such as `'yes'` or `'f'`,
+/// which are casted to booleans
+pub fn boolean_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Utf8 | LargeUtf8, Boolean) | (Boolean, Utf8 | LargeUtf8) => Some(Boolean),
+        _ => None,
+    }
+}
+
 /// Coercion rule for numerical types: The type that both lhs and rhs
 /// can be casted to for numerical calculation, while maintaining
 /// maximum precision
@@ -162,6 +200,10 @@ pub fn numerical_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Da
         return Some(lhs_type.clone());
     }
 
+    if matches!(lhs_type, Decimal(_, _)) || matches!(rhs_type, Decimal(_, _)) {
+        return decimal_coercion(lhs_type, rhs_type);
+    }
+
     // these are ordered from most informative to least informative so
     // that the coercion removes the least amount of information
     match (lhs_type, rhs_type) {
@@ -179,6 +221,108 @@ pub fn numerical_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<Da
     }
 }
 
+/// Precision and scale of the decimals of `data_type`, or of the decimals
+/// holding any integer of `data_type`
+pub fn decimal_precision_scale(data_type: &DataType) -> Option<(usize, usize)> {
+    use arrow::datatypes::DataType::*;
+    match data_type {
+        Decimal(precision, scale) => Some((*precision, *scale)),
+        Int8 | UInt8 => Some((3, 0)),
+        Int16 | UInt16 => Some((5, 0)),
+        Int32 | UInt32 => Some((10, 0)),
+        Int64 => Some((19, 0)),
+        UInt64 => Some((20, 0)),
+        _ => None,
+    }
+}
+
+/// Coercion rules for decimals: the decimal type that a decimal and a decimal
+/// or an integer can be casted to without losing digits, up to the maximum
+/// precision, or Float64 for a decimal and a float
+pub fn decimal_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, rhs_type) {
+        (Decimal(_, _), Float32 | Float64) | (Float32 | Float64, Decimal(_, _)) => {
+            Some(Float64)
+        }
+        (Decimal(_, _), _) | (_, Decimal(_, _)) => {
+            let (lhs_precision, lhs_scale) = decimal_precision_scale(lhs_type)?;
+            let (rhs_precision, rhs_scale) = decimal_precision_scale(rhs_type)?;
+            let scale = lhs_scale.max(rhs_scale);
+            let precision =
+                (lhs_precision - lhs_scale).max(rhs_precision - rhs_scale) + scale;
+            Some(Decimal(precision.min(MAX_PRECISION_FOR_DECIMAL128), scale))
+        }
+        _ => None,
+    }
+}
+
+/// The type of the result of the arithmetic operator `op` applied to a
+/// decimal and a decimal or an integer, following the rules of SQL Server and
+/// Spark. A precision larger than the maximum one is reduced to it, at the
+/// expense of the digits after the point, keeping at least 6 of them.
+pub fn decimal_arithmetic_type(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> Option<DataType> {
+    let (p1, s1) = decimal_precision_scale(lhs_type)?;
+    let (p2, s2) = decimal_precision_scale(rhs_type)?;
+    let (precision, scale) = match op {
+        Operator::Plus | Operator::Minus => {
+            let scale = s1.max(s2);
+            ((p1 - s1).max(p2 - s2) + scale + 1, scale)
+        }
+        Operator::Multiply => (p1 + p2 + 1, s1 + s2),
+        Operator::Divide => {
+            let scale = (s1 + p2 + 1).max(6);
+            (p1 - s1 + s2 + scale, scale)
+        }
+        Operator::Modulo => {
+            let scale = s1.max(s2);
+            (((p1 - s1).min(p2 - s2) + scale).max(1), scale)
+        }
+        _ => return None,
+    };
+    if precision <= MAX_PRECISION_FOR_DECIMAL128 {
+        return Some(DataType::Decimal(precision, scale));
+    }
+    let integer_digits = precision - scale;
+    let scale = MAX_PRECISION_FOR_DECIMAL128
+        .saturating_sub(integer_digits)
+        .max(scale.min(6));
+    Some(DataType::Decimal(MAX_PRECISION_FOR_DECIMAL128, scale))
+}
+
+/// The type of the result of the arithmetic operator `op` applied to
+/// intervals: the sum and the difference of intervals of the same unit, and
+/// the product of an interval and a number, are intervals of that unit, and
+/// the difference of two timestamps is an interval of days and milliseconds
+pub fn interval_arithmetic_type(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> Option<DataType> {
+    use arrow::datatypes::DataType::*;
+    match (lhs_type, op, rhs_type) {
+        (Interval(lhs_unit), Operator::Plus | Operator::Minus, Interval(rhs_unit))
+            if lhs_unit == rhs_unit =>
+        {
+            Some(lhs_type.clone())
+        }
+        (Interval(unit), Operator::Multiply, other)
+        | (other, Operator::Multiply, Interval(unit))
+            if is_numeric(other) =>
+        {
+            Some(Interval(unit.clone()))
+        }
+        (Timestamp(_, _), Operator::Minus, Timestamp(_, _)) => {
+            Some(Interval(IntervalUnit::DayTime))
+        }
+        _ => None,
+    }
+}
+
 // coercion rules for equality operations. This is a superset of all numerical coercion rules.
 pub fn eq_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType> {
     if lhs_type == rhs_type {
@@ -188,6 +332,8 @@ pub fn eq_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataType>
     numerical_coercion(lhs_type, rhs_type)
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| boolean_coercion(lhs_type, rhs_type))
+        .or_else(|| binary_coercion(lhs_type, rhs_type))
 }
 
 // coercion rules that assume an ordered set, such as "less than".
@@ -202,6 +348,8 @@ pub fn order_coercion(lhs_type: &DataType, rhs_type: &DataType) -> Option<DataTy
         .or_else(|| string_coercion(lhs_type, rhs_type))
         .or_else(|| dictionary_coercion(lhs_type, rhs_type))
         .or_else(|| temporal_coercion(lhs_type, rhs_type))
+        .or_else(|| boolean_coercion(lhs_type, rhs_type))
+        .or_else(|| binary_coercion(lhs_type, rhs_type))
 }
 
 #[cfg(test)]
@@ -229,4 +377,151 @@ mod tests {
         let rhs_type = Dictionary(Box::new(Int8), Box::new(Utf8));
         assert_eq!(dictionary_coercion(&lhs_type, &rhs_type), Some(Utf8));
     }
+
+    #[test]
+    fn test_temporal_coercion() {
+        use arrow::datatypes::TimeUnit::*;
+        use DataType::*;
+
+        let tz = |name: &str| Some(name.to_owned());
+        let new_york = Timestamp(Nanosecond, tz("America/New_York"));
+        let paris = Timestamp(Millisecond, tz("Europe/Paris"));
+        assert_eq!(
+            temporal_coercion(&new_york, &paris),
+            Some(Timestamp(Millisecond, tz("America/New_York")))
+        );
+        assert_eq!(
+            temporal_coercion(&paris, &new_york),
+            Some(Timestamp(Millisecond, tz("Europe/Paris")))
+        );
+        assert_eq!(
+            temporal_coercion(&Timestamp(Second, None), &new_york),
+            Some(Timestamp(Second, tz("America/New_York")))
+        );
+        assert_eq!(
+            temporal_coercion(&LargeUtf8, &paris),
+            Some(Timestamp(Millisecond, tz("Europe/Paris")))
+        );
+        assert_eq!(temporal_coercion(&Date32, &LargeUtf8), Some(Date32));
+        assert_eq!(eq_coercion(&Utf8, &Boolean), Some(Boolean));
+        assert_eq!(order_coercion(&Boolean, &LargeUtf8), Some(Boolean));
+        assert_eq!(boolean_coercion(&Boolean, &Int32), None);
+    }
+
+    #[test]
+    fn test_binary_coercion() {
+        use DataType::*;
+
+        assert_eq!(
+            eq_coercion(&FixedSizeBinary(4), &FixedSizeBinary(4)),
+            Some(FixedSizeBinary(4))
+        );
+        assert_eq!(
+            eq_coercion(&FixedSizeBinary(4), &FixedSizeBinary(2)),
+            Some(Binary)
+        );
+        assert_eq!(order_coercion(&Binary, &FixedSizeBinary(4)), Some(Binary));
+        assert_eq!(order_coercion(&LargeBinary, &Binary), Some(LargeBinary));
+        assert_eq!(eq_coercion(&Binary, &Utf8), None);
+    }
+
+    #[test]
+    fn test_decimal_coercion() {
+        use DataType::*;
+
+        assert_eq!(
+            numerical_coercion(&Decimal(10, 2), &Decimal(12, 5)),
+            Some(Decimal(13, 5))
+        );
+        assert_eq!(
+            numerical_coercion(&Int32, &Decimal(5, 3)),
+            Some(Decimal(13, 3))
+        );
+        assert_eq!(
+            numerical_coercion(&Decimal(38, 10), &Int64),
+            Some(Decimal(38, 10))
+        );
+        assert_eq!(numerical_coercion(&Decimal(10, 2), &Float32), Some(Float64));
+        assert_eq!(numerical_coercion(&Decimal(10, 2), &Utf8), None);
+    }
+
+    #[test]
+    fn test_interval_arithmetic_type() {
+        use arrow::datatypes::TimeUnit;
+        let months = DataType::Interval(IntervalUnit::YearMonth);
+        let days = DataType::Interval(IntervalUnit::DayTime);
+        assert_eq!(
+            interval_arithmetic_type(&months, &Operator::Plus, &months),
+            Some(months.clone())
+        );
+        assert_eq!(
+            interval_arithmetic_type(&months, &Operator::Minus, &days),
+            None
+        );
+        assert_eq!(
+            interval_arithmetic_type(&DataType::Int8, &Operator::Multiply, &days),
+            Some(days.clone())
+        );
+        assert_eq!(
+            interval_arithmetic_type(&days, &Operator::Divide, &DataType::Int8),
+            None
+        );
+        assert_eq!(
+            interval_arithmetic_type(
+                &DataType::Timestamp(TimeUnit::Second, None),
+                &Operator::Minus,
+                &DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".to_owned()))
+            ),
+            Some(days)
+        );
+    }
+
+    #[test]
+    fn test_decimal_arithmetic_type() {
+        use DataType::*;
+
+        let result_type = |lhs_type, op, rhs_type| {
+            decimal_arithmetic_type(&lhs_type, &op, &rhs_type).unwrap()
+        };
+        let (lhs_type, rhs_type) = (Decimal(5, 2), Decimal(3, 1));
+        assert_eq!(
+            result_type(lhs_type.clone(), Operator::Plus, rhs_type.clone()),
+            Decimal(6, 2)
+        );
+        assert_eq!(
+            result_type(lhs_type.clone(), Operator::Minus, Int8),
+            Decimal(6, 2)
+        );
+        assert_eq!(
+            result_type(lhs_type.clone(), Operator::Multiply, rhs_type.clone()),
+            Decimal(9, 3)
+        );
+        assert_eq!(
+            result_type(lhs_type.clone(), Operator::Divide, rhs_type.clone()),
+            Decimal(10, 6)
+        );
+        assert_eq!(
+            result_type(lhs_type.clone(), Operator::Modulo, rhs_type),
+            Decimal(4, 2)
+        );
+        assert_eq!(
+            decimal_arithmetic_type(&lhs_type, &Operator::Eq, &lhs_type),
+            None
+        );
+
+        // the digits after the point are reduced to fit the maximum precision
+        let large = Decimal(38, 10);
+        assert_eq!(
+            result_type(large.clone(), Operator::Plus, large.clone()),
+            Decimal(38, 9)
+        );
+        assert_eq!(
+            result_type(large.clone(), Operator::Multiply, large.clone()),
+            Decimal(38, 6)
+        );
+        assert_eq!(
+            result_type(large.clone(), Operator::Divide, large),
+            Decimal(38, 6)
+        );
+    }
 }
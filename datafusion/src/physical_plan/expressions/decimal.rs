@@ -0,0 +1,756 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels of the `Decimal` data type that arrow doesn't provide yet: casts
+//! between decimals and from and to the other numeric types and strings,
+//! comparisons and arithmetic.
+//!
+//! A value of type `Decimal(precision, scale)` is stored as an `i128` of at
+//! most `precision` digits, the value multiplied by `10^scale`.
+
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+
+/// `10^exp`, `None` if it overflows an `i128`
+fn pow10(exp: usize) -> Option<i128> {
+    10_i128.checked_pow(exp as u32)
+}
+
+/// Whether `value` has at most `precision` digits
+pub(super) fn fits_precision(value: i128, precision: usize) -> bool {
+    match pow10(precision) {
+        Some(max) => value.unsigned_abs() < max.unsigned_abs(),
+        None => true,
+    }
+}
+
+/// `numerator / divisor`, rounded half away from zero
+fn div_round(numerator: i128, divisor: i128) -> i128 {
+    let quotient = numerator / divisor;
+    let remainder = numerator % divisor;
+    if remainder.unsigned_abs() * 2 < divisor.unsigned_abs() {
+        quotient
+    } else if (numerator < 0) != (divisor < 0) {
+        quotient - 1
+    } else {
+        quotient + 1
+    }
+}
+
+/// Convert `value` from `from_scale` to `to_scale` digits after the point,
+/// rounding half away from zero. `None` if it overflows an `i128`
+pub(super) fn rescale(value: i128, from_scale: usize, to_scale: usize) -> Option<i128> {
+    if to_scale >= from_scale {
+        value.checked_mul(pow10(to_scale - from_scale)?)
+    } else {
+        // a divisor larger than any i128 rounds any value to zero
+        Some(pow10(from_scale - to_scale).map_or(0, |d| div_round(value, d)))
+    }
+}
+
+/// Parse `s`, a number such as `-12.345`, into a decimal of `scale` digits
+/// after the point, rounding half away from zero. `None` if `s` is not a
+/// number or if it overflows an `i128`
+pub(super) fn parse_decimal(s: &str, scale: usize) -> Option<i128> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+    if (integer.is_empty() && fraction.is_empty())
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let mut value: i128 = 0;
+    for b in integer.bytes().chain(fraction.bytes().take(scale)) {
+        value = value.checked_mul(10)?.checked_add((b - b'0') as i128)?;
+    }
+    value = value.checked_mul(pow10(scale - fraction.len().min(scale))?)?;
+    if matches!(fraction.as_bytes().get(scale), Some(b) if *b >= b'5') {
+        value = value.checked_add(1)?;
+    }
+    Some(if negative { -value } else { value })
+}
+
+/// Format `value`, a decimal of `scale` digits after the point
+pub(super) fn format_decimal(value: i128, scale: usize) -> String {
+    let sign = if value < 0 { "-" } else { "" };
+    let digits = value.unsigned_abs().to_string();
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
+/// Values of `array`, `None` for the nulls
+fn decimal_values(array: &DecimalArray) -> impl Iterator<Item = Option<i128>> + '_ {
+    (0..array.len()).map(move |i| {
+        if array.is_null(i) {
+            None
+        } else {
+            Some(array.value(i))
+        }
+    })
+}
+
+/// Whether [`cast_decimal`] supports casting from `from_type` to `to_type`
+pub(super) fn can_cast_decimal(from_type: &DataType, to_type: &DataType) -> bool {
+    use DataType::*;
+    match (from_type, to_type) {
+        (Decimal(_, _), Decimal(_, _)) => true,
+        (Decimal(_, _), other) | (other, Decimal(_, _)) => matches!(
+            other,
+            Int8 | Int16
+                | Int32
+                | Int64
+                | UInt8
+                | UInt16
+                | UInt32
+                | UInt64
+                | Float32
+                | Float64
+                | Utf8
+                | LargeUtf8
+        ),
+        _ => false,
+    }
+}
+
+fn invalid_cast(value: impl std::fmt::Display, to_type: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!("Cannot cast value '{}' to {:?}", value, to_type))
+}
+
+macro_rules! integer_to_decimal {
+    ($ARRAY:expr, $INDEX:expr, $SCALE:expr, $ARRAYTYPE:ident) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        pow10($SCALE).and_then(|m| (array.value($INDEX) as i128).checked_mul(m))
+    }};
+}
+
+macro_rules! float_to_decimal {
+    ($ARRAY:expr, $INDEX:expr, $SCALE:expr, $ARRAYTYPE:ident) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        let value = (array.value($INDEX) as f64 * 10_f64.powi($SCALE as i32)).round();
+        // beyond 1e38, the value overflows the largest precision anyway
+        if value.is_finite() && value.abs() < 1e38 {
+            Some(value as i128)
+        } else {
+            None
+        }
+    }};
+}
+
+macro_rules! string_to_decimal {
+    ($ARRAY:expr, $INDEX:expr, $SCALE:expr, $ARRAYTYPE:ident) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        parse_decimal(array.value($INDEX), $SCALE)
+    }};
+}
+
+/// The value at `index` of `array`, not null, as a decimal of `scale`
+/// digits after the point. `None` if it is not a number or overflows
+fn to_decimal(array: &ArrayRef, index: usize, scale: usize) -> Result<Option<i128>> {
+    Ok(match array.data_type() {
+        DataType::Int8 => integer_to_decimal!(array, index, scale, Int8Array),
+        DataType::Int16 => integer_to_decimal!(array, index, scale, Int16Array),
+        DataType::Int32 => integer_to_decimal!(array, index, scale, Int32Array),
+        DataType::Int64 => integer_to_decimal!(array, index, scale, Int64Array),
+        DataType::UInt8 => integer_to_decimal!(array, index, scale, UInt8Array),
+        DataType::UInt16 => integer_to_decimal!(array, index, scale, UInt16Array),
+        DataType::UInt32 => integer_to_decimal!(array, index, scale, UInt32Array),
+        DataType::UInt64 => integer_to_decimal!(array, index, scale, UInt64Array),
+        DataType::Float32 => float_to_decimal!(array, index, scale, Float32Array),
+        DataType::Float64 => float_to_decimal!(array, index, scale, Float64Array),
+        DataType::Utf8 => string_to_decimal!(array, index, scale, StringArray),
+        DataType::LargeUtf8 => string_to_decimal!(array, index, scale, LargeStringArray),
+        DataType::Decimal(_, from_scale) => {
+            let array = array.as_any().downcast_ref::<DecimalArray>().unwrap();
+            rescale(array.value(index), *from_scale, scale)
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to Decimal",
+                other
+            )))
+        }
+    })
+}
+
+macro_rules! decimal_to_integer {
+    ($ARRAY:expr, $TO_TYPE:expr, $SAFE:expr, $ARRAYTYPE:ident, $NATIVE:ty) => {{
+        let divisor = pow10($ARRAY.scale());
+        let array = decimal_values($ARRAY)
+            .map(|value| match value {
+                None => Ok(None),
+                Some(value) => {
+                    // the digits after the point are truncated
+                    let integer = divisor.map_or(0, |d| value / d);
+                    match <$NATIVE>::try_from(integer) {
+                        Ok(integer) => Ok(Some(integer)),
+                        Err(_) if $SAFE => Ok(None),
+                        Err(_) => Err(invalid_cast(
+                            format_decimal(value, $ARRAY.scale()),
+                            $TO_TYPE,
+                        )),
+                    }
+                }
+            })
+            .collect::<Result<$ARRAYTYPE>>()?;
+        Arc::new(array) as ArrayRef
+    }};
+}
+
+macro_rules! decimal_to_float {
+    ($ARRAY:expr, $ARRAYTYPE:ident, $NATIVE:ty) => {{
+        let divisor = 10_f64.powi($ARRAY.scale() as i32);
+        let array = decimal_values($ARRAY)
+            .map(|value| value.map(|v| (v as f64 / divisor) as $NATIVE))
+            .collect::<$ARRAYTYPE>();
+        Arc::new(array) as ArrayRef
+    }};
+}
+
+macro_rules! decimal_to_string {
+    ($ARRAY:expr, $ARRAYTYPE:ident) => {{
+        let array = decimal_values($ARRAY)
+            .map(|value| value.map(|v| format_decimal(v, $ARRAY.scale())))
+            .collect::<$ARRAYTYPE>();
+        Arc::new(array) as ArrayRef
+    }};
+}
+
+/// Cast `array` to `to_type`, one of them being a decimal type, see
+/// [`can_cast_decimal`]. The digits after the point that don't fit in the
+/// target type are rounded half away from zero, except when casting to an
+/// integer type, where they are truncated. Values that don't fit in the
+/// target type, and strings that are not numbers, are nulls if `safe` and
+/// errors otherwise.
+pub(super) fn cast_decimal(
+    array: &ArrayRef,
+    to_type: &DataType,
+    safe: bool,
+) -> Result<ArrayRef> {
+    if let DataType::Decimal(precision, scale) = to_type {
+        let mut builder = DecimalBuilder::new(array.len(), *precision, *scale);
+        for i in 0..array.len() {
+            if array.is_null(i) {
+                builder.append_null()?;
+                continue;
+            }
+            match to_decimal(array, i, *scale)? {
+                Some(value) if fits_precision(value, *precision) => {
+                    builder.append_value(value)?
+                }
+                _ if safe => builder.append_null()?,
+                _ => {
+                    return Err(invalid_cast(
+                        arrow::util::display::array_value_to_string(array, i)?,
+                        to_type,
+                    ))
+                }
+            }
+        }
+        return Ok(Arc::new(builder.finish()));
+    }
+
+    let decimals = match array.data_type() {
+        DataType::Decimal(_, _) => array.as_any().downcast_ref::<DecimalArray>().unwrap(),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to {:?}",
+                other, to_type
+            )))
+        }
+    };
+    Ok(match to_type {
+        DataType::Int8 => decimal_to_integer!(decimals, to_type, safe, Int8Array, i8),
+        DataType::Int16 => decimal_to_integer!(decimals, to_type, safe, Int16Array, i16),
+        DataType::Int32 => decimal_to_integer!(decimals, to_type, safe, Int32Array, i32),
+        DataType::Int64 => decimal_to_integer!(decimals, to_type, safe, Int64Array, i64),
+        DataType::UInt8 => decimal_to_integer!(decimals, to_type, safe, UInt8Array, u8),
+        DataType::UInt16 => {
+            decimal_to_integer!(decimals, to_type, safe, UInt16Array, u16)
+        }
+        DataType::UInt32 => {
+            decimal_to_integer!(decimals, to_type, safe, UInt32Array, u32)
+        }
+        DataType::UInt64 => {
+            decimal_to_integer!(decimals, to_type, safe, UInt64Array, u64)
+        }
+        DataType::Float32 => decimal_to_float!(decimals, Float32Array, f32),
+        DataType::Float64 => decimal_to_float!(decimals, Float64Array, f64),
+        DataType::Utf8 => decimal_to_string!(decimals, StringArray),
+        DataType::LargeUtf8 => decimal_to_string!(decimals, LargeStringArray),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to {:?}",
+                array.data_type(),
+                other
+            )))
+        }
+    })
+}
+
+fn compare_decimal<F: Fn(i128, i128) -> bool>(
+    left: &DecimalArray,
+    right: &DecimalArray,
+    op: F,
+) -> Result<BooleanArray> {
+    if left.len() != right.len() {
+        return Err(DataFusionError::ArrowError(ArrowError::ComputeError(
+            "Cannot perform comparison operation on arrays of different length"
+                .to_string(),
+        )));
+    }
+    Ok(decimal_values(left)
+        .zip(decimal_values(right))
+        .map(|(left, right)| match (left, right) {
+            (Some(left), Some(right)) => Some(op(left, right)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn compare_decimal_scalar<F: Fn(i128, i128) -> bool>(
+    left: &DecimalArray,
+    right: i128,
+    op: F,
+) -> Result<BooleanArray> {
+    Ok(decimal_values(left)
+        .map(|left| left.map(|left| op(left, right)))
+        .collect())
+}
+
+// The comparison kernels expect decimals of the same scale, the arguments
+// being coerced to a common decimal type
+
+pub(super) fn eq_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left == right)
+}
+
+pub(super) fn neq_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left != right)
+}
+
+pub(super) fn lt_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left < right)
+}
+
+pub(super) fn lt_eq_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left <= right)
+}
+
+pub(super) fn gt_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left > right)
+}
+
+pub(super) fn gt_eq_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    compare_decimal(left, right, |left, right| left >= right)
+}
+
+pub(super) fn is_distinct_from_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    Ok(decimal_values(left)
+        .zip(decimal_values(right))
+        .map(|(left, right)| Some(left != right))
+        .collect())
+}
+
+pub(super) fn is_not_distinct_from_decimal(
+    left: &DecimalArray,
+    right: &DecimalArray,
+) -> Result<BooleanArray> {
+    Ok(decimal_values(left)
+        .zip(decimal_values(right))
+        .map(|(left, right)| Some(left == right))
+        .collect())
+}
+
+pub(super) fn eq_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left == right)
+}
+
+pub(super) fn neq_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left != right)
+}
+
+pub(super) fn lt_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left < right)
+}
+
+pub(super) fn lt_eq_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left <= right)
+}
+
+pub(super) fn gt_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left > right)
+}
+
+pub(super) fn gt_eq_decimal_scalar(
+    left: &DecimalArray,
+    right: i128,
+) -> Result<BooleanArray> {
+    compare_decimal_scalar(left, right, |left, right| left >= right)
+}
+
+/// `left op right`, `left` of `left_scale` and `right` of `right_scale`
+/// digits after the point, as a decimal of `scale` digits after the point.
+/// `None` if it overflows an `i128`
+fn decimal_op(
+    left: i128,
+    left_scale: usize,
+    op: &Operator,
+    right: i128,
+    right_scale: usize,
+    scale: usize,
+) -> Result<Option<i128>> {
+    if matches!(op, Operator::Divide | Operator::Modulo) && right == 0 {
+        return Err(DataFusionError::ArrowError(ArrowError::DivideByZero));
+    }
+    let common_scale = left_scale.max(right_scale);
+    let value = match op {
+        Operator::Plus | Operator::Minus | Operator::Modulo => {
+            match (
+                rescale(left, left_scale, common_scale),
+                rescale(right, right_scale, common_scale),
+            ) {
+                (Some(left), Some(right)) => match op {
+                    Operator::Plus => left.checked_add(right),
+                    Operator::Minus => left.checked_sub(right),
+                    _ => left.checked_rem(right),
+                }
+                .and_then(|value| rescale(value, common_scale, scale)),
+                _ => None,
+            }
+        }
+        Operator::Multiply => left
+            .checked_mul(right)
+            .and_then(|value| rescale(value, left_scale + right_scale, scale)),
+        // left / 10^ls / (right / 10^rs) * 10^s = left * 10^(s + rs - ls) / right
+        Operator::Divide => {
+            if scale + right_scale >= left_scale {
+                pow10(scale + right_scale - left_scale)
+                    .and_then(|m| left.checked_mul(m))
+                    .map(|numerator| div_round(numerator, right))
+            } else {
+                pow10(left_scale - scale - right_scale)
+                    .and_then(|m| right.checked_mul(m))
+                    .map(|divisor| div_round(left, divisor))
+            }
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Operator {} is not an arithmetic operator of decimals",
+                other
+            )))
+        }
+    };
+    Ok(value)
+}
+
+/// Apply the arithmetic operator `op` to the decimals `left` and `right`,
+/// of any precisions and scales, into decimals of `result_type`, see
+/// [`decimal_arithmetic_type`](super::coercion::decimal_arithmetic_type).
+/// The digits after the point that don't fit in `result_type` are rounded
+/// half away from zero, and results that don't fit in its precision are
+/// errors.
+pub(super) fn decimal_arithmetic(
+    left: &DecimalArray,
+    op: &Operator,
+    right: &DecimalArray,
+    result_type: &DataType,
+) -> Result<DecimalArray> {
+    let (precision, scale) = match result_type {
+        DataType::Decimal(precision, scale) => (*precision, *scale),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Decimal arithmetic cannot produce values of type {:?}",
+                other
+            )))
+        }
+    };
+    if left.len() != right.len() {
+        return Err(DataFusionError::ArrowError(ArrowError::ComputeError(
+            "Cannot perform binary operation on arrays of different length".to_string(),
+        )));
+    }
+    let mut builder = DecimalBuilder::new(left.len(), precision, scale);
+    for (l, r) in decimal_values(left).zip(decimal_values(right)) {
+        match (l, r) {
+            (Some(l), Some(r)) => {
+                match decimal_op(l, left.scale(), op, r, right.scale(), scale)? {
+                    Some(value) if fits_precision(value, precision) => {
+                        builder.append_value(value)?
+                    }
+                    _ => {
+                        return Err(DataFusionError::Execution(format!(
+                            "Overflow of {:?} computing {} {} {}",
+                            result_type,
+                            format_decimal(l, left.scale()),
+                            op,
+                            format_decimal(r, right.scale())
+                        )))
+                    }
+                }
+            }
+            _ => builder.append_null()?,
+        }
+    }
+    Ok(builder.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal_array(
+        values: &[Option<i128>],
+        precision: usize,
+        scale: usize,
+    ) -> ArrayRef {
+        let mut builder = DecimalBuilder::new(values.len(), precision, scale);
+        for value in values {
+            match value {
+                Some(v) => builder.append_value(*v).unwrap(),
+                None => builder.append_null().unwrap(),
+            }
+        }
+        Arc::new(builder.finish())
+    }
+
+    fn values(array: &ArrayRef) -> Vec<Option<i128>> {
+        decimal_values(array.as_any().downcast_ref::<DecimalArray>().unwrap()).collect()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        assert_eq!(parse_decimal("12.345", 2), Some(1235));
+        assert_eq!(parse_decimal("-12.345", 2), Some(-1235));
+        assert_eq!(parse_decimal("+0.5", 3), Some(500));
+        assert_eq!(parse_decimal(".5", 0), Some(1));
+        assert_eq!(parse_decimal("7", 2), Some(700));
+        assert_eq!(parse_decimal("1e5", 2), None);
+        assert_eq!(parse_decimal("", 2), None);
+        assert_eq!(parse_decimal(".", 2), None);
+
+        assert_eq!(format_decimal(1235, 2), "12.35");
+        assert_eq!(format_decimal(-5, 3), "-0.005");
+        assert_eq!(format_decimal(42, 0), "42");
+    }
+
+    #[test]
+    fn rescale_rounds_half_away_from_zero() {
+        assert_eq!(rescale(125, 2, 1), Some(13));
+        assert_eq!(rescale(-125, 2, 1), Some(-13));
+        assert_eq!(rescale(124, 2, 1), Some(12));
+        assert_eq!(rescale(12, 1, 3), Some(1200));
+        assert_eq!(rescale(i128::MAX, 0, 1), None);
+        assert_eq!(rescale(i128::MAX, 40, 0), Some(0));
+    }
+
+    #[test]
+    fn cast_to_decimal() -> Result<()> {
+        let ints: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None, Some(-200)]));
+        let result = cast_decimal(&ints, &DataType::Decimal(5, 2), false)?;
+        assert_eq!(result.data_type(), &DataType::Decimal(5, 2));
+        assert_eq!(values(&result), vec![Some(100), None, Some(-20000)]);
+
+        // -200.00 doesn't fit in DECIMAL(4, 2)
+        assert!(cast_decimal(&ints, &DataType::Decimal(4, 2), false).is_err());
+        let result = cast_decimal(&ints, &DataType::Decimal(4, 2), true)?;
+        assert_eq!(values(&result), vec![Some(100), None, None]);
+
+        let floats: ArrayRef = Arc::new(Float64Array::from(vec![1.005, -2.5, f64::NAN]));
+        let result = cast_decimal(&floats, &DataType::Decimal(10, 1), true)?;
+        assert_eq!(values(&result), vec![Some(10), Some(-25), None]);
+
+        let strings: ArrayRef = Arc::new(StringArray::from(vec!["1.25", "x", "-3"]));
+        let result = cast_decimal(&strings, &DataType::Decimal(10, 1), true)?;
+        assert_eq!(values(&result), vec![Some(13), None, Some(-30)]);
+        assert!(cast_decimal(&strings, &DataType::Decimal(10, 1), false).is_err());
+
+        let decimals = decimal_array(&[Some(12345), Some(-5)], 10, 3);
+        let result = cast_decimal(&decimals, &DataType::Decimal(10, 1), false)?;
+        assert_eq!(values(&result), vec![Some(123), Some(0)]);
+        let result = cast_decimal(&decimals, &DataType::Decimal(20, 5), false)?;
+        assert_eq!(values(&result), vec![Some(1234500), Some(-500)]);
+        Ok(())
+    }
+
+    #[test]
+    fn cast_from_decimal() -> Result<()> {
+        let decimals = decimal_array(&[Some(12345), None, Some(-99999)], 10, 3);
+
+        let result = cast_decimal(&decimals, &DataType::Int64, false)?;
+        let result = result.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(result, &Int64Array::from(vec![Some(12), None, Some(-99)]));
+
+        let result = cast_decimal(&decimals, &DataType::Float64, false)?;
+        let result = result.as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(
+            result,
+            &Float64Array::from(vec![Some(12.345), None, Some(-99.999)])
+        );
+
+        let result = cast_decimal(&decimals, &DataType::Utf8, false)?;
+        let result = result.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(
+            result,
+            &StringArray::from(vec![Some("12.345"), None, Some("-99.999")])
+        );
+
+        let large = decimal_array(&[Some(100_000), Some(1)], 10, 0);
+        assert!(cast_decimal(&large, &DataType::Int16, false).is_err());
+        let result = cast_decimal(&large, &DataType::Int16, true)?;
+        let result = result.as_any().downcast_ref::<Int16Array>().unwrap();
+        assert_eq!(result, &Int16Array::from(vec![None, Some(1)]));
+        Ok(())
+    }
+
+    #[test]
+    fn comparisons() -> Result<()> {
+        let left = decimal_array(&[Some(100), Some(200), None], 10, 2);
+        let right = decimal_array(&[Some(100), Some(100), Some(100)], 10, 2);
+        let left = left.as_any().downcast_ref::<DecimalArray>().unwrap();
+        let right = right.as_any().downcast_ref::<DecimalArray>().unwrap();
+
+        assert_eq!(
+            eq_decimal(left, right)?,
+            BooleanArray::from(vec![Some(true), Some(false), None])
+        );
+        assert_eq!(
+            gt_decimal(left, right)?,
+            BooleanArray::from(vec![Some(false), Some(true), None])
+        );
+        assert_eq!(
+            is_distinct_from_decimal(left, right)?,
+            BooleanArray::from(vec![Some(false), Some(true), Some(true)])
+        );
+        assert_eq!(
+            lt_eq_decimal_scalar(left, 150)?,
+            BooleanArray::from(vec![Some(true), Some(false), None])
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arithmetic() -> Result<()> {
+        // 1.50, -2.25 and NULL with 2.5, 0.5 and 1.0
+        let left = decimal_array(&[Some(150), Some(-225), None], 5, 2);
+        let right = decimal_array(&[Some(25), Some(5), Some(10)], 3, 1);
+        let left = left.as_any().downcast_ref::<DecimalArray>().unwrap();
+        let right = right.as_any().downcast_ref::<DecimalArray>().unwrap();
+
+        let cases = vec![
+            (Operator::Plus, DataType::Decimal(6, 2), vec![400, -175]),
+            (Operator::Minus, DataType::Decimal(6, 2), vec![-100, -275]),
+            (
+                Operator::Multiply,
+                DataType::Decimal(9, 3),
+                vec![3750, -1125],
+            ),
+            (
+                Operator::Divide,
+                DataType::Decimal(10, 6),
+                vec![600000, -4500000],
+            ),
+            (Operator::Modulo, DataType::Decimal(4, 2), vec![150, -25]),
+        ];
+        for (op, result_type, expected) in cases {
+            let result = decimal_arithmetic(left, &op, right, &result_type)?;
+            assert_eq!(result.data_type(), &result_type);
+            let result = decimal_values(&result).collect::<Vec<_>>();
+            assert_eq!(result, vec![Some(expected[0]), Some(expected[1]), None]);
+        }
+
+        // rounds the digits that don't fit in the result scale
+        let result =
+            decimal_arithmetic(left, &Operator::Divide, right, &DataType::Decimal(5, 1))?;
+        assert_eq!(
+            decimal_values(&result).collect::<Vec<_>>(),
+            vec![Some(6), Some(-45), None]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn arithmetic_errors() {
+        let left = decimal_array(&[Some(99_999)], 5, 0);
+        let zero = decimal_array(&[Some(0)], 5, 0);
+        let left = left.as_any().downcast_ref::<DecimalArray>().unwrap();
+        let zero = zero.as_any().downcast_ref::<DecimalArray>().unwrap();
+
+        let result =
+            decimal_arithmetic(left, &Operator::Plus, left, &DataType::Decimal(5, 0));
+        assert!(matches!(result, Err(DataFusionError::Execution(_))));
+        let result =
+            decimal_arithmetic(left, &Operator::Divide, zero, &DataType::Decimal(11, 6));
+        assert!(matches!(
+            result,
+            Err(DataFusionError::ArrowError(ArrowError::DivideByZero))
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels of the comparisons of dictionary arrays, that arrow doesn't
+//! provide yet. A comparison with a literal is evaluated once per value of
+//! the dictionary and its results are mapped through the keys, so that the
+//! cost of the comparison depends on the cardinality of the dictionary rather
+//! than on the number of rows.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, BooleanArray, DictionaryArray};
+use arrow::compute::kernels::cast::cast;
+use arrow::datatypes::{
+    ArrowDictionaryKeyType, ArrowNativeType, DataType, Int16Type, Int32Type, Int64Type,
+    Int8Type, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::ColumnarValue;
+
+/// Call `$FN::<K>($ARGS)` with `K` the arrow type of the keys of the
+/// dictionary type `$KEY_TYPE`
+macro_rules! dictionary_key_dispatch {
+    ($KEY_TYPE:expr, $FN:ident, $($ARGS:expr),*) => {
+        match $KEY_TYPE {
+            DataType::Int8 => $FN::<Int8Type>($($ARGS),*),
+            DataType::Int16 => $FN::<Int16Type>($($ARGS),*),
+            DataType::Int32 => $FN::<Int32Type>($($ARGS),*),
+            DataType::Int64 => $FN::<Int64Type>($($ARGS),*),
+            DataType::UInt8 => $FN::<UInt8Type>($($ARGS),*),
+            DataType::UInt16 => $FN::<UInt16Type>($($ARGS),*),
+            DataType::UInt32 => $FN::<UInt32Type>($($ARGS),*),
+            DataType::UInt64 => $FN::<UInt64Type>($($ARGS),*),
+            other => Err(DataFusionError::Internal(format!(
+                "Unsupported dictionary key type {:?}",
+                other
+            ))),
+        }
+    };
+}
+
+/// Whether `data_type` is a dictionary type
+pub(super) fn is_dictionary(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Dictionary(_, _))
+}
+
+/// The values of the dictionary array `array`
+pub(super) fn dictionary_values(array: &ArrayRef) -> Result<ArrayRef> {
+    match array.data_type() {
+        DataType::Dictionary(key_type, _) => {
+            dictionary_key_dispatch!(key_type.as_ref(), typed_dictionary_values, array)
+        }
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a dictionary array, found an array of {:?}",
+            other
+        ))),
+    }
+}
+
+fn typed_dictionary_values<K: ArrowDictionaryKeyType>(
+    array: &ArrayRef,
+) -> Result<ArrayRef> {
+    let dictionary = array.as_any().downcast_ref::<DictionaryArray<K>>().unwrap();
+    Ok(dictionary.values().clone())
+}
+
+/// The result of a comparison for each row of the dictionary array `array`,
+/// given `results`, the results of the comparison for each of its values
+pub(super) fn map_dictionary_keys(
+    array: &ArrayRef,
+    results: &ArrayRef,
+) -> Result<ArrayRef> {
+    let results = results
+        .as_any()
+        .downcast_ref::<BooleanArray>()
+        .ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "Expected boolean results of the dictionary values, found {:?}",
+                results.data_type()
+            ))
+        })?;
+    match array.data_type() {
+        DataType::Dictionary(key_type, _) => {
+            dictionary_key_dispatch!(key_type.as_ref(), map_keys, array, results)
+        }
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a dictionary array, found an array of {:?}",
+            other
+        ))),
+    }
+}
+
+fn map_keys<K: ArrowDictionaryKeyType>(
+    array: &ArrayRef,
+    results: &BooleanArray,
+) -> Result<ArrayRef> {
+    let dictionary = array.as_any().downcast_ref::<DictionaryArray<K>>().unwrap();
+    let mapped = dictionary
+        .keys()
+        .iter()
+        .map(|key| match key {
+            Some(key) => {
+                let index = key.to_usize().ok_or_else(|| {
+                    DataFusionError::Internal(format!(
+                        "Can not convert key value {:?} to usize in dictionary of type {:?}",
+                        key,
+                        dictionary.data_type()
+                    ))
+                })?;
+                Ok(results.is_valid(index).then(|| results.value(index)))
+            }
+            None => Ok(None),
+        })
+        .collect::<Result<BooleanArray>>()?;
+    Ok(Arc::new(mapped))
+}
+
+/// `value` with its dictionary, if any, unpacked to an array of the type of
+/// its values
+pub(super) fn unpack_dictionary(value: ColumnarValue) -> Result<ColumnarValue> {
+    match value {
+        ColumnarValue::Array(array) => match array.data_type() {
+            DataType::Dictionary(_, value_type) => {
+                Ok(ColumnarValue::Array(cast(&array, value_type)?))
+            }
+            _ => Ok(ColumnarValue::Array(array)),
+        },
+        scalar => Ok(scalar),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+    use arrow::compute::kernels::comparison::eq_utf8_scalar;
+
+    #[test]
+    fn map_keys_of_dictionary() -> Result<()> {
+        let dictionary: DictionaryArray<Int8Type> =
+            vec![Some("a"), None, Some("b"), Some("a"), Some("c")]
+                .into_iter()
+                .collect();
+        let array = Arc::new(dictionary) as ArrayRef;
+
+        let values = dictionary_values(&array)?;
+        assert_eq!(values.len(), 3);
+        let values = values.as_any().downcast_ref::<StringArray>().unwrap();
+        let results = Arc::new(eq_utf8_scalar(values, "a")?) as ArrayRef;
+
+        let mapped = map_dictionary_keys(&array, &results)?;
+        let expected = BooleanArray::from(vec![
+            Some(true),
+            None,
+            Some(false),
+            Some(true),
+            Some(false),
+        ]);
+        assert_eq!(
+            mapped.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &expected
+        );
+
+        match unpack_dictionary(ColumnarValue::Array(array))? {
+            ColumnarValue::Array(array) => {
+                assert_eq!(array.data_type(), &DataType::Utf8);
+                assert_eq!(array.null_count(), 1);
+            }
+            ColumnarValue::Scalar(_) => unreachable!(),
+        }
+        Ok(())
+    }
+}
@@ -26,7 +26,7 @@ use arrow::{
 };
 
 use crate::arrow::array::Array;
-use crate::arrow::compute::concat;
+use crate::arrow::compute::{concat, take};
 use crate::scalar::ScalarValue;
 use crate::{
     error::DataFusionError,
@@ -34,7 +34,7 @@ use crate::{
     field_util::get_indexed_field as get_data_type_field,
     physical_plan::{ColumnarValue, PhysicalExpr},
 };
-use arrow::array::{ListArray, StructArray};
+use arrow::array::{ArrayRef, ListArray, StructArray, UInt32Array};
 use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
@@ -76,7 +76,8 @@ impl PhysicalExpr for GetIndexedFieldExpr {
 
     fn nullable(&self, input_schema: &Schema) -> Result<bool> {
         let data_type = self.arg.data_type(input_schema)?;
-        get_data_type_field(&data_type, &self.key).map(|f| f.is_nullable())
+        let field = get_data_type_field(&data_type, &self.key)?;
+        Ok(field.is_nullable() || self.arg.nullable(input_schema)?)
     }
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
@@ -106,11 +107,34 @@ impl PhysicalExpr for GetIndexedFieldExpr {
                     let as_struct_array = array.as_any().downcast_ref::<StructArray>().unwrap();
                     match as_struct_array.column_by_name(k) {
                         None => Err(DataFusionError::Execution(format!("get indexed field {} not found in struct", k))),
-                        Some(col) => Ok(ColumnarValue::Array(col.clone()))
+                        Some(col) => Ok(ColumnarValue::Array(struct_field(as_struct_array, col)?))
                     }
                 }
                 (dt, key) => Err(DataFusionError::NotImplemented(format!("get indexed field is only possible on lists with int64 indexes. Tried {} with {} index", dt, key))),
             },
+            ColumnarValue::Scalar(ScalarValue::Struct(values, fields)) => {
+                let k = match &self.key {
+                    ScalarValue::Utf8(Some(k)) => k,
+                    key => {
+                        return Err(DataFusionError::NotImplemented(format!(
+                            "get indexed field is only possible on structs with utf8 keys. Tried {} key",
+                            key
+                        )))
+                    }
+                };
+                let idx = fields.iter().position(|f| f.name() == k).ok_or_else(|| {
+                    DataFusionError::Execution(format!(
+                        "get indexed field {} not found in struct",
+                        k
+                    ))
+                })?;
+                match values {
+                    Some(values) => Ok(ColumnarValue::Scalar(values[idx].clone())),
+                    None => Ok(ColumnarValue::Scalar(
+                        fields[idx].data_type().try_into()?,
+                    )),
+                }
+            }
             ColumnarValue::Scalar(_) => Err(DataFusionError::NotImplemented(
                 "field access is not yet implemented for scalar values".to_string(),
             )),
@@ -118,6 +142,18 @@ impl PhysicalExpr for GetIndexedFieldExpr {
     }
 }
 
+/// Returns the child `column` of `array`, null wherever the struct itself is
+/// null: the values of the children under a null struct are undefined.
+fn struct_field(array: &StructArray, column: &ArrayRef) -> Result<ArrayRef> {
+    if array.null_count() == 0 {
+        return Ok(column.clone());
+    }
+    let indices = (0..array.len())
+        .map(|i| array.is_valid(i).then(|| i as u32))
+        .collect::<UInt32Array>();
+    Ok(take(column.as_ref(), &indices, None)?)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,6 +163,7 @@ mod tests {
     use arrow::array::{
         Int64Array, Int64Builder, ListBuilder, StringBuilder, StructArray, StructBuilder,
     };
+    use arrow::buffer::Buffer;
     use arrow::{array::StringArray, datatypes::Field};
 
     fn build_utf8_lists(list_of_lists: Vec<Vec<Option<&str>>>) -> GenericListArray<i32> {
@@ -367,4 +404,60 @@ mod tests {
         )?;
         Ok(())
     }
+
+    #[test]
+    fn get_indexed_field_null_struct() -> Result<()> {
+        let field = Field::new("foo", DataType::Int64, false);
+        let values: ArrayRef = Arc::new(Int64Array::from(vec![1, 2, 3]));
+        let struct_col =
+            StructArray::from((vec![(field.clone(), values)], Buffer::from([0b101_u8])));
+        let schema =
+            Schema::new(vec![Field::new("s", DataType::Struct(vec![field]), true)]);
+        let expr = GetIndexedFieldExpr::new(
+            col("s", &schema)?,
+            ScalarValue::Utf8(Some("foo".to_string())),
+        );
+        assert!(expr.nullable(&schema)?);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(struct_col)])?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!(&Int64Array::from(vec![Some(1), None, Some(3)]), result);
+        Ok(())
+    }
+
+    #[test]
+    fn get_indexed_field_scalar_struct() -> Result<()> {
+        let fields = vec![
+            Field::new("foo", DataType::Int64, true),
+            Field::new("bar", DataType::Utf8, true),
+        ];
+        let scalar = ScalarValue::Struct(
+            Some(Box::new(vec![
+                ScalarValue::Int64(Some(1)),
+                ScalarValue::Utf8(Some("a".to_string())),
+            ])),
+            Box::new(fields.clone()),
+        );
+        let schema = Schema::new(vec![Field::new("l", DataType::Int64, true)]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![Arc::new(Int64Array::from(vec![0]))],
+        )?;
+        let key = ScalarValue::Utf8(Some("bar".to_string()));
+        let expr = GetIndexedFieldExpr::new(lit(scalar), key.clone());
+        match expr.evaluate(&batch)? {
+            ColumnarValue::Scalar(v) => {
+                assert_eq!(v, ScalarValue::Utf8(Some("a".into())))
+            }
+            ColumnarValue::Array(_) => panic!("expected a scalar"),
+        }
+
+        let null = ScalarValue::Struct(None, Box::new(fields));
+        let expr = GetIndexedFieldExpr::new(lit(null), key);
+        match expr.evaluate(&batch)? {
+            ColumnarValue::Scalar(v) => assert_eq!(v, ScalarValue::Utf8(None)),
+            ColumnarValue::Array(_) => panic!("expected a scalar"),
+        }
+        Ok(())
+    }
 }
@@ -18,6 +18,7 @@
 //! InList expression
 
 use std::any::Any;
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use arrow::array::GenericStringArray;
@@ -26,12 +27,16 @@ use arrow::array::{
     Int64Array, Int8Array, StringOffsetSizeTrait, UInt16Array, UInt32Array, UInt64Array,
     UInt8Array,
 };
+use arrow::compute::CastOptions;
 use arrow::datatypes::ArrowPrimitiveType;
 use arrow::{
     datatypes::{DataType, Schema},
     record_batch::RecordBatch,
 };
 
+use super::cast::{can_cast_types, cast_column};
+use super::coercion::is_numeric;
+use super::Literal;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::{ColumnarValue, PhysicalExpr};
 use crate::scalar::ScalarValue;
@@ -64,12 +69,240 @@ macro_rules! compare_op_scalar {
 
 use serde::{Deserialize, Serialize};
 
+/// Number of literals of a list above which the values are looked up in a
+/// hash set rather than compared with each literal
+const HASH_SET_THRESHOLD: usize = 16;
+
 /// InList
 #[derive(Debug, Serialize, Deserialize)]
+#[serde(from = "InListFields")]
 pub struct InListExpr {
     expr: Arc<dyn PhysicalExpr>,
     list: Vec<Arc<dyn PhysicalExpr>>,
     negated: bool,
+    /// The values of a long list of literals, rebuilt when deserialized
+    #[serde(skip)]
+    set: Option<InSet>,
+}
+
+/// The serialized fields of an [`InListExpr`]
+#[derive(Deserialize)]
+struct InListFields {
+    expr: Arc<dyn PhysicalExpr>,
+    list: Vec<Arc<dyn PhysicalExpr>>,
+    negated: bool,
+}
+
+impl From<InListFields> for InListExpr {
+    fn from(fields: InListFields) -> Self {
+        Self::new(fields.expr, fields.list, fields.negated)
+    }
+}
+
+/// The non-null values of a list of literals of the same type
+#[derive(Debug)]
+enum ValueSet {
+    Int8(HashSet<i8>),
+    Int16(HashSet<i16>),
+    Int32(HashSet<i32>),
+    Int64(HashSet<i64>),
+    UInt8(HashSet<u8>),
+    UInt16(HashSet<u16>),
+    UInt32(HashSet<u32>),
+    UInt64(HashSet<u64>),
+    /// The bits of the floats, see [`float32_key`]
+    Float32(HashSet<u32>),
+    /// The bits of the floats, see [`float64_key`]
+    Float64(HashSet<u64>),
+    /// The strings of `Utf8` and `LargeUtf8` literals
+    Utf8(HashSet<String>),
+}
+
+/// The key of a float in a [`ValueSet`]: its bits, with `-0.0` hashed as
+/// `0.0` since they are equal. NaN, which is equal to no value, is never
+/// inserted.
+fn float32_key(value: f32) -> u32 {
+    if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    }
+}
+
+/// The key of a float in a [`ValueSet`], see [`float32_key`]
+fn float64_key(value: f64) -> u64 {
+    if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    }
+}
+
+impl ValueSet {
+    /// An empty set of the values of the type of `value`, if supported
+    fn empty(value: &ScalarValue) -> Option<Self> {
+        Some(match value {
+            ScalarValue::Int8(_) => Self::Int8(HashSet::new()),
+            ScalarValue::Int16(_) => Self::Int16(HashSet::new()),
+            ScalarValue::Int32(_) => Self::Int32(HashSet::new()),
+            ScalarValue::Int64(_) => Self::Int64(HashSet::new()),
+            ScalarValue::UInt8(_) => Self::UInt8(HashSet::new()),
+            ScalarValue::UInt16(_) => Self::UInt16(HashSet::new()),
+            ScalarValue::UInt32(_) => Self::UInt32(HashSet::new()),
+            ScalarValue::UInt64(_) => Self::UInt64(HashSet::new()),
+            ScalarValue::Float32(_) => Self::Float32(HashSet::new()),
+            ScalarValue::Float64(_) => Self::Float64(HashSet::new()),
+            ScalarValue::Utf8(_) | ScalarValue::LargeUtf8(_) => {
+                Self::Utf8(HashSet::new())
+            }
+            _ => return None,
+        })
+    }
+
+    /// Inserts the non-null `value`, returning false if it's not of the
+    /// type of the set
+    fn insert(&mut self, value: &ScalarValue) -> bool {
+        match (self, value) {
+            (Self::Int8(set), ScalarValue::Int8(Some(v))) => set.insert(*v),
+            (Self::Int16(set), ScalarValue::Int16(Some(v))) => set.insert(*v),
+            (Self::Int32(set), ScalarValue::Int32(Some(v))) => set.insert(*v),
+            (Self::Int64(set), ScalarValue::Int64(Some(v))) => set.insert(*v),
+            (Self::UInt8(set), ScalarValue::UInt8(Some(v))) => set.insert(*v),
+            (Self::UInt16(set), ScalarValue::UInt16(Some(v))) => set.insert(*v),
+            (Self::UInt32(set), ScalarValue::UInt32(Some(v))) => set.insert(*v),
+            (Self::UInt64(set), ScalarValue::UInt64(Some(v))) => set.insert(*v),
+            (Self::Float32(set), ScalarValue::Float32(Some(v))) => {
+                !v.is_nan() && set.insert(float32_key(*v))
+            }
+            (Self::Float64(set), ScalarValue::Float64(Some(v))) => {
+                !v.is_nan() && set.insert(float64_key(*v))
+            }
+            (
+                Self::Utf8(set),
+                ScalarValue::Utf8(Some(v)) | ScalarValue::LargeUtf8(Some(v)),
+            ) => set.insert(v.clone()),
+            _ => return false,
+        };
+        true
+    }
+}
+
+/// The values of a list of literals hashed to be looked up in constant time
+#[derive(Debug)]
+struct InSet {
+    values: ValueSet,
+    /// Whether the list contains a null, in which case a value not in the
+    /// list is not known not to be in it
+    contains_null: bool,
+}
+
+/// Whether each value of `array`, downcast to `$ARRAY_TYPE`, passes `IN` or
+/// `NOT IN` the hashed list `$IN_SET`, `$CONTAINS` looking a value up
+macro_rules! lookup {
+    ($ARRAY:expr, $ARRAY_TYPE:ident, $IN_SET:expr, $NEGATED:expr, $CONTAINS:expr) => {{
+        let array = $ARRAY.as_any().downcast_ref::<$ARRAY_TYPE>().unwrap();
+        Some(in_list_result(
+            array.iter(),
+            $CONTAINS,
+            $IN_SET.contains_null,
+            $NEGATED,
+        ))
+    }};
+}
+
+impl InSet {
+    /// The hashed values of `list`, if it's a list of literals of the same
+    /// supported type, or nulls
+    fn try_new(list: &[Arc<dyn PhysicalExpr>]) -> Option<Self> {
+        let mut values: Option<ValueSet> = None;
+        let mut contains_null = false;
+        for expr in list {
+            let value = expr.as_any().downcast_ref::<Literal>()?.value();
+            if value.is_null() {
+                contains_null = true;
+                continue;
+            }
+            if values.is_none() {
+                values = Some(ValueSet::empty(value)?);
+            }
+            if !values.as_mut()?.insert(value) {
+                return None;
+            }
+        }
+        Some(Self {
+            values: values?,
+            contains_null,
+        })
+    }
+
+    /// Whether each value of `array` passes `IN` or, if `negated`, `NOT IN`
+    /// the list, or `None` if `array` isn't of the type of the values
+    fn lookup(&self, array: &ArrayRef, negated: bool) -> Option<BooleanArray> {
+        match (&self.values, array.data_type()) {
+            (ValueSet::Int8(set), DataType::Int8) => {
+                lookup!(array, Int8Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::Int16(set), DataType::Int16) => {
+                lookup!(array, Int16Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::Int32(set), DataType::Int32) => {
+                lookup!(array, Int32Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::Int64(set), DataType::Int64) => {
+                lookup!(array, Int64Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::UInt8(set), DataType::UInt8) => {
+                lookup!(array, UInt8Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::UInt16(set), DataType::UInt16) => {
+                lookup!(array, UInt16Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::UInt32(set), DataType::UInt32) => {
+                lookup!(array, UInt32Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::UInt64(set), DataType::UInt64) => {
+                lookup!(array, UInt64Array, self, negated, |v| set.contains(&v))
+            }
+            (ValueSet::Float32(set), DataType::Float32) => {
+                lookup!(array, Float32Array, self, negated, |v: f32| {
+                    !v.is_nan() && set.contains(&float32_key(v))
+                })
+            }
+            (ValueSet::Float64(set), DataType::Float64) => {
+                lookup!(array, Float64Array, self, negated, |v: f64| {
+                    !v.is_nan() && set.contains(&float64_key(v))
+                })
+            }
+            (ValueSet::Utf8(set), DataType::Utf8) => {
+                lookup!(array, StringArray, self, negated, |v: &str| set.contains(v))
+            }
+            (ValueSet::Utf8(set), DataType::LargeUtf8) => {
+                lookup!(array, LargeStringArray, self, negated, |v: &str| set
+                    .contains(v))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Applies the three-valued logic of `IN` and `NOT IN` to whether each of
+/// `values` is one of the non-null values of a list: a value not in a list
+/// containing a null is not known not to be in it, and a null value is in
+/// no list
+fn in_list_result<T>(
+    values: impl Iterator<Item = Option<T>>,
+    contains: impl Fn(T) -> bool,
+    contains_null: bool,
+    negated: bool,
+) -> BooleanArray {
+    values
+        .map(|value| match value.map(&contains) {
+            Some(true) => Some(!negated),
+            Some(false) if contains_null => None,
+            Some(false) => Some(negated),
+            None => None,
+        })
+        .collect()
 }
 
 macro_rules! make_contains {
@@ -228,10 +461,16 @@ impl InListExpr {
         list: Vec<Arc<dyn PhysicalExpr>>,
         negated: bool,
     ) -> Self {
+        let set = if list.len() > HASH_SET_THRESHOLD {
+            InSet::try_new(&list)
+        } else {
+            None
+        };
         Self {
             expr,
             list,
             negated,
+            set,
         }
     }
 
@@ -344,17 +583,25 @@ impl PhysicalExpr for InListExpr {
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
         let value_data_type = value.data_type();
+        let array = match value {
+            ColumnarValue::Array(array) => array,
+            ColumnarValue::Scalar(scalar) => scalar.to_array(),
+        };
+
+        if let Some(result) = self
+            .set
+            .as_ref()
+            .and_then(|set| set.lookup(&array, self.negated))
+        {
+            return Ok(ColumnarValue::Array(Arc::new(result)));
+        }
+
         let list_values = self
             .list
             .iter()
             .map(|expr| expr.evaluate(batch))
             .collect::<Result<Vec<_>>>()?;
 
-        let array = match value {
-            ColumnarValue::Array(array) => array,
-            ColumnarValue::Scalar(scalar) => scalar.to_array(),
-        };
-
         match value_data_type {
             DataType::Float32 => {
                 make_contains_primitive!(
@@ -461,6 +708,66 @@ impl PhysicalExpr for InListExpr {
     }
 }
 
+/// Casts `value`, a literal of an IN list, to `data_type`, the type of the
+/// expression looked up in the list.
+///
+/// Fails if the cast isn't supported or fails, or if it changes a number
+/// cast to an integer or decimal type, such as `1.5` or `3000000000` cast
+/// to `Int32`, since the list would then match values it doesn't contain.
+/// The numbers cast to floats are rounded to the nearest float.
+pub fn coerce_list_literal(
+    value: &ScalarValue,
+    data_type: &DataType,
+) -> Result<ScalarValue> {
+    let value_type = value.get_datatype();
+    if &value_type == data_type {
+        return Ok(value.clone());
+    }
+    if !can_cast_types(&value_type, data_type) {
+        return Err(DataFusionError::Plan(format!(
+            "Unsupported CAST from {:?} to {:?}",
+            value_type, data_type
+        )));
+    }
+
+    let cast = |value: &ScalarValue, data_type: &DataType| {
+        let options = CastOptions { safe: false };
+        match cast_column(&ColumnarValue::Scalar(value.clone()), data_type, &options) {
+            Ok(ColumnarValue::Scalar(value)) => Ok(value),
+            Ok(ColumnarValue::Array(_)) => unreachable!(),
+            Err(e) => Err(DataFusionError::Plan(format!(
+                "Cannot cast {} in IN list to {:?}: {}",
+                value, data_type, e
+            ))),
+        }
+    };
+    let lossy = || {
+        DataFusionError::Plan(format!(
+            "Cannot cast {} in IN list to {:?} without loss",
+            value, data_type
+        ))
+    };
+
+    let cast_value = cast(value, data_type)?;
+    if cast_value.is_null() && !value.is_null() {
+        return Err(lossy());
+    }
+    let exact = !matches!(
+        data_type,
+        DataType::Float16 | DataType::Float32 | DataType::Float64
+    );
+    if exact
+        && !value.is_null()
+        && is_numeric(&value_type)
+        && is_numeric(data_type)
+        && can_cast_types(data_type, &value_type)
+        && cast(&cast_value, &value_type)? != *value
+    {
+        return Err(lossy());
+    }
+    Ok(cast_value)
+}
+
 /// Creates a unary expression InList
 pub fn in_list(
     expr: Arc<dyn PhysicalExpr>,
@@ -684,6 +991,192 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn in_list_hash_set() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let a = Int64Array::from(vec![Some(0), Some(5), Some(100), None]);
+        let col_a = col("a", &schema)?;
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(a)])?;
+        let list = || {
+            (0..20)
+                .map(|v| lit(ScalarValue::Int64(Some(v))))
+                .collect::<Vec<_>>()
+        };
+        let with_null = || {
+            let mut list = list();
+            list.push(lit(ScalarValue::Int64(None)));
+            list
+        };
+        assert!(InListExpr::new(col_a.clone(), list(), false).set.is_some());
+
+        // expression: "a in (0, ..., 19)"
+        in_list!(
+            batch,
+            list(),
+            &false,
+            vec![Some(true), Some(true), Some(false), None],
+            col_a.clone()
+        );
+
+        // expression: "a not in (0, ..., 19)"
+        in_list!(
+            batch,
+            list(),
+            &true,
+            vec![Some(false), Some(false), Some(true), None],
+            col_a.clone()
+        );
+
+        // expression: "a in (0, ..., 19, NULL)"
+        in_list!(
+            batch,
+            with_null(),
+            &false,
+            vec![Some(true), Some(true), None, None],
+            col_a.clone()
+        );
+
+        // expression: "a not in (0, ..., 19, NULL)"
+        in_list!(
+            batch,
+            with_null(),
+            &true,
+            vec![Some(false), Some(false), None, None],
+            col_a.clone()
+        );
+
+        // the comparisons of a short list give the same results
+        in_list!(
+            batch,
+            vec![
+                lit(ScalarValue::Int64(Some(0))),
+                lit(ScalarValue::Int64(Some(5))),
+                lit(ScalarValue::Int64(None))
+            ],
+            &false,
+            vec![Some(true), Some(true), None, None],
+            col_a.clone()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_hash_set_float_utf8() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("f", DataType::Float64, true),
+            Field::new("s", DataType::Utf8, true),
+        ]);
+        let f = Float64Array::from(vec![Some(-0.0), Some(f64::NAN), Some(1.5), None]);
+        let s = StringArray::from(vec![Some("v0"), Some("v19"), Some("v20"), None]);
+        let col_f = col("f", &schema)?;
+        let col_s = col("s", &schema)?;
+        let batch =
+            RecordBatch::try_new(Arc::new(schema), vec![Arc::new(f), Arc::new(s)])?;
+
+        // expression: "f in (0.0, 0.5, ..., 9.5, NaN)": -0.0 equals 0.0, but
+        // NaN equals no value
+        let mut list = (0..20)
+            .map(|v| lit(ScalarValue::Float64(Some(v as f64 / 2.0))))
+            .collect::<Vec<_>>();
+        list.push(lit(ScalarValue::Float64(Some(f64::NAN))));
+        in_list!(
+            batch,
+            list,
+            &false,
+            vec![Some(true), Some(false), Some(true), None],
+            col_f
+        );
+
+        // expression: "s not in ('v0', ..., 'v19')"
+        let list = (0..20)
+            .map(|v| lit(ScalarValue::Utf8(Some(format!("v{}", v)))))
+            .collect::<Vec<_>>();
+        in_list!(
+            batch,
+            list,
+            &true,
+            vec![Some(false), Some(false), Some(true), None],
+            col_s
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn in_list_serde() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+        let list = (0..20)
+            .map(|v| lit(ScalarValue::Int64(Some(v))))
+            .collect::<Vec<_>>();
+        let expr: Arc<dyn PhysicalExpr> = in_list(col("a", &schema)?, list, &false)?;
+
+        // the hash set is rebuilt
+        let json = serde_json::to_string(&expr).unwrap();
+        let expr: Arc<dyn PhysicalExpr> = serde_json::from_str(&json).unwrap();
+        let expr = expr.as_any().downcast_ref::<InListExpr>().unwrap();
+        assert_eq!(expr.list().len(), 20);
+        assert!(expr.set.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn coerce_list_literals() -> Result<()> {
+        let coerce = |value: ScalarValue, data_type: DataType| {
+            coerce_list_literal(&value, &data_type).map_err(|e| e.to_string())
+        };
+
+        assert_eq!(
+            coerce(ScalarValue::Int64(Some(1)), DataType::Int32),
+            Ok(ScalarValue::Int32(Some(1)))
+        );
+        assert_eq!(
+            coerce(ScalarValue::Int64(Some(1)), DataType::Utf8),
+            Ok(ScalarValue::Utf8(Some("1".to_string())))
+        );
+        assert_eq!(
+            coerce(ScalarValue::Float64(Some(2.0)), DataType::Int64),
+            Ok(ScalarValue::Int64(Some(2)))
+        );
+        assert_eq!(
+            coerce(ScalarValue::Int64(None), DataType::Int32),
+            Ok(ScalarValue::Int32(None))
+        );
+        // the floats are rounded
+        assert_eq!(
+            coerce(ScalarValue::Float64(Some(0.1)), DataType::Float32),
+            Ok(ScalarValue::Float32(Some(0.1)))
+        );
+
+        // out of range, which the cast kernel reports as an error or a null
+        assert!(
+            coerce(ScalarValue::Int64(Some(3_000_000_000)), DataType::Int32)
+                .unwrap_err()
+                .starts_with("Error during planning: Cannot cast 3000000000 in IN list")
+        );
+        assert_eq!(
+            coerce(ScalarValue::Float64(Some(1.5)), DataType::Int64),
+            Err(
+                "Error during planning: Cannot cast 1.5 in IN list to Int64 \
+                 without loss"
+                    .to_string()
+            )
+        );
+        assert!(
+            coerce(ScalarValue::Utf8(Some("a".to_string())), DataType::Int64)
+                .unwrap_err()
+                .starts_with("Error during planning: Cannot cast a in IN list to Int64")
+        );
+        assert_eq!(
+            coerce(ScalarValue::Int64(Some(1)), DataType::Struct(vec![])),
+            Err(
+                "Error during planning: Unsupported CAST from Int64 to Struct([])"
+                    .to_string()
+            )
+        );
+        Ok(())
+    }
+
     #[test]
     fn in_list_bool() -> Result<()> {
         let schema = Schema::new(vec![Field::new("a", DataType::Boolean, true)]);
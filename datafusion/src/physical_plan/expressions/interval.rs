@@ -0,0 +1,488 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Arithmetic, comparisons and formatting of intervals. The intervals of
+//! arrow are either a number of months, `IntervalYearMonth`, or a number of
+//! days and of milliseconds, `IntervalDayTime`, packed in an `i64` with the
+//! days in its upper 32 bits.
+
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::compute::kernels::cast::cast;
+use arrow::compute::kernels::comparison::{eq, gt, gt_eq, lt, lt_eq, neq};
+use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+
+use super::coercion::interval_arithmetic_type;
+
+/// Milliseconds in a day of an interval, which is always 24 hours long
+pub(crate) const MILLISECONDS_IN_DAY: i64 = 86_400_000;
+
+/// Whether `data_type` is an interval
+pub(crate) fn is_interval(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Interval(_))
+}
+
+/// The days and the milliseconds of the `IntervalDayTime` value `value`
+pub(crate) fn to_days_millis(value: i64) -> (i32, i32) {
+    ((value >> 32) as i32, value as i32)
+}
+
+/// The `IntervalDayTime` value of `days` and `millis`. The milliseconds out
+/// of the range of an `i32` are carried over to the days, and the days out of
+/// their range are an error.
+pub(crate) fn day_time(days: i64, millis: i64) -> Result<i64> {
+    let (days, millis) = match i32::try_from(millis) {
+        Ok(_) => (days, millis),
+        Err(_) => (
+            days + millis / MILLISECONDS_IN_DAY,
+            millis % MILLISECONDS_IN_DAY,
+        ),
+    };
+    let days = i32::try_from(days).map_err(|_| out_of_range())?;
+    Ok(((days as i64) << 32) | (millis as u32 as i64))
+}
+
+fn out_of_range() -> DataFusionError {
+    DataFusionError::Execution("Interval out of range".to_owned())
+}
+
+/// The duration of the `IntervalDayTime` value `value`, in milliseconds
+fn duration_millis(value: i64) -> i64 {
+    let (days, millis) = to_days_millis(value);
+    days as i64 * MILLISECONDS_IN_DAY + millis as i64
+}
+
+/// The durations of the intervals of `array`, which order them: their months,
+/// or their milliseconds
+fn durations(array: &dyn Array) -> Result<Int64Array> {
+    match array.data_type() {
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            let array = array
+                .as_any()
+                .downcast_ref::<IntervalYearMonthArray>()
+                .unwrap();
+            Ok(array.iter().map(|months| months.map(i64::from)).collect())
+        }
+        DataType::Interval(IntervalUnit::DayTime) => {
+            let array = array
+                .as_any()
+                .downcast_ref::<IntervalDayTimeArray>()
+                .unwrap();
+            Ok(array
+                .iter()
+                .map(|value| value.map(duration_millis))
+                .collect())
+        }
+        other => Err(DataFusionError::Internal(format!(
+            "Expected an interval, got {:?}",
+            other
+        ))),
+    }
+}
+
+fn is_comparison(op: &Operator) -> bool {
+    matches!(
+        op,
+        Operator::Eq
+            | Operator::NotEq
+            | Operator::Lt
+            | Operator::LtEq
+            | Operator::Gt
+            | Operator::GtEq
+    )
+}
+
+/// Whether `op` applied to `lhs_type` and `rhs_type` is evaluated by
+/// [`interval_operation`]: the comparisons of intervals of the same unit, and
+/// the operations typed by [`interval_arithmetic_type`]
+pub(crate) fn is_interval_operation(
+    lhs_type: &DataType,
+    op: &Operator,
+    rhs_type: &DataType,
+) -> bool {
+    match (lhs_type, rhs_type) {
+        (DataType::Interval(lhs_unit), DataType::Interval(rhs_unit))
+            if is_comparison(op) =>
+        {
+            lhs_unit == rhs_unit
+        }
+        _ => interval_arithmetic_type(lhs_type, op, rhs_type).is_some(),
+    }
+}
+
+/// Apply `op` to the pairs of values of `left` and `right`, the results of
+/// the pairs with a null being null
+fn zip_with<L, R, O, F>(
+    left: impl Iterator<Item = Option<L>>,
+    right: impl Iterator<Item = Option<R>>,
+    op: F,
+) -> Result<Vec<Option<O>>>
+where
+    F: Fn(L, R) -> Result<O>,
+{
+    left.zip(right)
+        .map(|pair| match pair {
+            (Some(left), Some(right)) => op(left, right).map(Some),
+            _ => Ok(None),
+        })
+        .collect()
+}
+
+/// Evaluate the operation `op` of `left` and `right`, see
+/// [`is_interval_operation`]. As in PostgreSQL:
+///
+/// * intervals are compared on their durations, with days of 24 hours
+/// * intervals of the same unit are added and subtracted field by field
+/// * the fractions of days of the product of an interval and a number are
+///   carried over to its time
+/// * the difference of two timestamps is a number of days and a time
+///
+/// The intervals of months can't hold the fractions of months of a product,
+/// which are errors. The numbers are expected as `Float64` values, and the
+/// timestamps of the same unit.
+pub(crate) fn interval_operation(
+    left: &ArrayRef,
+    op: &Operator,
+    right: &ArrayRef,
+) -> Result<ArrayRef> {
+    use DataType::*;
+    match (left.data_type(), op, right.data_type()) {
+        (Interval(_), _, Interval(_)) if is_comparison(op) => {
+            let (left, right) = (durations(left.as_ref())?, durations(right.as_ref())?);
+            let result = match op {
+                Operator::Eq => eq(&left, &right),
+                Operator::NotEq => neq(&left, &right),
+                Operator::Lt => lt(&left, &right),
+                Operator::LtEq => lt_eq(&left, &right),
+                Operator::Gt => gt(&left, &right),
+                _ => gt_eq(&left, &right),
+            }?;
+            Ok(Arc::new(result))
+        }
+        (
+            Interval(IntervalUnit::YearMonth),
+            Operator::Plus | Operator::Minus,
+            Interval(IntervalUnit::YearMonth),
+        ) => {
+            let sign = if *op == Operator::Plus { 1 } else { -1 };
+            let months = zip_with(
+                year_months(left).iter(),
+                year_months(right).iter(),
+                |left, right| {
+                    right
+                        .checked_mul(sign)
+                        .and_then(|right| left.checked_add(right))
+                        .ok_or_else(out_of_range)
+                },
+            )?;
+            Ok(Arc::new(IntervalYearMonthArray::from(months)))
+        }
+        (
+            Interval(IntervalUnit::DayTime),
+            Operator::Plus | Operator::Minus,
+            Interval(IntervalUnit::DayTime),
+        ) => {
+            let sign = if *op == Operator::Plus { 1 } else { -1 };
+            let values = zip_with(
+                day_times(left).iter(),
+                day_times(right).iter(),
+                |left, right| {
+                    let (left_days, left_millis) = to_days_millis(left);
+                    let (right_days, right_millis) = to_days_millis(right);
+                    day_time(
+                        left_days as i64 + sign * right_days as i64,
+                        left_millis as i64 + sign * right_millis as i64,
+                    )
+                },
+            )?;
+            Ok(Arc::new(IntervalDayTimeArray::from(values)))
+        }
+        (Interval(unit), Operator::Multiply, Float64) => {
+            multiply(left, unit, float64s(right).iter())
+        }
+        (Float64, Operator::Multiply, Interval(unit)) => {
+            multiply(right, unit, float64s(left).iter())
+        }
+        (Timestamp(unit, _), Operator::Minus, Timestamp(_, _)) => {
+            let (left, right) = (cast(left, &Int64)?, cast(right, &Int64)?);
+            let left = left.as_any().downcast_ref::<Int64Array>().unwrap();
+            let right = right.as_any().downcast_ref::<Int64Array>().unwrap();
+            let values = zip_with(left.iter(), right.iter(), |left, right| {
+                let difference = left.checked_sub(right).ok_or_else(out_of_range)?;
+                let millis = match unit {
+                    TimeUnit::Second => {
+                        difference.checked_mul(1_000).ok_or_else(out_of_range)?
+                    }
+                    TimeUnit::Millisecond => difference,
+                    TimeUnit::Microsecond => difference / 1_000,
+                    TimeUnit::Nanosecond => difference / 1_000_000,
+                };
+                day_time(millis / MILLISECONDS_IN_DAY, millis % MILLISECONDS_IN_DAY)
+            })?;
+            Ok(Arc::new(IntervalDayTimeArray::from(values)))
+        }
+        (left_type, op, right_type) => Err(DataFusionError::Internal(format!(
+            "Unsupported interval operation {:?} {} {:?}",
+            left_type, op, right_type
+        ))),
+    }
+}
+
+fn year_months(array: &ArrayRef) -> &IntervalYearMonthArray {
+    array
+        .as_any()
+        .downcast_ref::<IntervalYearMonthArray>()
+        .unwrap()
+}
+
+fn day_times(array: &ArrayRef) -> &IntervalDayTimeArray {
+    array
+        .as_any()
+        .downcast_ref::<IntervalDayTimeArray>()
+        .unwrap()
+}
+
+fn float64s(array: &ArrayRef) -> &Float64Array {
+    array.as_any().downcast_ref::<Float64Array>().unwrap()
+}
+
+/// Multiply the intervals of `intervals`, of unit `unit`, by `factors`
+fn multiply(
+    intervals: &ArrayRef,
+    unit: &IntervalUnit,
+    factors: impl Iterator<Item = Option<f64>>,
+) -> Result<ArrayRef> {
+    Ok(match unit {
+        IntervalUnit::YearMonth => {
+            let months =
+                zip_with(year_months(intervals).iter(), factors, |months, factor| {
+                    let product = months as f64 * factor;
+                    if product.fract() != 0.0 {
+                        return Err(DataFusionError::Execution(format!(
+                            "Interval of {} months is not a whole number of months",
+                            product
+                        )));
+                    }
+                    // the saturated casts out of range are caught below
+                    let months = product as i64;
+                    i32::try_from(months).map_err(|_| out_of_range())
+                })?;
+            Arc::new(IntervalYearMonthArray::from(months))
+        }
+        IntervalUnit::DayTime => {
+            let values =
+                zip_with(day_times(intervals).iter(), factors, |value, factor| {
+                    let (days, millis) = to_days_millis(value);
+                    let days = days as f64 * factor;
+                    let millis = millis as f64 * factor
+                        + days.fract() * MILLISECONDS_IN_DAY as f64;
+                    if !days.is_finite() || !millis.is_finite() {
+                        return Err(out_of_range());
+                    }
+                    day_time(days.trunc() as i64, millis.round() as i64)
+                })?;
+            Arc::new(IntervalDayTimeArray::from(values))
+        }
+    })
+}
+
+/// A comparator of the durations of the intervals of `left` and `right`, of
+/// the same unit, that ignores their validity, as
+/// [`build_compare`](arrow::array::build_compare) does
+pub(crate) fn build_interval_compare(
+    left: &dyn Array,
+    right: &dyn Array,
+) -> Result<DynComparator> {
+    if left.data_type() != right.data_type() {
+        return Err(DataFusionError::Internal(format!(
+            "Cannot compare the intervals of {:?} and {:?}",
+            left.data_type(),
+            right.data_type()
+        )));
+    }
+    let (left, right) = (durations(left)?, durations(right)?);
+    Ok(Box::new(move |i, j| left.value(i).cmp(&right.value(j))))
+}
+
+/// `value` followed by `unit`, in the plural unless `value` is 1
+fn format_unit(value: i32, unit: &str) -> String {
+    let plural = if value == 1 { "" } else { "s" };
+    format!("{} {}{}", value, unit, plural)
+}
+
+/// Format the `IntervalYearMonth` value `months` as PostgreSQL does, e.g.
+/// `1 year 2 mons`
+pub(crate) fn format_year_month(months: i32) -> String {
+    let mut parts = vec![];
+    if months / 12 != 0 {
+        parts.push(format_unit(months / 12, "year"));
+    }
+    if months % 12 != 0 {
+        parts.push(format_unit(months % 12, "mon"));
+    }
+    if parts.is_empty() {
+        return "00:00:00".to_owned();
+    }
+    parts.join(" ")
+}
+
+/// Format the `IntervalDayTime` value `value` as PostgreSQL does, e.g.
+/// `1 day 02:03:04.5` or `-03:00:00`
+pub(crate) fn format_day_time(value: i64) -> String {
+    let (days, millis) = to_days_millis(value);
+    let mut parts = vec![];
+    if days != 0 {
+        parts.push(format_unit(days, "day"));
+    }
+    if millis != 0 || days == 0 {
+        let sign = if millis < 0 { "-" } else { "" };
+        let millis = (millis as i64).abs();
+        let mut time = format!(
+            "{}{:02}:{:02}:{:02}",
+            sign,
+            millis / 3_600_000,
+            millis / 60_000 % 60,
+            millis / 1_000 % 60
+        );
+        if millis % 1_000 != 0 {
+            let fraction = format!("{:03}", millis % 1_000);
+            time.push('.');
+            time.push_str(fraction.trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+    parts.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day_time_array(values: &[(i32, i32)]) -> ArrayRef {
+        let values = values
+            .iter()
+            .map(|(days, millis)| Some(day_time(*days as i64, *millis as i64).unwrap()))
+            .collect::<Vec<_>>();
+        Arc::new(IntervalDayTimeArray::from(values))
+    }
+
+    fn formatted(array: &ArrayRef) -> Vec<String> {
+        day_times(array)
+            .iter()
+            .map(|value| format_day_time(value.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn day_time_arithmetic() -> Result<()> {
+        let left = day_time_array(&[(1, 0), (0, 7_200_000), (-1, -3_600_000)]);
+        let right = day_time_array(&[(0, 90_000_000), (2, -3_600_000), (1, 500)]);
+
+        let sum = interval_operation(&left, &Operator::Plus, &right)?;
+        assert_eq!(
+            formatted(&sum),
+            vec!["1 day 25:00:00", "2 days 01:00:00", "-00:59:59.5"]
+        );
+        let difference = interval_operation(&left, &Operator::Minus, &right)?;
+        assert_eq!(
+            formatted(&difference),
+            vec!["1 day -25:00:00", "-2 days 03:00:00", "-2 days -01:00:00.5"]
+        );
+
+        let factors: ArrayRef = Arc::new(Float64Array::from(vec![1.5, 400.0, -2.0]));
+        let product = interval_operation(&left, &Operator::Multiply, &factors)?;
+        assert_eq!(
+            formatted(&product),
+            vec!["1 day 12:00:00", "33 days 08:00:00", "2 days 02:00:00"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn year_month_arithmetic() -> Result<()> {
+        let left: ArrayRef =
+            Arc::new(IntervalYearMonthArray::from(vec![Some(14), None, Some(-3)]));
+        let right: ArrayRef = Arc::new(IntervalYearMonthArray::from(vec![
+            Some(1),
+            Some(2),
+            Some(1),
+        ]));
+        let sum = interval_operation(&left, &Operator::Plus, &right)?;
+        assert_eq!(
+            year_months(&sum),
+            &IntervalYearMonthArray::from(vec![Some(15), None, Some(-2)])
+        );
+
+        let factors: ArrayRef = Arc::new(Float64Array::from(vec![2.0, 2.0, 0.5]));
+        let err = interval_operation(&factors, &Operator::Multiply, &left).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Interval of -1.5 months is not a whole number of months"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn compare_durations() -> Result<()> {
+        let left = day_time_array(&[(1, 0), (1, 0), (0, 1)]);
+        let right = day_time_array(&[(0, 86_400_000), (0, 90_000_000), (0, 0)]);
+        let lt = interval_operation(&left, &Operator::Lt, &right)?;
+        assert_eq!(
+            lt.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &BooleanArray::from(vec![false, true, false])
+        );
+        let eq = interval_operation(&left, &Operator::Eq, &right)?;
+        assert_eq!(
+            eq.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &BooleanArray::from(vec![true, false, false])
+        );
+
+        let compare = build_interval_compare(left.as_ref(), right.as_ref())?;
+        assert_eq!(compare(1, 1), std::cmp::Ordering::Less);
+        assert_eq!(compare(2, 2), std::cmp::Ordering::Greater);
+        Ok(())
+    }
+
+    #[test]
+    fn subtract_timestamps() -> Result<()> {
+        let left: ArrayRef =
+            Arc::new(TimestampSecondArray::from_vec(vec![93_600, 0], None));
+        let right: ArrayRef =
+            Arc::new(TimestampSecondArray::from_vec(vec![0, 3_661], None));
+        let difference = interval_operation(&left, &Operator::Minus, &right)?;
+        assert_eq!(formatted(&difference), vec!["1 day 02:00:00", "-01:01:01"]);
+        Ok(())
+    }
+
+    #[test]
+    fn format_intervals() {
+        assert_eq!(format_year_month(0), "00:00:00");
+        assert_eq!(format_year_month(1), "1 mon");
+        assert_eq!(format_year_month(14), "1 year 2 mons");
+        assert_eq!(format_year_month(-25), "-2 years -1 mons");
+        assert_eq!(format_day_time(0), "00:00:00");
+        assert_eq!(format_day_time(day_time(1, 0).unwrap()), "1 day");
+        assert_eq!(
+            format_day_time(day_time(-2, 3_723_004).unwrap()),
+            "-2 days 01:02:03.004"
+        );
+    }
+}
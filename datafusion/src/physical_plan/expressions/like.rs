@@ -0,0 +1,318 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels of the case insensitive wildcard matches, `ILIKE` and `NOT ILIKE`,
+//! that arrow doesn't provide yet. Both the values and the patterns are
+//! lowercased character by character, following the Unicode case mappings,
+//! before being matched.
+
+use arrow::array::{BooleanArray, GenericStringArray, StringOffsetSizeTrait};
+use arrow::compute::kernels::boolean::not;
+
+use crate::error::Result;
+
+/// An element of a wildcard pattern
+#[derive(Debug, Clone, PartialEq)]
+enum LikeToken {
+    /// A character matching itself
+    Char(char),
+    /// `_`, matching any character
+    AnyChar,
+    /// `%`, matching any sequence of characters, even empty
+    AnyString,
+}
+
+/// A wildcard pattern, in which `%` matches any sequence of characters, `_`
+/// any character and `\` escapes the character following it. As for `LIKE`,
+/// the patterns whose only wildcards are a leading or trailing `%` are
+/// matched without going through the general matching of wildcards.
+#[derive(Debug, PartialEq)]
+enum LikePattern {
+    /// A pattern without wildcards, matching the values equal to it
+    Exact(String),
+    /// `prefix%`
+    Prefix(String),
+    /// `%suffix`
+    Suffix(String),
+    /// `%infix%`
+    Contains(String),
+    /// Any other pattern
+    Wildcards(Vec<LikeToken>),
+}
+
+impl LikePattern {
+    /// Compile `pattern`, matching the values regardless of their case
+    fn new_case_insensitive(pattern: &str) -> Self {
+        let mut tokens = vec![];
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            let token = match c {
+                '%' if tokens.last() == Some(&LikeToken::AnyString) => continue,
+                '%' => LikeToken::AnyString,
+                '_' => LikeToken::AnyChar,
+                // a trailing backslash matches itself
+                '\\' => LikeToken::Char(chars.next().unwrap_or('\\')),
+                c => LikeToken::Char(c),
+            };
+            tokens.push(token);
+        }
+
+        let (leading, trailing) = (
+            tokens.first() == Some(&LikeToken::AnyString),
+            tokens.len() > 1 && tokens.last() == Some(&LikeToken::AnyString),
+        );
+        let literal = &tokens[leading as usize..tokens.len() - trailing as usize];
+        let literal = literal
+            .iter()
+            .map(|token| match token {
+                LikeToken::Char(c) => Some(*c),
+                _ => None,
+            })
+            .collect::<Option<String>>()
+            .map(|literal| lowercase(&literal));
+        match (literal, leading, trailing) {
+            (Some(literal), false, false) => LikePattern::Exact(literal),
+            (Some(literal), false, true) => LikePattern::Prefix(literal),
+            (Some(literal), true, false) => LikePattern::Suffix(literal),
+            (Some(literal), true, true) => LikePattern::Contains(literal),
+            (None, _, _) => LikePattern::Wildcards(
+                tokens
+                    .into_iter()
+                    .flat_map(|token| match token {
+                        LikeToken::Char(c) => {
+                            c.to_lowercase().map(LikeToken::Char).collect()
+                        }
+                        token => vec![token],
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Whether the pattern matches `value`, regardless of its case
+    fn matches_case_insensitive(&self, value: &str) -> bool {
+        let value = lowercase(value);
+        match self {
+            LikePattern::Exact(literal) => value == *literal,
+            LikePattern::Prefix(literal) => value.starts_with(literal.as_str()),
+            LikePattern::Suffix(literal) => value.ends_with(literal.as_str()),
+            LikePattern::Contains(literal) => value.contains(literal.as_str()),
+            LikePattern::Wildcards(tokens) => matches_wildcards(tokens, &value),
+        }
+    }
+}
+
+/// The lowercase of each character of `value`, independently of the
+/// characters around it, unlike `str::to_lowercase` for a final sigma
+fn lowercase(value: &str) -> String {
+    value.chars().flat_map(char::to_lowercase).collect()
+}
+
+/// Whether the wildcard pattern `tokens` matches the whole of `value`. Each
+/// `%` first matches as few characters as possible, which are extended one
+/// by one when the rest of the pattern fails to match.
+fn matches_wildcards(tokens: &[LikeToken], value: &str) -> bool {
+    let chars = value.chars().collect::<Vec<_>>();
+    let (mut token, mut position) = (0, 0);
+    // the token after the last `%` and the position it matches from
+    let mut backtrack = None;
+    while position < chars.len() {
+        match tokens.get(token) {
+            Some(LikeToken::AnyString) => {
+                token += 1;
+                backtrack = Some((token, position));
+            }
+            Some(LikeToken::AnyChar) => {
+                token += 1;
+                position += 1;
+            }
+            Some(LikeToken::Char(c)) if *c == chars[position] => {
+                token += 1;
+                position += 1;
+            }
+            _ => match backtrack {
+                Some((after_any, from)) => {
+                    token = after_any;
+                    position = from + 1;
+                    backtrack = Some((after_any, from + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    tokens[token..]
+        .iter()
+        .all(|token| *token == LikeToken::AnyString)
+}
+
+/// Perform SQL `left ILIKE right` on two string arrays
+pub(super) fn ilike_utf8<OffsetSize: StringOffsetSizeTrait>(
+    left: &GenericStringArray<OffsetSize>,
+    right: &GenericStringArray<OffsetSize>,
+) -> Result<BooleanArray> {
+    Ok(left
+        .iter()
+        .zip(right.iter())
+        .map(|(value, pattern)| {
+            Some(
+                LikePattern::new_case_insensitive(pattern?)
+                    .matches_case_insensitive(value?),
+            )
+        })
+        .collect())
+}
+
+/// Perform SQL `left NOT ILIKE right` on two string arrays
+pub(super) fn nilike_utf8<OffsetSize: StringOffsetSizeTrait>(
+    left: &GenericStringArray<OffsetSize>,
+    right: &GenericStringArray<OffsetSize>,
+) -> Result<BooleanArray> {
+    Ok(not(&ilike_utf8(left, right)?)?)
+}
+
+/// Perform SQL `left ILIKE right` on a string array and a pattern
+pub(super) fn ilike_utf8_scalar<OffsetSize: StringOffsetSizeTrait>(
+    left: &GenericStringArray<OffsetSize>,
+    right: &str,
+) -> Result<BooleanArray> {
+    let pattern = LikePattern::new_case_insensitive(right);
+    Ok(left
+        .iter()
+        .map(|value| Some(pattern.matches_case_insensitive(value?)))
+        .collect())
+}
+
+/// Perform SQL `left NOT ILIKE right` on a string array and a pattern
+pub(super) fn nilike_utf8_scalar<OffsetSize: StringOffsetSizeTrait>(
+    left: &GenericStringArray<OffsetSize>,
+    right: &str,
+) -> Result<BooleanArray> {
+    Ok(not(&ilike_utf8_scalar(left, right)?)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::StringArray;
+
+    #[test]
+    fn like_patterns() {
+        let pattern = LikePattern::new_case_insensitive;
+        assert_eq!(pattern("Foo"), LikePattern::Exact("foo".to_string()));
+        assert_eq!(pattern("Foo%"), LikePattern::Prefix("foo".to_string()));
+        assert_eq!(pattern("%%Foo"), LikePattern::Suffix("foo".to_string()));
+        assert_eq!(pattern("%Foo%"), LikePattern::Contains("foo".to_string()));
+        assert_eq!(pattern("%"), LikePattern::Suffix("".to_string()));
+        assert_eq!(pattern("100\\%"), LikePattern::Exact("100%".to_string()));
+        assert_eq!(
+            pattern("F_o%"),
+            LikePattern::Wildcards(vec![
+                LikeToken::Char('f'),
+                LikeToken::AnyChar,
+                LikeToken::Char('o'),
+                LikeToken::AnyString,
+            ])
+        );
+    }
+
+    #[test]
+    fn ilike() -> Result<()> {
+        let values = StringArray::from(vec![
+            Some("Apache Arrow"),
+            Some("APACHE DATAFUSION"),
+            Some("arrow"),
+            Some("100%"),
+            None,
+        ]);
+        let cases = vec![
+            ("apache%", vec![true, true, false, false]),
+            ("%ARROW", vec![true, false, true, false]),
+            ("%e d%", vec![false, true, false, false]),
+            ("a%a%o%", vec![true, true, false, false]),
+            ("_rr_w", vec![false, false, true, false]),
+            ("%\\%", vec![false, false, false, true]),
+            ("ARROW", vec![false, false, true, false]),
+        ];
+        for (pattern, expected) in cases {
+            let expected = expected
+                .into_iter()
+                .map(Some)
+                .chain(vec![None])
+                .collect::<BooleanArray>();
+            assert_eq!(
+                ilike_utf8_scalar(&values, pattern)?,
+                expected,
+                "{}",
+                pattern
+            );
+            let patterns = StringArray::from(vec![pattern; values.len()]);
+            assert_eq!(ilike_utf8(&values, &patterns)?, expected, "{}", pattern);
+        }
+
+        let expected = BooleanArray::from(vec![
+            Some(false),
+            Some(true),
+            Some(false),
+            Some(true),
+            None,
+        ]);
+        assert_eq!(nilike_utf8_scalar(&values, "%a_r%")?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn ilike_unicode() -> Result<()> {
+        let values = StringArray::from(vec!["ΣΟΦΊΑ", "Ωμέγα", "ÉCOLE", "Straße", "ΟΔΟΣ"]);
+        let cases = vec![
+            ("σοφία", vec![true, false, false, false, false]),
+            ("%ΜΈΓ%", vec![false, true, false, false, false]),
+            ("éc_le", vec![false, false, true, false, false]),
+            ("STRA_E", vec![false, false, false, true, false]),
+            // the lowercase of `ß` is itself, not `ss`
+            ("STRASSE", vec![false, false, false, false, false]),
+            // a final capital sigma is lowercased as any other one
+            ("%σ", vec![false, false, false, false, true]),
+            ("_δ_σ", vec![false, false, false, false, true]),
+        ];
+        for (pattern, expected) in cases {
+            let expected = BooleanArray::from(expected);
+            assert_eq!(
+                ilike_utf8_scalar(&values, pattern)?,
+                expected,
+                "{}",
+                pattern
+            );
+        }
+
+        let patterns = StringArray::from(vec![
+            Some("%Σ%"),
+            None,
+            Some("é%"),
+            Some("s%"),
+            Some("%Ο%"),
+        ]);
+        let expected = BooleanArray::from(vec![
+            Some(false),
+            None,
+            Some(false),
+            Some(false),
+            Some(false),
+        ]);
+        assert_eq!(nilike_utf8(&values, &patterns)?, expected);
+        Ok(())
+    }
+}
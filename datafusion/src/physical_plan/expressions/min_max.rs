@@ -36,6 +36,7 @@ use arrow::{
     datatypes::Field,
 };
 
+use super::bytes::{max_binary, min_binary};
 use super::format_state_name;
 use crate::arrow::array::Array;
 use arrow::array::DecimalArray;
@@ -250,6 +251,11 @@ fn min_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::LargeUtf8 => {
             typed_min_max_batch_string!(values, LargeStringArray, LargeUtf8, min_string)
         }
+        DataType::Binary => ScalarValue::Binary(min_binary(values.as_ref())?),
+        DataType::LargeBinary => ScalarValue::LargeBinary(min_binary(values.as_ref())?),
+        DataType::FixedSizeBinary(size) => {
+            ScalarValue::FixedSizeBinary(*size, min_binary(values.as_ref())?)
+        }
         _ => min_max_batch!(values, min),
     })
 }
@@ -263,6 +269,11 @@ fn max_batch(values: &ArrayRef) -> Result<ScalarValue> {
         DataType::LargeUtf8 => {
             typed_min_max_batch_string!(values, LargeStringArray, LargeUtf8, max_string)
         }
+        DataType::Binary => ScalarValue::Binary(max_binary(values.as_ref())?),
+        DataType::LargeBinary => ScalarValue::LargeBinary(max_binary(values.as_ref())?),
+        DataType::FixedSizeBinary(size) => {
+            ScalarValue::FixedSizeBinary(*size, max_binary(values.as_ref())?)
+        }
         _ => min_max_batch!(values, max),
     })
 }
@@ -315,6 +326,18 @@ macro_rules! typed_min_max_string {
             (Some(a), Some(b)) => Some((a).$OP(b).clone()),
         })
     }};
+
+    ($VALUE:expr, $DELTA:expr, $SCALAR:ident, $OP:ident, $SIZE:expr) => {{
+        ScalarValue::$SCALAR(
+            *$SIZE,
+            match ($VALUE, $DELTA) {
+                (None, None) => None,
+                (Some(a), None) => Some(a.clone()),
+                (None, Some(b)) => Some(b.clone()),
+                (Some(a), Some(b)) => Some((a).$OP(b).clone()),
+            },
+        )
+    }};
 }
 
 // min/max of two scalar values of the same type
@@ -367,6 +390,18 @@ macro_rules! min_max {
             (ScalarValue::LargeUtf8(lhs), ScalarValue::LargeUtf8(rhs)) => {
                 typed_min_max_string!(lhs, rhs, LargeUtf8, $OP)
             }
+            (ScalarValue::Binary(lhs), ScalarValue::Binary(rhs)) => {
+                typed_min_max_string!(lhs, rhs, Binary, $OP)
+            }
+            (ScalarValue::LargeBinary(lhs), ScalarValue::LargeBinary(rhs)) => {
+                typed_min_max_string!(lhs, rhs, LargeBinary, $OP)
+            }
+            (
+                ScalarValue::FixedSizeBinary(size, lhs),
+                ScalarValue::FixedSizeBinary(_, rhs),
+            ) => {
+                typed_min_max_string!(lhs, rhs, FixedSizeBinary, $OP, size)
+            }
             (ScalarValue::TimestampSecond(lhs, l_tz), ScalarValue::TimestampSecond(rhs, _)) => {
                 typed_min_max!(lhs, rhs, TimestampSecond, $OP, l_tz)
             }
@@ -580,7 +615,7 @@ mod tests {
     use crate::physical_plan::expressions::tests::aggregate;
     use crate::scalar::ScalarValue::Decimal128;
     use crate::{error::Result, generic_test_op};
-    use arrow::array::DecimalBuilder;
+    use arrow::array::{BinaryArray, DecimalBuilder, FixedSizeBinaryArray};
     use arrow::datatypes::*;
     use arrow::record_batch::RecordBatch;
 
@@ -788,6 +823,36 @@ mod tests {
         )
     }
 
+    #[test]
+    fn max_binary() -> Result<()> {
+        let a: ArrayRef = Arc::new(BinaryArray::from(vec![
+            Some(&b"\x01\xff"[..]),
+            Some(b"\x02"),
+        ]));
+        generic_test_op!(
+            a,
+            DataType::Binary,
+            Max,
+            ScalarValue::Binary(Some(vec![0x02])),
+            DataType::Binary
+        )
+    }
+
+    #[test]
+    fn min_fixed_size_binary() -> Result<()> {
+        let values = vec![Some(b"\x02\x00"), Some(b"\x01\xff")];
+        let a: ArrayRef = Arc::new(FixedSizeBinaryArray::try_from_sparse_iter(
+            values.into_iter(),
+        )?);
+        generic_test_op!(
+            a,
+            DataType::FixedSizeBinary(2),
+            Min,
+            ScalarValue::FixedSizeBinary(2, Some(vec![0x01, 0xff])),
+            DataType::FixedSizeBinary(2)
+        )
+    }
+
     #[test]
     fn max_large_utf8() -> Result<()> {
         let a: ArrayRef = Arc::new(LargeStringArray::from(vec!["d", "a", "c", "b"]));
@@ -27,21 +27,27 @@ use arrow::record_batch::RecordBatch;
 use serde::{Deserialize, Serialize};
 
 mod approx_distinct;
+mod approx_median;
 mod array_agg;
 mod average;
 #[macro_use]
 mod binary;
+mod bytes;
 mod case;
 mod cast;
 mod coercion;
 mod column;
 mod count;
 mod cume_dist;
+mod decimal;
+mod dictionary;
 mod get_indexed_field;
 mod in_list;
+mod interval;
 mod is_not_null;
 mod is_null;
 mod lead_lag;
+mod like;
 mod literal;
 #[macro_use]
 mod min_max;
@@ -49,8 +55,12 @@ mod negative;
 mod not;
 mod nth_value;
 mod nullif;
+mod overflow;
 mod rank;
 mod row_number;
+mod stddev;
+mod string_cast;
+mod struct_expr;
 mod sum;
 mod try_cast;
 
@@ -60,19 +70,29 @@ pub mod helpers {
 }
 
 pub use approx_distinct::ApproxDistinct;
+pub use approx_median::ApproxMedian;
 pub use array_agg::ArrayAgg;
 pub(crate) use average::is_avg_support_arg_type;
 pub use average::{avg_return_type, Avg, AvgAccumulator};
-pub use binary::{binary, binary_operator_data_type, BinaryExpr};
-pub use case::{case, CaseExpr};
+pub use binary::{binary, binary_operator_data_type, binary_with_overflow, BinaryExpr};
+pub(crate) use bytes::{
+    build_binary_compare, format_hex, is_binary, lexsort_binary_to_indices,
+    octet_length_binary, parse_hex, BinaryValues,
+};
+pub use case::{case, case_return_type, CaseExpr};
 pub use cast::{
-    cast, cast_column, cast_with_options, CastExpr, DEFAULT_DATAFUSION_CAST_OPTIONS,
+    can_cast_types, cast, cast_column, cast_with_options, cast_with_overflow, CastExpr,
+    DEFAULT_DATAFUSION_CAST_OPTIONS,
 };
 pub use column::{col, Column};
 pub use count::Count;
 pub use cume_dist::cume_dist;
 pub use get_indexed_field::GetIndexedFieldExpr;
-pub use in_list::{in_list, InListExpr};
+pub use in_list::{coerce_list_literal, in_list, InListExpr};
+pub(crate) use interval::{
+    build_interval_compare, day_time, format_day_time, is_interval, to_days_millis,
+    MILLISECONDS_IN_DAY,
+};
 pub use is_not_null::{is_not_null, IsNotNullExpr};
 pub use is_null::{is_null, IsNullExpr};
 pub use lead_lag::{lag, lead};
@@ -83,8 +103,11 @@ pub use negative::{negative, NegativeExpr};
 pub use not::{not, NotExpr};
 pub use nth_value::NthValue;
 pub use nullif::{nullif_func, SUPPORTED_NULLIF_TYPES};
+pub use overflow::ArithmeticOverflow;
 pub use rank::{dense_rank, percent_rank, rank};
 pub use row_number::RowNumber;
+pub use stddev::Stddev;
+pub use struct_expr::{struct_expr, StructExpr};
 pub(crate) use sum::is_sum_support_arg_type;
 pub use sum::{sum_return_type, Sum};
 pub use try_cast::{try_cast, TryCastExpr};
@@ -17,6 +17,8 @@
 
 use std::sync::Arc;
 
+use super::bytes::{eq_binary, eq_binary_scalar};
+use super::decimal::{eq_decimal, eq_decimal_scalar};
 use super::ColumnarValue;
 use crate::error::{DataFusionError, Result};
 use crate::scalar::ScalarValue;
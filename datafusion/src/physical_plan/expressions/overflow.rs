@@ -0,0 +1,459 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Kernels of the integer arithmetic whose results may be out of the range of
+//! their type, which either fail, wrap around or saturate depending on the
+//! [`ArithmeticOverflow`] mode, unlike the arrow kernels which wrap around in
+//! release builds and panic in debug builds.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, PrimitiveArray};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Int16Type, Int32Type, Int64Type, Int8Type, UInt16Type,
+    UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::util::display::array_value_to_string;
+
+use super::coercion::is_numeric;
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::Operator;
+
+use serde::{Deserialize, Serialize};
+
+/// How the integer arithmetic handles the results out of the range of their
+/// type: the `+`, `-` and `*` operators, the accumulation of `SUM` and the
+/// casts to integer types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArithmeticOverflow {
+    /// Fail with an error naming the expression that overflowed, as in ANSI
+    /// SQL. The casts out of range fail as well.
+    Error,
+    /// Wrap around the bounds of the type. The casts out of range return
+    /// nulls.
+    Wrap,
+    /// Clamp the results to the minimum or maximum of the type. The casts
+    /// out of range return nulls.
+    Saturate,
+}
+
+impl Default for ArithmeticOverflow {
+    fn default() -> Self {
+        ArithmeticOverflow::Wrap
+    }
+}
+
+impl fmt::Display for ArithmeticOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArithmeticOverflow::Error => write!(f, "error"),
+            ArithmeticOverflow::Wrap => write!(f, "wrap"),
+            ArithmeticOverflow::Saturate => write!(f, "saturate"),
+        }
+    }
+}
+
+impl FromStr for ArithmeticOverflow {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "error" => Ok(ArithmeticOverflow::Error),
+            "wrap" => Ok(ArithmeticOverflow::Wrap),
+            "saturate" => Ok(ArithmeticOverflow::Saturate),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unknown arithmetic overflow mode '{}', expected one of error, wrap or saturate",
+                s
+            ))),
+        }
+    }
+}
+
+/// An integer operation whose result may overflow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) enum IntegerOp {
+    Add,
+    Subtract,
+    Multiply,
+}
+
+impl IntegerOp {
+    /// The operation of the operator `op`, if it may overflow
+    fn try_from_operator(op: &Operator) -> Option<Self> {
+        match op {
+            Operator::Plus => Some(IntegerOp::Add),
+            Operator::Minus => Some(IntegerOp::Subtract),
+            Operator::Multiply => Some(IntegerOp::Multiply),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for IntegerOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntegerOp::Add => write!(f, "+"),
+            IntegerOp::Subtract => write!(f, "-"),
+            IntegerOp::Multiply => write!(f, "*"),
+        }
+    }
+}
+
+/// The native integer types of the arithmetic that may overflow
+pub(super) trait OverflowArithmetic: Copy + fmt::Display {
+    /// `self op rhs`, `None` if it overflows in the `Error` mode
+    fn overflowing_op(
+        self,
+        op: IntegerOp,
+        rhs: Self,
+        overflow: ArithmeticOverflow,
+    ) -> Option<Self>;
+}
+
+macro_rules! impl_overflow_arithmetic {
+    ($($NATIVE:ty),*) => {
+        $(
+            impl OverflowArithmetic for $NATIVE {
+                fn overflowing_op(
+                    self,
+                    op: IntegerOp,
+                    rhs: Self,
+                    overflow: ArithmeticOverflow,
+                ) -> Option<Self> {
+                    match (overflow, op) {
+                        (ArithmeticOverflow::Error, IntegerOp::Add) => {
+                            self.checked_add(rhs)
+                        }
+                        (ArithmeticOverflow::Error, IntegerOp::Subtract) => {
+                            self.checked_sub(rhs)
+                        }
+                        (ArithmeticOverflow::Error, IntegerOp::Multiply) => {
+                            self.checked_mul(rhs)
+                        }
+                        (ArithmeticOverflow::Wrap, IntegerOp::Add) => {
+                            Some(self.wrapping_add(rhs))
+                        }
+                        (ArithmeticOverflow::Wrap, IntegerOp::Subtract) => {
+                            Some(self.wrapping_sub(rhs))
+                        }
+                        (ArithmeticOverflow::Wrap, IntegerOp::Multiply) => {
+                            Some(self.wrapping_mul(rhs))
+                        }
+                        (ArithmeticOverflow::Saturate, IntegerOp::Add) => {
+                            Some(self.saturating_add(rhs))
+                        }
+                        (ArithmeticOverflow::Saturate, IntegerOp::Subtract) => {
+                            Some(self.saturating_sub(rhs))
+                        }
+                        (ArithmeticOverflow::Saturate, IntegerOp::Multiply) => {
+                            Some(self.saturating_mul(rhs))
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_overflow_arithmetic!(i8, i16, i32, i64, u8, u16, u32, u64);
+
+/// Whether `data_type` is an integer type, whose arithmetic may overflow
+pub(super) fn is_integer(data_type: &DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int8
+            | DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::UInt8
+            | DataType::UInt16
+            | DataType::UInt32
+            | DataType::UInt64
+    )
+}
+
+/// The error of `lhs op rhs` overflowing `data_type` while evaluating `expr`
+pub(super) fn overflow_error(
+    expr: &dyn fmt::Display,
+    lhs: &dyn fmt::Display,
+    op: IntegerOp,
+    rhs: &dyn fmt::Display,
+    data_type: &DataType,
+) -> DataFusionError {
+    DataFusionError::Execution(format!(
+        "Arithmetic overflow in {}: {} {} {} is out of the range of {:?}",
+        expr, lhs, op, rhs, data_type
+    ))
+}
+
+/// `lhs op rhs`, or the error of its overflow in the `Error` mode
+pub(super) fn overflowing_op<T: OverflowArithmetic>(
+    lhs: T,
+    op: IntegerOp,
+    rhs: T,
+    overflow: ArithmeticOverflow,
+    expr: &dyn fmt::Display,
+    data_type: &DataType,
+) -> Result<T> {
+    lhs.overflowing_op(op, rhs, overflow)
+        .ok_or_else(|| overflow_error(expr, &lhs, op, &rhs, data_type))
+}
+
+fn typed_integer_arithmetic<T>(
+    left: &ArrayRef,
+    op: IntegerOp,
+    right: &ArrayRef,
+    overflow: ArithmeticOverflow,
+    expr: &dyn fmt::Display,
+) -> Result<ArrayRef>
+where
+    T: ArrowPrimitiveType,
+    T::Native: OverflowArithmetic,
+{
+    let data_type = left.data_type();
+    let left = left.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+    let right = right.as_any().downcast_ref::<PrimitiveArray<T>>().unwrap();
+    let result = left
+        .iter()
+        .zip(right.iter())
+        .map(|(lhs, rhs)| match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) => {
+                overflowing_op(lhs, op, rhs, overflow, expr, data_type).map(Some)
+            }
+            _ => Ok(None),
+        })
+        .collect::<Result<PrimitiveArray<T>>>()?;
+    Ok(Arc::new(result))
+}
+
+/// Evaluate `left op right` on two integer arrays of the same type, handling
+/// the overflows as `overflow` says, while evaluating `expr`. `None` if the
+/// arrays are not integers or `op` can't overflow.
+pub(super) fn integer_arithmetic(
+    left: &ArrayRef,
+    op: &Operator,
+    right: &ArrayRef,
+    overflow: ArithmeticOverflow,
+    expr: &dyn fmt::Display,
+) -> Result<Option<ArrayRef>> {
+    let op = match IntegerOp::try_from_operator(op) {
+        Some(op) => op,
+        None => return Ok(None),
+    };
+    let result = match left.data_type() {
+        DataType::Int8 => {
+            typed_integer_arithmetic::<Int8Type>(left, op, right, overflow, expr)
+        }
+        DataType::Int16 => {
+            typed_integer_arithmetic::<Int16Type>(left, op, right, overflow, expr)
+        }
+        DataType::Int32 => {
+            typed_integer_arithmetic::<Int32Type>(left, op, right, overflow, expr)
+        }
+        DataType::Int64 => {
+            typed_integer_arithmetic::<Int64Type>(left, op, right, overflow, expr)
+        }
+        DataType::UInt8 => {
+            typed_integer_arithmetic::<UInt8Type>(left, op, right, overflow, expr)
+        }
+        DataType::UInt16 => {
+            typed_integer_arithmetic::<UInt16Type>(left, op, right, overflow, expr)
+        }
+        DataType::UInt32 => {
+            typed_integer_arithmetic::<UInt32Type>(left, op, right, overflow, expr)
+        }
+        DataType::UInt64 => {
+            typed_integer_arithmetic::<UInt64Type>(left, op, right, overflow, expr)
+        }
+        _ => return Ok(None),
+    };
+    result.map(Some)
+}
+
+/// Check that the cast of `array` to the integers of `cast` didn't turn any
+/// value out of range into a null, in the `Error` mode
+pub(super) fn check_cast_overflow(
+    array: &ArrayRef,
+    cast: &ArrayRef,
+    overflow: ArithmeticOverflow,
+    expr: &dyn fmt::Display,
+) -> Result<()> {
+    if overflow != ArithmeticOverflow::Error
+        || !is_integer(cast.data_type())
+        || !is_numeric(array.data_type())
+        || cast.null_count() == array.null_count()
+    {
+        return Ok(());
+    }
+    match (0..array.len()).find(|i| array.is_valid(*i) && cast.is_null(*i)) {
+        Some(i) => Err(DataFusionError::Execution(format!(
+            "Arithmetic overflow in {}: {} is out of the range of {:?}",
+            expr,
+            array_value_to_string(array, i)?,
+            cast.data_type()
+        ))),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, Int8Array, UInt8Array};
+
+    #[test]
+    fn integer_arithmetic_modes() -> Result<()> {
+        let left = Arc::new(Int32Array::from(vec![
+            Some(i32::MAX),
+            Some(i32::MIN),
+            None,
+            Some(3),
+        ])) as ArrayRef;
+        let right = Arc::new(Int32Array::from(vec![Some(1), Some(1), Some(1), Some(2)]))
+            as ArrayRef;
+
+        let cases = vec![
+            (
+                Operator::Plus,
+                ArithmeticOverflow::Wrap,
+                vec![Some(i32::MIN), Some(i32::MIN + 1), None, Some(5)],
+            ),
+            (
+                Operator::Plus,
+                ArithmeticOverflow::Saturate,
+                vec![Some(i32::MAX), Some(i32::MIN + 1), None, Some(5)],
+            ),
+            (
+                Operator::Minus,
+                ArithmeticOverflow::Wrap,
+                vec![Some(i32::MAX - 1), Some(i32::MAX), None, Some(1)],
+            ),
+            (
+                Operator::Minus,
+                ArithmeticOverflow::Saturate,
+                vec![Some(i32::MAX - 1), Some(i32::MIN), None, Some(1)],
+            ),
+            (
+                Operator::Multiply,
+                ArithmeticOverflow::Wrap,
+                vec![Some(i32::MAX), Some(i32::MIN), None, Some(6)],
+            ),
+        ];
+        for (op, overflow, expected) in cases {
+            let result =
+                integer_arithmetic(&left, &op, &right, overflow, &"a + b")?.unwrap();
+            let result = result.as_any().downcast_ref::<Int32Array>().unwrap();
+            assert_eq!(result, &Int32Array::from(expected), "{} {}", op, overflow);
+        }
+
+        let err = integer_arithmetic(
+            &left,
+            &Operator::Plus,
+            &right,
+            ArithmeticOverflow::Error,
+            &"a@0 + b@1",
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow in a@0 + b@1: 2147483647 + 1 is out of the range of Int32"
+        );
+
+        // the values within range don't fail
+        let result = integer_arithmetic(
+            &left,
+            &Operator::Multiply,
+            &right,
+            ArithmeticOverflow::Error,
+            &"a * b",
+        )?
+        .unwrap();
+        assert_eq!(result.len(), 4);
+
+        // the operators that can't overflow are left to the arrow kernels
+        assert!(integer_arithmetic(
+            &left,
+            &Operator::Divide,
+            &right,
+            ArithmeticOverflow::Error,
+            &"a / b"
+        )?
+        .is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn unsigned_arithmetic_modes() -> Result<()> {
+        let left = Arc::new(UInt8Array::from(vec![0, 200])) as ArrayRef;
+        let right = Arc::new(UInt8Array::from(vec![1, 100])) as ArrayRef;
+
+        let cases = vec![
+            (Operator::Minus, ArithmeticOverflow::Wrap, vec![255, 100]),
+            (Operator::Minus, ArithmeticOverflow::Saturate, vec![0, 100]),
+            (Operator::Plus, ArithmeticOverflow::Wrap, vec![1, 44]),
+            (Operator::Plus, ArithmeticOverflow::Saturate, vec![1, 255]),
+        ];
+        for (op, overflow, expected) in cases {
+            let result = integer_arithmetic(&left, &op, &right, overflow, &"a")?.unwrap();
+            let result = result.as_any().downcast_ref::<UInt8Array>().unwrap();
+            assert_eq!(result, &UInt8Array::from(expected), "{} {}", op, overflow);
+        }
+        assert!(integer_arithmetic(
+            &left,
+            &Operator::Minus,
+            &right,
+            ArithmeticOverflow::Error,
+            &"a"
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn cast_overflow() -> Result<()> {
+        let array =
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(300)])) as ArrayRef;
+        let cast = Arc::new(Int8Array::from(vec![Some(1), None, None])) as ArrayRef;
+        check_cast_overflow(&array, &cast, ArithmeticOverflow::Wrap, &"CAST")?;
+        let err = check_cast_overflow(&array, &cast, ArithmeticOverflow::Error, &"CAST")
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow in CAST: 300 is out of the range of Int8"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parse_overflow_modes() -> Result<()> {
+        for mode in [
+            ArithmeticOverflow::Error,
+            ArithmeticOverflow::Wrap,
+            ArithmeticOverflow::Saturate,
+        ] {
+            assert_eq!(mode.to_string().parse::<ArithmeticOverflow>()?, mode);
+        }
+        assert_eq!(
+            "SATURATE".parse::<ArithmeticOverflow>()?,
+            ArithmeticOverflow::Saturate
+        );
+        assert!("ignore".parse::<ArithmeticOverflow>().is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines physical expressions that can evaluated at runtime during query execution
+
+use super::format_state_name;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{Accumulator, AggregateExpr, PhysicalExpr};
+use crate::scalar::ScalarValue;
+use arrow::array::{Array, ArrayRef, Float64Array, UInt64Array};
+use arrow::compute::cast;
+use arrow::datatypes::{DataType, Field};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::sync::Arc;
+
+/// STDDEV aggregate expression: the sample standard deviation of the values,
+/// null for less than two values
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Stddev {
+    name: String,
+    expr: Arc<dyn PhysicalExpr>,
+    data_type: DataType,
+}
+
+impl Stddev {
+    /// Create a new STDDEV aggregate function
+    pub fn new(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+    ) -> Self {
+        // the standard deviation is computed over the values cast to FLOAT64
+        assert_eq!(data_type, DataType::Float64);
+        Self {
+            name: name.into(),
+            expr,
+            data_type,
+        }
+    }
+}
+
+#[typetag::serde(name = "stddev")]
+impl AggregateExpr for Stddev {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        Ok(Box::new(StddevAccumulator::default()))
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        Ok(vec![
+            Field::new(
+                &format_state_name(&self.name, "count"),
+                DataType::UInt64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "mean"),
+                DataType::Float64,
+                true,
+            ),
+            Field::new(
+                &format_state_name(&self.name, "m2"),
+                DataType::Float64,
+                true,
+            ),
+        ])
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        vec![self.expr.clone()]
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+/// The count, the mean and the sum of the squared differences to the mean
+/// (M2) of values, updated with Welford's algorithm, which does not suffer
+/// from the cancellation of `sum(x * x) - sum(x) * sum(x) / count`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct VarianceAccumulator {
+    count: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl VarianceAccumulator {
+    fn update(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Merge the variance of other values, see Chan et al., "Updating
+    /// Formulae and a Pairwise Algorithm for Computing Sample Variances"
+    fn merge(&mut self, other: &VarianceAccumulator) {
+        if other.count == 0 {
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2
+            + delta * delta * self.count as f64 * other.count as f64 / count as f64;
+        self.count = count;
+    }
+
+    /// The sample variance, `None` for less than two values
+    fn sample_variance(&self) -> Option<f64> {
+        (self.count > 1).then(|| self.m2 / (self.count - 1) as f64)
+    }
+}
+
+/// Accumulates the non-null values of the input as FLOAT64 into a
+/// [`VarianceAccumulator`]
+#[derive(Debug, Default)]
+struct StddevAccumulator {
+    variance: VarianceAccumulator,
+}
+
+fn downcast_state<'a, T: 'static>(state: &'a ArrayRef, name: &str) -> Result<&'a T> {
+    state.as_any().downcast_ref::<T>().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "Invalid stddev {} state of type {:?}",
+            name,
+            state.data_type()
+        ))
+    })
+}
+
+impl Accumulator for StddevAccumulator {
+    fn state(&self) -> Result<Vec<ScalarValue>> {
+        Ok(vec![
+            ScalarValue::from(self.variance.count),
+            ScalarValue::from(self.variance.mean),
+            ScalarValue::from(self.variance.m2),
+        ])
+    }
+
+    fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
+        self.update_batch(&[values[0].to_array()])
+    }
+
+    fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
+        let array = cast(&values[0], &DataType::Float64)?;
+        let array = array.as_any().downcast_ref::<Float64Array>().unwrap();
+        for value in array.iter().flatten() {
+            self.variance.update(value);
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, states: &[ScalarValue]) -> Result<()> {
+        let states = states
+            .iter()
+            .map(|state| state.to_array())
+            .collect::<Vec<_>>();
+        self.merge_batch(&states)
+    }
+
+    fn merge_batch(&mut self, states: &[ArrayRef]) -> Result<()> {
+        let counts = downcast_state::<UInt64Array>(&states[0], "count")?;
+        let means = downcast_state::<Float64Array>(&states[1], "mean")?;
+        let m2s = downcast_state::<Float64Array>(&states[2], "m2")?;
+        for i in 0..counts.len() {
+            if counts.is_null(i) {
+                continue;
+            }
+            self.variance.merge(&VarianceAccumulator {
+                count: counts.value(i),
+                mean: means.value(i),
+                m2: m2s.value(i),
+            });
+        }
+        Ok(())
+    }
+
+    fn evaluate(&self) -> Result<ScalarValue> {
+        Ok(ScalarValue::Float64(
+            self.variance.sample_variance().map(f64::sqrt),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::col;
+    use crate::physical_plan::expressions::tests::aggregate;
+    use crate::{error::Result, generic_test_op};
+    use arrow::array::Int32Array;
+    use arrow::datatypes::*;
+    use arrow::record_batch::RecordBatch;
+
+    #[test]
+    fn stddev_i32() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5]));
+        generic_test_op!(
+            a,
+            DataType::Int32,
+            Stddev,
+            2.5_f64.sqrt(),
+            DataType::Float64
+        )
+    }
+
+    #[test]
+    fn stddev_large_values() -> Result<()> {
+        // the sum of the squares of these values is not exact in FLOAT64
+        let a: ArrayRef = Arc::new(Float64Array::from(vec![
+            1e9 + 4.0,
+            1e9 + 7.0,
+            1e9 + 13.0,
+            1e9 + 16.0,
+        ]));
+        generic_test_op!(
+            a,
+            DataType::Float64,
+            Stddev,
+            30_f64.sqrt(),
+            DataType::Float64
+        )
+    }
+
+    #[test]
+    fn stddev_with_nulls() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![
+            Some(1),
+            None,
+            Some(3),
+            Some(5),
+            None,
+        ]));
+        generic_test_op!(a, DataType::Int32, Stddev, 2_f64, DataType::Float64)
+    }
+
+    #[test]
+    fn stddev_single_value() -> Result<()> {
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![Some(1), None]));
+        generic_test_op!(
+            a,
+            DataType::Int32,
+            Stddev,
+            ScalarValue::Float64(None),
+            DataType::Float64
+        )
+    }
+
+    #[test]
+    fn stddev_merge() -> Result<()> {
+        let mut left = StddevAccumulator::default();
+        left.update_batch(&[Arc::new(Int32Array::from(vec![1, 2])) as ArrayRef])?;
+        let mut right = StddevAccumulator::default();
+        right.update_batch(&[Arc::new(Int32Array::from(vec![3, 4, 5])) as ArrayRef])?;
+        let empty = StddevAccumulator::default();
+
+        let mut merged = StddevAccumulator::default();
+        let states = [left.state()?, empty.state()?, right.state()?];
+        let states = (0..3)
+            .map(|i| ScalarValue::iter_to_array(states.iter().map(|s| s[i].clone())))
+            .collect::<Result<Vec<_>>>()?;
+        merged.merge_batch(&states)?;
+        assert_eq!(
+            merged.variance,
+            VarianceAccumulator {
+                count: 5,
+                mean: 3.0,
+                m2: 10.0
+            }
+        );
+        assert_eq!(
+            merged.evaluate()?,
+            ScalarValue::Float64(Some(2.5_f64.sqrt()))
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Casts from and to strings that arrow doesn't provide yet, or provides
+//! with another format than SQL's: strings to booleans, dates and timestamps
+//! of any unit, and floats and intervals to strings.
+
+use std::sync::Arc;
+
+use arrow::array::*;
+use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
+use arrow::datatypes::{DataType, IntervalUnit, TimeUnit};
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::error::{DataFusionError, Result};
+
+use super::interval::{format_day_time, format_year_month};
+
+const MILLISECONDS_IN_DAY: i64 = 86_400_000;
+
+/// Whether [`cast_string`] supports casting from `from_type` to `to_type`
+pub(super) fn can_cast_string(from_type: &DataType, to_type: &DataType) -> bool {
+    use DataType::*;
+    match (from_type, to_type) {
+        (Utf8 | LargeUtf8, Boolean | Date32 | Date64 | Timestamp(_, _)) => true,
+        (Float32 | Float64 | Interval(_), Utf8 | LargeUtf8) => true,
+        _ => false,
+    }
+}
+
+/// Parse a boolean: `true`, `t`, `yes`, `y`, `on` or `1`, or `false`, `f`,
+/// `no`, `n`, `off` or `0`, whatever their case and the spaces around them
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "t" | "yes" | "y" | "on" | "1" => Some(true),
+        "false" | "f" | "no" | "n" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parse a date, `YYYY-MM-DD`, or the date of a timestamp without time zone,
+/// as a number of days since the epoch
+fn parse_date(value: &str) -> Option<i32> {
+    let value = value.trim();
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+                .map(|timestamp| timestamp.date())
+        })
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+                .map(|timestamp| timestamp.date())
+        })
+        .ok()?;
+    Some(
+        date.signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+            .num_days() as i32,
+    )
+}
+
+/// Parse a timestamp, with or without fractional seconds and time zone, as
+/// `to_timestamp` does, or a date at midnight, as a number of nanoseconds
+/// since the epoch
+fn parse_timestamp_nanos(value: &str) -> Option<i64> {
+    let value = value.trim();
+    string_to_timestamp_nanos(value)
+        .or_else(|_| string_to_timestamp_nanos(&format!("{}T00:00:00", value)))
+        .ok()
+}
+
+/// Format a float as PostgreSQL does: in scientific notation when its
+/// exponent is below -4 or above 15, and with `NaN`, `Infinity` and
+/// `-Infinity` for the special values
+macro_rules! format_float {
+    ($VALUE:expr) => {{
+        let value = $VALUE;
+        if value.is_nan() {
+            "NaN".to_string()
+        } else if value.is_infinite() {
+            if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+        } else if value != 0.0 && (value.abs() >= 1e16 || value.abs() < 1e-4) {
+            let formatted = format!("{:e}", value);
+            // `1e20` is formatted as `1e+20`
+            match formatted.split_once('e') {
+                Some((mantissa, exponent)) if !exponent.starts_with('-') => {
+                    format!("{}e+{}", mantissa, exponent)
+                }
+                _ => formatted,
+            }
+        } else {
+            value.to_string()
+        }
+    }};
+}
+
+fn invalid_cast(value: &str, to_type: &DataType) -> DataFusionError {
+    DataFusionError::Execution(format!("Cannot cast string '{}' to {:?}", value, to_type))
+}
+
+/// Parse the strings of `array` with `parse`, the strings it doesn't parse
+/// being nulls if `safe` and errors otherwise
+fn parse_strings<O, T, A, F>(
+    array: &ArrayRef,
+    to_type: &DataType,
+    safe: bool,
+    parse: F,
+) -> Result<A>
+where
+    O: StringOffsetSizeTrait,
+    A: FromIterator<Option<T>>,
+    F: Fn(&str) -> Option<T>,
+{
+    array
+        .as_any()
+        .downcast_ref::<GenericStringArray<O>>()
+        .unwrap()
+        .iter()
+        .map(|value| match value {
+            None => Ok(None),
+            Some(value) => match parse(value) {
+                Some(parsed) => Ok(Some(parsed)),
+                None if safe => Ok(None),
+                None => Err(invalid_cast(value, to_type)),
+            },
+        })
+        .collect()
+}
+
+fn cast_from_string<O: StringOffsetSizeTrait>(
+    array: &ArrayRef,
+    to_type: &DataType,
+    safe: bool,
+) -> Result<ArrayRef> {
+    let parse_timestamps = |divisor: i64| {
+        parse_strings::<O, _, Vec<_>, _>(array, to_type, safe, |value| {
+            parse_timestamp_nanos(value).map(|nanos| nanos.div_euclid(divisor))
+        })
+    };
+    Ok(match to_type {
+        DataType::Boolean => Arc::new(parse_strings::<O, _, BooleanArray, _>(
+            array, to_type, safe, parse_bool,
+        )?),
+        DataType::Date32 => Arc::new(parse_strings::<O, _, Date32Array, _>(
+            array, to_type, safe, parse_date,
+        )?),
+        DataType::Date64 => Arc::new(parse_strings::<O, _, Date64Array, _>(
+            array,
+            to_type,
+            safe,
+            |value| parse_date(value).map(|days| days as i64 * MILLISECONDS_IN_DAY),
+        )?),
+        DataType::Timestamp(TimeUnit::Second, tz) => {
+            Arc::new(TimestampSecondArray::from_opt_vec(
+                parse_timestamps(1_000_000_000)?,
+                tz.clone(),
+            ))
+        }
+        DataType::Timestamp(TimeUnit::Millisecond, tz) => {
+            Arc::new(TimestampMillisecondArray::from_opt_vec(
+                parse_timestamps(1_000_000)?,
+                tz.clone(),
+            ))
+        }
+        DataType::Timestamp(TimeUnit::Microsecond, tz) => Arc::new(
+            TimestampMicrosecondArray::from_opt_vec(parse_timestamps(1_000)?, tz.clone()),
+        ),
+        DataType::Timestamp(TimeUnit::Nanosecond, tz) => Arc::new(
+            TimestampNanosecondArray::from_opt_vec(parse_timestamps(1)?, tz.clone()),
+        ),
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to {:?}",
+                array.data_type(),
+                other
+            )))
+        }
+    })
+}
+
+fn cast_to_string<O: StringOffsetSizeTrait>(array: &ArrayRef) -> Result<ArrayRef> {
+    let strings: GenericStringArray<O> = match array.data_type() {
+        DataType::Float32 => {
+            let floats = array.as_any().downcast_ref::<Float32Array>().unwrap();
+            floats.iter().map(|v| v.map(|v| format_float!(v))).collect()
+        }
+        DataType::Float64 => {
+            let floats = array.as_any().downcast_ref::<Float64Array>().unwrap();
+            floats.iter().map(|v| v.map(|v| format_float!(v))).collect()
+        }
+        DataType::Interval(IntervalUnit::YearMonth) => {
+            let months = array
+                .as_any()
+                .downcast_ref::<IntervalYearMonthArray>()
+                .unwrap();
+            months.iter().map(|v| v.map(format_year_month)).collect()
+        }
+        DataType::Interval(IntervalUnit::DayTime) => {
+            let values = array
+                .as_any()
+                .downcast_ref::<IntervalDayTimeArray>()
+                .unwrap();
+            values.iter().map(|v| v.map(format_day_time)).collect()
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Unsupported CAST from {:?} to strings",
+                other
+            )))
+        }
+    };
+    Ok(Arc::new(strings))
+}
+
+/// Cast `array` to `to_type`, see [`can_cast_string`]. The strings are
+/// trimmed before being parsed, and those that can't be parsed are nulls if
+/// `safe` and errors otherwise. Intervals are formatted as in PostgreSQL,
+/// e.g. `1 year 2 mons` or `3 days 04:05:06`.
+pub(super) fn cast_string(
+    array: &ArrayRef,
+    to_type: &DataType,
+    safe: bool,
+) -> Result<ArrayRef> {
+    match (array.data_type(), to_type) {
+        (DataType::Utf8, _) => cast_from_string::<i32>(array, to_type, safe),
+        (DataType::LargeUtf8, _) => cast_from_string::<i64>(array, to_type, safe),
+        (_, DataType::Utf8) => cast_to_string::<i32>(array),
+        (_, DataType::LargeUtf8) => cast_to_string::<i64>(array),
+        (from_type, to_type) => Err(DataFusionError::Internal(format!(
+            "Unsupported CAST from {:?} to {:?}",
+            from_type, to_type
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: Vec<Option<&str>>) -> ArrayRef {
+        Arc::new(StringArray::from(values))
+    }
+
+    #[test]
+    fn string_to_boolean() -> Result<()> {
+        let array = strings(vec![
+            Some("t"),
+            Some(" YES "),
+            Some("1"),
+            Some("Off"),
+            Some("false"),
+            Some("maybe"),
+            None,
+        ]);
+        let cast = cast_string(&array, &DataType::Boolean, true)?;
+        let expected = BooleanArray::from(vec![
+            Some(true),
+            Some(true),
+            Some(true),
+            Some(false),
+            Some(false),
+            None,
+            None,
+        ]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &expected
+        );
+
+        let err = cast_string(&array, &DataType::Boolean, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Cannot cast string 'maybe' to Boolean"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn string_to_date() -> Result<()> {
+        let array: ArrayRef = Arc::new(LargeStringArray::from(vec![
+            Some("1970-01-02"),
+            Some("2021-03-04 12:30:00"),
+            Some("1969-12-31T23:59:59.5"),
+            Some("2021-02-30"),
+            None,
+        ]));
+        let cast = cast_string(&array, &DataType::Date32, true)?;
+        let expected =
+            Date32Array::from(vec![Some(1), Some(18690), Some(-1), None, None]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<Date32Array>().unwrap(),
+            &expected
+        );
+
+        let cast = cast_string(&array, &DataType::Date64, true)?;
+        let expected = Date64Array::from(vec![
+            Some(MILLISECONDS_IN_DAY),
+            Some(18690 * MILLISECONDS_IN_DAY),
+            Some(-MILLISECONDS_IN_DAY),
+            None,
+            None,
+        ]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<Date64Array>().unwrap(),
+            &expected
+        );
+
+        assert!(cast_string(&array, &DataType::Date32, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn string_to_timestamp() -> Result<()> {
+        let array = strings(vec![
+            Some("2020-09-08T13:42:29.190855Z"),
+            Some("1969-12-31T23:59:59.5+00:00"),
+            Some("not a timestamp"),
+            None,
+        ]);
+        let tz = Some("UTC".to_string());
+        let cast = cast_string(
+            &array,
+            &DataType::Timestamp(TimeUnit::Millisecond, tz.clone()),
+            true,
+        )?;
+        assert_eq!(
+            cast.data_type(),
+            &DataType::Timestamp(TimeUnit::Millisecond, tz)
+        );
+        let cast = cast
+            .as_any()
+            .downcast_ref::<TimestampMillisecondArray>()
+            .unwrap();
+        assert_eq!(cast.value(0), 1599572549190);
+        // the instants before the epoch are rounded down
+        assert_eq!(cast.value(1), -500);
+        assert!(cast.is_null(2));
+        assert!(cast.is_null(3));
+
+        let cast =
+            cast_string(&array, &DataType::Timestamp(TimeUnit::Second, None), true)?;
+        let cast = cast
+            .as_any()
+            .downcast_ref::<TimestampSecondArray>()
+            .unwrap();
+        assert_eq!(cast.value(0), 1599572549);
+        assert_eq!(cast.value(1), -1);
+
+        let err =
+            cast_string(&array, &DataType::Timestamp(TimeUnit::Second, None), false)
+                .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Cannot cast string 'not a timestamp' to Timestamp(Second, None)"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn float_to_string() -> Result<()> {
+        let array: ArrayRef = Arc::new(Float64Array::from(vec![
+            Some(1.0),
+            Some(-2.5),
+            Some(0.1),
+            Some(1e20),
+            Some(-1.5e-7),
+            Some(f64::NAN),
+            Some(f64::INFINITY),
+            Some(f64::NEG_INFINITY),
+            None,
+        ]));
+        let cast = cast_string(&array, &DataType::Utf8, false)?;
+        let expected = StringArray::from(vec![
+            Some("1"),
+            Some("-2.5"),
+            Some("0.1"),
+            Some("1e+20"),
+            Some("-1.5e-7"),
+            Some("NaN"),
+            Some("Infinity"),
+            Some("-Infinity"),
+            None,
+        ]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<StringArray>().unwrap(),
+            &expected
+        );
+
+        let array: ArrayRef = Arc::new(Float32Array::from(vec![0.1, 3.0]));
+        let cast = cast_string(&array, &DataType::LargeUtf8, false)?;
+        let expected = LargeStringArray::from(vec!["0.1", "3"]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<LargeStringArray>().unwrap(),
+            &expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn interval_to_string() -> Result<()> {
+        let array: ArrayRef =
+            Arc::new(IntervalYearMonthArray::from(vec![Some(14), None]));
+        let cast = cast_string(&array, &DataType::Utf8, false)?;
+        let expected = StringArray::from(vec![Some("1 year 2 mons"), None]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<StringArray>().unwrap(),
+            &expected
+        );
+
+        let array: ArrayRef = Arc::new(IntervalDayTimeArray::from(vec![Some(
+            (3 << 32) | 14_706_000,
+        )]));
+        let cast = cast_string(&array, &DataType::Utf8, false)?;
+        let expected = StringArray::from(vec!["3 days 04:05:06"]);
+        assert_eq!(
+            cast.as_any().downcast_ref::<StringArray>().unwrap(),
+            &expected
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Struct expression
+
+use std::any::Any;
+use std::fmt;
+use std::sync::Arc;
+
+use super::ColumnarValue;
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::PhysicalExpr;
+use arrow::array::{ArrayRef, StructArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+
+use serde::{Deserialize, Serialize};
+
+/// Builds a `StructArray` with one named field per argument
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StructExpr {
+    /// The names of the fields
+    names: Vec<String>,
+    /// The expressions computing the values of the fields
+    args: Vec<Arc<dyn PhysicalExpr>>,
+}
+
+impl StructExpr {
+    /// Create a new struct expression
+    pub fn new(names: Vec<String>, args: Vec<Arc<dyn PhysicalExpr>>) -> Self {
+        Self { names, args }
+    }
+
+    /// The names of the fields
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// The expressions computing the values of the fields
+    pub fn args(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.args
+    }
+
+    fn fields(&self, input_schema: &Schema) -> Result<Vec<Field>> {
+        self.names
+            .iter()
+            .zip(self.args.iter())
+            .map(|(name, arg)| {
+                Ok(Field::new(
+                    name,
+                    arg.data_type(input_schema)?,
+                    arg.nullable(input_schema)?,
+                ))
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for StructExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "struct(")?;
+        for (i, (name, arg)) in self.names.iter().zip(self.args.iter()).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", name, arg)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[typetag::serde(name = "struct_expr")]
+impl PhysicalExpr for StructExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, input_schema: &Schema) -> Result<DataType> {
+        Ok(DataType::Struct(self.fields(input_schema)?))
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(false)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        let fields = self.fields(batch.schema().as_ref())?;
+        let columns = self
+            .args
+            .iter()
+            .map(|arg| Ok(arg.evaluate(batch)?.into_array(batch.num_rows())))
+            .collect::<Result<Vec<ArrayRef>>>()?;
+        Ok(ColumnarValue::Array(Arc::new(StructArray::from(
+            fields.into_iter().zip(columns).collect::<Vec<_>>(),
+        ))))
+    }
+}
+
+/// Creates a struct expression with one field per `(name, arg)` pair
+///
+/// # Errors
+///
+/// This function errors when there are no fields or two fields share a name
+pub fn struct_expr(
+    names: Vec<String>,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    if args.is_empty() {
+        return Err(DataFusionError::Plan(
+            "struct requires at least one field".to_owned(),
+        ));
+    }
+    for (i, name) in names.iter().enumerate() {
+        if names[..i].contains(name) {
+            return Err(DataFusionError::Plan(format!(
+                "struct has more than one field named '{}'",
+                name
+            )));
+        }
+    }
+    Ok(Arc::new(StructExpr::new(names, args)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, lit};
+    use crate::scalar::ScalarValue;
+    use arrow::array::{Array, Int32Array, StringArray};
+
+    #[test]
+    fn struct_of_columns() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2])),
+                Arc::new(StringArray::from(vec![Some("x"), None])),
+            ],
+        )?;
+        let expr = struct_expr(
+            vec!["a".to_owned(), "b".to_owned(), "c".to_owned()],
+            vec![
+                col("a", &schema)?,
+                col("b", &schema)?,
+                lit(ScalarValue::Int32(Some(7))),
+            ],
+        )?;
+        assert_eq!("struct(a: a@0, b: b@1, c: 7)", format!("{}", expr));
+
+        let expected_fields = vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, true),
+            Field::new("c", DataType::Int32, false),
+        ];
+        assert_eq!(
+            DataType::Struct(expected_fields.clone()),
+            expr.data_type(&schema)?
+        );
+        assert!(!expr.nullable(&schema)?);
+
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<StructArray>().unwrap();
+        assert_eq!(&DataType::Struct(expected_fields), result.data_type());
+        assert_eq!(0, result.null_count());
+        let b = result.column_by_name("b").unwrap();
+        let b = b.as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(&StringArray::from(vec![Some("x"), None]), b);
+        let c = result.column_by_name("c").unwrap();
+        let c = c.as_any().downcast_ref::<Int32Array>().unwrap();
+        assert_eq!(&Int32Array::from(vec![7, 7]), c);
+        Ok(())
+    }
+
+    #[test]
+    fn struct_with_duplicate_names() {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let a = col("a", &schema).unwrap();
+        let err = struct_expr(vec!["x".to_owned(), "x".to_owned()], vec![a.clone(), a])
+            .unwrap_err();
+        assert_eq!(
+            "Error during planning: struct has more than one field named 'x'",
+            err.to_string()
+        );
+    }
+}
@@ -19,6 +19,7 @@
 
 use std::any::Any;
 use std::convert::TryFrom;
+use std::fmt;
 use std::sync::Arc;
 
 use crate::error::{DataFusionError, Result};
@@ -35,6 +36,7 @@ use arrow::{
 };
 
 use super::format_state_name;
+use super::overflow::{overflowing_op, ArithmeticOverflow, IntegerOp};
 use crate::arrow::array::Array;
 use arrow::array::DecimalArray;
 
@@ -47,6 +49,9 @@ pub struct Sum {
     data_type: DataType,
     expr: Arc<dyn PhysicalExpr>,
     nullable: bool,
+    /// How the sum of integers handles the results out of range
+    #[serde(default)]
+    overflow: ArithmeticOverflow,
 }
 
 /// function return type of a sum
@@ -97,14 +102,31 @@ impl Sum {
         expr: Arc<dyn PhysicalExpr>,
         name: impl Into<String>,
         data_type: DataType,
+    ) -> Self {
+        Self::new_with_overflow(expr, name, data_type, ArithmeticOverflow::default())
+    }
+
+    /// Create a new SUM aggregate function whose sum of integers handles the
+    /// results out of range as `overflow` says
+    pub fn new_with_overflow(
+        expr: Arc<dyn PhysicalExpr>,
+        name: impl Into<String>,
+        data_type: DataType,
+        overflow: ArithmeticOverflow,
     ) -> Self {
         Self {
             name: name.into(),
             expr,
             data_type,
             nullable: true,
+            overflow,
         }
     }
+
+    /// How the sum of integers handles the results out of range
+    pub fn overflow(&self) -> ArithmeticOverflow {
+        self.overflow
+    }
 }
 
 #[typetag::serde(name = "sum")]
@@ -123,7 +145,11 @@ impl AggregateExpr for Sum {
     }
 
     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-        Ok(Box::new(SumAccumulator::try_new(&self.data_type)?))
+        Ok(Box::new(SumAccumulator::try_new_with_overflow(
+            &self.data_type,
+            self.overflow,
+            &self.name,
+        )?))
     }
 
     fn state_fields(&self) -> Result<Vec<Field>> {
@@ -146,13 +172,23 @@ impl AggregateExpr for Sum {
 #[derive(Debug)]
 struct SumAccumulator {
     sum: ScalarValue,
+    overflow: ArithmeticOverflow,
+    /// The name of the aggregate, for the errors of the overflows
+    name: String,
 }
 
 impl SumAccumulator {
-    /// new sum accumulator
-    pub fn try_new(data_type: &DataType) -> Result<Self> {
+    /// new sum accumulator, whose sum of integers handles the results out of
+    /// range as `overflow` says
+    pub fn try_new_with_overflow(
+        data_type: &DataType,
+        overflow: ArithmeticOverflow,
+        name: &str,
+    ) -> Result<Self> {
         Ok(Self {
             sum: ScalarValue::try_from(data_type)?,
+            overflow,
+            name: name.to_string(),
         })
     }
 }
@@ -166,6 +202,30 @@ macro_rules! typed_sum_delta_batch {
     }};
 }
 
+// returns the sum of the integers of the array in $TYPE, the type of their
+// sum, handling the overflows as $OVERFLOW says
+macro_rules! typed_sum_integer_batch {
+    ($VALUES:expr, $ARRAYTYPE:ident, $SCALAR:ident, $TYPE:ident, $OVERFLOW:expr, $NAME:expr) => {{
+        let array = $VALUES.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
+        let mut sum: Option<$TYPE> = None;
+        for value in array.iter().flatten() {
+            let value = value as $TYPE;
+            sum = Some(match sum {
+                Some(sum) => overflowing_op(
+                    sum,
+                    IntegerOp::Add,
+                    value,
+                    $OVERFLOW,
+                    $NAME,
+                    &DataType::$SCALAR,
+                )?,
+                None => value,
+            });
+        }
+        ScalarValue::$SCALAR(sum)
+    }};
+}
+
 // TODO implement this in arrow-rs with simd
 // https://github.com/apache/arrow-rs/issues/1010
 fn sum_decimal_batch(
@@ -190,20 +250,51 @@ fn sum_decimal_batch(
 
 // sums the array and returns a ScalarValue of its corresponding type.
 pub(super) fn sum_batch(values: &ArrayRef) -> Result<ScalarValue> {
+    sum_batch_with_overflow(values, ArithmeticOverflow::default(), &"SUM")
+}
+
+// sums the array and returns a ScalarValue of its corresponding type, the
+// integers being summed in the type of their sum and handling the overflows
+// as `overflow` says. The arrow kernel is kept for the wrapping sums of
+// 64-bit integers, which can't be widened.
+fn sum_batch_with_overflow(
+    values: &ArrayRef,
+    overflow: ArithmeticOverflow,
+    name: &dyn fmt::Display,
+) -> Result<ScalarValue> {
+    let wrap = overflow == ArithmeticOverflow::Wrap;
     Ok(match values.data_type() {
         DataType::Decimal(precision, scale) => {
             sum_decimal_batch(values, precision, scale)?
         }
         DataType::Float64 => typed_sum_delta_batch!(values, Float64Array, Float64),
         DataType::Float32 => typed_sum_delta_batch!(values, Float32Array, Float32),
-        DataType::Int64 => typed_sum_delta_batch!(values, Int64Array, Int64),
-        DataType::Int32 => typed_sum_delta_batch!(values, Int32Array, Int32),
-        DataType::Int16 => typed_sum_delta_batch!(values, Int16Array, Int16),
-        DataType::Int8 => typed_sum_delta_batch!(values, Int8Array, Int8),
-        DataType::UInt64 => typed_sum_delta_batch!(values, UInt64Array, UInt64),
-        DataType::UInt32 => typed_sum_delta_batch!(values, UInt32Array, UInt32),
-        DataType::UInt16 => typed_sum_delta_batch!(values, UInt16Array, UInt16),
-        DataType::UInt8 => typed_sum_delta_batch!(values, UInt8Array, UInt8),
+        DataType::Int64 if wrap => typed_sum_delta_batch!(values, Int64Array, Int64),
+        DataType::UInt64 if wrap => typed_sum_delta_batch!(values, UInt64Array, UInt64),
+        DataType::Int64 => {
+            typed_sum_integer_batch!(values, Int64Array, Int64, i64, overflow, name)
+        }
+        DataType::Int32 => {
+            typed_sum_integer_batch!(values, Int32Array, Int64, i64, overflow, name)
+        }
+        DataType::Int16 => {
+            typed_sum_integer_batch!(values, Int16Array, Int64, i64, overflow, name)
+        }
+        DataType::Int8 => {
+            typed_sum_integer_batch!(values, Int8Array, Int64, i64, overflow, name)
+        }
+        DataType::UInt64 => {
+            typed_sum_integer_batch!(values, UInt64Array, UInt64, u64, overflow, name)
+        }
+        DataType::UInt32 => {
+            typed_sum_integer_batch!(values, UInt32Array, UInt64, u64, overflow, name)
+        }
+        DataType::UInt16 => {
+            typed_sum_integer_batch!(values, UInt16Array, UInt64, u64, overflow, name)
+        }
+        DataType::UInt8 => {
+            typed_sum_integer_batch!(values, UInt8Array, UInt64, u64, overflow, name)
+        }
         e => {
             return Err(DataFusionError::Internal(format!(
                 "Sum is not expected to receive the type {:?}",
@@ -225,6 +316,26 @@ macro_rules! typed_sum {
     }};
 }
 
+// returns the sum of two integers, including coercion into $TYPE, handling
+// the overflows as $OVERFLOW says
+macro_rules! typed_sum_integer {
+    ($OLD_VALUE:expr, $DELTA:expr, $SCALAR:ident, $TYPE:ident, $OVERFLOW:expr, $NAME:expr) => {{
+        ScalarValue::$SCALAR(match ($OLD_VALUE, $DELTA) {
+            (None, None) => None,
+            (Some(a), None) => Some(*a),
+            (None, Some(b)) => Some(*b as $TYPE),
+            (Some(a), Some(b)) => Some(overflowing_op(
+                *a,
+                IntegerOp::Add,
+                *b as $TYPE,
+                $OVERFLOW,
+                $NAME,
+                &DataType::$SCALAR,
+            )?),
+        })
+    }};
+}
+
 // TODO implement this in arrow-rs with simd
 // https://github.com/apache/arrow-rs/issues/1010
 fn sum_decimal(
@@ -267,6 +378,17 @@ fn sum_decimal_with_diff_scale(
 }
 
 pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
+    sum_with_overflow(lhs, rhs, ArithmeticOverflow::default(), &"SUM")
+}
+
+// returns the sum of two scalar values, the sums of integers handling the
+// overflows as `overflow` says
+fn sum_with_overflow(
+    lhs: &ScalarValue,
+    rhs: &ScalarValue,
+    overflow: ArithmeticOverflow,
+    name: &dyn fmt::Display,
+) -> Result<ScalarValue> {
     Ok(match (lhs, rhs) {
         (ScalarValue::Decimal128(v1, p1, s1), ScalarValue::Decimal128(v2, p2, s2)) => {
             let max_precision = p1.max(p2);
@@ -318,29 +440,29 @@ pub(super) fn sum(lhs: &ScalarValue, rhs: &ScalarValue) -> Result<ScalarValue> {
         }
         // u64 coerces u* to u64
         (ScalarValue::UInt64(lhs), ScalarValue::UInt64(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_sum_integer!(lhs, rhs, UInt64, u64, overflow, name)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt32(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_sum_integer!(lhs, rhs, UInt64, u64, overflow, name)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt16(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_sum_integer!(lhs, rhs, UInt64, u64, overflow, name)
         }
         (ScalarValue::UInt64(lhs), ScalarValue::UInt8(rhs)) => {
-            typed_sum!(lhs, rhs, UInt64, u64)
+            typed_sum_integer!(lhs, rhs, UInt64, u64, overflow, name)
         }
         // i64 coerces i* to u64
         (ScalarValue::Int64(lhs), ScalarValue::Int64(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_sum_integer!(lhs, rhs, Int64, i64, overflow, name)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int32(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_sum_integer!(lhs, rhs, Int64, i64, overflow, name)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int16(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_sum_integer!(lhs, rhs, Int64, i64, overflow, name)
         }
         (ScalarValue::Int64(lhs), ScalarValue::Int8(rhs)) => {
-            typed_sum!(lhs, rhs, Int64, i64)
+            typed_sum_integer!(lhs, rhs, Int64, i64, overflow, name)
         }
         e => {
             return Err(DataFusionError::Internal(format!(
@@ -358,13 +480,14 @@ impl Accumulator for SumAccumulator {
 
     fn update(&mut self, values: &[ScalarValue]) -> Result<()> {
         // sum(v1, v2, v3) = v1 + v2 + v3
-        self.sum = sum(&self.sum, &values[0])?;
+        self.sum = sum_with_overflow(&self.sum, &values[0], self.overflow, &self.name)?;
         Ok(())
     }
 
     fn update_batch(&mut self, values: &[ArrayRef]) -> Result<()> {
         let values = &values[0];
-        self.sum = sum(&self.sum, &sum_batch(values)?)?;
+        let delta = sum_batch_with_overflow(values, self.overflow, &self.name)?;
+        self.sum = sum_with_overflow(&self.sum, &delta, self.overflow, &self.name)?;
         Ok(())
     }
 
@@ -608,6 +731,79 @@ mod tests {
         )
     }
 
+    #[test]
+    fn sum_i32_max_values() -> Result<()> {
+        // the values are summed in the type of their sum, which doesn't overflow
+        let a: ArrayRef = Arc::new(Int32Array::from(vec![i32::MAX; 1000]));
+        generic_test_op!(
+            a,
+            DataType::Int32,
+            Sum,
+            ScalarValue::from(i32::MAX as i64 * 1000),
+            DataType::Int64
+        )
+    }
+
+    #[test]
+    fn sum_i64_overflow() -> Result<()> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int64, false)]);
+        let a: ArrayRef = Arc::new(Int64Array::from(vec![i64::MAX, 1, 2]));
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), vec![a])?;
+        let sum_with = |overflow| -> Result<ScalarValue> {
+            let agg = Sum::new_with_overflow(
+                col("a", &schema)?,
+                "SUM(a)",
+                DataType::Int64,
+                overflow,
+            );
+            aggregate(&batch, Arc::new(agg))
+        };
+
+        assert_eq!(
+            sum_with(ArithmeticOverflow::Saturate)?,
+            ScalarValue::Int64(Some(i64::MAX))
+        );
+        let err = sum_with(ArithmeticOverflow::Error).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: Arithmetic overflow in SUM(a): 9223372036854775807 + 1 is out of the range of Int64"
+        );
+
+        // the sums of the partitions overflow when merged
+        for (overflow, expected) in [
+            (ArithmeticOverflow::Wrap, Some(i64::MIN)),
+            (ArithmeticOverflow::Saturate, Some(i64::MAX)),
+            (ArithmeticOverflow::Error, None),
+        ] {
+            let mut accum = SumAccumulator::try_new_with_overflow(
+                &DataType::Int64,
+                overflow,
+                "SUM(a)",
+            )?;
+            accum.merge(&[ScalarValue::Int64(Some(i64::MAX))])?;
+            match expected {
+                Some(expected) => {
+                    accum.merge(&[ScalarValue::Int64(Some(1))])?;
+                    assert_eq!(accum.evaluate()?, ScalarValue::Int64(Some(expected)));
+                }
+                None => assert!(accum.merge(&[ScalarValue::Int64(Some(1))]).is_err()),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn sum_unsigned_overflow() -> Result<()> {
+        let values: ArrayRef = Arc::new(UInt8Array::from(vec![u8::MAX, u8::MAX]));
+        let sum = sum_batch_with_overflow(&values, ArithmeticOverflow::Error, &"SUM")?;
+        assert_eq!(sum, ScalarValue::UInt64(Some(510)));
+
+        let values: ArrayRef = Arc::new(UInt64Array::from(vec![u64::MAX, 1]));
+        let sum = sum_batch_with_overflow(&values, ArithmeticOverflow::Saturate, &"SUM")?;
+        assert_eq!(sum, ScalarValue::UInt64(Some(u64::MAX)));
+        Ok(())
+    }
+
     fn aggregate(
         batch: &RecordBatch,
         agg: Arc<dyn AggregateExpr>,
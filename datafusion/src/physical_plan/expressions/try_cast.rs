@@ -19,15 +19,14 @@ use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
 
+use super::cast::{can_cast_types, cast_array};
 use super::ColumnarValue;
 use crate::error::{DataFusionError, Result};
 use crate::physical_plan::PhysicalExpr;
 use crate::scalar::ScalarValue;
-use arrow::compute;
-use arrow::compute::kernels;
+use arrow::compute::CastOptions;
 use arrow::datatypes::{DataType, Schema};
 use arrow::record_batch::RecordBatch;
-use compute::can_cast_types;
 
 use serde::{Deserialize, Serialize};
 
@@ -80,14 +79,16 @@ impl PhysicalExpr for TryCastExpr {
 
     fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
         let value = self.expr.evaluate(batch)?;
+        let options = CastOptions { safe: true };
         match value {
-            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(kernels::cast::cast(
+            ColumnarValue::Array(array) => Ok(ColumnarValue::Array(cast_array(
                 &array,
                 &self.cast_type,
+                &options,
             )?)),
             ColumnarValue::Scalar(scalar) => {
                 let scalar_array = scalar.to_array();
-                let cast_array = kernels::cast::cast(&scalar_array, &self.cast_type)?;
+                let cast_array = cast_array(&scalar_array, &self.cast_type, &options)?;
                 let cast_scalar = ScalarValue::try_from_array(&cast_array, 0)?;
                 Ok(ColumnarValue::Scalar(cast_scalar))
             }
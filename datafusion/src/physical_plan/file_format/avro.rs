@@ -19,9 +19,14 @@
 #[cfg(feature = "avro")]
 use crate::avro_to_arrow;
 use crate::error::{DataFusionError, Result};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
-    LambdaExecPlan,
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    SendableRecordBatchStream, Statistics,
 };
 use arrow::datatypes::SchemaRef;
 #[cfg(feature = "avro")]
@@ -33,8 +38,8 @@ use std::any::Any;
 use std::sync::Arc;
 
 #[cfg(feature = "avro")]
-use super::file_stream::{BatchIter, FileStream};
-use super::PhysicalPlanConfig;
+use super::file_stream::{BatchIter, FileScanMetrics, FileStream};
+use super::{fed_partition, PhysicalPlanConfig};
 use serde::{Deserialize, Serialize};
 
 /// Execution plan for scanning Avro data source
@@ -43,6 +48,11 @@ pub struct AvroExec {
     base_config: PhysicalPlanConfig,
     projected_statistics: Statistics,
     projected_schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+    /// Partitions fed in place of the files, see [`LambdaExecPlan`]
+    #[serde(skip)]
+    fed_partitions: Option<Vec<Vec<RecordBatch>>>,
 }
 
 impl AvroExec {
@@ -54,6 +64,8 @@ impl AvroExec {
             base_config,
             projected_schema,
             projected_statistics,
+            metrics: ExecutionPlanMetricsSet::new(),
+            fed_partitions: None,
         }
     }
     /// Ref to the base configs
@@ -64,8 +76,9 @@ impl AvroExec {
 
 #[async_trait]
 impl LambdaExecPlan for AvroExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!("AvroExec::feed_batches");
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.fed_partitions = Some(partitions);
+        Ok(())
     }
 }
 
@@ -85,7 +98,11 @@ impl ExecutionPlan for AvroExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        let partition_count = match &self.fed_partitions {
+            Some(partitions) => partitions.len(),
+            None => self.base_config.file_groups.len(),
+        };
+        Partitioning::UnknownPartitioning(partition_count)
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -107,17 +124,67 @@ impl ExecutionPlan for AvroExec {
     }
 
     #[cfg(not(feature = "avro"))]
-    async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "AvroExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
         Err(DataFusionError::NotImplemented(
             "Cannot execute avro plan without avro feature enabled".to_string(),
         ))
     }
 
     #[cfg(feature = "avro")]
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "AvroExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
         let proj = self.base_config.projected_file_column_names();
 
-        let batch_size = self.base_config.batch_size;
+        let batch_size = context.batch_size();
         let file_schema = Arc::clone(&self.base_config.file_schema);
 
         // The avro reader cannot limit the number of records, so `remaining` is ignored.
@@ -136,14 +203,23 @@ impl ExecutionPlan for AvroExec {
             }
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        )
+        .with_metrics(FileScanMetrics::new(&self.metrics, partition));
+        let stream = self.base_config.observe(
+            Box::pin(stream),
+            "AvroExec",
+            partition,
+            &self.metrics,
+            &context,
+        );
+        Ok(instrument_stream(self, partition, stream))
     }
 
     fn fmt_as(
@@ -161,9 +237,23 @@ impl ExecutionPlan for AvroExec {
                     self.base_config.limit,
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut exec = self.clone();
+        exec.base_config.bind_runtime(runtime)?;
+        Ok(Some(Arc::new(exec)))
+    }
+
     fn statistics(&self) -> Statistics {
         self.projected_statistics.clone()
     }
@@ -177,13 +267,16 @@ mod tests {
     use crate::datasource::object_store::local::{
         local_object_reader_stream, local_unpartitioned_file, LocalFileSystem,
     };
+    use crate::execution::context::ExecutionContext;
     use crate::scalar::ScalarValue;
+    use arrow::datatypes::DataType;
     use futures::StreamExt;
 
     use super::*;
 
     #[tokio::test]
     async fn avro_exec_without_partition() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let testdata = crate::test_util::arrow_test_data();
         let filename = format!("{}/avro/alltypes_plain.avro", testdata);
         let avro_exec = AvroExec::new(PhysicalPlanConfig {
@@ -200,7 +293,10 @@ mod tests {
         });
         assert_eq!(avro_exec.output_partitioning().partition_count(), 1);
 
-        let mut results = avro_exec.execute(0).await.expect("plan execution failed");
+        let mut results = avro_exec
+            .execute(0, task_ctx)
+            .await
+            .expect("plan execution failed");
         let batch = results
             .next()
             .await
@@ -233,11 +329,21 @@ mod tests {
         let batch = results.next().await;
         assert!(batch.is_none());
 
+        let metrics = avro_exec.metrics().unwrap();
+        let files_opened = metrics
+            .sum(|m| m.value().name() == "files_opened")
+            .map(|v| v.as_usize());
+        assert_eq!(files_opened, Some(1));
+        let metrics = metrics.aggregate_by_name();
+        assert_eq!(metrics.output_rows(), Some(8));
+        assert!(metrics.bytes_scanned().unwrap() > 0);
+
         Ok(())
     }
 
     #[tokio::test]
     async fn avro_exec_with_partition() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let testdata = crate::test_util::arrow_test_data();
         let filename = format!("{}/avro/alltypes_plain.avro", testdata);
         let mut partitioned_file = local_unpartitioned_file(filename.clone());
@@ -257,11 +363,14 @@ mod tests {
             statistics: Statistics::default(),
             batch_size: 1024,
             limit: None,
-            table_partition_cols: vec!["date".to_owned()],
+            table_partition_cols: vec![("date".to_owned(), DataType::Utf8)],
         });
         assert_eq!(avro_exec.output_partitioning().partition_count(), 1);
 
-        let mut results = avro_exec.execute(0).await.expect("plan execution failed");
+        let mut results = avro_exec
+            .execute(0, task_ctx)
+            .await
+            .expect("plan execution failed");
         let batch = results
             .next()
             .await
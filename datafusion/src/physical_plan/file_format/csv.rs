@@ -18,21 +18,40 @@
 //! Execution plan for reading CSV files
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::options::{CsvQuoteStyle, CsvWriteOptions};
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::expressions::{format_hex, is_binary, BinaryValues};
+use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
-    LambdaExecPlan,
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    SendableRecordBatchStream, Statistics,
 };
 
+use arrow::array::{as_primitive_array, ArrayRef};
 use arrow::csv;
-use arrow::datatypes::SchemaRef;
+use arrow::datatypes::{
+    DataType, Date32Type, Date64Type, SchemaRef, TimeUnit, TimestampMicrosecondType,
+    TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType,
+};
+use arrow::error::ArrowError;
 use arrow::record_batch::RecordBatch;
+use arrow::util::display::array_value_to_string;
 use std::any::Any;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use futures::StreamExt;
+use tokio::task::{self, JoinHandle};
 
-use super::file_stream::{BatchIter, FileStream};
-use super::PhysicalPlanConfig;
+use super::file_stream::{BatchIter, FileScanMetrics, FileStream, ScanErrorPolicy};
+use super::{fed_partition, PhysicalPlanConfig};
 use serde::{Deserialize, Serialize};
 
 /// Execution plan for scanning a CSV file
@@ -43,6 +62,20 @@ pub struct CsvExec {
     projected_schema: SchemaRef,
     has_header: bool,
     delimiter: u8,
+    /// Number of lines skipped at the start of each file, before the header
+    #[serde(default)]
+    skip_rows: usize,
+    /// What to do when a file cannot be read
+    #[serde(default)]
+    scan_error_policy: ScanErrorPolicy,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+    /// Partitions fed in place of the files, see [`LambdaExecPlan`]
+    #[serde(skip)]
+    fed_partitions: Option<Vec<Vec<RecordBatch>>>,
+    /// Executor decoding the files, bound by the runtime
+    #[serde(skip)]
+    cpu_executor: CpuExecutor,
 }
 
 impl CsvExec {
@@ -56,6 +89,11 @@ impl CsvExec {
             projected_statistics,
             has_header,
             delimiter,
+            skip_rows: 0,
+            scan_error_policy: ScanErrorPolicy::Fail,
+            metrics: ExecutionPlanMetricsSet::new(),
+            fed_partitions: None,
+            cpu_executor: CpuExecutor::default(),
         }
     }
 
@@ -71,12 +109,80 @@ impl CsvExec {
     pub fn delimiter(&self) -> u8 {
         self.delimiter
     }
+
+    /// Skip the first `skip_rows` lines of each file, before its header if
+    /// any, e.g. to ignore a preamble
+    pub fn with_skip_rows(mut self, skip_rows: usize) -> Self {
+        self.skip_rows = skip_rows;
+        self
+    }
+    /// Number of lines skipped at the start of each file
+    pub fn skip_rows(&self) -> usize {
+        self.skip_rows
+    }
+
+    /// Handle the files that cannot be read with `scan_error_policy`, see
+    /// [`ScanErrorPolicy`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
+}
+
+/// A reader skipping the first lines of its input
+pub(crate) struct SkipLines<R> {
+    inner: R,
+    /// The number of lines still to skip
+    remaining: usize,
+}
+
+impl<R: Read> SkipLines<R> {
+    /// Create a reader of `inner` skipping its first `lines` lines
+    pub(crate) fn new(inner: R, lines: usize) -> Self {
+        Self {
+            inner,
+            remaining: lines,
+        }
+    }
+}
+
+impl<R: Read> Read for SkipLines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.remaining > 0 {
+            let n = self.inner.read(buf)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut start = n;
+            for (i, byte) in buf[..n].iter().enumerate() {
+                if *byte == b'\n' {
+                    self.remaining -= 1;
+                    if self.remaining == 0 {
+                        start = i + 1;
+                        break;
+                    }
+                }
+            }
+            // return the bytes following the skipped lines, if any
+            if start < n {
+                buf.copy_within(start..n, 0);
+                return Ok(n - start);
+            }
+        }
+        self.inner.read(buf)
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for CsvExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.fed_partitions = Some(partitions);
+        Ok(())
     }
 }
 
@@ -99,7 +205,11 @@ impl ExecutionPlan for CsvExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        let partition_count = match &self.fed_partitions {
+            Some(partitions) => partitions.len(),
+            None => self.base_config.file_groups.len(),
+        };
+        Partitioning::UnknownPartitioning(partition_count)
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -121,18 +231,45 @@ impl ExecutionPlan for CsvExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let batch_size = self.base_config.batch_size;
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "CsvExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
+        let batch_size = context.batch_size();
         let file_schema = Arc::clone(&self.base_config.file_schema);
         let file_projection = self.base_config.file_column_projection_indices();
         let has_header = self.has_header;
         let delimiter = self.delimiter;
+        let skip_rows = self.skip_rows;
         let start_line = if has_header { 1 } else { 0 };
 
+        // the stream stops opening files once `limit` rows were read
         let fun = move |file, remaining: &Option<usize>| {
             let bounds = remaining.map(|x| (0, x + start_line));
             Box::new(csv::Reader::new(
-                file,
+                SkipLines::new(file, skip_rows),
                 Arc::clone(&file_schema),
                 has_header,
                 Some(delimiter),
@@ -142,14 +279,33 @@ impl ExecutionPlan for CsvExec {
             )) as BatchIter
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        )
+        .with_metrics(FileScanMetrics::new(&self.metrics, partition))
+        .with_scan_error_policy(self.scan_error_policy);
+        let stream =
+            self.cpu_executor
+                .spawn_batches(&self.projected_schema, move |sender| {
+                    for batch in stream {
+                        if !sender.send(batch) {
+                            break;
+                        }
+                    }
+                });
+        let stream = self.base_config.observe(
+            stream,
+            "CsvExec",
+            partition,
+            &self.metrics,
+            &context,
+        );
+        Ok(instrument_stream(self, partition, stream))
     }
 
     fn fmt_as(
@@ -168,17 +324,177 @@ impl ExecutionPlan for CsvExec {
                     self.base_config.limit,
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut exec = self.clone();
+        exec.base_config.bind_runtime(runtime)?;
+        exec.cpu_executor = runtime.cpu_executor.clone();
+        Ok(Some(Arc::new(exec)))
+    }
+
     fn statistics(&self) -> Statistics {
         self.projected_statistics.clone()
     }
 }
 
+/// Executes `plan` and writes each output partition to its own CSV file
+/// (`part-{i}.csv`) inside the directory `path`.
+///
+/// Batches are streamed to the files as they are produced. Returns the paths
+/// of the files that were written.
+pub async fn plan_to_csv(
+    plan: Arc<dyn ExecutionPlan>,
+    path: impl AsRef<str>,
+    options: &CsvWriteOptions,
+    context: Arc<TaskContext>,
+) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let fs_path = Path::new(path);
+    super::create_output_dir(fs_path, options.overwrite)?;
+
+    let mut tasks = vec![];
+    let mut files = vec![];
+    for i in 0..plan.output_partitioning().partition_count() {
+        let filename = format!("part-{}.csv", i);
+        let file_path = fs_path.join(&filename);
+        let file = fs::File::create(&file_path)?;
+        let mut writer = CsvBatchWriter::try_new(file, &plan.schema(), options)?;
+        let mut stream = plan.execute(i, context.clone()).await?;
+        let handle: JoinHandle<Result<()>> = task::spawn(async move {
+            while let Some(batch) = stream.next().await {
+                writer.write(&batch?)?;
+            }
+            writer.flush()
+        });
+        tasks.push(handle);
+        files.push(file_path.to_string_lossy().into_owned());
+    }
+
+    for result in futures::future::join_all(tasks).await {
+        result.map_err(|e| DataFusionError::Execution(e.to_string()))??;
+    }
+    Ok(files)
+}
+
+/// Writes record batches as CSV rows, formatting each value according to
+/// the [`CsvWriteOptions`].
+struct CsvBatchWriter<W: Write> {
+    writer: ::csv::Writer<W>,
+    null_value: String,
+    date_format: Option<String>,
+    timestamp_format: Option<String>,
+}
+
+impl<W: Write> CsvBatchWriter<W> {
+    /// Create a writer, emitting the header row right away if requested so
+    /// that empty partitions still produce a well-formed file
+    fn try_new(inner: W, schema: &SchemaRef, options: &CsvWriteOptions) -> Result<Self> {
+        let quote_style = match options.quote_style {
+            CsvQuoteStyle::Necessary => ::csv::QuoteStyle::Necessary,
+            CsvQuoteStyle::Always => ::csv::QuoteStyle::Always,
+            CsvQuoteStyle::NonNumeric => ::csv::QuoteStyle::NonNumeric,
+            CsvQuoteStyle::Never => ::csv::QuoteStyle::Never,
+        };
+        let mut writer = ::csv::WriterBuilder::new()
+            .delimiter(options.delimiter)
+            .quote_style(quote_style)
+            .from_writer(inner);
+        if options.has_header {
+            writer
+                .write_record(schema.fields().iter().map(|f| f.name()))
+                .map_err(csv_error)?;
+        }
+        Ok(Self {
+            writer,
+            null_value: options.null_value.clone(),
+            date_format: options.date_format.clone(),
+            timestamp_format: options.timestamp_format.clone(),
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        let mut record = Vec::with_capacity(batch.num_columns());
+        for row in 0..batch.num_rows() {
+            record.clear();
+            for column in batch.columns() {
+                record.push(self.format_value(column, row)?);
+            }
+            self.writer.write_record(&record).map_err(csv_error)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    fn format_value(&self, array: &ArrayRef, row: usize) -> Result<String> {
+        if array.is_null(row) {
+            return Ok(self.null_value.clone());
+        }
+        let formatted =
+            match (array.data_type(), &self.date_format, &self.timestamp_format) {
+                (DataType::Date32, Some(format), _) => {
+                    as_primitive_array::<Date32Type>(array.as_ref())
+                        .value_as_date(row)
+                        .map(|v| v.format(format).to_string())
+                }
+                (DataType::Date64, Some(format), _) => {
+                    as_primitive_array::<Date64Type>(array.as_ref())
+                        .value_as_date(row)
+                        .map(|v| v.format(format).to_string())
+                }
+                (DataType::Timestamp(unit, _), _, Some(format)) => match unit {
+                    TimeUnit::Second => {
+                        as_primitive_array::<TimestampSecondType>(array.as_ref())
+                            .value_as_datetime(row)
+                    }
+                    TimeUnit::Millisecond => {
+                        as_primitive_array::<TimestampMillisecondType>(array.as_ref())
+                            .value_as_datetime(row)
+                    }
+                    TimeUnit::Microsecond => {
+                        as_primitive_array::<TimestampMicrosecondType>(array.as_ref())
+                            .value_as_datetime(row)
+                    }
+                    TimeUnit::Nanosecond => {
+                        as_primitive_array::<TimestampNanosecondType>(array.as_ref())
+                            .value_as_datetime(row)
+                    }
+                }
+                .map(|v| v.format(format).to_string()),
+                (data_type, _, _) if is_binary(data_type) => Some(format_hex(
+                    BinaryValues::try_new(array.as_ref())?.value(row),
+                )),
+                _ => None,
+            };
+        match formatted {
+            Some(value) => Ok(value),
+            None => Ok(array_value_to_string(array, row)?),
+        }
+    }
+}
+
+fn csv_error(e: ::csv::Error) -> DataFusionError {
+    DataFusionError::ArrowError(ArrowError::CsvError(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use crate::physical_plan::{collect, common};
     use crate::{
         datasource::object_store::local::{local_unpartitioned_file, LocalFileSystem},
         scalar::ScalarValue,
@@ -188,6 +504,7 @@ mod tests {
 
     #[tokio::test]
     async fn csv_exec_with_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let file_schema = aggr_test_schema();
         let testdata = crate::test_util::arrow_test_data();
         let filename = "aggregate_test_100.csv";
@@ -210,7 +527,7 @@ mod tests {
         assert_eq!(3, csv.projected_schema.fields().len());
         assert_eq!(3, csv.schema().fields().len());
 
-        let mut stream = csv.execute(0).await?;
+        let mut stream = csv.execute(0, task_ctx).await?;
         let batch = stream.next().await.unwrap()?;
         assert_eq!(3, batch.num_columns());
         assert_eq!(100, batch.num_rows());
@@ -232,8 +549,40 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn csv_exec_records_io() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+        let file = local_unpartitioned_file(path);
+        let file_size = file.file_meta.size() as usize;
+        let csv = Arc::new(CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: aggr_test_schema(),
+                file_groups: vec![vec![file.clone()], vec![file]],
+                statistics: Statistics::default(),
+                projection: None,
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            true,
+            b',',
+        ));
+
+        let batches = collect(csv.clone(), task_ctx).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 200);
+
+        let metrics = csv.metrics().unwrap().aggregate_by_name();
+        assert_eq!(metrics.bytes_scanned(), Some(2 * file_size));
+        assert!(metrics.time_fetching().unwrap() > 0);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn csv_exec_with_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let file_schema = aggr_test_schema();
         let testdata = crate::test_util::arrow_test_data();
         let filename = "aggregate_test_100.csv";
@@ -256,7 +605,7 @@ mod tests {
         assert_eq!(13, csv.projected_schema.fields().len());
         assert_eq!(13, csv.schema().fields().len());
 
-        let mut it = csv.execute(0).await?;
+        let mut it = csv.execute(0, task_ctx).await?;
         let batch = it.next().await.unwrap()?;
         assert_eq!(13, batch.num_columns());
         assert_eq!(5, batch.num_rows());
@@ -278,8 +627,68 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn csv_exec_stops_opening_files_at_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+        let csv = Arc::new(CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: aggr_test_schema(),
+                file_groups: vec![vec![local_unpartitioned_file(path); 5]],
+                statistics: Statistics::default(),
+                projection: None,
+                batch_size: 1024,
+                limit: Some(10),
+                table_partition_cols: vec![],
+            },
+            true,
+            b',',
+        ));
+
+        let batches = collect(csv.clone(), task_ctx).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 10);
+
+        // the first file has enough rows
+        let files_opened = csv
+            .metrics()
+            .unwrap()
+            .sum(|m| m.value().name() == "files_opened")
+            .map(|v| v.as_usize());
+        assert_eq!(files_opened, Some(1));
+        Ok(())
+    }
+
+    #[test]
+    fn skip_lines() -> Result<()> {
+        let input = "junk\nmore junk\na,b\n1,2\n";
+        let mut read = String::new();
+        SkipLines::new(input.as_bytes(), 2).read_to_string(&mut read)?;
+        assert_eq!(read, "a,b\n1,2\n");
+
+        // with reads of fewer bytes than a line
+        let mut reader = SkipLines::new(input.as_bytes(), 2);
+        let mut read = vec![];
+        let mut buf = [0u8; 3];
+        loop {
+            match reader.read(&mut buf)? {
+                0 => break,
+                n => read.extend_from_slice(&buf[..n]),
+            }
+        }
+        assert_eq!(read, b"a,b\n1,2\n");
+
+        // with fewer lines than skipped
+        let mut read = String::new();
+        SkipLines::new(input.as_bytes(), 5).read_to_string(&mut read)?;
+        assert_eq!(read, "");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn csv_exec_with_partition() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let file_schema = aggr_test_schema();
         let testdata = crate::test_util::arrow_test_data();
         let filename = "aggregate_test_100.csv";
@@ -300,7 +709,7 @@ mod tests {
                 statistics: Statistics::default(),
                 batch_size: 1024,
                 limit: None,
-                table_partition_cols: vec!["date".to_owned()],
+                table_partition_cols: vec![("date".to_owned(), DataType::Utf8)],
             },
             true,
             b',',
@@ -309,7 +718,7 @@ mod tests {
         assert_eq!(2, csv.projected_schema.fields().len());
         assert_eq!(2, csv.schema().fields().len());
 
-        let mut it = csv.execute(0).await?;
+        let mut it = csv.execute(0, task_ctx).await?;
         let batch = it.next().await.unwrap()?;
         assert_eq!(2, batch.num_columns());
         assert_eq!(100, batch.num_rows());
@@ -329,4 +738,45 @@ mod tests {
         crate::assert_batches_eq!(expected, &[batch.slice(0, 5)]);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn batch_size_of_task_after_serde_round_trip() -> Result<()> {
+        let file_schema = aggr_test_schema();
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+        let csv = CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema,
+                file_groups: vec![vec![local_unpartitioned_file(path)]],
+                statistics: Statistics::default(),
+                projection: Some(vec![0]),
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            true,
+            b',',
+        );
+        let plan: Arc<dyn ExecutionPlan> =
+            Arc::new(CoalesceBatchesExec::new(Arc::new(csv), 4096));
+        let bytes = crate::physical_plan::serde::to_bytes(&plan)?;
+        let plan = crate::physical_plan::serde::from_bytes(&bytes)?;
+
+        // the process executing the plan reads batches of 7 rows
+        let task_ctx =
+            ExecutionContext::with_config(ExecutionConfig::new().with_batch_size(7))
+                .task_ctx();
+        let coalesce = plan.as_any().downcast_ref::<CoalesceBatchesExec>().unwrap();
+        let stream = coalesce.input().execute(0, task_ctx.clone()).await?;
+        let batches = common::collect(stream).await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+        assert_eq!(num_rows, [vec![7; 14], vec![2]].concat());
+
+        // and does not coalesce them beyond that size
+        let batches = collect(plan, task_ctx).await?;
+        let num_rows = batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>();
+        assert_eq!(num_rows, [vec![7; 14], vec![2]].concat());
+        Ok(())
+    }
 }
@@ -22,21 +22,34 @@
 //! compliant with the `SendableRecordBatchStream` trait.
 
 use crate::{
-    datasource::{object_store::ObjectStore, PartitionedFile},
-    physical_plan::RecordBatchStream,
+    datasource::{
+        object_store::{ObjectStore, SizedFile},
+        PartitionedFile,
+    },
+    error::{DataFusionError, Result},
+    physical_plan::{
+        metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, Time},
+        RecordBatchStream,
+    },
     scalar::ScalarValue,
 };
 use arrow::{
-    datatypes::SchemaRef,
+    datatypes::{DataType, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
 use futures::Stream;
+use log::warn;
+use serde::{Deserialize, Serialize};
 use std::{
-    io::Read,
+    fmt::Display,
+    io::{self, Read},
     iter,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 
@@ -60,6 +73,155 @@ impl<T> FormatReaderOpener for T where
 {
 }
 
+/// What a file scan does when a file cannot be read
+///
+/// Whatever the policy, the scan fails on the errors raised by a file whose
+/// schema does not match the one of the scan, as skipping them could silently
+/// return wrong results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScanErrorPolicy {
+    /// Fail the scan with the error
+    Fail,
+    /// Skip the rest of the file, keeping the batches read before the error
+    SkipFile,
+    /// Skip the batch that could not be read and go on with the file. The rest
+    /// of the file is skipped if the reader cannot move past the error.
+    SkipBatch,
+}
+
+impl Default for ScanErrorPolicy {
+    fn default() -> Self {
+        Self::Fail
+    }
+}
+
+/// The byte offset reached by the reader of a file, locating its errors
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FileOffset(Arc<AtomicU64>);
+
+impl FileOffset {
+    pub(crate) fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set(&self, offset: u64) {
+        self.0.store(offset, Ordering::Relaxed)
+    }
+
+    fn advance(&self, bytes: usize) {
+        self.0.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+/// The message of `error`, raised reading the file `path` at byte `offset`
+pub(crate) fn file_error_message(path: &str, offset: u64, error: impl Display) -> String {
+    format!(
+        "Error reading file {} at byte offset {}: {}",
+        path, offset, error
+    )
+}
+
+/// True if `error` is raised by a file whose schema does not match the one of
+/// the scan
+pub(crate) fn is_schema_error(error: &ArrowError) -> bool {
+    matches!(error, ArrowError::SchemaError(_))
+}
+
+/// IO metrics of a partition of a file scan
+#[derive(Debug, Clone)]
+pub struct FileScanMetrics {
+    /// Number of bytes read from the files
+    bytes_scanned: Count,
+    /// Time spent opening the files and reading their bytes
+    time_fetching: Time,
+    /// Number of files opened
+    files_opened: Count,
+    /// Number of files whose rest was skipped after an error
+    files_skipped: Count,
+    /// Number of batches skipped after an error
+    batches_skipped: Count,
+}
+
+impl FileScanMetrics {
+    /// Create the metrics of partition `partition` of a scan in `metrics`
+    pub fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            bytes_scanned: MetricBuilder::new(metrics).bytes_scanned(partition),
+            time_fetching: MetricBuilder::new(metrics).time_fetching(partition),
+            files_opened: MetricBuilder::new(metrics).counter("files_opened", partition),
+            files_skipped: MetricBuilder::new(metrics)
+                .counter("files_skipped", partition),
+            batches_skipped: MetricBuilder::new(metrics)
+                .counter("batches_skipped", partition),
+        }
+    }
+
+    /// Record that the rest of a file was skipped after an error
+    pub fn skip_file(&self) {
+        self.files_skipped.add(1);
+    }
+
+    /// Record that a batch was skipped after an error
+    pub fn skip_batch(&self) {
+        self.batches_skipped.add(1);
+    }
+
+    /// Run `open`, recording the time it takes to open a file, and that the
+    /// file was opened
+    pub fn open_file<T>(&self, open: impl FnOnce() -> T) -> T {
+        self.files_opened.add(1);
+        self.time_open(open)
+    }
+
+    /// Run `open`, recording the time it takes to open a file
+    pub fn time_open<T>(&self, open: impl FnOnce() -> T) -> T {
+        let _timer = self.time_fetching.timer();
+        open()
+    }
+
+    /// Wrap `reader`, recording the bytes read from it and the time spent
+    /// reading them
+    pub fn reader(
+        &self,
+        reader: Box<dyn Read + Send + Sync>,
+    ) -> Box<dyn Read + Send + Sync> {
+        Box::new(MeteredReader {
+            inner: reader,
+            metrics: self.clone(),
+        })
+    }
+}
+
+/// A reader recording its IO in [`FileScanMetrics`]
+struct MeteredReader {
+    inner: Box<dyn Read + Send + Sync>,
+    metrics: FileScanMetrics,
+}
+
+impl Read for MeteredReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timer = self.metrics.time_fetching.timer();
+        let n = self.inner.read(buf)?;
+        timer.done();
+        self.metrics.bytes_scanned.add(n);
+        Ok(n)
+    }
+}
+
+/// A reader recording the byte offset it reached in a [`FileOffset`]
+struct OffsetReader {
+    inner: Box<dyn Read + Send + Sync>,
+    offset: FileOffset,
+}
+
+impl Read for OffsetReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.offset.advance(n);
+        Ok(n)
+    }
+}
+
 /// A stream that iterates record batch by record batch, file over file.
 pub struct FileStream<F: FormatReaderOpener> {
     /// An iterator over record batches of the last file returned by file_iter
@@ -82,6 +244,16 @@ pub struct FileStream<F: FormatReaderOpener> {
     pc_projector: PartitionColumnProjector,
     /// the store from which to source the files.
     object_store: Arc<dyn ObjectStore>,
+    /// IO metrics of the scan, if recorded
+    metrics: Option<FileScanMetrics>,
+    /// What to do when a file cannot be read
+    scan_error_policy: ScanErrorPolicy,
+    /// The path of the file read by batch_iter
+    path: String,
+    /// The byte offset reached in the file read by batch_iter
+    offset: FileOffset,
+    /// The offset of the last batch of the file skipped after an error
+    skipped_at: Option<u64>,
 }
 
 impl<F: FormatReaderOpener> FileStream<F> {
@@ -91,7 +263,7 @@ impl<F: FormatReaderOpener> FileStream<F> {
         file_reader: F,
         projected_schema: SchemaRef,
         limit: Option<usize>,
-        table_partition_cols: Vec<String>,
+        table_partition_cols: Vec<(String, DataType)>,
     ) -> Self {
         let pc_projector = PartitionColumnProjector::new(
             Arc::clone(&projected_schema),
@@ -107,51 +279,119 @@ impl<F: FormatReaderOpener> FileStream<F> {
             file_reader,
             pc_projector,
             object_store,
+            metrics: None,
+            scan_error_policy: ScanErrorPolicy::Fail,
+            path: String::new(),
+            offset: FileOffset::default(),
+            skipped_at: None,
+        }
+    }
+
+    /// Record the IO of the stream in `metrics`
+    pub fn with_metrics(mut self, metrics: FileScanMetrics) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Handle the files that cannot be read with `scan_error_policy`
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// Open a reader of `file`, metered if the stream records its IO
+    fn open_file(&self, file: SizedFile) -> Result<Box<dyn Read + Send + Sync>> {
+        let open = || {
+            self.object_store
+                .file_reader(file)
+                .and_then(|r| r.sync_reader())
+        };
+        let reader = match &self.metrics {
+            Some(metrics) => metrics.open_file(open).map(|r| metrics.reader(r))?,
+            None => open()?,
+        };
+        Ok(Box::new(OffsetReader {
+            inner: reader,
+            offset: self.offset.clone(),
+        }))
+    }
+
+    /// `error` raised by the current file, located in the file
+    fn file_error(&self, error: impl Display) -> ArrowError {
+        let message = file_error_message(&self.path, self.offset.get(), error);
+        ArrowError::ExternalError(Box::new(DataFusionError::Execution(message)))
+    }
+
+    /// Skip the rest of the current file
+    fn skip_file(&mut self, error: ArrowError) {
+        warn!("Skipping the rest of the file after an error: {}", error);
+        self.batch_iter = Box::new(iter::empty());
+        if let Some(metrics) = &self.metrics {
+            metrics.skip_file();
         }
     }
 
     /// Acts as a flat_map of record batches over files. Adds the partitioning
     /// Columns to the returned record batches.
     fn next_batch(&mut self) -> Option<ArrowResult<RecordBatch>> {
-        match self.batch_iter.next() {
-            Some(Ok(batch)) => {
-                Some(self.pc_projector.project(batch, &self.partition_values))
-            }
-            Some(Err(e)) => Some(Err(e)),
-            None => match self.file_iter.next() {
-                Some(f) => {
-                    self.partition_values = f.partition_values;
-                    self.object_store
-                        .file_reader(f.file_meta.sized_file)
-                        .and_then(|r| r.sync_reader())
-                        .map_err(|e| ArrowError::ExternalError(Box::new(e)))
-                        .and_then(|f| {
-                            self.batch_iter = (self.file_reader)(f, &self.remain);
-                            self.next_batch().transpose()
-                        })
-                        .transpose()
+        loop {
+            match self.batch_iter.next() {
+                Some(Ok(batch)) => {
+                    return Some(self.pc_projector.project(batch, &self.partition_values))
                 }
-                None => None,
-            },
+                Some(Err(e)) => {
+                    let offset = self.offset.get();
+                    let error = self.file_error(&e);
+                    match self.scan_error_policy {
+                        _ if is_schema_error(&e) => return Some(Err(error)),
+                        ScanErrorPolicy::Fail => return Some(Err(error)),
+                        // the reader moved past the previous error
+                        ScanErrorPolicy::SkipBatch if self.skipped_at != Some(offset) => {
+                            warn!("Skipping a batch after an error: {}", error);
+                            self.skipped_at = Some(offset);
+                            if let Some(metrics) = &self.metrics {
+                                metrics.skip_batch();
+                            }
+                        }
+                        _ => self.skip_file(error),
+                    }
+                }
+                None => {
+                    let file = self.file_iter.next()?;
+                    self.partition_values = file.partition_values;
+                    self.path = file.file_meta.path().to_owned();
+                    self.offset = FileOffset::default();
+                    self.skipped_at = None;
+                    match self.open_file(file.file_meta.sized_file) {
+                        Ok(reader) => {
+                            self.batch_iter = (self.file_reader)(reader, &self.remain)
+                        }
+                        Err(e) => {
+                            let error = self.file_error(e);
+                            if self.scan_error_policy == ScanErrorPolicy::Fail {
+                                return Some(Err(error));
+                            }
+                            self.skip_file(error);
+                        }
+                    }
+                }
+            }
         }
     }
 }
 
-impl<F: FormatReaderOpener> Stream for FileStream<F> {
+impl<F: FormatReaderOpener> Iterator for FileStream<F> {
     type Item = ArrowResult<RecordBatch>;
 
-    fn poll_next(
-        mut self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
-    ) -> Poll<Option<Self::Item>> {
+    fn next(&mut self) -> Option<Self::Item> {
         // check if finished or no limit
         match self.remain {
-            Some(r) if r == 0 => return Poll::Ready(None),
-            None => return Poll::Ready(self.get_mut().next_batch()),
+            Some(r) if r == 0 => return None,
+            None => return self.next_batch(),
             Some(r) => r,
         };
 
-        Poll::Ready(match self.as_mut().next_batch() {
+        match self.next_batch() {
             Some(Ok(item)) => {
                 if let Some(remain) = self.remain.as_mut() {
                     if *remain >= item.num_rows() {
@@ -160,20 +400,31 @@ impl<F: FormatReaderOpener> Stream for FileStream<F> {
                     } else {
                         let len = *remain;
                         *remain = 0;
-                        Some(Ok(RecordBatch::try_new(
+                        Some(RecordBatch::try_new(
                             item.schema(),
                             item.columns()
                                 .iter()
                                 .map(|column| column.slice(0, len))
                                 .collect(),
-                        )?))
+                        ))
                     }
                 } else {
                     Some(Ok(item))
                 }
             }
             other => other,
-        })
+        }
+    }
+}
+
+impl<F: FormatReaderOpener> Stream for FileStream<F> {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.get_mut().next())
     }
 }
 
@@ -285,4 +536,66 @@ mod tests {
 
         Ok(())
     }
+
+    /// Collect a stream of 2 files whose reader fails between a batch [0,1,2]
+    /// and a batch [0,1], with the number of files and batches skipped
+    async fn collect_failing(
+        scan_error_policy: ScanErrorPolicy,
+    ) -> (ArrowResult<Vec<RecordBatch>>, usize, usize) {
+        let records = vec![make_partition(3), make_partition(2)];
+        let source_schema = records[0].schema();
+
+        let reader = move |_file, _remain: &Option<usize>| {
+            let error = ArrowError::ParseError("invalid record".to_owned());
+            let batches =
+                vec![Ok(records[0].clone()), Err(error), Ok(records[1].clone())];
+            Box::new(batches.into_iter()) as BatchIter
+        };
+
+        let metrics = ExecutionPlanMetricsSet::new();
+        let file_stream = FileStream::new(
+            TestObjectStore::new_arc(&[("mock_file1", 10), ("mock_file2", 20)]),
+            vec![
+                PartitionedFile::new("mock_file1".to_owned(), 10),
+                PartitionedFile::new("mock_file2".to_owned(), 20),
+            ],
+            reader,
+            source_schema,
+            None,
+            vec![],
+        )
+        .with_metrics(FileScanMetrics::new(&metrics, 0))
+        .with_scan_error_policy(scan_error_policy);
+
+        let batches = file_stream.collect::<Vec<_>>().await.into_iter().collect();
+        let metrics = metrics.clone_inner();
+        let count = |name: &str| {
+            metrics
+                .sum(|m| m.value().name() == name)
+                .map_or(0, |v| v.as_usize())
+        };
+        (batches, count("files_skipped"), count("batches_skipped"))
+    }
+
+    #[tokio::test]
+    async fn scan_error_policies() -> Result<()> {
+        let (batches, _, _) = collect_failing(ScanErrorPolicy::Fail).await;
+        let err = batches.unwrap_err().to_string();
+        assert!(
+            err.contains("Error reading file mock_file1 at byte offset 0: Parser error: invalid record"),
+            "{}",
+            err
+        );
+
+        let (batches, files_skipped, batches_skipped) =
+            collect_failing(ScanErrorPolicy::SkipFile).await;
+        let rows = batches?.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!((rows, files_skipped, batches_skipped), (6, 2, 0));
+
+        let (batches, files_skipped, batches_skipped) =
+            collect_failing(ScanErrorPolicy::SkipBatch).await;
+        let rows = batches?.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!((rows, files_skipped, batches_skipped), (10, 0, 2));
+        Ok(())
+    }
 }
@@ -0,0 +1,712 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Execution plan for reading and writing Arrow IPC (Feather v2) files
+use async_trait::async_trait;
+
+use crate::datasource::object_store::{ObjectReader, ObjectStore};
+use crate::datasource::PartitionedFile;
+use crate::error::{DataFusionError, Result};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::trace::instrument_stream;
+use crate::physical_plan::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
+};
+use crate::scalar::ScalarValue;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::ipc::reader::FileReader;
+use arrow::ipc::writer::{FileWriter, IpcWriteOptions};
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt};
+use std::any::Any;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::iter;
+use std::ops::Range;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::task::{self, JoinHandle};
+
+use super::file_stream::{file_error_message, BatchIter, FileOffset};
+use super::{fed_partition, PartitionColumnProjector, PhysicalPlanConfig};
+use serde::{Deserialize, Serialize};
+
+/// Record batch blocks of an IPC file scanned by a partition of an [`IpcExec`]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IpcFileSlice {
+    /// The scanned file
+    pub file: PartitionedFile,
+    /// Indices of the record batch blocks listed in the footer of the file
+    /// to read, all of them if `None`
+    pub blocks: Option<Range<usize>>,
+}
+
+/// Execution plan for scanning Arrow IPC files
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct IpcExec {
+    base_config: PhysicalPlanConfig,
+    projected_statistics: Statistics,
+    projected_schema: SchemaRef,
+    /// Slices of files scanned by each partition, in place of the file
+    /// groups of `base_config` once the large files are split by blocks
+    #[serde(default)]
+    block_partitions: Option<Vec<Vec<IpcFileSlice>>>,
+    /// Partitions fed in place of the files, see [`LambdaExecPlan`]
+    #[serde(skip)]
+    fed_partitions: Option<Vec<Vec<RecordBatch>>>,
+}
+
+impl IpcExec {
+    /// Create a new IPC reader execution plan provided base configurations
+    pub fn new(base_config: PhysicalPlanConfig) -> Self {
+        let (projected_schema, projected_statistics) = base_config.project();
+
+        Self {
+            base_config,
+            projected_schema,
+            projected_statistics,
+            block_partitions: None,
+            fed_partitions: None,
+        }
+    }
+
+    /// Ref to the base configs
+    pub fn base_config(&self) -> &PhysicalPlanConfig {
+        &self.base_config
+    }
+
+    /// Scan the files of more than `partition_size` bytes in several
+    /// partitions, each reading a range of the record batch blocks listed in
+    /// the footer of the file. A file is split into as many partitions of
+    /// about `partition_size` bytes as it has blocks at most, the smaller
+    /// files are scanned by the partitions of their groups. The footers of
+    /// the large files are read on blocking threads.
+    pub async fn with_block_partitions(mut self, partition_size: u64) -> Result<Self> {
+        let partition_size = partition_size.max(1);
+        let mut partitions = vec![];
+        for group in &self.base_config.file_groups {
+            let mut whole_files = vec![];
+            for file in group {
+                let size = file.file_meta.size();
+                let num_blocks = if size > partition_size {
+                    let object_store = Arc::clone(&self.base_config.object_store);
+                    count_blocks(object_store, file.clone()).await?
+                } else {
+                    0
+                };
+                let num_parts =
+                    ((size + partition_size - 1) / partition_size).min(num_blocks as u64);
+                if num_parts <= 1 {
+                    whole_files.push(IpcFileSlice {
+                        file: file.clone(),
+                        blocks: None,
+                    });
+                    continue;
+                }
+                let num_parts = num_parts as usize;
+                partitions.extend((0..num_parts).map(|i| {
+                    vec![IpcFileSlice {
+                        file: file.clone(),
+                        blocks: Some(
+                            i * num_blocks / num_parts..(i + 1) * num_blocks / num_parts,
+                        ),
+                    }]
+                }));
+            }
+            if !whole_files.is_empty() {
+                partitions.push(whole_files);
+            }
+        }
+        self.block_partitions = Some(partitions);
+        Ok(self)
+    }
+
+    /// Slices of files scanned by partition `partition`, if any
+    fn partition_slices(&self, partition: usize) -> Option<Vec<IpcFileSlice>> {
+        match &self.block_partitions {
+            Some(partitions) => partitions.get(partition).cloned(),
+            None => self.base_config.file_groups.get(partition).map(|files| {
+                files
+                    .iter()
+                    .map(|file| IpcFileSlice {
+                        file: file.clone(),
+                        blocks: None,
+                    })
+                    .collect()
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for IpcExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.fed_partitions = Some(partitions);
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "ipc_exec")]
+impl ExecutionPlan for IpcExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        let partition_count = match (&self.fed_partitions, &self.block_partitions) {
+            (Some(partitions), _) => partitions.len(),
+            (None, Some(partitions)) => partitions.len(),
+            (None, None) => self.base_config.file_groups.len(),
+        };
+        Partitioning::UnknownPartitioning(partition_count)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        Vec::new()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.is_empty() {
+            Ok(Arc::new(self.clone()))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "Children cannot be replaced in {:?}",
+                self
+            )))
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "IpcExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
+        let slices = self.partition_slices(partition).ok_or_else(|| {
+            DataFusionError::Internal(format!("IpcExec invalid partition {}", partition))
+        })?;
+        let file_projection = self.base_config.file_column_projection_indices();
+        let projected_file_schema = file_projection.as_ref().map(|proj| {
+            let file_schema = &self.base_config.file_schema;
+            Arc::new(Schema::new(
+                proj.iter().map(|i| file_schema.field(*i).clone()).collect(),
+            ))
+        });
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(IpcStream {
+                object_store: Arc::clone(&self.base_config.object_store),
+                slices: slices.into_iter(),
+                batches: Box::new(iter::empty()),
+                partition_values: vec![],
+                path: String::new(),
+                offset: FileOffset::default(),
+                file_projection,
+                projected_file_schema,
+                pc_projector: PartitionColumnProjector::new(
+                    Arc::clone(&self.projected_schema),
+                    &self.base_config.table_partition_cols,
+                ),
+                remain: self.base_config.limit,
+                schema: Arc::clone(&self.projected_schema),
+            }),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "IpcExec: limit={:?}, files={}",
+                    self.base_config.limit,
+                    super::FileGroupsDisplay(&self.base_config.file_groups),
+                )?;
+                if let Some(partitions) = &self.block_partitions {
+                    write!(f, ", block_partitions={}", partitions.len())?;
+                }
+                Ok(())
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut exec = self.clone();
+        exec.base_config.bind_runtime(runtime)?;
+        Ok(Some(Arc::new(exec)))
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.projected_statistics.clone()
+    }
+}
+
+/// Number of record batch blocks listed in the footer of `file`, read on a
+/// blocking thread as the object store readers are synchronous
+async fn count_blocks(
+    object_store: Arc<dyn ObjectStore>,
+    file: PartitionedFile,
+) -> Result<usize> {
+    task::spawn_blocking(move || -> Result<usize> {
+        let reader = object_store.file_reader(file.file_meta.sized_file)?;
+        Ok(open_ipc_file(reader, FileOffset::default())?.num_batches())
+    })
+    .await
+    .map_err(|e| DataFusionError::Execution(e.to_string()))?
+}
+
+/// Stream of the record batches of the slices of IPC files scanned by a
+/// partition of an [`IpcExec`]
+struct IpcStream {
+    object_store: Arc<dyn ObjectStore>,
+    slices: std::vec::IntoIter<IpcFileSlice>,
+    /// The record batches of the current slice
+    batches: BatchIter,
+    /// Values of the partition columns of the current slice
+    partition_values: Vec<ScalarValue>,
+    /// The path of the file of the current slice
+    path: String,
+    /// The byte offset reached in the file of the current slice
+    offset: FileOffset,
+    file_projection: Option<Vec<usize>>,
+    projected_file_schema: Option<SchemaRef>,
+    pc_projector: PartitionColumnProjector,
+    /// The remaining number of records to read, None if no limit
+    remain: Option<usize>,
+    schema: SchemaRef,
+}
+
+impl IpcStream {
+    /// Open a reader of the blocks of `slice`
+    fn open_slice(&self, slice: &IpcFileSlice) -> ArrowResult<BatchIter> {
+        let reader = self
+            .object_store
+            .file_reader(slice.file.file_meta.sized_file.clone())
+            .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+        let mut reader = open_ipc_file(reader, self.offset.clone())?;
+        let blocks = match &slice.blocks {
+            Some(blocks) => {
+                reader.set_index(blocks.start)?;
+                blocks.len()
+            }
+            None => reader.num_batches(),
+        };
+        let projection = self.file_projection.clone();
+        let projected_schema = self.projected_file_schema.clone();
+        Ok(Box::new(reader.take(blocks).map(move |batch| {
+            let batch = batch?;
+            match (&projection, &projected_schema) {
+                (Some(proj), Some(schema)) => RecordBatch::try_new(
+                    schema.clone(),
+                    proj.iter().map(|i| batch.column(*i).clone()).collect(),
+                ),
+                _ => Ok(batch),
+            }
+        })))
+    }
+
+    /// The record batch following the ones read, with the partition columns
+    fn next_batch(&mut self) -> Option<ArrowResult<RecordBatch>> {
+        loop {
+            match self.batches.next() {
+                Some(Ok(batch)) => {
+                    return Some(self.pc_projector.project(batch, &self.partition_values))
+                }
+                Some(Err(e)) => {
+                    let message = file_error_message(&self.path, self.offset.get(), e);
+                    return Some(Err(ArrowError::ExternalError(Box::new(
+                        DataFusionError::Execution(message),
+                    ))));
+                }
+                None => {
+                    let slice = self.slices.next()?;
+                    self.path = slice.file.file_meta.path().to_owned();
+                    self.offset = FileOffset::default();
+                    self.batches = match self.open_slice(&slice) {
+                        Ok(batches) => batches,
+                        Err(e) => Box::new(iter::once(Err(e))),
+                    };
+                    self.partition_values = slice.file.partition_values;
+                }
+            }
+        }
+    }
+}
+
+impl Stream for IpcStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        // the IPC reader cannot limit the number of records, the last batch
+        // is truncated instead
+        let batch = match self.remain {
+            Some(0) => None,
+            _ => self.next_batch(),
+        };
+        let batch = match (batch, self.remain.as_mut()) {
+            (Some(Ok(batch)), Some(remain)) => {
+                let num_rows = batch.num_rows().min(*remain);
+                *remain -= num_rows;
+                Some(Ok(batch.slice(0, num_rows)))
+            }
+            (batch, _) => batch,
+        };
+        Poll::Ready(batch)
+    }
+}
+
+impl RecordBatchStream for IpcStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// Random access to the content of an [`ObjectReader`]. After a seek, the
+/// file is read from the new position to its end on demand.
+pub(crate) struct ObjectReaderCursor {
+    inner: Arc<dyn ObjectReader>,
+    position: u64,
+    /// The reader of the file from `position`, opened by the first read
+    /// after a seek
+    reader: Option<Box<dyn Read + Send + Sync>>,
+    /// The byte offset reached in the file
+    offset: FileOffset,
+}
+
+impl Read for ObjectReaderCursor {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.reader.is_none() {
+            let length = self.inner.length().saturating_sub(self.position);
+            let reader = self
+                .inner
+                .sync_chunk_reader(self.position, length as usize)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            self.reader = Some(reader);
+        }
+        let n = self.reader.as_mut().unwrap().read(buf)?;
+        self.position += n as u64;
+        self.offset.set(self.position);
+        Ok(n)
+    }
+}
+
+impl Seek for ObjectReaderCursor {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => offset_position(self.inner.length(), offset),
+            SeekFrom::Current(offset) => offset_position(self.position, offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )
+        })?;
+        if position != self.position {
+            self.reader = None;
+            self.position = position;
+            self.offset.set(position);
+        }
+        Ok(position)
+    }
+}
+
+/// `base` moved by `offset` bytes, if not negative
+fn offset_position(base: u64, offset: i64) -> Option<u64> {
+    if offset < 0 {
+        base.checked_sub(offset.unsigned_abs())
+    } else {
+        base.checked_add(offset as u64)
+    }
+}
+
+/// Open an IPC file reader on the content of `file`, recording the byte
+/// offset reached in `offset`.
+///
+/// The IPC file format keeps its schema and record batch offsets in a footer,
+/// which is read first. The dictionaries and record batches are then read
+/// from the ranges of the file listed in the footer, without buffering the
+/// rest of the file.
+pub(crate) fn open_ipc_file(
+    file: Arc<dyn ObjectReader>,
+    offset: FileOffset,
+) -> ArrowResult<FileReader<ObjectReaderCursor>> {
+    FileReader::try_new(ObjectReaderCursor {
+        inner: file,
+        position: 0,
+        reader: None,
+        offset,
+    })
+}
+
+/// Executes `plan` and writes each output partition to its own Arrow IPC file
+/// (`part-{i}.arrow`) inside the directory `path`, which must not exist yet.
+///
+/// Returns the paths of the files that were written.
+pub async fn plan_to_ipc(
+    plan: Arc<dyn ExecutionPlan>,
+    path: impl AsRef<str>,
+    write_options: Option<IpcWriteOptions>,
+    context: Arc<TaskContext>,
+) -> Result<Vec<String>> {
+    let path = path.as_ref();
+    let fs_path = Path::new(path);
+    super::create_output_dir(fs_path, false)?;
+
+    let write_options = write_options.unwrap_or_default();
+    let mut tasks = vec![];
+    let mut files = vec![];
+    for i in 0..plan.output_partitioning().partition_count() {
+        let filename = format!("part-{}.arrow", i);
+        let file_path = fs_path.join(&filename);
+        let file = fs::File::create(&file_path)?;
+        let mut writer = FileWriter::try_new_with_options(
+            file,
+            plan.schema().as_ref(),
+            write_options.clone(),
+        )?;
+        let mut stream = plan.execute(i, context.clone()).await?;
+        let handle: JoinHandle<Result<()>> = task::spawn(async move {
+            while let Some(batch) = stream.next().await {
+                writer.write(&batch?)?;
+            }
+            writer.finish().map_err(DataFusionError::from)
+        });
+        tasks.push(handle);
+        files.push(file_path.to_string_lossy().into_owned());
+    }
+
+    for result in futures::future::join_all(tasks).await {
+        result.map_err(|e| DataFusionError::Execution(e.to_string()))??;
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::object_store::local::{
+        local_unpartitioned_file, LocalFileSystem,
+    };
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::{DictionaryArray, Int32Array};
+    use arrow::datatypes::{DataType, Field, Int8Type};
+    use arrow::util::pretty::pretty_format_batches;
+    use tempfile::TempDir;
+
+    fn test_batches() -> Result<(SchemaRef, Vec<RecordBatch>)> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new(
+                "b",
+                DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8)),
+                true,
+            ),
+        ]));
+        let batch1 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+                Arc::new(
+                    vec![Some("x"), Some("y"), Some("x")]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int8Type>>(),
+                ),
+            ],
+        )?;
+        let batch2 = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(4), Some(5)])),
+                Arc::new(
+                    vec![None, Some("z")]
+                        .into_iter()
+                        .collect::<DictionaryArray<Int8Type>>(),
+                ),
+            ],
+        )?;
+        Ok((schema, vec![batch1, batch2]))
+    }
+
+    fn ipc_exec(
+        path: String,
+        schema: SchemaRef,
+        projection: Option<Vec<usize>>,
+        limit: Option<usize>,
+    ) -> IpcExec {
+        IpcExec::new(PhysicalPlanConfig {
+            object_store: Arc::new(LocalFileSystem {}),
+            file_groups: vec![vec![local_unpartitioned_file(path)]],
+            file_schema: schema,
+            statistics: Statistics::default(),
+            projection,
+            batch_size: 1024,
+            limit,
+            table_partition_cols: vec![],
+        })
+    }
+
+    #[tokio::test]
+    async fn ipc_write_then_read() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let (schema, batches) = test_batches()?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[batches.clone()],
+            schema.clone(),
+            None,
+        )?);
+
+        let files = plan_to_ipc(input, &out_dir, None, task_ctx.clone()).await?;
+        assert_eq!(files, vec![format!("{}/part-0.arrow", out_dir)]);
+
+        // the schema is read back from the file footer
+        let file = local_unpartitioned_file(files[0].clone())
+            .file_meta
+            .sized_file;
+        let reader =
+            open_ipc_file(LocalFileSystem {}.file_reader(file)?, FileOffset::default())?;
+        assert_eq!(reader.schema(), schema);
+
+        let exec = ipc_exec(files[0].clone(), schema, None, None);
+        let read = collect(Arc::new(exec), task_ctx).await?;
+        assert_eq!(read.len(), 2);
+        assert_eq!(
+            pretty_format_batches(&read)?,
+            pretty_format_batches(&batches)?
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ipc_exec_with_projection_and_limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let (schema, batches) = test_batches()?;
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+        let files = plan_to_ipc(input, &out_dir, None, task_ctx.clone()).await?;
+
+        let exec = ipc_exec(files[0].clone(), schema, Some(vec![1]), Some(4));
+        assert_eq!(exec.schema().fields().len(), 1);
+
+        let read = collect(Arc::new(exec), task_ctx).await?;
+        let expected = vec![
+            "+---+", "| b |", "+---+", "| x |", "| y |", "| x |", "|   |", "+---+",
+        ];
+        crate::assert_batches_eq!(expected, &read);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ipc_exec_block_partitions() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let tmp_dir = TempDir::new()?;
+        let out_dir = tmp_dir.as_ref().to_str().unwrap().to_string() + "/out";
+        let (schema, batches) = test_batches()?;
+        // the batches of "b" have different dictionaries, only "a" is written
+        let input = Arc::new(MemoryExec::try_new(&[batches], schema, Some(vec![0]))?);
+        let schema = input.schema();
+        let batches = collect(input.clone(), task_ctx.clone()).await?;
+        let files = plan_to_ipc(input, &out_dir, None, task_ctx.clone()).await?;
+
+        // a file smaller than the partition size is scanned as a whole
+        let exec = ipc_exec(files[0].clone(), schema.clone(), None, None)
+            .with_block_partitions(u64::MAX)
+            .await?;
+        assert_eq!(exec.output_partitioning().partition_count(), 1);
+
+        // the two blocks of the file are scanned by their own partitions
+        let exec = ipc_exec(files[0].clone(), schema, None, None)
+            .with_block_partitions(1)
+            .await?;
+        assert_eq!(exec.output_partitioning().partition_count(), 2);
+        let exec: Arc<dyn ExecutionPlan> = Arc::new(exec);
+        for (partition, batch) in batches.iter().enumerate() {
+            let stream = exec.execute(partition, task_ctx.clone()).await?;
+            let read = crate::physical_plan::common::collect(stream).await?;
+            assert_eq!(
+                pretty_format_batches(&read)?,
+                pretty_format_batches(&[batch.clone()])?
+            );
+        }
+
+        // the partitions survive the serialization of the plan
+        let json = serde_json::to_string(&exec).unwrap();
+        let exec: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+        assert_eq!(exec.output_partitioning().partition_count(), 2);
+
+        Ok(())
+    }
+}
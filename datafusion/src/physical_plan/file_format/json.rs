@@ -19,17 +19,23 @@
 use async_trait::async_trait;
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::metrics::{ExecutionPlanMetricsSet, MetricsSet};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream, Statistics,
-    LambdaExecPlan,
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    SendableRecordBatchStream, Statistics,
 };
 use arrow::{datatypes::SchemaRef, json};
 use arrow::record_batch::RecordBatch;
 use std::any::Any;
 use std::sync::Arc;
 
-use super::file_stream::{BatchIter, FileStream};
-use super::PhysicalPlanConfig;
+use super::file_stream::{BatchIter, FileScanMetrics, FileStream, ScanErrorPolicy};
+use super::{fed_partition, PhysicalPlanConfig};
 use serde::{Deserialize, Serialize};
 
 /// Execution plan for scanning NdJson data source
@@ -38,6 +44,17 @@ pub struct NdJsonExec {
     base_config: PhysicalPlanConfig,
     projected_statistics: Statistics,
     projected_schema: SchemaRef,
+    /// What to do when a file cannot be read
+    #[serde(default)]
+    scan_error_policy: ScanErrorPolicy,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+    /// Partitions fed in place of the files, see [`LambdaExecPlan`]
+    #[serde(skip)]
+    fed_partitions: Option<Vec<Vec<RecordBatch>>>,
+    /// Executor decoding the files, bound by the runtime
+    #[serde(skip)]
+    cpu_executor: CpuExecutor,
 }
 
 impl NdJsonExec {
@@ -49,14 +66,36 @@ impl NdJsonExec {
             base_config,
             projected_schema,
             projected_statistics,
+            scan_error_policy: ScanErrorPolicy::Fail,
+            metrics: ExecutionPlanMetricsSet::new(),
+            fed_partitions: None,
+            cpu_executor: CpuExecutor::default(),
         }
     }
+
+    /// Ref to the base configs
+    pub fn base_config(&self) -> &PhysicalPlanConfig {
+        &self.base_config
+    }
+
+    /// Handle the files that cannot be read with `scan_error_policy`, see
+    /// [`ScanErrorPolicy`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for NdJsonExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.fed_partitions = Some(partitions);
+        Ok(())
     }
 }
 
@@ -76,7 +115,11 @@ impl ExecutionPlan for NdJsonExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        let partition_count = match &self.fed_partitions {
+            Some(partitions) => partitions.len(),
+            None => self.base_config.file_groups.len(),
+        };
+        Partitioning::UnknownPartitioning(partition_count)
     }
 
     fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
@@ -97,10 +140,35 @@ impl ExecutionPlan for NdJsonExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "NdJsonExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
         let proj = self.base_config.projected_file_column_names();
 
-        let batch_size = self.base_config.batch_size;
+        let batch_size = context.batch_size();
         let file_schema = Arc::clone(&self.base_config.file_schema);
 
         // The json reader cannot limit the number of records, so `remaining` is ignored.
@@ -113,14 +181,33 @@ impl ExecutionPlan for NdJsonExec {
             )) as BatchIter
         };
 
-        Ok(Box::pin(FileStream::new(
+        let stream = FileStream::new(
             Arc::clone(&self.base_config.object_store),
             self.base_config.file_groups[partition].clone(),
             fun,
             Arc::clone(&self.projected_schema),
             self.base_config.limit,
             self.base_config.table_partition_cols.clone(),
-        )))
+        )
+        .with_metrics(FileScanMetrics::new(&self.metrics, partition))
+        .with_scan_error_policy(self.scan_error_policy);
+        let stream =
+            self.cpu_executor
+                .spawn_batches(&self.projected_schema, move |sender| {
+                    for batch in stream {
+                        if !sender.send(batch) {
+                            break;
+                        }
+                    }
+                });
+        let stream = self.base_config.observe(
+            stream,
+            "NdJsonExec",
+            partition,
+            &self.metrics,
+            &context,
+        );
+        Ok(instrument_stream(self, partition, stream))
     }
 
     fn fmt_as(
@@ -138,9 +225,24 @@ impl ExecutionPlan for NdJsonExec {
                     super::FileGroupsDisplay(&self.base_config.file_groups),
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut exec = self.clone();
+        exec.base_config.bind_runtime(runtime)?;
+        exec.cpu_executor = runtime.cpu_executor.clone();
+        Ok(Some(Arc::new(exec)))
+    }
+
     fn statistics(&self) -> Statistics {
         self.projected_statistics.clone()
     }
@@ -156,6 +258,7 @@ mod tests {
             local_object_reader_stream, local_unpartitioned_file, LocalFileSystem,
         },
     };
+    use crate::execution::context::ExecutionContext;
 
     use super::*;
 
@@ -169,6 +272,7 @@ mod tests {
 
     #[tokio::test]
     async fn nd_json_exec_file_without_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         use arrow::datatypes::DataType;
         let path = format!("{}/1.json", TEST_DATA_BASE);
         let exec = NdJsonExec::new(PhysicalPlanConfig {
@@ -206,7 +310,7 @@ mod tests {
             &DataType::Utf8
         );
 
-        let mut it = exec.execute(0).await?;
+        let mut it = exec.execute(0, task_ctx).await?;
         let batch = it.next().await.unwrap()?;
 
         assert_eq!(batch.num_rows(), 3);
@@ -224,6 +328,7 @@ mod tests {
 
     #[tokio::test]
     async fn nd_json_exec_file_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let path = format!("{}/1.json", TEST_DATA_BASE);
         let exec = NdJsonExec::new(PhysicalPlanConfig {
             object_store: Arc::new(LocalFileSystem {}),
@@ -243,7 +348,7 @@ mod tests {
         inferred_schema.field_with_name("c").unwrap();
         inferred_schema.field_with_name("d").unwrap_err();
 
-        let mut it = exec.execute(0).await?;
+        let mut it = exec.execute(0, task_ctx).await?;
         let batch = it.next().await.unwrap()?;
 
         assert_eq!(batch.num_rows(), 4);
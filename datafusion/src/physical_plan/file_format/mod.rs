@@ -19,7 +19,8 @@
 
 mod avro;
 mod csv;
-mod file_stream;
+pub(crate) mod file_stream;
+mod ipc;
 mod json;
 mod parquet;
 
@@ -32,28 +33,53 @@ use arrow::{
     record_batch::RecordBatch,
 };
 pub use avro::AvroExec;
-pub use csv::CsvExec;
+pub(crate) use csv::SkipLines;
+pub use csv::{plan_to_csv, CsvExec};
+pub use file_stream::ScanErrorPolicy;
+pub(crate) use ipc::open_ipc_file;
+pub use ipc::{plan_to_ipc, IpcExec, IpcFileSlice};
 pub use json::NdJsonExec;
 
 use crate::{
-    datasource::{object_store::ObjectStore, PartitionedFile},
+    datasource::{
+        object_store::ObjectStore,
+        PartitionedFile,
+    },
+    error::{DataFusionError, Result},
+    execution::{
+        progress::ScanTotal, runtime_env::RuntimeEnv, task_context::TaskContext,
+    },
+    physical_plan::{
+        metrics::{BaselineMetrics, ExecutionPlanMetricsSet},
+        stream::ObservedStream,
+        SendableRecordBatchStream,
+    },
     scalar::ScalarValue,
 };
 use lazy_static::lazy_static;
 use std::{
     collections::HashMap,
     fmt::{Display, Formatter, Result as FmtResult},
+    fs,
+    path::Path,
     sync::Arc,
     vec,
 };
 
-use super::{ColumnStatistics, Statistics};
+use super::{ColumnStatistics, ExecutionPlan, Statistics};
 
 use serde::{Deserialize, Serialize};
 
 lazy_static! {
-    /// The datatype used for all partitioning columns for now
-    pub static ref DEFAULT_PARTITION_COLUMN_DATATYPE: DataType = DataType::Dictionary(Box::new(DataType::UInt8), Box::new(DataType::Utf8));
+    /// The datatype of the partitioning columns of type `Utf8`
+    pub static ref DEFAULT_PARTITION_COLUMN_DATATYPE: DataType = partition_column_type(DataType::Utf8);
+}
+
+/// The datatype of the partitioning columns whose values are of type
+/// `value_type`. As the value is the same for all the rows of a file, the
+/// columns are dictionary encoded.
+pub fn partition_column_type(value_type: DataType) -> DataType {
+    DataType::Dictionary(Box::new(DataType::UInt8), Box::new(value_type))
 }
 
 /// The base configurations to provide when creating a physical plan for
@@ -76,11 +102,22 @@ pub struct PhysicalPlanConfig {
     pub batch_size: usize,
     /// The minimum number of records required from this source plan
     pub limit: Option<usize>,
-    /// The partitioning column names
-    pub table_partition_cols: Vec<String>,
+    /// The partitioning column names and the types of their values
+    pub table_partition_cols: Vec<(String, DataType)>,
 }
 
 impl PhysicalPlanConfig {
+    /// Bind the configuration to `runtime`: a store that has a
+    /// [registry scheme](ObjectStore::registry_scheme) is replaced by the store
+    /// registered for that scheme, and the batch size by the one of `runtime`
+    fn bind_runtime(&mut self, runtime: &RuntimeEnv) -> Result<()> {
+        if let Some(scheme) = self.object_store.registry_scheme() {
+            self.object_store = runtime.object_store_registry.get_by_scheme(scheme)?;
+        }
+        self.batch_size = runtime.batch_size;
+        Ok(())
+    }
+
     /// Project the schema and the statistics on the given column indices
     fn project(&self) -> (SchemaRef, Statistics) {
         if self.projection.is_none() && self.table_partition_cols.is_empty() {
@@ -106,13 +143,13 @@ impl PhysicalPlanConfig {
                 }
             } else {
                 let partition_idx = idx - self.file_schema.fields().len();
+                let (name, value_type) = &self.table_partition_cols[partition_idx];
                 table_fields.push(Field::new(
-                    &self.table_partition_cols[partition_idx],
-                    DEFAULT_PARTITION_COLUMN_DATATYPE.clone(),
+                    name,
+                    partition_column_type(value_type.clone()),
                     false,
                 ));
-                // TODO provide accurate stat for partition column (#1186)
-                table_cols_stats.push(ColumnStatistics::default())
+                table_cols_stats.push(self.partition_column_statistics(partition_idx))
             }
         }
 
@@ -124,11 +161,44 @@ impl PhysicalPlanConfig {
             column_statistics: Some(table_cols_stats),
         };
 
-        let table_schema = Arc::new(Schema::new(table_fields));
+        let table_schema = Arc::new(Schema::new_with_metadata(
+            table_fields,
+            self.file_schema.metadata().clone(),
+        ));
 
         (table_schema, table_stats)
     }
 
+    /// The statistics of a partition column, from the values of the files to
+    /// scan. The min and max values are dictionary encoded like the column, see
+    /// [`partition_column_type`].
+    fn partition_column_statistics(&self, partition_idx: usize) -> ColumnStatistics {
+        let mut values = self
+            .file_groups
+            .iter()
+            .flatten()
+            .filter_map(|file| file.partition_values.get(partition_idx));
+        let first = match values.next() {
+            Some(first) => first,
+            None => return ColumnStatistics::default(),
+        };
+        let (min, max) = values.fold((first, first), |(min, max), value| {
+            (
+                if value < min { value } else { min },
+                if value > max { value } else { max },
+            )
+        });
+        let dictionary_value = |value: &ScalarValue| {
+            ScalarValue::Dictionary(Box::new(DataType::UInt8), Box::new(value.clone()))
+        };
+        ColumnStatistics {
+            null_count: Some(0),
+            max_value: Some(dictionary_value(max)),
+            min_value: Some(dictionary_value(min)),
+            distinct_count: None,
+        }
+    }
+
     fn projected_file_column_names(&self) -> Option<Vec<String>> {
         self.projection.as_ref().map(|p| {
             p.iter()
@@ -147,6 +217,72 @@ impl PhysicalPlanConfig {
                 .collect()
         })
     }
+
+    /// The files scanned by all the partitions, listed when the scan was
+    /// planned
+    fn scan_total(&self) -> ScanTotal {
+        let files = self.file_groups.iter().flatten();
+        ScanTotal {
+            files: files.clone().count(),
+            bytes: files.map(|file| file.file_meta.size()).sum(),
+        }
+    }
+
+    /// Record `stream`, the output of partition `partition` of the scan named
+    /// `operator`, in `metrics`, reporting its progress along with the files
+    /// to scan if `context` has a progress handler
+    fn observe(
+        &self,
+        stream: SendableRecordBatchStream,
+        operator: &'static str,
+        partition: usize,
+        metrics: &ExecutionPlanMetricsSet,
+        context: &TaskContext,
+    ) -> SendableRecordBatchStream {
+        let progress = context
+            .progress_reporter(operator, partition)
+            .map(|progress| progress.with_scan_total(self.scan_total()));
+        let baseline_metrics =
+            BaselineMetrics::new(metrics, partition).with_progress(progress);
+        Box::pin(ObservedStream::new(stream, baseline_metrics))
+    }
+}
+
+/// Create the directory that will receive the files written by a query.
+///
+/// An existing directory is only replaced if `overwrite` is set.
+pub(crate) fn create_output_dir(path: &Path, overwrite: bool) -> Result<()> {
+    if path.exists() {
+        if !overwrite {
+            return Err(DataFusionError::Execution(format!(
+                "Output directory {} already exists and overwrite is not enabled",
+                path.display()
+            )));
+        }
+        fs::remove_dir_all(path)?;
+    }
+    fs::create_dir_all(path).map_err(|e| {
+        DataFusionError::Execution(format!(
+            "Could not create directory {}: {:?}",
+            path.display(),
+            e
+        ))
+    })
+}
+
+/// The batches fed to the partition `partition` of a file scan, see
+/// [`LambdaExecPlan`](super::LambdaExecPlan)
+fn fed_partition(
+    partitions: &[Vec<RecordBatch>],
+    partition: usize,
+) -> Result<Vec<RecordBatch>> {
+    partitions.get(partition).cloned().ok_or_else(|| {
+        DataFusionError::Internal(format!(
+            "Invalid partition {}, {} partitions were fed",
+            partition,
+            partitions.len()
+        ))
+    })
 }
 
 /// A wrapper to customize partitioned file display
@@ -191,10 +327,15 @@ struct PartitionColumnProjector {
 impl PartitionColumnProjector {
     // Create a projector to insert the partitioning columns into batches read from files
     // - projected_schema: the target schema with both file and partitioning columns
-    // - table_partition_cols: all the partitioning column names
-    fn new(projected_schema: SchemaRef, table_partition_cols: &[String]) -> Self {
+    // - table_partition_cols: all the partitioning columns
+    fn new(
+        projected_schema: SchemaRef,
+        table_partition_cols: &[(String, DataType)],
+    ) -> Self {
         let mut idx_map = HashMap::new();
-        for (partition_idx, partition_name) in table_partition_cols.iter().enumerate() {
+        for (partition_idx, (partition_name, _)) in
+            table_partition_cols.iter().enumerate()
+        {
             if let Ok(schema_idx) = projected_schema.index_of(partition_name) {
                 idx_map.insert(partition_idx, schema_idx);
             }
@@ -264,10 +405,7 @@ fn create_dict_array(
     };
 
     // create data type
-    let data_type =
-        DataType::Dictionary(Box::new(DataType::UInt8), Box::new(val.get_datatype()));
-
-    debug_assert_eq!(data_type, *DEFAULT_PARTITION_COLUMN_DATATYPE);
+    let data_type = partition_column_type(val.get_datatype());
 
     // assemble pieces together
     let mut builder = ArrayData::builder(data_type)
@@ -293,7 +431,7 @@ mod tests {
             Arc::clone(&file_schema),
             None,
             Statistics::default(),
-            vec!["date".to_owned()],
+            vec![("date".to_owned(), DataType::Utf8)],
         );
 
         let (proj_schema, proj_statistics) = conf.project();
@@ -310,7 +448,11 @@ mod tests {
                 .len(),
             file_schema.fields().len() + 1
         );
-        // TODO implement tests for partition column statistics once implemented
+        // there are no files to take the partition values from
+        assert_eq!(
+            proj_statistics.column_statistics.unwrap()[file_schema.fields().len()],
+            ColumnStatistics::default()
+        );
 
         let col_names = conf.projected_file_column_names();
         assert_eq!(col_names, None);
@@ -339,7 +481,7 @@ mod tests {
                 ),
                 ..Default::default()
             },
-            vec!["date".to_owned()],
+            vec![("date".to_owned(), DataType::Utf8)],
         );
 
         let (proj_schema, proj_statistics) = conf.project();
@@ -351,8 +493,6 @@ mod tests {
             .column_statistics
             .expect("projection creates column statistics");
         assert_eq!(proj_stat_cols.len(), 2);
-        // TODO implement tests for proj_stat_cols[0] once partition column
-        // statistics are implemented
         assert_eq!(proj_stat_cols[1].distinct_count, Some(0));
 
         let col_names = conf.projected_file_column_names();
@@ -369,8 +509,11 @@ mod tests {
             ("b", &vec![-2, -1, 0]),
             ("c", &vec![10, 11, 12]),
         );
-        let partition_cols =
-            vec!["year".to_owned(), "month".to_owned(), "day".to_owned()];
+        let partition_cols = vec![
+            ("year".to_owned(), DataType::Utf8),
+            ("month".to_owned(), DataType::Utf8),
+            ("day".to_owned(), DataType::Utf8),
+        ];
         // create a projected schema
         let conf = config_for_projection(
             file_batch.schema(),
@@ -471,12 +614,84 @@ mod tests {
         crate::assert_batches_eq!(expected, &[projected_batch]);
     }
 
+    #[test]
+    fn partition_column_statistics() {
+        let file_batch =
+            build_table_i32(("a", &vec![0]), ("b", &vec![0]), ("c", &vec![0]));
+        let mut conf = config_for_projection(
+            file_batch.schema(),
+            Some(vec![3, 0]),
+            Statistics::default(),
+            vec![("year".to_owned(), DataType::Int32)],
+        );
+        let file = |year: i32| PartitionedFile {
+            partition_values: vec![ScalarValue::Int32(Some(year))],
+            ..PartitionedFile::new(format!("year={}/file.csv", year), 10)
+        };
+        conf.file_groups = vec![vec![file(2021), file(2019)], vec![file(2020)]];
+
+        let (proj_schema, proj_statistics) = conf.project();
+        assert_eq!(
+            proj_schema.field(0).data_type(),
+            &partition_column_type(DataType::Int32)
+        );
+        let year_stats = &proj_statistics.column_statistics.unwrap()[0];
+        let year = |year: i32| {
+            ScalarValue::Dictionary(
+                Box::new(DataType::UInt8),
+                Box::new(ScalarValue::Int32(Some(year))),
+            )
+        };
+        assert_eq!(year_stats.min_value, Some(year(2019)));
+        assert_eq!(year_stats.max_value, Some(year(2021)));
+        assert_eq!(year_stats.null_count, Some(0));
+        // the statistics have the type of the column
+        for value in [&year_stats.min_value, &year_stats.max_value] {
+            assert_eq!(
+                &value.as_ref().unwrap().get_datatype(),
+                proj_schema.field(0).data_type()
+            );
+        }
+    }
+
+    #[test]
+    fn typed_partition_column_projector() {
+        let file_batch =
+            build_table_i32(("a", &vec![0, 1]), ("b", &vec![2, 3]), ("c", &vec![4, 5]));
+        let partition_cols = vec![("year".to_owned(), DataType::Int32)];
+        let conf = config_for_projection(
+            file_batch.schema(),
+            Some(vec![0, 1, 2, 3]),
+            Statistics::default(),
+            partition_cols.clone(),
+        );
+        let (proj_schema, _) = conf.project();
+        let mut proj = PartitionColumnProjector::new(proj_schema, &partition_cols);
+
+        let projected_batch = proj
+            .project(file_batch, &[ScalarValue::Int32(Some(2021))])
+            .expect("Projection of partition columns into record batch failed");
+        assert_eq!(
+            projected_batch.column(3).data_type(),
+            &partition_column_type(DataType::Int32)
+        );
+        let expected = vec![
+            "+---+---+---+------+",
+            "| a | b | c | year |",
+            "+---+---+---+------+",
+            "| 0 | 2 | 4 | 2021 |",
+            "| 1 | 3 | 5 | 2021 |",
+            "+---+---+---+------+",
+        ];
+        crate::assert_batches_eq!(expected, &[projected_batch]);
+    }
+
     // sets default for configs that play no role in projections
     fn config_for_projection(
         file_schema: SchemaRef,
         projection: Option<Vec<usize>>,
         statistics: Statistics,
-        table_partition_cols: Vec<String>,
+        table_partition_cols: Vec<(String, DataType)>,
     ) -> PhysicalPlanConfig {
         PhysicalPlanConfig {
             batch_size: 1024,
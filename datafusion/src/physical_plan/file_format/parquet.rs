@@ -18,9 +18,16 @@
 //! Execution plan for reading Parquet files
 
 use std::fmt;
+use std::io::Read;
 use std::sync::Arc;
 use std::{any::Any, convert::TryInto};
 
+use crate::execution::cpu_executor::{BatchSender, CpuExecutor};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::dynamic_filter::DynamicFilterSource;
+use crate::physical_plan::memory::MemoryStream;
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::LambdaExecPlan;
 
 use crate::datasource::file_format::parquet::ChunkObjectReader;
@@ -33,8 +40,8 @@ use crate::{
     physical_optimizer::pruning::{PruningPredicate, PruningStatistics},
     physical_plan::{
         file_format::PhysicalPlanConfig,
+        fmt_with_statistics,
         metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
-        stream::RecordBatchReceiverStream,
         DisplayFormatType, ExecutionPlan, Partitioning, SendableRecordBatchStream,
         Statistics,
     },
@@ -42,31 +49,32 @@ use crate::{
 };
 
 use arrow::{
-    array::ArrayRef,
-    datatypes::{Schema, SchemaRef},
+    array::{ArrayRef, UInt64Array},
+    datatypes::{DataType, Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
 };
-use log::debug;
+use log::{debug, warn};
+use parquet::errors::Result as ParquetResult;
 use parquet::file::{
     metadata::RowGroupMetaData,
-    reader::{FileReader, SerializedFileReader},
+    reader::{ChunkReader, FileReader, Length, SerializedFileReader},
     statistics::Statistics as ParquetStatistics,
 };
 
 use fmt::Debug;
-use parquet::arrow::{ArrowReader, ParquetFileArrowReader};
-
-use tokio::{
-    sync::mpsc::{channel, Receiver, Sender},
-    task,
+use parquet::arrow::{
+    arrow_reader::ParquetRecordBatchReader, ArrowReader, ParquetFileArrowReader,
 };
 
 use async_trait::async_trait;
 
 use serde::{Deserialize, Serialize};
 
-use super::PartitionColumnProjector;
+use super::file_stream::{
+    file_error_message, is_schema_error, FileOffset, FileScanMetrics, ScanErrorPolicy,
+};
+use super::{fed_partition, PartitionColumnProjector};
 
 /// Execution plan for scanning one or more Parquet partitions
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +86,19 @@ pub struct ParquetExec {
     metrics: ExecutionPlanMetricsSet,
     /// Optional predicate for pruning row groups
     pruning_predicate: Option<PruningPredicate>,
+    /// What to do when a file cannot be read
+    #[serde(default)]
+    scan_error_policy: ScanErrorPolicy,
+    /// Filter published at runtime by a hash join probing the rows of this
+    /// scan, also pruning row groups
+    #[serde(skip)]
+    dynamic_filter: Option<DynamicFilterSource>,
+    /// Partitions fed in place of the files, see [`LambdaExecPlan`]
+    #[serde(skip)]
+    fed_partitions: Option<Vec<Vec<RecordBatch>>>,
+    /// Executor decoding the files, bound by the runtime
+    #[serde(skip)]
+    cpu_executor: CpuExecutor,
 }
 
 /// Stores metrics about the parquet execution for a particular parquet file
@@ -125,6 +146,10 @@ impl ParquetExec {
             projected_statistics,
             metrics,
             pruning_predicate,
+            scan_error_policy: ScanErrorPolicy::Fail,
+            dynamic_filter: None,
+            fed_partitions: None,
+            cpu_executor: CpuExecutor::default(),
         }
     }
 
@@ -132,6 +157,55 @@ impl ParquetExec {
     pub fn base_config(&self) -> &PhysicalPlanConfig {
         &self.base_config
     }
+
+    /// Prune the row groups with the filter of `source` too, once published
+    pub fn with_dynamic_filter(mut self, source: DynamicFilterSource) -> Self {
+        self.dynamic_filter = Some(source);
+        self
+    }
+
+    /// The source of the filter published at runtime by a hash join, if any
+    pub fn dynamic_filter(&self) -> Option<&DynamicFilterSource> {
+        self.dynamic_filter.as_ref()
+    }
+
+    /// Handle the files that cannot be read with `scan_error_policy`, see
+    /// [`ScanErrorPolicy`]
+    pub fn with_scan_error_policy(mut self, scan_error_policy: ScanErrorPolicy) -> Self {
+        self.scan_error_policy = scan_error_policy;
+        self
+    }
+
+    /// What the scan does when a file cannot be read
+    pub fn scan_error_policy(&self) -> ScanErrorPolicy {
+        self.scan_error_policy
+    }
+}
+
+/// A [`ChunkObjectReader`] recording the bytes read from the file and the
+/// time spent fetching them, and the offset of the last chunk read
+struct MeteredChunkReader {
+    inner: ChunkObjectReader,
+    metrics: FileScanMetrics,
+    offset: FileOffset,
+}
+
+impl Length for MeteredChunkReader {
+    fn len(&self) -> u64 {
+        self.inner.len()
+    }
+}
+
+impl ChunkReader for MeteredChunkReader {
+    type T = Box<dyn Read + Send + Sync>;
+
+    fn get_read(&self, start: u64, length: usize) -> ParquetResult<Self::T> {
+        self.offset.set(start);
+        let reader = self
+            .metrics
+            .time_open(|| self.inner.get_read(start, length))?;
+        Ok(self.metrics.reader(reader))
+    }
 }
 
 impl ParquetFileMetrics {
@@ -179,7 +253,11 @@ impl ExecutionPlan for ParquetExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        Partitioning::UnknownPartitioning(self.base_config.file_groups.len())
+        let partition_count = match &self.fed_partitions {
+            Some(partitions) => partitions.len(),
+            None => self.base_config.file_groups.len(),
+        };
+        Partitioning::UnknownPartitioning(partition_count)
     }
 
     fn with_new_children(
@@ -196,14 +274,32 @@ impl ExecutionPlan for ParquetExec {
         }
     }
 
-    async fn execute(&self, partition_index: usize) -> Result<SendableRecordBatchStream> {
-        // because the parquet implementation is not thread-safe, it is necessary to execute
-        // on a thread and communicate with channels
-        let (response_tx, response_rx): (
-            Sender<ArrowResult<RecordBatch>>,
-            Receiver<ArrowResult<RecordBatch>>,
-        ) = channel(2);
-
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ParquetExec",
+            skip_all,
+            fields(partition = partition_index),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition_index: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if let Some(partitions) = &self.fed_partitions {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(MemoryStream::try_new(
+                    fed_partition(partitions, partition_index)?,
+                    self.schema(),
+                    None,
+                )?),
+            ));
+        }
         let partition = self.base_config.file_groups[partition_index].clone();
         let metrics = self.metrics.clone();
         let projection = match self.base_config.file_column_projection_indices() {
@@ -211,7 +307,10 @@ impl ExecutionPlan for ParquetExec {
             None => (0..self.base_config.file_schema.fields().len()).collect(),
         };
         let pruning_predicate = self.pruning_predicate.clone();
-        let batch_size = self.base_config.batch_size;
+        let dynamic_filter = self.dynamic_filter.clone();
+        let scan_error_policy = self.scan_error_policy;
+        let file_schema = Arc::clone(&self.base_config.file_schema);
+        let batch_size = context.batch_size();
         let limit = self.base_config.limit;
         let object_store = Arc::clone(&self.base_config.object_store);
         let partition_col_proj = PartitionColumnProjector::new(
@@ -219,28 +318,40 @@ impl ExecutionPlan for ParquetExec {
             &self.base_config.table_partition_cols,
         );
 
-        let join_handle = task::spawn_blocking(move || {
-            if let Err(e) = read_partition(
-                object_store.as_ref(),
-                partition_index,
-                partition,
-                metrics,
-                &projection,
-                &pruning_predicate,
-                batch_size,
-                response_tx,
-                limit,
-                partition_col_proj,
-            ) {
-                println!("Parquet reader thread terminated due to error: {:?}", e);
-            }
-        });
-
-        Ok(RecordBatchReceiverStream::create(
-            &self.projected_schema,
-            response_rx,
-            join_handle,
-        ))
+        // because the parquet implementation is not thread-safe, a partition
+        // is read by a single task of the executor
+        let stream =
+            self.cpu_executor
+                .spawn_batches(&self.projected_schema, move |response_tx| {
+                    if let Err(e) = read_partition(
+                        object_store.as_ref(),
+                        partition_index,
+                        partition,
+                        metrics,
+                        &projection,
+                        &pruning_predicate,
+                        &dynamic_filter,
+                        &file_schema,
+                        batch_size,
+                        response_tx,
+                        limit,
+                        partition_col_proj,
+                        scan_error_policy,
+                    ) {
+                        println!(
+                            "Parquet reader thread terminated due to error: {:?}",
+                            e
+                        );
+                    }
+                });
+        let stream = self.base_config.observe(
+            stream,
+            "ParquetExec",
+            partition_index,
+            &self.metrics,
+            &context,
+        );
+        Ok(instrument_stream(self, partition, stream))
     }
 
     fn fmt_as(
@@ -258,6 +369,7 @@ impl ExecutionPlan for ParquetExec {
                     super::FileGroupsDisplay(&self.base_config.file_groups)
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -265,6 +377,16 @@ impl ExecutionPlan for ParquetExec {
         Some(self.metrics.clone_inner())
     }
 
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut exec = self.clone();
+        exec.base_config.bind_runtime(runtime)?;
+        exec.cpu_executor = runtime.cpu_executor.clone();
+        Ok(Some(Arc::new(exec)))
+    }
+
     fn statistics(&self) -> Statistics {
         self.projected_statistics.clone()
     }
@@ -272,20 +394,21 @@ impl ExecutionPlan for ParquetExec {
 
 #[async_trait]
 impl LambdaExecPlan for ParquetExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.fed_partitions = Some(partitions);
+        Ok(())
     }
 }
 
 fn send_result(
-    response_tx: &Sender<ArrowResult<RecordBatch>>,
+    response_tx: &BatchSender,
     result: ArrowResult<RecordBatch>,
 ) -> Result<()> {
-    // Note this function is running on its own blockng tokio thread so blocking here is ok.
-    response_tx
-        .blocking_send(result)
-        .map_err(|e| DataFusionError::Execution(e.to_string()))?;
-    Ok(())
+    if response_tx.send(result) {
+        Ok(())
+    } else {
+        Err(DataFusionError::Execution("channel closed".to_owned()))
+    }
 }
 
 /// Wraps parquet statistics in a way
@@ -339,13 +462,24 @@ macro_rules! get_min_max_values {
             return None
         };
 
+        // the row groups without statistics have null values, to keep one
+        // value per row group
         let scalar_values : Vec<ScalarValue> = $self.row_group_metadata
             .iter()
-            .flat_map(|meta| {
-                meta.column(column_index).statistics()
+            .map(|meta| {
+                meta.column(column_index)
+                    .statistics()
+                    .and_then(|stats| get_statistic!(stats, $func, $bytes_func))
             })
-            .map(|stats| {
-                get_statistic!(stats, $func, $bytes_func)
+            .map(|maybe_scalar| match (maybe_scalar, data_type) {
+                // decimals of up to 18 digits may be stored as integers
+                (Some(ScalarValue::Int32(Some(v))), DataType::Decimal(p, s)) => {
+                    Some(ScalarValue::Decimal128(Some(v as i128), *p, *s))
+                }
+                (Some(ScalarValue::Int64(Some(v))), DataType::Decimal(p, s)) => {
+                    Some(ScalarValue::Decimal128(Some(v as i128), *p, *s))
+                }
+                (maybe_scalar, _) => maybe_scalar,
             })
             .map(|maybe_scalar| {
                 // column either did't have statistics at all or didn't have min/max values
@@ -370,6 +504,20 @@ impl<'a> PruningStatistics for RowGroupPruningStatistics<'a> {
     fn num_containers(&self) -> usize {
         self.row_group_metadata.len()
     }
+
+    fn null_counts(&self, column: &Column) -> Option<ArrayRef> {
+        let (column_index, _) = self.parquet_schema.column_with_name(&column.name)?;
+        let null_counts = self
+            .row_group_metadata
+            .iter()
+            .map(|meta| {
+                meta.column(column_index)
+                    .statistics()
+                    .map(|stats| stats.null_count())
+            })
+            .collect::<UInt64Array>();
+        Some(Arc::new(null_counts))
+    }
 }
 
 fn build_row_group_predicate(
@@ -402,6 +550,111 @@ fn build_row_group_predicate(
     }
 }
 
+/// Check that the columns `projection` of the file of schema `file` are the
+/// columns of the scan
+fn check_file_schema(
+    file: &Schema,
+    file_schema: &Schema,
+    projection: &[usize],
+) -> ArrowResult<()> {
+    for &i in projection {
+        let expected = file_schema.field(i);
+        match file.fields().get(i) {
+            Some(field)
+                if field.name() == expected.name()
+                    && field.data_type() == expected.data_type() => {}
+            Some(field) => {
+                return Err(ArrowError::SchemaError(format!(
+                    "Expected column {} of type {:?} at index {}, found column {} of type {:?}",
+                    expected.name(),
+                    expected.data_type(),
+                    i,
+                    field.name(),
+                    field.data_type()
+                )))
+            }
+            None => {
+                return Err(ArrowError::SchemaError(format!(
+                    "Expected column {} at index {}, the file has {} columns",
+                    expected.name(),
+                    i,
+                    file.fields().len()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Open a reader of the batches of `partitioned_file`, pruning its row groups
+/// with the predicates. The schema of the file is checked if `check_schema`.
+#[allow(clippy::too_many_arguments)]
+fn open_file(
+    object_store: &dyn ObjectStore,
+    partitioned_file: &PartitionedFile,
+    scan_metrics: &FileScanMetrics,
+    offset: &FileOffset,
+    file_metrics: ParquetFileMetrics,
+    projection: &[usize],
+    pruning_predicate: &Option<PruningPredicate>,
+    dynamic_filter: &Option<DynamicFilterSource>,
+    file_schema: &SchemaRef,
+    batch_size: usize,
+    check_schema: bool,
+) -> ArrowResult<ParquetRecordBatchReader> {
+    let object_reader = scan_metrics
+        .open_file(|| {
+            object_store.file_reader(partitioned_file.file_meta.sized_file.clone())
+        })
+        .map_err(|e| ArrowError::ExternalError(Box::new(e)))?;
+    let mut file_reader = SerializedFileReader::new(MeteredChunkReader {
+        inner: ChunkObjectReader(object_reader),
+        metrics: scan_metrics.clone(),
+        offset: offset.clone(),
+    })?;
+    if let Some(pruning_predicate) = pruning_predicate {
+        let row_group_predicate = build_row_group_predicate(
+            pruning_predicate,
+            file_metrics.clone(),
+            file_reader.metadata().row_groups(),
+        );
+        file_reader.filter_row_groups(&row_group_predicate);
+    }
+    // the filter is read for each file, it may be published while
+    // reading the previous ones
+    let dynamic_filter = dynamic_filter.as_ref().and_then(|source| source.filter());
+    if let Some(filter) = dynamic_filter {
+        match PruningPredicate::try_new(&filter, Arc::clone(file_schema)) {
+            Ok(pruning_predicate) => {
+                let row_group_predicate = build_row_group_predicate(
+                    &pruning_predicate,
+                    file_metrics,
+                    file_reader.metadata().row_groups(),
+                );
+                file_reader.filter_row_groups(&row_group_predicate);
+            }
+            Err(e) => {
+                debug!("Could not create pruning predicate for {:?}: {}", filter, e);
+                file_metrics.predicate_evaluation_errors.add(1);
+            }
+        }
+    }
+    let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
+    if check_schema {
+        let schema = arrow_reader
+            .get_schema()
+            .map_err(|e| ArrowError::SchemaError(e.to_string()))?;
+        check_file_schema(&schema, file_schema, projection)?;
+    }
+    Ok(arrow_reader.get_record_reader_by_columns(projection.to_owned(), batch_size)?)
+}
+
+/// Send the error `message` to the operator and terminate the thread with it
+fn fail(response_tx: &BatchSender, message: String) -> Result<()> {
+    send_result(response_tx, Err(ArrowError::ParquetError(message.clone())))?;
+    Err(DataFusionError::Execution(message))
+}
+
 #[allow(clippy::too_many_arguments)]
 fn read_partition(
     object_store: &dyn ObjectStore,
@@ -410,33 +663,48 @@ fn read_partition(
     metrics: ExecutionPlanMetricsSet,
     projection: &[usize],
     pruning_predicate: &Option<PruningPredicate>,
+    dynamic_filter: &Option<DynamicFilterSource>,
+    file_schema: &SchemaRef,
     batch_size: usize,
-    response_tx: Sender<ArrowResult<RecordBatch>>,
+    response_tx: &BatchSender,
     limit: Option<usize>,
     mut partition_column_projector: PartitionColumnProjector,
+    scan_error_policy: ScanErrorPolicy,
 ) -> Result<()> {
+    let scan_metrics = FileScanMetrics::new(&metrics, partition_index);
     let mut total_rows = 0;
     'outer: for partitioned_file in partition {
-        let file_metrics = ParquetFileMetrics::new(
-            partition_index,
-            &*partitioned_file.file_meta.path(),
-            &metrics,
+        let path = partitioned_file.file_meta.path();
+        let file_metrics = ParquetFileMetrics::new(partition_index, path, &metrics);
+        let offset = FileOffset::default();
+        // the skip policies must not hide the files of another schema
+        let batch_reader = open_file(
+            object_store,
+            &partitioned_file,
+            &scan_metrics,
+            &offset,
+            file_metrics,
+            projection,
+            pruning_predicate,
+            dynamic_filter,
+            file_schema,
+            batch_size,
+            scan_error_policy != ScanErrorPolicy::Fail,
         );
-        let object_reader =
-            object_store.file_reader(partitioned_file.file_meta.sized_file.clone())?;
-        let mut file_reader =
-            SerializedFileReader::new(ChunkObjectReader(object_reader))?;
-        if let Some(pruning_predicate) = pruning_predicate {
-            let row_group_predicate = build_row_group_predicate(
-                pruning_predicate,
-                file_metrics,
-                file_reader.metadata().row_groups(),
-            );
-            file_reader.filter_row_groups(&row_group_predicate);
-        }
-        let mut arrow_reader = ParquetFileArrowReader::new(Arc::new(file_reader));
-        let mut batch_reader = arrow_reader
-            .get_record_reader_by_columns(projection.to_owned(), batch_size)?;
+        let mut batch_reader = match batch_reader {
+            Ok(batch_reader) => batch_reader,
+            Err(e) => {
+                let message = file_error_message(path, offset.get(), &e);
+                if scan_error_policy == ScanErrorPolicy::Fail || is_schema_error(&e) {
+                    return fail(response_tx, message);
+                }
+                warn!("Skipping the file after an error: {}", message);
+                scan_metrics.skip_file();
+                continue;
+            }
+        };
+        // the offset of the last batch of the file skipped after an error
+        let mut skipped_at = None;
         loop {
             match batch_reader.next() {
                 Some(Ok(batch)) => {
@@ -444,7 +712,7 @@ fn read_partition(
                     let proj_batch = partition_column_projector
                         .project(batch, &partitioned_file.partition_values);
 
-                    send_result(&response_tx, proj_batch)?;
+                    send_result(response_tx, proj_batch)?;
                     if limit.map(|l| total_rows >= l).unwrap_or(false) {
                         break 'outer;
                     }
@@ -453,18 +721,26 @@ fn read_partition(
                     break;
                 }
                 Some(Err(e)) => {
-                    let err_msg = format!(
-                        "Error reading batch from {}: {}",
-                        partitioned_file,
-                        e.to_string()
-                    );
-                    // send error to operator
-                    send_result(
-                        &response_tx,
-                        Err(ArrowError::ParquetError(err_msg.clone())),
-                    )?;
-                    // terminate thread with error
-                    return Err(DataFusionError::Execution(err_msg));
+                    let at = offset.get();
+                    let message = file_error_message(path, at, &e);
+                    match scan_error_policy {
+                        _ if is_schema_error(&e) => return fail(response_tx, message),
+                        ScanErrorPolicy::Fail => return fail(response_tx, message),
+                        // the reader moved past the previous error
+                        ScanErrorPolicy::SkipBatch if skipped_at != Some(at) => {
+                            warn!("Skipping a batch after an error: {}", message);
+                            skipped_at = Some(at);
+                            scan_metrics.skip_batch();
+                        }
+                        _ => {
+                            warn!(
+                                "Skipping the rest of the file after an error: {}",
+                                message
+                            );
+                            scan_metrics.skip_file();
+                            break;
+                        }
+                    }
                 }
             }
         }
@@ -483,18 +759,24 @@ mod tests {
             local_object_reader_stream, local_unpartitioned_file, LocalFileSystem,
         },
     };
+    use crate::execution::context::ExecutionContext;
 
     use super::*;
+    use arrow::array::{Int32Array, StringArray};
     use arrow::datatypes::{DataType, Field};
     use futures::StreamExt;
     use parquet::{
+        arrow::ArrowWriter,
         basic::Type as PhysicalType,
         file::{metadata::RowGroupMetaData, statistics::Statistics as ParquetStatistics},
         schema::types::SchemaDescPtr,
     };
+    use std::fs;
+    use tempfile::TempDir;
 
     #[tokio::test]
     async fn parquet_exec_with_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let testdata = crate::test_util::parquet_test_data();
         let filename = format!("{}/alltypes_plain.parquet", testdata);
         let parquet_exec = ParquetExec::new(
@@ -514,7 +796,7 @@ mod tests {
         );
         assert_eq!(parquet_exec.output_partitioning().partition_count(), 1);
 
-        let mut results = parquet_exec.execute(0).await?;
+        let mut results = parquet_exec.execute(0, task_ctx).await?;
         let batch = results.next().await.unwrap()?;
 
         assert_eq!(8, batch.num_rows());
@@ -537,8 +819,165 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn parquet_exec_records_io() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let testdata = crate::test_util::parquet_test_data();
+        let filename = format!("{}/alltypes_plain.parquet", testdata);
+        let parquet_exec = Arc::new(ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_groups: vec![vec![local_unpartitioned_file(filename.clone())]],
+                file_schema: ParquetFormat::default()
+                    .infer_schema(local_object_reader_stream(vec![filename]))
+                    .await?,
+                statistics: Statistics::default(),
+                projection: None,
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            None,
+        ));
+
+        crate::physical_plan::collect(parquet_exec.clone(), task_ctx).await?;
+
+        let metrics = parquet_exec.metrics().unwrap().aggregate_by_name();
+        assert!(metrics.bytes_scanned().unwrap() > 0);
+        assert!(metrics.time_fetching().unwrap() > 0);
+        Ok(())
+    }
+
+    /// Write the column `a` of `values` to the parquet file `name` of `dir`,
+    /// truncated to half its size if `truncate`
+    fn write_file(
+        dir: &TempDir,
+        name: &str,
+        values: ArrayRef,
+        truncate: bool,
+    ) -> Result<String> {
+        let batch = RecordBatch::try_from_iter(vec![("a", values)])?;
+        let path = dir.path().join(name);
+        let file = fs::File::create(&path)?;
+        let mut writer = ArrowWriter::try_new(file.try_clone()?, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        if truncate {
+            file.set_len(file.metadata()?.len() / 2)?;
+        }
+        Ok(path.to_str().unwrap().to_owned())
+    }
+
+    /// Scan the INT column `a` of `files` in a single partition
+    fn scan_files(
+        files: &[String],
+        scan_error_policy: ScanErrorPolicy,
+    ) -> Arc<ParquetExec> {
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+        let exec = ParquetExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_groups: vec![files
+                    .iter()
+                    .cloned()
+                    .map(local_unpartitioned_file)
+                    .collect()],
+                file_schema: Arc::new(schema),
+                statistics: Statistics::default(),
+                projection: None,
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            None,
+        );
+        Arc::new(exec.with_scan_error_policy(scan_error_policy))
+    }
+
+    fn count_metric(exec: &ParquetExec, name: &str) -> Option<usize> {
+        exec.metrics()
+            .unwrap()
+            .sum(|m| m.value().name() == name)
+            .map(|v| v.as_usize())
+    }
+
+    #[tokio::test]
+    async fn parquet_exec_truncated_file() -> Result<()> {
+        let dir = TempDir::new()?;
+        let ints = |values: Vec<i32>| Arc::new(Int32Array::from(values)) as ArrayRef;
+        let files = vec![
+            write_file(&dir, "1.parquet", ints(vec![1, 2, 3]), false)?,
+            write_file(&dir, "2.parquet", ints(vec![4, 5, 6]), true)?,
+            write_file(&dir, "3.parquet", ints(vec![7, 8]), false)?,
+        ];
+
+        let exec = scan_files(&files, ScanErrorPolicy::Fail);
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let err = crate::physical_plan::collect(exec, task_ctx)
+            .await
+            .unwrap_err()
+            .to_string();
+        let expected = format!("Error reading file {} at byte offset", files[1]);
+        assert!(err.contains(&expected), "{}", err);
+
+        for policy in [ScanErrorPolicy::SkipFile, ScanErrorPolicy::SkipBatch] {
+            let exec = scan_files(&files, policy);
+            let task_ctx = ExecutionContext::new().task_ctx();
+            let batches = crate::physical_plan::collect(exec.clone(), task_ctx).await?;
+            #[rustfmt::skip]
+            crate::assert_batches_eq!(&[
+                "+---+",
+                "| a |",
+                "+---+",
+                "| 1 |",
+                "| 2 |",
+                "| 3 |",
+                "| 7 |",
+                "| 8 |",
+                "+---+",
+            ], &batches);
+            assert_eq!(count_metric(&exec, "files_skipped"), Some(1));
+            assert_eq!(count_metric(&exec, "files_opened"), Some(3));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn parquet_exec_skip_fails_on_schema_mismatch() -> Result<()> {
+        let dir = TempDir::new()?;
+        let files = vec![
+            write_file(
+                &dir,
+                "1.parquet",
+                Arc::new(Int32Array::from(vec![1])),
+                false,
+            )?,
+            write_file(
+                &dir,
+                "2.parquet",
+                Arc::new(StringArray::from(vec!["a"])),
+                false,
+            )?,
+        ];
+
+        for policy in [ScanErrorPolicy::SkipFile, ScanErrorPolicy::SkipBatch] {
+            let exec = scan_files(&files, policy);
+            let task_ctx = ExecutionContext::new().task_ctx();
+            let err = crate::physical_plan::collect(exec.clone(), task_ctx)
+                .await
+                .unwrap_err()
+                .to_string();
+            let expected =
+                "Expected column a of type Int32 at index 0, found column a of type Utf8";
+            assert!(err.contains(expected), "{}", err);
+            assert_eq!(count_metric(&exec, "files_skipped"), Some(0));
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn parquet_exec_with_partition() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let testdata = crate::test_util::parquet_test_data();
         let filename = format!("{}/alltypes_plain.parquet", testdata);
         let mut partitioned_file = local_unpartitioned_file(filename.clone());
@@ -560,16 +999,16 @@ mod tests {
                 batch_size: 1024,
                 limit: None,
                 table_partition_cols: vec![
-                    "year".to_owned(),
-                    "month".to_owned(),
-                    "day".to_owned(),
+                    ("year".to_owned(), DataType::Utf8),
+                    ("month".to_owned(), DataType::Utf8),
+                    ("day".to_owned(), DataType::Utf8),
                 ],
             },
             None,
         );
         assert_eq!(parquet_exec.output_partitioning().partition_count(), 1);
 
-        let mut results = parquet_exec.execute(0).await?;
+        let mut results = parquet_exec.execute(0, task_ctx).await?;
         let batch = results.next().await.unwrap()?;
         let expected = vec![
             "+----+----------+-------------+-------+",
@@ -666,6 +1105,39 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn row_group_pruning_predicate_null_counts() -> Result<()> {
+        use crate::logical_plan::col;
+        // c1 IS NULL => c1_null_count > 0
+        let expr = col("c1").is_null();
+        let schema = Schema::new(vec![Field::new("c1", DataType::Int32, true)]);
+        let pruning_predicate = PruningPredicate::try_new(&expr, Arc::new(schema))?;
+
+        let schema_descr = get_test_schema_descr(vec![("c1", PhysicalType::INT32)]);
+        let rgm1 = get_row_group_meta_data(
+            &schema_descr,
+            vec![ParquetStatistics::int32(Some(1), Some(10), None, 0, false)],
+        );
+        let rgm2 = get_row_group_meta_data(
+            &schema_descr,
+            vec![ParquetStatistics::int32(Some(11), Some(20), None, 5, false)],
+        );
+        let row_group_metadata = vec![rgm1, rgm2];
+        let row_group_predicate = build_row_group_predicate(
+            &pruning_predicate,
+            parquet_file_metrics(),
+            &row_group_metadata,
+        );
+        let row_group_filter = row_group_metadata
+            .iter()
+            .enumerate()
+            .map(|(i, g)| row_group_predicate(g, i))
+            .collect::<Vec<_>>();
+        assert_eq!(row_group_filter, vec![false, true]);
+
+        Ok(())
+    }
+
     #[test]
     fn row_group_pruning_predicate_partial_expr() -> Result<()> {
         use crate::logical_plan::{col, lit};
@@ -19,19 +19,36 @@
 //! include in its output batches.
 
 use std::any::Any;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::ops::Range;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 
-use super::{RecordBatchStream, SendableRecordBatchStream, Statistics};
+use super::{
+    ColumnStatistics, ColumnarValue, RecordBatchStream, SendableRecordBatchStream,
+    Statistics,
+};
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::memory::MemoryExec;
+use crate::execution::task_context::TaskContext;
+use crate::logical_plan::Operator;
+use crate::physical_plan::coalesce_batches::concat_batches;
+use crate::physical_plan::expressions::{
+    cast_column, BinaryExpr, CastExpr, Column, IsNotNullExpr, IsNullExpr, Literal,
+    NotExpr, PhysicalSortExpr,
+};
+use crate::physical_plan::memory::{feed_input, MemoryExec};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet},
-    DisplayFormatType, ExecutionPlan, Partitioning, PhysicalExpr,
-    LambdaExecPlan,
+    fmt_with_statistics,
+    metrics::{
+        self, BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+    },
+    DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning, PhysicalExpr,
 };
-use arrow::array::BooleanArray;
+use crate::scalar::ScalarValue;
+use arrow::array::{Array, BooleanArray};
 use arrow::compute::filter_record_batch;
 use arrow::datatypes::{DataType, SchemaRef};
 use arrow::error::Result as ArrowResult;
@@ -45,6 +62,15 @@ use serde::{Deserialize, Serialize};
 
 /// FilterExec evaluates a boolean predicate against all input batches to determine which rows to
 /// include in its output batches.
+///
+/// Unless `coalesce_filter_output` is disabled in the settings of the task, the selected rows
+/// are combined into batches of between half the batch size and the batch size.
+///
+/// While the fraction of the rows selected so far is below the
+/// `filter_slice_selectivity` of the settings, the rows selected in a batch
+/// are returned as a slice of the batch when they are contiguous, as in point
+/// lookups, instead of being copied. The slice keeps the whole batch in
+/// memory.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FilterExec {
     /// The expression to filter on. This expression must evaluate to a boolean value.
@@ -101,13 +127,9 @@ impl FilterExec {
 
 #[async_trait]
 impl LambdaExecPlan for FilterExec {
-    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) {
-        self.input = Arc::new(MemoryExec {
-            partitions,
-            projected_schema: self.schema(),
-            schema: self.schema(),
-            projection: None,
-        });
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -138,6 +160,10 @@ impl ExecutionPlan for FilterExec {
         self.input.output_partitioning()
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -153,15 +179,48 @@ impl ExecutionPlan for FilterExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "FilterExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("FilterExec", partition));
+        let batch_size = if context.session_config().coalesce_filter_output {
+            Some(context.batch_size())
+        } else {
+            None
+        };
 
-        Ok(Box::pin(FilterExecStream {
-            schema: self.input.schema().clone(),
-            predicate: self.predicate.clone(),
-            input: self.input.execute(partition).await?,
-            baseline_metrics,
-        }))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(FilterExecStream {
+                schema: self.input.schema().clone(),
+                predicate: self.predicate.clone(),
+                input: self.input.execute(partition, context).await?,
+                batch_size,
+                slice_selectivity: context.session_config().filter_slice_selectivity,
+                input_rows: 0,
+                selected_rows: 0,
+                buffer: VecDeque::new(),
+                buffered_rows: 0,
+                is_closed: false,
+                baseline_metrics,
+                avg_output_batch_rows: MetricBuilder::new(&self.metrics)
+                    .average("avg_output_batch_rows", partition),
+            }),
+        ))
     }
 
     fn fmt_as(
@@ -173,6 +232,7 @@ impl ExecutionPlan for FilterExec {
             DisplayFormatType::Default => {
                 write!(f, "FilterExec: {}", self.predicate)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -180,9 +240,305 @@ impl ExecutionPlan for FilterExec {
         Some(self.metrics.clone_inner())
     }
 
-    /// The output statistics of a filtering operation are unknown
+    /// The output statistics of a filtering operation are estimated from the
+    /// statistics of the input, see [`filter_statistics`]
     fn statistics(&self) -> Statistics {
-        Statistics::default()
+        filter_statistics(&self.predicate, self.input.statistics())
+    }
+}
+
+/// Estimates the statistics of the rows satisfying `predicate` from the
+/// `input` statistics.
+///
+/// The fraction of the rows selected by a comparison of a column with a
+/// literal assumes that the values are uniformly distributed between the
+/// minimum and maximum of the column, and that equal values are equally
+/// frequent. The predicates of a conjunction are assumed independent, and the
+/// fraction selected by a disjunction is bounded by the sum of the fractions
+/// of its predicates. The estimates are never exact.
+pub fn filter_statistics(
+    predicate: &Arc<dyn PhysicalExpr>,
+    input: Statistics,
+) -> Statistics {
+    let num_rows = match input.num_rows {
+        Some(num_rows) => num_rows,
+        None => return Statistics::default(),
+    };
+    let columns = input.column_statistics.unwrap_or_default();
+    let estimator = SelectivityEstimator {
+        num_rows,
+        columns: &columns,
+    };
+    let selectivity = match estimator.selectivity(predicate) {
+        Some(selectivity) => selectivity.max(0.0).min(1.0),
+        None => return Statistics::default(),
+    };
+    let rows = ((num_rows as f64 * selectivity).round() as usize).min(num_rows);
+
+    let column_statistics = if columns.is_empty() {
+        None
+    } else {
+        let mut columns = columns;
+        restrict_columns(predicate, &mut columns);
+        for column in columns.iter_mut() {
+            column.null_count = column.null_count.map(|count| count.min(rows));
+            column.distinct_count = column.distinct_count.map(|count| count.min(rows));
+        }
+        Some(columns)
+    };
+
+    Statistics {
+        num_rows: Some(rows),
+        total_byte_size: input
+            .total_byte_size
+            .map(|bytes| (bytes as f64 * selectivity).round() as usize),
+        column_statistics,
+        is_exact: false,
+    }
+}
+
+/// Estimates the fraction of the rows of an input satisfying a predicate
+struct SelectivityEstimator<'a> {
+    num_rows: usize,
+    columns: &'a [ColumnStatistics],
+}
+
+impl SelectivityEstimator<'_> {
+    /// The fraction of the rows satisfying `predicate`, `None` if unknown
+    fn selectivity(&self, predicate: &Arc<dyn PhysicalExpr>) -> Option<f64> {
+        let any = predicate.as_any();
+        if let Some(binary) = any.downcast_ref::<BinaryExpr>() {
+            match binary.op() {
+                Operator::And => {
+                    match (
+                        self.selectivity(binary.left()),
+                        self.selectivity(binary.right()),
+                    ) {
+                        (Some(left), Some(right)) => Some(left * right),
+                        // the fraction selected by a conjunction is at most
+                        // the fraction selected by any of its predicates
+                        (Some(known), None) | (None, Some(known)) => Some(known),
+                        (None, None) => None,
+                    }
+                }
+                Operator::Or => {
+                    let left = self.selectivity(binary.left())?;
+                    let right = self.selectivity(binary.right())?;
+                    Some((left + right).min(1.0))
+                }
+                op => {
+                    let (column, op, value) =
+                        column_comparison(binary.left(), op, binary.right())?;
+                    self.comparison(column, op, &value)
+                }
+            }
+        } else if let Some(is_null) = any.downcast_ref::<IsNullExpr>() {
+            let column = is_null.arg().as_any().downcast_ref::<Column>()?;
+            self.null_fraction(column)
+        } else if let Some(is_not_null) = any.downcast_ref::<IsNotNullExpr>() {
+            let column = is_not_null.arg().as_any().downcast_ref::<Column>()?;
+            self.null_fraction(column).map(|nulls| 1.0 - nulls)
+        } else if let Some(not) = any.downcast_ref::<NotExpr>() {
+            self.selectivity(not.arg())
+                .map(|selectivity| 1.0 - selectivity)
+        } else {
+            match literal_value(predicate)? {
+                ScalarValue::Boolean(Some(true)) => Some(1.0),
+                ScalarValue::Boolean(_) => Some(0.0),
+                _ => None,
+            }
+        }
+    }
+
+    /// The fraction of the rows where `column` is null
+    fn null_fraction(&self, column: &Column) -> Option<f64> {
+        let null_count = self.columns.get(column.index())?.null_count?;
+        if self.num_rows == 0 {
+            Some(0.0)
+        } else {
+            Some(null_count as f64 / self.num_rows as f64)
+        }
+    }
+
+    /// The fraction of the rows where `column op value` is true
+    fn comparison(
+        &self,
+        column: &Column,
+        op: Operator,
+        value: &ScalarValue,
+    ) -> Option<f64> {
+        if value.is_null() {
+            // comparisons with null are never true
+            return Some(0.0);
+        }
+        let statistics = self.columns.get(column.index())?;
+        // comparisons with nulls are never true
+        let not_null = self.null_fraction(column).map_or(1.0, |nulls| 1.0 - nulls);
+        let range = match (&statistics.min_value, &statistics.max_value) {
+            (Some(min), Some(max)) => Some((
+                scalar_to_f64(min)?,
+                scalar_to_f64(max)?,
+                scalar_to_f64(value)?,
+            )),
+            _ => None,
+        };
+
+        match op {
+            Operator::Eq => {
+                if let Some((min, max, value)) = range {
+                    if value < min || value > max {
+                        return Some(0.0);
+                    }
+                }
+                match (statistics.distinct_count, range) {
+                    (Some(distinct), _) if distinct > 0 => {
+                        Some(not_null / distinct as f64)
+                    }
+                    (_, Some((min, max, _))) if min == max => Some(not_null),
+                    _ => None,
+                }
+            }
+            Operator::NotEq => self
+                .comparison(column, Operator::Eq, value)
+                .map(|equal| not_null - equal),
+            Operator::Lt | Operator::LtEq | Operator::Gt | Operator::GtEq => {
+                let (min, max, value) = range?;
+                // the fraction of the values smaller than `value`
+                let below = if max > min {
+                    ((value - min) / (max - min)).max(0.0).min(1.0)
+                } else if value > min || (value == min && op == Operator::LtEq) {
+                    1.0
+                } else if value < min || op == Operator::Lt {
+                    0.0
+                } else {
+                    // `value` is the single value of the column
+                    return Some(if op == Operator::GtEq { not_null } else { 0.0 });
+                };
+                match op {
+                    Operator::Lt | Operator::LtEq => Some(not_null * below),
+                    _ => Some(not_null * (1.0 - below)),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Narrows the statistics of the columns compared with literals in the
+/// conjunction `predicate`
+fn restrict_columns(predicate: &Arc<dyn PhysicalExpr>, columns: &mut [ColumnStatistics]) {
+    let any = predicate.as_any();
+    if let Some(binary) = any.downcast_ref::<BinaryExpr>() {
+        if *binary.op() == Operator::And {
+            restrict_columns(binary.left(), columns);
+            restrict_columns(binary.right(), columns);
+            return;
+        }
+        let (column, op, value) =
+            match column_comparison(binary.left(), binary.op(), binary.right()) {
+                Some(comparison) => comparison,
+                None => return,
+            };
+        let statistics = match columns.get_mut(column.index()) {
+            Some(statistics) => statistics,
+            None => return,
+        };
+        if value.is_null() {
+            return;
+        }
+        // the rows where the column is null are filtered out
+        statistics.null_count = Some(0);
+        let narrows = |bound: &Option<ScalarValue>, ordering: Ordering| match bound {
+            Some(bound) => value.partial_cmp(bound) == Some(ordering),
+            None => false,
+        };
+        match op {
+            Operator::Eq => {
+                if narrows(&statistics.min_value, Ordering::Greater) {
+                    statistics.min_value = Some(value.clone());
+                }
+                if narrows(&statistics.max_value, Ordering::Less) {
+                    statistics.max_value = Some(value);
+                }
+                statistics.distinct_count = Some(1);
+            }
+            Operator::Lt | Operator::LtEq => {
+                if narrows(&statistics.max_value, Ordering::Less) {
+                    statistics.max_value = Some(value);
+                }
+            }
+            Operator::Gt | Operator::GtEq => {
+                if narrows(&statistics.min_value, Ordering::Greater) {
+                    statistics.min_value = Some(value);
+                }
+            }
+            _ => {}
+        }
+    } else if let Some(is_not_null) = any.downcast_ref::<IsNotNullExpr>() {
+        if let Some(column) = is_not_null.arg().as_any().downcast_ref::<Column>() {
+            if let Some(statistics) = columns.get_mut(column.index()) {
+                statistics.null_count = Some(0);
+            }
+        }
+    }
+}
+
+/// Matches `left op right` as `column op literal`, swapping the operands if
+/// the column is on the right
+fn column_comparison<'a>(
+    left: &'a Arc<dyn PhysicalExpr>,
+    op: &Operator,
+    right: &'a Arc<dyn PhysicalExpr>,
+) -> Option<(&'a Column, Operator, ScalarValue)> {
+    if let Some(column) = left.as_any().downcast_ref::<Column>() {
+        return Some((column, *op, literal_value(right)?));
+    }
+    let column = right.as_any().downcast_ref::<Column>()?;
+    let op = match op {
+        Operator::Lt => Operator::Gt,
+        Operator::LtEq => Operator::GtEq,
+        Operator::Gt => Operator::Lt,
+        Operator::GtEq => Operator::LtEq,
+        op => *op,
+    };
+    Some((column, op, literal_value(left)?))
+}
+
+/// The value of a literal, possibly cast to another type
+fn literal_value(expr: &Arc<dyn PhysicalExpr>) -> Option<ScalarValue> {
+    let any = expr.as_any();
+    if let Some(literal) = any.downcast_ref::<Literal>() {
+        Some(literal.value().clone())
+    } else if let Some(cast) = any.downcast_ref::<CastExpr>() {
+        let value = ColumnarValue::Scalar(literal_value(cast.expr())?);
+        match cast_column(&value, cast.cast_type(), cast.cast_options()).ok()? {
+            ColumnarValue::Scalar(value) => Some(value),
+            ColumnarValue::Array(_) => None,
+        }
+    } else {
+        None
+    }
+}
+
+/// The position of a numeric or temporal value on the real line
+fn scalar_to_f64(value: &ScalarValue) -> Option<f64> {
+    match value {
+        ScalarValue::Int8(v) => v.map(|v| v as f64),
+        ScalarValue::Int16(v) => v.map(|v| v as f64),
+        ScalarValue::Int32(v) | ScalarValue::Date32(v) => v.map(|v| v as f64),
+        ScalarValue::Int64(v)
+        | ScalarValue::Date64(v)
+        | ScalarValue::TimestampSecond(v, _)
+        | ScalarValue::TimestampMillisecond(v, _)
+        | ScalarValue::TimestampMicrosecond(v, _)
+        | ScalarValue::TimestampNanosecond(v, _) => v.map(|v| v as f64),
+        ScalarValue::UInt8(v) => v.map(|v| v as f64),
+        ScalarValue::UInt16(v) => v.map(|v| v as f64),
+        ScalarValue::UInt32(v) => v.map(|v| v as f64),
+        ScalarValue::UInt64(v) => v.map(|v| v as f64),
+        ScalarValue::Float32(v) => v.map(|v| v as f64),
+        ScalarValue::Float64(v) => *v,
+        _ => None,
     }
 }
 
@@ -195,13 +551,33 @@ struct FilterExecStream {
     predicate: Arc<dyn PhysicalExpr>,
     /// The input partition to filter.
     input: SendableRecordBatchStream,
+    /// Maximum number of rows of the output batches, which have at least half
+    /// as many rows except for the last one. `None` if a batch is returned per
+    /// input batch
+    batch_size: Option<usize>,
+    /// Observed selectivity under which contiguous selected rows are returned
+    /// as a slice of the input batch
+    slice_selectivity: f64,
+    /// Number of rows of the input batches filtered so far
+    input_rows: usize,
+    /// Number of rows selected so far
+    selected_rows: usize,
+    /// Selected rows not returned yet
+    buffer: VecDeque<RecordBatch>,
+    /// Number of rows of `buffer`
+    buffered_rows: usize,
+    /// Whether the input is exhausted
+    is_closed: bool,
     /// runtime metrics recording
     baseline_metrics: BaselineMetrics,
+    /// Average number of rows of the output batches
+    avg_output_batch_rows: metrics::Average,
 }
 
 fn batch_filter(
     batch: &RecordBatch,
     predicate: &Arc<dyn PhysicalExpr>,
+    slice: bool,
 ) -> ArrowResult<RecordBatch> {
     predicate
         .evaluate(batch)
@@ -218,10 +594,30 @@ fn batch_filter(
                     .into_arrow_external_error()
                 })
                 // apply filter array to record batch
-                .and_then(|filter_array| filter_record_batch(batch, filter_array))
+                .and_then(|filter_array| {
+                    match slice.then(|| selected_range(filter_array)).flatten() {
+                        Some(range) => Ok(batch.slice(range.start, range.len())),
+                        None => filter_record_batch(batch, filter_array),
+                    }
+                })
         })
 }
 
+/// The range of the rows selected by `filter` if they are contiguous
+fn selected_range(filter: &BooleanArray) -> Option<Range<usize>> {
+    let mut selected: Option<Range<usize>> = None;
+    for i in 0..filter.len() {
+        if filter.is_valid(i) && filter.value(i) {
+            match &mut selected {
+                Some(range) if range.end == i => range.end += 1,
+                Some(_) => return None,
+                None => selected = Some(i..i + 1),
+            }
+        }
+    }
+    Some(selected.unwrap_or(0..0))
+}
+
 impl Stream for FilterExecStream {
     type Item = ArrowResult<RecordBatch>;
 
@@ -229,21 +625,109 @@ impl Stream for FilterExecStream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
-        let poll = self.input.poll_next_unpin(cx).map(|x| match x {
-            Some(Ok(batch)) => {
-                let timer = self.baseline_metrics.elapsed_compute().timer();
-                let filtered_batch = batch_filter(&batch, &self.predicate);
-                timer.done();
-                Some(filtered_batch)
-            }
-            other => other,
-        });
+        let poll = match self.batch_size {
+            Some(batch_size) => self.poll_coalesced(cx, batch_size),
+            None => self.input.poll_next_unpin(cx).map(|x| match x {
+                Some(Ok(batch)) => Some(self.filter(&batch)),
+                other => other,
+            }),
+        };
+        if let Poll::Ready(Some(Ok(batch))) = &poll {
+            self.avg_output_batch_rows.add(batch.num_rows());
+        }
         self.baseline_metrics.record_poll(poll)
     }
 
     fn size_hint(&self) -> (usize, Option<usize>) {
-        // same number of record batches
-        self.input.size_hint()
+        match self.batch_size {
+            // at most as many record batches
+            Some(_) => (0, self.input.size_hint().1),
+            // same number of record batches
+            None => self.input.size_hint(),
+        }
+    }
+}
+
+impl FilterExecStream {
+    /// Filters `batch`, slicing it while the observed selectivity is low
+    fn filter(&mut self, batch: &RecordBatch) -> ArrowResult<RecordBatch> {
+        let slice = self.slice_selectivity > 0.0
+            && self.selected_rows as f64
+                <= self.slice_selectivity * self.input_rows as f64;
+        let timer = self.baseline_metrics.elapsed_compute().timer();
+        let filtered_batch = batch_filter(batch, &self.predicate, slice);
+        timer.done();
+        let filtered_batch = filtered_batch?;
+        self.input_rows += batch.num_rows();
+        self.selected_rows += filtered_batch.num_rows();
+        Ok(filtered_batch)
+    }
+
+    /// Filters the input batches until the selected rows reach half of
+    /// `batch_size`, and returns up to `batch_size` of them
+    fn poll_coalesced(
+        &mut self,
+        cx: &mut Context<'_>,
+        batch_size: usize,
+    ) -> Poll<Option<ArrowResult<RecordBatch>>> {
+        let min_rows = (batch_size / 2).max(1);
+        loop {
+            if self.buffered_rows >= min_rows
+                || (self.is_closed && self.buffered_rows > 0)
+            {
+                return Poll::Ready(Some(self.flush(batch_size)));
+            }
+            if self.is_closed {
+                return Poll::Ready(None);
+            }
+            match futures::ready!(self.input.poll_next_unpin(cx)) {
+                Some(Ok(batch)) => {
+                    let filtered_batch = self.filter(&batch);
+                    let filtered_batch = match filtered_batch {
+                        Ok(batch) => batch,
+                        Err(e) => return Poll::Ready(Some(Err(e))),
+                    };
+                    if filtered_batch.num_rows() > 0 {
+                        self.buffered_rows += filtered_batch.num_rows();
+                        self.buffer.push_back(filtered_batch);
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e))),
+                None => self.is_closed = true,
+            }
+        }
+    }
+
+    /// Removes the first `batch_size` buffered rows, or all of them if there
+    /// are fewer, and returns them as one batch. A single buffered batch is
+    /// returned as is, without copying it.
+    fn flush(&mut self, batch_size: usize) -> ArrowResult<RecordBatch> {
+        let _timer = self.baseline_metrics.elapsed_compute().timer();
+        let mut batches = vec![];
+        let mut rows = 0;
+        while rows < batch_size {
+            let batch = match self.buffer.pop_front() {
+                Some(batch) => batch,
+                None => break,
+            };
+            let needed = batch_size - rows;
+            if batch.num_rows() > needed {
+                // keep the rows that do not fit for the next batch
+                self.buffer
+                    .push_front(batch.slice(needed, batch.num_rows() - needed));
+                batches.push(batch.slice(0, needed));
+                rows += needed;
+            } else {
+                rows += batch.num_rows();
+                batches.push(batch);
+            }
+        }
+        self.buffered_rows -= rows;
+        if batches.len() == 1 {
+            Ok(batches.pop().unwrap())
+        } else {
+            concat_batches(&self.schema, &batches, rows)
+        }
     }
 }
 
@@ -258,17 +742,22 @@ mod tests {
 
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
     use crate::physical_plan::expressions::*;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
     use crate::physical_plan::ExecutionPlan;
     use crate::scalar::ScalarValue;
     use crate::test;
+    use crate::test::exec::StatisticsExec;
     use crate::test_util;
     use crate::{logical_plan::Operator, physical_plan::collect};
+    use arrow::array::UInt32Array;
+    use arrow::datatypes::{Field, Schema};
     use std::iter::Iterator;
 
     #[tokio::test]
     async fn simple_predicate() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let partitions = 4;
@@ -310,7 +799,7 @@ mod tests {
         let filter: Arc<dyn ExecutionPlan> =
             Arc::new(FilterExec::try_new(predicate, Arc::new(csv))?);
 
-        let results = collect(filter).await?;
+        let results = collect(filter, task_ctx).await?;
 
         results
             .iter()
@@ -320,4 +809,345 @@ mod tests {
 
         Ok(())
     }
+
+    /// Filters `num_rows` rows with values from 0, in batches of `input_rows`
+    /// rows, with `predicate` on the column `a`, and returns the number of rows
+    /// of the output batches and of the metric `avg_output_batch_rows`
+    async fn filter_batch_rows(
+        config: ExecutionConfig,
+        num_rows: u32,
+        input_rows: u32,
+        predicate: impl Fn(&Schema) -> Result<Arc<dyn PhysicalExpr>>,
+    ) -> Result<(Vec<usize>, usize)> {
+        let task_ctx = ExecutionContext::with_config(config).task_ctx();
+        let schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::UInt32, false)]));
+        let batches = (0..num_rows)
+            .step_by(input_rows as usize)
+            .map(|start| {
+                let end = (start + input_rows).min(num_rows);
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(UInt32Array::from_iter_values(start..end))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let input = MemoryExec::try_new(&[batches], schema.clone(), None)?;
+        let filter = Arc::new(FilterExec::try_new(predicate(&schema)?, Arc::new(input))?);
+
+        let results = collect(filter.clone(), task_ctx).await?;
+        let rows = results.iter().map(|batch| batch.num_rows()).collect();
+        let average = filter
+            .metrics()
+            .unwrap()
+            .sum(|metric| metric.value().name() == "avg_output_batch_rows")
+            .unwrap()
+            .as_usize();
+        Ok((rows, average))
+    }
+
+    #[tokio::test]
+    async fn selective_filter_output_batch_rows() -> Result<()> {
+        // selects 0.1% of 10M rows
+        let predicate = |schema: &Schema| {
+            binary(
+                binary(
+                    col("a", schema)?,
+                    Operator::Modulo,
+                    lit(ScalarValue::UInt32(Some(1000))),
+                    schema,
+                )?,
+                Operator::Eq,
+                lit(ScalarValue::UInt32(Some(0))),
+                schema,
+            )
+        };
+        let config = ExecutionConfig::new().with_batch_size(1024);
+        let (rows, average) =
+            filter_batch_rows(config, 10_000_000, 8192, predicate).await?;
+
+        assert_eq!(rows.iter().sum::<usize>(), 10_000);
+        let (last, others) = rows.split_last().unwrap();
+        assert!(
+            others.iter().all(|rows| (512..=1024).contains(rows)),
+            "{:?}",
+            rows
+        );
+        assert!(*last <= 1024);
+        assert_eq!(average, 10_000 / rows.len());
+
+        // a batch per input batch
+        let config = ExecutionConfig::new()
+            .with_batch_size(1024)
+            .with_coalesce_filter_output(false);
+        let (rows, _) = filter_batch_rows(config, 1_000_000, 8192, predicate).await?;
+        assert_eq!(rows.len(), 123);
+        Ok(())
+    }
+
+    #[test]
+    fn contiguous_selected_range() {
+        let range = |filter: Vec<Option<bool>>| selected_range(&filter.into());
+        assert_eq!(range(vec![Some(false), Some(true), Some(true)]), Some(1..3));
+        assert_eq!(range(vec![Some(false), None, Some(false)]), Some(0..0));
+        assert_eq!(range(vec![Some(true), None, Some(true)]), None);
+        assert_eq!(range(vec![Some(true), Some(false), Some(true)]), None);
+    }
+
+    #[tokio::test]
+    async fn point_lookup_slices_input() -> Result<()> {
+        let predicate = |schema: &Schema| {
+            binary(
+                binary(
+                    col("a", schema)?,
+                    Operator::Divide,
+                    lit(ScalarValue::UInt32(Some(10))),
+                    schema,
+                )?,
+                Operator::Eq,
+                lit(ScalarValue::UInt32(Some(4321))),
+                schema,
+            )
+        };
+        let offsets = |config: ExecutionConfig| async move {
+            let task_ctx = ExecutionContext::with_config(config).task_ctx();
+            let schema =
+                Arc::new(Schema::new(vec![Field::new("a", DataType::UInt32, false)]));
+            let batches = (0..100_000)
+                .step_by(1000)
+                .map(|start| {
+                    RecordBatch::try_new(
+                        schema.clone(),
+                        vec![Arc::new(UInt32Array::from_iter_values(
+                            start..start + 1000,
+                        ))],
+                    )
+                })
+                .collect::<ArrowResult<Vec<_>>>()?;
+            let input = MemoryExec::try_new(&[batches], schema.clone(), None)?;
+            let filter = FilterExec::try_new(predicate(&schema)?, Arc::new(input))?;
+            let results = collect(Arc::new(filter), task_ctx).await?;
+            let offsets = results
+                .iter()
+                .filter(|batch| batch.num_rows() > 0)
+                .map(|batch| (batch.num_rows(), batch.column(0).offset()))
+                .collect::<Vec<_>>();
+            Result::Ok(offsets)
+        };
+
+        // the rows 43210 to 43219 are a slice of the 44th batch
+        let config = ExecutionConfig::new();
+        assert_eq!(offsets(config).await?, vec![(10, 210)]);
+
+        let config = ExecutionConfig::new().with_filter_slice_selectivity(0.0);
+        assert_eq!(offsets(config).await?, vec![(10, 0)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn large_selections_are_split() -> Result<()> {
+        let predicate = |schema: &Schema| {
+            binary(
+                col("a", schema)?,
+                Operator::GtEq,
+                lit(ScalarValue::UInt32(Some(100))),
+                schema,
+            )
+        };
+        let config = ExecutionConfig::new().with_batch_size(1024);
+        let (rows, _) = filter_batch_rows(config, 15_000, 1500, predicate).await?;
+
+        assert_eq!(rows.iter().sum::<usize>(), 14_900);
+        assert!(
+            rows.iter().all(|rows| (512..=1024).contains(rows)),
+            "{:?}",
+            rows
+        );
+        Ok(())
+    }
+
+    fn statistics_schema() -> Schema {
+        Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Int32, true),
+        ])
+    }
+
+    /// 1000 rows where `a` takes 50 values between 0 and 100, and `b` has
+    /// 100 nulls
+    fn statistics_input() -> Arc<dyn ExecutionPlan> {
+        let statistics = Statistics {
+            num_rows: Some(1000),
+            total_byte_size: Some(8000),
+            column_statistics: Some(vec![
+                ColumnStatistics {
+                    null_count: Some(0),
+                    min_value: Some(ScalarValue::Int32(Some(0))),
+                    max_value: Some(ScalarValue::Int32(Some(100))),
+                    distinct_count: Some(50),
+                },
+                ColumnStatistics {
+                    null_count: Some(100),
+                    ..Default::default()
+                },
+            ]),
+            is_exact: true,
+        };
+        Arc::new(StatisticsExec::new(statistics, statistics_schema()))
+    }
+
+    fn estimate(predicate: Arc<dyn PhysicalExpr>) -> Result<Statistics> {
+        Ok(FilterExec::try_new(predicate, statistics_input())?.statistics())
+    }
+
+    fn compare_a(op: Operator, value: i32) -> Result<Arc<dyn PhysicalExpr>> {
+        let schema = statistics_schema();
+        binary(
+            col("a", &schema)?,
+            op,
+            lit(ScalarValue::Int32(Some(value))),
+            &schema,
+        )
+    }
+
+    #[test]
+    fn range_selectivity() -> Result<()> {
+        let statistics = estimate(compare_a(Operator::Lt, 25)?)?;
+        assert_eq!(statistics.num_rows, Some(250));
+        assert_eq!(statistics.total_byte_size, Some(2000));
+        assert!(!statistics.is_exact);
+        let columns = statistics.column_statistics.unwrap();
+        assert_eq!(columns[0].min_value, Some(ScalarValue::Int32(Some(0))));
+        assert_eq!(columns[0].max_value, Some(ScalarValue::Int32(Some(25))));
+        assert_eq!(columns[1].null_count, Some(100));
+
+        // the literal on the left
+        let schema = statistics_schema();
+        let predicate = binary(
+            lit(ScalarValue::Int32(Some(25))),
+            Operator::Gt,
+            col("a", &schema)?,
+            &schema,
+        )?;
+        assert_eq!(estimate(predicate)?.num_rows, Some(250));
+
+        let statistics = estimate(compare_a(Operator::GtEq, 60)?)?;
+        assert_eq!(statistics.num_rows, Some(400));
+        let columns = statistics.column_statistics.unwrap();
+        assert_eq!(columns[0].min_value, Some(ScalarValue::Int32(Some(60))));
+        assert_eq!(columns[0].max_value, Some(ScalarValue::Int32(Some(100))));
+
+        // out of the range of the column
+        let statistics = estimate(compare_a(Operator::Gt, 200)?)?;
+        assert_eq!(statistics.num_rows, Some(0));
+        let statistics = estimate(compare_a(Operator::Lt, 200)?)?;
+        assert_eq!(statistics.num_rows, Some(1000));
+        Ok(())
+    }
+
+    #[test]
+    fn equality_selectivity() -> Result<()> {
+        let statistics = estimate(compare_a(Operator::Eq, 42)?)?;
+        assert_eq!(statistics.num_rows, Some(20));
+        let columns = statistics.column_statistics.unwrap();
+        assert_eq!(columns[0].min_value, Some(ScalarValue::Int32(Some(42))));
+        assert_eq!(columns[0].max_value, Some(ScalarValue::Int32(Some(42))));
+        assert_eq!(columns[0].distinct_count, Some(1));
+
+        let statistics = estimate(compare_a(Operator::NotEq, 42)?)?;
+        assert_eq!(statistics.num_rows, Some(980));
+
+        let statistics = estimate(compare_a(Operator::Eq, -1)?)?;
+        assert_eq!(statistics.num_rows, Some(0));
+
+        // no statistics on `b` besides its nulls
+        let schema = statistics_schema();
+        let predicate = binary(
+            col("b", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Int32(Some(1))),
+            &schema,
+        )?;
+        assert_eq!(estimate(predicate)?, Statistics::default());
+        Ok(())
+    }
+
+    #[test]
+    fn null_selectivity() -> Result<()> {
+        let schema = statistics_schema();
+        let statistics = estimate(is_null(col("b", &schema)?)?)?;
+        assert_eq!(statistics.num_rows, Some(100));
+
+        let statistics = estimate(is_not_null(col("b", &schema)?)?)?;
+        assert_eq!(statistics.num_rows, Some(900));
+        let columns = statistics.column_statistics.unwrap();
+        assert_eq!(columns[1].null_count, Some(0));
+        Ok(())
+    }
+
+    #[test]
+    fn combined_selectivity() -> Result<()> {
+        let schema = statistics_schema();
+        let between = binary(
+            compare_a(Operator::Gt, 50)?,
+            Operator::And,
+            compare_a(Operator::LtEq, 75)?,
+            &schema,
+        )?;
+        let statistics = estimate(between)?;
+        assert_eq!(statistics.num_rows, Some(375));
+        let columns = statistics.column_statistics.unwrap();
+        assert_eq!(columns[0].min_value, Some(ScalarValue::Int32(Some(50))));
+        assert_eq!(columns[0].max_value, Some(ScalarValue::Int32(Some(75))));
+
+        let outside = binary(
+            compare_a(Operator::Lt, 10)?,
+            Operator::Or,
+            compare_a(Operator::Gt, 90)?,
+            &schema,
+        )?;
+        assert_eq!(estimate(outside)?.num_rows, Some(200));
+
+        // a disjunction selects at most all the rows
+        let any = binary(
+            compare_a(Operator::Lt, 80)?,
+            Operator::Or,
+            compare_a(Operator::Gt, 20)?,
+            &schema,
+        )?;
+        assert_eq!(estimate(any)?.num_rows, Some(1000));
+
+        // the unknown predicates of a conjunction are ignored
+        let unknown = binary(
+            col("b", &schema)?,
+            Operator::Eq,
+            lit(ScalarValue::Int32(Some(1))),
+            &schema,
+        )?;
+        let partially_known = binary(
+            compare_a(Operator::Lt, 25)?,
+            Operator::And,
+            unknown.clone(),
+            &schema,
+        )?;
+        assert_eq!(estimate(partially_known)?.num_rows, Some(250));
+        let partially_known =
+            binary(compare_a(Operator::Lt, 25)?, Operator::Or, unknown, &schema)?;
+        assert_eq!(estimate(partially_known)?, Statistics::default());
+
+        let negated = not(compare_a(Operator::Lt, 25)?, &schema)?;
+        assert_eq!(estimate(negated)?.num_rows, Some(750));
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_input_rows() -> Result<()> {
+        let input = Arc::new(StatisticsExec::new(
+            Statistics::default(),
+            statistics_schema(),
+        ));
+        let filter = FilterExec::try_new(compare_a(Operator::Lt, 25)?, input)?;
+        assert_eq!(filter.statistics(), Statistics::default());
+        Ok(())
+    }
 }
@@ -0,0 +1,173 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Deterministic fingerprints of physical plans, stable across processes and
+//! serde round trips, so that compiled query stages can be cached and
+//! deduplicated.
+//!
+//! The fingerprint of an operator hashes its name, its serialized parameters
+//! and the fingerprints of its children. The parameters that are bound to the
+//! process running the plan (execution metrics, runtime handles and object
+//! stores) are left out.
+
+use serde_json::Value;
+
+use super::ExecutionPlan;
+use crate::error::{DataFusionError, Result};
+
+/// Tag under which the name of a serialized execution plan is stored, see
+/// `#[typetag::serde(tag = "execution_plan")]` on [`ExecutionPlan`]
+pub(crate) const PLAN_TAG: &str = "execution_plan";
+
+/// Serialized fields that are not part of the identity of an operator
+pub(crate) const EXCLUDED_FIELDS: &[&str] = &["metrics", "runtime", "object_store"];
+
+/// 64-bit FNV-1a hash. Unlike the hashers of the standard library, its
+/// output is specified and does not depend on the process or the platform.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FingerprintHasher(u64);
+
+impl Default for FingerprintHasher {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl FingerprintHasher {
+    /// Hash `bytes`, prefixed with their length so that consecutive writes
+    /// cannot be confused with each other
+    pub(crate) fn write(&mut self, bytes: &[u8]) {
+        for byte in (bytes.len() as u64).to_le_bytes().iter().chain(bytes) {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    /// The hash of everything written so far
+    pub(crate) fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Compute the fingerprint of `plan`
+pub fn fingerprint(plan: &dyn ExecutionPlan) -> Result<u64> {
+    let value = serde_json::to_value(plan).map_err(|e| {
+        DataFusionError::Internal(format!("Cannot serialize the physical plan: {}", e))
+    })?;
+    Ok(fingerprint_value(&value))
+}
+
+/// Fingerprint of a serialized plan
+fn fingerprint_value(plan: &Value) -> u64 {
+    let mut hasher = FingerprintHasher::default();
+    hash_value(plan, true, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_value(value: &Value, is_root: bool, hasher: &mut FingerprintHasher) {
+    match value {
+        Value::Object(fields) if !is_root && fields.contains_key(PLAN_TAG) => {
+            // a child plan only contributes its fingerprint
+            hasher.write(b"plan");
+            hasher.write(&fingerprint_value(value).to_le_bytes());
+        }
+        Value::Object(fields) => {
+            // the order of the fields depends on the features of serde_json
+            let mut names = fields
+                .keys()
+                .filter(|name| !EXCLUDED_FIELDS.contains(&name.as_str()))
+                .collect::<Vec<_>>();
+            names.sort();
+            hasher.write(b"{");
+            for name in names {
+                hasher.write(name.as_bytes());
+                hash_value(&fields[name], false, hasher);
+            }
+            hasher.write(b"}");
+        }
+        Value::Array(values) => {
+            hasher.write(b"[");
+            for value in values {
+                hash_value(value, false, hasher);
+            }
+            hasher.write(b"]");
+        }
+        scalar => hasher.write(scalar.to_string().as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::{collect, serde};
+    use crate::test;
+    use std::sync::Arc;
+
+    async fn plan(sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut ctx = ExecutionContext::new();
+        ctx.register_table("t", test::table_with_sequence(1, 10)?)?;
+        let plan = ctx.create_logical_plan(sql)?;
+        ctx.create_physical_plan(&ctx.optimize(&plan)?).await
+    }
+
+    #[tokio::test]
+    async fn equal_plans_have_equal_fingerprints() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let sql = "SELECT i, COUNT(*) FROM t WHERE i > 3 GROUP BY i ORDER BY i";
+        let first = plan(sql).await?;
+        let second = plan(sql).await?;
+        assert_eq!(first.fingerprint()?, second.fingerprint()?);
+
+        // running the plan updates its metrics, not its fingerprint
+        let before = first.fingerprint()?;
+        collect(first.clone(), task_ctx).await?;
+        assert_eq!(before, first.fingerprint()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roundtrip_preserves_fingerprint() -> Result<()> {
+        let plan = plan("SELECT i + 1 AS j FROM t WHERE i > 3").await?;
+        let json = serde::to_json(&plan)?;
+        let roundtrip: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+        assert_eq!(plan.fingerprint()?, roundtrip.fingerprint()?);
+
+        let roundtrip = serde::from_bytes(&serde::to_bytes(&plan)?)?;
+        assert_eq!(plan.fingerprint()?, roundtrip.fingerprint()?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn literals_change_fingerprint() -> Result<()> {
+        let three = plan("SELECT i FROM t WHERE i > 3").await?;
+        let four = plan("SELECT i FROM t WHERE i > 4").await?;
+        assert_ne!(three.fingerprint()?, four.fingerprint()?);
+        Ok(())
+    }
+
+    #[test]
+    fn hasher_is_deterministic() {
+        let mut hasher = FingerprintHasher::default();
+        assert_eq!(hasher.finish(), 0xcbf2_9ce4_8422_2325);
+        hasher.write(b"ab");
+        let mut other = FingerprintHasher::default();
+        other.write(b"a");
+        other.write(b"b");
+        assert_ne!(hasher.finish(), other.finish());
+    }
+}
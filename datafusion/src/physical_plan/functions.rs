@@ -37,7 +37,8 @@ use crate::execution::context::ExecutionContextState;
 use crate::physical_plan::array_expressions;
 use crate::physical_plan::datetime_expressions;
 use crate::physical_plan::expressions::{
-    cast_column, nullif_func, DEFAULT_DATAFUSION_CAST_OPTIONS, SUPPORTED_NULLIF_TYPES,
+    cast_column, is_binary, nullif_func, octet_length_binary, Literal,
+    DEFAULT_DATAFUSION_CAST_OPTIONS, SUPPORTED_NULLIF_TYPES,
 };
 use crate::physical_plan::math_expressions;
 use crate::physical_plan::string_expressions;
@@ -47,12 +48,14 @@ use crate::{
 };
 use arrow::{
     array::{ArrayRef, NullArray},
+    compute::can_cast_types,
     compute::kernels::length::{bit_length, length},
-    datatypes::TimeUnit,
     datatypes::{DataType, Field, Int32Type, Int64Type, Schema},
+    datatypes::{IntervalUnit, TimeUnit},
     record_batch::RecordBatch,
 };
 use fmt::{Debug, Formatter};
+use futures::future::BoxFuture;
 use std::convert::From;
 use std::{any::Any, fmt, str::FromStr, sync::Arc};
 
@@ -81,7 +84,7 @@ pub enum TypeSignature {
 }
 
 ///The Signature of a function defines its supported input types as well as its volatility.
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Signature {
     /// type_signature - The types that the function accepts. See [TypeSignature] for more information.
     pub type_signature: TypeSignature,
@@ -146,7 +149,7 @@ impl Signature {
 }
 
 ///A function's volatility, which defines the functions eligibility for certain optimizations
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize)]
 pub enum Volatility {
     /// Immutable - An immutable function will always return the same output when given the same input. An example of this is [BuiltinScalarFunction::Cos].
     Immutable,
@@ -167,6 +170,13 @@ pub enum Volatility {
 pub type ScalarFunctionImplementation =
     Arc<dyn Fn(&[ColumnarValue]) -> Result<ColumnarValue> + Send + Sync>;
 
+/// The implementation of an async scalar function, called with the arrays of
+/// its arguments for a batch, which returns the future of the array of its
+/// values. Zero-argument functions are called with a null array of the size of
+/// the batch, as for [`ScalarFunctionImplementation`].
+pub type AsyncScalarFunctionImplementation =
+    Arc<dyn Fn(Vec<ArrayRef>) -> BoxFuture<'static, Result<ArrayRef>> + Send + Sync>;
+
 /// A function's return type
 pub type ReturnTypeFunction =
     Arc<dyn Fn(&[DataType]) -> Result<Arc<DataType>> + Send + Sync>;
@@ -217,8 +227,16 @@ pub enum BuiltinScalarFunction {
     // string functions
     /// construct an array from columns
     Array,
+    /// array_contains
+    ArrayContains,
+    /// array_length
+    ArrayLength,
+    /// array_position
+    ArrayPosition,
     /// ascii
     Ascii,
+    /// at_time_zone, also `timestamp AT TIME ZONE time_zone`
+    AtTimeZone,
     /// bit_length
     BitLength,
     /// btrim
@@ -235,8 +253,14 @@ pub enum BuiltinScalarFunction {
     DatePart,
     /// date_trunc
     DateTrunc,
+    /// from_unixtime
+    FromUnixtime,
     /// initcap
     InitCap,
+    /// justify_days
+    JustifyDays,
+    /// justify_hours
+    JustifyHours,
     /// left
     Left,
     /// lpad
@@ -275,6 +299,8 @@ pub enum BuiltinScalarFunction {
     SHA384,
     /// Sha512
     SHA512,
+    /// similar_to_escape
+    SimilarToEscape,
     /// split_part
     SplitPart,
     /// starts_with
@@ -293,6 +319,8 @@ pub enum BuiltinScalarFunction {
     ToTimestampMicros,
     /// to_timestamp_seconds
     ToTimestampSeconds,
+    /// to_timezone
+    ToTimezone,
     ///now
     Now,
     /// translate
@@ -374,6 +402,9 @@ impl BuiltinScalarFunction {
             // so we don't have to pay a per-array/batch cost.
             BuiltinScalarFunction::ToTimestamp => {
                 Arc::new(match args[0].data_type(input_schema) {
+                    Ok(DataType::Timestamp(_, Some(tz))) => {
+                        return Ok(cast_timestamp_fn(TimeUnit::Nanosecond, tz));
+                    }
                     Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
                         |col_values: &[ColumnarValue]| {
                             cast_column(
@@ -394,6 +425,9 @@ impl BuiltinScalarFunction {
             }
             BuiltinScalarFunction::ToTimestampMillis => {
                 Arc::new(match args[0].data_type(input_schema) {
+                    Ok(DataType::Timestamp(_, Some(tz))) => {
+                        return Ok(cast_timestamp_fn(TimeUnit::Millisecond, tz));
+                    }
                     Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
                         |col_values: &[ColumnarValue]| {
                             cast_column(
@@ -414,6 +448,9 @@ impl BuiltinScalarFunction {
             }
             BuiltinScalarFunction::ToTimestampMicros => {
                 Arc::new(match args[0].data_type(input_schema) {
+                    Ok(DataType::Timestamp(_, Some(tz))) => {
+                        return Ok(cast_timestamp_fn(TimeUnit::Microsecond, tz));
+                    }
                     Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
                         |col_values: &[ColumnarValue]| {
                             cast_column(
@@ -434,6 +471,9 @@ impl BuiltinScalarFunction {
             }
             BuiltinScalarFunction::ToTimestampSeconds => Arc::new({
                 match args[0].data_type(input_schema) {
+                    Ok(DataType::Timestamp(_, Some(tz))) => {
+                        return Ok(cast_timestamp_fn(TimeUnit::Second, tz));
+                    }
                     Ok(DataType::Int64) | Ok(DataType::Timestamp(_, None)) => {
                         |col_values: &[ColumnarValue]| {
                             cast_column(
@@ -489,7 +529,11 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::Tan => Volatility::Immutable,
             BuiltinScalarFunction::Trunc => Volatility::Immutable,
             BuiltinScalarFunction::Array => Volatility::Immutable,
+            BuiltinScalarFunction::ArrayContains => Volatility::Immutable,
+            BuiltinScalarFunction::ArrayLength => Volatility::Immutable,
+            BuiltinScalarFunction::ArrayPosition => Volatility::Immutable,
             BuiltinScalarFunction::Ascii => Volatility::Immutable,
+            BuiltinScalarFunction::AtTimeZone => Volatility::Immutable,
             BuiltinScalarFunction::BitLength => Volatility::Immutable,
             BuiltinScalarFunction::Btrim => Volatility::Immutable,
             BuiltinScalarFunction::CharacterLength => Volatility::Immutable,
@@ -498,7 +542,10 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::ConcatWithSeparator => Volatility::Immutable,
             BuiltinScalarFunction::DatePart => Volatility::Immutable,
             BuiltinScalarFunction::DateTrunc => Volatility::Immutable,
+            BuiltinScalarFunction::FromUnixtime => Volatility::Immutable,
             BuiltinScalarFunction::InitCap => Volatility::Immutable,
+            BuiltinScalarFunction::JustifyDays => Volatility::Immutable,
+            BuiltinScalarFunction::JustifyHours => Volatility::Immutable,
             BuiltinScalarFunction::Left => Volatility::Immutable,
             BuiltinScalarFunction::Lpad => Volatility::Immutable,
             BuiltinScalarFunction::Lower => Volatility::Immutable,
@@ -517,6 +564,7 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::SHA256 => Volatility::Immutable,
             BuiltinScalarFunction::SHA384 => Volatility::Immutable,
             BuiltinScalarFunction::SHA512 => Volatility::Immutable,
+            BuiltinScalarFunction::SimilarToEscape => Volatility::Immutable,
             BuiltinScalarFunction::Digest => Volatility::Immutable,
             BuiltinScalarFunction::SplitPart => Volatility::Immutable,
             BuiltinScalarFunction::StartsWith => Volatility::Immutable,
@@ -527,6 +575,7 @@ impl BuiltinScalarFunction {
             BuiltinScalarFunction::ToTimestampMillis => Volatility::Immutable,
             BuiltinScalarFunction::ToTimestampMicros => Volatility::Immutable,
             BuiltinScalarFunction::ToTimestampSeconds => Volatility::Immutable,
+            BuiltinScalarFunction::ToTimezone => Volatility::Immutable,
             BuiltinScalarFunction::Translate => Volatility::Immutable,
             BuiltinScalarFunction::Trim => Volatility::Immutable,
             BuiltinScalarFunction::Upper => Volatility::Immutable,
@@ -574,7 +623,11 @@ impl FromStr for BuiltinScalarFunction {
 
             // string functions
             "array" => BuiltinScalarFunction::Array,
+            "array_contains" => BuiltinScalarFunction::ArrayContains,
+            "array_length" => BuiltinScalarFunction::ArrayLength,
+            "array_position" => BuiltinScalarFunction::ArrayPosition,
             "ascii" => BuiltinScalarFunction::Ascii,
+            "at_time_zone" => BuiltinScalarFunction::AtTimeZone,
             "bit_length" => BuiltinScalarFunction::BitLength,
             "btrim" => BuiltinScalarFunction::Btrim,
             "char_length" => BuiltinScalarFunction::CharacterLength,
@@ -584,7 +637,10 @@ impl FromStr for BuiltinScalarFunction {
             "chr" => BuiltinScalarFunction::Chr,
             "date_part" | "datepart" => BuiltinScalarFunction::DatePart,
             "date_trunc" | "datetrunc" => BuiltinScalarFunction::DateTrunc,
+            "from_unixtime" => BuiltinScalarFunction::FromUnixtime,
             "initcap" => BuiltinScalarFunction::InitCap,
+            "justify_days" => BuiltinScalarFunction::JustifyDays,
+            "justify_hours" => BuiltinScalarFunction::JustifyHours,
             "left" => BuiltinScalarFunction::Left,
             "length" => BuiltinScalarFunction::CharacterLength,
             "lower" => BuiltinScalarFunction::Lower,
@@ -606,6 +662,7 @@ impl FromStr for BuiltinScalarFunction {
             "sha384" => BuiltinScalarFunction::SHA384,
             "sha512" => BuiltinScalarFunction::SHA512,
             "digest" => BuiltinScalarFunction::Digest,
+            "similar_to_escape" => BuiltinScalarFunction::SimilarToEscape,
             "split_part" => BuiltinScalarFunction::SplitPart,
             "starts_with" => BuiltinScalarFunction::StartsWith,
             "strpos" => BuiltinScalarFunction::Strpos,
@@ -615,6 +672,7 @@ impl FromStr for BuiltinScalarFunction {
             "to_timestamp_millis" => BuiltinScalarFunction::ToTimestampMillis,
             "to_timestamp_micros" => BuiltinScalarFunction::ToTimestampMicros,
             "to_timestamp_seconds" => BuiltinScalarFunction::ToTimestampSeconds,
+            "to_timezone" => BuiltinScalarFunction::ToTimezone,
             "now" => BuiltinScalarFunction::Now,
             "translate" => BuiltinScalarFunction::Translate,
             "trim" => BuiltinScalarFunction::Trim,
@@ -673,10 +731,19 @@ pub fn return_type(
     // the return type of the built in function.
     // Some built-in functions' return type depends on the incoming type.
     match fun {
-        BuiltinScalarFunction::Array => Ok(DataType::FixedSizeList(
-            Box::new(Field::new("item", input_expr_types[0].clone(), true)),
-            input_expr_types.len() as i32,
-        )),
+        BuiltinScalarFunction::Array => Ok(DataType::List(Box::new(Field::new(
+            "item",
+            input_expr_types[0].clone(),
+            true,
+        )))),
+        BuiltinScalarFunction::ArrayContains => {
+            check_list_types(input_expr_types, fun)?;
+            Ok(DataType::Boolean)
+        }
+        BuiltinScalarFunction::ArrayLength | BuiltinScalarFunction::ArrayPosition => {
+            check_list_types(input_expr_types, fun)?;
+            Ok(DataType::Int64)
+        }
         BuiltinScalarFunction::Ascii => Ok(DataType::Int32),
         BuiltinScalarFunction::BitLength => {
             utf8_to_int_type(&input_expr_types[0], "bit_length")
@@ -689,12 +756,21 @@ pub fn return_type(
         BuiltinScalarFunction::Concat => Ok(DataType::Utf8),
         BuiltinScalarFunction::ConcatWithSeparator => Ok(DataType::Utf8),
         BuiltinScalarFunction::DatePart => Ok(DataType::Int32),
-        BuiltinScalarFunction::DateTrunc => {
-            Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
+        BuiltinScalarFunction::DateTrunc => Ok(DataType::Timestamp(
+            TimeUnit::Nanosecond,
+            timestamp_time_zone(&input_expr_types[1]),
+        )),
+        BuiltinScalarFunction::AtTimeZone
+        | BuiltinScalarFunction::FromUnixtime
+        | BuiltinScalarFunction::ToTimezone => {
+            return_type_with_literals(fun, input_expr_types, &[])
         }
         BuiltinScalarFunction::InitCap => {
             utf8_to_str_type(&input_expr_types[0], "initcap")
         }
+        BuiltinScalarFunction::JustifyDays | BuiltinScalarFunction::JustifyHours => {
+            Ok(input_expr_types[0].clone())
+        }
         BuiltinScalarFunction::Left => utf8_to_str_type(&input_expr_types[0], "left"),
         BuiltinScalarFunction::Lower => utf8_to_str_type(&input_expr_types[0], "lower"),
         BuiltinScalarFunction::Lpad => utf8_to_str_type(&input_expr_types[0], "lpad"),
@@ -705,9 +781,11 @@ pub fn return_type(
             let coerced_types = data_types(input_expr_types, &signature(fun));
             coerced_types.map(|typs| typs[0].clone())
         }
-        BuiltinScalarFunction::OctetLength => {
-            utf8_to_int_type(&input_expr_types[0], "octet_length")
-        }
+        BuiltinScalarFunction::OctetLength => match &input_expr_types[0] {
+            DataType::Binary => Ok(DataType::Int32),
+            DataType::LargeBinary => Ok(DataType::Int64),
+            other => utf8_to_int_type(other, "octet_length"),
+        },
         BuiltinScalarFunction::Random => Ok(DataType::Float64),
         BuiltinScalarFunction::RegexpReplace => {
             utf8_to_str_type(&input_expr_types[0], "regex_replace")
@@ -737,6 +815,9 @@ pub fn return_type(
         BuiltinScalarFunction::Digest => {
             utf8_to_binary_type(&input_expr_types[0], "digest")
         }
+        BuiltinScalarFunction::SimilarToEscape => {
+            utf8_to_str_type(&input_expr_types[0], "similar_to_escape")
+        }
         BuiltinScalarFunction::SplitPart => {
             utf8_to_str_type(&input_expr_types[0], "split_part")
         }
@@ -754,18 +835,22 @@ pub fn return_type(
                 ));
             }
         }),
-        BuiltinScalarFunction::ToTimestamp => {
-            Ok(DataType::Timestamp(TimeUnit::Nanosecond, None))
-        }
-        BuiltinScalarFunction::ToTimestampMillis => {
-            Ok(DataType::Timestamp(TimeUnit::Millisecond, None))
-        }
-        BuiltinScalarFunction::ToTimestampMicros => {
-            Ok(DataType::Timestamp(TimeUnit::Microsecond, None))
-        }
-        BuiltinScalarFunction::ToTimestampSeconds => {
-            Ok(DataType::Timestamp(TimeUnit::Second, None))
-        }
+        BuiltinScalarFunction::ToTimestamp => Ok(DataType::Timestamp(
+            TimeUnit::Nanosecond,
+            timestamp_time_zone(&input_expr_types[0]),
+        )),
+        BuiltinScalarFunction::ToTimestampMillis => Ok(DataType::Timestamp(
+            TimeUnit::Millisecond,
+            timestamp_time_zone(&input_expr_types[0]),
+        )),
+        BuiltinScalarFunction::ToTimestampMicros => Ok(DataType::Timestamp(
+            TimeUnit::Microsecond,
+            timestamp_time_zone(&input_expr_types[0]),
+        )),
+        BuiltinScalarFunction::ToTimestampSeconds => Ok(DataType::Timestamp(
+            TimeUnit::Second,
+            timestamp_time_zone(&input_expr_types[0]),
+        )),
         BuiltinScalarFunction::Now => Ok(DataType::Timestamp(
             TimeUnit::Nanosecond,
             Some("UTC".to_owned()),
@@ -814,6 +899,88 @@ pub fn return_type(
     }
 }
 
+/// Returns the datatype of the scalar function, `literals` being the values of
+/// its literal arguments. Unlike [`return_type`], it knows the time zone of the
+/// functions converting timestamps to the time zone of a literal argument.
+pub fn return_type_with_literals(
+    fun: &BuiltinScalarFunction,
+    input_expr_types: &[DataType],
+    literals: &[Option<ScalarValue>],
+) -> Result<DataType> {
+    match fun {
+        BuiltinScalarFunction::AtTimeZone
+        | BuiltinScalarFunction::FromUnixtime
+        | BuiltinScalarFunction::ToTimezone => {
+            data_types(input_expr_types, &signature(fun))?;
+        }
+        _ => return return_type(fun, input_expr_types),
+    }
+
+    let time_zone = |index: usize| match literals.get(index) {
+        Some(Some(ScalarValue::Utf8(Some(time_zone)))) => {
+            datetime_expressions::parse_time_zone(time_zone)?;
+            Ok(Some(time_zone.clone()))
+        }
+        _ => Err(DataFusionError::Plan(format!(
+            "The time zone of {} must be a literal string",
+            fun
+        ))),
+    };
+    match (fun, input_expr_types.first()) {
+        (BuiltinScalarFunction::FromUnixtime, _) if input_expr_types.len() == 1 => Ok(
+            DataType::Timestamp(TimeUnit::Second, Some("UTC".to_owned())),
+        ),
+        (BuiltinScalarFunction::FromUnixtime, _) => {
+            Ok(DataType::Timestamp(TimeUnit::Second, time_zone(1)?))
+        }
+        // the wall clock time of a timestamp with a time zone
+        (BuiltinScalarFunction::AtTimeZone, Some(DataType::Timestamp(unit, Some(_)))) => {
+            time_zone(1)?;
+            Ok(DataType::Timestamp(unit.clone(), None))
+        }
+        (BuiltinScalarFunction::AtTimeZone, Some(DataType::Timestamp(unit, None)))
+        | (BuiltinScalarFunction::ToTimezone, Some(DataType::Timestamp(unit, _))) => {
+            Ok(DataType::Timestamp(unit.clone(), time_zone(1)?))
+        }
+        (_, other) => Err(DataFusionError::Plan(format!(
+            "The function {} expects a timestamp, got {:?}",
+            fun, other
+        ))),
+    }
+}
+
+/// Time zone of the timestamps of type `data_type`
+fn timestamp_time_zone(data_type: &DataType) -> Option<String> {
+    match data_type {
+        DataType::Timestamp(_, tz_opt) => tz_opt.clone(),
+        _ => None,
+    }
+}
+
+/// Checks that the first argument of the list function `fun` is a list, and
+/// that its second argument, if any, can be cast to the type of its elements
+fn check_list_types(
+    input_expr_types: &[DataType],
+    fun: &BuiltinScalarFunction,
+) -> Result<()> {
+    let element_type = array_expressions::list_element_type(&input_expr_types[0])
+        .ok_or_else(|| {
+            DataFusionError::Plan(format!(
+                "The function {} requires a list, found {:?}",
+                fun, input_expr_types[0]
+            ))
+        })?;
+    match input_expr_types.get(1) {
+        Some(value_type) if !can_cast_types(value_type, element_type) => {
+            Err(DataFusionError::Plan(format!(
+                "The function {} can not compare values of type {:?} to elements of type {:?}",
+                fun, value_type, element_type
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(feature = "crypto_expressions")]
 macro_rules! invoke_if_crypto_expressions_feature_flag {
     ($FUNC:ident, $NAME:expr) => {{
@@ -874,6 +1041,15 @@ macro_rules! invoke_if_unicode_expressions_feature_flag {
     };
 }
 
+/// Implementation of the `to_timestamp` functions of the timestamps of the time
+/// zone `tz`, converted to `unit` in the same time zone
+fn cast_timestamp_fn(unit: TimeUnit, tz: String) -> ScalarFunctionImplementation {
+    let data_type = DataType::Timestamp(unit, Some(tz));
+    Arc::new(move |col_values: &[ColumnarValue]| {
+        cast_column(&col_values[0], &data_type, &DEFAULT_DATAFUSION_CAST_OPTIONS)
+    })
+}
+
 /// Create a physical scalar function.
 pub fn create_physical_fun(
     fun: &BuiltinScalarFunction
@@ -901,6 +1077,15 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::Trunc => Arc::new(math_expressions::trunc),
         // string functions
         BuiltinScalarFunction::Array => Arc::new(array_expressions::array),
+        BuiltinScalarFunction::ArrayContains => {
+            make_scalar_function(array_expressions::array_contains)
+        }
+        BuiltinScalarFunction::ArrayLength => {
+            make_scalar_function(array_expressions::array_length)
+        }
+        BuiltinScalarFunction::ArrayPosition => {
+            make_scalar_function(array_expressions::array_position)
+        }
         BuiltinScalarFunction::Ascii => Arc::new(|args| match args[0].data_type() {
             DataType::Utf8 => {
                 make_scalar_function(string_expressions::ascii::<i32>)(args)
@@ -970,6 +1155,17 @@ pub fn create_physical_fun(
         }
         BuiltinScalarFunction::DatePart => Arc::new(datetime_expressions::date_part),
         BuiltinScalarFunction::DateTrunc => Arc::new(datetime_expressions::date_trunc),
+        BuiltinScalarFunction::AtTimeZone => Arc::new(datetime_expressions::at_time_zone),
+        BuiltinScalarFunction::ToTimezone => Arc::new(datetime_expressions::to_timezone),
+        BuiltinScalarFunction::FromUnixtime => {
+            Arc::new(datetime_expressions::from_unixtime)
+        }
+        BuiltinScalarFunction::JustifyDays => {
+            Arc::new(datetime_expressions::justify_days)
+        }
+        BuiltinScalarFunction::JustifyHours => {
+            Arc::new(datetime_expressions::justify_hours)
+        }
         BuiltinScalarFunction::Now => {
             // bind value for now at plan time
             Arc::new(datetime_expressions::make_now(
@@ -1037,8 +1233,17 @@ pub fn create_physical_fun(
         }
         BuiltinScalarFunction::NullIf => Arc::new(nullif_func),
         BuiltinScalarFunction::OctetLength => Arc::new(|args| match &args[0] {
+            ColumnarValue::Array(v) if is_binary(v.data_type()) => {
+                Ok(ColumnarValue::Array(octet_length_binary(v.as_ref())?))
+            }
             ColumnarValue::Array(v) => Ok(ColumnarValue::Array(length(v.as_ref())?)),
             ColumnarValue::Scalar(v) => match v {
+                ScalarValue::Binary(v) => Ok(ColumnarValue::Scalar(ScalarValue::Int32(
+                    v.as_ref().map(|x| x.len() as i32),
+                ))),
+                ScalarValue::LargeBinary(v) => Ok(ColumnarValue::Scalar(
+                    ScalarValue::Int64(v.as_ref().map(|x| x.len() as i64)),
+                )),
                 ScalarValue::Utf8(v) => Ok(ColumnarValue::Scalar(ScalarValue::Int32(
                     v.as_ref().map(|x| x.len() as i32),
                 ))),
@@ -1190,6 +1395,20 @@ pub fn create_physical_fun(
         BuiltinScalarFunction::SHA512 => {
             Arc::new(invoke_if_crypto_expressions_feature_flag!(sha512, "sha512"))
         }
+        BuiltinScalarFunction::SimilarToEscape => {
+            Arc::new(|args| match args[0].data_type() {
+                DataType::Utf8 => make_scalar_function(
+                    string_expressions::similar_to_escape::<i32>,
+                )(args),
+                DataType::LargeUtf8 => make_scalar_function(
+                    string_expressions::similar_to_escape::<i64>,
+                )(args),
+                other => Err(DataFusionError::Internal(format!(
+                    "Unsupported data type {:?} for function similar_to_escape",
+                    other,
+                ))),
+            })
+        }
         BuiltinScalarFunction::SplitPart => Arc::new(|args| match args[0].data_type() {
             DataType::Utf8 => {
                 make_scalar_function(string_expressions::split_part::<i32>)(args)
@@ -1319,7 +1538,16 @@ pub fn create_physical_expr(
         .map(|e| e.data_type(input_schema))
         .collect::<Result<Vec<_>>>()?;
 
-    let data_type = return_type(fun, &coerced_expr_types)?;
+    let literals = coerced_phy_exprs
+        .iter()
+        .map(|e| {
+            e.as_any()
+                .downcast_ref::<Literal>()
+                .map(|literal| literal.value().clone())
+        })
+        .collect::<Vec<_>>();
+
+    let data_type = return_type_with_literals(fun, &coerced_expr_types, &literals)?;
 
     Ok(Arc::new(ScalarFunctionExpr::new(
         &format!("{}", fun),
@@ -1339,6 +1567,12 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             array_expressions::SUPPORTED_ARRAY_TYPES.to_vec(),
             fun.volatility(),
         ),
+        // the type of the list is checked by `return_type`, and the value is
+        // cast to the type of its elements on evaluation
+        BuiltinScalarFunction::ArrayLength => Signature::any(1, fun.volatility()),
+        BuiltinScalarFunction::ArrayContains | BuiltinScalarFunction::ArrayPosition => {
+            Signature::any(2, fun.volatility())
+        }
         BuiltinScalarFunction::Concat | BuiltinScalarFunction::ConcatWithSeparator => {
             Signature::variadic(vec![DataType::Utf8], fun.volatility())
         }
@@ -1348,7 +1582,6 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
         | BuiltinScalarFunction::InitCap
         | BuiltinScalarFunction::Lower
         | BuiltinScalarFunction::MD5
-        | BuiltinScalarFunction::OctetLength
         | BuiltinScalarFunction::Reverse
         | BuiltinScalarFunction::SHA224
         | BuiltinScalarFunction::SHA256
@@ -1360,6 +1593,18 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             vec![DataType::Utf8, DataType::LargeUtf8],
             fun.volatility(),
         ),
+        // the binary types come first for the fixed size binary values to be
+        // coerced to binary values rather than strings
+        BuiltinScalarFunction::OctetLength => Signature::uniform(
+            1,
+            vec![
+                DataType::Binary,
+                DataType::LargeBinary,
+                DataType::Utf8,
+                DataType::LargeUtf8,
+            ],
+            fun.volatility(),
+        ),
         BuiltinScalarFunction::Btrim
         | BuiltinScalarFunction::Ltrim
         | BuiltinScalarFunction::Rtrim => Signature::one_of(
@@ -1455,6 +1700,27 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
         BuiltinScalarFunction::Digest => {
             Signature::exact(vec![DataType::Utf8, DataType::Utf8], fun.volatility())
         }
+        // the time zone of the timestamp is any
+        BuiltinScalarFunction::AtTimeZone | BuiltinScalarFunction::ToTimezone => {
+            Signature::any(2, fun.volatility())
+        }
+        BuiltinScalarFunction::FromUnixtime => Signature::one_of(
+            vec![
+                TypeSignature::Exact(vec![DataType::Int64]),
+                TypeSignature::Exact(vec![DataType::Int64, DataType::Utf8]),
+            ],
+            fun.volatility(),
+        ),
+        BuiltinScalarFunction::JustifyDays | BuiltinScalarFunction::JustifyHours => {
+            Signature::uniform(
+                1,
+                vec![
+                    DataType::Interval(IntervalUnit::YearMonth),
+                    DataType::Interval(IntervalUnit::DayTime),
+                ],
+                fun.volatility(),
+            )
+        }
         BuiltinScalarFunction::DateTrunc => Signature::exact(
             vec![
                 DataType::Utf8,
@@ -1485,6 +1751,15 @@ fn signature(fun: &BuiltinScalarFunction) -> Signature {
             ],
             fun.volatility(),
         ),
+        BuiltinScalarFunction::SimilarToEscape => Signature::one_of(
+            vec![
+                TypeSignature::Exact(vec![DataType::Utf8]),
+                TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8]),
+                TypeSignature::Exact(vec![DataType::LargeUtf8]),
+                TypeSignature::Exact(vec![DataType::LargeUtf8, DataType::LargeUtf8]),
+            ],
+            fun.volatility(),
+        ),
         BuiltinScalarFunction::SplitPart => Signature::one_of(
             vec![
                 TypeSignature::Exact(vec![
@@ -1768,8 +2043,8 @@ mod tests {
     };
     use arrow::{
         array::{
-            Array, ArrayRef, BinaryArray, BooleanArray, FixedSizeListArray, Float32Array,
-            Float64Array, Int32Array, StringArray, UInt32Array, UInt64Array,
+            Array, ArrayRef, BinaryArray, BooleanArray, Float32Array, Float64Array,
+            Int32Array, Int64Array, ListArray, StringArray, UInt32Array, UInt64Array,
         },
         datatypes::Field,
         record_batch::RecordBatch,
@@ -2658,6 +2933,30 @@ mod tests {
             Int32,
             Int32Array
         );
+        test_function!(
+            OctetLength,
+            &[lit(ScalarValue::Binary(Some(vec![0xde, 0xad, 0xbe, 0xef])))],
+            Ok(Some(4)),
+            i32,
+            Int32,
+            Int32Array
+        );
+        test_function!(
+            OctetLength,
+            &[lit(ScalarValue::LargeBinary(Some(vec![0xff])))],
+            Ok(Some(1)),
+            i64,
+            Int64,
+            Int64Array
+        );
+        test_function!(
+            OctetLength,
+            &[lit(ScalarValue::FixedSizeBinary(2, Some(vec![0xde, 0xad])))],
+            Ok(Some(2)),
+            i32,
+            Int32,
+            Int32Array
+        );
         test_function!(
             OctetLength,
             &[lit(ScalarValue::Utf8(None))],
@@ -3454,6 +3753,61 @@ mod tests {
             Binary,
             BinaryArray
         );
+        test_function!(
+            SimilarToEscape,
+            &[lit(ScalarValue::Utf8(Some("a%(b|c)_.\\%".to_string())))],
+            Ok(Some("^(?:a.*(?:b|c).\\.%)$")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SimilarToEscape,
+            &[
+                lit(ScalarValue::Utf8(Some("[a-c%]#_+$".to_string()))),
+                lit(ScalarValue::Utf8(Some("#".to_string()))),
+            ],
+            Ok(Some("^(?:[a-c%]_+\\$)$")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SimilarToEscape,
+            &[
+                lit(ScalarValue::Utf8(Some("a\\%".to_string()))),
+                lit(ScalarValue::Utf8(Some("".to_string()))),
+            ],
+            Ok(Some("^(?:a\\\\.*)$")),
+            &str,
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SimilarToEscape,
+            &[
+                lit(ScalarValue::Utf8(Some("a%".to_string()))),
+                lit(ScalarValue::Utf8(None)),
+            ],
+            Ok(None),
+            &str,
+            Utf8,
+            StringArray
+        );
+        test_function!(
+            SimilarToEscape,
+            &[
+                lit(ScalarValue::Utf8(Some("a%".to_string()))),
+                lit(ScalarValue::Utf8(Some("ab".to_string()))),
+            ],
+            Err(DataFusionError::Execution(
+                "The escape string of SIMILAR TO must be empty or one character, found 'ab'"
+                    .to_string()
+            )),
+            &str,
+            Utf8,
+            StringArray
+        );
         test_function!(
             SplitPart,
             &[
@@ -4032,7 +4386,7 @@ mod tests {
         assert_eq!(
             expr.data_type(&schema)?,
             // type equals to a common coercion
-            DataType::FixedSizeList(Box::new(Field::new("item", expected_type, true)), 2)
+            DataType::List(Box::new(Field::new("item", expected_type, true)))
         );
 
         // evaluate works
@@ -4040,10 +4394,7 @@ mod tests {
         let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
 
         // downcast works
-        let result = result
-            .as_any()
-            .downcast_ref::<FixedSizeListArray>()
-            .unwrap();
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
 
         // value is correct
         assert_eq!(format!("{:?}", result.value(0)), expected);
@@ -4077,6 +4428,143 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_array_scalars() -> Result<()> {
+        let ctx_state = ExecutionContextState::new();
+        let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
+        let expr = create_physical_expr(
+            &BuiltinScalarFunction::Array,
+            &[lit(ScalarValue::Int32(Some(1))), col("a", &schema)?],
+            &schema,
+            &ctx_state,
+        )?;
+
+        let columns: Vec<ArrayRef> = vec![Arc::new(Int32Array::from(vec![2, 3]))];
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+        let result = expr.evaluate(&batch)?.into_array(batch.num_rows());
+        let result = result.as_any().downcast_ref::<ListArray>().unwrap();
+        let expected = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![Some(1), Some(3)]),
+        ]);
+        assert_eq!(result, &expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_functions() -> Result<()> {
+        let ctx_state = ExecutionContextState::new();
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2), None, Some(2)]),
+            Some(vec![]),
+            None,
+            Some(vec![None, Some(3)]),
+        ]);
+        let schema = Schema::new(vec![Field::new("a", list.data_type().clone(), true)]);
+        let columns: Vec<ArrayRef> = vec![Arc::new(list)];
+        let batch = RecordBatch::try_new(Arc::new(schema.clone()), columns)?;
+        // the value is cast to the type of the elements
+        let two = lit(ScalarValue::Int64(Some(2)));
+
+        let evaluate = |fun: BuiltinScalarFunction,
+                        args: &[Arc<dyn PhysicalExpr>]|
+         -> Result<ArrayRef> {
+            let expr = create_physical_expr(&fun, args, &schema, &ctx_state)?;
+            Ok(expr.evaluate(&batch)?.into_array(batch.num_rows()))
+        };
+
+        let result = evaluate(BuiltinScalarFunction::ArrayLength, &[col("a", &schema)?])?;
+        let expected = Int64Array::from(vec![Some(4), Some(0), None, Some(2)]);
+        assert_eq!(
+            result.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &expected
+        );
+
+        let args = [col("a", &schema)?, two.clone()];
+        let result = evaluate(BuiltinScalarFunction::ArrayContains, &args)?;
+        let expected =
+            BooleanArray::from(vec![Some(true), Some(false), None, Some(false)]);
+        assert_eq!(
+            result.as_any().downcast_ref::<BooleanArray>().unwrap(),
+            &expected
+        );
+
+        let args = [col("a", &schema)?, two];
+        let result = evaluate(BuiltinScalarFunction::ArrayPosition, &args)?;
+        let expected = Int64Array::from(vec![Some(2), None, None, None]);
+        assert_eq!(
+            result.as_any().downcast_ref::<Int64Array>().unwrap(),
+            &expected
+        );
+
+        // a null value is never found
+        let args = [col("a", &schema)?, lit(ScalarValue::Int32(None))];
+        let result = evaluate(BuiltinScalarFunction::ArrayContains, &args)?;
+        assert_eq!(result.null_count(), 4);
+
+        // the first argument must be a list
+        let err = evaluate(
+            BuiltinScalarFunction::ArrayLength,
+            &[lit(ScalarValue::Int32(Some(1)))],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("requires a list, found Int32"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_zone_return_types() -> Result<()> {
+        let tz = |name: &str| Some(name.to_owned());
+        let zone = |name: &str| Some(ScalarValue::Utf8(tz(name)));
+        let naive = DataType::Timestamp(TimeUnit::Millisecond, None);
+        let aware = DataType::Timestamp(TimeUnit::Millisecond, tz("UTC"));
+        let cases = vec![
+            (
+                BuiltinScalarFunction::AtTimeZone,
+                vec![naive.clone(), DataType::Utf8],
+                DataType::Timestamp(TimeUnit::Millisecond, tz("Europe/Paris")),
+            ),
+            (
+                BuiltinScalarFunction::AtTimeZone,
+                vec![aware.clone(), DataType::Utf8],
+                naive.clone(),
+            ),
+            (
+                BuiltinScalarFunction::ToTimezone,
+                vec![aware, DataType::Utf8],
+                DataType::Timestamp(TimeUnit::Millisecond, tz("Europe/Paris")),
+            ),
+            (
+                BuiltinScalarFunction::FromUnixtime,
+                vec![DataType::Int64, DataType::Utf8],
+                DataType::Timestamp(TimeUnit::Second, tz("Europe/Paris")),
+            ),
+        ];
+        for (fun, types, expected) in cases {
+            let literals = vec![None, zone("Europe/Paris")];
+            assert_eq!(
+                return_type_with_literals(&fun, &types, &literals)?,
+                expected
+            );
+        }
+        assert_eq!(
+            return_type_with_literals(
+                &BuiltinScalarFunction::FromUnixtime,
+                &[DataType::Int64],
+                &[None]
+            )?,
+            DataType::Timestamp(TimeUnit::Second, tz("UTC"))
+        );
+
+        // the time zone must be a valid literal
+        let fun = BuiltinScalarFunction::AtTimeZone;
+        let types = vec![naive, DataType::Utf8];
+        assert!(return_type_with_literals(&fun, &types, &[None, None]).is_err());
+        let literals = vec![None, zone("Mars/Olympus_Mons")];
+        assert!(return_type_with_literals(&fun, &types, &literals).is_err());
+        Ok(())
+    }
+
     #[test]
     #[cfg(feature = "regex_expressions")]
     fn test_regexp_match() -> Result<()> {
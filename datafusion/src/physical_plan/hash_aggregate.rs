@@ -29,12 +29,17 @@ use futures::{
 };
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::memory_manager::{MemoryConsumer, MemoryManager};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
 use crate::physical_plan::hash_utils::create_hashes;
 use crate::physical_plan::{
-    Accumulator, AggregateExpr, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, PhysicalExpr, LambdaExecPlan,
+    fmt_with_statistics, Accumulator, AggregateExpr, DisplayFormatType, Distribution,
+    ExecutionPlan, LambdaExecPlan, Partitioning, PhysicalExpr,
 };
-use crate::physical_plan::memory::MemoryExec;
+use crate::physical_plan::memory::{feed_input, MemoryExec};
+use crate::physical_plan::trace::instrument_stream;
 use crate::scalar::ScalarValue;
 
 use arrow::{array::ArrayRef, compute, compute::cast};
@@ -52,10 +57,16 @@ use pin_project_lite::pin_project;
 use async_trait::async_trait;
 
 use serde::{Deserialize, Serialize};
+use super::aggregates::{
+    check_state_layout, STATE_LAYOUT_VERSION, STATE_LAYOUT_VERSION_KEY,
+};
 use super::common::AbortOnDropSingle;
 use super::metrics::{
-    self, BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, RecordOutput,
+    self, BaselineMetrics, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+    RecordOutput,
 };
+use super::projection::get_field_metadata;
+use super::row_aggregate::{RowGroups, RowLayout};
 use super::Statistics;
 use super::{expressions::Column, RecordBatchStream, SendableRecordBatchStream};
 
@@ -73,6 +84,21 @@ pub enum AggregateMode {
     /// with Hash repartitioning on the group keys. If a group key is
     /// duplicated, duplicate groups would be produced
     FinalPartitioned,
+    /// Single-phase aggregate that works on pre-partitioned input.
+    ///
+    /// Like `FinalPartitioned`, this requires that all rows with a
+    /// particular grouping key are in the same partition, but the input
+    /// holds raw rows rather than partial states, so no partial
+    /// aggregate or exchange is needed before it
+    SinglePartitioned,
+}
+
+impl AggregateMode {
+    /// Whether the input of this mode holds the states of a partial
+    /// aggregate, which are merged, rather than raw rows
+    pub fn merges_states(&self) -> bool {
+        matches!(self, AggregateMode::Final | AggregateMode::FinalPartitioned)
+    }
 }
 
 /// Hash aggregate execution plan
@@ -94,6 +120,12 @@ pub struct HashAggregateExec {
     input_schema: SchemaRef,
     /// Execution Metrics
     metrics: ExecutionPlanMetricsSet,
+    /// Memory manager the groups are reserved from, bound by the runtime
+    #[serde(skip)]
+    memory_manager: Option<Arc<MemoryManager>>,
+    /// Token cancelling the aggregation of the input, bound by the runtime
+    #[serde(skip)]
+    cancellation: CancellationToken,
 }
 
 fn create_schema(
@@ -104,11 +136,13 @@ fn create_schema(
 ) -> Result<Schema> {
     let mut fields = Vec::with_capacity(group_expr.len() + aggr_expr.len());
     for (expr, name) in group_expr {
-        fields.push(Field::new(
+        let mut field = Field::new(
             name,
             expr.data_type(input_schema)?,
             expr.nullable(input_schema)?,
-        ))
+        );
+        field.set_metadata(get_field_metadata(expr, input_schema));
+        fields.push(field)
     }
 
     match mode {
@@ -117,16 +151,27 @@ fn create_schema(
             for expr in aggr_expr {
                 fields.extend(expr.state_fields()?.iter().cloned())
             }
+            // record the layout of the states so that a final aggregation
+            // running in another process can tell whether it can merge them
+            let metadata = [(
+                STATE_LAYOUT_VERSION_KEY.to_owned(),
+                STATE_LAYOUT_VERSION.to_string(),
+            )]
+            .iter()
+            .cloned()
+            .collect();
+            Ok(Schema::new_with_metadata(fields, metadata))
         }
-        AggregateMode::Final | AggregateMode::FinalPartitioned => {
+        AggregateMode::Final
+        | AggregateMode::FinalPartitioned
+        | AggregateMode::SinglePartitioned => {
             // in final mode, the field with the final result of the accumulator
             for expr in aggr_expr {
                 fields.push(expr.field()?)
             }
+            Ok(Schema::new(fields))
         }
     }
-
-    Ok(Schema::new(fields))
 }
 
 impl HashAggregateExec {
@@ -138,6 +183,9 @@ impl HashAggregateExec {
         input: Arc<dyn ExecutionPlan>,
         input_schema: SchemaRef,
     ) -> Result<Self> {
+        if mode.merges_states() {
+            check_state_layout(&input.schema())?;
+        }
         let schema = create_schema(&input.schema(), &group_expr, &aggr_expr, mode)?;
 
         let schema = Arc::new(schema);
@@ -150,6 +198,8 @@ impl HashAggregateExec {
             schema,
             input_schema,
             metrics: ExecutionPlanMetricsSet::new(),
+            memory_manager: None,
+            cancellation: CancellationToken::new(),
         })
     }
 
@@ -168,6 +218,8 @@ impl HashAggregateExec {
             schema: self.schema().clone(),
             input_schema: self.input_schema().clone(),
             metrics: self.metrics.clone(),
+            memory_manager: self.memory_manager.clone(),
+            cancellation: self.cancellation.clone(),
         })
     }
 
@@ -199,13 +251,9 @@ impl HashAggregateExec {
 
 #[async_trait]
 impl LambdaExecPlan for HashAggregateExec {
-    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) {
-        self.input = Arc::new(MemoryExec {
-            partitions,
-            projected_schema: self.schema(),
-            schema: self.schema(),
-            projection: None,
-        });
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -232,9 +280,11 @@ impl ExecutionPlan for HashAggregateExec {
     fn required_child_distribution(&self) -> Distribution {
         match &self.mode {
             AggregateMode::Partial => Distribution::UnspecifiedDistribution,
-            AggregateMode::FinalPartitioned => Distribution::HashPartitioned(
-                self.group_expr.iter().map(|x| x.0.clone()).collect(),
-            ),
+            AggregateMode::FinalPartitioned | AggregateMode::SinglePartitioned => {
+                Distribution::HashPartitioned(
+                    self.group_expr.iter().map(|x| x.0.clone()).collect(),
+                )
+            }
             AggregateMode::Final => Distribution::SinglePartition,
         }
     }
@@ -244,29 +294,70 @@ impl ExecutionPlan for HashAggregateExec {
         self.input.output_partitioning()
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let input = self.input.execute(partition).await?;
-        let group_expr = self.group_expr.iter().map(|x| x.0.clone()).collect();
-
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "HashAggregateExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("HashAggregateExec", partition));
+        let row_aggregation = context.session_config().row_aggregation;
+        let input = self.input.execute(partition, context).await?;
+        let group_expr: Vec<Arc<dyn PhysicalExpr>> =
+            self.group_expr.iter().map(|x| x.0.clone()).collect();
 
         if self.group_expr.is_empty() {
-            Ok(Box::pin(HashAggregateStream::new(
-                self.mode,
-                self.schema.clone(),
-                self.aggr_expr.clone(),
-                input,
-                baseline_metrics,
-            )))
+            Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(HashAggregateStream::new(
+                    self.mode,
+                    self.schema.clone(),
+                    self.aggr_expr.clone(),
+                    input,
+                    baseline_metrics,
+                    self.cancellation.clone(),
+                )),
+            ))
         } else {
-            Ok(Box::pin(GroupedHashAggregateStream::new(
-                self.mode,
-                self.schema.clone(),
-                group_expr,
-                self.aggr_expr.clone(),
-                input,
-                baseline_metrics,
-            )))
+            let row_layout = if row_aggregation {
+                RowLayout::try_new(&group_expr, &self.aggr_expr, &self.input.schema())?
+            } else {
+                None
+            };
+            // the groups cannot spill, the aggregation fails if they do not
+            // fit in the memory pool
+            let consumer = self.memory_manager.as_ref().map(|manager| {
+                manager.register_consumer(
+                    format!("HashAggregateExec[{}]", partition),
+                    MetricBuilder::new(&self.metrics).mem_used(partition),
+                )
+            });
+            Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(GroupedHashAggregateStream::new(
+                    self.mode,
+                    self.schema.clone(),
+                    group_expr,
+                    self.aggr_expr.clone(),
+                    row_layout,
+                    input,
+                    baseline_metrics,
+                    consumer,
+                    self.cancellation.clone(),
+                )),
+            ))
         }
     }
 
@@ -275,13 +366,18 @@ impl ExecutionPlan for HashAggregateExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(HashAggregateExec::try_new(
-                self.mode,
-                self.group_expr.clone(),
-                self.aggr_expr.clone(),
-                children[0].clone(),
-                self.input_schema.clone(),
-            )?)),
+            1 => {
+                let mut aggregate = HashAggregateExec::try_new(
+                    self.mode,
+                    self.group_expr.clone(),
+                    self.aggr_expr.clone(),
+                    children[0].clone(),
+                    self.input_schema.clone(),
+                )?;
+                aggregate.memory_manager = self.memory_manager.clone();
+                aggregate.cancellation = self.cancellation.clone();
+                Ok(Arc::new(aggregate))
+            }
             _ => Err(DataFusionError::Internal(
                 "HashAggregateExec wrong number of children".to_string(),
             )),
@@ -321,6 +417,7 @@ impl ExecutionPlan for HashAggregateExec {
                     .collect();
                 write!(f, ", aggr=[{}]", a.join(", "))?;
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
         Ok(())
     }
@@ -332,7 +429,9 @@ impl ExecutionPlan for HashAggregateExec {
         // TODO stats: aggr expression:
         // - aggregations somtimes also preserve invariants such as min, max...
         match self.mode {
-            AggregateMode::Final | AggregateMode::FinalPartitioned
+            AggregateMode::Final
+            | AggregateMode::FinalPartitioned
+            | AggregateMode::SinglePartitioned
                 if self.group_expr.is_empty() =>
             {
                 Statistics {
@@ -344,6 +443,22 @@ impl ExecutionPlan for HashAggregateExec {
             _ => Statistics::default(),
         }
     }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut aggregate = HashAggregateExec::try_new(
+            self.mode,
+            self.group_expr.clone(),
+            self.aggr_expr.clone(),
+            self.input.clone(),
+            self.input_schema.clone(),
+        )?;
+        aggregate.memory_manager = runtime.memory_manager.clone();
+        aggregate.cancellation = runtime.cancellation.clone();
+        Ok(Some(Arc::new(aggregate)))
+    }
 }
 
 /*
@@ -390,6 +505,10 @@ fn group_aggregate_batch(
     mut accumulators: Accumulators,
     aggregate_expressions: &[Vec<Arc<dyn PhysicalExpr>>],
 ) -> Result<Accumulators> {
+    if mode.merges_states() {
+        check_state_layout(&batch.schema())?;
+    }
+
     // evaluate the grouping expressions
     let group_values = evaluate(group_expr, &batch)?;
 
@@ -519,7 +638,9 @@ fn group_aggregate_batch(
                     )
                 })
                 .try_for_each(|(accumulator, values)| match mode {
-                    AggregateMode::Partial => accumulator.update_batch(&values),
+                    AggregateMode::Partial | AggregateMode::SinglePartitioned => {
+                        accumulator.update_batch(&values)
+                    }
                     AggregateMode::FinalPartitioned | AggregateMode::Final => {
                         // note: the aggregation here is over states, not values, thus the merge
                         accumulator.merge_batch(&values)
@@ -542,6 +663,8 @@ async fn compute_grouped_hash_aggregate(
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     mut input: SendableRecordBatchStream,
     elapsed_compute: metrics::Time,
+    mut consumer: Option<MemoryConsumer>,
+    cancellation: CancellationToken,
 ) -> ArrowResult<RecordBatch> {
     let timer = elapsed_compute.timer();
     // The expressions to evaluate the batch, one vec of expressions per aggregation.
@@ -552,13 +675,18 @@ async fn compute_grouped_hash_aggregate(
             .map_err(DataFusionError::into_arrow_external_error)?;
 
     let random_state = RandomState::new();
+    let group_size = estimated_group_size(group_expr.len(), aggr_expr.len());
 
     // iterate over all input batches and update the accumulators
     let mut accumulators = Accumulators::default();
     timer.done();
     while let Some(batch) = input.next().await {
         let batch = batch?;
+        cancellation
+            .check()
+            .map_err(DataFusionError::into_arrow_external_error)?;
         let timer = elapsed_compute.timer();
+        let num_groups = accumulators.group_states.len();
         accumulators = group_aggregate_batch(
             &mode,
             &random_state,
@@ -569,6 +697,12 @@ async fn compute_grouped_hash_aggregate(
             &aggregate_expressions,
         )
         .map_err(DataFusionError::into_arrow_external_error)?;
+        if let Some(consumer) = consumer.as_mut() {
+            let new_groups = accumulators.group_states.len() - num_groups;
+            consumer
+                .try_grow(new_groups * group_size)
+                .map_err(DataFusionError::into_arrow_external_error)?;
+        }
         timer.done();
     }
 
@@ -578,15 +712,87 @@ async fn compute_grouped_hash_aggregate(
     batch
 }
 
+fn row_aggregate_batch(
+    mode: &AggregateMode,
+    group_expr: &[Arc<dyn PhysicalExpr>],
+    batch: &RecordBatch,
+    groups: &mut RowGroups,
+    aggregate_expressions: &[Vec<Arc<dyn PhysicalExpr>>],
+) -> Result<()> {
+    let merge = mode.merges_states();
+    if merge {
+        check_state_layout(&batch.schema())?;
+    }
+    let group_values = evaluate(group_expr, batch)?;
+    let aggr_input_values = evaluate_many(aggregate_expressions, batch)?;
+    groups.aggregate_batch(&group_values, &aggr_input_values, merge)
+}
+
+/// Aggregate the batches of `input` like [`compute_grouped_hash_aggregate`],
+/// keeping the states of the groups in rows of `layout`
+#[allow(clippy::too_many_arguments)]
+async fn compute_row_hash_aggregate(
+    mode: AggregateMode,
+    schema: SchemaRef,
+    group_expr: Vec<Arc<dyn PhysicalExpr>>,
+    aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+    layout: RowLayout,
+    mut input: SendableRecordBatchStream,
+    elapsed_compute: metrics::Time,
+    mut consumer: Option<MemoryConsumer>,
+    cancellation: CancellationToken,
+) -> ArrowResult<RecordBatch> {
+    let timer = elapsed_compute.timer();
+    let aggregate_expressions =
+        aggregate_expressions(&aggr_expr, &mode, group_expr.len())
+            .map_err(DataFusionError::into_arrow_external_error)?;
+    let group_size = layout.group_size();
+    let mut groups = RowGroups::new(layout);
+    timer.done();
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        cancellation
+            .check()
+            .map_err(DataFusionError::into_arrow_external_error)?;
+        let timer = elapsed_compute.timer();
+        let num_groups = groups.len();
+        row_aggregate_batch(
+            &mode,
+            &group_expr,
+            &batch,
+            &mut groups,
+            &aggregate_expressions,
+        )
+        .map_err(DataFusionError::into_arrow_external_error)?;
+        if let Some(consumer) = consumer.as_mut() {
+            let new_groups = groups.len() - num_groups;
+            consumer
+                .try_grow(new_groups * group_size)
+                .map_err(DataFusionError::into_arrow_external_error)?;
+        }
+        timer.done();
+    }
+
+    let timer = elapsed_compute.timer();
+    let batch = groups.to_batch(schema);
+    timer.done();
+    batch
+}
+
 impl GroupedHashAggregateStream {
-    /// Create a new HashAggregateStream
+    /// Create a new HashAggregateStream, keeping the states of the groups in
+    /// rows of `row_layout` if any
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         mode: AggregateMode,
         schema: SchemaRef,
         group_expr: Vec<Arc<dyn PhysicalExpr>>,
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
+        row_layout: Option<RowLayout>,
         input: SendableRecordBatchStream,
         baseline_metrics: BaselineMetrics,
+        consumer: Option<MemoryConsumer>,
+        cancellation: CancellationToken,
     ) -> Self {
         let (tx, rx) = futures::channel::oneshot::channel();
 
@@ -594,15 +800,35 @@ impl GroupedHashAggregateStream {
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
 
         let join_handle = tokio::spawn(async move {
-            let result = compute_grouped_hash_aggregate(
-                mode,
-                schema_clone,
-                group_expr,
-                aggr_expr,
-                input,
-                elapsed_compute,
-            )
-            .await
+            let result = match row_layout {
+                Some(layout) => {
+                    compute_row_hash_aggregate(
+                        mode,
+                        schema_clone,
+                        group_expr,
+                        aggr_expr,
+                        layout,
+                        input,
+                        elapsed_compute,
+                        consumer,
+                        cancellation,
+                    )
+                    .await
+                }
+                None => {
+                    compute_grouped_hash_aggregate(
+                        mode,
+                        schema_clone,
+                        group_expr,
+                        aggr_expr,
+                        input,
+                        elapsed_compute,
+                        consumer,
+                        cancellation,
+                    )
+                    .await
+                }
+            }
             .record_output(&baseline_metrics);
 
             // failing here is OK, the receiver is gone and does not care about the result
@@ -620,6 +846,15 @@ impl GroupedHashAggregateStream {
 
 type AccumulatorItem = Box<dyn Accumulator>;
 
+/// Estimated number of bytes of the state of a group, excluding the memory
+/// allocated by its accumulators
+fn estimated_group_size(num_group_exprs: usize, num_aggr_exprs: usize) -> usize {
+    std::mem::size_of::<GroupState>()
+        + std::mem::size_of::<(u64, usize)>()
+        + num_group_exprs * std::mem::size_of::<ScalarValue>()
+        + num_aggr_exprs * std::mem::size_of::<AccumulatorItem>()
+}
+
 /// The state that is built for each output group.
 #[derive(Debug)]
 struct GroupState {
@@ -748,7 +983,7 @@ fn aggregate_expressions(
     col_idx_base: usize,
 ) -> Result<Vec<Vec<Arc<dyn PhysicalExpr>>>> {
     match mode {
-        AggregateMode::Partial => {
+        AggregateMode::Partial | AggregateMode::SinglePartitioned => {
             Ok(aggr_expr.iter().map(|agg| agg.expressions()).collect())
         }
         // in this mode, we build the merge expressions of the aggregation
@@ -784,6 +1019,7 @@ async fn compute_hash_aggregate(
     aggr_expr: Vec<Arc<dyn AggregateExpr>>,
     mut input: SendableRecordBatchStream,
     elapsed_compute: metrics::Time,
+    cancellation: CancellationToken,
 ) -> ArrowResult<RecordBatch> {
     let timer = elapsed_compute.timer();
     let mut accumulators = create_accumulators(&aggr_expr)
@@ -797,6 +1033,9 @@ async fn compute_hash_aggregate(
     // future is ready when all batches are computed
     while let Some(batch) = input.next().await {
         let batch = batch?;
+        cancellation
+            .check()
+            .map_err(DataFusionError::into_arrow_external_error)?;
         let timer = elapsed_compute.timer();
         aggregate_batch(&mode, &batch, &mut accumulators, &expressions)
             .map_err(DataFusionError::into_arrow_external_error)?;
@@ -820,6 +1059,7 @@ impl HashAggregateStream {
         aggr_expr: Vec<Arc<dyn AggregateExpr>>,
         input: SendableRecordBatchStream,
         baseline_metrics: BaselineMetrics,
+        cancellation: CancellationToken,
     ) -> Self {
         let (tx, rx) = futures::channel::oneshot::channel();
 
@@ -832,6 +1072,7 @@ impl HashAggregateStream {
                 aggr_expr,
                 input,
                 elapsed_compute,
+                cancellation,
             )
             .await
             .record_output(&baseline_metrics);
@@ -855,6 +1096,10 @@ fn aggregate_batch(
     accumulators: &mut [AccumulatorItem],
     expressions: &[Vec<Arc<dyn PhysicalExpr>>],
 ) -> Result<()> {
+    if mode.merges_states() {
+        check_state_layout(&batch.schema())?;
+    }
+
     // 1.1 iterate accumulators and respective expressions together
     // 1.2 evaluate expressions
     // 1.3 update / merge accumulators with the expressions' values
@@ -873,7 +1118,9 @@ fn aggregate_batch(
 
             // 1.3
             match mode {
-                AggregateMode::Partial => accum.update_batch(values),
+                AggregateMode::Partial | AggregateMode::SinglePartitioned => {
+                    accum.update_batch(values)
+                }
                 AggregateMode::Final | AggregateMode::FinalPartitioned => {
                     accum.merge_batch(values)
                 }
@@ -942,7 +1189,9 @@ fn create_batch_from_map(
                 acc_data_types.push(state.len());
             }
         }
-        AggregateMode::Final | AggregateMode::FinalPartitioned => {
+        AggregateMode::Final
+        | AggregateMode::FinalPartitioned
+        | AggregateMode::SinglePartitioned => {
             acc_data_types = vec![1; accs.len()];
         }
     }
@@ -974,7 +1223,9 @@ fn create_batch_from_map(
 
                     columns.push(res);
                 }
-                AggregateMode::Final | AggregateMode::FinalPartitioned => {
+                AggregateMode::Final
+                | AggregateMode::FinalPartitioned
+                | AggregateMode::SinglePartitioned => {
                     let res = ScalarValue::iter_to_array(
                         accumulators.group_states.iter().map(|group_state| {
                             group_state.accumulator_set[x].evaluate().unwrap()
@@ -1028,7 +1279,9 @@ fn finalize_aggregation(
                 .collect::<Result<Vec<_>>>()?;
             Ok(a.iter().flatten().cloned().collect::<Vec<_>>())
         }
-        AggregateMode::Final | AggregateMode::FinalPartitioned => {
+        AggregateMode::Final
+        | AggregateMode::FinalPartitioned
+        | AggregateMode::SinglePartitioned => {
             // merge the state to the final value
             accumulators
                 .iter()
@@ -1041,12 +1294,17 @@ fn finalize_aggregation(
 #[cfg(test)]
 mod tests {
 
-    use arrow::array::{Float64Array, UInt32Array};
+    use arrow::array::{
+        Float64Array, Int32Array, Int64Array, Int8Array, UInt32Array, UInt8Array,
+    };
     use arrow::datatypes::DataType;
     use futures::FutureExt;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
 
     use super::*;
-    use crate::physical_plan::expressions::{col, Avg};
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::expressions::{col, Avg, Count, Max, Min, Sum};
     use crate::test::assert_is_pending;
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
     use crate::{assert_batches_sorted_eq, physical_plan::common};
@@ -1087,6 +1345,7 @@ mod tests {
 
     /// build the aggregates on the data from some_data() and check the results
     async fn check_aggregates(input: Arc<dyn ExecutionPlan>) -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let input_schema = input.schema();
 
         let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
@@ -1106,7 +1365,9 @@ mod tests {
             input_schema.clone(),
         )?);
 
-        let result = common::collect(partial_aggregate.execute(0).await?).await?;
+        let result =
+            common::collect(partial_aggregate.execute(0, task_ctx.clone()).await?)
+                .await?;
 
         let expected = vec![
             "+---+---------------+-------------+",
@@ -1137,7 +1398,8 @@ mod tests {
             input_schema,
         )?);
 
-        let result = common::collect(merged_aggregate.execute(0).await?).await?;
+        let result =
+            common::collect(merged_aggregate.execute(0, task_ctx).await?).await?;
         assert_eq!(result.len(), 1);
 
         let batch = &result[0];
@@ -1173,7 +1435,7 @@ mod tests {
 
     #[async_trait]
     impl LambdaExecPlan for TestYieldingExec {
-        fn feed_batches(&mut self, _: Vec<Vec<RecordBatch>>) {
+        fn feed_batches(&mut self, _: Vec<Vec<RecordBatch>>) -> Result<()> {
             unimplemented!();
         }
     }
@@ -1211,7 +1473,11 @@ mod tests {
             )))
         }
 
-        async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+        async fn execute(
+            &self,
+            _partition: usize,
+            _context: Arc<TaskContext>,
+        ) -> Result<SendableRecordBatchStream> {
             let stream;
             if self.yield_first {
                 stream = TestYieldingStream::New;
@@ -1287,6 +1553,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel_without_groups() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -1308,7 +1575,7 @@ mod tests {
             schema,
         )?);
 
-        let fut = crate::physical_plan::collect(hash_aggregate_exec);
+        let fut = crate::physical_plan::collect(hash_aggregate_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
@@ -1320,6 +1587,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel_with_groups() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = Arc::new(Schema::new(vec![
             Field::new("a", DataType::Float32, true),
             Field::new("b", DataType::Float32, true),
@@ -1344,7 +1612,7 @@ mod tests {
             schema,
         )?);
 
-        let fut = crate::physical_plan::collect(hash_aggregate_exec);
+        let fut = crate::physical_plan::collect(hash_aggregate_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
@@ -1353,4 +1621,133 @@ mod tests {
 
         Ok(())
     }
+
+    /// batches of random values, a tenth of them null, with few distinct
+    /// values of the keys `a` and `b`
+    fn random_batches(rng: &mut StdRng, schema: &SchemaRef) -> Vec<RecordBatch> {
+        (0..4)
+            .map(|_| {
+                let mut a = vec![];
+                let mut b = vec![];
+                let mut c = vec![];
+                let mut d = vec![];
+                let mut e = vec![];
+                for _ in 0..256 {
+                    a.push(rng.gen_bool(0.9).then(|| rng.gen_range(-4..4)));
+                    b.push(rng.gen_bool(0.9).then(|| rng.gen_range(0..3)));
+                    c.push(rng.gen_bool(0.9).then(|| rng.gen_range(-1 << 40..1 << 40)));
+                    // integers, whose sums don't depend on their order
+                    d.push(rng.gen_bool(0.9).then(|| rng.gen_range(-1000..1000) as f64));
+                    e.push(rng.gen_bool(0.9).then(|| rng.gen::<i8>()));
+                }
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from(a)),
+                        Arc::new(UInt8Array::from(b)),
+                        Arc::new(Int64Array::from(c)),
+                        Arc::new(Float64Array::from(d)),
+                        Arc::new(Int8Array::from(e)),
+                    ],
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    /// The partial and the final aggregations of `partitions` by `a` and `b`
+    async fn aggregate_partitions(
+        partitions: &[Vec<RecordBatch>],
+        schema: &SchemaRef,
+        config: ExecutionConfig,
+    ) -> Result<(Vec<RecordBatch>, Vec<RecordBatch>)> {
+        let task_ctx = ExecutionContext::with_config(config).task_ctx();
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> = vec![
+            (col("a", schema)?, "a".to_string()),
+            (col("b", schema)?, "b".to_string()),
+        ];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![
+            Arc::new(Sum::new(col("c", schema)?, "SUM(c)", DataType::Int64)),
+            Arc::new(Sum::new(col("d", schema)?, "SUM(d)", DataType::Float64)),
+            Arc::new(Sum::new(col("e", schema)?, "SUM(e)", DataType::Int64)),
+            Arc::new(Count::new(col("c", schema)?, "COUNT(c)", DataType::UInt64)),
+            Arc::new(Min::new(col("d", schema)?, "MIN(d)", DataType::Float64)),
+            Arc::new(Max::new(col("e", schema)?, "MAX(e)", DataType::Int8)),
+            Arc::new(Min::new(col("b", schema)?, "MIN(b)", DataType::UInt8)),
+        ];
+        let input = Arc::new(MemoryExec::try_new(partitions, schema.clone(), None)?);
+        let partial_aggregate = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            groups.clone(),
+            aggregates.clone(),
+            input,
+            schema.clone(),
+        )?);
+        let partial = common::collect(
+            CoalescePartitionsExec::new(partial_aggregate.clone())
+                .execute(0, task_ctx.clone())
+                .await?,
+        )
+        .await?;
+
+        let final_groups = groups
+            .iter()
+            .map(|(_, name)| Ok((col(name, &partial_aggregate.schema())?, name.clone())))
+            .collect::<Result<_>>()?;
+        let final_aggregate = HashAggregateExec::try_new(
+            AggregateMode::Final,
+            final_groups,
+            aggregates,
+            Arc::new(CoalescePartitionsExec::new(partial_aggregate)),
+            schema.clone(),
+        )?;
+        let result = common::collect(final_aggregate.execute(0, task_ctx).await?).await?;
+        Ok((partial, result))
+    }
+
+    /// sorted lines of the pretty printed `batches`
+    fn sorted_lines(batches: &[RecordBatch]) -> Vec<String> {
+        let formatted = arrow::util::pretty::pretty_format_batches(batches)
+            .unwrap()
+            .to_string();
+        let mut lines = formatted.lines().map(String::from).collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines
+    }
+
+    #[tokio::test]
+    async fn row_aggregation_matches_accumulators() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::UInt8, true),
+            Field::new("c", DataType::Int64, true),
+            Field::new("d", DataType::Float64, true),
+            Field::new("e", DataType::Int8, true),
+        ]));
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..4 {
+            let partitions = vec![
+                random_batches(&mut rng, &schema),
+                random_batches(&mut rng, &schema),
+            ];
+            let (rows_partial, rows_result) = aggregate_partitions(
+                &partitions,
+                &schema,
+                ExecutionConfig::new().with_row_aggregation(true),
+            )
+            .await?;
+            let (partial, result) = aggregate_partitions(
+                &partitions,
+                &schema,
+                ExecutionConfig::new().with_row_aggregation(false),
+            )
+            .await?;
+
+            assert_eq!(sorted_lines(&rows_partial), sorted_lines(&partial));
+            assert_eq!(sorted_lines(&rows_result), sorted_lines(&result));
+            // the 8 values of `a` and the 3 of `b`, and their nulls
+            assert_eq!(result.iter().map(|b| b.num_rows()).sum::<usize>(), 9 * 4);
+        }
+        Ok(())
+    }
 }
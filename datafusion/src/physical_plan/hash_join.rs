@@ -22,9 +22,10 @@ use ahash::RandomState;
 
 use arrow::{
     array::{
-        ArrayData, ArrayRef, BooleanArray, LargeStringArray, PrimitiveArray,
-        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampSecondArray,
-        UInt32BufferBuilder, UInt32Builder, UInt64BufferBuilder, UInt64Builder,
+        ArrayData, ArrayRef, BinaryArray, BooleanArray, FixedSizeBinaryArray,
+        LargeBinaryArray, LargeStringArray, PrimitiveArray, TimestampMicrosecondArray,
+        TimestampMillisecondArray, TimestampSecondArray, UInt32BufferBuilder,
+        UInt32Builder, UInt64BufferBuilder, UInt64Builder,
     },
     compute,
     datatypes::{UInt32Type, UInt64Type},
@@ -35,7 +36,7 @@ use std::{any::Any, usize};
 use std::{time::Instant, vec};
 
 use async_trait::async_trait;
-use futures::{Stream, StreamExt, TryStreamExt};
+use futures::{Stream, StreamExt};
 use tokio::sync::Mutex;
 
 use arrow::array::Array;
@@ -54,27 +55,43 @@ use hashbrown::raw::RawTable;
 
 use super::{
     coalesce_partitions::CoalescePartitionsExec,
-    join_utils::{build_join_schema, check_join_is_valid, ColumnIndex, JoinOn, JoinSide},
+    dynamic_filter::DynamicFilterSource,
+    join_utils::{
+        build_join_schema, check_join_is_valid, right_output_partitioning, ColumnIndex,
+        JoinOn, JoinSide,
+    },
 };
 use super::{
     expressions::Column,
     metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet},
 };
-use super::{hash_utils::create_hashes, Statistics};
+use super::{
+    hash_utils::{create_hashes, partition_random_state},
+    Statistics,
+};
 use crate::error::{DataFusionError, Result};
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::memory_manager::{MemoryConsumer, MemoryManager};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
 use crate::logical_plan::JoinType;
+use crate::physical_plan::trace::instrument_stream;
 
 use super::{
-    DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream,
+    fmt_with_statistics, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream,
 };
 use crate::arrow::array::BooleanBufferBuilder;
 use crate::arrow::datatypes::TimeUnit;
 use crate::physical_plan::coalesce_batches::concat_batches;
+use crate::physical_plan::common::collect_reserved;
+use crate::physical_plan::memory::feed_input;
 use crate::physical_plan::LambdaExecPlan;
 
 use serde::{Deserialize, Serialize};
 use crate::physical_plan::PhysicalExpr;
+use crate::scalar::dictionary_value_index;
 use log::debug;
 use std::fmt;
 
@@ -114,9 +131,9 @@ pub struct HashJoinExec {
     join_type: JoinType,
     /// The schema once the join is applied
     schema: SchemaRef,
-    /// Build-side
+    /// Build-side, with its reservation from the memory manager
     #[serde(skip)]
-    build_side: Arc<Mutex<Option<JoinLeftData>>>,
+    build_side: Arc<Mutex<Option<(JoinLeftData, Option<MemoryConsumer>)>>>,
     /// Shares the `RandomState` for the hashing algorithm
     random_state: RandomState,
     /// Partitioning mode to use
@@ -127,6 +144,24 @@ pub struct HashJoinExec {
     column_indices: Vec<ColumnIndex>,
     /// If null_equals_null is true, null == null else null != null
     null_equals_null: bool,
+    /// If null_aware is true, the anti join follows the semantics of `NOT IN`,
+    /// see [`HashJoinExec::with_null_aware`]
+    #[serde(default)]
+    null_aware: bool,
+    /// Memory manager the build side is reserved from, bound by the runtime
+    #[serde(skip)]
+    memory_manager: Option<Arc<MemoryManager>>,
+    /// Token cancelling the build and the probe, bound by the runtime
+    #[serde(skip)]
+    cancellation: CancellationToken,
+    /// Executor building the hash table of the build side, bound by the
+    /// runtime
+    #[serde(skip)]
+    cpu_executor: CpuExecutor,
+    /// Source of the filter of the probe side published once the build side
+    /// is collected, see [DynamicFilterSource]
+    #[serde(skip)]
+    dynamic_filter: Option<DynamicFilterSource>,
 }
 
 /// Metrics for HashJoinExec
@@ -142,6 +177,8 @@ struct HashJoinMetrics {
     output_batches: metrics::Count,
     /// Number of rows produced by this operator
     output_rows: metrics::Count,
+    /// Average number of rows of the batches produced by this operator
+    avg_output_batch_rows: metrics::Average,
 }
 
 impl HashJoinMetrics {
@@ -158,14 +195,25 @@ impl HashJoinMetrics {
 
         let output_rows = MetricBuilder::new(metrics).output_rows(partition);
 
+        let avg_output_batch_rows =
+            MetricBuilder::new(metrics).average("avg_output_batch_rows", partition);
+
         Self {
             join_time,
             input_batches,
             input_rows,
             output_batches,
             output_rows,
+            avg_output_batch_rows,
         }
     }
+
+    /// Record the production of `batch`
+    fn record_output(&self, batch: &RecordBatch) {
+        self.output_batches.add(1);
+        self.output_rows.add(batch.num_rows());
+        self.avg_output_batch_rows.add(batch.num_rows());
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
@@ -196,7 +244,7 @@ impl HashJoinExec {
         let (schema, column_indices) =
             build_join_schema(&left_schema, &right_schema, join_type);
 
-        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let random_state = partition_random_state();
 
         Ok(HashJoinExec {
             left,
@@ -210,9 +258,163 @@ impl HashJoinExec {
             metrics: ExecutionPlanMetricsSet::new(),
             column_indices,
             null_equals_null: *null_equals_null,
+            null_aware: false,
+            memory_manager: None,
+            cancellation: CancellationToken::new(),
+            cpu_executor: CpuExecutor::default(),
+            dynamic_filter: None,
         })
     }
 
+    /// Make the anti join null-aware, following the semantics of
+    /// `left_key NOT IN (SELECT right_key FROM right)`: if the probe side has
+    /// a null key, no row is returned, and the rows of the build side with a
+    /// null key are only returned if the probe side is empty. The whole probe
+    /// side is read by a single partition of the join.
+    pub fn with_null_aware(mut self, null_aware: bool) -> Result<Self> {
+        if null_aware && (self.join_type != JoinType::Anti || self.on.len() != 1) {
+            return Err(DataFusionError::Plan(format!(
+                "Only anti joins on a single key can be null-aware, got a {:?} join on {} keys",
+                self.join_type,
+                self.on.len()
+            )));
+        }
+        self.null_aware = null_aware;
+        Ok(self)
+    }
+
+    /// Publish the ranges of the keys of the build side to `source` once it
+    /// is collected. Only the joins in [PartitionMode::CollectLeft] publish
+    /// them, the build side of a partition not bounding the keys of the other
+    /// partitions of the probe side.
+    pub fn with_dynamic_filter(mut self, source: DynamicFilterSource) -> Self {
+        self.dynamic_filter = Some(source);
+        self
+    }
+
+    /// The source of the filter of the probe side, if any
+    pub fn dynamic_filter(&self) -> Option<&DynamicFilterSource> {
+        self.dynamic_filter.as_ref()
+    }
+
+    /// Registers a consumer reserving the build side of `partition` from the
+    /// memory manager, if any
+    fn register_consumer(&self, partition: usize) -> Option<MemoryConsumer> {
+        self.memory_manager.as_ref().map(|manager| {
+            manager.register_consumer(
+                format!("HashJoinExec[{}]", partition),
+                MetricBuilder::new(&self.metrics).mem_used(partition),
+            )
+        })
+    }
+
+    /// Builds the hash table of the keys `on_left` of the `batches` of the
+    /// left side on the CPU executor, and merges the batches into the single
+    /// batch it indexes
+    async fn build_left_data(
+        &self,
+        on_left: &[Column],
+        batches: Vec<RecordBatch>,
+        num_rows: usize,
+    ) -> Result<JoinLeftData> {
+        let on_left = on_left.to_vec();
+        let schema = self.left.schema();
+        let random_state = self.random_state.clone();
+        let cancellation = self.cancellation.clone();
+        self.cpu_executor
+            .spawn(move || -> Result<JoinLeftData> {
+                let mut hashmap = JoinHashMap(RawTable::with_capacity(num_rows));
+                let mut hashes_buffer = Vec::new();
+                let mut offset = 0;
+                for batch in batches.iter() {
+                    cancellation.check()?;
+                    hashes_buffer.clear();
+                    hashes_buffer.resize(batch.num_rows(), 0);
+                    update_hash(
+                        &on_left,
+                        batch,
+                        &mut hashmap,
+                        offset,
+                        &random_state,
+                        &mut hashes_buffer,
+                    )?;
+                    offset += batch.num_rows();
+                }
+                // Merge all batches into a single batch, so we
+                // can directly index into the arrays
+                let single_batch = concat_batches(&schema, &batches, num_rows)?;
+
+                Ok(Arc::new((hashmap, single_batch)))
+            })
+            .await?
+    }
+
+    /// The build side shared by all the partitions in
+    /// [PartitionMode::CollectLeft]. The first partition executed collects
+    /// the whole left side and builds its hash table while the others wait
+    /// for it, and then reuse it: the left side is executed and reserved
+    /// from the memory manager once, and the time of the build is recorded in
+    /// the `build_time` metric of the operator rather than of that partition.
+    /// The ranges of its keys are then published to the dynamic filter, if
+    /// any, before any partition of the probe side is executed.
+    async fn collect_left_data(
+        &self,
+        on_left: &[Column],
+        context: Arc<TaskContext>,
+    ) -> Result<JoinLeftData> {
+        let mut build_side = self.build_side.lock().await;
+        if let Some((left_data, _)) = build_side.as_ref() {
+            return Ok(left_data.clone());
+        }
+
+        // merge all left parts into a single stream
+        let merge = CoalescePartitionsExec::new(self.left.clone());
+        let stream = merge.execute(0, context).await?;
+        let build_time = MetricBuilder::new(&self.metrics).global_timer("build_time");
+        let mut consumer = self.register_consumer(0);
+        let left_data = self
+            .load_left_data(on_left, stream, &mut consumer, &build_time)
+            .await?;
+
+        debug!(
+            "Built build-side of hash join containing {} rows in {} ms",
+            left_data.1.num_rows(),
+            build_time.value() / 1_000_000
+        );
+
+        if let Some(source) = &self.dynamic_filter {
+            let keys = on_left
+                .iter()
+                .map(|column| left_data.1.column(column.index()).clone())
+                .collect::<Vec<_>>();
+            source.publish(&keys);
+        }
+
+        *build_side = Some((left_data.clone(), consumer));
+        Ok(left_data)
+    }
+
+    /// Collects the batches of `stream`, a stream of the left side, and
+    /// builds their hash table, reserving both from `consumer` and recording
+    /// the time spent in `build_time`. The build side cannot spill, the join
+    /// fails if it does not fit in the memory pool.
+    async fn load_left_data(
+        &self,
+        on_left: &[Column],
+        stream: SendableRecordBatchStream,
+        consumer: &mut Option<MemoryConsumer>,
+        build_time: &metrics::Time,
+    ) -> Result<JoinLeftData> {
+        let start = Instant::now();
+        let batches =
+            collect_reserved(stream, consumer.as_mut(), &self.cancellation).await?;
+        let num_rows: usize = batches.iter().map(|batch| batch.num_rows()).sum();
+        reserve_hash_map(consumer.as_mut(), num_rows)?;
+        let left_data = self.build_left_data(on_left, batches, num_rows).await?;
+        build_time.add_elapsed(start);
+        Ok(left_data)
+    }
+
     /// left (build) side which gets hashed
     pub fn left(&self) -> &Arc<dyn ExecutionPlan> {
         &self.left
@@ -242,6 +444,11 @@ impl HashJoinExec {
     pub fn null_equals_null(&self) -> &bool {
         &self.null_equals_null
     }
+
+    /// Whether the anti join follows the semantics of `NOT IN`
+    pub fn null_aware(&self) -> bool {
+        self.null_aware
+    }
 }
 
 #[async_trait]
@@ -268,14 +475,22 @@ impl ExecutionPlan for HashJoinExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            2 => Ok(Arc::new(HashJoinExec::try_new(
-                children[0].clone(),
-                children[1].clone(),
-                self.on.clone(),
-                &self.join_type,
-                self.mode,
-                &self.null_equals_null,
-            )?)),
+            2 => {
+                let mut join = HashJoinExec::try_new(
+                    children[0].clone(),
+                    children[1].clone(),
+                    self.on.clone(),
+                    &self.join_type,
+                    self.mode,
+                    &self.null_equals_null,
+                )?;
+                join.null_aware = self.null_aware;
+                join.memory_manager = self.memory_manager.clone();
+                join.cancellation = self.cancellation.clone();
+                join.cpu_executor = self.cpu_executor.clone();
+                join.dynamic_filter = self.dynamic_filter.clone();
+                Ok(Arc::new(join))
+            }
             _ => Err(DataFusionError::Internal(
                 "HashJoinExec wrong number of children".to_string(),
             )),
@@ -283,129 +498,74 @@ impl ExecutionPlan for HashJoinExec {
     }
 
     fn output_partitioning(&self) -> Partitioning {
-        self.right.output_partitioning()
+        let right = self.right.output_partitioning();
+        match self.join_type {
+            JoinType::Inner | JoinType::Right => {
+                right_output_partitioning(right, self.left.schema().fields().len())
+            }
+            // the unmatched rows of the left side have null right columns, and
+            // semi and anti joins output no right column
+            _ => Partitioning::UnknownPartitioning(right.partition_count()),
+        }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let on_left = self.on.iter().map(|on| on.0.clone()).collect::<Vec<_>>();
-        // we only want to compute the build side once for PartitionMode::CollectLeft
-        let left_data = {
-            match self.mode {
-                PartitionMode::CollectLeft => {
-                    let mut build_side = self.build_side.lock().await;
-
-                    match build_side.as_ref() {
-                        Some(stream) => stream.clone(),
-                        None => {
-                            let start = Instant::now();
-
-                            // merge all left parts into a single stream
-                            let merge = CoalescePartitionsExec::new(self.left.clone());
-                            let stream = merge.execute(0).await?;
-
-                            // This operation performs 2 steps at once:
-                            // 1. creates a [JoinHashMap] of all batches from the stream
-                            // 2. stores the batches in a vector.
-                            let initial = (0, Vec::new());
-                            let (num_rows, batches) = stream
-                                .try_fold(initial, |mut acc, batch| async {
-                                    acc.0 += batch.num_rows();
-                                    acc.1.push(batch);
-                                    Ok(acc)
-                                })
-                                .await?;
-                            let mut hashmap =
-                                JoinHashMap(RawTable::with_capacity(num_rows));
-                            let mut hashes_buffer = Vec::new();
-                            let mut offset = 0;
-                            for batch in batches.iter() {
-                                hashes_buffer.clear();
-                                hashes_buffer.resize(batch.num_rows(), 0);
-                                update_hash(
-                                    &on_left,
-                                    batch,
-                                    &mut hashmap,
-                                    offset,
-                                    &self.random_state,
-                                    &mut hashes_buffer,
-                                )?;
-                                offset += batch.num_rows();
-                            }
-                            // Merge all batches into a single batch, so we
-                            // can directly index into the arrays
-                            let single_batch =
-                                concat_batches(&self.left.schema(), &batches, num_rows)?;
-
-                            let left_side = Arc::new((hashmap, single_batch));
-
-                            *build_side = Some(left_side.clone());
-
-                            debug!(
-                                "Built build-side of hash join containing {} rows in {} ms",
-                                num_rows,
-                                start.elapsed().as_millis()
-                            );
-
-                            left_side
-                        }
-                    }
-                }
-                PartitionMode::Partitioned => {
-                    let start = Instant::now();
-
-                    // Load 1 partition of left side in memory
-                    let stream = self.left.execute(partition).await?;
-
-                    // This operation performs 2 steps at once:
-                    // 1. creates a [JoinHashMap] of all batches from the stream
-                    // 2. stores the batches in a vector.
-                    let initial = (0, Vec::new());
-                    let (num_rows, batches) = stream
-                        .try_fold(initial, |mut acc, batch| async {
-                            acc.0 += batch.num_rows();
-                            acc.1.push(batch);
-                            Ok(acc)
-                        })
-                        .await?;
-                    let mut hashmap = JoinHashMap(RawTable::with_capacity(num_rows));
-                    let mut hashes_buffer = Vec::new();
-                    let mut offset = 0;
-                    for batch in batches.iter() {
-                        hashes_buffer.clear();
-                        hashes_buffer.resize(batch.num_rows(), 0);
-                        update_hash(
-                            &on_left,
-                            batch,
-                            &mut hashmap,
-                            offset,
-                            &self.random_state,
-                            &mut hashes_buffer,
-                        )?;
-                        offset += batch.num_rows();
-                    }
-                    // Merge all batches into a single batch, so we
-                    // can directly index into the arrays
-                    let single_batch =
-                        concat_batches(&self.left.schema(), &batches, num_rows)?;
-
-                    let left_side = Arc::new((hashmap, single_batch));
-
-                    debug!(
-                        "Built build-side {} of hash join containing {} rows in {} ms",
-                        partition,
-                        num_rows,
-                        start.elapsed().as_millis()
-                    );
+    fn required_child_distribution(&self) -> Distribution {
+        // whether the probe side has a null key or any row is only known
+        // once all its partitions are read
+        if self.null_aware {
+            Distribution::SinglePartition
+        } else {
+            Distribution::UnspecifiedDistribution
+        }
+    }
 
-                    left_side
-                }
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "HashJoinExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let on_left = self.on.iter().map(|on| on.0.clone()).collect::<Vec<_>>();
+        // the reservation of a partitioned build side lives as long as its stream
+        let mut reservation = None;
+        let left_data = match self.mode {
+            PartitionMode::CollectLeft => {
+                self.collect_left_data(&on_left, context.clone()).await?
+            }
+            PartitionMode::Partitioned => {
+                // Load 1 partition of left side in memory
+                let stream = self.left.execute(partition, context.clone()).await?;
+                let build_time = MetricBuilder::new(&self.metrics)
+                    .subset_time("build_time", partition);
+                reservation = self.register_consumer(partition);
+                let left_data = self
+                    .load_left_data(&on_left, stream, &mut reservation, &build_time)
+                    .await?;
+
+                debug!(
+                    "Built build-side {} of hash join containing {} rows in {} ms",
+                    partition,
+                    left_data.1.num_rows(),
+                    build_time.value() / 1_000_000
+                );
+
+                left_data
             }
         };
 
         // we have the batches and the hash map with their keys. We can how create a stream
         // over the right that uses this information to issue new batches.
 
-        let right_stream = self.right.execute(partition).await?;
+        let right_stream = self.right.execute(partition, context.clone()).await?;
         let on_right = self.on.iter().map(|on| on.1.clone()).collect::<Vec<_>>();
 
         let num_rows = left_data.1.num_rows();
@@ -419,19 +579,32 @@ impl ExecutionPlan for HashJoinExec {
             }
             JoinType::Inner | JoinType::Right => BooleanBufferBuilder::new(0),
         };
-        Ok(Box::pin(HashJoinStream::new(
-            self.schema.clone(),
-            on_left,
-            on_right,
-            self.join_type,
-            left_data,
-            right_stream,
-            self.column_indices.clone(),
-            self.random_state.clone(),
-            visited_left_side,
-            HashJoinMetrics::new(partition, &self.metrics),
-            self.null_equals_null,
-        )))
+        let batch_size = if context.session_config().split_join_output {
+            Some(context.batch_size())
+        } else {
+            None
+        };
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(HashJoinStream::new(
+                self.schema.clone(),
+                on_left,
+                on_right,
+                self.join_type,
+                left_data,
+                right_stream,
+                self.column_indices.clone(),
+                self.random_state.clone(),
+                visited_left_side,
+                HashJoinMetrics::new(partition, &self.metrics),
+                self.null_equals_null,
+                self.null_aware,
+                reservation,
+                self.cancellation.clone(),
+                batch_size,
+            )),
+        ))
     }
 
     fn fmt_as(
@@ -445,8 +618,13 @@ impl ExecutionPlan for HashJoinExec {
                     f,
                     "HashJoinExec: mode={:?}, join_type={:?}, on={:?}",
                     self.mode, self.join_type, self.on
-                )
+                )?;
+                if self.null_aware {
+                    write!(f, ", null_aware=true")?;
+                }
+                Ok(())
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -460,12 +638,67 @@ impl ExecutionPlan for HashJoinExec {
         // - `A LEFT JOIN B ON A.col=B.col` with `COUNT_DISTINCT(B.col)=COUNT(B.col)`
         Statistics::default()
     }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut join = HashJoinExec::try_new(
+            self.left.clone(),
+            self.right.clone(),
+            self.on.clone(),
+            &self.join_type,
+            self.mode,
+            &self.null_equals_null,
+        )?;
+        join.null_aware = self.null_aware;
+        join.memory_manager = runtime.memory_manager.clone();
+        join.cancellation = runtime.cancellation.clone();
+        join.cpu_executor = runtime.cpu_executor.clone();
+        join.dynamic_filter = self.dynamic_filter.clone();
+        Ok(Some(Arc::new(join)))
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for HashJoinExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.feed_child_batches(0, partitions)
+    }
+
+    fn feed_child_batches(
+        &mut self,
+        child: usize,
+        partitions: Vec<Vec<RecordBatch>>,
+    ) -> Result<()> {
+        match child {
+            0 => {
+                feed_input(&mut self.left, partitions);
+                // the build side is collected again from the fed batches
+                self.build_side = Arc::new(Mutex::new(None));
+            }
+            1 => feed_input(&mut self.right, partitions),
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "HashJoinExec has no input {} to feed",
+                    child
+                )))
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reserves the estimated memory of a [JoinHashMap] of `num_rows` rows
+fn reserve_hash_map(
+    consumer: Option<&mut MemoryConsumer>,
+    num_rows: usize,
+) -> Result<()> {
+    match consumer {
+        Some(consumer) => {
+            consumer.try_grow(num_rows * std::mem::size_of::<(u64, SmallVec<[u64; 1]>)>())
+        }
+        None => Ok(()),
     }
 }
 
@@ -532,6 +765,37 @@ struct HashJoinStream {
     column_indices: Vec<ColumnIndex>,
     /// If null_equals_null is true, null == null else null != null
     null_equals_null: bool,
+    /// If null_aware is true, the anti join follows the semantics of `NOT IN`
+    null_aware: bool,
+    /// Whether the probe side had any row
+    probe_has_rows: bool,
+    /// Whether the probe side had a row with a null key
+    probe_has_null_key: bool,
+    /// Reservation of the build side, when it is not shared with the other
+    /// partitions
+    _reservation: Option<MemoryConsumer>,
+    /// Token cancelling the probe, checked before each probe-side batch
+    cancellation: CancellationToken,
+    /// Whether the stream ended on a cancellation
+    cancelled: bool,
+    /// Maximum number of rows of the batches of the rows joined with a
+    /// probe-side batch, unlimited if `None`
+    batch_size: Option<usize>,
+    /// Rows joined with the last probe-side batch not returned yet
+    probe_output: Option<ProbeOutput>,
+}
+
+/// The rows joined with a probe-side batch, returned in batches of at most
+/// the batch size
+struct ProbeOutput {
+    /// The probe-side batch
+    batch: RecordBatch,
+    /// Build-side rows of the joined rows
+    left_indices: UInt64Array,
+    /// Probe-side rows of the joined rows
+    right_indices: UInt32Array,
+    /// Number of joined rows already returned
+    offset: usize,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -548,6 +812,10 @@ impl HashJoinStream {
         visited_left_side: BooleanBufferBuilder,
         join_metrics: HashJoinMetrics,
         null_equals_null: bool,
+        null_aware: bool,
+        reservation: Option<MemoryConsumer>,
+        cancellation: CancellationToken,
+        batch_size: Option<usize>,
     ) -> Self {
         HashJoinStream {
             schema,
@@ -562,7 +830,69 @@ impl HashJoinStream {
             is_exhausted: false,
             join_metrics,
             null_equals_null,
+            null_aware,
+            probe_has_rows: false,
+            probe_has_null_key: false,
+            _reservation: reservation,
+            cancellation,
+            cancelled: false,
+            batch_size,
+            probe_output: None,
+        }
+    }
+
+    /// Marks the build-side rows a null-aware anti join does not return as
+    /// visited: all of them if the probe side has a null key, and otherwise
+    /// the rows with a null key if the probe side has any row, `NULL NOT IN`
+    /// a list being only true for an empty list
+    fn visit_null_aware_excluded_rows(&mut self) {
+        let num_rows = self.visited_left_side.len();
+        if self.probe_has_null_key {
+            (0..num_rows).for_each(|row| self.visited_left_side.set_bit(row, true));
+        } else if self.probe_has_rows {
+            for column in &self.on_left {
+                let keys = self.left_data.1.column(column.index());
+                (0..num_rows)
+                    .filter(|row| keys.is_null(*row))
+                    .for_each(|row| self.visited_left_side.set_bit(row, true));
+            }
+        }
+    }
+
+    /// Returns the next batch of the rows joined with the last probe-side
+    /// batch, if they are not all returned yet. The first batch is returned
+    /// even if no row was joined.
+    fn next_probe_output(&mut self) -> Option<ArrowResult<RecordBatch>> {
+        let output = self.probe_output.as_mut()?;
+        let timer = self.join_metrics.join_time.timer();
+        let num_rows = output.left_indices.len();
+        let length = match self.batch_size {
+            Some(batch_size) => (num_rows - output.offset).min(batch_size),
+            None => num_rows,
+        };
+        let left_indices =
+            UInt64Array::from(output.left_indices.data().slice(output.offset, length));
+        let right_indices =
+            UInt32Array::from(output.right_indices.data().slice(output.offset, length));
+        let result = build_batch_from_indices(
+            &self.schema,
+            &self.left_data.1,
+            &output.batch,
+            left_indices,
+            right_indices,
+            &self.column_indices,
+        )
+        .map(|(batch, _)| batch);
+        output.offset += length;
+        if output.offset >= num_rows {
+            self.probe_output = None;
+        }
+        timer.done();
+
+        if let Ok(batch) = &result {
+            self.join_metrics.record_output(batch);
         }
+        Some(result)
     }
 }
 
@@ -606,46 +936,6 @@ fn build_batch_from_indices(
     RecordBatch::try_new(Arc::new(schema.clone()), columns).map(|x| (x, left_indices))
 }
 
-#[allow(clippy::too_many_arguments)]
-fn build_batch(
-    batch: &RecordBatch,
-    left_data: &JoinLeftData,
-    on_left: &[Column],
-    on_right: &[Column],
-    join_type: JoinType,
-    schema: &Schema,
-    column_indices: &[ColumnIndex],
-    random_state: &RandomState,
-    null_equals_null: &bool,
-) -> ArrowResult<(RecordBatch, UInt64Array)> {
-    let (left_indices, right_indices) = build_join_indexes(
-        left_data,
-        batch,
-        join_type,
-        on_left,
-        on_right,
-        random_state,
-        null_equals_null,
-    )
-    .unwrap();
-
-    if matches!(join_type, JoinType::Semi | JoinType::Anti) {
-        return Ok((
-            RecordBatch::new_empty(Arc::new(schema.clone())),
-            left_indices,
-        ));
-    }
-
-    build_batch_from_indices(
-        schema,
-        &left_data.1,
-        batch,
-        left_indices,
-        right_indices,
-        column_indices,
-    )
-}
-
 /// returns a vector with (index from left, index from right).
 /// The size of this vector corresponds to the total size of a joined batch
 // For a join on column A:
@@ -829,10 +1119,23 @@ fn equal_rows(
     null_equals_null: bool,
 ) -> Result<bool> {
     let mut err = None;
-    let res = left_arrays
-        .iter()
-        .zip(right_arrays)
-        .all(|(l, r)| match l.data_type() {
+    let res = left_arrays.iter().zip(right_arrays).all(|(l, r)| {
+        // dictionaries are compared on their values, so that they can be
+        // joined with other dictionaries and with arrays of their values
+        let resolved = resolve_dictionary(l, left)
+            .and_then(|l| Ok((l, resolve_dictionary(r, right)?)));
+        let ((l, left), (r, right)) = match resolved {
+            Ok((Some(l), Some(r))) => (l, r),
+            // null keys
+            Ok((None, None)) => return null_equals_null,
+            Ok((None, Some((r, right)))) => return null_equals_null && r.is_null(right),
+            Ok((Some((l, left)), None)) => return null_equals_null && l.is_null(left),
+            Err(e) => {
+                err = Some(Err(e));
+                return false;
+            }
+        };
+        match l.data_type() {
             DataType::Null => true,
             DataType::Boolean => {
                 equal_rows_elem!(BooleanArray, l, r, left, right, null_equals_null)
@@ -867,7 +1170,7 @@ fn equal_rows(
             DataType::Float64 => {
                 equal_rows_elem!(Float64Array, l, r, left, right, null_equals_null)
             }
-            DataType::Timestamp(time_unit, None) => match time_unit {
+            DataType::Timestamp(time_unit, _) => match time_unit {
                 TimeUnit::Second => {
                     equal_rows_elem!(
                         TimestampSecondArray,
@@ -915,6 +1218,22 @@ fn equal_rows(
             DataType::LargeUtf8 => {
                 equal_rows_elem!(LargeStringArray, l, r, left, right, null_equals_null)
             }
+            DataType::Binary => {
+                equal_rows_elem!(BinaryArray, l, r, left, right, null_equals_null)
+            }
+            DataType::LargeBinary => {
+                equal_rows_elem!(LargeBinaryArray, l, r, left, right, null_equals_null)
+            }
+            DataType::FixedSizeBinary(_) => {
+                equal_rows_elem!(
+                    FixedSizeBinaryArray,
+                    l,
+                    r,
+                    left,
+                    right,
+                    null_equals_null
+                )
+            }
             _ => {
                 // This is internal because we should have caught this before.
                 err = Some(Err(DataFusionError::Internal(
@@ -922,11 +1241,28 @@ fn equal_rows(
                 )));
                 false
             }
-        });
+        }
+    });
 
     err.unwrap_or(Ok(res))
 }
 
+/// The array holding the value of `array` at `row` and the index of the value
+/// in it: the dictionary values and the key at `row` for a dictionary array.
+/// `None` if the key of a dictionary array is null.
+fn resolve_dictionary(
+    array: &ArrayRef,
+    row: usize,
+) -> Result<Option<(&ArrayRef, usize)>> {
+    match array.data_type() {
+        DataType::Dictionary(_, _) => {
+            let (values, index) = dictionary_value_index(array, row)?;
+            Ok(index.map(|index| (values, index)))
+        }
+        _ => Ok(Some((array, row))),
+    }
+}
+
 // Produces a batch for left-side rows that have/have not been matched during the whole join
 fn produce_from_matched(
     visited_left_side: &BooleanBufferBuilder,
@@ -974,42 +1310,69 @@ impl Stream for HashJoinStream {
         mut self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
     ) -> std::task::Poll<Option<Self::Item>> {
+        if self.cancelled {
+            return std::task::Poll::Ready(None);
+        }
+        if let Some(result) = self.next_probe_output() {
+            return std::task::Poll::Ready(Some(result));
+        }
+        if let Err(e) = self.cancellation.check() {
+            self.cancelled = true;
+            return std::task::Poll::Ready(Some(Err(e.into_arrow_external_error())));
+        }
         self.right
             .poll_next_unpin(cx)
             .map(|maybe_batch| match maybe_batch {
                 Some(Ok(batch)) => {
                     let timer = self.join_metrics.join_time.timer();
-                    let result = build_batch(
-                        &batch,
+                    self.join_metrics.input_batches.add(1);
+                    self.join_metrics.input_rows.add(batch.num_rows());
+                    if self.null_aware {
+                        self.probe_has_rows |= batch.num_rows() > 0;
+                        self.probe_has_null_key |= self
+                            .on_right
+                            .iter()
+                            .any(|column| batch.column(column.index()).null_count() > 0);
+                    }
+                    let (left_indices, right_indices) = match build_join_indexes(
                         &self.left_data,
+                        &batch,
+                        self.join_type,
                         &self.on_left,
                         &self.on_right,
-                        self.join_type,
-                        &self.schema,
-                        &self.column_indices,
                         &self.random_state,
                         &self.null_equals_null,
-                    );
-                    self.join_metrics.input_batches.add(1);
-                    self.join_metrics.input_rows.add(batch.num_rows());
-                    if let Ok((ref batch, ref left_side)) = result {
-                        timer.done();
-                        self.join_metrics.output_batches.add(1);
-                        self.join_metrics.output_rows.add(batch.num_rows());
-
-                        match self.join_type {
-                            JoinType::Left
-                            | JoinType::Full
-                            | JoinType::Semi
-                            | JoinType::Anti => {
-                                left_side.iter().flatten().for_each(|x| {
-                                    self.visited_left_side.set_bit(x as usize, true);
-                                });
-                            }
-                            JoinType::Inner | JoinType::Right => {}
+                    ) {
+                        Ok(indices) => indices,
+                        Err(e) => return Some(Err(e.into_arrow_external_error())),
+                    };
+                    timer.done();
+                    match self.join_type {
+                        JoinType::Left
+                        | JoinType::Full
+                        | JoinType::Semi
+                        | JoinType::Anti => {
+                            left_indices.iter().flatten().for_each(|x| {
+                                self.visited_left_side.set_bit(x as usize, true);
+                            });
                         }
+                        JoinType::Inner | JoinType::Right => {}
+                    }
+
+                    if matches!(self.join_type, JoinType::Semi | JoinType::Anti) {
+                        // the rows of semi and anti joins are produced once the
+                        // probe side is exhausted
+                        let batch = RecordBatch::new_empty(self.schema.clone());
+                        self.join_metrics.record_output(&batch);
+                        return Some(Ok(batch));
                     }
-                    Some(result.map(|x| x.0))
+                    self.probe_output = Some(ProbeOutput {
+                        batch,
+                        left_indices,
+                        right_indices,
+                        offset: 0,
+                    });
+                    self.next_probe_output()
                 }
                 other => {
                     let timer = self.join_metrics.join_time.timer();
@@ -1021,6 +1384,9 @@ impl Stream for HashJoinStream {
                         | JoinType::Anti
                             if !self.is_exhausted =>
                         {
+                            if self.null_aware {
+                                self.visit_null_aware_excluded_rows();
+                            }
                             let result = produce_from_matched(
                                 &self.visited_left_side,
                                 &self.schema,
@@ -1031,10 +1397,7 @@ impl Stream for HashJoinStream {
                             if let Ok(ref batch) = result {
                                 self.join_metrics.input_batches.add(1);
                                 self.join_metrics.input_rows.add(batch.num_rows());
-                                if let Ok(ref batch) = result {
-                                    self.join_metrics.output_batches.add(1);
-                                    self.join_metrics.output_rows.add(batch.num_rows());
-                                }
+                                self.join_metrics.record_output(batch);
                             }
                             timer.done();
                             self.is_exhausted = true;
@@ -1058,13 +1421,15 @@ impl Stream for HashJoinStream {
 mod tests {
     use crate::{
         assert_batches_sorted_eq,
+        execution::context::{ExecutionConfig, ExecutionContext},
         physical_plan::{
             common, expressions::Column, memory::MemoryExec, repartition::RepartitionExec,
         },
-        test::{build_table_i32, columns},
+        test::{build_table_i32, columns, exec::CountingExec},
     };
 
     use super::*;
+    use arrow::datatypes::Field;
     use std::sync::Arc;
 
     fn build_table(
@@ -1101,10 +1466,11 @@ mod tests {
         join_type: &JoinType,
         null_equals_null: bool,
     ) -> Result<(Vec<String>, Vec<RecordBatch>)> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let join = join(left, right, on, join_type, null_equals_null)?;
         let columns = columns(&join.schema());
 
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx).await?;
         let batches = common::collect(stream).await?;
 
         Ok((columns, batches))
@@ -1117,6 +1483,7 @@ mod tests {
         join_type: &JoinType,
         null_equals_null: bool,
     ) -> Result<(Vec<String>, Vec<RecordBatch>)> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let partition_count = 4;
 
         let (left_expr, right_expr) = on
@@ -1148,7 +1515,7 @@ mod tests {
 
         let mut batches = vec![];
         for i in 0..partition_count {
-            let stream = join.execute(i).await?;
+            let stream = join.execute(i, task_ctx.clone()).await?;
             let more_batches = common::collect(stream).await?;
             batches.extend(
                 more_batches
@@ -1246,6 +1613,64 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn collect_left_builds_once() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let left = Arc::new(CountingExec::new(build_table(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        )));
+        let right = build_table(
+            ("a2", &vec![10, 20, 30, 40]),
+            ("b1", &vec![4, 5, 6, 5]),
+            ("c2", &vec![70, 80, 90, 100]),
+        );
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b1", &right.schema())?,
+        )];
+        let right = Arc::new(RepartitionExec::try_new(
+            right,
+            Partitioning::RoundRobinBatch(4),
+        )?);
+
+        let join = join(left.clone(), right, on, &JoinType::Inner, false)?;
+        let streams = futures::future::try_join_all(
+            (0..4).map(|partition| join.execute(partition, task_ctx.clone())),
+        )
+        .await?;
+        let mut batches = vec![];
+        for stream in streams {
+            batches.extend(common::collect(stream).await?);
+        }
+
+        let expected = vec![
+            "+----+----+----+----+----+-----+",
+            "| a1 | b1 | c1 | a2 | b1 | c2  |",
+            "+----+----+----+----+----+-----+",
+            "| 1  | 4  | 7  | 10 | 4  | 70  |",
+            "| 2  | 5  | 8  | 20 | 5  | 80  |",
+            "| 2  | 5  | 8  | 40 | 5  | 100 |",
+            "| 3  | 5  | 9  | 20 | 5  | 80  |",
+            "| 3  | 5  | 9  | 40 | 5  | 100 |",
+            "+----+----+----+----+----+-----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+        assert_eq!(left.executions(), 1);
+
+        // the build is timed once, for the whole operator
+        let metrics = join.metrics().unwrap();
+        let build_times = metrics
+            .iter()
+            .filter(|metric| metric.value().name() == "build_time")
+            .collect::<Vec<_>>();
+        assert_eq!(build_times.len(), 1);
+        assert_eq!(build_times[0].partition(), &None);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_inner_one_no_shared_column_names() -> Result<()> {
         let left = build_table(
@@ -1283,6 +1708,90 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn join_dictionary_with_plain_column() -> Result<()> {
+        use arrow::array::DictionaryArray;
+        use arrow::datatypes::{Field, Int8Type};
+
+        let dict_type =
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8));
+        let left_schema = Arc::new(Schema::new(vec![
+            Field::new("a1", DataType::Int32, false),
+            Field::new("b1", dict_type.clone(), true),
+        ]));
+        let b1: DictionaryArray<Int8Type> = vec![Some("x"), Some("y"), None, Some("y")]
+            .into_iter()
+            .collect();
+        let left_batch = RecordBatch::try_new(
+            left_schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1, 2, 3, 4])), Arc::new(b1)],
+        )?;
+        let left = Arc::new(MemoryExec::try_new(&[vec![left_batch]], left_schema, None)?);
+
+        let right_schema = Arc::new(Schema::new(vec![
+            Field::new("a2", DataType::Int32, false),
+            Field::new("b2", DataType::Utf8, true),
+        ]));
+        let right_batch = RecordBatch::try_new(
+            right_schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![10, 20, 30, 40])),
+                Arc::new(StringArray::from(vec![
+                    Some("y"),
+                    None,
+                    Some("x"),
+                    Some("z"),
+                ])),
+            ],
+        )?;
+        let right = Arc::new(MemoryExec::try_new(
+            &[vec![right_batch]],
+            right_schema,
+            None,
+        )?);
+
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b2", &right.schema())?,
+        )];
+
+        let (_, batches) = join_collect(
+            left.clone(),
+            right.clone(),
+            on.clone(),
+            &JoinType::Inner,
+            false,
+        )
+        .await?;
+        // the dictionary column of the output is still encoded
+        assert_eq!(batches[0].column(1).data_type(), &dict_type);
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 1  | x  | 30 | x  |",
+            "| 2  | y  | 10 | y  |",
+            "| 4  | y  | 10 | y  |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        let (_, batches) = join_collect(left, right, on, &JoinType::Inner, true).await?;
+        let expected = vec![
+            "+----+----+----+----+",
+            "| a1 | b1 | a2 | b2 |",
+            "+----+----+----+----+",
+            "| 1  | x  | 30 | x  |",
+            "| 2  | y  | 10 | y  |",
+            "| 3  |    | 20 |    |",
+            "| 4  | y  | 10 | y  |",
+            "+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn join_inner_two() -> Result<()> {
         let left = build_table(
@@ -1384,6 +1893,7 @@ mod tests {
     /// Test where the left has 1 part, the right has 2 parts => 2 parts
     #[tokio::test]
     async fn join_inner_one_two_parts_right() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 5]), // this has a repetition
@@ -1413,7 +1923,7 @@ mod tests {
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b1", "c2"]);
 
         // first part
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx.clone()).await?;
         let batches = common::collect(stream).await?;
         assert_eq!(batches.len(), 1);
 
@@ -1427,7 +1937,7 @@ mod tests {
         assert_batches_sorted_eq!(expected, &batches);
 
         // second part
-        let stream = join.execute(1).await?;
+        let stream = join.execute(1, task_ctx).await?;
         let batches = common::collect(stream).await?;
         assert_eq!(batches.len(), 1);
         let expected = vec![
@@ -1458,6 +1968,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_left_multi_batch() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
@@ -1478,7 +1989,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b1", "c2"]);
 
-        let stream = join.execute(0).await.unwrap();
+        let stream = join.execute(0, task_ctx).await.unwrap();
         let batches = common::collect(stream).await.unwrap();
 
         let expected = vec![
@@ -1498,6 +2009,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_full_multi_batch() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
@@ -1519,7 +2031,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
 
-        let stream = join.execute(0).await.unwrap();
+        let stream = join.execute(0, task_ctx).await.unwrap();
         let batches = common::collect(stream).await.unwrap();
 
         let expected = vec![
@@ -1541,6 +2053,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_left_empty_right() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 7]),
@@ -1558,7 +2071,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b1", "c2"]);
 
-        let stream = join.execute(0).await.unwrap();
+        let stream = join.execute(0, task_ctx).await.unwrap();
         let batches = common::collect(stream).await.unwrap();
 
         let expected = vec![
@@ -1576,6 +2089,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_full_empty_right() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 7]),
@@ -1593,7 +2107,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
 
-        let stream = join.execute(0).await.unwrap();
+        let stream = join.execute(0, task_ctx).await.unwrap();
         let batches = common::collect(stream).await.unwrap();
 
         let expected = vec![
@@ -1693,6 +2207,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_semi() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 2, 3]),
             ("b1", &vec![4, 5, 5, 7]), // 7 does not exist on the right
@@ -1713,7 +2228,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1"]);
 
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx).await?;
         let batches = common::collect(stream).await?;
 
         let expected = vec![
@@ -1732,6 +2247,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_anti() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 2, 3, 5]),
             ("b1", &vec![4, 5, 5, 7, 7]), // 7 does not exist on the right
@@ -1752,7 +2268,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1"]);
 
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx).await?;
         let batches = common::collect(stream).await?;
 
         let expected = vec![
@@ -1843,6 +2359,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_full_one() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a1", &vec![1, 2, 3]),
             ("b1", &vec![4, 5, 7]), // 7 does not exist on the right
@@ -1863,7 +2380,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a1", "b1", "c1", "a2", "b2", "c2"]);
 
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx).await?;
         let batches = common::collect(stream).await?;
 
         let expected = vec![
@@ -1933,6 +2450,7 @@ mod tests {
 
     #[tokio::test]
     async fn join_with_duplicated_column_names() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let left = build_table(
             ("a", &vec![1, 2, 3]),
             ("b", &vec![4, 5, 7]),
@@ -1954,7 +2472,7 @@ mod tests {
         let columns = columns(&join.schema());
         assert_eq!(columns, vec!["a", "b", "c", "a", "b", "c"]);
 
-        let stream = join.execute(0).await?;
+        let stream = join.execute(0, task_ctx).await?;
         let batches = common::collect(stream).await?;
 
         let expected = vec![
@@ -1969,4 +2487,142 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn join_output_batches_are_bounded() -> Result<()> {
+        let task_ctx =
+            ExecutionContext::with_config(ExecutionConfig::new().with_batch_size(1000))
+                .task_ctx();
+        // all the rows have the same key, each probe-side row joins 2000 rows
+        let left = build_table(
+            ("a1", &vec![1; 2000]),
+            ("b1", &vec![5; 2000]),
+            ("c1", &(0..2000).collect()),
+        );
+        let right = build_table(
+            ("a2", &vec![10; 10]),
+            ("b1", &vec![5; 10]),
+            ("c2", &vec![70; 10]),
+        );
+        let on = vec![(
+            Column::new_with_schema("b1", &left.schema())?,
+            Column::new_with_schema("b1", &right.schema())?,
+        )];
+
+        let join = join(left, right, on, &JoinType::Inner, false)?;
+        let stream = join.execute(0, task_ctx).await?;
+        let batches = common::collect(stream).await?;
+
+        let rows = batches
+            .iter()
+            .map(|batch| batch.num_rows())
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![1000; 20]);
+
+        let metrics = join.metrics().unwrap();
+        let average = metrics
+            .sum(|metric| metric.value().name() == "avg_output_batch_rows")
+            .unwrap();
+        assert_eq!(average.as_usize(), 1000);
+
+        // a single batch per probe-side batch
+        let task_ctx = ExecutionContext::with_config(
+            ExecutionConfig::new()
+                .with_batch_size(1000)
+                .with_split_join_output(false),
+        )
+        .task_ctx();
+        let stream = join.execute(0, task_ctx).await?;
+        let batches = common::collect(stream).await?;
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 20_000);
+
+        Ok(())
+    }
+
+    fn build_nullable_table(
+        name: &str,
+        values: Vec<Option<i32>>,
+    ) -> Arc<dyn ExecutionPlan> {
+        let schema = Arc::new(Schema::new(vec![Field::new(name, DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(values))],
+        )
+        .unwrap();
+        Arc::new(MemoryExec::try_new(&[vec![batch]], schema, None).unwrap())
+    }
+
+    /// The rows of `left` for which `a NOT IN (SELECT b FROM right)` is true
+    async fn null_aware_anti_join(
+        left: Vec<Option<i32>>,
+        right: Vec<Option<i32>>,
+    ) -> Result<Vec<RecordBatch>> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let left = build_nullable_table("a", left);
+        let right = build_nullable_table("b", right);
+        let on = vec![(
+            Column::new_with_schema("a", &left.schema())?,
+            Column::new_with_schema("b", &right.schema())?,
+        )];
+        let join =
+            join(left, right, on, &JoinType::Anti, false)?.with_null_aware(true)?;
+        let stream = join.execute(0, task_ctx).await?;
+        common::collect(stream).await
+    }
+
+    #[tokio::test]
+    async fn join_anti_null_aware() -> Result<()> {
+        // no nulls: the rows without a match
+        let batches =
+            null_aware_anti_join(vec![Some(1), Some(2)], vec![Some(2), Some(3)]).await?;
+        let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "+---+"];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // a null on the build side: `NULL NOT IN (...)` is null
+        let batches =
+            null_aware_anti_join(vec![Some(1), None], vec![Some(2), Some(3)]).await?;
+        let expected = vec!["+---+", "| a |", "+---+", "| 1 |", "+---+"];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        // a null on the probe side: `1 NOT IN (2, NULL)` is null
+        let batches =
+            null_aware_anti_join(vec![Some(1), Some(2)], vec![Some(2), None]).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        // nulls on both sides
+        let batches = null_aware_anti_join(vec![Some(1), None], vec![None]).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+        // an empty probe side: anything is not in an empty list, even null
+        let batches = null_aware_anti_join(vec![Some(1), None], vec![]).await?;
+        let expected = vec!["+---+", "| a |", "+---+", "|   |", "| 1 |", "+---+"];
+        assert_batches_sorted_eq!(expected, &batches);
+
+        Ok(())
+    }
+
+    #[test]
+    fn join_null_aware_requires_single_key_anti_join() -> Result<()> {
+        let left = build_table(
+            ("a1", &vec![1, 2]),
+            ("b1", &vec![4, 5]),
+            ("c1", &vec![7, 8]),
+        );
+        let right = build_table(
+            ("a2", &vec![1, 2]),
+            ("b2", &vec![4, 5]),
+            ("c2", &vec![7, 8]),
+        );
+        let on = vec![(
+            Column::new_with_schema("a1", &left.schema())?,
+            Column::new_with_schema("a2", &right.schema())?,
+        )];
+
+        let err = join(left, right, on, &JoinType::Semi, false)?
+            .with_null_aware(true)
+            .unwrap_err();
+        assert!(err.to_string().contains("can be null-aware"));
+        Ok(())
+    }
 }
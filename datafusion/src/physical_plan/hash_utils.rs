@@ -20,17 +20,28 @@
 use crate::error::{DataFusionError, Result};
 use ahash::{CallHasher, RandomState};
 use arrow::array::{
-    Array, ArrayRef, BooleanArray, Date32Array, Date64Array, DictionaryArray,
-    Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array,
-    LargeStringArray, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
-    TimestampNanosecondArray, UInt16Array, UInt32Array, UInt64Array, UInt8Array,
+    Array, ArrayRef, BinaryArray, BooleanArray, Date32Array, Date64Array,
+    DictionaryArray, FixedSizeBinaryArray, Float32Array, Float64Array, Int16Array,
+    Int32Array, Int64Array, Int8Array, IntervalDayTimeArray, IntervalYearMonthArray,
+    LargeBinaryArray, LargeStringArray, StringArray, TimestampMicrosecondArray,
+    TimestampMillisecondArray, TimestampNanosecondArray, TimestampSecondArray,
+    UInt16Array, UInt32Array, UInt64Array, UInt8Array,
 };
 use arrow::datatypes::{
     ArrowDictionaryKeyType, ArrowNativeType, DataType, Int16Type, Int32Type, Int64Type,
-    Int8Type, TimeUnit, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+    Int8Type, IntervalUnit, TimeUnit, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
 };
 use std::sync::Arc;
 
+/// Random state of the hashes assigning rows to partitions. It is fixed so
+/// that two inputs hash partitioned on the same keys, by [`RepartitionExec`]
+/// or by the hash joins, are co-partitioned.
+///
+/// [`RepartitionExec`]: crate::physical_plan::repartition::RepartitionExec
+pub fn partition_random_state() -> RandomState {
+    RandomState::with_seeds(0, 0, 0, 0)
+}
+
 // Combines two hashes into one hash
 #[inline]
 fn combine_hashes(l: u64, r: u64) -> u64 {
@@ -349,7 +360,17 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
-            DataType::Timestamp(TimeUnit::Millisecond, None) => {
+            DataType::Timestamp(TimeUnit::Second, _) => {
+                hash_array_primitive!(
+                    TimestampSecondArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Timestamp(TimeUnit::Millisecond, _) => {
                 hash_array_primitive!(
                     TimestampMillisecondArray,
                     col,
@@ -359,7 +380,7 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
-            DataType::Timestamp(TimeUnit::Microsecond, None) => {
+            DataType::Timestamp(TimeUnit::Microsecond, _) => {
                 hash_array_primitive!(
                     TimestampMicrosecondArray,
                     col,
@@ -399,6 +420,26 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                hash_array_primitive!(
+                    IntervalYearMonthArray,
+                    col,
+                    i32,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                hash_array_primitive!(
+                    IntervalDayTimeArray,
+                    col,
+                    i64,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
             DataType::Boolean => {
                 hash_array!(
                     BooleanArray,
@@ -429,6 +470,29 @@ pub fn create_hashes<'a>(
                     multi_col
                 );
             }
+            DataType::Binary => {
+                hash_array!(BinaryArray, col, u8, hashes_buffer, random_state, multi_col);
+            }
+            DataType::LargeBinary => {
+                hash_array!(
+                    LargeBinaryArray,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
+            DataType::FixedSizeBinary(_) => {
+                hash_array!(
+                    FixedSizeBinaryArray,
+                    col,
+                    u8,
+                    hashes_buffer,
+                    random_state,
+                    multi_col
+                );
+            }
             DataType::Dictionary(index_type, _) => match **index_type {
                 DataType::Int8 => {
                     create_hashes_dictionary::<Int8Type>(
@@ -581,6 +645,40 @@ mod tests {
         assert_ne!(dict_hashes[0], dict_hashes[2]);
     }
 
+    #[test]
+    // Tests actual values of hashes, which are different if forcing collisions
+    #[cfg(not(feature = "force_hash_collisions"))]
+    fn create_hashes_for_binary_arrays() -> Result<()> {
+        let values: Vec<Option<&[u8]>> = vec![
+            Some(&b"\xde\xad"[..]),
+            None,
+            Some(&b"\xbe\xef"[..]),
+            Some(&b"\xde\xad"[..]),
+        ];
+        let binary: ArrayRef = Arc::new(values.iter().cloned().collect::<BinaryArray>());
+        let large_binary: ArrayRef =
+            Arc::new(values.iter().cloned().collect::<LargeBinaryArray>());
+        let fixed_size_binary: ArrayRef = Arc::new(
+            FixedSizeBinaryArray::try_from_sparse_iter(values.iter().cloned())?,
+        );
+
+        let random_state = RandomState::with_seeds(0, 0, 0, 0);
+        let mut hashes = vec![];
+        for array in [binary, large_binary, fixed_size_binary] {
+            let mut array_hashes = vec![0; values.len()];
+            create_hashes(&[array], &random_state, &mut array_hashes)?;
+            hashes.push(array_hashes);
+        }
+
+        // the same bytes hash the same whatever their binary type
+        assert_eq!(hashes[0], hashes[1]);
+        assert_eq!(hashes[0], hashes[2]);
+        assert_eq!(hashes[0][1], 0);
+        assert_eq!(hashes[0][0], hashes[0][3]);
+        assert_ne!(hashes[0][0], hashes[0][2]);
+        Ok(())
+    }
+
     #[test]
     // Tests actual values of hashes, which are different if forcing collisions
     #[cfg(not(feature = "force_hash_collisions"))]
@@ -20,8 +20,10 @@
 use crate::error::{DataFusionError, Result};
 use crate::logical_plan::JoinType;
 use crate::physical_plan::expressions::Column;
+use crate::physical_plan::{Partitioning, PhysicalExpr};
 use arrow::datatypes::{Field, Schema};
 use std::collections::HashSet;
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 
 /// The on clause of the join, as vector of (left, right) columns.
@@ -145,7 +147,34 @@ pub fn build_join_schema(
             .unzip(),
     };
 
-    (Schema::new(fields), column_indices)
+    // the metadata of the left side takes precedence, as in the logical plan
+    let mut metadata = right.metadata().clone();
+    metadata.extend(left.metadata().clone());
+    (Schema::new_with_metadata(fields, metadata), column_indices)
+}
+
+/// The partitioning of the output of a join whose rows are in the partition of
+/// the right row they were joined with. The columns of the right side follow the
+/// `left_columns` columns of the left side in the output, so the hash columns of
+/// `right` are shifted past them
+pub fn right_output_partitioning(
+    right: Partitioning,
+    left_columns: usize,
+) -> Partitioning {
+    match right {
+        Partitioning::Hash(exprs, n) => exprs
+            .iter()
+            .map(|expr| {
+                let column = expr.as_any().downcast_ref::<Column>()?;
+                let index = column.index() + left_columns;
+                Some(Arc::new(Column::new(column.name(), index)) as Arc<dyn PhysicalExpr>)
+            })
+            .collect::<Option<Vec<_>>>()
+            .map_or(Partitioning::UnknownPartitioning(n), |exprs| {
+                Partitioning::Hash(exprs, n)
+            }),
+        partitioning => partitioning,
+    }
 }
 
 #[cfg(test)]
@@ -210,4 +239,23 @@ mod tests {
 
         assert!(check(&left, &right, on).is_ok());
     }
+
+    #[test]
+    fn right_partitioning_shifted() {
+        // the hash column "a" of the right side follows the 2 left columns
+        let a: Arc<dyn PhysicalExpr> = Arc::new(Column::new("a", 0));
+        let right = Partitioning::Hash(vec![a], 4);
+        let a: Arc<dyn PhysicalExpr> = Arc::new(Column::new("a", 2));
+        let expected = Partitioning::Hash(vec![a], 4);
+        assert_eq!(
+            format!("{:?}", right_output_partitioning(right, 2)),
+            format!("{:?}", expected)
+        );
+
+        let right = Partitioning::RoundRobinBatch(4);
+        assert_eq!(
+            format!("{:?}", right_output_partitioning(right, 2)),
+            format!("{:?}", Partitioning::RoundRobinBatch(4))
+        );
+    }
 }
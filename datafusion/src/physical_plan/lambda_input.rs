@@ -0,0 +1,391 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Placeholder for the input of a plan running in a lambda function: the
+//! plan is serialized without its input data, which is fed to it once it has
+//! been deserialized.
+
+use std::any::Any;
+use std::sync::Arc;
+
+use arrow::datatypes::SchemaRef;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::memory::MemoryStream;
+use super::{
+    common, fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan,
+    Partitioning, SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
+
+/// Leaf operator standing for data that is produced elsewhere, e.g. by
+/// another query stage. It serializes its declared schema and partition
+/// count, reads the partitions fed to it and fails if executed before being
+/// fed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LambdaInputExec {
+    /// Identity of the input, e.g. the stage and partition producing it
+    id: String,
+    /// Schema of the input
+    schema: SchemaRef,
+    /// Number of partitions of the input
+    partition_count: usize,
+    /// The fed partitions
+    #[serde(skip)]
+    partitions: Option<Vec<Vec<RecordBatch>>>,
+}
+
+impl LambdaInputExec {
+    /// Create a new LambdaInputExec that has not been fed yet
+    pub fn new(id: impl Into<String>, schema: SchemaRef, partition_count: usize) -> Self {
+        Self {
+            id: id.into(),
+            schema,
+            partition_count,
+            partitions: None,
+        }
+    }
+
+    /// Create a new LambdaInputExec with the schema and partition count of
+    /// the output of `plan`
+    pub fn for_plan(id: impl Into<String>, plan: &Arc<dyn ExecutionPlan>) -> Self {
+        Self::new(
+            id,
+            plan.schema(),
+            plan.output_partitioning().partition_count(),
+        )
+    }
+
+    /// Identity of the input
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether the input has been fed
+    pub fn is_fed(&self) -> bool {
+        self.partitions.is_some()
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "lambda_input_exec")]
+impl ExecutionPlan for LambdaInputExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        match &self.partitions {
+            Some(partitions) => Partitioning::UnknownPartitioning(partitions.len()),
+            None => Partitioning::UnknownPartitioning(self.partition_count),
+        }
+    }
+
+    fn with_new_children(
+        &self,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::Internal(format!(
+            "Children cannot be replaced in {:?}",
+            self
+        )))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "LambdaInputExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let partitions = self.partitions.as_ref().ok_or_else(|| {
+            DataFusionError::Execution(format!("The input {} has not been fed", self.id))
+        })?;
+        let batches = partitions.get(partition).ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "LambdaInputExec invalid partition {}",
+                partition
+            ))
+        })?;
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(MemoryStream::try_new(
+                batches.clone(),
+                self.schema.clone(),
+                None,
+            )?),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "LambdaInputExec: id={}, partitions={}, fed={}",
+                self.id,
+                self.partition_count,
+                self.is_fed()
+            ),
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        match &self.partitions {
+            Some(partitions) => {
+                common::compute_record_batch_statistics(partitions, &self.schema, None)
+            }
+            None => Statistics::default(),
+        }
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for LambdaInputExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.partitions = Some(partitions);
+        Ok(())
+    }
+}
+
+/// Rewrite `plan` bottom-up, replacing every subtree for which `input_id`
+/// returns an identity by a [`LambdaInputExec`] with that identity and the
+/// schema and partition count of the subtree. `input_id` is called on the
+/// subtrees after their own children have been rewritten.
+pub fn replace_with_inputs<F>(
+    plan: Arc<dyn ExecutionPlan>,
+    input_id: &mut F,
+) -> Result<Arc<dyn ExecutionPlan>>
+where
+    F: FnMut(&Arc<dyn ExecutionPlan>) -> Result<Option<String>>,
+{
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let children = children
+            .into_iter()
+            .map(|child| replace_with_inputs(child, input_id))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(children)?
+    };
+
+    Ok(match input_id(&plan)? {
+        Some(id) => Arc::new(LambdaInputExec::for_plan(id, &plan)),
+        None => plan,
+    })
+}
+
+/// Feed `partitions` to the [`LambdaInputExec`] of `plan` identified by `id`.
+///
+/// Fails if `plan` has no such input or if the number of partitions differs
+/// from the one the input declares.
+pub fn feed_lambda_input(
+    plan: Arc<dyn ExecutionPlan>,
+    id: &str,
+    partitions: Vec<Vec<RecordBatch>>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let mut partitions = Some(partitions);
+    let plan = feed_lambda_input_inner(plan, id, &mut partitions)?;
+    match partitions {
+        None => Ok(plan),
+        Some(_) => Err(DataFusionError::Execution(format!(
+            "The plan has no input {}",
+            id
+        ))),
+    }
+}
+
+fn feed_lambda_input_inner(
+    plan: Arc<dyn ExecutionPlan>,
+    id: &str,
+    partitions: &mut Option<Vec<Vec<RecordBatch>>>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    if let Some(input) = plan.as_any().downcast_ref::<LambdaInputExec>() {
+        if input.id != id {
+            return Ok(plan);
+        }
+        let fed = partitions.take().unwrap_or_default();
+        if fed.len() != input.partition_count {
+            return Err(DataFusionError::Execution(format!(
+                "The input {} expects {} partitions, got {}",
+                id,
+                input.partition_count,
+                fed.len()
+            )));
+        }
+        let mut input = LambdaInputExec::new(
+            input.id.clone(),
+            input.schema.clone(),
+            input.partition_count,
+        );
+        input.feed_batches(fed)?;
+        return Ok(Arc::new(input));
+    }
+
+    let children = plan.children();
+    if children.is_empty() || partitions.is_none() {
+        return Ok(plan);
+    }
+    let children = children
+        .into_iter()
+        .map(|child| feed_lambda_input_inner(child, id, partitions))
+        .collect::<Result<Vec<_>>>()?;
+    plan.with_new_children(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_sorted_eq;
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::JoinType;
+    use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::expressions::Column;
+    use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::test::build_table_i32;
+
+    #[tokio::test]
+    async fn join_fed_inputs() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let left = build_table_i32(
+            ("a1", &vec![1, 2, 3]),
+            ("b1", &vec![4, 5, 5]),
+            ("c1", &vec![7, 8, 9]),
+        );
+        let right = build_table_i32(
+            ("a2", &vec![10, 20, 30]),
+            ("b1", &vec![4, 5, 6]),
+            ("c2", &vec![70, 80, 90]),
+        );
+        let join = HashJoinExec::try_new(
+            Arc::new(LambdaInputExec::new("left", left.schema(), 1)),
+            Arc::new(LambdaInputExec::new("right", right.schema(), 1)),
+            vec![(
+                Column::new_with_schema("b1", &left.schema())?,
+                Column::new_with_schema("b1", &right.schema())?,
+            )],
+            &JoinType::Inner,
+            PartitionMode::CollectLeft,
+            &false,
+        )?;
+        let json =
+            serde_json::to_string(&(Arc::new(join) as Arc<dyn ExecutionPlan>)).unwrap();
+        let plan: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+
+        let inputs = plan.children();
+        let left_input = inputs[0]
+            .as_any()
+            .downcast_ref::<LambdaInputExec>()
+            .unwrap();
+        assert_eq!(left_input.id(), "left");
+        assert!(!left_input.is_fed());
+        let err = collect(plan.clone(), task_ctx.clone()).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: The input left has not been fed"
+        );
+
+        let err = feed_lambda_input(plan.clone(), "other", vec![vec![left.clone()]])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: The plan has no input other"
+        );
+        let err = feed_lambda_input(plan.clone(), "left", vec![]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Execution error: The input left expects 1 partitions, got 0"
+        );
+
+        let plan = feed_lambda_input(plan, "left", vec![vec![left]])?;
+        let plan = feed_lambda_input(plan, "right", vec![vec![right]])?;
+        let expected = vec![
+            "+----+----+----+----+----+----+",
+            "| a1 | b1 | c1 | a2 | b1 | c2 |",
+            "+----+----+----+----+----+----+",
+            "| 1  | 4  | 7  | 10 | 4  | 70 |",
+            "| 2  | 5  | 8  | 20 | 5  | 80 |",
+            "| 3  | 5  | 9  | 20 | 5  | 80 |",
+            "+----+----+----+----+----+----+",
+        ];
+        assert_batches_sorted_eq!(expected, &collect(plan, task_ctx).await?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn replace_subtree() -> Result<()> {
+        let batch =
+            build_table_i32(("a", &vec![1, 2]), ("b", &vec![3, 4]), ("c", &vec![5, 6]));
+        let memory: Arc<dyn ExecutionPlan> = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch.clone()]],
+            batch.schema(),
+            None,
+        )?);
+        let plan: Arc<dyn ExecutionPlan> =
+            Arc::new(CoalesceBatchesExec::new(memory, 1024));
+
+        let plan = replace_with_inputs(plan, &mut |node| {
+            Ok(if node.as_any().is::<MemoryExec>() {
+                Some("scan".to_owned())
+            } else {
+                None
+            })
+        })?;
+        let input = plan.children()[0].clone();
+        let input = input.as_any().downcast_ref::<LambdaInputExec>().unwrap();
+        assert_eq!(input.id(), "scan");
+        assert_eq!(input.schema(), batch.schema());
+        assert_eq!(
+            format!("{:?}", input.output_partitioning()),
+            "UnknownPartitioning(2)"
+        );
+        Ok(())
+    }
+}
@@ -26,9 +26,13 @@ use futures::stream::Stream;
 use futures::stream::StreamExt;
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::physical_plan::{
-    DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    fmt_with_statistics, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
 };
+use crate::physical_plan::memory::feed_input;
+use crate::physical_plan::trace::{self, instrument_stream};
 use crate::physical_plan::LambdaExecPlan;
 use arrow::array::ArrayRef;
 use arrow::compute::limit;
@@ -106,6 +110,10 @@ impl ExecutionPlan for GlobalLimitExec {
         Partitioning::UnknownPartitioning(1)
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -121,7 +129,21 @@ impl ExecutionPlan for GlobalLimitExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "GlobalLimitExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // GlobalLimitExec has a single output partition
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
@@ -137,13 +159,14 @@ impl ExecutionPlan for GlobalLimitExec {
             ));
         }
 
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
-        let stream = self.input.execute(0).await?;
-        Ok(Box::pin(LimitStream::new(
-            stream,
-            self.limit,
-            baseline_metrics,
-        )))
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("GlobalLimitExec", partition));
+        let stream = self.input.execute(0, context).await?;
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(LimitStream::new(stream, self.limit, baseline_metrics)),
+        ))
     }
 
     fn fmt_as(
@@ -155,6 +178,7 @@ impl ExecutionPlan for GlobalLimitExec {
             DisplayFormatType::Default => {
                 write!(f, "GlobalLimitExec: limit={}", self.limit)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -186,8 +210,9 @@ impl ExecutionPlan for GlobalLimitExec {
 
 #[async_trait]
 impl LambdaExecPlan for GlobalLimitExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -247,6 +272,10 @@ impl ExecutionPlan for LocalLimitExec {
         self.input.output_partitioning()
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.input.output_ordering()
+    }
+
     fn with_new_children(
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
@@ -262,14 +291,29 @@ impl ExecutionPlan for LocalLimitExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
-        let stream = self.input.execute(partition).await?;
-        Ok(Box::pin(LimitStream::new(
-            stream,
-            self.limit,
-            baseline_metrics,
-        )))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "LocalLimitExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("LocalLimitExec", partition));
+        let stream = self.input.execute(partition, context).await?;
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(LimitStream::new(stream, self.limit, baseline_metrics)),
+        ))
     }
 
     fn fmt_as(
@@ -281,6 +325,7 @@ impl ExecutionPlan for LocalLimitExec {
             DisplayFormatType::Default => {
                 write!(f, "LocalLimitExec: limit={}", self.limit)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -315,8 +360,9 @@ impl ExecutionPlan for LocalLimitExec {
 
 #[async_trait]
 impl LambdaExecPlan for LocalLimitExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -365,6 +411,7 @@ impl LimitStream {
         let _timer = self.baseline_metrics.elapsed_compute().timer();
         if self.current_len == self.limit {
             self.input = None; // clear input so it can be dropped early
+            trace::early_termination(self.current_len);
             None
         } else if self.current_len + batch.num_rows() <= self.limit {
             self.current_len += batch.num_rows();
@@ -373,6 +420,7 @@ impl LimitStream {
             let batch_rows = self.limit - self.current_len;
             self.current_len = self.limit;
             self.input = None; // clear input so it can be dropped early
+            trace::early_termination(self.current_len);
             Some(truncate_batch(&batch, batch_rows))
         }
     }
@@ -412,6 +460,7 @@ mod tests {
 
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
     use crate::physical_plan::common;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
@@ -419,6 +468,7 @@ mod tests {
 
     #[tokio::test]
     async fn limit() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let num_partitions = 4;
@@ -447,7 +497,7 @@ mod tests {
             GlobalLimitExec::new(Arc::new(CoalescePartitionsExec::new(Arc::new(csv))), 7);
 
         // the result should contain 4 batches (one per input partition)
-        let iter = limit.execute(0).await?;
+        let iter = limit.execute(0, task_ctx).await?;
         let batches = common::collect(iter).await?;
 
         // there should be a total of 100 rows
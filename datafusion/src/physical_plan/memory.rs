@@ -23,9 +23,12 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use super::{
-    common, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream, Statistics,
+    common, fmt_with_statistics, DisplayFormatType, ExecutionPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
 };
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::LambdaExecPlan;
 use crate::error::{DataFusionError, Result};
 use arrow::datatypes::{Field, Schema, SchemaRef};
@@ -49,6 +52,10 @@ pub struct MemoryExec {
     pub projected_schema: SchemaRef,
     /// Optional projection
     pub projection: Option<Vec<usize>>,
+    /// Optional sort order of the batches within each partition, in terms of
+    /// the projected schema
+    #[serde(default)]
+    pub ordering: Option<Vec<PhysicalSortExpr>>,
 }
 
 impl fmt::Debug for MemoryExec {
@@ -86,6 +93,10 @@ impl ExecutionPlan for MemoryExec {
         Partitioning::UnknownPartitioning(self.partitions.len())
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.ordering.as_deref()
+    }
+
     fn with_new_children(
         &self,
         _: Vec<Arc<dyn ExecutionPlan>>,
@@ -96,12 +107,37 @@ impl ExecutionPlan for MemoryExec {
         )))
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(MemoryStream::try_new(
-            self.partitions[partition].clone(),
-            self.projected_schema.clone(),
-            self.projection.clone(),
-        )?))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "MemoryExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let batches = self.partitions.get(partition).ok_or_else(|| {
+            DataFusionError::Internal(format!(
+                "MemoryExec invalid partition {} ({} partitions)",
+                partition,
+                self.partitions.len()
+            ))
+        })?;
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(MemoryStream::try_new(
+                batches.clone(),
+                self.projected_schema.clone(),
+                self.projection.clone(),
+            )?),
+        ))
     }
 
     fn fmt_as(
@@ -118,8 +154,15 @@ impl ExecutionPlan for MemoryExec {
                     "MemoryExec: partitions={}, partition_sizes={:?}",
                     partitions.len(),
                     partitions
-                )
+                )?;
+                if let Some(ordering) = &self.ordering {
+                    let ordering: Vec<String> =
+                        ordering.iter().map(|e| e.to_string()).collect();
+                    write!(f, ", ordering=[{}]", ordering.join(", "))?;
+                }
+                Ok(())
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -135,11 +178,29 @@ impl ExecutionPlan for MemoryExec {
 
 #[async_trait]
 impl LambdaExecPlan for MemoryExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        // the fed batches are already projected
+        self.partitions = partitions;
+        self.schema = self.projected_schema.clone();
+        self.projection = None;
+        Ok(())
     }
 }
 
+/// Replace `input` by a [`MemoryExec`] over the fed `partitions`, which must
+/// have the schema of `input`. This is how the plans with inputs implement
+/// [`LambdaExecPlan`].
+pub fn feed_input(input: &mut Arc<dyn ExecutionPlan>, partitions: Vec<Vec<RecordBatch>>) {
+    let schema = input.schema();
+    *input = Arc::new(MemoryExec {
+        partitions,
+        schema: schema.clone(),
+        projected_schema: schema,
+        projection: None,
+        ordering: None,
+    });
+}
+
 impl MemoryExec {
     /// Create a new execution plan for reading in-memory record batches
     /// The provided `schema` should not have the projection applied.
@@ -162,7 +223,10 @@ impl MemoryExec {
                         }
                     })
                     .collect();
-                Arc::new(Schema::new(fields?))
+                Arc::new(Schema::new_with_metadata(
+                    fields?,
+                    schema.metadata().clone(),
+                ))
             }
             None => Arc::clone(&schema),
         };
@@ -171,9 +235,17 @@ impl MemoryExec {
             schema,
             projected_schema,
             projection,
+            ordering: None,
         })
     }
 
+    /// Declare the sort order of the batches within each partition, in terms
+    /// of the projected schema
+    pub fn with_ordering(mut self, ordering: Vec<PhysicalSortExpr>) -> Self {
+        self.ordering = Some(ordering);
+        self
+    }
+
     /// Set the partitions and schema
     pub fn set_partitions_and_schema(&mut self, partitions: Vec<Vec<RecordBatch>>, schema: SchemaRef) {
         self.partitions = partitions;
@@ -257,6 +329,7 @@ impl RecordBatchStream for MemoryStream {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::ColumnStatistics;
     use arrow::array::Int32Array;
     use arrow::datatypes::{DataType, Field, Schema};
@@ -285,6 +358,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_with_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let (schema, batch) = mock_data()?;
 
         let executor = MemoryExec::try_new(&[vec![batch]], schema, Some(vec![2, 1]))?;
@@ -310,7 +384,7 @@ mod tests {
         );
 
         // scan with projection
-        let mut it = executor.execute(0).await?;
+        let mut it = executor.execute(0, task_ctx).await?;
         let batch2 = it.next().await.unwrap()?;
         assert_eq!(2, batch2.schema().fields().len());
         assert_eq!("c", batch2.schema().field(0).name());
@@ -322,6 +396,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_without_projection() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let (schema, batch) = mock_data()?;
 
         let executor = MemoryExec::try_new(&[vec![batch]], schema, None)?;
@@ -358,7 +433,7 @@ mod tests {
             ])
         );
 
-        let mut it = executor.execute(0).await?;
+        let mut it = executor.execute(0, task_ctx).await?;
         let batch1 = it.next().await.unwrap()?;
         assert_eq!(4, batch1.schema().fields().len());
         assert_eq!(4, batch1.num_columns());
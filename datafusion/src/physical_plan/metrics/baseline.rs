@@ -21,6 +21,8 @@ use std::task::Poll;
 
 use arrow::{error::ArrowError, record_batch::RecordBatch};
 
+use crate::execution::progress::ProgressReporter;
+
 use super::{Count, ExecutionPlanMetricsSet, MetricBuilder, Time, Timestamp};
 
 /// Helper for creating and tracking common "baseline" metrics for
@@ -52,6 +54,10 @@ pub struct BaselineMetrics {
 
     /// output rows: the total output rows
     output_rows: Count,
+
+    /// reporter of the progress of the operator, if the context has a
+    /// progress handler
+    progress: Option<ProgressReporter>,
 }
 
 impl BaselineMetrics {
@@ -64,9 +70,17 @@ impl BaselineMetrics {
             end_time: MetricBuilder::new(metrics).end_timestamp(partition),
             elapsed_compute: MetricBuilder::new(metrics).elapsed_compute(partition),
             output_rows: MetricBuilder::new(metrics).output_rows(partition),
+            progress: None,
         }
     }
 
+    /// Report the output batches to `progress`, typically
+    /// [`TaskContext::progress_reporter`](crate::execution::task_context::TaskContext::progress_reporter)
+    pub fn with_progress(mut self, progress: Option<ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+
     /// return the metric for cpu time spend in this operator
     pub fn elapsed_compute(&self) -> &Time {
         &self.elapsed_compute
@@ -85,7 +99,10 @@ impl BaselineMetrics {
     /// completion, as async streams may not be dropped immediately
     /// depending on the consumer.
     pub fn done(&self) {
-        self.end_time.record()
+        self.end_time.record();
+        if let Some(progress) = &self.progress {
+            progress.done();
+        }
     }
 
     /// Record that some number of rows have been produced as output
@@ -96,6 +113,15 @@ impl BaselineMetrics {
         self.output_rows.add(num_rows);
     }
 
+    /// Record that `batch` has been produced as output, reporting the
+    /// progress of the operator if it is reported
+    pub fn record_batch(&self, batch: &RecordBatch) {
+        self.output_rows.add(batch.num_rows());
+        if let Some(progress) = &self.progress {
+            progress.record_batch(batch);
+        }
+    }
+
     /// Process a poll result of a stream producing output for an
     /// operator, recording the output rows and stream done time and
     /// returning the same poll result
@@ -120,7 +146,7 @@ impl Drop for BaselineMetrics {
     fn drop(&mut self) {
         // if not previously recorded, record
         if self.end_time.value().is_none() {
-            self.end_time.record()
+            self.done()
         }
     }
 }
@@ -143,14 +169,14 @@ impl RecordOutput for usize {
 
 impl RecordOutput for RecordBatch {
     fn record_output(self, bm: &BaselineMetrics) -> Self {
-        bm.record_output(self.num_rows());
+        bm.record_batch(&self);
         self
     }
 }
 
 impl RecordOutput for &RecordBatch {
     fn record_output(self, bm: &BaselineMetrics) -> Self {
-        bm.record_output(self.num_rows());
+        bm.record_batch(self);
         self
     }
 }
@@ -20,7 +20,8 @@
 use std::{borrow::Cow, sync::Arc};
 
 use super::{
-    Count, ExecutionPlanMetricsSet, Label, Metric, MetricValue, Time, Timestamp,
+    Average, Count, ExecutionPlanMetricsSet, Gauge, Label, Metric, MetricValue, Time,
+    Timestamp,
 };
 
 /// Structure for constructing metrics, counters, timers, etc.
@@ -105,6 +106,59 @@ impl<'a> MetricBuilder<'a> {
         count
     }
 
+    /// Consume self and create a new counter for recording the number of
+    /// times the operator spilled to disk
+    pub fn spill_count(self, partition: usize) -> Count {
+        let count = Count::new();
+        self.with_partition(partition)
+            .build(MetricValue::SpillCount(count.clone()));
+        count
+    }
+
+    /// Consume self and create a new counter for recording the number of
+    /// bytes the operator spilled to disk
+    pub fn spilled_bytes(self, partition: usize) -> Count {
+        let count = Count::new();
+        self.with_partition(partition)
+            .build(MetricValue::SpilledBytes(count.clone()));
+        count
+    }
+
+    /// Consume self and create a new gauge for recording the peak memory
+    /// reserved by the operator
+    pub fn mem_used(self, partition: usize) -> Gauge {
+        let gauge = Gauge::new();
+        self.with_partition(partition)
+            .build(MetricValue::MemUsed(gauge.clone()));
+        gauge
+    }
+
+    /// Consume self and create a new gauge for recording the peak memory
+    /// reserved by an overall operator (not per partition)
+    pub fn global_mem_used(self) -> Gauge {
+        let gauge = Gauge::new();
+        self.build(MetricValue::MemUsed(gauge.clone()));
+        gauge
+    }
+
+    /// Consume self and create a new counter for recording the number of
+    /// bytes read from the scanned files
+    pub fn bytes_scanned(self, partition: usize) -> Count {
+        let count = Count::new();
+        self.with_partition(partition)
+            .build(MetricValue::BytesScanned(count.clone()));
+        count
+    }
+
+    /// Consume self and create a new Timer for recording the time spent
+    /// waiting for the bytes of the scanned files
+    pub fn time_fetching(self, partition: usize) -> Time {
+        let time = Time::new();
+        self.with_partition(partition)
+            .build(MetricValue::TimeFetching(time.clone()));
+        time
+    }
+
     /// Consumes self and creates a new [`Count`] for recording some
     /// arbitrary metric of an operator.
     pub fn counter(
@@ -126,6 +180,17 @@ impl<'a> MetricBuilder<'a> {
         count
     }
 
+    /// Consumes self and creates a new Timer for recording some
+    /// time spent by an overall operator (not per partition)
+    pub fn global_timer(self, timer_name: impl Into<Cow<'static, str>>) -> Time {
+        let time = Time::new();
+        self.build(MetricValue::Time {
+            name: timer_name.into(),
+            time: time.clone(),
+        });
+        time
+    }
+
     /// Consume self and create a new Timer for recording the elapsed
     /// CPU time spent by an operator
     pub fn elapsed_compute(self, partition: usize) -> Time {
@@ -150,6 +215,22 @@ impl<'a> MetricBuilder<'a> {
         time
     }
 
+    /// Consumes self and creates a new [`Average`] for recording the
+    /// average of some values of an operator, such as the number of rows
+    /// of its output batches
+    pub fn average(
+        self,
+        average_name: impl Into<Cow<'static, str>>,
+        partition: usize,
+    ) -> Average {
+        let average = Average::new();
+        self.with_partition(partition).build(MetricValue::Average {
+            name: average_name.into(),
+            average: average.clone(),
+        });
+        average
+    }
+
     /// Consumes self and creates a new Timestamp for recording the
     /// starting time of execution for a partition
     pub fn start_timestamp(self, partition: usize) -> Timestamp {
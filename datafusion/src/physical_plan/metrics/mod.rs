@@ -19,6 +19,7 @@
 
 mod baseline;
 mod builder;
+mod tree;
 mod value;
 
 use std::{
@@ -34,7 +35,8 @@ use serde::{Deserialize, Serialize};
 // public exports
 pub use baseline::{BaselineMetrics, RecordOutput};
 pub use builder::MetricBuilder;
-pub use value::{Count, MetricValue, ScopedTimerGuard, Time, Timestamp};
+pub use tree::{collect_tree, metrics_batch, metrics_schema, OperatorMetrics};
+pub use value::{Average, Count, Gauge, MetricValue, ScopedTimerGuard, Time, Timestamp};
 
 /// Something that tracks a value of interest (metric) of a DataFusion
 /// [`ExecutionPlan`] execution.
@@ -200,6 +202,41 @@ impl MetricsSet {
             .map(|v| v.as_usize())
     }
 
+    /// convenience: return the number of spills, aggregated across
+    /// partitions or None if no metric is present
+    pub fn spill_count(&self) -> Option<usize> {
+        self.sum(|metric| matches!(metric.value(), MetricValue::SpillCount(_)))
+            .map(|v| v.as_usize())
+    }
+
+    /// convenience: return the number of spilled bytes, aggregated across
+    /// partitions or None if no metric is present
+    pub fn spilled_bytes(&self) -> Option<usize> {
+        self.sum(|metric| matches!(metric.value(), MetricValue::SpilledBytes(_)))
+            .map(|v| v.as_usize())
+    }
+
+    /// convenience: return the peak memory reserved, summed across
+    /// partitions or None if no metric is present
+    pub fn mem_used(&self) -> Option<usize> {
+        self.sum(|metric| matches!(metric.value(), MetricValue::MemUsed(_)))
+            .map(|v| v.as_usize())
+    }
+
+    /// convenience: return the number of bytes scanned, aggregated across
+    /// partitions or None if no metric is present
+    pub fn bytes_scanned(&self) -> Option<usize> {
+        self.sum(|metric| matches!(metric.value(), MetricValue::BytesScanned(_)))
+            .map(|v| v.as_usize())
+    }
+
+    /// convenience: return the amount of time spent fetching the scanned
+    /// files, aggregated across partitions or None if no metric is present
+    pub fn time_fetching(&self) -> Option<usize> {
+        self.sum(|metric| matches!(metric.value(), MetricValue::TimeFetching(_)))
+            .map(|v| v.as_usize())
+    }
+
     /// Sums the values for metrics for which `f(metric)` returns
     /// true, and returns the value. Returns None if no metrics match
     /// the predicate.
@@ -262,6 +299,36 @@ impl MetricsSet {
         }
     }
 
+    /// Returns a new derived `MetricsSet` where all metrics that had
+    /// the same name have been aggregated together, regardless of
+    /// their partition and labels. The resulting `MetricsSet` has all
+    /// metrics with `Partition=None` and no labels
+    pub fn aggregate_by_name(&self) -> Self {
+        let mut map = HashMap::new();
+
+        for metric in &self.metrics {
+            map.entry(metric.value.name())
+                .and_modify(|accum: &mut Metric| {
+                    accum.value_mut().aggregate(metric.value());
+                })
+                .or_insert_with(|| {
+                    let partition = None;
+                    let mut accum = Metric::new(metric.value().new_empty(), partition);
+                    accum.value_mut().aggregate(metric.value());
+                    accum
+                });
+        }
+
+        let new_metrics = map
+            .into_iter()
+            .map(|(_k, v)| Arc::new(v))
+            .collect::<Vec<_>>();
+
+        Self {
+            metrics: new_metrics,
+        }
+    }
+
     /// Sort the order of metrics so the "most useful" show up first
     pub fn sorted_for_display(mut self) -> Self {
         self.metrics
@@ -536,6 +603,34 @@ mod tests {
         assert!(output_rows[0].partition.is_none())
     }
 
+    #[test]
+    fn test_aggregate_by_name() {
+        let metrics = ExecutionPlanMetricsSet::new();
+
+        let spill_count1 = MetricBuilder::new(&metrics).spill_count(1);
+        spill_count1.add(2);
+        let spill_count2 = MetricBuilder::new(&metrics)
+            .with_new_label("foo", "bar")
+            .spill_count(2);
+        spill_count2.add(3);
+
+        let bytes_scanned = MetricBuilder::new(&metrics).bytes_scanned(1);
+        bytes_scanned.add(100);
+        MetricBuilder::new(&metrics).mem_used(1).set_max(10);
+        MetricBuilder::new(&metrics).mem_used(2).set_max(20);
+
+        let aggregated = metrics.clone_inner().aggregate_by_name();
+        assert_eq!(aggregated.iter().count(), 3);
+        assert!(aggregated
+            .iter()
+            .all(|m| m.partition().is_none() && m.labels().is_empty()));
+        assert_eq!(aggregated.spill_count(), Some(5));
+        assert_eq!(aggregated.bytes_scanned(), Some(100));
+        assert_eq!(aggregated.mem_used(), Some(30));
+        assert_eq!(aggregated.spilled_bytes(), None);
+        assert_eq!(aggregated.time_fetching(), None);
+    }
+
     #[test]
     #[should_panic(expected = "Mismatched metric types. Can not aggregate Count")]
     fn test_aggregate_partition_bad_sum() {
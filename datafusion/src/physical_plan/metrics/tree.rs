@@ -0,0 +1,263 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Metrics of all the operators of an executed plan, as rows that can be
+//! registered as a table and queried with SQL
+
+use std::fmt;
+use std::sync::Arc;
+
+use arrow::array::{StringArray, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+
+use crate::error::{DataFusionError, Result};
+use crate::physical_plan::{
+    accept, DisplayFormatType, ExecutionPlan, ExecutionPlanVisitor,
+};
+
+/// A metric of an operator of an executed plan, see [`collect_tree`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperatorMetrics {
+    /// Position of the operator in a depth-first walk of the plan, visiting
+    /// the operators before their inputs
+    pub operator_id: usize,
+    /// Name of the operator, such as `FilterExec`
+    pub operator: String,
+    /// Depth of the operator in the plan, 0 for its root
+    pub depth: usize,
+    /// Partition the metric applies to, all of them if `None`
+    pub partition: Option<usize>,
+    /// Name of the metric, such as `output_rows`
+    pub metric: String,
+    /// Labels of the metric, as `name=value` pairs separated by commas
+    pub labels: String,
+    /// Value of the metric, see
+    /// [`MetricValue::as_usize`](super::MetricValue::as_usize)
+    pub value: usize,
+}
+
+/// Collect the metrics of all the operators of `plan`, once executed: a row
+/// per metric of each partition of each operator. The operators are visited
+/// before their inputs, in the order of [`accept`].
+pub fn collect_tree(plan: &dyn ExecutionPlan) -> Vec<OperatorMetrics> {
+    let mut visitor = TreeVisitor {
+        next_id: 0,
+        depth: 0,
+        rows: vec![],
+    };
+    match accept(plan, &mut visitor) {
+        Ok(()) => visitor.rows,
+        Err(e) => match e {},
+    }
+}
+
+/// Schema of the batches of [`metrics_batch`]
+pub fn metrics_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("operator_id", DataType::UInt64, false),
+        Field::new("operator", DataType::Utf8, false),
+        Field::new("depth", DataType::UInt64, false),
+        Field::new("partition", DataType::UInt64, true),
+        Field::new("metric", DataType::Utf8, false),
+        Field::new("labels", DataType::Utf8, false),
+        Field::new("value", DataType::UInt64, false),
+    ]))
+}
+
+/// Convert the metrics collected by [`collect_tree`] into a batch of the
+/// schema [`metrics_schema`], to be registered as a table, e.g. a
+/// [`MemTable`](crate::datasource::MemTable)
+pub fn metrics_batch(metrics: &[OperatorMetrics]) -> Result<RecordBatch> {
+    let operator_id = metrics
+        .iter()
+        .map(|m| m.operator_id as u64)
+        .collect::<UInt64Array>();
+    let operator = metrics
+        .iter()
+        .map(|m| Some(m.operator.as_str()))
+        .collect::<StringArray>();
+    let depth = metrics
+        .iter()
+        .map(|m| m.depth as u64)
+        .collect::<UInt64Array>();
+    let partition = metrics
+        .iter()
+        .map(|m| m.partition.map(|p| p as u64))
+        .collect::<UInt64Array>();
+    let metric = metrics
+        .iter()
+        .map(|m| Some(m.metric.as_str()))
+        .collect::<StringArray>();
+    let labels = metrics
+        .iter()
+        .map(|m| Some(m.labels.as_str()))
+        .collect::<StringArray>();
+    let value = metrics
+        .iter()
+        .map(|m| m.value as u64)
+        .collect::<UInt64Array>();
+    RecordBatch::try_new(
+        metrics_schema(),
+        vec![
+            Arc::new(operator_id),
+            Arc::new(operator),
+            Arc::new(depth),
+            Arc::new(partition),
+            Arc::new(metric),
+            Arc::new(labels),
+            Arc::new(value),
+        ],
+    )
+    .map_err(DataFusionError::ArrowError)
+}
+
+/// Name of an operator: its [`DisplayFormatType::Default`] format, up to the
+/// first colon
+fn operator_name(plan: &dyn ExecutionPlan) -> String {
+    struct Node<'a>(&'a dyn ExecutionPlan);
+    impl<'a> fmt::Display for Node<'a> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.fmt_as(DisplayFormatType::Default, f)
+        }
+    }
+    let description = Node(plan).to_string();
+    match description.split_once(':') {
+        Some((name, _)) => name.to_owned(),
+        None => description,
+    }
+}
+
+struct TreeVisitor {
+    next_id: usize,
+    depth: usize,
+    rows: Vec<OperatorMetrics>,
+}
+
+impl ExecutionPlanVisitor for TreeVisitor {
+    type Error = std::convert::Infallible;
+
+    fn pre_visit(
+        &mut self,
+        plan: &dyn ExecutionPlan,
+    ) -> std::result::Result<bool, Self::Error> {
+        let operator_id = self.next_id;
+        self.next_id += 1;
+        if let Some(metrics) = plan.metrics() {
+            let operator = operator_name(plan);
+            for metric in metrics.sorted_for_display().iter() {
+                let labels = metric
+                    .labels()
+                    .iter()
+                    .map(|label| label.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                self.rows.push(OperatorMetrics {
+                    operator_id,
+                    operator: operator.clone(),
+                    depth: self.depth,
+                    partition: *metric.partition(),
+                    metric: metric.value().name().to_owned(),
+                    labels,
+                    value: metric.value().as_usize(),
+                });
+            }
+        }
+        self.depth += 1;
+        Ok(true)
+    }
+
+    fn post_visit(
+        &mut self,
+        _plan: &dyn ExecutionPlan,
+    ) -> std::result::Result<bool, Self::Error> {
+        self.depth -= 1;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::MemTable;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::{collect, displayable};
+    use crate::test::make_partition;
+    use arrow::array::Array;
+
+    #[tokio::test]
+    async fn collect_join_tree() -> Result<()> {
+        let config = ExecutionConfig::new().with_target_partitions(2);
+        let mut ctx = ExecutionContext::with_config(config);
+        let batch = make_partition(5);
+        for name in &["t1", "t2"] {
+            let table = MemTable::try_new(batch.schema(), vec![vec![batch.clone()]; 2])?;
+            ctx.register_table(*name, Arc::new(table))?;
+        }
+        let plan = ctx.create_logical_plan("SELECT * FROM t1 JOIN t2 ON t1.i = t2.i")?;
+        let plan = ctx.optimize(&plan)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        let results = collect(plan.clone(), ctx.task_ctx()).await?;
+        let joined_rows = results.iter().map(|b| b.num_rows()).sum::<usize>();
+
+        let tree = collect_tree(plan.as_ref());
+
+        // the operators and their depths are the lines of the indented plan
+        let lines = displayable(plan.as_ref()).indent().to_string();
+        let lines = lines.lines().collect::<Vec<_>>();
+        for row in &tree {
+            let line = lines[row.operator_id];
+            let description = line.trim_start();
+            assert_eq!((line.len() - description.len()) / 2, row.depth);
+            assert!(description.starts_with(&row.operator));
+        }
+        let join_rows = tree
+            .iter()
+            .filter(|m| m.operator == "HashJoinExec" && m.metric == "output_rows")
+            .map(|m| m.value)
+            .sum::<usize>();
+        assert_eq!(join_rows, joined_rows);
+
+        let batch = metrics_batch(&tree)?;
+        assert_eq!(batch.schema(), metrics_schema());
+        assert_eq!(batch.num_rows(), tree.len());
+        let operator = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let partition = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        let value = batch
+            .column(6)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        for (i, row) in tree.iter().enumerate() {
+            assert_eq!(operator.value(i), row.operator);
+            assert_eq!(
+                partition.is_valid(i).then(|| partition.value(i) as usize),
+                row.partition
+            );
+            assert_eq!(value.value(i) as usize, row.value);
+        }
+        Ok(())
+    }
+}
@@ -73,6 +73,62 @@ impl Count {
     }
 }
 
+/// A gauge to record a value that goes up and down, such as the number
+/// of bytes of memory reserved by an operator
+///
+/// Note `clone`ing gauges update the same underlying metrics
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Gauge {
+    /// value of the metric gauge
+    value: std::sync::Arc<AtomicUsize>,
+}
+
+impl PartialEq for Gauge {
+    fn eq(&self, other: &Self) -> bool {
+        self.value().eq(&other.value())
+    }
+}
+
+impl Display for Gauge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl Gauge {
+    /// create a new gauge
+    pub fn new() -> Self {
+        Self {
+            value: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Add `n` to the metric's value
+    pub fn add(&self, n: usize) {
+        self.value.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Subtract `n` from the metric's value
+    pub fn sub(&self, n: usize) {
+        self.value.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    /// Set the metric's value to `n`
+    pub fn set(&self, n: usize) {
+        self.value.store(n, Ordering::Relaxed);
+    }
+
+    /// Set the metric's value to `n` if it is larger, to record a peak
+    pub fn set_max(&self, n: usize) {
+        self.value.fetch_max(n, Ordering::Relaxed);
+    }
+
+    /// Get the current value
+    pub fn value(&self) -> usize {
+        self.value.load(Ordering::Relaxed)
+    }
+}
+
 /// Measure a potentially non contiguous duration of time
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Time {
@@ -134,6 +190,71 @@ impl Time {
     }
 }
 
+/// An average of recorded values, such as the number of rows of the
+/// batches produced by an operator
+///
+/// Note `clone`ing averages update the same underlying metrics
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Average {
+    /// sum of the recorded values
+    sum: Arc<AtomicUsize>,
+    /// number of recorded values
+    count: Arc<AtomicUsize>,
+}
+
+impl PartialEq for Average {
+    fn eq(&self, other: &Self) -> bool {
+        self.value().eq(&other.value())
+    }
+}
+
+impl Display for Average {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.value())
+    }
+}
+
+impl Average {
+    /// create a new average of no values
+    pub fn new() -> Self {
+        Self {
+            sum: Arc::new(AtomicUsize::new(0)),
+            count: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Record `value`
+    pub fn add(&self, value: usize) {
+        self.sum.fetch_add(value, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Add the values recorded by `other` to self
+    pub fn merge(&self, other: &Average) {
+        self.sum.fetch_add(other.sum(), Ordering::Relaxed);
+        self.count.fetch_add(other.count(), Ordering::Relaxed);
+    }
+
+    /// Sum of the recorded values
+    pub fn sum(&self) -> usize {
+        self.sum.load(Ordering::Relaxed)
+    }
+
+    /// Number of recorded values
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    /// Get the average of the recorded values, rounded down, or 0 if no
+    /// value was recorded
+    pub fn value(&self) -> usize {
+        match self.count() {
+            0 => 0,
+            count => self.sum() / count,
+        }
+    }
+}
+
 /// Stores a single timestamp, stored as the number of nanoseconds
 /// elapsed from Jan 1, 1970 UTC
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -267,6 +388,21 @@ pub enum MetricValue {
     /// classical defintion of "cpu_time", which is the time reported
     /// from `clock_gettime(CLOCK_THREAD_CPUTIME_ID, ..)`.
     ElapsedCompute(Time),
+    /// Number of times the operator spilled its buffered data to disk:
+    /// "spill_count" metric
+    SpillCount(Count),
+    /// Number of bytes the operator wrote to its spill files:
+    /// "spilled_bytes" metric
+    SpilledBytes(Count),
+    /// Largest number of bytes of memory reserved by the operator:
+    /// "mem_used" metric
+    MemUsed(Gauge),
+    /// Number of bytes read from the files scanned by the operator:
+    /// "bytes_scanned" metric
+    BytesScanned(Count),
+    /// Time spent opening the scanned files and waiting for their bytes,
+    /// as opposed to decoding them: "time_fetching" metric
+    TimeFetching(Time),
     /// Operator defined count.
     Count {
         /// The provided name of this metric
@@ -281,6 +417,13 @@ pub enum MetricValue {
         /// The value of the metric
         time: Time,
     },
+    /// Operator defined average
+    Average {
+        /// The provided name of this metric
+        name: Cow<'static, str>,
+        /// The value of the metric
+        average: Average,
+    },
     /// The time at which execution started
     StartTimestamp(Timestamp),
     /// The time at which execution ended
@@ -293,8 +436,14 @@ impl MetricValue {
         match self {
             Self::OutputRows(_) => "output_rows",
             Self::ElapsedCompute(_) => "elapsed_compute",
+            Self::SpillCount(_) => "spill_count",
+            Self::SpilledBytes(_) => "spilled_bytes",
+            Self::MemUsed(_) => "mem_used",
+            Self::BytesScanned(_) => "bytes_scanned",
+            Self::TimeFetching(_) => "time_fetching",
             Self::Count { name, .. } => name.borrow(),
             Self::Time { name, .. } => name.borrow(),
+            Self::Average { name, .. } => name.borrow(),
             Self::StartTimestamp(_) => "start_timestamp",
             Self::EndTimestamp(_) => "end_timestamp",
         }
@@ -305,8 +454,14 @@ impl MetricValue {
         match self {
             Self::OutputRows(count) => count.value(),
             Self::ElapsedCompute(time) => time.value(),
+            Self::SpillCount(count) => count.value(),
+            Self::SpilledBytes(count) => count.value(),
+            Self::MemUsed(gauge) => gauge.value(),
+            Self::BytesScanned(count) => count.value(),
+            Self::TimeFetching(time) => time.value(),
             Self::Count { count, .. } => count.value(),
             Self::Time { time, .. } => time.value(),
+            Self::Average { average, .. } => average.value(),
             Self::StartTimestamp(timestamp) => timestamp
                 .value()
                 .map(|ts| ts.timestamp_nanos() as usize)
@@ -324,6 +479,11 @@ impl MetricValue {
         match self {
             Self::OutputRows(_) => Self::OutputRows(Count::new()),
             Self::ElapsedCompute(_) => Self::ElapsedCompute(Time::new()),
+            Self::SpillCount(_) => Self::SpillCount(Count::new()),
+            Self::SpilledBytes(_) => Self::SpilledBytes(Count::new()),
+            Self::MemUsed(_) => Self::MemUsed(Gauge::new()),
+            Self::BytesScanned(_) => Self::BytesScanned(Count::new()),
+            Self::TimeFetching(_) => Self::TimeFetching(Time::new()),
             Self::Count { name, .. } => Self::Count {
                 name: name.clone(),
                 count: Count::new(),
@@ -332,6 +492,10 @@ impl MetricValue {
                 name: name.clone(),
                 time: Time::new(),
             },
+            Self::Average { name, .. } => Self::Average {
+                name: name.clone(),
+                average: Average::new(),
+            },
             Self::StartTimestamp(_) => Self::StartTimestamp(Timestamp::new()),
             Self::EndTimestamp(_) => Self::EndTimestamp(Timestamp::new()),
         }
@@ -349,6 +513,9 @@ impl MetricValue {
     pub fn aggregate(&mut self, other: &Self) {
         match (self, other) {
             (Self::OutputRows(count), Self::OutputRows(other_count))
+            | (Self::SpillCount(count), Self::SpillCount(other_count))
+            | (Self::SpilledBytes(count), Self::SpilledBytes(other_count))
+            | (Self::BytesScanned(count), Self::BytesScanned(other_count))
             | (
                 Self::Count { count, .. },
                 Self::Count {
@@ -356,12 +523,24 @@ impl MetricValue {
                 },
             ) => count.add(other_count.value()),
             (Self::ElapsedCompute(time), Self::ElapsedCompute(other_time))
+            | (Self::TimeFetching(time), Self::TimeFetching(other_time))
             | (
                 Self::Time { time, .. },
                 Self::Time {
                     time: other_time, ..
                 },
             ) => time.add(other_time),
+            (
+                Self::Average { average, .. },
+                Self::Average {
+                    average: other_average,
+                    ..
+                },
+            ) => average.merge(other_average),
+            // the partitions reserve their memory at the same time
+            (Self::MemUsed(gauge), Self::MemUsed(other_gauge)) => {
+                gauge.add(other_gauge.value())
+            }
             // timestamps are aggregated by min/max
             (Self::StartTimestamp(timestamp), Self::StartTimestamp(other_timestamp)) => {
                 timestamp.update_to_min(other_timestamp);
@@ -385,8 +564,13 @@ impl MetricValue {
         match self {
             Self::OutputRows(_) => 0,     // show first
             Self::ElapsedCompute(_) => 1, // show second
-            Self::Count { .. } => 2,
-            Self::Time { .. } => 3,
+            Self::SpillCount(_)
+            | Self::SpilledBytes(_)
+            | Self::MemUsed(_)
+            | Self::BytesScanned(_)
+            | Self::Count { .. }
+            | Self::Average { .. } => 2,
+            Self::TimeFetching(_) | Self::Time { .. } => 3,
             Self::StartTimestamp(_) => 4, // show timestamps last
             Self::EndTimestamp(_) => 5,
         }
@@ -402,10 +586,18 @@ impl std::fmt::Display for MetricValue {
     /// Prints the value of this metric
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::OutputRows(count) | Self::Count { count, .. } => {
+            Self::OutputRows(count)
+            | Self::SpillCount(count)
+            | Self::SpilledBytes(count)
+            | Self::BytesScanned(count)
+            | Self::Count { count, .. } => {
                 write!(f, "{}", count)
             }
-            Self::ElapsedCompute(time) | Self::Time { time, .. } => {
+            Self::MemUsed(gauge) => write!(f, "{}", gauge),
+            Self::Average { average, .. } => write!(f, "{}", average),
+            Self::ElapsedCompute(time)
+            | Self::TimeFetching(time)
+            | Self::Time { time, .. } => {
                 // distinguish between no time recorded and very small
                 // amount of time recorded
                 if time.value() > 0 {
@@ -432,6 +624,9 @@ mod tests {
         let count = Count::new();
         let values = vec![
             MetricValue::OutputRows(count.clone()),
+            MetricValue::SpillCount(count.clone()),
+            MetricValue::SpilledBytes(count.clone()),
+            MetricValue::BytesScanned(count.clone()),
             MetricValue::Count {
                 name: "my_counter".into(),
                 count: count.clone(),
@@ -448,11 +643,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_average() {
+        let average = Average::new();
+        let mut value = MetricValue::Average {
+            name: "my_average".into(),
+            average: average.clone(),
+        };
+        assert_eq!("0", value.to_string());
+
+        average.add(4);
+        average.add(7);
+        assert_eq!("5", value.to_string());
+
+        // aggregated averages weigh the values of both
+        let other = Average::new();
+        other.add(10);
+        value.aggregate(&MetricValue::Average {
+            name: "my_average".into(),
+            average: other,
+        });
+        assert_eq!(value.as_usize(), 7);
+        assert_eq!(average.count(), 3);
+    }
+
+    #[test]
+    fn test_gauge() {
+        let gauge = Gauge::new();
+        let mut value = MetricValue::MemUsed(gauge.clone());
+        assert_eq!("0", value.to_string());
+
+        gauge.set_max(30);
+        gauge.set_max(20);
+        assert_eq!("30", value.to_string());
+        gauge.sub(10);
+        gauge.add(5);
+        assert_eq!(gauge.value(), 25);
+
+        // the peaks of the partitions add up
+        let other = Gauge::new();
+        other.set(15);
+        value.aggregate(&MetricValue::MemUsed(other));
+        assert_eq!(value.as_usize(), 40);
+    }
+
     #[test]
     fn test_display_time() {
         let time = Time::new();
         let values = vec![
             MetricValue::ElapsedCompute(time.clone()),
+            MetricValue::TimeFetching(time.clone()),
             MetricValue::Time {
                 name: "my_time".into(),
                 time: time.clone(),
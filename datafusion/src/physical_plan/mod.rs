@@ -22,6 +22,9 @@ use self::metrics::MetricsSet;
 use self::{
     coalesce_partitions::CoalescePartitionsExec, display::DisplayableExecutionPlan,
 };
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::logical_plan::PlanSummary;
 use crate::physical_plan::expressions::PhysicalSortExpr;
 use crate::{
     error::{DataFusionError, Result},
@@ -34,7 +37,7 @@ use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
 use async_trait::async_trait;
-pub use display::DisplayFormatType;
+pub use display::{fmt_with_statistics, DisplayFormatType};
 use futures::stream::Stream;
 use std::fmt;
 use std::fmt::{Debug, Display};
@@ -43,7 +46,7 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{any::Any, pin::Pin};
 
-use serde::{Deserialize, Serialize};
+use ::serde::{Deserialize, Serialize};
 
 /// Trait for types that stream [arrow::record_batch::RecordBatch]
 pub trait RecordBatchStream: Stream<Item = ArrowResult<RecordBatch>> {
@@ -90,6 +93,10 @@ impl Stream for EmptyRecordBatchStream {
 
 /// Physical planner interface
 pub use self::planner::PhysicalPlanner;
+/// Deserialization of plans calling user-defined functions
+pub use self::serde::from_json_with_registry;
+/// Binding of deserialized plans to the runtime environment
+pub use crate::execution::runtime_env::bind_runtime;
 
 /// Statistics for a physical plan node
 /// Fields are optional and can be inexact because the sources
@@ -143,6 +150,16 @@ pub trait ExecutionPlan: Debug + Send + Sync + LambdaExecPlan {
     fn schema(&self) -> SchemaRef;
     /// Specifies the output partitioning scheme of this plan
     fn output_partitioning(&self) -> Partitioning;
+    /// If the output of each partition of this plan is sorted, returns the
+    /// sort order. `None` means no particular order is guaranteed.
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
+    /// If this plan relies on each partition of its children being sorted,
+    /// returns that sort order. `None` means it accepts any order.
+    fn required_input_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        None
+    }
     /// Specifies the data distribution requirements of all the children for this operator
     fn required_child_distribution(&self) -> Distribution {
         Distribution::UnspecifiedDistribution
@@ -158,8 +175,14 @@ pub trait ExecutionPlan: Debug + Send + Sync + LambdaExecPlan {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>>;
 
-    /// creates an iterator
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream>;
+    /// Returns the stream of the batches of the output `partition`, reading
+    /// the settings and the runtime environment of the execution from
+    /// `context` and passing it on to the children
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream>;
 
     /// Return a snapshot of the set of [`Metric`]s for this
     /// [`ExecutionPlan`].
@@ -178,7 +201,9 @@ pub trait ExecutionPlan: Debug + Send + Sync + LambdaExecPlan {
 
     /// Format this `ExecutionPlan` to `f` in the specified type.
     ///
-    /// Should not include a newline
+    /// Should not include a newline. Most operators format themselves
+    /// in the [`DisplayFormatType::Verbose`] type with
+    /// [`fmt_with_statistics`].
     ///
     /// Note this function prints a placeholder by default to preserve
     /// backwards compatibility.
@@ -188,12 +213,72 @@ pub trait ExecutionPlan: Debug + Send + Sync + LambdaExecPlan {
 
     /// Returns the global output statistics for this `ExecutionPlan` node.
     fn statistics(&self) -> Statistics;
+
+    /// Returns a copy of this `ExecutionPlan` bound to the runtime
+    /// environment of the executing process (object stores, batch size,
+    /// memory limit...), or `None` if it does not depend on it. It is called
+    /// on every operator of a plan by [`bind_runtime`], after the children of
+    /// the operator have been bound.
+    fn with_runtime(
+        &self,
+        _runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        Ok(None)
+    }
+}
+
+impl dyn ExecutionPlan {
+    /// Returns a 64-bit digest identifying this plan, see
+    /// [`fingerprint::fingerprint`].
+    ///
+    /// Plans built the same way have the same fingerprint, in any process and
+    /// after any number of serde round trips, so it can be used to cache and
+    /// deduplicate compiled query stages.
+    pub fn fingerprint(&self) -> Result<u64> {
+        fingerprint::fingerprint(self)
+    }
+
+    /// Returns the structured summary of this plan, see
+    /// [`summary::to_summary`].
+    pub fn to_summary(&self) -> Result<PlanSummary> {
+        summary::to_summary(self)
+    }
 }
 
 /// Partition-aware execution plan for a relation on AWS Lambda
-pub trait LambdaExecPlan: Debug + Send + Sync { 
-    /// Feed record batches from other lambda function
-    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>);
+///
+/// The record batches computed by other lambda functions are fed to a plan
+/// in place of one of its inputs:
+/// - leaf plans (the file scans, [`MemoryExec`](memory::MemoryExec), ...)
+///   read the fed partitions instead of their data source. The fed batches
+///   must have the output schema of the plan.
+/// - the other plans replace their input at index `child` of
+///   [`ExecutionPlan::children`] by a [`MemoryExec`](memory::MemoryExec)
+///   over the fed partitions, see [`memory::feed_input`]. The fed batches
+///   must have the schema of the replaced input. For the joins, the left
+///   (build) side is the child `0` and the right (probe) side the child `1`.
+///
+/// Feeding a plan that has no such input returns an error.
+pub trait LambdaExecPlan: Debug + Send + Sync {
+    /// Feed record batches from other lambda functions to the first input of
+    /// the plan, or to the plan itself if it is a leaf
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()>;
+
+    /// Feed record batches from other lambda functions to the input `child`
+    /// of the plan. Only the plans with several inputs need to implement it.
+    fn feed_child_batches(
+        &mut self,
+        child: usize,
+        partitions: Vec<Vec<RecordBatch>>,
+    ) -> Result<()> {
+        match child {
+            0 => self.feed_batches(partitions),
+            _ => Err(DataFusionError::Plan(format!(
+                "{:?} has no input {} to feed",
+                self, child
+            ))),
+        }
+    }
 }
 
 /// Return a [wrapper](DisplayableExecutionPlan) around an
@@ -225,11 +310,9 @@ pub trait LambdaExecPlan: Debug + Send + Sync {
 ///   let displayable_plan = displayable(physical_plan.as_ref());
 ///   let plan_string = format!("{}", displayable_plan.indent());
 ///
-///   assert_eq!("ProjectionExec: expr=[a@0 as a]\
-///              \n  CoalesceBatchesExec: target_batch_size=4096\
-///              \n    FilterExec: a@0 < 5\
-///              \n      RepartitionExec: partitioning=RoundRobinBatch(3)\
-///              \n        CsvExec: files=[tests/example.csv], has_header=true, batch_size=8192, limit=None",
+///   assert_eq!("FilterExec: a@0 < 5\
+///              \n  RepartitionExec: partitioning=RoundRobinBatch(3)\
+///              \n    CsvExec: files=[tests/example.csv], has_header=true, batch_size=8192, limit=None",
 ///               plan_string.trim());
 /// }
 /// ```
@@ -242,7 +325,7 @@ pub fn displayable(plan: &dyn ExecutionPlan) -> DisplayableExecutionPlan<'_> {
 // Note that this would be really nice if it were a method on
 // ExecutionPlan, but it can not be because it takes a generic
 // parameter and `ExecutionPlan` is a trait
-pub fn accept<V: ExecutionPlanVisitor>(
+pub fn accept<V: ExecutionPlanVisitor + ?Sized>(
     plan: &dyn ExecutionPlan,
     visitor: &mut V,
 ) -> std::result::Result<(), V::Error> {
@@ -308,7 +391,7 @@ pub trait ExecutionPlanVisitor {
 
 /// Recursively calls `pre_visit` and `post_visit` for this node and
 /// all of its children, as described on [`ExecutionPlanVisitor`]
-pub fn visit_execution_plan<V: ExecutionPlanVisitor>(
+pub fn visit_execution_plan<V: ExecutionPlanVisitor + ?Sized>(
     plan: &dyn ExecutionPlan,
     visitor: &mut V,
 ) -> std::result::Result<(), V::Error> {
@@ -321,24 +404,28 @@ pub fn visit_execution_plan<V: ExecutionPlanVisitor>(
 }
 
 /// Execute the [ExecutionPlan] and collect the results in memory
-pub async fn collect(plan: Arc<dyn ExecutionPlan>) -> Result<Vec<RecordBatch>> {
-    let stream = execute_stream(plan).await?;
+pub async fn collect(
+    plan: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
+) -> Result<Vec<RecordBatch>> {
+    let stream = execute_stream(plan, context).await?;
     common::collect(stream).await
 }
 
 /// Execute the [ExecutionPlan] and return a single stream of results
 pub async fn execute_stream(
     plan: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
 ) -> Result<SendableRecordBatchStream> {
     match plan.output_partitioning().partition_count() {
         0 => Ok(Box::pin(EmptyRecordBatchStream::new(plan.schema()))),
-        1 => plan.execute(0).await,
+        1 => plan.execute(0, context).await,
         _ => {
             // merge into a single partition
             let plan = CoalescePartitionsExec::new(plan.clone());
             // CoalescePartitionsExec must produce a single partition
             assert_eq!(1, plan.output_partitioning().partition_count());
-            plan.execute(0).await
+            plan.execute(0, context).await
         }
     }
 }
@@ -346,8 +433,9 @@ pub async fn execute_stream(
 /// Execute the [ExecutionPlan] and collect the results in memory
 pub async fn collect_partitioned(
     plan: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
 ) -> Result<Vec<Vec<RecordBatch>>> {
-    let streams = execute_stream_partitioned(plan).await?;
+    let streams = execute_stream_partitioned(plan, context).await?;
     let mut batches = Vec::with_capacity(streams.len());
     for stream in streams {
         batches.push(common::collect(stream).await?);
@@ -358,11 +446,12 @@ pub async fn collect_partitioned(
 /// Execute the [ExecutionPlan] and return a vec with one stream per output partition
 pub async fn execute_stream_partitioned(
     plan: Arc<dyn ExecutionPlan>,
+    context: Arc<TaskContext>,
 ) -> Result<Vec<SendableRecordBatchStream>> {
     let num_partitions = plan.output_partitioning().partition_count();
     let mut streams = Vec::with_capacity(num_partitions);
     for i in 0..num_partitions {
-        streams.push(plan.execute(i).await?);
+        streams.push(plan.execute(i, context.clone()).await?);
     }
     Ok(streams)
 }
@@ -372,6 +461,9 @@ pub async fn execute_stream_partitioned(
 pub enum Partitioning {
     /// Allocate batches using a round-robin algorithm and the specified number of partitions
     RoundRobinBatch(usize),
+    /// Allocate rows to the partitions with the fewest rows so far, splitting the batches
+    /// so that the partitions get the same number of rows whatever the size of the batches
+    RoundRobinRows(usize),
     /// Allocate rows based on a hash of one of more expressions and the specified number of
     /// partitions
     Hash(Vec<Arc<dyn PhysicalExpr>>, usize),
@@ -387,6 +479,7 @@ impl Partitioning {
         use Partitioning::*;
         match self {
             RoundRobinBatch(n) => *n,
+            RoundRobinRows(n) => *n,
             Hash(_, n) => *n,
             HashDiff(_, n) => *n,
             UnknownPartitioning(n) => *n,
@@ -618,6 +711,21 @@ pub trait Accumulator: Send + Sync + Debug {
 
     /// returns its value based on its current state.
     fn evaluate(&self) -> Result<ScalarValue>;
+
+    /// removes a vector of arrays from the accumulator's state, the inverse of
+    /// [`Accumulator::update_batch`]. Window frames that don't start at the
+    /// beginning of their partition use it to slide along the partition instead
+    /// of accumulating every frame from scratch.
+    fn retract_batch(&mut self, _values: &[ArrayRef]) -> Result<()> {
+        Err(DataFusionError::NotImplemented(
+            "Retracting values from this accumulator is not supported".to_owned(),
+        ))
+    }
+
+    /// whether [`Accumulator::retract_batch`] is implemented.
+    fn supports_retract_batch(&self) -> bool {
+        false
+    }
 }
 
 pub mod aggregates;
@@ -633,36 +741,50 @@ pub mod crypto_expressions;
 pub mod datetime_expressions;
 pub mod display;
 pub mod distinct_expressions;
+pub mod dynamic_filter;
 pub mod empty;
 pub mod explain;
 pub mod expressions;
 pub mod file_format;
 pub mod filter;
+pub mod fingerprint;
 pub mod functions;
 pub mod hash_aggregate;
 pub mod hash_join;
 pub mod hash_utils;
 pub(crate) mod hyperloglog;
 pub mod join_utils;
+pub mod lambda_input;
 pub mod limit;
 pub mod math_expressions;
 pub mod memory;
 pub mod metrics;
 pub mod planner;
 pub mod projection;
+pub mod recursive_query;
 #[cfg(feature = "regex_expressions")]
 pub mod regex_expressions;
 pub mod repartition;
+pub mod rewrite;
+mod row_aggregate;
+pub mod serde;
+pub mod shuffle;
 pub mod sort;
 pub mod sort_preserving_merge;
+pub mod stage;
 pub mod stream;
+pub mod streaming;
 pub mod string_expressions;
+pub mod summary;
+pub mod trace;
 pub mod type_coercion;
 pub mod udaf;
 pub mod udf;
+pub mod udwf;
 #[cfg(feature = "unicode_expressions")]
 pub mod unicode_expressions;
 pub mod union;
+pub mod unnest;
 pub mod values;
 pub mod window_functions;
 pub mod windows;
@@ -22,43 +22,51 @@ use super::{
     aggregates, empty::EmptyExec, expressions::binary, functions,
     hash_join::PartitionMode, union::UnionExec, values::ValuesExec, windows,
 };
+use crate::datasource::ScanArgs;
 use crate::execution::context::ExecutionContextState;
 use crate::logical_plan::plan::{
     Aggregate, EmptyRelation, Filter, Join, Projection, Sort, TableScan, Window,
 };
 use crate::logical_plan::{
-    unalias, unnormalize_cols, CrossJoin, DFSchema, Expr, LogicalPlan, Operator,
-    Partitioning as LogicalPartitioning, PlanType, Repartition, ToStringifiedPlan, Union,
-    UserDefinedLogicalNode,
+    unalias, unnormalize_cols, Column as LogicalColumn, CrossJoin, DFSchema,
+    ExplainFormat, Expr, ExprRewriter, JoinType, LogicalPlan, LogicalPlanBuilder,
+    Operator, Partitioning as LogicalPartitioning, PlanType, RecursiveQuery, Repartition,
+    StringifiedPlan, ToStringifiedPlan, Union, Unnest, UserDefinedLogicalNode,
 };
 use crate::logical_plan::{Limit, Values};
+use crate::optimizer::optimizer::apply_rule;
+use crate::optimizer::utils;
 use crate::physical_optimizer::optimizer::PhysicalOptimizerRule;
+use crate::physical_optimizer::utils::ordering_satisfies;
 use crate::physical_plan::cross_join::CrossJoinExec;
 use crate::physical_plan::explain::ExplainExec;
 use crate::physical_plan::expressions;
 use crate::physical_plan::expressions::{
-    CaseExpr, Column, GetIndexedFieldExpr, Literal, PhysicalSortExpr,
+    can_cast_types, Column, GetIndexedFieldExpr, Literal, PhysicalSortExpr,
 };
 use crate::physical_plan::filter::FilterExec;
 use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
 use crate::physical_plan::hash_join::HashJoinExec;
 use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
 use crate::physical_plan::projection::ProjectionExec;
+use crate::physical_plan::recursive_query::RecursiveQueryExec;
 use crate::physical_plan::repartition::RepartitionExec;
 use crate::physical_plan::sort::SortExec;
+use crate::physical_plan::unnest::UnnestExec;
 use crate::physical_plan::windows::WindowAggExec;
 use crate::physical_plan::{join_utils, Partitioning};
+use crate::physical_plan::{udaf, udf};
 use crate::physical_plan::{AggregateExpr, ExecutionPlan, PhysicalExpr, WindowExpr};
 use crate::scalar::ScalarValue;
 use crate::sql::utils::{generate_sort_key, window_expr_common_partition_keys};
 use crate::variable::VarType;
 use crate::{
     error::{DataFusionError, Result},
-    physical_plan::displayable,
+    physical_plan::{displayable, DisplayFormatType},
 };
 use arrow::compute::SortOptions;
 use arrow::datatypes::{Schema, SchemaRef};
-use arrow::{compute::can_cast_types, datatypes::DataType};
+use arrow::datatypes::DataType;
 use async_trait::async_trait;
 use expressions::col;
 use futures::future::BoxFuture;
@@ -150,6 +158,16 @@ fn create_physical_name(e: &Expr, is_first_expr: bool) -> Result<String> {
             let expr = create_physical_name(expr, false)?;
             Ok(format!("{}[{}]", expr, key))
         }
+        Expr::Struct { names, args } => {
+            let fields = names
+                .iter()
+                .zip(args.iter())
+                .map(|(name, arg)| {
+                    Ok(format!("{}: {}", name, create_physical_name(arg, false)?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(format!("struct({})", fields.join(", ")))
+        }
         Expr::ScalarFunction { fun, args, .. } => {
             create_function_physical_name(&fun.to_string(), false, args)
         }
@@ -340,8 +358,22 @@ impl DefaultPhysicalPlanner {
                     // doesn't know (nor should care) how the relation was
                     // referred to in the query
                     let filters = unnormalize_cols(filters.iter().cloned());
-                    let unaliased: Vec<Expr> = filters.into_iter().map(unalias).collect();
-                    source.scan(projection, batch_size, &unaliased, *limit).await
+                    let filters = filters
+                        .into_iter()
+                        .map(unalias)
+                        .map(|filter| {
+                            let support = source.supports_filter_pushdown(&filter)?;
+                            Ok((filter, support))
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    source
+                        .scan_with_args(ScanArgs {
+                            projection: projection.clone(),
+                            batch_size,
+                            filters,
+                            limit: *limit,
+                        })
+                        .await
                 }
                 LogicalPlan::Values(Values {
                     values,
@@ -386,29 +418,6 @@ impl DefaultPhysicalPlanner {
                         && ctx_state.config.target_partitions > 1
                         && ctx_state.config.repartition_windows;
 
-                    let input_exec = if can_repartition {
-                        let partition_keys = partition_keys
-                            .iter()
-                            .map(|e| {
-                                self.create_physical_expr(
-                                    e,
-                                    input.schema(),
-                                    &input_exec.schema(),
-                                    ctx_state,
-                                )
-                            })
-                            .collect::<Result<Vec<Arc<dyn PhysicalExpr>>>>()?;
-                        Arc::new(RepartitionExec::try_new(
-                            input_exec,
-                            Partitioning::Hash(
-                                partition_keys,
-                                ctx_state.config.target_partitions,
-                            ),
-                        )?)
-                    } else {
-                        input_exec
-                    };
-
                     // add a sort phase
                     let get_sort_keys = |expr: &Expr| match expr {
                         Expr::WindowFunction {
@@ -429,36 +438,59 @@ impl DefaultPhysicalPlanner {
                     }
 
                     let logical_input_schema = input.schema();
+                    let physical_input_schema = input_exec.schema();
+                    let partition_keys = partition_keys
+                        .iter()
+                        .map(|e| {
+                            self.create_physical_expr(
+                                e,
+                                logical_input_schema,
+                                &physical_input_schema,
+                                ctx_state,
+                            )
+                        })
+                        .collect::<Result<Vec<Arc<dyn PhysicalExpr>>>>()?;
+                    let sort_keys = sort_keys
+                        .iter()
+                        .map(|e| match e {
+                            Expr::Sort {
+                                expr,
+                                asc,
+                                nulls_first,
+                            } => self.create_physical_sort_expr(
+                                expr,
+                                logical_input_schema,
+                                &physical_input_schema,
+                                SortOptions {
+                                    descending: !*asc,
+                                    nulls_first: *nulls_first,
+                                },
+                                ctx_state,
+                            ),
+                            _ => unreachable!(),
+                        })
+                        .collect::<Result<Vec<_>>>()?;
 
-                    let input_exec = if sort_keys.is_empty() {
+                    // the logical planner nests the windows so that a window
+                    // sorted by a prefix of the sort keys of the window below
+                    // it shares its sort
+                    let input_exec = if sort_keys.is_empty()
+                        || is_window_input_ready(&input_exec, &sort_keys, &partition_keys)
+                    {
                         input_exec
+                    } else if can_repartition {
+                        let input_exec = Arc::new(RepartitionExec::try_new(
+                            input_exec,
+                            Partitioning::Hash(
+                                partition_keys,
+                                ctx_state.config.target_partitions,
+                            ),
+                        )?);
+                        Arc::new(SortExec::new_with_partitioning(
+                            sort_keys, input_exec, true,
+                        ))
                     } else {
-                        let physical_input_schema = input_exec.schema();
-                        let sort_keys = sort_keys
-                            .iter()
-                            .map(|e| match e {
-                                Expr::Sort {
-                                    expr,
-                                    asc,
-                                    nulls_first,
-                                } => self.create_physical_sort_expr(
-                                    expr,
-                                    logical_input_schema,
-                                    &physical_input_schema,
-                                    SortOptions {
-                                        descending: !*asc,
-                                        nulls_first: *nulls_first,
-                                    },
-                                    ctx_state,
-                                ),
-                                _ => unreachable!(),
-                            })
-                            .collect::<Result<Vec<_>>>()?;
-                        Arc::new(if can_repartition {
-                            SortExec::new_with_partitioning(sort_keys, input_exec, true)
-                        } else {
-                            SortExec::try_new(sort_keys, input_exec)?
-                        })
+                        Arc::new(SortExec::try_new(sort_keys, input_exec)?)
                     };
 
                     let physical_input_schema = input_exec.schema();
@@ -517,6 +549,19 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect::<Result<Vec<_>>>()?;
 
+                    // all the rows of a group are already in the same partition,
+                    // so each partition is aggregated on its own, without a
+                    // partial aggregate nor an exchange
+                    if is_partitioned_on_groups(&input_exec, &groups) {
+                        return Ok(Arc::new(HashAggregateExec::try_new(
+                            AggregateMode::SinglePartitioned,
+                            groups,
+                            aggregates,
+                            input_exec,
+                            physical_input_schema,
+                        )?));
+                    }
+
                     let initial_aggr = Arc::new(HashAggregateExec::try_new(
                         AggregateMode::Partial,
                         groups.clone(),
@@ -574,6 +619,12 @@ impl DefaultPhysicalPlanner {
                     )?) )
                 }
                 LogicalPlan::Projection(Projection { input, expr, .. }) => {
+                    // the async UDFs nested in the expressions are called by a
+                    // projection below this one
+                    if let Some(plan) = lift_async_calls(input, expr)? {
+                        return self.create_initial_plan(&plan, ctx_state).await;
+                    }
+
                     let input_exec = self.create_initial_plan(input, ctx_state).await?;
                     let input_schema = input.as_ref().schema();
 
@@ -678,6 +729,27 @@ impl DefaultPhysicalPlanner {
                         physical_partitioning,
                     )?) )
                 }
+                LogicalPlan::Unnest(Unnest { input, column, .. }) => {
+                    let physical_input = self.create_initial_plan(input, ctx_state).await?;
+                    let index = input.schema().index_of_column(column)?;
+                    Ok(Arc::new(UnnestExec::try_new(physical_input, index)?))
+                }
+                LogicalPlan::RecursiveQuery(RecursiveQuery {
+                    name,
+                    static_term,
+                    recursive_term,
+                    is_distinct,
+                }) => {
+                    let static_term = self.create_initial_plan(static_term, ctx_state).await?;
+                    let recursive_term =
+                        self.create_initial_plan(recursive_term, ctx_state).await?;
+                    Ok(Arc::new(RecursiveQueryExec::new(
+                        name,
+                        static_term,
+                        recursive_term,
+                        *is_distinct,
+                    )))
+                }
                 LogicalPlan::Sort(Sort { expr, input, .. }) => {
                     let physical_input = self.create_initial_plan(input, ctx_state).await?;
                     let input_schema = physical_input.as_ref().schema();
@@ -712,6 +784,7 @@ impl DefaultPhysicalPlanner {
                     on: keys,
                     join_type,
                     null_equals_null,
+                    null_aware,
                     ..
                 }) => {
                     let left_df_schema = left.schema();
@@ -728,8 +801,23 @@ impl DefaultPhysicalPlanner {
                         })
                         .collect::<Result<join_utils::JoinOn>>()?;
 
+                    // a small enough left input is collected rather than
+                    // repartitioned, see `ExecutionConfig::collect_left_join_rows`
+                    let collect_left = *join_type == JoinType::Inner
+                        && matches!(
+                            (
+                                ctx_state.config.collect_left_join_rows,
+                                physical_left.statistics().num_rows,
+                            ),
+                            (Some(threshold), Some(rows)) if rows <= threshold
+                        );
+
+                    // a null-aware join reads its whole probe side in a
+                    // single partition
                     if ctx_state.config.target_partitions > 1
                         && ctx_state.config.repartition_joins
+                        && !collect_left
+                        && !*null_aware
                     {
                         let (left_expr, right_expr) = join_on
                             .iter()
@@ -763,14 +851,17 @@ impl DefaultPhysicalPlanner {
                             null_equals_null,
                         )?))
                     } else {
-                        Ok(Arc::new(HashJoinExec::try_new(
-                            physical_left,
-                            physical_right,
-                            join_on,
-                            join_type,
-                            PartitionMode::CollectLeft,
-                            null_equals_null,
-                        )?))
+                        Ok(Arc::new(
+                            HashJoinExec::try_new(
+                                physical_left,
+                                physical_right,
+                                join_on,
+                                join_type,
+                                PartitionMode::CollectLeft,
+                                null_equals_null,
+                            )?
+                            .with_null_aware(*null_aware)?,
+                        ))
                     }
                 }
                 LogicalPlan::CrossJoin(CrossJoin { left, right, .. }) => {
@@ -809,7 +900,7 @@ impl DefaultPhysicalPlanner {
                         "Unsupported logical plan: CreateExternalTable".to_string(),
                     ))
                 }
-                | LogicalPlan::CreateMemoryTable(_) | LogicalPlan::DropTable (_) => {
+                | LogicalPlan::CreateMemoryTable(_) | LogicalPlan::InsertInto(_) | LogicalPlan::DropTable (_) | LogicalPlan::SetVariable(_) => {
                     // Create a dummy exec.
                     Ok(Arc::new(EmptyExec::new(
                         false,
@@ -926,7 +1017,13 @@ impl DefaultPhysicalPlanner {
                     input_schema,
                     ctx_state,
                 )?;
-                binary(lhs, *op, rhs, input_schema)
+                expressions::binary_with_overflow(
+                    lhs,
+                    *op,
+                    rhs,
+                    input_schema,
+                    ctx_state.config.arithmetic_overflow,
+                )
             }
             Expr::Case {
                 expr,
@@ -983,16 +1080,13 @@ impl DefaultPhysicalPlanner {
                 } else {
                     None
                 };
-                Ok(Arc::new(CaseExpr::try_new(
-                    expr,
-                    &when_then_expr,
-                    else_expr,
-                )?))
+                expressions::case(expr, &when_then_expr, else_expr, input_schema)
             }
-            Expr::Cast { expr, data_type } => expressions::cast(
+            Expr::Cast { expr, data_type } => expressions::cast_with_overflow(
                 self.create_physical_expr(expr, input_dfschema, input_schema, ctx_state)?,
                 input_schema,
                 data_type.clone(),
+                ctx_state.config.arithmetic_overflow,
             ),
             Expr::TryCast { expr, data_type } => expressions::try_cast(
                 self.create_physical_expr(expr, input_dfschema, input_schema, ctx_state)?,
@@ -1027,6 +1121,20 @@ impl DefaultPhysicalPlanner {
                     key.clone(),
                 )))
             }
+            Expr::Struct { names, args } => {
+                let physical_args = args
+                    .iter()
+                    .map(|e| {
+                        self.create_physical_expr(
+                            e,
+                            input_dfschema,
+                            input_schema,
+                            ctx_state,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                expressions::struct_expr(names.clone(), physical_args)
+            }
 
             Expr::ScalarFunction { fun, args } => {
                 let physical_args = args
@@ -1047,22 +1155,24 @@ impl DefaultPhysicalPlanner {
                     ctx_state,
                 )
             }
-            // Expr::ScalarUDF { fun, args } => {
-            //     let mut physical_args = vec![];
-            //     for e in args {
-            //         physical_args.push(self.create_physical_expr(
-            //             e,
-            //             input_schema,
-            //             ctx_state,
-            //         )?);
-            //     }
-
-            //     udf::create_physical_expr(
-            //         fun.clone().as_ref(),
-            //         &physical_args,
-            //         input_schema,
-            //     )
-            // }
+            Expr::ScalarUDF { fun, args } => {
+                let mut physical_args = vec![];
+                for e in args {
+                    physical_args.push(self.create_physical_expr(
+                        e,
+                        input_dfschema,
+                        input_schema,
+                        ctx_state,
+                    )?);
+                }
+
+                udf::create_physical_expr(
+                    fun.clone().as_ref(),
+                    &physical_args,
+                    input_schema,
+                    ctx_state.config.udf_error_mode,
+                )
+            }
             Expr::Between {
                 expr,
                 negated,
@@ -1129,6 +1239,14 @@ impl DefaultPhysicalPlanner {
                                     input_schema,
                                     ctx_state,
                                 ),
+                            // the literals are cast when planned, so that a
+                            // long list can be hashed
+                            Expr::Literal(value) => {
+                                Ok(expressions::lit(expressions::coerce_list_literal(
+                                    value,
+                                    &value_expr_data_type,
+                                )?))
+                            }
                             _ => {
                                 let list_expr = self.create_physical_expr(
                                     expr,
@@ -1304,29 +1422,35 @@ impl DefaultPhysicalPlanner {
                         )
                     })
                     .collect::<Result<Vec<_>>>()?;
-                aggregates::create_aggregate_expr(
+                aggregates::create_aggregate_expr_with_overflow(
                     fun,
                     *distinct,
                     &args,
                     physical_input_schema,
                     name,
+                    ctx_state.config.arithmetic_overflow,
+                )
+            }
+            Expr::AggregateUDF { fun, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|e| {
+                        self.create_physical_expr(
+                            e,
+                            logical_input_schema,
+                            physical_input_schema,
+                            ctx_state,
+                        )
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+
+                udaf::create_aggregate_expr(
+                    fun,
+                    &args,
+                    physical_input_schema,
+                    name.into(),
                 )
             }
-            // Expr::AggregateUDF { fun, args, .. } => {
-            //     let args = args
-            //         .iter()
-            //         .map(|e| {
-            //             self.create_physical_expr(
-            //                 e,
-            //                 logical_input_schema,
-            //                 physical_input_schema,
-            //                 ctx_state,
-            //             )
-            //         })
-            //         .collect::<Result<Vec<_>>>()?;
-
-            //     udaf::create_aggregate_expr(fun, &args, physical_input_schema, name)
-            // }
             other => Err(DataFusionError::Internal(format!(
                 "Invalid aggregate expression '{:?}'",
                 other
@@ -1390,22 +1514,41 @@ impl DefaultPhysicalPlanner {
         if let LogicalPlan::Explain(e) = logical_plan {
             use PlanType::*;
             let mut stringified_plans = e.stringified_plans.clone();
+            // the physical plans show the statistics of their operators
+            let format = if e.verbose || ctx_state.config.explain_statistics {
+                DisplayFormatType::Verbose
+            } else {
+                DisplayFormatType::Default
+            };
+            let stringify = |plan: &dyn ExecutionPlan, plan_type| match e.format {
+                ExplainFormat::Indent => displayable(plan)
+                    .with_format(format)
+                    .to_stringified(plan_type),
+                ExplainFormat::Graphviz => {
+                    let graph = displayable(plan).with_format(format).graphviz();
+                    StringifiedPlan::new(plan_type, graph.to_string())
+                }
+            };
 
-            stringified_plans.push(e.plan.to_stringified(FinalLogicalPlan));
+            stringified_plans.push(e.format.stringify(&e.plan, FinalLogicalPlan));
+            if e.verbose && e.format == ExplainFormat::Indent {
+                stringified_plans.push(StringifiedPlan::new(
+                    FinalLogicalPlanWithSchema,
+                    e.plan.display_indent_qualified_schema().to_string(),
+                ));
+            }
 
             let input = self.create_initial_plan(e.plan.as_ref(), ctx_state).await?;
 
-            stringified_plans
-                .push(displayable(input.as_ref()).to_stringified(InitialPhysicalPlan));
+            stringified_plans.push(stringify(input.as_ref(), InitialPhysicalPlan));
 
             let input = self.optimize_internal(input, ctx_state, |plan, optimizer| {
                 let optimizer_name = optimizer.name().to_string();
                 let plan_type = OptimizedPhysicalPlan { optimizer_name };
-                stringified_plans.push(displayable(plan).to_stringified(plan_type));
+                stringified_plans.push(stringify(plan, plan_type));
             })?;
 
-            stringified_plans
-                .push(displayable(input.as_ref()).to_stringified(FinalPhysicalPlan));
+            stringified_plans.push(stringify(input.as_ref(), FinalPhysicalPlan));
 
             Ok(Some(Arc::new(ExplainExec::new(
                 SchemaRef::new(e.schema.as_ref().to_owned().into()),
@@ -1428,12 +1571,16 @@ impl DefaultPhysicalPlanner {
     where
         F: FnMut(&dyn ExecutionPlan, &dyn PhysicalOptimizerRule),
     {
-        let optimizers = &ctx_state.config.physical_optimizers;
+        let config = &ctx_state.config;
         debug!("Physical plan:\n{:?}", plan);
 
         let mut new_plan = plan;
-        for optimizer in optimizers {
-            new_plan = optimizer.optimize(new_plan, &ctx_state.config)?;
+        for optimizer in &config.physical_optimizers {
+            if !config.is_rule_enabled(optimizer.name()) {
+                continue;
+            }
+            let plan = new_plan;
+            new_plan = apply_rule(optimizer.name(), || optimizer.optimize(plan, config))?;
             observer(new_plan.as_ref(), optimizer.as_ref())
         }
         debug!(
@@ -1454,15 +1601,190 @@ fn tuple_err<T, R>(value: (Result<T>, Result<R>)) -> Result<(T, R)> {
     }
 }
 
+/// Returns true if all the rows of `input` sharing the values of `group_expr`
+/// are in the same partition, so that an aggregate grouped by `group_expr` can
+/// aggregate each partition on its own
+fn is_partitioned_on_groups(
+    input: &Arc<dyn ExecutionPlan>,
+    group_expr: &[(Arc<dyn PhysicalExpr>, String)],
+) -> bool {
+    let is_key = |expr: &Arc<dyn PhysicalExpr>| {
+        let column = expr.as_any().downcast_ref::<Column>();
+        column.is_some()
+            && group_expr
+                .iter()
+                .any(|(key, _)| key.as_any().downcast_ref::<Column>() == column)
+    };
+    match input.output_partitioning() {
+        // the rows sharing the values of the group keys share the values of
+        // any subset of them, and are hashed to the same partition
+        Partitioning::Hash(exprs, n) => {
+            n > 1 && !exprs.is_empty() && exprs.iter().all(is_key)
+        }
+        _ => false,
+    }
+}
+
+/// Returns true if each partition of `input` is sorted by `sort_keys` and
+/// holds all the rows of the window partitions it has rows of, so that a
+/// window partitioned by `partition_keys` can read it as is
+fn is_window_input_ready(
+    input: &Arc<dyn ExecutionPlan>,
+    sort_keys: &[PhysicalSortExpr],
+    partition_keys: &[Arc<dyn PhysicalExpr>],
+) -> bool {
+    let sorted = input
+        .output_ordering()
+        .map(|provided| ordering_satisfies(provided, sort_keys))
+        .unwrap_or(false);
+    let is_key = |expr: &Arc<dyn PhysicalExpr>| {
+        let column = expr.as_any().downcast_ref::<Column>();
+        column.is_some()
+            && partition_keys
+                .iter()
+                .any(|key| key.as_any().downcast_ref::<Column>() == column)
+    };
+    sorted
+        && match input.output_partitioning() {
+            partitioning if partitioning.partition_count() == 1 => true,
+            // the rows sharing the values of the partition keys share the
+            // values of any subset of them, and are hashed to the same
+            // partition
+            Partitioning::Hash(exprs, _) => {
+                !partition_keys.is_empty() && exprs.iter().all(is_key)
+            }
+            _ => false,
+        }
+}
+
+/// Whether `expr` calls an async UDF
+fn calls_async_udf(expr: &Expr) -> Result<bool> {
+    if matches!(expr, Expr::ScalarUDF { fun, .. } if fun.is_async()) {
+        return Ok(true);
+    }
+    for sub_expr in utils::expr_sub_expressions(expr)? {
+        if calls_async_udf(&sub_expr)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Collects the distinct calls of async UDFs of `expr` whose arguments don't
+/// call async UDFs
+fn innermost_async_calls(expr: &Expr, calls: &mut Vec<Expr>) -> Result<()> {
+    let sub_exprs = utils::expr_sub_expressions(expr)?;
+    if matches!(expr, Expr::ScalarUDF { fun, .. } if fun.is_async()) {
+        let mut nested = false;
+        for sub_expr in &sub_exprs {
+            nested |= calls_async_udf(sub_expr)?;
+        }
+        if !nested {
+            if !calls.contains(expr) {
+                calls.push(expr.clone());
+            }
+            return Ok(());
+        }
+    }
+    sub_exprs
+        .iter()
+        .try_for_each(|sub_expr| innermost_async_calls(sub_expr, calls))
+}
+
+/// Replaces the calls of async UDFs lifted to the projection below by its columns
+struct AsyncCallRewriter<'a> {
+    calls: &'a [Expr],
+    names: &'a [String],
+}
+
+impl ExprRewriter for AsyncCallRewriter<'_> {
+    fn mutate(&mut self, expr: Expr) -> Result<Expr> {
+        Ok(match self.calls.iter().position(|call| call == &expr) {
+            Some(i) => Expr::Column(LogicalColumn::from_name(&self.names[i])),
+            None => expr,
+        })
+    }
+}
+
+/// [`ProjectionExec`] only awaits the calls of async UDFs that are whole
+/// expressions of the projection. If async calls are nested in the expressions
+/// `expr` of a projection of `input`, returns a projection of `input` computing
+/// the innermost ones along with the columns of `input`, followed by the
+/// projection of `expr` using them instead.
+fn lift_async_calls(
+    input: &Arc<LogicalPlan>,
+    expr: &[Expr],
+) -> Result<Option<LogicalPlan>> {
+    let mut calls = vec![];
+    for e in expr {
+        let mut top = e;
+        while let Expr::Alias(inner, _) = top {
+            top = inner;
+        }
+        match top {
+            Expr::ScalarUDF { fun, args } if fun.is_async() => {
+                for arg in args {
+                    innermost_async_calls(arg, &mut calls)?;
+                }
+            }
+            _ => innermost_async_calls(top, &mut calls)?,
+        }
+    }
+    if calls.is_empty() {
+        return Ok(None);
+    }
+
+    let input_schema = input.schema();
+    let names = calls
+        .iter()
+        .map(|call| call.name(input_schema))
+        .collect::<Result<Vec<_>>>()?;
+    let lifted = input_schema
+        .fields()
+        .iter()
+        .map(|field| Expr::Column(field.qualified_column()))
+        .chain(
+            calls
+                .iter()
+                .zip(&names)
+                .map(|(call, name)| call.clone().alias(name)),
+        )
+        .collect::<Vec<_>>();
+
+    let mut rewriter = AsyncCallRewriter {
+        calls: &calls,
+        names: &names,
+    };
+    let expr = expr
+        .iter()
+        .map(|e| {
+            let rewritten = e.clone().rewrite(&mut rewriter)?;
+            // keep the physical names of the expressions
+            Ok(match rewritten {
+                Expr::Alias(..) => rewritten,
+                _ if &rewritten == e => rewritten,
+                _ => rewritten.alias(&physical_name(e)?),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    LogicalPlanBuilder::from(input.as_ref().clone())
+        .project(lifted)?
+        .project(expr)?
+        .build()
+        .map(Some)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
     use crate::execution::options::CsvReadOptions;
+    use crate::execution::task_context::TaskContext;
     use crate::logical_plan::plan::Extension;
     use crate::logical_plan::{DFField, DFSchema, DFSchemaRef};
     use crate::physical_plan::{
-        expressions, DisplayFormatType, Partitioning, Statistics,
+        expressions, fmt_with_statistics, DisplayFormatType, Partitioning, Statistics,
     };
     use crate::scalar::ScalarValue;
     use crate::{
@@ -1470,12 +1792,12 @@ mod tests {
         physical_plan::SendableRecordBatchStream,
     };
     use arrow::datatypes::{DataType, Field, SchemaRef};
+    use arrow::record_batch::RecordBatch;
     use async_trait::async_trait;
     use fmt::Debug;
+    use serde::{Deserialize, Serialize};
     use std::convert::TryFrom;
     use std::{any::Any, fmt};
-    use serde::{Deserialize, Serialize};
-    use arrow::record_batch::RecordBatch;
 
     fn make_ctx_state() -> ExecutionContextState {
         ExecutionContextState::new()
@@ -1658,7 +1980,7 @@ mod tests {
                 dict_id: 0, \
                 dict_is_ordered: false, \
                 metadata: None } }\
-        ] }, \
+        ], metadata: {} }, \
         ExecutionPlan schema: Schema { fields: [\
             Field { \
                 name: \"b\", \
@@ -1703,8 +2025,8 @@ mod tests {
         .project(vec![col("c1").in_list(list, false)])?
         .build()?;
         let execution_plan = plan(&logical_plan).await?;
-        // verify that the plan correctly adds cast from Int64(1) to Utf8
-        let expected = "InListExpr { expr: Column { name: \"c1\", index: 0 }, list: [Literal { value: Utf8(\"a\") }, CastExpr { expr: Literal { value: Int64(1) }, cast_type: Utf8, cast_options: CastOptions { safe: false } }], negated: false }";
+        // verify that the plan correctly casts Int64(1) to Utf8
+        let expected = "InListExpr { expr: Column { name: \"c1\", index: 0 }, list: [Literal { value: Utf8(\"a\") }, Literal { value: Utf8(\"1\") }], negated: false, set: None }";
         assert!(format!("{:?}", execution_plan).contains(expected));
 
         // expression: "a in (true, 'a')"
@@ -1726,7 +2048,7 @@ mod tests {
         .build()?;
         let execution_plan = plan(&logical_plan).await;
 
-        let expected_error = "Unsupported CAST from Utf8 to Boolean";
+        let expected_error = "Cannot cast a in IN list to Boolean";
         match execution_plan {
             Ok(_) => panic!("Expected planning failure"),
             Err(e) => assert!(
@@ -1801,6 +2123,56 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn hash_agg_group_by_pre_partitioned() -> Result<()> {
+        let testdata = crate::test_util::arrow_test_data();
+        let path = format!("{}/csv/aggregate_test_100.csv", testdata);
+
+        // the input is hash partitioned on c1, renamed to x by the projection
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(
+            Arc::new(LocalFileSystem {}),
+            &path,
+            options,
+            None,
+            1,
+        )
+        .await?
+        .repartition(LogicalPartitioning::Hash(vec![col("c1")], 4))?
+        .project(vec![col("c1").alias("x"), col("c2")])?
+        .aggregate(vec![col("x"), col("c2")], vec![sum(col("c2"))])?
+        .build()?;
+
+        let execution_plan = plan(&logical_plan).await?;
+        let formatted = format!("{:?}", execution_plan);
+
+        // a single aggregate runs on each partition, without another exchange
+        assert!(formatted.contains("SinglePartitioned"));
+        assert!(!formatted.contains("Partial"));
+        assert_eq!(formatted.matches("RepartitionExec").count(), 1);
+
+        // the input is not partitioned on a subset of the group keys
+        let options = CsvReadOptions::new().schema_infer_max_records(100);
+        let logical_plan = LogicalPlanBuilder::scan_csv(
+            Arc::new(LocalFileSystem {}),
+            &path,
+            options,
+            None,
+            1,
+        )
+        .await?
+        .repartition(LogicalPartitioning::Hash(vec![col("c1")], 4))?
+        .aggregate(vec![col("c2")], vec![sum(col("c3"))])?
+        .build()?;
+
+        let execution_plan = plan(&logical_plan).await?;
+        let formatted = format!("{:?}", execution_plan);
+        assert!(!formatted.contains("SinglePartitioned"));
+        assert!(formatted.contains("FinalPartitioned"));
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_explain() {
         let schema = Schema::new(vec![Field::new("id", DataType::Int32, false)]);
@@ -1815,20 +2187,18 @@ mod tests {
 
         let plan = plan(&logical_plan).await.unwrap();
         if let Some(plan) = plan.as_any().downcast_ref::<ExplainExec>() {
-            let stringified_plans = plan.stringified_plans();
-            assert!(stringified_plans.len() >= 4);
-            assert!(stringified_plans
+            let plans = plan.plans();
+            assert!(plans.len() >= 4);
+            let plan_types = plans
                 .iter()
-                .any(|p| matches!(p.plan_type, PlanType::FinalLogicalPlan)));
-            assert!(stringified_plans
+                .map(|(plan_type, _)| plan_type.as_str())
+                .collect::<Vec<_>>();
+            assert!(plan_types.contains(&"logical_plan"));
+            assert!(plan_types.contains(&"initial_physical_plan"));
+            assert!(plan_types
                 .iter()
-                .any(|p| matches!(p.plan_type, PlanType::InitialPhysicalPlan)));
-            assert!(stringified_plans
-                .iter()
-                .any(|p| matches!(p.plan_type, PlanType::OptimizedPhysicalPlan { .. })));
-            assert!(stringified_plans
-                .iter()
-                .any(|p| matches!(p.plan_type, PlanType::FinalPhysicalPlan)));
+                .any(|plan_type| plan_type.starts_with("physical_plan after ")));
+            assert!(plan_types.contains(&"physical_plan"));
         } else {
             panic!(
                 "Plan was not an explain plan: {}",
@@ -1896,7 +2266,7 @@ mod tests {
 
     #[async_trait]
     impl LambdaExecPlan for NoOpExecutionPlan {
-        fn feed_batches(&mut self, _: Vec<Vec<RecordBatch>>) {
+        fn feed_batches(&mut self, _: Vec<Vec<RecordBatch>>) -> Result<()> {
             unimplemented!();
         }
     }
@@ -1928,7 +2298,11 @@ mod tests {
             unimplemented!("NoOpExecutionPlan::with_new_children");
         }
 
-        async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+        async fn execute(
+            &self,
+            _partition: usize,
+            _context: Arc<TaskContext>,
+        ) -> Result<SendableRecordBatchStream> {
             unimplemented!("NoOpExecutionPlan::execute");
         }
 
@@ -1941,6 +2315,7 @@ mod tests {
                 DisplayFormatType::Default => {
                     write!(f, "NoOpExecutionPlan")
                 }
+                DisplayFormatType::Verbose => fmt_with_statistics(self, f),
             }
         }
 
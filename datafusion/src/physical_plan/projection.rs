@@ -27,21 +27,26 @@ use std::sync::Arc;
 use std::task::{Context, Poll};
 
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::memory::MemoryExec;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::memory::{feed_input, MemoryExec};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    ColumnStatistics, DisplayFormatType, ExecutionPlan, Partitioning, PhysicalExpr,
-    LambdaExecPlan,
+    fmt_with_statistics, ColumnStatistics, DisplayFormatType, ExecutionPlan,
+    LambdaExecPlan, Partitioning, PhysicalExpr,
 };
+use arrow::array::ArrayRef;
 use arrow::datatypes::{Field, Schema, SchemaRef};
 use arrow::error::Result as ArrowResult;
 use arrow::record_batch::RecordBatch;
 
 use super::expressions::Column;
 use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::udf::ScalarUDFExpr;
 use super::{RecordBatchStream, SendableRecordBatchStream, Statistics};
 use async_trait::async_trait;
+use futures::future::{self, BoxFuture, FutureExt};
 use futures::stream::Stream;
-use futures::stream::StreamExt;
+use futures::stream::{BoxStream, StreamExt};
 
 use serde::{Deserialize, Serialize};
 
@@ -121,13 +126,9 @@ impl ProjectionExec {
 
 #[async_trait]
 impl LambdaExecPlan for ProjectionExec {
-    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) {
-        self.input = Arc::new(MemoryExec {
-            partitions,
-            projected_schema: self.schema(),
-            schema: self.schema(),
-            projection: None,
-        });
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -154,7 +155,25 @@ impl ExecutionPlan for ProjectionExec {
 
     /// Get the output partitioning of this plan
     fn output_partitioning(&self) -> Partitioning {
-        self.input.output_partitioning()
+        match self.input.output_partitioning() {
+            // the hash columns are renamed to the output of the projection, and
+            // the partitioning is unknown once one of them is projected away
+            Partitioning::Hash(exprs, n) => exprs
+                .iter()
+                .map(|expr| {
+                    let column = expr.as_any().downcast_ref::<Column>()?;
+                    self.expr.iter().enumerate().find_map(|(i, (e, name))| {
+                        (e.as_any().downcast_ref::<Column>() == Some(column)).then(|| {
+                            Arc::new(Column::new(name, i)) as Arc<dyn PhysicalExpr>
+                        })
+                    })
+                })
+                .collect::<Option<Vec<_>>>()
+                .map_or(Partitioning::UnknownPartitioning(n), |exprs| {
+                    Partitioning::Hash(exprs, n)
+                }),
+            partitioning => partitioning,
+        }
     }
 
     fn with_new_children(
@@ -172,13 +191,59 @@ impl ExecutionPlan for ProjectionExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        Ok(Box::pin(ProjectionStream {
-            schema: self.schema.clone(),
-            expr: self.expr.iter().map(|x| x.0.clone()).collect(),
-            input: self.input.execute(partition).await?,
-            baseline_metrics: BaselineMetrics::new(&self.metrics, partition),
-        }))
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ProjectionExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("ProjectionExec", partition));
+        let expr = self.expr.iter().map(|x| x.0.clone()).collect::<Vec<_>>();
+        let async_calls = expr.iter().filter(|e| is_async_call(e)).count();
+        let concurrency = context.session_config().async_udf_concurrency;
+        let input = self.input.execute(partition, context).await?;
+        let columns = expr
+            .iter()
+            .map(|expr| expr.as_any().downcast_ref::<Column>().map(|c| c.index()))
+            .collect::<Option<Vec<_>>>();
+        let passthrough = columns.as_ref().map_or(false, |columns| {
+            columns.iter().copied().eq(0..input.schema().fields().len())
+        }) && *self.schema == *input.schema();
+        if async_calls > 0 {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(AsyncProjectionStream::new(
+                    self.schema.clone(),
+                    expr,
+                    input,
+                    (concurrency / async_calls).max(1),
+                    baseline_metrics,
+                )),
+            ));
+        }
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(ProjectionStream {
+                schema: self.schema.clone(),
+                expr,
+                columns,
+                passthrough,
+                input,
+                baseline_metrics,
+            }),
+        ))
     }
 
     fn fmt_as(
@@ -203,6 +268,7 @@ impl ExecutionPlan for ProjectionExec {
 
                 write!(f, "ProjectionExec: expr=[{}]", expr.join(", "))
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -220,7 +286,7 @@ impl ExecutionPlan for ProjectionExec {
 
 /// If e is a direct column reference, returns the field level
 /// metadata for that field, if any. Otherwise returns None
-fn get_field_metadata(
+pub(crate) fn get_field_metadata(
     e: &Arc<dyn PhysicalExpr>,
     input_schema: &Schema,
 ) -> Option<BTreeMap<String, String>> {
@@ -267,6 +333,13 @@ impl ProjectionStream {
     fn batch_project(&self, batch: &RecordBatch) -> ArrowResult<RecordBatch> {
         // records time on drop
         let _timer = self.baseline_metrics.elapsed_compute().timer();
+        if self.passthrough {
+            return Ok(batch.clone());
+        }
+        if let Some(columns) = &self.columns {
+            let arrays = columns.iter().map(|i| batch.column(*i).clone()).collect();
+            return RecordBatch::try_new(self.schema.clone(), arrays);
+        }
         self.expr
             .iter()
             .map(|expr| expr.evaluate(batch))
@@ -283,6 +356,12 @@ impl ProjectionStream {
 struct ProjectionStream {
     schema: SchemaRef,
     expr: Vec<Arc<dyn PhysicalExpr>>,
+    /// The indices of the projected columns if all the expressions are
+    /// columns, whose arrays are then reused without evaluating them
+    columns: Option<Vec<usize>>,
+    /// Whether the projection returns all the columns of the input in order
+    /// and with the same schema, returning the input batches as is
+    passthrough: bool,
     input: SendableRecordBatchStream,
     baseline_metrics: BaselineMetrics,
 }
@@ -315,20 +394,118 @@ impl RecordBatchStream for ProjectionStream {
     }
 }
 
+/// Whether `expr` is a call of an async UDF, which is awaited by the projection
+fn is_async_call(expr: &Arc<dyn PhysicalExpr>) -> bool {
+    matches!(
+        expr.as_any().downcast_ref::<ScalarUDFExpr>(),
+        Some(udf) if udf.fun().is_async()
+    )
+}
+
+/// Evaluates `expr` on `batch`, returning the future of the values of the
+/// calls of async UDFs
+fn evaluate_async(
+    expr: &Arc<dyn PhysicalExpr>,
+    batch: &RecordBatch,
+) -> Result<BoxFuture<'static, Result<ArrayRef>>> {
+    match expr.as_any().downcast_ref::<ScalarUDFExpr>() {
+        Some(udf) if udf.fun().is_async() => udf.evaluate_async(batch),
+        _ => {
+            let array = expr.evaluate(batch)?.into_array(batch.num_rows());
+            Ok(future::ready(Ok(array)).boxed())
+        }
+    }
+}
+
+/// Projection iterator of a projection calling async UDFs, which awaits the
+/// calls on up to a number of batches at once and returns the batches in order
+struct AsyncProjectionStream {
+    schema: SchemaRef,
+    output: BoxStream<'static, ArrowResult<RecordBatch>>,
+    baseline_metrics: BaselineMetrics,
+}
+
+impl AsyncProjectionStream {
+    fn new(
+        schema: SchemaRef,
+        expr: Vec<Arc<dyn PhysicalExpr>>,
+        input: SendableRecordBatchStream,
+        batches: usize,
+        baseline_metrics: BaselineMetrics,
+    ) -> Self {
+        let output_schema = schema.clone();
+        let elapsed_compute = baseline_metrics.elapsed_compute().clone();
+        let output = input
+            .map(move |batch| {
+                // the arguments are evaluated and the async UDFs are called when
+                // the batch is received, their values are awaited with those of the
+                // other batches in flight
+                let columns = batch.and_then(|batch| {
+                    let _timer = elapsed_compute.timer();
+                    expr.iter()
+                        .map(|e| evaluate_async(e, &batch))
+                        .collect::<Result<Vec<_>>>()
+                        .map_err(DataFusionError::into_arrow_external_error)
+                });
+                let schema = output_schema.clone();
+                async move {
+                    let columns = future::try_join_all(columns?)
+                        .await
+                        .map_err(DataFusionError::into_arrow_external_error)?;
+                    RecordBatch::try_new(schema, columns)
+                }
+            })
+            .buffered(batches)
+            .boxed();
+        Self {
+            schema,
+            output,
+            baseline_metrics,
+        }
+    }
+}
+
+impl Stream for AsyncProjectionStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let poll = self.output.poll_next_unpin(cx);
+        self.baseline_metrics.record_poll(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.output.size_hint()
+    }
+}
+
+impl RecordBatchStream for AsyncProjectionStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::expressions::{self, col};
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
+    use crate::physical_plan::repartition::RepartitionExec;
     use crate::scalar::ScalarValue;
     use crate::test::{self};
     use crate::test_util;
+    use arrow::datatypes::DataType;
     use futures::future;
 
     #[tokio::test]
     async fn project_first_column() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let partitions = 4;
@@ -365,7 +542,7 @@ mod tests {
         let mut row_count = 0;
         for partition in 0..projection.output_partitioning().partition_count() {
             partition_count += 1;
-            let stream = projection.execute(partition).await?;
+            let stream = projection.execute(partition, task_ctx.clone()).await?;
 
             row_count += stream
                 .map(|batch| {
@@ -382,6 +559,44 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn hash_partitioning_renamed() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let input: Arc<dyn ExecutionPlan> = Arc::new(RepartitionExec::try_new(
+            Arc::new(MemoryExec::try_new(&[vec![]], schema.clone(), None)?),
+            Partitioning::Hash(vec![col("a", &schema)?], 4),
+        )?);
+
+        // `a` is renamed to `x` and moved after `b`
+        let projection = ProjectionExec::try_new(
+            vec![
+                (col("b", &schema)?, "b".to_string()),
+                (col("a", &schema)?, "x".to_string()),
+            ],
+            input.clone(),
+        )?;
+        assert_eq!(
+            format!("{:?}", projection.output_partitioning()),
+            format!(
+                "{:?}",
+                Partitioning::Hash(vec![col("x", &projection.schema())?], 4)
+            )
+        );
+
+        // `a` is projected away
+        let projection =
+            ProjectionExec::try_new(vec![(col("b", &schema)?, "b".to_string())], input)?;
+        assert_eq!(
+            format!("{:?}", projection.output_partitioning()),
+            format!("{:?}", Partitioning::UnknownPartitioning(4))
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_stats_projection_columns_only() {
         let source = Statistics {
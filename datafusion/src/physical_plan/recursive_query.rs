@@ -0,0 +1,589 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the execution plan of recursive queries, which evaluate their
+//! recursive term over the rows of the previous iteration until a fixpoint
+
+use std::any::Any;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use arrow::array::BooleanArray;
+use arrow::compute::filter_record_batch;
+use arrow::datatypes::{Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::Sender;
+
+use super::memory::{feed_input, MemoryStream};
+use super::metrics::{Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
+use super::stream::RecordBatchReceiverStream;
+use super::trace::instrument_stream;
+use super::{
+    collect, fmt_with_statistics, DisplayFormatType, Distribution, ExecutionPlan,
+    LambdaExecPlan, Partitioning, SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::scalar::ScalarValue;
+
+/// Execution plan of a recursive query: the rows of its static term are the
+/// rows of the first iteration, and each following iteration evaluates its
+/// recursive term over the rows of the previous one, read through the
+/// [`WorkTableExec`] scans of the query. The query outputs the rows of every
+/// iteration, and stops at the first iteration without rows.
+///
+/// With `UNION` semantics, the rows already output are dropped from the rows
+/// of an iteration. The query fails if it doesn't stop within the
+/// `recursive_query_max_iterations` of the settings of the task.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecursiveQueryExec {
+    /// The name of the common table expression
+    name: String,
+    /// The term producing the rows of the first iteration
+    static_term: Arc<dyn ExecutionPlan>,
+    /// The term evaluated over the rows of the previous iteration
+    recursive_term: Arc<dyn ExecutionPlan>,
+    /// Whether the rows already output are dropped from the rows of an
+    /// iteration
+    is_distinct: bool,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl RecursiveQueryExec {
+    /// Create the execution plan of the recursive query `name`
+    pub fn new(
+        name: &str,
+        static_term: Arc<dyn ExecutionPlan>,
+        recursive_term: Arc<dyn ExecutionPlan>,
+        is_distinct: bool,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            static_term,
+            recursive_term,
+            is_distinct,
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// The name of the common table expression
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the rows already output are dropped from the rows of an
+    /// iteration
+    pub fn is_distinct(&self) -> bool {
+        self.is_distinct
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for RecursiveQueryExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        // the recursive term reads the previous iteration, it is not fed
+        feed_input(&mut self.static_term, partitions);
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "recursive_query_exec")]
+impl ExecutionPlan for RecursiveQueryExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.static_term.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.static_term.clone(), self.recursive_term.clone()]
+    }
+
+    /// The terms are evaluated over all their partitions
+    fn required_child_distribution(&self) -> Distribution {
+        Distribution::UnspecifiedDistribution
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.as_slice() {
+            [static_term, recursive_term] => Ok(Arc::new(Self::new(
+                &self.name,
+                static_term.clone(),
+                recursive_term.clone(),
+                self.is_distinct,
+            ))),
+            _ => Err(DataFusionError::Internal(format!(
+                "Invalid child count for RecursiveQueryExec. Expected 2 got {}",
+                children.len()
+            ))),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "RecursiveQueryExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "RecursiveQueryExec invalid partition. Expected 0, got {}",
+                partition
+            )));
+        }
+
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        let recursion = Recursion {
+            name: self.name.clone(),
+            schema: self.schema(),
+            static_term: self.static_term.clone(),
+            recursive_term: self.recursive_term.clone(),
+            is_distinct: self.is_distinct,
+            max_iterations: context.session_config().recursive_query_max_iterations,
+            iterations: MetricBuilder::new(&self.metrics)
+                .counter("iterations", partition),
+        };
+
+        let join_handle = tokio::task::spawn(async move {
+            if let Err(e) = recursion.run(context, &tx).await {
+                // ignore the error of a receiver hanging up: no one will look at
+                // the error
+                tx.send(Err(e.into_arrow_external_error())).await.ok();
+            }
+        });
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            RecordBatchReceiverStream::create(&self.schema(), rx, join_handle),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "RecursiveQueryExec: name={}, is_distinct={}",
+                self.name, self.is_distinct
+            ),
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        // the number of iterations is unknown
+        Statistics::default()
+    }
+}
+
+/// The evaluation of a recursive query by the task of its stream
+struct Recursion {
+    name: String,
+    schema: SchemaRef,
+    static_term: Arc<dyn ExecutionPlan>,
+    recursive_term: Arc<dyn ExecutionPlan>,
+    is_distinct: bool,
+    max_iterations: usize,
+    /// Number of evaluations of the recursive term
+    iterations: Count,
+}
+
+impl Recursion {
+    /// Send the rows of every iteration to `tx`, until an iteration has no
+    /// rows or the receiver hangs up
+    async fn run(
+        self,
+        context: Arc<TaskContext>,
+        tx: &Sender<ArrowResult<RecordBatch>>,
+    ) -> Result<()> {
+        let mut seen = HashSet::new();
+        let batches = collect(self.static_term.clone(), context.clone()).await?;
+        let mut delta = self.new_rows(batches, &mut seen)?;
+        let mut iteration = 0;
+        while !delta.is_empty() {
+            for batch in &delta {
+                if tx.send(Ok(batch.clone())).await.is_err() {
+                    return Ok(());
+                }
+            }
+            if iteration == self.max_iterations {
+                return Err(DataFusionError::Execution(format!(
+                    "Recursive query {} did not reach a fixpoint within {} iterations, \
+                    see the setting datafusion.execution.recursive_query_max_iterations",
+                    self.name, self.max_iterations
+                )));
+            }
+            iteration += 1;
+            self.iterations.add(1);
+
+            let term = bind_work_table(&self.recursive_term, &self.name, &delta)?;
+            let batches = collect(term, context.clone()).await?;
+            delta = self.new_rows(batches, &mut seen)?;
+        }
+        Ok(())
+    }
+
+    /// The non-empty batches of an iteration in the schema of the query,
+    /// without the rows already output if the query is distinct. The rows
+    /// kept are added to `seen`.
+    fn new_rows(
+        &self,
+        batches: Vec<RecordBatch>,
+        seen: &mut HashSet<Vec<ScalarValue>>,
+    ) -> Result<Vec<RecordBatch>> {
+        let mut rows = vec![];
+        for batch in batches {
+            // the terms have the same types, but their columns may be named
+            // differently
+            let batch =
+                RecordBatch::try_new(self.schema.clone(), batch.columns().to_vec())?;
+            let batch = if self.is_distinct {
+                distinct_rows(&batch, seen)?
+            } else {
+                batch
+            };
+            if batch.num_rows() > 0 {
+                rows.push(batch);
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// The rows of `batch` not in `seen`, once each, adding them to `seen`
+fn distinct_rows(
+    batch: &RecordBatch,
+    seen: &mut HashSet<Vec<ScalarValue>>,
+) -> Result<RecordBatch> {
+    let mask = (0..batch.num_rows())
+        .map(|row| {
+            let key = batch
+                .columns()
+                .iter()
+                .map(|column| ScalarValue::try_from_array(column, row))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Some(seen.insert(key)))
+        })
+        .collect::<Result<BooleanArray>>()?;
+    Ok(filter_record_batch(batch, &mask)?)
+}
+
+/// Returns a copy of `plan` in which the scans of the work table `name` read
+/// `batches`. All the operators with inputs are rebuilt, so that those keeping
+/// state across executions, such as repartitions, are executed afresh.
+fn bind_work_table(
+    plan: &Arc<dyn ExecutionPlan>,
+    name: &str,
+    batches: &[RecordBatch],
+) -> Result<Arc<dyn ExecutionPlan>> {
+    if let Some(work_table) = plan.as_any().downcast_ref::<WorkTableExec>() {
+        if work_table.name == name {
+            let mut work_table = work_table.clone();
+            work_table.batches = Some(batches.to_vec());
+            return Ok(Arc::new(work_table));
+        }
+    }
+    let children = plan.children();
+    if children.is_empty() {
+        return Ok(plan.clone());
+    }
+    let children = children
+        .iter()
+        .map(|child| bind_work_table(child, name, batches))
+        .collect::<Result<Vec<_>>>()?;
+    plan.with_new_children(children)
+}
+
+/// Scan of the rows of the previous iteration of the recursive query `name`,
+/// in its recursive term. The scan is bound to the rows of an iteration by
+/// the [`RecursiveQueryExec`] of the query, and fails if executed unbound.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkTableExec {
+    /// The name of the common table expression
+    name: String,
+    /// The schema of the rows of the iterations
+    schema: SchemaRef,
+    /// Optional column indices to use as a projection
+    projection: Option<Vec<usize>>,
+    /// The schema after the projection
+    projected_schema: SchemaRef,
+    /// The rows of the previous iteration, once bound
+    #[serde(skip)]
+    batches: Option<Vec<RecordBatch>>,
+}
+
+impl WorkTableExec {
+    /// Create an unbound scan of the work table `name`
+    pub fn new(name: &str, schema: SchemaRef, projection: Option<Vec<usize>>) -> Self {
+        let projected_schema = match &projection {
+            Some(columns) => Arc::new(Schema::new(
+                columns.iter().map(|i| schema.field(*i).clone()).collect(),
+            )),
+            None => schema.clone(),
+        };
+        Self {
+            name: name.to_owned(),
+            schema,
+            projection,
+            projected_schema,
+            batches: None,
+        }
+    }
+
+    /// The name of the common table expression
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for WorkTableExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.batches = Some(partitions.into_iter().flatten().collect());
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "work_table_exec")]
+impl ExecutionPlan for WorkTableExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(1)
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.is_empty() {
+            Ok(Arc::new(self.clone()))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "Children cannot be replaced in {:?}",
+                self
+            )))
+        }
+    }
+
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if 0 != partition {
+            return Err(DataFusionError::Internal(format!(
+                "WorkTableExec invalid partition. Expected 0, got {}",
+                partition
+            )));
+        }
+        let batches = self.batches.clone().ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "The work table {} can only be read by its recursive query",
+                self.name
+            ))
+        })?;
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(MemoryStream::try_new(
+                batches,
+                self.projected_schema.clone(),
+                self.projection.clone(),
+            )?),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "WorkTableExec: name={}, projection={:?}",
+                self.name, self.projection
+            ),
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::logical_plan::Operator;
+    use crate::physical_plan::expressions::{col, lit, BinaryExpr};
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::projection::ProjectionExec;
+    use crate::physical_plan::PhysicalExpr;
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field};
+
+    fn schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![Field::new("n", DataType::Int32, false)]))
+    }
+
+    /// `n` from 1 while `n < limit`, by `n + step` in the recursive term
+    fn counter(limit: i32, step: i32, is_distinct: bool) -> Result<RecursiveQueryExec> {
+        let schema = schema();
+        let one = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )?;
+        let static_term =
+            Arc::new(MemoryExec::try_new(&[vec![one]], schema.clone(), None)?);
+        let work_table = Arc::new(WorkTableExec::new("t", schema.clone(), None));
+        let n = col("n", &schema)?;
+        let below_limit =
+            Arc::new(BinaryExpr::new(n.clone(), Operator::Lt, lit(limit.into())));
+        let filter = Arc::new(FilterExec::try_new(below_limit, work_table)?);
+        let next: Arc<dyn PhysicalExpr> =
+            Arc::new(BinaryExpr::new(n, Operator::Plus, lit(step.into())));
+        let recursive_term = Arc::new(ProjectionExec::try_new(
+            vec![(next, "n".to_owned())],
+            filter,
+        )?);
+        Ok(RecursiveQueryExec::new(
+            "t",
+            static_term,
+            recursive_term,
+            is_distinct,
+        ))
+    }
+
+    #[tokio::test]
+    async fn iterate_to_fixpoint() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let query = Arc::new(counter(5, 1, false)?);
+        let batches = collect(query.clone(), task_ctx).await?;
+
+        #[rustfmt::skip]
+        crate::assert_batches_eq!(&[
+            "+---+",
+            "| n |",
+            "+---+",
+            "| 1 |",
+            "| 2 |",
+            "| 3 |",
+            "| 4 |",
+            "| 5 |",
+            "+---+",
+        ], &batches);
+        let iterations = query
+            .metrics()
+            .unwrap()
+            .sum(|m| m.value().name() == "iterations")
+            .map(|v| v.as_usize());
+        // the last iteration produces no rows
+        assert_eq!(iterations, Some(5));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn distinct_iterations() -> Result<()> {
+        // the recursive term of the cycle 1 -> 1 outputs the rows of the
+        // previous iteration, already output
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let batches = collect(Arc::new(counter(5, 0, true)?), task_ctx).await?;
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn max_iterations() -> Result<()> {
+        let config = ExecutionConfig::new().with_recursive_query_max_iterations(3);
+        let task_ctx = ExecutionContext::with_config(config).task_ctx();
+        let err = collect(Arc::new(counter(5, 0, false)?), task_ctx)
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "Recursive query t did not reach a fixpoint within 3 iterations"
+            ),
+            "{}",
+            err
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unbound_work_table() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let work_table = WorkTableExec::new("t", schema(), None);
+        assert!(work_table.execute(0, task_ctx).await.is_err());
+        Ok(())
+    }
+}
@@ -19,13 +19,22 @@
 //! partitioning scheme.
 
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::{any::Any, vec};
 
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::hash_utils::create_hashes;
-use crate::physical_plan::{DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning, Statistics};
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::hash_utils::{create_hashes, partition_random_state};
+use crate::physical_plan::memory::feed_input;
+use crate::physical_plan::trace::instrument_stream;
+use crate::physical_plan::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    Statistics,
+};
 use arrow::record_batch::RecordBatch;
 use arrow::{array::Array, error::Result as ArrowResult};
 use arrow::{compute::take, datatypes::SchemaRef};
@@ -41,7 +50,7 @@ use futures::StreamExt;
 use hashbrown::HashMap;
 use tokio::sync::{
     mpsc::{self, UnboundedReceiver, UnboundedSender},
-    Mutex,
+    Mutex, OwnedSemaphorePermit, Semaphore, TryAcquireError,
 };
 use tokio::task::JoinHandle;
 
@@ -49,18 +58,70 @@ use serde::{Deserialize, Serialize};
 
 type MaybeBatch = Option<ArrowResult<RecordBatch>>;
 
+/// A batch sent to an output partition, with the permit it holds on the
+/// capacity of the partition if it counts against it
+type PartitionItem = (MaybeBatch, Option<OwnedSemaphorePermit>);
+
 /// Inner state of [`RepartitionExec`].
 #[derive(Debug, Default)]
 struct RepartitionExecState {
     /// Channels for sending batches from input partitions to output partitions.
     /// Key is the partition number.
-    channels:
-        HashMap<usize, (UnboundedSender<MaybeBatch>, UnboundedReceiver<MaybeBatch>)>,
+    channels: HashMap<usize, (PartitionSender, UnboundedReceiver<PartitionItem>)>,
 
     /// Helper that ensures that that background job is killed once it is no longer needed.
+    /// Held by the output streams only once they are all created, so that dropping them
+    /// all aborts the tasks.
     abort_helper: Arc<AbortOnDropMany<()>>,
 }
 
+/// The sending side of the channel of an output partition
+#[derive(Debug, Clone)]
+struct PartitionSender {
+    tx: UnboundedSender<PartitionItem>,
+    /// Permits of the batches buffered for the partition, closed once its output
+    /// stream is dropped
+    capacity: Arc<Semaphore>,
+    /// Whether the output stream of the partition has been polled: the batches
+    /// only count against the capacity from then on
+    polled: Arc<AtomicBool>,
+}
+
+impl PartitionSender {
+    fn new(capacity: usize) -> (Self, UnboundedReceiver<PartitionItem>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let sender = Self {
+            tx,
+            capacity: Arc::new(Semaphore::new(capacity)),
+            polled: Arc::new(AtomicBool::new(false)),
+        };
+        (sender, rx)
+    }
+
+    /// Sends `batch`, waiting for the capacity of the partition if its output
+    /// stream is being read. Returns false if the output stream is dropped.
+    async fn send(&self, batch: MaybeBatch, r_metrics: &RepartitionMetrics) -> bool {
+        let permit = if self.polled.load(Ordering::Acquire) {
+            match self.capacity.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(TryAcquireError::NoPermits) => {
+                    let timer = r_metrics.send_blocked_time.timer();
+                    let permit = self.capacity.clone().acquire_owned().await;
+                    timer.done();
+                    match permit {
+                        Ok(permit) => Some(permit),
+                        Err(_) => return false,
+                    }
+                }
+                Err(TryAcquireError::Closed) => return false,
+            }
+        } else {
+            None
+        };
+        self.tx.send((batch, permit)).is_ok()
+    }
+}
+
 /// The repartition operator maps N input partitions to M output partitions based on a
 /// partitioning scheme. No guarantees are made about the order of the resulting partitions.
 #[derive(Debug, Serialize, Deserialize)]
@@ -77,6 +138,11 @@ pub struct RepartitionExec {
 
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
+
+    /// Token cancelling the tasks pulling the input partitions, bound by the
+    /// runtime
+    #[serde(skip)]
+    cancellation: CancellationToken,
 }
 
 #[derive(Debug, Clone)]
@@ -87,6 +153,8 @@ struct RepartitionMetrics {
     repart_time: metrics::Time,
     /// Time in nanos for sending resulting batches to channels
     send_time: metrics::Time,
+    /// Time in nanos waiting for full channels to be read, part of `send_time`
+    send_blocked_time: metrics::Time,
 }
 
 impl RepartitionMetrics {
@@ -109,13 +177,19 @@ impl RepartitionMetrics {
 
         // Time in nanos for sending resulting batches to channels
         let send_time = MetricBuilder::new(metrics)
-            .with_label(label)
+            .with_label(label.clone())
             .subset_time("send_time", output_partition);
 
+        // Time in nanos waiting for full channels to be read
+        let send_blocked_time = MetricBuilder::new(metrics)
+            .with_label(label)
+            .subset_time("send_blocked_time", output_partition);
+
         Self {
             fetch_time,
             repart_time,
             send_time,
+            send_blocked_time,
         }
     }
 }
@@ -158,10 +232,14 @@ impl ExecutionPlan for RepartitionExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(RepartitionExec::try_new(
-                children[0].clone(),
-                self.partitioning.clone(),
-            )?)),
+            1 => {
+                let mut repartition = RepartitionExec::try_new(
+                    children[0].clone(),
+                    self.partitioning.clone(),
+                )?;
+                repartition.cancellation = self.cancellation.clone();
+                Ok(Arc::new(repartition))
+            }
             _ => Err(DataFusionError::Internal(
                 "RepartitionExec wrong number of children".to_string(),
             )),
@@ -172,7 +250,21 @@ impl ExecutionPlan for RepartitionExec {
         self.partitioning.clone()
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "RepartitionExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // lock mutexes
         let mut state = self.state.lock().await;
 
@@ -182,19 +274,20 @@ impl ExecutionPlan for RepartitionExec {
         // if this is the first partition to be invoked then we need to set up initial state
         if state.channels.is_empty() {
             // create one channel per *output* partition
+            let capacity = context.session_config().repartition_channel_capacity.max(1);
             for partition in 0..num_output_partitions {
-                // Note that this operator uses unbounded channels to avoid deadlocks because
-                // the output partitions can be read in any order and this could cause input
-                // partitions to be blocked when sending data to output UnboundedReceivers that are not
-                // being read yet. This may cause high memory usage if the next operator is
-                // reading output partitions in order rather than concurrently. One workaround
-                // for this would be to add spill-to-disk capabilities.
-                let (sender, receiver) =
-                    mpsc::unbounded_channel::<Option<ArrowResult<RecordBatch>>>();
+                // Note that once an output partition is read, at most `capacity` batches
+                // are buffered for it, so that a slow consumer holds back the input tasks
+                // instead of having them buffer their whole input. The batches of the
+                // partitions that are not read yet are buffered without bound, so that
+                // an operator reading the partitions one after another, or only some of
+                // them, does not wait forever on the input tasks waiting for the others.
+                let (sender, receiver) = PartitionSender::new(capacity);
                 state.channels.insert(partition, (sender, receiver));
             }
-            // Use fixed random state
-            let random = ahash::RandomState::with_seeds(0, 0, 0, 0);
+            // Use the random state of the hash joins, for their inputs
+            // repartitioned on the join keys to be co-partitioned
+            let random = partition_random_state();
 
             // launch one async task per *input* partition
             let mut join_handles = Vec::with_capacity(num_input_partitions);
@@ -202,7 +295,7 @@ impl ExecutionPlan for RepartitionExec {
                 let txs: HashMap<_, _> = state
                     .channels
                     .iter()
-                    .map(|(partition, (tx, _rx))| (*partition, tx.clone()))
+                    .map(|(partition, (sender, _rx))| (*partition, sender.clone()))
                     .collect();
 
                 let r_metrics = RepartitionMetrics::new(i, partition, &self.metrics);
@@ -215,6 +308,8 @@ impl ExecutionPlan for RepartitionExec {
                         txs.clone(),
                         self.partitioning.clone(),
                         r_metrics,
+                        self.cancellation.clone(),
+                        context.clone(),
                     ));
 
                 // In a separate task, wait for each input to be done
@@ -229,17 +324,35 @@ impl ExecutionPlan for RepartitionExec {
             state.abort_helper = Arc::new(AbortOnDropMany(join_handles))
         }
 
+        let (sender, receiver) = state.channels.remove(&partition).ok_or_else(|| {
+            DataFusionError::Execution(format!(
+                "Partition {} of RepartitionExec is already executed",
+                partition
+            ))
+        })?;
+        let drop_helper = Arc::clone(&state.abort_helper);
+        if state.channels.is_empty() {
+            // all the output streams are created, the tasks are aborted once the
+            // last of them is dropped
+            state.abort_helper = Arc::new(AbortOnDropMany::<()>(vec![]));
+        }
+
         // now return stream for the specified *output* partition which will
         // read from the channel
-        Ok(Box::pin(RepartitionStream {
-            num_input_partitions,
-            num_input_partitions_processed: 0,
-            schema: self.input.schema(),
-            input: UnboundedReceiverStream::new(
-                state.channels.remove(&partition).unwrap().1,
-            ),
-            drop_helper: Arc::clone(&state.abort_helper),
-        }))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(RepartitionStream {
+                num_input_partitions,
+                num_input_partitions_processed: 0,
+                schema: self.input.schema(),
+                input: UnboundedReceiverStream::new(receiver),
+                capacity: sender.capacity,
+                polled: sender.polled,
+                drop_helper,
+                output_rows: MetricBuilder::new(&self.metrics).output_rows(partition),
+            }),
+        ))
     }
 
     fn metrics(&self) -> Option<MetricsSet> {
@@ -255,18 +368,34 @@ impl ExecutionPlan for RepartitionExec {
             DisplayFormatType::Default => {
                 write!(f, "RepartitionExec: partitioning={:?}", self.partitioning)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
     fn statistics(&self) -> Statistics {
         self.input.statistics()
     }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut repartition =
+            RepartitionExec::try_new(self.input.clone(), self.partitioning.clone())?;
+        repartition.cancellation = runtime.cancellation.clone();
+        Ok(Some(Arc::new(repartition)))
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for RepartitionExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        self.state = Arc::new(Mutex::new(RepartitionExecState {
+            channels: HashMap::new(),
+            abort_helper: Arc::new(AbortOnDropMany::<()>(vec![])),
+        }));
+        Ok(())
     }
 }
 
@@ -284,6 +413,7 @@ impl RepartitionExec {
                 abort_helper: Arc::new(AbortOnDropMany::<()>(vec![])),
             })),
             metrics: ExecutionPlanMetricsSet::new(),
+            cancellation: CancellationToken::new(),
         })
     }
 
@@ -293,23 +423,28 @@ impl RepartitionExec {
     /// i is the input partition index
     ///
     /// txs hold the output sending channels for each output partition
+    #[allow(clippy::too_many_arguments)]
     async fn pull_from_input(
         random_state: ahash::RandomState,
         input: Arc<dyn ExecutionPlan>,
         i: usize,
-        mut txs: HashMap<usize, UnboundedSender<Option<ArrowResult<RecordBatch>>>>,
+        mut txs: HashMap<usize, PartitionSender>,
         partitioning: Partitioning,
         r_metrics: RepartitionMetrics,
+        cancellation: CancellationToken,
+        context: Arc<TaskContext>,
     ) -> Result<()> {
         let num_output_partitions = txs.len();
 
         // execute the child operator
         let timer = r_metrics.fetch_time.timer();
-        let mut stream = input.execute(i).await?;
+        let mut stream = input.execute(i, context).await?;
         timer.done();
 
         let mut counter = 0;
         let hashes_buf = &mut vec![];
+        // number of rows sent to each output partition, for RoundRobinRows
+        let mut partition_rows = vec![0; num_output_partitions];
 
         // While there are still outputs to send to, keep
         // pulling inputs
@@ -324,20 +459,34 @@ impl RepartitionExec {
                 break;
             }
             let result: ArrowResult<RecordBatch> = result.unwrap();
+            cancellation.check()?;
 
             match &partitioning {
                 Partitioning::RoundRobinBatch(_) => {
                     let timer = r_metrics.send_time.timer();
                     let output_partition = counter % num_output_partitions;
-                    // if there is still a receiver, send to it
-                    if let Some(tx) = txs.get_mut(&output_partition) {
-                        if tx.send(Some(result)).is_err() {
-                            // If the other end has hung up, it was an early shutdown (e.g. LIMIT)
-                            txs.remove(&output_partition);
-                        }
-                    }
+                    send_to_partition(&mut txs, output_partition, result, &r_metrics)
+                        .await;
                     timer.done();
                 }
+                Partitioning::RoundRobinRows(_) => {
+                    let input_batch = result?;
+                    let slices =
+                        round_robin_rows(&mut partition_rows, input_batch.num_rows());
+                    for (output_partition, offset, length) in slices {
+                        let output_batch = input_batch.slice(offset, length);
+
+                        let timer = r_metrics.send_time.timer();
+                        send_to_partition(
+                            &mut txs,
+                            output_partition,
+                            Ok(output_batch),
+                            &r_metrics,
+                        )
+                        .await;
+                        timer.done();
+                    }
+                }
                 Partitioning::Hash(exprs, _) => {
                     let timer = r_metrics.repart_time.timer();
                     let input_batch = result?;
@@ -383,13 +532,13 @@ impl RepartitionExec {
                         timer.done();
 
                         let timer = r_metrics.send_time.timer();
-                        // if there is still a receiver, send to it
-                        if let Some(tx) = txs.get_mut(&num_output_partition) {
-                            if tx.send(Some(output_batch)).is_err() {
-                                // If the other end has hung up, it was an early shutdown (e.g. LIMIT)
-                                txs.remove(&num_output_partition);
-                            }
-                        }
+                        send_to_partition(
+                            &mut txs,
+                            num_output_partition,
+                            output_batch,
+                            &r_metrics,
+                        )
+                        .await;
                         timer.done();
                     }
                 }
@@ -445,13 +594,13 @@ impl RepartitionExec {
                         timer.done();
 
                         let timer = r_metrics.repart_time.timer();
-                        // if there is still a receiver, send to it
-                        if let Some(tx) = txs.get_mut(&num_output_partition) {
-                            if tx.send(Some(output_batch)).is_err() {
-                                // If the other end has hung up, it was an early shutdown (e.g. LIMIT)
-                                txs.remove(&num_output_partition);
-                            }
-                        }
+                        send_to_partition(
+                            &mut txs,
+                            num_output_partition,
+                            output_batch,
+                            &r_metrics,
+                        )
+                        .await;
                         timer.done();
                     }                                   
                 }
@@ -477,39 +626,98 @@ impl RepartitionExec {
     /// channels.
     async fn wait_for_task(
         input_task: AbortOnDropSingle<Result<()>>,
-        txs: HashMap<usize, UnboundedSender<Option<ArrowResult<RecordBatch>>>>,
+        txs: HashMap<usize, PartitionSender>,
     ) {
         // wait for completion, and propagate error
         // note we ignore errors on send (.ok) as that means the receiver has already shutdown.
         match input_task.await {
             // Error in joining task
             Err(e) => {
-                for (_, tx) in txs {
+                for (_, sender) in txs {
                     let err = DataFusionError::Execution(format!("Join Error: {}", e));
                     let err = Err(err.into_arrow_external_error());
-                    tx.send(Some(err)).ok();
+                    sender.tx.send((Some(err), None)).ok();
                 }
             }
             // Error from running input task
             Ok(Err(e)) => {
-                for (_, tx) in txs {
+                for (_, sender) in txs {
                     // wrap it because need to send error to all output partitions
                     let err = DataFusionError::Execution(e.to_string());
                     let err = Err(err.into_arrow_external_error());
-                    tx.send(Some(err)).ok();
+                    sender.tx.send((Some(err), None)).ok();
                 }
             }
             // Input task completed successfully
             Ok(Ok(())) => {
                 // notify each output partition that this input partition has no more data
-                for (_, tx) in txs {
-                    tx.send(None).ok();
+                for (_, sender) in txs {
+                    sender.tx.send((None, None)).ok();
                 }
             }
         }
     }
 }
 
+/// Sends `batch` to the output partition `partition` if it still has a receiver,
+/// waiting while the partition is read and its buffer is full. Forgets the
+/// partition once its receiver is dropped, e.g. by a LIMIT that does not need
+/// more rows.
+async fn send_to_partition(
+    txs: &mut HashMap<usize, PartitionSender>,
+    partition: usize,
+    batch: ArrowResult<RecordBatch>,
+    r_metrics: &RepartitionMetrics,
+) {
+    let sent = match txs.get(&partition) {
+        Some(tx) => tx.send(Some(batch), r_metrics).await,
+        None => return,
+    };
+    if !sent {
+        txs.remove(&partition);
+    }
+}
+
+/// Splits `num_rows` rows into the slices `(output partition, offset, length)`
+/// going to the partitions with the fewest rows in `partition_rows`, and adds
+/// them to `partition_rows`. The numbers of rows of the partitions stay within
+/// one row of each other.
+fn round_robin_rows(
+    partition_rows: &mut [usize],
+    num_rows: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut partitions = (0..partition_rows.len()).collect::<Vec<_>>();
+    partitions.sort_by_key(|partition| partition_rows[*partition]);
+
+    // fill the `filled` partitions with the fewest rows up to the same level,
+    // the next partitions already have as many rows as that level
+    let mut filled = 0;
+    let mut rows = num_rows;
+    for partition in &partitions {
+        let count = partition_rows[*partition];
+        if filled > 0 && rows / filled <= count {
+            break;
+        }
+        filled += 1;
+        rows += count;
+    }
+    let level = rows / filled;
+    let remainder = rows % filled;
+
+    let mut slices = Vec::with_capacity(filled);
+    let mut offset = 0;
+    for (i, partition) in partitions[..filled].iter().enumerate() {
+        let target = if i < remainder { level + 1 } else { level };
+        let length = target - partition_rows[*partition];
+        if length > 0 {
+            slices.push((*partition, offset, length));
+            partition_rows[*partition] = target;
+            offset += length;
+        }
+    }
+    slices
+}
+
 struct RepartitionStream {
     /// Number of input partitions that will be sending batches to this output channel
     num_input_partitions: usize,
@@ -521,11 +729,21 @@ struct RepartitionStream {
     schema: SchemaRef,
 
     /// channel containing the repartitioned batches
-    input: UnboundedReceiverStream<Option<ArrowResult<RecordBatch>>>,
+    input: UnboundedReceiverStream<PartitionItem>,
+
+    /// Permits of the batches buffered for this output partition
+    capacity: Arc<Semaphore>,
+
+    /// Set once this stream is polled, for the input tasks to wait for the
+    /// capacity of the partition
+    polled: Arc<AtomicBool>,
 
     /// Handle to ensure background tasks are killed when no longer needed.
     #[allow(dead_code)]
     drop_helper: Arc<AbortOnDropMany<()>>,
+
+    /// Number of rows of this output partition
+    output_rows: metrics::Count,
 }
 
 impl Stream for RepartitionStream {
@@ -535,9 +753,16 @@ impl Stream for RepartitionStream {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Option<Self::Item>> {
+        self.polled.store(true, Ordering::Release);
+        // the permit of the batch, if any, is released once it is received
         match self.input.poll_next_unpin(cx) {
-            Poll::Ready(Some(Some(v))) => Poll::Ready(Some(v)),
-            Poll::Ready(Some(None)) => {
+            Poll::Ready(Some((Some(v), _permit))) => {
+                if let Ok(batch) = &v {
+                    self.output_rows.add(batch.num_rows());
+                }
+                Poll::Ready(Some(v))
+            }
+            Poll::Ready(Some((None, _))) => {
                 self.num_input_partitions_processed += 1;
                 if self.num_input_partitions == self.num_input_partitions_processed {
                     // all input partitions have finished sending batches
@@ -553,6 +778,13 @@ impl Stream for RepartitionStream {
     }
 }
 
+impl Drop for RepartitionStream {
+    fn drop(&mut self) {
+        // wake up the input tasks waiting for the capacity of this partition
+        self.capacity.close();
+    }
+}
+
 impl RecordBatchStream for RepartitionStream {
     /// Get the schema
     fn schema(&self) -> SchemaRef {
@@ -567,7 +799,16 @@ mod tests {
     use super::*;
     use crate::{
         assert_batches_sorted_eq,
-        physical_plan::{collect, expressions::col, memory::MemoryExec},
+        execution::context::{ExecutionConfig, ExecutionContext},
+        logical_plan::{JoinType, Operator},
+        physical_plan::{
+            collect,
+            expressions::{binary, col, lit, Column},
+            hash_join::{HashJoinExec, PartitionMode},
+            memory::MemoryExec,
+            metrics::MetricValue,
+        },
+        scalar::ScalarValue,
         test::{
             assert_is_pending,
             exec::{
@@ -583,6 +824,7 @@ mod tests {
         error::ArrowError,
     };
     use futures::FutureExt;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn one_to_many_round_robin() -> Result<()> {
@@ -667,6 +909,326 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn round_robin_rows_bounded_skew() -> Result<()> {
+        // a few large batches among small ones
+        let schema = test_schema();
+        let sizes = [1000, 1, 1, 1, 1000, 3, 500, 7];
+        let partition = sizes
+            .iter()
+            .map(|size| uint32_batch(&schema, 0..*size))
+            .collect::<Vec<_>>();
+
+        let output_partitions = repartition(
+            &schema,
+            vec![partition.clone()],
+            Partitioning::RoundRobinBatch(4),
+        )
+        .await?;
+        let rows = rows_per_partition(&output_partitions);
+        assert_eq!(rows, vec![2000, 4, 501, 8]);
+
+        let output_partitions = repartition(
+            &schema,
+            vec![partition.clone()],
+            Partitioning::RoundRobinRows(4),
+        )
+        .await?;
+        let rows = rows_per_partition(&output_partitions);
+        assert_eq!(rows.iter().sum::<usize>(), 2513);
+        assert!(
+            rows.iter().all(|rows| *rows == 628 || *rows == 629),
+            "{:?}",
+            rows
+        );
+
+        // each input partition is balanced on its own
+        let partitions = vec![partition.clone(), partition.clone(), partition];
+        let output_partitions =
+            repartition(&schema, partitions, Partitioning::RoundRobinRows(5)).await?;
+        let rows = rows_per_partition(&output_partitions);
+        assert_eq!(rows.iter().sum::<usize>(), 3 * 2513);
+        let skew = rows.iter().max().unwrap() - rows.iter().min().unwrap();
+        assert!(skew <= 3, "{:?}", rows);
+
+        Ok(())
+    }
+
+    #[test]
+    fn round_robin_rows_fills_emptiest_partitions() {
+        let mut partition_rows = vec![5, 0, 9, 2];
+        let slices = round_robin_rows(&mut partition_rows, 10);
+        assert_eq!(slices, vec![(1, 0, 6), (3, 6, 4)]);
+        assert_eq!(partition_rows, vec![5, 6, 9, 6]);
+
+        let slices = round_robin_rows(&mut partition_rows, 0);
+        assert!(slices.is_empty());
+
+        let slices = round_robin_rows(&mut partition_rows, 7);
+        assert_eq!(slices, vec![(0, 0, 3), (1, 3, 2), (3, 5, 2)]);
+        assert_eq!(partition_rows, vec![8, 8, 9, 8]);
+    }
+
+    #[tokio::test]
+    async fn output_rows_per_partition() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 5);
+        let exec = MemoryExec::try_new(&[partition], schema.clone(), None)?;
+        let exec =
+            RepartitionExec::try_new(Arc::new(exec), Partitioning::RoundRobinBatch(3))?;
+
+        let mut rows = vec![];
+        for i in 0..3 {
+            let batches = crate::physical_plan::common::collect(
+                exec.execute(i, task_ctx.clone()).await?,
+            )
+            .await?;
+            rows.push(batches.iter().map(|batch| batch.num_rows()).sum::<usize>());
+        }
+        assert_eq!(rows, vec![16, 16, 8]);
+
+        let metrics = exec.metrics().unwrap();
+        let mut output_rows = metrics
+            .iter()
+            .filter_map(|metric| match metric.value() {
+                MetricValue::OutputRows(count) => {
+                    Some((metric.partition().unwrap(), count.value()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        output_rows.sort_unstable();
+        assert_eq!(output_rows, vec![(0, 16), (1, 16), (2, 8)]);
+        assert_eq!(metrics.output_rows(), Some(40));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn slow_consumer_holds_back_input() -> Result<()> {
+        let task_ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_repartition_channel_capacity(2),
+        )
+        .task_ctx();
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 100);
+        let exec = MemoryExec::try_new(&[partition], schema.clone(), None)?;
+        let exec =
+            RepartitionExec::try_new(Arc::new(exec), Partitioning::RoundRobinBatch(2))?;
+
+        let mut output_stream0 = exec.execute(0, task_ctx.clone()).await?;
+        let mut output_stream1 = exec.execute(1, task_ctx).await?;
+        output_stream1.next().await.unwrap()?;
+
+        // partition 1 is read but stalls, so the input task waits for it once 2
+        // batches are buffered for it, after sending 4 batches to partition 0
+        let mut received = 0;
+        while let Ok(Some(batch)) =
+            tokio::time::timeout(Duration::from_millis(100), output_stream0.next()).await
+        {
+            batch?;
+            received += 1;
+        }
+        assert!(received <= 4, "received {} batches", received);
+
+        // reading partition 1 lets the input task go on
+        let (batches0, batches1) = futures::try_join!(
+            crate::physical_plan::common::collect(output_stream0),
+            crate::physical_plan::common::collect(output_stream1),
+        )?;
+        assert_eq!(received + batches0.len(), 50);
+        assert_eq!(batches1.len() + 1, 50);
+
+        let blocked = exec
+            .metrics()
+            .unwrap()
+            .sum(|metric| metric.value().name() == "send_blocked_time")
+            .unwrap();
+        assert!(blocked.as_usize() > 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn reading_only_partition_0() -> Result<()> {
+        let task_ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_repartition_channel_capacity(1),
+        )
+        .task_ctx();
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 100);
+        let exec = MemoryExec::try_new(&[partition], schema.clone(), None)?;
+        let exec =
+            RepartitionExec::try_new(Arc::new(exec), Partitioning::RoundRobinBatch(2))?;
+
+        // the batches of partition 1 do not hold back the input while it is not
+        // executed
+        let output_stream0 = exec.execute(0, task_ctx.clone()).await?;
+        let batches0 = tokio::time::timeout(
+            Duration::from_secs(5),
+            crate::physical_plan::common::collect(output_stream0),
+        )
+        .await
+        .expect("reading partition 0 alone hangs")?;
+        assert_eq!(batches0.len(), 50);
+
+        // nor while it is executed but not read yet
+        let output_stream1 = exec.execute(1, task_ctx).await?;
+        let batches1 = crate::physical_plan::common::collect(output_stream1).await?;
+        assert_eq!(batches1.len(), 50);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dropping_output_streams_stops_input() -> Result<()> {
+        let task_ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_repartition_channel_capacity(1),
+        )
+        .task_ctx();
+        let schema = test_schema();
+        let partition = create_vec_batches(&schema, 100);
+        let input = Arc::new(MemoryExec::try_new(&[partition], schema.clone(), None)?);
+        // the input tasks hold the input until they stop
+        let refs = Arc::downgrade(&input);
+        let exec = RepartitionExec::try_new(input, Partitioning::RoundRobinBatch(2))?;
+
+        let mut output_stream0 = exec.execute(0, task_ctx.clone()).await?;
+        let mut output_stream1 = exec.execute(1, task_ctx).await?;
+        output_stream0.next().await.unwrap()?;
+        output_stream1.next().await.unwrap()?;
+        drop(exec);
+
+        // the input task waits for the partitions to be read
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert!(refs.strong_count() > 0);
+
+        drop(output_stream0);
+        drop(output_stream1);
+        assert_strong_count_converges_to_zero(refs).await;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hash_partitioning_co_partitions_hash_join() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let left_schema =
+            Arc::new(Schema::new(vec![Field::new("a", DataType::UInt32, false)]));
+        let right_schema =
+            Arc::new(Schema::new(vec![Field::new("b", DataType::UInt32, false)]));
+        let left = MemoryExec::try_new(
+            &[
+                vec![uint32_batch(&left_schema, 0..50)],
+                vec![uint32_batch(&left_schema, 50..100)],
+                vec![uint32_batch(&left_schema, 100..150)],
+            ],
+            left_schema.clone(),
+            None,
+        )?;
+        let right = MemoryExec::try_new(
+            &[
+                vec![uint32_batch(&right_schema, (0..150).step_by(3))],
+                vec![uint32_batch(&right_schema, (1..150).step_by(3))],
+            ],
+            right_schema.clone(),
+            None,
+        )?;
+        let left = Arc::new(RepartitionExec::try_new(
+            Arc::new(left),
+            Partitioning::Hash(vec![col("a", &left_schema)?], 4),
+        )?);
+        let right = Arc::new(RepartitionExec::try_new(
+            Arc::new(right),
+            Partitioning::Hash(vec![col("b", &right_schema)?], 4),
+        )?);
+
+        // the join of each pair of partitions finds all the matches
+        let join = HashJoinExec::try_new(
+            left,
+            right,
+            vec![(Column::new("a", 0), Column::new("b", 0))],
+            &JoinType::Inner,
+            PartitionMode::Partitioned,
+            &false,
+        )?;
+        let batches = collect(Arc::new(join), task_ctx).await?;
+        let rows = batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+        assert_eq!(rows, 100);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn hash_partitioning_on_expressions() -> Result<()> {
+        // rows hashed on `a + 1` go where the rows hashed on a column holding
+        // the same values go
+        let schema = test_schema();
+        let plus_one = binary(
+            col("c0", &schema)?,
+            Operator::Plus,
+            lit(ScalarValue::UInt32(Some(1))),
+            &schema,
+        )?;
+        let output_partitions = repartition(
+            &schema,
+            vec![vec![uint32_batch(&schema, 0..100)]],
+            Partitioning::Hash(vec![plus_one], 4),
+        )
+        .await?;
+        let shifted_partitions = repartition(
+            &schema,
+            vec![vec![uint32_batch(&schema, 1..101)]],
+            Partitioning::Hash(vec![col("c0", &schema)?], 4),
+        )
+        .await?;
+
+        let values = |batches: &[RecordBatch]| {
+            let mut values = batches
+                .iter()
+                .flat_map(|batch| {
+                    let array = batch
+                        .column(0)
+                        .as_any()
+                        .downcast_ref::<UInt32Array>()
+                        .unwrap();
+                    array.iter().map(|v| v.unwrap()).collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            values.sort_unstable();
+            values
+        };
+        for (output, shifted) in output_partitions.iter().zip(&shifted_partitions) {
+            let shifted = values(shifted).iter().map(|v| v - 1).collect::<Vec<_>>();
+            assert_eq!(values(output), shifted);
+        }
+        assert_eq!(
+            rows_per_partition(&output_partitions).iter().sum::<usize>(),
+            100
+        );
+
+        Ok(())
+    }
+
+    fn rows_per_partition(partitions: &[Vec<RecordBatch>]) -> Vec<usize> {
+        partitions
+            .iter()
+            .map(|batches| batches.iter().map(|batch| batch.num_rows()).sum())
+            .collect()
+    }
+
+    fn uint32_batch(
+        schema: &Arc<Schema>,
+        values: impl Iterator<Item = u32>,
+    ) -> RecordBatch {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(UInt32Array::from(values.collect::<Vec<_>>()))],
+        )
+        .unwrap()
+    }
+
     fn test_schema() -> Arc<Schema> {
         Arc::new(Schema::new(vec![Field::new("c0", DataType::UInt32, false)]))
     }
@@ -693,6 +1255,7 @@ mod tests {
         input_partitions: Vec<Vec<RecordBatch>>,
         partitioning: Partitioning,
     ) -> Result<Vec<Vec<RecordBatch>>> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         // create physical plan
         let exec = MemoryExec::try_new(&input_partitions, schema.clone(), None)?;
         let exec = RepartitionExec::try_new(Arc::new(exec), partitioning)?;
@@ -701,7 +1264,7 @@ mod tests {
         let mut output_partitions = vec![];
         for i in 0..exec.partitioning.partition_count() {
             // execute this *output* partition and collect all batches
-            let mut stream = exec.execute(i).await?;
+            let mut stream = exec.execute(i, task_ctx.clone()).await?;
             let mut batches = vec![];
             while let Some(result) = stream.next().await {
                 batches.push(result?);
@@ -741,6 +1304,7 @@ mod tests {
 
     #[tokio::test]
     async fn unsupported_partitioning() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         // have to send at least one batch through to provoke error
         let batch = RecordBatch::try_from_iter(vec![(
             "my_awesome_field",
@@ -755,7 +1319,7 @@ mod tests {
         // returned and no results produced
         let partitioning = Partitioning::UnknownPartitioning(1);
         let exec = RepartitionExec::try_new(Arc::new(input), partitioning).unwrap();
-        let output_stream = exec.execute(0).await.unwrap();
+        let output_stream = exec.execute(0, task_ctx).await.unwrap();
 
         // Expect that an error is returned
         let result_string = crate::physical_plan::common::collect(output_stream)
@@ -772,6 +1336,7 @@ mod tests {
 
     #[tokio::test]
     async fn error_for_input_exec() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         // This generates an error on a call to execute. The error
         // should be returned and no results produced.
 
@@ -781,7 +1346,7 @@ mod tests {
 
         // Note: this should pass (the stream can be created) but the
         // error when the input is executed should get passed back
-        let output_stream = exec.execute(0).await.unwrap();
+        let output_stream = exec.execute(0, task_ctx).await.unwrap();
 
         // Expect that an error is returned
         let result_string = crate::physical_plan::common::collect(output_stream)
@@ -797,6 +1362,7 @@ mod tests {
 
     #[tokio::test]
     async fn repartition_with_error_in_stream() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let batch = RecordBatch::try_from_iter(vec![(
             "my_awesome_field",
             Arc::new(StringArray::from(vec!["foo", "bar"])) as ArrayRef,
@@ -814,7 +1380,7 @@ mod tests {
 
         // Note: this should pass (the stream can be created) but the
         // error when the input is executed should get passed back
-        let output_stream = exec.execute(0).await.unwrap();
+        let output_stream = exec.execute(0, task_ctx).await.unwrap();
 
         // Expect that an error is returned
         let result_string = crate::physical_plan::common::collect(output_stream)
@@ -830,6 +1396,7 @@ mod tests {
 
     #[tokio::test]
     async fn repartition_with_delayed_stream() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let batch1 = RecordBatch::try_from_iter(vec![(
             "my_awesome_field",
             Arc::new(StringArray::from(vec!["foo", "bar"])) as ArrayRef,
@@ -864,7 +1431,7 @@ mod tests {
 
         assert_batches_sorted_eq!(&expected, &expected_batches);
 
-        let output_stream = exec.execute(0).await.unwrap();
+        let output_stream = exec.execute(0, task_ctx).await.unwrap();
         let batches = crate::physical_plan::common::collect(output_stream)
             .await
             .unwrap();
@@ -874,6 +1441,7 @@ mod tests {
 
     #[tokio::test]
     async fn robin_repartition_with_dropping_output_stream() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let partitioning = Partitioning::RoundRobinBatch(2);
         // The barrier exec waits to be pinged
         // requires the input to wait at least once)
@@ -882,8 +1450,8 @@ mod tests {
         // partition into two output streams
         let exec = RepartitionExec::try_new(input.clone(), partitioning).unwrap();
 
-        let output_stream0 = exec.execute(0).await.unwrap();
-        let output_stream1 = exec.execute(1).await.unwrap();
+        let output_stream0 = exec.execute(0, task_ctx.clone()).await.unwrap();
+        let output_stream1 = exec.execute(1, task_ctx).await.unwrap();
 
         // now, purposely drop output stream 0
         // *before* any outputs are produced
@@ -916,6 +1484,7 @@ mod tests {
     // wiht different compilers, we will compare the same execution with
     // and without droping the output stream.
     async fn hash_repartition_with_dropping_output_stream() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let partitioning = Partitioning::Hash(
             vec![Arc::new(crate::physical_plan::expressions::Column::new(
                 "my_awesome_field",
@@ -927,7 +1496,7 @@ mod tests {
         // We first collect the results without droping the output stream.
         let input = Arc::new(make_barrier_exec());
         let exec = RepartitionExec::try_new(input.clone(), partitioning.clone()).unwrap();
-        let output_stream1 = exec.execute(1).await.unwrap();
+        let output_stream1 = exec.execute(1, task_ctx.clone()).await.unwrap();
         input.wait().await;
         let batches_without_drop = crate::physical_plan::common::collect(output_stream1)
             .await
@@ -947,8 +1516,8 @@ mod tests {
         // Now do the same but dropping the stream before waiting for the barrier
         let input = Arc::new(make_barrier_exec());
         let exec = RepartitionExec::try_new(input.clone(), partitioning).unwrap();
-        let output_stream0 = exec.execute(0).await.unwrap();
-        let output_stream1 = exec.execute(1).await.unwrap();
+        let output_stream0 = exec.execute(0, task_ctx.clone()).await.unwrap();
+        let output_stream1 = exec.execute(1, task_ctx).await.unwrap();
         // now, purposely drop output stream 0
         // *before* any outputs are produced
         std::mem::drop(output_stream0);
@@ -1013,6 +1582,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -1023,7 +1593,7 @@ mod tests {
             Partitioning::UnknownPartitioning(1),
         )?);
 
-        let fut = collect(repartition_exec);
+        let fut = collect(repartition_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
@@ -1035,6 +1605,7 @@ mod tests {
 
     #[tokio::test]
     async fn hash_repartition_avoid_empty_batch() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let batch = RecordBatch::try_from_iter(vec![(
             "a",
             Arc::new(StringArray::from(vec!["foo"])) as ArrayRef,
@@ -1049,11 +1620,11 @@ mod tests {
         let schema = batch.schema();
         let input = MockExec::new(vec![Ok(batch)], schema);
         let exec = RepartitionExec::try_new(Arc::new(input), partitioning).unwrap();
-        let output_stream0 = exec.execute(0).await.unwrap();
+        let output_stream0 = exec.execute(0, task_ctx.clone()).await.unwrap();
         let batch0 = crate::physical_plan::common::collect(output_stream0)
             .await
             .unwrap();
-        let output_stream1 = exec.execute(1).await.unwrap();
+        let output_stream1 = exec.execute(1, task_ctx).await.unwrap();
         let batch1 = crate::physical_plan::common::collect(output_stream1)
             .await
             .unwrap();
@@ -0,0 +1,198 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Rewriting of the physical expressions of the operators of a plan
+
+use std::sync::Arc;
+
+use super::expressions::PhysicalSortExpr;
+use super::filter::FilterExec;
+use super::hash_aggregate::HashAggregateExec;
+use super::projection::ProjectionExec;
+use super::sort::SortExec;
+use super::{ExecutionPlan, PhysicalExpr};
+use crate::error::Result;
+
+/// Returns a copy of `plan` in which `rewrite` replaced the expressions of
+/// the projections, filters and sorts and the grouping expressions of the
+/// aggregates, in the whole tree.
+///
+/// Aggregate and window functions are kept as they are, since they can't be
+/// rebuilt with other arguments. Operators without rewritable expressions
+/// are rebuilt with [`ExecutionPlan::with_new_children`] over their
+/// rewritten children.
+pub fn try_rewrite_expressions(
+    plan: &Arc<dyn ExecutionPlan>,
+    rewrite: &dyn Fn(&Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    let children = plan
+        .children()
+        .iter()
+        .map(|child| try_rewrite_expressions(child, rewrite))
+        .collect::<Result<Vec<_>>>()?;
+    let input = match children.first() {
+        Some(input) => input.clone(),
+        None => return Ok(plan.clone()),
+    };
+
+    let plan_any = plan.as_any();
+    if let Some(projection) = plan_any.downcast_ref::<ProjectionExec>() {
+        let expr = projection
+            .expr()
+            .iter()
+            .map(|(expr, name)| Ok((rewrite(expr)?, name.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(ProjectionExec::try_new(expr, input)?))
+    } else if let Some(filter) = plan_any.downcast_ref::<FilterExec>() {
+        let predicate = rewrite(filter.predicate())?;
+        Ok(Arc::new(FilterExec::try_new(predicate, input)?))
+    } else if let Some(sort) = plan_any.downcast_ref::<SortExec>() {
+        let expr = sort
+            .expr()
+            .iter()
+            .map(|sort_expr| {
+                Ok(PhysicalSortExpr {
+                    expr: rewrite(&sort_expr.expr)?,
+                    options: sort_expr.options,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(SortExec::new_with_partitioning(
+            expr,
+            input,
+            sort.preserve_partitioning(),
+        )))
+    } else if let Some(aggregate) = plan_any.downcast_ref::<HashAggregateExec>() {
+        let group_expr = aggregate
+            .group_expr()
+            .iter()
+            .map(|(expr, name)| Ok((rewrite(expr)?, name.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(HashAggregateExec::try_new(
+            aggregate.mode().clone(),
+            group_expr,
+            aggregate.aggr_expr().to_vec(),
+            input,
+            aggregate.input_schema(),
+        )?))
+    } else {
+        plan.with_new_children(children)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Int32Array;
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use arrow::util::pretty::pretty_format_batches;
+
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::Operator;
+    use crate::physical_plan::expressions::{
+        binary, col, lit, BinaryExpr, Column, Count,
+    };
+    use crate::physical_plan::hash_aggregate::AggregateMode;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, displayable, AggregateExpr};
+    use crate::scalar::ScalarValue;
+
+    /// Aggregate of `b` by `a` over the rows where `a > 2`, sorted by `a`
+    fn plan() -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Int32, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![3, 1, 2, 3])),
+                Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            ],
+        )?;
+        let memory = Arc::new(MemoryExec::try_new(&[vec![batch]], schema.clone(), None)?);
+
+        let predicate = binary(
+            col("a", &schema)?,
+            Operator::Gt,
+            lit(ScalarValue::Int32(Some(2))),
+            &schema,
+        )?;
+        let filter = Arc::new(FilterExec::try_new(predicate, memory)?);
+        let sort = Arc::new(SortExec::try_new(
+            vec![PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions::default(),
+            }],
+            filter,
+        )?);
+        let aggr_expr: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Count::new(
+            col("b", &schema)?,
+            "COUNT(b)",
+            DataType::UInt64,
+        ))];
+        Ok(Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            vec![(col("a", &schema)?, "a".to_string())],
+            aggr_expr,
+            sort,
+            schema,
+        )?))
+    }
+
+    /// Rename the column `a` to `renamed_a`, wherever it is used
+    fn rename(expr: &Arc<dyn PhysicalExpr>) -> Result<Arc<dyn PhysicalExpr>> {
+        let expr_any = expr.as_any();
+        if let Some(column) = expr_any.downcast_ref::<Column>() {
+            if column.name() == "a" {
+                return Ok(Arc::new(Column::new("renamed_a", column.index())));
+            }
+        } else if let Some(binary) = expr_any.downcast_ref::<BinaryExpr>() {
+            return Ok(Arc::new(BinaryExpr::new(
+                rename(binary.left())?,
+                *binary.op(),
+                rename(binary.right())?,
+            )));
+        }
+        Ok(expr.clone())
+    }
+
+    #[tokio::test]
+    async fn rename_column() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let plan = plan()?;
+        let rewritten = try_rewrite_expressions(&plan, &rename)?;
+
+        let actual = format!("{}", displayable(rewritten.as_ref()).indent());
+        let expected =
+            "HashAggregateExec: mode=Partial, gby=[renamed_a@0 as a], aggr=[COUNT(b)]\
+             \n  SortExec: [renamed_a@0 ASC]\
+             \n    FilterExec: renamed_a@0 > 2\
+             \n      MemoryExec: partitions=1, partition_sizes=[1]";
+        assert_eq!(expected, actual.trim());
+        assert_eq!(plan.schema(), rewritten.schema());
+
+        let expected =
+            pretty_format_batches(&collect(plan, task_ctx.clone()).await?)?.to_string();
+        let actual =
+            pretty_format_batches(&collect(rewritten, task_ctx).await?)?.to_string();
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+}
@@ -0,0 +1,543 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Row-based states of the groups of a hash aggregation whose group keys and
+//! aggregates are all of fixed width, see [`RowGroups`]
+
+use std::sync::Arc;
+
+use ahash::RandomState;
+use arrow::array::{as_primitive_array, Array, ArrayRef, PrimitiveArray};
+use arrow::compute::cast;
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType, Float64Type, Int64Type, Schema, SchemaRef, UInt64Type,
+};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use hashbrown::raw::RawTable;
+
+use crate::error::Result;
+use crate::physical_plan::expressions::{ArithmeticOverflow, Count, Max, Min, Sum};
+use crate::physical_plan::hash_utils::create_hashes;
+use crate::physical_plan::{AggregateExpr, PhysicalExpr};
+
+/// Number of bytes of a value in a row: a validity byte, then the value in 8
+/// little-endian bytes, all zeros if the value is null
+const SLOT_WIDTH: usize = 9;
+
+/// The type of the values of a slot of a row, the widest of the kind of
+/// the type of the key or of the state it stores
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowType {
+    Int64,
+    UInt64,
+    Float64,
+}
+
+impl RowType {
+    /// The type of the slots storing the values of `data_type`, if they fit
+    fn of(data_type: &DataType) -> Option<Self> {
+        match data_type {
+            DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => {
+                Some(RowType::Int64)
+            }
+            DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => {
+                Some(RowType::UInt64)
+            }
+            DataType::Float32 | DataType::Float64 => Some(RowType::Float64),
+            _ => None,
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            RowType::Int64 => DataType::Int64,
+            RowType::UInt64 => DataType::UInt64,
+            RowType::Float64 => DataType::Float64,
+        }
+    }
+}
+
+/// The native values stored in the slots of the rows
+trait RowNative: Copy + PartialOrd {
+    fn read(slot: &[u8]) -> Self;
+
+    fn write(self, slot: &mut [u8]);
+
+    /// The sum of `self` and `other`, wrapping around the bounds of the
+    /// integers
+    fn add(self, other: Self) -> Self;
+}
+
+macro_rules! impl_row_native {
+    ($NATIVE:ty, $ADD:path) => {
+        impl RowNative for $NATIVE {
+            fn read(slot: &[u8]) -> Self {
+                let mut bytes = [0; 8];
+                bytes.copy_from_slice(&slot[..8]);
+                Self::from_le_bytes(bytes)
+            }
+
+            fn write(self, slot: &mut [u8]) {
+                slot[..8].copy_from_slice(&self.to_le_bytes());
+            }
+
+            fn add(self, other: Self) -> Self {
+                $ADD(self, other)
+            }
+        }
+    };
+}
+
+impl_row_native!(i64, i64::wrapping_add);
+impl_row_native!(u64, u64::wrapping_add);
+impl_row_native!(f64, std::ops::Add::add);
+
+fn min<T: RowNative>(state: T, value: T) -> T {
+    if value < state {
+        value
+    } else {
+        state
+    }
+}
+
+fn max<T: RowNative>(state: T, value: T) -> T {
+    if value > state {
+        value
+    } else {
+        state
+    }
+}
+
+/// The aggregates whose states are stored in the rows
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RowAggregateKind {
+    Sum,
+    Count,
+    Min,
+    Max,
+}
+
+/// An aggregate with a state of a single fixed-width value, stored in a
+/// slot of the rows
+#[derive(Debug, Clone, Copy)]
+struct RowAggregate {
+    kind: RowAggregateKind,
+    /// The type of the slot of the state
+    row_type: RowType,
+}
+
+impl RowAggregate {
+    /// The aggregate of `expr`, if its state can be stored in a row
+    fn try_new(expr: &Arc<dyn AggregateExpr>) -> Result<Option<Self>> {
+        let any = expr.as_any();
+        let kind = if let Some(sum) = any.downcast_ref::<Sum>() {
+            // the sums failing or saturating on the overflows are left to
+            // their accumulators
+            if sum.overflow() != ArithmeticOverflow::Wrap {
+                return Ok(None);
+            }
+            RowAggregateKind::Sum
+        } else if any.is::<Count>() {
+            RowAggregateKind::Count
+        } else if any.is::<Min>() {
+            RowAggregateKind::Min
+        } else if any.is::<Max>() {
+            RowAggregateKind::Max
+        } else {
+            return Ok(None);
+        };
+
+        let state_fields = expr.state_fields()?;
+        let state_type = match state_fields.as_slice() {
+            [field] => field.data_type(),
+            _ => return Ok(None),
+        };
+        let row_type = match RowType::of(state_type) {
+            Some(row_type) => row_type,
+            None => return Ok(None),
+        };
+        // the sums and the counts are computed in the type of their state,
+        // which must not be widened
+        let widened = *state_type != row_type.data_type();
+        if widened && matches!(kind, RowAggregateKind::Sum | RowAggregateKind::Count) {
+            return Ok(None);
+        }
+        Ok(Some(Self { kind, row_type }))
+    }
+
+    /// The initial state of the aggregate: 0 for the counts, null otherwise
+    fn initial_slot(&self) -> [u8; SLOT_WIDTH] {
+        let mut slot = [0; SLOT_WIDTH];
+        if self.kind == RowAggregateKind::Count {
+            slot[0] = 1;
+        }
+        slot
+    }
+
+    /// Update the states at `offset` in `rows` of the groups `groups` of the
+    /// rows of `values`, merging states rather than aggregating values if
+    /// `merge`
+    fn update(
+        &self,
+        rows: &mut [u8],
+        width: usize,
+        offset: usize,
+        groups: &[usize],
+        values: &ArrayRef,
+        merge: bool,
+    ) -> Result<()> {
+        if self.kind == RowAggregateKind::Count && !merge {
+            for (row, group) in groups.iter().enumerate() {
+                if values.is_valid(row) {
+                    let slot = &mut rows[group * width + offset + 1..];
+                    (u64::read(slot) + 1).write(slot);
+                }
+            }
+            return Ok(());
+        }
+
+        let values = cast(values, &self.row_type.data_type())?;
+        match self.row_type {
+            RowType::Int64 => {
+                self.fold::<Int64Type>(rows, width, offset, groups, &values)
+            }
+            RowType::UInt64 => {
+                self.fold::<UInt64Type>(rows, width, offset, groups, &values)
+            }
+            RowType::Float64 => {
+                self.fold::<Float64Type>(rows, width, offset, groups, &values)
+            }
+        }
+        Ok(())
+    }
+
+    fn fold<T>(
+        &self,
+        rows: &mut [u8],
+        width: usize,
+        offset: usize,
+        groups: &[usize],
+        values: &ArrayRef,
+    ) where
+        T: ArrowPrimitiveType,
+        T::Native: RowNative,
+    {
+        let values = as_primitive_array::<T>(values);
+        // the counts being merged are summed
+        let op: fn(T::Native, T::Native) -> T::Native = match self.kind {
+            RowAggregateKind::Sum | RowAggregateKind::Count => RowNative::add,
+            RowAggregateKind::Min => min,
+            RowAggregateKind::Max => max,
+        };
+        for (row, group) in groups.iter().enumerate() {
+            if values.is_null(row) {
+                continue;
+            }
+            let value = values.value(row);
+            let slot = &mut rows[group * width + offset..];
+            let state = if slot[0] == 0 {
+                value
+            } else {
+                op(T::Native::read(&slot[1..]), value)
+            };
+            slot[0] = 1;
+            state.write(&mut slot[1..]);
+        }
+    }
+}
+
+/// The layout of the rows of the groups of an aggregation: a slot per group
+/// key, then a slot per aggregate
+#[derive(Debug, Clone)]
+pub(crate) struct RowLayout {
+    key_types: Vec<RowType>,
+    aggregates: Vec<RowAggregate>,
+}
+
+impl RowLayout {
+    /// The layout of the rows of the groups of `group_expr` and the states
+    /// of `aggr_expr` on the batches of `input_schema`, or `None` if a key
+    /// or an aggregate is not of fixed width or is not supported
+    pub(crate) fn try_new(
+        group_expr: &[Arc<dyn PhysicalExpr>],
+        aggr_expr: &[Arc<dyn AggregateExpr>],
+        input_schema: &Schema,
+    ) -> Result<Option<Self>> {
+        let mut key_types = Vec::with_capacity(group_expr.len());
+        for expr in group_expr {
+            match RowType::of(&expr.data_type(input_schema)?) {
+                Some(row_type) => key_types.push(row_type),
+                None => return Ok(None),
+            }
+        }
+        let mut aggregates = Vec::with_capacity(aggr_expr.len());
+        for expr in aggr_expr {
+            match RowAggregate::try_new(expr)? {
+                Some(aggregate) => aggregates.push(aggregate),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(Self {
+            key_types,
+            aggregates,
+        }))
+    }
+
+    fn key_width(&self) -> usize {
+        self.key_types.len() * SLOT_WIDTH
+    }
+
+    fn width(&self) -> usize {
+        (self.key_types.len() + self.aggregates.len()) * SLOT_WIDTH
+    }
+
+    /// Number of bytes of the state of a group
+    pub(crate) fn group_size(&self) -> usize {
+        self.width() + std::mem::size_of::<(u64, usize)>()
+    }
+}
+
+/// The states of the groups of an aggregation of fixed-width keys and
+/// aggregates, stored contiguously in rows of bytes of a [`RowLayout`]
+/// rather than in an accumulator per aggregate and group.
+///
+/// The keys are compared by their bytes: unlike the accumulators, the rows
+/// put the NaN keys of the same bits in a single group.
+#[derive(Debug)]
+pub(crate) struct RowGroups {
+    layout: RowLayout,
+    /// Maps the hashes of the keys to the index of their group in `rows`
+    map: RawTable<(u64, usize)>,
+    /// The rows of the groups, one after the other
+    rows: Vec<u8>,
+    /// The states of a new group after its keys
+    initial_states: Vec<u8>,
+    random_state: RandomState,
+    /// Scratch space for the keys of the rows of a batch
+    keys: Vec<u8>,
+    /// Scratch space for the hashes of the keys of the rows of a batch
+    hashes: Vec<u64>,
+    /// Scratch space for the groups of the rows of a batch
+    groups: Vec<usize>,
+}
+
+impl RowGroups {
+    /// Create the empty groups of rows of `layout`
+    pub(crate) fn new(layout: RowLayout) -> Self {
+        let initial_states = layout
+            .aggregates
+            .iter()
+            .flat_map(|aggregate| aggregate.initial_slot())
+            .collect();
+        Self {
+            layout,
+            map: RawTable::new(),
+            rows: vec![],
+            initial_states,
+            random_state: RandomState::new(),
+            keys: vec![],
+            hashes: vec![],
+            groups: vec![],
+        }
+    }
+
+    /// Number of groups
+    pub(crate) fn len(&self) -> usize {
+        self.rows.len() / self.layout.width()
+    }
+
+    /// Aggregate the values `aggr_values` of each aggregate into the groups
+    /// of the keys `group_values`, merging states rather than aggregating
+    /// values if `merge`
+    pub(crate) fn aggregate_batch(
+        &mut self,
+        group_values: &[ArrayRef],
+        aggr_values: &[Vec<ArrayRef>],
+        merge: bool,
+    ) -> Result<()> {
+        let num_rows = group_values.first().map(|v| v.len()).unwrap_or_default();
+        self.encode_keys(group_values, num_rows)?;
+        self.hashes.clear();
+        self.hashes.resize(num_rows, 0);
+        create_hashes(group_values, &self.random_state, &mut self.hashes)?;
+
+        let Self {
+            layout,
+            map,
+            rows,
+            initial_states,
+            keys,
+            hashes,
+            groups,
+            ..
+        } = self;
+        let width = layout.width();
+        let key_width = layout.key_width();
+        groups.clear();
+        for (key, hash) in keys.chunks_exact(key_width).zip(hashes.iter()) {
+            let group = map
+                .get(*hash, |(_, group)| {
+                    let start = group * width;
+                    &rows[start..start + key_width] == key
+                })
+                .map(|(_, group)| *group);
+            let group = match group {
+                Some(group) => group,
+                None => {
+                    let group = rows.len() / width;
+                    rows.extend_from_slice(key);
+                    rows.extend_from_slice(initial_states);
+                    map.insert(*hash, (*hash, group), |(hash, _)| *hash);
+                    group
+                }
+            };
+            groups.push(group);
+        }
+
+        for (index, (aggregate, values)) in
+            layout.aggregates.iter().zip(aggr_values).enumerate()
+        {
+            let offset = key_width + index * SLOT_WIDTH;
+            aggregate.update(rows, width, offset, groups, &values[0], merge)?;
+        }
+        Ok(())
+    }
+
+    /// Write the keys of the rows of a batch to `self.keys`
+    fn encode_keys(&mut self, group_values: &[ArrayRef], num_rows: usize) -> Result<()> {
+        let key_width = self.layout.key_width();
+        self.keys.clear();
+        self.keys.resize(num_rows * key_width, 0);
+        for (index, (values, row_type)) in
+            group_values.iter().zip(&self.layout.key_types).enumerate()
+        {
+            let values = cast(values, &row_type.data_type())?;
+            let offset = index * SLOT_WIDTH;
+            let keys = &mut self.keys;
+            match row_type {
+                RowType::Int64 => encode::<Int64Type>(keys, key_width, offset, &values),
+                RowType::UInt64 => encode::<UInt64Type>(keys, key_width, offset, &values),
+                RowType::Float64 => {
+                    encode::<Float64Type>(keys, key_width, offset, &values)
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The batch of the keys and the states of the groups, cast to the types
+    /// of `schema`
+    pub(crate) fn to_batch(&self, schema: SchemaRef) -> ArrowResult<RecordBatch> {
+        let width = self.layout.width();
+        let row_types = self
+            .layout
+            .key_types
+            .iter()
+            .chain(self.layout.aggregates.iter().map(|a| &a.row_type));
+        let columns = row_types
+            .zip(schema.fields())
+            .enumerate()
+            .map(|(index, (row_type, field))| {
+                let offset = index * SLOT_WIDTH;
+                let column = match row_type {
+                    RowType::Int64 => decode::<Int64Type>(&self.rows, width, offset),
+                    RowType::UInt64 => decode::<UInt64Type>(&self.rows, width, offset),
+                    RowType::Float64 => decode::<Float64Type>(&self.rows, width, offset),
+                };
+                cast(&column, field.data_type())
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        RecordBatch::try_new(schema, columns)
+    }
+}
+
+/// Write the values of `values` to the slots at `offset` of rows of `width`
+/// bytes
+fn encode<T>(rows: &mut [u8], width: usize, offset: usize, values: &ArrayRef)
+where
+    T: ArrowPrimitiveType,
+    T::Native: RowNative,
+{
+    let values = as_primitive_array::<T>(values);
+    for (row, value) in values.iter().enumerate() {
+        if let Some(value) = value {
+            let slot = &mut rows[row * width + offset..];
+            slot[0] = 1;
+            value.write(&mut slot[1..]);
+        }
+    }
+}
+
+/// The array of the values of the slots at `offset` of rows of `width` bytes
+fn decode<T>(rows: &[u8], width: usize, offset: usize) -> ArrayRef
+where
+    T: ArrowPrimitiveType,
+    T::Native: RowNative,
+{
+    let array = rows
+        .chunks_exact(width)
+        .map(|row| {
+            let slot = &row[offset..];
+            (slot[0] != 0).then(|| T::Native::read(&slot[1..]))
+        })
+        .collect::<PrimitiveArray<T>>();
+    Arc::new(array)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::expressions::{col, Avg};
+    use arrow::datatypes::Field;
+
+    #[test]
+    fn layout_of_fixed_width_aggregates() -> Result<()> {
+        let schema = Schema::new(vec![
+            Field::new("a", DataType::Int32, true),
+            Field::new("b", DataType::Float32, true),
+            Field::new("c", DataType::Utf8, true),
+        ]);
+        let a = col("a", &schema)?;
+        let b = col("b", &schema)?;
+        let c = col("c", &schema)?;
+        let sum: Arc<dyn AggregateExpr> =
+            Arc::new(Sum::new(b.clone(), "SUM(b)", DataType::Float64));
+        let count: Arc<dyn AggregateExpr> =
+            Arc::new(Count::new(c.clone(), "COUNT(c)", DataType::UInt64));
+        let max: Arc<dyn AggregateExpr> =
+            Arc::new(Max::new(a.clone(), "MAX(a)", DataType::Int32));
+        let avg: Arc<dyn AggregateExpr> =
+            Arc::new(Avg::new(a.clone(), "AVG(a)", DataType::Float64));
+        let saturating: Arc<dyn AggregateExpr> = Arc::new(Sum::new_with_overflow(
+            a.clone(),
+            "SUM(a)",
+            DataType::Int64,
+            ArithmeticOverflow::Saturate,
+        ));
+
+        let layout =
+            RowLayout::try_new(&[a.clone(), b], &[sum, count, max], &schema)?.unwrap();
+        assert_eq!(layout.key_types, vec![RowType::Int64, RowType::Float64]);
+        assert_eq!(layout.width(), 5 * SLOT_WIDTH);
+
+        assert!(RowLayout::try_new(&[c], &[], &schema)?.is_none());
+        assert!(RowLayout::try_new(&[a.clone()], &[avg], &schema)?.is_none());
+        assert!(RowLayout::try_new(&[a], &[saturating], &schema)?.is_none());
+        Ok(())
+    }
+}
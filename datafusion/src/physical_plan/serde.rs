@@ -0,0 +1,657 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Serialization of physical plans.
+//!
+//! Besides JSON, the plans can be encoded with MessagePack, a compact
+//! self-describing format that, unlike bincode, supports the tagged trait
+//! objects of `typetag`. The encoded plan can also be compressed with zstd.
+//! The bytes start with a header made of [`MAGIC`], the [`FORMAT_VERSION`] and
+//! the [`Codec`] of the payload.
+//!
+//! The user-defined functions of a plan are serialized by name and signature.
+//! Their implementation is resolved from the [`FunctionRegistry`] given to
+//! [`from_json_with_registry`] or [`from_bytes_with_registry`]; deserializing
+//! a plan that calls a user-defined function without a registry fails.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::FunctionRegistry;
+use crate::physical_plan::functions::Signature;
+use crate::physical_plan::udaf::AggregateUDF;
+use crate::physical_plan::udf::ScalarUDF;
+use crate::physical_plan::udwf::WindowUDF;
+use crate::physical_plan::ExecutionPlan;
+
+/// Leading bytes of a serialized plan
+pub const MAGIC: &[u8; 4] = b"DFPL";
+
+/// Version of the layout of the serialized plans
+pub const FORMAT_VERSION: u8 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Encoding of the payload of a serialized plan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Codec {
+    /// Plain MessagePack
+    MessagePack,
+    /// MessagePack compressed with zstd
+    MessagePackZstd,
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Codec::MessagePack => 0,
+            Codec::MessagePackZstd => 1,
+        }
+    }
+
+    fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Codec::MessagePack),
+            1 => Ok(Codec::MessagePackZstd),
+            _ => Err(DataFusionError::Execution(format!(
+                "Unknown physical plan codec {}",
+                id
+            ))),
+        }
+    }
+}
+
+/// Serialize `plan` to MessagePack
+pub fn to_bytes(plan: &Arc<dyn ExecutionPlan>) -> Result<Vec<u8>> {
+    let mut bytes = header(Codec::MessagePack);
+    rmp_serde::encode::write_named(&mut bytes, plan).map_err(|e| {
+        DataFusionError::Execution(format!("Could not serialize plan: {}", e))
+    })?;
+    Ok(bytes)
+}
+
+/// Serialize `plan` to MessagePack compressed with zstd at the given `level`
+/// (`0` selects the default level of zstd)
+pub fn to_compressed_bytes(plan: &Arc<dyn ExecutionPlan>, level: i32) -> Result<Vec<u8>> {
+    let payload = rmp_serde::to_vec_named(plan).map_err(|e| {
+        DataFusionError::Execution(format!("Could not serialize plan: {}", e))
+    })?;
+    let mut bytes = header(Codec::MessagePackZstd);
+    bytes.extend(zstd::encode_all(payload.as_slice(), level)?);
+    Ok(bytes)
+}
+
+/// Deserialize a plan serialized with [`to_bytes`] or [`to_compressed_bytes`]
+pub fn from_bytes(bytes: &[u8]) -> Result<Arc<dyn ExecutionPlan>> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(DataFusionError::Execution(
+            "Not a serialized physical plan".to_owned(),
+        ));
+    }
+    let version = bytes[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(DataFusionError::Execution(format!(
+            "Unsupported physical plan format version {}, expected {}",
+            version, FORMAT_VERSION
+        )));
+    }
+    let payload = &bytes[HEADER_LEN..];
+    let plan = match Codec::from_id(bytes[MAGIC.len() + 1])? {
+        Codec::MessagePack => rmp_serde::from_read_ref(payload),
+        Codec::MessagePackZstd => rmp_serde::from_read_ref(&zstd::decode_all(payload)?),
+    };
+    plan.map_err(|e| {
+        DataFusionError::Execution(format!("Could not deserialize plan: {}", e))
+    })
+}
+
+/// Serialize `plan` to JSON
+pub fn to_json(plan: &Arc<dyn ExecutionPlan>) -> Result<String> {
+    serde_json::to_string(plan).map_err(|e| {
+        DataFusionError::Execution(format!("Could not serialize plan: {}", e))
+    })
+}
+
+/// Deserialize a plan serialized with [`to_json`], resolving its user-defined
+/// functions from `registry`
+pub fn from_json_with_registry(
+    json: &str,
+    registry: &Arc<dyn FunctionRegistry>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    with_function_registry(registry, || serde_json::from_str(json)).map_err(|e| {
+        DataFusionError::Execution(format!("Could not deserialize plan: {}", e))
+    })
+}
+
+/// Deserialize a plan serialized with [`to_bytes`] or [`to_compressed_bytes`],
+/// resolving its user-defined functions from `registry`
+pub fn from_bytes_with_registry(
+    bytes: &[u8],
+    registry: &Arc<dyn FunctionRegistry>,
+) -> Result<Arc<dyn ExecutionPlan>> {
+    with_function_registry(registry, || from_bytes(bytes))
+}
+
+thread_local! {
+    /// The registry resolving the user-defined functions of the plans
+    /// deserialized by this thread
+    static FUNCTION_REGISTRY: RefCell<Option<Arc<dyn FunctionRegistry>>> =
+        RefCell::new(None);
+}
+
+/// Run `f`, resolving the user-defined functions of the plans it deserializes
+/// from `registry`
+pub fn with_function_registry<T>(
+    registry: &Arc<dyn FunctionRegistry>,
+    f: impl FnOnce() -> T,
+) -> T {
+    /// Restores the previous registry, even if `f` panics
+    struct Restore(Option<Arc<dyn FunctionRegistry>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            FUNCTION_REGISTRY.with(|current| *current.borrow_mut() = previous);
+        }
+    }
+
+    let previous =
+        FUNCTION_REGISTRY.with(|current| current.replace(Some(Arc::clone(registry))));
+    let _restore = Restore(previous);
+    f()
+}
+
+/// Resolve the implementation of a deserialized scalar UDF
+pub(crate) fn resolve_udf(name: &str, signature: &Signature) -> Result<Arc<ScalarUDF>> {
+    let udf = FUNCTION_REGISTRY.with(|registry| match registry.borrow().as_ref() {
+        Some(registry) => registry.udf(name),
+        None => Err(missing_registry(name)),
+    })?;
+    check_signature(name, &udf.signature, signature)?;
+    Ok(udf)
+}
+
+/// Resolve the implementation of a deserialized UDAF
+pub(crate) fn resolve_udaf(
+    name: &str,
+    signature: &Signature,
+) -> Result<Arc<AggregateUDF>> {
+    let udaf = FUNCTION_REGISTRY.with(|registry| match registry.borrow().as_ref() {
+        Some(registry) => registry.udaf(name),
+        None => Err(missing_registry(name)),
+    })?;
+    check_signature(name, &udaf.signature, signature)?;
+    Ok(udaf)
+}
+
+/// Resolve the implementation of a deserialized UDWF
+pub(crate) fn resolve_udwf(name: &str, signature: &Signature) -> Result<Arc<WindowUDF>> {
+    let udwf = FUNCTION_REGISTRY.with(|registry| match registry.borrow().as_ref() {
+        Some(registry) => registry.udwf(name),
+        None => Err(missing_registry(name)),
+    })?;
+    check_signature(name, &udwf.signature, signature)?;
+    Ok(udwf)
+}
+
+fn missing_registry(name: &str) -> DataFusionError {
+    DataFusionError::Plan(format!(
+        "Cannot resolve the user-defined function \"{}\" without a function registry",
+        name
+    ))
+}
+
+fn check_signature(
+    name: &str,
+    registered: &Signature,
+    expected: &Signature,
+) -> Result<()> {
+    if registered != expected {
+        return Err(DataFusionError::Plan(format!(
+            "The user-defined function \"{}\" of the registry has the signature {:?}, \
+             expected {:?}",
+            name, registered, expected
+        )));
+    }
+    Ok(())
+}
+
+fn header(codec: Codec) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(FORMAT_VERSION);
+    bytes.push(codec.id());
+    bytes
+}
+
+/// Serde for the record batches embedded in a plan, for use with
+/// `#[serde(with = "crate::physical_plan::serde::record_batches")]`.
+///
+/// The batches are written as a single Arrow IPC stream, which keeps their
+/// schema (including the field metadata and the dictionary types) intact.
+pub mod record_batches {
+    use std::io::Cursor;
+
+    use arrow::ipc::reader::StreamReader;
+    use arrow::ipc::writer::StreamWriter;
+    use arrow::record_batch::RecordBatch;
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// Serialize `batches` as the bytes of an IPC stream
+    pub fn serialize<S: Serializer>(
+        batches: &[RecordBatch],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut bytes = vec![];
+        if let Some(first) = batches.first() {
+            let mut writer = StreamWriter::try_new(&mut bytes, &first.schema())
+                .map_err(S::Error::custom)?;
+            for batch in batches {
+                writer.write(batch).map_err(S::Error::custom)?;
+            }
+            writer.finish().map_err(S::Error::custom)?;
+        }
+        bytes.serialize(serializer)
+    }
+
+    /// Deserialize batches serialized with [`serialize`]
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<RecordBatch>, D::Error> {
+        let bytes = Vec::<u8>::deserialize(deserializer)?;
+        if bytes.is_empty() {
+            return Ok(vec![]);
+        }
+        StreamReader::try_new(Cursor::new(bytes))
+            .map_err(D::Error::custom)?
+            .collect::<arrow::error::Result<Vec<_>>>()
+            .map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::datasource::MemTable;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::logical_plan::{JoinType, Operator};
+    use crate::physical_plan::coalesce_batches::CoalesceBatchesExec;
+    use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+    use crate::physical_plan::cross_join::CrossJoinExec;
+    use crate::physical_plan::empty::EmptyExec;
+    use crate::physical_plan::expressions::{
+        binary, col, lit, Column, PhysicalSortExpr, Sum,
+    };
+    use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+    use crate::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+    use crate::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+    use crate::physical_plan::projection::ProjectionExec;
+    use crate::physical_plan::repartition::RepartitionExec;
+    use crate::physical_plan::sort::SortExec;
+    use crate::physical_plan::sort_preserving_merge::SortPreservingMergeExec;
+    use crate::physical_plan::union::UnionExec;
+    use crate::physical_plan::values::ValuesExec;
+    use crate::physical_plan::{
+        AggregateExpr, ColumnStatistics, Partitioning, Statistics,
+    };
+    use crate::scalar::ScalarValue;
+    use crate::test;
+    use crate::test_util::aggr_test_schema;
+    use arrow::array::{Int32Array, StringArray, TimestampNanosecondArray};
+    use arrow::compute::SortOptions;
+    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use arrow::record_batch::RecordBatch;
+    use std::collections::BTreeMap;
+
+    fn context() -> Result<ExecutionContext> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(4),
+        );
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::Int32, false),
+            Field::new("b", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["x", "y", "z"])),
+            ],
+        )?;
+        for name in ["t1", "t2"] {
+            let table = MemTable::try_new(schema.clone(), vec![vec![batch.clone()]])?;
+            ctx.register_table(name, Arc::new(table))?;
+        }
+        Ok(ctx)
+    }
+
+    async fn physical_plan(
+        ctx: &ExecutionContext,
+        sql: &str,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.optimize(&plan)?;
+        ctx.create_physical_plan(&plan).await
+    }
+
+    /// The batches of the in-memory scans are not serialized, so the plans
+    /// are compared through their serialized form
+    fn assert_roundtrip(plan: &Arc<dyn ExecutionPlan>) -> Result<()> {
+        let expected = to_bytes(plan)?;
+        for bytes in [to_bytes(plan)?, to_compressed_bytes(plan, 0)?] {
+            let roundtrip = from_bytes(&bytes)?;
+            assert_eq!(expected, to_bytes(&roundtrip)?);
+            assert_eq!(plan.schema(), roundtrip.schema());
+            assert_eq!(
+                plan.output_partitioning().partition_count(),
+                roundtrip.output_partitioning().partition_count()
+            );
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn roundtrip_operators() -> Result<()> {
+        let ctx = context()?;
+        let queries = [
+            // projection, filter, memory
+            "SELECT a + 1, b FROM t1 WHERE a > 1",
+            // hash aggregate, repartition, coalesce batches
+            "SELECT b, SUM(a), COUNT(DISTINCT a) FROM t1 GROUP BY b",
+            // hash join
+            "SELECT t1.a, t2.b FROM t1 JOIN t2 ON t1.a = t2.a",
+            // cross join
+            "SELECT t1.a, t2.a FROM t1, t2",
+            // sort, sort preserving merge, limits
+            "SELECT a FROM t1 ORDER BY a DESC LIMIT 2",
+            // union, coalesce partitions
+            "SELECT a FROM t1 UNION ALL SELECT a FROM t2",
+            // window
+            "SELECT a, ROW_NUMBER() OVER (ORDER BY a), MAX(a) OVER () FROM t1",
+            // empty
+            "SELECT 1",
+            // values
+            "SELECT * FROM (VALUES (1, 'one'), (2, 'two')) AS t (num, letter)",
+            // explain and analyze
+            "EXPLAIN SELECT a FROM t1",
+            "EXPLAIN ANALYZE SELECT a FROM t1",
+        ];
+        for sql in queries {
+            assert_roundtrip(&physical_plan(&ctx, sql).await?)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_file_scan() -> Result<()> {
+        let schema = aggr_test_schema();
+        let (_, files) = test::create_partitioned_csv("aggregate_test_100.csv", 4)?;
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: schema,
+                file_groups: files,
+                statistics: Statistics::default(),
+                projection: Some(vec![0, 2, 4]),
+                batch_size: 1024,
+                limit: Some(10),
+                table_partition_cols: vec![],
+            },
+            true,
+            b',',
+        ));
+        assert_roundtrip(&plan)
+    }
+
+    fn csv_with_statistics(
+        projection: Option<Vec<usize>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = aggr_test_schema();
+        let (_, files) = test::create_partitioned_csv("aggregate_test_100.csv", 4)?;
+        let mut column_statistics = vec![ColumnStatistics::default(); 13];
+        column_statistics[1] = ColumnStatistics {
+            null_count: Some(0),
+            max_value: Some(ScalarValue::UInt32(Some(5))),
+            min_value: Some(ScalarValue::UInt32(Some(1))),
+            distinct_count: Some(5),
+        };
+        Ok(Arc::new(CsvExec::new(
+            PhysicalPlanConfig {
+                object_store: Arc::new(LocalFileSystem {}),
+                file_schema: schema,
+                file_groups: files,
+                statistics: Statistics {
+                    num_rows: Some(100),
+                    total_byte_size: Some(7_000),
+                    column_statistics: Some(column_statistics),
+                    is_exact: true,
+                },
+                projection,
+                batch_size: 1024,
+                limit: None,
+                table_partition_cols: vec![],
+            },
+            true,
+            b',',
+        )))
+    }
+
+    #[test]
+    fn roundtrip_schema_and_statistics() -> Result<()> {
+        let csv = csv_with_statistics(Some(vec![0, 1, 2]))?;
+        let schema = csv.schema();
+        let c2 = col("c2", &schema)?;
+        let sort_expr = PhysicalSortExpr {
+            expr: Arc::clone(&c2),
+            options: SortOptions::default(),
+        };
+        let sum: Arc<dyn AggregateExpr> = Arc::new(Sum::new(
+            col("c3", &schema)?,
+            "SUM(c3)".to_string(),
+            DataType::Int64,
+        ));
+        let join_on = vec![(Column::new("c2", 1), Column::new("c2", 1))];
+
+        let plans: Vec<Arc<dyn ExecutionPlan>> = vec![
+            Arc::clone(&csv),
+            csv_with_statistics(None)?,
+            Arc::new(FilterExec::try_new(
+                binary(
+                    Arc::clone(&c2),
+                    Operator::Gt,
+                    lit(ScalarValue::from(1u32)),
+                    &schema,
+                )?,
+                Arc::clone(&csv),
+            )?),
+            Arc::new(ProjectionExec::try_new(
+                vec![
+                    (col("c1", &schema)?, "c1".to_string()),
+                    (Arc::clone(&c2), "x".to_string()),
+                ],
+                Arc::clone(&csv),
+            )?),
+            Arc::new(SortExec::try_new(
+                vec![sort_expr.clone()],
+                Arc::clone(&csv),
+            )?),
+            Arc::new(SortPreservingMergeExec::new(
+                vec![sort_expr],
+                Arc::clone(&csv),
+                1024,
+            )),
+            Arc::new(GlobalLimitExec::new(Arc::clone(&csv), 10)),
+            Arc::new(LocalLimitExec::new(Arc::clone(&csv), 10)),
+            Arc::new(CoalesceBatchesExec::new(Arc::clone(&csv), 4096)),
+            Arc::new(CoalescePartitionsExec::new(Arc::clone(&csv))),
+            Arc::new(RepartitionExec::try_new(
+                Arc::clone(&csv),
+                Partitioning::Hash(vec![Arc::clone(&c2)], 3),
+            )?),
+            Arc::new(HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                vec![(Arc::clone(&c2), "c2".to_string())],
+                vec![sum.clone()],
+                Arc::clone(&csv),
+                Arc::clone(&schema),
+            )?),
+            Arc::new(HashAggregateExec::try_new(
+                AggregateMode::Final,
+                vec![],
+                vec![sum],
+                Arc::new(CoalescePartitionsExec::new(Arc::clone(&csv))),
+                Arc::clone(&schema),
+            )?),
+            Arc::new(UnionExec::new(vec![Arc::clone(&csv), Arc::clone(&csv)])),
+            Arc::new(UnionExec::new_with_interleave(
+                vec![Arc::clone(&csv), Arc::clone(&csv)],
+                3,
+            )),
+            Arc::new(CrossJoinExec::try_new(Arc::clone(&csv), Arc::clone(&csv))?),
+            Arc::new(HashJoinExec::try_new(
+                Arc::clone(&csv),
+                Arc::clone(&csv),
+                join_on,
+                &JoinType::Inner,
+                PartitionMode::CollectLeft,
+                &false,
+            )?),
+            Arc::new(ValuesExec::try_new(
+                Arc::clone(&schema),
+                vec![vec![
+                    lit(ScalarValue::from("a")),
+                    lit(ScalarValue::from(1u32)),
+                    lit(ScalarValue::Int8(Some(-1))),
+                ]],
+            )?),
+        ];
+        for plan in plans {
+            test::assert_plan_roundtrip(plan);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn roundtrip_schema_metadata_and_dictionaries() {
+        let mut metadata = BTreeMap::new();
+        metadata.insert("origin".to_string(), "lambda".to_string());
+        let mut field = Field::new(
+            "d",
+            DataType::Dictionary(Box::new(DataType::Int16), Box::new(DataType::Utf8)),
+            true,
+        );
+        field.set_metadata(Some(metadata));
+        let schema_metadata = [("version".to_string(), "1".to_string())]
+            .into_iter()
+            .collect();
+        let schema = Arc::new(Schema::new_with_metadata(
+            vec![field, Field::new("a", DataType::Int32, false)],
+            schema_metadata,
+        ));
+        test::assert_plan_roundtrip(Arc::new(EmptyExec::new(false, schema)));
+    }
+
+    #[tokio::test]
+    async fn roundtrip_time_zones() -> Result<()> {
+        let mut ctx = context()?;
+        let tz = Some("America/New_York".to_string());
+        let schema = Arc::new(Schema::new(vec![Field::new(
+            "ts",
+            DataType::Timestamp(TimeUnit::Nanosecond, tz.clone()),
+            true,
+        )]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(TimestampNanosecondArray::from_opt_vec(
+                vec![Some(1_600_000_000_000_000_000), None],
+                tz,
+            ))],
+        )?;
+        let table = MemTable::try_new(schema, vec![vec![batch]])?;
+        ctx.register_table("tz", Arc::new(table))?;
+
+        let plan = physical_plan(
+            &ctx,
+            "SELECT ts, to_timezone(ts, 'Asia/Tokyo'), \
+             at_time_zone(ts, 'Europe/Paris') FROM tz WHERE ts > 0",
+        )
+        .await?;
+        let fields = plan.schema().fields().clone();
+        assert_eq!(
+            fields[1].data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, Some("Asia/Tokyo".to_string()))
+        );
+        assert_roundtrip(&plan)
+    }
+
+    #[tokio::test]
+    async fn binary_smaller_than_json() -> Result<()> {
+        let ctx = context()?;
+        let plan = physical_plan(
+            &ctx,
+            "SELECT t1.b, SUM(t2.a) FROM t1 JOIN t2 ON t1.a = t2.a \
+             WHERE t1.a > 1 GROUP BY t1.b ORDER BY t1.b",
+        )
+        .await?;
+        let json = serde_json::to_vec(&plan).unwrap();
+        let bytes = to_bytes(&plan)?;
+        let compressed = to_compressed_bytes(&plan, 0)?;
+        assert!(
+            bytes.len() * 4 < json.len() * 3,
+            "{} vs {}",
+            bytes.len(),
+            json.len()
+        );
+        assert!(
+            compressed.len() * 4 < json.len(),
+            "{} vs {}",
+            compressed.len(),
+            json.len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_header() {
+        let err = from_bytes(b"{}").unwrap_err();
+        assert_eq!(
+            "Execution error: Not a serialized physical plan",
+            err.to_string()
+        );
+
+        let mut bytes = header(Codec::MessagePack);
+        bytes[MAGIC.len()] = FORMAT_VERSION + 1;
+        let err = from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            "Execution error: Unsupported physical plan format version 2, expected 1",
+            err.to_string()
+        );
+
+        let mut bytes = header(Codec::MessagePack);
+        bytes[MAGIC.len() + 1] = 7;
+        let err = from_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            "Execution error: Unknown physical plan codec 7",
+            err.to_string()
+        );
+    }
+}
@@ -0,0 +1,871 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Exchange of data between query stages through an object store.
+//!
+//! The [`ShuffleWriterExec`] ending a stage hash partitions each of its input
+//! partitions and streams the batches to Arrow IPC stream files, the
+//! [`ShuffleReaderExec`] starting the next stage streams them back. Unlike
+//! the inputs fed to a plan (see [`LambdaExecPlan`]), the exchanged data never
+//! has to fit in memory.
+//!
+//! Input partition `i` of a writer at `location` writes one file per output
+//! partition `j`, `{location}/{i}/part-{j}.arrow`, and the manifest
+//! `{location}/{i}/manifest.json` describing them.
+
+use std::any::Any;
+use std::io::{self, Read, Write};
+use std::iter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, StringArray, UInt32Array, UInt64Array};
+use arrow::compute::take;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use super::file_format::file_stream::{BatchIter, FileStream};
+use super::hash_utils::create_hashes;
+use super::memory::feed_input;
+use super::metrics::{self, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet};
+use super::stream::RecordBatchReceiverStream;
+use super::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    PhysicalExpr, SendableRecordBatchStream, Statistics,
+};
+use crate::datasource::object_store::ObjectStore;
+use crate::datasource::PartitionedFile;
+use crate::error::{DataFusionError, Result};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
+
+/// The files written by one input partition of a [`ShuffleWriterExec`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShuffleManifest {
+    /// Schema of the written batches
+    pub schema: SchemaRef,
+    /// Expressions the rows were hash partitioned on
+    pub hash_exprs: Vec<Arc<dyn PhysicalExpr>>,
+    /// The written files, indexed by output partition
+    pub partitions: Vec<ShufflePartition>,
+}
+
+/// One output partition written by a [`ShuffleWriterExec`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShufflePartition {
+    /// Path of the Arrow IPC stream file in the object store
+    pub path: String,
+    /// Size of the file in bytes
+    pub size: u64,
+    /// Number of rows of the file
+    pub num_rows: usize,
+    /// Number of batches of the file
+    pub num_batches: usize,
+    /// In-memory size of the batches of the file in bytes
+    pub num_bytes: usize,
+}
+
+impl ShuffleManifest {
+    /// Read the manifest at `path` in `object_store`
+    pub async fn read(object_store: &dyn ObjectStore, path: &str) -> Result<Self> {
+        let mut files = object_store.list_file(path).await?;
+        while let Some(file) = files.next().await {
+            let file = file?;
+            if file.path() != path {
+                continue;
+            }
+            let reader = object_store.file_reader(file.sized_file)?.sync_reader()?;
+            return serde_json::from_reader(reader).map_err(|e| {
+                DataFusionError::Execution(format!(
+                    "Invalid shuffle manifest {}: {}",
+                    path, e
+                ))
+            });
+        }
+        Err(DataFusionError::Execution(format!(
+            "Shuffle manifest {} not found",
+            path
+        )))
+    }
+
+    fn write(&self, object_store: &dyn ObjectStore, path: &str) -> Result<()> {
+        let json = serde_json::to_vec(self).map_err(|e| {
+            DataFusionError::Internal(format!(
+                "Cannot serialize the shuffle manifest: {}",
+                e
+            ))
+        })?;
+        let mut writer = object_store.file_writer(path)?;
+        writer.write_all(&json)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Execution plan writing the output of its input to an object store, hash
+/// partitioned on the given expressions.
+///
+/// Each of its partitions writes an input partition and returns one row per
+/// written file, see [`ShuffleWriterExec::manifest_path`] for the manifest
+/// describing them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShuffleWriterExec {
+    /// Input execution plan
+    input: Arc<dyn ExecutionPlan>,
+    /// Store the files are written to
+    object_store: Arc<dyn ObjectStore>,
+    /// Directory of the written files in the store
+    location: String,
+    /// Expressions the rows are hash partitioned on
+    hash_exprs: Vec<Arc<dyn PhysicalExpr>>,
+    /// Number of output partitions
+    partition_count: usize,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl ShuffleWriterExec {
+    /// Create a writer of the output of `input` to `location` in
+    /// `object_store`, hash partitioned on `hash_exprs` into
+    /// `partition_count` partitions
+    pub fn try_new(
+        input: Arc<dyn ExecutionPlan>,
+        object_store: Arc<dyn ObjectStore>,
+        location: impl Into<String>,
+        hash_exprs: Vec<Arc<dyn PhysicalExpr>>,
+        partition_count: usize,
+    ) -> Result<Self> {
+        if hash_exprs.is_empty() || partition_count == 0 {
+            return Err(DataFusionError::Plan(
+                "ShuffleWriterExec requires hash expressions and at least one partition"
+                    .to_owned(),
+            ));
+        }
+        Ok(Self {
+            input,
+            object_store,
+            location: location.into(),
+            hash_exprs,
+            partition_count,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// Input execution plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// Directory of the written files in the store
+    pub fn location(&self) -> &str {
+        &self.location
+    }
+
+    /// Path of the manifest written by the input partition `input_partition`
+    pub fn manifest_path(&self, input_partition: usize) -> String {
+        format!("{}/{}/manifest.json", self.location, input_partition)
+    }
+
+    /// Paths of the manifests written by all the input partitions
+    pub fn manifest_paths(&self) -> Vec<String> {
+        (0..self.input.output_partitioning().partition_count())
+            .map(|partition| self.manifest_path(partition))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for ShuffleWriterExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "shuffle_writer_exec")]
+impl ExecutionPlan for ShuffleWriterExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("output_partition", DataType::UInt32, false),
+            Field::new("path", DataType::Utf8, false),
+            Field::new("num_rows", DataType::UInt64, false),
+            Field::new("size", DataType::UInt64, false),
+        ]))
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(
+            self.input.output_partitioning().partition_count(),
+        )
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(ShuffleWriterExec::try_new(
+                children[0].clone(),
+                self.object_store.clone(),
+                self.location.clone(),
+                self.hash_exprs.clone(),
+                self.partition_count,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "ShuffleWriterExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let object_store = match self.object_store.registry_scheme() {
+            Some(scheme) => runtime.object_store_registry.get_by_scheme(scheme)?,
+            None => return Ok(None),
+        };
+        Ok(Some(Arc::new(ShuffleWriterExec::try_new(
+            self.input.clone(),
+            object_store,
+            self.location.clone(),
+            self.hash_exprs.clone(),
+            self.partition_count,
+        )?)))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ShuffleWriterExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let input = self.input.execute(partition, context).await?;
+        let writer = PartitionWriter {
+            object_store: self.object_store.clone(),
+            directory: format!("{}/{}", self.location, partition),
+            hash_exprs: self.hash_exprs.clone(),
+            partition_count: self.partition_count,
+            schema: self.schema(),
+            write_time: MetricBuilder::new(&self.metrics)
+                .subset_time("write_time", partition),
+            bytes_written: MetricBuilder::new(&self.metrics)
+                .counter("bytes_written", partition),
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let join_handle = tokio::task::spawn(async move {
+            let result = writer
+                .write(input)
+                .await
+                .map_err(DataFusionError::into_arrow_external_error);
+            // failing here is OK, the receiver is gone and does not care about the result
+            tx.send(result).await.ok();
+        });
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            RecordBatchReceiverStream::create(&self.schema(), rx, join_handle),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "ShuffleWriterExec: location={}, partitioning={:?}",
+                    self.location,
+                    Partitioning::Hash(self.hash_exprs.clone(), self.partition_count)
+                )
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        Statistics::default()
+    }
+}
+
+/// Writes one input partition of a [`ShuffleWriterExec`]
+struct PartitionWriter {
+    object_store: Arc<dyn ObjectStore>,
+    directory: String,
+    hash_exprs: Vec<Arc<dyn PhysicalExpr>>,
+    partition_count: usize,
+    /// Schema of the returned summary
+    schema: SchemaRef,
+    /// Time spent partitioning and writing batches
+    write_time: metrics::Time,
+    /// Number of bytes of the written files
+    bytes_written: metrics::Count,
+}
+
+impl PartitionWriter {
+    /// Write the batches of `input` and their manifest, returning the
+    /// summary of the written files
+    async fn write(self, mut input: SendableRecordBatchStream) -> Result<RecordBatch> {
+        let input_schema = input.schema();
+        let mut files = (0..self.partition_count)
+            .map(|partition| {
+                ShuffleFile::try_new(
+                    self.object_store.as_ref(),
+                    format!("{}/part-{}.arrow", self.directory, partition),
+                    &input_schema,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // same seeds as RepartitionExec, so that both partition rows alike
+        let random_state = ahash::RandomState::with_seeds(0, 0, 0, 0);
+        let mut hashes_buf = vec![];
+        while let Some(batch) = input.next().await {
+            let batch = batch?;
+            let timer = self.write_time.timer();
+            let indices = hash_partition_indices(
+                &batch,
+                &self.hash_exprs,
+                &random_state,
+                &mut hashes_buf,
+                self.partition_count,
+            )?;
+            for (partition, indices) in indices.into_iter().enumerate() {
+                if indices.is_empty() {
+                    continue;
+                }
+                let indices = indices.into();
+                let columns = batch
+                    .columns()
+                    .iter()
+                    .map(|c| take(c.as_ref(), &indices, None))
+                    .collect::<ArrowResult<Vec<ArrayRef>>>()?;
+                files[partition]
+                    .write(&RecordBatch::try_new(batch.schema(), columns)?)?;
+            }
+            timer.done();
+        }
+
+        let timer = self.write_time.timer();
+        let partitions = files
+            .into_iter()
+            .map(ShuffleFile::finish)
+            .collect::<Result<Vec<_>>>()?;
+        let manifest = ShuffleManifest {
+            schema: input_schema,
+            hash_exprs: self.hash_exprs,
+            partitions,
+        };
+        manifest.write(
+            self.object_store.as_ref(),
+            &format!("{}/manifest.json", self.directory),
+        )?;
+        timer.done();
+        self.bytes_written
+            .add(manifest.partitions.iter().map(|p| p.size as usize).sum());
+
+        let partitions = &manifest.partitions;
+        Ok(RecordBatch::try_new(
+            self.schema,
+            vec![
+                Arc::new(UInt32Array::from(
+                    (0..partitions.len() as u32).collect::<Vec<_>>(),
+                )),
+                Arc::new(StringArray::from(
+                    partitions
+                        .iter()
+                        .map(|p| p.path.as_str())
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(UInt64Array::from(
+                    partitions
+                        .iter()
+                        .map(|p| p.num_rows as u64)
+                        .collect::<Vec<_>>(),
+                )),
+                Arc::new(UInt64Array::from(
+                    partitions.iter().map(|p| p.size).collect::<Vec<_>>(),
+                )),
+            ],
+        )?)
+    }
+}
+
+/// The indices of the rows of `batch` that belong to each of the
+/// `partition_count` partitions
+fn hash_partition_indices(
+    batch: &RecordBatch,
+    hash_exprs: &[Arc<dyn PhysicalExpr>],
+    random_state: &ahash::RandomState,
+    hashes_buf: &mut Vec<u64>,
+    partition_count: usize,
+) -> Result<Vec<Vec<u64>>> {
+    let arrays = hash_exprs
+        .iter()
+        .map(|expr| Ok(expr.evaluate(batch)?.into_array(batch.num_rows())))
+        .collect::<Result<Vec<_>>>()?;
+    hashes_buf.clear();
+    hashes_buf.resize(batch.num_rows(), 0);
+    let hashes = create_hashes(&arrays, random_state, hashes_buf)?;
+    let mut indices = vec![vec![]; partition_count];
+    for (index, hash) in hashes.iter().enumerate() {
+        indices[(*hash % partition_count as u64) as usize].push(index as u64)
+    }
+    Ok(indices)
+}
+
+/// Arrow IPC stream file of one output partition
+struct ShuffleFile {
+    path: String,
+    writer: StreamWriter<CountingWriter>,
+    /// Number of bytes written to the file so far
+    size: Arc<AtomicU64>,
+    num_rows: usize,
+    num_batches: usize,
+    num_bytes: usize,
+}
+
+impl ShuffleFile {
+    fn try_new(
+        object_store: &dyn ObjectStore,
+        path: String,
+        schema: &Schema,
+    ) -> Result<Self> {
+        let size = Arc::new(AtomicU64::new(0));
+        let writer = CountingWriter {
+            inner: object_store.file_writer(&path)?,
+            count: size.clone(),
+        };
+        Ok(Self {
+            path,
+            writer: StreamWriter::try_new(writer, schema)?,
+            size,
+            num_rows: 0,
+            num_batches: 0,
+            num_bytes: 0,
+        })
+    }
+
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        self.writer.write(batch)?;
+        self.num_rows += batch.num_rows();
+        self.num_batches += 1;
+        self.num_bytes += batch
+            .columns()
+            .iter()
+            .map(|array| array.get_array_memory_size())
+            .sum::<usize>();
+        Ok(())
+    }
+
+    /// Write the end of the stream and flush the file
+    fn finish(mut self) -> Result<ShufflePartition> {
+        self.writer.finish()?;
+        Ok(ShufflePartition {
+            path: self.path,
+            size: self.size.load(Ordering::SeqCst),
+            num_rows: self.num_rows,
+            num_batches: self.num_batches,
+            num_bytes: self.num_bytes,
+        })
+    }
+}
+
+/// Writer counting the bytes written to its inner writer
+struct CountingWriter {
+    inner: Box<dyn Write + Send>,
+    count: Arc<AtomicU64>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count.fetch_add(written as u64, Ordering::SeqCst);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Execution plan reading the files written by [`ShuffleWriterExec`]s.
+///
+/// Its partition `j` streams the output partition `j` of every manifest, its
+/// partitioning and statistics are those declared by the manifests.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShuffleReaderExec {
+    /// Store the files are read from
+    object_store: Arc<dyn ObjectStore>,
+    /// Manifests of the files to read
+    manifests: Vec<ShuffleManifest>,
+}
+
+impl ShuffleReaderExec {
+    /// Create a reader of the files described by the manifests at
+    /// `manifest_paths` in `object_store`
+    pub async fn try_new(
+        object_store: Arc<dyn ObjectStore>,
+        manifest_paths: &[String],
+    ) -> Result<Self> {
+        let mut manifests = Vec::with_capacity(manifest_paths.len());
+        for path in manifest_paths {
+            manifests.push(ShuffleManifest::read(object_store.as_ref(), path).await?);
+        }
+        Self::try_new_with_manifests(object_store, manifests)
+    }
+
+    /// Create a reader of the files described by `manifests`, which must
+    /// agree on the schema and the number of partitions
+    pub fn try_new_with_manifests(
+        object_store: Arc<dyn ObjectStore>,
+        manifests: Vec<ShuffleManifest>,
+    ) -> Result<Self> {
+        let first = manifests.first().ok_or_else(|| {
+            DataFusionError::Plan(
+                "ShuffleReaderExec requires at least one manifest".to_owned(),
+            )
+        })?;
+        if manifests.iter().any(|manifest| {
+            manifest.schema != first.schema
+                || manifest.partitions.len() != first.partitions.len()
+        }) {
+            return Err(DataFusionError::Plan(
+                "The shuffle manifests disagree on the schema or the number of partitions"
+                    .to_owned(),
+            ));
+        }
+        Ok(Self {
+            object_store,
+            manifests,
+        })
+    }
+
+    /// Manifests of the files to read
+    pub fn manifests(&self) -> &[ShuffleManifest] {
+        &self.manifests
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for ShuffleReaderExec {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "ShuffleReaderExec reads its input from the shuffle files".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "shuffle_reader_exec")]
+impl ExecutionPlan for ShuffleReaderExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.manifests[0].schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        let manifest = &self.manifests[0];
+        Partitioning::Hash(manifest.hash_exprs.clone(), manifest.partitions.len())
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.is_empty() {
+            Ok(Arc::new(ShuffleReaderExec::try_new_with_manifests(
+                self.object_store.clone(),
+                self.manifests.clone(),
+            )?))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "Children cannot be replaced in {:?}",
+                self
+            )))
+        }
+    }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let object_store = match self.object_store.registry_scheme() {
+            Some(scheme) => runtime.object_store_registry.get_by_scheme(scheme)?,
+            None => return Ok(None),
+        };
+        Ok(Some(Arc::new(ShuffleReaderExec::try_new_with_manifests(
+            object_store,
+            self.manifests.clone(),
+        )?)))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ShuffleReaderExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition >= self.manifests[0].partitions.len() {
+            return Err(DataFusionError::Internal(format!(
+                "ShuffleReaderExec invalid partition {}",
+                partition
+            )));
+        }
+        let files = self
+            .manifests
+            .iter()
+            .map(|manifest| {
+                let file = &manifest.partitions[partition];
+                PartitionedFile::new(file.path.clone(), file.size)
+            })
+            .collect();
+        let fun = |file: Box<dyn Read + Send + Sync>, _remaining: &Option<usize>| {
+            match StreamReader::try_new(file) {
+                Ok(reader) => Box::new(reader) as BatchIter,
+                Err(e) => Box::new(iter::once(Err(e))) as BatchIter,
+            }
+        };
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(FileStream::new(
+                self.object_store.clone(),
+                files,
+                fun,
+                self.schema(),
+                None,
+                vec![],
+            )),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(
+                    f,
+                    "ShuffleReaderExec: manifests={}, partitions={}",
+                    self.manifests.len(),
+                    self.manifests[0].partitions.len()
+                )
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        let files = self
+            .manifests
+            .iter()
+            .flat_map(|manifest| manifest.partitions.iter());
+        let (num_rows, num_bytes) = files.fold((0, 0), |(rows, bytes), file| {
+            (rows + file.num_rows, bytes + file.num_bytes)
+        });
+        Statistics {
+            num_rows: Some(num_rows),
+            total_byte_size: Some(num_bytes),
+            column_statistics: None,
+            is_exact: true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_sorted_eq;
+    use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::physical_plan::repartition::RepartitionExec;
+    use crate::physical_plan::{collect, serde};
+    use crate::test;
+    use tempfile::TempDir;
+
+    /// The input and hash expressions of the first hash RepartitionExec of
+    /// `plan`
+    fn find_hash_repartition(
+        plan: &Arc<dyn ExecutionPlan>,
+    ) -> Option<(Arc<dyn ExecutionPlan>, Vec<Arc<dyn PhysicalExpr>>, usize)> {
+        if let Some(repartition) = plan.as_any().downcast_ref::<RepartitionExec>() {
+            if let Partitioning::Hash(exprs, n) = repartition.partitioning() {
+                return Some((repartition.input().clone(), exprs.clone(), *n));
+            }
+        }
+        plan.children().iter().find_map(find_hash_repartition)
+    }
+
+    /// Replace the hash RepartitionExec of `plan` by `reader`
+    fn replace_hash_repartition(
+        plan: Arc<dyn ExecutionPlan>,
+        reader: &Arc<dyn ExecutionPlan>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if let Some(repartition) = plan.as_any().downcast_ref::<RepartitionExec>() {
+            if let Partitioning::Hash(_, _) = repartition.partitioning() {
+                return Ok(reader.clone());
+            }
+        }
+        if plan.children().is_empty() {
+            return Ok(plan);
+        }
+        let children = plan
+            .children()
+            .into_iter()
+            .map(|child| replace_hash_repartition(child, reader))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(children)
+    }
+
+    #[tokio::test]
+    async fn shuffle_aggregation() -> Result<()> {
+        let config = ExecutionConfig::new().with_target_partitions(3);
+        let mut ctx = ExecutionContext::with_config(config);
+        ctx.register_table("t", test::table_with_sequence(1, 100)?)?;
+        let sql = "SELECT i % 7 AS k, COUNT(*) AS c, SUM(i) AS s FROM t GROUP BY i % 7";
+        let expected = ctx.sql(sql).await?.collect().await?;
+
+        let plan = ctx.create_logical_plan(sql)?;
+        let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+        let (partial, hash_exprs, partition_count) =
+            find_hash_repartition(&plan).expect("hash repartition");
+
+        let tmp = TempDir::new()?;
+        let location = tmp.path().join("shuffle");
+        let writer = ShuffleWriterExec::try_new(
+            partial,
+            Arc::new(LocalFileSystem),
+            location.to_str().unwrap(),
+            hash_exprs,
+            partition_count,
+        )?;
+        let manifest_paths = writer.manifest_paths();
+        // the writer is shipped to the process running it
+        let writer = serde::from_bytes(&serde::to_bytes(
+            &(Arc::new(writer) as Arc<dyn ExecutionPlan>),
+        )?)?;
+        let task_ctx = ctx.task_ctx();
+        let summary = collect(writer, task_ctx.clone()).await?;
+        let rows = summary.iter().map(|b| b.num_rows()).sum::<usize>();
+        assert_eq!(rows, manifest_paths.len() * partition_count);
+
+        let reader =
+            ShuffleReaderExec::try_new(Arc::new(LocalFileSystem), &manifest_paths)
+                .await?;
+        // one row per group and input partition of the partial aggregation
+        let stats = reader.statistics();
+        assert!(stats.num_rows.unwrap() >= 7);
+        assert!(stats.is_exact);
+        assert_eq!(
+            reader.output_partitioning().partition_count(),
+            partition_count
+        );
+        let reader = serde::from_bytes(&serde::to_bytes(
+            &(Arc::new(reader) as Arc<dyn ExecutionPlan>),
+        )?)?;
+
+        let plan = replace_hash_repartition(plan, &reader)?;
+        let actual = collect(plan, task_ctx).await?;
+        let expected = arrow::util::pretty::pretty_format_batches(&expected)?;
+        let expected = expected.trim().lines().collect::<Vec<_>>();
+        assert_batches_sorted_eq!(expected, &actual);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn invalid_manifests() -> Result<()> {
+        let tmp = TempDir::new()?;
+        let path = tmp.path().join("manifest.json");
+        std::fs::write(&path, "{}")?;
+        let paths = vec![path.to_str().unwrap().to_owned()];
+        let err = ShuffleReaderExec::try_new(Arc::new(LocalFileSystem), &paths)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("Invalid shuffle manifest"));
+
+        let err = ShuffleReaderExec::try_new(Arc::new(LocalFileSystem), &[])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: ShuffleReaderExec requires at least one manifest"
+        );
+        Ok(())
+    }
+}
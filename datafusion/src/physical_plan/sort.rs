@@ -17,27 +17,47 @@
 
 //! Defines the SORT plan
 
-use super::common::AbortOnDropSingle;
+use super::common::{batch_memory_size, AbortOnDropMany, AbortOnDropSingle};
+use super::memory::MemoryStream;
 use super::metrics::{
-    BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet, RecordOutput,
+    BaselineMetrics, Count, ExecutionPlanMetricsSet, MetricBuilder, MetricsSet,
+    RecordOutput,
 };
+use super::sort_preserving_merge::SortPreservingMergeStream;
+use super::stream::RecordBatchReceiverStream;
 use super::{RecordBatchStream, SendableRecordBatchStream, Statistics};
 use crate::error::{DataFusionError, Result};
-use crate::physical_plan::expressions::PhysicalSortExpr;
+use crate::execution::cancellation::CancellationToken;
+use crate::execution::cpu_executor::CpuExecutor;
+use crate::execution::disk_manager::{DiskManager, SpillFile};
+use crate::execution::memory_manager::{MemoryConsumer, MemoryManager};
+use crate::execution::runtime_env::RuntimeEnv;
+use crate::execution::task_context::TaskContext;
+use crate::physical_optimizer::utils::same_sort_key;
+use crate::physical_plan::expressions::{
+    is_binary, is_interval, lexsort_binary_to_indices, PhysicalSortExpr,
+};
 use crate::physical_plan::{
-    common, DisplayFormatType, Distribution, ExecutionPlan, Partitioning,
+    common, fmt_with_statistics, DisplayFormatType, Distribution, ExecutionPlan,
+    Partitioning,
 };
+use arrow::compute::kernels::partition::lexicographical_partition_ranges;
 pub use arrow::compute::SortOptions;
 use arrow::compute::{lexsort_to_indices, take, SortColumn, TakeOptions};
 use arrow::datatypes::SchemaRef;
 use arrow::error::Result as ArrowResult;
+use arrow::ipc::reader::StreamReader;
+use arrow::ipc::writer::StreamWriter;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, error::ArrowError};
+use crate::physical_plan::memory::feed_input;
+use crate::physical_plan::trace::{self, instrument_stream};
 use crate::physical_plan::LambdaExecPlan;
 use serde::{Deserialize, Serialize};
 use async_trait::async_trait;
+use futures::channel::mpsc;
 use futures::stream::Stream;
-use futures::Future;
+use futures::{Future, SinkExt, StreamExt};
 use pin_project_lite::pin_project;
 use std::any::Any;
 use std::pin::Pin;
@@ -55,6 +75,23 @@ pub struct SortExec {
     metrics: ExecutionPlanMetricsSet,
     /// Preserve partitions of input plan
     preserve_partitioning: bool,
+    /// Maximum number of bytes of input to buffer, bound by the runtime
+    #[serde(skip)]
+    memory_limit: Option<usize>,
+    /// Memory manager the buffered input is reserved from, bound by the
+    /// runtime. The sort spills sorted runs to disk when the reservation
+    /// fails.
+    #[serde(skip)]
+    memory_manager: Option<Arc<MemoryManager>>,
+    /// Disk manager the sorted runs are spilled to, bound by the runtime
+    #[serde(skip)]
+    disk_manager: Option<Arc<DiskManager>>,
+    /// Token cancelling the buffering of the input, bound by the runtime
+    #[serde(skip)]
+    cancellation: CancellationToken,
+    /// Executor sorting the buffered input, bound by the runtime
+    #[serde(skip)]
+    cpu_executor: CpuExecutor,
 }
 
 impl SortExec {
@@ -78,6 +115,11 @@ impl SortExec {
             input,
             metrics: ExecutionPlanMetricsSet::new(),
             preserve_partitioning,
+            memory_limit: None,
+            memory_manager: None,
+            disk_manager: None,
+            cancellation: CancellationToken::new(),
+            cpu_executor: CpuExecutor::default(),
         }
     }
 
@@ -90,6 +132,33 @@ impl SortExec {
     pub fn expr(&self) -> &[PhysicalSortExpr] {
         &self.expr
     }
+
+    /// Whether each partition of the input is sorted separately
+    pub fn preserve_partitioning(&self) -> bool {
+        self.preserve_partitioning
+    }
+
+    /// Number of leading sort expressions the partitions of the input are
+    /// already sorted on. When positive, each run of rows with equal values
+    /// of these expressions is sorted separately as soon as it ends.
+    pub fn sorted_prefix_len(&self) -> usize {
+        let schema = self.input.schema();
+        self.input.output_ordering().map_or(0, |ordering| {
+            self.expr
+                .iter()
+                .zip(ordering)
+                .take_while(|(required, provided)| {
+                    // arrow can't compare binary values yet, nor intervals on
+                    // their durations
+                    same_sort_key(required, provided)
+                        && required
+                            .expr
+                            .data_type(&schema)
+                            .map_or(false, |t| !is_binary(&t) && !is_interval(&t))
+                })
+                .count()
+        })
+    }
 }
 
 #[async_trait]
@@ -121,6 +190,10 @@ impl ExecutionPlan for SortExec {
         }
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        Some(&self.expr)
+    }
+
     fn required_child_distribution(&self) -> Distribution {
         if self.preserve_partitioning {
             Distribution::UnspecifiedDistribution
@@ -134,17 +207,36 @@ impl ExecutionPlan for SortExec {
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
         match children.len() {
-            1 => Ok(Arc::new(SortExec::try_new(
-                self.expr.clone(),
-                children[0].clone(),
-            )?)),
+            1 => {
+                let mut sort = SortExec::try_new(self.expr.clone(), children[0].clone())?;
+                sort.memory_limit = self.memory_limit;
+                sort.memory_manager = self.memory_manager.clone();
+                sort.disk_manager = self.disk_manager.clone();
+                sort.cancellation = self.cancellation.clone();
+                sort.cpu_executor = self.cpu_executor.clone();
+                Ok(Arc::new(sort))
+            }
             _ => Err(DataFusionError::Internal(
                 "SortExec wrong number of children".to_string(),
             )),
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "SortExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         if !self.preserve_partitioning {
             if 0 != partition {
                 return Err(DataFusionError::Internal(format!(
@@ -161,14 +253,63 @@ impl ExecutionPlan for SortExec {
             }
         }
 
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
-        let input = self.input.execute(partition).await?;
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("SortExec", partition));
+        let input = self.input.execute(partition, context).await?;
+        let consumer = self.memory_manager.as_ref().map(|manager| {
+            manager.register_consumer(
+                format!("SortExec[{}]", partition),
+                MetricBuilder::new(&self.metrics).mem_used(partition),
+            )
+        });
 
-        Ok(Box::pin(SortStream::new(
-            input,
-            self.expr.clone(),
-            baseline_metrics,
-        )))
+        let sorted_prefix_len = self.sorted_prefix_len();
+        if sorted_prefix_len > 0 {
+            return Ok(instrument_stream(
+                self,
+                partition,
+                prefix_sort(
+                    input,
+                    self.expr.clone(),
+                    sorted_prefix_len,
+                    baseline_metrics,
+                    RunBuffer::new(consumer, self.memory_limit),
+                    self.disk_manager.clone().unwrap_or_default(),
+                    SpillMetrics::new(&self.metrics, partition),
+                    self.cancellation.clone(),
+                    self.cpu_executor.clone(),
+                ),
+            ));
+        }
+
+        if let Some(consumer) = consumer {
+            let spill_metrics = SpillMetrics::new(&self.metrics, partition);
+            return external_sort(
+                input,
+                self.expr.clone(),
+                baseline_metrics,
+                consumer,
+                self.disk_manager.clone().unwrap_or_default(),
+                spill_metrics,
+                self.cancellation.clone(),
+                self.cpu_executor.clone(),
+            )
+            .await
+            .map(|stream| instrument_stream(self, partition, stream));
+        }
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(SortStream::new(
+                input,
+                self.expr.clone(),
+                baseline_metrics,
+                self.memory_limit,
+                self.cancellation.clone(),
+                self.cpu_executor.clone(),
+            )),
+        ))
     }
 
     fn fmt_as(
@@ -179,8 +320,14 @@ impl ExecutionPlan for SortExec {
         match t {
             DisplayFormatType::Default => {
                 let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
-                write!(f, "SortExec: [{}]", expr.join(","))
+                write!(f, "SortExec: [{}]", expr.join(","))?;
+                let sorted_prefix_len = self.sorted_prefix_len();
+                if sorted_prefix_len > 0 {
+                    write!(f, ", sorted_prefix={}", sorted_prefix_len)?;
+                }
+                Ok(())
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -191,12 +338,30 @@ impl ExecutionPlan for SortExec {
     fn statistics(&self) -> Statistics {
         self.input.statistics()
     }
+
+    fn with_runtime(
+        &self,
+        runtime: &RuntimeEnv,
+    ) -> Result<Option<Arc<dyn ExecutionPlan>>> {
+        let mut sort = SortExec::new_with_partitioning(
+            self.expr.clone(),
+            self.input.clone(),
+            self.preserve_partitioning,
+        );
+        sort.memory_limit = runtime.memory_limit;
+        sort.memory_manager = runtime.memory_manager.clone();
+        sort.disk_manager = Some(runtime.disk_manager.clone());
+        sort.cancellation = runtime.cancellation.clone();
+        sort.cpu_executor = runtime.cpu_executor.clone();
+        Ok(Some(Arc::new(sort)))
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for SortExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -205,15 +370,21 @@ fn sort_batch(
     schema: SchemaRef,
     expr: &[PhysicalSortExpr],
 ) -> ArrowResult<RecordBatch> {
+    let columns = expr
+        .iter()
+        .map(|e| e.evaluate_to_sort_column(&batch))
+        .collect::<Result<Vec<SortColumn>>>()
+        .map_err(DataFusionError::into_arrow_external_error)?;
     // TODO: pushup the limit expression to sort
-    let indices = lexsort_to_indices(
-        &expr
-            .iter()
-            .map(|e| e.evaluate_to_sort_column(&batch))
-            .collect::<Result<Vec<SortColumn>>>()
-            .map_err(DataFusionError::into_arrow_external_error)?,
-        None,
-    )?;
+    let indices = if columns.iter().any(|column| {
+        is_binary(column.values.data_type()) || is_interval(column.values.data_type())
+    }) {
+        // arrow can't sort binary values yet, nor intervals on their durations
+        lexsort_binary_to_indices(&columns, None)
+            .map_err(DataFusionError::into_arrow_external_error)?
+    } else {
+        lexsort_to_indices(&columns, None)?
+    };
 
     // reorder all rows based on sorted indices
     RecordBatch::try_new(
@@ -236,6 +407,449 @@ fn sort_batch(
     )
 }
 
+/// Fail if the buffered `batches` take more than `memory_limit` bytes
+fn check_memory_limit(
+    batches: Vec<RecordBatch>,
+    memory_limit: Option<usize>,
+) -> Result<Vec<RecordBatch>> {
+    if let Some(limit) = memory_limit {
+        let size: usize = batches.iter().map(batch_memory_size).sum();
+        if size > limit {
+            return Err(DataFusionError::Execution(format!(
+                "SortExec buffered {} bytes, more than the memory limit of {} bytes",
+                size, limit
+            )));
+        }
+    }
+    Ok(batches)
+}
+
+/// Metrics of the runs spilled by a partition of an external sort
+struct SpillMetrics {
+    /// Number of spilled runs
+    spill_count: Count,
+    /// Number of bytes written to the spill files
+    spilled_bytes: Count,
+}
+
+impl SpillMetrics {
+    fn new(metrics: &ExecutionPlanMetricsSet, partition: usize) -> Self {
+        Self {
+            spill_count: MetricBuilder::new(metrics).spill_count(partition),
+            spilled_bytes: MetricBuilder::new(metrics).spilled_bytes(partition),
+        }
+    }
+}
+
+/// Sort `input`, reserving the buffered batches from `consumer`.
+///
+/// When a batch cannot be reserved, the buffered batches are sorted into a
+/// run spilled to a file allocated by `disk_manager` and their reservation
+/// is released. The spilled runs are then merged into the output stream.
+/// Fails with [`DataFusionError::ResourcesExhausted`] if a single batch does
+/// not fit in the memory pool, and with [`DataFusionError::Cancelled`] once
+/// `cancellation` is cancelled. Sorts the batches fitting in memory on
+/// `cpu_executor`.
+#[allow(clippy::too_many_arguments)]
+async fn external_sort(
+    mut input: SendableRecordBatchStream,
+    expr: Vec<PhysicalSortExpr>,
+    baseline_metrics: BaselineMetrics,
+    mut consumer: MemoryConsumer,
+    disk_manager: Arc<DiskManager>,
+    spill_metrics: SpillMetrics,
+    cancellation: CancellationToken,
+    cpu_executor: CpuExecutor,
+) -> Result<SendableRecordBatchStream> {
+    let schema = input.schema();
+    let mut buffer = vec![];
+    let mut runs = vec![];
+    // the merged runs are output in batches as large as the input batches
+    let mut target_batch_size = 1;
+    while let Some(batch) = input.next().await {
+        let batch = batch?;
+        cancellation.check()?;
+        target_batch_size = target_batch_size.max(batch.num_rows());
+        let size = batch_memory_size(&batch);
+        if let Err(e) = consumer.try_grow(size) {
+            if buffer.is_empty() {
+                return Err(e);
+            }
+            let batches = std::mem::take(&mut buffer);
+            runs.push(spill_sorted_run(
+                &batches,
+                schema.clone(),
+                &expr,
+                &baseline_metrics,
+                &disk_manager,
+                &spill_metrics,
+            )?);
+            consumer.free();
+            consumer.try_grow(size)?;
+        }
+        buffer.push(batch);
+    }
+
+    if runs.is_empty() {
+        let sort_schema = schema.clone();
+        let sorted = cpu_executor
+            .spawn(move || sort_batches(&buffer, sort_schema, &expr, &baseline_metrics))
+            .await??;
+        return Ok(Box::pin(MemoryStream::try_new(
+            sorted.into_iter().collect(),
+            schema,
+            None,
+        )?));
+    }
+
+    if !buffer.is_empty() {
+        runs.push(spill_sorted_run(
+            &buffer,
+            schema.clone(),
+            &expr,
+            &baseline_metrics,
+            &disk_manager,
+            &spill_metrics,
+        )?);
+    }
+    drop(buffer);
+    consumer.free();
+
+    let (receivers, join_handles) = runs
+        .into_iter()
+        .map(|run| {
+            let (mut sender, receiver) = mpsc::channel(1);
+            let join_handle = tokio::spawn(async move {
+                match run.reopen().map(StreamReader::try_new) {
+                    Ok(Ok(reader)) => {
+                        for batch in reader {
+                            // If send fails, plan being torn down,
+                            // there is no place to send the batch
+                            if sender.send(batch).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Ok(Err(e)) => {
+                        sender.send(Err(e)).await.ok();
+                    }
+                    Err(e) => {
+                        let e = ArrowError::IoError(e.to_string());
+                        sender.send(Err(e)).await.ok();
+                    }
+                }
+                // delete the run before the end of the stream is seen
+                drop(run);
+            });
+            (receiver, join_handle)
+        })
+        .unzip();
+
+    Ok(Box::pin(SortPreservingMergeStream::new(
+        receivers,
+        AbortOnDropMany(join_handles),
+        schema,
+        &expr,
+        target_batch_size,
+        baseline_metrics,
+    )))
+}
+
+/// Sort `batches` combined into a single batch, recording the sort in
+/// `baseline_metrics`
+fn sort_batches(
+    batches: &[RecordBatch],
+    schema: SchemaRef,
+    expr: &[PhysicalSortExpr],
+    baseline_metrics: &BaselineMetrics,
+) -> ArrowResult<Option<RecordBatch>> {
+    let timer = baseline_metrics.elapsed_compute().timer();
+    // combine all record batches into one for each column
+    let combined = common::combine_batches(batches, schema.clone())?;
+    // sort combined record batch
+    let result = combined
+        .map(|batch| sort_batch(batch, schema, expr))
+        .transpose()?
+        .record_output(baseline_metrics);
+    timer.done();
+    Ok(result)
+}
+
+/// Sort `input`, whose rows are already sorted on the first `prefix_len`
+/// expressions of `expr`, by sorting each run of rows with equal values of
+/// these expressions separately.
+///
+/// A run is buffered in `buffer` until the first row of the next run is
+/// seen, so that the memory used is bounded by the size of the largest run
+/// rather than of the whole input. Each run is sorted on `cpu_executor` and
+/// output as soon as it ends. If a run does not fit in the memory pool of
+/// the consumer of `buffer`, it and the rest of `input` are sorted with
+/// [`external_sort`], spilling to files allocated by `disk_manager`. Fails
+/// with [`DataFusionError::Cancelled`] once `cancellation` is cancelled.
+#[allow(clippy::too_many_arguments)]
+fn prefix_sort(
+    input: SendableRecordBatchStream,
+    expr: Vec<PhysicalSortExpr>,
+    prefix_len: usize,
+    baseline_metrics: BaselineMetrics,
+    buffer: RunBuffer,
+    disk_manager: Arc<DiskManager>,
+    spill_metrics: SpillMetrics,
+    cancellation: CancellationToken,
+    cpu_executor: CpuExecutor,
+) -> SendableRecordBatchStream {
+    let schema = input.schema();
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    let join_handle = tokio::spawn(async move {
+        let error_tx = tx.clone();
+        let sorter = RunSorter {
+            expr: Arc::new(expr),
+            prefix_len,
+            baseline_metrics: Arc::new(baseline_metrics),
+            disk_manager,
+            spill_metrics,
+            cpu_executor,
+            tx,
+        };
+        if let Err(e) = sorter.sort_runs(input, buffer, cancellation).await {
+            // failing here is OK, the receiver is gone and does not care about the result
+            error_tx.send(Err(e.into_arrow_external_error())).await.ok();
+        }
+    });
+    RecordBatchReceiverStream::create(&schema, rx, join_handle)
+}
+
+/// Batches of the runs not output yet of a [`prefix_sort`], reserved from a
+/// memory consumer or checked against a memory limit
+struct RunBuffer {
+    batches: Vec<RecordBatch>,
+    /// Number of bytes of the buffered batches
+    size: usize,
+    consumer: Option<MemoryConsumer>,
+    memory_limit: Option<usize>,
+}
+
+impl RunBuffer {
+    fn new(consumer: Option<MemoryConsumer>, memory_limit: Option<usize>) -> Self {
+        Self {
+            batches: vec![],
+            size: 0,
+            consumer,
+            memory_limit,
+        }
+    }
+
+    /// Buffer `batch`, handing it back if the consumer cannot reserve it
+    fn push(&mut self, batch: RecordBatch) -> Result<Option<RecordBatch>> {
+        let size = batch_memory_size(&batch);
+        if let Some(consumer) = &mut self.consumer {
+            if consumer.try_grow(size).is_err() {
+                return Ok(Some(batch));
+            }
+        }
+        self.size += size;
+        if let Some(limit) = self.memory_limit {
+            if self.size > limit {
+                return Err(DataFusionError::Execution(format!(
+                    "SortExec buffered {} bytes, more than the memory limit of {} bytes",
+                    self.size, limit
+                )));
+            }
+        }
+        self.batches.push(batch);
+        Ok(None)
+    }
+
+    /// Take the buffered batches, releasing their reservation
+    fn take(&mut self) -> Vec<RecordBatch> {
+        if let Some(consumer) = &mut self.consumer {
+            consumer.free();
+        }
+        self.size = 0;
+        std::mem::take(&mut self.batches)
+    }
+}
+
+/// Sorts the runs of a [`prefix_sort`], sending them to `tx`
+struct RunSorter {
+    expr: Arc<Vec<PhysicalSortExpr>>,
+    prefix_len: usize,
+    baseline_metrics: Arc<BaselineMetrics>,
+    disk_manager: Arc<DiskManager>,
+    spill_metrics: SpillMetrics,
+    cpu_executor: CpuExecutor,
+    tx: tokio::sync::mpsc::Sender<ArrowResult<RecordBatch>>,
+}
+
+impl RunSorter {
+    async fn sort_runs(
+        self,
+        mut input: SendableRecordBatchStream,
+        mut buffer: RunBuffer,
+        cancellation: CancellationToken,
+    ) -> Result<()> {
+        while let Some(batch) = input.next().await {
+            let batch = batch?;
+            cancellation.check()?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+
+            // the buffered run ends before the batch
+            let run_ended = match buffer.batches.last() {
+                Some(last) => !self.continues_run(last, &batch)?,
+                None => false,
+            };
+            if run_ended && !self.output(buffer.take()).await? {
+                return Ok(());
+            }
+
+            // the runs of the batch but the last one end within it
+            let start = self.last_run_start(&batch)?;
+            if let Some(batch) = buffer.push(batch)? {
+                return self
+                    .sort_externally(buffer, batch, input, cancellation)
+                    .await;
+            }
+            if start > 0 {
+                let mut batches = buffer.take();
+                let batch = batches.pop().unwrap();
+                batches.push(batch.slice(0, start));
+                if !self.output(batches).await? {
+                    return Ok(());
+                }
+                let batch = batch.slice(start, batch.num_rows() - start);
+                if let Some(batch) = buffer.push(batch)? {
+                    return self
+                        .sort_externally(buffer, batch, input, cancellation)
+                        .await;
+                }
+            }
+        }
+        self.output(buffer.take()).await?;
+        Ok(())
+    }
+
+    /// Sort the buffered run, `batch` that continues it and the rest of
+    /// `input` with [`external_sort`], which spills them to disk as needed.
+    /// The runs output before all sort before these rows.
+    async fn sort_externally(
+        self,
+        mut buffer: RunBuffer,
+        batch: RecordBatch,
+        input: SendableRecordBatchStream,
+        cancellation: CancellationToken,
+    ) -> Result<()> {
+        let schema = input.schema();
+        let mut batches = buffer.take();
+        batches.push(batch);
+        let consumer = buffer.consumer.take().ok_or_else(|| {
+            DataFusionError::Internal("SortExec spills without a consumer".to_owned())
+        })?;
+        // the sorts of the runs output before are complete
+        let baseline_metrics = Arc::try_unwrap(self.baseline_metrics).map_err(|_| {
+            DataFusionError::Internal("SortExec spills while sorting a run".to_owned())
+        })?;
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        let join_handle = tokio::spawn(async move {
+            let mut rest =
+                futures::stream::iter(batches.into_iter().map(Ok)).chain(input);
+            while let Some(batch) = rest.next().await {
+                if tx.send(batch).await.is_err() {
+                    break;
+                }
+            }
+        });
+        let mut sorted = external_sort(
+            RecordBatchReceiverStream::create(&schema, rx, join_handle),
+            self.expr.to_vec(),
+            baseline_metrics,
+            consumer,
+            self.disk_manager,
+            self.spill_metrics,
+            cancellation,
+            self.cpu_executor,
+        )
+        .await?;
+        while let Some(batch) = sorted.next().await {
+            if self.tx.send(batch).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the first row of `batch` has the same values of the sorted
+    /// prefix as the last row of `last`
+    fn continues_run(&self, last: &RecordBatch, batch: &RecordBatch) -> Result<bool> {
+        let rows = common::combine_batches(
+            &[last.slice(last.num_rows() - 1, 1), batch.slice(0, 1)],
+            batch.schema(),
+        )?;
+        match rows {
+            Some(rows) => Ok(self.last_run_start(&rows)? == 0),
+            None => Ok(false),
+        }
+    }
+
+    /// Index of the first row of the last run of the rows of `batch`
+    fn last_run_start(&self, batch: &RecordBatch) -> Result<usize> {
+        let columns = self.expr[..self.prefix_len]
+            .iter()
+            .map(|e| e.evaluate_to_sort_column(batch))
+            .collect::<Result<Vec<SortColumn>>>()?;
+        Ok(lexicographical_partition_ranges(&columns)?
+            .last()
+            .map_or(0, |range| range.start))
+    }
+
+    /// Sort the complete runs of `batches` and send them, returning false if
+    /// the receiver is gone
+    async fn output(&self, batches: Vec<RecordBatch>) -> Result<bool> {
+        let schema = match batches.first() {
+            Some(batch) => batch.schema(),
+            None => return Ok(true),
+        };
+        let expr = self.expr.clone();
+        let baseline_metrics = self.baseline_metrics.clone();
+        let sorted = self
+            .cpu_executor
+            .spawn(move || sort_batches(&batches, schema, &expr, &baseline_metrics))
+            .await??;
+        match sorted {
+            Some(batch) => Ok(self.tx.send(Ok(batch)).await.is_ok()),
+            None => Ok(true),
+        }
+    }
+}
+
+/// Sort `batches` into a run written to a spill file, deleted when the
+/// returned file is dropped
+fn spill_sorted_run(
+    batches: &[RecordBatch],
+    schema: SchemaRef,
+    expr: &[PhysicalSortExpr],
+    baseline_metrics: &BaselineMetrics,
+    disk_manager: &DiskManager,
+    spill_metrics: &SpillMetrics,
+) -> Result<SpillFile> {
+    let timer = baseline_metrics.elapsed_compute().timer();
+    let mut file = disk_manager.create_spill_file("sort")?;
+    {
+        let mut writer = StreamWriter::try_new(&mut file, &schema)?;
+        if let Some(batch) = common::combine_batches(batches, schema.clone())? {
+            writer.write(&sort_batch(batch, schema, expr)?)?;
+        }
+        writer.finish()?;
+    }
+    timer.done();
+    spill_metrics.spill_count.add(1);
+    spill_metrics.spilled_bytes.add(file.size());
+    trace::spill(file.size());
+    Ok(file)
+}
+
 pin_project! {
     /// stream for sort plan
     struct SortStream {
@@ -252,26 +866,27 @@ impl SortStream {
         input: SendableRecordBatchStream,
         expr: Vec<PhysicalSortExpr>,
         baseline_metrics: BaselineMetrics,
+        memory_limit: Option<usize>,
+        cancellation: CancellationToken,
+        cpu_executor: CpuExecutor,
     ) -> Self {
         let (tx, rx) = futures::channel::oneshot::channel();
         let schema = input.schema();
         let join_handle = tokio::spawn(async move {
             let schema = input.schema();
-            let sorted_batch = common::collect(input)
+            let batches = common::collect_reserved(input, None, &cancellation)
                 .await
-                .map_err(DataFusionError::into_arrow_external_error)
-                .and_then(move |batches| {
-                    let timer = baseline_metrics.elapsed_compute().timer();
-                    // combine all record batches into one for each column
-                    let combined = common::combine_batches(&batches, schema.clone())?;
-                    // sort combined record batch
-                    let result = combined
-                        .map(|batch| sort_batch(batch, schema, &expr))
-                        .transpose()?
-                        .record_output(&baseline_metrics);
-                    timer.done();
-                    Ok(result)
-                });
+                .and_then(|batches| check_memory_limit(batches, memory_limit));
+            let sorted_batch = match batches {
+                Ok(batches) => cpu_executor
+                    .spawn(move || {
+                        sort_batches(&batches, schema, &expr, &baseline_metrics)
+                    })
+                    .await
+                    .map_err(DataFusionError::into_arrow_external_error)
+                    .and_then(|sorted| sorted),
+                Err(e) => Err(e.into_arrow_external_error()),
+            };
 
             // failing here is OK, the receiver is gone and does not care about the result
             tx.send(sorted_batch).ok();
@@ -327,11 +942,14 @@ mod tests {
 
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::coalesce_partitions::CoalescePartitionsExec;
     use crate::physical_plan::expressions::col;
     use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::metrics::Gauge;
+    use crate::physical_plan::stream::RecordBatchReceiverStream;
     use crate::physical_plan::{
-        collect,
+        collect, displayable,
         file_format::{CsvExec, PhysicalPlanConfig},
     };
     use crate::test::assert_is_pending;
@@ -341,9 +959,11 @@ mod tests {
     use arrow::array::*;
     use arrow::datatypes::*;
     use futures::FutureExt;
+    use tempfile::TempDir;
 
     #[tokio::test]
     async fn test_sort() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
         let partitions = 4;
         let (_, files) =
@@ -385,7 +1005,7 @@ mod tests {
             Arc::new(CoalescePartitionsExec::new(Arc::new(csv))),
         )?);
 
-        let result: Vec<RecordBatch> = collect(sort_exec).await?;
+        let result: Vec<RecordBatch> = collect(sort_exec, task_ctx).await?;
         assert_eq!(result.len(), 1);
 
         let columns = result[0].columns();
@@ -407,6 +1027,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_sort_metadata() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let field_metadata: BTreeMap<String, String> =
             vec![("foo".to_string(), "bar".to_string())]
                 .into_iter()
@@ -436,7 +1057,7 @@ mod tests {
             input,
         )?);
 
-        let result: Vec<RecordBatch> = collect(sort_exec).await?;
+        let result: Vec<RecordBatch> = collect(sort_exec, task_ctx).await?;
 
         let expected_data: ArrayRef =
             Arc::new(vec![1, 2, 3].into_iter().map(Some).collect::<UInt64Array>());
@@ -458,6 +1079,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_lex_sort_by_float() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = Arc::new(Schema::new(vec![
             Field::new("a", DataType::Float32, true),
             Field::new("b", DataType::Float64, true),
@@ -513,7 +1135,7 @@ mod tests {
         assert_eq!(DataType::Float32, *sort_exec.schema().field(0).data_type());
         assert_eq!(DataType::Float64, *sort_exec.schema().field(1).data_type());
 
-        let result: Vec<RecordBatch> = collect(sort_exec.clone()).await?;
+        let result: Vec<RecordBatch> = collect(sort_exec.clone(), task_ctx).await?;
         let metrics = sort_exec.metrics().unwrap();
         assert!(metrics.elapsed_compute().unwrap() > 0);
         assert_eq!(metrics.output_rows().unwrap(), 8);
@@ -560,8 +1182,297 @@ mod tests {
         Ok(())
     }
 
+    /// Sort of 10 batches of 100 shuffled values of "a"
+    fn spill_test_sort() -> Result<(SortExec, Vec<RecordBatch>)> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batches = (0..10)
+            .map(|i| {
+                let values = (0..100).map(|j| (i * 100 + j) * 37 % 1000);
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![Arc::new(Int32Array::from_iter_values(values))],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let sort = SortExec::try_new(
+            vec![PhysicalSortExpr {
+                expr: col("a", &schema)?,
+                options: SortOptions::default(),
+            }],
+            Arc::new(MemoryExec::try_new(&[batches.clone()], schema, None)?),
+        )?;
+        Ok((sort, batches))
+    }
+
+    #[tokio::test]
+    async fn test_sort_spills_to_disk() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let (mut sort, batches) = spill_test_sort()?;
+        // room for three input batches
+        let manager = Arc::new(MemoryManager::new(3 * batch_memory_size(&batches[0])));
+        let dir = TempDir::new()?;
+        let disk_manager = Arc::new(DiskManager::new(vec![dir.path().to_owned()], None));
+        sort.memory_manager = Some(manager.clone());
+        sort.disk_manager = Some(disk_manager.clone());
+        let sort = Arc::new(sort);
+
+        let result = collect(sort.clone(), task_ctx).await?;
+        let values = result
+            .iter()
+            .flat_map(|batch| as_primitive_array::<Int32Type>(batch.column(0)).values())
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(values, (0..1000).collect::<Vec<_>>());
+
+        let metrics = sort.metrics().unwrap().aggregate_by_name();
+        assert_eq!(metrics.output_rows().unwrap(), 1000);
+        assert!(metrics.spill_count().unwrap() >= 3);
+        let mem_used = metrics.mem_used().unwrap();
+        assert!(mem_used > 0 && mem_used <= manager.pool_size());
+        assert_eq!(manager.used(), 0);
+        assert!(metrics.spilled_bytes().unwrap() > 0);
+        // the runs are deleted once merged
+        assert_eq!(disk_manager.spilled_bytes(), 0);
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_cancelled_mid_spill() -> Result<()> {
+        let (sort, batches) = spill_test_sort()?;
+
+        // an input still running after its batches
+        let (tx, rx) = tokio::sync::mpsc::channel(batches.len());
+        for batch in &batches {
+            tx.try_send(Ok(batch.clone())).unwrap();
+        }
+        let join_handle = tokio::spawn(async move {
+            let _tx = tx;
+            futures::future::pending::<()>().await
+        });
+        let input = RecordBatchReceiverStream::create(&sort.schema(), rx, join_handle);
+
+        let manager = Arc::new(MemoryManager::new(3 * batch_memory_size(&batches[0])));
+        let dir = TempDir::new()?;
+        let disk_manager = Arc::new(DiskManager::new(vec![dir.path().to_owned()], None));
+        let metrics = ExecutionPlanMetricsSet::new();
+        let fut = external_sort(
+            input,
+            sort.expr().to_vec(),
+            BaselineMetrics::new(&metrics, 0),
+            manager.register_consumer("sort", Gauge::new()),
+            disk_manager.clone(),
+            SpillMetrics::new(&metrics, 0),
+            CancellationToken::new(),
+            CpuExecutor::default(),
+        );
+        let mut fut = fut.boxed();
+        assert_is_pending(&mut fut);
+
+        // three runs of three batches are spilled to the spill directory
+        let sizes = std::fs::read_dir(dir.path())?
+            .map(|entry| Ok(entry?.metadata()?.len() as usize))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(sizes.len(), 3);
+        assert_eq!(disk_manager.spilled_bytes(), sizes.iter().sum::<usize>());
+        let spilled_bytes = metrics
+            .clone_inner()
+            .sum(|m| m.value().name() == "spilled_bytes")
+            .unwrap()
+            .as_usize();
+        assert_eq!(spilled_bytes, disk_manager.spilled_bytes());
+
+        // cancelling the query deletes them
+        drop(fut);
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+        assert_eq!(disk_manager.spilled_bytes(), 0);
+        assert_eq!(manager.used(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_in_memory_pool() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let (mut sort, _) = spill_test_sort()?;
+        let manager = Arc::new(MemoryManager::new(usize::MAX));
+        sort.memory_manager = Some(manager.clone());
+        let sort = Arc::new(sort);
+
+        let result = collect(sort.clone(), task_ctx).await?;
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].num_rows(), 1000);
+        let metrics = sort.metrics().unwrap();
+        assert_eq!(
+            metrics
+                .sum(|m| m.value().name() == "spill_count")
+                .unwrap()
+                .as_usize(),
+            0
+        );
+        assert_eq!(manager.used(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_resources_exhausted() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let (mut sort, _) = spill_test_sort()?;
+        // not even a single input batch fits in the pool
+        let manager = Arc::new(MemoryManager::new(1));
+        sort.memory_manager = Some(manager.clone());
+
+        let err = collect(Arc::new(sort), task_ctx).await.unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::ResourcesExhausted(_)),
+            "unexpected error: {}",
+            err
+        );
+        assert_eq!(manager.used(), 0);
+
+        Ok(())
+    }
+
+    /// Sort on "date" and "user_id" of 10 batches of 100 rows sorted on
+    /// "date", with `rows_per_date` rows per date and shuffled values of
+    /// "user_id"
+    fn prefix_test_sort(rows_per_date: i32) -> Result<(SortExec, Vec<RecordBatch>)> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("date", DataType::Int32, false),
+            Field::new("user_id", DataType::Int32, false),
+        ]));
+        let batches = (0..10)
+            .map(|i| {
+                let rows = (0..100).map(|j| i * 100 + j);
+                RecordBatch::try_new(
+                    schema.clone(),
+                    vec![
+                        Arc::new(Int32Array::from_iter_values(
+                            rows.clone().map(|r| r / rows_per_date),
+                        )),
+                        Arc::new(Int32Array::from_iter_values(
+                            rows.map(|r| r * 37 % 1000),
+                        )),
+                    ],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        let sort_expr = |name: &str| -> Result<PhysicalSortExpr> {
+            Ok(PhysicalSortExpr {
+                expr: col(name, &schema)?,
+                options: SortOptions::default(),
+            })
+        };
+        let input = MemoryExec::try_new(&[batches.clone()], schema.clone(), None)?
+            .with_ordering(vec![sort_expr("date")?]);
+        let sort = SortExec::try_new(
+            vec![sort_expr("date")?, sort_expr("user_id")?],
+            Arc::new(input),
+        )?;
+        Ok((sort, batches))
+    }
+
+    #[tokio::test]
+    async fn test_sort_sorted_prefix() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let (mut sort, batches) = prefix_test_sort(30)?;
+        assert_eq!(sort.sorted_prefix_len(), 1);
+        assert_eq!(
+            displayable(&sort).one_line().to_string(),
+            "SortExec: [date@0 ASC,user_id@1 ASC], sorted_prefix=1"
+        );
+        // room for three of the ten input batches
+        let batch_size = batch_memory_size(&batches[0]);
+        let manager = Arc::new(MemoryManager::new(3 * batch_size));
+        sort.memory_manager = Some(manager.clone());
+        let sort = Arc::new(sort);
+
+        let result = collect(sort.clone(), task_ctx).await?;
+        // the runs are output as they end
+        assert!(result.len() > 1);
+        let rows = |batches: &[RecordBatch]| {
+            batches
+                .iter()
+                .flat_map(|batch| {
+                    let date = as_primitive_array::<Int32Type>(batch.column(0));
+                    let user_id = as_primitive_array::<Int32Type>(batch.column(1));
+                    (0..batch.num_rows())
+                        .map(|i| (date.value(i), user_id.value(i)))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut expected = rows(&batches);
+        expected.sort_unstable();
+        assert_eq!(rows(&result), expected);
+
+        // a run is buffered with at most the batch it ends in
+        let metrics = sort.metrics().unwrap().aggregate_by_name();
+        assert_eq!(metrics.output_rows().unwrap(), 1000);
+        assert_eq!(metrics.spill_count(), Some(0));
+        let mem_used = metrics.mem_used().unwrap();
+        assert!(mem_used > 0 && mem_used <= 2 * batch_size);
+        assert_eq!(manager.used(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_sorted_prefix_spills_to_disk() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        // a single run of all the rows
+        let (mut sort, batches) = prefix_test_sort(1000)?;
+        // room for three of the ten input batches
+        let manager = Arc::new(MemoryManager::new(3 * batch_memory_size(&batches[0])));
+        let dir = TempDir::new()?;
+        let disk_manager = Arc::new(DiskManager::new(vec![dir.path().to_owned()], None));
+        sort.memory_manager = Some(manager.clone());
+        sort.disk_manager = Some(disk_manager.clone());
+        let sort = Arc::new(sort);
+
+        let result = collect(sort.clone(), task_ctx).await?;
+        let user_ids = result
+            .iter()
+            .flat_map(|batch| as_primitive_array::<Int32Type>(batch.column(1)).values())
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(user_ids, (0..1000).collect::<Vec<_>>());
+
+        let metrics = sort.metrics().unwrap().aggregate_by_name();
+        assert_eq!(metrics.output_rows().unwrap(), 1000);
+        assert!(metrics.spill_count().unwrap() >= 3);
+        assert!(metrics.mem_used().unwrap() <= manager.pool_size());
+        assert_eq!(manager.used(), 0);
+        assert_eq!(disk_manager.spilled_bytes(), 0);
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_sort_sorted_prefix_resources_exhausted() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let (mut sort, _) = prefix_test_sort(30)?;
+        // not even a single input batch fits in the pool
+        let manager = Arc::new(MemoryManager::new(1));
+        sort.memory_manager = Some(manager.clone());
+
+        let err = collect(Arc::new(sort), task_ctx).await.unwrap_err();
+        assert!(
+            matches!(err, DataFusionError::ResourcesExhausted(_)),
+            "unexpected error: {}",
+            err
+        );
+        assert_eq!(manager.used(), 0);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -575,7 +1486,7 @@ mod tests {
             blocking_exec,
         )?);
 
-        let fut = collect(sort_exec);
+        let fut = collect(sort_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
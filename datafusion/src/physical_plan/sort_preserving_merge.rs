@@ -19,6 +19,7 @@
 
 use super::common::AbortOnDropMany;
 use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::memory::feed_input;
 use std::any::Any;
 use std::cmp::Ordering;
 use std::collections::VecDeque;
@@ -41,10 +42,15 @@ use futures::{Stream, StreamExt};
 use hashbrown::HashMap;
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::expressions::{
+    build_binary_compare, build_interval_compare, is_binary, is_interval,
+};
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    common::spawn_execution, expressions::PhysicalSortExpr, DisplayFormatType,
-    Distribution, ExecutionPlan, Partitioning, PhysicalExpr, RecordBatchStream,
-    SendableRecordBatchStream, Statistics, LambdaExecPlan,
+    common::spawn_execution, expressions::PhysicalSortExpr, fmt_with_statistics,
+    DisplayFormatType, Distribution, ExecutionPlan, LambdaExecPlan, Partitioning,
+    PhysicalExpr, RecordBatchStream, SendableRecordBatchStream, Statistics,
 };
 
 use serde::{Deserialize, Serialize};
@@ -95,8 +101,9 @@ impl SortPreservingMergeExec {
 
 #[async_trait]
 impl LambdaExecPlan for SortPreservingMergeExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!("SortPreservingMergeExec::feed_batches");
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -121,6 +128,14 @@ impl ExecutionPlan for SortPreservingMergeExec {
         Partitioning::UnknownPartitioning(1)
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        Some(&self.expr)
+    }
+
+    fn required_input_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        Some(&self.expr)
+    }
+
     fn required_child_distribution(&self) -> Distribution {
         Distribution::UnspecifiedDistribution
     }
@@ -145,7 +160,21 @@ impl ExecutionPlan for SortPreservingMergeExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "SortPreservingMergeExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
                 "SortPreservingMergeExec invalid partition {}",
@@ -153,7 +182,10 @@ impl ExecutionPlan for SortPreservingMergeExec {
             )));
         }
 
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(
+                context.progress_reporter("SortPreservingMergeExec", partition),
+            );
 
         let input_partitions = self.input.output_partitioning().partition_count();
         match input_partitions {
@@ -163,27 +195,35 @@ impl ExecutionPlan for SortPreservingMergeExec {
             )),
             1 => {
                 // bypass if there is only one partition to merge (no metrics in this case either)
-                self.input.execute(0).await
+                self.input.execute(0, context).await
             }
             _ => {
                 let (receivers, join_handles) = (0..input_partitions)
                     .into_iter()
                     .map(|part_i| {
                         let (sender, receiver) = mpsc::channel(1);
-                        let join_handle =
-                            spawn_execution(self.input.clone(), sender, part_i);
+                        let join_handle = spawn_execution(
+                            self.input.clone(),
+                            sender,
+                            part_i,
+                            context.clone(),
+                        );
                         (receiver, join_handle)
                     })
                     .unzip();
 
-                Ok(Box::pin(SortPreservingMergeStream::new(
-                    receivers,
-                    AbortOnDropMany(join_handles),
-                    self.schema(),
-                    &self.expr,
-                    self.target_batch_size,
-                    baseline_metrics,
-                )))
+                Ok(instrument_stream(
+                    self,
+                    partition,
+                    Box::pin(SortPreservingMergeStream::new(
+                        receivers,
+                        AbortOnDropMany(join_handles),
+                        self.schema(),
+                        &self.expr,
+                        self.target_batch_size,
+                        baseline_metrics,
+                    )),
+                ))
             }
         }
     }
@@ -198,6 +238,7 @@ impl ExecutionPlan for SortPreservingMergeExec {
                 let expr: Vec<String> = self.expr.iter().map(|e| e.to_string()).collect();
                 write!(f, "SortPreservingMergeExec: [{}]", expr.join(","))
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -315,8 +356,15 @@ impl SortKeyCursor {
 
         for (i, ((l, r), sort_options)) in zipped.enumerate() {
             if i >= cmp.len() {
-                // initialise comparators as potentially needed
-                cmp.push(arrow::array::build_compare(l.as_ref(), r.as_ref())?);
+                // initialise comparators as potentially needed, arrow can't
+                // compare binary values yet, nor intervals on their durations
+                cmp.push(if is_binary(l.data_type()) {
+                    build_binary_compare(l.as_ref(), r.as_ref())?
+                } else if is_interval(l.data_type()) {
+                    build_interval_compare(l.as_ref(), r.as_ref())?
+                } else {
+                    arrow::array::build_compare(l.as_ref(), r.as_ref())?
+                });
             }
 
             match (l.is_valid(self.cur_row), r.is_valid(other.cur_row)) {
@@ -352,7 +400,7 @@ struct RowIndex {
 }
 
 #[derive(Debug)]
-struct SortPreservingMergeStream {
+pub(crate) struct SortPreservingMergeStream {
     /// The schema of the RecordBatches yielded by this stream
     schema: SchemaRef,
 
@@ -391,7 +439,7 @@ struct SortPreservingMergeStream {
 }
 
 impl SortPreservingMergeStream {
-    fn new(
+    pub(crate) fn new(
         receivers: Vec<mpsc::Receiver<ArrowResult<RecordBatch>>>,
         _drop_helper: AbortOnDropMany<()>,
         schema: SchemaRef,
@@ -673,6 +721,7 @@ impl RecordBatchStream for SortPreservingMergeStream {
 #[cfg(test)]
 mod tests {
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::metrics::MetricValue;
     use crate::test::exec::{assert_strong_count_converges_to_zero, BlockingExec};
     use std::iter::FromIterator;
@@ -894,6 +943,7 @@ mod tests {
     }
 
     async fn _test_merge(partitions: &[Vec<RecordBatch>], exp: &[&str]) {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = partitions[0][0].schema();
         let sort = vec![
             PhysicalSortExpr {
@@ -908,7 +958,7 @@ mod tests {
         let exec = MemoryExec::try_new(partitions, schema, None).unwrap();
         let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec), 1024));
 
-        let collected = collect(merge).await.unwrap();
+        let collected = collect(merge, task_ctx).await.unwrap();
         assert_batches_eq!(exp, collected.as_slice());
     }
 
@@ -916,8 +966,9 @@ mod tests {
         input: Arc<dyn ExecutionPlan>,
         sort: Vec<PhysicalSortExpr>,
     ) -> RecordBatch {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let merge = Arc::new(SortPreservingMergeExec::new(sort, input, 1024));
-        let mut result = collect(merge).await.unwrap();
+        let mut result = collect(merge, task_ctx).await.unwrap();
         assert_eq!(result.len(), 1);
         result.remove(0)
     }
@@ -935,9 +986,10 @@ mod tests {
         src: Arc<dyn ExecutionPlan>,
         sort: Vec<PhysicalSortExpr>,
     ) -> RecordBatch {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let merge = Arc::new(CoalescePartitionsExec::new(src));
         let sort_exec = Arc::new(SortExec::try_new(sort, merge).unwrap());
-        let mut result = collect(sort_exec).await.unwrap();
+        let mut result = collect(sort_exec, task_ctx).await.unwrap();
         assert_eq!(result.len(), 1);
         result.remove(0)
     }
@@ -1094,6 +1146,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_partition_sort_streaming_input_output() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
 
         let sort = vec![
@@ -1113,7 +1166,7 @@ mod tests {
         let basic = basic_sort(input.clone(), sort.clone()).await;
 
         let merge = Arc::new(SortPreservingMergeExec::new(sort, input, 23));
-        let merged = collect(merge).await.unwrap();
+        let merged = collect(merge, task_ctx).await.unwrap();
 
         assert_eq!(merged.len(), 14);
 
@@ -1129,6 +1182,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_nulls() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2, 7, 9, 3]));
         let b: ArrayRef = Arc::new(StringArray::from_iter(vec![
             None,
@@ -1183,7 +1237,7 @@ mod tests {
         let exec = MemoryExec::try_new(&[vec![b1], vec![b2]], schema, None).unwrap();
         let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec), 1024));
 
-        let collected = collect(merge).await.unwrap();
+        let collected = collect(merge, task_ctx).await.unwrap();
         assert_eq!(collected.len(), 1);
 
         assert_batches_eq!(
@@ -1209,6 +1263,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_async() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
         let sort = vec![PhysicalSortExpr {
             expr: col("c12", &schema).unwrap(),
@@ -1223,7 +1278,7 @@ mod tests {
 
         for partition in 0..partition_count {
             let (mut sender, receiver) = mpsc::channel(1);
-            let mut stream = batches.execute(partition).await.unwrap();
+            let mut stream = batches.execute(partition, task_ctx.clone()).await.unwrap();
             let join_handle = tokio::spawn(async move {
                 while let Some(batch) = stream.next().await {
                     sender.send(batch).await.unwrap();
@@ -1271,6 +1326,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_merge_metrics() {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let a: ArrayRef = Arc::new(Int32Array::from(vec![1, 2]));
         let b: ArrayRef = Arc::new(StringArray::from_iter(vec![Some("a"), Some("c")]));
         let b1 = RecordBatch::try_from_iter(vec![("a", a), ("b", b)]).unwrap();
@@ -1287,7 +1343,7 @@ mod tests {
         let exec = MemoryExec::try_new(&[vec![b1], vec![b2]], schema, None).unwrap();
         let merge = Arc::new(SortPreservingMergeExec::new(sort, Arc::new(exec), 1024));
 
-        let collected = collect(merge.clone()).await.unwrap();
+        let collected = collect(merge.clone(), task_ctx).await.unwrap();
         let expected = vec![
             "+----+---+",
             "| a  | b |",
@@ -1326,6 +1382,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -1340,7 +1397,7 @@ mod tests {
             1,
         ));
 
-        let fut = collect(sort_preserving_merge_exec);
+        let fut = collect(sort_preserving_merge_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
@@ -0,0 +1,398 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Splits a physical plan into query stages at its exchange boundaries
+//! ([`RepartitionExec`] and [`CoalescePartitionsExec`]) so that each stage can
+//! be serialized and executed by a different lambda function.
+//!
+//! The exchange at the root of a stage is kept in that stage: the output of
+//! the stage is already partitioned the way its consumer expects. In the
+//! consuming stage, the exchange is replaced by a [`LambdaInputExec`] that must
+//! be fed the output partitions of the producing stage before execution.
+
+use std::sync::Arc;
+
+use arrow::record_batch::RecordBatch;
+use serde::{Deserialize, Serialize};
+
+use super::analyze::AnalyzeExec;
+use super::coalesce_partitions::CoalescePartitionsExec;
+use super::explain::ExplainExec;
+use super::lambda_input::{feed_lambda_input, replace_with_inputs, LambdaInputExec};
+use super::repartition::RepartitionExec;
+use super::{collect_partitioned, ExecutionPlan, Partitioning};
+use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+
+/// A part of a physical plan that runs once the output of the stages it
+/// depends on has been fed to it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QueryStage {
+    /// Identifier of the stage, unique in the plan it was split from
+    pub stage_id: usize,
+    /// The plan of the stage, with a [`LambdaInputExec`] in place of each
+    /// exchange it consumes
+    pub plan: Arc<dyn ExecutionPlan>,
+    /// Partitioning of the output of the stage
+    pub output_partitioning: Partitioning,
+    /// Identifiers of the stages whose output is consumed by this stage
+    pub dependencies: Vec<usize>,
+}
+
+impl QueryStage {
+    fn new(stage_id: usize, plan: Arc<dyn ExecutionPlan>) -> Self {
+        let mut dependencies = vec![];
+        collect_input_stages(&plan, &mut dependencies);
+        Self {
+            stage_id,
+            output_partitioning: plan.output_partitioning(),
+            plan,
+            dependencies,
+        }
+    }
+
+    /// Feed the output partitions of the stage `stage_id` to the
+    /// [`LambdaInputExec`] that stands for it in this stage
+    pub fn feed(
+        &mut self,
+        stage_id: usize,
+        partitions: Vec<Vec<RecordBatch>>,
+    ) -> Result<()> {
+        if !self.dependencies.contains(&stage_id) {
+            return Err(DataFusionError::Execution(format!(
+                "Stage {} does not depend on stage {}",
+                self.stage_id, stage_id
+            )));
+        }
+        self.plan = feed_lambda_input(
+            Arc::clone(&self.plan),
+            &stage_input_id(stage_id),
+            partitions,
+        )?;
+        Ok(())
+    }
+
+    /// Execute all the output partitions of the stage
+    pub async fn execute(
+        &self,
+        context: Arc<TaskContext>,
+    ) -> Result<Vec<Vec<RecordBatch>>> {
+        collect_partitioned(Arc::clone(&self.plan), context).await
+    }
+}
+
+/// Identity of the [`LambdaInputExec`] that stands for the output of the
+/// stage `stage_id`
+pub fn stage_input_id(stage_id: usize) -> String {
+    format!("stage-{}", stage_id)
+}
+
+/// Split `plan` into query stages at its [`RepartitionExec`] and
+/// [`CoalescePartitionsExec`] nodes.
+///
+/// The stages are returned in dependency order: a stage only depends on
+/// stages that come before it, and the last stage is the one that produces
+/// the output of `plan`.
+///
+/// An `EXPLAIN` or `EXPLAIN ANALYZE` plan is never split: the plan it
+/// describes runs in a single stage, executed where the query was submitted.
+pub fn split_into_stages(plan: Arc<dyn ExecutionPlan>) -> Result<Vec<QueryStage>> {
+    if is_explain(&plan) {
+        return Ok(vec![QueryStage::new(0, plan)]);
+    }
+
+    let mut stages = vec![];
+    let mut cut_exchange = |node: &Arc<dyn ExecutionPlan>| -> Result<Option<String>> {
+        if !is_exchange(node) {
+            return Ok(None);
+        }
+        let stage_id = stages.len();
+        stages.push(QueryStage::new(stage_id, node.clone()));
+        Ok(Some(stage_input_id(stage_id)))
+    };
+
+    // the root of the plan is never cut, even if it is an exchange
+    let children = plan.children();
+    let plan = if children.is_empty() {
+        plan
+    } else {
+        let children = children
+            .into_iter()
+            .map(|child| replace_with_inputs(child, &mut cut_exchange))
+            .collect::<Result<Vec<_>>>()?;
+        plan.with_new_children(children)?
+    };
+
+    stages.push(QueryStage::new(stages.len(), plan));
+    Ok(stages)
+}
+
+fn is_exchange(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let any = plan.as_any();
+    any.is::<RepartitionExec>() || any.is::<CoalescePartitionsExec>()
+}
+
+fn is_explain(plan: &Arc<dyn ExecutionPlan>) -> bool {
+    let any = plan.as_any();
+    any.is::<ExplainExec>() || any.is::<AnalyzeExec>()
+}
+
+/// Collect the stages whose output `plan` reads
+fn collect_input_stages(plan: &Arc<dyn ExecutionPlan>, stages: &mut Vec<usize>) {
+    if let Some(input) = plan.as_any().downcast_ref::<LambdaInputExec>() {
+        if let Some(stage_id) = input
+            .id()
+            .strip_prefix("stage-")
+            .and_then(|stage_id| stage_id.parse().ok())
+        {
+            stages.push(stage_id);
+        }
+    }
+    for child in plan.children() {
+        collect_input_stages(&child, stages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_sorted_eq;
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::{PlanType, StringifiedPlan};
+    use crate::physical_plan::expressions::{col, Avg};
+    use crate::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::{collect, displayable};
+    use crate::physical_plan::{AggregateExpr, PhysicalExpr};
+    use arrow::array::{Float64Array, StringArray, UInt32Array};
+    use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+
+    fn two_phase_aggregate() -> Result<Arc<dyn ExecutionPlan>> {
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("a", DataType::UInt32, false),
+            Field::new("b", DataType::Float64, false),
+        ]));
+        let batch = |a: Vec<u32>, b: Vec<f64>| {
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(UInt32Array::from(a)),
+                    Arc::new(Float64Array::from(b)),
+                ],
+            )
+        };
+        let input = Arc::new(MemoryExec::try_new(
+            &[
+                vec![batch(vec![2, 3, 4, 4], vec![1.0, 2.0, 3.0, 4.0])?],
+                vec![batch(vec![2, 3, 3, 4], vec![1.0, 2.0, 3.0, 4.0])?],
+            ],
+            schema.clone(),
+            None,
+        )?);
+
+        let groups: Vec<(Arc<dyn PhysicalExpr>, String)> =
+            vec![(col("a", &schema)?, "a".to_string())];
+        let aggregates: Vec<Arc<dyn AggregateExpr>> = vec![Arc::new(Avg::new(
+            col("b", &schema)?,
+            "AVG(b)".to_string(),
+            DataType::Float64,
+        ))];
+
+        let partial = Arc::new(HashAggregateExec::try_new(
+            AggregateMode::Partial,
+            groups.clone(),
+            aggregates.clone(),
+            input,
+            schema.clone(),
+        )?);
+        let repartition = Arc::new(RepartitionExec::try_new(
+            partial.clone(),
+            Partitioning::Hash(vec![col("a", &partial.schema())?], 3),
+        )?);
+        let final_groups: Vec<(Arc<dyn PhysicalExpr>, String)> = groups
+            .iter()
+            .map(|(_, name)| Ok((col(name, &partial.schema())?, name.clone())))
+            .collect::<Result<_>>()?;
+        Ok(Arc::new(HashAggregateExec::try_new(
+            AggregateMode::FinalPartitioned,
+            final_groups,
+            aggregates,
+            repartition,
+            schema,
+        )?))
+    }
+
+    #[tokio::test]
+    async fn split_two_phase_aggregate() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let plan = two_phase_aggregate()?;
+        let single_process = collect(plan.clone(), task_ctx.clone()).await?;
+
+        let stages = split_into_stages(plan)?;
+        assert_eq!(stages.len(), 2);
+        assert_eq!(stages[0].stage_id, 0);
+        assert!(stages[0].dependencies.is_empty());
+        assert!(stages[0].plan.as_any().is::<RepartitionExec>());
+        assert_eq!(stages[0].output_partitioning.partition_count(), 3);
+        assert_eq!(stages[1].stage_id, 1);
+        assert_eq!(stages[1].dependencies, vec![0]);
+        assert_eq!(stages[1].output_partitioning.partition_count(), 3);
+        let final_input = stages[1].plan.children()[0].clone();
+        let final_input = final_input
+            .as_any()
+            .downcast_ref::<LambdaInputExec>()
+            .unwrap();
+        assert_eq!(final_input.id(), "stage-0");
+        assert_eq!(
+            format!("{:?}", final_input.output_partitioning()),
+            "UnknownPartitioning(3)"
+        );
+
+        // the consuming stage is shipped to another lambda function
+        let json = serde_json::to_string(&stages[1]).unwrap();
+        let mut final_stage: QueryStage = serde_json::from_str(&json).unwrap();
+        assert!(final_stage.plan.as_any().is::<HashAggregateExec>());
+        let err = final_stage.execute(task_ctx.clone()).await.unwrap_err();
+        assert_eq!(
+            "Execution error: The input stage-0 has not been fed",
+            err.to_string()
+        );
+
+        let partitions = stages[0].execute(task_ctx.clone()).await?;
+        assert_eq!(partitions.len(), 3);
+        assert!(final_stage.feed(1, partitions.clone()).is_err());
+        final_stage.feed(0, partitions)?;
+        let result = final_stage
+            .execute(task_ctx)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let expected = vec![
+            "+---+--------------------+",
+            "| a | AVG(b)             |",
+            "+---+--------------------+",
+            "| 2 | 1                  |",
+            "| 3 | 2.3333333333333335 |",
+            "| 4 | 3.6666666666666665 |",
+            "+---+--------------------+",
+        ];
+        assert_batches_sorted_eq!(expected, &single_process);
+        assert_batches_sorted_eq!(expected, &result);
+
+        Ok(())
+    }
+
+    #[test]
+    fn stages_roundtrip_through_json() -> Result<()> {
+        let stages = split_into_stages(two_phase_aggregate()?)?;
+        let json = serde_json::to_string(&stages).unwrap();
+        let roundtrip: Vec<QueryStage> = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtrip.len(), stages.len());
+        for (stage, roundtrip) in stages.iter().zip(&roundtrip) {
+            assert_eq!(stage.stage_id, roundtrip.stage_id);
+            assert_eq!(stage.dependencies, roundtrip.dependencies);
+            assert_eq!(stage.plan.schema(), roundtrip.plan.schema());
+            assert_eq!(
+                format!("{:?}", stage.output_partitioning),
+                format!("{:?}", roundtrip.output_partitioning)
+            );
+        }
+        assert!(roundtrip[0].plan.as_any().is::<RepartitionExec>());
+        let input = roundtrip[1].plan.children()[0].clone();
+        let input = input.as_any().downcast_ref::<LambdaInputExec>().unwrap();
+        assert_eq!(input.id(), stage_input_id(0));
+        Ok(())
+    }
+
+    fn explain_schema() -> SchemaRef {
+        Arc::new(Schema::new(vec![
+            Field::new("plan_type", DataType::Utf8, false),
+            Field::new("plan", DataType::Utf8, false),
+        ]))
+    }
+
+    /// The text of the plans output by an `EXPLAIN` plan
+    async fn explained_plans(
+        plan: Arc<dyn ExecutionPlan>,
+        context: Arc<TaskContext>,
+    ) -> Result<Vec<String>> {
+        let batches = collect(plan, context).await?;
+        Ok(batches
+            .iter()
+            .flat_map(|batch| {
+                let plans = batch
+                    .column(1)
+                    .as_any()
+                    .downcast_ref::<StringArray>()
+                    .unwrap();
+                plans
+                    .iter()
+                    .flatten()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>()
+            })
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn explain_stage_with_lambda_input() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let stages = split_into_stages(two_phase_aggregate()?)?;
+        let text = displayable(stages[1].plan.as_ref()).indent().to_string();
+        let plan: Arc<dyn ExecutionPlan> = Arc::new(ExplainExec::new(
+            explain_schema(),
+            vec![StringifiedPlan::new(PlanType::FinalPhysicalPlan, text)],
+            false,
+        ));
+
+        let json = serde_json::to_string(&plan).unwrap();
+        let plan: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+        let stages = split_into_stages(plan)?;
+        assert_eq!(stages.len(), 1);
+        assert!(stages[0].dependencies.is_empty());
+
+        let plans = explained_plans(stages[0].plan.clone(), task_ctx).await?;
+        assert_eq!(plans.len(), 1);
+        assert!(
+            plans[0].contains("LambdaInputExec: id=stage-0, partitions=3, fed=false"),
+            "{}",
+            plans[0]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn explain_analyze_runs_in_one_stage() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let plan = Arc::new(AnalyzeExec::new(
+            false,
+            two_phase_aggregate()?,
+            explain_schema(),
+        ));
+
+        let stages = split_into_stages(plan)?;
+        assert_eq!(stages.len(), 1);
+        assert!(stages[0].dependencies.is_empty());
+
+        let plans = explained_plans(stages[0].plan.clone(), task_ctx).await?;
+        assert!(plans[0].contains("RepartitionExec"), "{}", plans[0]);
+        assert!(!plans[0].contains("LambdaInputExec"), "{}", plans[0]);
+        Ok(())
+    }
+}
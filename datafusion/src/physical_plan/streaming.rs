@@ -0,0 +1,260 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Execution plan for reading record batches that arrive over async channels
+
+use std::any::Any;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use super::expressions::PhysicalSortExpr;
+use super::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
+};
+use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
+use arrow::datatypes::SchemaRef;
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+use tokio::sync::mpsc::Receiver;
+use tokio_stream::wrappers::ReceiverStream;
+
+use serde::{Deserialize, Serialize};
+
+/// Receivers of a streaming source, one per partition. A receiver is taken
+/// out of its slot when the partition is executed.
+pub type PartitionReceivers = Arc<Mutex<Vec<Option<Receiver<RecordBatch>>>>>;
+
+/// Execution plan that yields the record batches of each partition as they
+/// are received from a channel. A partition ends when all the senders of its
+/// channel are dropped.
+///
+/// Each partition can only be executed once since the batches are consumed.
+#[derive(Serialize, Deserialize)]
+pub struct StreamingTableExec {
+    /// The channels to read from
+    #[serde(skip)]
+    receivers: PartitionReceivers,
+    /// Number of partitions of the source
+    num_partitions: usize,
+    /// Schema before projection is applied
+    schema: SchemaRef,
+    /// Schema after projection is applied
+    projected_schema: SchemaRef,
+    /// Optional projection
+    projection: Option<Vec<usize>>,
+    /// Sort order of the batches within each partition, if known
+    ordering: Option<Vec<PhysicalSortExpr>>,
+}
+
+impl fmt::Debug for StreamingTableExec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "partitions: {}", self.num_partitions)?;
+        write!(f, "schema: {:?}", self.projected_schema)?;
+        write!(f, "projection: {:?}", self.projection)
+    }
+}
+
+impl StreamingTableExec {
+    /// Create a new execution plan reading from `receivers`. The provided
+    /// `schema` should not have the projection applied, while `ordering`
+    /// refers to the projected schema.
+    pub fn try_new(
+        receivers: PartitionReceivers,
+        schema: SchemaRef,
+        projection: Option<Vec<usize>>,
+        ordering: Option<Vec<PhysicalSortExpr>>,
+    ) -> Result<Self> {
+        let projected_schema = match &projection {
+            Some(columns) => {
+                if let Some(i) = columns.iter().find(|i| **i >= schema.fields().len()) {
+                    return Err(DataFusionError::Internal(format!(
+                        "Projection index {} out of range",
+                        i
+                    )));
+                }
+                Arc::new(schema.project(columns)?)
+            }
+            None => Arc::clone(&schema),
+        };
+        let num_partitions = receivers.lock().unwrap().len();
+        Ok(Self {
+            receivers,
+            num_partitions,
+            schema,
+            projected_schema,
+            projection,
+            ordering,
+        })
+    }
+
+    /// Get the projection
+    pub fn projection(&self) -> &Option<Vec<usize>> {
+        &self.projection
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for StreamingTableExec {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "StreamingTableExec reads its channels and can not be fed".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "streaming_table_exec")]
+impl ExecutionPlan for StreamingTableExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.projected_schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.num_partitions)
+    }
+
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.ordering.as_deref()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        // this is a leaf node and has no children
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::Internal(format!(
+            "Children cannot be replaced in {:?}",
+            self
+        )))
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "StreamingTableExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let receiver = self
+            .receivers
+            .lock()
+            .unwrap()
+            .get_mut(partition)
+            .and_then(Option::take)
+            .ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "Partition {} of the streaming source is not available, \
+                     it may have already been consumed",
+                    partition
+                ))
+            })?;
+
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(ReceiverRecordBatchStream {
+                inner: ReceiverStream::new(receiver),
+                schema: self.projected_schema.clone(),
+                projection: self.projection.clone(),
+            }),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "StreamingTableExec: partitions={}", self.num_partitions)?;
+                if let Some(ordering) = &self.ordering {
+                    let ordering: Vec<String> =
+                        ordering.iter().map(|e| e.to_string()).collect();
+                    write!(f, ", ordering=[{}]", ordering.join(", "))?;
+                }
+                Ok(())
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        // the data has not arrived yet, nothing is known about it
+        Statistics::default()
+    }
+}
+
+/// Stream of the batches received on a channel, with the projection applied
+struct ReceiverRecordBatchStream {
+    inner: ReceiverStream<RecordBatch>,
+    /// Schema after the projection is applied
+    schema: SchemaRef,
+    projection: Option<Vec<usize>>,
+}
+
+impl Stream for ReceiverRecordBatchStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx).map(|batch| {
+            batch.map(|batch| match &self.projection {
+                Some(columns) => RecordBatch::try_new(
+                    self.schema.clone(),
+                    columns.iter().map(|i| batch.column(*i).clone()).collect(),
+                ),
+                None => Ok(batch),
+            })
+        })
+    }
+}
+
+impl RecordBatchStream for ReceiverRecordBatchStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
@@ -518,6 +518,104 @@ pub fn rtrim<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
     }
 }
 
+/// Converts the pattern of SIMILAR TO to a regular expression matching the same strings, with the character escaping the next one (a backslash by default, none if empty).
+/// similar_to_escape('a%(b|c)_') = '^(?:a.*(?:b|c).)$'
+pub fn similar_to_escape<T: StringOffsetSizeTrait>(
+    args: &[ArrayRef],
+) -> Result<ArrayRef> {
+    let pattern_array = downcast_string_arg!(args[0], "pattern", T);
+
+    let result = match args.len() {
+        1 => pattern_array
+            .iter()
+            .map(|pattern| {
+                pattern
+                    .map(|pattern| similar_to_regex(pattern, Some('\\')))
+                    .transpose()
+            })
+            .collect::<Result<GenericStringArray<T>>>()?,
+        2 => {
+            let escape_array = downcast_string_arg!(args[1], "escape", T);
+            pattern_array
+                .iter()
+                .zip(escape_array.iter())
+                .map(|(pattern, escape)| match (pattern, escape) {
+                    (Some(pattern), Some(escape)) => {
+                        let mut chars = escape.chars();
+                        match (chars.next(), chars.next()) {
+                            (escape, None) => {
+                                similar_to_regex(pattern, escape).map(Some)
+                            }
+                            _ => Err(DataFusionError::Execution(format!(
+                                "The escape string of SIMILAR TO must be empty or one character, found '{}'",
+                                escape
+                            ))),
+                        }
+                    }
+                    _ => Ok(None),
+                })
+                .collect::<Result<GenericStringArray<T>>>()?
+        }
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "similar_to_escape was called with {} arguments. It requires at least 1 and at most 2.",
+                other
+            )))
+        }
+    };
+
+    Ok(Arc::new(result) as ArrayRef)
+}
+
+/// Translate the SIMILAR TO `pattern` to a regular expression: `%` and `_`
+/// are the wildcards of LIKE, the other metacharacters of regular expressions
+/// are kept except `.`, `^` and `$`, which match themselves, and the groups
+/// don't capture. The characters of bracket expressions are kept as well.
+fn similar_to_regex(pattern: &str, escape: Option<char>) -> Result<String> {
+    let mut regex = String::from("^(?:");
+    let mut chars = pattern.chars();
+    let mut in_brackets = false;
+    while let Some(c) = chars.next() {
+        if Some(c) == escape {
+            let c = chars.next().ok_or_else(|| {
+                DataFusionError::Execution(format!(
+                    "The SIMILAR TO pattern '{}' must not end with the escape character",
+                    pattern
+                ))
+            })?;
+            push_regex_literal(&mut regex, c);
+            continue;
+        }
+        match c {
+            ']' if in_brackets => {
+                in_brackets = false;
+                regex.push(c);
+            }
+            '\\' | '[' if in_brackets => push_regex_literal(&mut regex, c),
+            _ if in_brackets => regex.push(c),
+            '[' => {
+                in_brackets = true;
+                regex.push(c);
+            }
+            '%' => regex.push_str(".*"),
+            '_' => regex.push('.'),
+            '(' => regex.push_str("(?:"),
+            '.' | '^' | '$' | '\\' => push_regex_literal(&mut regex, c),
+            c => regex.push(c),
+        }
+    }
+    regex.push_str(")$");
+    Ok(regex)
+}
+
+/// Append to `regex` the character `c`, escaped if it's a metacharacter
+fn push_regex_literal(regex: &mut String, c: char) {
+    if "\\.+*?()|[]{}^$#&-~".contains(c) {
+        regex.push('\\');
+    }
+    regex.push(c);
+}
+
 /// Splits string at occurrences of delimiter and returns the n'th field (counting from one).
 /// split_part('abc~@~def~@~ghi', '~@~', 2) = 'def'
 pub fn split_part<T: StringOffsetSizeTrait>(args: &[ArrayRef]) -> Result<ArrayRef> {
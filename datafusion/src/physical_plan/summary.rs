@@ -0,0 +1,166 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Structured summaries of physical plans, see [`PlanSummary`]
+//!
+//! The operator of a node is the name under which it is serialized, and its
+//! parameters are its serialized fields, but its inputs and the fields left
+//! out of its [fingerprint](super::fingerprint), plus its one line display
+//! under the `display` parameter.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use super::fingerprint::{EXCLUDED_FIELDS, PLAN_TAG};
+use super::{DisplayFormatType, ExecutionPlan};
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::PlanSummary;
+
+/// The parameter holding the one line display of an operator
+pub const DISPLAY_PARAMETER: &str = "display";
+
+/// Returns the structured summary of `plan`
+pub fn to_summary(plan: &dyn ExecutionPlan) -> Result<PlanSummary> {
+    let value = serde_json::to_value(plan).map_err(|e| {
+        DataFusionError::Internal(format!("Cannot serialize the physical plan: {}", e))
+    })?;
+    let mut fields = match value {
+        Value::Object(fields) => fields,
+        other => {
+            return Err(DataFusionError::Internal(format!(
+                "Physical plan serialized as {}",
+                other
+            )))
+        }
+    };
+    let operator = match fields.remove(PLAN_TAG) {
+        Some(Value::String(operator)) => operator,
+        _ => {
+            return Err(DataFusionError::Internal(
+                "Physical plan serialized without its name".to_owned(),
+            ))
+        }
+    };
+
+    let children = plan
+        .children()
+        .iter()
+        .map(|child| to_summary(child.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    let mut summary = PlanSummary::new(operator, children)
+        .with_parameter(DISPLAY_PARAMETER, OneLine(plan).to_string());
+    for (name, value) in fields {
+        if EXCLUDED_FIELDS.contains(&name.as_str()) || is_plan(&value) {
+            continue;
+        }
+        let value = match value {
+            Value::String(value) => value,
+            other => other.to_string(),
+        };
+        summary = summary.with_parameter(&name, value);
+    }
+    Ok(summary)
+}
+
+/// Whether `value` is a serialized plan, or a list of them
+fn is_plan(value: &Value) -> bool {
+    match value {
+        Value::Object(fields) => fields.contains_key(PLAN_TAG),
+        Value::Array(values) => !values.is_empty() && values.iter().all(is_plan),
+        _ => false,
+    }
+}
+
+/// The display of an operator, without its inputs
+struct OneLine<'a>(&'a dyn ExecutionPlan);
+
+impl<'a> fmt::Display for OneLine<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt_as(DisplayFormatType::Default, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::context::{ExecutionConfig, ExecutionContext};
+    use crate::test;
+    use std::sync::Arc;
+
+    async fn plan(sql: &str) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut ctx = ExecutionContext::with_config(
+            ExecutionConfig::new().with_target_partitions(1),
+        );
+        for name in &["a", "b", "c", "d"] {
+            ctx.register_table(*name, test::table_with_sequence(1, 10)?)?;
+        }
+        let plan = ctx.create_logical_plan(sql)?;
+        ctx.create_physical_plan(&ctx.optimize(&plan)?).await
+    }
+
+    /// The nodes of `summary`, in the order of a depth first walk
+    fn nodes(summary: &PlanSummary) -> Vec<&PlanSummary> {
+        let mut nodes = vec![summary];
+        for child in &summary.children {
+            nodes.extend(self::nodes(child));
+        }
+        nodes
+    }
+
+    #[tokio::test]
+    async fn summary() -> Result<()> {
+        let plan = plan(
+            "SELECT a.i FROM a \
+             JOIN b ON a.i = b.i \
+             JOIN c ON b.i = c.i \
+             JOIN d ON c.i = d.i \
+             WHERE a.i > 3",
+        )
+        .await?;
+        let summary = plan.to_summary()?;
+        assert_eq!("ProjectionExec", summary.operator);
+        assert_eq!(
+            "ProjectionExec: expr=[i@0 as i]",
+            summary.parameters[DISPLAY_PARAMETER]
+        );
+
+        let nodes = nodes(&summary);
+        let count = |operator: &str| {
+            nodes
+                .iter()
+                .filter(|node| node.operator == operator)
+                .count()
+        };
+        assert_eq!(3, count("HashJoinExec"));
+        assert_eq!(4, count("MemoryExec"));
+
+        // the inputs are children, not parameters
+        let join = nodes
+            .iter()
+            .find(|node| node.operator == "HashJoinExec")
+            .unwrap();
+        assert_eq!(2, join.children.len());
+        assert_eq!("Inner", join.parameters["join_type"]);
+        assert!(join.parameters.contains_key("on"));
+        assert!(!join.parameters.contains_key("left"));
+        assert!(!join.parameters.contains_key("metrics"));
+
+        assert_eq!(summary, PlanSummary::from_json(&summary.to_json()?)?);
+        Ok(())
+    }
+}
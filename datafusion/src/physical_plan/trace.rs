@@ -0,0 +1,264 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Instrumentation of the execution of the operators: the errors of their
+//! output streams are annotated with the operator and the partition which
+//! failed, and their execution is traced with the
+//! [`tracing`](https://docs.rs/tracing) crate, enabled by the `tracing`
+//! feature.
+//!
+//! An error is annotated by the first operator it goes through, the one
+//! which raised it, as a [`DataFusionError::Context`] such as
+//! `FilterExec: CAST(c1@0 AS Int32) > 1 in partition 3: Arrow error: ...`.
+//!
+//! The `execute` method of each operator runs in a span named after the
+//! operator, with a `partition` field, at the `DEBUG` level. The same span is
+//! entered whenever its output stream is polled, so that the events of the
+//! operators and of their inputs are nested under it. Events are emitted at
+//! the `DEBUG` level when an operator spills to disk or stops reading its
+//! input early, and at the `ERROR` level when it fails.
+//!
+//! Without the feature, or without a subscriber interested in these spans,
+//! the spans are not entered and the events cost nothing.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use arrow::datatypes::SchemaRef;
+use arrow::error::{ArrowError, Result as ArrowResult};
+use arrow::record_batch::RecordBatch;
+use futures::{Stream, StreamExt};
+
+use super::display::DisplayableExecutionPlan;
+use super::{ExecutionPlan, RecordBatchStream, SendableRecordBatchStream};
+use crate::error::DataFusionError;
+
+/// Run `future`, the execution of the query `query_id`, in a root span named
+/// `query` with a `query_id` field, so that the spans of its operators can be
+/// correlated across the processes running the stages of the query.
+pub async fn in_query_span<F: Future>(query_id: &str, future: F) -> F::Output {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+        future
+            .instrument(tracing::info_span!("query", query_id))
+            .await
+    }
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = query_id;
+        future.await
+    }
+}
+
+/// Instrument `stream`, the output of the partition `partition` of `plan`:
+/// annotate its errors with `plan` and `partition`, and enter the current
+/// span, the span of the `execute` method of `plan`, whenever it is polled
+pub(crate) fn instrument_stream(
+    plan: &dyn ExecutionPlan,
+    partition: usize,
+    stream: SendableRecordBatchStream,
+) -> SendableRecordBatchStream {
+    let context = format!(
+        "{} in partition {}",
+        DisplayableExecutionPlan::new(plan).one_line(),
+        partition
+    );
+    Box::pin(InstrumentedStream {
+        inner: stream,
+        context,
+        #[cfg(feature = "tracing")]
+        span: Some(tracing::Span::current()).filter(|span| !span.is_disabled()),
+    })
+}
+
+/// Record the spill of `spilled_bytes` bytes to disk by the current operator
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn spill(spilled_bytes: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(spilled_bytes, "spill");
+}
+
+/// Record that the current operator stops reading its input, having
+/// produced `output_rows` rows
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn early_termination(output_rows: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(output_rows, "early termination");
+}
+
+/// Whether `e` was already annotated by an operator
+fn is_annotated(e: &ArrowError) -> bool {
+    match e {
+        ArrowError::ExternalError(source) => {
+            match source.downcast_ref::<DataFusionError>() {
+                Some(DataFusionError::Context(_, _)) => true,
+                Some(DataFusionError::ArrowError(e)) => is_annotated(e),
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Stream annotating the errors of its input with `context`, and entering
+/// `span` whenever it is polled
+struct InstrumentedStream {
+    inner: SendableRecordBatchStream,
+    context: String,
+    #[cfg(feature = "tracing")]
+    span: Option<tracing::Span>,
+}
+
+impl Stream for InstrumentedStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        #[cfg(feature = "tracing")]
+        let _entered = this.span.as_ref().map(|span| span.enter());
+        match this.inner.poll_next_unpin(cx) {
+            Poll::Ready(Some(Err(e))) if !is_annotated(&e) => {
+                let e = DataFusionError::from(e).context(this.context.clone());
+                #[cfg(feature = "tracing")]
+                tracing::error!(error = %e, "execution failed");
+                Poll::Ready(Some(Err(e.into_arrow_external_error())))
+            }
+            poll => poll,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl RecordBatchStream for InstrumentedStream {
+    fn schema(&self) -> SchemaRef {
+        self.inner.schema()
+    }
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    use arrow::datatypes::{DataType, Field, Schema};
+    use arrow::record_batch::RecordBatch;
+    use futures::StreamExt;
+    use tracing::field::{Field as TracingField, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use super::*;
+    use crate::error::Result;
+    use crate::execution::context::ExecutionContext;
+    use crate::logical_plan::Operator;
+    use crate::physical_plan::expressions::{binary, col, lit};
+    use crate::physical_plan::filter::FilterExec;
+    use crate::physical_plan::memory::MemoryExec;
+    use crate::physical_plan::ExecutionPlan;
+    use crate::scalar::ScalarValue;
+
+    /// Subscriber recording the names of the spans and the Debug format of
+    /// their `partition` field
+    #[derive(Default)]
+    struct RecordingSubscriber {
+        next_id: AtomicU64,
+        spans: Arc<Mutex<Vec<(String, Option<String>)>>>,
+    }
+
+    struct PartitionVisitor(Option<String>);
+
+    impl Visit for PartitionVisitor {
+        fn record_debug(&mut self, field: &TracingField, value: &dyn std::fmt::Debug) {
+            if field.name() == "partition" {
+                self.0 = Some(format!("{:?}", value));
+            }
+        }
+    }
+
+    impl Subscriber for RecordingSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut visitor = PartitionVisitor(None);
+            span.record(&mut visitor);
+            self.spans
+                .lock()
+                .unwrap()
+                .push((span.metadata().name().to_owned(), visitor.0));
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::SeqCst) + 1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, _event: &Event<'_>) {}
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn operators_run_in_spans() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(arrow::array::Int32Array::from(vec![1, 2, 3]))],
+        )?;
+        let input = Arc::new(MemoryExec::try_new(
+            &[vec![batch.clone()], vec![batch]],
+            schema.clone(),
+            None,
+        )?);
+        let predicate = binary(
+            col("a", &schema)?,
+            Operator::Gt,
+            lit(ScalarValue::Int32(Some(1))),
+            &schema,
+        )?;
+        let filter = FilterExec::try_new(predicate, input)?;
+
+        let subscriber = RecordingSubscriber::default();
+        let spans = subscriber.spans.clone();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let stream = in_query_span("query-1", filter.execute(1, task_ctx)).await?;
+        let batches = stream.collect::<Vec<_>>().await;
+        assert_eq!(batches.len(), 1);
+
+        let spans = spans.lock().unwrap().clone();
+        let expected = vec![
+            ("query".to_owned(), None),
+            ("FilterExec".to_owned(), Some("1".to_owned())),
+            ("MemoryExec".to_owned(), Some("1".to_owned())),
+        ];
+        assert_eq!(spans, expected);
+        Ok(())
+    }
+}
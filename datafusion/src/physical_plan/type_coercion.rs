@@ -85,17 +85,24 @@ pub fn data_types(
         return Ok(current_types.to_vec());
     }
 
-    for valid_types in valid_types {
-        if let Some(types) = maybe_data_types(&valid_types, current_types) {
-            return Ok(types);
-        }
-    }
-
-    // none possible -> Error
-    Err(DataFusionError::Plan(format!(
-        "Coercion from {:?} to the signature {:?} failed.",
-        current_types, &signature.type_signature
-    )))
+    // the valid types requiring the fewest casts, the first ones on a tie
+    valid_types
+        .iter()
+        .filter_map(|valid_types| maybe_data_types(valid_types, current_types))
+        .min_by_key(|types| {
+            types
+                .iter()
+                .zip(current_types)
+                .filter(|(new_type, current_type)| new_type != current_type)
+                .count()
+        })
+        .ok_or_else(|| {
+            // none possible -> Error
+            DataFusionError::Plan(format!(
+                "Coercion from {:?} to the signature {:?} failed.",
+                current_types, &signature.type_signature
+            ))
+        })
 }
 
 fn get_valid_types(
@@ -152,7 +159,7 @@ fn maybe_data_types(
     for (i, valid_type) in valid_types.iter().enumerate() {
         let current_type = &current_types[i];
 
-        if current_type == valid_type {
+        if current_type == valid_type || same_timestamp_unit(valid_type, current_type) {
             new_type.push(current_type.clone())
         } else {
             // attempt to coerce
@@ -167,6 +174,17 @@ fn maybe_data_types(
     Some(new_type)
 }
 
+/// Whether `current_type` is a timestamp with a time zone of the unit of the
+/// timestamp without one `valid_type`: its time zone is kept, as it doesn't
+/// change the instants of the timestamps
+fn same_timestamp_unit(valid_type: &DataType, current_type: &DataType) -> bool {
+    matches!(
+        (valid_type, current_type),
+        (DataType::Timestamp(valid_unit, None), DataType::Timestamp(unit, Some(_)))
+            if valid_unit == unit
+    )
+}
+
 /// Return true if a value of type `type_from` can be coerced
 /// (losslessly converted) into a value of `type_to`
 ///
@@ -202,6 +220,8 @@ pub fn can_coerce_from(type_into: &DataType, type_from: &DataType) -> bool {
                 | Float64
         ),
         Timestamp(TimeUnit::Nanosecond, None) => matches!(type_from, Timestamp(_, None)),
+        Binary => matches!(type_from, Binary | FixedSizeBinary(_)),
+        LargeBinary => matches!(type_from, Binary | LargeBinary | FixedSizeBinary(_)),
         Utf8 | LargeUtf8 => true,
         _ => false,
     }
@@ -218,6 +238,7 @@ mod tests {
 
     #[test]
     fn test_maybe_data_types() {
+        let tz = || Some("America/New_York".to_owned());
         // this vec contains: arg1, arg2, expected result
         let cases = vec![
             // 2 entries, same values
@@ -246,6 +267,27 @@ mod tests {
                 vec![DataType::Boolean, DataType::UInt16],
                 Some(vec![DataType::Boolean, DataType::UInt32]),
             ),
+            // the time zone of a timestamp is kept
+            (
+                vec![
+                    DataType::Utf8,
+                    DataType::Timestamp(TimeUnit::Nanosecond, None),
+                ],
+                vec![
+                    DataType::Utf8,
+                    DataType::Timestamp(TimeUnit::Nanosecond, tz()),
+                ],
+                Some(vec![
+                    DataType::Utf8,
+                    DataType::Timestamp(TimeUnit::Nanosecond, tz()),
+                ]),
+            ),
+            // but not its unit
+            (
+                vec![DataType::Timestamp(TimeUnit::Nanosecond, None)],
+                vec![DataType::Timestamp(TimeUnit::Second, tz())],
+                None,
+            ),
         ];
 
         for case in cases {
@@ -404,4 +446,26 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_data_types_fewest_casts() -> Result<()> {
+        let signature = Signature::one_of(
+            vec![
+                TypeSignature::Exact(vec![DataType::Float64, DataType::Float64]),
+                TypeSignature::Exact(vec![DataType::Int64, DataType::Float64]),
+            ],
+            Volatility::Immutable,
+        );
+
+        // both variants are valid, the second one casts a single argument
+        let types = data_types(&[DataType::Int64, DataType::Float32], &signature)?;
+        assert_eq!(types, vec![DataType::Int64, DataType::Float64]);
+
+        // on a tie, the first variant is chosen
+        let types = data_types(&[DataType::Int32, DataType::Float32], &signature)?;
+        assert_eq!(types, vec![DataType::Float64, DataType::Float64]);
+
+        assert!(data_types(&[DataType::Utf8, DataType::Float64], &signature).is_err());
+        Ok(())
+    }
 }
@@ -18,25 +18,27 @@
 //! This module contains functions and structs supporting user-defined aggregate functions.
 
 use fmt::{Debug, Formatter};
-// use std::any::Any;
+use std::any::Any;
 use std::fmt;
 
-// use arrow::{
-//     datatypes::Field,
-//     datatypes::{DataType, Schema},
-// };
+use arrow::{
+    datatypes::Field,
+    datatypes::{DataType, Schema},
+};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 
-// use crate::physical_plan::PhysicalExpr;
-// use crate::error::Result;
+use crate::error::Result;
 use crate::logical_plan::Expr;
+use crate::physical_plan::PhysicalExpr;
 
 use super::{
     aggregates::AccumulatorFunctionImplementation,
     aggregates::StateTypeFunction,
-    // expressions::format_state_name,
+    expressions::format_state_name,
     functions::{ReturnTypeFunction, Signature},
-    // type_coercion::coerce,
-    // Accumulator, AggregateExpr,
+    type_coercion::coerce,
+    Accumulator, AggregateExpr,
 };
 use std::sync::Arc;
 
@@ -54,6 +56,9 @@ pub struct AggregateUDF {
     pub accumulator: AccumulatorFunctionImplementation,
     /// the accumulator's state's description as a function of the return type
     pub state_type: StateTypeFunction,
+    /// the names and nullability of the fields of the accumulator's state, which are
+    /// otherwise named after their position and nullable
+    pub state_fields: Option<Arc<Vec<Field>>>,
 }
 
 impl Debug for AggregateUDF {
@@ -98,9 +103,25 @@ impl AggregateUDF {
             return_type: return_type.clone(),
             accumulator: accumulator.clone(),
             state_type: state_type.clone(),
+            state_fields: None,
         }
     }
 
+    /// Declares the fields of the accumulator's state, replacing its state type.
+    /// The partial states of the UDAF are exchanged between the stages of a plan
+    /// as columns of these types, in this order.
+    pub fn with_state_fields(mut self, state_fields: Vec<Field>) -> Self {
+        let state_type = Arc::new(
+            state_fields
+                .iter()
+                .map(|field| field.data_type().clone())
+                .collect::<Vec<_>>(),
+        );
+        self.state_type = Arc::new(move |_| Ok(state_type.clone()));
+        self.state_fields = Some(Arc::new(state_fields));
+        self
+    }
+
     /// creates a logical expression with a call of the UDAF
     /// This utility allows using the UDAF without requiring access to the registry.
     pub fn call(&self, args: Vec<Expr>) -> Expr {
@@ -111,74 +132,136 @@ impl AggregateUDF {
     }
 }
 
-// Creates a physical expression of the UDAF, that includes all necessary type coercion.
-// This function errors when `args`' can't be coerced to a valid argument type of the UDAF.
-// pub fn create_aggregate_expr(
-//     fun: &AggregateUDF,
-//     args: &[Arc<dyn PhysicalExpr>],
-//     input_schema: &Schema,
-//     name: String,
-// ) -> Result<Arc<dyn AggregateExpr>> {
-//     // coerce
-//     let args = coerce(args, input_schema, &fun.signature)?;
-
-//     let arg_types = args
-//         .iter()
-//         .map(|arg| arg.data_type(input_schema))
-//         .collect::<Result<Vec<_>>>()?;
-
-//     Ok(Arc::new(AggregateFunctionExpr {
-//         fun: fun.clone(),
-//         args: args.clone(),
-//         data_type: (fun.return_type)(&arg_types)?.as_ref().clone(),
-//         name,
-//     }))
-// }
-
-// Physical aggregate expression of a UDAF.
-// #[derive(Debug)]
-// pub struct AggregateFunctionExpr {
-//     fun: AggregateUDF,
-//     args: Vec<Arc<dyn PhysicalExpr>>,
-//     data_type: DataType,
-//     name: String,
-// }
-
-// impl AggregateExpr for AggregateFunctionExpr {
-//     /// Return a reference to Any that can be used for downcasting
-//     fn as_any(&self) -> &dyn Any {
-//         self
-//     }
-
-//     fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
-//         self.args.clone()
-//     }
-
-//     fn state_fields(&self) -> Result<Vec<Field>> {
-//         let fields = (self.fun.state_type)(&self.data_type)?
-//             .iter()
-//             .enumerate()
-//             .map(|(i, data_type)| {
-//                 Field::new(
-//                     &format_state_name(&self.name, &format!("{}", i)),
-//                     data_type.clone(),
-//                     true,
-//                 )
-//             })
-//             .collect::<Vec<Field>>();
-
-//         Ok(fields)
-//     }
-
-//     fn field(&self) -> Result<Field> {
-//         Ok(Field::new(&self.name, self.data_type.clone(), true))
-//     }
-
-//     fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
-//         (self.fun.accumulator)()
-//     }
-
-//     fn name(&self) -> &str {
-//         &self.name
-//     }
-// }
+/// Creates a physical expression of the UDAF, that includes all necessary type coercion.
+/// This function errors when `args`' can't be coerced to a valid argument type of the UDAF.
+pub fn create_aggregate_expr(
+    fun: &AggregateUDF,
+    args: &[Arc<dyn PhysicalExpr>],
+    input_schema: &Schema,
+    name: String,
+) -> Result<Arc<dyn AggregateExpr>> {
+    // coerce
+    let args = coerce(args, input_schema, &fun.signature)?;
+
+    let arg_types = args
+        .iter()
+        .map(|arg| arg.data_type(input_schema))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(AggregateFunctionExpr {
+        fun: Arc::new(fun.clone()),
+        args,
+        data_type: (fun.return_type)(&arg_types)?.as_ref().clone(),
+        name,
+    }))
+}
+
+/// Physical aggregate expression of a UDAF.
+///
+/// Only the name and the signature of the UDAF are serialized: its
+/// implementation is resolved by name when the expression is deserialized,
+/// see [`from_json_with_registry`](super::serde::from_json_with_registry).
+#[derive(Debug, Serialize)]
+pub struct AggregateFunctionExpr {
+    #[serde(serialize_with = "serialize_udaf")]
+    fun: Arc<AggregateUDF>,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    data_type: DataType,
+    name: String,
+}
+
+fn serialize_udaf<S: serde::Serializer>(
+    fun: &Arc<AggregateUDF>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    (&fun.name, &fun.signature).serialize(serializer)
+}
+
+impl<'de> Deserialize<'de> for AggregateFunctionExpr {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SerializedExpr {
+            fun: (String, Signature),
+            args: Vec<Arc<dyn PhysicalExpr>>,
+            data_type: DataType,
+            name: String,
+        }
+
+        let SerializedExpr {
+            fun: (fun_name, signature),
+            args,
+            data_type,
+            name,
+        } = SerializedExpr::deserialize(deserializer)?;
+        let fun = super::serde::resolve_udaf(&fun_name, &signature)
+            .map_err(D::Error::custom)?;
+        Ok(Self {
+            fun,
+            args,
+            data_type,
+            name,
+        })
+    }
+}
+
+impl AggregateFunctionExpr {
+    /// The UDAF called by this expression
+    pub fn fun(&self) -> &AggregateUDF {
+        &self.fun
+    }
+}
+
+#[typetag::serde(name = "aggregate_udf_expr")]
+impl AggregateExpr for AggregateFunctionExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.args.clone()
+    }
+
+    fn state_fields(&self) -> Result<Vec<Field>> {
+        if let Some(state_fields) = &self.fun.state_fields {
+            return Ok(state_fields
+                .iter()
+                .map(|field| {
+                    Field::new(
+                        &format_state_name(&self.name, field.name()),
+                        field.data_type().clone(),
+                        field.is_nullable(),
+                    )
+                })
+                .collect());
+        }
+
+        let fields = (self.fun.state_type)(&self.data_type)?
+            .iter()
+            .enumerate()
+            .map(|(i, data_type)| {
+                Field::new(
+                    &format_state_name(&self.name, &format!("{}", i)),
+                    data_type.clone(),
+                    true,
+                )
+            })
+            .collect::<Vec<Field>>();
+
+        Ok(fields)
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn create_accumulator(&self) -> Result<Box<dyn Accumulator>> {
+        (self.fun.accumulator)()
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
@@ -18,21 +18,32 @@
 //! UDF support
 
 use fmt::{Debug, Formatter};
+use std::any::Any;
 use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::Mutex;
 
-// use arrow::datatypes::Schema;
+use arrow::array::{new_empty_array, Array, ArrayRef, NullArray};
+use arrow::datatypes::{DataType, Schema};
+use arrow::record_batch::RecordBatch;
+use futures::future::{BoxFuture, FutureExt};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
 
-// use crate::error::Result;
+use crate::error::{DataFusionError, Result};
 use crate::logical_plan::Expr;
-// use crate::physical_plan::PhysicalExpr;
+use crate::physical_plan::metrics::Count;
+use crate::physical_plan::{ColumnarValue, PhysicalExpr};
+use crate::scalar::ScalarValue;
 
 use super::{
     functions::{
-        ReturnTypeFunction, ScalarFunctionImplementation, Signature,
+        AsyncScalarFunctionImplementation, ReturnTypeFunction,
+        ScalarFunctionImplementation, Signature, Volatility,
     },
-    // type_coercion::coerce,
+    type_coercion::coerce,
 };
-// use supper::functions::ScalarFunctionExpr;
 
 use std::sync::Arc;
 
@@ -54,6 +65,17 @@ pub struct ScalarUDF {
     /// the batch's row count (so that the generative zero-argument function can know
     /// the result array size).
     pub fun: ScalarFunctionImplementation,
+    /// The implementation of an async UDF, see [`ScalarUDF::new_async`]
+    pub async_fun: Option<AsyncScalarFunctionImplementation>,
+    /// Whether the optimizer may move the filters calling the UDF, e.g. below a
+    /// join, true by default. The filters calling volatile UDFs are never moved.
+    pub supports_filter_pushdown: bool,
+    /// How the errors of the UDF on some rows of a batch are handled, the
+    /// `udf_error_mode` of the context if `None`
+    pub error_mode: Option<UdfErrorMode>,
+    /// The rows the UDF failed on in permissive mode, shared by the clones of
+    /// the UDF
+    pub row_errors: Arc<UdfRowErrors>,
 }
 
 impl Debug for ScalarUDF {
@@ -61,7 +83,10 @@ impl Debug for ScalarUDF {
         f.debug_struct("ScalarUDF")
             .field("name", &self.name)
             .field("signature", &self.signature)
+            .field("supports_filter_pushdown", &self.supports_filter_pushdown)
+            .field("error_mode", &self.error_mode)
             .field("fun", &"<FUNC>")
+            .field("async", &self.async_fun.is_some())
             .finish()
     }
 }
@@ -96,9 +121,57 @@ impl ScalarUDF {
             signature: signature.clone(),
             return_type: return_type.clone(),
             fun: fun.clone(),
+            async_fun: None,
+            supports_filter_pushdown: true,
+            error_mode: None,
+            row_errors: Arc::new(UdfRowErrors::default()),
         }
     }
 
+    /// Create a new async ScalarUDF, e.g. looking the values of its arguments up
+    /// in an external service. Its calls are awaited by the projections, which
+    /// await the calls on several batches at once; the other operators fail to
+    /// evaluate it.
+    pub fn new_async(
+        name: &str,
+        signature: &Signature,
+        return_type: &ReturnTypeFunction,
+        fun: &AsyncScalarFunctionImplementation,
+    ) -> Self {
+        let message = async_udf_error(name);
+        let sync_fun: ScalarFunctionImplementation =
+            Arc::new(move |_| Err(DataFusionError::NotImplemented(message.clone())));
+        Self {
+            async_fun: Some(fun.clone()),
+            ..Self::new(name, signature, return_type, &sync_fun)
+        }
+    }
+
+    /// Whether the UDF is async, see [`ScalarUDF::new_async`]
+    pub fn is_async(&self) -> bool {
+        self.async_fun.is_some()
+    }
+
+    /// Sets how the errors of the UDF on some rows of a batch are handled,
+    /// whatever the `udf_error_mode` of the context
+    pub fn with_error_mode(mut self, error_mode: UdfErrorMode) -> Self {
+        self.error_mode = Some(error_mode);
+        self
+    }
+
+    /// Sets whether the filters calling the UDF may be pushed down, which may be
+    /// disabled for expensive UDFs that should only be evaluated on the rows
+    /// remaining after the other operators of the plan.
+    pub fn with_filter_pushdown(mut self, supports_filter_pushdown: bool) -> Self {
+        self.supports_filter_pushdown = supports_filter_pushdown;
+        self
+    }
+
+    /// Whether the filters calling the UDF may be moved by the optimizer
+    pub fn filter_pushdown_allowed(&self) -> bool {
+        self.supports_filter_pushdown && self.signature.volatility != Volatility::Volatile
+    }
+
     /// creates a logical expression with a call of the UDF
     /// This utility allows using the UDF without requiring access to the registry.
     pub fn call(&self, args: Vec<Expr>) -> Expr {
@@ -109,25 +182,442 @@ impl ScalarUDF {
     }
 }
 
-// Create a physical expression of the UDF.
-// This function errors when `args`' can't be coerced to a valid argument type of the UDF.
-// pub fn create_physical_expr(
-//     fun: &ScalarUDF,
-//     args: &[Arc<dyn PhysicalExpr>],
-//     input_schema: &Schema,
-// ) -> Result<Arc<dyn PhysicalExpr>> {
-//     // coerce
-//     let args = coerce(args, input_schema, &fun.signature)?;
-
-//     let arg_types = args
-//         .iter()
-//         .map(|e| e.data_type(input_schema))
-//         .collect::<Result<Vec<_>>>()?;
-
-//     Ok(Arc::new(ScalarFunctionExpr::new(
-//         &fun.name,
-//         fun.fun.clone(),
-//         args,
-//         (fun.return_type)(&arg_types)?.as_ref(),
-//     )))
-// }
+/// How the errors and the panics of a UDF on some rows of a batch are handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UdfErrorMode {
+    /// Fail with an error naming the UDF and the arguments of the first row
+    /// it failed on
+    Strict,
+    /// Return nulls for the rows the UDF failed on, which are counted and
+    /// sampled in the [`UdfRowErrors`] of the UDF
+    Permissive,
+}
+
+impl Default for UdfErrorMode {
+    fn default() -> Self {
+        UdfErrorMode::Strict
+    }
+}
+
+impl fmt::Display for UdfErrorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UdfErrorMode::Strict => write!(f, "strict"),
+            UdfErrorMode::Permissive => write!(f, "permissive"),
+        }
+    }
+}
+
+impl FromStr for UdfErrorMode {
+    type Err = DataFusionError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(UdfErrorMode::Strict),
+            "permissive" => Ok(UdfErrorMode::Permissive),
+            _ => Err(DataFusionError::Plan(format!(
+                "Unknown UDF error mode '{}', expected one of strict or permissive",
+                s
+            ))),
+        }
+    }
+}
+
+/// Maximum number of rows sampled by [`UdfRowErrors`]
+pub const MAX_UDF_ROW_ERROR_SAMPLES: usize = 16;
+
+/// A row a UDF failed on in permissive mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct UdfRowError {
+    /// The arguments of the UDF on the row
+    pub args: Vec<ScalarValue>,
+    /// The message of the error or of the panic
+    pub message: String,
+}
+
+/// The rows a UDF failed on in permissive mode, whose values were replaced by
+/// nulls
+#[derive(Debug)]
+pub struct UdfRowErrors {
+    count: Count,
+    samples: Mutex<Vec<UdfRowError>>,
+}
+
+impl Default for UdfRowErrors {
+    fn default() -> Self {
+        Self {
+            count: Count::new(),
+            samples: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl UdfRowErrors {
+    /// Number of rows the UDF failed on
+    pub fn count(&self) -> usize {
+        self.count.value()
+    }
+
+    /// The first rows the UDF failed on, at most [`MAX_UDF_ROW_ERROR_SAMPLES`]
+    pub fn samples(&self) -> Vec<UdfRowError> {
+        self.samples.lock().unwrap().clone()
+    }
+
+    fn record(&self, num_rows: usize, error: UdfRowError) {
+        self.count.add(num_rows);
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() < MAX_UDF_ROW_ERROR_SAMPLES {
+            samples.push(error);
+        }
+    }
+}
+
+/// Create a physical expression of the UDF, whose errors are handled in
+/// `error_mode` unless the UDF has its own error mode.
+/// This function errors when `args`' can't be coerced to a valid argument type of the UDF.
+pub fn create_physical_expr(
+    fun: &ScalarUDF,
+    args: &[Arc<dyn PhysicalExpr>],
+    input_schema: &Schema,
+    error_mode: UdfErrorMode,
+) -> Result<Arc<dyn PhysicalExpr>> {
+    // coerce
+    let args = coerce(args, input_schema, &fun.signature)?;
+
+    let arg_types = args
+        .iter()
+        .map(|e| e.data_type(input_schema))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(ScalarUDFExpr {
+        fun: Arc::new(fun.clone()),
+        args,
+        return_type: (fun.return_type)(&arg_types)?.as_ref().clone(),
+        error_mode: fun.error_mode.unwrap_or(error_mode),
+    }))
+}
+
+/// Physical expression of a UDF.
+///
+/// Only the name and the signature of the UDF are serialized: its
+/// implementation is resolved by name when the expression is deserialized,
+/// see [`from_json_with_registry`](super::serde::from_json_with_registry).
+#[derive(Serialize)]
+pub struct ScalarUDFExpr {
+    #[serde(serialize_with = "serialize_udf")]
+    fun: Arc<ScalarUDF>,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    return_type: DataType,
+    error_mode: UdfErrorMode,
+}
+
+fn serialize_udf<S: serde::Serializer>(
+    fun: &Arc<ScalarUDF>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    (&fun.name, &fun.signature).serialize(serializer)
+}
+
+impl<'de> Deserialize<'de> for ScalarUDFExpr {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SerializedExpr {
+            fun: (String, Signature),
+            args: Vec<Arc<dyn PhysicalExpr>>,
+            return_type: DataType,
+            #[serde(default)]
+            error_mode: UdfErrorMode,
+        }
+
+        let SerializedExpr {
+            fun: (name, signature),
+            args,
+            return_type,
+            error_mode,
+        } = SerializedExpr::deserialize(deserializer)?;
+        let fun =
+            super::serde::resolve_udf(&name, &signature).map_err(D::Error::custom)?;
+        Ok(Self {
+            fun,
+            args,
+            return_type,
+            error_mode,
+        })
+    }
+}
+
+impl ScalarUDFExpr {
+    /// The UDF called by this expression
+    pub fn fun(&self) -> &ScalarUDF {
+        &self.fun
+    }
+
+    /// Input arguments
+    pub fn args(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.args
+    }
+
+    /// How the errors of the UDF on some rows are handled
+    pub fn error_mode(&self) -> UdfErrorMode {
+        self.error_mode
+    }
+
+    /// Evaluates the arguments of an async UDF on `batch` and calls it,
+    /// returning the future of its values, which doesn't borrow the batch
+    pub fn evaluate_async(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<BoxFuture<'static, Result<ArrayRef>>> {
+        let async_fun = self.fun.async_fun.as_ref().ok_or_else(|| {
+            DataFusionError::Internal(format!("UDF {} is not async", self.fun.name))
+        })?;
+        let num_rows = batch.num_rows();
+        let args = if self.args.is_empty() {
+            vec![Arc::new(NullArray::new(num_rows)) as ArrayRef]
+        } else {
+            self.args
+                .iter()
+                .map(|e| Ok(e.evaluate(batch)?.into_array(num_rows)))
+                .collect::<Result<Vec<_>>>()?
+        };
+        let values = async_fun(args);
+        let name = self.fun.name.clone();
+        Ok(async move {
+            let values = values.await?;
+            if values.len() != num_rows {
+                return Err(DataFusionError::Execution(format!(
+                    "Async UDF {} returned {} values for {} rows",
+                    name,
+                    values.len(),
+                    num_rows
+                )));
+            }
+            Ok(values)
+        }
+        .boxed())
+    }
+
+    /// Evaluates the UDF on each row of `inputs` after it failed on the whole
+    /// batch with `batch_error`, to find the rows it fails on
+    fn evaluate_rows(
+        &self,
+        inputs: &[ColumnarValue],
+        num_rows: usize,
+        batch_error: DataFusionError,
+    ) -> Result<ColumnarValue> {
+        let row_args = |row: usize| -> Result<Vec<ScalarValue>> {
+            if self.args.is_empty() {
+                return Ok(vec![]);
+            }
+            inputs
+                .iter()
+                .map(|input| match input {
+                    ColumnarValue::Array(array) => {
+                        ScalarValue::try_from_array(array, row)
+                    }
+                    ColumnarValue::Scalar(scalar) => Ok(scalar.clone()),
+                })
+                .collect()
+        };
+
+        // the arguments are the same for all the rows
+        if inputs
+            .iter()
+            .all(|input| matches!(input, ColumnarValue::Scalar(_)))
+        {
+            let args = row_args(0)?;
+            return match self.error_mode {
+                UdfErrorMode::Strict => Err(self.row_error(0, &args, batch_error)),
+                UdfErrorMode::Permissive => {
+                    self.fun.row_errors.record(
+                        num_rows,
+                        UdfRowError {
+                            args,
+                            message: error_message(batch_error),
+                        },
+                    );
+                    Ok(ColumnarValue::Scalar(ScalarValue::try_from(
+                        &self.return_type,
+                    )?))
+                }
+            };
+        }
+
+        let mut values = Vec::with_capacity(num_rows);
+        for row in 0..num_rows {
+            let row_inputs = inputs
+                .iter()
+                .map(|input| match input {
+                    ColumnarValue::Array(array) => {
+                        ColumnarValue::Array(array.slice(row, 1))
+                    }
+                    ColumnarValue::Scalar(scalar) => {
+                        ColumnarValue::Scalar(scalar.clone())
+                    }
+                })
+                .collect::<Vec<_>>();
+            let value = invoke(&self.fun, &row_inputs).and_then(|result| match result {
+                ColumnarValue::Array(array) if array.len() == 1 => {
+                    ScalarValue::try_from_array(&array, 0)
+                }
+                ColumnarValue::Array(array) => Err(DataFusionError::Execution(format!(
+                    "returned {} values for a row",
+                    array.len()
+                ))),
+                ColumnarValue::Scalar(scalar) => Ok(scalar),
+            });
+            match (value, self.error_mode) {
+                (Ok(value), _) => values.push(value),
+                (Err(e), UdfErrorMode::Strict) => {
+                    return Err(self.row_error(row, &row_args(row)?, e))
+                }
+                (Err(e), UdfErrorMode::Permissive) => {
+                    self.fun.row_errors.record(
+                        1,
+                        UdfRowError {
+                            args: row_args(row)?,
+                            message: error_message(e),
+                        },
+                    );
+                    values.push(ScalarValue::try_from(&self.return_type)?);
+                }
+            }
+        }
+
+        match self.error_mode {
+            // the UDF only fails on the whole batch
+            UdfErrorMode::Strict => Err(DataFusionError::Execution(format!(
+                "UDF {} failed: {}",
+                self.fun.name,
+                error_message(batch_error)
+            ))),
+            UdfErrorMode::Permissive if values.is_empty() => {
+                Ok(ColumnarValue::Array(new_empty_array(&self.return_type)))
+            }
+            UdfErrorMode::Permissive => {
+                Ok(ColumnarValue::Array(ScalarValue::iter_to_array(values)?))
+            }
+        }
+    }
+
+    /// The error of the UDF on the row `row` of a batch, whose arguments are
+    /// `args`
+    fn row_error(
+        &self,
+        row: usize,
+        args: &[ScalarValue],
+        error: DataFusionError,
+    ) -> DataFusionError {
+        DataFusionError::Execution(format!(
+            "UDF {} failed on row {} of a batch with arguments ({}): {}",
+            self.fun.name,
+            row,
+            args.iter()
+                .map(|arg| format!("{:?}", arg))
+                .collect::<Vec<_>>()
+                .join(", "),
+            error_message(error)
+        ))
+    }
+}
+
+fn async_udf_error(name: &str) -> String {
+    format!(
+        "Async UDF {} can only be called in the expressions of a projection",
+        name
+    )
+}
+
+/// Calls the implementation of `fun`, turning its panics into errors.
+///
+/// The arguments are only read by the implementation, which is asserted to be
+/// unwind safe: a UDF keeping state across calls must leave it consistent
+/// when it panics.
+fn invoke(fun: &ScalarUDF, args: &[ColumnarValue]) -> Result<ColumnarValue> {
+    match panic::catch_unwind(AssertUnwindSafe(|| (fun.fun)(args))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                message.to_string()
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message.clone()
+            } else {
+                "panic".to_string()
+            };
+            Err(DataFusionError::Execution(format!("panicked: {}", message)))
+        }
+    }
+}
+
+/// The message of `error`, without the prefix of its kind for execution errors
+fn error_message(error: DataFusionError) -> String {
+    match error {
+        DataFusionError::Execution(message) => message,
+        error => error.to_string(),
+    }
+}
+
+impl Debug for ScalarUDFExpr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScalarUDFExpr")
+            .field("fun", &self.fun)
+            .field("args", &self.args)
+            .field("return_type", &self.return_type)
+            .field("error_mode", &self.error_mode)
+            .finish()
+    }
+}
+
+impl fmt::Display for ScalarUDFExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}({})",
+            self.fun.name,
+            self.args
+                .iter()
+                .map(|e| format!("{}", e))
+                .collect::<Vec<String>>()
+                .join(", ")
+        )
+    }
+}
+
+#[typetag::serde(name = "scalar_udf_expr")]
+impl PhysicalExpr for ScalarUDFExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn data_type(&self, _input_schema: &Schema) -> Result<DataType> {
+        Ok(self.return_type.clone())
+    }
+
+    fn nullable(&self, _input_schema: &Schema) -> Result<bool> {
+        Ok(true)
+    }
+
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ColumnarValue> {
+        if self.fun.is_async() {
+            return Err(DataFusionError::NotImplemented(async_udf_error(
+                &self.fun.name,
+            )));
+        }
+        // a zero argument UDF is passed a null array indicating the batch size
+        let inputs = if self.args.is_empty() {
+            vec![ColumnarValue::Array(Arc::new(NullArray::new(
+                batch.num_rows(),
+            )))]
+        } else {
+            self.args
+                .iter()
+                .map(|e| e.evaluate(batch))
+                .collect::<Result<Vec<_>>>()?
+        };
+        invoke(&self.fun, &inputs)
+            .or_else(|e| self.evaluate_rows(&inputs, batch.num_rows(), e))
+    }
+}
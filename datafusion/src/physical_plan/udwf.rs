@@ -0,0 +1,344 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! This module contains functions and structs supporting user-defined window functions.
+
+use fmt::{Debug, Formatter};
+use std::any::Any;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+use arrow::array::{new_empty_array, Array, ArrayRef};
+use arrow::compute::concat;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{DataFusionError, Result};
+use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
+use crate::logical_plan::Expr;
+use crate::scalar::ScalarValue;
+
+use super::{
+    expressions::PhysicalSortExpr,
+    functions::{ReturnTypeFunction, Signature},
+    type_coercion::coerce,
+    window_functions::WindowFunction,
+    windows::row_frame,
+    PhysicalExpr, WindowExpr,
+};
+
+/// Evaluates a user-defined window function over the rows of one partition of
+/// a window, sorted by the ORDER BY of the window.
+pub trait PartitionEvaluator: Send {
+    /// Returns the values of the function for the `num_rows` rows of a
+    /// partition, given the values of its arguments for these rows.
+    fn evaluate_partition(
+        &mut self,
+        values: &[ArrayRef],
+        num_rows: usize,
+    ) -> Result<ArrayRef>;
+
+    /// Returns the value of the function for a row of a partition, given the
+    /// values of its arguments for the rows of the partition and the rows of
+    /// the partition in the frame of the row.
+    ///
+    /// It is called on every row, in order, instead of
+    /// [`PartitionEvaluator::evaluate_partition`] when the window has a
+    /// ROWS frame.
+    fn evaluate_frame(
+        &mut self,
+        _values: &[ArrayRef],
+        _frame: Range<usize>,
+    ) -> Result<ScalarValue> {
+        Err(DataFusionError::NotImplemented(
+            "evaluate_frame is not implemented by default".to_owned(),
+        ))
+    }
+}
+
+/// Creates the evaluator of each partition of a UDWF
+pub type PartitionEvaluatorFactory =
+    Arc<dyn Fn() -> Result<Box<dyn PartitionEvaluator>> + Send + Sync>;
+
+/// Logical representation of a user-defined window function (UDWF).
+/// Unlike a UDAF, a UDWF computes the values of all the rows of a partition
+/// at once, e.g. from the previous rows of the partition.
+#[derive(Clone)]
+pub struct WindowUDF {
+    /// name
+    pub name: String,
+    /// signature
+    pub signature: Signature,
+    /// Return type
+    pub return_type: ReturnTypeFunction,
+    /// actual implementation
+    pub partition_evaluator_factory: PartitionEvaluatorFactory,
+}
+
+impl Debug for WindowUDF {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WindowUDF")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .field("fun", &"<FUNC>")
+            .finish()
+    }
+}
+
+impl PartialEq for WindowUDF {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.signature == other.signature
+    }
+}
+
+impl PartialOrd for WindowUDF {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        let c = self.name.partial_cmp(&other.name);
+        if matches!(c, Some(std::cmp::Ordering::Equal)) {
+            self.signature.partial_cmp(&other.signature)
+        } else {
+            c
+        }
+    }
+}
+
+impl WindowUDF {
+    /// Create a new WindowUDF
+    pub fn new(
+        name: &str,
+        signature: &Signature,
+        return_type: &ReturnTypeFunction,
+        partition_evaluator_factory: &PartitionEvaluatorFactory,
+    ) -> Self {
+        Self {
+            name: name.to_owned(),
+            signature: signature.clone(),
+            return_type: return_type.clone(),
+            partition_evaluator_factory: partition_evaluator_factory.clone(),
+        }
+    }
+
+    /// creates a logical expression with a call of the UDWF over the window
+    /// `PARTITION BY partition_by ORDER BY order_by`, where `order_by` holds
+    /// sort expressions.
+    /// This utility allows using the UDWF without requiring access to the registry.
+    pub fn call(
+        &self,
+        args: Vec<Expr>,
+        partition_by: Vec<Expr>,
+        order_by: Vec<Expr>,
+    ) -> Expr {
+        Expr::WindowFunction {
+            fun: WindowFunction::WindowUDF(Arc::new(self.clone())),
+            args,
+            partition_by,
+            order_by,
+            window_frame: None,
+        }
+    }
+}
+
+/// Creates a physical expression of the UDWF, that includes all necessary type coercion.
+/// This function errors when `args`' can't be coerced to a valid argument type of the UDWF.
+pub fn create_window_expr(
+    fun: &WindowUDF,
+    args: &[Arc<dyn PhysicalExpr>],
+    partition_by: &[Arc<dyn PhysicalExpr>],
+    order_by: &[PhysicalSortExpr],
+    window_frame: Option<WindowFrame>,
+    input_schema: &Schema,
+    name: String,
+) -> Result<Arc<dyn WindowExpr>> {
+    // coerce
+    let args = coerce(args, input_schema, &fun.signature)?;
+
+    let arg_types = args
+        .iter()
+        .map(|arg| arg.data_type(input_schema))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Arc::new(WindowUDFExpr {
+        fun: Arc::new(fun.clone()),
+        args,
+        partition_by: partition_by.to_vec(),
+        order_by: order_by.to_vec(),
+        window_frame,
+        data_type: (fun.return_type)(&arg_types)?.as_ref().clone(),
+        name,
+    }))
+}
+
+/// Physical window expression of a UDWF.
+///
+/// Only the name and the signature of the UDWF are serialized: its
+/// implementation is resolved by name when the expression is deserialized,
+/// see [`from_json_with_registry`](super::serde::from_json_with_registry).
+#[derive(Debug, Serialize)]
+pub struct WindowUDFExpr {
+    #[serde(serialize_with = "serialize_udwf")]
+    fun: Arc<WindowUDF>,
+    args: Vec<Arc<dyn PhysicalExpr>>,
+    partition_by: Vec<Arc<dyn PhysicalExpr>>,
+    order_by: Vec<PhysicalSortExpr>,
+    window_frame: Option<WindowFrame>,
+    data_type: DataType,
+    name: String,
+}
+
+fn serialize_udwf<S: serde::Serializer>(
+    fun: &Arc<WindowUDF>,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    (&fun.name, &fun.signature).serialize(serializer)
+}
+
+impl<'de> Deserialize<'de> for WindowUDFExpr {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SerializedExpr {
+            fun: (String, Signature),
+            args: Vec<Arc<dyn PhysicalExpr>>,
+            partition_by: Vec<Arc<dyn PhysicalExpr>>,
+            order_by: Vec<PhysicalSortExpr>,
+            window_frame: Option<WindowFrame>,
+            data_type: DataType,
+            name: String,
+        }
+
+        let SerializedExpr {
+            fun: (fun_name, signature),
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+            data_type,
+            name,
+        } = SerializedExpr::deserialize(deserializer)?;
+        let fun = super::serde::resolve_udwf(&fun_name, &signature)
+            .map_err(D::Error::custom)?;
+        Ok(Self {
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+            data_type,
+            name,
+        })
+    }
+}
+
+impl WindowUDFExpr {
+    /// The UDWF called by this expression
+    pub fn fun(&self) -> &WindowUDF {
+        &self.fun
+    }
+
+    /// Evaluate the rows of one partition, given the values of the arguments
+    /// for these rows
+    fn evaluate_partition(
+        &self,
+        values: &[ArrayRef],
+        num_rows: usize,
+    ) -> Result<ArrayRef> {
+        let mut evaluator = (self.fun.partition_evaluator_factory)()?;
+        let result = match self.window_frame {
+            Some(window_frame) if window_frame.units == WindowFrameUnits::Rows => {
+                let partition_range = 0..num_rows;
+                let scalars = partition_range
+                    .clone()
+                    .map(|row| {
+                        let frame = row_frame(&window_frame, row, &partition_range);
+                        evaluator.evaluate_frame(values, frame)
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                ScalarValue::iter_to_array(scalars)?
+            }
+            _ => evaluator.evaluate_partition(values, num_rows)?,
+        };
+        if result.len() != num_rows || result.data_type() != &self.data_type {
+            return Err(DataFusionError::Execution(format!(
+                "UDWF {} returned {} values of type {:?} for a partition of {} rows, \
+                 expected values of type {:?}",
+                self.fun.name,
+                result.len(),
+                result.data_type(),
+                num_rows,
+                self.data_type
+            )));
+        }
+        Ok(result)
+    }
+}
+
+#[typetag::serde(name = "window_udf_expr")]
+impl WindowExpr for WindowUDFExpr {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn field(&self) -> Result<Field> {
+        Ok(Field::new(&self.name, self.data_type.clone(), true))
+    }
+
+    fn expressions(&self) -> Vec<Arc<dyn PhysicalExpr>> {
+        self.args.clone()
+    }
+
+    fn partition_by(&self) -> &[Arc<dyn PhysicalExpr>] {
+        &self.partition_by
+    }
+
+    fn order_by(&self) -> &[PhysicalSortExpr] {
+        &self.order_by
+    }
+
+    /// evaluate the window function values against the batch, which is
+    /// sorted by the partition and the order of the window
+    fn evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(new_empty_array(&self.data_type));
+        }
+        let partition_points =
+            self.evaluate_partition_points(num_rows, &self.partition_columns(batch)?)?;
+        let values = self.evaluate_args(batch)?;
+        let results = partition_points
+            .into_iter()
+            .map(|partition| {
+                let len = partition.end - partition.start;
+                let values = values
+                    .iter()
+                    .map(|v| v.slice(partition.start, len))
+                    .collect::<Vec<_>>();
+                self.evaluate_partition(&values, len)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let results = results.iter().map(|i| i.as_ref()).collect::<Vec<_>>();
+        concat(&results).map_err(DataFusionError::ArrowError)
+    }
+}
@@ -24,16 +24,20 @@
 use std::{any::Any, sync::Arc};
 
 use arrow::{datatypes::SchemaRef, record_batch::RecordBatch};
+use futures::stream::SelectAll;
 use futures::StreamExt;
 
 use super::{
+    fmt_with_statistics,
+    memory::feed_input,
     metrics::{ExecutionPlanMetricsSet, MetricsSet},
-    ColumnStatistics, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream, Statistics, LambdaExecPlan
+    ColumnStatistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
 };
 use crate::{
-    error::Result,
-    physical_plan::{expressions, metrics::BaselineMetrics},
+    error::{DataFusionError, Result},
+    execution::task_context::TaskContext,
+    physical_plan::{expressions, metrics::BaselineMetrics, trace::instrument_stream},
 };
 use async_trait::async_trait;
 
@@ -44,24 +48,82 @@ use serde::{Deserialize, Serialize};
 pub struct UnionExec {
     /// Input execution plan
     inputs: Vec<Arc<dyn ExecutionPlan>>,
+    /// The number of output partitions the partitions of the inputs are
+    /// interleaved into, `None` if they are output one after the other
+    #[serde(default)]
+    interleave: Option<usize>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
 
 impl UnionExec {
-    /// Create a new UnionExec
+    /// Create a new UnionExec, whose output partitions are the partitions
+    /// of each of its inputs, one input after the other
     pub fn new(inputs: Vec<Arc<dyn ExecutionPlan>>) -> Self {
         UnionExec {
             inputs,
+            interleave: None,
             metrics: ExecutionPlanMetricsSet::new(),
         }
     }
+
+    /// Create a new UnionExec with `target_partitions` output partitions,
+    /// the partitions of its inputs being dealt to them in a round-robin
+    /// fashion, so that an input with many partitions is spread across the
+    /// output partitions rather than making most of them
+    pub fn new_with_interleave(
+        inputs: Vec<Arc<dyn ExecutionPlan>>,
+        target_partitions: usize,
+    ) -> Self {
+        UnionExec {
+            inputs,
+            // at least one partition, to deal the partitions of the inputs to
+            interleave: Some(target_partitions.max(1)),
+            metrics: ExecutionPlanMetricsSet::new(),
+        }
+    }
+
+    /// The number of output partitions the partitions of the inputs are
+    /// interleaved into, `None` if they are output one after the other
+    pub fn interleave(&self) -> Option<usize> {
+        self.interleave
+    }
+
+    /// The inputs and their partitions making the output partition
+    /// `partition` of an interleaving union into `target_partitions`
+    fn interleaved_partitions(
+        &self,
+        partition: usize,
+        target_partitions: usize,
+    ) -> Vec<(&Arc<dyn ExecutionPlan>, usize)> {
+        self.inputs
+            .iter()
+            .flat_map(|input| {
+                let partition_count = input.output_partitioning().partition_count();
+                (0..partition_count).map(move |input_partition| (input, input_partition))
+            })
+            .skip(partition)
+            .step_by(target_partitions)
+            .collect()
+    }
 }
 
 #[async_trait]
 impl LambdaExecPlan for UnionExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!(); 
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        self.feed_child_batches(0, partitions)
+    }
+
+    fn feed_child_batches(
+        &mut self,
+        child: usize,
+        partitions: Vec<Vec<RecordBatch>>,
+    ) -> Result<()> {
+        let input = self.inputs.get_mut(child).ok_or_else(|| {
+            DataFusionError::Plan(format!("UnionExec has no input {} to feed", child))
+        })?;
+        feed_input(input, partitions);
+        Ok(())
     }
 }
 
@@ -87,6 +149,9 @@ impl ExecutionPlan for UnionExec {
 
     /// Output of the union is the combination of all output partitions of the inputs
     fn output_partitioning(&self) -> Partitioning {
+        if let Some(target_partitions) = self.interleave {
+            return Partitioning::UnknownPartitioning(target_partitions);
+        }
         // Sums all the output partitions
         let num_partitions = self
             .inputs
@@ -102,22 +167,71 @@ impl ExecutionPlan for UnionExec {
         &self,
         children: Vec<Arc<dyn ExecutionPlan>>,
     ) -> Result<Arc<dyn ExecutionPlan>> {
-        Ok(Arc::new(UnionExec::new(children)))
+        Ok(Arc::new(match self.interleave {
+            Some(target_partitions) => {
+                UnionExec::new_with_interleave(children, target_partitions)
+            }
+            None => UnionExec::new(children),
+        }))
     }
 
-    async fn execute(&self, mut partition: usize) -> Result<SendableRecordBatchStream> {
-        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition);
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "UnionExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        mut partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("UnionExec", partition));
         // record the tiny amount of work done in this function so
         // elapsed_compute is reported as non zero
         let elapsed_compute = baseline_metrics.elapsed_compute().clone();
         let _timer = elapsed_compute.timer(); // record on drop
 
+        if let Some(target_partitions) = self.interleave {
+            if partition >= target_partitions {
+                return Err(crate::error::DataFusionError::Execution(format!(
+                    "Partition {} not found in Union",
+                    partition
+                )));
+            }
+            let mut streams = vec![];
+            for (input, input_partition) in
+                self.interleaved_partitions(partition, target_partitions)
+            {
+                streams.push(input.execute(input_partition, context.clone()).await?);
+            }
+            let stream = InterleavedStream {
+                schema: self.schema(),
+                inner: futures::stream::select_all(streams),
+            };
+            return Ok(instrument_stream(
+                self,
+                partition,
+                Box::pin(ObservedStream::new(Box::pin(stream), baseline_metrics)),
+            ));
+        }
+
         // find partition to execute
+        let output_partition = partition;
         for input in self.inputs.iter() {
             // Calculate whether partition belongs to the current partition
             if partition < input.output_partitioning().partition_count() {
-                let stream = input.execute(partition).await?;
-                return Ok(Box::pin(ObservedStream::new(stream, baseline_metrics)));
+                let stream = input.execute(partition, context).await?;
+                return Ok(instrument_stream(
+                    self,
+                    output_partition,
+                    Box::pin(ObservedStream::new(stream, baseline_metrics)),
+                ));
             } else {
                 partition -= input.output_partitioning().partition_count();
             }
@@ -135,9 +249,13 @@ impl ExecutionPlan for UnionExec {
         f: &mut std::fmt::Formatter,
     ) -> std::fmt::Result {
         match t {
-            DisplayFormatType::Default => {
-                write!(f, "UnionExec")
-            }
+            DisplayFormatType::Default => match self.interleave {
+                Some(target_partitions) => {
+                    write!(f, "UnionExec: interleave={}", target_partitions)
+                }
+                None => write!(f, "UnionExec"),
+            },
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -188,6 +306,30 @@ impl futures::Stream for ObservedStream {
     }
 }
 
+/// The batches of the streams of several partitions, as soon as each is
+/// ready
+struct InterleavedStream {
+    schema: SchemaRef,
+    inner: SelectAll<SendableRecordBatchStream>,
+}
+
+impl RecordBatchStream for InterleavedStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+impl futures::Stream for InterleavedStream {
+    type Item = arrow::error::Result<RecordBatch>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
 fn col_stats_union(
     mut left: ColumnStatistics,
     right: ColumnStatistics,
@@ -233,8 +375,10 @@ fn stats_union(mut left: Statistics, right: Statistics) -> Statistics {
 mod tests {
     use super::*;
     use crate::datasource::object_store::{local::LocalFileSystem, ObjectStore};
+    use crate::execution::context::ExecutionContext;
     use crate::{test, test_util};
 
+    use crate::physical_plan::{common, displayable, memory::MemoryExec};
     use crate::{
         physical_plan::{
             collect,
@@ -242,10 +386,13 @@ mod tests {
         },
         scalar::ScalarValue,
     };
+    use arrow::array::Int32Array;
+    use arrow::datatypes::{DataType, Field, Schema};
     use arrow::record_batch::RecordBatch;
 
     #[tokio::test]
     async fn test_union_partitions() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema = test_util::aggr_test_schema();
         let fs: Arc<dyn ObjectStore> = Arc::new(LocalFileSystem {});
 
@@ -288,12 +435,69 @@ mod tests {
         // Should have 9 partitions and 9 output batches
         assert_eq!(union_exec.output_partitioning().partition_count(), 9);
 
-        let result: Vec<RecordBatch> = collect(union_exec).await?;
+        let result: Vec<RecordBatch> = collect(union_exec, task_ctx).await?;
         assert_eq!(result.len(), 9);
 
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_union_interleave() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, true)]));
+        let batch = RecordBatch::try_new(
+            Arc::clone(&schema),
+            vec![Arc::new(Int32Array::from(vec![
+                Some(1),
+                None,
+                Some(3),
+                Some(4),
+            ]))],
+        )?;
+        // an input of a single partition and an input of seven partitions
+        let small =
+            MemoryExec::try_new(&[vec![batch.clone()]], Arc::clone(&schema), None)?;
+        let large =
+            MemoryExec::try_new(&vec![vec![batch]; 7], Arc::clone(&schema), None)?;
+        let inputs: Vec<Arc<dyn ExecutionPlan>> = vec![Arc::new(small), Arc::new(large)];
+
+        let union = UnionExec::new(inputs.clone());
+        assert_eq!(union.output_partitioning().partition_count(), 8);
+        assert_eq!(
+            displayable(&union).indent().to_string(),
+            "UnionExec\n  MemoryExec: partitions=1, partition_sizes=[1]\n  \
+             MemoryExec: partitions=7, partition_sizes=[1, 1, 1, 1, 1, 1, 1]\n"
+        );
+
+        let union = UnionExec::new_with_interleave(inputs, 4);
+        assert_eq!(union.output_partitioning().partition_count(), 4);
+        assert!(displayable(&union)
+            .indent()
+            .to_string()
+            .starts_with("UnionExec: interleave=4\n"));
+
+        // the partitions of the inputs are dealt to the output partitions
+        let mut rows = vec![];
+        for partition in 0..4 {
+            let stream = union.execute(partition, Arc::clone(&task_ctx)).await?;
+            let batches = common::collect(stream).await?;
+            rows.push(batches.iter().map(RecordBatch::num_rows).sum::<usize>());
+        }
+        assert_eq!(rows, vec![8, 8, 8, 8]);
+        assert!(union.execute(4, task_ctx).await.is_err());
+
+        // the statistics of the inputs are merged
+        let statistics = union.statistics();
+        assert!(statistics.is_exact);
+        assert_eq!(statistics.num_rows, Some(32));
+        let column_statistics = statistics.column_statistics.unwrap();
+        assert_eq!(column_statistics[0].null_count, Some(8));
+
+        let union = union.with_new_children(union.children())?;
+        assert_eq!(union.output_partitioning().partition_count(), 4);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_stats_union() {
         let left = Statistics {
@@ -0,0 +1,375 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Defines the unnest execution plan, which explodes a column of lists into a
+//! row per element of each list. The SQL statement
+//! `SELECT id, unnest(tags) FROM t` is an example of an unnest of the column
+//! `tags` of table `t`.
+
+use std::any::Any;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::array_expressions::{list_element_type, list_ranges};
+use crate::physical_plan::memory::feed_input;
+use crate::physical_plan::trace::instrument_stream;
+use crate::physical_plan::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+};
+use arrow::array::UInt32Array;
+use arrow::compute::take;
+use arrow::datatypes::{Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+
+use super::metrics::{BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet};
+use super::{RecordBatchStream, SendableRecordBatchStream, Statistics};
+use async_trait::async_trait;
+use futures::stream::Stream;
+use futures::stream::StreamExt;
+
+use serde::{Deserialize, Serialize};
+
+/// Execution plan for an unnest, which outputs a row per element of the lists
+/// of a column, repeating the values of the other columns of the row. The rows
+/// whose list is empty or null produce no rows.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnnestExec {
+    /// The input plan
+    input: Arc<dyn ExecutionPlan>,
+    /// The index of the column of lists to unnest
+    column: usize,
+    /// The schema of the input, with the column of lists replaced by a
+    /// column of their elements
+    schema: SchemaRef,
+    /// Execution metrics
+    metrics: ExecutionPlanMetricsSet,
+}
+
+impl UnnestExec {
+    /// Create an unnest of the column of lists at index `column` of `input`
+    pub fn try_new(input: Arc<dyn ExecutionPlan>, column: usize) -> Result<Self> {
+        let input_schema = input.schema();
+        if column >= input_schema.fields().len() {
+            return Err(DataFusionError::Internal(format!(
+                "UnnestExec column {} out of bounds of {} columns",
+                column,
+                input_schema.fields().len()
+            )));
+        }
+        let fields = input_schema
+            .fields()
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                if i != column {
+                    return Ok(field.clone());
+                }
+                match list_element_type(field.data_type()) {
+                    Some(element_type) => {
+                        Ok(Field::new(field.name(), element_type.clone(), true))
+                    }
+                    None => Err(DataFusionError::Plan(format!(
+                        "Can not unnest {} of type {:?}, which is not a list",
+                        field.name(),
+                        field.data_type()
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let schema = Arc::new(Schema::new_with_metadata(
+            fields,
+            input_schema.metadata().clone(),
+        ));
+
+        Ok(Self {
+            input,
+            column,
+            schema,
+            metrics: ExecutionPlanMetricsSet::new(),
+        })
+    }
+
+    /// The input plan
+    pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
+        &self.input
+    }
+
+    /// The index of the column of lists to unnest
+    pub fn column(&self) -> usize {
+        self.column
+    }
+}
+
+#[async_trait]
+impl LambdaExecPlan for UnnestExec {
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "unnest_exec")]
+impl ExecutionPlan for UnnestExec {
+    /// Return a reference to Any that can be used for downcasting
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    /// Get the schema for this execution plan
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    /// Get the output partitioning of this plan
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        match children.len() {
+            1 => Ok(Arc::new(UnnestExec::try_new(
+                children[0].clone(),
+                self.column,
+            )?)),
+            _ => Err(DataFusionError::Internal(
+                "UnnestExec wrong number of children".to_string(),
+            )),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "UnnestExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("UnnestExec", partition));
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(UnnestStream {
+                schema: self.schema.clone(),
+                column: self.column,
+                input: self.input.execute(partition, context).await?,
+                baseline_metrics,
+            }),
+        ))
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => write!(
+                f,
+                "UnnestExec: column={}@{}",
+                self.schema.field(self.column).name(),
+                self.column
+            ),
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn metrics(&self) -> Option<MetricsSet> {
+        Some(self.metrics.clone_inner())
+    }
+
+    fn statistics(&self) -> Statistics {
+        // the number of rows depends on the lengths of the lists
+        Statistics::default()
+    }
+}
+
+/// Unnest the lists of the column at index `column` of `batch`
+fn unnest_batch(
+    batch: &RecordBatch,
+    column: usize,
+    schema: &SchemaRef,
+) -> Result<RecordBatch> {
+    let (values, ranges) = list_ranges(batch.column(column).as_ref())?;
+
+    // for each output row, the index of its input row and of its element
+    let mut row_indices = vec![];
+    let mut value_indices = vec![];
+    for (row, range) in ranges.into_iter().enumerate() {
+        for value in range.into_iter().flatten() {
+            row_indices.push(row as u32);
+            value_indices.push(value as u32);
+        }
+    }
+    let row_indices = UInt32Array::from(row_indices);
+    let value_indices = UInt32Array::from(value_indices);
+
+    let columns = batch
+        .columns()
+        .iter()
+        .enumerate()
+        .map(|(i, array)| {
+            if i == column {
+                take(values.as_ref(), &value_indices, None)
+            } else {
+                take(array.as_ref(), &row_indices, None)
+            }
+        })
+        .collect::<ArrowResult<Vec<_>>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), columns)?)
+}
+
+/// Unnest iterator
+struct UnnestStream {
+    schema: SchemaRef,
+    column: usize,
+    input: SendableRecordBatchStream,
+    baseline_metrics: BaselineMetrics,
+}
+
+impl UnnestStream {
+    fn batch_unnest(&self, batch: &RecordBatch) -> ArrowResult<RecordBatch> {
+        // records time on drop
+        let _timer = self.baseline_metrics.elapsed_compute().timer();
+        unnest_batch(batch, self.column, &self.schema)
+            .map_err(DataFusionError::into_arrow_external_error)
+    }
+}
+
+impl Stream for UnnestStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let poll = self.input.poll_next_unpin(cx).map(|x| match x {
+            Some(Ok(batch)) => Some(self.batch_unnest(&batch)),
+            other => other,
+        });
+
+        self.baseline_metrics.record_poll(poll)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // same number of record batches
+        self.input.size_hint()
+    }
+}
+
+impl RecordBatchStream for UnnestStream {
+    /// Get the schema
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assert_batches_eq;
+    use crate::execution::context::ExecutionContext;
+    use crate::physical_plan::collect;
+    use crate::physical_plan::memory::MemoryExec;
+    use arrow::array::{ArrayRef, Int32Array, ListArray, StringArray};
+    use arrow::datatypes::{DataType, Int32Type};
+
+    #[tokio::test]
+    async fn unnest_lists() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
+        let list = ListArray::from_iter_primitive::<Int32Type, _, _>(vec![
+            Some(vec![Some(1), Some(2)]),
+            Some(vec![]),
+            None,
+            Some(vec![None, Some(3), Some(4)]),
+        ]);
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("id", DataType::Utf8, false),
+            Field::new("values", list.data_type().clone(), true),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b", "c", "d"])) as ArrayRef,
+                Arc::new(list),
+            ],
+        )?;
+        let input = MemoryExec::try_new(&[vec![batch]], schema, None)?;
+        let unnest = Arc::new(UnnestExec::try_new(Arc::new(input), 1)?);
+        assert_eq!(unnest.schema().field(1).data_type(), &DataType::Int32);
+
+        let results = collect(unnest.clone(), task_ctx).await?;
+        // empty and null lists produce no rows
+        let expected = vec![
+            "+----+--------+",
+            "| id | values |",
+            "+----+--------+",
+            "| a  | 1      |",
+            "| a  | 2      |",
+            "| d  |        |",
+            "| d  | 3      |",
+            "| d  | 4      |",
+            "+----+--------+",
+        ];
+        assert_batches_eq!(expected, &results);
+
+        let output_rows = unnest.metrics().unwrap().output_rows().unwrap();
+        assert_eq!(output_rows, 5);
+        Ok(())
+    }
+
+    #[test]
+    fn unnest_not_a_list() -> Result<()> {
+        let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(Int32Array::from(vec![1]))],
+        )?;
+        let input = MemoryExec::try_new(&[vec![batch]], schema, None)?;
+        let err = UnnestExec::try_new(Arc::new(input), 0).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Error during planning: Can not unnest a of type Int32, which is not a list"
+        );
+        Ok(())
+    }
+}
@@ -19,9 +19,11 @@
 
 use super::{common, SendableRecordBatchStream, Statistics};
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    memory::MemoryStream, ColumnarValue, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, PhysicalExpr, LambdaExecPlan,
+    fmt_with_statistics, memory::MemoryStream, ColumnarValue, DisplayFormatType,
+    Distribution, ExecutionPlan, LambdaExecPlan, Partitioning, PhysicalExpr,
 };
 use crate::scalar::ScalarValue;
 use arrow::array::new_null_array;
@@ -37,8 +39,8 @@ use serde::{Deserialize, Serialize};
 pub struct ValuesExec {
     /// The schema
     schema: SchemaRef,
-    /// The data
-    #[serde(skip)]
+    /// The data, which is part of the plan and thus serialized with it
+    #[serde(with = "crate::physical_plan::serde::record_batches")]
     data: Vec<RecordBatch>,
 }
 
@@ -98,8 +100,10 @@ impl ValuesExec {
 
 #[async_trait]
 impl LambdaExecPlan for ValuesExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!();
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        // the values are a single partition
+        self.data = partitions.into_iter().flatten().collect();
+        Ok(())
     }
 }
 
@@ -147,7 +151,21 @@ impl ExecutionPlan for ValuesExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "ValuesExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         // GlobalLimitExec has a single output partition
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
@@ -156,11 +174,15 @@ impl ExecutionPlan for ValuesExec {
             )));
         }
 
-        Ok(Box::pin(MemoryStream::try_new(
-            self.data(),
-            self.schema.clone(),
-            None,
-        )?))
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(MemoryStream::try_new(
+                self.data(),
+                self.schema.clone(),
+                None,
+            )?),
+        ))
     }
 
     fn fmt_as(
@@ -172,6 +194,7 @@ impl ExecutionPlan for ValuesExec {
             DisplayFormatType::Default => {
                 write!(f, "ValuesExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -24,7 +24,8 @@ use crate::error::{DataFusionError, Result};
 use crate::physical_plan::functions::{TypeSignature, Volatility};
 use crate::physical_plan::{
     aggregates, aggregates::AggregateFunction, functions::Signature,
-    type_coercion::data_types, windows::find_ranges_in_range, PhysicalExpr,
+    type_coercion::data_types, udaf::AggregateUDF, udwf::WindowUDF,
+    windows::find_ranges_in_range, PhysicalExpr,
 };
 use arrow::array::ArrayRef;
 use arrow::datatypes::DataType;
@@ -38,12 +39,16 @@ use std::{fmt, str::FromStr};
 use serde::{Deserialize, Serialize};
 
 /// WindowFunction
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd)]
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum WindowFunction {
     /// window function that leverages an aggregate function
     AggregateFunction(AggregateFunction),
     /// window function that leverages a built-in window function
     BuiltInWindowFunction(BuiltInWindowFunction),
+    /// window function that leverages a user-defined aggregate function
+    AggregateUDF(Arc<AggregateUDF>),
+    /// user-defined window function
+    WindowUDF(Arc<WindowUDF>),
 }
 
 impl FromStr for WindowFunction {
@@ -88,6 +93,8 @@ impl fmt::Display for WindowFunction {
         match self {
             WindowFunction::AggregateFunction(fun) => fun.fmt(f),
             WindowFunction::BuiltInWindowFunction(fun) => fun.fmt(f),
+            WindowFunction::AggregateUDF(fun) => write!(f, "{}", fun.name),
+            WindowFunction::WindowUDF(fun) => write!(f, "{}", fun.name),
         }
     }
 }
@@ -162,6 +169,14 @@ pub fn return_type(
         WindowFunction::BuiltInWindowFunction(fun) => {
             return_type_for_built_in(fun, input_expr_types)
         }
+        WindowFunction::AggregateUDF(fun) => {
+            let input_expr_types = data_types(input_expr_types, &fun.signature)?;
+            Ok((fun.return_type)(&input_expr_types)?.as_ref().clone())
+        }
+        WindowFunction::WindowUDF(fun) => {
+            let input_expr_types = data_types(input_expr_types, &fun.signature)?;
+            Ok((fun.return_type)(&input_expr_types)?.as_ref().clone())
+        }
     }
 }
 
@@ -197,6 +212,8 @@ pub fn signature(fun: &WindowFunction) -> Signature {
     match fun {
         WindowFunction::AggregateFunction(fun) => aggregates::signature(fun),
         WindowFunction::BuiltInWindowFunction(fun) => signature_for_built_in(fun),
+        WindowFunction::AggregateUDF(fun) => fun.signature.clone(),
+        WindowFunction::WindowUDF(fun) => fun.signature.clone(),
     }
 }
 
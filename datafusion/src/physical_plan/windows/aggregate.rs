@@ -18,11 +18,15 @@
 //! Physical exec for aggregate window function expressions.
 
 use crate::error::{DataFusionError, Result};
-use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
+use crate::logical_plan::window_frames::{
+    WindowFrame, WindowFrameBound, WindowFrameUnits,
+};
 use crate::physical_plan::windows::find_ranges_in_range;
 use crate::physical_plan::{
     expressions::PhysicalSortExpr, Accumulator, AggregateExpr, PhysicalExpr, WindowExpr,
 };
+use crate::scalar::ScalarValue;
+use arrow::array::new_empty_array;
 use arrow::compute::concat;
 use arrow::record_batch::RecordBatch;
 use arrow::{array::ArrayRef, datatypes::Field};
@@ -67,7 +71,10 @@ impl AggregateWindowExpr {
     /// create a new accumulator based on the underlying aggregation function
     fn create_accumulator(&self) -> Result<AggregateWindowAccumulator> {
         let accumulator = self.aggregate.create_accumulator()?;
-        Ok(AggregateWindowAccumulator { accumulator })
+        Ok(AggregateWindowAccumulator {
+            accumulator,
+            frame: 0..0,
+        })
     }
 
     /// peer based evaluation based on the fact that batch is pre-sorted given the sort columns
@@ -106,14 +113,67 @@ impl AggregateWindowExpr {
         )))
     }
 
-    fn row_based_evaluate(&self, _batch: &RecordBatch) -> Result<ArrayRef> {
-        Err(DataFusionError::NotImplemented(format!(
-            "Row based evaluation for {} is not yet implemented",
-            self.name()
-        )))
+    /// row based evaluation, where the frame of every row is given by offsets from that row
+    /// within its partition. Since the frames only move forward, the accumulator slides along
+    /// the partition when it can retract the rows leaving the frame (or when no row ever
+    /// leaves it), otherwise every frame is accumulated from scratch.
+    fn row_based_evaluate(&self, batch: &RecordBatch) -> Result<ArrayRef> {
+        let num_rows = batch.num_rows();
+        if num_rows == 0 {
+            return Ok(new_empty_array(self.field()?.data_type()));
+        }
+        let window_frame = self.window_frame.unwrap_or_default();
+        let partition_points =
+            self.evaluate_partition_points(num_rows, &self.partition_columns(batch)?)?;
+        let values = self.evaluate_args(batch)?;
+        let mut results = Vec::with_capacity(num_rows);
+        for partition_range in &partition_points {
+            let mut window_accumulator = self.create_accumulator()?;
+            let sliding = window_accumulator.accumulator.supports_retract_batch()
+                || matches!(window_frame.start_bound, WindowFrameBound::Preceding(None));
+            for row in partition_range.clone() {
+                let frame = row_frame(&window_frame, row, partition_range);
+                let value = if sliding {
+                    window_accumulator.slide(&values, &frame)?
+                } else {
+                    self.create_accumulator()?.slide(&values, &frame)?
+                };
+                results.push(value);
+            }
+        }
+        ScalarValue::iter_to_array(results)
     }
 }
 
+/// The rows of the partition `partition_range` in the ROWS frame of `row`, empty when the
+/// frame lies entirely outside of the partition.
+pub(crate) fn row_frame(
+    window_frame: &WindowFrame,
+    row: usize,
+    partition_range: &Range<usize>,
+) -> Range<usize> {
+    let offset = |n: u64| usize::try_from(n).unwrap_or(usize::MAX);
+    let start = match window_frame.start_bound {
+        WindowFrameBound::Preceding(None) => partition_range.start,
+        WindowFrameBound::Preceding(Some(n)) => row.saturating_sub(offset(n)),
+        WindowFrameBound::CurrentRow => row,
+        WindowFrameBound::Following(Some(n)) => row.saturating_add(offset(n)),
+        WindowFrameBound::Following(None) => partition_range.end,
+    };
+    let end = match window_frame.end_bound {
+        WindowFrameBound::Preceding(None) => partition_range.start,
+        WindowFrameBound::Preceding(Some(n)) => (row + 1).saturating_sub(offset(n)),
+        WindowFrameBound::CurrentRow => row + 1,
+        WindowFrameBound::Following(Some(n)) => {
+            row.saturating_add(offset(n)).saturating_add(1)
+        }
+        WindowFrameBound::Following(None) => partition_range.end,
+    };
+    let start = start.clamp(partition_range.start, partition_range.end);
+    let end = end.clamp(start, partition_range.end);
+    start..end
+}
+
 #[typetag::serde(name = "aggregate_window_expr")]
 impl WindowExpr for AggregateWindowExpr {
     /// Return a reference to Any that can be used for downcasting
@@ -156,6 +216,8 @@ impl WindowExpr for AggregateWindowExpr {
 #[derive(Debug)]
 struct AggregateWindowAccumulator {
     accumulator: Box<dyn Accumulator>,
+    /// the rows currently accumulated, when sliding along the rows of a partition
+    frame: Range<usize>,
 }
 
 impl AggregateWindowAccumulator {
@@ -180,4 +242,34 @@ impl AggregateWindowAccumulator {
         let value = self.accumulator.evaluate()?;
         Ok(value.to_array_of_size(len))
     }
+
+    /// move the accumulated rows to `frame`, which must not start or end before the current
+    /// frame, and return the evaluation result over the new frame. Rows leaving the frame are
+    /// retracted from the accumulator, and rows entering it are added.
+    fn slide(
+        &mut self,
+        values: &[ArrayRef],
+        frame: &Range<usize>,
+    ) -> Result<ScalarValue> {
+        if self.frame.is_empty() {
+            self.frame = frame.start..frame.start;
+        }
+        let added = self.frame.end.max(frame.start)..frame.end;
+        if !added.is_empty() {
+            self.accumulator.update_batch(&slice(values, &added))?;
+        }
+        let retracted = self.frame.start..self.frame.end.min(frame.start);
+        if !retracted.is_empty() {
+            self.accumulator.retract_batch(&slice(values, &retracted))?;
+        }
+        self.frame = frame.clone();
+        self.accumulator.evaluate()
+    }
+}
+
+fn slice(values: &[ArrayRef], range: &Range<usize>) -> Vec<ArrayRef> {
+    values
+        .iter()
+        .map(|v| v.slice(range.start, range.end - range.start))
+        .collect()
 }
@@ -26,6 +26,7 @@ use crate::physical_plan::{
         PhysicalSortExpr, RowNumber,
     },
     type_coercion::coerce,
+    udaf, udwf,
     window_functions::{
         signature_for_built_in, BuiltInWindowFunction, BuiltInWindowFunctionExpr,
         WindowFunction,
@@ -42,6 +43,7 @@ mod aggregate;
 mod built_in;
 mod window_agg_exec;
 
+pub(crate) use aggregate::row_frame;
 pub use aggregate::AggregateWindowExpr;
 pub use built_in::BuiltInWindowExpr;
 pub use window_agg_exec::WindowAggExec;
@@ -68,6 +70,21 @@ pub fn create_window_expr(
             partition_by,
             order_by,
         )),
+        WindowFunction::AggregateUDF(fun) => Arc::new(AggregateWindowExpr::new(
+            udaf::create_aggregate_expr(fun, args, input_schema, name)?,
+            partition_by,
+            order_by,
+            window_frame,
+        )),
+        WindowFunction::WindowUDF(fun) => udwf::create_window_expr(
+            fun,
+            args,
+            partition_by,
+            order_by,
+            window_frame,
+            input_schema,
+            name,
+        )?,
     })
 }
 
@@ -174,6 +191,7 @@ pub(crate) fn find_ranges_in_range<'a>(
 mod tests {
     use super::*;
     use crate::datasource::object_store::local::LocalFileSystem;
+    use crate::execution::context::ExecutionContext;
     use crate::physical_plan::aggregates::AggregateFunction;
     use crate::physical_plan::expressions::col;
     use crate::physical_plan::file_format::{CsvExec, PhysicalPlanConfig};
@@ -182,6 +200,7 @@ mod tests {
     use crate::test::{self, assert_is_pending};
     use crate::test_util::{self, aggr_test_schema};
     use arrow::array::*;
+    use arrow::compute::SortOptions;
     use arrow::datatypes::{DataType, Field, SchemaRef};
     use arrow::record_batch::RecordBatch;
     use futures::FutureExt;
@@ -211,6 +230,7 @@ mod tests {
 
     #[tokio::test]
     async fn window_function() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let (input, schema) = create_test_schema(1)?;
 
         let window_exec = Arc::new(WindowAggExec::try_new(
@@ -247,7 +267,7 @@ mod tests {
             schema.clone(),
         )?);
 
-        let result: Vec<RecordBatch> = collect(window_exec).await?;
+        let result: Vec<RecordBatch> = collect(window_exec, task_ctx).await?;
         assert_eq!(result.len(), 1);
 
         let columns = result[0].columns();
@@ -269,8 +289,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn window_sort_expr() -> Result<()> {
+        let (input, schema) = create_test_schema(1)?;
+        let order_by = vec![PhysicalSortExpr {
+            expr: col("c3", &schema)?,
+            options: SortOptions {
+                descending: true,
+                nulls_first: true,
+            },
+        }];
+        let window_exec = WindowAggExec::try_new(
+            vec![create_window_expr(
+                &WindowFunction::AggregateFunction(AggregateFunction::Count),
+                "count".to_owned(),
+                &[col("c3", &schema)?],
+                &[col("c1", &schema)?],
+                &order_by,
+                Some(WindowFrame::default()),
+                schema.as_ref(),
+            )?],
+            input,
+            schema.clone(),
+        )?;
+
+        let sort_expr = window_exec
+            .sort_expr()
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["c1@0 ASC", "c3@2 DESC"], sort_expr);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_drop_cancel() -> Result<()> {
+        let task_ctx = ExecutionContext::new().task_ctx();
         let schema =
             Arc::new(Schema::new(vec![Field::new("a", DataType::Float32, true)]));
 
@@ -290,7 +344,7 @@ mod tests {
             schema,
         )?);
 
-        let fut = collect(window_agg_exec);
+        let fut = collect(window_agg_exec, task_ctx);
         let mut fut = fut.boxed();
 
         assert_is_pending(&mut fut);
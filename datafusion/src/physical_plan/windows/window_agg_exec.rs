@@ -18,17 +18,22 @@
 //! Stream and channel implementations for window function expressions.
 
 use crate::error::{DataFusionError, Result};
+use crate::execution::task_context::TaskContext;
 use crate::physical_plan::common::AbortOnDropSingle;
+use crate::physical_plan::expressions::{Column, PhysicalSortExpr};
+use crate::physical_plan::memory::feed_input;
 use crate::physical_plan::metrics::{
     BaselineMetrics, ExecutionPlanMetricsSet, MetricsSet,
 };
+use crate::physical_plan::trace::instrument_stream;
 use crate::physical_plan::{
-    common, ColumnStatistics, DisplayFormatType, Distribution, ExecutionPlan,
-    Partitioning, RecordBatchStream, SendableRecordBatchStream, Statistics,
-    WindowExpr, LambdaExecPlan,
+    common, fmt_with_statistics, ColumnStatistics, DisplayFormatType, Distribution,
+    ExecutionPlan, LambdaExecPlan, Partitioning, RecordBatchStream,
+    SendableRecordBatchStream, Statistics, WindowExpr,
 };
 use arrow::{
     array::ArrayRef,
+    compute::SortOptions,
     datatypes::{Schema, SchemaRef},
     error::{ArrowError, Result as ArrowResult},
     record_batch::RecordBatch,
@@ -55,6 +60,12 @@ pub struct WindowAggExec {
     schema: SchemaRef,
     /// Schema before the window
     input_schema: SchemaRef,
+    /// The sort order the input is expected in
+    #[serde(default)]
+    sort_expr: Vec<PhysicalSortExpr>,
+    /// The sort order of the output, the one of the input
+    #[serde(default)]
+    output_ordering: Option<Vec<PhysicalSortExpr>>,
     /// Execution metrics
     metrics: ExecutionPlanMetricsSet,
 }
@@ -68,11 +79,32 @@ impl WindowAggExec {
     ) -> Result<Self> {
         let schema = create_schema(&input_schema, &window_expr)?;
         let schema = Arc::new(schema);
+        let sort_expr = create_sort_expr(&window_expr);
+        // the rows are output in the order of the input, after the window
+        // columns shifting the columns of the input
+        let output_ordering = input.output_ordering().and_then(|ordering| {
+            let ordering = ordering
+                .iter()
+                .map_while(|sort_expr| {
+                    let column = sort_expr.expr.as_any().downcast_ref::<Column>()?;
+                    Some(PhysicalSortExpr {
+                        expr: Arc::new(Column::new(
+                            column.name(),
+                            column.index() + window_expr.len(),
+                        )),
+                        options: sort_expr.options,
+                    })
+                })
+                .collect::<Vec<_>>();
+            Some(ordering).filter(|ordering| !ordering.is_empty())
+        });
         Ok(Self {
             input,
             window_expr,
             schema,
             input_schema,
+            sort_expr,
+            output_ordering,
             metrics: ExecutionPlanMetricsSet::new(),
         })
     }
@@ -82,6 +114,12 @@ impl WindowAggExec {
         &self.window_expr
     }
 
+    /// The sort order the input is expected in: the partition keys, then the
+    /// sort keys of the window functions, which all share them
+    pub fn sort_expr(&self) -> &[PhysicalSortExpr] {
+        &self.sort_expr
+    }
+
     /// Input plan
     pub fn input(&self) -> &Arc<dyn ExecutionPlan> {
         &self.input
@@ -95,8 +133,9 @@ impl WindowAggExec {
 
 #[async_trait]
 impl LambdaExecPlan for WindowAggExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
-        unimplemented!("WindowAggExec::feed_batches");
+    fn feed_batches(&mut self, partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        feed_input(&mut self.input, partitions);
+        Ok(())
     }
 }
 
@@ -128,6 +167,14 @@ impl ExecutionPlan for WindowAggExec {
         self.input.output_partitioning()
     }
 
+    fn output_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        self.output_ordering.as_deref()
+    }
+
+    fn required_input_ordering(&self) -> Option<&[PhysicalSortExpr]> {
+        Some(self.sort_expr.as_slice()).filter(|sort_expr| !sort_expr.is_empty())
+    }
+
     fn required_child_distribution(&self) -> Distribution {
         if self
             .window_expr()
@@ -156,15 +203,31 @@ impl ExecutionPlan for WindowAggExec {
         }
     }
 
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
-        let input = self.input.execute(partition).await?;
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "WindowAggExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        let baseline_metrics = BaselineMetrics::new(&self.metrics, partition)
+            .with_progress(context.progress_reporter("WindowAggExec", partition));
+        let input = self.input.execute(partition, context).await?;
         let stream = Box::pin(WindowAggStream::new(
             self.schema.clone(),
             self.window_expr.clone(),
             input,
-            BaselineMetrics::new(&self.metrics, partition),
+            baseline_metrics,
         ));
-        Ok(stream)
+        Ok(instrument_stream(self, partition, stream))
     }
 
     fn fmt_as(
@@ -182,6 +245,7 @@ impl ExecutionPlan for WindowAggExec {
                     .collect();
                 write!(f, "wdw=[{}]", g.join(", "))?;
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
         Ok(())
     }
@@ -223,6 +287,26 @@ fn create_schema(
     Ok(Schema::new(fields))
 }
 
+fn create_sort_expr(window_expr: &[Arc<dyn WindowExpr>]) -> Vec<PhysicalSortExpr> {
+    let window_expr = match window_expr.first() {
+        Some(window_expr) => window_expr,
+        None => return vec![],
+    };
+    let partition_options = SortOptions {
+        descending: false,
+        nulls_first: true,
+    };
+    window_expr
+        .partition_by()
+        .iter()
+        .map(|expr| PhysicalSortExpr {
+            expr: expr.clone(),
+            options: partition_options,
+        })
+        .chain(window_expr.order_by().iter().cloned())
+        .collect()
+}
+
 /// Compute the window aggregate columns
 fn compute_window_aggregates(
     window_expr: Vec<Arc<dyn WindowExpr>>,
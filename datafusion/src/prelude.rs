@@ -27,13 +27,17 @@
 
 pub use crate::dataframe::DataFrame;
 pub use crate::execution::context::{ExecutionConfig, ExecutionContext};
-pub use crate::execution::options::AvroReadOptions;
-pub use crate::execution::options::{CsvReadOptions, NdJsonReadOptions};
+pub use crate::execution::options::{AlignOptions, AvroReadOptions, IpcReadOptions};
+pub use crate::execution::options::{
+    CsvQuoteStyle, CsvReadOptions, CsvWriteOptions, NdJsonReadOptions, ScanErrorPolicy,
+};
 pub use crate::logical_plan::{
-    array, ascii, avg, bit_length, btrim, character_length, chr, col, concat, concat_ws,
-    count, create_udf, date_part, date_trunc, digest, in_list, initcap, left, length,
-    lit, lower, lpad, ltrim, max, md5, min, now, octet_length, random, regexp_match,
-    regexp_replace, repeat, replace, reverse, right, rpad, rtrim, sha224, sha256, sha384,
-    sha512, split_part, starts_with, strpos, substr, sum, to_hex, translate, trim, upper,
+    array, array_contains, array_length, array_position, ascii, avg, bit_length, btrim,
+    cast, character_length, chr, col, concat, concat_ws, count, create_async_udf,
+    create_udf, create_udf_with_signature, date_part, date_trunc, digest, in_list,
+    initcap, left, length, lit, lower, lpad, ltrim, max, md5, min, named_struct, now,
+    octet_length, random, regexp_match, regexp_replace, repeat, replace, reverse, right,
+    rpad, rtrim, sha224, sha256, sha384, sha512, similar_to_escape, split_part,
+    starts_with, strpos, struct_, substr, sum, to_hex, translate, trim, try_cast, upper,
     Column, JoinType, Partitioning,
 };
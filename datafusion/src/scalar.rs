@@ -77,6 +77,8 @@ pub enum ScalarValue {
     Binary(Option<Vec<u8>>),
     /// large binary
     LargeBinary(Option<Vec<u8>>),
+    /// fixed size binary, of the given number of bytes
+    FixedSizeBinary(i32, Option<Vec<u8>>),
     /// list of nested ScalarValue (boxed to reduce size_of(ScalarValue))
     #[allow(clippy::box_collection)]
     List(Option<Box<Vec<ScalarValue>>>, Box<DataType>),
@@ -99,6 +101,8 @@ pub enum ScalarValue {
     /// struct of nested ScalarValue (boxed to reduce size_of(ScalarValue))
     #[allow(clippy::box_collection)]
     Struct(Option<Box<Vec<ScalarValue>>>, Box<Vec<Field>>),
+    /// value of a dictionary encoded array, with keys of the given type
+    Dictionary(Box<DataType>, Box<ScalarValue>),
 }
 
 // manual implementation of `PartialEq` that uses OrderedFloat to
@@ -152,6 +156,8 @@ impl PartialEq for ScalarValue {
             (Binary(_), _) => false,
             (LargeBinary(v1), LargeBinary(v2)) => v1.eq(v2),
             (LargeBinary(_), _) => false,
+            (FixedSizeBinary(s1, v1), FixedSizeBinary(s2, v2)) => s1.eq(s2) && v1.eq(v2),
+            (FixedSizeBinary(_, _), _) => false,
             (List(v1, t1), List(v2, t2)) => v1.eq(v2) && t1.eq(t2),
             (List(_, _), _) => false,
             (Date32(v1), Date32(v2)) => v1.eq(v2),
@@ -172,6 +178,8 @@ impl PartialEq for ScalarValue {
             (IntervalDayTime(_), _) => false,
             (Struct(v1, t1), Struct(v2, t2)) => v1.eq(v2) && t1.eq(t2),
             (Struct(_, _), _) => false,
+            (Dictionary(k1, v1), Dictionary(k2, v2)) => k1.eq(k2) && v1.eq(v2),
+            (Dictionary(_, _), _) => false,
         }
     }
 }
@@ -232,6 +240,14 @@ impl PartialOrd for ScalarValue {
             (Binary(_), _) => None,
             (LargeBinary(v1), LargeBinary(v2)) => v1.partial_cmp(v2),
             (LargeBinary(_), _) => None,
+            (FixedSizeBinary(s1, v1), FixedSizeBinary(s2, v2)) => {
+                if s1.eq(s2) {
+                    v1.partial_cmp(v2)
+                } else {
+                    None
+                }
+            }
+            (FixedSizeBinary(_, _), _) => None,
             (List(v1, t1), List(v2, t2)) => {
                 if t1.eq(t2) {
                     v1.partial_cmp(v2)
@@ -270,6 +286,14 @@ impl PartialOrd for ScalarValue {
                 }
             }
             (Struct(_, _), _) => None,
+            (Dictionary(k1, v1), Dictionary(k2, v2)) => {
+                if k1.eq(k2) {
+                    v1.partial_cmp(v2)
+                } else {
+                    None
+                }
+            }
+            (Dictionary(_, _), _) => None,
         }
     }
 }
@@ -308,6 +332,7 @@ impl std::hash::Hash for ScalarValue {
             LargeUtf8(v) => v.hash(state),
             Binary(v) => v.hash(state),
             LargeBinary(v) => v.hash(state),
+            FixedSizeBinary(_, v) => v.hash(state),
             List(v, t) => {
                 v.hash(state);
                 t.hash(state);
@@ -324,6 +349,10 @@ impl std::hash::Hash for ScalarValue {
                 v.hash(state);
                 t.hash(state);
             }
+            Dictionary(k, v) => {
+                k.hash(state);
+                v.hash(state);
+            }
         }
     }
 }
@@ -353,6 +382,61 @@ fn get_dict_value<K: ArrowDictionaryKeyType>(
     Ok((dict_array.values(), Some(values_index)))
 }
 
+/// The index into the dictionary values for the dictionary array `array` at
+/// `index`, as well as a reference to the dictionary values array. Returns
+/// None for the index if the array is NULL at index
+pub(crate) fn dictionary_value_index(
+    array: &ArrayRef,
+    index: usize,
+) -> Result<(&ArrayRef, Option<usize>)> {
+    match array.data_type() {
+        DataType::Dictionary(index_type, _) => match **index_type {
+            DataType::Int8 => get_dict_value::<Int8Type>(array, index),
+            DataType::Int16 => get_dict_value::<Int16Type>(array, index),
+            DataType::Int32 => get_dict_value::<Int32Type>(array, index),
+            DataType::Int64 => get_dict_value::<Int64Type>(array, index),
+            DataType::UInt8 => get_dict_value::<UInt8Type>(array, index),
+            DataType::UInt16 => get_dict_value::<UInt16Type>(array, index),
+            DataType::UInt32 => get_dict_value::<UInt32Type>(array, index),
+            DataType::UInt64 => get_dict_value::<UInt64Type>(array, index),
+            _ => Err(DataFusionError::Internal(format!(
+                "Index type not supported while creating scalar from dictionary: {}",
+                array.data_type(),
+            ))),
+        },
+        other => Err(DataFusionError::Internal(format!(
+            "Expected a dictionary array, found an array of {:?}",
+            other
+        ))),
+    }
+}
+
+/// A dictionary array of `size` rows with keys of type `K` referencing the
+/// single value of `values`, or null if this value is
+fn dictionary_array_of_size<K: ArrowDictionaryKeyType>(
+    values: ArrayRef,
+    size: usize,
+) -> ArrayRef {
+    let keys: PrimitiveArray<K> = if values.is_valid(0) {
+        repeat(Some(K::Native::default())).take(size).collect()
+    } else {
+        repeat(None).take(size).collect()
+    };
+    let keys = keys.data();
+    let data_type = DataType::Dictionary(
+        Box::new(K::DATA_TYPE),
+        Box::new(values.data_type().clone()),
+    );
+    let mut builder = ArrayData::builder(data_type)
+        .len(size)
+        .add_buffer(keys.buffers()[0].clone())
+        .add_child_data(values.data().clone());
+    if let Some(nulls) = keys.null_buffer() {
+        builder = builder.null_bit_buffer(nulls.clone());
+    }
+    Arc::new(DictionaryArray::<K>::from(builder.build().unwrap()))
+}
+
 macro_rules! typed_cast_tz {
     ($array:expr, $index:expr, $ARRAYTYPE:ident, $SCALAR:ident, $TZ:expr) => {{
         let array = $array.as_any().downcast_ref::<$ARRAYTYPE>().unwrap();
@@ -420,13 +504,13 @@ macro_rules! build_timestamp_list {
                         values,
                         $SIZE
                     ),
-                    TimeUnit::Microsecond => build_values_list_tz!(
+                    TimeUnit::Millisecond => build_values_list_tz!(
                         TimestampMillisecondBuilder,
                         TimestampMillisecond,
                         values,
                         $SIZE
                     ),
-                    TimeUnit::Millisecond => build_values_list_tz!(
+                    TimeUnit::Microsecond => build_values_list_tz!(
                         TimestampMicrosecondBuilder,
                         TimestampMicrosecond,
                         values,
@@ -576,6 +660,7 @@ impl ScalarValue {
             ScalarValue::LargeUtf8(_) => DataType::LargeUtf8,
             ScalarValue::Binary(_) => DataType::Binary,
             ScalarValue::LargeBinary(_) => DataType::LargeBinary,
+            ScalarValue::FixedSizeBinary(size, _) => DataType::FixedSizeBinary(*size),
             ScalarValue::List(_, data_type) => DataType::List(Box::new(Field::new(
                 "item",
                 data_type.as_ref().clone(),
@@ -588,6 +673,9 @@ impl ScalarValue {
             }
             ScalarValue::IntervalDayTime(_) => DataType::Interval(IntervalUnit::DayTime),
             ScalarValue::Struct(_, fields) => DataType::Struct(fields.as_ref().clone()),
+            ScalarValue::Dictionary(key_type, value) => {
+                DataType::Dictionary(key_type.clone(), Box::new(value.get_datatype()))
+            }
         }
     }
 
@@ -615,6 +703,9 @@ impl ScalarValue {
 
     /// whether this value is null or not.
     pub fn is_null(&self) -> bool {
+        if let ScalarValue::Dictionary(_, value) = self {
+            return value.is_null();
+        }
         matches!(
             *self,
             ScalarValue::Boolean(None)
@@ -632,11 +723,16 @@ impl ScalarValue {
                 | ScalarValue::Date64(None)
                 | ScalarValue::Utf8(None)
                 | ScalarValue::LargeUtf8(None)
+                | ScalarValue::Binary(None)
+                | ScalarValue::LargeBinary(None)
+                | ScalarValue::FixedSizeBinary(_, None)
                 | ScalarValue::List(None, _)
                 | ScalarValue::TimestampSecond(None, _)
                 | ScalarValue::TimestampMillisecond(None, _)
                 | ScalarValue::TimestampMicrosecond(None, _)
                 | ScalarValue::TimestampNanosecond(None, _)
+                | ScalarValue::IntervalYearMonth(None)
+                | ScalarValue::IntervalDayTime(None)
                 | ScalarValue::Struct(None, _)
                 | ScalarValue::Decimal128(None, _, _) // For decimal type, the value is null means ScalarValue::Decimal128 is null.
         )
@@ -718,9 +814,9 @@ impl ScalarValue {
         }
 
         macro_rules! build_array_primitive_tz {
-            ($ARRAY_TY:ident, $SCALAR_TY:ident) => {{
+            ($ARRAY_TY:ident, $SCALAR_TY:ident, $TZ:expr) => {{
                 {
-                    let array = scalars
+                    let values = scalars
                         .map(|sv| {
                             if let ScalarValue::$SCALAR_TY(v, _) = sv {
                                 Ok(v)
@@ -732,9 +828,9 @@ impl ScalarValue {
                                 )))
                             }
                         })
-                        .collect::<Result<$ARRAY_TY>>()?;
+                        .collect::<Result<Vec<_>>>()?;
 
-                    Arc::new(array)
+                    Arc::new($ARRAY_TY::from_opt_vec(values, $TZ.clone()))
                 }
             }};
         }
@@ -846,19 +942,34 @@ impl ScalarValue {
             DataType::LargeUtf8 => build_array_string!(LargeStringArray, LargeUtf8),
             DataType::Binary => build_array_string!(BinaryArray, Binary),
             DataType::LargeBinary => build_array_string!(LargeBinaryArray, LargeBinary),
+            DataType::FixedSizeBinary(size) => Arc::new(
+                ScalarValue::iter_to_fixed_size_binary_array(scalars, *size)?,
+            ),
             DataType::Date32 => build_array_primitive!(Date32Array, Date32),
             DataType::Date64 => build_array_primitive!(Date64Array, Date64),
-            DataType::Timestamp(TimeUnit::Second, _) => {
-                build_array_primitive_tz!(TimestampSecondArray, TimestampSecond)
+            DataType::Timestamp(TimeUnit::Second, tz_opt) => {
+                build_array_primitive_tz!(TimestampSecondArray, TimestampSecond, tz_opt)
             }
-            DataType::Timestamp(TimeUnit::Millisecond, _) => {
-                build_array_primitive_tz!(TimestampMillisecondArray, TimestampMillisecond)
+            DataType::Timestamp(TimeUnit::Millisecond, tz_opt) => {
+                build_array_primitive_tz!(
+                    TimestampMillisecondArray,
+                    TimestampMillisecond,
+                    tz_opt
+                )
             }
-            DataType::Timestamp(TimeUnit::Microsecond, _) => {
-                build_array_primitive_tz!(TimestampMicrosecondArray, TimestampMicrosecond)
+            DataType::Timestamp(TimeUnit::Microsecond, tz_opt) => {
+                build_array_primitive_tz!(
+                    TimestampMicrosecondArray,
+                    TimestampMicrosecond,
+                    tz_opt
+                )
             }
-            DataType::Timestamp(TimeUnit::Nanosecond, _) => {
-                build_array_primitive_tz!(TimestampNanosecondArray, TimestampNanosecond)
+            DataType::Timestamp(TimeUnit::Nanosecond, tz_opt) => {
+                build_array_primitive_tz!(
+                    TimestampNanosecondArray,
+                    TimestampNanosecond,
+                    tz_opt
+                )
             }
             DataType::Interval(IntervalUnit::DayTime) => {
                 build_array_primitive!(IntervalDayTimeArray, IntervalDayTime)
@@ -911,10 +1022,14 @@ impl ScalarValue {
                 // Initialize a Vector to store the ScalarValues for each column
                 let mut columns: Vec<Vec<ScalarValue>> =
                     (0..fields.len()).map(|_| Vec::new()).collect();
+                let mut valid = BooleanBufferBuilder::new(0);
+                let mut has_nulls = false;
 
                 // Iterate over scalars to populate the column scalars for each row
                 for scalar in scalars {
                     if let ScalarValue::Struct(values, fields) = scalar {
+                        valid.append(values.is_some());
+                        has_nulls |= values.is_none();
                         match values {
                             Some(values) => {
                                 // Push value for each field
@@ -949,7 +1064,25 @@ impl ScalarValue {
                     })
                     .collect::<Result<Vec<_>>>()?;
 
-                Arc::new(StructArray::from(field_values))
+                if has_nulls {
+                    Arc::new(StructArray::from((field_values, valid.finish())))
+                } else {
+                    Arc::new(StructArray::from(field_values))
+                }
+            }
+            DataType::Dictionary(key_type, _) => {
+                // build the array of the values, then dictionary encode it
+                let values = scalars
+                    .map(|sv| match sv {
+                        ScalarValue::Dictionary(k, value) if &k == key_type => Ok(*value),
+                        _ => Err(DataFusionError::Internal(format!(
+                            "Inconsistent types in ScalarValue::iter_to_array. \
+                             Expected {:?}, got {:?}",
+                            data_type, sv
+                        ))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                cast(&Self::iter_to_array(values)?, &data_type)?
             }
             _ => {
                 return Err(DataFusionError::Internal(format!(
@@ -990,6 +1123,33 @@ impl ScalarValue {
         Ok(builder.finish())
     }
 
+    fn iter_to_fixed_size_binary_array(
+        scalars: impl IntoIterator<Item = ScalarValue>,
+        size: i32,
+    ) -> Result<FixedSizeBinaryArray> {
+        let scalars = scalars.into_iter();
+        let mut builder = FixedSizeBinaryBuilder::new(scalars.size_hint().0, size);
+        for scalar in scalars {
+            match scalar {
+                ScalarValue::FixedSizeBinary(s, Some(value)) if s == size => {
+                    builder.append_value(&value)?
+                }
+                ScalarValue::FixedSizeBinary(s, None) if s == size => {
+                    builder.append_null()?
+                }
+                sv => {
+                    return Err(DataFusionError::Internal(format!(
+                        "Inconsistent types in ScalarValue::iter_to_array. \
+                         Expected {:?}, got {:?}",
+                        DataType::FixedSizeBinary(size),
+                        sv
+                    )))
+                }
+            }
+        }
+        Ok(builder.finish())
+    }
+
     fn iter_to_array_list(
         scalars: impl IntoIterator<Item = ScalarValue>,
         data_type: &DataType,
@@ -1040,9 +1200,15 @@ impl ScalarValue {
         // Concatenate element arrays to create single flat array
         let element_arrays: Vec<&dyn Array> =
             elements.iter().map(|a| a.as_ref()).collect();
-        let flat_array = match arrow::compute::concat(&element_arrays) {
-            Ok(flat_array) => flat_array,
-            Err(err) => return Err(DataFusionError::ArrowError(err)),
+        let flat_array = match data_type {
+            // all the lists are null
+            DataType::List(field) if elements.is_empty() => {
+                new_empty_array(field.data_type())
+            }
+            _ => match arrow::compute::concat(&element_arrays) {
+                Ok(flat_array) => flat_array,
+                Err(err) => return Err(DataFusionError::ArrowError(err)),
+            },
         };
 
         // Build ListArray using ArrayData so we can specify a flat inner array, and offset indices
@@ -1176,6 +1342,16 @@ impl ScalarValue {
                         .collect::<LargeBinaryArray>(),
                 ),
             },
+            ScalarValue::FixedSizeBinary(s, e) => match e {
+                Some(value) => {
+                    let mut builder = FixedSizeBinaryBuilder::new(size, *s);
+                    for _ in 0..size {
+                        builder.append_value(value).unwrap();
+                    }
+                    Arc::new(builder.finish())
+                }
+                None => new_null_array(&DataType::FixedSizeBinary(*s), size),
+            },
             ScalarValue::List(values, data_type) => Arc::new(match data_type.as_ref() {
                 DataType::Boolean => build_list!(BooleanBuilder, Boolean, values, size),
                 DataType::Int8 => build_list!(Int8Builder, Int8, values, size),
@@ -1248,10 +1424,40 @@ impl ScalarValue {
                             (field.clone(), none_field.to_array_of_size(size))
                         })
                         .collect();
+                    let mut valid = BooleanBufferBuilder::new(size);
+                    valid.append_n(size, false);
 
-                    Arc::new(StructArray::from(field_values))
+                    Arc::new(StructArray::from((field_values, valid.finish())))
                 }
             },
+            ScalarValue::Dictionary(key_type, value) => {
+                let values = value.to_array();
+                match key_type.as_ref() {
+                    DataType::Int8 => dictionary_array_of_size::<Int8Type>(values, size),
+                    DataType::Int16 => {
+                        dictionary_array_of_size::<Int16Type>(values, size)
+                    }
+                    DataType::Int32 => {
+                        dictionary_array_of_size::<Int32Type>(values, size)
+                    }
+                    DataType::Int64 => {
+                        dictionary_array_of_size::<Int64Type>(values, size)
+                    }
+                    DataType::UInt8 => {
+                        dictionary_array_of_size::<UInt8Type>(values, size)
+                    }
+                    DataType::UInt16 => {
+                        dictionary_array_of_size::<UInt16Type>(values, size)
+                    }
+                    DataType::UInt32 => {
+                        dictionary_array_of_size::<UInt32Type>(values, size)
+                    }
+                    DataType::UInt64 => {
+                        dictionary_array_of_size::<UInt64Type>(values, size)
+                    }
+                    _ => unreachable!("Invalid dictionary keys type: {:?}", key_type),
+                }
+            }
         }
     }
 
@@ -1295,6 +1501,19 @@ impl ScalarValue {
             DataType::LargeBinary => {
                 typed_cast!(array, index, LargeBinaryArray, LargeBinary)
             }
+            DataType::FixedSizeBinary(size) => {
+                let array = array
+                    .as_any()
+                    .downcast_ref::<FixedSizeBinaryArray>()
+                    .unwrap();
+                ScalarValue::FixedSizeBinary(
+                    *size,
+                    match array.is_null(index) {
+                        true => None,
+                        false => Some(array.value(index).into()),
+                    },
+                )
+            }
             DataType::Utf8 => typed_cast!(array, index, StringArray, Utf8),
             DataType::LargeUtf8 => typed_cast!(array, index, LargeStringArray, LargeUtf8),
             DataType::List(nested_type) => {
@@ -1324,6 +1543,12 @@ impl ScalarValue {
             DataType::Date64 => {
                 typed_cast!(array, index, Date64Array, Date64)
             }
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                typed_cast!(array, index, IntervalYearMonthArray, IntervalYearMonth)
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                typed_cast!(array, index, IntervalDayTimeArray, IntervalDayTime)
+            }
             DataType::Timestamp(TimeUnit::Second, tz_opt) => {
                 typed_cast_tz!(
                     array,
@@ -1360,23 +1585,8 @@ impl ScalarValue {
                     tz_opt
                 )
             }
-            DataType::Dictionary(index_type, _) => {
-                let (values, values_index) = match **index_type {
-                    DataType::Int8 => get_dict_value::<Int8Type>(array, index)?,
-                    DataType::Int16 => get_dict_value::<Int16Type>(array, index)?,
-                    DataType::Int32 => get_dict_value::<Int32Type>(array, index)?,
-                    DataType::Int64 => get_dict_value::<Int64Type>(array, index)?,
-                    DataType::UInt8 => get_dict_value::<UInt8Type>(array, index)?,
-                    DataType::UInt16 => get_dict_value::<UInt16Type>(array, index)?,
-                    DataType::UInt32 => get_dict_value::<UInt32Type>(array, index)?,
-                    DataType::UInt64 => get_dict_value::<UInt64Type>(array, index)?,
-                    _ => {
-                        return Err(DataFusionError::Internal(format!(
-                            "Index type not supported while creating scalar from dictionary: {}",
-                            array.data_type(),
-                        )));
-                    }
-                };
+            DataType::Dictionary(_, _) => {
+                let (values, values_index) = dictionary_value_index(array, index)?;
 
                 match values_index {
                     Some(values_index) => Self::try_from_array(values, values_index)?,
@@ -1487,7 +1697,12 @@ impl ScalarValue {
             ScalarValue::LargeBinary(val) => {
                 eq_array_primitive!(array, index, LargeBinaryArray, val)
             }
-            ScalarValue::List(_, _) => unimplemented!(),
+            ScalarValue::FixedSizeBinary(_, val) => {
+                eq_array_primitive!(array, index, FixedSizeBinaryArray, val)
+            }
+            ScalarValue::List(_, _) | ScalarValue::Struct(_, _) => {
+                Self::try_from_array(array, index).map_or(false, |value| &value == self)
+            }
             ScalarValue::Date32(val) => {
                 eq_array_primitive!(array, index, Date32Array, val)
             }
@@ -1512,7 +1727,8 @@ impl ScalarValue {
             ScalarValue::IntervalDayTime(val) => {
                 eq_array_primitive!(array, index, IntervalDayTimeArray, val)
             }
-            ScalarValue::Struct(_, _) => unimplemented!(),
+            // dictionaries are compared by value, like the arrow kernels do
+            ScalarValue::Dictionary(_, value) => value.eq_array(array, index),
         }
     }
 
@@ -1695,8 +1911,17 @@ impl TryFrom<&DataType> for ScalarValue {
             }
             DataType::Utf8 => ScalarValue::Utf8(None),
             DataType::LargeUtf8 => ScalarValue::LargeUtf8(None),
+            DataType::Binary => ScalarValue::Binary(None),
+            DataType::LargeBinary => ScalarValue::LargeBinary(None),
+            DataType::FixedSizeBinary(size) => ScalarValue::FixedSizeBinary(*size, None),
             DataType::Date32 => ScalarValue::Date32(None),
             DataType::Date64 => ScalarValue::Date64(None),
+            DataType::Interval(IntervalUnit::YearMonth) => {
+                ScalarValue::IntervalYearMonth(None)
+            }
+            DataType::Interval(IntervalUnit::DayTime) => {
+                ScalarValue::IntervalDayTime(None)
+            }
             DataType::Timestamp(TimeUnit::Second, tz_opt) => {
                 ScalarValue::TimestampSecond(None, tz_opt.clone())
             }
@@ -1737,12 +1962,26 @@ macro_rules! format_option {
     }};
 }
 
+/// Format the decimal `value` of scale `scale`, e.g. `-1.05` for -105 of
+/// scale 2
+fn format_decimal(value: i128, scale: usize) -> String {
+    let digits = value.unsigned_abs().to_string();
+    let sign = if value < 0 { "-" } else { "" };
+    if scale == 0 {
+        return format!("{}{}", sign, digits);
+    }
+    let digits = format!("{:0>width$}", digits, width = scale + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    format!("{}{}.{}", sign, integer, fraction)
+}
+
 impl fmt::Display for ScalarValue {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            ScalarValue::Decimal128(v, p, s) => {
-                write!(f, "{}", format!("{:?},{:?},{:?}", v, p, s))?;
-            }
+            ScalarValue::Decimal128(v, _, s) => match v {
+                Some(v) => write!(f, "{}", format_decimal(*v, *s))?,
+                None => write!(f, "NULL")?,
+            },
             ScalarValue::Boolean(e) => format_option!(f, e)?,
             ScalarValue::Float32(e) => format_option!(f, e)?,
             ScalarValue::Float64(e) => format_option!(f, e)?,
@@ -1760,25 +1999,13 @@ impl fmt::Display for ScalarValue {
             ScalarValue::TimestampNanosecond(e, _) => format_option!(f, e)?,
             ScalarValue::Utf8(e) => format_option!(f, e)?,
             ScalarValue::LargeUtf8(e) => format_option!(f, e)?,
-            ScalarValue::Binary(e) => match e {
+            ScalarValue::Binary(e)
+            | ScalarValue::LargeBinary(e)
+            | ScalarValue::FixedSizeBinary(_, e) => match e {
                 Some(l) => write!(
                     f,
                     "{}",
-                    l.iter()
-                        .map(|v| format!("{}", v))
-                        .collect::<Vec<_>>()
-                        .join(",")
-                )?,
-                None => write!(f, "NULL")?,
-            },
-            ScalarValue::LargeBinary(e) => match e {
-                Some(l) => write!(
-                    f,
-                    "{}",
-                    l.iter()
-                        .map(|v| format!("{}", v))
-                        .collect::<Vec<_>>()
-                        .join(",")
+                    l.iter().map(|v| format!("{:02x}", v)).collect::<String>()
                 )?,
                 None => write!(f, "NULL")?,
             },
@@ -1809,6 +2036,7 @@ impl fmt::Display for ScalarValue {
                 )?,
                 None => write!(f, "NULL")?,
             },
+            ScalarValue::Dictionary(_, value) => write!(f, "{}", value)?,
         };
         Ok(())
     }
@@ -1817,7 +2045,9 @@ impl fmt::Display for ScalarValue {
 impl fmt::Debug for ScalarValue {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ScalarValue::Decimal128(_, _, _) => write!(f, "Decimal128({})", self),
+            ScalarValue::Decimal128(_, p, s) => {
+                write!(f, "Decimal128({}, {}, {})", self, p, s)
+            }
             ScalarValue::Boolean(_) => write!(f, "Boolean({})", self),
             ScalarValue::Float32(_) => write!(f, "Float32({})", self),
             ScalarValue::Float64(_) => write!(f, "Float64({})", self),
@@ -1849,6 +2079,12 @@ impl fmt::Debug for ScalarValue {
             ScalarValue::Binary(Some(_)) => write!(f, "Binary(\"{}\")", self),
             ScalarValue::LargeBinary(None) => write!(f, "LargeBinary({})", self),
             ScalarValue::LargeBinary(Some(_)) => write!(f, "LargeBinary(\"{}\")", self),
+            ScalarValue::FixedSizeBinary(size, None) => {
+                write!(f, "FixedSizeBinary({}, {})", size, self)
+            }
+            ScalarValue::FixedSizeBinary(size, Some(_)) => {
+                write!(f, "FixedSizeBinary({}, \"{}\")", size, self)
+            }
             ScalarValue::List(_, _) => write!(f, "List([{}])", self),
             ScalarValue::Date32(_) => write!(f, "Date32(\"{}\")", self),
             ScalarValue::Date64(_) => write!(f, "Date64(\"{}\")", self),
@@ -1873,6 +2109,9 @@ impl fmt::Debug for ScalarValue {
                     None => write!(f, "Struct(NULL)"),
                 }
             }
+            ScalarValue::Dictionary(key_type, value) => {
+                write!(f, "Dictionary({:?}, {:?})", key_type, value)
+            }
         }
     }
 }
@@ -2209,6 +2448,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn scalar_iter_to_array_time_zone() {
+        let tz = Some("America/New_York".to_owned());
+        let scalars = vec![
+            ScalarValue::TimestampMicrosecond(Some(1), tz.clone()),
+            ScalarValue::TimestampMicrosecond(None, tz.clone()),
+        ];
+        let data_type = DataType::Timestamp(TimeUnit::Microsecond, tz);
+
+        let array = ScalarValue::iter_to_array(scalars).unwrap();
+        assert_eq!(array.data_type(), &data_type);
+        let scalar = ScalarValue::try_from_array(&array, 0).unwrap();
+        assert_eq!(scalar.get_datatype(), data_type);
+    }
+
+    #[test]
+    fn scalar_fixed_size_binary() {
+        let scalars = vec![
+            ScalarValue::FixedSizeBinary(2, Some(vec![0xde, 0xad])),
+            ScalarValue::FixedSizeBinary(2, None),
+        ];
+        let array = ScalarValue::iter_to_array(scalars.clone()).unwrap();
+        assert_eq!(array.data_type(), &DataType::FixedSizeBinary(2));
+        for (index, scalar) in scalars.iter().enumerate() {
+            assert_eq!(&ScalarValue::try_from_array(&array, index).unwrap(), scalar);
+            assert!(scalar.eq_array(&array, index));
+            assert_eq!(scalar.to_array_of_size(3).len(), 3);
+        }
+        assert_eq!(format!("{}", scalars[0]), "dead");
+        assert!(scalars[1].is_null());
+        assert_eq!(
+            ScalarValue::try_from(&DataType::FixedSizeBinary(2)).unwrap(),
+            scalars[1]
+        );
+
+        // the values must all be of the same size
+        let scalars = vec![
+            ScalarValue::FixedSizeBinary(2, None),
+            ScalarValue::FixedSizeBinary(3, None),
+        ];
+        assert!(ScalarValue::iter_to_array(scalars).is_err());
+    }
+
     #[test]
     fn scalar_iter_to_array_empty() {
         let scalars = vec![] as Vec<ScalarValue>;
@@ -3083,4 +3365,156 @@ mod tests {
             DataType::Timestamp(TimeUnit::Nanosecond, Some("UTC".to_owned()))
         );
     }
+
+    #[test]
+    fn scalar_decimal_display() {
+        let decimal = |v, p, s| ScalarValue::Decimal128(v, p, s);
+        assert_eq!(decimal(Some(12345), 10, 2).to_string(), "123.45");
+        assert_eq!(decimal(Some(-5), 10, 3).to_string(), "-0.005");
+        assert_eq!(decimal(Some(123), 3, 0).to_string(), "123");
+        assert_eq!(decimal(None, 10, 2).to_string(), "NULL");
+        assert_eq!(
+            format!("{:?}", decimal(Some(12345), 10, 2)),
+            "Decimal128(123.45, 10, 2)"
+        );
+    }
+
+    #[test]
+    fn scalar_dictionary() {
+        let scalar = ScalarValue::Dictionary(
+            Box::new(DataType::Int8),
+            Box::new(ScalarValue::from("foo")),
+        );
+        assert_eq!(
+            scalar.get_datatype(),
+            DataType::Dictionary(Box::new(DataType::Int8), Box::new(DataType::Utf8))
+        );
+        assert_eq!(scalar.to_string(), "foo");
+        assert_eq!(format!("{:?}", scalar), r#"Dictionary(Int8, Utf8("foo"))"#);
+        assert!(!scalar.is_null());
+
+        // dictionaries are compared by value with arrays
+        let array: ArrayRef = Arc::new(StringArray::from(vec!["foo", "bar"]));
+        assert!(scalar.eq_array(&array, 0));
+        assert!(!scalar.eq_array(&array, 1));
+
+        let array = scalar.to_array_of_size(2);
+        let array = array
+            .as_any()
+            .downcast_ref::<DictionaryArray<Int8Type>>()
+            .unwrap();
+        assert_eq!(array.keys(), &Int8Array::from(vec![0, 0]));
+        assert_eq!(array.values().len(), 1);
+    }
+
+    /// A value and a null of every variant
+    fn scalars_of_every_variant() -> Vec<ScalarValue> {
+        let list = |values: Vec<ScalarValue>, data_type| {
+            ScalarValue::List(Some(Box::new(values)), Box::new(data_type))
+        };
+        let values = vec![
+            ScalarValue::Boolean(Some(true)),
+            ScalarValue::Float32(Some(1.5)),
+            ScalarValue::Float64(Some(-2.25)),
+            ScalarValue::Decimal128(Some(-12345), 10, 2),
+            ScalarValue::Int8(Some(-8)),
+            ScalarValue::Int16(Some(-16)),
+            ScalarValue::Int32(Some(-32)),
+            ScalarValue::Int64(Some(-64)),
+            ScalarValue::UInt8(Some(8)),
+            ScalarValue::UInt16(Some(16)),
+            ScalarValue::UInt32(Some(32)),
+            ScalarValue::UInt64(Some(64)),
+            ScalarValue::Utf8(Some("foo".to_owned())),
+            ScalarValue::LargeUtf8(Some("bar".to_owned())),
+            ScalarValue::Binary(Some(b"foo".to_vec())),
+            ScalarValue::LargeBinary(Some(b"bar".to_vec())),
+            ScalarValue::FixedSizeBinary(3, Some(b"baz".to_vec())),
+            list(
+                vec![ScalarValue::Int32(Some(1)), ScalarValue::Int32(None)],
+                DataType::Int32,
+            ),
+            list(vec![ScalarValue::from("foo")], DataType::Utf8),
+            list(
+                vec![ScalarValue::TimestampMillisecond(Some(1), None)],
+                DataType::Timestamp(TimeUnit::Millisecond, None),
+            ),
+            list(
+                vec![ScalarValue::TimestampMicrosecond(Some(1), None)],
+                DataType::Timestamp(TimeUnit::Microsecond, None),
+            ),
+            list(
+                vec![ScalarValue::Date32(Some(1)), ScalarValue::Date32(None)],
+                DataType::Date32,
+            ),
+            ScalarValue::Date32(Some(1)),
+            ScalarValue::Date64(Some(86_400_000)),
+            ScalarValue::TimestampSecond(Some(1), Some("UTC".to_owned())),
+            ScalarValue::TimestampMillisecond(Some(2), None),
+            ScalarValue::TimestampMicrosecond(Some(3), None),
+            ScalarValue::TimestampNanosecond(Some(4), Some("UTC".to_owned())),
+            ScalarValue::IntervalYearMonth(Some(13)),
+            ScalarValue::IntervalDayTime(Some(1 << 32)),
+            ScalarValue::from(vec![
+                ("a", ScalarValue::Int32(Some(1))),
+                ("b", ScalarValue::Utf8(None)),
+            ]),
+            ScalarValue::Dictionary(
+                Box::new(DataType::Int8),
+                Box::new(ScalarValue::from("foo")),
+            ),
+            ScalarValue::Dictionary(
+                Box::new(DataType::UInt32),
+                Box::new(ScalarValue::Int64(Some(42))),
+            ),
+        ];
+        let nulls = values
+            .iter()
+            .map(|value| match value {
+                ScalarValue::Dictionary(key_type, value) => ScalarValue::Dictionary(
+                    key_type.clone(),
+                    Box::new(ScalarValue::try_from(&value.get_datatype()).unwrap()),
+                ),
+                value => ScalarValue::try_from(&value.get_datatype()).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        values.into_iter().chain(nulls).collect()
+    }
+
+    fn hash_of(scalar: &ScalarValue) -> u64 {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        scalar.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn scalar_round_trips() {
+        for scalar in scalars_of_every_variant() {
+            // arrays are decoded to the values of their dictionaries
+            let expected = match &scalar {
+                ScalarValue::Dictionary(_, value) => value.as_ref().clone(),
+                scalar => scalar.clone(),
+            };
+
+            let array = scalar.to_array_of_size(3);
+            assert_eq!(array.data_type(), &scalar.get_datatype(), "{:?}", scalar);
+            assert_eq!(array.null_count(), if scalar.is_null() { 3 } else { 0 });
+            for index in 0..3 {
+                let value = ScalarValue::try_from_array(&array, index).unwrap();
+                assert_eq!(value, expected);
+                assert_eq!(hash_of(&value), hash_of(&expected));
+                assert!(scalar.eq_array(&array, index), "{:?}", scalar);
+            }
+
+            let array = ScalarValue::iter_to_array(vec![scalar.clone(); 2]).unwrap();
+            assert_eq!(array.data_type(), &scalar.get_datatype(), "{:?}", scalar);
+            assert_eq!(ScalarValue::try_from_array(&array, 1).unwrap(), expected);
+
+            let json = serde_json::to_string(&scalar).unwrap();
+            let value: ScalarValue = serde_json::from_str(&json).unwrap();
+            assert_eq!(value, scalar);
+            assert_eq!(hash_of(&value), hash_of(&scalar));
+        }
+    }
 }
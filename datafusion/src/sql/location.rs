@@ -0,0 +1,264 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Locate the identifiers named by the errors of the SQL planner in the SQL
+//! text, see [`with_location`]
+
+use crate::error::DataFusionError;
+
+/// Annotate `e`, an error raised while planning `sql`, with the line and
+/// column of the identifier it is about, and the line of `sql` with that
+/// identifier underlined:
+///
+/// ```text
+/// line 3, column 7: Error during planning: No field named 'l_quantiy'. ...
+///  3 | WHERE l_quantiy > 5
+///    |       ^^^^^^^^^
+/// ```
+///
+/// The AST of the parser has no positions, so the identifier is searched in
+/// the text, outside of its string literals and comments. Only the errors
+/// about unknown columns are annotated, and returned unchanged if the column
+/// is not found.
+pub(crate) fn with_location(sql: &str, e: DataFusionError) -> DataFusionError {
+    let name = match e.find_root() {
+        DataFusionError::Plan(message)
+            if message.starts_with("No field named")
+                || message.starts_with("Invalid identifier") =>
+        {
+            message
+                .split('\'')
+                .nth(1)
+                .map(|name| name.trim_start_matches('#'))
+        }
+        _ => None,
+    };
+    match name.and_then(|name| find_identifier(sql, name)) {
+        Some(span) => {
+            let context = format_span(sql, span);
+            e.context(context)
+        }
+        None => e,
+    }
+}
+
+/// A span of the SQL text: its byte offset and length
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Span {
+    offset: usize,
+    len: usize,
+}
+
+/// An identifier of the SQL text
+struct Word<'a> {
+    span: Span,
+    value: &'a str,
+    quoted: bool,
+}
+
+impl<'a> Word<'a> {
+    /// Whether this identifier is `name`, ignoring the case of unquoted
+    /// identifiers
+    fn is(&self, name: &str) -> bool {
+        if self.quoted {
+            self.value == name
+        } else {
+            self.value.eq_ignore_ascii_case(name)
+        }
+    }
+}
+
+/// Find the first occurrence of the possibly qualified identifier `name` in
+/// `sql`, or of its last part if it does not occur qualified
+fn find_identifier(sql: &str, name: &str) -> Option<Span> {
+    let parts = name.split('.').collect::<Vec<_>>();
+    let words = words(sql);
+    let qualified = words.windows(parts.len()).find(|window| {
+        window.iter().zip(&parts).all(|(word, part)| word.is(part))
+            && window.windows(2).all(|pair| {
+                let (first, second) = (pair[0].span, pair[1].span);
+                sql[first.offset + first.len..second.offset].trim() == "."
+            })
+    });
+    if let Some(window) = qualified {
+        let (first, last) = (window[0].span, window[window.len() - 1].span);
+        return Some(Span {
+            offset: first.offset,
+            len: last.offset + last.len - first.offset,
+        });
+    }
+    let last = parts[parts.len() - 1];
+    words
+        .iter()
+        .find(|word| word.is(last))
+        .map(|word| word.span)
+}
+
+/// The identifiers of `sql`, skipping its string literals and comments
+fn words(sql: &str) -> Vec<Word<'_>> {
+    let mut words = vec![];
+    let mut chars = sql.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            '\'' | '"' | '`' => {
+                // the escaped quotes of quoted identifiers are not unescaped,
+                // they are not found
+                let mut end = sql.len();
+                while let Some((i, next)) = chars.next() {
+                    if next != c {
+                        continue;
+                    } else if chars.peek().map(|(_, c)| *c) == Some(c) {
+                        chars.next();
+                    } else {
+                        end = i + c.len_utf8();
+                        break;
+                    }
+                }
+                if c != '\'' && end > offset + 1 {
+                    words.push(Word {
+                        span: Span {
+                            offset,
+                            len: end - offset,
+                        },
+                        value: &sql[offset + 1..end - 1],
+                        quoted: true,
+                    });
+                }
+            }
+            '-' if sql[offset..].starts_with("--") => {
+                chars.find(|(_, c)| *c == '\n');
+            }
+            '/' if sql[offset..].starts_with("/*") => {
+                chars.next();
+                while let Some((i, _)) = chars.next() {
+                    if sql[i..].starts_with("*/") {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut end = offset + c.len_utf8();
+                while let Some((i, c)) = chars.peek().copied() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '$') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                words.push(Word {
+                    span: Span {
+                        offset,
+                        len: end - offset,
+                    },
+                    value: &sql[offset..end],
+                    quoted: false,
+                });
+            }
+            c if c.is_ascii_digit() => {
+                // skip the rest of the number, e.g. the exponent of `1e5`
+                while let Some((_, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || *c == '.') {
+                        break;
+                    }
+                    chars.next();
+                }
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+/// Format the line and column of `span` in `sql`, followed by the line of
+/// `sql` with `span` underlined
+fn format_span(sql: &str, span: Span) -> String {
+    let line_start = sql[..span.offset].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = sql[span.offset..]
+        .find('\n')
+        .map_or(sql.len(), |i| span.offset + i);
+    let line = sql[..span.offset].matches('\n').count() + 1;
+    let column = sql[line_start..span.offset].chars().count() + 1;
+
+    let text = sql[line_start..line_end].trim_end();
+    let underlined = sql[span.offset..(span.offset + span.len).min(line_end)]
+        .chars()
+        .count();
+    let gutter = line.to_string().len();
+    format!(
+        "line {}, column {}\n{:>w$} | {}\n{:>w$} | {}{}",
+        line,
+        column,
+        line,
+        text,
+        "",
+        " ".repeat(column - 1),
+        "^".repeat(underlined.max(1)),
+        w = gutter + 1,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn located(sql: &str, message: &str) -> String {
+        let e = DataFusionError::Plan(message.to_owned());
+        with_location(sql, e).to_string()
+    }
+
+    #[test]
+    fn locate_unknown_column() {
+        let sql = "SELECT a,\n  'b' AS c -- b\nFROM t\nWHERE B > 1";
+        assert_eq!(
+            located(sql, "No field named 'b'. Valid fields are 't.a'."),
+            "line 4, column 7: Error during planning: No field named 'b'. \
+             Valid fields are 't.a'.\n 4 | WHERE B > 1\n   |       ^"
+        );
+    }
+
+    #[test]
+    fn locate_qualified_column() {
+        let sql = "SELECT \"T\".a FROM \"T\" JOIN u ON \"T\" . a = u.a";
+        assert_eq!(
+            find_identifier(sql, "u.a"),
+            Some(Span { offset: 42, len: 3 })
+        );
+        assert_eq!(
+            find_identifier(sql, "T.a"),
+            Some(Span { offset: 7, len: 5 })
+        );
+        // unqualified in the query
+        assert_eq!(
+            find_identifier("SELECT a FROM t", "t.a"),
+            Some(Span { offset: 7, len: 1 })
+        );
+    }
+
+    #[test]
+    fn ignore_other_errors() {
+        let sql = "SELECT a FROM t";
+        assert_eq!(
+            located(sql, "Table or CTE with name 't' not found"),
+            "Error during planning: Table or CTE with name 't' not found"
+        );
+        assert_eq!(
+            located(sql, "No field named 'b'. Valid fields are 't.a'."),
+            "Error during planning: No field named 'b'. Valid fields are 't.a'."
+        );
+    }
+}
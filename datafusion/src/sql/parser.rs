@@ -20,13 +20,21 @@
 //! Declares a SQL parser based on sqlparser that handles custom formats that we need.
 
 use sqlparser::{
-    ast::{ColumnDef, ColumnOptionDef, Statement as SQLStatement, TableConstraint},
+    ast::{
+        ColumnDef, ColumnOptionDef, DataType, Ident, Statement as SQLStatement,
+        TableConstraint,
+    },
     dialect::{keywords::Keyword, Dialect, GenericDialect},
     parser::{Parser, ParserError},
     tokenizer::{Token, Tokenizer},
 };
+use std::collections::HashMap;
+use std::iter;
+use std::ops::Range;
 use std::str::FromStr;
 
+use crate::logical_plan::ExplainFormat;
+
 // Use `Parser::expected` instead, if possible
 macro_rules! parser_err {
     ($MSG:expr) => {
@@ -45,6 +53,8 @@ pub enum FileType {
     CSV,
     /// Avro binary records
     Avro,
+    /// Arrow IPC file format (Feather v2)
+    Arrow,
 }
 
 impl FromStr for FileType {
@@ -56,8 +66,9 @@ impl FromStr for FileType {
             "NDJSON" => Ok(Self::NdJson),
             "CSV" => Ok(Self::CSV),
             "AVRO" => Ok(Self::Avro),
+            "ARROW" => Ok(Self::Arrow),
             other => Err(ParserError::ParserError(format!(
-                "expect one of PARQUET, AVRO, NDJSON, or CSV, found: {}",
+                "expect one of PARQUET, AVRO, ARROW, NDJSON, or CSV, found: {}",
                 other
             ))),
         }
@@ -75,8 +86,34 @@ pub struct CreateExternalTable {
     pub file_type: FileType,
     /// CSV Header row?
     pub has_header: bool,
-    /// Path to file
+    /// Path to file, which may contain glob patterns
     pub location: String,
+    /// Partition columns of the `PARTITIONED BY` clause. Columns declared
+    /// without a type are `STRING`s.
+    pub table_partition_cols: Vec<ColumnDef>,
+    /// Format specific options of the `OPTIONS` clause, with lowercase keys
+    pub options: HashMap<String, String>,
+}
+
+/// DataFusion extension for `SET name = value`, whose name may be made of
+/// several identifiers separated by dots
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetVariable {
+    /// Name of the setting
+    pub variable: String,
+    /// Value of the setting, unquoted
+    pub value: String,
+}
+
+/// DataFusion extension for `EXPLAIN (FORMAT format) [VERBOSE] statement`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainWithFormat {
+    /// How the plans are formatted
+    pub format: ExplainFormat,
+    /// Whether the intermediate plans are shown
+    pub verbose: bool,
+    /// The statement whose plans are shown
+    pub statement: Box<SQLStatement>,
 }
 
 /// DataFusion Statement representations.
@@ -88,6 +125,10 @@ pub enum Statement {
     Statement(Box<SQLStatement>),
     /// Extension: `CREATE EXTERNAL TABLE`
     CreateExternalTable(CreateExternalTable),
+    /// Extension: `SET name = value`
+    SetVariable(SetVariable),
+    /// Extension: `EXPLAIN (FORMAT format) statement`
+    Explain(ExplainWithFormat),
 }
 
 /// SQL Parser
@@ -108,7 +149,9 @@ impl<'a> DFParser<'a> {
         dialect: &'a dyn Dialect,
     ) -> Result<Self, ParserError> {
         let mut tokenizer = Tokenizer::new(dialect, sql);
-        let tokens = tokenizer.tokenize()?;
+        let tokens = rewrite_numbers(tokenizer.tokenize()?);
+        let tokens = rewrite_at_time_zone(tokens)?;
+        let tokens = rewrite_comparisons(tokens, dialect)?;
 
         Ok(DFParser {
             parser: Parser::new(tokens, dialect),
@@ -165,6 +208,14 @@ impl<'a> DFParser<'a> {
                         // use custom parsing
                         self.parse_create()
                     }
+                    Keyword::SET => {
+                        self.parser.next_token();
+                        self.parse_set()
+                    }
+                    Keyword::EXPLAIN => {
+                        self.parser.next_token();
+                        self.parse_explain()
+                    }
                     _ => {
                         // use the native parser
                         Ok(Statement::Statement(Box::from(
@@ -191,6 +242,57 @@ impl<'a> DFParser<'a> {
         }
     }
 
+    /// Parse a SQL SET statement, `SET name [= | TO] value`
+    pub fn parse_set(&mut self) -> Result<Statement, ParserError> {
+        let variable = self.parser.parse_object_name()?.to_string();
+        if !self.parser.consume_token(&Token::Eq)
+            && !self.parser.parse_keyword(Keyword::TO)
+        {
+            return self.expected("'=' or TO", self.parser.peek_token());
+        }
+        let value = match self.parser.next_token() {
+            Token::Word(w) => w.value,
+            Token::Number(n, _) => n,
+            Token::SingleQuotedString(s) => s,
+            unexpected => return self.expected("variable value", unexpected),
+        };
+        Ok(Statement::SetVariable(SetVariable { variable, value }))
+    }
+
+    /// Parse a SQL EXPLAIN statement, with the native parser unless it is
+    /// followed by `(FORMAT format)`
+    pub fn parse_explain(&mut self) -> Result<Statement, ParserError> {
+        if !self.parser.consume_token(&Token::LParen) {
+            // move back to EXPLAIN for the native parser
+            self.parser.prev_token();
+            return Ok(Statement::Statement(Box::from(
+                self.parser.parse_statement()?,
+            )));
+        }
+        if !self.consume_token(&Token::make_keyword("FORMAT")) {
+            return self.expected("FORMAT", self.parser.peek_token());
+        }
+        let format = match self.parser.next_token() {
+            Token::Word(w) => match w.value.to_uppercase().as_str() {
+                "INDENT" => ExplainFormat::Indent,
+                "GRAPHVIZ" => ExplainFormat::Graphviz,
+                _ => return self.expected("INDENT or GRAPHVIZ", Token::Word(w)),
+            },
+            unexpected => return self.expected("INDENT or GRAPHVIZ", unexpected),
+        };
+        self.parser.expect_token(&Token::RParen)?;
+        if self.parser.parse_keyword(Keyword::ANALYZE) {
+            return parser_err!("EXPLAIN ANALYZE does not support FORMAT");
+        }
+        let verbose = self.parser.parse_keyword(Keyword::VERBOSE);
+        let statement = Box::new(self.parser.parse_statement()?);
+        Ok(Statement::Explain(ExplainWithFormat {
+            format,
+            verbose,
+            statement,
+        }))
+    }
+
     // This is a copy of the equivalent implementation in sqlparser.
     fn parse_columns(
         &mut self,
@@ -276,6 +378,20 @@ impl<'a> DFParser<'a> {
 
         let has_header = self.parse_csv_has_header();
 
+        let table_partition_cols =
+            if self.consume_token(&Token::make_keyword("PARTITIONED")) {
+                self.parser.expect_keyword(Keyword::BY)?;
+                self.parse_partition_columns()?
+            } else {
+                vec![]
+            };
+
+        let options = if self.consume_token(&Token::make_keyword("OPTIONS")) {
+            self.parse_options()?
+        } else {
+            HashMap::new()
+        };
+
         self.parser.expect_keyword(Keyword::LOCATION)?;
         let location = self.parser.parse_literal_string()?;
 
@@ -285,6 +401,8 @@ impl<'a> DFParser<'a> {
             file_type,
             has_header,
             location,
+            table_partition_cols,
+            options,
         };
         Ok(Statement::CreateExternalTable(create))
     }
@@ -293,8 +411,55 @@ impl<'a> DFParser<'a> {
     fn parse_file_format(&mut self) -> Result<FileType, ParserError> {
         match self.parser.next_token() {
             Token::Word(w) => w.value.parse(),
-            unexpected => self.expected("one of PARQUET, NDJSON, or CSV", unexpected),
+            unexpected => {
+                self.expected("one of PARQUET, AVRO, ARROW, NDJSON, or CSV", unexpected)
+            }
+        }
+    }
+
+    /// Parses `(name [type], ...)` of a `PARTITIONED BY` clause
+    fn parse_partition_columns(&mut self) -> Result<Vec<ColumnDef>, ParserError> {
+        self.parser.expect_token(&Token::LParen)?;
+        let mut columns = vec![];
+        loop {
+            let name = self.parser.parse_identifier()?;
+            let data_type = match self.parser.peek_token() {
+                Token::Comma | Token::RParen => DataType::String,
+                _ => self.parser.parse_data_type()?,
+            };
+            columns.push(make_partition_column(name, data_type));
+
+            if self.parser.consume_token(&Token::RParen) {
+                break;
+            } else if !self.parser.consume_token(&Token::Comma) {
+                return self.expected(
+                    "',' or ')' after partition column",
+                    self.parser.peek_token(),
+                );
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Parses `('key' 'value', ...)` of an `OPTIONS` clause
+    fn parse_options(&mut self) -> Result<HashMap<String, String>, ParserError> {
+        self.parser.expect_token(&Token::LParen)?;
+        let mut options = HashMap::new();
+        loop {
+            let key = self.parser.parse_literal_string()?.to_lowercase();
+            let value = self.parser.parse_literal_string()?;
+            if options.insert(key.clone(), value).is_some() {
+                return parser_err!(format!("Option '{}' is specified twice", key));
+            }
+
+            if self.parser.consume_token(&Token::RParen) {
+                break;
+            } else if !self.parser.consume_token(&Token::Comma) {
+                return self
+                    .expected("',' or ')' after option", self.parser.peek_token());
+            }
         }
+        Ok(options)
     }
 
     fn consume_token(&mut self, expected: &Token) -> bool {
@@ -315,10 +480,362 @@ impl<'a> DFParser<'a> {
     }
 }
 
+fn make_partition_column(name: Ident, data_type: DataType) -> ColumnDef {
+    ColumnDef {
+        name,
+        data_type,
+        collation: None,
+        options: vec![],
+    }
+}
+
+/// Merge the tokens of the numbers that the SQL tokenizer splits into a single
+/// number token: the numbers with underscores between their digits, such as
+/// `1_000_000`, and the numbers with an exponent, such as `1e-3` or `2.5E+10`.
+/// The merged tokens follow each other without whitespace, their text is
+/// validated when the number is planned.
+fn rewrite_numbers(mut tokens: Vec<Token>) -> Vec<Token> {
+    let mut index = 0;
+    while index < tokens.len() {
+        let mut number = match &tokens[index] {
+            Token::Number(number, _) => number.clone(),
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let mut end = index + 1;
+        while let Some(token) = tokens.get(end) {
+            let has_exponent = number.contains(|c: char| c == 'e' || c == 'E');
+            match token {
+                // the digits after an underscore or an exponent marker
+                Token::Word(w)
+                    if w.quote_style.is_none()
+                        && !has_exponent
+                        && w.value
+                            .starts_with(|c: char| matches!(c, '_' | 'e' | 'E'))
+                        && w.value.chars().all(|c| {
+                            c.is_ascii_digit() || matches!(c, '_' | 'e' | 'E')
+                        }) =>
+                {
+                    number.push_str(&w.value)
+                }
+                // the fraction after digits separated by underscores
+                Token::Number(fraction, _)
+                    if fraction.starts_with('.')
+                        && !number.contains('.')
+                        && !has_exponent =>
+                {
+                    number.push_str(fraction)
+                }
+                // the sign of an exponent
+                Token::Plus | Token::Minus
+                    if number.ends_with(|c: char| c == 'e' || c == 'E')
+                        && matches!(
+                            tokens.get(end + 1),
+                            Some(Token::Number(digits, _))
+                                if digits.bytes().all(|b| b.is_ascii_digit())
+                        ) =>
+                {
+                    number.push_str(&token.to_string())
+                }
+                Token::Number(digits, _)
+                    if matches!(number.chars().last(), Some('+' | '-')) =>
+                {
+                    number.push_str(digits)
+                }
+                _ => break,
+            }
+            end += 1;
+        }
+        if end > index + 1 {
+            tokens.splice(index..end, iter::once(Token::Number(number, false)));
+        }
+        index += 1;
+    }
+    tokens
+}
+
+/// Rewrite the expressions `operand AT TIME ZONE 'time_zone'`, which the SQL
+/// parser doesn't support, into calls of `at_time_zone(operand, 'time_zone')`.
+/// As the operator binds tighter than all the others, the operand is the
+/// expression just before `AT`: a column, a literal, a typed string such as
+/// `TIMESTAMP '2021-11-07 01:30:00'`, a function call or an expression in
+/// parentheses.
+fn rewrite_at_time_zone(mut tokens: Vec<Token>) -> Result<Vec<Token>, ParserError> {
+    let mut index = 0;
+    while index < tokens.len() {
+        let time = next_token(&tokens, index);
+        let zone = time.and_then(|time| next_token(&tokens, time));
+        let zone = match (time, zone) {
+            (Some(time), Some(zone))
+                if is_word(&tokens[index], "AT")
+                    && is_word(&tokens[time], "TIME")
+                    && is_word(&tokens[zone], "ZONE") =>
+            {
+                zone
+            }
+            _ => {
+                index += 1;
+                continue;
+            }
+        };
+        let time_zone = match next_token(&tokens, zone) {
+            Some(time_zone)
+                if matches!(tokens[time_zone], Token::SingleQuotedString(_)) =>
+            {
+                time_zone
+            }
+            _ => return parser_err!("Expected a time zone string after AT TIME ZONE"),
+        };
+        let (start, end) = match previous_token(&tokens, index) {
+            Some(end) => (operand_start(&tokens, end), end),
+            None => (None, index),
+        };
+        let start = match start {
+            Some(start) => start,
+            None => return parser_err!("Expected an expression before AT TIME ZONE"),
+        };
+
+        let mut call = vec![Token::make_word("at_time_zone", None), Token::LParen];
+        call.extend(tokens[start..=end].iter().cloned());
+        call.extend(vec![Token::Comma, tokens[time_zone].clone(), Token::RParen]);
+        index = start + call.len();
+        tokens.splice(start..=time_zone, call);
+    }
+    Ok(tokens)
+}
+
+/// The precedence of the comparisons, `BETWEEN` and `LIKE` in the SQL parser
+const COMPARISON_PRECEDENCE: u8 = 20;
+
+/// Rewrite the comparisons that the SQL parser doesn't support:
+///
+/// * `expr [NOT] SIMILAR TO pattern [ESCAPE escape]` into the match of `expr`
+///   with a regular expression, `expr [!]~ similar_to_escape(pattern[, escape])`
+/// * `expr [NOT] BETWEEN SYMMETRIC low AND high` into
+///   `expr [NOT] BETWEEN CASE WHEN low <= high THEN low ELSE high END
+///   AND CASE WHEN low <= high THEN high ELSE low END`
+/// * `expr [NOT] BETWEEN ASYMMETRIC low AND high` into
+///   `expr [NOT] BETWEEN low AND high`
+///
+/// The comparisons are rewritten from the last one, so that the operands
+/// have no comparison left to rewrite when they are parsed to find where
+/// they end.
+fn rewrite_comparisons(
+    mut tokens: Vec<Token>,
+    dialect: &dyn Dialect,
+) -> Result<Vec<Token>, ParserError> {
+    let mut index = tokens.len();
+    while index > 0 {
+        index -= 1;
+        let next = match next_token(&tokens, index) {
+            Some(next) => next,
+            None => continue,
+        };
+        if is_word(&tokens[index], "SIMILAR") && is_word(&tokens[next], "TO") {
+            let pattern = operand_after(&tokens, next, dialect)?;
+            let mut args = tokens[pattern.clone()].to_vec();
+            let mut end = pattern.end;
+            match next_token(&tokens, end - 1) {
+                Some(escape) if is_word(&tokens[escape], "ESCAPE") => {
+                    let escape = operand_after(&tokens, escape, dialect)?;
+                    args.push(Token::Comma);
+                    args.extend(tokens[escape.clone()].iter().cloned());
+                    end = escape.end;
+                }
+                _ => {}
+            }
+            let (start, operator) = match previous_token(&tokens, index) {
+                Some(not) if is_word(&tokens[not], "NOT") => {
+                    (not, Token::ExclamationMarkTilde)
+                }
+                _ => (index, Token::Tilde),
+            };
+
+            let mut call = vec![
+                operator,
+                Token::make_word("similar_to_escape", None),
+                Token::LParen,
+            ];
+            call.extend(args);
+            call.push(Token::RParen);
+            tokens.splice(start..end, call);
+            index = start;
+        } else if is_word(&tokens[index], "BETWEEN")
+            && (is_word(&tokens[next], "SYMMETRIC")
+                || is_word(&tokens[next], "ASYMMETRIC"))
+        {
+            let low = operand_after(&tokens, next, dialect)?;
+            let and = match next_token(&tokens, low.end - 1) {
+                Some(and) if is_word(&tokens[and], "AND") => and,
+                _ => return parser_err!("Expected AND after the lower bound of BETWEEN"),
+            };
+            let high = operand_after(&tokens, and, dialect)?;
+            let (low_bound, high_bound) = if is_word(&tokens[next], "SYMMETRIC") {
+                let low = parenthesized(&tokens[low.clone()]);
+                let high = parenthesized(&tokens[high.clone()]);
+                // the smallest and the largest of the bounds
+                let ordered = |first: &[Token], second: &[Token]| {
+                    let mut case =
+                        vec![Token::make_keyword("CASE"), Token::make_keyword("WHEN")];
+                    case.extend(low.iter().cloned());
+                    case.push(Token::LtEq);
+                    case.extend(high.iter().cloned());
+                    case.push(Token::make_keyword("THEN"));
+                    case.extend(first.iter().cloned());
+                    case.push(Token::make_keyword("ELSE"));
+                    case.extend(second.iter().cloned());
+                    case.push(Token::make_keyword("END"));
+                    case
+                };
+                (ordered(&low, &high), ordered(&high, &low))
+            } else {
+                (tokens[low.clone()].to_vec(), tokens[high.clone()].to_vec())
+            };
+
+            let mut between = vec![tokens[index].clone()];
+            between.extend(low_bound);
+            between.push(tokens[and].clone());
+            between.extend(high_bound);
+            tokens.splice(index..high.end, between);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Range of the tokens of the operand after the token at `index`, the
+/// expression ending before the first operator binding as loosely as the
+/// comparisons, like the bounds of `BETWEEN` and the pattern of `LIKE`
+fn operand_after(
+    tokens: &[Token],
+    index: usize,
+    dialect: &dyn Dialect,
+) -> Result<Range<usize>, ParserError> {
+    let start = match next_token(tokens, index) {
+        Some(start) => start,
+        None => {
+            return parser_err!(format!("Expected an expression after {}", tokens[index]))
+        }
+    };
+    let mut parser = Parser::new(tokens[start..].to_vec(), dialect);
+    parser.parse_subexpr(COMPARISON_PRECEDENCE)?;
+    let mut remaining = 0;
+    while parser.next_token() != Token::EOF {
+        remaining += 1;
+    }
+    let operand = (start..tokens.len())
+        .filter(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+        .collect::<Vec<_>>();
+    Ok(start..operand[operand.len() - remaining - 1] + 1)
+}
+
+/// `tokens` in parentheses
+fn parenthesized(tokens: &[Token]) -> Vec<Token> {
+    let mut parenthesized = vec![Token::LParen];
+    parenthesized.extend(tokens.iter().cloned());
+    parenthesized.push(Token::RParen);
+    parenthesized
+}
+
+/// Index of the first token of the operand of `AT TIME ZONE` ending with the
+/// token at `end`
+fn operand_start(tokens: &[Token], end: usize) -> Option<usize> {
+    let start = match &tokens[end] {
+        Token::RParen => {
+            let mut depth = 0;
+            let open = (0..=end).rev().find(|i| {
+                match tokens[*i] {
+                    Token::RParen => depth += 1,
+                    Token::LParen => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            })?;
+            match previous_token(tokens, open) {
+                Some(name) if is_function_name(&tokens[name]) => name,
+                _ => open,
+            }
+        }
+        Token::SingleQuotedString(_) => match previous_token(tokens, end) {
+            Some(data_type)
+                if ["DATE", "TIME", "TIMESTAMP"]
+                    .iter()
+                    .any(|name| is_word(&tokens[data_type], name)) =>
+            {
+                data_type
+            }
+            _ => end,
+        },
+        Token::Word(_) | Token::Number(_, _) => end,
+        _ => return None,
+    };
+    // the qualifier of a column or of a function
+    match previous_token(tokens, start) {
+        Some(period) if tokens[period] == Token::Period => {
+            match previous_token(tokens, period) {
+                Some(qualifier) if matches!(tokens[qualifier], Token::Word(_)) => {
+                    operand_start(tokens, qualifier)
+                }
+                _ => None,
+            }
+        }
+        _ => Some(start),
+    }
+}
+
+/// Whether the word before an opening parenthesis is the name of a function
+/// rather than a keyword followed by an expression in parentheses
+fn is_function_name(token: &Token) -> bool {
+    match token {
+        Token::Word(w) => !matches!(
+            w.keyword,
+            Keyword::AND
+                | Keyword::BETWEEN
+                | Keyword::BY
+                | Keyword::DISTINCT
+                | Keyword::ELSE
+                | Keyword::HAVING
+                | Keyword::ILIKE
+                | Keyword::IN
+                | Keyword::IS
+                | Keyword::LIKE
+                | Keyword::NOT
+                | Keyword::ON
+                | Keyword::OR
+                | Keyword::SELECT
+                | Keyword::THEN
+                | Keyword::VALUES
+                | Keyword::WHEN
+                | Keyword::WHERE
+        ),
+        _ => false,
+    }
+}
+
+/// Whether `token` is the unquoted word `word`, whatever its case
+fn is_word(token: &Token, word: &str) -> bool {
+    match token {
+        Token::Word(w) => w.quote_style.is_none() && w.value.eq_ignore_ascii_case(word),
+        _ => false,
+    }
+}
+
+/// Index of the last token before `index` that is not a whitespace
+fn previous_token(tokens: &[Token], index: usize) -> Option<usize> {
+    (0..index)
+        .rev()
+        .find(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+}
+
+/// Index of the first token after `index` that is not a whitespace
+fn next_token(tokens: &[Token], index: usize) -> Option<usize> {
+    (index + 1..tokens.len()).find(|i| !matches!(tokens[*i], Token::Whitespace(_)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sqlparser::ast::{DataType, Ident};
 
     fn expect_parse_ok(sql: &str, expected: Statement) -> Result<(), ParserError> {
         let statements = DFParser::parse_sql(sql)?;
@@ -375,6 +892,8 @@ mod tests {
             file_type: FileType::CSV,
             has_header: false,
             location: "foo.csv".into(),
+            table_partition_cols: vec![],
+            options: HashMap::new(),
         });
         expect_parse_ok(sql, expected)?;
 
@@ -390,6 +909,8 @@ mod tests {
                 file_type: FileType::CSV,
                 has_header: true,
                 location: "foo.csv".into(),
+                table_partition_cols: vec![],
+                options: HashMap::new(),
             });
             expect_parse_ok(sql, expected)?;
         }
@@ -402,6 +923,8 @@ mod tests {
             file_type: FileType::Parquet,
             has_header: false,
             location: "foo.parquet".into(),
+            table_partition_cols: vec![],
+            options: HashMap::new(),
         });
         expect_parse_ok(sql, expected)?;
 
@@ -413,6 +936,8 @@ mod tests {
             file_type: FileType::Parquet,
             has_header: false,
             location: "foo.parquet".into(),
+            table_partition_cols: vec![],
+            options: HashMap::new(),
         });
         expect_parse_ok(sql, expected)?;
 
@@ -424,14 +949,230 @@ mod tests {
             file_type: FileType::Avro,
             has_header: false,
             location: "foo.avro".into(),
+            table_partition_cols: vec![],
+            options: HashMap::new(),
+        });
+        expect_parse_ok(sql, expected)?;
+
+        // positive case: the schema of arrow files is read from their footer
+        let sql = "CREATE EXTERNAL TABLE t STORED AS ARROW LOCATION 'foo.arrow'";
+        let expected = Statement::CreateExternalTable(CreateExternalTable {
+            name: "t".into(),
+            columns: vec![],
+            file_type: FileType::Arrow,
+            has_header: false,
+            location: "foo.arrow".into(),
+            table_partition_cols: vec![],
+            options: HashMap::new(),
+        });
+        expect_parse_ok(sql, expected)?;
+
+        // positive case: partition columns, options and a glob location
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV \
+            PARTITIONED BY (dt, region STRING) \
+            OPTIONS ('HAS_HEADER' 'true', 'delimiter' '|') \
+            LOCATION 's3://bucket/path/dt=*/'";
+        let expected = Statement::CreateExternalTable(CreateExternalTable {
+            name: "t".into(),
+            columns: vec![make_column_def("c1", DataType::Int(display))],
+            file_type: FileType::CSV,
+            has_header: false,
+            location: "s3://bucket/path/dt=*/".into(),
+            table_partition_cols: vec![
+                make_column_def("dt", DataType::String),
+                make_column_def("region", DataType::String),
+            ],
+            options: vec![
+                ("has_header".to_owned(), "true".to_owned()),
+                ("delimiter".to_owned(), "|".to_owned()),
+            ]
+            .into_iter()
+            .collect(),
         });
         expect_parse_ok(sql, expected)?;
 
         // Error cases: Invalid type
         let sql =
             "CREATE EXTERNAL TABLE t(c1 int) STORED AS UNKNOWN_TYPE LOCATION 'foo.csv'";
-        expect_parse_error(sql, "expect one of PARQUET, AVRO, NDJSON, or CSV");
+        expect_parse_error(sql, "expect one of PARQUET, AVRO, ARROW, NDJSON, or CSV");
+
+        // Error cases: malformed partition columns and options
+        let sql =
+            "CREATE EXTERNAL TABLE t STORED AS CSV PARTITIONED BY dt LOCATION 'foo'";
+        expect_parse_error(sql, "Expected (");
+        let sql =
+            "CREATE EXTERNAL TABLE t STORED AS CSV OPTIONS ('has_header') LOCATION 'foo'";
+        expect_parse_error(sql, "Expected literal string");
+        let sql = "CREATE EXTERNAL TABLE t STORED AS CSV \
+            OPTIONS ('delimiter' '|', 'DELIMITER' ';') LOCATION 'foo'";
+        expect_parse_error(sql, "Option 'delimiter' is specified twice");
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_variable() -> Result<(), ParserError> {
+        let expected = |value: &str| {
+            Statement::SetVariable(SetVariable {
+                variable: "datafusion.execution.batch_size".into(),
+                value: value.into(),
+            })
+        };
+        expect_parse_ok(
+            "SET datafusion.execution.batch_size = 1024",
+            expected("1024"),
+        )?;
+        expect_parse_ok("SET datafusion.execution.batch_size TO '8'", expected("8"))?;
+        expect_parse_ok(
+            "set datafusion.execution.batch_size = true",
+            expected("true"),
+        )?;
+
+        expect_parse_error(
+            "SET datafusion.execution.batch_size 8",
+            "Expected '=' or TO",
+        );
+        expect_parse_error("SET batch_size =", "Expected variable value");
+
+        Ok(())
+    }
+
+    #[test]
+    fn explain_with_format() -> Result<(), ParserError> {
+        let query = Parser::parse_sql(&GenericDialect {}, "SELECT 1")?.remove(0);
+        let expected = |format, verbose| {
+            Statement::Explain(ExplainWithFormat {
+                format,
+                verbose,
+                statement: Box::new(query.clone()),
+            })
+        };
+        expect_parse_ok(
+            "EXPLAIN (FORMAT GRAPHVIZ) SELECT 1",
+            expected(ExplainFormat::Graphviz, false),
+        )?;
+        expect_parse_ok(
+            "explain (format indent) verbose SELECT 1",
+            expected(ExplainFormat::Indent, true),
+        )?;
+
+        // without a format, EXPLAIN is parsed by the native parser
+        let sql = "EXPLAIN VERBOSE SELECT 1";
+        let native = Parser::parse_sql(&GenericDialect {}, sql)?.remove(0);
+        expect_parse_ok(sql, Statement::Statement(Box::new(native)))?;
+
+        expect_parse_error(
+            "EXPLAIN (FORMAT JSON) SELECT 1",
+            "Expected INDENT or GRAPHVIZ, found: JSON",
+        );
+        expect_parse_error("EXPLAIN (VERBOSE) SELECT 1", "Expected FORMAT");
+        expect_parse_error(
+            "EXPLAIN (FORMAT GRAPHVIZ) ANALYZE SELECT 1",
+            "EXPLAIN ANALYZE does not support FORMAT",
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn at_time_zone() -> Result<(), ParserError> {
+        let cases = [
+            (
+                "SELECT ts AT TIME ZONE 'UTC' FROM t",
+                "SELECT at_time_zone(ts, 'UTC') FROM t",
+            ),
+            (
+                "SELECT t.ts at time zone 'UTC' + 1",
+                "SELECT at_time_zone(t.ts, 'UTC') + 1",
+            ),
+            (
+                "SELECT TIMESTAMP '2021-11-07 01:30:00' AT TIME ZONE 'Asia/Tokyo'",
+                "SELECT at_time_zone(TIMESTAMP '2021-11-07 01:30:00', 'Asia/Tokyo')",
+            ),
+            (
+                "SELECT now() AT TIME ZONE 'UTC' AT TIME ZONE 'Asia/Tokyo'",
+                "SELECT at_time_zone(at_time_zone(now(), 'UTC'), 'Asia/Tokyo')",
+            ),
+            (
+                "SELECT (a + b) AT TIME ZONE 'UTC' FROM t \
+                 WHERE (ts) AT TIME ZONE 'UTC' > x",
+                "SELECT at_time_zone((a + b), 'UTC') FROM t \
+                 WHERE at_time_zone((ts), 'UTC') > x",
+            ),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(DFParser::parse_sql(sql)?, DFParser::parse_sql(expected)?);
+        }
+
+        expect_parse_error(
+            "SELECT ts AT TIME ZONE tz FROM t",
+            "Expected a time zone string after AT TIME ZONE",
+        );
+        expect_parse_error(
+            "SELECT , AT TIME ZONE 'UTC'",
+            "Expected an expression before AT TIME ZONE",
+        );
+        Ok(())
+    }
 
+    #[test]
+    fn number_literals() -> Result<(), ParserError> {
+        let cases = [
+            ("SELECT 1_000_000", "SELECT 1_000_000"),
+            ("SELECT 1e-3, 1.5E+10, 2e3", "SELECT 1e-3, 1.5E+10, 2e3"),
+            ("SELECT 1_000.000_5 FROM t", "SELECT 1_000.000_5 FROM t"),
+            ("SELECT x-1e-3 FROM t", "SELECT x - 1e-3 FROM t"),
+            // an alias after whitespace is not part of the number
+            ("SELECT 1 e FROM t", "SELECT 1 AS e FROM t"),
+        ];
+        for (sql, expected) in cases {
+            match &DFParser::parse_sql(sql)?[0] {
+                Statement::Statement(statement) => {
+                    assert_eq!(statement.to_string(), expected)
+                }
+                other => panic!("Expected a query, got {:?}", other),
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn similar_to_and_between_symmetric() -> Result<(), ParserError> {
+        let cases = [
+            (
+                "SELECT a FROM t WHERE a SIMILAR TO 'x%'",
+                "SELECT a FROM t WHERE a ~ similar_to_escape('x%')",
+            ),
+            (
+                "SELECT a NOT SIMILAR TO 'x' || b ESCAPE '#' AND c FROM t",
+                "SELECT a !~ similar_to_escape('x' || b, '#') AND c FROM t",
+            ),
+            (
+                "SELECT a BETWEEN SYMMETRIC b + 1 AND 10 FROM t",
+                "SELECT a BETWEEN CASE WHEN (b + 1) <= (10) THEN (b + 1) ELSE (10) END \
+                 AND CASE WHEN (b + 1) <= (10) THEN (10) ELSE (b + 1) END FROM t",
+            ),
+            (
+                "SELECT * FROM t WHERE a NOT BETWEEN ASYMMETRIC 1 AND 2 OR b",
+                "SELECT * FROM t WHERE a NOT BETWEEN 1 AND 2 OR b",
+            ),
+            (
+                "SELECT a BETWEEN SYMMETRIC b AND (c NOT SIMILAR TO 'y')",
+                "SELECT a BETWEEN \
+                 CASE WHEN (b) <= ((c !~ similar_to_escape('y'))) \
+                 THEN (b) ELSE ((c !~ similar_to_escape('y'))) END \
+                 AND CASE WHEN (b) <= ((c !~ similar_to_escape('y'))) \
+                 THEN ((c !~ similar_to_escape('y'))) ELSE (b) END",
+            ),
+        ];
+        for (sql, expected) in cases {
+            assert_eq!(DFParser::parse_sql(sql)?, DFParser::parse_sql(expected)?);
+        }
+
+        expect_parse_error(
+            "SELECT a BETWEEN SYMMETRIC 1 OR 2",
+            "Expected AND after the lower bound of BETWEEN",
+        );
+        expect_parse_error("SELECT a SIMILAR TO", "Expected an expression after TO");
         Ok(())
     }
 }
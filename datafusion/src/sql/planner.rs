@@ -17,6 +17,7 @@
 
 //! SQL Query Planner (produces logical plan from SQL AST)
 
+use std::borrow::Cow;
 use std::collections::HashSet;
 use std::iter;
 use std::str::FromStr;
@@ -24,18 +25,22 @@ use std::sync::Arc;
 use std::{convert::TryInto, vec};
 
 use crate::catalog::TableReference;
+use crate::datasource::cte_worktable::CteWorkTable;
+use crate::datasource::table_function::TableFunction;
 use crate::datasource::TableProvider;
+use crate::execution::context::ExecutionProps;
 use crate::logical_plan::window_frames::{WindowFrame, WindowFrameUnits};
 use crate::logical_plan::Expr::Alias;
 use crate::logical_plan::{
-    and, builder::expand_wildcard, col, lit, normalize_col, union_with_alias, Column,
-    CreateExternalTable as PlanCreateExternalTable, CreateMemoryTable, DFSchema,
-    DFSchemaRef, DropTable, Expr, LogicalPlan, LogicalPlanBuilder, Operator, PlanType,
-    ToDFSchema, ToStringifiedPlan,
+    and, builder::expand_wildcard, col, lit, normalize_col, struct_, union_with_alias,
+    Column, CreateExternalTable as PlanCreateExternalTable, CreateMemoryTable, DFField,
+    DFSchema, DFSchemaRef, DropTable, ExplainFormat, Expr, InsertInto, LogicalPlan,
+    LogicalPlanBuilder, Operator, PlanType, SetVariable as PlanSetVariable, ToDFSchema,
 };
+use crate::optimizer::simplify_expressions::ConstEvaluator;
 use crate::optimizer::utils::exprlist_to_columns;
 use crate::prelude::JoinType;
-use crate::scalar::ScalarValue;
+use crate::scalar::{ScalarValue, MAX_PRECISION_FOR_DECIMAL128};
 use crate::sql::utils::make_decimal_type;
 use crate::{
     error::{DataFusionError, Result},
@@ -43,16 +48,20 @@ use crate::{
 };
 use crate::{
     physical_plan::udf::ScalarUDF,
-    physical_plan::{aggregates, functions, window_functions},
-    sql::parser::{CreateExternalTable, FileType, Statement as DFStatement},
+    physical_plan::udwf::WindowUDF,
+    physical_plan::{aggregates, expressions::parse_hex, functions, window_functions},
+    sql::parser::{CreateExternalTable, FileType, SetVariable, Statement as DFStatement},
 };
+use arrow::compute::kernels::cast_utils::string_to_timestamp_nanos;
 use arrow::datatypes::*;
+use chrono::{DateTime, NaiveDate};
 use hashbrown::HashMap;
 use sqlparser::ast::{
-    BinaryOperator, DataType as SQLDataType, DateTimeField, Expr as SQLExpr, FunctionArg,
-    HiveDistributionStyle, Ident, Join, JoinConstraint, JoinOperator, ObjectName, Query,
-    Select, SelectItem, SetExpr, SetOperator, ShowStatementFilter, TableFactor,
-    TableWithJoins, TrimWhereField, UnaryOperator, Value, Values as SQLValues,
+    BinaryOperator, Cte, DataType as SQLDataType, DateTimeField, Expr as SQLExpr,
+    FunctionArg, HiveDistributionStyle, Ident, Join, JoinConstraint, JoinOperator,
+    ObjectName, Query, Select, SelectItem, SetExpr, SetOperator, ShowStatementFilter,
+    TableAlias, TableFactor, TableWithJoins, TrimWhereField, UnaryOperator, Value,
+    Values as SQLValues,
 };
 use sqlparser::ast::{ColumnDef as SQLColumnDef, ColumnOption};
 use sqlparser::ast::{ObjectType, OrderByExpr, Statement};
@@ -78,6 +87,19 @@ pub trait ContextProvider {
     fn get_function_meta(&self, name: &str) -> Option<Arc<ScalarUDF>>;
     /// Getter for a UDAF description
     fn get_aggregate_meta(&self, name: &str) -> Option<Arc<AggregateUDF>>;
+    /// Getter for a UDWF description
+    fn get_window_meta(&self, _name: &str) -> Option<Arc<WindowUDF>> {
+        None
+    }
+    /// Getter for a table function, called in the FROM clause
+    fn get_table_function(&self, _name: &str) -> Option<Arc<dyn TableFunction>> {
+        None
+    }
+    /// Name and value of the settings of the session listed by `SHOW ALL`,
+    /// sorted by name
+    fn get_config_options(&self) -> Vec<(String, String)> {
+        vec![]
+    }
 }
 
 /// SQL query planner
@@ -93,6 +115,72 @@ fn plan_key(key: Value) -> ScalarValue {
     }
 }
 
+/// Plans `named_struct('name1', value1, 'name2', value2, ...)`
+fn plan_named_struct(args: Vec<Expr>) -> Result<Expr> {
+    if args.is_empty() || args.len() % 2 != 0 {
+        return Err(DataFusionError::Plan(
+            "named_struct requires pairs of field names and values".to_owned(),
+        ));
+    }
+    let mut names = Vec::with_capacity(args.len() / 2);
+    let mut values = Vec::with_capacity(args.len() / 2);
+    let mut args = args.into_iter();
+    while let (Some(name), Some(value)) = (args.next(), args.next()) {
+        match name {
+            Expr::Literal(ScalarValue::Utf8(Some(name))) => names.push(name),
+            name => {
+                return Err(DataFusionError::Plan(format!(
+                    "named_struct field names must be string literals, found {:?}",
+                    name
+                )))
+            }
+        }
+        values.push(value);
+    }
+    Ok(Expr::Struct {
+        names,
+        args: values,
+    })
+}
+
+/// The name of the column of the elements of the lists of an unnest, unless
+/// given a column alias
+const UNNEST_COLUMN: &str = "unnest";
+
+/// The name of the column of the subquery of an `IN` subquery joined with the
+/// outer query
+const IN_SUBQUERY_COLUMN: &str = "__in_subquery";
+
+/// The prefix of the names of the columns of the subquery of an `EXISTS`
+/// subquery joined with the outer query
+const EXISTS_SUBQUERY_COLUMN: &str = "__exists_subquery";
+
+/// Whether `name` is the name of the `unnest` table function
+fn is_unnest(name: &ObjectName) -> bool {
+    matches!(name.0.as_slice(), [ident]
+        if ident.quote_style.is_none() && ident.value.eq_ignore_ascii_case("unnest"))
+}
+
+/// The arguments of `expr` if it is an `unnest(list)`
+fn unnest_args(expr: &SQLExpr) -> Option<&[FunctionArg]> {
+    match expr {
+        SQLExpr::Function(function) if is_unnest(&function.name) => Some(&function.args),
+        _ => None,
+    }
+}
+
+/// The arguments and the alias of `relation` if it is an `unnest(list)`
+fn unnest_relation(
+    relation: &TableFactor,
+) -> Option<(&[FunctionArg], &Option<TableAlias>)> {
+    match relation {
+        TableFactor::Table {
+            name, alias, args, ..
+        } if !args.is_empty() && is_unnest(name) => Some((args, alias)),
+        _ => None,
+    }
+}
+
 #[allow(clippy::branches_sharing_code)]
 fn plan_indexed(expr: Expr, mut keys: Vec<Value>) -> Expr {
     if keys.len() == 1 {
@@ -121,7 +209,11 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     pub fn statement_to_plan(&self, statement: &DFStatement) -> Result<LogicalPlan> {
         match statement {
             DFStatement::CreateExternalTable(s) => self.external_table_to_plan(s),
+            DFStatement::SetVariable(s) => self.set_variable_to_plan(s),
             DFStatement::Statement(s) => self.sql_statement_to_plan(s),
+            DFStatement::Explain(s) => {
+                self.explain_statement_to_plan(s.verbose, false, &s.statement, s.format)
+            }
         }
     }
 
@@ -133,7 +225,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 statement,
                 analyze,
                 describe_alias: _,
-            } => self.explain_statement_to_plan(*verbose, *analyze, statement),
+            } => self.explain_statement_to_plan(
+                *verbose,
+                *analyze,
+                statement,
+                ExplainFormat::Indent,
+            ),
             Statement::Query(query) => self.query_to_plan(query),
             Statement::ShowVariable { variable } => self.show_variable_to_plan(variable),
             Statement::CreateTable {
@@ -169,6 +266,18 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 "Only `CREATE TABLE table_name AS SELECT ...` statement is supported"
                     .to_string(),
             )),
+            Statement::Insert {
+                table_name,
+                columns,
+                overwrite: false,
+                source,
+                partitioned: None,
+                ..
+            } => self.insert_to_plan(table_name, columns, source),
+            Statement::Insert { .. } => Err(DataFusionError::NotImplemented(
+                "Only `INSERT INTO table_name [(column, ...)] query` statement is supported"
+                    .to_string(),
+            )),
 
             Statement::Drop {
                 object_type: ObjectType::Table,
@@ -213,16 +322,30 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<LogicalPlan> {
         let set_expr = &query.body;
         if let Some(with) = &query.with {
+            if with.recursive {
+                check_no_mutual_recursion(&with.cte_tables)?;
+            }
             // Process CTEs from top to bottom
-            // do not allow self-references
+            // only allow self-references in recursive CTEs
             for cte in &with.cte_tables {
+                let name = &cte.alias.name.value;
                 // create logical plan & pass backreferencing CTEs
-                let logical_plan = self.query_to_plan_with_alias(
-                    &cte.query,
-                    Some(cte.alias.name.value.clone()),
-                    &mut ctes.clone(),
-                )?;
-                ctes.insert(cte.alias.name.value.clone(), logical_plan);
+                let logical_plan =
+                    if with.recursive && references_table(&cte.query.body, name) {
+                        self.recursive_cte_to_plan(cte, &mut ctes.clone())?
+                    } else {
+                        let plan = self.query_to_plan_with_alias(
+                            &cte.query,
+                            Some(name.clone()),
+                            &mut ctes.clone(),
+                        )?;
+                        if cte.alias.columns.is_empty() {
+                            plan
+                        } else {
+                            cte_projection(plan, &cte.alias)?
+                        }
+                    };
+                ctes.insert(name.clone(), logical_plan);
             }
         }
         let plan = self.set_expr_to_plan(set_expr, alias, ctes)?;
@@ -232,6 +355,90 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         self.limit(plan, &query.limit)
     }
 
+    /// Plans the recursive CTE `cte`, of the form `<static term> UNION [ALL]
+    /// <recursive term>`, where only the recursive term references the CTE
+    fn recursive_cte_to_plan(
+        &self,
+        cte: &Cte,
+        ctes: &mut HashMap<String, LogicalPlan>,
+    ) -> Result<LogicalPlan> {
+        let name = &cte.alias.name.value;
+        let (static_term, recursive_term, all) = match &cte.query.body {
+            SetExpr::SetOperation {
+                op: SetOperator::Union,
+                left,
+                right,
+                all,
+            } if cte.query.with.is_none() => (left, right, *all),
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Recursive query {} must be of the form \
+                    <static term> UNION [ALL] <recursive term>",
+                    name
+                )))
+            }
+        };
+        if references_table(static_term, name) {
+            return Err(DataFusionError::Plan(format!(
+                "The static term of recursive query {} can not reference the query",
+                name
+            )));
+        }
+
+        let static_plan =
+            self.set_expr_to_plan(static_term, Some(name.clone()), &mut ctes.clone())?;
+        let static_plan = cte_projection(static_plan, &cte.alias)?;
+
+        // the recursive term reads the rows of the previous iteration from the
+        // work table of the query
+        let work_table = CteWorkTable::new(
+            name,
+            Arc::new(static_plan.schema().as_ref().clone().into()),
+        );
+        let work_table_scan =
+            LogicalPlanBuilder::scan(name, Arc::new(work_table), None)?.build()?;
+        ctes.insert(name.clone(), work_table_scan);
+        let recursive_plan = self.set_expr_to_plan(recursive_term, None, ctes)?;
+
+        // the rows of the recursive term take the names and the types of the
+        // columns of the static term
+        let static_fields = static_plan.schema().fields();
+        let recursive_fields = recursive_plan.schema().fields();
+        if static_fields.len() != recursive_fields.len() {
+            return Err(DataFusionError::Plan(format!(
+                "The static and recursive terms of recursive query {} must have the \
+                same number of columns, got {} and {}",
+                name,
+                static_fields.len(),
+                recursive_fields.len()
+            )));
+        }
+        let expr = recursive_fields
+            .iter()
+            .zip(static_fields)
+            .map(|(field, static_field)| {
+                let column = Expr::Column(field.qualified_column());
+                let column = if field.data_type() == static_field.data_type() {
+                    column
+                } else {
+                    Expr::Cast {
+                        expr: Box::new(column),
+                        data_type: static_field.data_type().clone(),
+                    }
+                };
+                column.alias(static_field.name())
+            })
+            .collect::<Vec<_>>();
+        let recursive_plan =
+            project_with_alias(recursive_plan, expr, Some(name.clone()))?;
+
+        let plan = LogicalPlanBuilder::from(static_plan)
+            .recursive_query(name, recursive_plan, !all)?
+            .build()?;
+        let plan = self.order_by(plan, &cte.query.order_by)?;
+        self.limit(plan, &cte.query.limit)
+    }
+
     fn set_expr_to_plan(
         &self,
         set_expr: &SetExpr,
@@ -278,6 +485,16 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Generate a logical plan from a SET statement. The setting is validated
+    /// when the plan is executed.
+    pub fn set_variable_to_plan(&self, statement: &SetVariable) -> Result<LogicalPlan> {
+        Ok(LogicalPlan::SetVariable(PlanSetVariable {
+            variable: statement.variable.clone(),
+            value: statement.value.clone(),
+            schema: DFSchemaRef::new(DFSchema::empty()),
+        }))
+    }
+
     /// Generate a logical plan from a CREATE EXTERNAL TABLE statement
     pub fn external_table_to_plan(
         &self,
@@ -289,6 +506,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             file_type,
             has_header,
             location,
+            table_partition_cols,
+            options,
         } = statement;
 
         // semantic checks
@@ -304,16 +523,146 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
             FileType::NdJson => {}
             FileType::Avro => {}
+            FileType::Arrow => {}
         };
 
         let schema = self.build_schema(columns)?;
 
+        let mut partition_names = HashSet::new();
+        let mut partition_cols = vec![];
+        for column in table_partition_cols {
+            let name = &column.name.value;
+            if schema.field_with_name(name).is_ok() {
+                return Err(DataFusionError::Plan(format!(
+                    "Partition column {} collides with a column of the table",
+                    name
+                )));
+            }
+            if !partition_names.insert(name) {
+                return Err(DataFusionError::Plan(format!(
+                    "Partition column {} is specified twice",
+                    name
+                )));
+            }
+            // the partition values of the paths are cast from strings
+            let data_type = self.make_data_type(&column.data_type)?;
+            match data_type {
+                DataType::Utf8
+                | DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                | DataType::Float32
+                | DataType::Float64
+                | DataType::Date32
+                | DataType::Date64
+                | DataType::Timestamp(_, _) => {
+                    partition_cols.push((name.clone(), data_type))
+                }
+                _ => {
+                    return Err(DataFusionError::NotImplemented(format!(
+                        "Partition column {} can not be of type {}",
+                        name, column.data_type
+                    )))
+                }
+            }
+        }
+
         Ok(LogicalPlan::CreateExternalTable(PlanCreateExternalTable {
             schema: schema.to_dfschema_ref()?,
             name: name.clone(),
             location: location.clone(),
             file_type: *file_type,
             has_header: *has_header,
+            table_partition_cols: partition_cols,
+            options: options.clone(),
+        }))
+    }
+
+    /// Generate a plan that appends the rows of `source` to a table, with the
+    /// columns of the query cast to the types of the table in the table order
+    fn insert_to_plan(
+        &self,
+        table_name: &ObjectName,
+        columns: &[Ident],
+        source: &Query,
+    ) -> Result<LogicalPlan> {
+        let provider = self
+            .schema_provider
+            .get_table_provider(table_name.try_into()?)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "Table with name '{}' not found",
+                    table_name
+                ))
+            })?;
+        let table_schema = provider.schema();
+        let plan = self.query_to_plan(source)?;
+
+        let targets = if columns.is_empty() {
+            table_schema
+                .fields()
+                .iter()
+                .map(|field| field.name().clone())
+                .collect::<Vec<_>>()
+        } else {
+            columns.iter().map(|ident| ident.value.clone()).collect()
+        };
+        if targets.len() != plan.schema().fields().len() {
+            return Err(DataFusionError::Plan(format!(
+                "INSERT INTO {} has {} target columns but the query returns {} columns",
+                table_name,
+                targets.len(),
+                plan.schema().fields().len()
+            )));
+        }
+
+        if let Some(target) = targets
+            .iter()
+            .find(|target| table_schema.field_with_name(target).is_err())
+        {
+            return Err(DataFusionError::Plan(format!(
+                "Table {} has no column {}",
+                table_name, target
+            )));
+        }
+
+        let exprs = table_schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let position = targets
+                    .iter()
+                    .position(|target| target == field.name())
+                    .ok_or_else(|| {
+                    DataFusionError::NotImplemented(format!(
+                        "INSERT INTO {} without a value for column {}",
+                        table_name,
+                        field.name()
+                    ))
+                })?;
+                let source_field = plan.schema().field(position);
+                let expr = Expr::Column(source_field.qualified_column());
+                let expr = if source_field.data_type() == field.data_type() {
+                    expr
+                } else {
+                    Expr::Cast {
+                        expr: Box::new(expr),
+                        data_type: field.data_type().clone(),
+                    }
+                };
+                Ok(expr.alias(field.name()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let input = LogicalPlanBuilder::from(plan).project(exprs)?.build()?;
+        Ok(LogicalPlan::InsertInto(InsertInto {
+            name: table_name.to_string(),
+            input: Arc::new(input),
         }))
     }
 
@@ -324,6 +673,7 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         verbose: bool,
         analyze: bool,
         statement: &Statement,
+        format: ExplainFormat,
     ) -> Result<LogicalPlan> {
         let plan = self.sql_statement_to_plan(statement)?;
         let plan = Arc::new(plan);
@@ -338,12 +688,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }))
         } else {
             let stringified_plans =
-                vec![plan.to_stringified(PlanType::InitialLogicalPlan)];
+                vec![format.stringify(&plan, PlanType::InitialLogicalPlan)];
             Ok(LogicalPlan::Explain(Explain {
                 verbose,
                 plan,
                 stringified_plans,
                 schema,
+                format,
             }))
         }
     }
@@ -369,9 +720,10 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             SQLDataType::BigInt(_) => Ok(DataType::Int64),
             SQLDataType::Int(_) => Ok(DataType::Int32),
             SQLDataType::SmallInt(_) => Ok(DataType::Int16),
-            SQLDataType::Char(_) | SQLDataType::Varchar(_) | SQLDataType::Text => {
-                Ok(DataType::Utf8)
-            }
+            SQLDataType::Char(_)
+            | SQLDataType::Varchar(_)
+            | SQLDataType::Text
+            | SQLDataType::String => Ok(DataType::Utf8),
             SQLDataType::Decimal(precision, scale) => {
                 make_decimal_type(*precision, *scale)
             }
@@ -396,10 +748,24 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<Vec<LogicalPlan>> {
         match from.len() {
             0 => Ok(vec![LogicalPlanBuilder::empty(true).build()?]),
-            _ => from
-                .iter()
-                .map(|t| self.plan_table_with_joins(t, ctes))
-                .collect::<Result<Vec<_>>>(),
+            _ => {
+                let mut plans: Vec<LogicalPlan> = Vec::with_capacity(from.len());
+                for t in from {
+                    // an unnest is lateral to the relation preceding it
+                    let plan = match (unnest_relation(&t.relation), plans.pop()) {
+                        (Some((args, alias)), Some(left)) => {
+                            let left = self.plan_lateral_unnest(left, args, alias)?;
+                            self.plan_joins(left, &t.joins, ctes)?
+                        }
+                        (_, left) => {
+                            plans.extend(left);
+                            self.plan_table_with_joins(t, ctes)?
+                        }
+                    };
+                    plans.push(plan);
+                }
+                Ok(plans)
+            }
         }
     }
 
@@ -409,16 +775,20 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         ctes: &mut HashMap<String, LogicalPlan>,
     ) -> Result<LogicalPlan> {
         let left = self.create_relation(&t.relation, ctes)?;
-        match t.joins.len() {
-            0 => Ok(left),
-            n => {
-                let mut left = self.parse_relation_join(left, &t.joins[0], ctes)?;
-                for i in 1..n {
-                    left = self.parse_relation_join(left, &t.joins[i], ctes)?;
-                }
-                Ok(left)
-            }
+        self.plan_joins(left, &t.joins, ctes)
+    }
+
+    fn plan_joins(
+        &self,
+        left: LogicalPlan,
+        joins: &[Join],
+        ctes: &mut HashMap<String, LogicalPlan>,
+    ) -> Result<LogicalPlan> {
+        let mut left = left;
+        for join in joins {
+            left = self.parse_relation_join(left, join, ctes)?;
         }
+        Ok(left)
     }
 
     fn parse_relation_join(
@@ -427,6 +797,12 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         join: &Join,
         ctes: &mut HashMap<String, LogicalPlan>,
     ) -> Result<LogicalPlan> {
+        // an unnest is lateral to the relation it is cross joined with
+        if let (Some((args, alias)), JoinOperator::CrossJoin) =
+            (unnest_relation(&join.relation), &join.join_operator)
+        {
+            return self.plan_lateral_unnest(left, args, alias);
+        }
         let right = self.create_relation(&join.relation, ctes)?;
         match &join.join_operator {
             JoinOperator::LeftOuter(constraint) => {
@@ -596,6 +972,28 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         ctes: &mut HashMap<String, LogicalPlan>,
     ) -> Result<LogicalPlan> {
         let (plan, alias) = match relation {
+            TableFactor::Table { alias, args, .. }
+                if unnest_relation(relation).is_some() =>
+            {
+                let plan = self.plan_unnest(
+                    LogicalPlanBuilder::empty(true).build()?,
+                    args,
+                    UNNEST_COLUMN,
+                )?;
+                (
+                    project_with_alias(
+                        plan,
+                        vec![col(UNNEST_COLUMN)],
+                        alias.as_ref().map(|a| a.name.value.to_string()),
+                    )?,
+                    alias,
+                )
+            }
+            TableFactor::Table {
+                name, alias, args, ..
+            } if !args.is_empty() => {
+                (self.plan_table_function(name, args, alias)?, alias)
+            }
             TableFactor::Table { name, alias, .. } => {
                 let table_name = name.to_string();
                 let cte = ctes.get(&table_name);
@@ -689,6 +1087,325 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         }
     }
 
+    /// Plans the scan of the table returned by the table function `name`,
+    /// called with `args` evaluated to constants.
+    fn plan_table_function(
+        &self,
+        name: &ObjectName,
+        args: &[FunctionArg],
+        alias: &Option<TableAlias>,
+    ) -> Result<LogicalPlan> {
+        let function_name = match name.0.as_slice() {
+            [ident] if ident.quote_style.is_none() => ident.value.to_ascii_lowercase(),
+            [ident] => ident.value.clone(),
+            _ => name.to_string(),
+        };
+        let function = self
+            .schema_provider
+            .get_table_function(&function_name)
+            .ok_or_else(|| {
+                DataFusionError::Plan(format!(
+                    "There is no table function named '{}'",
+                    function_name
+                ))
+            })?;
+
+        let schema = DFSchema::empty();
+        let mut const_evaluator = ConstEvaluator::new(&ExecutionProps::new());
+        let args = args
+            .iter()
+            .map(|arg| {
+                match self
+                    .sql_fn_arg_to_logical_expr(arg, &schema)?
+                    .rewrite(&mut const_evaluator)?
+                {
+                    Expr::Literal(value) => Ok(value),
+                    expr => Err(DataFusionError::Plan(format!(
+                        "The arguments of table function '{}' must be constants, got {:?}",
+                        function_name, expr
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        LogicalPlanBuilder::scan(
+            alias
+                .as_ref()
+                .map(|a| a.name.value.as_str())
+                .unwrap_or(&function_name),
+            function.call(&args)?,
+            None,
+        )?
+        .build()
+    }
+
+    /// Plans `unnest(list) AS alias(name)` lateral to `left`: the lists, an
+    /// expression of the columns of `left`, are unnested into a column named
+    /// after the column alias, or `unnest`, appended to the columns of `left`.
+    fn plan_lateral_unnest(
+        &self,
+        left: LogicalPlan,
+        args: &[FunctionArg],
+        alias: &Option<TableAlias>,
+    ) -> Result<LogicalPlan> {
+        let name = match alias.as_ref().map(|alias| alias.columns.as_slice()) {
+            None | Some([]) => UNNEST_COLUMN,
+            Some([name]) => name.value.as_str(),
+            Some(names) => {
+                return Err(DataFusionError::Plan(format!(
+                    "unnest produces 1 column but {} names given as column alias",
+                    names.len()
+                )))
+            }
+        };
+        self.plan_unnest(left, args, name)
+    }
+
+    /// Unnests the lists of `args`, a single list expression of the columns of
+    /// `plan`, into a column `name` appended to the columns of `plan`.
+    ///
+    /// As with an inner join of each row to the elements of its list, the rows
+    /// whose list is empty or null produce no rows.
+    fn plan_unnest(
+        &self,
+        plan: LogicalPlan,
+        args: &[FunctionArg],
+        name: &str,
+    ) -> Result<LogicalPlan> {
+        let list = match args {
+            [arg] => self.sql_fn_arg_to_logical_expr(arg, plan.schema())?,
+            _ => {
+                return Err(DataFusionError::Plan(
+                    "unnest requires a single list argument".to_string(),
+                ))
+            }
+        };
+        let columns = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|field| Expr::Column(field.qualified_column()))
+            .chain(iter::once(list.alias(name)))
+            .collect::<Vec<_>>();
+        LogicalPlanBuilder::from(plan)
+            .project(columns)?
+            .unnest(Column::from_name(name))?
+            .build()
+    }
+
+    /// Plans the `unnest(list)` of the select list, if any, by unnesting the
+    /// lists into a column `unnest` of `plan`, which replaces the call in the
+    /// returned select. The lists are unnested before any aggregation.
+    fn plan_select_unnest<'b>(
+        &self,
+        plan: LogicalPlan,
+        select: &'b Select,
+    ) -> Result<(LogicalPlan, Cow<'b, Select>)> {
+        let unnests = select
+            .projection
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| match item {
+                SelectItem::UnnamedExpr(expr)
+                | SelectItem::ExprWithAlias { expr, .. } => {
+                    unnest_args(expr).map(|args| (i, args))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let (index, args) = match unnests.as_slice() {
+            [] => return Ok((plan, Cow::Borrowed(select))),
+            [(index, args)] => (*index, *args),
+            _ => {
+                return Err(DataFusionError::NotImplemented(
+                    "Only one unnest is supported in the select list".to_string(),
+                ))
+            }
+        };
+
+        let plan = self.plan_unnest(plan, args, UNNEST_COLUMN)?;
+        let mut select = select.clone();
+        let column = SQLExpr::Identifier(Ident::new(UNNEST_COLUMN));
+        select.projection[index] = match &select.projection[index] {
+            SelectItem::ExprWithAlias { alias, .. } => SelectItem::ExprWithAlias {
+                expr: column,
+                alias: alias.clone(),
+            },
+            _ => SelectItem::UnnamedExpr(column),
+        };
+        Ok((plan, Cow::Owned(select)))
+    }
+
+    /// Filter `plan` on `expr [NOT] IN (subquery)` with a semi join, or a
+    /// null-aware anti join if `negated`. Only uncorrelated subqueries
+    /// compared with a column are supported.
+    fn plan_in_subquery(
+        &self,
+        plan: LogicalPlan,
+        expr: &SQLExpr,
+        subquery: &Query,
+        negated: bool,
+        ctes: &mut HashMap<String, LogicalPlan>,
+    ) -> Result<LogicalPlan> {
+        let column = match self.sql_to_rex(expr, plan.schema())? {
+            Expr::Column(column) => column,
+            expr => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Only columns can be compared with an IN subquery, got {:?}",
+                    expr
+                )))
+            }
+        };
+        let subquery =
+            self.query_to_plan_with_alias(subquery, None, &mut ctes.clone())?;
+        let subquery_field = match subquery.schema().fields().as_slice() {
+            [field] => field.clone(),
+            fields => {
+                return Err(DataFusionError::Plan(format!(
+                    "An IN subquery must return a single column, got {}",
+                    fields.len()
+                )))
+            }
+        };
+
+        let subquery = project_subquery_keys(
+            subquery,
+            &[subquery_field],
+            plan.schema(),
+            &[column.clone()],
+            &[IN_SUBQUERY_COLUMN.to_owned()],
+        )?;
+
+        let builder = LogicalPlanBuilder::from(plan);
+        let join_keys = (column, Column::from_name(IN_SUBQUERY_COLUMN));
+        if negated {
+            builder.null_aware_anti_join(&subquery, join_keys)?.build()
+        } else {
+            builder
+                .join(
+                    &subquery,
+                    JoinType::Semi,
+                    (vec![join_keys.0], vec![join_keys.1]),
+                )?
+                .build()
+        }
+    }
+
+    /// Filter `plan` on `[NOT] EXISTS (subquery)` with a semi join, or an
+    /// anti join if `negated`. Unlike the one of `NOT IN`, this anti join is
+    /// not null-aware: a null key on either side only fails to match. Only
+    /// subqueries correlated with `plan` by equalities between columns of
+    /// their WHERE clause are supported.
+    fn plan_exists_subquery(
+        &self,
+        plan: LogicalPlan,
+        subquery: &Query,
+        negated: bool,
+        ctes: &mut HashMap<String, LogicalPlan>,
+    ) -> Result<LogicalPlan> {
+        // the subquery is only decorrelated if it filters the rows of a table
+        let select = match &subquery.body {
+            SetExpr::Select(select)
+                if select.group_by.is_empty()
+                    && select.having.is_none()
+                    && subquery.limit.is_none() =>
+            {
+                select
+            }
+            _ => {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Unsupported EXISTS subquery {}",
+                    subquery
+                )))
+            }
+        };
+
+        // the columns of the subquery hide the ones of `plan` with the same name
+        let mut inner_schema = DFSchema::empty();
+        for inner in self.plan_from_tables(&select.from, &mut ctes.clone())? {
+            inner_schema.merge(inner.schema());
+        }
+        let mut conjuncts = vec![];
+        if let Some(selection) = &select.selection {
+            split_conjunction(selection, &mut conjuncts);
+        }
+        let mut columns = vec![];
+        let mut keys = vec![];
+        let mut predicates = vec![];
+        for conjunct in conjuncts {
+            match self.correlation_key(conjunct, plan.schema(), &inner_schema) {
+                Some((column, key)) => {
+                    columns.push(column);
+                    keys.push(SelectItem::UnnamedExpr(key.clone()));
+                }
+                None => predicates.push(conjunct.clone()),
+            }
+        }
+        if columns.is_empty() {
+            return Err(DataFusionError::NotImplemented(
+                "Only EXISTS subqueries correlated by equalities between columns \
+                 are supported"
+                    .to_owned(),
+            ));
+        }
+
+        // the subquery selects the keys of the correlation instead
+        let mut select = select.clone();
+        select.projection = keys;
+        select.selection = conjunction(predicates);
+        let mut query = subquery.clone();
+        query.body = SetExpr::Select(select);
+        let subquery = self.query_to_plan_with_alias(&query, None, &mut ctes.clone())?;
+        let fields = subquery.schema().fields().clone();
+        let names = (0..columns.len())
+            .map(|i| format!("{}_{}", EXISTS_SUBQUERY_COLUMN, i))
+            .collect::<Vec<_>>();
+        let subquery =
+            project_subquery_keys(subquery, &fields, plan.schema(), &columns, &names)?;
+
+        let join_type = if negated {
+            JoinType::Anti
+        } else {
+            JoinType::Semi
+        };
+        let keys = names.into_iter().map(Column::from_name).collect::<Vec<_>>();
+        LogicalPlanBuilder::from(plan)
+            .join(&subquery, join_type, (columns, keys))?
+            .build()
+    }
+
+    /// The column of `outer` and the expression of a column of `inner`
+    /// compared by `predicate`, if it is an equality correlating a subquery
+    /// over `inner` with a query over `outer`
+    fn correlation_key<'e>(
+        &self,
+        predicate: &'e SQLExpr,
+        outer: &DFSchema,
+        inner: &DFSchema,
+    ) -> Option<(Column, &'e SQLExpr)> {
+        let (left, right) = match predicate {
+            SQLExpr::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            } => (left.as_ref(), right.as_ref()),
+            _ => return None,
+        };
+        let is_inner_column =
+            |expr| matches!(self.sql_to_rex(expr, inner), Ok(Expr::Column(_)));
+        let outer_column = |expr| match self.sql_to_rex(expr, outer) {
+            Ok(Expr::Column(column)) if !is_inner_column(expr) => Some(column),
+            _ => None,
+        };
+        if is_inner_column(left) {
+            outer_column(right).map(|column| (column, left))
+        } else if is_inner_column(right) {
+            outer_column(left).map(|column| (column, right))
+        } else {
+            None
+        }
+    }
+
     /// Generate a logic plan from an SQL select
     fn select_to_plan(
         &self,
@@ -698,7 +1415,14 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     ) -> Result<LogicalPlan> {
         let plans = self.plan_from_tables(&select.from, ctes)?;
 
-        let plan = match &select.selection {
+        // The `IN` and `EXISTS` subqueries of the WHERE clause are planned as
+        // joins
+        let (selection, subqueries) = match &select.selection {
+            Some(selection) => split_subqueries(selection),
+            None => (None, vec![]),
+        };
+
+        let plan = match &selection {
             Some(predicate_expr) => {
                 // build join schema
                 let mut fields = vec![];
@@ -768,7 +1492,23 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 }
             }
         };
-        let plan = plan?;
+        let mut plan = plan?;
+        for predicate in subqueries {
+            plan = match predicate {
+                SubqueryPredicate::In {
+                    expr,
+                    subquery,
+                    negated,
+                } => self.plan_in_subquery(plan, expr, subquery, negated, ctes)?,
+                SubqueryPredicate::Exists { subquery, negated } => {
+                    self.plan_exists_subquery(plan, subquery, negated, ctes)?
+                }
+            };
+        }
+
+        // The lists of an unnest of the SELECT expressions are unnested first.
+        let (plan, select) = self.plan_select_unnest(plan, select)?;
+        let select = select.as_ref();
 
         // The SELECT expressions, with wildcards expanded.
         let select_exprs = self.prepare_select_exprs(&plan, select)?;
@@ -1200,6 +1940,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             BinaryOperator::Or => Ok(Operator::Or),
             BinaryOperator::Like => Ok(Operator::Like),
             BinaryOperator::NotLike => Ok(Operator::NotLike),
+            BinaryOperator::ILike => Ok(Operator::ILike),
+            BinaryOperator::NotILike => Ok(Operator::NotILike),
             BinaryOperator::PGRegexMatch => Ok(Operator::RegexMatch),
             BinaryOperator::PGRegexIMatch => Ok(Operator::RegexIMatch),
             BinaryOperator::PGRegexNotMatch => Ok(Operator::RegexNotMatch),
@@ -1217,6 +1959,56 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
         })
     }
 
+    /// Plan `left IS [NOT] DISTINCT FROM right`. As the untyped `NULL` can't
+    /// be compared to the values of other types, comparing an expression to
+    /// it is planned as checking whether the expression is null.
+    fn parse_sql_is_distinct_from(
+        &self,
+        left: &SQLExpr,
+        right: &SQLExpr,
+        negated: bool,
+        schema: &DFSchema,
+    ) -> Result<Expr> {
+        match (left, right) {
+            (SQLExpr::Value(Value::Null), SQLExpr::Value(Value::Null)) => {
+                Ok(lit(negated))
+            }
+            (SQLExpr::Value(Value::Null), expr) | (expr, SQLExpr::Value(Value::Null)) => {
+                let expr = Box::new(self.sql_expr_to_logical_expr(expr, schema)?);
+                Ok(if negated {
+                    Expr::IsNull(expr)
+                } else {
+                    Expr::IsNotNull(expr)
+                })
+            }
+            _ => Ok(Expr::BinaryExpr {
+                left: Box::new(self.sql_expr_to_logical_expr(left, schema)?),
+                op: if negated {
+                    Operator::IsNotDistinctFrom
+                } else {
+                    Operator::IsDistinctFrom
+                },
+                right: Box::new(self.sql_expr_to_logical_expr(right, schema)?),
+            }),
+        }
+    }
+
+    fn parse_sql_number(&self, n: &str) -> Result<Expr> {
+        let value = parse_number_literal(n, self.parse_float_as_decimal())?;
+        Ok(Expr::Literal(value))
+    }
+
+    /// Whether the numbers with a decimal point are decimals, see
+    /// [`ExecutionConfig::parse_float_as_decimal`](crate::execution::context::ExecutionConfig::parse_float_as_decimal)
+    fn parse_float_as_decimal(&self) -> bool {
+        self.schema_provider
+            .get_config_options()
+            .iter()
+            .any(|(name, value)| {
+                name == "datafusion.sql_parser.parse_float_as_decimal" && value == "true"
+            })
+    }
+
     fn parse_sql_unary_op(
         &self,
         op: &UnaryOperator,
@@ -1232,18 +2024,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 match expr {
                     // optimization: if it's a number literal, we apply the negative operator
                     // here directly to calculate the new literal.
-                    SQLExpr::Value(Value::Number(n, _)) => match n.parse::<i64>() {
-                        Ok(n) => Ok(lit(-n)),
-                        Err(_) => Ok(lit(-n
-                            .parse::<f64>()
-                            .map_err(|_e| {
-                                DataFusionError::Internal(format!(
-                                    "negative operator can be only applied to integer and float operands, got: {}",
-                                    n))
-                            })?)),
-                    },
+                    SQLExpr::Value(Value::Number(n, _)) => {
+                        self.parse_sql_number(&format!("-{}", n))
+                    }
                     // not a literal, apply negative operator on expression
-                    _ => Ok(Expr::Negative(Box::new(self.sql_expr_to_logical_expr(expr, schema)?))),
+                    _ => Ok(Expr::Negative(Box::new(
+                        self.sql_expr_to_logical_expr(expr, schema)?,
+                    ))),
                 }
             }
             _ => Err(DataFusionError::NotImplemented(format!(
@@ -1262,10 +2049,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             .map(|row| {
                 row.iter()
                     .map(|v| match v {
-                        SQLExpr::Value(Value::Number(n, _)) => parse_sql_number(n),
+                        SQLExpr::Value(Value::Number(n, _)) => self.parse_sql_number(n),
                         SQLExpr::Value(Value::SingleQuotedString(ref s)) => {
                             Ok(lit(s.clone()))
                         }
+                        SQLExpr::Value(Value::HexStringLiteral(ref s)) => {
+                            parse_sql_hex_string(s)
+                        }
                         SQLExpr::Value(Value::Null) => {
                             Ok(Expr::Literal(ScalarValue::Utf8(None)))
                         }
@@ -1291,8 +2081,9 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
 
     fn sql_expr_to_logical_expr(&self, sql: &SQLExpr, schema: &DFSchema) -> Result<Expr> {
         match sql {
-            SQLExpr::Value(Value::Number(n, _)) => parse_sql_number(n),
+            SQLExpr::Value(Value::Number(n, _)) => self.parse_sql_number(n),
             SQLExpr::Value(Value::SingleQuotedString(ref s)) => Ok(lit(s.clone())),
+            SQLExpr::Value(Value::HexStringLiteral(ref s)) => parse_sql_hex_string(s),
             SQLExpr::Value(Value::Boolean(n)) => Ok(lit(*n)),
             SQLExpr::Value(Value::Null) => Ok(Expr::Literal(ScalarValue::Utf8(None))),
             SQLExpr::Extract { field, expr } => Ok(Expr::ScalarFunction {
@@ -1334,14 +2125,8 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
             }
 
             SQLExpr::MapAccess { ref column, keys } => {
-                if let SQLExpr::Identifier(ref id) = column.as_ref() {
-                    Ok(plan_indexed(col(&id.value), keys.clone()))
-                } else {
-                    Err(DataFusionError::NotImplemented(format!(
-                        "map access requires an identifier, found column {} instead",
-                        column
-                    )))
-                }
+                let column = self.sql_expr_to_logical_expr(column, schema)?;
+                Ok(plan_indexed(column, keys.clone()))
             }
 
             SQLExpr::CompoundIdentifier(ids) => {
@@ -1351,18 +2136,22 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 if &var_names[0][0..1] == "@" {
                     Ok(Expr::ScalarVariable(var_names))
                 } else {
-                    match (var_names.pop(), var_names.pop()) {
-                        (Some(name), Some(relation)) if var_names.is_empty() => {
-                            // table.column identifier
-                            Ok(Expr::Column(Column {
-                                relation: Some(relation),
-                                name,
-                            }))
-                        }
-                        _ => Err(DataFusionError::NotImplemented(format!(
-                            "Unsupported compound identifier '{:?}'",
-                            var_names,
-                        ))),
+                    match schema.resolve_column_path(&var_names) {
+                        // column followed by the fields of nested structs
+                        Ok((column, fields)) => Ok(fields
+                            .iter()
+                            .fold(Expr::Column(column), |expr, field| expr.field(field))),
+                        Err(e) => match (var_names.pop(), var_names.pop()) {
+                            (Some(name), Some(relation)) if var_names.is_empty() => {
+                                // table.column identifier, left for the
+                                // validation of the plan to report
+                                Ok(Expr::Column(Column {
+                                    relation: Some(relation),
+                                    name,
+                                }))
+                            }
+                            _ => Err(e),
+                        },
                     }
                 }
             }
@@ -1421,6 +2210,30 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 data_type: convert_data_type(data_type)?,
             }),
 
+            // the precision and scale of a decimal without them are the ones of
+            // the value
+            SQLExpr::TypedString {
+                data_type: SQLDataType::Decimal(None, None),
+                ref value,
+            } => match parse_decimal_literal(value) {
+                Some(value) => Ok(Expr::Literal(value)),
+                None => Err(DataFusionError::Plan(format!(
+                    "Cannot parse '{}' as a decimal",
+                    value
+                ))),
+            },
+
+            // the dates and the timestamps are validated when planned
+            SQLExpr::TypedString {
+                data_type: SQLDataType::Date,
+                ref value,
+            } => Ok(Expr::Literal(parse_date_literal(value)?)),
+
+            SQLExpr::TypedString {
+                data_type: SQLDataType::Timestamp,
+                ref value,
+            } => Ok(Expr::Literal(parse_timestamp_literal(value)?)),
+
             SQLExpr::TypedString {
                 ref data_type,
                 ref value,
@@ -1437,17 +2250,13 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 self.sql_expr_to_logical_expr(expr, schema)?,
             ))),
 
-            SQLExpr::IsDistinctFrom(left, right) => Ok(Expr::BinaryExpr {
-                left: Box::new(self.sql_expr_to_logical_expr(left, schema)?),
-                op: Operator::IsDistinctFrom,
-                right: Box::new(self.sql_expr_to_logical_expr(right, schema)?),
-            }),
+            SQLExpr::IsDistinctFrom(left, right) => {
+                self.parse_sql_is_distinct_from(left, right, false, schema)
+            }
 
-            SQLExpr::IsNotDistinctFrom(left, right) => Ok(Expr::BinaryExpr {
-                left: Box::new(self.sql_expr_to_logical_expr(left, schema)?),
-                op: Operator::IsNotDistinctFrom,
-                right: Box::new(self.sql_expr_to_logical_expr(right, schema)?),
-            }),
+            SQLExpr::IsNotDistinctFrom(left, right) => {
+                self.parse_sql_is_distinct_from(left, right, true, schema)
+            }
 
             SQLExpr::UnaryOp { ref op, ref expr } => {
                 self.parse_sql_unary_op(op, expr, schema)
@@ -1534,6 +2343,23 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                     return Ok(Expr::ScalarFunction { fun, args });
                 };
 
+                // then, struct constructors
+                if name == "struct" {
+                    let args = self.function_args_to_expr(function, schema)?;
+                    return Ok(struct_(args));
+                }
+                if name == "named_struct" {
+                    let args = self.function_args_to_expr(function, schema)?;
+                    return plan_named_struct(args);
+                }
+
+                // unnest is planned as an operator rather than an expression
+                if name == "unnest" {
+                    return Err(DataFusionError::NotImplemented(
+                        "unnest is only supported in the FROM clause and as an expression of the select list".to_string(),
+                    ));
+                }
+
                 // then, window function
                 if let Some(window) = &function.over {
                     let partition_by = window
@@ -1561,7 +2387,20 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                             }
                         })
                         .transpose()?;
-                    let fun = window_functions::WindowFunction::from_str(&name)?;
+                    let fun = match window_functions::WindowFunction::from_str(&name) {
+                        Ok(fun) => fun,
+                        Err(e) => match self.schema_provider.get_aggregate_meta(&name) {
+                            Some(fm) => {
+                                window_functions::WindowFunction::AggregateUDF(fm)
+                            }
+                            None => match self.schema_provider.get_window_meta(&name) {
+                                Some(fm) => {
+                                    window_functions::WindowFunction::WindowUDF(fm)
+                                }
+                                None => return Err(e),
+                            },
+                        },
+                    };
                     match fun {
                         window_functions::WindowFunction::AggregateFunction(
                             aggregate_fun,
@@ -1593,6 +2432,24 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                                 window_frame,
                             });
                         }
+                        window_functions::WindowFunction::AggregateUDF(fm) => {
+                            return Ok(Expr::WindowFunction {
+                                fun: window_functions::WindowFunction::AggregateUDF(fm),
+                                args: self.function_args_to_expr(function, schema)?,
+                                partition_by,
+                                order_by,
+                                window_frame,
+                            });
+                        }
+                        window_functions::WindowFunction::WindowUDF(fm) => {
+                            return Ok(Expr::WindowFunction {
+                                fun: window_functions::WindowFunction::WindowUDF(fm),
+                                args: self.function_args_to_expr(function, schema)?,
+                                partition_by,
+                                order_by,
+                                window_frame,
+                            });
+                        }
                     }
                 }
 
@@ -1853,10 +2710,33 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
                 ))
             }
         } else {
-            Err(DataFusionError::NotImplemented(format!(
-                "SHOW {} not implemented. Supported syntax: SHOW <TABLES>",
-                variable
-            )))
+            // SHOW ALL lists all the settings, SHOW <setting> a single one
+            let options = self.schema_provider.get_config_options();
+            let options = if variable.eq_ignore_ascii_case("all") {
+                options
+            } else {
+                options
+                    .into_iter()
+                    .filter(|(name, _)| name.eq_ignore_ascii_case(&variable))
+                    .collect()
+            };
+            if options.is_empty() {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "SHOW {} not implemented. Supported syntax: SHOW <TABLES>, \
+                     SHOW ALL or SHOW <setting>",
+                    variable
+                )));
+            }
+            let values = options
+                .into_iter()
+                .map(|(name, value)| vec![lit(name), lit(value)])
+                .collect();
+            LogicalPlanBuilder::values(values)?
+                .project(vec![
+                    col("column1").alias("name"),
+                    col("column2").alias("setting"),
+                ])?
+                .build()
         }
     }
 
@@ -1927,6 +2807,219 @@ impl<'a, S: ContextProvider> SqlToRel<'a, S> {
     }
 }
 
+/// The plan of the CTE `alias` from the plan of its query: its columns are
+/// qualified by the name of the CTE, and take the names of the columns of
+/// `alias` if any
+fn cte_projection(plan: LogicalPlan, alias: &TableAlias) -> Result<LogicalPlan> {
+    let name = &alias.name.value;
+    let fields = plan.schema().fields();
+    let qualified = fields.iter().all(|field| field.qualifier() == Some(name));
+    if alias.columns.is_empty() && qualified {
+        return Ok(plan);
+    }
+    if !alias.columns.is_empty() && alias.columns.len() != fields.len() {
+        return Err(DataFusionError::Plan(format!(
+            "CTE {} has {} columns but {} names given as column alias",
+            name,
+            fields.len(),
+            alias.columns.len(),
+        )));
+    }
+    let expr = fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let column_name = alias
+                .columns
+                .get(i)
+                .map_or(field.name().as_str(), |ident| ident.value.as_str());
+            Expr::Column(field.qualified_column()).alias(column_name)
+        })
+        .collect::<Vec<_>>();
+    project_with_alias(plan, expr, Some(name.clone()))
+}
+
+/// Whether the query `set_expr` reads the table or CTE `name` in its FROM
+/// clauses, its subqueries in FROM clauses included
+fn references_table(set_expr: &SetExpr, name: &str) -> bool {
+    fn relation_references(relation: &TableFactor, name: &str) -> bool {
+        match relation {
+            TableFactor::Table { name: table, .. } => table.to_string() == name,
+            TableFactor::Derived { subquery, .. } => {
+                references_table(&subquery.body, name)
+            }
+            TableFactor::NestedJoin(table_with_joins) => {
+                table_references(table_with_joins, name)
+            }
+            TableFactor::TableFunction { .. } => false,
+        }
+    }
+
+    fn table_references(table_with_joins: &TableWithJoins, name: &str) -> bool {
+        relation_references(&table_with_joins.relation, name)
+            || table_with_joins
+                .joins
+                .iter()
+                .any(|join| relation_references(&join.relation, name))
+    }
+
+    match set_expr {
+        SetExpr::Select(select) => select
+            .from
+            .iter()
+            .any(|table_with_joins| table_references(table_with_joins, name)),
+        SetExpr::Query(query) => references_table(&query.body, name),
+        SetExpr::SetOperation { left, right, .. } => {
+            references_table(left, name) || references_table(right, name)
+        }
+        SetExpr::Values(_) | SetExpr::Insert(_) => false,
+    }
+}
+
+/// Fails if some of the CTEs of a `WITH RECURSIVE` clause reference each
+/// other in a cycle, which is not supported: a CTE may only reference itself
+/// and the CTEs before it.
+fn check_no_mutual_recursion(ctes: &[Cte]) -> Result<()> {
+    // the CTEs referenced by each CTE, other than itself
+    let references = ctes
+        .iter()
+        .map(|cte| {
+            ctes.iter()
+                .enumerate()
+                .filter(|(_, other)| other.alias.name.value != cte.alias.name.value)
+                .filter(|(_, other)| {
+                    references_table(&cte.query.body, &other.alias.name.value)
+                })
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for (start, cte) in ctes.iter().enumerate() {
+        // the CTEs reached from `start`, looking for `start`
+        let mut reached = HashSet::new();
+        let mut stack = references[start].clone();
+        while let Some(i) = stack.pop() {
+            if i == start {
+                return Err(DataFusionError::NotImplemented(format!(
+                    "Mutual recursion between the common table expressions of \
+                    a WITH RECURSIVE clause is not supported, found for {}",
+                    cte.alias.name.value
+                )));
+            }
+            if reached.insert(i) {
+                stack.extend(&references[i]);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A subquery predicate of the WHERE clause, planned as a join
+enum SubqueryPredicate<'a> {
+    /// `expr [NOT] IN (subquery)`
+    In {
+        expr: &'a SQLExpr,
+        subquery: &'a Query,
+        negated: bool,
+    },
+    /// `[NOT] EXISTS (subquery)`
+    Exists { subquery: &'a Query, negated: bool },
+}
+
+/// Split the subquery predicates out of the conjuncts of `selection`,
+/// returning the remaining predicate, if any, and the subquery predicates
+fn split_subqueries(selection: &SQLExpr) -> (Option<SQLExpr>, Vec<SubqueryPredicate>) {
+    let mut conjuncts = vec![];
+    split_conjunction(selection, &mut conjuncts);
+    let mut predicates = vec![];
+    let mut subqueries = vec![];
+    for conjunct in conjuncts {
+        match conjunct {
+            SQLExpr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => subqueries.push(SubqueryPredicate::In {
+                expr,
+                subquery,
+                negated: *negated,
+            }),
+            SQLExpr::Exists(subquery) => subqueries.push(SubqueryPredicate::Exists {
+                subquery,
+                negated: false,
+            }),
+            SQLExpr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => match expr.as_ref() {
+                SQLExpr::Exists(subquery) => subqueries.push(SubqueryPredicate::Exists {
+                    subquery,
+                    negated: true,
+                }),
+                _ => predicates.push(conjunct.clone()),
+            },
+            _ => predicates.push(conjunct.clone()),
+        }
+    }
+    (conjunction(predicates), subqueries)
+}
+
+/// Push the conjuncts of `expr` to `conjuncts`
+fn split_conjunction<'a>(expr: &'a SQLExpr, conjuncts: &mut Vec<&'a SQLExpr>) {
+    match expr {
+        SQLExpr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            split_conjunction(left, conjuncts);
+            split_conjunction(right, conjuncts);
+        }
+        SQLExpr::Nested(expr) => split_conjunction(expr, conjuncts),
+        _ => conjuncts.push(expr),
+    }
+}
+
+/// The conjunction of `predicates`, if any
+fn conjunction(predicates: Vec<SQLExpr>) -> Option<SQLExpr> {
+    predicates
+        .into_iter()
+        .reduce(|left, right| SQLExpr::BinaryOp {
+            left: Box::new(left),
+            op: BinaryOperator::And,
+            right: Box::new(right),
+        })
+}
+
+/// Project `fields` of `subquery` as the keys joined with `columns` of
+/// `schema`, renamed to `names` to not clash with the columns of `schema`
+/// and cast to the types of `columns`
+fn project_subquery_keys(
+    subquery: LogicalPlan,
+    fields: &[DFField],
+    schema: &DFSchema,
+    columns: &[Column],
+    names: &[String],
+) -> Result<LogicalPlan> {
+    let keys = fields
+        .iter()
+        .zip(columns)
+        .zip(names)
+        .map(|((field, column), name)| {
+            let data_type = schema.field_from_column(column)?.data_type();
+            let mut key = Expr::Column(field.qualified_column());
+            if field.data_type() != data_type {
+                key = Expr::Cast {
+                    expr: Box::new(key),
+                    data_type: data_type.clone(),
+                };
+            }
+            Ok(key.alias(name))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    LogicalPlanBuilder::from(subquery).project(keys)?.build()
+}
+
 /// Remove join expressions from a filter expression
 fn remove_join_expressions(
     expr: &Expr,
@@ -2128,6 +3221,120 @@ mod tests {
         );
     }
 
+    #[test]
+    fn select_hex_string_literal() {
+        quick_test(
+            "SELECT X'DEADbeef'",
+            "Projection: Binary(\"deadbeef\")\
+             \n  EmptyRelation",
+        );
+        let err = logical_plan("SELECT X'ABC'").expect_err("query should have failed");
+        assert!(err
+            .to_string()
+            .contains("Invalid hexadecimal string X'ABC'"));
+    }
+
+    #[test]
+    fn select_struct() {
+        quick_test(
+            "SELECT struct(id, age + 1), named_struct('n', first_name) AS p FROM person",
+            "Projection: struct(id: #person.id, c1: #person.age + Int64(1)), \
+             struct(n: #person.first_name) AS p\
+             \n  TableScan: person projection=None",
+        );
+        let err = logical_plan("SELECT named_struct(first_name, id) FROM person")
+            .expect_err("query should have failed");
+        assert!(err
+            .to_string()
+            .contains("named_struct field names must be string literals"));
+        let err = logical_plan("SELECT named_struct('n') FROM person")
+            .expect_err("query should have failed");
+        assert!(err
+            .to_string()
+            .contains("named_struct requires pairs of field names and values"));
+    }
+
+    #[test]
+    fn select_struct_field() {
+        let sql = "SELECT p.n, p['a']
+                   FROM (
+                     SELECT named_struct('n', first_name, 'a', age) AS p
+                     FROM person
+                   ) AS a
+                   WHERE a.p.a > 21";
+        let expected = "Projection: (#a.p)[n], (#a.p)[a]\
+                        \n  Filter: (#a.p)[a] > Int64(21)\
+                        \n    Projection: #a.p, alias=a\
+                        \n      Projection: struct(n: #person.first_name, a: #person.age) AS p, alias=a\
+                        \n        TableScan: person projection=None";
+        quick_test(sql, expected);
+
+        let err = logical_plan(
+            "SELECT p.x FROM (SELECT named_struct('n', first_name) AS p FROM person) AS a",
+        )
+        .expect_err("query should have failed");
+        assert!(err.to_string().contains("Field x not found in struct"));
+    }
+
+    #[test]
+    fn select_unnest() {
+        let names = "(SELECT id, array(first_name, last_name) AS names FROM person) AS p";
+        let input = "\n      Projection: #p.id, #p.names, alias=p\
+                     \n        Projection: #person.id, array(#person.first_name, #person.last_name) AS names, alias=p\
+                     \n          TableScan: person projection=None";
+
+        // in the select list
+        let sql = format!("SELECT id, unnest(names) AS name FROM {}", names);
+        let expected = format!(
+            "Projection: #p.id, #unnest AS name\
+             \n  Unnest: #unnest\
+             \n    Projection: #p.id, #p.names, #p.names AS unnest{}",
+            input
+        );
+        quick_test(&sql, &expected);
+
+        // lateral to a relation of the FROM clause
+        let sql = format!(
+            "SELECT id, name FROM {} CROSS JOIN unnest(names) AS u(name)",
+            names
+        );
+        let expected = format!(
+            "Projection: #p.id, #name\
+             \n  Unnest: #name\
+             \n    Projection: #p.id, #p.names, #p.names AS name{}",
+            input
+        );
+        quick_test(&sql, &expected);
+
+        // alone in the FROM clause
+        quick_test(
+            "SELECT x FROM unnest(array(1, 2)) AS u(x)",
+            "Projection: #u.x\
+             \n  Projection: #u.unnest AS x, alias=u\
+             \n    Projection: #unnest, alias=u\
+             \n      Unnest: #unnest\
+             \n        Projection: array(Int64(1), Int64(2)) AS unnest\
+             \n          EmptyRelation",
+        );
+
+        let err = logical_plan(&format!(
+            "SELECT unnest(names), unnest(names) FROM {}",
+            names
+        ))
+        .expect_err("query should have failed");
+        assert!(err.to_string().contains("Only one unnest is supported"));
+        let err = logical_plan(&format!("SELECT unnest(names) + 1 FROM {}", names))
+            .expect_err("query should have failed");
+        assert!(err
+            .to_string()
+            .contains("unnest is only supported in the FROM clause"));
+        let err = logical_plan("SELECT unnest(id) FROM person")
+            .expect_err("query should have failed");
+        assert!(err
+            .to_string()
+            .contains("Can not unnest #unnest of type UInt32"));
+    }
+
     #[test]
     fn select_simple_filter() {
         let sql = "SELECT id, first_name, last_name \
@@ -3155,6 +4362,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn insert_into_with_columns() {
+        let sql = "INSERT INTO lineitem (price, l_description, l_item_id) \
+            SELECT qty, o_item_id, order_id FROM orders";
+        let expected = "InsertInto: \"lineitem\"\
+        \n  Projection: #orders.order_id AS l_item_id, #orders.o_item_id AS l_description, CAST(#orders.qty AS Float64) AS price\
+        \n    Projection: #orders.qty, #orders.o_item_id, #orders.order_id\
+        \n      TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn insert_into_column_count_mismatch() {
+        let sql = "INSERT INTO lineitem SELECT order_id, o_item_id FROM orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"INSERT INTO lineitem has 3 target columns but the query returns 2 columns\")",
+            format!("{:?}", err)
+        );
+    }
+
+    #[test]
+    fn insert_into_unknown_column() {
+        let sql = "INSERT INTO lineitem (l_item_id, l_description, cost) \
+            SELECT order_id, o_item_id, price FROM orders";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Table lineitem has no column cost\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn create_external_table_csv() {
         let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV LOCATION 'foo.csv'";
@@ -3187,6 +4426,46 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn create_external_table_partitioned() {
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV \
+            PARTITIONED BY (dt STRING, region, year INT) LOCATION 'foo/'";
+        let plan = logical_plan(sql).unwrap();
+        match &plan {
+            LogicalPlan::CreateExternalTable(PlanCreateExternalTable {
+                table_partition_cols,
+                ..
+            }) => assert_eq!(
+                &vec![
+                    ("dt".to_owned(), DataType::Utf8),
+                    ("region".to_owned(), DataType::Utf8),
+                    ("year".to_owned(), DataType::Int32)
+                ],
+                table_partition_cols
+            ),
+            _ => panic!("Expected CreateExternalTable, found {:?}", plan),
+        }
+    }
+
+    #[test]
+    fn create_external_table_partition_column_collision() {
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV \
+            PARTITIONED BY (c1) LOCATION 'foo/'";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "Plan(\"Partition column c1 collides with a column of the table\")",
+            format!("{:?}", err)
+        );
+
+        let sql = "CREATE EXTERNAL TABLE t(c1 int) STORED AS CSV \
+            PARTITIONED BY (dt BOOLEAN) LOCATION 'foo/'";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Partition column dt can not be of type BOOLEAN\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn equijoin_explicit_syntax() {
         let sql = "SELECT id, order_id \
@@ -3200,6 +4479,71 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn in_subquery_semi_join() {
+        let sql = "SELECT id FROM person \
+            WHERE age IN (SELECT customer_id FROM orders)";
+        let expected = "Projection: #person.id\
+        \n  Join: #person.age = #__in_subquery\
+        \n    TableScan: person projection=None\
+        \n    Projection: CAST(#orders.customer_id AS Int32) AS __in_subquery\
+        \n      Projection: #orders.customer_id\
+        \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn not_in_subquery_null_aware_anti_join() {
+        let sql = "SELECT id FROM person \
+            WHERE id NOT IN (SELECT customer_id FROM orders) AND age > 21";
+        let expected = "Projection: #person.id\
+        \n  Join: #person.id = #__in_subquery, null_aware=true\
+        \n    Filter: #person.age > Int64(21)\
+        \n      TableScan: person projection=None\
+        \n    Projection: #orders.customer_id AS __in_subquery\
+        \n      Projection: #orders.customer_id\
+        \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn exists_subquery_semi_join() {
+        let sql = "SELECT id FROM person WHERE EXISTS \
+            (SELECT * FROM orders WHERE orders.customer_id = person.id AND qty > 5)";
+        let expected = "Projection: #person.id\
+        \n  Join: #person.id = #__exists_subquery_0\
+        \n    TableScan: person projection=None\
+        \n    Projection: #orders.customer_id AS __exists_subquery_0\
+        \n      Projection: #orders.customer_id\
+        \n        Filter: #orders.qty > Int64(5)\
+        \n          TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn not_exists_subquery_anti_join() {
+        // `id` is not a column of orders, it is the one of person
+        let sql = "SELECT id FROM person \
+            WHERE NOT EXISTS (SELECT 1 FROM orders WHERE customer_id = id)";
+        let expected = "Projection: #person.id\
+        \n  Join: #person.id = #__exists_subquery_0\
+        \n    TableScan: person projection=None\
+        \n    Projection: #orders.customer_id AS __exists_subquery_0\
+        \n      Projection: #orders.customer_id\
+        \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
+    #[test]
+    fn uncorrelated_exists_subquery() {
+        let sql = "SELECT id FROM person WHERE NOT EXISTS (SELECT 1 FROM orders)";
+        let err = logical_plan(sql).expect_err("query should have failed");
+        assert_eq!(
+            "NotImplemented(\"Only EXISTS subqueries correlated by equalities between columns are supported\")",
+            format!("{:?}", err)
+        );
+    }
+
     #[test]
     fn equijoin_unsupported_expression() {
         let sql = "SELECT id, order_id \
@@ -3319,6 +4663,24 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn recursive_cte() {
+        let sql = "WITH RECURSIVE t(n) AS (
+                    SELECT order_id FROM orders
+                    UNION SELECT n FROM t WHERE n > 1
+                ) SELECT n FROM t";
+        let expected = "Projection: #t.n\
+            \n  RecursiveQuery: name=t, is_distinct=true\
+            \n    Projection: #t.order_id AS n, alias=t\
+            \n      Projection: #orders.order_id, alias=t\
+            \n        TableScan: orders projection=None\
+            \n    Projection: #t.n AS n, alias=t\
+            \n      Projection: #t.n\
+            \n        Filter: #t.n > Int64(1)\
+            \n          TableScan: t projection=None";
+        quick_test(sql, expected);
+    }
+
     #[test]
     fn union_4_combined_in_one() {
         let sql = "SELECT order_id from orders
@@ -3575,8 +4937,6 @@ mod tests {
     ///                     Sort Key: order_id, qty
     ///                     ->  Seq Scan on orders  (cost=0.00..20.00 rows=1000 width=8)
     /// ```
-    ///
-    /// FIXME: for now we are not detecting prefix of sorting keys in order to save one sort exec phase
     #[test]
     fn over_order_by_sort_keys_sorting_prefix_compacting() {
         let sql = "SELECT order_id, MAX(qty) OVER (ORDER BY order_id), SUM(qty) OVER (), MIN(qty) OVER (ORDER BY order_id, qty) from orders";
@@ -3589,6 +4949,21 @@ mod tests {
         quick_test(sql, expected);
     }
 
+    /// The window partitioned by `order_id` is nested right above the window
+    /// sorted by `order_id, qty`, whose sort it shares, rather than below the
+    /// window sorted by `price`
+    #[test]
+    fn over_partition_by_sort_keys_sorting_prefix_grouping() {
+        let sql = "SELECT order_id, MAX(qty) OVER (PARTITION BY order_id ORDER BY qty), SUM(qty) OVER (PARTITION BY order_id), MIN(qty) OVER (ORDER BY price) from orders";
+        let expected = "\
+        Projection: #orders.order_id, #MAX(orders.qty) PARTITION BY [#orders.order_id] ORDER BY [#orders.qty ASC NULLS LAST], #SUM(orders.qty) PARTITION BY [#orders.order_id], #MIN(orders.qty) ORDER BY [#orders.price ASC NULLS LAST]\
+        \n  WindowAggr: windowExpr=[[MIN(#orders.qty) ORDER BY [#orders.price ASC NULLS LAST]]]\
+        \n    WindowAggr: windowExpr=[[SUM(#orders.qty) PARTITION BY [#orders.order_id]]]\
+        \n      WindowAggr: windowExpr=[[MAX(#orders.qty) PARTITION BY [#orders.order_id] ORDER BY [#orders.qty ASC NULLS LAST]]]\
+        \n        TableScan: orders projection=None";
+        quick_test(sql, expected);
+    }
+
     /// psql result
     /// ```
     ///                                        QUERY PLAN
@@ -3708,11 +5083,56 @@ mod tests {
     #[test]
     fn select_typedstring() {
         let sql = "SELECT date '2020-12-10' AS date FROM person";
-        let expected = "Projection: CAST(Utf8(\"2020-12-10\") AS Date32) AS date\
+        let expected = "Projection: Date32(\"18606\") AS date\
             \n  TableScan: person projection=None";
         quick_test(sql, expected);
     }
 
+    #[test]
+    fn select_typed_literals() -> Result<()> {
+        let sql = "SELECT DATE '2021-02-28', TIMESTAMP '2021-01-01 00:00:00+05:30', \
+            TIMESTAMP '2021-01-01 00:00:00', 1_000, -1e-3, -9223372036854775808";
+        let plan = logical_plan(sql)?;
+        let types = plan
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Date32,
+                DataType::Timestamp(TimeUnit::Nanosecond, Some("+05:30".to_owned())),
+                DataType::Timestamp(TimeUnit::Nanosecond, None),
+                DataType::Int64,
+                DataType::Float64,
+                DataType::Int64,
+            ]
+        );
+
+        let cases = [
+            (
+                "SELECT DATE '2021-02-30'",
+                "Invalid DATE literal '2021-02-30'",
+            ),
+            (
+                "SELECT TIMESTAMP '2021-01-01 25:00:00'",
+                "Invalid TIMESTAMP literal '2021-01-01 25:00:00'",
+            ),
+            ("SELECT 1__000", "Invalid number literal '1__000'"),
+            (
+                "SELECT 9223372036854775808",
+                "Integer literal '9223372036854775808' is out of the range of BIGINT",
+            ),
+        ];
+        for (sql, expected) in cases {
+            let err = logical_plan(sql).unwrap_err().to_string();
+            assert!(err.contains(expected), "{}", err);
+        }
+        Ok(())
+    }
+
     #[test]
     fn select_multibyte_column() {
         let sql = r#"SELECT "😀" FROM person"#;
@@ -3820,9 +5240,128 @@ mod tests {
     }
 }
 
-fn parse_sql_number(n: &str) -> Result<Expr> {
-    match n.parse::<i64>() {
-        Ok(n) => Ok(lit(n)),
-        Err(_) => Ok(lit(n.parse::<f64>().unwrap())),
+/// Parse `s`, the digits of a hexadecimal string such as `X'DEADBEEF'`, into
+/// a binary literal
+fn parse_sql_hex_string(s: &str) -> Result<Expr> {
+    match parse_hex(s) {
+        Some(value) => Ok(Expr::Literal(ScalarValue::Binary(Some(value)))),
+        None => Err(DataFusionError::Plan(format!(
+            "Invalid hexadecimal string X'{}', it must be made of pairs of hexadecimal digits",
+            s
+        ))),
+    }
+}
+
+/// Parse `n`, a number such as `-12.345`, into a decimal of as many digits
+/// after the point as it has. `None` if `n` is not such a number, or if it
+/// has more digits than a decimal holds
+fn parse_decimal_literal(n: &str) -> Option<ScalarValue> {
+    let (negative, unsigned) = match n.strip_prefix('-') {
+        Some(unsigned) => (true, unsigned),
+        None => (false, n),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    let integer = integer.trim_start_matches('0');
+    let digits = format!("{}{}", integer, fraction);
+    if digits.is_empty()
+        || digits.len() > MAX_PRECISION_FOR_DECIMAL128
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    let value = digits.parse::<i128>().ok()?;
+    Some(ScalarValue::Decimal128(
+        Some(if negative { -value } else { value }),
+        digits.len(),
+        fraction.len(),
+    ))
+}
+
+/// Parse `n`, a number such as `-1_000`, `12.5` or `1e-3` whose digits may be
+/// separated by underscores:
+/// * an integer is an `Int64`, or a decimal if it is out of its range and
+///   `as_decimal`
+/// * a number with an exponent is a `Float64`
+/// * a number with a decimal point is a decimal if `as_decimal`, a `Float64`
+///   otherwise
+fn parse_number_literal(n: &str, as_decimal: bool) -> Result<ScalarValue> {
+    let invalid = || DataFusionError::Plan(format!("Invalid number literal '{}'", n));
+    // an underscore must be between two digits
+    let bytes = n.as_bytes();
+    let is_digit = |i: usize| bytes.get(i).map_or(false, u8::is_ascii_digit);
+    if (0..bytes.len())
+        .any(|i| bytes[i] == b'_' && !(i > 0 && is_digit(i - 1) && is_digit(i + 1)))
+    {
+        return Err(invalid());
+    }
+    let number = n.replace('_', "");
+    if number.contains(|c: char| c == 'e' || c == 'E') {
+        return match number.parse::<f64>() {
+            Ok(value) => Ok(ScalarValue::Float64(Some(value))),
+            Err(_) => Err(invalid()),
+        };
+    }
+    let unsigned = number.strip_prefix('-').unwrap_or(&number);
+    if unsigned.is_empty() || !unsigned.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        return Err(invalid());
+    }
+    let is_integer = !unsigned.contains('.');
+    if is_integer {
+        if let Ok(value) = number.parse::<i64>() {
+            return Ok(ScalarValue::Int64(Some(value)));
+        }
+    }
+    match parse_decimal_literal(&number) {
+        Some(value) if as_decimal => Ok(value),
+        None if as_decimal => Err(DataFusionError::Plan(format!(
+            "Number literal '{}' has more than {} digits",
+            n, MAX_PRECISION_FOR_DECIMAL128
+        ))),
+        _ if is_integer => Err(DataFusionError::Plan(format!(
+            "Integer literal '{}' is out of the range of BIGINT, enable \
+             datafusion.sql_parser.parse_float_as_decimal to plan it as a decimal",
+            n
+        ))),
+        _ => number
+            .parse::<f64>()
+            .map(|value| ScalarValue::Float64(Some(value)))
+            .map_err(|_| invalid()),
+    }
+}
+
+/// Parse the literal `DATE 'value'`, such as `DATE '2021-02-28'`
+fn parse_date_literal(value: &str) -> Result<ScalarValue> {
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| {
+        DataFusionError::Plan(format!("Invalid DATE literal '{}'", value))
+    })?;
+    let days = date
+        .signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+        .num_days();
+    Ok(ScalarValue::Date32(Some(days as i32)))
+}
+
+/// Parse the literal `TIMESTAMP 'value'`. A timestamp with an offset from UTC,
+/// such as `TIMESTAMP '2021-01-01 00:00:00+05:30'`, keeps the offset as its
+/// time zone.
+fn parse_timestamp_literal(value: &str) -> Result<ScalarValue> {
+    let invalid =
+        || DataFusionError::Plan(format!("Invalid TIMESTAMP literal '{}'", value));
+    for format in ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"] {
+        if let Ok(datetime) = DateTime::parse_from_str(value, format) {
+            let nanos = datetime
+                .timestamp()
+                .checked_mul(1_000_000_000)
+                .and_then(|nanos| {
+                    nanos.checked_add(datetime.timestamp_subsec_nanos() as i64)
+                })
+                .ok_or_else(invalid)?;
+            let offset = datetime.offset().to_string();
+            return Ok(ScalarValue::TimestampNanosecond(Some(nanos), Some(offset)));
+        }
     }
+    // a date alone is at midnight
+    string_to_timestamp_nanos(value)
+        .or_else(|_| string_to_timestamp_nanos(&format!("{}T00:00:00", value)))
+        .map(|nanos| ScalarValue::TimestampNanosecond(Some(nanos), None))
+        .map_err(|_| invalid())
 }
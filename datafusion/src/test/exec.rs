@@ -18,10 +18,14 @@
 //! Simple iterator over batches for use in testing
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::{
     any::Any,
     pin::Pin,
-    sync::{Arc, Weak},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Weak,
+    },
     task::{Context, Poll},
 };
 use tokio::sync::Barrier;
@@ -34,11 +38,12 @@ use arrow::{
 use futures::Stream;
 
 use crate::physical_plan::{
-    common, DisplayFormatType, ExecutionPlan, Partitioning, RecordBatchStream,
-    SendableRecordBatchStream, Statistics,
+    common, fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan,
+    Partitioning, RecordBatchStream, SendableRecordBatchStream, Statistics,
 };
 use crate::{
     error::{DataFusionError, Result},
+    execution::task_context::TaskContext,
     physical_plan::stream::RecordBatchReceiverStream,
 };
 
@@ -162,7 +167,11 @@ impl ExecutionPlan for MockExec {
     }
 
     /// Returns a stream which yields data
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         assert_eq!(partition, 0);
 
         // Result doesn't implement clone, so do it ourself
@@ -206,6 +215,7 @@ impl ExecutionPlan for MockExec {
             DisplayFormatType::Default => {
                 write!(f, "MockExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -293,7 +303,11 @@ impl ExecutionPlan for BarrierExec {
     }
 
     /// Returns a stream which yields data
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         assert!(partition < self.data.len());
 
         let (tx, rx) = tokio::sync::mpsc::channel(2);
@@ -329,6 +343,7 @@ impl ExecutionPlan for BarrierExec {
             DisplayFormatType::Default => {
                 write!(f, "BarrierExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -379,7 +394,11 @@ impl ExecutionPlan for ErrorExec {
     }
 
     /// Returns a stream which yields data
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         Err(DataFusionError::Internal(format!(
             "ErrorExec, unsurprisingly, errored in partition {}",
             partition
@@ -395,6 +414,7 @@ impl ExecutionPlan for ErrorExec {
             DisplayFormatType::Default => {
                 write!(f, "ErrorExec")
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -456,7 +476,11 @@ impl ExecutionPlan for StatisticsExec {
         }
     }
 
-    async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         unimplemented!("This plan only serves for testing statistics")
     }
 
@@ -478,6 +502,7 @@ impl ExecutionPlan for StatisticsExec {
                     self.stats.num_rows,
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 }
@@ -546,7 +571,11 @@ impl ExecutionPlan for BlockingExec {
         )))
     }
 
-    async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         Ok(Box::pin(BlockingStream {
             schema: Arc::clone(&self.schema),
             _refs: Arc::clone(&self.refs),
@@ -562,6 +591,7 @@ impl ExecutionPlan for BlockingExec {
             DisplayFormatType::Default => {
                 write!(f, "BlockingExec",)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -612,3 +642,96 @@ pub async fn assert_strong_count_converges_to_zero<T>(refs: Weak<T>) {
     .await
     .unwrap();
 }
+
+/// Execution plan counting the executions of its partitions, which are
+/// delegated to its input
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CountingExec {
+    input: Arc<dyn ExecutionPlan>,
+    /// Number of executions of the partitions of this plan
+    #[serde(skip)]
+    executions: Arc<AtomicUsize>,
+}
+
+impl CountingExec {
+    /// Create a new [`CountingExec`] delegating to `input`
+    pub fn new(input: Arc<dyn ExecutionPlan>) -> Self {
+        Self {
+            input,
+            executions: Default::default(),
+        }
+    }
+
+    /// Number of executions of the partitions of this plan so far
+    pub fn executions(&self) -> usize {
+        self.executions.load(Ordering::SeqCst)
+    }
+}
+
+impl LambdaExecPlan for CountingExec {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "CountingExec can't be fed".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "counting_exec")]
+impl ExecutionPlan for CountingExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.input.schema()
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![self.input.clone()]
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        self.input.output_partitioning()
+    }
+
+    fn with_new_children(
+        &self,
+        _: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        Err(DataFusionError::Internal(format!(
+            "Children cannot be replaced in {:?}",
+            self
+        )))
+    }
+
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        self.executions.fetch_add(1, Ordering::SeqCst);
+        self.input.execute(partition, context).await
+    }
+
+    fn fmt_as(
+        &self,
+        t: DisplayFormatType,
+        f: &mut std::fmt::Formatter,
+    ) -> std::fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                write!(f, "CountingExec")
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        self.input.statistics()
+    }
+}
@@ -21,6 +21,7 @@ use crate::datasource::object_store::local::local_unpartitioned_file;
 use crate::datasource::{MemTable, PartitionedFile, TableProvider};
 use crate::error::Result;
 use crate::logical_plan::{LogicalPlan, LogicalPlanBuilder};
+use crate::physical_plan::{displayable, ExecutionPlan};
 use array::{
     Array, ArrayRef, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
     TimestampNanosecondArray, TimestampSecondArray,
@@ -301,6 +302,31 @@ pub fn assert_is_pending<'a, T>(fut: &mut Pin<Box<dyn Future<Output = T> + Send
     assert!(poll.is_pending());
 }
 
+/// Asserts that `plan` describes its output the same way after a round trip
+/// through JSON and through the binary serialization of plans
+pub fn assert_plan_roundtrip(plan: Arc<dyn ExecutionPlan>) {
+    let json = serde_json::to_string(&plan).unwrap();
+    let from_json: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+    let bytes = crate::physical_plan::serde::to_bytes(&plan).unwrap();
+    let from_bytes = crate::physical_plan::serde::from_bytes(&bytes).unwrap();
+    for roundtrip in [from_json, from_bytes] {
+        assert_eq!(plan.schema(), roundtrip.schema());
+        assert_eq!(
+            format!("{:?}", plan.output_partitioning()),
+            format!("{:?}", roundtrip.output_partitioning())
+        );
+        assert_eq!(
+            format!("{:?}", plan.output_ordering()),
+            format!("{:?}", roundtrip.output_ordering())
+        );
+        assert_eq!(plan.statistics(), roundtrip.statistics());
+        assert_eq!(
+            displayable(plan.as_ref()).indent().to_string(),
+            displayable(roundtrip.as_ref()).indent().to_string()
+        );
+    }
+}
+
 pub mod exec;
 pub mod object_store;
 pub mod user_defined;
@@ -17,19 +17,24 @@
 //! Object store implem used for testing
 
 use std::{
+    collections::BTreeMap,
     io,
     io::{Cursor, Read},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
 use crate::{
     datasource::object_store::{
-        FileMeta, FileMetaStream, ListEntryStream, ObjectReader, ObjectStore, SizedFile,
+        paginate, FileMeta, FileMetaPage, FileMetaStream, ListEntryStream, ObjectReader,
+        ObjectStore, SizedFile,
     },
     error::{DataFusionError, Result},
 };
 use async_trait::async_trait;
-use futures::{stream, AsyncRead, StreamExt};
+use futures::{stream, AsyncRead};
 
 use serde::{Deserialize, Serialize};
 
@@ -38,13 +43,81 @@ use serde::{Deserialize, Serialize};
 /// `ObjectReader`s are filled with zero bytes.
 pub struct TestObjectStore {
     /// The `(path,size)` of the files that "exist" in the store
-    files: Vec<(String, u64)>,
+    files: Arc<Vec<(String, u64)>>,
+    /// The number of files listed per page
+    page_size: usize,
+    /// The number of calls to `list_file`
+    #[serde(skip)]
+    list_calls: AtomicUsize,
+    /// The number of pages listed
+    #[serde(skip)]
+    page_calls: Arc<AtomicUsize>,
 }
 
 impl TestObjectStore {
+    pub fn new(files: &[(&str, u64)]) -> Self {
+        Self {
+            files: Arc::new(files.iter().map(|f| (f.0.to_owned(), f.1)).collect()),
+            page_size: usize::MAX,
+            list_calls: AtomicUsize::new(0),
+            page_calls: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// List the files by pages of `page_size` files
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
     pub fn new_arc(files: &[(&str, u64)]) -> Arc<dyn ObjectStore> {
-        Arc::new(Self {
-            files: files.iter().map(|f| (f.0.to_owned(), f.1)).collect(),
+        Arc::new(Self::new(files))
+    }
+
+    /// The number of times the files of the store were listed
+    pub fn list_calls(&self) -> usize {
+        self.list_calls.load(Ordering::SeqCst)
+    }
+
+    /// The number of pages of files listed
+    pub fn page_calls(&self) -> usize {
+        self.page_calls.load(Ordering::SeqCst)
+    }
+
+    /// List the page of `files` starting at the index `continuation`
+    async fn list_page(
+        files: Arc<Vec<(String, u64)>>,
+        page_size: usize,
+        page_calls: Arc<AtomicUsize>,
+        prefix: String,
+        continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        page_calls.fetch_add(1, Ordering::SeqCst);
+        let start = match continuation {
+            Some(token) => token.parse::<usize>().map_err(|e| {
+                DataFusionError::Plan(format!("Invalid continuation token: {}", e))
+            })?,
+            None => 0,
+        };
+        let matching = files
+            .iter()
+            .filter(|f| f.0.starts_with(&prefix))
+            .collect::<Vec<_>>();
+        let end = start.saturating_add(page_size).min(matching.len());
+        Ok(FileMetaPage {
+            files: matching[start.min(end)..end]
+                .iter()
+                .map(|f| FileMeta {
+                    sized_file: SizedFile {
+                        path: f.0.clone(),
+                        size: f.1,
+                    },
+                    last_modified: None,
+                })
+                .collect(),
+            continuation: Some(end)
+                .filter(|end| *end < matching.len())
+                .map(|end| end.to_string()),
         })
     }
 }
@@ -53,24 +126,35 @@ impl TestObjectStore {
 #[typetag::serde(name = "test_object_store")]
 impl ObjectStore for TestObjectStore {
     async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        self.list_calls.fetch_add(1, Ordering::SeqCst);
+        let files = Arc::clone(&self.files);
+        let page_size = self.page_size;
+        let page_calls = Arc::clone(&self.page_calls);
         let prefix = prefix.to_owned();
-        Ok(Box::pin(
-            stream::iter(
-                self.files
-                    .clone()
-                    .into_iter()
-                    .filter(move |f| f.0.starts_with(&prefix)),
+        Ok(paginate(move |continuation| {
+            Self::list_page(
+                Arc::clone(&files),
+                page_size,
+                Arc::clone(&page_calls),
+                prefix.clone(),
+                continuation,
             )
-            .map(|f| {
-                Ok(FileMeta {
-                    sized_file: SizedFile {
-                        path: f.0.clone(),
-                        size: f.1,
-                    },
-                    last_modified: None,
-                })
-            }),
-        ))
+        }))
+    }
+
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        Self::list_page(
+            Arc::clone(&self.files),
+            self.page_size,
+            Arc::clone(&self.page_calls),
+            prefix.to_owned(),
+            continuation,
+        )
+        .await
     }
 
     async fn list_dir(
@@ -122,3 +206,111 @@ impl ObjectReader for EmptyObjectReader {
         self.0
     }
 }
+
+/// An object store that holds its files in memory. The files are not
+/// serialized, so deserialized plans must resolve the store registered under
+/// the `mem` scheme.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct InMemoryObjectStore {
+    #[serde(skip)]
+    files: BTreeMap<String, Arc<Vec<u8>>>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new(files: &[(&str, &str)]) -> Self {
+        Self {
+            files: files
+                .iter()
+                .map(|(path, data)| {
+                    (path.to_string(), Arc::new(data.as_bytes().to_vec()))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "in_memory_object_store")]
+impl ObjectStore for InMemoryObjectStore {
+    async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
+        // the files are listed in a single page
+        let page = self.list_file_page(prefix, None).await?;
+        Ok(Box::pin(stream::iter(page.files.into_iter().map(Ok))))
+    }
+
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        _continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        let files = self
+            .files
+            .iter()
+            .filter(|(path, _)| path.starts_with(prefix))
+            .map(|(path, data)| FileMeta {
+                sized_file: SizedFile {
+                    path: path.clone(),
+                    size: data.len() as u64,
+                },
+                last_modified: None,
+            })
+            .collect();
+        Ok(FileMetaPage {
+            files,
+            continuation: None,
+        })
+    }
+
+    async fn list_dir(
+        &self,
+        _prefix: &str,
+        _delimiter: Option<String>,
+    ) -> Result<ListEntryStream> {
+        unimplemented!()
+    }
+
+    fn file_reader(&self, file: SizedFile) -> Result<Arc<dyn ObjectReader>> {
+        match self.files.get(&file.path) {
+            Some(data) => Ok(Arc::new(InMemoryObjectReader(Arc::clone(data)))),
+            None => Err(DataFusionError::IoError(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not in memory", file.path),
+            ))),
+        }
+    }
+
+    fn registry_scheme(&self) -> Option<&str> {
+        Some("mem")
+    }
+}
+
+struct InMemoryObjectReader(Arc<Vec<u8>>);
+
+#[async_trait]
+impl ObjectReader for InMemoryObjectReader {
+    async fn chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn AsyncRead>> {
+        let start = start as usize;
+        Ok(Box::new(futures::io::Cursor::new(
+            self.0[start..start + length].to_vec(),
+        )))
+    }
+
+    fn sync_chunk_reader(
+        &self,
+        start: u64,
+        length: usize,
+    ) -> Result<Box<dyn Read + Send + Sync>> {
+        let start = start as usize;
+        Ok(Box::new(Cursor::new(
+            self.0[start..start + length].to_vec(),
+        )))
+    }
+
+    fn length(&self) -> u64 {
+        self.0.len() as u64
+    }
+}
@@ -19,6 +19,7 @@
 
 use std::{
     any::Any,
+    collections::HashSet,
     fmt::{self, Debug},
     sync::Arc,
 };
@@ -28,12 +29,34 @@ use crate::logical_plan::{DFSchemaRef, Expr, LogicalPlan, UserDefinedLogicalNode
 
 /// Create a new user defined plan node, for testing
 pub fn new(input: LogicalPlan) -> LogicalPlan {
-    let node = Arc::new(TestUserDefinedPlanNode { input });
+    let prevented_columns = input
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.name().clone())
+        .collect();
+    new_with_prevented_columns(input, prevented_columns)
+}
+
+/// Create a new user defined plan node, for testing, through which the
+/// filters on the columns other than `prevented_columns` can be pushed down
+pub fn new_with_prevented_columns(
+    input: LogicalPlan,
+    prevented_columns: HashSet<String>,
+) -> LogicalPlan {
+    let node = Arc::new(TestUserDefinedPlanNode {
+        schema: input.schema().clone(),
+        input,
+        prevented_columns,
+    });
     LogicalPlan::Extension(Extension { node })
 }
 
+/// A node passing its input through, whose schema is fixed when it is created
 struct TestUserDefinedPlanNode {
     input: LogicalPlan,
+    schema: DFSchemaRef,
+    prevented_columns: HashSet<String>,
 }
 
 impl Debug for TestUserDefinedPlanNode {
@@ -52,13 +75,17 @@ impl UserDefinedLogicalNode for TestUserDefinedPlanNode {
     }
 
     fn schema(&self) -> &DFSchemaRef {
-        self.input.schema()
+        &self.schema
     }
 
     fn expressions(&self) -> Vec<Expr> {
         vec![]
     }
 
+    fn prevent_predicate_push_down_columns(&self) -> HashSet<String> {
+        self.prevented_columns.clone()
+    }
+
     fn fmt_for_explain(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "TestUserDefined")
     }
@@ -72,6 +99,8 @@ impl UserDefinedLogicalNode for TestUserDefinedPlanNode {
         assert_eq!(exprs.len(), 0, "expression size inconsistent");
         Arc::new(TestUserDefinedPlanNode {
             input: inputs[0].clone(),
+            schema: self.schema.clone(),
+            prevented_columns: self.prevented_columns.clone(),
         })
     }
 }
@@ -0,0 +1,1251 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! In-process generator of the tables of the [TPC-H](http://www.tpc.org/tpch/)
+//! benchmark, to run it without generating data files first, see
+//! [`register_tpch_tables`].
+//!
+//! The tables have the cardinalities and the value distributions of the
+//! specification at the given scale factor, but they are not identical to
+//! the tables generated by `dbgen`: each value is a function of its column
+//! and of the key of its row only, so that the partitions are generated
+//! independently of each other and the columns which are not projected are
+//! not generated at all. The comments are made of a smaller vocabulary than
+//! the grammar of the specification.
+
+use std::any::Any;
+use std::fmt;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use arrow::array::{
+    ArrayRef, Date32Array, Float64Array, Int32Array, Int64Array, StringArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::Result as ArrowResult;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+
+use crate::datasource::TableProvider;
+use crate::error::{DataFusionError, Result};
+use crate::execution::context::ExecutionContext;
+use crate::execution::task_context::TaskContext;
+use crate::logical_plan::Expr;
+use crate::physical_plan::trace::instrument_stream;
+use crate::physical_plan::{
+    fmt_with_statistics, DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning,
+    RecordBatchStream, SendableRecordBatchStream, Statistics,
+};
+
+/// The names of the eight tables of the TPC-H benchmark
+pub const TPCH_TABLES: &[&str] = &[
+    "part", "supplier", "partsupp", "customer", "orders", "lineitem", "nation", "region",
+];
+
+/// Register the eight tables of the TPC-H benchmark on `ctx`, generated at
+/// the scale factor `scale_factor` in `partitions` partitions
+pub fn register_tpch_tables(
+    ctx: &mut ExecutionContext,
+    scale_factor: f64,
+    partitions: usize,
+) -> Result<()> {
+    for table in TPCH_TABLES {
+        let provider = TpchTable::try_new(table, scale_factor, partitions)?;
+        ctx.register_table(*table, Arc::new(provider))?;
+    }
+    Ok(())
+}
+
+/// The schema of the TPC-H table `table`. As in the benchmarks, the prices
+/// are `Float64`s and the keys are signed integers.
+pub fn tpch_schema(table: &str) -> Result<Schema> {
+    Ok(Table::try_from_name(table)?.schema())
+}
+
+/// A table of the TPC-H benchmark, generated when scanned
+#[derive(Debug)]
+pub struct TpchTable {
+    table: Table,
+    scale_factor: f64,
+    partitions: usize,
+    schema: SchemaRef,
+}
+
+impl TpchTable {
+    /// Create the TPC-H table `table`, generated at the scale factor
+    /// `scale_factor`, e.g. 1 for the lineitem table to have about 6
+    /// million rows, in `partitions` partitions
+    pub fn try_new(table: &str, scale_factor: f64, partitions: usize) -> Result<Self> {
+        if scale_factor.is_nan() || scale_factor <= 0.0 || partitions == 0 {
+            return Err(DataFusionError::Plan(format!(
+                "Invalid TPC-H scale factor {} or number of partitions {}",
+                scale_factor, partitions
+            )));
+        }
+        let table = Table::try_from_name(table)?;
+        Ok(Self {
+            table,
+            scale_factor,
+            partitions,
+            schema: Arc::new(table.schema()),
+        })
+    }
+}
+
+#[async_trait]
+impl TableProvider for TpchTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        _filters: &[Expr],
+        _limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let projection = projection
+            .clone()
+            .unwrap_or_else(|| (0..self.schema.fields().len()).collect());
+        Ok(Arc::new(TpchExec::try_new(
+            self.table,
+            self.scale_factor,
+            self.partitions,
+            projection,
+            batch_size,
+        )?))
+    }
+
+    fn statistics(&self) -> Statistics {
+        Generator::new(self.scale_factor).statistics(self.table)
+    }
+
+    fn version(&self) -> Option<u64> {
+        // the generated data never change
+        Some(0)
+    }
+}
+
+/// Execution plan generating the projected columns of a TPC-H table, each
+/// partition generating the rows of a range of keys
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TpchExec {
+    table: Table,
+    scale_factor: f64,
+    partitions: usize,
+    projection: Vec<usize>,
+    batch_size: usize,
+    /// The schema of the projected columns
+    schema: SchemaRef,
+}
+
+impl TpchExec {
+    /// Create a plan generating the columns `projection` of the TPC-H table
+    /// `table` at the scale factor `scale_factor`, in `partitions` partitions
+    /// of batches of about `batch_size` rows
+    fn try_new(
+        table: Table,
+        scale_factor: f64,
+        partitions: usize,
+        projection: Vec<usize>,
+        batch_size: usize,
+    ) -> Result<Self> {
+        let table_schema = table.schema();
+        let fields = projection
+            .iter()
+            .map(|i| {
+                table_schema.fields().get(*i).cloned().ok_or_else(|| {
+                    DataFusionError::Internal("Projection index out of range".to_string())
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let schema = Arc::new(Schema::new(fields));
+        Ok(Self {
+            table,
+            scale_factor,
+            partitions,
+            projection,
+            batch_size,
+            schema,
+        })
+    }
+}
+
+impl LambdaExecPlan for TpchExec {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
+        Err(DataFusionError::Plan(
+            "TpchExec has no input to feed".to_owned(),
+        ))
+    }
+}
+
+#[async_trait]
+#[typetag::serde(name = "tpch_exec")]
+impl ExecutionPlan for TpchExec {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    fn output_partitioning(&self) -> Partitioning {
+        Partitioning::UnknownPartitioning(self.partitions)
+    }
+
+    fn children(&self) -> Vec<Arc<dyn ExecutionPlan>> {
+        vec![]
+    }
+
+    fn with_new_children(
+        &self,
+        children: Vec<Arc<dyn ExecutionPlan>>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        if children.is_empty() {
+            Ok(Arc::new(Self::try_new(
+                self.table,
+                self.scale_factor,
+                self.partitions,
+                self.projection.clone(),
+                self.batch_size,
+            )?))
+        } else {
+            Err(DataFusionError::Internal(format!(
+                "Children cannot be replaced in {:?}",
+                self
+            )))
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            name = "TpchExec",
+            skip_all,
+            fields(partition = partition),
+            err
+        )
+    )]
+    async fn execute(
+        &self,
+        partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
+        if partition >= self.partitions {
+            return Err(DataFusionError::Internal(format!(
+                "TpchExec invalid partition {} (expected less than {})",
+                partition, self.partitions
+            )));
+        }
+        let generator = Generator::new(self.scale_factor);
+        let rows = generator.rows(self.table);
+        let start = rows * partition as i64 / self.partitions as i64;
+        let end = rows * (partition as i64 + 1) / self.partitions as i64;
+        Ok(instrument_stream(
+            self,
+            partition,
+            Box::pin(TpchStream {
+                generator,
+                table: self.table,
+                projection: self.projection.clone(),
+                schema: self.schema.clone(),
+                rows: start..end,
+                batch_rows: self.table.batch_rows(self.batch_size),
+            }),
+        ))
+    }
+
+    fn fmt_as(&self, t: DisplayFormatType, f: &mut fmt::Formatter) -> fmt::Result {
+        match t {
+            DisplayFormatType::Default => {
+                let columns = self
+                    .schema
+                    .fields()
+                    .iter()
+                    .map(|field| field.name().as_str())
+                    .collect::<Vec<_>>();
+                write!(
+                    f,
+                    "TpchExec: table={}, scale_factor={}, partitions={}, projection=[{}]",
+                    self.table.name(),
+                    self.scale_factor,
+                    self.partitions,
+                    columns.join(", ")
+                )
+            }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
+        }
+    }
+
+    fn statistics(&self) -> Statistics {
+        let statistics = Generator::new(self.scale_factor).statistics(self.table);
+        Statistics {
+            total_byte_size: None,
+            ..statistics
+        }
+    }
+}
+
+/// Stream generating the batches of a partition of a TPC-H table
+struct TpchStream {
+    generator: Generator,
+    table: Table,
+    projection: Vec<usize>,
+    schema: SchemaRef,
+    /// The rows of the partition which are not generated yet
+    rows: Range<i64>,
+    /// The number of rows generated per batch
+    batch_rows: i64,
+}
+
+impl Stream for TpchStream {
+    type Item = ArrowResult<RecordBatch>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        if self.rows.is_empty() {
+            return Poll::Ready(None);
+        }
+        let end = self.rows.end.min(self.rows.start + self.batch_rows);
+        let rows = self.rows.start..end;
+        self.rows.start = end;
+
+        let columns = self
+            .projection
+            .iter()
+            .map(|column| self.generator.column(self.table, *column, rows.clone()))
+            .collect();
+        Poll::Ready(Some(RecordBatch::try_new(self.schema.clone(), columns)))
+    }
+}
+
+impl RecordBatchStream for TpchStream {
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+}
+
+/// The tables of the TPC-H benchmark
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Table {
+    Part,
+    Supplier,
+    PartSupp,
+    Customer,
+    Orders,
+    LineItem,
+    Nation,
+    Region,
+}
+
+impl Table {
+    fn try_from_name(name: &str) -> Result<Self> {
+        match name {
+            "part" => Ok(Table::Part),
+            "supplier" => Ok(Table::Supplier),
+            "partsupp" => Ok(Table::PartSupp),
+            "customer" => Ok(Table::Customer),
+            "orders" => Ok(Table::Orders),
+            "lineitem" => Ok(Table::LineItem),
+            "nation" => Ok(Table::Nation),
+            "region" => Ok(Table::Region),
+            _ => Err(DataFusionError::Plan(format!(
+                "There is no TPC-H table named '{}'",
+                name
+            ))),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Table::Part => "part",
+            Table::Supplier => "supplier",
+            Table::PartSupp => "partsupp",
+            Table::Customer => "customer",
+            Table::Orders => "orders",
+            Table::LineItem => "lineitem",
+            Table::Nation => "nation",
+            Table::Region => "region",
+        }
+    }
+
+    /// The number of rows of the table generated per row of the partitioned
+    /// keys: the parts of the partsupp table and the orders of the lineitem
+    /// table have several rows
+    fn rows_per_key(&self) -> i64 {
+        match self {
+            Table::PartSupp | Table::LineItem => 4,
+            _ => 1,
+        }
+    }
+
+    /// The number of keys generated per batch, for the batches to have
+    /// about `batch_size` rows
+    fn batch_rows(&self, batch_size: usize) -> i64 {
+        (batch_size as i64 / self.rows_per_key()).max(1)
+    }
+
+    fn schema(&self) -> Schema {
+        let fields = match self {
+            Table::Part => vec![
+                Field::new("p_partkey", DataType::Int64, false),
+                Field::new("p_name", DataType::Utf8, false),
+                Field::new("p_mfgr", DataType::Utf8, false),
+                Field::new("p_brand", DataType::Utf8, false),
+                Field::new("p_type", DataType::Utf8, false),
+                Field::new("p_size", DataType::Int32, false),
+                Field::new("p_container", DataType::Utf8, false),
+                Field::new("p_retailprice", DataType::Float64, false),
+                Field::new("p_comment", DataType::Utf8, false),
+            ],
+            Table::Supplier => vec![
+                Field::new("s_suppkey", DataType::Int64, false),
+                Field::new("s_name", DataType::Utf8, false),
+                Field::new("s_address", DataType::Utf8, false),
+                Field::new("s_nationkey", DataType::Int64, false),
+                Field::new("s_phone", DataType::Utf8, false),
+                Field::new("s_acctbal", DataType::Float64, false),
+                Field::new("s_comment", DataType::Utf8, false),
+            ],
+            Table::PartSupp => vec![
+                Field::new("ps_partkey", DataType::Int64, false),
+                Field::new("ps_suppkey", DataType::Int64, false),
+                Field::new("ps_availqty", DataType::Int32, false),
+                Field::new("ps_supplycost", DataType::Float64, false),
+                Field::new("ps_comment", DataType::Utf8, false),
+            ],
+            Table::Customer => vec![
+                Field::new("c_custkey", DataType::Int64, false),
+                Field::new("c_name", DataType::Utf8, false),
+                Field::new("c_address", DataType::Utf8, false),
+                Field::new("c_nationkey", DataType::Int64, false),
+                Field::new("c_phone", DataType::Utf8, false),
+                Field::new("c_acctbal", DataType::Float64, false),
+                Field::new("c_mktsegment", DataType::Utf8, false),
+                Field::new("c_comment", DataType::Utf8, false),
+            ],
+            Table::Orders => vec![
+                Field::new("o_orderkey", DataType::Int64, false),
+                Field::new("o_custkey", DataType::Int64, false),
+                Field::new("o_orderstatus", DataType::Utf8, false),
+                Field::new("o_totalprice", DataType::Float64, false),
+                Field::new("o_orderdate", DataType::Date32, false),
+                Field::new("o_orderpriority", DataType::Utf8, false),
+                Field::new("o_clerk", DataType::Utf8, false),
+                Field::new("o_shippriority", DataType::Int32, false),
+                Field::new("o_comment", DataType::Utf8, false),
+            ],
+            Table::LineItem => vec![
+                Field::new("l_orderkey", DataType::Int64, false),
+                Field::new("l_partkey", DataType::Int64, false),
+                Field::new("l_suppkey", DataType::Int64, false),
+                Field::new("l_linenumber", DataType::Int32, false),
+                Field::new("l_quantity", DataType::Float64, false),
+                Field::new("l_extendedprice", DataType::Float64, false),
+                Field::new("l_discount", DataType::Float64, false),
+                Field::new("l_tax", DataType::Float64, false),
+                Field::new("l_returnflag", DataType::Utf8, false),
+                Field::new("l_linestatus", DataType::Utf8, false),
+                Field::new("l_shipdate", DataType::Date32, false),
+                Field::new("l_commitdate", DataType::Date32, false),
+                Field::new("l_receiptdate", DataType::Date32, false),
+                Field::new("l_shipinstruct", DataType::Utf8, false),
+                Field::new("l_shipmode", DataType::Utf8, false),
+                Field::new("l_comment", DataType::Utf8, false),
+            ],
+            Table::Nation => vec![
+                Field::new("n_nationkey", DataType::Int64, false),
+                Field::new("n_name", DataType::Utf8, false),
+                Field::new("n_regionkey", DataType::Int64, false),
+                Field::new("n_comment", DataType::Utf8, false),
+            ],
+            Table::Region => vec![
+                Field::new("r_regionkey", DataType::Int64, false),
+                Field::new("r_name", DataType::Utf8, false),
+                Field::new("r_comment", DataType::Utf8, false),
+            ],
+        };
+        Schema::new(fields)
+    }
+}
+
+/// 1992-01-01, the first order date, in days since the UNIX epoch
+const START_DATE: i32 = 8035;
+/// 1995-06-17, the date the line items are shipped or returned against
+const CURRENT_DATE: i32 = 9298;
+/// 1998-12-31, the last receipt date
+const END_DATE: i32 = 10591;
+
+const NATIONS: &[(&str, i64)] = &[
+    ("ALGERIA", 0),
+    ("ARGENTINA", 1),
+    ("BRAZIL", 1),
+    ("CANADA", 1),
+    ("EGYPT", 4),
+    ("ETHIOPIA", 0),
+    ("FRANCE", 3),
+    ("GERMANY", 3),
+    ("INDIA", 2),
+    ("INDONESIA", 2),
+    ("IRAN", 4),
+    ("IRAQ", 4),
+    ("JAPAN", 2),
+    ("JORDAN", 4),
+    ("KENYA", 0),
+    ("MOROCCO", 0),
+    ("MOZAMBIQUE", 0),
+    ("PERU", 1),
+    ("CHINA", 2),
+    ("ROMANIA", 3),
+    ("SAUDI ARABIA", 4),
+    ("VIETNAM", 2),
+    ("RUSSIA", 3),
+    ("UNITED KINGDOM", 3),
+    ("UNITED STATES", 1),
+];
+const REGIONS: &[&str] = &["AFRICA", "AMERICA", "ASIA", "EUROPE", "MIDDLE EAST"];
+const SEGMENTS: &[&str] = &[
+    "AUTOMOBILE",
+    "BUILDING",
+    "FURNITURE",
+    "MACHINERY",
+    "HOUSEHOLD",
+];
+const PRIORITIES: &[&str] =
+    &["1-URGENT", "2-HIGH", "3-MEDIUM", "4-NOT SPECIFIED", "5-LOW"];
+const INSTRUCTIONS: &[&str] = &[
+    "DELIVER IN PERSON",
+    "COLLECT COD",
+    "NONE",
+    "TAKE BACK RETURN",
+];
+const MODES: &[&str] = &["REG AIR", "AIR", "RAIL", "SHIP", "TRUCK", "MAIL", "FOB"];
+const TYPES: [&[&str]; 3] = [
+    &["STANDARD", "SMALL", "MEDIUM", "LARGE", "ECONOMY", "PROMO"],
+    &["ANODIZED", "BURNISHED", "PLATED", "POLISHED", "BRUSHED"],
+    &["TIN", "NICKEL", "BRASS", "STEEL", "COPPER"],
+];
+const CONTAINERS: [&[&str]; 2] = [
+    &["SM", "LG", "MED", "JUMBO", "WRAP"],
+    &["CASE", "BOX", "BAG", "JAR", "PKG", "PACK", "CAN", "DRUM"],
+];
+const COLORS: &[&str] = &[
+    "almond",
+    "antique",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanched",
+    "blue",
+    "blush",
+    "brown",
+    "burlywood",
+    "burnished",
+    "chartreuse",
+    "chiffon",
+    "chocolate",
+    "coral",
+    "cornflower",
+    "cornsilk",
+    "cream",
+    "cyan",
+    "dark",
+    "deep",
+    "dim",
+    "dodger",
+    "drab",
+    "firebrick",
+    "floral",
+    "forest",
+    "frosted",
+    "gainsboro",
+    "ghost",
+    "goldenrod",
+    "green",
+    "grey",
+    "honeydew",
+    "hot",
+    "indian",
+    "ivory",
+    "khaki",
+    "lace",
+    "lavender",
+    "lawn",
+    "lemon",
+    "light",
+    "lime",
+    "linen",
+    "magenta",
+    "maroon",
+    "medium",
+    "metallic",
+    "midnight",
+    "mint",
+    "misty",
+    "moccasin",
+    "navajo",
+    "navy",
+    "olive",
+    "orange",
+    "orchid",
+    "pale",
+    "papaya",
+    "peach",
+    "peru",
+    "pink",
+    "plum",
+    "powder",
+    "puff",
+    "purple",
+    "red",
+    "rose",
+    "rosy",
+    "royal",
+    "saddle",
+    "salmon",
+    "sandy",
+    "seashell",
+    "sienna",
+    "sky",
+    "slate",
+    "smoke",
+    "snow",
+    "spring",
+    "steel",
+    "tan",
+    "thistle",
+    "tomato",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "yellow",
+];
+const WORDS: &[&str] = &[
+    "furiously",
+    "quickly",
+    "carefully",
+    "blithely",
+    "slyly",
+    "fluffily",
+    "ironic",
+    "final",
+    "regular",
+    "express",
+    "pending",
+    "special",
+    "bold",
+    "even",
+    "silent",
+    "unusual",
+    "packages",
+    "requests",
+    "accounts",
+    "deposits",
+    "foxes",
+    "ideas",
+    "theodolites",
+    "pinto",
+    "beans",
+    "instructions",
+    "dependencies",
+    "excuses",
+    "platelets",
+    "asymptotes",
+    "courts",
+    "dolphins",
+    "sleep",
+    "wake",
+    "are",
+    "haggle",
+    "nag",
+    "use",
+    "boost",
+    "affix",
+    "detect",
+    "integrate",
+    "cajole",
+    "among",
+    "about",
+    "above",
+    "after",
+    "against",
+    "along",
+    "the",
+    "of",
+    "to",
+];
+const ALPHANUMERIC: &[u8] =
+    b"0123456789abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ,";
+
+/// The sequences of pseudo-random numbers, one per generated attribute
+#[derive(Clone, Copy)]
+enum Sequence {
+    PartName,
+    PartManufacturer,
+    PartBrand,
+    PartType,
+    PartSize,
+    PartContainer,
+    PartComment,
+    SupplierAddress,
+    SupplierNation,
+    SupplierPhone,
+    SupplierBalance,
+    SupplierComment,
+    PartSuppQuantity,
+    PartSuppCost,
+    PartSuppComment,
+    CustomerAddress,
+    CustomerNation,
+    CustomerPhone,
+    CustomerBalance,
+    CustomerSegment,
+    CustomerComment,
+    OrderCustomer,
+    OrderDate,
+    OrderLines,
+    OrderPriority,
+    OrderClerk,
+    OrderComment,
+    LinePart,
+    LineSupplier,
+    LineQuantity,
+    LineDiscount,
+    LineTax,
+    LineShipDate,
+    LineCommitDate,
+    LineReceiptDate,
+    LineReturnFlag,
+    LineInstruction,
+    LineMode,
+    LineComment,
+    NationComment,
+    RegionComment,
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+/// The `i`-th pseudo-random number of `sequence` for the row of key `key`
+fn random(sequence: Sequence, key: i64, i: u64) -> u64 {
+    splitmix64(splitmix64(((sequence as u64) << 32) | i) ^ key as u64)
+}
+
+/// The `i`-th pseudo-random number of `sequence` for the row of key `key`,
+/// uniformly distributed between `low` and `high` included
+fn uniform_at(sequence: Sequence, key: i64, i: u64, low: i64, high: i64) -> i64 {
+    low + (random(sequence, key, i) % (high - low + 1) as u64) as i64
+}
+
+fn uniform(sequence: Sequence, key: i64, low: i64, high: i64) -> i64 {
+    uniform_at(sequence, key, 0, low, high)
+}
+
+fn pick(sequence: Sequence, key: i64, i: u64, values: &[&'static str]) -> &'static str {
+    values[uniform_at(sequence, key, i, 0, values.len() as i64 - 1) as usize]
+}
+
+/// An amount of money between `low` and `high` cents included
+fn money(sequence: Sequence, key: i64, low: i64, high: i64) -> f64 {
+    uniform(sequence, key, low, high) as f64 / 100.0
+}
+
+/// Words of [`WORDS`] of `min_len` to `max_len` characters in total
+fn text(sequence: Sequence, key: i64, min_len: i64, max_len: i64) -> String {
+    let len = uniform(sequence, key, min_len, max_len) as usize;
+    let mut text = String::with_capacity(len + 12);
+    let mut i = 1;
+    while text.len() < len {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(pick(sequence, key, i, WORDS));
+        i += 1;
+    }
+    text.truncate(len);
+    text
+}
+
+/// Random characters, `min_len` to `max_len` of them
+fn address(sequence: Sequence, key: i64, min_len: i64, max_len: i64) -> String {
+    let len = uniform(sequence, key, min_len, max_len) as u64;
+    (1..=len)
+        .map(|i| {
+            let c = uniform_at(sequence, key, i, 0, ALPHANUMERIC.len() as i64 - 1);
+            ALPHANUMERIC[c as usize] as char
+        })
+        .collect()
+}
+
+/// A phone number whose country code is derived from the nation
+fn phone(sequence: Sequence, key: i64, nation: i64) -> String {
+    format!(
+        "{}-{}-{}-{}",
+        nation + 10,
+        uniform_at(sequence, key, 0, 100, 999),
+        uniform_at(sequence, key, 1, 100, 999),
+        uniform_at(sequence, key, 2, 1000, 9999)
+    )
+}
+
+fn int64(values: impl Iterator<Item = i64>) -> ArrayRef {
+    Arc::new(Int64Array::from_iter_values(values))
+}
+
+fn int32(values: impl Iterator<Item = i32>) -> ArrayRef {
+    Arc::new(Int32Array::from_iter_values(values))
+}
+
+fn float64(values: impl Iterator<Item = f64>) -> ArrayRef {
+    Arc::new(Float64Array::from_iter_values(values))
+}
+
+fn date32(values: impl Iterator<Item = i32>) -> ArrayRef {
+    Arc::new(Date32Array::from_iter_values(values))
+}
+
+fn utf8<S: AsRef<str>>(values: impl Iterator<Item = S>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+/// The key of the line item `line`, numbered from 1, of the order `order`
+fn line_key(order: i64, line: i64) -> i64 {
+    order * 8 + line
+}
+
+/// Generates the values of the tables at a scale factor, by key
+#[derive(Debug, Clone, Copy)]
+struct Generator {
+    parts: i64,
+    suppliers: i64,
+    customers: i64,
+    orders: i64,
+    clerks: i64,
+}
+
+impl Generator {
+    fn new(scale_factor: f64) -> Self {
+        let scaled = |rows: f64| ((rows * scale_factor).round() as i64).max(1);
+        Self {
+            parts: scaled(200_000.0),
+            suppliers: scaled(10_000.0),
+            customers: scaled(150_000.0),
+            orders: scaled(1_500_000.0),
+            clerks: scaled(1_000.0),
+        }
+    }
+
+    /// The number of keys of `table`, which are partitioned
+    fn rows(&self, table: Table) -> i64 {
+        match table {
+            Table::Part | Table::PartSupp => self.parts,
+            Table::Supplier => self.suppliers,
+            Table::Customer => self.customers,
+            Table::Orders | Table::LineItem => self.orders,
+            Table::Nation => NATIONS.len() as i64,
+            Table::Region => REGIONS.len() as i64,
+        }
+    }
+
+    fn statistics(&self, table: Table) -> Statistics {
+        let num_rows = match table {
+            // the number of line items per order is random
+            Table::LineItem => None,
+            _ => Some((self.rows(table) * table.rows_per_key()) as usize),
+        };
+        Statistics {
+            num_rows,
+            total_byte_size: None,
+            column_statistics: None,
+            is_exact: true,
+        }
+    }
+
+    /// The column `column` of `table` for the keys `rows`
+    fn column(&self, table: Table, column: usize, rows: Range<i64>) -> ArrayRef {
+        match table {
+            Table::Part => self.part_column(column, rows.start + 1..rows.end + 1),
+            Table::Supplier => self.supplier_column(column, rows.start + 1..rows.end + 1),
+            Table::PartSupp => {
+                let keys = (rows.start + 1..rows.end + 1)
+                    .flat_map(|part| (0..4).map(move |i| (part, i)))
+                    .collect();
+                self.partsupp_column(column, keys)
+            }
+            Table::Customer => self.customer_column(column, rows.start + 1..rows.end + 1),
+            Table::Orders => self.orders_column(column, rows.map(order_key).collect()),
+            Table::LineItem => {
+                let keys = rows
+                    .map(order_key)
+                    .flat_map(|order| {
+                        (1..=self.order_lines(order)).map(move |line| (order, line))
+                    })
+                    .collect();
+                self.lineitem_column(column, keys)
+            }
+            Table::Nation => nation_column(column, rows),
+            Table::Region => region_column(column, rows),
+        }
+    }
+
+    fn part_column(&self, column: usize, keys: Range<i64>) -> ArrayRef {
+        use Sequence::*;
+        match column {
+            0 => int64(keys),
+            1 => utf8(keys.map(|key| {
+                let mut words: Vec<&str> = Vec::with_capacity(5);
+                let mut i = 0;
+                while words.len() < 5 {
+                    let word = pick(PartName, key, i, COLORS);
+                    if !words.contains(&word) {
+                        words.push(word);
+                    }
+                    i += 1;
+                }
+                words.join(" ")
+            })),
+            2 => utf8(keys.map(|key| {
+                format!("Manufacturer#{}", uniform(PartManufacturer, key, 1, 5))
+            })),
+            3 => utf8(keys.map(|key| {
+                format!(
+                    "Brand#{}{}",
+                    uniform(PartManufacturer, key, 1, 5),
+                    uniform(PartBrand, key, 1, 5)
+                )
+            })),
+            4 => utf8(keys.map(|key| {
+                let syllables = (0..3).map(|i| pick(PartType, key, i, TYPES[i as usize]));
+                syllables.collect::<Vec<_>>().join(" ")
+            })),
+            5 => int32(keys.map(|key| uniform(PartSize, key, 1, 50) as i32)),
+            6 => utf8(keys.map(|key| {
+                let syllables =
+                    (0..2).map(|i| pick(PartContainer, key, i, CONTAINERS[i as usize]));
+                syllables.collect::<Vec<_>>().join(" ")
+            })),
+            7 => float64(keys.map(|key| retail_price_cents(key) as f64 / 100.0)),
+            _ => utf8(keys.map(|key| text(PartComment, key, 5, 22))),
+        }
+    }
+
+    fn supplier_column(&self, column: usize, keys: Range<i64>) -> ArrayRef {
+        use Sequence::*;
+        let nation = |key| uniform(SupplierNation, key, 0, NATIONS.len() as i64 - 1);
+        match column {
+            0 => int64(keys),
+            1 => utf8(keys.map(|key| format!("Supplier#{:09}", key))),
+            2 => utf8(keys.map(|key| address(SupplierAddress, key, 10, 40))),
+            3 => int64(keys.map(nation)),
+            4 => utf8(keys.map(|key| phone(SupplierPhone, key, nation(key)))),
+            5 => float64(keys.map(|key| money(SupplierBalance, key, -99_999, 999_999))),
+            _ => utf8(keys.map(|key| text(SupplierComment, key, 25, 100))),
+        }
+    }
+
+    fn partsupp_column(&self, column: usize, keys: Vec<(i64, i64)>) -> ArrayRef {
+        use Sequence::*;
+        let keys = keys.into_iter();
+        match column {
+            0 => int64(keys.map(|(part, _)| part)),
+            1 => int64(keys.map(|(part, i)| self.part_supplier(part, i))),
+            2 => int32(keys.map(|(part, i)| {
+                uniform(PartSuppQuantity, part * 4 + i, 1, 9999) as i32
+            })),
+            3 => float64(
+                keys.map(|(part, i)| money(PartSuppCost, part * 4 + i, 100, 100_000)),
+            ),
+            _ => utf8(keys.map(|(part, i)| text(PartSuppComment, part * 4 + i, 49, 198))),
+        }
+    }
+
+    fn customer_column(&self, column: usize, keys: Range<i64>) -> ArrayRef {
+        use Sequence::*;
+        let nation = |key| uniform(CustomerNation, key, 0, NATIONS.len() as i64 - 1);
+        match column {
+            0 => int64(keys),
+            1 => utf8(keys.map(|key| format!("Customer#{:09}", key))),
+            2 => utf8(keys.map(|key| address(CustomerAddress, key, 10, 40))),
+            3 => int64(keys.map(nation)),
+            4 => utf8(keys.map(|key| phone(CustomerPhone, key, nation(key)))),
+            5 => float64(keys.map(|key| money(CustomerBalance, key, -99_999, 999_999))),
+            6 => utf8(keys.map(|key| pick(CustomerSegment, key, 0, SEGMENTS))),
+            _ => utf8(keys.map(|key| text(CustomerComment, key, 29, 116))),
+        }
+    }
+
+    fn orders_column(&self, column: usize, keys: Vec<i64>) -> ArrayRef {
+        use Sequence::*;
+        let keys = keys.into_iter();
+        match column {
+            0 => int64(keys),
+            1 => int64(keys.map(|key| {
+                // a third of the customers have no orders
+                (0..)
+                    .map(|i| uniform_at(OrderCustomer, key, i, 1, self.customers))
+                    .find(|customer| customer % 3 != 0 || self.customers < 3)
+                    .unwrap()
+            })),
+            2 => utf8(keys.map(|key| {
+                let lines = 1..=self.order_lines(key);
+                let shipped = lines
+                    .map(|line| self.ship_date(key, line_key(key, line)) <= CURRENT_DATE)
+                    .collect::<Vec<_>>();
+                if shipped.iter().all(|shipped| *shipped) {
+                    "F"
+                } else if shipped.iter().all(|shipped| !*shipped) {
+                    "O"
+                } else {
+                    "P"
+                }
+            })),
+            3 => float64(keys.map(|key| {
+                let total = (1..=self.order_lines(key))
+                    .map(|line| {
+                        let key = line_key(key, line);
+                        self.extended_price(key)
+                            * (1.0 + tax(key))
+                            * (1.0 - discount(key))
+                    })
+                    .sum::<f64>();
+                (total * 100.0).round() / 100.0
+            })),
+            4 => date32(keys.map(order_date)),
+            5 => utf8(keys.map(|key| pick(OrderPriority, key, 0, PRIORITIES))),
+            6 => utf8(keys.map(|key| {
+                format!("Clerk#{:09}", uniform(OrderClerk, key, 1, self.clerks))
+            })),
+            7 => int32(keys.map(|_| 0)),
+            _ => utf8(keys.map(|key| text(OrderComment, key, 19, 78))),
+        }
+    }
+
+    fn lineitem_column(&self, column: usize, keys: Vec<(i64, i64)>) -> ArrayRef {
+        use Sequence::*;
+        let keys = keys
+            .into_iter()
+            .map(|(order, line)| (order, line, line_key(order, line)));
+        match column {
+            0 => int64(keys.map(|(order, _, _)| order)),
+            1 => int64(keys.map(|(_, _, key)| self.line_part(key))),
+            2 => int64(keys.map(|(_, _, key)| {
+                let i = uniform(LineSupplier, key, 0, 3);
+                self.part_supplier(self.line_part(key), i)
+            })),
+            3 => int32(keys.map(|(_, line, _)| line as i32)),
+            4 => float64(keys.map(|(_, _, key)| quantity(key) as f64)),
+            5 => float64(keys.map(|(_, _, key)| self.extended_price(key))),
+            6 => float64(keys.map(|(_, _, key)| discount(key))),
+            7 => float64(keys.map(|(_, _, key)| tax(key))),
+            8 => utf8(keys.map(|(order, _, key)| {
+                if self.receipt_date(order, key) <= CURRENT_DATE {
+                    pick(LineReturnFlag, key, 0, &["R", "A"])
+                } else {
+                    "N"
+                }
+            })),
+            9 => utf8(keys.map(|(order, _, key)| {
+                if self.ship_date(order, key) > CURRENT_DATE {
+                    "O"
+                } else {
+                    "F"
+                }
+            })),
+            10 => date32(keys.map(|(order, _, key)| self.ship_date(order, key))),
+            11 => date32(keys.map(|(order, _, key)| {
+                order_date(order) + uniform(LineCommitDate, key, 30, 90) as i32
+            })),
+            12 => date32(keys.map(|(order, _, key)| self.receipt_date(order, key))),
+            13 => {
+                utf8(keys.map(|(_, _, key)| pick(LineInstruction, key, 0, INSTRUCTIONS)))
+            }
+            14 => utf8(keys.map(|(_, _, key)| pick(LineMode, key, 0, MODES))),
+            _ => utf8(keys.map(|(_, _, key)| text(LineComment, key, 10, 43))),
+        }
+    }
+
+    /// The `i`-th of the four suppliers of the part `part`
+    fn part_supplier(&self, part: i64, i: i64) -> i64 {
+        let suppliers = self.suppliers;
+        (part + i * (suppliers / 4 + (part - 1) / suppliers)) % suppliers + 1
+    }
+
+    fn order_lines(&self, order: i64) -> i64 {
+        uniform(Sequence::OrderLines, order, 1, 7)
+    }
+
+    fn line_part(&self, line: i64) -> i64 {
+        uniform(Sequence::LinePart, line, 1, self.parts)
+    }
+
+    fn extended_price(&self, line: i64) -> f64 {
+        (quantity(line) * retail_price_cents(self.line_part(line))) as f64 / 100.0
+    }
+
+    fn ship_date(&self, order: i64, line: i64) -> i32 {
+        order_date(order) + uniform(Sequence::LineShipDate, line, 1, 121) as i32
+    }
+
+    fn receipt_date(&self, order: i64, line: i64) -> i32 {
+        self.ship_date(order, line)
+            + uniform(Sequence::LineReceiptDate, line, 1, 30) as i32
+    }
+}
+
+/// The key of the order `index`: only the first 8 of every 32 keys are used
+fn order_key(index: i64) -> i64 {
+    index / 8 * 32 + index % 8 + 1
+}
+
+fn order_date(order: i64) -> i32 {
+    // the last order is received 151 days before the last receipt
+    uniform(
+        Sequence::OrderDate,
+        order,
+        START_DATE as i64,
+        END_DATE as i64 - 151,
+    ) as i32
+}
+
+fn retail_price_cents(part: i64) -> i64 {
+    90_000 + (part / 10) % 20_001 + 100 * (part % 1_000)
+}
+
+fn quantity(line: i64) -> i64 {
+    uniform(Sequence::LineQuantity, line, 1, 50)
+}
+
+fn discount(line: i64) -> f64 {
+    uniform(Sequence::LineDiscount, line, 0, 10) as f64 / 100.0
+}
+
+fn tax(line: i64) -> f64 {
+    uniform(Sequence::LineTax, line, 0, 8) as f64 / 100.0
+}
+
+fn nation_column(column: usize, keys: Range<i64>) -> ArrayRef {
+    match column {
+        0 => int64(keys),
+        1 => utf8(keys.map(|key| NATIONS[key as usize].0)),
+        2 => int64(keys.map(|key| NATIONS[key as usize].1)),
+        _ => utf8(keys.map(|key| text(Sequence::NationComment, key, 31, 114))),
+    }
+}
+
+fn region_column(column: usize, keys: Range<i64>) -> ArrayRef {
+    match column {
+        0 => int64(keys),
+        1 => utf8(keys.map(|key| REGIONS[key as usize])),
+        _ => utf8(keys.map(|key| text(Sequence::RegionComment, key, 31, 115))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::physical_plan::collect;
+
+    async fn generate(
+        table: &str,
+        partitions: usize,
+        projection: Option<Vec<usize>>,
+    ) -> Result<Vec<RecordBatch>> {
+        let table = TpchTable::try_new(table, 0.01, partitions)?;
+        let plan = table.scan(&projection, 1000, &[], None).await?;
+        let task_ctx = ExecutionContext::new().task_ctx();
+        collect(plan, task_ctx).await
+    }
+
+    fn num_rows(batches: &[RecordBatch]) -> usize {
+        batches.iter().map(|batch| batch.num_rows()).sum()
+    }
+
+    #[tokio::test]
+    async fn cardinalities() -> Result<()> {
+        for (table, rows) in [
+            ("part", 2000),
+            ("supplier", 100),
+            ("partsupp", 8000),
+            ("customer", 1500),
+            ("orders", 15000),
+            ("nation", 25),
+            ("region", 5),
+        ] {
+            let batches = generate(table, 3, None).await?;
+            assert_eq!(rows, num_rows(&batches), "{}", table);
+            assert_eq!(Arc::new(tpch_schema(table)?), batches[0].schema());
+        }
+        let lines = num_rows(&generate("lineitem", 3, Some(vec![0])).await?);
+        assert!((55_000..65_000).contains(&lines), "{}", lines);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn independent_of_partitions_and_projection() -> Result<()> {
+        let all = generate("lineitem", 1, None).await?;
+        let partitioned = generate("lineitem", 4, Some(vec![10, 5])).await?;
+        let projected = all
+            .iter()
+            .map(|batch| {
+                RecordBatch::try_new(
+                    partitioned[0].schema(),
+                    vec![batch.column(10).clone(), batch.column(5).clone()],
+                )
+            })
+            .collect::<ArrowResult<Vec<_>>>()?;
+        assert_eq!(
+            arrow::util::pretty::pretty_format_batches(&projected)?.to_string(),
+            arrow::util::pretty::pretty_format_batches(&partitioned)?.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_tables() {
+        assert!(TpchTable::try_new("lineitems", 1.0, 1).is_err());
+        assert!(TpchTable::try_new("lineitem", 0.0, 1).is_err());
+        assert!(TpchTable::try_new("lineitem", 1.0, 0).is_err());
+    }
+}
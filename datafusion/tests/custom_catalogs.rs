@@ -0,0 +1,209 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+
+use datafusion::catalog::catalog::{CatalogProvider, MemoryCatalogProvider};
+use datafusion::catalog::schema::SchemaProvider;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::error::{DataFusionError, Result};
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
+use datafusion::{assert_batches_eq, assert_batches_sorted_eq};
+
+/// A schema whose tables are only materialized the first time they are
+/// looked up, like the tables of a remote metastore
+struct LazySchemaProvider {
+    /// The values of the column `v` of each table
+    definitions: HashMap<String, Vec<i32>>,
+    materialized: Mutex<HashMap<String, Arc<dyn TableProvider>>>,
+}
+
+impl LazySchemaProvider {
+    fn new(definitions: &[(&str, Vec<i32>)]) -> Self {
+        Self {
+            definitions: definitions
+                .iter()
+                .map(|(name, values)| (name.to_string(), values.clone()))
+                .collect(),
+            materialized: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn materialized(&self) -> Vec<String> {
+        let mut names: Vec<String> =
+            self.materialized.lock().unwrap().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+impl SchemaProvider for LazySchemaProvider {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn table_names(&self) -> Vec<String> {
+        self.definitions.keys().cloned().collect()
+    }
+
+    fn table(&self, name: &str) -> Option<Arc<dyn TableProvider>> {
+        let values = self.definitions.get(name)?;
+        let mut materialized = self.materialized.lock().unwrap();
+        let table = materialized.entry(name.to_owned()).or_insert_with(|| {
+            let schema =
+                Arc::new(Schema::new(vec![Field::new("v", DataType::Int32, false)]));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![Arc::new(Int32Array::from(values.clone()))],
+            )
+            .unwrap();
+            Arc::new(MemTable::try_new(schema, vec![vec![batch]]).unwrap())
+        });
+        Some(table.clone())
+    }
+
+    fn table_exist(&self, name: &str) -> bool {
+        self.definitions.contains_key(name)
+    }
+}
+
+/// A context whose default catalog and schema are `remote.warehouse`, a
+/// [`LazySchemaProvider`]
+fn remote_context() -> Result<(ExecutionContext, Arc<LazySchemaProvider>)> {
+    let ctx = ExecutionContext::with_config(
+        ExecutionConfig::new()
+            .with_information_schema(true)
+            .create_default_catalog_and_schema(false)
+            .with_default_catalog_and_schema("remote", "warehouse"),
+    );
+    let schema = Arc::new(LazySchemaProvider::new(&[
+        ("orders", vec![1, 2, 3]),
+        ("customers", vec![10, 20]),
+        ("archive", vec![100]),
+    ]));
+    let catalog = MemoryCatalogProvider::new();
+    catalog.register_schema("warehouse", schema.clone())?;
+    ctx.register_catalog("remote", Arc::new(catalog));
+    Ok((ctx, schema))
+}
+
+#[tokio::test]
+async fn lazy_schema_provider() -> Result<()> {
+    let (mut ctx, schema) = remote_context()?;
+    assert!(schema.materialized().is_empty());
+
+    let expected = vec!["+-----+", "| s   |", "+-----+", "| 6   |", "+-----+"];
+    for table_ref in &["orders", "warehouse.orders", "remote.warehouse.orders"] {
+        let sql = format!("SELECT SUM(v) AS s FROM {}", table_ref);
+        let results = ctx.sql(&sql).await?.collect().await?;
+        assert_batches_eq!(expected, &results);
+    }
+    assert_eq!(vec!["orders"], schema.materialized());
+
+    let results = ctx
+        .sql(
+            "SELECT o.v AS o, c.v AS c \
+             FROM remote.warehouse.orders AS o \
+             JOIN remote.warehouse.customers AS c ON o.v * 10 = c.v",
+        )
+        .await?
+        .collect()
+        .await?;
+    let expected = vec![
+        "+---+----+",
+        "| o | c  |",
+        "+---+----+",
+        "| 1 | 10 |",
+        "| 2 | 20 |",
+        "+---+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+    assert_eq!(vec!["customers", "orders"], schema.materialized());
+
+    let err = ctx
+        .sql("SELECT * FROM remote.warehouse.missing")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, DataFusionError::Plan(_)), "{}", err);
+
+    // the information schema enumerates the tables through the providers
+    let results = ctx
+        .sql(
+            "SELECT table_catalog, table_schema, table_name \
+             FROM information_schema.tables WHERE table_schema = 'warehouse'",
+        )
+        .await?
+        .collect()
+        .await?;
+    let expected = vec![
+        "+---------------+--------------+------------+",
+        "| table_catalog | table_schema | table_name |",
+        "+---------------+--------------+------------+",
+        "| remote        | warehouse    | archive    |",
+        "| remote        | warehouse    | customers  |",
+        "| remote        | warehouse    | orders     |",
+        "+---------------+--------------+------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+    Ok(())
+}
+
+#[tokio::test]
+async fn register_and_deregister_schemas() -> Result<()> {
+    let (mut ctx, _) = remote_context()?;
+    let catalog = ctx.catalog("remote").unwrap();
+
+    let staging = Arc::new(LazySchemaProvider::new(&[("orders", vec![4, 5])]));
+    assert!(catalog
+        .register_schema("staging", staging.clone())?
+        .is_none());
+    let mut schema_names = catalog.schema_names();
+    schema_names.sort();
+    assert_eq!(
+        vec!["information_schema", "staging", "warehouse"],
+        schema_names
+    );
+
+    let results = ctx
+        .sql("SELECT SUM(v) AS s FROM staging.orders")
+        .await?
+        .collect()
+        .await?;
+    let expected = vec!["+---+", "| s |", "+---+", "| 9 |", "+---+"];
+    assert_batches_eq!(expected, &results);
+    assert_eq!(vec!["orders"], staging.materialized());
+
+    assert!(catalog.deregister_schema("staging")?.is_some());
+    assert!(catalog.deregister_schema("staging")?.is_none());
+    assert!(ctx.sql("SELECT * FROM staging.orders").await.is_err());
+
+    // the information schema can't be replaced
+    let err = catalog
+        .register_schema("information_schema", staging)
+        .unwrap_err();
+    assert_eq!(
+        "Execution error: The schema information_schema is reserved",
+        err.to_string()
+    );
+    Ok(())
+}
@@ -26,10 +26,11 @@ use datafusion::scalar::ScalarValue;
 use datafusion::{datasource::TableProvider, physical_plan::collect};
 use datafusion::{
     error::{DataFusionError, Result},
-    physical_plan::DisplayFormatType,
+    physical_plan::{fmt_with_statistics, DisplayFormatType},
 };
 
 use datafusion::execution::context::ExecutionContext;
+use datafusion::execution::task_context::TaskContext;
 use datafusion::logical_plan::{
     col, Expr, LogicalPlan, LogicalPlanBuilder, TableScan, UNNAMED_TABLE,
 };
@@ -104,7 +105,7 @@ impl Stream for TestCustomRecordBatchStream {
 
 #[async_trait]
 impl LambdaExecPlan for CustomExecutionPlan {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
         unimplemented!();
     }
 }
@@ -147,7 +148,11 @@ impl ExecutionPlan for CustomExecutionPlan {
             ))
         }
     }
-    async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         Ok(Box::pin(TestCustomRecordBatchStream { nb_batch: 1 }))
     }
 
@@ -160,6 +165,7 @@ impl ExecutionPlan for CustomExecutionPlan {
             DisplayFormatType::Default => {
                 write!(f, "CustomExecutionPlan: projection={:#?}", self.projection)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -258,7 +264,8 @@ async fn custom_source_dataframe() -> Result<()> {
     assert_eq!(1, physical_plan.schema().fields().len());
     assert_eq!("c2", physical_plan.schema().field(0).name().as_str());
 
-    let batches = collect(physical_plan).await?;
+    let task_ctx = ctx.task_ctx();
+    let batches = collect(physical_plan, task_ctx).await?;
     let origin_rec_batch = TEST_CUSTOM_RECORD_BATCH!()?;
     assert_eq!(1, batches.len());
     assert_eq!(1, batches[0].num_columns());
@@ -304,7 +311,8 @@ async fn optimizers_catch_all_statistics() {
     )
     .unwrap();
 
-    let actual = collect(physical_plan).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let actual = collect(physical_plan, task_ctx).await.unwrap();
 
     assert_eq!(actual.len(), 1);
     assert_eq!(format!("{:?}", actual[0]), format!("{:?}", expected));
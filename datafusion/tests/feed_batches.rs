@@ -0,0 +1,377 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Tests of the plans fed with the batches of other lambda functions,
+//! after a serialization round trip
+
+use std::sync::Arc;
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use datafusion::assert_batches_sorted_eq;
+use datafusion::datasource::object_store::local::LocalFileSystem;
+use datafusion::datasource::PartitionedFile;
+use datafusion::error::Result;
+use datafusion::execution::context::ExecutionContext;
+use datafusion::logical_plan::{JoinType, Operator};
+use datafusion::physical_plan::coalesce_batches::CoalesceBatchesExec;
+use datafusion::physical_plan::coalesce_partitions::CoalescePartitionsExec;
+use datafusion::physical_plan::cross_join::CrossJoinExec;
+use datafusion::physical_plan::empty::EmptyExec;
+use datafusion::physical_plan::expressions::{
+    binary, col, lit, Column, Max, PhysicalSortExpr,
+};
+use datafusion::physical_plan::file_format::{CsvExec, ParquetExec, PhysicalPlanConfig};
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::hash_aggregate::{AggregateMode, HashAggregateExec};
+use datafusion::physical_plan::hash_join::{HashJoinExec, PartitionMode};
+use datafusion::physical_plan::limit::{GlobalLimitExec, LocalLimitExec};
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::repartition::RepartitionExec;
+use datafusion::physical_plan::sort::SortExec;
+use datafusion::physical_plan::union::UnionExec;
+use datafusion::physical_plan::{
+    collect, AggregateExpr, ExecutionPlan, LambdaExecPlan, Partitioning, Statistics,
+};
+use datafusion::scalar::ScalarValue;
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, false),
+        Field::new("b", DataType::Int32, false),
+    ]))
+}
+
+fn batch(a: Vec<i32>, b: Vec<i32>) -> RecordBatch {
+    RecordBatch::try_new(
+        schema(),
+        vec![Arc::new(Int32Array::from(a)), Arc::new(Int32Array::from(b))],
+    )
+    .unwrap()
+}
+
+/// The batches fed to the plans: 2 partitions with 5 rows overall
+fn fed_partitions() -> Vec<Vec<RecordBatch>> {
+    vec![
+        vec![batch(vec![1, 2], vec![10, 20]), batch(vec![3], vec![30])],
+        vec![batch(vec![4, 5], vec![40, 50])],
+    ]
+}
+
+/// The same rows in a single partition
+fn fed_partition() -> Vec<Vec<RecordBatch>> {
+    vec![fed_partitions().concat()]
+}
+
+/// An input whose data is not serialized with the plan
+fn memory_input() -> Arc<dyn ExecutionPlan> {
+    Arc::new(MemoryExec::try_new(&[vec![]], schema(), None).unwrap())
+}
+
+fn file_config(path: &str) -> PhysicalPlanConfig {
+    PhysicalPlanConfig {
+        object_store: Arc::new(LocalFileSystem {}),
+        file_schema: schema(),
+        file_groups: vec![vec![PartitionedFile::new(path.to_owned(), 10)]],
+        statistics: Statistics::default(),
+        projection: None,
+        batch_size: 1024,
+        limit: None,
+        table_partition_cols: vec![],
+    }
+}
+
+/// Serialize and deserialize `plan`, feed it with `fed` (the partitions for
+/// each child index) and execute it
+async fn round_trip_and_feed(
+    plan: Arc<dyn ExecutionPlan>,
+    fed: Vec<Vec<Vec<RecordBatch>>>,
+) -> Result<Vec<RecordBatch>> {
+    let task_ctx = ExecutionContext::new().task_ctx();
+    let json = serde_json::to_string(&plan).unwrap();
+    let mut plan: Arc<dyn ExecutionPlan> = serde_json::from_str(&json).unwrap();
+    let plan_mut = Arc::get_mut(&mut plan).unwrap();
+    for (child, partitions) in fed.into_iter().enumerate() {
+        plan_mut.feed_child_batches(child, partitions)?;
+    }
+    collect(plan, task_ctx).await
+}
+
+const ALL_ROWS: [&str; 9] = [
+    "+---+----+",
+    "| a | b  |",
+    "+---+----+",
+    "| 1 | 10 |",
+    "| 2 | 20 |",
+    "| 3 | 30 |",
+    "| 4 | 40 |",
+    "| 5 | 50 |",
+    "+---+----+",
+];
+
+#[tokio::test]
+async fn feed_leaves() -> Result<()> {
+    let leaves: Vec<Arc<dyn ExecutionPlan>> = vec![
+        memory_input(),
+        Arc::new(CsvExec::new(file_config("missing.csv"), true, b',')),
+        Arc::new(ParquetExec::new(file_config("missing.parquet"), None)),
+    ];
+    for leaf in leaves {
+        let results = round_trip_and_feed(leaf, vec![fed_partitions()]).await?;
+        assert_batches_sorted_eq!(ALL_ROWS, &results);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn feed_unary_operators() -> Result<()> {
+    let schema = schema();
+    let a_gt_2 = binary(
+        col("a", &schema)?,
+        Operator::Gt,
+        lit(ScalarValue::Int32(Some(2))),
+        &schema,
+    )?;
+    let max_b: Arc<dyn AggregateExpr> =
+        Arc::new(Max::new(col("b", &schema)?, "max_b", DataType::Int32));
+    let cases: Vec<(Arc<dyn ExecutionPlan>, Vec<Vec<RecordBatch>>, Vec<&str>)> = vec![
+        (
+            Arc::new(FilterExec::try_new(a_gt_2, memory_input())?),
+            fed_partitions(),
+            vec![
+                "+---+----+",
+                "| a | b  |",
+                "+---+----+",
+                "| 3 | 30 |",
+                "| 4 | 40 |",
+                "| 5 | 50 |",
+                "+---+----+",
+            ],
+        ),
+        (
+            Arc::new(ProjectionExec::try_new(
+                vec![(col("b", &schema)?, "b".to_owned())],
+                memory_input(),
+            )?),
+            fed_partitions(),
+            vec![
+                "+----+", "| b  |", "+----+", "| 10 |", "| 20 |", "| 30 |", "| 40 |",
+                "| 50 |", "+----+",
+            ],
+        ),
+        (
+            Arc::new(CoalesceBatchesExec::new(memory_input(), 1024)),
+            fed_partitions(),
+            ALL_ROWS.to_vec(),
+        ),
+        (
+            Arc::new(CoalescePartitionsExec::new(memory_input())),
+            fed_partitions(),
+            ALL_ROWS.to_vec(),
+        ),
+        (
+            Arc::new(RepartitionExec::try_new(
+                memory_input(),
+                Partitioning::RoundRobinBatch(3),
+            )?),
+            fed_partitions(),
+            ALL_ROWS.to_vec(),
+        ),
+        (
+            Arc::new(LocalLimitExec::new(memory_input(), 1)),
+            fed_partitions(),
+            vec![
+                "+---+----+",
+                "| a | b  |",
+                "+---+----+",
+                "| 1 | 10 |",
+                "| 4 | 40 |",
+                "+---+----+",
+            ],
+        ),
+        (
+            Arc::new(GlobalLimitExec::new(memory_input(), 2)),
+            fed_partition(),
+            vec![
+                "+---+----+",
+                "| a | b  |",
+                "+---+----+",
+                "| 1 | 10 |",
+                "| 2 | 20 |",
+                "+---+----+",
+            ],
+        ),
+        (
+            Arc::new(SortExec::try_new(
+                vec![PhysicalSortExpr {
+                    expr: col("b", &schema)?,
+                    options: Default::default(),
+                }],
+                memory_input(),
+            )?),
+            fed_partition(),
+            ALL_ROWS.to_vec(),
+        ),
+        (
+            Arc::new(HashAggregateExec::try_new(
+                AggregateMode::Partial,
+                vec![],
+                vec![max_b],
+                memory_input(),
+                schema.clone(),
+            )?),
+            fed_partitions(),
+            vec![
+                "+------------+",
+                "| max_b[max] |",
+                "+------------+",
+                "| 30         |",
+                "| 50         |",
+                "+------------+",
+            ],
+        ),
+    ];
+
+    for (plan, fed, expected) in cases {
+        let results = round_trip_and_feed(plan, vec![fed]).await?;
+        assert_batches_sorted_eq!(expected, &results);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn feed_binary_operators() -> Result<()> {
+    let left = vec![vec![batch(vec![1, 2, 3], vec![10, 20, 30])]];
+    let right = vec![
+        vec![batch(vec![2, 3], vec![200, 300])],
+        vec![batch(vec![4], vec![400])],
+    ];
+
+    let join = HashJoinExec::try_new(
+        memory_input(),
+        memory_input(),
+        vec![(Column::new("a", 0), Column::new("a", 0))],
+        &JoinType::Inner,
+        PartitionMode::CollectLeft,
+        &false,
+    )?;
+    let results =
+        round_trip_and_feed(Arc::new(join), vec![left.clone(), right.clone()]).await?;
+    let expected = vec![
+        "+---+----+---+-----+",
+        "| a | b  | a | b   |",
+        "+---+----+---+-----+",
+        "| 2 | 20 | 2 | 200 |",
+        "| 3 | 30 | 3 | 300 |",
+        "+---+----+---+-----+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+
+    let cross_join = CrossJoinExec::try_new(memory_input(), memory_input())?;
+    let results = round_trip_and_feed(
+        Arc::new(cross_join),
+        vec![vec![vec![batch(vec![1], vec![10])]], right.clone()],
+    )
+    .await?;
+    let expected = vec![
+        "+---+----+---+-----+",
+        "| a | b  | a | b   |",
+        "+---+----+---+-----+",
+        "| 1 | 10 | 2 | 200 |",
+        "| 1 | 10 | 3 | 300 |",
+        "| 1 | 10 | 4 | 400 |",
+        "+---+----+---+-----+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+
+    let union = UnionExec::new(vec![memory_input(), memory_input()]);
+    let results = round_trip_and_feed(Arc::new(union), vec![left, right]).await?;
+    let expected = vec![
+        "+---+-----+",
+        "| a | b   |",
+        "+---+-----+",
+        "| 1 | 10  |",
+        "| 2 | 20  |",
+        "| 2 | 200 |",
+        "| 3 | 30  |",
+        "| 3 | 300 |",
+        "| 4 | 400 |",
+        "+---+-----+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+    Ok(())
+}
+
+#[tokio::test]
+async fn feed_missing_input() -> Result<()> {
+    let schema = schema();
+    let join = HashJoinExec::try_new(
+        memory_input(),
+        memory_input(),
+        vec![(Column::new("a", 0), Column::new("a", 0))],
+        &JoinType::Inner,
+        PartitionMode::CollectLeft,
+        &false,
+    )?;
+    let cases: Vec<(Arc<dyn ExecutionPlan>, usize)> = vec![
+        (Arc::new(EmptyExec::new(false, schema.clone())), 0),
+        (Arc::new(CoalesceBatchesExec::new(memory_input(), 1024)), 1),
+        (Arc::new(join), 2),
+        (
+            Arc::new(CrossJoinExec::try_new(memory_input(), memory_input())?),
+            2,
+        ),
+        (
+            Arc::new(UnionExec::new(vec![memory_input(), memory_input()])),
+            2,
+        ),
+    ];
+    for (mut plan, child) in cases {
+        let err = Arc::get_mut(&mut plan)
+            .unwrap()
+            .feed_child_batches(child, fed_partitions())
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(&format!("no input {}", child))
+                || err.to_string().contains("no input to feed"),
+            "{}",
+            err
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn feed_fewer_partitions() -> Result<()> {
+    let task_ctx = ExecutionContext::new().task_ctx();
+    let leaves: Vec<Arc<dyn ExecutionPlan>> = vec![
+        memory_input(),
+        Arc::new(CsvExec::new(file_config("missing.csv"), true, b',')),
+        Arc::new(ParquetExec::new(file_config("missing.parquet"), None)),
+    ];
+    for mut leaf in leaves {
+        Arc::get_mut(&mut leaf)
+            .unwrap()
+            .feed_batches(fed_partitions())?;
+        // a partition that was not fed can't be executed
+        let err = leaf.execute(2, task_ctx.clone()).await.err().unwrap();
+        assert!(err.to_string().contains("partition 2"), "{}", err);
+    }
+    Ok(())
+}
@@ -0,0 +1,235 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Test queries served by a Flight service on an ephemeral port
+
+#![cfg(feature = "flight")]
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arrow::datatypes::Schema;
+use arrow::record_batch::RecordBatch;
+use arrow::util::pretty::pretty_format_batches;
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::{HandshakeRequest, SchemaResult, Ticket};
+use tokio::net::TcpListener;
+use tonic::transport::Channel;
+use tonic::Code;
+
+use datafusion::flight::{FlightQuery, FlightQueryService};
+use datafusion::prelude::*;
+use datafusion::test_util;
+
+type TestResult<T> = std::result::Result<T, Box<dyn Error>>;
+
+const QUERY: &str = "SELECT c1, COUNT(*), SUM(c4) FROM aggregate_test_100 GROUP BY c1";
+
+async fn test_context() -> TestResult<ExecutionContext> {
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_target_partitions(3));
+    let testdata = test_util::arrow_test_data();
+    ctx.register_csv(
+        "aggregate_test_100",
+        &format!("{}/csv/aggregate_test_100.csv", testdata),
+        CsvReadOptions::new().schema(&test_util::aggr_test_schema()),
+    )
+    .await?;
+    Ok(ctx)
+}
+
+/// Serve `ctx` on an ephemeral port, and connect a client to it
+async fn serve(ctx: ExecutionContext) -> TestResult<FlightServiceClient<Channel>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(FlightQueryService::new(ctx).serve(listener));
+    Ok(FlightServiceClient::connect(format!("http://{}", addr)).await?)
+}
+
+/// Fetch the result of `ticket`, whose first message is its schema
+async fn fetch(
+    mut client: FlightServiceClient<Channel>,
+    ticket: Ticket,
+) -> TestResult<Vec<RecordBatch>> {
+    let mut stream = client.do_get(ticket).await?.into_inner();
+    let schema = Arc::new(Schema::try_from(&stream.message().await?.unwrap())?);
+    let dictionaries_by_field = vec![None; schema.fields().len()];
+    let mut batches = vec![];
+    while let Some(data) = stream.message().await? {
+        batches.push(flight_data_to_arrow_batch(
+            &data,
+            schema.clone(),
+            &dictionaries_by_field,
+        )?);
+    }
+    Ok(batches)
+}
+
+/// The lines of the rows of `batches`, sorted
+fn sorted_rows(batches: &[RecordBatch]) -> TestResult<Vec<String>> {
+    let formatted = pretty_format_batches(batches)?.to_string();
+    let mut lines = formatted
+        .lines()
+        .filter(|line| line.starts_with('|'))
+        .map(|line| line.to_owned())
+        .collect::<Vec<_>>();
+    lines.sort();
+    Ok(lines)
+}
+
+async fn local_rows(sql: &str) -> TestResult<Vec<String>> {
+    let mut ctx = test_context().await?;
+    sorted_rows(&ctx.sql(sql).await?.collect().await?)
+}
+
+#[tokio::test]
+async fn do_get_sql() -> TestResult<()> {
+    let client = serve(test_context().await?).await?;
+    let ticket = FlightQuery::Sql(QUERY.to_owned()).to_ticket()?;
+    let batches = fetch(client, ticket).await?;
+    assert_eq!(local_rows(QUERY).await?, sorted_rows(&batches)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn do_get_physical_plan() -> TestResult<()> {
+    let ctx = test_context().await?;
+    let client = serve(ctx.clone()).await?;
+
+    let plan = ctx.create_logical_plan(QUERY)?;
+    let plan = ctx.create_physical_plan(&ctx.optimize(&plan)?).await?;
+    let ticket = FlightQuery::physical_plan(&plan)?.to_ticket()?;
+    let batches = fetch(client, ticket).await?;
+    assert_eq!(local_rows(QUERY).await?, sorted_rows(&batches)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn fetch_partitions_in_parallel() -> TestResult<()> {
+    let mut client = serve(test_context().await?).await?;
+    let query = FlightQuery::Sql(QUERY.to_owned());
+    let info = client
+        .get_flight_info(query.to_descriptor()?)
+        .await?
+        .into_inner();
+
+    let schema = Schema::try_from(&SchemaResult {
+        schema: info.schema.clone(),
+    })?;
+    let field_names = schema
+        .fields()
+        .iter()
+        .map(|field| field.name().as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        vec!["c1", "COUNT(UInt8(1))", "SUM(aggregate_test_100.c4)"],
+        field_names
+    );
+    assert_eq!(3, info.endpoint.len());
+
+    let fetches = info.endpoint.into_iter().map(|endpoint| {
+        assert!(endpoint.location.is_empty());
+        fetch(client.clone(), endpoint.ticket.unwrap())
+    });
+    let mut batches = vec![];
+    for fetched in futures::future::join_all(fetches).await {
+        batches.extend(fetched?);
+    }
+    assert_eq!(local_rows(QUERY).await?, sorted_rows(&batches)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn fetch_partitions_one_after_another() -> TestResult<()> {
+    // the join and the aggregation repartition their inputs
+    let sql = "SELECT a.c1, COUNT(*), SUM(b.c4) FROM aggregate_test_100 a \
+        JOIN aggregate_test_100 b ON a.c1 = b.c1 AND a.c2 = b.c2 GROUP BY a.c1";
+    let mut client = serve(test_context().await?).await?;
+    let query = FlightQuery::Sql(sql.to_owned());
+    let info = client
+        .get_flight_info(query.to_descriptor()?)
+        .await?
+        .into_inner();
+    assert_eq!(3, info.endpoint.len());
+
+    // each ticket executes a partition of the same plan
+    let tickets = info
+        .endpoint
+        .into_iter()
+        .map(|endpoint| endpoint.ticket.unwrap())
+        .collect::<Vec<_>>();
+    let mut batches = vec![];
+    for ticket in &tickets {
+        let fetched = tokio::time::timeout(
+            Duration::from_secs(30),
+            fetch(client.clone(), ticket.clone()),
+        )
+        .await?;
+        batches.extend(fetched?);
+    }
+    assert_eq!(local_rows(sql).await?, sorted_rows(&batches)?);
+
+    // the plan is forgotten once all its partitions are fetched
+    let status = client.do_get(tickets[0].clone()).await.unwrap_err();
+    assert_eq!(Code::NotFound, status.code());
+    Ok(())
+}
+
+#[tokio::test]
+async fn handshake_query() -> TestResult<()> {
+    let mut client = serve(test_context().await?).await?;
+    let request = HandshakeRequest {
+        protocol_version: 0,
+        payload: FlightQuery::Sql(QUERY.to_owned()).to_bytes()?,
+    };
+    let mut responses = client
+        .handshake(futures::stream::iter(vec![request]))
+        .await?
+        .into_inner();
+    let ticket = Ticket {
+        ticket: responses.message().await?.unwrap().payload,
+    };
+    assert!(responses.message().await?.is_none());
+
+    let batches = fetch(client, ticket).await?;
+    assert_eq!(local_rows(QUERY).await?, sorted_rows(&batches)?);
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalid_queries() -> TestResult<()> {
+    let mut client = serve(test_context().await?).await?;
+    let query = FlightQuery::Sql("SELECT * FROM missing".to_owned());
+    let status = client
+        .get_flight_info(query.to_descriptor()?)
+        .await
+        .unwrap_err();
+    assert_eq!(Code::InvalidArgument, status.code());
+    assert!(status.message().contains("missing"), "{}", status.message());
+
+    let status = client
+        .do_get(Ticket {
+            ticket: b"SELECT 1".to_vec(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(Code::InvalidArgument, status.code());
+    Ok(())
+}
@@ -21,9 +21,9 @@ use std::sync::Arc;
 
 use arrow::{
     array::{
-        Array, ArrayRef, Date32Array, Date64Array, Float64Array, Int32Array, StringArray,
-        TimestampMicrosecondArray, TimestampMillisecondArray, TimestampNanosecondArray,
-        TimestampSecondArray,
+        Array, ArrayRef, BooleanArray, Date32Array, Date64Array, Float64Array,
+        Int32Array, StringArray, TimestampMicrosecondArray, TimestampMillisecondArray,
+        TimestampNanosecondArray, TimestampSecondArray,
     },
     datatypes::{DataType, Field, Schema},
     record_batch::RecordBatch,
@@ -31,7 +31,7 @@ use arrow::{
 };
 use chrono::{Datelike, Duration};
 use datafusion::{
-    datasource::TableProvider,
+    datasource::{MemTable, TableProvider},
     logical_plan::{col, lit, Expr, LogicalPlan, LogicalPlanBuilder},
     physical_plan::{
         accept, file_format::ParquetExec, metrics::MetricsSet, ExecutionPlan,
@@ -235,6 +235,42 @@ async fn prune_int32_eq() {
     assert_eq!(output.result_rows, 1, "{}", output.description());
 }
 
+#[tokio::test]
+async fn prune_int32_in_list() {
+    // the row groups containing neither 1 nor 7 are pruned
+    let output = ContextWithParquet::new(Scenario::Int32)
+        .await
+        .query("SELECT * FROM t where i IN (1, 7)")
+        .await;
+    println!("{}", output.description());
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+    assert_eq!(output.row_groups_pruned(), Some(2));
+    assert_eq!(output.result_rows, 2, "{}", output.description());
+
+    // the row groups outside of the range of a long list are pruned
+    let list = (6..36)
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let output = ContextWithParquet::new(Scenario::Int32)
+        .await
+        .query(&format!("SELECT * FROM t where i IN ({})", list))
+        .await;
+    println!("{}", output.description());
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+    assert_eq!(output.row_groups_pruned(), Some(3));
+    assert_eq!(output.result_rows, 4, "{}", output.description());
+
+    let output = ContextWithParquet::new(Scenario::Int32)
+        .await
+        .query("SELECT * FROM t where i NOT IN (1, 7)")
+        .await;
+    println!("{}", output.description());
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+    assert_eq!(output.row_groups_pruned(), Some(0));
+    assert_eq!(output.result_rows, 18, "{}", output.description());
+}
+
 #[tokio::test]
 async fn prune_int32_scalar_fun_and_eq() {
     // resulrt of sql "SELECT * FROM t where abs(i) = 1 and i = 1"
@@ -403,6 +439,87 @@ async fn prune_f64_complex_expr_subtract() {
     assert_eq!(output.result_rows, 9, "{}", output.description());
 }
 
+#[tokio::test]
+async fn prune_probe_side_of_join() {
+    let query = "SELECT t.i FROM dim JOIN t ON dim.k = t.i WHERE dim.selected";
+    let config = ExecutionConfig::new().with_target_partitions(1);
+    let output = ContextWithParquet::with_dim(config.clone())
+        .await
+        .query(query)
+        .await;
+    println!("{}", output.description());
+    // only the 3 row groups of the keys 1000 to 1014 can match the keys 1000
+    // and 1010 of the build side
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+    assert_eq!(output.row_groups_pruned(), Some(397));
+    assert_eq!(output.metric_value("output_rows"), Some(15));
+    assert_eq!(output.result_rows, 2, "{}", output.description());
+
+    // without the filter of the join, the whole probe side is scanned
+    let config = config.with_disabled_rules(&["dynamic_filter_push_down"]);
+    let output = ContextWithParquet::with_dim(config)
+        .await
+        .query(query)
+        .await;
+    assert_eq!(output.row_groups_pruned(), Some(0));
+    assert_eq!(output.metric_value("output_rows"), Some(2000));
+    assert_eq!(output.result_rows, 2, "{}", output.description());
+}
+
+#[tokio::test]
+async fn prune_like_prefix() {
+    // only the row group of the urls of example.com can match
+    let output =
+        query_urls("SELECT * FROM t WHERE url LIKE 'https://example.com/%'").await;
+    assert_eq!(output.row_groups_pruned(), Some(3));
+    assert_eq!(output.result_rows, 5, "{}", output.description());
+
+    let output = query_urls("SELECT * FROM t WHERE url LIKE 'https://example.%'").await;
+    assert_eq!(output.row_groups_pruned(), Some(2));
+    assert_eq!(output.result_rows, 10, "{}", output.description());
+
+    // the prefix of a case insensitive pattern matches its case variants
+    let output = query_urls("SELECT * FROM t WHERE url ILIKE 'FTP:%'").await;
+    assert_eq!(output.row_groups_pruned(), Some(3));
+    assert_eq!(output.result_rows, 5, "{}", output.description());
+}
+
+#[tokio::test]
+async fn prune_like_without_prefix() {
+    let output = query_urls("SELECT * FROM t WHERE url LIKE '%example.com%'").await;
+    assert_eq!(output.row_groups_pruned(), Some(0));
+    assert_eq!(output.result_rows, 10, "{}", output.description());
+
+    // the prefix stops at the escape character
+    let output = query_urls("SELECT * FROM t WHERE url LIKE 'http\\_%'").await;
+    assert_eq!(output.row_groups_pruned(), Some(1));
+}
+
+/// Runs `query` on the table of [`Scenario::Urls`], checking that it returns
+/// the same rows as without pruning
+async fn query_urls(query: &str) -> TestOutput {
+    let output = ContextWithParquet::new(Scenario::Urls)
+        .await
+        .query(query)
+        .await;
+    println!("{}", output.description());
+    assert_eq!(output.predicate_evaluation_errors(), Some(0));
+
+    let config = ExecutionConfig::new().with_parquet_pruning(false);
+    let unpruned = ContextWithParquet::with_config(Scenario::Urls, config)
+        .await
+        .query(query)
+        .await;
+    assert_eq!(unpruned.row_groups_pruned(), Some(0));
+    let sorted_lines = |output: &TestOutput| {
+        let mut lines = output.pretty_results.lines().collect::<Vec<_>>();
+        lines.sort_unstable();
+        lines.join("\n")
+    };
+    assert_eq!(sorted_lines(&output), sorted_lines(&unpruned));
+    output
+}
+
 // ----------------------
 // Begin test fixture
 // ----------------------
@@ -413,6 +530,11 @@ enum Scenario {
     Dates,
     Int32,
     Float64,
+    /// 2000 consecutive integers, from 0
+    Int32Range,
+    /// Sorted urls of ftp://mirror.org/, http://example.com/,
+    /// https://example.com/ and https://example.org/, a row group each
+    Urls,
 }
 
 /// Test fixture that has an execution context that has an external
@@ -493,6 +615,23 @@ impl ContextWithParquet {
         }
     }
 
+    /// The context of the scenario [`Scenario::Int32Range`] with a table
+    /// "dim" too, whose 200 keys "k" are multiples of 10 and 2 of which are
+    /// "selected"
+    async fn with_dim(config: ExecutionConfig) -> Self {
+        let mut context = Self::with_config(Scenario::Int32Range, config).await;
+        let keys = Int32Array::from_iter_values((0..200).map(|i| i * 10));
+        let selected = BooleanArray::from_iter((0..200).map(|i| Some(i / 2 == 50)));
+        let batch = RecordBatch::try_from_iter(vec![
+            ("k", Arc::new(keys) as ArrayRef),
+            ("selected", Arc::new(selected) as ArrayRef),
+        ])
+        .unwrap();
+        let dim = MemTable::try_new(batch.schema(), vec![vec![batch]]).unwrap();
+        context.ctx.register_table("dim", Arc::new(dim)).unwrap();
+        context
+    }
+
     /// runs a query like "SELECT * from t WHERE <expr> and returns
     /// the number of output rows and normalized execution metrics
     async fn query_with_expr(&mut self, expr: Expr) -> TestOutput {
@@ -537,7 +676,8 @@ impl ContextWithParquet {
             .await
             .expect("creating physical plan");
 
-        let results = datafusion::physical_plan::collect(physical_plan.clone())
+        let task_ctx = self.ctx.task_ctx();
+        let results = datafusion::physical_plan::collect(physical_plan.clone(), task_ctx)
             .await
             .expect("Running");
 
@@ -622,6 +762,15 @@ async fn make_test_file(scenario: Scenario) -> NamedTempFile {
                 make_f64_batch(vec![5.0, 6.0, 7.0, 8.0, 9.0]),
             ]
         }
+        Scenario::Int32Range => vec![make_int32_batch(0, 2000)],
+        Scenario::Urls => {
+            vec![
+                make_url_batch("ftp://mirror.org/"),
+                make_url_batch("http://example.com/"),
+                make_url_batch("https://example.com/"),
+                make_url_batch("https://example.org/"),
+            ]
+        }
     };
 
     let schema = batches[0].schema();
@@ -738,6 +887,19 @@ fn make_int32_batch(start: i32, end: i32) -> RecordBatch {
     RecordBatch::try_new(schema, vec![array.clone()]).unwrap()
 }
 
+/// Return record batch with 5 sorted urls starting with `prefix`
+///
+/// Columns are named
+/// "url" -> StringArray
+fn make_url_batch(prefix: &str) -> RecordBatch {
+    let schema = Arc::new(Schema::new(vec![Field::new("url", DataType::Utf8, true)]));
+    let urls = (0..5)
+        .map(|i| format!("{}{}", prefix, i))
+        .collect::<Vec<_>>();
+    let array = Arc::new(StringArray::from_iter_values(urls)) as ArrayRef;
+    RecordBatch::try_new(schema, vec![array]).unwrap()
+}
+
 /// Return record batch with f64 vector
 ///
 /// Columns are named
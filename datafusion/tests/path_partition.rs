@@ -19,6 +19,7 @@
 
 use std::{fs, io, sync::Arc};
 
+use arrow::datatypes::DataType;
 use async_trait::async_trait;
 use datafusion::{
     assert_batches_sorted_eq,
@@ -26,16 +27,17 @@ use datafusion::{
         file_format::{csv::CsvFormat, parquet::ParquetFormat},
         listing::{ListingOptions, ListingTable},
         object_store::{
-            local::LocalFileSystem, FileMeta, FileMetaStream, ListEntryStream,
-            ObjectReader, ObjectStore, SizedFile,
+            local::LocalFileSystem, FileMeta, FileMetaPage, FileMetaStream,
+            ListEntryStream, ObjectReader, ObjectStore, SizedFile,
         },
     },
     error::{DataFusionError, Result},
     physical_plan::ColumnStatistics,
     prelude::ExecutionContext,
+    scalar::ScalarValue,
     test_util::{self, arrow_test_data, parquet_test_data},
 };
-use futures::{stream, StreamExt};
+use futures::stream;
 
 use serde::{Deserialize, Serialize};
 
@@ -219,10 +221,17 @@ async fn parquet_statistics() -> Result<()> {
     assert_eq!(stat_cols.len(), 4);
     // stats for the first col are read from the parquet file
     assert_eq!(stat_cols[0].null_count, Some(3));
-    // TODO assert partition column (1,2,3) stats once implemented (#1186)
-    assert_eq!(stat_cols[1], ColumnStatistics::default());
-    assert_eq!(stat_cols[2], ColumnStatistics::default());
-    assert_eq!(stat_cols[3], ColumnStatistics::default());
+    // stats for the partition columns are the values of the files
+    assert_eq!(stat_cols[1], partition_column_statistics("2021", "2021"));
+    assert_eq!(stat_cols[2], partition_column_statistics("09", "10"));
+    assert_eq!(stat_cols[3], partition_column_statistics("09", "28"));
+    let schema = physical_plan.schema();
+    for (stats, field) in stat_cols[1..].iter().zip(&schema.fields()[1..]) {
+        assert_eq!(
+            &stats.min_value.as_ref().unwrap().get_datatype(),
+            field.data_type()
+        );
+    }
 
     //// WITH PROJECTION ////
     let logical_plan = ctx
@@ -240,12 +249,28 @@ async fn parquet_statistics() -> Result<()> {
     assert_eq!(stat_cols.len(), 2);
     // stats for the first col are read from the parquet file
     assert_eq!(stat_cols[0].null_count, Some(1));
-    // TODO assert partition column stats once implemented (#1186)
-    assert_eq!(stat_cols[1], ColumnStatistics::default());
+    assert_eq!(stat_cols[1], partition_column_statistics("28", "28"));
 
     Ok(())
 }
 
+/// The statistics of a `Utf8` partition column whose values range from `min`
+/// to `max`
+fn partition_column_statistics(min: &str, max: &str) -> ColumnStatistics {
+    let value = |v: &str| {
+        ScalarValue::Dictionary(
+            Box::new(DataType::UInt8),
+            Box::new(ScalarValue::Utf8(Some(v.to_owned()))),
+        )
+    };
+    ColumnStatistics {
+        null_count: Some(0),
+        max_value: Some(value(max)),
+        min_value: Some(value(min)),
+        distinct_count: None,
+    }
+}
+
 #[tokio::test]
 async fn parquet_overlapping_columns() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -285,7 +310,10 @@ fn register_partitioned_aggregate_csv(
     let object_store = MirroringObjectStore::new_arc(csv_file_path, store_paths);
 
     let mut options = ListingOptions::new(Arc::new(CsvFormat::default()));
-    options.table_partition_cols = partition_cols.iter().map(|&s| s.to_owned()).collect();
+    options.table_partition_cols = partition_cols
+        .iter()
+        .map(|&s| (s.to_owned(), DataType::Utf8))
+        .collect();
 
     let table =
         ListingTable::new(object_store, table_path.to_owned(), file_schema, options);
@@ -307,7 +335,10 @@ async fn register_partitioned_alltypes_parquet(
         MirroringObjectStore::new_arc(parquet_file_path.clone(), store_paths);
 
     let mut options = ListingOptions::new(Arc::new(ParquetFormat::default()));
-    options.table_partition_cols = partition_cols.iter().map(|&s| s.to_owned()).collect();
+    options.table_partition_cols = partition_cols
+        .iter()
+        .map(|&s| (s.to_owned(), DataType::Utf8))
+        .collect();
     options.collect_stat = true;
 
     let file_schema = options
@@ -348,22 +379,32 @@ impl MirroringObjectStore {
 #[typetag::serde(name = "mirroring_object_store")]
 impl ObjectStore for MirroringObjectStore {
     async fn list_file(&self, prefix: &str) -> Result<FileMetaStream> {
-        let prefix = prefix.to_owned();
-        let size = self.file_size;
-        Ok(Box::pin(
-            stream::iter(
-                self.files
-                    .clone()
-                    .into_iter()
-                    .filter(move |f| f.starts_with(&prefix)),
-            )
-            .map(move |f| {
-                Ok(FileMeta {
-                    sized_file: SizedFile { path: f, size },
-                    last_modified: None,
-                })
-            }),
-        ))
+        // the files are listed in a single page
+        let page = self.list_file_page(prefix, None).await?;
+        Ok(Box::pin(stream::iter(page.files.into_iter().map(Ok))))
+    }
+
+    async fn list_file_page(
+        &self,
+        prefix: &str,
+        _continuation: Option<String>,
+    ) -> Result<FileMetaPage> {
+        let files = self
+            .files
+            .iter()
+            .filter(|f| f.starts_with(prefix))
+            .map(|f| FileMeta {
+                sized_file: SizedFile {
+                    path: f.clone(),
+                    size: self.file_size,
+                },
+                last_modified: None,
+            })
+            .collect();
+        Ok(FileMetaPage {
+            files,
+            continuation: None,
+        })
     }
 
     async fn list_dir(
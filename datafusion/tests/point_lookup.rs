@@ -0,0 +1,121 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Allocations of a point lookup, counted by the global allocator. The test
+//! is alone in its binary so that no other test allocates concurrently.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::array::{Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use arrow::util::pretty::pretty_format_batches;
+use datafusion::error::Result;
+use datafusion::execution::context::{ExecutionConfig, ExecutionContext};
+use datafusion::logical_plan::Operator;
+use datafusion::physical_plan::expressions::{binary, col, lit};
+use datafusion::physical_plan::filter::FilterExec;
+use datafusion::physical_plan::memory::MemoryExec;
+use datafusion::physical_plan::projection::ProjectionExec;
+use datafusion::physical_plan::{collect, ExecutionPlan};
+use datafusion::scalar::ScalarValue;
+
+/// Number of bytes allocated so far
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Looks up the 500 rows of the key 42 among 100,000 rows in batches of
+/// 10,000 rows, and returns the result and the number of bytes allocated by
+/// the execution
+async fn lookup(config: ExecutionConfig) -> Result<(Vec<RecordBatch>, usize)> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("key", DataType::Int64, false),
+        Field::new("value", DataType::Utf8, false),
+    ]));
+    let batches = (0..10)
+        .map(|batch| {
+            let keys = (0..10_000).map(|row| (batch * 10_000 + row) / 500);
+            let values = (0..10_000).map(|row| format!("{:0100}", row));
+            RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(keys)),
+                    Arc::new(StringArray::from_iter_values(values)),
+                ],
+            )
+        })
+        .collect::<arrow::error::Result<Vec<_>>>()?;
+    let input = Arc::new(MemoryExec::try_new(&[batches], schema.clone(), None)?);
+    let predicate = binary(
+        col("key", &schema)?,
+        Operator::Eq,
+        lit(ScalarValue::Int64(Some(42))),
+        &schema,
+    )?;
+    let filter = Arc::new(FilterExec::try_new(predicate, input)?);
+    let plan = Arc::new(ProjectionExec::try_new(
+        vec![
+            (col("value", &schema)?, "value".to_owned()),
+            (col("key", &schema)?, "key".to_owned()),
+        ],
+        filter,
+    )?) as Arc<dyn ExecutionPlan>;
+
+    let task_ctx = ExecutionContext::with_config(config).task_ctx();
+    let before = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let results = collect(plan, task_ctx).await?;
+    let allocated = ALLOCATED_BYTES.load(Ordering::Relaxed) - before;
+    Ok((results, allocated))
+}
+
+#[tokio::test]
+async fn point_lookup_slices_input() -> Result<()> {
+    let (sliced, sliced_bytes) = lookup(ExecutionConfig::new()).await?;
+    let (copied, copied_bytes) =
+        lookup(ExecutionConfig::new().with_filter_slice_selectivity(0.0)).await?;
+
+    assert_eq!(
+        pretty_format_batches(&sliced)?.to_string(),
+        pretty_format_batches(&copied)?.to_string()
+    );
+    assert_eq!(sliced.iter().map(|b| b.num_rows()).sum::<usize>(), 500);
+    // the copy of the 500 selected rows takes more than 50KB
+    assert!(
+        sliced_bytes + 50_000 < copied_bytes,
+        "{} {}",
+        sliced_bytes,
+        copied_bytes
+    );
+    Ok(())
+}
@@ -15,21 +15,28 @@
 // specific language governing permissions and limitations
 // under the License.
 
-use arrow::array::{as_primitive_array, Int32Builder, UInt64Array};
+use arrow::array::{as_primitive_array, Int32Builder, Int64Array, UInt64Array};
 use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
-use datafusion::datasource::datasource::{TableProvider, TableProviderFilterPushDown};
+use datafusion::assert_batches_eq;
+use datafusion::datasource::datasource::{
+    ScanArgs, TableProvider, TableProviderFilterPushDown,
+};
 use datafusion::error::Result;
 use datafusion::execution::context::ExecutionContext;
-use datafusion::logical_plan::Expr;
+use datafusion::execution::task_context::TaskContext;
+use datafusion::logical_plan::{Expr, Operator};
 use datafusion::physical_plan::common::SizedRecordBatchStream;
+use datafusion::physical_plan::memory::MemoryExec;
 use datafusion::physical_plan::{
-    DisplayFormatType, ExecutionPlan, LambdaExecPlan, Partitioning, SendableRecordBatchStream, Statistics,
+    collect, displayable, fmt_with_statistics, DisplayFormatType, ExecutionPlan,
+    LambdaExecPlan, Partitioning, SendableRecordBatchStream, Statistics,
 };
 use datafusion::prelude::*;
 use datafusion::scalar::ScalarValue;
-use std::sync::Arc;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
 
 use serde::{Deserialize, Serialize};
 
@@ -58,7 +65,7 @@ struct CustomPlan {
 
 #[async_trait]
 impl LambdaExecPlan for CustomPlan {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
         unimplemented!();
     }
 }
@@ -93,7 +100,11 @@ impl ExecutionPlan for CustomPlan {
         unreachable!()
     }
 
-    async fn execute(&self, _: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        _: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         Ok(Box::pin(SizedRecordBatchStream::new(
             self.schema(),
             self.batches.clone(),
@@ -109,6 +120,7 @@ impl ExecutionPlan for CustomPlan {
             DisplayFormatType::Default => {
                 write!(f, "CustomPlan: batch_size={}", self.batches.len(),)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 
@@ -210,3 +222,177 @@ async fn test_filter_pushdown_results() -> Result<()> {
     assert_provider_row_count(2, 0).await?;
     Ok(())
 }
+
+/// A table over a sorted column `a` that serves the range filters on `a`
+/// exactly with binary searches
+struct SortedVecProvider {
+    schema: SchemaRef,
+    values: Vec<i64>,
+    last_scan: Mutex<Option<ScanArgs>>,
+}
+
+impl SortedVecProvider {
+    fn new(mut values: Vec<i64>) -> Self {
+        values.sort_unstable();
+        Self {
+            schema: Arc::new(Schema::new(vec![Field::new("a", DataType::Int64, false)])),
+            values,
+            last_scan: Mutex::new(None),
+        }
+    }
+
+    /// The range of the values matching `filter`, `None` if it is not a
+    /// comparison of `a` with a literal
+    fn filter_range(&self, filter: &Expr) -> Option<Range<usize>> {
+        let (op, value) = match filter {
+            Expr::BinaryExpr { left, op, right } => match (&**left, &**right) {
+                (Expr::Column(col), Expr::Literal(ScalarValue::Int64(Some(value))))
+                    if col.name == "a" =>
+                {
+                    (*op, *value)
+                }
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let lower = self.values.partition_point(|v| *v < value);
+        let upper = self.values.partition_point(|v| *v <= value);
+        match op {
+            Operator::Eq => Some(lower..upper),
+            Operator::Lt => Some(0..lower),
+            Operator::LtEq => Some(0..upper),
+            Operator::Gt => Some(upper..self.values.len()),
+            Operator::GtEq => Some(lower..self.values.len()),
+            _ => None,
+        }
+    }
+}
+
+#[async_trait]
+impl TableProvider for SortedVecProvider {
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn std::any::Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.schema.clone()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        let filters = filters
+            .iter()
+            .map(|filter| Ok((filter.clone(), self.supports_filter_pushdown(filter)?)))
+            .collect::<Result<_>>()?;
+        self.scan_with_args(ScanArgs {
+            projection: projection.clone(),
+            batch_size,
+            filters,
+            limit,
+        })
+        .await
+    }
+
+    async fn scan_with_args(&self, args: ScanArgs) -> Result<Arc<dyn ExecutionPlan>> {
+        let mut range = 0..self.values.len();
+        for filter in args.exact_filters() {
+            let filter_range = self.filter_range(filter).unwrap();
+            range.start = range.start.max(filter_range.start);
+            range.end = range.end.min(filter_range.end);
+        }
+        range.end = range.end.max(range.start);
+        if let Some(limit) = args.limit {
+            range.end = range.end.min(range.start + limit);
+        }
+
+        let batch = RecordBatch::try_new(
+            self.schema.clone(),
+            vec![Arc::new(Int64Array::from(self.values[range].to_vec()))],
+        )?;
+        let exec = MemoryExec::try_new(
+            &[vec![batch]],
+            self.schema.clone(),
+            args.projection.clone(),
+        )?;
+        *self.last_scan.lock().unwrap() = Some(args);
+        Ok(Arc::new(exec))
+    }
+
+    fn supports_filter_pushdown(
+        &self,
+        filter: &Expr,
+    ) -> Result<TableProviderFilterPushDown> {
+        Ok(match self.filter_range(filter) {
+            Some(_) => TableProviderFilterPushDown::Exact,
+            None => TableProviderFilterPushDown::Unsupported,
+        })
+    }
+}
+
+/// Run `sql` on a `SortedVecProvider` registered as `sorted`, returning the
+/// physical plan, the results and the arguments of the scan
+async fn query_sorted_vec(sql: &str) -> Result<(String, Vec<RecordBatch>, ScanArgs)> {
+    let provider = Arc::new(SortedVecProvider::new(vec![7, 1, 5, 3, 9, 0, 8, 2, 6, 4]));
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("sorted", provider.clone())?;
+
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let displayed = displayable(plan.as_ref()).indent().to_string();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await?;
+    let scan = provider.last_scan.lock().unwrap().take().unwrap();
+    Ok((displayed, results, scan))
+}
+
+#[tokio::test]
+async fn sorted_vec_exact_range_filters() -> Result<()> {
+    let (plan, results, scan) =
+        query_sorted_vec("SELECT a FROM sorted WHERE a >= 3 AND a < 6").await?;
+
+    assert!(!plan.contains("FilterExec"), "{}", plan);
+    assert_eq!(scan.exact_filters().count(), 2);
+    let expected = vec![
+        "+---+", "| a |", "+---+", "| 3 |", "| 4 |", "| 5 |", "+---+",
+    ];
+    assert_batches_eq!(expected, &results);
+    Ok(())
+}
+
+#[tokio::test]
+async fn sorted_vec_unsupported_filters() -> Result<()> {
+    let (plan, results, scan) =
+        query_sorted_vec("SELECT a FROM sorted WHERE a > 4 AND a % 2 = 0").await?;
+
+    // only the filter that is not pushed down remains in the plan
+    assert!(plan.contains("FilterExec"), "{}", plan);
+    assert!(!plan.contains("a@0 > 4"), "{}", plan);
+    assert_eq!(scan.filters.len(), 1);
+    let expected = vec!["+---+", "| a |", "+---+", "| 6 |", "| 8 |", "+---+"];
+    assert_batches_eq!(expected, &results);
+    Ok(())
+}
+
+#[tokio::test]
+async fn sorted_vec_limit() -> Result<()> {
+    let (plan, results, scan) =
+        query_sorted_vec("SELECT a FROM sorted WHERE a > 2 LIMIT 3").await?;
+
+    assert!(!plan.contains("FilterExec"), "{}", plan);
+    assert_eq!(scan.limit, Some(3));
+    let expected = vec![
+        "+---+", "| a |", "+---+", "| 3 |", "| 4 |", "| 5 |", "+---+",
+    ];
+    assert_batches_eq!(expected, &results);
+    Ok(())
+}
@@ -0,0 +1,178 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::any::Any;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use arrow::array::Int32Array;
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+
+use datafusion::assert_batches_sorted_eq;
+use datafusion::datasource::{MemTable, TableProvider};
+use datafusion::error::Result;
+use datafusion::logical_plan::Expr;
+use datafusion::physical_plan::ExecutionPlan;
+use datafusion::prelude::*;
+
+/// A [`MemTable`] counting its scans
+struct CountingTable {
+    table: Arc<MemTable>,
+    scans: AtomicUsize,
+}
+
+impl CountingTable {
+    fn scans(&self) -> usize {
+        self.scans.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl TableProvider for CountingTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.scans.fetch_add(1, Ordering::SeqCst);
+        self.table
+            .scan(projection, batch_size, filters, limit)
+            .await
+    }
+
+    fn version(&self) -> Option<u64> {
+        self.table.version()
+    }
+}
+
+fn batch(values: Vec<i32>) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![Field::new("a", DataType::Int32, false)]));
+    Ok(RecordBatch::try_new(
+        schema,
+        vec![Arc::new(Int32Array::from(values))],
+    )?)
+}
+
+fn cached_context() -> Result<(ExecutionContext, Arc<CountingTable>)> {
+    let mut ctx = ExecutionContext::with_config(
+        ExecutionConfig::new().with_result_cache_size(1 << 20),
+    );
+    let batch = batch(vec![1, 2, 3])?;
+    let table = Arc::new(CountingTable {
+        table: Arc::new(MemTable::try_new(batch.schema(), vec![vec![batch]])?),
+        scans: AtomicUsize::new(0),
+    });
+    ctx.register_table("t", table.clone())?;
+    Ok((ctx, table))
+}
+
+#[tokio::test]
+async fn serve_repeated_queries_from_cache() -> Result<()> {
+    let (mut ctx, table) = cached_context()?;
+    let sql = "SELECT SUM(a) AS total FROM t WHERE a > 1";
+    let expected = vec![
+        "+-------+",
+        "| total |",
+        "+-------+",
+        "| 5     |",
+        "+-------+",
+    ];
+
+    let results = ctx.sql(sql).await?.collect().await?;
+    assert_batches_sorted_eq!(expected, &results);
+    assert_eq!(1, table.scans());
+    assert_eq!(1, ctx.result_cache().unwrap().len());
+
+    let results = ctx.sql(sql).await?.collect().await?;
+    assert_batches_sorted_eq!(expected, &results);
+    assert_eq!(1, table.scans());
+
+    // a different query is not served from the cache
+    ctx.sql("SELECT SUM(a) AS total FROM t WHERE a > 2")
+        .await?
+        .collect()
+        .await?;
+    assert_eq!(2, table.scans());
+    Ok(())
+}
+
+#[tokio::test]
+async fn invalidate_on_insert() -> Result<()> {
+    let (mut ctx, table) = cached_context()?;
+    let sql = "SELECT SUM(a) AS total FROM t";
+    ctx.sql(sql).await?.collect().await?;
+    assert_eq!(1, table.scans());
+
+    table.table.insert_batches(vec![batch(vec![4])?])?;
+    let results = ctx.sql(sql).await?.collect().await?;
+    let expected = vec![
+        "+-------+",
+        "| total |",
+        "+-------+",
+        "| 10    |",
+        "+-------+",
+    ];
+    assert_batches_sorted_eq!(expected, &results);
+    assert_eq!(2, table.scans());
+
+    ctx.sql(sql).await?.collect().await?;
+    assert_eq!(2, table.scans());
+    Ok(())
+}
+
+#[tokio::test]
+async fn volatile_queries_are_not_cached() -> Result<()> {
+    let (mut ctx, table) = cached_context()?;
+    for sql in [
+        "SELECT a, now() FROM t",
+        "SELECT a FROM t WHERE random() < 2.0",
+    ] {
+        ctx.sql(sql).await?.collect().await?;
+        ctx.sql(sql).await?.collect().await?;
+    }
+    assert_eq!(4, table.scans());
+    assert!(ctx.result_cache().unwrap().is_empty());
+    Ok(())
+}
+
+#[tokio::test]
+async fn no_cache_by_default() -> Result<()> {
+    let (_, table) = cached_context()?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("t", table.clone())?;
+    ctx.sql("SELECT a FROM t").await?.collect().await?;
+    ctx.sql("SELECT a FROM t").await?.collect().await?;
+    assert_eq!(2, table.scans());
+    assert!(ctx.result_cache().is_none());
+    Ok(())
+}
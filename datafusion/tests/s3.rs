@@ -0,0 +1,115 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Test queries on an S3 compatible service, e.g. MinIO or localstack.
+//!
+//! The tests only run if `DATAFUSION_S3_TEST_ENDPOINT` is set to the endpoint
+//! of the service, e.g. `http://localhost:9000`. The credentials are taken
+//! from the standard AWS environment variables.
+
+#![cfg(feature = "s3")]
+
+use std::sync::Arc;
+
+use datafusion::{
+    assert_batches_sorted_eq,
+    datasource::object_store::s3::{S3FileSystem, S3Options},
+    error::Result,
+    prelude::{CsvReadOptions, ExecutionContext},
+};
+use rusoto_core::{ByteStream, Region};
+use rusoto_s3::{CreateBucketRequest, PutObjectRequest, S3Client, S3};
+
+static ENDPOINT_VAR: &str = "DATAFUSION_S3_TEST_ENDPOINT";
+
+fn test_options() -> Option<S3Options> {
+    let endpoint = std::env::var(ENDPOINT_VAR).ok()?;
+    Some(S3Options {
+        endpoint: Some(endpoint),
+        ..Default::default()
+    })
+}
+
+/// Create `bucket` with the objects `files`
+async fn put_files(options: &S3Options, bucket: &str, files: &[(&str, &str)]) {
+    let client = S3Client::new(Region::Custom {
+        name: options.region.clone(),
+        endpoint: options.endpoint.clone().unwrap(),
+    });
+    // the bucket might remain from a previous run
+    let _ = client
+        .create_bucket(CreateBucketRequest {
+            bucket: bucket.to_owned(),
+            ..Default::default()
+        })
+        .await;
+    for (key, data) in files {
+        client
+            .put_object(PutObjectRequest {
+                bucket: bucket.to_owned(),
+                key: key.to_string(),
+                body: Some(ByteStream::from(data.as_bytes().to_vec())),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn query_csv_on_s3() -> Result<()> {
+    let options = match test_options() {
+        Some(options) => options,
+        None => {
+            println!("{} is not set, skipping", ENDPOINT_VAR);
+            return Ok(());
+        }
+    };
+    put_files(
+        &options,
+        "datafusion-test",
+        &[
+            ("csv/part-0.csv", "a,b\n1,x\n2,y\n"),
+            ("csv/part-1.csv", "a,b\n3,z\n"),
+            ("other/part-0.csv", "a,b\n4,w\n"),
+        ],
+    )
+    .await;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_object_store("s3", Arc::new(S3FileSystem::new(options)));
+    ctx.register_csv("t", "s3://datafusion-test/csv/", CsvReadOptions::new())
+        .await?;
+
+    let result = ctx
+        .sql("SELECT a, b FROM t WHERE a > 1")
+        .await?
+        .collect()
+        .await?;
+
+    let expected = vec![
+        "+---+---+",
+        "| a | b |",
+        "+---+---+",
+        "| 2 | y |",
+        "| 3 | z |",
+        "+---+---+",
+    ];
+    assert_batches_sorted_eq!(expected, &result);
+
+    Ok(())
+}
@@ -18,8 +18,9 @@
 //! This module contains end to end tests of running SQL queries using
 //! DataFusion
 
+use std::any::Any;
 use std::convert::TryFrom;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use chrono::prelude::*;
 use chrono::Duration;
@@ -32,18 +33,26 @@ use arrow::{
     util::display::array_value_to_string,
 };
 
+use async_trait::async_trait;
 use datafusion::assert_batches_eq;
 use datafusion::assert_batches_sorted_eq;
 use datafusion::assert_contains;
 use datafusion::assert_not_contains;
-use datafusion::logical_plan::plan::{Aggregate, Projection};
+use datafusion::datasource::file_format::json::JsonFormat;
+use datafusion::datasource::listing::ListingOptions;
+use datafusion::datasource::table_function::TableFunction;
+use datafusion::datasource::TableProvider;
+use datafusion::execution::progress::ProgressHandler;
+use datafusion::logical_plan::plan::{Aggregate, EmptyRelation, Projection};
 use datafusion::logical_plan::LogicalPlan;
 use datafusion::logical_plan::TableScan;
-use datafusion::physical_plan::functions::Volatility;
-use datafusion::physical_plan::metrics::MetricValue;
+use datafusion::physical_plan::functions::{make_scalar_function, Volatility};
+use datafusion::physical_plan::metrics::{self, MetricValue};
 use datafusion::physical_plan::ExecutionPlan;
 use datafusion::physical_plan::ExecutionPlanVisitor;
+use datafusion::physical_plan::{accept, file_format::ParquetExec};
 use datafusion::prelude::*;
+use datafusion::scalar::ScalarValue;
 use datafusion::test_util;
 use datafusion::{datasource::MemTable, physical_plan::collect};
 use datafusion::{
@@ -51,6 +60,8 @@ use datafusion::{
     physical_plan::ColumnarValue,
 };
 use datafusion::{execution::context::ExecutionContext, physical_plan::displayable};
+use parquet::arrow::ArrowWriter;
+use std::fs::File;
 
 #[tokio::test]
 async fn nyc() -> Result<()> {
@@ -148,7 +159,8 @@ async fn parquet_single_nan_schema() {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let results = collect(plan).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.unwrap();
     for batch in results {
         assert_eq!(1, batch.num_rows());
         assert_eq!(1, batch.num_columns());
@@ -184,7 +196,8 @@ async fn parquet_list_columns() {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let results = collect(plan).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.unwrap();
 
     //   int64_list              utf8_list
     // 0  [1, 2, 3]        [abc, efg, hij]
@@ -732,6 +745,54 @@ async fn drop_table() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn insert_into() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int64, false),
+        Field::new("b", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1])),
+            Arc::new(StringArray::from(vec![Some("x")])),
+        ],
+    )?;
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+
+    let sql = "INSERT INTO t SELECT * FROM (VALUES (2, 'y')) AS v (a, b)";
+    let results = execute_to_batches(&mut ctx, sql).await;
+    assert!(results.is_empty());
+
+    // the columns are matched by name and cast to the types of the table
+    let sql = "INSERT INTO t (b, a) SELECT 'z', CAST(3 AS INT)";
+    execute_to_batches(&mut ctx, sql).await;
+
+    let results = execute_to_batches(&mut ctx, "SELECT a, b FROM t ORDER BY a").await;
+    let expected = vec![
+        "+---+---+",
+        "| a | b |",
+        "+---+---+",
+        "| 1 | x |",
+        "| 2 | y |",
+        "| 3 | z |",
+        "+---+---+",
+    ];
+    assert_batches_eq!(expected, &results);
+
+    register_aggregate_simple_csv(&mut ctx).await?;
+    let sql = "INSERT INTO aggregate_simple SELECT * FROM aggregate_simple";
+    let err = ctx.sql(sql).await.err().expect("insert should have failed");
+    assert_eq!(
+        "This feature is not implemented: \
+        INSERT INTO is only supported for in memory tables, not aggregate_simple",
+        err.to_string()
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn select_distinct() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -878,6 +939,221 @@ async fn select_distinct_from_utf8() {
     assert_batches_eq!(expected, &actual);
 }
 
+#[tokio::test]
+async fn select_distinct_from_nulls() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Int32, true),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(3), None])),
+            Arc::new(Int32Array::from(vec![Some(1), Some(2), None, None])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t", Arc::new(table))?;
+
+    let sql = "SELECT a, b,
+        a IS DISTINCT FROM b AS d,
+        a IS NOT DISTINCT FROM b AS nd,
+        a IS DISTINCT FROM NULL AS dn,
+        NULL IS NOT DISTINCT FROM b AS ndn
+    FROM t";
+    // the comparisons are never null
+    let plan = ctx.create_logical_plan(sql)?;
+    assert!(plan.schema().fields()[2..].iter().all(|f| !f.is_nullable()));
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+---+-------+-------+-------+-------+",
+        "| a | b | d     | nd    | dn    | ndn   |",
+        "+---+---+-------+-------+-------+-------+",
+        "| 1 | 1 | false | true  | true  | false |",
+        "|   | 2 | true  | false | false | false |",
+        "| 3 |   | true  | false | true  | true  |",
+        "|   |   | false | true  | false | true  |",
+        "+---+---+-------+-------+-------+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+fn create_strings_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("s", DataType::Utf8, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(StringArray::from(vec![
+            Some("Apache Arrow"),
+            Some("ÉCOLE"),
+            Some("Ωμέγα"),
+            None,
+        ]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t", Arc::new(table))?;
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn query_ilike() -> Result<()> {
+    let mut ctx = create_strings_context()?;
+
+    let sql = "SELECT s,
+        s ILIKE 'apache%' AS a,
+        s ILIKE 'éc_le' AS b,
+        s NOT ILIKE '%ΜΈΓ%' AS c,
+        s ILIKE s AS d
+    FROM t";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+--------------+-------+-------+-------+------+",
+        "| s            | a     | b     | c     | d    |",
+        "+--------------+-------+-------+-------+------+",
+        "| Apache Arrow | true  | false | true  | true |",
+        "| ÉCOLE        | false | true  | true  | true |",
+        "| Ωμέγα        | false | false | false | true |",
+        "|              |       |       |       |      |",
+        "+--------------+-------+-------+-------+------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT s FROM t WHERE s ILIKE '%E%'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+--------------+",
+        "| s            |",
+        "+--------------+",
+        "| Apache Arrow |",
+        "| ÉCOLE        |",
+        "+--------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_similar_to() -> Result<()> {
+    let mut ctx = create_strings_context()?;
+
+    let sql = "SELECT s,
+        s SIMILAR TO '(Apache|Ω)%' AS a,
+        s NOT SIMILAR TO '%[ÉO]%' AS b
+    FROM t";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+--------------+-------+-------+",
+        "| s            | a     | b     |",
+        "+--------------+-------+-------+",
+        "| Apache Arrow | true  | true  |",
+        "| ÉCOLE        | false | false |",
+        "| Ωμέγα        | true  | true  |",
+        "|              |       |       |",
+        "+--------------+-------+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT s FROM t WHERE s SIMILAR TO '%#_%' ESCAPE '#'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["++", "++"];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_between_symmetric() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id BETWEEN SYMMETRIC 40 AND 20";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+",
+        "| t1_id |",
+        "+-------+",
+        "| 22    |",
+        "| 33    |",
+        "+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // the bounds are ordered when the plan is optimized
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    assert_contains!(
+        format!("{:?}", plan),
+        "#t1.t1_id BETWEEN Int64(20) AND Int64(40)"
+    );
+
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id NOT BETWEEN SYMMETRIC 40 AND 20";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+",
+        "| t1_id |",
+        "+-------+",
+        "| 11    |",
+        "| 44    |",
+        "+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id BETWEEN ASYMMETRIC 40 AND 20";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["++", "++"];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn arithmetic_overflow() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, false)]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from(vec![i32::MAX; 1000]))],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![batch.clone()], vec![batch]])?;
+    ctx.register_table("t", Arc::new(table))?;
+
+    // the sum of many values doesn't overflow their type
+    let actual = execute(&mut ctx, "SELECT sum(i) FROM t").await;
+    assert_eq!(actual, vec![vec!["4294967294000"]]);
+
+    ctx.sql("SET datafusion.execution.arithmetic_overflow = 'saturate'")
+        .await?;
+    let actual = execute(&mut ctx, "SELECT i + i, i - i - i - i FROM t LIMIT 1").await;
+    assert_eq!(actual, vec![vec!["2147483647", "-2147483648"]]);
+    // the casts out of range return nulls
+    let actual = execute(&mut ctx, "SELECT CAST(i AS SMALLINT) FROM t LIMIT 1").await;
+    assert_eq!(actual, vec![vec!["NULL"]]);
+
+    ctx.sql("SET datafusion.execution.arithmetic_overflow = 'error'")
+        .await?;
+    let actual = execute(&mut ctx, "SELECT sum(i) FROM t").await;
+    assert_eq!(actual, vec![vec!["4294967294000"]]);
+    let queries = vec![
+        (
+            "SELECT i + i FROM t",
+            "Arithmetic overflow in i@0 + i@0: 2147483647 + 2147483647 is out of the range of Int32",
+        ),
+        (
+            "SELECT CAST(i AS SMALLINT) FROM t",
+            "Arithmetic overflow in CAST(i@0 AS Int16): 2147483647 is out of the range of Int16",
+        ),
+        (
+            "SELECT sum(CAST(i AS BIGINT) * 4294967298) FROM t",
+            "is out of the range of Int64",
+        ),
+    ];
+    for (sql, error) in queries {
+        let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+        assert_contains!(err.to_string(), error);
+    }
+    Ok(())
+}
+
 #[tokio::test]
 async fn projection_same_fields() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -1345,7 +1621,8 @@ async fn csv_query_avg_multi_batch() -> Result<()> {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let results = collect(plan).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.unwrap();
     let batch = &results[0];
     let column = batch.column(0);
     let array = column.as_any().downcast_ref::<Float64Array>().unwrap();
@@ -1610,6 +1887,51 @@ async fn csv_query_window_with_partition_by_order_by() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_window_shared_sort() -> Result<()> {
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "g",
+            Arc::new(Int32Array::from(vec![1, 2, 1, 2, 1])) as ArrayRef,
+        ),
+        (
+            "v",
+            Arc::new(Int32Array::from(vec![5, 3, 1, 4, 2])) as ArrayRef,
+        ),
+    ])?;
+    let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_target_partitions(4));
+    ctx.register_table("t", Arc::new(table))?;
+
+    // the window partitioned by g shares the sort of the window partitioned
+    // by g and sorted by v, only the window sorted by v DESC sorts again
+    let sql = "SELECT g, v, \
+               SUM(v) OVER (PARTITION BY g ORDER BY v) AS running, \
+               COUNT(v) OVER (PARTITION BY g) AS size, \
+               ROW_NUMBER() OVER (ORDER BY v DESC) AS rn \
+               FROM t";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let plan = format!("{}", displayable(plan.as_ref()).indent());
+    assert_eq!(plan.matches("SortExec").count(), 2, "{}", plan);
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+---+---------+------+----+",
+        "| g | v | running | size | rn |",
+        "+---+---+---------+------+----+",
+        "| 1 | 1 | 1       | 3    | 5  |",
+        "| 1 | 2 | 3       | 3    | 4  |",
+        "| 1 | 5 | 8       | 3    | 1  |",
+        "| 2 | 3 | 3       | 2    | 3  |",
+        "| 2 | 4 | 7       | 2    | 2  |",
+        "+---+---+---------+------+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_group_by_int_count() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -2231,6 +2553,29 @@ async fn case_when_else_with_base_expr() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn case_when_coerced_results() -> Result<()> {
+    let mut ctx = create_case_context()?;
+    let sql = "SELECT \
+        CASE c1 WHEN 'a' THEN 1 \
+             WHEN 'b' THEN 2.5 \
+             WHEN 'c' THEN NULL END AS v \
+        FROM t1";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let field = plan.schema().field_with_unqualified_name("v")?;
+    assert_eq!(field.data_type(), &DataType::Float64);
+    assert!(field.is_nullable());
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-----+", "| v   |", "+-----+", "| 1   |", "| 2.5 |", "|     |", "|     |",
+        "+-----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 fn create_case_context() -> Result<ExecutionContext> {
     let mut ctx = ExecutionContext::new();
     let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Utf8, true)]));
@@ -2960,10 +3305,9 @@ async fn csv_explain() {
         ],
         vec!["physical_plan",
              "ProjectionExec: expr=[c1@0 as c1]\
-              \n  CoalesceBatchesExec: target_batch_size=4096\
-              \n    FilterExec: CAST(c2@1 AS Int64) > 10\
-              \n      RepartitionExec: partitioning=RoundRobinBatch(NUM_CORES)\
-              \n        CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None\
+              \n  FilterExec: CAST(c2@1 AS Int64) > 10\
+              \n    RepartitionExec: partitioning=RoundRobinBatch(NUM_CORES)\
+              \n      CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None\
               \n"
     ]];
     assert_eq!(expected, actual);
@@ -3049,7 +3393,8 @@ async fn explain_analyze_baseline_metrics() {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let physical_plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let results = collect(physical_plan.clone()).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(physical_plan.clone(), task_ctx).await.unwrap();
     let formatted = arrow::util::pretty::pretty_format_batches(&results).unwrap();
     println!("Query Output:\n\n{}", formatted);
 
@@ -3168,6 +3513,76 @@ async fn explain_analyze_baseline_metrics() {
         .unwrap();
 }
 
+#[tokio::test]
+async fn progress_of_scan() -> Result<()> {
+    let events = Arc::new(std::sync::Mutex::new(vec![]));
+    let handler: ProgressHandler = {
+        let events = events.clone();
+        Arc::new(move |event| events.lock().unwrap().push(event))
+    };
+    let config = ExecutionConfig::new()
+        .with_target_partitions(1)
+        .with_batch_size(10)
+        .with_progress_interval(2);
+    let mut ctx = ExecutionContext::with_config(config).with_progress_handler(handler);
+    register_aggregate_csv(&mut ctx).await?;
+    let sql = "SELECT c1, c2 FROM aggregate_test_100 WHERE c2 > 0";
+    let results = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(results.iter().map(|b| b.num_rows()).sum::<usize>(), 100);
+
+    let events = events.lock().unwrap();
+    let scan_events = events
+        .iter()
+        .filter(|e| e.operator == "CsvExec")
+        .collect::<Vec<_>>();
+    // 10 batches of 10 rows, reported every 2 batches
+    let rows = scan_events.iter().map(|e| e.rows_out).collect::<Vec<_>>();
+    assert_eq!(rows, vec![20, 40, 60, 80, 100]);
+    assert!(scan_events
+        .windows(2)
+        .all(|w| w[0].bytes_out < w[1].bytes_out));
+    let scan_total = scan_events[0].scan_total.unwrap();
+    assert_eq!(scan_total.files, 1);
+    assert!(scan_total.bytes > 0);
+
+    // the other operators report their progress without the files to scan
+    assert!(events
+        .iter()
+        .any(|e| e.operator == "FilterExec" && e.scan_total.is_none()));
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_metrics_table() -> Result<()> {
+    let config = ExecutionConfig::new().with_target_partitions(1);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_aggregate_csv(&mut ctx).await?;
+    let sql = "SELECT c1, count(*) FROM aggregate_test_100 \
+               WHERE c13 != 'C2GT5KVyOPZpgKVl110TyZO0NcJ434' GROUP BY c1";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    collect(plan.clone(), ctx.task_ctx()).await?;
+
+    let batch = metrics::metrics_batch(&metrics::collect_tree(plan.as_ref()))?;
+    let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+    ctx.register_table("query_metrics", Arc::new(table))?;
+    let sql = "SELECT operator, sum(value) FROM query_metrics \
+               WHERE metric = 'output_rows' AND operator IN ('CsvExec', 'FilterExec') \
+               GROUP BY operator ORDER BY operator";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+------------+--------------------------+",
+        "| operator   | SUM(query_metrics.value) |",
+        "+------------+--------------------------+",
+        "| CsvExec    | 100                      |",
+        "| FilterExec | 99                       |",
+        "+------------+--------------------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_explain_plans() {
     // This test verify the look of each plan in its full cycle plan creation
@@ -3333,7 +3748,8 @@ async fn csv_explain_plans() {
     //
     // Execute plan
     let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
-    let results = collect(plan).await.expect(&msg);
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.expect(&msg);
     let actual = result_vec(&results);
     // flatten to a single string
     let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
@@ -3360,8 +3776,57 @@ async fn csv_explain_verbose() {
     assert_contains!(&actual, "physical_plan");
     assert_contains!(&actual, "#aggregate_test_100.c2 > Int64(10)");
 
+    // the final logical plan is also shown with the qualified schemas
+    assert_contains!(&actual, "logical_plan_with_schema");
+    assert_contains!(&actual, "[aggregate_test_100.c1:Utf8]");
+
     // ensure the "same text as above" optimization is working
     assert_contains!(actual, "SAME TEXT AS ABOVE");
+
+    // the physical plans show the statistics of the operators
+    assert_contains!(actual, "FilterExec: CAST(c2@1 AS Int64) > 10, statistics=[");
+}
+
+#[tokio::test]
+async fn csv_explain_statistics() {
+    let sql = "EXPLAIN SELECT c1 FROM aggregate_test_100 where c2 > 10";
+
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let actual = execute(&mut ctx, sql).await;
+    let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
+    assert_not_contains!(actual, "statistics=[");
+
+    let config = ExecutionConfig::new().with_explain_statistics(true);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let actual = execute(&mut ctx, sql).await;
+    let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
+    assert_contains!(actual, "FilterExec: CAST(c2@1 AS Int64) > 10, statistics=[");
+}
+
+#[tokio::test]
+async fn csv_explain_graphviz() {
+    let sql = "EXPLAIN (FORMAT GRAPHVIZ) SELECT c1 FROM aggregate_test_100 where c2 > 10";
+
+    let mut ctx = ExecutionContext::new();
+    register_aggregate_csv_by_sql(&mut ctx).await;
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 2);
+
+    assert_eq!(actual[0][0], "logical_plan");
+    assert_contains!(&actual[0][1], "digraph {");
+    assert_contains!(
+        &actual[0][1],
+        "[shape=box label=\"Filter: #aggregate_test_100.c2 > Int64(10)\"]"
+    );
+
+    assert_eq!(actual[1][0], "physical_plan");
+    assert!(actual[1][1].starts_with("digraph {"), "{}", actual[1][1]);
+    assert_contains!(
+        &actual[1][1],
+        "[shape=box label=\"FilterExec: CAST(c2@1 AS Int64) > 10\"]"
+    );
 }
 
 #[tokio::test]
@@ -3530,7 +3995,8 @@ async fn csv_explain_verbose_plans() {
     //
     // Execute plan
     let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
-    let results = collect(plan).await.expect(&msg);
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.expect(&msg);
     let actual = result_vec(&results);
     // flatten to a single string
     let actual = actual.into_iter().map(|r| r.join("\t")).collect::<String>();
@@ -3628,58 +4094,379 @@ order by
     Ok(())
 }
 
-fn get_tpch_table_schema(table: &str) -> Schema {
-    match table {
-        "customer" => Schema::new(vec![
-            Field::new("c_custkey", DataType::Int64, false),
-            Field::new("c_name", DataType::Utf8, false),
-            Field::new("c_address", DataType::Utf8, false),
-            Field::new("c_nationkey", DataType::Int64, false),
-            Field::new("c_phone", DataType::Utf8, false),
-            Field::new("c_acctbal", DataType::Float64, false),
-            Field::new("c_mktsegment", DataType::Utf8, false),
-            Field::new("c_comment", DataType::Utf8, false),
-        ]),
-
-        "orders" => Schema::new(vec![
-            Field::new("o_orderkey", DataType::Int64, false),
-            Field::new("o_custkey", DataType::Int64, false),
-            Field::new("o_orderstatus", DataType::Utf8, false),
-            Field::new("o_totalprice", DataType::Float64, false),
-            Field::new("o_orderdate", DataType::Date32, false),
-            Field::new("o_orderpriority", DataType::Utf8, false),
-            Field::new("o_clerk", DataType::Utf8, false),
-            Field::new("o_shippriority", DataType::Int32, false),
-            Field::new("o_comment", DataType::Utf8, false),
-        ]),
-
-        "lineitem" => Schema::new(vec![
-            Field::new("l_orderkey", DataType::Int64, false),
-            Field::new("l_partkey", DataType::Int64, false),
-            Field::new("l_suppkey", DataType::Int64, false),
-            Field::new("l_linenumber", DataType::Int32, false),
-            Field::new("l_quantity", DataType::Float64, false),
-            Field::new("l_extendedprice", DataType::Float64, false),
-            Field::new("l_discount", DataType::Float64, false),
-            Field::new("l_tax", DataType::Float64, false),
-            Field::new("l_returnflag", DataType::Utf8, false),
-            Field::new("l_linestatus", DataType::Utf8, false),
-            Field::new("l_shipdate", DataType::Date32, false),
-            Field::new("l_commitdate", DataType::Date32, false),
-            Field::new("l_receiptdate", DataType::Date32, false),
-            Field::new("l_shipinstruct", DataType::Utf8, false),
-            Field::new("l_shipmode", DataType::Utf8, false),
-            Field::new("l_comment", DataType::Utf8, false),
-        ]),
+#[tokio::test]
+async fn explain_filter_push_down_left_join() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let sql = "SELECT t1_id, t1_name, t2_name FROM t1 LEFT JOIN t2 ON t1_id = t2_id \
+               WHERE t1_id > 11 AND t2_name IS NULL";
 
-        "nation" => Schema::new(vec![
-            Field::new("n_nationkey", DataType::Int64, false),
-            Field::new("n_name", DataType::Utf8, false),
-            Field::new("n_regionkey", DataType::Int64, false),
-            Field::new("n_comment", DataType::Utf8, false),
-        ]),
+    // the predicate on the non-preserved side remains above the join
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_id, #t1.t1_name, #t2.t2_name\
+    \n  Filter: #t2.t2_name IS NULL\
+    \n    Join: #t1.t1_id = #t2.t2_id\
+    \n      Filter: #t1.t1_id > Int64(11)\
+    \n        TableScan: t1 projection=Some([0, 1])\
+    \n      TableScan: t2 projection=Some([0, 1])";
+    assert_eq!(format!("{:?}", plan), expected);
 
-        _ => unimplemented!(),
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+---------+---------+",
+        "| t1_id | t1_name | t2_name |",
+        "+-------+---------+---------+",
+        "| 33    | c       |         |",
+        "+-------+---------+---------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_infer_join_predicates() -> Result<()> {
+    let create_context = |config: ExecutionConfig| -> Result<ExecutionContext> {
+        let mut ctx = ExecutionContext::with_config(config);
+        let tables = vec![
+            ("a", vec![1, 5, 5, 7], vec![10, 50, 51, 70]),
+            ("b", vec![5, 7, 5, 9], vec![500, 700, 501, 900]),
+            ("c", vec![9, 5, 1], vec![9000, 5000, 1000]),
+        ];
+        for (name, keys, values) in tables {
+            let schema = Arc::new(Schema::new(vec![
+                Field::new("k", DataType::Int64, true),
+                Field::new(&format!("{}_v", name), DataType::Int64, true),
+            ]));
+            let batch = RecordBatch::try_new(
+                schema.clone(),
+                vec![
+                    Arc::new(Int64Array::from(keys)),
+                    Arc::new(Int64Array::from(values)),
+                ],
+            )?;
+            ctx.register_table(
+                name,
+                Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+            )?;
+        }
+        Ok(ctx)
+    };
+    let sql = "SELECT a.k, a_v, b_v, c_v FROM a, b, c \
+               WHERE a.k = b.k AND b.k = c.k AND a.k = 5";
+
+    // the equality on a.k is propagated to b.k and c.k through the join keys,
+    // and each one is pushed to its own scan
+    let mut ctx =
+        create_context(ExecutionConfig::new().with_disabled_rules(&["join_reorder"]))?;
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #a.k, #a.a_v, #b.b_v, #c.c_v\
+    \n  Join: #b.k = #c.k\
+    \n    Join: #a.k = #b.k\
+    \n      Filter: #a.k = Int64(5)\
+    \n        TableScan: a projection=Some([0, 1])\
+    \n      Filter: #b.k = Int64(5)\
+    \n        TableScan: b projection=Some([0, 1])\
+    \n    Filter: #c.k = Int64(5)\
+    \n      TableScan: c projection=Some([0, 1])";
+    assert_eq!(format!("{:?}", plan), expected);
+
+    let expected_rows = vec![
+        "+---+-----+-----+------+",
+        "| k | a_v | b_v | c_v  |",
+        "+---+-----+-----+------+",
+        "| 5 | 50  | 500 | 5000 |",
+        "| 5 | 50  | 501 | 5000 |",
+        "| 5 | 51  | 500 | 5000 |",
+        "| 5 | 51  | 501 | 5000 |",
+        "+---+-----+-----+------+",
+    ];
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_sorted_eq!(expected_rows, &actual);
+
+    // the same results with only the written predicates
+    let mut ctx = create_context(
+        ExecutionConfig::new().with_disabled_rules(&["infer_predicates"]),
+    )?;
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    assert_not_contains!(format!("{:?}", plan), "#c.k = Int64(5)");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_sorted_eq!(expected_rows, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_constant_folding() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+
+    // constant subtrees are evaluated and `true AND x` is simplified to `x`
+    let sql = "SELECT t1_id FROM t1 WHERE 1 = 1 AND t1_id > 2 + 20";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_id\
+    \n  Filter: #t1.t1_id > Int64(22) AS Int64(1) = Int64(1) AND t1.t1_id > Int64(2) + Int64(20)\
+    \n    TableScan: t1 projection=Some([0])";
+    assert_eq!(format!("{:?}", plan), expected);
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+",
+        "| t1_id |",
+        "+-------+",
+        "| 33    |",
+        "| 44    |",
+        "+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // a filter that is always false is replaced by an empty relation
+    let sql = "SELECT t1_id FROM t1 WHERE t1_id > 11 AND 1 = 0";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_id\
+    \n  EmptyRelation";
+    assert_eq!(format!("{:?}", plan), expected);
+    assert!(execute(&mut ctx, sql).await.is_empty());
+
+    // the CASE branches that are never taken are removed
+    let sql = "SELECT CASE WHEN 1 > 2 THEN 'x' ELSE t1_name END AS n FROM t1";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_name AS n\
+    \n  TableScan: t1 projection=Some([1])";
+    assert_eq!(format!("{:?}", plan), expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_udf_folding_and_filter_push_down() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+    let add_one = |name: &str| {
+        create_udf(
+            name,
+            vec![DataType::Int64],
+            Arc::new(DataType::Int64),
+            Volatility::Immutable,
+            make_scalar_function(|args: &[ArrayRef]| {
+                let input = args[0].as_any().downcast_ref::<Int64Array>().unwrap();
+                let array: Int64Array = input.iter().map(|v| v.map(|v| v + 1)).collect();
+                Ok(Arc::new(array) as ArrayRef)
+            }),
+        )
+    };
+    ctx.register_udf(add_one("add_one"));
+    ctx.register_udf(add_one("expensive_add_one").with_filter_pushdown(false));
+
+    // the call with a literal argument is evaluated at plan time, and the filter
+    // calling the pure UDF is pushed below the join
+    let sql = "SELECT t1_id, t2_name FROM t1 JOIN t2 ON t1_id = t2_id \
+               WHERE add_one(t1_id) > add_one(20)";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_id, #t2.t2_name\
+    \n  Join: #t1.t1_id = #t2.t2_id\
+    \n    Filter: add_one(#t1.t1_id) > Int64(21)\
+    \n      TableScan: t1 projection=Some([0])\
+    \n    TableScan: t2 projection=Some([0, 1])";
+    assert_eq!(format!("{:?}", plan), expected);
+
+    let expected_rows = vec![
+        "+-------+---------+",
+        "| t1_id | t2_name |",
+        "+-------+---------+",
+        "| 22    | y       |",
+        "| 44    | x       |",
+        "+-------+---------+",
+    ];
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_sorted_eq!(expected_rows, &actual);
+
+    // the filter calling a UDF without filter pushdown support stays above the join
+    let sql = "SELECT t1_id, t2_name FROM t1 JOIN t2 ON t1_id = t2_id \
+               WHERE expensive_add_one(t1_id) > 21";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let expected = "\
+    Projection: #t1.t1_id, #t2.t2_name\
+    \n  Filter: expensive_add_one(#t1.t1_id) > Int64(21)\
+    \n    Join: #t1.t1_id = #t2.t2_id\
+    \n      TableScan: t1 projection=Some([0])\
+    \n      TableScan: t2 projection=Some([0, 1])";
+    assert_eq!(format!("{:?}", plan), expected);
+
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_batches_sorted_eq!(expected_rows, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_redundant_operators_removed() -> Result<()> {
+    let mut ctx = create_join_context("t1_id", "t2_id")?;
+
+    async fn physical_plan(ctx: &mut ExecutionContext, sql: &str) -> Result<String> {
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        Ok(format!("{}", displayable(plan.as_ref()).indent()))
+    }
+
+    // the outer sort is satisfied by the inner one, and the projections of
+    // the subquery only forward its columns
+    let sql = "SELECT t1_id, t1_name FROM \
+               (SELECT t1_id, t1_name FROM t1 ORDER BY t1_id, t1_name) AS a \
+               ORDER BY t1_id";
+    let plan = physical_plan(&mut ctx, sql).await?;
+    assert_eq!(plan.matches("SortExec").count(), 1, "{}", plan);
+    assert_contains!(
+        &plan,
+        "SortExec: [t1_id@0 ASC NULLS LAST,t1_name@1 ASC NULLS LAST]"
+    );
+    assert_not_contains!(&plan, "ProjectionExec");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+---------+",
+        "| t1_id | t1_name |",
+        "+-------+---------+",
+        "| 11    | a       |",
+        "| 22    | b       |",
+        "| 33    | c       |",
+        "| 44    | d       |",
+        "+-------+---------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // the outer limit is larger than the inner one
+    let sql = "SELECT t1_id FROM (SELECT t1_id FROM t1 LIMIT 2) AS a LIMIT 10";
+    let plan = physical_plan(&mut ctx, sql).await?;
+    assert_eq!(plan.matches("GlobalLimitExec").count(), 1, "{}", plan);
+    assert_contains!(&plan, "GlobalLimitExec: limit=2");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-------+",
+        "| t1_id |",
+        "+-------+",
+        "| 11    |",
+        "| 22    |",
+        "+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // a projection renaming a column changes the schema and is kept
+    let sql = "SELECT t1_id AS id, t1_name FROM t1";
+    let plan = physical_plan(&mut ctx, sql).await?;
+    assert_contains!(
+        &plan,
+        "ProjectionExec: expr=[t1_id@0 as id, t1_name@1 as t1_name]"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn selective_filter_collects_join_left() -> Result<()> {
+    let config = ExecutionConfig::new()
+        .with_target_partitions(4)
+        .with_collect_left_join_rows(10);
+    let mut ctx = ExecutionContext::with_config(config);
+
+    // `v` is null for 2 of the 100 rows of t1
+    let t1_schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("v", DataType::Int32, true),
+    ]));
+    let t1_data = RecordBatch::try_new(
+        t1_schema.clone(),
+        vec![
+            Arc::new(Int32Array::from((0..100).collect::<Vec<_>>())),
+            Arc::new(Int32Array::from(
+                (0..100)
+                    .map(|i| if i % 50 == 0 { None } else { Some(i) })
+                    .collect::<Vec<_>>(),
+            )),
+        ],
+    )?;
+    let t1_table = MemTable::try_new(t1_schema, vec![vec![t1_data]])?;
+    ctx.register_table("t1", Arc::new(t1_table))?;
+
+    let t2_schema = Arc::new(Schema::new(vec![Field::new("id", DataType::Int32, false)]));
+    let t2_data = RecordBatch::try_new(
+        t2_schema.clone(),
+        vec![Arc::new(Int32Array::from((0..200).collect::<Vec<_>>()))],
+    )?;
+    let t2_table = MemTable::try_new(t2_schema, vec![vec![t2_data]])?;
+    ctx.register_table("t2", Arc::new(t2_table))?;
+
+    async fn physical_plan(ctx: &mut ExecutionContext, sql: &str) -> Result<String> {
+        let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+        let plan = ctx.create_physical_plan(&plan).await?;
+        Ok(format!("{}", displayable(plan.as_ref()).indent()))
+    }
+
+    // the 100 rows of t1 are too many to be collected
+    let sql = "SELECT t1.id FROM t1 JOIN t2 ON t1.id = t2.id";
+    let plan = physical_plan(&mut ctx, sql).await?;
+    assert_contains!(&plan, "HashJoinExec: mode=Partitioned");
+
+    // the filter is estimated to keep 2 rows of t1
+    let sql = "SELECT t1.id FROM t1 JOIN t2 ON t1.id = t2.id \
+               WHERE t1.v IS NULL ORDER BY t1.id";
+    let plan = physical_plan(&mut ctx, sql).await?;
+    assert_contains!(&plan, "HashJoinExec: mode=CollectLeft");
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+----+", "| id |", "+----+", "| 0  |", "| 50 |", "+----+"];
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+fn get_tpch_table_schema(table: &str) -> Schema {
+    match table {
+        "customer" => Schema::new(vec![
+            Field::new("c_custkey", DataType::Int64, false),
+            Field::new("c_name", DataType::Utf8, false),
+            Field::new("c_address", DataType::Utf8, false),
+            Field::new("c_nationkey", DataType::Int64, false),
+            Field::new("c_phone", DataType::Utf8, false),
+            Field::new("c_acctbal", DataType::Float64, false),
+            Field::new("c_mktsegment", DataType::Utf8, false),
+            Field::new("c_comment", DataType::Utf8, false),
+        ]),
+
+        "orders" => Schema::new(vec![
+            Field::new("o_orderkey", DataType::Int64, false),
+            Field::new("o_custkey", DataType::Int64, false),
+            Field::new("o_orderstatus", DataType::Utf8, false),
+            Field::new("o_totalprice", DataType::Float64, false),
+            Field::new("o_orderdate", DataType::Date32, false),
+            Field::new("o_orderpriority", DataType::Utf8, false),
+            Field::new("o_clerk", DataType::Utf8, false),
+            Field::new("o_shippriority", DataType::Int32, false),
+            Field::new("o_comment", DataType::Utf8, false),
+        ]),
+
+        "lineitem" => Schema::new(vec![
+            Field::new("l_orderkey", DataType::Int64, false),
+            Field::new("l_partkey", DataType::Int64, false),
+            Field::new("l_suppkey", DataType::Int64, false),
+            Field::new("l_linenumber", DataType::Int32, false),
+            Field::new("l_quantity", DataType::Float64, false),
+            Field::new("l_extendedprice", DataType::Float64, false),
+            Field::new("l_discount", DataType::Float64, false),
+            Field::new("l_tax", DataType::Float64, false),
+            Field::new("l_returnflag", DataType::Utf8, false),
+            Field::new("l_linestatus", DataType::Utf8, false),
+            Field::new("l_shipdate", DataType::Date32, false),
+            Field::new("l_commitdate", DataType::Date32, false),
+            Field::new("l_receiptdate", DataType::Date32, false),
+            Field::new("l_shipinstruct", DataType::Utf8, false),
+            Field::new("l_shipmode", DataType::Utf8, false),
+            Field::new("l_comment", DataType::Utf8, false),
+        ]),
+
+        "nation" => Schema::new(vec![
+            Field::new("n_nationkey", DataType::Int64, false),
+            Field::new("n_name", DataType::Utf8, false),
+            Field::new("n_regionkey", DataType::Int64, false),
+            Field::new("n_comment", DataType::Utf8, false),
+        ]),
+
+        _ => unimplemented!(),
     }
 }
 
@@ -3860,7 +4647,8 @@ async fn execute_to_batches(ctx: &mut ExecutionContext, sql: &str) -> Vec<Record
     let plan = ctx.create_physical_plan(&plan).await.expect(&msg);
 
     let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
-    let results = collect(plan).await.expect(&msg);
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.expect(&msg);
 
     assert_eq!(logical_schema.as_ref(), optimized_logical_schema.as_ref());
     results
@@ -3878,19 +4666,42 @@ fn col_str(column: &ArrayRef, row_index: usize) -> String {
         return "NULL".to_string();
     }
 
-    // Special case ListArray as there is no pretty print support for it yet
-    if let DataType::FixedSizeList(_, n) = column.data_type() {
+    // Special case lists as there is no pretty print support for them yet
+    let list = match column.data_type() {
+        DataType::List(_) => Some(
+            column
+                .as_any()
+                .downcast_ref::<ListArray>()
+                .unwrap()
+                .value(row_index),
+        ),
+        DataType::FixedSizeList(_, _) => Some(
+            column
+                .as_any()
+                .downcast_ref::<FixedSizeListArray>()
+                .unwrap()
+                .value(row_index),
+        ),
+        _ => None,
+    };
+    if let Some(array) = list {
+        let r = (0..array.len())
+            .map(|i| col_str(&array, i))
+            .collect::<Vec<_>>();
+        return format!("[{}]", r.join(","));
+    }
+
+    // Special case FixedSizeBinaryArray as there is no pretty print support for it yet
+    if let DataType::FixedSizeBinary(_) = column.data_type() {
         let array = column
             .as_any()
-            .downcast_ref::<FixedSizeListArray>()
-            .unwrap()
-            .value(row_index);
-
-        let mut r = Vec::with_capacity(*n as usize);
-        for i in 0..*n {
-            r.push(col_str(&array, i as usize));
-        }
-        return format!("[{}]", r.join(","));
+            .downcast_ref::<FixedSizeBinaryArray>()
+            .unwrap();
+        return array
+            .value(row_index)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
     }
 
     array_value_to_string(column, row_index)
@@ -4047,6 +4858,206 @@ async fn query_array() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_unnest() -> Result<()> {
+    let tmp_dir = tempfile::tempdir()?;
+    let path = tmp_dir.path().join("orders.json");
+    std::fs::write(
+        &path,
+        r#"{"id": 1, "tags": ["a", "b"], "amounts": [1, 2, 3]}
+{"id": 2, "tags": [], "amounts": [4]}
+{"id": 3, "tags": null, "amounts": [5, 6]}
+{"id": 4, "tags": ["b", "b", "c"], "amounts": []}
+"#,
+    )?;
+
+    let mut ctx = ExecutionContext::new();
+    let options = ListingOptions {
+        file_extension: ".json".to_owned(),
+        ..ListingOptions::new(Arc::new(JsonFormat::default()))
+    };
+    ctx.register_listing_table("orders", path.to_str().unwrap(), options, None)
+        .await?;
+
+    // empty and null lists produce no rows
+    let sql = "SELECT id, unnest(tags) AS tag FROM orders ORDER BY id, tag";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "a"],
+        vec!["1", "b"],
+        vec!["4", "b"],
+        vec!["4", "b"],
+        vec!["4", "c"],
+    ];
+    assert_eq!(expected, actual);
+
+    // aggregate over the exploded rows
+    let sql = "SELECT tag, COUNT(*) FROM \
+               (SELECT unnest(tags) AS tag FROM orders) AS t \
+               GROUP BY tag ORDER BY tag";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["a", "1"], vec!["b", "3"], vec!["c", "1"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT id, SUM(amount) FROM orders \
+               CROSS JOIN unnest(amounts) AS u(amount) \
+               GROUP BY id ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1", "6"], vec!["2", "4"], vec!["3", "11"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT x FROM unnest(array(3, 1, 2)) AS u(x) ORDER BY x";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1"], vec!["2"], vec!["3"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT id, array_length(tags), array_contains(tags, 'b'), \
+               array_position(tags, 'b') FROM orders ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "2", "true", "2"],
+        vec!["2", "0", "false", "NULL"],
+        vec!["3", "NULL", "NULL", "NULL"],
+        vec!["4", "3", "true", "1"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+/// `lookup(n)`: the keys from 0 to `n` (exclusive) and their values, recording
+/// the projection and the limit of the scans of its tables
+struct Lookup {
+    scans: Arc<Mutex<Vec<(Option<Vec<usize>>, Option<usize>)>>>,
+}
+
+struct LookupTable {
+    table: MemTable,
+    scans: Arc<Mutex<Vec<(Option<Vec<usize>>, Option<usize>)>>>,
+}
+
+impl TableFunction for Lookup {
+    fn call(&self, args: &[ScalarValue]) -> Result<Arc<dyn TableProvider>> {
+        let n = match args {
+            [ScalarValue::Int64(Some(n))] => *n,
+            _ => {
+                return Err(DataFusionError::Plan(format!(
+                    "lookup takes an integer, got {:?}",
+                    args
+                )))
+            }
+        };
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("k", DataType::Int64, false),
+            Field::new("v", DataType::Utf8, false),
+        ]));
+        let batch = RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(Int64Array::from_iter_values(0..n)),
+                Arc::new(StringArray::from_iter_values(
+                    (0..n).map(|k| format!("v{}", k)),
+                )),
+            ],
+        )?;
+        Ok(Arc::new(LookupTable {
+            table: MemTable::try_new(schema, vec![vec![batch]])?,
+            scans: self.scans.clone(),
+        }))
+    }
+}
+
+#[async_trait]
+impl TableProvider for LookupTable {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_mut_any(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn schema(&self) -> SchemaRef {
+        self.table.schema()
+    }
+
+    async fn scan(
+        &self,
+        projection: &Option<Vec<usize>>,
+        batch_size: usize,
+        filters: &[Expr],
+        limit: Option<usize>,
+    ) -> Result<Arc<dyn ExecutionPlan>> {
+        self.scans.lock().unwrap().push((projection.clone(), limit));
+        self.table
+            .scan(projection, batch_size, filters, limit)
+            .await
+    }
+}
+
+#[tokio::test]
+async fn query_table_functions() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+
+    let sql = "SELECT * FROM generate_series(1, 5)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1"], vec!["2"], vec!["3"], vec!["4"], vec!["5"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT generate_series FROM GENERATE_SERIES(5 + 5, 1, -3)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["10"], vec!["7"], vec!["4"], vec!["1"]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT COUNT(*), SUM(n) FROM generate_series(1, 1000000) AS s(n)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["1000000", "500000500000"]];
+    assert_eq!(expected, actual);
+
+    // join against a real table
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("name", DataType::Utf8, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 4])),
+            Arc::new(StringArray::from(vec!["a", "b", "c", "d"])),
+        ],
+    )?;
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![batch]])?))?;
+    let sql = "SELECT t.name, s.n FROM t \
+               JOIN generate_series(2, 8, 2) AS s(n) ON t.id = s.n ORDER BY s.n";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["b", "2"], vec!["d", "4"]];
+    assert_eq!(expected, actual);
+
+    // the projection and the limit reach the table of the function
+    let scans = Arc::new(Mutex::new(vec![]));
+    ctx.register_udtf(
+        "lookup",
+        Arc::new(Lookup {
+            scans: scans.clone(),
+        }),
+    );
+    let sql = "SELECT v FROM lookup(10) LIMIT 3";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["v0"], vec!["v1"], vec!["v2"]];
+    assert_eq!(expected, actual);
+    assert_eq!(vec![(Some(vec![1]), Some(3))], *scans.lock().unwrap());
+
+    let sql = "SELECT * FROM unknown_function(1)";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_eq!(
+        "Error during planning: There is no table function named 'unknown_function'",
+        err.to_string()
+    );
+    let sql = "SELECT * FROM lookup(t.id)";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_contains!(err.to_string(), "must be constants");
+    Ok(())
+}
+
 #[tokio::test]
 async fn csv_query_sum_cast() {
     let mut ctx = ExecutionContext::new();
@@ -4345,7 +5356,89 @@ async fn query_count_distinct() -> Result<()> {
         "| 3                       |",
         "+-------------------------+",
     ];
-    assert_batches_eq!(expected, &actual);
+    assert_batches_eq!(expected, &actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_count_distinct_partitioned() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("g", DataType::Utf8, false),
+        Field::new("u", DataType::Int32, true),
+    ]));
+    let partition = |g: Vec<&str>, u: Vec<Option<i32>>| {
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(StringArray::from(g)),
+                Arc::new(Int32Array::from(u)),
+            ],
+        )
+    };
+    let partitions = vec![
+        vec![partition(
+            vec!["a", "a", "b", "a"],
+            vec![Some(1), Some(2), Some(1), None],
+        )?],
+        vec![partition(
+            vec!["a", "b", "b"],
+            vec![Some(1), Some(3), Some(3)],
+        )?],
+        vec![partition(
+            vec!["a", "b", "b"],
+            vec![Some(4), None, Some(1)],
+        )?],
+    ];
+
+    let config = ExecutionConfig::new().with_target_partitions(4);
+    let mut ctx = ExecutionContext::with_config(config.clone());
+    let table = MemTable::try_new(schema.clone(), partitions.clone())?;
+    ctx.register_table("events", Arc::new(table))?;
+    let mut unoptimized_ctx =
+        ExecutionContext::with_config(config.with_optimizer_rules(vec![]));
+    let table = MemTable::try_new(schema.clone(), partitions)?;
+    unoptimized_ctx.register_table("events", Arc::new(table))?;
+
+    // the distinct values are deduplicated by a partitioned aggregation
+    let sql = "SELECT COUNT(DISTINCT u) FROM events";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let plan = format!("{}", displayable(plan.as_ref()).indent());
+    assert_contains!(&plan, "HashAggregateExec: mode=Partial, gby=[u@0 as u]");
+    let expected = vec![
+        "+--------------------------+",
+        "| COUNT(DISTINCT events.u) |",
+        "+--------------------------+",
+        "| 4                        |",
+        "+--------------------------+",
+    ];
+    assert_batches_eq!(expected, &execute_to_batches(&mut ctx, sql).await);
+    assert_batches_eq!(
+        expected,
+        &execute_to_batches(&mut unoptimized_ctx, sql).await
+    );
+
+    let sql = "SELECT g, COUNT(DISTINCT u), MAX(u), SUM(u) FROM events GROUP BY g";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let plan = format!("{}", displayable(plan.as_ref()).indent());
+    assert_contains!(
+        &plan,
+        "HashAggregateExec: mode=Partial, gby=[g@0 as g, u@1 as u]"
+    );
+    let expected = vec![
+        "+---+--------------------------+---------------+---------------+",
+        "| g | COUNT(DISTINCT events.u) | MAX(events.u) | SUM(events.u) |",
+        "+---+--------------------------+---------------+---------------+",
+        "| a | 3                        | 4             | 8             |",
+        "| b | 2                        | 3             | 8             |",
+        "+---+--------------------------+---------------+---------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &execute_to_batches(&mut ctx, sql).await);
+    assert_batches_sorted_eq!(
+        expected,
+        &execute_to_batches(&mut unoptimized_ctx, sql).await
+    );
     Ok(())
 }
 
@@ -4587,6 +5680,80 @@ async fn query_on_string_dictionary() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_on_string_dictionary_without_unpacking() -> Result<()> {
+    let dict_type =
+        DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8));
+    let array = vec![Some("one"), None, Some("three"), Some("one")]
+        .into_iter()
+        .collect::<DictionaryArray<Int32Type>>();
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "id",
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])) as ArrayRef,
+        ),
+        ("d1", Arc::new(array) as ArrayRef),
+    ])?;
+    let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("test", Arc::new(table))?;
+
+    let batch = RecordBatch::try_from_iter(vec![
+        (
+            "n",
+            Arc::new(StringArray::from(vec!["one", "two", "three"])) as ArrayRef,
+        ),
+        (
+            "v",
+            Arc::new(Int32Array::from(vec![10, 20, 30])) as ArrayRef,
+        ),
+    ])?;
+    let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+    ctx.register_table("names", Arc::new(table))?;
+
+    // the filtered dictionary column is still encoded
+    let sql = "SELECT id, d1 FROM test WHERE d1 <> 'one'";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(actual[0].schema().field(1).data_type(), &dict_type);
+    let expected = vec![
+        "+----+-------+",
+        "| id | d1    |",
+        "+----+-------+",
+        "| 3  | three |",
+        "+----+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT id, d1 > 'p' AS gt, 'p' >= d1 AS lt_eq FROM test";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+-------+-------+",
+        "| id | gt    | lt_eq |",
+        "+----+-------+-------+",
+        "| 1  | false | true  |",
+        "| 2  |       |       |",
+        "| 3  | true  | false |",
+        "| 4  | false | true  |",
+        "+----+-------+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // dictionaries are joined on their values with plain columns
+    let sql = "SELECT id, d1, v FROM test JOIN names ON d1 = n";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----+-------+----+",
+        "| id | d1    | v  |",
+        "+----+-------+----+",
+        "| 1  | one   | 10 |",
+        "| 3  | three | 30 |",
+        "| 4  | one   | 10 |",
+        "+----+-------+----+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_without_from() -> Result<()> {
     // Test for SELECT <expression> without FROM.
@@ -4615,6 +5782,44 @@ async fn query_without_from() -> Result<()> {
     ];
     assert_batches_eq!(expected, &actual);
 
+    // the fields of the single row are named after the aliases
+    let sql = "SELECT upper('a') AS up, now() IS NOT NULL AS has_now, 1 + 2 AS three";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].num_rows(), 1);
+    let names = actual[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|field| field.name().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["up", "has_now", "three"]);
+    let expected = vec![
+        "+----+---------+-------+",
+        "| up | has_now | three |",
+        "+----+---------+-------+",
+        "| A  | true    | 3     |",
+        "+----+---------+-------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // an always false filter produces no row
+    let sql = "SELECT 1 AS one WHERE 1 = 2";
+    let plan = ctx.create_logical_plan(sql)?;
+    let plan = ctx.optimize(&plan)?;
+    match &plan {
+        LogicalPlan::Projection(Projection { input, .. }) => assert!(matches!(
+            input.as_ref(),
+            LogicalPlan::EmptyRelation(EmptyRelation {
+                produce_one_row: false,
+                ..
+            })
+        )),
+        _ => panic!("expected a projection, got {:?}", plan),
+    }
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(actual.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
     Ok(())
 }
 
@@ -4692,6 +5897,145 @@ async fn query_cte_incorrect() -> Result<()> {
     Ok(())
 }
 
+/// A context with the table `edges` of the graph 1 -> 2 -> 3 -> 1, 3 -> 4
+fn edges_context(config: ExecutionConfig) -> Result<ExecutionContext> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("src", DataType::Int64, false),
+        Field::new("dst", DataType::Int64, false),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int64Array::from(vec![1, 2, 3, 3])),
+            Arc::new(Int64Array::from(vec![2, 3, 1, 4])),
+        ],
+    )?;
+    let mut ctx = ExecutionContext::with_config(config);
+    ctx.register_table(
+        "edges",
+        Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+    )?;
+    Ok(ctx)
+}
+
+#[tokio::test]
+async fn query_recursive_cte() -> Result<()> {
+    let mut ctx = edges_context(ExecutionConfig::new())?;
+
+    // transitive closure, terminating on the cycle of the graph as the rows
+    // already produced are dropped
+    let sql = "WITH RECURSIVE reach(src, dst) AS ( \
+        SELECT src, dst FROM edges \
+        UNION \
+        SELECT reach.src, edges.dst FROM reach JOIN edges ON reach.dst = edges.src \
+    ) SELECT src, dst FROM reach ORDER BY src, dst";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-----+-----+",
+        "| src | dst |",
+        "+-----+-----+",
+        "| 1   | 1   |",
+        "| 1   | 2   |",
+        "| 1   | 3   |",
+        "| 1   | 4   |",
+        "| 2   | 1   |",
+        "| 2   | 2   |",
+        "| 2   | 3   |",
+        "| 2   | 4   |",
+        "| 3   | 1   |",
+        "| 3   | 2   |",
+        "| 3   | 3   |",
+        "| 3   | 4   |",
+        "+-----+-----+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    // counter, with the recursive term cast to the types of the static term
+    let sql = "WITH RECURSIVE t(n) AS ( \
+        VALUES (1) \
+        UNION ALL \
+        SELECT CAST(n + 1 AS INT) FROM t WHERE n < 5 \
+    ) SELECT n FROM t";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+---+", "| n |", "+---+", "| 1 |", "| 2 |", "| 3 |", "| 4 |", "| 5 |", "+---+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_recursive_cte_max_iterations() -> Result<()> {
+    let config = ExecutionConfig::new().with_recursive_query_max_iterations(10);
+    let mut ctx = edges_context(config)?;
+
+    // walking the cycle of the graph never ends when keeping all the rows
+    let sql = "WITH RECURSIVE walk(node) AS ( \
+        SELECT 1 \
+        UNION ALL \
+        SELECT edges.dst FROM walk JOIN edges ON walk.node = edges.src \
+    ) SELECT count(*) FROM walk";
+    let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+    assert_contains!(
+        err.to_string(),
+        "Recursive query walk did not reach a fixpoint within 10 iterations"
+    );
+
+    // a larger limit doesn't help
+    ctx.sql("SET datafusion.execution.recursive_query_max_iterations = 50")
+        .await?
+        .collect()
+        .await?;
+    let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+    assert_contains!(err.to_string(), "within 50 iterations");
+    Ok(())
+}
+
+#[tokio::test]
+async fn query_recursive_cte_incorrect() -> Result<()> {
+    let ctx = edges_context(ExecutionConfig::new())?;
+
+    // mutual recursion
+    let sql = "WITH RECURSIVE \
+        a(n) AS (SELECT 1 UNION SELECT n FROM b), \
+        b(n) AS (SELECT 2 UNION SELECT n FROM a) \
+        SELECT * FROM a";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "This feature is not implemented: Mutual recursion between the common table \
+        expressions of a WITH RECURSIVE clause is not supported, found for a"
+    );
+
+    // self reference in the static term
+    let sql = "WITH RECURSIVE t(n) AS (SELECT n FROM t UNION SELECT 1) SELECT * FROM t";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: The static term of recursive query t can not \
+        reference the query"
+    );
+
+    // self reference outside of a UNION
+    let sql = "WITH RECURSIVE t(n) AS (SELECT n FROM t) SELECT * FROM t";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_contains!(
+        err.to_string(),
+        "Recursive query t must be of the form <static term> UNION [ALL] <recursive term>"
+    );
+
+    // without RECURSIVE, a CTE can't reference itself
+    let sql = "WITH t(n) AS (SELECT 1 UNION SELECT n FROM t) SELECT * FROM t";
+    let err = ctx.create_logical_plan(sql).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Error during planning: Table or CTE with name 't' not found"
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn query_scalar_minus_array() -> Result<()> {
     let schema = Arc::new(Schema::new(vec![Field::new("c1", DataType::Int32, true)]));
@@ -5097,6 +6441,81 @@ async fn test_interval_expressions() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_interval_arithmetic() -> Result<()> {
+    test_expression!(
+        "CAST(interval '1 day' + interval '2 hours' AS VARCHAR)",
+        "1 day 02:00:00"
+    );
+    test_expression!(
+        "CAST(interval '1 month' + interval '1 year' AS VARCHAR)",
+        "1 year 1 mon"
+    );
+    test_expression!(
+        "CAST(interval '3 month' - interval '5 month' AS VARCHAR)",
+        "-2 mons"
+    );
+    test_expression!("CAST(interval '2 hours' * 15 AS VARCHAR)", "30:00:00");
+    test_expression!("CAST(1.5 * interval '1 day' AS VARCHAR)", "1 day 12:00:00");
+    test_expression!(
+        "CAST(justify_hours(interval '2 hours' * 15) AS VARCHAR)",
+        "1 day 06:00:00"
+    );
+    test_expression!("interval '1 day' > interval '23 hours'", "true");
+    test_expression!("interval '1 day' = interval '24 hours'", "true");
+    test_expression!("interval '1 month' <= interval '2 month'", "true");
+    Ok(())
+}
+
+#[tokio::test]
+async fn group_by_timestamp_difference() -> Result<()> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new(
+            "ts1",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+        Field::new(
+            "ts2",
+            DataType::Timestamp(TimeUnit::Millisecond, None),
+            false,
+        ),
+    ]));
+    let hour = 3_600_000;
+    let starts = vec![0, hour, 2 * hour, 0, 5 * hour + 1_500];
+    let ends = vec![2 * hour, 3 * hour, 28 * hour, 26 * hour, 5 * hour];
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(TimestampMillisecondArray::from_vec(starts, None)),
+            Arc::new(TimestampMillisecondArray::from_vec(ends, None)),
+        ],
+    )?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table(
+        "trips",
+        Arc::new(MemTable::try_new(schema, vec![vec![batch]])?),
+    )?;
+
+    // the negative difference is packed in an i64 larger than the positive
+    // ones of less than a day, intervals are sorted on their durations
+    let sql = "SELECT d, CAST(d AS VARCHAR) AS duration, n FROM \
+               (SELECT ts2 - ts1 AS d, COUNT(*) AS n FROM trips GROUP BY ts2 - ts1) AS t \
+               ORDER BY d DESC";
+    let actual = execute(&mut ctx, sql)
+        .await
+        .into_iter()
+        .map(|row| row[1..].to_vec())
+        .collect::<Vec<_>>();
+    let expected = vec![
+        vec!["1 day 02:00:00", "2"],
+        vec!["02:00:00", "2"],
+        vec!["-00:00:01.5", "1"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_string_expressions() -> Result<()> {
     test_expression!("ascii('')", "0");
@@ -5383,6 +6802,69 @@ async fn in_list_array() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn in_list_long() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![Field::new("i", DataType::Int32, true)]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![Arc::new(Int32Array::from(vec![
+            Some(1),
+            Some(2),
+            Some(50),
+            Some(1000),
+            None,
+        ]))],
+    )?;
+    ctx.register_table("t", Arc::new(MemTable::try_new(schema, vec![vec![data]])?))?;
+
+    // the 100 values are hashed
+    let list = (0..100)
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT i, i IN ({0}) AS in_list, i NOT IN ({0}) AS not_in_list, \
+         i IN ({0}, NULL) AS in_null, i NOT IN ({0}, NULL) AS not_in_null FROM t",
+        list
+    );
+    let actual = execute_to_batches(&mut ctx, &sql).await;
+    let expected = vec![
+        "+------+---------+-------------+---------+-------------+",
+        "| i    | in_list | not_in_list | in_null | not_in_null |",
+        "+------+---------+-------------+---------+-------------+",
+        "| 1    | true    | false       | true    | false       |",
+        "| 2    | true    | false       | true    | false       |",
+        "| 50   | true    | false       | true    | false       |",
+        "| 1000 | false   | true        |         |             |",
+        "|      |         |             |         |             |",
+        "+------+---------+-------------+---------+-------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &actual);
+
+    // no value passes NOT IN a list with a null
+    let sql = format!("SELECT i FROM t WHERE i NOT IN ({}, NULL)", list);
+    let actual = execute_to_batches(&mut ctx, &sql).await;
+    assert_eq!(actual.iter().map(|b| b.num_rows()).sum::<usize>(), 0);
+
+    // the literals are cast to the type of the column without loss
+    let queries = vec![
+        (
+            "SELECT i FROM t WHERE i IN (1, 1.5)",
+            "Cannot cast 1.5 in IN list to Int32 without loss",
+        ),
+        (
+            "SELECT i FROM t WHERE i NOT IN (1, 3000000000)",
+            "Cannot cast 3000000000 in IN list to Int32",
+        ),
+    ];
+    for (sql, error) in queries {
+        let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+        assert_contains!(err.to_string(), error);
+    }
+    Ok(())
+}
+
 // TODO Tests to prove correct implementation of INNER JOIN's with qualified names.
 //  https://issues.apache.org/jira/projects/ARROW/issues/ARROW-11432.
 #[tokio::test]
@@ -5432,6 +6914,72 @@ async fn inner_join_nulls() {
     assert_batches_eq!(expected, &actual);
 }
 
+#[tokio::test]
+async fn in_subquery_nulls() -> Result<()> {
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_target_partitions(4));
+    for (name, values) in [
+        ("t", vec![Some(1), Some(2), None]),
+        ("u", vec![Some(2), Some(3)]),
+        ("v", vec![Some(2), None]),
+    ] {
+        let batch = RecordBatch::try_from_iter(vec![(
+            "a",
+            Arc::new(Int32Array::from(values)) as ArrayRef,
+        )])?;
+        let table = MemTable::try_new(batch.schema(), vec![vec![batch]])?;
+        ctx.register_table(name, Arc::new(table))?;
+    }
+
+    // the results of PostgreSQL
+    let cases = [
+        ("a IN (SELECT a FROM u)", vec!["2"]),
+        ("a IN (SELECT a FROM v)", vec!["2"]),
+        ("a NOT IN (SELECT a FROM u)", vec!["1"]),
+        ("a NOT IN (SELECT a FROM v)", vec![]),
+        (
+            "a NOT IN (SELECT a FROM u WHERE a > 5)",
+            vec!["1", "2", "NULL"],
+        ),
+        (
+            "a NOT IN (SELECT a FROM v WHERE a > 5)",
+            vec!["1", "2", "NULL"],
+        ),
+        // NOT EXISTS is a plain anti join: nulls only fail to match
+        ("EXISTS (SELECT 1 FROM u WHERE u.a = t.a)", vec!["2"]),
+        ("EXISTS (SELECT 1 FROM v WHERE v.a = t.a)", vec!["2"]),
+        (
+            "NOT EXISTS (SELECT 1 FROM u WHERE u.a = t.a)",
+            vec!["1", "NULL"],
+        ),
+        (
+            "NOT EXISTS (SELECT 1 FROM v WHERE v.a = t.a)",
+            vec!["1", "NULL"],
+        ),
+        (
+            "NOT EXISTS (SELECT 1 FROM u WHERE u.a = t.a AND u.a > 5)",
+            vec!["1", "2", "NULL"],
+        ),
+        (
+            "NOT EXISTS (SELECT 1 FROM v WHERE v.a = t.a AND v.a > 5)",
+            vec!["1", "2", "NULL"],
+        ),
+    ];
+    for (predicate, expected) in cases {
+        let sql = format!("SELECT a FROM t WHERE {}", predicate);
+        let mut actual = execute(&mut ctx, &sql).await;
+        actual.sort();
+        let expected: Vec<Vec<String>> =
+            expected.into_iter().map(|a| vec![a.to_owned()]).collect();
+        assert_eq!(expected, actual, "{}", sql);
+    }
+
+    // correlated IN subqueries are not decorrelated
+    let sql = "SELECT a FROM t WHERE a NOT IN (SELECT a FROM u WHERE u.a = t.a)";
+    assert!(ctx.create_logical_plan(sql).is_err());
+    Ok(())
+}
+
 #[tokio::test]
 async fn qualified_table_references() -> Result<()> {
     let mut ctx = ExecutionContext::new();
@@ -5558,6 +7106,102 @@ async fn test_cast_expressions() -> Result<()> {
     test_expression!("CAST(NULL AS INT)", "NULL");
     test_expression!("TRY_CAST('0' AS INT)", "0");
     test_expression!("TRY_CAST('x' AS INT)", "NULL");
+    test_expression!("CAST(' Yes ' AS BOOLEAN)", "true");
+    test_expression!("CAST('0' AS BOOLEAN)", "false");
+    test_expression!("CAST('2021-03-04' AS DATE)", "2021-03-04");
+    test_expression!(
+        "CAST('2020-09-08T13:42:29.190855Z' AS TIMESTAMP)",
+        "2020-09-08 13:42:29.190855"
+    );
+    test_expression!("CAST(1.5 AS VARCHAR)", "1.5");
+    test_expression!("CAST(CAST('1e20' AS DOUBLE) AS VARCHAR)", "1e+20");
+    test_expression!(
+        "CAST(INTERVAL '1 day' AS VARCHAR)",
+        "0 years 0 mons 1 days 0 hours 0 mins 0.00 secs"
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn cast_strings_of_mixed_validity() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("flag", DataType::Utf8, true),
+        Field::new("day", DataType::Utf8, true),
+        Field::new("ts", DataType::Utf8, true),
+    ]));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec![Some("t"), Some("maybe"), None])),
+            Arc::new(StringArray::from(vec![
+                Some("2021-01-31"),
+                None,
+                Some("2021-02-31"),
+            ])),
+            Arc::new(StringArray::from(vec![
+                Some("2021-01-31T10:00:00.250Z"),
+                Some("yesterday"),
+                Some("2021-02-01T00:00:00+01:00"),
+            ])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![batch]])?;
+    ctx.register_table("t", Arc::new(table))?;
+
+    // the strings that can't be casted are nulls with TRY_CAST
+    let sql = "SELECT id, TRY_CAST(flag AS BOOLEAN), TRY_CAST(day AS DATE), \
+               TRY_CAST(ts AS TIMESTAMP) FROM t ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "true", "2021-01-31", "2021-01-31 10:00:00.250"],
+        vec!["2", "NULL", "NULL", "NULL"],
+        vec!["3", "NULL", "NULL", "2021-01-31 23:00:00"],
+    ];
+    assert_eq!(expected, actual);
+
+    // and errors with CAST
+    let queries = vec![
+        ("SELECT CAST(flag AS BOOLEAN) FROM t", "'maybe' to Boolean"),
+        ("SELECT CAST(day AS DATE) FROM t", "'2021-02-31' to Date32"),
+        (
+            "SELECT CAST(ts AS TIMESTAMP) FROM t",
+            "'yesterday' to Timestamp",
+        ),
+    ];
+    for (sql, error) in queries {
+        let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+        assert_contains!(err.to_string(), error);
+    }
+
+    // the valid values are casted back to strings
+    let sql = "SELECT id, CAST(TRY_CAST(flag AS BOOLEAN) AS VARCHAR), \
+               CAST(CAST(id AS DOUBLE) / 4 AS VARCHAR) FROM t ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["1", "true", "0.25"],
+        vec!["2", "NULL", "0.5"],
+        vec!["3", "NULL", "0.75"],
+    ];
+    assert_eq!(expected, actual);
+
+    // booleans are compared to strings
+    let sql = "SELECT id FROM t WHERE TRY_CAST(flag AS BOOLEAN) = 'yes'";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["1"]], actual);
+
+    // DataFrame API
+    let df = ctx
+        .table("t")?
+        .select(vec![
+            try_cast(col("day"), DataType::Date32).alias("day"),
+            cast(col("id"), DataType::Float64).alias("id"),
+        ])?
+        .filter(col("day").is_not_null())?;
+    let actual = result_vec(&df.collect().await?);
+    assert_eq!(vec![vec!["2021-01-31", "1"]], actual);
     Ok(())
 }
 
@@ -5592,7 +7236,8 @@ async fn test_current_timestamp_expressions_non_optimized() -> Result<()> {
     let plan = ctx.create_physical_plan(&plan).await.expect(&msg);
 
     let msg = format!("Executing physical plan for '{}': {:?}", sql, plan);
-    let res = collect(plan).await.expect(&msg);
+    let task_ctx = ctx.task_ctx();
+    let res = collect(plan, task_ctx).await.expect(&msg);
     let actual = result_vec(&res);
 
     let res1 = actual[0][0].as_str();
@@ -5628,7 +7273,8 @@ async fn test_cast_expressions_error() -> Result<()> {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let result = collect(plan).await;
+    let task_ctx = ctx.task_ctx();
+    let result = collect(plan, task_ctx).await;
 
     match result {
         Ok(_) => panic!("expected error"),
@@ -5642,6 +7288,71 @@ async fn test_cast_expressions_error() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn locate_misspelled_column() -> Result<()> {
+    let mut ctx = create_ctx()?;
+    register_aggregate_csv(&mut ctx).await?;
+    let sql = [
+        "SELECT c1,",
+        "       MIN(c12) AS min_c12,",
+        "       MAX(c12) AS max_c12",
+        "FROM aggregate_test_100",
+        "WHERE c11 > 0.1 -- unlike c33, a valid column",
+        "  AND c33 > 10",
+        "  AND c13 <> 'c33'",
+        "GROUP BY c1",
+        "ORDER BY c1",
+    ]
+    .join("\n");
+    let err = ctx.create_logical_plan(&sql).unwrap_err();
+    assert!(
+        matches!(err.find_root(), DataFusionError::Plan(_)),
+        "{}",
+        err
+    );
+
+    let message = err.to_string();
+    assert!(
+        message.starts_with("line 6, column 7: Error during planning: "),
+        "{}",
+        message
+    );
+    assert_contains!(&message, "'c33'");
+    assert!(
+        message.ends_with("\n 6 |   AND c33 > 10\n   |       ^^^"),
+        "{}",
+        message
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn locate_operator_of_execution_error() -> Result<()> {
+    let mut ctx =
+        ExecutionContext::with_config(ExecutionConfig::new().with_target_partitions(1));
+    register_aggregate_csv(&mut ctx).await?;
+    let sql = "SELECT c1, c2 FROM aggregate_test_100 WHERE CAST(c1 AS INT) > 0";
+    let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+    assert!(
+        matches!(
+            err.find_root(),
+            DataFusionError::ArrowError(arrow::error::ArrowError::CastError(_))
+        ),
+        "{}",
+        err
+    );
+
+    let message = err.to_string();
+    assert!(message.starts_with("FilterExec: "), "{}", message);
+    assert_contains!(&message, "CAST(c1@0 AS Int32)");
+    assert_contains!(
+        &message,
+        " in partition 0: Arrow error: Cast error: Cannot cast string 'c' to value of \
+         arrow::datatypes::types::Int32Type type"
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_physical_plan_display_indent() {
     // Hard code target_partitions as it appears in the RepartitionExec output
@@ -5667,10 +7378,9 @@ async fn test_physical_plan_display_indent() {
         "          CoalesceBatchesExec: target_batch_size=4096",
         "            RepartitionExec: partitioning=Hash([Column { name: \"c1\", index: 0 }], 3)",
         "              HashAggregateExec: mode=Partial, gby=[c1@0 as c1], aggr=[MAX(aggregate_test_100.c12), MIN(aggregate_test_100.c12)]",
-        "                CoalesceBatchesExec: target_batch_size=4096",
-        "                  FilterExec: c12@1 < CAST(10 AS Float64)",
-        "                    RepartitionExec: partitioning=RoundRobinBatch(3)",
-        "                      CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
+        "                FilterExec: c12@1 < CAST(10 AS Float64)",
+        "                  RepartitionExec: partitioning=RoundRobinBatch(3)",
+        "                    CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
     ];
 
     let data_path = datafusion::test_util::arrow_test_data();
@@ -5688,6 +7398,45 @@ async fn test_physical_plan_display_indent() {
     );
 }
 
+#[tokio::test]
+async fn aggregate_pre_partitioned_input() -> Result<()> {
+    let config = ExecutionConfig::new().with_target_partitions(3);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_aggregate_csv(&mut ctx).await?;
+
+    // the subquery is hash partitioned on c1, renamed to k, so the outer
+    // aggregate runs on each partition without another exchange
+    let sql = "SELECT k, SUM(total) FROM \
+                 (SELECT c1 AS k, SUM(c2) AS total \
+                  FROM aggregate_test_100 \
+                  GROUP BY c1) AS t \
+               GROUP BY k";
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let plan = format!("{}", displayable(plan.as_ref()).indent());
+    assert_contains!(
+        &plan,
+        "HashAggregateExec: mode=SinglePartitioned, gby=[k@0 as k]"
+    );
+    assert_eq!(
+        plan.matches("RepartitionExec: partitioning=Hash").count(),
+        1
+    );
+    assert_eq!(plan.matches("mode=Partial").count(), 1);
+
+    let mut actual = execute(&mut ctx, sql).await;
+    actual.sort();
+    let mut expected = execute(
+        &mut ctx,
+        "SELECT c1, SUM(c2) FROM aggregate_test_100 GROUP BY c1",
+    )
+    .await;
+    expected.sort();
+    assert_eq!(expected, actual);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_physical_plan_display_indent_multi_children() {
     // Hard code target_partitions as it appears in the RepartitionExec output
@@ -5712,16 +7461,13 @@ async fn test_physical_plan_display_indent_multi_children() {
         "    HashJoinExec: mode=Partitioned, join_type=Inner, on=[(Column { name: \"c1\", index: 0 }, Column { name: \"c2\", index: 0 })]",
         "      CoalesceBatchesExec: target_batch_size=4096",
         "        RepartitionExec: partitioning=Hash([Column { name: \"c1\", index: 0 }], 3)",
-        "          ProjectionExec: expr=[c1@0 as c1]",
-        "            ProjectionExec: expr=[c1@0 as c1]",
-        "              RepartitionExec: partitioning=RoundRobinBatch(3)",
-        "                CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
+        "          RepartitionExec: partitioning=RoundRobinBatch(3)",
+        "            CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
         "      CoalesceBatchesExec: target_batch_size=4096",
         "        RepartitionExec: partitioning=Hash([Column { name: \"c2\", index: 0 }], 3)",
-        "          ProjectionExec: expr=[c2@0 as c2]",
-        "            ProjectionExec: expr=[c1@0 as c2]",
-        "              RepartitionExec: partitioning=RoundRobinBatch(3)",
-        "                CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
+        "          ProjectionExec: expr=[c1@0 as c2]",
+        "            RepartitionExec: partitioning=RoundRobinBatch(3)",
+        "              CsvExec: files=[ARROW_TEST_DATA/csv/aggregate_test_100.csv], has_header=true, batch_size=8192, limit=None",
     ];
 
     let data_path = datafusion::test_util::arrow_test_data();
@@ -6005,6 +7751,411 @@ async fn avro_query() {
     assert_batches_eq!(expected, &actual);
 }
 
+/// The names of the columns read by the Parquet scans of the plan of `sql`
+async fn parquet_scanned_columns(
+    ctx: &mut ExecutionContext,
+    sql: &str,
+) -> Result<Vec<Vec<String>>> {
+    struct ScanFinder {
+        columns: Vec<Vec<String>>,
+    }
+
+    impl ExecutionPlanVisitor for ScanFinder {
+        type Error = DataFusionError;
+
+        fn pre_visit(&mut self, plan: &dyn ExecutionPlan) -> Result<bool> {
+            if plan.as_any().downcast_ref::<ParquetExec>().is_some() {
+                let schema = plan.schema();
+                let names = schema.fields().iter().map(|f| f.name().clone());
+                self.columns.push(names.collect());
+            }
+            Ok(true)
+        }
+    }
+
+    let plan = ctx.optimize(&ctx.create_logical_plan(sql)?)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let mut finder = ScanFinder { columns: vec![] };
+    accept(plan.as_ref(), &mut finder)?;
+    Ok(finder.columns)
+}
+
+#[tokio::test]
+async fn parquet_scan_reads_referenced_columns() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("wide.parquet");
+
+    // 50 columns c0 to c49
+    let schema = Arc::new(Schema::new(
+        (0..50)
+            .map(|i| Field::new(&format!("c{}", i), DataType::Int32, false))
+            .collect(),
+    ));
+    let columns = (0..50)
+        .map(|i| Arc::new(Int32Array::from(vec![i, i + 1, i + 2])) as ArrayRef)
+        .collect();
+    let batch = RecordBatch::try_new(schema.clone(), columns)?;
+    let file = File::create(&path)?;
+    let mut writer = ArrowWriter::try_new(file, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_parquet("wide", path.to_str().unwrap()).await?;
+
+    let sql = "SELECT c3 + c7 AS s FROM wide WHERE c10 > 10 ORDER BY s";
+    let scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    assert_eq!(scanned, vec![vec!["c3", "c7", "c10"]]);
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec!["+----+", "| s  |", "+----+", "| 12 |", "| 14 |", "+----+"];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT w.c1 FROM (SELECT c1, c2, c3 FROM wide) AS w ORDER BY w.c2";
+    let scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    assert_eq!(scanned, vec![vec!["c1", "c2"]]);
+
+    let sql = "SELECT c4, MAX(c5) FROM wide GROUP BY c4";
+    let scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    assert_eq!(scanned, vec![vec!["c4", "c5"]]);
+
+    let sql = "SELECT a.c1 FROM wide AS a JOIN wide AS b ON a.c2 = b.c3";
+    let mut scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    scanned.sort();
+    assert_eq!(scanned, vec![vec!["c1", "c2"], vec!["c3"]]);
+
+    // answered from the row counts of the file metadata
+    let sql = "SELECT COUNT(*) FROM wide";
+    let scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    assert!(scanned.is_empty(), "{:?}", scanned);
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+-----------------+",
+        "| COUNT(UInt8(1)) |",
+        "+-----------------+",
+        "| 3               |",
+        "+-----------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+
+    let sql = "SELECT COUNT(*) FROM wide WHERE c9 > 9";
+    let scanned = parquet_scanned_columns(&mut ctx, sql).await?;
+    assert_eq!(scanned, vec![vec!["c9"]]);
+    Ok(())
+}
+
+/// Register the Parquet file `prices` of prices and quantities of type
+/// DECIMAL(38, 10): 1.5 * 2, 2.25 * 4, -0.75 * 10 and NULL * 1
+async fn register_decimal_parquet(
+    ctx: &mut ExecutionContext,
+    path: &std::path::Path,
+) -> Result<RecordBatch> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("price", DataType::Decimal(38, 10), true),
+        Field::new("qty", DataType::Decimal(38, 10), false),
+    ]));
+    let scale = 10_i128.pow(10);
+    let mut price = DecimalBuilder::new(4, 38, 10);
+    for value in &[150, 225, -75] {
+        price.append_value(value * scale / 100)?;
+    }
+    price.append_null()?;
+    let mut qty = DecimalBuilder::new(4, 38, 10);
+    for value in &[2, 4, 10, 1] {
+        qty.append_value(value * scale)?;
+    }
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4])),
+            Arc::new(price.finish()),
+            Arc::new(qty.finish()),
+        ],
+    )?;
+    if !path.exists() {
+        let mut writer = ArrowWriter::try_new(File::create(path)?, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+    }
+    ctx.register_parquet("prices", path.to_str().unwrap())
+        .await?;
+    Ok(batch)
+}
+
+#[tokio::test]
+async fn parquet_decimal() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("prices.parquet");
+    let mut ctx = ExecutionContext::new();
+    let batch = register_decimal_parquet(&mut ctx, &path).await?;
+
+    // the decimals are read as they were written
+    let actual = execute_to_batches(&mut ctx, "SELECT * FROM prices").await;
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual[0].schema().fields(), batch.schema().fields());
+    assert_eq!(actual[0].columns(), batch.columns());
+
+    // the precision and scale of the results follow the SQL rules, reduced to
+    // fit 38 digits
+    let sql = "SELECT price * qty, price + qty, price / qty, price * 2 FROM prices";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let types = actual[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            DataType::Decimal(38, 6),
+            DataType::Decimal(38, 9),
+            DataType::Decimal(38, 6),
+            DataType::Decimal(38, 6),
+        ]
+    );
+
+    let sql = "SELECT id, CAST(price * qty AS VARCHAR), CAST(price + qty AS VARCHAR), \
+        CAST(qty - price AS VARCHAR), CAST(price / qty AS VARCHAR), \
+        CAST(price * 2 AS VARCHAR) FROM prices ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec![
+            "1",
+            "3.000000",
+            "3.500000000",
+            "0.500000000",
+            "0.750000",
+            "3.000000",
+        ],
+        vec![
+            "2",
+            "9.000000",
+            "6.250000000",
+            "1.750000000",
+            "0.562500",
+            "4.500000",
+        ],
+        vec![
+            "3",
+            "-7.500000",
+            "9.250000000",
+            "10.750000000",
+            "-0.075000",
+            "-1.500000",
+        ],
+        vec!["4", "NULL", "NULL", "NULL", "NULL", "NULL"],
+    ];
+    assert_eq!(expected, actual);
+
+    // comparisons and sorting
+    let sql = "SELECT id FROM prices WHERE price > 1 AND qty <= 4.0 ORDER BY price DESC";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["2"], vec!["1"]], actual);
+
+    // casts
+    let sql = "SELECT CAST(price AS DOUBLE), CAST(price AS INT), \
+        CAST(CAST('3.14159' AS DECIMAL(10, 2)) AS VARCHAR), \
+        CAST(DECIMAL '-12.345' AS VARCHAR) FROM prices WHERE id = 3";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["-0.75", "0", "3.14", "-12.345"]], actual);
+
+    // aggregates
+    let sql = "SELECT CAST(SUM(price) AS VARCHAR), CAST(AVG(price) AS VARCHAR), \
+        CAST(MIN(price) AS VARCHAR), CAST(MAX(price) AS VARCHAR), \
+        CAST(SUM(price * qty) AS VARCHAR) FROM prices";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec![
+        "3.0000000000",
+        "1.00000000000000",
+        "-0.7500000000",
+        "2.2500000000",
+        "4.500000",
+    ]];
+    assert_eq!(expected, actual);
+
+    // the results are written to Parquet and read back as decimals
+    let plan = ctx.create_logical_plan("SELECT id, price * qty AS total FROM prices")?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let totals = tempdir.path().join("totals");
+    ctx.write_parquet(plan, totals.to_str().unwrap(), None)
+        .await?;
+    ctx.register_parquet("totals", totals.to_str().unwrap())
+        .await?;
+    let sql = "SELECT total FROM totals ORDER BY id";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    assert_eq!(
+        actual[0].schema().field(0).data_type(),
+        &DataType::Decimal(38, 6)
+    );
+    let sql = "SELECT CAST(total AS VARCHAR) FROM totals ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["3.000000"],
+        vec!["9.000000"],
+        vec!["-7.500000"],
+        vec!["NULL"],
+    ];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn parse_float_as_decimal() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("prices.parquet");
+    let config = ExecutionConfig::new().with_parse_float_as_decimal(true);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_decimal_parquet(&mut ctx, &path).await?;
+
+    let sql = "SELECT 1.5, -0.25, 1e3 FROM prices WHERE id = 1";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let types = actual[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            DataType::Decimal(2, 1),
+            DataType::Decimal(2, 2),
+            DataType::Float64
+        ]
+    );
+
+    let sql = "SELECT id, CAST(price + 0.1 AS VARCHAR) FROM prices \
+        WHERE price = 2.25 OR price < -0.5 ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2", "2.350000000"], vec!["3", "-0.650000000"]];
+    assert_eq!(expected, actual);
+    Ok(())
+}
+
+#[tokio::test]
+async fn typed_literals() -> Result<()> {
+    let config = ExecutionConfig::new().with_parse_float_as_decimal(true);
+    let mut ctx = ExecutionContext::with_config(config);
+    let sql = "SELECT DATE '2021-02-28', TIMESTAMP '2021-01-01 00:00:00+05:30', \
+        1_000_000, 1e-3, 99999999999999999999, -0.000_001";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let types = actual[0]
+        .schema()
+        .fields()
+        .iter()
+        .map(|f| f.data_type().clone())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        types,
+        vec![
+            DataType::Date32,
+            DataType::Timestamp(TimeUnit::Nanosecond, Some("+05:30".to_owned())),
+            DataType::Int64,
+            DataType::Float64,
+            DataType::Decimal(20, 0),
+            DataType::Decimal(6, 6),
+        ]
+    );
+
+    // the timestamp is the instant in UTC
+    let sql = "SELECT DATE '2021-02-28', TIMESTAMP '2021-01-01 00:00:00+05:30', \
+        1_000_000, 1e-3";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec![
+        "2021-02-28",
+        "2020-12-31 18:30:00",
+        "1000000",
+        "0.001",
+    ]];
+    assert_eq!(expected, actual);
+
+    let sql = "SELECT DATE '2021-02-30'";
+    let err = ctx.create_logical_plan(sql).unwrap_err().to_string();
+    assert!(err.contains("Invalid DATE literal '2021-02-30'"), "{}", err);
+    Ok(())
+}
+
+#[tokio::test]
+async fn parquet_binary() -> Result<()> {
+    let tempdir = tempfile::tempdir().unwrap();
+    let path = tempdir.path().join("blobs.parquet");
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("key", DataType::Binary, true),
+        Field::new("tag", DataType::FixedSizeBinary(2), false),
+    ]));
+    let key = BinaryArray::from(vec![
+        Some(&b"ab"[..]),
+        Some(b"\xbe\xef"),
+        Some(b"ab"),
+        None,
+        Some(b"\xff"),
+    ]);
+    let tag = [[0_u8, 1], [0, 2], [0, 1], [0, 3], [0, 2]];
+    let tag = FixedSizeBinaryArray::try_from_iter(tag.iter())?;
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5])),
+            Arc::new(key),
+            Arc::new(tag),
+        ],
+    )?;
+    let mut writer = ArrowWriter::try_new(File::create(&path)?, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    let mut ctx = ExecutionContext::new();
+    ctx.register_parquet("blobs", path.to_str().unwrap())
+        .await?;
+
+    // group by
+    let sql = "SELECT key, COUNT(*), MIN(id) FROM blobs GROUP BY key ORDER BY MIN(id)";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![
+        vec!["6162", "2", "1"],
+        vec!["beef", "1", "2"],
+        vec!["NULL", "1", "4"],
+        vec!["ff", "1", "5"],
+    ];
+    assert_eq!(expected, actual);
+    let sql = "SELECT tag, COUNT(key) FROM blobs GROUP BY tag ORDER BY tag";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["0001", "2"], vec!["0002", "2"], vec!["0003", "0"]];
+    assert_eq!(expected, actual);
+
+    // joins
+    let sql = "SELECT a.id, b.id FROM blobs AS a JOIN blobs AS b ON a.key = b.key \
+        WHERE a.id < b.id";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["1", "3"]], actual);
+    let sql = "SELECT a.id, b.id FROM blobs AS a JOIN blobs AS b ON a.tag = b.tag \
+        WHERE a.id < b.id ORDER BY a.id";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["1", "3"], vec!["2", "5"]], actual);
+
+    // comparisons with literals, sorting and aggregates
+    let sql = "SELECT id FROM blobs WHERE key = X'6162' OR tag > X'0002' \
+        ORDER BY key DESC, id";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["4"], vec!["1"], vec!["3"]], actual);
+    let sql = "SELECT MIN(key), MAX(key), MIN(tag), MAX(tag) FROM blobs";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["6162", "ff", "0001", "0003"]], actual);
+
+    // functions and casts
+    let sql = "SELECT octet_length(key), octet_length(tag), CAST(key AS VARCHAR) \
+        FROM blobs WHERE id IN (1, 4) ORDER BY id";
+    let actual = execute(&mut ctx, sql).await;
+    let expected = vec![vec!["2", "2", "ab"], vec!["NULL", "2", "NULL"]];
+    assert_eq!(expected, actual);
+    let sql = "SELECT CAST(key AS VARCHAR) FROM blobs";
+    let err = ctx.sql(sql).await?.collect().await.unwrap_err();
+    assert_contains!(err.to_string(), "Cannot cast binary value beef to a string");
+    Ok(())
+}
+
 #[cfg(feature = "avro")]
 #[tokio::test]
 async fn avro_query_multiple_files() {
@@ -6077,7 +8228,8 @@ async fn avro_single_nan_schema() {
     let plan = ctx.create_logical_plan(sql).unwrap();
     let plan = ctx.optimize(&plan).unwrap();
     let plan = ctx.create_physical_plan(&plan).await.unwrap();
-    let results = collect(plan).await.unwrap();
+    let task_ctx = ctx.task_ctx();
+    let results = collect(plan, task_ctx).await.unwrap();
     for batch in results {
         assert_eq!(1, batch.num_rows());
         assert_eq!(1, batch.num_columns());
@@ -6098,16 +8250,15 @@ async fn avro_explain() {
             "logical_plan",
             "Projection: #COUNT(UInt8(1))\
             \n  Aggregate: groupBy=[[]], aggr=[[COUNT(UInt8(1))]]\
-            \n    TableScan: alltypes_plain projection=Some([0])",
+            \n    TableScan: alltypes_plain projection=Some([1])",
         ],
         vec![
             "physical_plan",
-            "ProjectionExec: expr=[COUNT(UInt8(1))@0 as COUNT(UInt8(1))]\
-            \n  HashAggregateExec: mode=Final, gby=[], aggr=[COUNT(UInt8(1))]\
-            \n    CoalescePartitionsExec\
-            \n      HashAggregateExec: mode=Partial, gby=[], aggr=[COUNT(UInt8(1))]\
-            \n        RepartitionExec: partitioning=RoundRobinBatch(NUM_CORES)\
-            \n          AvroExec: files=[ARROW_TEST_DATA/avro/alltypes_plain.avro], batch_size=8192, limit=None\
+            "HashAggregateExec: mode=Final, gby=[], aggr=[COUNT(UInt8(1))]\
+            \n  CoalescePartitionsExec\
+            \n    HashAggregateExec: mode=Partial, gby=[], aggr=[COUNT(UInt8(1))]\
+            \n      RepartitionExec: partitioning=RoundRobinBatch(NUM_CORES)\
+            \n        AvroExec: files=[ARROW_TEST_DATA/avro/alltypes_plain.avro], batch_size=8192, limit=None\
             \n",
         ],
     ];
@@ -6349,6 +8500,80 @@ async fn query_nested_get_indexed_field_on_struct() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn query_struct_construction_and_access() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("id", DataType::Int32, false),
+        Field::new("name", DataType::Utf8, true),
+    ]));
+    let data = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(Int32Array::from(vec![1, 2, 3])),
+            Arc::new(StringArray::from(vec![Some("a"), None, Some("c")])),
+        ],
+    )?;
+    let table = MemTable::try_new(schema, vec![vec![data]])?;
+    ctx.register_table("t", Arc::new(table))?;
+
+    // nest structs and project their fields
+    let nested = "(SELECT struct(p, id) AS q \
+        FROM (SELECT named_struct('i', id, 'n', name) AS p, id FROM t) AS a) AS s";
+    let sql = format!(
+        "SELECT q.p.n, q['p']['i'], s.q.id FROM {} ORDER BY 3",
+        nested
+    );
+    let actual = execute(&mut ctx, &sql).await;
+    let expected = vec![
+        vec!["a", "1", "1"],
+        vec!["NULL", "2", "2"],
+        vec!["c", "3", "3"],
+    ];
+    assert_eq!(expected, actual);
+
+    // filter on a nested field
+    let sql = format!(
+        "SELECT q.id FROM {} WHERE q.p.n = 'c' OR s.q.p.i = 1",
+        nested
+    );
+    let actual = execute(&mut ctx, &sql).await;
+    assert_eq!(vec![vec!["1"], vec!["3"]], actual);
+
+    // unknown fields are reported when planning
+    let err = ctx
+        .create_logical_plan(&format!("SELECT q.p.x FROM {}", nested))
+        .unwrap_err();
+    assert_contains!(err.to_string(), "Field x not found in struct");
+
+    // DataFrame API
+    let df = ctx
+        .table("t")?
+        .select(vec![struct_(vec![col("id"), col("name")]).alias("pair")])?
+        .filter(col("pair").field("id").gt(lit(1)))?
+        .select(vec![col("pair").field("name")])?;
+    let actual = result_vec(&df.collect().await?);
+    assert_eq!(vec![vec!["NULL"], vec!["c"]], actual);
+
+    // write the structs to parquet and read them back
+    let tempdir = tempfile::tempdir().unwrap();
+    let out_dir = tempdir.path().join("pairs");
+    let out_dir = out_dir.to_str().unwrap();
+    let plan = ctx.create_logical_plan(
+        "SELECT named_struct('i', id, 'n', name) AS p, struct(name) AS q FROM t",
+    )?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.create_physical_plan(&plan).await?;
+    ctx.write_parquet(plan, out_dir, None).await?;
+
+    let mut ctx = ExecutionContext::new();
+    ctx.register_parquet("pairs", out_dir).await?;
+    let sql = "SELECT p.i, p['n'], q.name FROM pairs WHERE p.i > 1 ORDER BY 1 DESC";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(vec![vec!["3", "c", "c"], vec!["2", "NULL", "NULL"]], actual);
+    Ok(())
+}
+
 #[tokio::test]
 async fn intersect_with_null_not_equal() {
     let sql = "SELECT * FROM (SELECT null AS id1, 1 AS id2) t1
@@ -6646,6 +8871,117 @@ async fn timestamp_minmax() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn timestamp_time_zones() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let new_york = Some("America/New_York".to_owned());
+    let table_a = make_timestamp_tz_table::<TimestampNanosecondType>(new_york.clone())?;
+    let tokyo = Some("Asia/Tokyo".to_owned());
+    let table_b = make_timestamp_tz_table::<TimestampNanosecondType>(tokyo)?;
+    ctx.register_table("table_a", table_a)?;
+    ctx.register_table("table_b", table_b)?;
+
+    // the same instants are equal whatever their time zones
+    let sql = "SELECT COUNT(*) FROM table_a JOIN table_b ON table_a.ts = table_b.ts";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["3"]]);
+    let sql = "SELECT COUNT(*) FROM table_a, table_b WHERE table_a.ts < table_b.ts";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["3"]]);
+
+    // projections, aggregates and date_trunc keep the time zone
+    let sql = "SELECT ts, MIN(ts), date_trunc('hour', ts) FROM table_a GROUP BY ts";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let schema = actual[0].schema();
+    for field in schema.fields() {
+        assert_eq!(
+            field.data_type(),
+            &DataType::Timestamp(TimeUnit::Nanosecond, new_york.clone())
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn at_time_zone() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    let table =
+        make_timestamp_tz_table::<TimestampMicrosecondType>(Some("UTC".to_owned()))?;
+    ctx.register_table("ts_data", table)?;
+
+    // the wall clock time in New York, UTC-4 in September, and back to the
+    // instants, displayed in UTC
+    let sql = "SELECT ts AT TIME ZONE 'America/New_York' AS local, \
+               (ts AT TIME ZONE 'America/New_York') AT TIME ZONE 'America/New_York' \
+               AS instant FROM ts_data";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----------------------------+----------------------------+",
+        "| local                      | instant                    |",
+        "+----------------------------+----------------------------+",
+        "| 2020-09-08 09:42:29.190855 | 2020-09-08 13:42:29.190855 |",
+        "| 2020-09-08 08:42:29.190855 | 2020-09-08 12:42:29.190855 |",
+        "| 2020-09-08 07:42:29.190855 | 2020-09-08 11:42:29.190855 |",
+        "+----------------------------+----------------------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    let schema = actual[0].schema();
+    assert_eq!(
+        schema.field(0).data_type(),
+        &DataType::Timestamp(TimeUnit::Microsecond, None)
+    );
+    assert_eq!(
+        schema.field(1).data_type(),
+        &DataType::Timestamp(TimeUnit::Microsecond, Some("America/New_York".to_owned()))
+    );
+
+    // 02:30 does not exist on the day New York switches to daylight saving
+    // time, and takes the offset of the day before
+    let sql = "SELECT TIMESTAMP '2021-03-14 02:30:00' AT TIME ZONE 'America/New_York'";
+    let actual = execute(&mut ctx, sql).await;
+    assert_eq!(actual, vec![vec!["2021-03-14 07:30:00"]]);
+
+    let sql = "SELECT ts AT TIME ZONE 'Mars/Olympus_Mons' FROM ts_data";
+    assert!(ctx.create_logical_plan(sql).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn to_timezone_and_from_unixtime() -> Result<()> {
+    let mut ctx = ExecutionContext::new();
+    ctx.register_table("ts_data", make_timestamp_nano_table()?)?;
+
+    let sql = "SELECT to_timezone(ts, 'Asia/Tokyo') AS tokyo, \
+               from_unixtime(CAST(ts AS BIGINT) / 1000000000, 'Asia/Tokyo') AS secs, \
+               from_unixtime(value) AS epoch FROM ts_data";
+    let actual = execute_to_batches(&mut ctx, sql).await;
+    let expected = vec![
+        "+----------------------------+---------------------+---------------------+",
+        "| tokyo                      | secs                | epoch               |",
+        "+----------------------------+---------------------+---------------------+",
+        "| 2020-09-08 13:42:29.190855 | 2020-09-08 13:42:29 | 1970-01-01 00:00:01 |",
+        "| 2020-09-08 12:42:29.190855 | 2020-09-08 12:42:29 | 1970-01-01 00:00:02 |",
+        "| 2020-09-08 11:42:29.190855 | 2020-09-08 11:42:29 | 1970-01-01 00:00:03 |",
+        "+----------------------------+---------------------+---------------------+",
+    ];
+    assert_batches_eq!(expected, &actual);
+    let schema = actual[0].schema();
+    let tokyo = Some("Asia/Tokyo".to_owned());
+    assert_eq!(
+        schema.field(0).data_type(),
+        &DataType::Timestamp(TimeUnit::Nanosecond, tokyo.clone())
+    );
+    assert_eq!(
+        schema.field(1).data_type(),
+        &DataType::Timestamp(TimeUnit::Second, tokyo)
+    );
+    assert_eq!(
+        schema.field(2).data_type(),
+        &DataType::Timestamp(TimeUnit::Second, Some("UTC".to_owned()))
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn timestamp_coercion() -> Result<()> {
     {
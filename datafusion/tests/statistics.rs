@@ -23,10 +23,11 @@ use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
 use datafusion::{
     datasource::TableProvider,
     error::{DataFusionError, Result},
+    execution::task_context::TaskContext,
     logical_plan::Expr,
     physical_plan::{
-        ColumnStatistics, DisplayFormatType, ExecutionPlan, Partitioning,
-        SendableRecordBatchStream, Statistics,
+        fmt_with_statistics, ColumnStatistics, DisplayFormatType, ExecutionPlan,
+        Partitioning, SendableRecordBatchStream, Statistics,
     },
     prelude::ExecutionContext,
     scalar::ScalarValue,
@@ -144,7 +145,11 @@ impl ExecutionPlan for StatisticsValidation {
         }
     }
 
-    async fn execute(&self, _partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        _partition: usize,
+        _context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         unimplemented!("This plan only serves for testing statistics")
     }
 
@@ -166,6 +171,7 @@ impl ExecutionPlan for StatisticsValidation {
                     self.stats.num_rows,
                 )
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 }
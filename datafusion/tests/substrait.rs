@@ -0,0 +1,170 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Test the round trips of logical plans through Substrait plans
+
+#![cfg(feature = "substrait")]
+
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::util::pretty::pretty_format_batches;
+
+use datafusion::error::{DataFusionError, Result};
+use datafusion::logical_plan::{from_substrait, to_substrait, LogicalPlan};
+use datafusion::physical_plan::collect;
+use datafusion::prelude::*;
+
+/// Registers the TPC-H tables of `tests/tpch-csv` read by the queries
+async fn tpch_context() -> Result<ExecutionContext> {
+    let mut ctx = ExecutionContext::new();
+    let lineitem = Schema::new(vec![
+        Field::new("l_orderkey", DataType::Int64, false),
+        Field::new("l_partkey", DataType::Int64, false),
+        Field::new("l_suppkey", DataType::Int64, false),
+        Field::new("l_linenumber", DataType::Int32, false),
+        Field::new("l_quantity", DataType::Float64, false),
+        Field::new("l_extendedprice", DataType::Float64, false),
+        Field::new("l_discount", DataType::Float64, false),
+        Field::new("l_tax", DataType::Float64, false),
+        Field::new("l_returnflag", DataType::Utf8, false),
+        Field::new("l_linestatus", DataType::Utf8, false),
+        Field::new("l_shipdate", DataType::Date32, false),
+        Field::new("l_commitdate", DataType::Date32, false),
+        Field::new("l_receiptdate", DataType::Date32, false),
+        Field::new("l_shipinstruct", DataType::Utf8, false),
+        Field::new("l_shipmode", DataType::Utf8, false),
+        Field::new("l_comment", DataType::Utf8, false),
+    ]);
+    let orders = Schema::new(vec![
+        Field::new("o_orderkey", DataType::Int64, false),
+        Field::new("o_custkey", DataType::Int64, false),
+        Field::new("o_orderstatus", DataType::Utf8, false),
+        Field::new("o_totalprice", DataType::Float64, false),
+        Field::new("o_orderdate", DataType::Date32, false),
+        Field::new("o_orderpriority", DataType::Utf8, false),
+        Field::new("o_clerk", DataType::Utf8, false),
+        Field::new("o_shippriority", DataType::Int32, false),
+        Field::new("o_comment", DataType::Utf8, false),
+    ]);
+    for (table, schema) in [("lineitem", lineitem), ("orders", orders)] {
+        ctx.register_csv(
+            table,
+            &format!("tests/tpch-csv/{}.csv", table),
+            CsvReadOptions::new().schema(&schema),
+        )
+        .await?;
+    }
+    Ok(ctx)
+}
+
+async fn execute(ctx: &ExecutionContext, plan: &LogicalPlan) -> Result<String> {
+    let plan = ctx.create_physical_plan(&ctx.optimize(plan)?).await?;
+    let batches = collect(plan, ctx.task_ctx()).await?;
+    Ok(pretty_format_batches(&batches)?.to_string())
+}
+
+fn field_names(plan: &LogicalPlan) -> Vec<String> {
+    let fields = plan.schema().fields();
+    fields.iter().map(|field| field.name().clone()).collect()
+}
+
+/// Asserts that the plans of `sql`, before and after their optimization,
+/// produce the same results once imported from their Substrait plans
+async fn assert_round_trip(sql: &str) -> Result<()> {
+    let ctx = tpch_context().await?;
+    let plan = ctx.create_logical_plan(sql)?;
+    let expected = execute(&ctx, &plan).await?;
+    assert!(expected.lines().count() > 4, "{}", expected);
+
+    for plan in [plan.clone(), ctx.optimize(&plan)?] {
+        let bytes = to_substrait(&plan)?;
+        let imported = from_substrait(&bytes, &ctx)?;
+        assert_eq!(field_names(&plan), field_names(&imported));
+        assert_eq!(expected, execute(&ctx, &imported).await?);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn round_trip_pricing_summary() -> Result<()> {
+    // TPC-H query 1
+    assert_round_trip(
+        "SELECT l_returnflag, l_linestatus, \
+         SUM(l_quantity) AS sum_qty, \
+         SUM(l_extendedprice * (1 - l_discount)) AS sum_disc_price, \
+         AVG(l_discount) AS avg_disc, \
+         COUNT(*) AS count_order, \
+         SUM(CASE WHEN l_quantity > 30 THEN 1 ELSE 0 END) AS large_items \
+         FROM lineitem \
+         WHERE l_shipdate <= CAST('1998-09-02' AS DATE) \
+         GROUP BY l_returnflag, l_linestatus \
+         ORDER BY l_returnflag, l_linestatus",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn round_trip_shipping_priority() -> Result<()> {
+    // TPC-H query 3, without the customers
+    assert_round_trip(
+        "SELECT l_orderkey, \
+         SUM(l_extendedprice * (1 - l_discount)) AS revenue, \
+         o_orderdate, o_shippriority \
+         FROM orders JOIN lineitem ON l_orderkey = o_orderkey \
+         WHERE o_orderdate < CAST('1997-01-01' AS DATE) \
+         AND l_quantity BETWEEN 10 AND 50 \
+         AND l_returnflag IS NOT NULL \
+         GROUP BY l_orderkey, o_orderdate, o_shippriority \
+         ORDER BY revenue DESC, o_orderdate \
+         LIMIT 10",
+    )
+    .await
+}
+
+#[tokio::test]
+async fn unsupported_nodes() -> Result<()> {
+    let ctx = tpch_context().await?;
+    let plan = ctx.create_logical_plan(
+        "SELECT l_orderkey, ROW_NUMBER() OVER (ORDER BY l_quantity) FROM lineitem",
+    )?;
+    match to_substrait(&plan) {
+        Err(DataFusionError::NotImplemented(message)) => {
+            assert!(message.contains("Window"), "{}", message)
+        }
+        other => panic!("Unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let plan = ctx.create_logical_plan(
+        "SELECT l_orderkey FROM lineitem WHERE l_linenumber IN (1, 2)",
+    )?;
+    assert!(matches!(
+        to_substrait(&plan),
+        Err(DataFusionError::NotImplemented(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn import_unknown_table() -> Result<()> {
+    let ctx = tpch_context().await?;
+    let plan = ctx.create_logical_plan("SELECT o_orderkey FROM orders")?;
+    let bytes = to_substrait(&plan)?;
+
+    let err = from_substrait(&bytes, &ExecutionContext::new()).unwrap_err();
+    assert!(err.to_string().contains("orders"), "{}", err);
+    assert!(from_substrait(b"not a plan", &ctx).is_err());
+    Ok(())
+}
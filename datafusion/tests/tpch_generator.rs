@@ -0,0 +1,232 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+//! Run TPC-H queries on the tables of the in-process TPC-H generator
+
+use std::collections::BTreeMap;
+
+use arrow::array::{Array, Date32Array, Float64Array, StringArray, UInt64Array};
+use arrow::record_batch::RecordBatch;
+use chrono::NaiveDate;
+
+use datafusion::datasource::TableProvider;
+use datafusion::error::Result;
+use datafusion::physical_plan::collect;
+use datafusion::prelude::*;
+use datafusion::test_util::tpch::{register_tpch_tables, TpchTable};
+
+/// Run `sql` on the TPC-H tables at the scale factor 0.01
+async fn execute(sql: &str) -> Result<Vec<RecordBatch>> {
+    let config = ExecutionConfig::new().with_target_partitions(4);
+    let mut ctx = ExecutionContext::with_config(config);
+    register_tpch_tables(&mut ctx, 0.01, 4)?;
+    ctx.sql(sql).await?.collect().await
+}
+
+/// Scan all the columns of the lineitem table at the scale factor 0.01,
+/// without running a query on them
+async fn lineitem() -> Result<Vec<RecordBatch>> {
+    let ctx = ExecutionContext::new();
+    let table = TpchTable::try_new("lineitem", 0.01, 4)?;
+    let plan = table.scan(&None, 8192, &[], None).await?;
+    collect(plan, ctx.task_ctx()).await
+}
+
+/// The number of days from the UNIX epoch to a date, the value of a `Date32`
+fn days_since_epoch(year: i32, month: u32, day: u32) -> i32 {
+    NaiveDate::from_ymd(year, month, day)
+        .signed_duration_since(NaiveDate::from_ymd(1970, 1, 1))
+        .num_days() as i32
+}
+
+fn column<'a, T: Array + 'static>(batch: &'a RecordBatch, name: &str) -> &'a T {
+    let index = batch.schema().index_of(name).unwrap();
+    batch.column(index).as_any().downcast_ref::<T>().unwrap()
+}
+
+/// Asserts that `actual` equals `expected`, up to the rounding errors of
+/// summing in another order
+fn assert_close(expected: f64, actual: f64) {
+    assert!(
+        ((actual - expected) / expected).abs() < 1e-9,
+        "expected {}, got {}",
+        expected,
+        actual
+    );
+}
+
+/// The sums of TPC-H query 1 for a group of line items
+#[derive(Default)]
+struct PricingSummary {
+    sum_qty: f64,
+    sum_base_price: f64,
+    sum_disc_price: f64,
+    sum_charge: f64,
+    sum_disc: f64,
+    count: u64,
+}
+
+#[tokio::test]
+async fn pricing_summary_report() -> Result<()> {
+    // TPC-H query 1
+    let batches = execute(
+        "SELECT l_returnflag, l_linestatus, \
+         SUM(l_quantity) AS sum_qty, \
+         SUM(l_extendedprice) AS sum_base_price, \
+         SUM(l_extendedprice * (1 - l_discount)) AS sum_disc_price, \
+         SUM(l_extendedprice * (1 - l_discount) * (1 + l_tax)) AS sum_charge, \
+         AVG(l_quantity) AS avg_qty, \
+         AVG(l_extendedprice) AS avg_price, \
+         AVG(l_discount) AS avg_disc, \
+         COUNT(*) AS count_order \
+         FROM lineitem \
+         WHERE l_shipdate <= DATE '1998-09-02' \
+         GROUP BY l_returnflag, l_linestatus \
+         ORDER BY l_returnflag, l_linestatus",
+    )
+    .await?;
+
+    // the same aggregates, folded over the generated line items
+    let max_shipdate = days_since_epoch(1998, 9, 2);
+    let mut expected = BTreeMap::<(String, String), PricingSummary>::new();
+    for batch in lineitem().await? {
+        let quantity = column::<Float64Array>(&batch, "l_quantity");
+        let price = column::<Float64Array>(&batch, "l_extendedprice");
+        let discount = column::<Float64Array>(&batch, "l_discount");
+        let tax = column::<Float64Array>(&batch, "l_tax");
+        let flag = column::<StringArray>(&batch, "l_returnflag");
+        let status = column::<StringArray>(&batch, "l_linestatus");
+        let shipdate = column::<Date32Array>(&batch, "l_shipdate");
+        for row in 0..batch.num_rows() {
+            if shipdate.value(row) > max_shipdate {
+                continue;
+            }
+            let key = (flag.value(row).to_owned(), status.value(row).to_owned());
+            let summary = expected.entry(key).or_default();
+            let disc_price = price.value(row) * (1.0 - discount.value(row));
+            summary.sum_qty += quantity.value(row);
+            summary.sum_base_price += price.value(row);
+            summary.sum_disc_price += disc_price;
+            summary.sum_charge += disc_price * (1.0 + tax.value(row));
+            summary.sum_disc += discount.value(row);
+            summary.count += 1;
+        }
+    }
+    // the generated line items have the four groups of the specification
+    assert_eq!(
+        vec![("A", "F"), ("N", "F"), ("N", "O"), ("R", "F")],
+        expected
+            .keys()
+            .map(|(flag, status)| (flag.as_str(), status.as_str()))
+            .collect::<Vec<_>>()
+    );
+
+    let mut expected = expected.into_iter();
+    for batch in &batches {
+        for row in 0..batch.num_rows() {
+            let ((flag, status), summary) = expected.next().unwrap();
+            assert_eq!(
+                flag,
+                column::<StringArray>(batch, "l_returnflag").value(row)
+            );
+            assert_eq!(
+                status,
+                column::<StringArray>(batch, "l_linestatus").value(row)
+            );
+            let count = summary.count as f64;
+            let aggregates = [
+                ("sum_qty", summary.sum_qty),
+                ("sum_base_price", summary.sum_base_price),
+                ("sum_disc_price", summary.sum_disc_price),
+                ("sum_charge", summary.sum_charge),
+                ("avg_qty", summary.sum_qty / count),
+                ("avg_price", summary.sum_base_price / count),
+                ("avg_disc", summary.sum_disc / count),
+            ];
+            for (name, value) in aggregates {
+                assert_close(value, column::<Float64Array>(batch, name).value(row));
+            }
+            assert_eq!(
+                summary.count,
+                column::<UInt64Array>(batch, "count_order").value(row)
+            );
+        }
+    }
+    assert!(expected.next().is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn forecasting_revenue_change() -> Result<()> {
+    // TPC-H query 6, with the bounds of the discount as literals since
+    // 0.06 + 0.01 < 0.07 in floating point
+    let batches = execute(
+        "SELECT SUM(l_extendedprice * l_discount) AS revenue \
+         FROM lineitem \
+         WHERE l_shipdate >= DATE '1994-01-01' \
+         AND l_shipdate < DATE '1995-01-01' \
+         AND l_discount BETWEEN 0.05 AND 0.07 \
+         AND l_quantity < 24",
+    )
+    .await?;
+
+    // the same sum, folded over the generated line items
+    let shipdates = days_since_epoch(1994, 1, 1)..days_since_epoch(1995, 1, 1);
+    let mut expected = 0.0;
+    let mut rows = 0;
+    for batch in lineitem().await? {
+        let quantity = column::<Float64Array>(&batch, "l_quantity");
+        let price = column::<Float64Array>(&batch, "l_extendedprice");
+        let discount = column::<Float64Array>(&batch, "l_discount");
+        let shipdate = column::<Date32Array>(&batch, "l_shipdate");
+        for row in 0..batch.num_rows() {
+            if shipdates.contains(&shipdate.value(row))
+                && (0.05..=0.07).contains(&discount.value(row))
+                && quantity.value(row) < 24.0
+            {
+                expected += price.value(row) * discount.value(row);
+                rows += 1;
+            }
+        }
+    }
+    assert!(rows > 0);
+
+    assert_eq!(1, batches.len());
+    assert_close(
+        expected,
+        column::<Float64Array>(&batches[0], "revenue").value(0),
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn generate_all_tables() -> Result<()> {
+    let batches = execute(
+        "SELECT COUNT(*) AS count FROM lineitem \
+         JOIN orders ON l_orderkey = o_orderkey \
+         JOIN partsupp ON l_partkey = ps_partkey AND l_suppkey = ps_suppkey \
+         JOIN part ON ps_partkey = p_partkey \
+         JOIN supplier ON ps_suppkey = s_suppkey \
+         JOIN customer ON o_custkey = c_custkey \
+         JOIN nation ON c_nationkey = n_nationkey \
+         JOIN region ON n_regionkey = r_regionkey",
+    )
+    .await?;
+    // every line item references existing rows of the other tables
+    assert_eq!(59986, column::<UInt64Array>(&batches[0], "count").value(0));
+    Ok(())
+}
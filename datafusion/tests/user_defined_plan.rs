@@ -68,15 +68,18 @@ use arrow::{
     util::pretty::pretty_format_batches,
 };
 use datafusion::{
+    assert_batches_sorted_eq,
     error::{DataFusionError, Result},
     execution::context::ExecutionContextState,
     execution::context::QueryPlanner,
+    execution::task_context::TaskContext,
     logical_plan::{Expr, LogicalPlan, UserDefinedLogicalNode},
     optimizer::{optimizer::OptimizerRule, utils::optimize_children},
     physical_plan::{
+        collect, fmt_with_statistics,
         planner::{DefaultPhysicalPlanner, ExtensionPlanner},
-        DisplayFormatType, Distribution, ExecutionPlan, Partitioning, PhysicalPlanner,
-        RecordBatchStream, SendableRecordBatchStream, Statistics, LambdaExecPlan,
+        DisplayFormatType, Distribution, ExecutionPlan, LambdaExecPlan, Partitioning,
+        PhysicalPlanner, RecordBatchStream, SendableRecordBatchStream, Statistics,
     },
     prelude::{ExecutionConfig, ExecutionContext},
 };
@@ -245,6 +248,36 @@ async fn topk_plan() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+// Optimize a plan with a TopK node again: the built-in rules keep the node and
+// don't push the filter above it below it
+async fn topk_reoptimized() -> Result<()> {
+    let ctx = setup_table(make_topk_context()).await?;
+
+    let sql = format!(
+        "SELECT customer_id FROM ({}) AS t WHERE revenue > 160",
+        QUERY
+    );
+    let plan = ctx.create_logical_plan(&sql)?;
+    let plan = ctx.optimize(&plan)?;
+    let plan = ctx.optimize(&plan)?;
+    let formatted = format!("{:?}", plan);
+    assert!(formatted.contains("TopK: k=3"), "{}", formatted);
+
+    let plan = ctx.create_physical_plan(&plan).await?;
+    let batches = collect(plan, ctx.task_ctx()).await?;
+    let expected = vec![
+        "+-------------+",
+        "| customer_id |",
+        "+-------------+",
+        "| jorge       |",
+        "| paul        |",
+        "+-------------+",
+    ];
+    assert_batches_sorted_eq!(expected, &batches);
+    Ok(())
+}
+
 fn make_topk_context() -> ExecutionContext {
     let config = ExecutionConfig::new()
         .with_query_planner(Arc::new(TopKQueryPlanner {}))
@@ -419,7 +452,7 @@ impl Debug for TopKExec {
 
 #[async_trait]
 impl LambdaExecPlan for TopKExec {
-    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) {
+    fn feed_batches(&mut self, _partitions: Vec<Vec<RecordBatch>>) -> Result<()> {
         unimplemented!();
     }
 }
@@ -464,7 +497,11 @@ impl ExecutionPlan for TopKExec {
     }
 
     /// Execute one partition and return an iterator over RecordBatch
-    async fn execute(&self, partition: usize) -> Result<SendableRecordBatchStream> {
+    async fn execute(
+        &self,
+        partition: usize,
+        context: Arc<TaskContext>,
+    ) -> Result<SendableRecordBatchStream> {
         if 0 != partition {
             return Err(DataFusionError::Internal(format!(
                 "TopKExec invalid partition {}",
@@ -473,7 +510,7 @@ impl ExecutionPlan for TopKExec {
         }
 
         Ok(Box::pin(TopKReader {
-            input: self.input.execute(partition).await?,
+            input: self.input.execute(partition, context).await?,
             k: self.k,
             done: false,
             state: BTreeMap::new(),
@@ -489,6 +526,7 @@ impl ExecutionPlan for TopKExec {
             DisplayFormatType::Default => {
                 write!(f, "TopKExec: k={}", self.k)
             }
+            DisplayFormatType::Verbose => fmt_with_statistics(self, f),
         }
     }
 